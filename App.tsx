@@ -14,6 +14,8 @@ import { Header, ResizeHandles, AppBackground } from './components/layout';
 import { Background3D } from './components/customization';
 import { useTauriWindow } from './hooks';
 import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+import { isTauriApp } from './services/tauriDetection';
 import { chatStorage } from './services/chatStorage';
 import { authService } from './services/auth';
 import { initScaleManager, destroyScaleManager } from './services/scaleManager';
@@ -295,6 +297,20 @@ const AppContent: React.FC = () => {
     tokenTrackingService.resetConversation();
   }, []);
 
+  // Global hotkey (desktop only): the shell already brought the window forward
+  useEffect(() => {
+    if (!isTauriApp()) return;
+    let unlisten: (() => void) | undefined;
+    listen<'new_conversation' | 'toggle_voice'>('global-hotkey', (event) => {
+      if (event.payload === 'new_conversation') {
+        handleNewChat();
+      } else if (event.payload === 'toggle_voice') {
+        window.dispatchEvent(new CustomEvent('toggle-voice-capture'));
+      }
+    }).then(fn => { unlisten = fn; });
+    return () => unlisten?.();
+  }, [handleNewChat]);
+
   const handleSelectChat = useCallback((chatId: string) => {
     pendingChatIdRef.current = null;
     setCurrentChatId(chatId);
@@ -0,0 +1,12 @@
+-- Create disk snapshots table for scheduled disk analysis history
+CREATE TABLE IF NOT EXISTS disk_snapshots (
+    id TEXT PRIMARY KEY,
+    scan_id TEXT NOT NULL,
+    path TEXT NOT NULL,
+    size INTEGER NOT NULL,
+    file_count INTEGER NOT NULL,
+    captured_at TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_disk_snapshots_path ON disk_snapshots(path);
+CREATE INDEX IF NOT EXISTS idx_disk_snapshots_captured_at ON disk_snapshots(captured_at);
@@ -0,0 +1,14 @@
+-- Persistent cache of extracted web pages
+CREATE TABLE IF NOT EXISTS web_cache (
+    key TEXT PRIMARY KEY,
+    url TEXT NOT NULL,
+    extract TEXT NOT NULL,
+    size_bytes INTEGER NOT NULL,
+    etag TEXT,
+    last_modified TEXT,
+    cached_at TEXT NOT NULL,
+    last_accessed TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_web_cache_last_accessed ON web_cache(last_accessed);
+CREATE INDEX IF NOT EXISTS idx_web_cache_cached_at ON web_cache(cached_at);
@@ -0,0 +1,38 @@
+-- Agent conversation messages with a full-text index for history search
+CREATE TABLE IF NOT EXISTS conversation_messages (
+    session_id TEXT NOT NULL,
+    id TEXT NOT NULL,
+    agent_id TEXT,
+    role TEXT NOT NULL,
+    content TEXT NOT NULL,
+    -- Tool names and arguments, so commands the agent ran can be found
+    tool_calls TEXT NOT NULL DEFAULT '',
+    created_at TEXT NOT NULL,
+    PRIMARY KEY (session_id, id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_conversation_messages_agent ON conversation_messages(agent_id);
+CREATE INDEX IF NOT EXISTS idx_conversation_messages_created_at ON conversation_messages(created_at);
+
+CREATE VIRTUAL TABLE IF NOT EXISTS conversation_fts USING fts5(
+    content,
+    tool_calls,
+    content = 'conversation_messages',
+    content_rowid = 'rowid',
+    tokenize = 'unicode61'
+);
+
+CREATE TRIGGER IF NOT EXISTS conversation_messages_ai AFTER INSERT ON conversation_messages BEGIN
+    INSERT INTO conversation_fts(rowid, content, tool_calls) VALUES (new.rowid, new.content, new.tool_calls);
+END;
+
+CREATE TRIGGER IF NOT EXISTS conversation_messages_ad AFTER DELETE ON conversation_messages BEGIN
+    INSERT INTO conversation_fts(conversation_fts, rowid, content, tool_calls)
+    VALUES ('delete', old.rowid, old.content, old.tool_calls);
+END;
+
+CREATE TRIGGER IF NOT EXISTS conversation_messages_au AFTER UPDATE ON conversation_messages BEGIN
+    INSERT INTO conversation_fts(conversation_fts, rowid, content, tool_calls)
+    VALUES ('delete', old.rowid, old.content, old.tool_calls);
+    INSERT INTO conversation_fts(rowid, content, tool_calls) VALUES (new.rowid, new.content, new.tool_calls);
+END;
@@ -0,0 +1,23 @@
+-- Code structure index: definitions and imports per source file
+CREATE TABLE IF NOT EXISTS code_files (
+    path TEXT PRIMARY KEY,
+    language TEXT NOT NULL,
+    modified_at TEXT NOT NULL,
+    indexed_at TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS code_symbols (
+    path TEXT NOT NULL,
+    name TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    line INTEGER NOT NULL,
+    end_line INTEGER NOT NULL,
+    -- Enclosing type, trait, class or module
+    container TEXT,
+    -- First line of the definition
+    signature TEXT NOT NULL DEFAULT ''
+);
+
+CREATE INDEX IF NOT EXISTS idx_code_symbols_name ON code_symbols(name COLLATE NOCASE);
+CREATE INDEX IF NOT EXISTS idx_code_symbols_path ON code_symbols(path);
+CREATE INDEX IF NOT EXISTS idx_code_symbols_kind ON code_symbols(kind);
@@ -0,0 +1,15 @@
+-- Filename index: every file under the indexed roots, for searching by name
+-- without walking the disk
+CREATE TABLE IF NOT EXISTS filename_roots (
+    root TEXT PRIMARY KEY,
+    reconciled_at TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS filename_index (
+    path TEXT PRIMARY KEY,
+    root TEXT NOT NULL,
+    size INTEGER NOT NULL,
+    modified_at TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_filename_index_root ON filename_index(root);
@@ -0,0 +1,502 @@
+//! Hook bindings for agents
+//!
+//! A binding attaches an agent to an app event: a file saved in a
+//! workspace, a shell command that failed, or a workflow that completed or
+//! failed. The dispatcher (see `api::agents`) watches the event bus and, for
+//! each binding whose filter matches and whose cooldown has passed, starts an
+//! execution of the agent with the event's fields as context.
+//!
+//! Filters use the workflow expression syntax over the event's fields, e.g.
+//! `extension == 'rs' && not (path contains '/target/')`. Bindings persist to
+//! `~/.skhoot/agent_hooks.json`.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use crate::events::Event;
+use crate::ignore_rules::IgnoreRules;
+use crate::json_store::{self, non_empty};
+use crate::workflows::expression::{evaluate, validate, EvalContext};
+use crate::workflows::WorkflowStatus;
+
+/// Cooldown of a binding that doesn't set one
+pub const DEFAULT_COOLDOWN_SECS: u64 = 60;
+
+/// App events an agent can be bound to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookEvent {
+    /// Fields: `path`, `file_name`, `extension`
+    FileSaved,
+    /// Fields: `command`, `exit_code`, `session_id`
+    CommandFailed,
+    /// Fields: `workflow_id`, `run_id`
+    WorkflowCompleted,
+    /// Fields: `workflow_id`, `run_id`, `step_id`
+    WorkflowFailed,
+}
+
+/// An agent attached to an event
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HookBinding {
+    pub id: String,
+    pub agent_id: String,
+    pub event: HookEvent,
+    /// Expression over the event's fields; every event matches without one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filter: Option<String>,
+    /// Directory saved files must be in (`file_saved` only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace: Option<PathBuf>,
+    /// Minimum time between two runs started by this binding
+    pub cooldown_secs: u64,
+    pub enabled: bool,
+    pub created_at: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_fired_at: Option<i64>,
+    #[serde(default)]
+    pub fire_count: u64,
+}
+
+/// Fields of a new binding
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreateHookRequest {
+    pub event: HookEvent,
+    #[serde(default)]
+    pub filter: Option<String>,
+    #[serde(default)]
+    pub workspace: Option<PathBuf>,
+    #[serde(default)]
+    pub cooldown_secs: Option<u64>,
+    #[serde(default)]
+    pub enabled: Option<bool>,
+}
+
+/// Changes to a binding; `filter` and `workspace` are cleared with an empty string
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct UpdateHookRequest {
+    pub event: Option<HookEvent>,
+    pub filter: Option<String>,
+    pub workspace: Option<PathBuf>,
+    pub cooldown_secs: Option<u64>,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum HookError {
+    #[error("Hook not found: {0}")]
+    NotFound(String),
+
+    #[error("Invalid hook filter: {0}")]
+    InvalidFilter(String),
+
+    #[error("Hook workspace {0} is not a directory")]
+    InvalidWorkspace(String),
+
+    #[error("Failed to save hooks: {0}")]
+    Io(String),
+}
+
+/// The hook event an app event stands for, with the fields filters see
+pub fn hook_event(event: &Event) -> Option<(HookEvent, HashMap<String, Value>)> {
+    let fields = |value: Value| match value {
+        Value::Object(map) => map.into_iter().collect(),
+        _ => HashMap::new(),
+    };
+    match event {
+        Event::FileSaved { path } => {
+            let path_ref = Path::new(path);
+            Some((
+                HookEvent::FileSaved,
+                fields(json!({
+                    "path": path,
+                    "file_name": path_ref.file_name().map(|n| n.to_string_lossy()),
+                    "extension": path_ref.extension().map(|e| e.to_string_lossy()),
+                })),
+            ))
+        }
+        Event::CommandFailed { session_id, command, exit_code } => Some((
+            HookEvent::CommandFailed,
+            fields(json!({ "command": command, "exit_code": exit_code, "session_id": session_id })),
+        )),
+        Event::WorkflowRun { workflow_id, run_id, status, current_step_id }
+        | Event::WorkflowExecution { workflow_id, execution_id: run_id, status, current_step_id } => {
+            let kind = match status {
+                WorkflowStatus::Completed => HookEvent::WorkflowCompleted,
+                WorkflowStatus::Failed => HookEvent::WorkflowFailed,
+                _ => return None,
+            };
+            Some((
+                kind,
+                fields(json!({ "workflow_id": workflow_id, "run_id": run_id, "step_id": current_step_id })),
+            ))
+        }
+        _ => None,
+    }
+}
+
+impl HookBinding {
+    /// Whether the binding applies to an event with these fields at `now`
+    pub fn matches(&self, event: HookEvent, fields: &HashMap<String, Value>, now: i64) -> bool {
+        if !self.enabled || self.event != event {
+            return false;
+        }
+        if self
+            .last_fired_at
+            .is_some_and(|last| now - last < self.cooldown_secs as i64)
+        {
+            return false;
+        }
+        if let Some(workspace) = &self.workspace {
+            let in_workspace = fields
+                .get("path")
+                .and_then(Value::as_str)
+                .is_some_and(|path| Path::new(path).starts_with(workspace));
+            if !in_workspace {
+                return false;
+            }
+        }
+        let Some(filter) = &self.filter else {
+            return true;
+        };
+        let steps = HashMap::new();
+        let ctx = EvalContext {
+            variables: fields,
+            steps: &steps,
+            current_step_id: None,
+        };
+        evaluate(filter, &ctx).unwrap_or_else(|e| {
+            tracing::warn!("Hook {} filter failed: {}", self.id, e);
+            false
+        })
+    }
+}
+
+/// Persistent hook bindings
+pub struct HookStore {
+    path: PathBuf,
+    bindings: RwLock<Vec<HookBinding>>,
+}
+
+lazy_static::lazy_static! {
+    static ref GLOBAL_STORE: Arc<HookStore> = Arc::new(HookStore::new(
+        dirs::home_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join(".skhoot")
+            .join("agent_hooks.json"),
+    ));
+}
+
+impl HookStore {
+    /// Open the store at `path`; see [`json_store::load`]
+    pub fn new(path: PathBuf) -> Self {
+        let bindings = json_store::load(&path);
+        Self {
+            path,
+            bindings: RwLock::new(bindings),
+        }
+    }
+
+    /// Shared store at `~/.skhoot/agent_hooks.json`
+    pub fn global() -> Arc<HookStore> {
+        GLOBAL_STORE.clone()
+    }
+
+    /// Bindings of an agent
+    pub fn list(&self, agent_id: &str) -> Vec<HookBinding> {
+        self.bindings
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|b| b.agent_id == agent_id)
+            .cloned()
+            .collect()
+    }
+
+    pub fn create(&self, agent_id: &str, request: CreateHookRequest) -> Result<HookBinding, HookError> {
+        let binding = HookBinding {
+            id: uuid::Uuid::new_v4().to_string(),
+            agent_id: agent_id.to_string(),
+            event: request.event,
+            filter: non_empty(request.filter),
+            workspace: request.workspace.filter(|w| !w.as_os_str().is_empty()),
+            cooldown_secs: request.cooldown_secs.unwrap_or(DEFAULT_COOLDOWN_SECS),
+            enabled: request.enabled.unwrap_or(true),
+            created_at: chrono::Utc::now().timestamp(),
+            last_fired_at: None,
+            fire_count: 0,
+        };
+        let binding = check(binding)?;
+        let mut bindings = self.bindings.write().unwrap();
+        bindings.push(binding.clone());
+        self.save(&bindings)?;
+        Ok(binding)
+    }
+
+    pub fn update(&self, agent_id: &str, id: &str, request: UpdateHookRequest) -> Result<HookBinding, HookError> {
+        let mut bindings = self.bindings.write().unwrap();
+        let binding = bindings
+            .iter_mut()
+            .find(|b| b.id == id && b.agent_id == agent_id)
+            .ok_or_else(|| HookError::NotFound(id.to_string()))?;
+
+        let mut updated = binding.clone();
+        if let Some(event) = request.event {
+            updated.event = event;
+        }
+        if let Some(filter) = request.filter {
+            updated.filter = non_empty(Some(filter));
+        }
+        if let Some(workspace) = request.workspace {
+            updated.workspace = Some(workspace).filter(|w| !w.as_os_str().is_empty());
+        }
+        if let Some(cooldown) = request.cooldown_secs {
+            updated.cooldown_secs = cooldown;
+        }
+        if let Some(enabled) = request.enabled {
+            updated.enabled = enabled;
+        }
+        *binding = check(updated)?;
+        let binding = binding.clone();
+        self.save(&bindings)?;
+        Ok(binding)
+    }
+
+    /// Remove a binding. Returns whether it existed.
+    pub fn remove(&self, agent_id: &str, id: &str) -> Result<bool, HookError> {
+        self.retain(|b| !(b.id == id && b.agent_id == agent_id))
+    }
+
+    /// Remove every binding of a deleted agent
+    pub fn remove_agent(&self, agent_id: &str) -> Result<bool, HookError> {
+        self.retain(|b| b.agent_id != agent_id)
+    }
+
+    /// Bindings that fire for `event`, marked as fired now
+    pub fn fire(&self, event: &Event) -> Vec<(HookBinding, HashMap<String, Value>)> {
+        let Some((kind, fields)) = hook_event(event) else {
+            return Vec::new();
+        };
+        let now = chrono::Utc::now().timestamp();
+        let mut bindings = self.bindings.write().unwrap();
+        let mut fired = Vec::new();
+        for binding in bindings.iter_mut() {
+            if binding.matches(kind, &fields, now) {
+                binding.last_fired_at = Some(now);
+                binding.fire_count += 1;
+                fired.push((binding.clone(), fields.clone()));
+            }
+        }
+        if !fired.is_empty() {
+            if let Err(e) = self.save(&bindings) {
+                tracing::warn!("{}", e);
+            }
+        }
+        fired
+    }
+
+    /// Workspaces of enabled `file_saved` bindings
+    pub fn watched_workspaces(&self) -> Vec<PathBuf> {
+        let mut workspaces: Vec<PathBuf> = self
+            .bindings
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|b| b.enabled && b.event == HookEvent::FileSaved)
+            .filter_map(|b| b.workspace.clone())
+            .collect();
+        workspaces.sort();
+        workspaces.dedup();
+        workspaces
+    }
+
+    fn retain(&self, keep: impl Fn(&HookBinding) -> bool) -> Result<bool, HookError> {
+        let mut bindings = self.bindings.write().unwrap();
+        let before = bindings.len();
+        bindings.retain(|b| keep(b));
+        if bindings.len() == before {
+            return Ok(false);
+        }
+        self.save(&bindings)?;
+        Ok(true)
+    }
+
+    fn save(&self, bindings: &[HookBinding]) -> Result<(), HookError> {
+        json_store::save(&self.path, bindings).map_err(|e| HookError::Io(e.to_string()))
+    }
+}
+
+/// Validate the filter and resolve the workspace of a binding
+fn check(mut binding: HookBinding) -> Result<HookBinding, HookError> {
+    if let Some(filter) = &binding.filter {
+        validate(filter).map_err(HookError::InvalidFilter)?;
+    }
+    if let Some(workspace) = &binding.workspace {
+        binding.workspace = Some(
+            workspace
+                .canonicalize()
+                .ok()
+                .filter(|w| w.is_dir())
+                .ok_or_else(|| HookError::InvalidWorkspace(workspace.display().to_string()))?,
+        );
+    }
+    Ok(binding)
+}
+
+/// Publish [`Event::FileSaved`] for files written in the workspaces of
+/// `file_saved` bindings. The watched set follows the bindings as they change.
+pub fn spawn_file_watcher(store: Arc<HookStore>) -> tokio::task::JoinHandle<()> {
+    use notify::{EventKind, RecursiveMode, Watcher};
+
+    tokio::spawn(async move {
+        let mut watched = Vec::new();
+        // Kept alive until the workspaces change
+        let mut _watcher: Option<notify::RecommendedWatcher> = None;
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(2));
+        loop {
+            interval.tick().await;
+            let workspaces = store.watched_workspaces();
+            if workspaces == watched {
+                continue;
+            }
+            watched = workspaces;
+            _watcher = None;
+            if watched.is_empty() {
+                continue;
+            }
+
+            let created = notify::recommended_watcher(|res: Result<notify::Event, notify::Error>| {
+                let Ok(event) = res else { return };
+                if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+                    return;
+                }
+                for path in event.paths {
+                    if path.is_file() && !is_ignored(&path) {
+                        crate::events::publish(Event::FileSaved {
+                            path: path.to_string_lossy().to_string(),
+                        });
+                    }
+                }
+            });
+            match created {
+                Ok(mut created) => {
+                    for workspace in &watched {
+                        if let Err(e) = created.watch(workspace, RecursiveMode::Recursive) {
+                            tracing::warn!("Cannot watch hook workspace {}: {}", workspace.display(), e);
+                        }
+                    }
+                    _watcher = Some(created);
+                }
+                Err(e) => tracing::warn!("Cannot watch hook workspaces: {}", e),
+            }
+        }
+    })
+}
+
+/// Git internals and files the workspace ignores don't count as saves
+fn is_ignored(path: &Path) -> bool {
+    path.components().any(|c| c.as_os_str() == ".git")
+        || path
+            .parent()
+            .is_some_and(|parent| IgnoreRules::SEARCH.matcher(parent).is_ignored(path, false))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn saved(path: &str) -> Event {
+        Event::FileSaved { path: path.to_string() }
+    }
+
+    #[test]
+    fn test_filter_workspace_and_cooldown() {
+        let dir = tempfile::tempdir().unwrap();
+        let workspace = dir.path().canonicalize().unwrap();
+        let store = HookStore::new(dir.path().join("hooks.json"));
+
+        assert!(matches!(
+            store.create("a1", CreateHookRequest {
+                event: HookEvent::FileSaved,
+                filter: Some("extension ==".to_string()),
+                workspace: None,
+                cooldown_secs: None,
+                enabled: None,
+            }),
+            Err(HookError::InvalidFilter(_))
+        ));
+        let binding = store
+            .create("a1", CreateHookRequest {
+                event: HookEvent::FileSaved,
+                filter: Some("extension == 'rs'".to_string()),
+                workspace: Some(dir.path().to_path_buf()),
+                cooldown_secs: Some(3600),
+                enabled: None,
+            })
+            .unwrap();
+        assert_eq!(store.watched_workspaces(), [workspace.clone()]);
+
+        let inside = workspace.join("src/main.rs").to_string_lossy().to_string();
+        assert!(store.fire(&saved("/elsewhere/main.rs")).is_empty());
+        assert!(store.fire(&saved(&workspace.join("notes.md").to_string_lossy())).is_empty());
+
+        let fired = store.fire(&saved(&inside));
+        assert_eq!(fired.len(), 1);
+        assert_eq!(fired[0].1["file_name"], "main.rs");
+        // Still cooling down
+        assert!(store.fire(&saved(&inside)).is_empty());
+
+        store
+            .update("a1", &binding.id, UpdateHookRequest { cooldown_secs: Some(0), ..Default::default() })
+            .unwrap();
+        assert_eq!(store.fire(&saved(&inside)).len(), 1);
+
+        // Persisted, including the fire count
+        let reopened = HookStore::new(dir.path().join("hooks.json"));
+        assert_eq!(reopened.list("a1")[0].fire_count, 2);
+        assert!(matches!(
+            reopened.update("a2", &binding.id, UpdateHookRequest::default()),
+            Err(HookError::NotFound(_))
+        ));
+        assert!(reopened.remove_agent("a1").unwrap());
+        assert!(reopened.list("a1").is_empty());
+    }
+
+    #[test]
+    fn test_hook_events() {
+        let failed = Event::CommandFailed {
+            session_id: Some("s1".to_string()),
+            command: "cargo test".to_string(),
+            exit_code: Some(101),
+        };
+        let (kind, fields) = hook_event(&failed).unwrap();
+        assert_eq!(kind, HookEvent::CommandFailed);
+        let binding = HookBinding {
+            id: "h".to_string(),
+            agent_id: "a".to_string(),
+            event: HookEvent::CommandFailed,
+            filter: Some("command starts_with 'cargo' && exit_code != 130".to_string()),
+            workspace: None,
+            cooldown_secs: 0,
+            enabled: true,
+            created_at: 0,
+            last_fired_at: None,
+            fire_count: 0,
+        };
+        assert!(binding.matches(kind, &fields, 0));
+        assert!(!HookBinding { enabled: false, ..binding.clone() }.matches(kind, &fields, 0));
+
+        let run = |status| Event::WorkflowRun {
+            workflow_id: "wf".to_string(),
+            run_id: "r".to_string(),
+            status,
+            current_step_id: None,
+        };
+        assert_eq!(hook_event(&run(WorkflowStatus::Completed)).unwrap().0, HookEvent::WorkflowCompleted);
+        assert_eq!(hook_event(&run(WorkflowStatus::Failed)).unwrap().0, HookEvent::WorkflowFailed);
+        assert!(hook_event(&run(WorkflowStatus::Running)).is_none());
+    }
+}
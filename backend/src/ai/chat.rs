@@ -0,0 +1,405 @@
+//! Multi-turn chat with tool calling
+//!
+//! Conversations are kept in the OpenAI chat format (`system`, `user`,
+//! `assistant` with `tool_calls`, and `tool` messages) and converted to the
+//! Anthropic and Gemini formats when those providers are asked. The model's
+//! tool calls are returned to the caller, which runs them and sends the
+//! results back in `tool` messages.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+use super::AIManager;
+use crate::cli_agent::{ToolCall, ToolRegistry};
+use crate::error::AppError;
+
+/// Longest answer asked of providers that need a limit
+const MAX_OUTPUT_TOKENS: u32 = 4096;
+
+/// Tokens a request used, as reported by the provider
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct TokenUsage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub total_tokens: u64,
+}
+
+impl TokenUsage {
+    fn new(prompt_tokens: u64, completion_tokens: u64) -> Self {
+        Self {
+            prompt_tokens,
+            completion_tokens,
+            total_tokens: prompt_tokens + completion_tokens,
+        }
+    }
+
+    pub fn add(&mut self, other: TokenUsage) {
+        self.prompt_tokens += other.prompt_tokens;
+        self.completion_tokens += other.completion_tokens;
+        self.total_tokens += other.total_tokens;
+    }
+}
+
+/// The model's answer to one request
+#[derive(Debug, Clone, Default)]
+pub struct ChatTurn {
+    pub content: String,
+    /// Tools the model wants run before it answers
+    pub tool_calls: Vec<ToolCall>,
+    pub usage: TokenUsage,
+}
+
+impl ChatTurn {
+    /// The turn as an OpenAI `assistant` message
+    pub fn to_message(&self) -> Value {
+        let mut message = json!({ "role": "assistant", "content": self.content });
+        if !self.tool_calls.is_empty() {
+            message["tool_calls"] = self
+                .tool_calls
+                .iter()
+                .map(|call| {
+                    json!({
+                        "id": call.id,
+                        "type": "function",
+                        "function": { "name": call.name, "arguments": call.arguments.to_string() },
+                    })
+                })
+                .collect();
+        }
+        message
+    }
+}
+
+/// Text of a message whose content is a string or a list of parts
+pub fn message_text(message: &Value) -> String {
+    match &message["content"] {
+        Value::String(text) => text.clone(),
+        Value::Array(parts) => parts
+            .iter()
+            .filter_map(|part| part["text"].as_str())
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => String::new(),
+    }
+}
+
+/// Arguments of an OpenAI tool call, which arrive as a JSON string
+fn call_arguments(arguments: &Value) -> Value {
+    match arguments {
+        Value::String(text) => serde_json::from_str(text).unwrap_or_else(|_| json!({})),
+        Value::Null => json!({}),
+        other => other.clone(),
+    }
+}
+
+/// Append content blocks, merging them into the last message when it has
+/// the same role; Anthropic and Gemini want roles to alternate
+fn push_blocks(messages: &mut Vec<Value>, role: &str, key: &str, blocks: Vec<Value>) {
+    if blocks.is_empty() {
+        return;
+    }
+    match messages.last_mut() {
+        Some(last) if last["role"] == role => {
+            if let Some(existing) = last[key].as_array_mut() {
+                existing.extend(blocks);
+            }
+        }
+        _ => messages.push(json!({ "role": role, key: blocks })),
+    }
+}
+
+fn anthropic_messages(messages: &[Value]) -> Vec<Value> {
+    let mut converted = Vec::new();
+    for message in messages {
+        let text = message_text(message);
+        match message["role"].as_str() {
+            Some("system") => {}
+            Some("assistant") => {
+                let mut blocks = Vec::new();
+                if !text.is_empty() {
+                    blocks.push(json!({ "type": "text", "text": text }));
+                }
+                for call in message["tool_calls"].as_array().into_iter().flatten() {
+                    blocks.push(json!({
+                        "type": "tool_use",
+                        "id": call["id"],
+                        "name": call["function"]["name"],
+                        "input": call_arguments(&call["function"]["arguments"]),
+                    }));
+                }
+                push_blocks(&mut converted, "assistant", "content", blocks);
+            }
+            Some("tool") => {
+                let block = json!({ "type": "tool_result", "tool_use_id": message["tool_call_id"], "content": text });
+                push_blocks(&mut converted, "user", "content", vec![block]);
+            }
+            _ if text.is_empty() => {}
+            _ => push_blocks(&mut converted, "user", "content", vec![json!({ "type": "text", "text": text })]),
+        }
+    }
+    converted
+}
+
+fn gemini_contents(messages: &[Value]) -> Vec<Value> {
+    // Gemini matches results to calls by tool name
+    let mut call_names: HashMap<&str, &str> = HashMap::new();
+    let mut contents = Vec::new();
+    for message in messages {
+        let text = message_text(message);
+        match message["role"].as_str() {
+            Some("system") => {}
+            Some("assistant") => {
+                let mut parts = Vec::new();
+                if !text.is_empty() {
+                    parts.push(json!({ "text": text }));
+                }
+                for call in message["tool_calls"].as_array().into_iter().flatten() {
+                    let name = call["function"]["name"].as_str().unwrap_or_default();
+                    call_names.insert(call["id"].as_str().unwrap_or_default(), name);
+                    parts.push(json!({
+                        "functionCall": { "name": name, "args": call_arguments(&call["function"]["arguments"]) },
+                    }));
+                }
+                push_blocks(&mut contents, "model", "parts", parts);
+            }
+            Some("tool") => {
+                let id = message["tool_call_id"].as_str().unwrap_or_default();
+                let name = message["name"].as_str().or_else(|| call_names.get(id).copied()).unwrap_or(id);
+                let part = json!({ "functionResponse": { "name": name, "response": { "content": text } } });
+                push_blocks(&mut contents, "user", "parts", vec![part]);
+            }
+            _ if text.is_empty() => {}
+            _ => push_blocks(&mut contents, "user", "parts", vec![json!({ "text": text })]),
+        }
+    }
+    contents
+}
+
+fn invalid_response() -> AppError {
+    AppError::Internal("Invalid chat response".to_string())
+}
+
+impl AIManager {
+    /// Ask the model for the next turn of `messages`, offering the tools in
+    /// `tools`. System messages in `messages` are ignored; `system` is used
+    /// instead.
+    pub async fn chat(
+        &self,
+        provider: &str,
+        api_key: &str,
+        model: &str,
+        system: &str,
+        messages: &[Value],
+        tools: &ToolRegistry,
+    ) -> Result<ChatTurn, AppError> {
+        let started = std::time::Instant::now();
+        let turn = match provider {
+            "openai" => self.chat_openai(api_key, model, system, messages, tools).await,
+            "anthropic" => self.chat_anthropic(api_key, model, system, messages, tools).await,
+            "google" => self.chat_google(api_key, model, system, messages, tools).await,
+            _ => return Err(AppError::BadRequest(format!("Unsupported provider: {}", provider))),
+        };
+        crate::metrics::record_ai_request(provider, "chat", turn.is_ok(), started.elapsed());
+        turn
+    }
+
+    async fn chat_openai(
+        &self,
+        api_key: &str,
+        model: &str,
+        system: &str,
+        messages: &[Value],
+        tools: &ToolRegistry,
+    ) -> Result<ChatTurn, AppError> {
+        let mut payload = json!({
+            "model": model,
+            "messages": std::iter::once(json!({ "role": "system", "content": system }))
+                .chain(messages.iter().filter(|m| m["role"] != "system").cloned())
+                .collect::<Vec<_>>(),
+        });
+        let tools = tools.to_openai_tools();
+        if !tools.is_empty() {
+            payload["tools"] = Value::Array(tools);
+        }
+
+        let response = self
+            .retry
+            .send(false, || {
+                self.client
+                    .post("https://api.openai.com/v1/chat/completions")
+                    .header("Authorization", format!("Bearer {}", api_key))
+                    .json(&payload)
+            })
+            .await?;
+        if !response.status().is_success() {
+            return Err(Self::provider_error("OpenAI", response).await);
+        }
+
+        let result: Value = response.json().await?;
+        let message = result["choices"].get(0).ok_or_else(invalid_response)?["message"].clone();
+        Ok(ChatTurn {
+            content: message_text(&message),
+            tool_calls: message["tool_calls"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .map(|call| ToolCall {
+                    id: call["id"].as_str().unwrap_or_default().to_string(),
+                    name: call["function"]["name"].as_str().unwrap_or_default().to_string(),
+                    arguments: call_arguments(&call["function"]["arguments"]),
+                })
+                .collect(),
+            usage: TokenUsage::new(
+                result["usage"]["prompt_tokens"].as_u64().unwrap_or(0),
+                result["usage"]["completion_tokens"].as_u64().unwrap_or(0),
+            ),
+        })
+    }
+
+    async fn chat_anthropic(
+        &self,
+        api_key: &str,
+        model: &str,
+        system: &str,
+        messages: &[Value],
+        tools: &ToolRegistry,
+    ) -> Result<ChatTurn, AppError> {
+        let mut payload = json!({
+            "model": model,
+            "max_tokens": MAX_OUTPUT_TOKENS,
+            "system": system,
+            "messages": anthropic_messages(messages),
+        });
+        let tools = tools.to_anthropic_tools();
+        if !tools.is_empty() {
+            payload["tools"] = Value::Array(tools);
+        }
+
+        let response = self
+            .retry
+            .send(false, || {
+                self.client
+                    .post("https://api.anthropic.com/v1/messages")
+                    .header("x-api-key", api_key)
+                    .header("anthropic-version", "2023-06-01")
+                    .json(&payload)
+            })
+            .await?;
+        if !response.status().is_success() {
+            return Err(Self::provider_error("Anthropic", response).await);
+        }
+
+        let result: Value = response.json().await?;
+        let blocks = result["content"].as_array().ok_or_else(invalid_response)?;
+        Ok(ChatTurn {
+            content: blocks.iter().filter_map(|block| block["text"].as_str()).collect(),
+            tool_calls: blocks
+                .iter()
+                .filter(|block| block["type"] == "tool_use")
+                .map(|block| ToolCall {
+                    id: block["id"].as_str().unwrap_or_default().to_string(),
+                    name: block["name"].as_str().unwrap_or_default().to_string(),
+                    arguments: block["input"].clone(),
+                })
+                .collect(),
+            usage: TokenUsage::new(
+                result["usage"]["input_tokens"].as_u64().unwrap_or(0),
+                result["usage"]["output_tokens"].as_u64().unwrap_or(0),
+            ),
+        })
+    }
+
+    async fn chat_google(
+        &self,
+        api_key: &str,
+        model: &str,
+        system: &str,
+        messages: &[Value],
+        tools: &ToolRegistry,
+    ) -> Result<ChatTurn, AppError> {
+        let mut payload = json!({
+            "systemInstruction": { "parts": [{ "text": system }] },
+            "contents": gemini_contents(messages),
+        });
+        if !tools.definitions().is_empty() {
+            payload["tools"] = Value::Array(tools.to_gemini_tools());
+        }
+
+        let url = format!(
+            "https://generativelanguage.googleapis.com/v1beta/models/{}:generateContent?key={}",
+            model, api_key
+        );
+        let response = self.retry.send(false, || self.client.post(&url).json(&payload)).await?;
+        if !response.status().is_success() {
+            return Err(Self::provider_error("Google", response).await);
+        }
+
+        let result: Value = response.json().await?;
+        let parts = result["candidates"][0]["content"]["parts"]
+            .as_array()
+            .ok_or_else(invalid_response)?;
+        Ok(ChatTurn {
+            content: parts.iter().filter_map(|part| part["text"].as_str()).collect(),
+            tool_calls: parts
+                .iter()
+                .filter_map(|part| part.get("functionCall"))
+                .map(|call| ToolCall {
+                    id: format!("call_{}", uuid::Uuid::new_v4().simple()),
+                    name: call["name"].as_str().unwrap_or_default().to_string(),
+                    arguments: call["args"].clone(),
+                })
+                .collect(),
+            usage: TokenUsage::new(
+                result["usageMetadata"]["promptTokenCount"].as_u64().unwrap_or(0),
+                result["usageMetadata"]["candidatesTokenCount"].as_u64().unwrap_or(0),
+            ),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transcript() -> Vec<Value> {
+        let turn = ChatTurn {
+            content: String::new(),
+            tool_calls: vec![ToolCall {
+                id: "call_1".to_string(),
+                name: "search_files".to_string(),
+                arguments: json!({ "query": "notes" }),
+            }],
+            usage: TokenUsage::default(),
+        };
+        vec![
+            json!({ "role": "system", "content": "ignored" }),
+            json!({ "role": "user", "content": [{ "type": "text", "text": "find my notes" }] }),
+            turn.to_message(),
+            json!({ "role": "tool", "tool_call_id": "call_1", "content": "/home/me/notes.md" }),
+            json!({ "role": "user", "content": "open it" }),
+        ]
+    }
+
+    #[test]
+    fn test_anthropic_messages() {
+        let messages = anthropic_messages(&transcript());
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[0]["content"][0]["text"], "find my notes");
+        assert_eq!(messages[1]["content"][0]["type"], "tool_use");
+        assert_eq!(messages[1]["content"][0]["input"]["query"], "notes");
+        // The tool result and the next user message share one user turn
+        assert_eq!(messages[2]["role"], "user");
+        assert_eq!(messages[2]["content"][0]["tool_use_id"], "call_1");
+        assert_eq!(messages[2]["content"][1]["text"], "open it");
+    }
+
+    #[test]
+    fn test_gemini_contents() {
+        let contents = gemini_contents(&transcript());
+        assert_eq!(contents.len(), 3);
+        assert_eq!(contents[1]["role"], "model");
+        assert_eq!(contents[1]["parts"][0]["functionCall"]["name"], "search_files");
+        assert_eq!(contents[2]["parts"][0]["functionResponse"]["name"], "search_files");
+    }
+}
@@ -0,0 +1,502 @@
+//! Provider health monitoring and automatic failover between AI providers
+//!
+//! `ProviderHealth` keeps a sliding window of request outcomes per provider.
+//! Providers with repeated failures (or a rate limit response) are marked
+//! down for a cooldown period, and `FailoverPolicy` decides which configured
+//! provider a request should go to next. Every switch is recorded as a
+//! `FailoverEvent` so the UI can tell the user which provider answered.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+/// Failover events kept for the UI
+const MAX_EVENTS: usize = 50;
+
+/// Thresholds for classifying provider health
+#[derive(Debug, Clone)]
+pub struct HealthConfig {
+    /// Outcomes kept per provider
+    pub window_size: usize,
+    /// Outcomes needed before the error rate is trusted
+    pub min_samples: usize,
+    /// Error rate at which a provider counts as degraded
+    pub error_rate_threshold: f32,
+    /// Consecutive failures after which a provider is taken out of rotation
+    pub down_after_failures: u32,
+    /// How long a down provider is skipped before it is tried again
+    pub cooldown: Duration,
+    /// Average latency at which a provider counts as degraded
+    pub slow_latency_ms: u64,
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        Self {
+            window_size: 20,
+            min_samples: 4,
+            error_rate_threshold: 0.5,
+            down_after_failures: 3,
+            cooldown: Duration::from_secs(60),
+            slow_latency_ms: 30_000,
+        }
+    }
+}
+
+/// Result of one request to a provider
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct RequestOutcome {
+    pub success: bool,
+    pub latency_ms: u64,
+    /// HTTP status of a failed request; None for network errors
+    #[serde(default)]
+    pub status_code: Option<u16>,
+}
+
+impl RequestOutcome {
+    pub fn success(latency_ms: u64) -> Self {
+        Self { success: true, latency_ms, status_code: None }
+    }
+
+    pub fn failure(latency_ms: u64, status_code: Option<u16>) -> Self {
+        Self { success: false, latency_ms, status_code }
+    }
+
+    fn is_rate_limited(&self) -> bool {
+        self.status_code == Some(429)
+    }
+}
+
+/// Health classification of a provider
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProviderStatus {
+    Healthy,
+    Degraded,
+    Down,
+}
+
+/// Health summary of one provider
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderHealthReport {
+    pub provider: String,
+    pub status: ProviderStatus,
+    /// Requests in the current window
+    pub requests: usize,
+    pub error_rate: f32,
+    pub avg_latency_ms: Option<u64>,
+    pub consecutive_failures: u32,
+    pub last_error_status: Option<u16>,
+    /// Seconds until a down provider is tried again
+    pub retry_after_secs: Option<u64>,
+}
+
+/// Switch from a failing provider to the next one
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FailoverEvent {
+    pub id: u64,
+    pub from: String,
+    pub to: String,
+    pub reason: String,
+    pub at: DateTime<Utc>,
+}
+
+/// Failed provider request
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("{provider}: {message}")]
+pub struct ProviderError {
+    pub provider: String,
+    /// HTTP status, or None for network errors and timeouts
+    pub status_code: Option<u16>,
+    pub message: String,
+}
+
+impl ProviderError {
+    /// Whether another provider might succeed where this one failed
+    ///
+    /// Network errors, timeouts, rate limits and server errors are outages;
+    /// other client errors (bad key, invalid request) would fail anywhere.
+    pub fn is_retryable(&self) -> bool {
+        is_retryable_status(self.status_code)
+    }
+}
+
+/// Whether a failure with this status should move on to another provider
+pub fn is_retryable_status(status_code: Option<u16>) -> bool {
+    match status_code {
+        None => true,
+        Some(code) => code == 408 || code == 429 || code >= 500,
+    }
+}
+
+/// Which providers a request may fall back to
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FailoverPolicy {
+    /// Providers tried after the preferred one, in order
+    #[serde(default)]
+    pub fallbacks: Vec<String>,
+}
+
+#[derive(Default)]
+struct ProviderStats {
+    outcomes: VecDeque<RequestOutcome>,
+    consecutive_failures: u32,
+    last_error_status: Option<u16>,
+    down_until: Option<Instant>,
+}
+
+#[derive(Default)]
+struct HealthState {
+    providers: HashMap<String, ProviderStats>,
+    events: VecDeque<FailoverEvent>,
+    next_event_id: u64,
+}
+
+/// Per-provider health tracker shared by all AI requests
+pub struct ProviderHealth {
+    config: RwLock<HealthConfig>,
+    state: Mutex<HealthState>,
+}
+
+impl ProviderHealth {
+    pub fn new(config: HealthConfig) -> Self {
+        Self {
+            config: RwLock::new(config),
+            state: Mutex::new(HealthState::default()),
+        }
+    }
+
+    /// Replaces the health thresholds; recorded outcomes are kept
+    pub fn set_config(&self, config: HealthConfig) {
+        *self.config.write().unwrap() = config;
+    }
+
+    fn config(&self) -> HealthConfig {
+        self.config.read().unwrap().clone()
+    }
+
+    /// Records the outcome of a request
+    pub fn record(&self, provider: &str, outcome: RequestOutcome) -> ProviderHealthReport {
+        let config = self.config();
+        let mut state = self.state.lock().unwrap();
+        let stats = state.providers.entry(provider.to_string()).or_default();
+
+        stats.outcomes.push_back(outcome);
+        while stats.outcomes.len() > config.window_size {
+            stats.outcomes.pop_front();
+        }
+
+        if outcome.success {
+            stats.consecutive_failures = 0;
+            stats.down_until = None;
+        } else {
+            stats.consecutive_failures += 1;
+            stats.last_error_status = outcome.status_code;
+            if outcome.is_rate_limited() || stats.consecutive_failures >= config.down_after_failures {
+                if stats.down_until.is_none() {
+                    tracing::warn!(
+                        "AI provider {} marked down for {}s after {} failure(s)",
+                        provider,
+                        config.cooldown.as_secs(),
+                        stats.consecutive_failures
+                    );
+                }
+                stats.down_until = Some(Instant::now() + config.cooldown);
+            }
+        }
+
+        self.report(provider, stats)
+    }
+
+    /// Current status of a provider; unknown providers are healthy
+    pub fn status(&self, provider: &str) -> ProviderStatus {
+        let state = self.state.lock().unwrap();
+        state
+            .providers
+            .get(provider)
+            .map(|stats| self.classify(stats))
+            .unwrap_or(ProviderStatus::Healthy)
+    }
+
+    /// Health of every provider that has handled a request
+    pub fn reports(&self) -> Vec<ProviderHealthReport> {
+        let state = self.state.lock().unwrap();
+        let mut reports: Vec<_> = state
+            .providers
+            .iter()
+            .map(|(provider, stats)| self.report(provider, stats))
+            .collect();
+        reports.sort_by(|a, b| a.provider.cmp(&b.provider));
+        reports
+    }
+
+    /// Order in which to try providers for a request
+    ///
+    /// The preferred provider comes first and fallbacks follow in their
+    /// configured order. Providers that are down move to the end, so they
+    /// are still tried when nothing else is left.
+    pub fn plan(&self, preferred: &str, policy: &FailoverPolicy) -> Vec<String> {
+        let mut candidates = vec![preferred.to_string()];
+        for fallback in &policy.fallbacks {
+            if !fallback.is_empty() && !candidates.contains(fallback) {
+                candidates.push(fallback.clone());
+            }
+        }
+
+        let (up, down): (Vec<_>, Vec<_>) = candidates
+            .into_iter()
+            .partition(|provider| self.status(provider) != ProviderStatus::Down);
+        up.into_iter().chain(down).collect()
+    }
+
+    /// Records a switch between providers
+    pub fn record_failover(&self, from: &str, to: &str, reason: &str) -> FailoverEvent {
+        let mut state = self.state.lock().unwrap();
+        state.next_event_id += 1;
+        let event = FailoverEvent {
+            id: state.next_event_id,
+            from: from.to_string(),
+            to: to.to_string(),
+            reason: reason.to_string(),
+            at: Utc::now(),
+        };
+
+        tracing::info!("AI request failed over from {} to {}: {}", from, to, reason);
+        crate::events::publish(crate::events::Event::ProviderFailover {
+            from: from.to_string(),
+            to: to.to_string(),
+            reason: reason.to_string(),
+        });
+        state.events.push_back(event.clone());
+        while state.events.len() > MAX_EVENTS {
+            state.events.pop_front();
+        }
+        event
+    }
+
+    /// Failover events newer than `since` (all kept events when None)
+    pub fn events_since(&self, since: Option<u64>) -> Vec<FailoverEvent> {
+        let state = self.state.lock().unwrap();
+        state
+            .events
+            .iter()
+            .filter(|event| since.is_none_or(|id| event.id > id))
+            .cloned()
+            .collect()
+    }
+
+    /// Runs a request against the planned providers until one succeeds
+    ///
+    /// Each attempt is recorded. Non-retryable errors are returned at once;
+    /// otherwise the last error is returned when every provider failed.
+    /// On success, returns the provider that answered with its result.
+    pub async fn execute<T, F, Fut>(
+        &self,
+        preferred: &str,
+        policy: &FailoverPolicy,
+        mut request: F,
+    ) -> Result<(String, T), ProviderError>
+    where
+        F: FnMut(String) -> Fut,
+        Fut: Future<Output = Result<T, ProviderError>>,
+    {
+        let plan = self.plan(preferred, policy);
+        let mut last_error: Option<ProviderError> = None;
+
+        for provider in plan {
+            if let Some(error) = &last_error {
+                self.record_failover(&error.provider, &provider, &error.message);
+            }
+
+            let start = Instant::now();
+            let result = request(provider.clone()).await;
+            let latency_ms = start.elapsed().as_millis() as u64;
+
+            match result {
+                Ok(value) => {
+                    self.record(&provider, RequestOutcome::success(latency_ms));
+                    return Ok((provider, value));
+                }
+                Err(error) => {
+                    self.record(&provider, RequestOutcome::failure(latency_ms, error.status_code));
+                    if !error.is_retryable() {
+                        return Err(error);
+                    }
+                    last_error = Some(error);
+                }
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| ProviderError {
+            provider: preferred.to_string(),
+            status_code: None,
+            message: "No provider available".to_string(),
+        }))
+    }
+
+    fn classify(&self, stats: &ProviderStats) -> ProviderStatus {
+        if stats.down_until.is_some_and(|until| until > Instant::now()) {
+            return ProviderStatus::Down;
+        }
+        let config = self.config.read().unwrap();
+        if stats.outcomes.len() >= config.min_samples {
+            let avg_latency = stats.outcomes.iter().map(|o| o.latency_ms).sum::<u64>() / stats.outcomes.len() as u64;
+            if error_rate(stats) >= config.error_rate_threshold || avg_latency >= config.slow_latency_ms {
+                return ProviderStatus::Degraded;
+            }
+        }
+        ProviderStatus::Healthy
+    }
+
+    fn report(&self, provider: &str, stats: &ProviderStats) -> ProviderHealthReport {
+        let requests = stats.outcomes.len();
+        let avg_latency_ms = (requests > 0)
+            .then(|| stats.outcomes.iter().map(|o| o.latency_ms).sum::<u64>() / requests as u64);
+        let retry_after_secs = stats
+            .down_until
+            .and_then(|until| until.checked_duration_since(Instant::now()))
+            .map(|remaining| remaining.as_secs().max(1));
+
+        ProviderHealthReport {
+            provider: provider.to_string(),
+            status: self.classify(stats),
+            requests,
+            error_rate: error_rate(stats),
+            avg_latency_ms,
+            consecutive_failures: stats.consecutive_failures,
+            last_error_status: stats.last_error_status,
+            retry_after_secs,
+        }
+    }
+}
+
+impl Default for ProviderHealth {
+    fn default() -> Self {
+        Self::new(HealthConfig::default())
+    }
+}
+
+fn error_rate(stats: &ProviderStats) -> f32 {
+    if stats.outcomes.is_empty() {
+        return 0.0;
+    }
+    let failures = stats.outcomes.iter().filter(|o| !o.success).count();
+    failures as f32 / stats.outcomes.len() as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(fallbacks: &[&str]) -> FailoverPolicy {
+        FailoverPolicy {
+            fallbacks: fallbacks.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    fn error(provider: &str, status_code: Option<u16>) -> ProviderError {
+        ProviderError {
+            provider: provider.to_string(),
+            status_code,
+            message: format!("status {:?}", status_code),
+        }
+    }
+
+    #[test]
+    fn test_status_transitions() {
+        let health = ProviderHealth::default();
+        assert_eq!(health.status("openai"), ProviderStatus::Healthy);
+
+        health.record("openai", RequestOutcome::success(100));
+        health.record("openai", RequestOutcome::success(100));
+        health.record("openai", RequestOutcome::failure(100, Some(500)));
+        health.record("openai", RequestOutcome::failure(100, Some(500)));
+        assert_eq!(health.status("openai"), ProviderStatus::Degraded);
+
+        let report = health.record("openai", RequestOutcome::failure(100, Some(502)));
+        assert_eq!(report.status, ProviderStatus::Down);
+        assert_eq!(report.consecutive_failures, 3);
+        assert!(report.retry_after_secs.is_some());
+
+        // One success brings the provider back
+        health.record("openai", RequestOutcome::success(100));
+        assert_ne!(health.status("openai"), ProviderStatus::Down);
+    }
+
+    #[test]
+    fn test_rate_limit_marks_down_immediately() {
+        let health = ProviderHealth::default();
+        let report = health.record("openai", RequestOutcome::failure(50, Some(429)));
+        assert_eq!(report.status, ProviderStatus::Down);
+        assert_eq!(report.last_error_status, Some(429));
+    }
+
+    #[test]
+    fn test_cooldown_expires() {
+        let health = ProviderHealth::new(HealthConfig {
+            cooldown: Duration::ZERO,
+            ..Default::default()
+        });
+        health.record("openai", RequestOutcome::failure(50, Some(429)));
+        assert_ne!(health.status("openai"), ProviderStatus::Down);
+    }
+
+    #[test]
+    fn test_plan_moves_down_providers_last() {
+        let health = ProviderHealth::default();
+        assert_eq!(
+            health.plan("openai", &policy(&["anthropic", "openai", "google"])),
+            vec!["openai", "anthropic", "google"]
+        );
+
+        health.record("openai", RequestOutcome::failure(50, Some(429)));
+        assert_eq!(
+            health.plan("openai", &policy(&["anthropic"])),
+            vec!["anthropic", "openai"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_execute_fails_over() {
+        let health = ProviderHealth::default();
+
+        let (provider, answer) = health
+            .execute("openai", &policy(&["anthropic"]), |provider| async move {
+                if provider == "openai" {
+                    Err(error(&provider, Some(503)))
+                } else {
+                    Ok(format!("answer from {}", provider))
+                }
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(provider, "anthropic");
+        assert_eq!(answer, "answer from anthropic");
+
+        let events = health.events_since(None);
+        assert_eq!(events.len(), 1);
+        assert_eq!((events[0].from.as_str(), events[0].to.as_str()), ("openai", "anthropic"));
+        assert!(health.events_since(Some(events[0].id)).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_execute_stops_on_client_error() {
+        let health = ProviderHealth::default();
+        let mut attempts = 0;
+
+        let result: Result<(String, ()), _> = health
+            .execute("openai", &policy(&["anthropic"]), |provider| {
+                attempts += 1;
+                async move { Err(error(&provider, Some(401))) }
+            })
+            .await;
+
+        assert_eq!(result.unwrap_err().status_code, Some(401));
+        assert_eq!(attempts, 1);
+        assert!(health.events_since(None).is_empty());
+    }
+}
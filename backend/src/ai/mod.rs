@@ -0,0 +1,404 @@
+//! AI provider management for embeddings and completions
+#![allow(dead_code)]
+
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use crate::error::AppError;
+
+pub mod chat;
+pub mod health;
+pub mod response_cache;
+pub mod retry;
+
+pub use chat::{ChatTurn, TokenUsage};
+pub use health::{FailoverEvent, FailoverPolicy, ProviderHealth, ProviderHealthReport, RequestOutcome};
+pub use response_cache::{CacheableRequest, ResponseCache, ResponseCacheStats};
+pub use retry::RetryPolicy;
+
+/// Fast model used when a request names none
+pub fn default_model(provider: &str) -> Option<&'static str> {
+    match provider {
+        "openai" => Some("gpt-4o-mini"),
+        "anthropic" => Some("claude-3-5-haiku-20241022"),
+        "google" => Some("gemini-2.0-flash"),
+        _ => None,
+    }
+}
+
+#[derive(Clone)]
+pub struct AIManager {
+    client: Client,
+    providers: HashMap<String, ProviderConfig>,
+    retry: RetryPolicy,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProviderConfig {
+    pub name: String,
+    pub base_url: String,
+    pub models: Vec<String>,
+    pub embedding_model: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct ProviderInfo {
+    pub provider: String,
+    pub models: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct OpenAIModelsResponse {
+    data: Vec<OpenAIModel>,
+}
+
+#[derive(Deserialize)]
+struct OpenAIModel {
+    id: String,
+}
+
+#[derive(Deserialize)]
+struct GeminiModelsResponse {
+    models: Vec<GeminiModel>,
+}
+
+#[derive(Deserialize)]
+struct GeminiModel {
+    name: String,
+}
+
+impl AIManager {
+    pub fn new() -> Self {
+        let mut providers = HashMap::new();
+        
+        providers.insert("openai".to_string(), ProviderConfig {
+            name: "OpenAI".to_string(),
+            base_url: "https://api.openai.com/v1".to_string(),
+            models: vec![
+                "gpt-4o".to_string(),
+                "gpt-4o-mini".to_string(),
+                "o1".to_string(),
+                "o1-mini".to_string(),
+                "o3-mini".to_string(),
+            ],
+            embedding_model: Some("text-embedding-3-small".to_string()),
+        });
+
+        providers.insert("anthropic".to_string(), ProviderConfig {
+            name: "Anthropic".to_string(),
+            base_url: "https://api.anthropic.com/v1".to_string(),
+            models: vec![
+                "claude-3-5-sonnet-20241022".to_string(),
+                "claude-3-5-haiku-20241022".to_string(),
+                "claude-3-opus-20240229".to_string(),
+            ],
+            embedding_model: None,
+        });
+
+        providers.insert("google".to_string(), ProviderConfig {
+            name: "Google".to_string(),
+            base_url: "https://generativelanguage.googleapis.com/v1beta".to_string(),
+            models: vec![
+                "gemini-3-pro".to_string(),
+                "gemini-3-flash".to_string(),
+                "gemini-2.0-flash".to_string(),
+                "gemini-2.0-flash-thinking-preview".to_string(),
+                "gemini-2.0-pro-exp-02-05".to_string(),
+                "gemini-1.5-pro".to_string(),
+                "gemini-1.5-flash".to_string(),
+            ],
+            embedding_model: Some("text-embedding-004".to_string()),
+        });
+
+        Self {
+            client: Client::new(),
+            providers,
+            retry: RetryPolicy::default(),
+        }
+    }
+
+    pub async fn detect_provider(&self, api_key: &str) -> Result<ProviderInfo, AppError> {
+        let provider = self.detect_provider_from_key(api_key)?;
+        let started = std::time::Instant::now();
+        let models = self.fetch_models(&provider, api_key).await;
+        crate::metrics::record_ai_request(&provider, "list_models", models.is_ok(), started.elapsed());
+        let models = models?;
+        
+        Ok(ProviderInfo {
+            provider: self.providers[&provider].name.clone(),
+            models,
+        })
+    }
+
+    fn detect_provider_from_key(&self, api_key: &str) -> Result<String, AppError> {
+        if api_key.starts_with("sk-") {
+            Ok("openai".to_string())
+        } else if api_key.starts_with("sk-ant-") {
+            Ok("anthropic".to_string())
+        } else if api_key.starts_with("AIza") {
+            Ok("google".to_string())
+        } else {
+            Err(AppError::BadRequest("Unknown API key format".to_string()))
+        }
+    }
+
+    async fn fetch_models(&self, provider: &str, api_key: &str) -> Result<Vec<String>, AppError> {
+        match provider {
+            "openai" => self.fetch_openai_models(api_key).await,
+            "google" => self.fetch_google_models(api_key).await,
+            "anthropic" => {
+                // Anthropic doesn't have a models endpoint, return predefined models
+                Ok(self.providers["anthropic"].models.clone())
+            }
+            _ => Err(AppError::BadRequest("Unsupported provider".to_string())),
+        }
+    }
+
+    async fn fetch_openai_models(&self, api_key: &str) -> Result<Vec<String>, AppError> {
+        let response = self
+            .retry
+            .send(true, || {
+                self.client
+                    .get("https://api.openai.com/v1/models")
+                    .header("Authorization", format!("Bearer {}", api_key))
+            })
+            .await?;
+
+        if !response.status().is_success() {
+            return Ok(self.providers["openai"].models.clone());
+        }
+
+        let models_response: OpenAIModelsResponse = response.json().await?;
+        let mut models: Vec<String> = models_response
+            .data
+            .into_iter()
+            .filter(|m| m.id.starts_with("gpt-") || m.id.starts_with("o1-"))
+            .map(|m| m.id)
+            .collect();
+
+        models.sort();
+        Ok(models)
+    }
+
+    async fn fetch_google_models(&self, api_key: &str) -> Result<Vec<String>, AppError> {
+        let url = format!("https://generativelanguage.googleapis.com/v1beta/models?key={}", api_key);
+        let response = self.retry.send(true, || self.client.get(&url)).await?;
+
+        if !response.status().is_success() {
+            return Ok(self.providers["google"].models.clone());
+        }
+
+        let models_response: GeminiModelsResponse = response.json().await?;
+        let mut models: Vec<String> = models_response
+            .models
+            .into_iter()
+            .filter(|m| m.name.contains("gemini"))
+            .map(|m| m.name.replace("models/", ""))
+            .collect();
+
+        models.sort();
+        Ok(models)
+    }
+
+    pub async fn generate_embedding(&self, provider: &str, api_key: &str, text: &str) -> Result<Vec<f32>, AppError> {
+        let started = std::time::Instant::now();
+        let embedding = match provider {
+            "openai" => self.generate_openai_embedding(api_key, text).await,
+            "google" => self.generate_google_embedding(api_key, text).await,
+            _ => return Err(AppError::BadRequest("Provider doesn't support embeddings".to_string())),
+        };
+        crate::metrics::record_ai_request(provider, "embedding", embedding.is_ok(), started.elapsed());
+        embedding
+    }
+
+    /// Single-turn text completion; `model` defaults to a fast model of the
+    /// provider
+    pub async fn complete(
+        &self,
+        provider: &str,
+        api_key: &str,
+        model: Option<&str>,
+        system: &str,
+        prompt: &str,
+    ) -> Result<String, AppError> {
+        let started = std::time::Instant::now();
+        let model = model
+            .or_else(|| default_model(provider))
+            .ok_or_else(|| AppError::BadRequest(format!("Unsupported provider: {}", provider)))?;
+        let completion = match provider {
+            "openai" => self.complete_openai(api_key, model, system, prompt).await,
+            "anthropic" => self.complete_anthropic(api_key, model, system, prompt).await,
+            "google" => self.complete_google(api_key, model, system, prompt).await,
+            _ => return Err(AppError::BadRequest(format!("Unsupported provider: {}", provider))),
+        };
+        crate::metrics::record_ai_request(provider, "completion", completion.is_ok(), started.elapsed());
+        completion
+    }
+
+    /// The provider's error message for a failed request; rejected requests
+    /// (bad key, unknown model) are the caller's to fix
+    async fn provider_error(provider: &str, response: reqwest::Response) -> AppError {
+        let status = response.status();
+        let body: serde_json::Value = response.json().await.unwrap_or_default();
+        let message = format!(
+            "{} returned {}: {}",
+            provider,
+            status,
+            body["error"]["message"].as_str().unwrap_or("no details")
+        );
+        if status.is_client_error() && status != reqwest::StatusCode::TOO_MANY_REQUESTS {
+            AppError::BadRequest(message)
+        } else {
+            AppError::Internal(message)
+        }
+    }
+
+    async fn complete_openai(&self, api_key: &str, model: &str, system: &str, prompt: &str) -> Result<String, AppError> {
+        let payload = serde_json::json!({
+            "model": model,
+            "messages": [
+                {"role": "system", "content": system},
+                {"role": "user", "content": prompt}
+            ]
+        });
+
+        // No tools are offered, so asking again is harmless
+        let response = self
+            .retry
+            .send(true, || {
+                self.client
+                    .post("https://api.openai.com/v1/chat/completions")
+                    .header("Authorization", format!("Bearer {}", api_key))
+                    .json(&payload)
+            })
+            .await?;
+        if !response.status().is_success() {
+            return Err(Self::provider_error("OpenAI", response).await);
+        }
+
+        let result: serde_json::Value = response.json().await?;
+        result["choices"][0]["message"]["content"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| AppError::Internal("Invalid completion response".to_string()))
+    }
+
+    async fn complete_anthropic(&self, api_key: &str, model: &str, system: &str, prompt: &str) -> Result<String, AppError> {
+        let payload = serde_json::json!({
+            "model": model,
+            "max_tokens": 2048,
+            "system": system,
+            "messages": [{"role": "user", "content": prompt}]
+        });
+
+        let response = self
+            .retry
+            .send(true, || {
+                self.client
+                    .post("https://api.anthropic.com/v1/messages")
+                    .header("x-api-key", api_key)
+                    .header("anthropic-version", "2023-06-01")
+                    .json(&payload)
+            })
+            .await?;
+        if !response.status().is_success() {
+            return Err(Self::provider_error("Anthropic", response).await);
+        }
+
+        let result: serde_json::Value = response.json().await?;
+        let text: String = result["content"]
+            .as_array()
+            .ok_or_else(|| AppError::Internal("Invalid completion response".to_string()))?
+            .iter()
+            .filter_map(|block| block["text"].as_str())
+            .collect();
+        Ok(text)
+    }
+
+    async fn complete_google(&self, api_key: &str, model: &str, system: &str, prompt: &str) -> Result<String, AppError> {
+        let payload = serde_json::json!({
+            "systemInstruction": {"parts": [{"text": system}]},
+            "contents": [{"role": "user", "parts": [{"text": prompt}]}]
+        });
+
+        let url = format!(
+            "https://generativelanguage.googleapis.com/v1beta/models/{}:generateContent?key={}",
+            model, api_key
+        );
+        let response = self
+            .retry
+            .send(true, || self.client.post(&url).json(&payload))
+            .await?;
+        if !response.status().is_success() {
+            return Err(Self::provider_error("Google", response).await);
+        }
+
+        let result: serde_json::Value = response.json().await?;
+        let text: String = result["candidates"][0]["content"]["parts"]
+            .as_array()
+            .ok_or_else(|| AppError::Internal("Invalid completion response".to_string()))?
+            .iter()
+            .filter_map(|part| part["text"].as_str())
+            .collect();
+        Ok(text)
+    }
+
+    async fn generate_openai_embedding(&self, api_key: &str, text: &str) -> Result<Vec<f32>, AppError> {
+        let payload = serde_json::json!({
+            "input": text,
+            "model": "text-embedding-3-small"
+        });
+
+        // Embedding the same text twice is harmless
+        let response = self
+            .retry
+            .send(true, || {
+                self.client
+                    .post("https://api.openai.com/v1/embeddings")
+                    .header("Authorization", format!("Bearer {}", api_key))
+                    .header("Content-Type", "application/json")
+                    .json(&payload)
+            })
+            .await?;
+
+        let result: serde_json::Value = response.json().await?;
+        let embedding = result["data"][0]["embedding"]
+            .as_array()
+            .ok_or_else(|| AppError::Internal("Invalid embedding response".to_string()))?
+            .iter()
+            .map(|v| v.as_f64().unwrap_or(0.0) as f32)
+            .collect();
+
+        Ok(embedding)
+    }
+
+    async fn generate_google_embedding(&self, api_key: &str, text: &str) -> Result<Vec<f32>, AppError> {
+        let payload = serde_json::json!({
+            "model": "models/text-embedding-004",
+            "content": {
+                "parts": [{"text": text}]
+            }
+        });
+
+        let url = format!("https://generativelanguage.googleapis.com/v1beta/models/text-embedding-004:embedContent?key={}", api_key);
+        let response = self
+            .retry
+            .send(true, || {
+                self.client
+                    .post(&url)
+                    .header("Content-Type", "application/json")
+                    .json(&payload)
+            })
+            .await?;
+
+        let result: serde_json::Value = response.json().await?;
+        let embedding = result["embedding"]["values"]
+            .as_array()
+            .ok_or_else(|| AppError::Internal("Invalid embedding response".to_string()))?
+            .iter()
+            .map(|v| v.as_f64().unwrap_or(0.0) as f32)
+            .collect();
+
+        Ok(embedding)
+    }
+}
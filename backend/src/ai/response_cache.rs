@@ -0,0 +1,353 @@
+//! Deterministic response cache for AI requests
+//!
+//! Requests made with temperature 0 are expected to return the same answer
+//! for the same input, so their responses can be replayed instead of
+//! re-billing tokens (repeated workflow steps, regression tests). Entries are
+//! keyed on the model and hashes of the system prompt, message history and
+//! tool definitions, and are bounded by a TTL, an entry count and a total size.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+/// Response cache limits
+#[derive(Debug, Clone)]
+pub struct ResponseCacheConfig {
+    pub enabled: bool,
+    pub ttl: Duration,
+    pub max_entries: usize,
+    pub max_size_bytes: usize,
+}
+
+impl Default for ResponseCacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: std::env::var("SKHOOT_AI_CACHE")
+                .map(|v| !matches!(v.to_ascii_lowercase().as_str(), "0" | "false" | "off"))
+                .unwrap_or(true),
+            ttl: Duration::from_secs(
+                std::env::var("SKHOOT_AI_CACHE_TTL_SECS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(24 * 60 * 60),
+            ),
+            max_entries: std::env::var("SKHOOT_AI_CACHE_MAX_ENTRIES")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(500),
+            max_size_bytes: 20 * 1024 * 1024,
+        }
+    }
+}
+
+/// Inputs that determine an AI response
+#[derive(Debug, Clone, Deserialize)]
+pub struct CacheableRequest {
+    pub model: String,
+    #[serde(default)]
+    pub system_prompt: String,
+    /// Message history including the new message, in the provider-neutral format
+    #[serde(default)]
+    pub messages: serde_json::Value,
+    #[serde(default)]
+    pub tools: Option<serde_json::Value>,
+    /// Only temperature 0 requests are cached
+    pub temperature: Option<f32>,
+    /// Skip the lookup and fetch a fresh response (which is still stored)
+    #[serde(default)]
+    pub bypass_cache: bool,
+}
+
+impl CacheableRequest {
+    /// Cache key, or None when the request is not deterministic
+    pub fn cache_key(&self) -> Option<String> {
+        if self.temperature != Some(0.0) {
+            return None;
+        }
+
+        let mut hasher = Sha256::new();
+        hasher.update(self.model.as_bytes());
+        hasher.update([0]);
+        hasher.update(hash_str(&self.system_prompt));
+        hasher.update(hash_str(&self.messages.to_string()));
+        hasher.update(hash_str(
+            &self.tools.as_ref().map(|t| t.to_string()).unwrap_or_default(),
+        ));
+        Some(hex::encode(hasher.finalize()))
+    }
+}
+
+fn hash_str(value: &str) -> [u8; 32] {
+    Sha256::digest(value.as_bytes()).into()
+}
+
+/// Response cache statistics
+#[derive(Debug, Clone, Serialize)]
+pub struct ResponseCacheStats {
+    pub enabled: bool,
+    pub entries: usize,
+    pub size_bytes: usize,
+    pub hits: u64,
+    pub misses: u64,
+    pub max_entries: usize,
+    pub max_size_bytes: usize,
+    pub ttl_secs: u64,
+}
+
+struct CachedResponse {
+    response: serde_json::Value,
+    size_bytes: usize,
+    stored_at: Instant,
+    last_used: Instant,
+}
+
+#[derive(Default)]
+struct CacheState {
+    entries: HashMap<String, CachedResponse>,
+    size_bytes: usize,
+    hits: u64,
+    misses: u64,
+}
+
+/// In-memory cache of deterministic AI responses
+pub struct ResponseCache {
+    config: RwLock<ResponseCacheConfig>,
+    state: Mutex<CacheState>,
+}
+
+impl ResponseCache {
+    pub fn new(config: ResponseCacheConfig) -> Self {
+        Self {
+            config: RwLock::new(config),
+            state: Mutex::new(CacheState::default()),
+        }
+    }
+
+    /// Replaces the limits, evicting entries that no longer fit
+    pub fn set_config(&self, config: ResponseCacheConfig) {
+        *self.config.write().unwrap() = config.clone();
+        let mut state = self.state.lock().unwrap();
+        if config.enabled {
+            Self::evict(&config, &mut state);
+        } else {
+            state.entries.clear();
+            state.size_bytes = 0;
+        }
+    }
+
+    fn config(&self) -> ResponseCacheConfig {
+        self.config.read().unwrap().clone()
+    }
+
+    /// Cached response for a request, if it is cacheable and fresh
+    pub fn get(&self, request: &CacheableRequest) -> Option<serde_json::Value> {
+        let config = self.config();
+        if !config.enabled || request.bypass_cache {
+            return None;
+        }
+        let key = request.cache_key()?;
+
+        let mut state = self.state.lock().unwrap();
+        let fresh = match state.entries.get_mut(&key) {
+            Some(entry) if entry.stored_at.elapsed() < config.ttl => {
+                entry.last_used = Instant::now();
+                Some(entry.response.clone())
+            }
+            Some(_) => {
+                Self::remove_entry(&mut state, &key);
+                None
+            }
+            None => None,
+        };
+
+        match fresh {
+            Some(_) => state.hits += 1,
+            None => state.misses += 1,
+        }
+        crate::metrics::record_cache_lookup("ai_response", fresh.is_some());
+        fresh
+    }
+
+    /// Stores a response; returns false when the request is not cacheable
+    /// or the response exceeds the size limit
+    pub fn put(&self, request: &CacheableRequest, response: serde_json::Value) -> bool {
+        let config = self.config();
+        if !config.enabled {
+            return false;
+        }
+        let Some(key) = request.cache_key() else {
+            return false;
+        };
+        let size_bytes = key.len() + response.to_string().len();
+        if size_bytes > config.max_size_bytes {
+            return false;
+        }
+
+        let mut state = self.state.lock().unwrap();
+        Self::remove_entry(&mut state, &key);
+        let now = Instant::now();
+        state.entries.insert(
+            key,
+            CachedResponse {
+                response,
+                size_bytes,
+                stored_at: now,
+                last_used: now,
+            },
+        );
+        state.size_bytes += size_bytes;
+        Self::evict(&config, &mut state);
+        true
+    }
+
+    /// Removes every entry, returning how many were dropped
+    pub fn clear(&self) -> usize {
+        let mut state = self.state.lock().unwrap();
+        let count = state.entries.len();
+        state.entries.clear();
+        state.size_bytes = 0;
+        count
+    }
+
+    pub fn stats(&self) -> ResponseCacheStats {
+        let config = self.config();
+        let state = self.state.lock().unwrap();
+        ResponseCacheStats {
+            enabled: config.enabled,
+            entries: state.entries.len(),
+            size_bytes: state.size_bytes,
+            hits: state.hits,
+            misses: state.misses,
+            max_entries: config.max_entries,
+            max_size_bytes: config.max_size_bytes,
+            ttl_secs: config.ttl.as_secs(),
+        }
+    }
+
+    /// Drops expired entries, then least recently used ones over the limits
+    fn evict(config: &ResponseCacheConfig, state: &mut CacheState) {
+        let expired: Vec<String> = state
+            .entries
+            .iter()
+            .filter(|(_, entry)| entry.stored_at.elapsed() >= config.ttl)
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in expired {
+            Self::remove_entry(state, &key);
+        }
+
+        while state.entries.len() > config.max_entries || state.size_bytes > config.max_size_bytes {
+            let Some(oldest) = state
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone())
+            else {
+                break;
+            };
+            Self::remove_entry(state, &oldest);
+        }
+    }
+
+    fn remove_entry(state: &mut CacheState, key: &str) {
+        if let Some(entry) = state.entries.remove(key) {
+            state.size_bytes -= entry.size_bytes;
+        }
+    }
+}
+
+impl Default for ResponseCache {
+    fn default() -> Self {
+        Self::new(ResponseCacheConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn config(max_entries: usize) -> ResponseCacheConfig {
+        ResponseCacheConfig {
+            enabled: true,
+            ttl: Duration::from_secs(60),
+            max_entries,
+            max_size_bytes: 1024 * 1024,
+        }
+    }
+
+    fn request(message: &str, temperature: Option<f32>) -> CacheableRequest {
+        CacheableRequest {
+            model: "gpt-4o".to_string(),
+            system_prompt: "You are Skhoot".to_string(),
+            messages: json!([{ "role": "user", "content": message }]),
+            tools: None,
+            temperature,
+            bypass_cache: false,
+        }
+    }
+
+    #[test]
+    fn test_key_depends_on_inputs() {
+        let base = request("hello", Some(0.0));
+        assert_eq!(base.cache_key(), request("hello", Some(0.0)).cache_key());
+        assert_ne!(base.cache_key(), request("hello!", Some(0.0)).cache_key());
+
+        let mut other_model = base.clone();
+        other_model.model = "gpt-4o-mini".to_string();
+        assert_ne!(base.cache_key(), other_model.cache_key());
+
+        let mut with_tools = base.clone();
+        with_tools.tools = Some(json!([{ "name": "findFile" }]));
+        assert_ne!(base.cache_key(), with_tools.cache_key());
+
+        assert!(request("hello", Some(0.7)).cache_key().is_none());
+        assert!(request("hello", None).cache_key().is_none());
+    }
+
+    #[test]
+    fn test_get_and_put() {
+        let cache = ResponseCache::new(config(10));
+        let req = request("hello", Some(0.0));
+
+        assert!(cache.get(&req).is_none());
+        assert!(cache.put(&req, json!({ "content": "hi" })));
+        assert_eq!(cache.get(&req), Some(json!({ "content": "hi" })));
+
+        // Bypass skips the lookup but not the store
+        let mut bypass = req.clone();
+        bypass.bypass_cache = true;
+        assert!(cache.get(&bypass).is_none());
+        assert!(cache.put(&bypass, json!({ "content": "fresh" })));
+        assert_eq!(cache.get(&req), Some(json!({ "content": "fresh" })));
+
+        assert!(!cache.put(&request("hello", Some(1.0)), json!({ "content": "random" })));
+
+        let stats = cache.stats();
+        assert_eq!((stats.entries, stats.hits, stats.misses), (1, 2, 1));
+        assert_eq!(cache.clear(), 1);
+        assert_eq!(cache.stats().size_bytes, 0);
+    }
+
+    #[test]
+    fn test_lru_eviction_and_ttl() {
+        let cache = ResponseCache::new(config(2));
+        cache.put(&request("a", Some(0.0)), json!("a"));
+        cache.put(&request("b", Some(0.0)), json!("b"));
+        assert!(cache.get(&request("a", Some(0.0))).is_some());
+        cache.put(&request("c", Some(0.0)), json!("c"));
+
+        assert!(cache.get(&request("a", Some(0.0))).is_some());
+        assert!(cache.get(&request("b", Some(0.0))).is_none());
+        assert!(cache.get(&request("c", Some(0.0))).is_some());
+
+        let expired = ResponseCache::new(ResponseCacheConfig {
+            ttl: Duration::ZERO,
+            ..config(10)
+        });
+        expired.put(&request("a", Some(0.0)), json!("a"));
+        assert!(expired.get(&request("a", Some(0.0))).is_none());
+    }
+}
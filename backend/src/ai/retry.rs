@@ -0,0 +1,201 @@
+//! Retries of transient provider errors
+//!
+//! Rate limits (429) and server errors (5xx) are usually gone a moment later,
+//! so a request is retried with exponential backoff and full jitter, waiting
+//! at least as long as the provider's `Retry-After` asks. Requests that are
+//! not idempotent (chat requests offering tools) are only retried when the
+//! provider rejected them before doing any work: rate limits and overload.
+//! A timeout or a 500 could mean the model already answered, and asking
+//! again could produce a different set of tool calls.
+
+use rand::Rng;
+use reqwest::{RequestBuilder, Response, StatusCode};
+use std::time::{Duration, SystemTime};
+
+/// Status codes meaning the request was turned away unprocessed
+const REJECTED: &[u16] = &[429, 503, 529];
+
+/// Backoff limits for provider requests
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// Attempts in total, including the first
+    pub max_attempts: u32,
+    /// Backoff before the first retry, doubled for each further one
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    /// Longest `Retry-After` honored; asking for more fails the request
+    pub max_retry_after: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(8),
+            max_retry_after: Duration::from_secs(60),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Whether a response with this status is worth another attempt
+    pub fn is_retryable(&self, status: StatusCode, idempotent: bool) -> bool {
+        let code = status.as_u16();
+        if REJECTED.contains(&code) {
+            return true;
+        }
+        idempotent && (code == 408 || (500..600).contains(&code))
+    }
+
+    /// Wait before retry number `retry` (from 1), or `None` if the budget is
+    /// spent or the provider asks for a longer pause than we accept
+    pub fn delay(&self, retry: u32, retry_after: Option<Duration>) -> Option<Duration> {
+        if retry >= self.max_attempts {
+            return None;
+        }
+        let ceiling = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(retry.saturating_sub(1)))
+            .min(self.max_delay);
+        let jittered = rand::thread_rng().gen_range(Duration::ZERO..=ceiling);
+        match retry_after {
+            Some(wait) if wait > self.max_retry_after => None,
+            Some(wait) => Some(wait.max(jittered)),
+            None => Some(jittered),
+        }
+    }
+
+    /// Send a request, retrying transient failures. `build` is called for
+    /// every attempt. Returns the last response when retries run out, so the
+    /// caller sees the provider's error.
+    pub async fn send<F>(&self, idempotent: bool, build: F) -> Result<Response, reqwest::Error>
+    where
+        F: Fn() -> RequestBuilder,
+    {
+        let mut retry = 1;
+        loop {
+            match build().send().await {
+                Ok(response) if self.is_retryable(response.status(), idempotent) => {
+                    let retry_after = response
+                        .headers()
+                        .get(reqwest::header::RETRY_AFTER)
+                        .and_then(|v| v.to_str().ok())
+                        .and_then(|v| parse_retry_after(v, SystemTime::now()));
+                    let Some(wait) = self.delay(retry, retry_after) else {
+                        return Ok(response);
+                    };
+                    tracing::debug!("Provider returned {}, retrying in {:?}", response.status(), wait);
+                    tokio::time::sleep(wait).await;
+                }
+                // Unreachable hosts never saw the request; other failures may
+                // have happened after it was processed
+                Err(e) if idempotent || e.is_connect() => {
+                    let Some(wait) = self.delay(retry, None) else {
+                        return Err(e);
+                    };
+                    tracing::debug!("Provider request failed ({}), retrying in {:?}", e, wait);
+                    tokio::time::sleep(wait).await;
+                }
+                result => return result,
+            }
+            retry += 1;
+        }
+    }
+}
+
+/// Parse a `Retry-After` value: seconds, or an HTTP date
+pub fn parse_retry_after(value: &str, now: SystemTime) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let at: SystemTime = chrono::DateTime::parse_from_rfc2822(value).ok()?.into();
+    Some(at.duration_since(now).unwrap_or(Duration::ZERO))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retryable_statuses() {
+        let policy = RetryPolicy::default();
+        for code in [429, 503, 529] {
+            let status = StatusCode::from_u16(code).unwrap();
+            assert!(policy.is_retryable(status, true));
+            assert!(policy.is_retryable(status, false));
+        }
+        for code in [408, 500, 502, 504] {
+            let status = StatusCode::from_u16(code).unwrap();
+            assert!(policy.is_retryable(status, true));
+            assert!(!policy.is_retryable(status, false));
+        }
+        assert!(!policy.is_retryable(StatusCode::BAD_REQUEST, true));
+        assert!(!policy.is_retryable(StatusCode::UNAUTHORIZED, true));
+    }
+
+    #[test]
+    fn test_backoff() {
+        let policy = RetryPolicy::default();
+        for _ in 0..20 {
+            assert!(policy.delay(1, None).unwrap() <= Duration::from_millis(500));
+            assert!(policy.delay(2, None).unwrap() <= Duration::from_secs(1));
+        }
+        assert_eq!(policy.delay(3, None), None);
+
+        // Retry-After is a floor, but not an unbounded one
+        assert_eq!(policy.delay(1, Some(Duration::from_secs(5))), Some(Duration::from_secs(5)));
+        assert_eq!(policy.delay(1, Some(Duration::from_secs(120))), None);
+
+        let long = RetryPolicy {
+            max_attempts: 20,
+            ..RetryPolicy::default()
+        };
+        assert!(long.delay(15, None).unwrap() <= Duration::from_secs(8));
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_445_412_480);
+        assert_eq!(parse_retry_after("30", now), Some(Duration::from_secs(30)));
+        // 2015-10-21T07:28:00Z is 1445412480
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:20 GMT", now),
+            Some(Duration::from_secs(20))
+        );
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:27:00 GMT", now), Some(Duration::ZERO));
+        assert_eq!(parse_retry_after("soon", now), None);
+    }
+
+    #[tokio::test]
+    async fn test_send_retries_until_success() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let replies = [
+                "HTTP/1.1 429 Too Many Requests\r\nRetry-After: 0\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                "HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok",
+            ];
+            for reply in replies {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buf = [0u8; 1024];
+                let _ = socket.read(&mut buf).await;
+                socket.write_all(reply.as_bytes()).await.unwrap();
+            }
+        });
+
+        let policy = RetryPolicy {
+            base_delay: Duration::from_millis(10),
+            ..RetryPolicy::default()
+        };
+        let client = reqwest::Client::new();
+        let url = format!("http://{}/", addr);
+        let response = policy.send(false, || client.get(&url)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.text().await.unwrap(), "ok");
+    }
+}
@@ -0,0 +1,19 @@
+//! Administration API routes
+//! Reports the database schema version, migration history and backups
+
+use axum::{extract::State, response::Json, routing::get, Router};
+
+use crate::db::migrations::SchemaStatus;
+use crate::error::AppError;
+use crate::AppState;
+
+/// API routes for administration
+pub fn admin_routes() -> Router<AppState> {
+    Router::new().route("/admin/db/status", get(get_db_status))
+}
+
+/// Schema version, applied and pending migrations, integrity check result
+/// and pre-migration backups of the database
+pub async fn get_db_status(State(state): State<AppState>) -> Result<Json<SchemaStatus>, AppError> {
+    Ok(Json(state.db.schema_status().await?))
+}
@@ -3,8 +3,8 @@
 
 use axum::{
     extract::{Path, Query, State},
-    http::StatusCode,
-    response::Json,
+    http::{header, StatusCode},
+    response::{IntoResponse, Json, Response},
     routing::{delete, get, post, put},
     Router,
 };
@@ -14,7 +14,13 @@ use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
+use crate::agent_hooks::{CreateHookRequest, HookBinding, HookError, HookStore, UpdateHookRequest};
+use crate::cli_agent::context_set::{ContextSetError, PinRequest, RenderedContext};
+use crate::cli_agent::session::{AgentMessage as ConversationMessage, MessageRole};
+use crate::cli_agent::{AgentMail, ContextPin, ContextSet, ContextSetStore, PinMode, ConversationArchive, ConversationMetadata, ExportFormat, Mailbox, SessionVar, SessionVars, TraceError, TraceEvent, TraceRecorder, VarError};
+use crate::conversation_search::{ConversationIndex, ConversationMessage as IndexedMessage};
 use crate::error::AppError;
+use crate::secrets::{RedactionStatus, SecretScanner};
 
 /// API routes for agent management
 pub fn agent_routes() -> Router<crate::AppState> {
@@ -27,6 +33,20 @@ pub fn agent_routes() -> Router<crate::AppState> {
         .route("/agents/:id/execute", post(execute_agent))
         .route("/agents/:id/status", get(get_agent_status))
         .route("/agents/:id/executions", get(list_agent_executions))
+        .route("/agents/:id/export", get(export_agent_conversation))
+        .route("/agents/:id/trace", get(download_agent_trace))
+        .route("/agents/:id/trace", post(append_agent_trace))
+        .route("/agents/:id/trace", delete(delete_agent_trace))
+        .route("/agents/:id/redaction", get(get_secret_redaction))
+        .route("/agents/:id/redaction", put(set_secret_redaction))
+        .route("/agents/:id/inbox", get(get_inbox))
+        .route("/agents/:id/vars", get(list_session_vars))
+        .route("/agents/:id/vars/:name", put(set_session_var).delete(delete_session_var))
+        .route("/agents/:id/hooks", get(list_agent_hooks).post(create_agent_hook))
+        .route("/agents/:id/hooks/:hook_id", put(update_agent_hook).delete(delete_agent_hook))
+        .route("/agents/:id/context", get(get_context_set).post(pin_context).put(set_context_budget))
+        .route("/agents/:id/context/preview", get(preview_context))
+        .route("/agents/:id/context/:pin_id", put(update_context_pin).delete(unpin_context))
         .route("/executions/:execution_id", get(get_execution))
         .route("/executions/:execution_id", put(update_execution_status))
 }
@@ -229,6 +249,34 @@ pub struct ExecuteAgentRequest {
     pub message: Option<String>,
 }
 
+/// Export query parameters
+#[derive(Debug, Deserialize)]
+pub struct ExportQuery {
+    #[serde(default)]
+    pub format: ExportFormat,
+}
+
+/// Trace download query parameters
+#[derive(Debug, Deserialize)]
+pub struct TraceQuery {
+    /// Replace file contents with their size
+    #[serde(default)]
+    pub redact: bool,
+}
+
+/// Events reported by the chat loop for a session's trace
+#[derive(Debug, Deserialize)]
+pub struct AppendTraceRequest {
+    pub events: Vec<TraceEvent>,
+}
+
+/// Per-session override of secret redaction
+#[derive(Debug, Deserialize)]
+pub struct SetRedactionRequest {
+    /// `null` goes back to the `security.redact_secrets` setting
+    pub enabled: Option<bool>,
+}
+
 /// Update execution status request
 #[derive(Debug, Deserialize)]
 pub struct UpdateExecutionStatusRequest {
@@ -421,6 +469,7 @@ pub async fn create_agent(
     };
     
     STORAGE.save(&agent).await?;
+    publish_agent_changed(&agent.id, "created");
     
     tracing::info!("Created agent: {} ({})", agent.name, agent.id);
     
@@ -525,6 +574,7 @@ pub async fn update_agent(
     agent.updated_at = chrono::Utc::now().timestamp();
     
     STORAGE.save(&agent).await?;
+    publish_agent_changed(&agent.id, "updated");
     
     tracing::info!("Updated agent: {} ({})", agent.name, agent.id);
     
@@ -533,7 +583,7 @@ pub async fn update_agent(
 
 /// Delete agent
 pub async fn delete_agent(
-    State(_state): State<crate::AppState>,
+    State(state): State<crate::AppState>,
     Path(id): Path<String>,
 ) -> Result<StatusCode, AppError> {
     // Check if agent exists
@@ -545,18 +595,67 @@ pub async fn delete_agent(
     }
     
     STORAGE.delete(&id).await?;
+    publish_agent_changed(&id, "deleted");
+    if let Err(e) = HookStore::global().remove_agent(&id) {
+        tracing::warn!("Failed to remove hooks of agent {}: {}", id, e);
+    }
+    if let Err(e) = ConversationIndex::new(state.db.clone()).remove_agent(&id).await {
+        tracing::warn!("Failed to remove agent {} from conversation search: {}", id, e);
+    }
     
     tracing::info!("Deleted agent: {} ({})", agent.name, agent.id);
     
     Ok(StatusCode::NO_CONTENT)
 }
 
-/// Execute agent
-pub async fn execute_agent(
-    State(_state): State<crate::AppState>,
-    Path(id): Path<String>,
-    Json(request): Json<ExecuteAgentRequest>,
-) -> Result<Json<AgentExecution>, AppError> {
+/// Make an execution's messages searchable; failures don't fail the update
+async fn index_execution(state: &crate::AppState, execution: &AgentExecution) {
+    let messages: Vec<IndexedMessage> = execution
+        .messages
+        .iter()
+        .map(|m| IndexedMessage {
+            id: m.id.clone(),
+            role: match m.message_type {
+                MessageType::Input => "user",
+                MessageType::Output => "assistant",
+                MessageType::System => "system",
+            }
+            .to_string(),
+            content: m.content.clone(),
+            tool_calls: Vec::new(),
+            timestamp: chrono::DateTime::from_timestamp_millis(m.timestamp).unwrap_or_default(),
+        })
+        .collect();
+    let index = ConversationIndex::new(state.db.clone());
+    if let Err(e) = index.index_session(&execution.id, Some(&execution.agent_id), &messages).await {
+        tracing::warn!("Failed to index execution {} for search: {}", execution.id, e);
+    }
+}
+
+fn publish_agent_changed(agent_id: &str, action: &str) {
+    crate::events::publish(crate::events::Event::AgentChanged {
+        agent_id: agent_id.to_string(),
+        action: action.to_string(),
+    });
+}
+
+fn publish_execution(execution: &AgentExecution) {
+    let status = serde_json::to_value(&execution.status)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_default();
+    crate::events::publish(crate::events::Event::AgentExecution {
+        agent_id: execution.agent_id.clone(),
+        execution_id: execution.id.clone(),
+        status,
+    });
+}
+
+/// Record a new running execution of an agent; the frontend picks it up
+async fn start_execution(
+    id: &str,
+    context: HashMap<String, serde_json::Value>,
+) -> Result<AgentExecution, AppError> {
     let mut agent = STORAGE.load(&id).await?;
     
     // Check if agent is enabled
@@ -567,12 +666,12 @@ pub async fn execute_agent(
     let now = chrono::Utc::now().timestamp();
     let execution = AgentExecution {
         id: format!("exec-{}-{}", now, uuid::Uuid::new_v4().to_string()[..8].to_string()),
-        agent_id: id.clone(),
+        agent_id: id.to_string(),
         status: ExecutionStatus::Running,
         started_at: now,
         completed_at: None,
         current_workflow_id: agent.workflows.first().cloned(),
-        context: request.context,
+        context,
         messages: Vec::new(),
         error: None,
     };
@@ -584,8 +683,19 @@ pub async fn execute_agent(
     
     // Save execution
     STORAGE.save_execution(&execution).await?;
+    publish_execution(&execution);
     
     tracing::info!("Started execution: {} for agent: {}", execution.id, agent.name);
+    Ok(execution)
+}
+
+/// Execute agent
+pub async fn execute_agent(
+    State(_state): State<crate::AppState>,
+    Path(id): Path<String>,
+    Json(request): Json<ExecuteAgentRequest>,
+) -> Result<Json<AgentExecution>, AppError> {
+    let execution = start_execution(&id, request.context).await?;
     
     // NOTE: Actual agent execution happens in the frontend using agentChatService
     // The frontend will call update_execution_status when complete
@@ -632,7 +742,7 @@ pub async fn get_execution(
 
 /// Update execution status
 pub async fn update_execution_status(
-    State(_state): State<crate::AppState>,
+    State(state): State<crate::AppState>,
     Path(execution_id): Path<String>,
     Json(request): Json<UpdateExecutionStatusRequest>,
 ) -> Result<Json<AgentExecution>, AppError> {
@@ -652,12 +762,17 @@ pub async fn update_execution_status(
     }
     
     // Update messages if provided
+    let messages_changed = request.messages.is_some();
     if let Some(messages) = request.messages {
         execution.messages = messages;
     }
     
     // Save updated execution
     STORAGE.save_execution(&execution).await?;
+    publish_execution(&execution);
+    if messages_changed {
+        index_execution(&state, &execution).await;
+    }
     
     tracing::info!(
         "Updated execution {} status to {:?}", 
@@ -668,6 +783,318 @@ pub async fn update_execution_status(
     Ok(Json(execution))
 }
 
+/// Export an agent's conversation history as a JSON archive or Markdown transcript
+pub async fn export_agent_conversation(
+    State(_state): State<crate::AppState>,
+    Path(id): Path<String>,
+    Query(query): Query<ExportQuery>,
+) -> Result<Response, AppError> {
+    let agent = STORAGE.load(&id).await?;
+    let mut executions = STORAGE.list_agent_executions(&id).await?;
+    executions.sort_by_key(|e| e.started_at);
+
+    let archive = agent_archive(&agent, &executions);
+    let disposition = format!("attachment; filename=\"{}\"", archive.file_name(query.format));
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, query.format.content_type().to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        archive.render(query.format),
+    )
+        .into_response())
+}
+
+/// Download a session's run trace as JSON lines
+pub async fn download_agent_trace(
+    Path(id): Path<String>,
+    Query(query): Query<TraceQuery>,
+) -> Result<Response, AppError> {
+    let entries = TraceRecorder::global()
+        .read(&id, query.redact)?
+        .ok_or_else(|| AppError::NotFound(format!("No trace recorded for session {}", id)))?;
+
+    let mut body = String::new();
+    for entry in &entries {
+        body.push_str(&serde_json::to_string(entry)?);
+        body.push('\n');
+    }
+    let disposition = format!("attachment; filename=\"trace-{}.jsonl\"", id);
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/x-ndjson".to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+/// Record model traffic and other events the chat loop observed
+pub async fn append_agent_trace(
+    Path(id): Path<String>,
+    Json(request): Json<AppendTraceRequest>,
+) -> StatusCode {
+    let recorder = TraceRecorder::global();
+    for event in request.events {
+        recorder.record(&id, event);
+    }
+    StatusCode::NO_CONTENT
+}
+
+/// Delete a session's run trace
+pub async fn delete_agent_trace(Path(id): Path<String>) -> Result<StatusCode, AppError> {
+    if !TraceRecorder::global().delete(&id) {
+        return Err(AppError::NotFound(format!("No trace recorded for session {}", id)));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Messages other agents sent to a session, with their read receipts; reading
+/// them here doesn't mark them as read
+pub async fn get_inbox(Path(id): Path<String>) -> Json<Vec<AgentMail>> {
+    Json(Mailbox::global().inbox(&id))
+}
+
+impl From<TraceError> for AppError {
+    fn from(error: TraceError) -> Self {
+        match error {
+            TraceError::Io(e) => AppError::Io(e),
+            TraceError::Vault(e) => AppError::Vault(e),
+        }
+    }
+}
+
+impl From<VarError> for AppError {
+    fn from(error: VarError) -> Self {
+        match error {
+            VarError::NotFound(_) => AppError::NotFound(error.to_string()),
+            VarError::Invalid(_) => AppError::BadRequest(error.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetVarRequest {
+    pub value: String,
+    #[serde(default)]
+    pub secret: bool,
+}
+
+/// Variables of a session; secret values are left out
+pub async fn list_session_vars(Path(id): Path<String>) -> Json<Vec<SessionVar>> {
+    Json(SessionVars::global().list(&id))
+}
+
+/// Set a variable the session's agent can use as `${name}`
+pub async fn set_session_var(
+    Path((id, name)): Path<(String, String)>,
+    Json(request): Json<SetVarRequest>,
+) -> Result<Json<SessionVar>, AppError> {
+    let var = SessionVars::global().set(&id, &name, &request.value, request.secret)?;
+    Ok(Json(var.masked()))
+}
+
+pub async fn delete_session_var(Path((id, name)): Path<(String, String)>) -> Result<StatusCode, AppError> {
+    SessionVars::global().remove(&id, &name)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Whether secrets are redacted from a session's tool output and trace
+pub async fn get_secret_redaction(Path(id): Path<String>) -> Json<RedactionStatus> {
+    Json(SecretScanner::global().status(Some(&id)))
+}
+
+/// Override secret redaction for a session
+pub async fn set_secret_redaction(
+    Path(id): Path<String>,
+    Json(request): Json<SetRedactionRequest>,
+) -> Json<RedactionStatus> {
+    let scanner = SecretScanner::global();
+    scanner.set_override(&id, request.enabled);
+    if request.enabled == Some(false) {
+        tracing::info!("Secret redaction turned off for session {}", id);
+    }
+    Json(scanner.status(Some(&id)))
+}
+
+impl From<HookError> for AppError {
+    fn from(error: HookError) -> Self {
+        match error {
+            HookError::NotFound(_) => AppError::NotFound(error.to_string()),
+            HookError::InvalidFilter(_) | HookError::InvalidWorkspace(_) => {
+                AppError::BadRequest(error.to_string())
+            }
+            HookError::Io(_) => AppError::Internal(error.to_string()),
+        }
+    }
+}
+
+/// List the event hooks bound to an agent
+pub async fn list_agent_hooks(Path(id): Path<String>) -> Result<Json<Vec<HookBinding>>, AppError> {
+    STORAGE.load(&id).await?;
+    Ok(Json(HookStore::global().list(&id)))
+}
+
+/// Bind an agent to an app event
+pub async fn create_agent_hook(
+    Path(id): Path<String>,
+    Json(request): Json<CreateHookRequest>,
+) -> Result<Json<HookBinding>, AppError> {
+    STORAGE.load(&id).await?;
+    Ok(Json(HookStore::global().create(&id, request)?))
+}
+
+/// Change an agent's hook filter, workspace, cooldown or enabled flag
+pub async fn update_agent_hook(
+    Path((id, hook_id)): Path<(String, String)>,
+    Json(request): Json<UpdateHookRequest>,
+) -> Result<Json<HookBinding>, AppError> {
+    Ok(Json(HookStore::global().update(&id, &hook_id, request)?))
+}
+
+/// Remove an agent's hook
+pub async fn delete_agent_hook(
+    Path((id, hook_id)): Path<(String, String)>,
+) -> Result<Json<bool>, AppError> {
+    Ok(Json(HookStore::global().remove(&id, &hook_id)?))
+}
+
+impl From<ContextSetError> for AppError {
+    fn from(error: ContextSetError) -> Self {
+        match error {
+            ContextSetError::NotFound(_) => AppError::NotFound(error.to_string()),
+            ContextSetError::Invalid(_) => AppError::BadRequest(error.to_string()),
+            ContextSetError::Io(_) => AppError::Internal(error.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ContextBudgetRequest {
+    pub budget_bytes: usize,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ContextPinUpdate {
+    pub mode: PinMode,
+}
+
+/// Files and folders pinned to a conversation
+pub async fn get_context_set(Path(id): Path<String>) -> Json<ContextSet> {
+    Json(ContextSetStore::global().get(&id))
+}
+
+/// Pin a file or folder; pinning it again changes its mode
+pub async fn pin_context(
+    Path(id): Path<String>,
+    Json(request): Json<PinRequest>,
+) -> Result<Json<ContextPin>, AppError> {
+    Ok(Json(ContextSetStore::global().pin(&id, request)?))
+}
+
+/// Change how many bytes of pinned content go into each request
+pub async fn set_context_budget(
+    Path(id): Path<String>,
+    Json(request): Json<ContextBudgetRequest>,
+) -> Result<Json<ContextSet>, AppError> {
+    Ok(Json(ContextSetStore::global().set_budget(&id, request.budget_bytes)?))
+}
+
+/// The system message the pinned files currently render to
+pub async fn preview_context(Path(id): Path<String>) -> Json<Option<RenderedContext>> {
+    Json(ContextSetStore::global().render(&id))
+}
+
+pub async fn update_context_pin(
+    Path((id, pin_id)): Path<(String, String)>,
+    Json(request): Json<ContextPinUpdate>,
+) -> Result<Json<ContextPin>, AppError> {
+    Ok(Json(ContextSetStore::global().set_mode(&id, &pin_id, request.mode)?))
+}
+
+pub async fn unpin_context(Path((id, pin_id)): Path<(String, String)>) -> Result<Json<bool>, AppError> {
+    Ok(Json(ContextSetStore::global().unpin(&id, &pin_id)?))
+}
+
+/// Start an execution of every agent whose hook matches an event on the bus
+pub fn spawn_hook_dispatcher() -> tokio::task::JoinHandle<()> {
+    let mut rx = crate::events::bus().subscribe();
+    tokio::spawn(async move {
+        loop {
+            let envelope = match rx.recv().await {
+                Ok(envelope) => envelope,
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::warn!("Agent hook dispatcher lagged, skipped {} events", skipped);
+                    continue;
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+            };
+            for (binding, fields) in HookStore::global().fire(&envelope.event) {
+                let mut context = HashMap::new();
+                context.insert("hook_id".to_string(), serde_json::json!(binding.id));
+                context.insert("event".to_string(), serde_json::json!(binding.event));
+                context.insert("fields".to_string(), serde_json::json!(fields));
+                match start_execution(&binding.agent_id, context).await {
+                    Ok(execution) => tracing::info!(
+                        "Hook {} started execution {} for agent {}",
+                        binding.id, execution.id, binding.agent_id
+                    ),
+                    Err(e) => tracing::warn!(
+                        "Hook {} could not start agent {}: {:?}",
+                        binding.id, binding.agent_id, e
+                    ),
+                }
+            }
+        }
+    })
+}
+
+/// Build a conversation archive from an agent and its executions, oldest first
+fn agent_archive(agent: &Agent, executions: &[AgentExecution]) -> ConversationArchive {
+    let messages = executions
+        .iter()
+        .flat_map(|e| e.messages.iter())
+        .map(|m| {
+            let role = match m.message_type {
+                MessageType::Input => MessageRole::User,
+                MessageType::Output => MessageRole::Assistant,
+                MessageType::System => MessageRole::System,
+            };
+            ConversationMessage {
+                id: m.id.clone(),
+                role,
+                content: m.content.clone(),
+                tool_calls: None,
+                tool_call_id: None,
+                attachments: Vec::new(),
+                timestamp: m.timestamp.max(0) as u64,
+            }
+        })
+        .collect();
+
+    let mut extra = HashMap::new();
+    extra.insert("agent_id".to_string(), serde_json::json!(agent.id));
+    extra.insert(
+        "execution_ids".to_string(),
+        serde_json::json!(executions.iter().map(|e| &e.id).collect::<Vec<_>>()),
+    );
+
+    ConversationArchive::new(
+        ConversationMetadata {
+            session_id: agent.id.clone(),
+            title: Some(agent.name.clone()),
+            created_at: agent.created_at.max(0) as u64,
+            last_activity: agent.last_used_at.unwrap_or(agent.updated_at).max(0) as u64,
+            extra,
+            ..Default::default()
+        },
+        messages,
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -326,4 +326,50 @@ mod tests {
         assert_eq!(loaded.agent_id, execution.agent_id);
         assert_eq!(loaded.status, ExecutionStatus::Running);
     }
+
+    #[test]
+    fn test_agent_archive_maps_execution_messages() {
+        let agent = Agent {
+            id: "test-agent-1".to_string(),
+            name: "Test Agent".to_string(),
+            description: "A test agent".to_string(),
+            tags: vec![],
+            master_prompt: "You are a test agent".to_string(),
+            workflows: vec![],
+            allowed_tools: vec![],
+            allowed_workflows: vec![],
+            trigger: None,
+            state: AgentState::On,
+            is_default: false,
+            created_at: 1234567890,
+            updated_at: 1234567890,
+            last_used_at: None,
+            usage_count: 1,
+            config: AgentConfig::default(),
+        };
+        let message = |id: &str, message_type| AgentMessage {
+            id: id.to_string(),
+            agent_id: agent.id.clone(),
+            content: format!("content {}", id),
+            timestamp: 1234567900,
+            message_type,
+        };
+        let execution = AgentExecution {
+            id: "exec-1".to_string(),
+            agent_id: agent.id.clone(),
+            status: ExecutionStatus::Completed,
+            started_at: 1234567890,
+            completed_at: Some(1234567990),
+            current_workflow_id: None,
+            context: std::collections::HashMap::new(),
+            messages: vec![message("m1", MessageType::Input), message("m2", MessageType::Output)],
+            error: None,
+        };
+
+        let archive = agent_archive(&agent, &[execution]);
+        assert_eq!(archive.metadata.title.as_deref(), Some("Test Agent"));
+        assert_eq!(archive.messages[0].role, MessageRole::User);
+        assert_eq!(archive.messages[1].role, MessageRole::Assistant);
+        assert!(archive.to_markdown().contains("content m2"));
+    }
 }
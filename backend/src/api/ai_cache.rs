@@ -0,0 +1,77 @@
+//! AI response cache API routes
+//! Replays responses to identical temperature-0 requests made by the chat client
+
+use axum::{
+    extract::State,
+    response::Json,
+    routing::{get, post},
+    Router,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::ai::{CacheableRequest, ResponseCacheStats};
+use crate::AppState;
+
+/// API routes for the AI response cache
+pub fn ai_cache_routes() -> Router<AppState> {
+    Router::new()
+        .route("/ai/cache", get(get_cache_stats).delete(clear_cache))
+        .route("/ai/cache/lookup", post(lookup_response))
+        .route("/ai/cache/store", post(store_response))
+}
+
+#[derive(Debug, Serialize)]
+pub struct LookupResponse {
+    pub hit: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StoreRequest {
+    #[serde(flatten)]
+    pub request: CacheableRequest,
+    pub response: serde_json::Value,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StoreResponse {
+    pub stored: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ClearResponse {
+    pub removed: usize,
+}
+
+/// Cached response for a request, if any
+pub async fn lookup_response(
+    State(state): State<AppState>,
+    Json(request): Json<CacheableRequest>,
+) -> Json<LookupResponse> {
+    let response = state.ai_response_cache.get(&request);
+    Json(LookupResponse {
+        hit: response.is_some(),
+        response,
+    })
+}
+
+/// Store the response to a request; non-deterministic requests are ignored
+pub async fn store_response(
+    State(state): State<AppState>,
+    Json(body): Json<StoreRequest>,
+) -> Json<StoreResponse> {
+    Json(StoreResponse {
+        stored: state.ai_response_cache.put(&body.request, body.response),
+    })
+}
+
+pub async fn get_cache_stats(State(state): State<AppState>) -> Json<ResponseCacheStats> {
+    Json(state.ai_response_cache.stats())
+}
+
+pub async fn clear_cache(State(state): State<AppState>) -> Json<ClearResponse> {
+    Json(ClearResponse {
+        removed: state.ai_response_cache.clear(),
+    })
+}
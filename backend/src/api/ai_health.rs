@@ -0,0 +1,113 @@
+//! AI provider health API routes
+//! Lets the chat client report request outcomes, plan failover and poll
+//! failover events for the UI
+
+use axum::{
+    extract::{Query, State},
+    response::Json,
+    routing::{get, post},
+    Router,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::ai::{FailoverEvent, FailoverPolicy, ProviderHealthReport, RequestOutcome};
+use crate::error::AppError;
+use crate::AppState;
+
+/// API routes for provider health and failover
+pub fn ai_health_routes() -> Router<AppState> {
+    Router::new()
+        .route("/ai/health", get(get_provider_health))
+        .route("/ai/health/report", post(report_outcome))
+        .route("/ai/failover/plan", post(plan_failover))
+        .route("/ai/failover/events", get(get_failover_events).post(record_failover))
+}
+
+/// Outcome of a chat request made by the client
+#[derive(Debug, Deserialize)]
+pub struct ReportOutcomeRequest {
+    pub provider: String,
+    #[serde(flatten)]
+    pub outcome: RequestOutcome,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PlanRequest {
+    pub preferred: String,
+    #[serde(flatten)]
+    pub policy: FailoverPolicy,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PlanResponse {
+    /// Providers to try, in order
+    pub providers: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RecordFailoverRequest {
+    pub from: String,
+    pub to: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EventsQuery {
+    /// Only return events with a larger id
+    pub since: Option<u64>,
+}
+
+/// Health of every provider that has handled a request
+pub async fn get_provider_health(State(state): State<AppState>) -> Json<Vec<ProviderHealthReport>> {
+    Json(state.provider_health.reports())
+}
+
+/// Record the outcome of a chat request
+pub async fn report_outcome(
+    State(state): State<AppState>,
+    Json(request): Json<ReportOutcomeRequest>,
+) -> Result<Json<ProviderHealthReport>, AppError> {
+    if request.provider.trim().is_empty() {
+        return Err(AppError::BadRequest("provider is required".to_string()));
+    }
+    crate::metrics::record_ai_request(
+        &request.provider,
+        "chat",
+        request.outcome.success,
+        std::time::Duration::from_millis(request.outcome.latency_ms),
+    );
+    Ok(Json(state.provider_health.record(&request.provider, request.outcome)))
+}
+
+/// Order in which to try providers for the next request
+pub async fn plan_failover(
+    State(state): State<AppState>,
+    Json(request): Json<PlanRequest>,
+) -> Result<Json<PlanResponse>, AppError> {
+    if request.preferred.trim().is_empty() {
+        return Err(AppError::BadRequest("preferred provider is required".to_string()));
+    }
+    let mut policy = request.policy;
+    if policy.fallbacks.is_empty() {
+        policy.fallbacks = crate::config::SettingsStore::global().get().providers.fallbacks;
+    }
+    Ok(Json(PlanResponse {
+        providers: state.provider_health.plan(&request.preferred, &policy),
+    }))
+}
+
+/// Record a provider switch made by the client
+pub async fn record_failover(
+    State(state): State<AppState>,
+    Json(request): Json<RecordFailoverRequest>,
+) -> Json<FailoverEvent> {
+    Json(state.provider_health.record_failover(&request.from, &request.to, &request.reason))
+}
+
+/// Failover events, optionally only those after `since`
+pub async fn get_failover_events(
+    State(state): State<AppState>,
+    Query(query): Query<EventsQuery>,
+) -> Json<Vec<FailoverEvent>> {
+    Json(state.provider_health.events_since(query.since))
+}
@@ -0,0 +1,101 @@
+//! Archive API routes
+//! Lists, extracts and creates zip and tar archives
+
+use axum::{
+    extract::Query,
+    response::Json,
+    routing::{get, post},
+    Router,
+};
+use serde::Deserialize;
+
+use super::search::resolve_path;
+use crate::archives::{self, ArchiveError, ArchiveLimits, ArchiveListing, CreateOutcome, ExtractOutcome};
+use crate::error::AppError;
+
+/// API routes for archives
+pub fn archive_routes() -> Router<crate::AppState> {
+    Router::new()
+        .route("/archives/list", get(list_archive))
+        .route("/archives/extract", post(extract_archive))
+        .route("/archives/create", post(create_archive))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListArchiveQuery {
+    pub path: String,
+}
+
+/// List the entries of an archive
+pub async fn list_archive(Query(params): Query<ListArchiveQuery>) -> Result<Json<ArchiveListing>, AppError> {
+    let archive = resolve_path(&params.path);
+    let listing = tokio::task::spawn_blocking(move || archives::list(&archive, &ArchiveLimits::default()))
+        .await
+        .map_err(|e| AppError::Internal(format!("Archive task failed: {}", e)))?
+        .map_err(to_app_error)?;
+    Ok(Json(listing))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExtractArchiveRequest {
+    pub path: String,
+    pub destination: String,
+    /// Entries to extract; a folder selects everything under it. Empty
+    /// extracts the whole archive.
+    #[serde(default)]
+    pub entries: Vec<String>,
+    /// Replace existing files instead of skipping them
+    #[serde(default)]
+    pub overwrite: bool,
+}
+
+/// Extract an archive, or selected entries, into a folder
+pub async fn extract_archive(Json(request): Json<ExtractArchiveRequest>) -> Result<Json<ExtractOutcome>, AppError> {
+    let archive = resolve_path(&request.path);
+    let destination = resolve_path(&request.destination);
+    let outcome = tokio::task::spawn_blocking(move || {
+        archives::extract(
+            &archive,
+            &destination,
+            &request.entries,
+            request.overwrite,
+            &ArchiveLimits::default(),
+        )
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Archive task failed: {}", e)))?
+    .map_err(to_app_error)?;
+    Ok(Json(outcome))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateArchiveRequest {
+    /// Archive to write; the extension picks the format
+    pub path: String,
+    /// Files and folders to add
+    pub paths: Vec<String>,
+    #[serde(default)]
+    pub overwrite: bool,
+}
+
+/// Create an archive from a list of files and folders
+pub async fn create_archive(Json(request): Json<CreateArchiveRequest>) -> Result<Json<CreateOutcome>, AppError> {
+    if request.paths.is_empty() {
+        return Err(AppError::BadRequest("No paths to archive".to_string()));
+    }
+    let archive = resolve_path(&request.path);
+    let paths: Vec<_> = request.paths.iter().map(|p| resolve_path(p)).collect();
+    let outcome = tokio::task::spawn_blocking(move || archives::create(&archive, &paths, request.overwrite))
+        .await
+        .map_err(|e| AppError::Internal(format!("Archive task failed: {}", e)))?
+        .map_err(to_app_error)?;
+    Ok(Json(outcome))
+}
+
+fn to_app_error(e: ArchiveError) -> AppError {
+    match e {
+        ArchiveError::NotFound(_) | ArchiveError::EntryNotFound(_) => AppError::NotFound(e.to_string()),
+        ArchiveError::Io { .. } => AppError::Internal(e.to_string()),
+        _ => AppError::BadRequest(e.to_string()),
+    }
+}
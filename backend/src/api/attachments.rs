@@ -0,0 +1,131 @@
+//! Chat attachment API routes
+//! Uploads files dropped into a conversation and serves their extracted text
+
+use axum::{
+    extract::{DefaultBodyLimit, Multipart, Path, Query},
+    http::header,
+    response::{IntoResponse, Json, Response},
+    routing::get,
+    Router,
+};
+use serde::Deserialize;
+
+use crate::attachments::{Attachment, AttachmentChunk, AttachmentError, AttachmentStore, MAX_ATTACHMENT_BYTES};
+use crate::error::AppError;
+
+/// API routes for chat attachments
+pub fn attachment_routes() -> Router<crate::AppState> {
+    Router::new()
+        .route("/attachments", get(list_attachments).post(upload_attachments))
+        .route("/attachments/:id", get(get_attachment).delete(delete_attachment))
+        .route("/attachments/:id/chunks", get(get_chunks))
+        .route("/attachments/:id/chunks/:index", get(get_chunk))
+        .route("/attachments/:id/file", get(download_attachment))
+        // Leave room for multipart framing around the largest accepted file
+        .layer(DefaultBodyLimit::max(MAX_ATTACHMENT_BYTES + 1024 * 1024))
+}
+
+/// Attachment list filter
+#[derive(Debug, Deserialize)]
+pub struct ListAttachmentsQuery {
+    pub conversation_id: Option<String>,
+}
+
+/// Store every `file` field of a multipart upload. An optional
+/// `conversation_id` field (sent before the files) links them to a conversation.
+pub async fn upload_attachments(mut multipart: Multipart) -> Result<Json<Vec<Attachment>>, AppError> {
+    let mut conversation_id = None;
+    let mut uploads = Vec::new();
+
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|e| AppError::BadRequest(format!("Invalid upload: {}", e)))?
+    {
+        match field.name() {
+            Some("conversation_id") => {
+                let value = field
+                    .text()
+                    .await
+                    .map_err(|e| AppError::BadRequest(format!("Invalid conversation_id: {}", e)))?;
+                conversation_id = Some(value).filter(|v| !v.trim().is_empty());
+            }
+            Some("file") => {
+                let file_name = field
+                    .file_name()
+                    .map(str::to_string)
+                    .ok_or_else(|| AppError::BadRequest("File field without a file name".to_string()))?;
+                let bytes = field
+                    .bytes()
+                    .await
+                    .map_err(|e| AppError::BadRequest(format!("Failed to read {}: {}", file_name, e)))?;
+                uploads.push((file_name, bytes));
+            }
+            _ => {}
+        }
+    }
+
+    if uploads.is_empty() {
+        return Err(AppError::BadRequest("No file in upload".to_string()));
+    }
+
+    // Extraction (PDF parsing, OCR) is blocking work
+    let attachments = tokio::task::spawn_blocking(move || {
+        let store = AttachmentStore::global();
+        uploads
+            .iter()
+            .map(|(name, bytes)| store.store(name, bytes, conversation_id.clone()))
+            .collect::<Result<Vec<_>, _>>()
+    })
+    .await
+    .map_err(|e| AppError::Internal(e.to_string()))?
+    .map_err(to_app_error)?;
+
+    Ok(Json(attachments))
+}
+
+/// List attachments, newest first
+pub async fn list_attachments(Query(query): Query<ListAttachmentsQuery>) -> Json<Vec<Attachment>> {
+    Json(AttachmentStore::global().list(query.conversation_id.as_deref()))
+}
+
+pub async fn get_attachment(Path(id): Path<String>) -> Result<Json<Attachment>, AppError> {
+    AttachmentStore::global().get(&id).map(Json).map_err(to_app_error)
+}
+
+pub async fn get_chunks(Path(id): Path<String>) -> Result<Json<Vec<AttachmentChunk>>, AppError> {
+    AttachmentStore::global().chunks(&id).map(Json).map_err(to_app_error)
+}
+
+pub async fn get_chunk(Path((id, index)): Path<(String, usize)>) -> Result<Json<AttachmentChunk>, AppError> {
+    AttachmentStore::global().chunk(&id, index).map(Json).map_err(to_app_error)
+}
+
+/// Original file with its content type
+pub async fn download_attachment(Path(id): Path<String>) -> Result<Response, AppError> {
+    let (attachment, bytes) = AttachmentStore::global().read_original(&id).map_err(to_app_error)?;
+    let disposition = format!("inline; filename=\"{}\"", attachment.file_name.replace('"', ""));
+    Ok((
+        [
+            (header::CONTENT_TYPE, attachment.mime_type),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        bytes,
+    )
+        .into_response())
+}
+
+pub async fn delete_attachment(Path(id): Path<String>) -> Result<Json<bool>, AppError> {
+    match AttachmentStore::global().delete(&id).map_err(to_app_error)? {
+        true => Ok(Json(true)),
+        false => Err(AppError::NotFound(format!("Attachment not found: {}", id))),
+    }
+}
+
+fn to_app_error(e: AttachmentError) -> AppError {
+    match e {
+        AttachmentError::NotFound(_) => AppError::NotFound(e.to_string()),
+        AttachmentError::TooLarge(_) | AttachmentError::Invalid(_) => AppError::BadRequest(e.to_string()),
+        AttachmentError::Io(_) => AppError::Internal(e.to_string()),
+    }
+}
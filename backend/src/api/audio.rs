@@ -0,0 +1,98 @@
+//! Voice transcription API routes
+//! The chat input opens a session, posts recorded audio chunks as they come
+//! and finishes the session for the final transcript. Partial transcripts are
+//! also published on the event stream (`audio` topic).
+
+use axum::{
+    body::Bytes,
+    extract::Path,
+    http::StatusCode,
+    response::Json,
+    routing::{delete, post},
+    Router,
+};
+use serde::Deserialize;
+
+use crate::audio::{
+    AudioError, ChunkFormat, CreateSessionOptions, EngineChoice, PcmSpec, ProviderStt, SessionInfo,
+    TranscriptUpdate, TranscriptionManager, WHISPER_SAMPLE_RATE,
+};
+use crate::error::AppError;
+
+/// API routes for voice transcription
+pub fn audio_routes() -> Router<crate::AppState> {
+    Router::new()
+        .route("/audio/transcriptions", post(create_transcription))
+        .route("/audio/transcriptions/:id/chunks", post(append_chunk))
+        .route("/audio/transcriptions/:id/finish", post(finish_transcription))
+        .route("/audio/transcriptions/:id", delete(cancel_transcription))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateTranscriptionRequest {
+    /// local, provider or auto; defaults to the `transcription.engine` setting
+    pub engine: Option<EngineChoice>,
+    pub language: Option<String>,
+    #[serde(default)]
+    pub format: ChunkFormat,
+    /// Layout of raw PCM chunks
+    pub sample_rate: Option<u32>,
+    pub channels: Option<u16>,
+    /// Whisper-compatible endpoint; defaults to OpenAI
+    pub provider_url: Option<String>,
+    /// Key for the provider; without it only local transcription is possible
+    pub api_key: Option<String>,
+}
+
+/// Open a transcription session
+pub async fn create_transcription(
+    Json(request): Json<CreateTranscriptionRequest>,
+) -> Result<Json<SessionInfo>, AppError> {
+    let options = CreateSessionOptions {
+        engine: request.engine,
+        language: request.language,
+        format: request.format,
+        pcm: PcmSpec {
+            sample_rate: request.sample_rate.unwrap_or(WHISPER_SAMPLE_RATE),
+            channels: request.channels.unwrap_or(1),
+        },
+        provider: request
+            .api_key
+            .filter(|key| !key.trim().is_empty())
+            .map(|key| ProviderStt::new(request.provider_url, key)),
+    };
+    let info = TranscriptionManager::global().create(options).await.map_err(to_app_error)?;
+    Ok(Json(info))
+}
+
+/// Add a chunk of audio (the raw request body) and get the transcript so far
+pub async fn append_chunk(Path(id): Path<String>, body: Bytes) -> Result<Json<TranscriptUpdate>, AppError> {
+    let update = TranscriptionManager::global()
+        .append(&id, &body)
+        .await
+        .map_err(to_app_error)?;
+    Ok(Json(update))
+}
+
+/// Transcribe the rest of the recording and close the session
+pub async fn finish_transcription(Path(id): Path<String>) -> Result<Json<TranscriptUpdate>, AppError> {
+    let update = TranscriptionManager::global().finish(&id).await.map_err(to_app_error)?;
+    Ok(Json(update))
+}
+
+/// Discard a session
+pub async fn cancel_transcription(Path(id): Path<String>) -> Result<StatusCode, AppError> {
+    if TranscriptionManager::global().cancel(&id).await {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(AppError::NotFound(format!("Transcription session not found: {}", id)))
+    }
+}
+
+fn to_app_error(e: AudioError) -> AppError {
+    match e {
+        AudioError::SessionNotFound(_) => AppError::NotFound(e.to_string()),
+        AudioError::Provider(_) | AudioError::Engine(_) => AppError::Internal(e.to_string()),
+        _ => AppError::BadRequest(e.to_string()),
+    }
+}
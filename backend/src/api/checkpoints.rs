@@ -0,0 +1,86 @@
+//! Agent checkpoint API routes
+//! Lists and reverts the file snapshots taken before agent file modifications
+
+use axum::{
+    extract::Path,
+    response::Json,
+    routing::{get, post},
+    Router,
+};
+use serde::Serialize;
+use std::path::PathBuf;
+
+use crate::cli_agent::executor::ExecutorError;
+use crate::cli_agent::{Checkpoint, CheckpointManager};
+use crate::error::AppError;
+
+/// API routes for agent checkpoints
+pub fn checkpoint_routes() -> Router<crate::AppState> {
+    Router::new()
+        .route("/checkpoints/:session_id", get(list_checkpoints))
+        .route("/checkpoints/:session_id/revert", post(revert_session))
+        .route("/checkpoints/:session_id/:checkpoint_id/revert", post(revert_checkpoint))
+}
+
+/// Checkpoints of a session
+#[derive(Debug, Serialize)]
+pub struct CheckpointListResponse {
+    pub session_id: String,
+    pub checkpoints: Vec<Checkpoint>,
+}
+
+/// Files restored by a revert
+#[derive(Debug, Serialize)]
+pub struct RevertResponse {
+    pub session_id: String,
+    pub restored_files: Vec<PathBuf>,
+}
+
+/// List checkpoints for an agent session, oldest first
+pub async fn list_checkpoints(
+    Path(session_id): Path<String>,
+) -> Result<Json<CheckpointListResponse>, AppError> {
+    let checkpoints = CheckpointManager::global()
+        .list(&session_id)
+        .map_err(to_app_error)?;
+
+    Ok(Json(CheckpointListResponse {
+        session_id,
+        checkpoints,
+    }))
+}
+
+/// Revert a checkpoint and every checkpoint after it
+pub async fn revert_checkpoint(
+    Path((session_id, checkpoint_id)): Path<(String, String)>,
+) -> Result<Json<RevertResponse>, AppError> {
+    let restored_files = CheckpointManager::global()
+        .revert_checkpoint(&session_id, &checkpoint_id)
+        .map_err(to_app_error)?;
+
+    Ok(Json(RevertResponse {
+        session_id,
+        restored_files,
+    }))
+}
+
+/// Revert every file modification made in an agent session
+pub async fn revert_session(
+    Path(session_id): Path<String>,
+) -> Result<Json<RevertResponse>, AppError> {
+    let restored_files = CheckpointManager::global()
+        .revert_session(&session_id)
+        .map_err(to_app_error)?;
+
+    Ok(Json(RevertResponse {
+        session_id,
+        restored_files,
+    }))
+}
+
+fn to_app_error(e: ExecutorError) -> AppError {
+    match e {
+        ExecutorError::InvalidArgument(msg) => AppError::NotFound(msg),
+        other => AppError::Internal(other.to_string()),
+    }
+}
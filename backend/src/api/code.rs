@@ -0,0 +1,145 @@
+//! Code intelligence API routes
+//! Go to definition, find references and document symbols answered by the
+//! language server for the file's language (see `crate::lsp`), and symbol
+//! lookups in the tree-sitter index (see `crate::code_index`).
+
+use axum::{
+    extract::{Query, State},
+    response::Json,
+    routing::{get, post},
+    Router,
+};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::code_index::{CodeIndex, CodeIndexStats, CodeIndexTotals, SymbolQuery};
+use crate::db::CodeSymbolRecord;
+use crate::error::AppError;
+use crate::lsp::{CodeLocation, CodePosition, CodeSymbol, LspError, LspManager, LspServerStatus};
+
+/// API routes for code intelligence
+pub fn code_routes() -> Router<crate::AppState> {
+    Router::new()
+        .route("/code/definition", get(definition))
+        .route("/code/references", get(references))
+        .route("/code/symbols", get(symbols))
+        .route("/code/servers", get(list_servers))
+        .route("/code/servers/shutdown", post(shutdown_servers))
+        .route("/code/index", get(index_totals).post(index_directory))
+        .route("/code/index/symbols", get(search_symbols))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PositionQuery {
+    pub path: String,
+    /// 1-based
+    pub line: u32,
+    /// 1-based; looked up from `symbol` when missing
+    pub column: Option<u32>,
+    pub symbol: Option<String>,
+    #[serde(default)]
+    pub include_declaration: bool,
+}
+
+fn source_file(path: &str) -> Result<PathBuf, AppError> {
+    std::fs::canonicalize(path).map_err(|e| AppError::NotFound(format!("{}: {}", path, e)))
+}
+
+impl PositionQuery {
+    fn position(&self) -> CodePosition {
+        CodePosition {
+            line: self.line,
+            column: self.column,
+            symbol: self.symbol.clone(),
+        }
+    }
+}
+
+/// Where the symbol at a position is defined
+pub async fn definition(Query(query): Query<PositionQuery>) -> Result<Json<Vec<CodeLocation>>, AppError> {
+    let locations = LspManager::global()
+        .definition(&source_file(&query.path)?, &query.position())
+        .await
+        .map_err(to_app_error)?;
+    Ok(Json(locations))
+}
+
+/// Uses of the symbol at a position
+pub async fn references(Query(query): Query<PositionQuery>) -> Result<Json<Vec<CodeLocation>>, AppError> {
+    let locations = LspManager::global()
+        .references(&source_file(&query.path)?, &query.position(), query.include_declaration)
+        .await
+        .map_err(to_app_error)?;
+    Ok(Json(locations))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SymbolsQuery {
+    pub path: String,
+}
+
+/// Symbols declared in a file
+pub async fn symbols(Query(query): Query<SymbolsQuery>) -> Result<Json<Vec<CodeSymbol>>, AppError> {
+    let symbols = LspManager::global()
+        .document_symbols(&source_file(&query.path)?)
+        .await
+        .map_err(to_app_error)?;
+    Ok(Json(symbols))
+}
+
+/// Language servers that have been started
+pub async fn list_servers() -> Json<Vec<LspServerStatus>> {
+    Json(LspManager::global().statuses().await)
+}
+
+#[derive(Debug, Serialize)]
+pub struct ShutdownResponse {
+    pub stopped: usize,
+}
+
+/// Stop every language server
+pub async fn shutdown_servers() -> Json<ShutdownResponse> {
+    Json(ShutdownResponse {
+        stopped: LspManager::global().shutdown_all().await,
+    })
+}
+
+/// Indexed symbols matching a name, kind and path prefix
+pub async fn search_symbols(
+    State(state): State<crate::AppState>,
+    Query(query): Query<SymbolQuery>,
+) -> Result<Json<Vec<CodeSymbolRecord>>, AppError> {
+    Ok(Json(CodeIndex::new(state.db.clone()).search(&query).await?))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct IndexRequest {
+    pub path: String,
+}
+
+/// Index every code file under a directory
+pub async fn index_directory(
+    State(state): State<crate::AppState>,
+    Json(request): Json<IndexRequest>,
+) -> Result<Json<CodeIndexStats>, AppError> {
+    let root = source_file(&request.path)?;
+    if !root.is_dir() {
+        return Err(AppError::BadRequest(format!("{} is not a directory", request.path)));
+    }
+    Ok(Json(CodeIndex::new(state.db.clone()).index_directory(&root).await?))
+}
+
+/// Files and symbols in the index
+pub async fn index_totals(State(state): State<crate::AppState>) -> Result<Json<CodeIndexTotals>, AppError> {
+    Ok(Json(CodeIndex::new(state.db.clone()).totals().await?))
+}
+
+fn to_app_error(e: LspError) -> AppError {
+    match e {
+        LspError::Unsupported(_) | LspError::InvalidPosition(_) | LspError::Disabled => {
+            AppError::BadRequest(e.to_string())
+        }
+        LspError::Io(ref io) if io.kind() == std::io::ErrorKind::NotFound => AppError::NotFound(e.to_string()),
+        _ => AppError::Internal(e.to_string()),
+    }
+}
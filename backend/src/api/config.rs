@@ -0,0 +1,117 @@
+//! Backend settings API routes
+//! Read and update sections of `skhoot.toml`; changes are applied to the
+//! running services without a restart where possible
+
+use axum::{
+    extract::{Path, State},
+    response::Json,
+    routing::get,
+    Router,
+};
+use serde::Serialize;
+
+use crate::config::settings::{RESTART_SECTIONS, SECTIONS};
+use crate::config::{Settings, SettingsStore};
+use crate::error::AppError;
+use crate::AppState;
+
+/// API routes for backend settings
+pub fn config_routes() -> Router<AppState> {
+    Router::new()
+        .route("/config", get(get_config))
+        .route("/config/:section", get(get_section).put(update_section))
+}
+
+#[derive(Debug, Serialize)]
+pub struct ConfigResponse {
+    /// Path of the settings file
+    pub path: String,
+    pub settings: Settings,
+    /// Why the file on disk was rejected, if it was; the last valid
+    /// settings stay in effect
+    pub error: Option<String>,
+    /// Server settings differ from the ones the backend started with
+    pub restart_required: bool,
+}
+
+/// All settings
+pub async fn get_config(State(state): State<AppState>) -> Json<ConfigResponse> {
+    let store = SettingsStore::global();
+    let settings = store.get();
+    Json(ConfigResponse {
+        path: store.path().display().to_string(),
+        restart_required: needs_restart(&state, &settings),
+        error: store.last_error(),
+        settings,
+    })
+}
+
+/// One settings section
+pub async fn get_section(Path(section): Path<String>) -> Result<Json<serde_json::Value>, AppError> {
+    SettingsStore::global()
+        .get()
+        .section(&section)
+        .map(Json)
+        .ok_or_else(|| AppError::NotFound(format!("Unknown config section '{}'", section)))
+}
+
+/// Update keys of one section; omitted keys keep their values
+pub async fn update_section(
+    State(state): State<AppState>,
+    Path(section): Path<String>,
+    Json(patch): Json<serde_json::Value>,
+) -> Result<Json<ConfigResponse>, AppError> {
+    if !SECTIONS.contains(&section.as_str()) {
+        return Err(AppError::NotFound(format!("Unknown config section '{}'", section)));
+    }
+
+    let store = SettingsStore::global();
+    let before = store.get();
+    let settings = store.update_section(&section, patch).map_err(AppError::BadRequest)?;
+    settings_changed(&state, &settings, settings.changed_sections(&before)).await;
+
+    Ok(Json(ConfigResponse {
+        path: store.path().display().to_string(),
+        restart_required: needs_restart(&state, &settings),
+        error: None,
+        settings,
+    }))
+}
+
+/// Apply changed settings to the running services and notify the UI
+pub async fn settings_changed(state: &AppState, settings: &Settings, sections: Vec<&'static str>) {
+    if sections.is_empty() {
+        return;
+    }
+
+    if sections.contains(&"cache") {
+        state.ai_response_cache.set_config(settings.cache.ai_response_config());
+        state.content_extraction_system.lock().await.set_cache_limits(
+            settings.cache.web_max_mb * 1024 * 1024,
+            std::time::Duration::from_secs(settings.cache.web_ttl_secs),
+        );
+    }
+    if sections.contains(&"providers") {
+        state.provider_health.set_config(settings.providers.health_config());
+    }
+    if sections.contains(&"mcp") {
+        let servers = settings.mcp.servers.clone();
+        tokio::spawn(async move { crate::mcp::McpManager::global().connect_all(servers).await });
+    }
+    if sections.contains(&"tool_limits") {
+        crate::cli_agent::ToolThrottle::global().set_limits(settings.tool_limits.clone());
+    }
+    if sections.contains(&"plugins") {
+        crate::plugins::PluginRegistry::global().configure(&settings.plugins);
+    }
+
+    tracing::info!("Applied settings changes: {}", sections.join(", "));
+    crate::events::publish(crate::events::Event::ConfigChanged {
+        restart_required: sections.iter().any(|s| RESTART_SECTIONS.contains(s)),
+        sections: sections.into_iter().map(str::to_string).collect(),
+    });
+}
+
+fn needs_restart(state: &AppState, settings: &Settings) -> bool {
+    settings.server.port != state.config.port || settings.server.host != state.config.host
+}
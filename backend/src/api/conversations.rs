@@ -0,0 +1,95 @@
+//! Conversation history search routes
+//! Chats are indexed by the UI as they are saved; agent executions are
+//! indexed when their messages are updated. Sessions can be listed while
+//! the conversation vault is locked, but their text stays hidden.
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+    routing::{delete, get, put},
+    Router,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::conversation_search::{ConversationIndex, ConversationMessage, ConversationQuery, ConversationTranscript};
+use crate::db::{ConversationSearchHit, ConversationSessionRecord};
+use crate::error::AppError;
+
+/// API routes for conversation search
+pub fn conversation_routes() -> Router<crate::AppState> {
+    Router::new()
+        .route("/conversations", get(list_conversations))
+        .route("/conversations/search", get(search_conversations))
+        .route("/conversations/:session_id", get(get_conversation))
+        .route("/conversations/:session_id", put(index_conversation))
+        .route("/conversations/:session_id", delete(delete_conversation))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct IndexConversationRequest {
+    #[serde(default)]
+    pub agent_id: Option<String>,
+    pub messages: Vec<ConversationMessage>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct IndexConversationResponse {
+    pub indexed: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ConversationSearchResponse {
+    pub results: Vec<ConversationSearchHit>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ConversationListResponse {
+    pub sessions: Vec<ConversationSessionRecord>,
+}
+
+/// Indexed conversations, most recently active first
+pub async fn list_conversations(
+    State(state): State<crate::AppState>,
+) -> Result<Json<ConversationListResponse>, AppError> {
+    let sessions = ConversationIndex::new(state.db.clone()).sessions().await?;
+    Ok(Json(ConversationListResponse { sessions }))
+}
+
+/// Indexed messages of a conversation
+pub async fn get_conversation(
+    State(state): State<crate::AppState>,
+    Path(session_id): Path<String>,
+) -> Result<Json<ConversationTranscript>, AppError> {
+    Ok(Json(ConversationIndex::new(state.db.clone()).transcript(&session_id).await?))
+}
+
+/// Messages matching a query, best matches first
+pub async fn search_conversations(
+    State(state): State<crate::AppState>,
+    Query(query): Query<ConversationQuery>,
+) -> Result<Json<ConversationSearchResponse>, AppError> {
+    let results = ConversationIndex::new(state.db.clone()).search(&query).await?;
+    Ok(Json(ConversationSearchResponse { results }))
+}
+
+/// Index a conversation, replacing its previously indexed messages
+pub async fn index_conversation(
+    State(state): State<crate::AppState>,
+    Path(session_id): Path<String>,
+    Json(request): Json<IndexConversationRequest>,
+) -> Result<Json<IndexConversationResponse>, AppError> {
+    let indexed = ConversationIndex::new(state.db.clone())
+        .index_session(&session_id, request.agent_id.as_deref(), &request.messages)
+        .await?;
+    Ok(Json(IndexConversationResponse { indexed }))
+}
+
+/// Remove a deleted conversation from the index
+pub async fn delete_conversation(
+    State(state): State<crate::AppState>,
+    Path(session_id): Path<String>,
+) -> Result<StatusCode, AppError> {
+    ConversationIndex::new(state.db.clone()).remove_session(&session_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
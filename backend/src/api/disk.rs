@@ -2,24 +2,41 @@
 //! Provides endpoints for disk usage analysis, cleanup suggestions, and storage management
 
 use axum::{
-    extract::{Query, State},
+    extract::{Path, Query, State},
+    http::StatusCode,
     response::Json,
-    routing::get,
+    routing::{get, post},
     Router,
 };
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use std::collections::HashMap;
 
+use crate::disk_analyzer::{
+    compute_trends, file_category, list_volumes, CleanupCandidate, CleanupCategory, DiskScanScheduler,
+    DiskSnapshot, FileCategorizer, FileQuery, FileQueryPage, SafetyLevel, ScheduledScanConfig, TreemapConfig,
+    TreemapProgress, TreemapScan, TreemapScanSummary, TreemapScans, TrendPoint, VolumeInfo,
+};
 use crate::error::AppError;
+use crate::ignore_rules::IgnoreRules;
+use crate::mounts::{MountGuard, MountPolicy, SkippedMount};
 
 /// API routes for disk management
 pub fn disk_routes() -> Router<crate::AppState> {
     Router::new()
         .route("/disk/info", get(get_disk_info))
+        .route("/disk/volumes", get(get_disk_volumes))
         .route("/disk/analyze", get(analyze_disk))
         .route("/disk/cleanup-suggestions", get(get_cleanup_suggestions))
         .route("/disk/categories", get(get_storage_categories))
+        .route("/disk/trends", get(get_disk_trends))
+        .route("/disk/snapshots", post(run_disk_snapshot))
+        .route("/disk/treemap", post(start_treemap_scan))
+        .route("/disk/treemap/:scan_id", get(get_treemap_scan).delete(cancel_treemap_scan))
+        .route("/disk/treemap/:scan_id/files", get(query_scan_files))
+        .route("/disk/files", get(query_latest_files))
+        .route("/disk/scans", get(list_disk_scans))
+        .route("/disk/scans/:scan_id", get(get_disk_scan).delete(cancel_treemap_scan))
 }
 
 // ============================================================================
@@ -50,6 +67,10 @@ pub struct AnalyzeQuery {
     pub path: Option<String>,      // Path to analyze (defaults to home)
     pub max_depth: Option<usize>,  // Maximum directory depth
     pub top_n: Option<usize>,      // Number of top consumers to return
+    pub respect_gitignore: Option<bool>,    // Skip what git ignores (off by default)
+    pub respect_skhootignore: Option<bool>, // Skip what .skhootignore lists (on by default)
+    pub include_network_mounts: Option<bool>,   // Scan network shares below the path
+    pub include_removable_mounts: Option<bool>, // Scan removable drives below the path
 }
 
 /// Disk analysis response
@@ -61,6 +82,8 @@ pub struct DiskAnalysisResponse {
     pub dir_count: usize,
     pub top_consumers: Vec<SpaceConsumer>,
     pub analysis_time_ms: u64,
+    /// Mounts below the path that were not scanned
+    pub skipped_mounts: Vec<SkippedMount>,
 }
 
 #[derive(Debug, Serialize)]
@@ -93,6 +116,18 @@ pub struct CleanupSuggestion {
     pub description: String,
     pub consequence: String,
     pub last_accessed: Option<String>,
+    /// Shell command that reclaims the space safely
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cleanup_command: Option<String>,
+}
+
+/// Query parameters for cleanup suggestions
+#[derive(Debug, Deserialize)]
+pub struct CleanupQuery {
+    /// Also look below this directory for build output such as Cargo
+    /// `target` directories
+    pub path: Option<String>,
+    pub max_depth: Option<usize>,
 }
 
 /// Storage categories response
@@ -113,6 +148,71 @@ pub struct StorageCategory {
     pub color: String,
 }
 
+/// Query parameters for disk usage trends
+#[derive(Debug, Deserialize)]
+pub struct TrendsQuery {
+    pub days: Option<i64>,         // Period to compare (defaults to 7)
+    pub path: Option<String>,      // Only include paths under this prefix
+    pub limit: Option<usize>,      // Number of trends to return
+}
+
+/// Disk usage trends response
+#[derive(Debug, Serialize)]
+pub struct DiskTrendsResponse {
+    pub period_days: i64,
+    pub snapshot_count: usize,
+    pub trends: Vec<DiskTrendEntry>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DiskTrendEntry {
+    pub path: String,
+    pub name: String,
+    pub first_size: u64,
+    pub last_size: u64,
+    pub growth_bytes: i64,
+    pub growth_formatted: String,
+    pub growth_percentage: f64,
+    pub summary: String,           // e.g. "node_modules grew 4.0 GB"
+    pub points: Vec<TrendPoint>,
+}
+
+/// Request body for an on-demand snapshot scan
+#[derive(Debug, Deserialize)]
+pub struct SnapshotRequest {
+    pub path: Option<String>,
+    pub respect_gitignore: Option<bool>,
+    pub respect_skhootignore: Option<bool>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SnapshotResponse {
+    pub snapshots_stored: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DiskVolumesResponse {
+    pub volumes: Vec<VolumeInfo>,
+    pub total_bytes: u64,
+    pub free_bytes: u64,
+}
+
+/// Request body for a treemap scan
+#[derive(Debug, Deserialize)]
+pub struct TreemapRequest {
+    pub path: Option<String>,
+    pub depth: Option<usize>,
+    pub top_n: Option<usize>,
+    pub respect_gitignore: Option<bool>,
+    pub respect_skhootignore: Option<bool>,
+    /// Walker threads (default: one per CPU)
+    pub threads: Option<usize>,
+    /// Scan network shares below the path
+    pub include_network_mounts: Option<bool>,
+    /// Scan removable drives below the path
+    pub include_removable_mounts: Option<bool>,
+}
+
 // ============================================================================
 // Helper Functions
 // ============================================================================
@@ -136,30 +236,6 @@ fn format_size(bytes: u64) -> String {
     }
 }
 
-fn get_file_category(path: &std::path::Path) -> &'static str {
-    let extension = path.extension()
-        .and_then(|e| e.to_str())
-        .unwrap_or("")
-        .to_lowercase();
-    
-    match extension.as_str() {
-        // Documents
-        "pdf" | "doc" | "docx" | "txt" | "rtf" | "odt" | "xls" | "xlsx" | "ppt" | "pptx" => "Documents",
-        // Images
-        "jpg" | "jpeg" | "png" | "gif" | "bmp" | "svg" | "webp" | "ico" | "tiff" | "raw" => "Images",
-        // Videos
-        "mp4" | "avi" | "mkv" | "mov" | "wmv" | "flv" | "webm" | "m4v" => "Videos",
-        // Audio
-        "mp3" | "wav" | "flac" | "aac" | "ogg" | "wma" | "m4a" => "Audio",
-        // Code
-        "rs" | "js" | "ts" | "py" | "java" | "cpp" | "c" | "h" | "go" | "rb" | "php" | "swift" | "kt" => "Code",
-        // Archives
-        "zip" | "tar" | "gz" | "rar" | "7z" | "bz2" | "xz" => "Archives",
-        // Other
-        _ => "Other",
-    }
-}
-
 // ============================================================================
 // Endpoints
 // ============================================================================
@@ -321,22 +397,31 @@ pub async fn analyze_disk(
     
     let max_depth = params.max_depth.unwrap_or(3);
     let top_n = params.top_n.unwrap_or(20);
+    let rules = IgnoreRules::DISK_USAGE.with_overrides(params.respect_gitignore, params.respect_skhootignore);
+    let defaults = MountPolicy::default();
+    let mounts = MountPolicy {
+        include_network: params.include_network_mounts.unwrap_or(defaults.include_network),
+        include_removable: params.include_removable_mounts.unwrap_or(defaults.include_removable),
+        ..defaults
+    };
     
     // Use spawn_blocking for filesystem operations
     let analysis = tokio::task::spawn_blocking(move || {
-        use walkdir::WalkDir;
-        
         let mut total_size: u64 = 0;
         let mut file_count: usize = 0;
         let mut dir_count: usize = 0;
         let mut entries: Vec<(PathBuf, u64, bool)> = Vec::new();
+        let guard = MountGuard::new(&search_path, &mounts);
+        let entry_guard = guard.clone();
+        // Nothing is walked when the path itself is on an unresponsive mount
+        let walker = guard.allows(&search_path).then(|| {
+            rules.walker(&search_path)
+                .max_depth(Some(max_depth))
+                .filter_entry(move |entry| entry_guard.allows(entry.path()))
+                .build()
+        });
         
-        for entry in WalkDir::new(&search_path)
-            .max_depth(max_depth)
-            .follow_links(false)
-            .into_iter()
-            .filter_map(|e| e.ok())
-        {
+        for entry in walker.into_iter().flatten().filter_map(|e| e.ok()) {
             if let Ok(metadata) = entry.metadata() {
                 if metadata.is_file() {
                     let size = metadata.len();
@@ -350,7 +435,7 @@ pub async fn analyze_disk(
                 } else if metadata.is_dir() && entry.depth() == 1 {
                     // Calculate directory sizes for top-level directories
                     dir_count += 1;
-                    let dir_size = calculate_dir_size(entry.path());
+                    let dir_size = calculate_dir_size(entry.path(), rules, &guard);
                     if dir_size > 1024 * 1024 {
                         entries.push((entry.path().to_path_buf(), dir_size, true));
                     }
@@ -381,12 +466,12 @@ pub async fn analyze_disk(
             })
             .collect();
         
-        (total_size, file_count, dir_count, top_consumers)
+        (total_size, file_count, dir_count, top_consumers, guard.skipped().to_vec())
     })
     .await
     .map_err(|e| AppError::Internal(format!("Analysis task failed: {}", e)))?;
     
-    let (total_size, file_count, dir_count, top_consumers) = analysis;
+    let (total_size, file_count, dir_count, top_consumers, skipped_mounts) = analysis;
     
     Ok(Json(DiskAnalysisResponse {
         total_size,
@@ -395,15 +480,25 @@ pub async fn analyze_disk(
         dir_count,
         top_consumers,
         analysis_time_ms: start_time.elapsed().as_millis() as u64,
+        skipped_mounts,
     }))
 }
 
-fn calculate_dir_size(path: &std::path::Path) -> u64 {
-    use walkdir::WalkDir;
-    
-    WalkDir::new(path)
-        .follow_links(false)
-        .into_iter()
+fn calculate_dir_size(path: &std::path::Path, rules: IgnoreRules, guard: &MountGuard) -> u64 {
+    calculate_dir_size_excluding(path, rules, guard, Vec::new())
+}
+
+/// Size of a directory, leaving out the directories in `exclude`
+fn calculate_dir_size_excluding(
+    path: &std::path::Path,
+    rules: IgnoreRules,
+    guard: &MountGuard,
+    exclude: Vec<PathBuf>,
+) -> u64 {
+    let guard = guard.clone();
+    rules.walker(path)
+        .filter_entry(move |entry| guard.allows(entry.path()) && !exclude.iter().any(|dir| dir == entry.path()))
+        .build()
         .filter_map(|e| e.ok())
         .filter_map(|e| e.metadata().ok())
         .filter(|m| m.is_file())
@@ -413,6 +508,7 @@ fn calculate_dir_size(path: &std::path::Path) -> u64 {
 
 /// Get cleanup suggestions
 pub async fn get_cleanup_suggestions(
+    Query(params): Query<CleanupQuery>,
     State(_state): State<crate::AppState>,
 ) -> Result<Json<CleanupSuggestionsResponse>, AppError> {
     let home_dir = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
@@ -420,25 +516,43 @@ pub async fn get_cleanup_suggestions(
     let suggestions = tokio::task::spawn_blocking(move || {
         let mut suggestions = Vec::new();
         let mut total_reclaimable: u64 = 0;
+        let guard = MountGuard::new(&home_dir, &MountPolicy::default());
         
-        // Check common cleanup locations
+        // Caches, stores and temporary directories the categorizer knows
+        let categorizer = FileCategorizer::new(&home_dir);
+        let mut candidates = categorizer.known_locations(&guard);
+        if let Some(root) = params.path.map(PathBuf::from).filter(|p| p.is_dir()) {
+            let project_guard = MountGuard::new(&root, &MountPolicy::default());
+            for candidate in categorizer.find_candidates(
+                &root,
+                IgnoreRules::DISK_USAGE,
+                Some(params.max_depth.unwrap_or(6)),
+                &project_guard,
+            ) {
+                if !candidates.iter().any(|c| c.path == candidate.path) {
+                    candidates.push(candidate);
+                }
+            }
+        }
+        let recognized: Vec<PathBuf> = candidates.iter().map(|c| c.path.clone()).collect();
+        for candidate in candidates {
+            if candidate.size > 1024 * 1024 {
+                total_reclaimable += candidate.size;
+                let id = format!("cleanup-{}", suggestions.len());
+                suggestions.push(suggestion_from_candidate(id, candidate));
+            }
+        }
+        
+        // Check common cleanup locations, without what was suggested above
         let cleanup_paths = get_cleanup_paths(&home_dir);
         
         for (path, name, category, safety, description, consequence) in cleanup_paths {
-            if path.exists() {
-                let size = calculate_dir_size(&path);
+            if path.exists() && !recognized.iter().any(|dir| path.starts_with(dir)) {
+                let size = calculate_dir_size_excluding(&path, IgnoreRules::DISK_USAGE, &guard, recognized.clone());
                 if size > 1024 * 1024 { // Only suggest if > 1MB
                     total_reclaimable += size;
                     
-                    let last_accessed = std::fs::metadata(&path)
-                        .ok()
-                        .and_then(|m| m.accessed().ok())
-                        .map(|t| {
-                            let duration = std::time::SystemTime::now()
-                                .duration_since(t)
-                                .unwrap_or_default();
-                            format_duration(duration)
-                        });
+                    let last_accessed = last_accessed(&path);
                     
                     suggestions.push(CleanupSuggestion {
                         id: format!("cleanup-{}", suggestions.len()),
@@ -451,6 +565,7 @@ pub async fn get_cleanup_suggestions(
                         description: description.to_string(),
                         consequence: consequence.to_string(),
                         last_accessed,
+                        cleanup_command: None,
                     });
                 }
             }
@@ -473,6 +588,35 @@ pub async fn get_cleanup_suggestions(
     }))
 }
 
+fn suggestion_from_candidate(id: String, candidate: CleanupCandidate) -> CleanupSuggestion {
+    let category = match candidate.category {
+        CleanupCategory::Cache => "cache",
+        CleanupCategory::Temporary => "temp",
+        CleanupCategory::Downloads => "downloads",
+        CleanupCategory::Projects => "old_projects",
+        CleanupCategory::AppData => "app_data",
+        CleanupCategory::Other => "other",
+    };
+    let safety_level = match candidate.safety_level {
+        SafetyLevel::Safe => "safe",
+        SafetyLevel::Maybe => "review",
+        SafetyLevel::Risky => "risky",
+    };
+    CleanupSuggestion {
+        id,
+        name: candidate.name,
+        path: candidate.path.display().to_string(),
+        size: candidate.size,
+        size_formatted: format_size(candidate.size),
+        category: category.to_string(),
+        safety_level: safety_level.to_string(),
+        description: candidate.description,
+        consequence: candidate.consequence,
+        last_accessed: last_accessed(&candidate.path),
+        cleanup_command: candidate.cleanup_command,
+    }
+}
+
 fn get_cleanup_paths(home: &PathBuf) -> Vec<(PathBuf, &'static str, &'static str, &'static str, &'static str, &'static str)> {
     let mut paths = Vec::new();
     
@@ -515,6 +659,19 @@ fn get_cleanup_paths(home: &PathBuf) -> Vec<(PathBuf, &'static str, &'static str
     paths
 }
 
+/// How long ago a path was last accessed, for display
+fn last_accessed(path: &std::path::Path) -> Option<String> {
+    std::fs::metadata(path)
+        .ok()
+        .and_then(|m| m.accessed().ok())
+        .map(|t| {
+            let duration = std::time::SystemTime::now()
+                .duration_since(t)
+                .unwrap_or_default();
+            format_duration(duration)
+        })
+}
+
 fn format_duration(duration: std::time::Duration) -> String {
     let secs = duration.as_secs();
     if secs < 60 {
@@ -542,17 +699,15 @@ pub async fn get_storage_categories(
         .unwrap_or_else(|| dirs::home_dir().unwrap_or_else(|| PathBuf::from(".")));
     
     let max_depth = params.max_depth.unwrap_or(4);
+    let rules = IgnoreRules::DISK_USAGE.with_overrides(params.respect_gitignore, params.respect_skhootignore);
     
     let categories = tokio::task::spawn_blocking(move || {
-        use walkdir::WalkDir;
-        
         let mut category_sizes: HashMap<&str, (u64, usize)> = HashMap::new();
         let mut total_size: u64 = 0;
         
-        for entry in WalkDir::new(&search_path)
-            .max_depth(max_depth)
-            .follow_links(false)
-            .into_iter()
+        for entry in rules.walker(&search_path)
+            .max_depth(Some(max_depth))
+            .build()
             .filter_map(|e| e.ok())
         {
             if let Ok(metadata) = entry.metadata() {
@@ -560,7 +715,7 @@ pub async fn get_storage_categories(
                     let size = metadata.len();
                     total_size += size;
                     
-                    let category = get_file_category(entry.path());
+                    let category = file_category(entry.path());
                     let entry = category_sizes.entry(category).or_insert((0, 0));
                     entry.0 += size;
                     entry.1 += 1;
@@ -607,3 +762,199 @@ pub async fn get_storage_categories(
         total_size_formatted: format_size(total_size),
     }))
 }
+
+/// Get disk usage growth trends from stored scan snapshots
+pub async fn get_disk_trends(
+    Query(params): Query<TrendsQuery>,
+    State(state): State<crate::AppState>,
+) -> Result<Json<DiskTrendsResponse>, AppError> {
+    let period_days = params.days.unwrap_or(7).max(1);
+    let limit = params.limit.unwrap_or(20);
+    let since = chrono::Utc::now() - chrono::Duration::days(period_days);
+
+    let snapshots: Vec<DiskSnapshot> = state.db
+        .get_disk_snapshots_since(since, params.path.as_deref())
+        .await?
+        .into_iter()
+        .map(DiskSnapshot::from)
+        .collect();
+    let snapshot_count = snapshots.len();
+
+    let trends = compute_trends(&snapshots)
+        .into_iter()
+        .take(limit)
+        .map(|trend| {
+            let name = trend.path.file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or("Unknown")
+                .to_string();
+            let growth_formatted = format_size(trend.growth_bytes.unsigned_abs());
+            let summary = match trend.growth_bytes {
+                g if g > 0 => format!("{} grew {}", name, growth_formatted),
+                g if g < 0 => format!("{} shrank {}", name, growth_formatted),
+                _ => format!("{} unchanged", name),
+            };
+
+            DiskTrendEntry {
+                path: trend.path.display().to_string(),
+                name,
+                first_size: trend.first_size,
+                last_size: trend.last_size,
+                growth_bytes: trend.growth_bytes,
+                growth_formatted,
+                growth_percentage: trend.growth_percentage,
+                summary,
+                points: trend.points,
+            }
+        })
+        .collect();
+
+    Ok(Json(DiskTrendsResponse {
+        period_days,
+        snapshot_count,
+        trends,
+    }))
+}
+
+/// Run a snapshot scan immediately instead of waiting for the schedule
+pub async fn run_disk_snapshot(
+    State(state): State<crate::AppState>,
+    Json(request): Json<SnapshotRequest>,
+) -> Result<Json<SnapshotResponse>, AppError> {
+    let path = request.path
+        .map(PathBuf::from)
+        .unwrap_or_else(|| dirs::home_dir().unwrap_or_else(|| PathBuf::from(".")));
+
+    if !path.exists() {
+        return Err(AppError::NotFound(format!("Path {} not found", path.display())));
+    }
+
+    let scheduler = DiskScanScheduler::new(
+        state.db.clone(),
+        ScheduledScanConfig {
+            paths: vec![path],
+            ignore_rules: IgnoreRules::DISK_USAGE
+                .with_overrides(request.respect_gitignore, request.respect_skhootignore),
+            ..Default::default()
+        },
+    );
+    let snapshots_stored = scheduler.run_once().await?;
+
+    Ok(Json(SnapshotResponse { snapshots_stored }))
+}
+
+/// Start a treemap scan. The scan runs in the background; poll it by ID to
+/// get the tree built so far.
+pub async fn start_treemap_scan(
+    Json(request): Json<TreemapRequest>,
+) -> Result<Json<TreemapProgress>, AppError> {
+    let root = request.path
+        .map(PathBuf::from)
+        .unwrap_or_else(|| dirs::home_dir().unwrap_or_else(|| PathBuf::from(".")));
+
+    if !root.is_dir() {
+        return Err(AppError::NotFound(format!("Directory {} not found", root.display())));
+    }
+
+    let defaults = TreemapConfig::default();
+    let config = TreemapConfig {
+        root,
+        depth: request.depth.unwrap_or(defaults.depth).clamp(1, 8),
+        top_n: request.top_n.unwrap_or(defaults.top_n).clamp(1, 200),
+        ignore_rules: defaults.ignore_rules.with_overrides(request.respect_gitignore, request.respect_skhootignore),
+        threads: request.threads.unwrap_or(defaults.threads).min(64),
+        mounts: MountPolicy {
+            include_network: request.include_network_mounts.unwrap_or(defaults.mounts.include_network),
+            include_removable: request.include_removable_mounts.unwrap_or(defaults.mounts.include_removable),
+            ..defaults.mounts
+        },
+    };
+    let scan = TreemapScans::global().start(config);
+
+    Ok(Json(scan.progress()))
+}
+
+/// Progress of a treemap scan, with the tree as built so far
+pub async fn get_treemap_scan(
+    Path(scan_id): Path<String>,
+) -> Result<Json<TreemapProgress>, AppError> {
+    let scan = TreemapScans::global()
+        .get(&scan_id)
+        .ok_or_else(|| AppError::NotFound(format!("Treemap scan {} not found", scan_id)))?;
+
+    Ok(Json(scan.progress()))
+}
+
+/// Stop a running treemap scan; what was scanned so far stays available
+pub async fn cancel_treemap_scan(
+    Path(scan_id): Path<String>,
+) -> Result<StatusCode, AppError> {
+    let scan = TreemapScans::global()
+        .get(&scan_id)
+        .ok_or_else(|| AppError::NotFound(format!("Treemap scan {} not found", scan_id)))?;
+    scan.cancel();
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Treemap scans kept in memory, most recent first, without their trees
+pub async fn list_disk_scans() -> Json<Vec<TreemapScanSummary>> {
+    Json(TreemapScans::global().list())
+}
+
+/// Status and throughput of a treemap scan, without its tree; cheaper to
+/// poll than the full progress
+pub async fn get_disk_scan(
+    Path(scan_id): Path<String>,
+) -> Result<Json<TreemapScanSummary>, AppError> {
+    let scan = TreemapScans::global()
+        .get(&scan_id)
+        .ok_or_else(|| AppError::NotFound(format!("Treemap scan {} not found", scan_id)))?;
+
+    Ok(Json(scan.summary()))
+}
+
+/// Filter, sort and page the files a treemap scan found, without rescanning
+pub async fn query_scan_files(
+    Path(scan_id): Path<String>,
+    Query(query): Query<FileQuery>,
+) -> Result<Json<FileQueryPage>, AppError> {
+    let scan = TreemapScans::global()
+        .get(&scan_id)
+        .ok_or_else(|| AppError::NotFound(format!("Treemap scan {} not found", scan_id)))?;
+
+    query_files(scan, query).await
+}
+
+/// Same as `query_scan_files`, over the most recent scan
+pub async fn query_latest_files(
+    Query(query): Query<FileQuery>,
+) -> Result<Json<FileQueryPage>, AppError> {
+    let scan = TreemapScans::global()
+        .latest()
+        .ok_or_else(|| AppError::NotFound("No disk scan has run yet; start a treemap scan first".to_string()))?;
+
+    query_files(scan, query).await
+}
+
+async fn query_files(scan: std::sync::Arc<TreemapScan>, query: FileQuery) -> Result<Json<FileQueryPage>, AppError> {
+    let page = tokio::task::spawn_blocking(move || scan.query_files(&query))
+        .await
+        .map_err(|e| AppError::Internal(format!("File query failed: {}", e)))?;
+
+    Ok(Json(page))
+}
+
+/// List mounted volumes with filesystem, removable flag and SMART health
+pub async fn get_disk_volumes() -> Result<Json<DiskVolumesResponse>, AppError> {
+    let volumes = tokio::task::spawn_blocking(list_volumes)
+        .await
+        .map_err(|e| AppError::Internal(format!("Volume listing failed: {}", e)))?
+        .map_err(AppError::Internal)?;
+
+    Ok(Json(DiskVolumesResponse {
+        total_bytes: volumes.iter().map(|v| v.total_bytes).sum(),
+        free_bytes: volumes.iter().map(|v| v.free_bytes).sum(),
+        volumes,
+    }))
+}
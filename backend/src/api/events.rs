@@ -0,0 +1,75 @@
+//! Event stream API route
+//! Forwards events from the backend event bus to the UI as server-sent events
+
+use axum::{
+    extract::Query,
+    response::sse::{Event as SseEvent, KeepAlive, Sse},
+    routing::get,
+    Router,
+};
+use futures::stream::{self, Stream};
+use serde::Deserialize;
+use std::convert::Infallible;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::events;
+
+/// API route for the event stream
+pub fn event_routes() -> Router<crate::AppState> {
+    Router::new().route("/events", get(event_stream))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EventStreamQuery {
+    /// Comma-separated topics to receive (agents, workflows, terminal,
+    /// indexer, search, ai, config, files, audio, feeds, disk); all topics when
+    /// omitted
+    pub topics: Option<String>,
+}
+
+/// Server-sent event stream of backend events
+///
+/// Each message is named after its topic and carries the JSON event. A
+/// `lagged` message with the number of dropped events is sent when the client
+/// falls behind, so it can resync by refetching state.
+pub async fn event_stream(
+    Query(query): Query<EventStreamQuery>,
+) -> Sse<impl Stream<Item = Result<SseEvent, Infallible>>> {
+    let topics: Option<Vec<String>> = query.topics.map(|topics| {
+        topics
+            .split(',')
+            .map(|t| t.trim().to_lowercase())
+            .filter(|t| !t.is_empty())
+            .collect()
+    });
+    let receiver = events::bus().subscribe();
+
+    let stream = stream::unfold((receiver, topics), |(mut receiver, topics)| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(envelope) => {
+                    if topics.as_ref().is_some_and(|t| !t.iter().any(|topic| topic == envelope.topic)) {
+                        continue;
+                    }
+                    let message = SseEvent::default()
+                        .id(envelope.id.to_string())
+                        .event(envelope.topic)
+                        .json_data(&envelope)
+                        .unwrap_or_else(|_| SseEvent::default().comment("unserializable event"));
+                    return Some((Ok(message), (receiver, topics)));
+                }
+                Err(RecvError::Lagged(missed)) => {
+                    tracing::debug!("Event stream subscriber lagged by {} events", missed);
+                    let message = SseEvent::default()
+                        .event("lagged")
+                        .data(format!("{{\"missed\":{}}}", missed));
+                    return Some((Ok(message), (receiver, topics)));
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+
+    Sse::new(stream).keep_alive(KeepAlive::new().interval(Duration::from_secs(15)))
+}
@@ -0,0 +1,101 @@
+//! Feed subscription API routes
+//! CRUD for RSS/Atom subscriptions, their stored entries, and checking a feed now
+
+use axum::{
+    extract::{Path, Query, State},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+
+use crate::content_extraction::HttpFetcher;
+use crate::error::AppError;
+use crate::feeds::{self, CreateFeedRequest, FeedEntry, FeedError, FeedStore, FeedSubscription, UpdateFeedRequest};
+use crate::AppState;
+
+pub fn feed_routes() -> Router<AppState> {
+    Router::new()
+        .route("/feeds", get(list_feeds).post(create_feed))
+        .route("/feeds/:id", get(get_feed).put(update_feed).delete(delete_feed))
+        .route("/feeds/:id/entries", get(list_entries))
+        .route("/feeds/:id/check", post(check_feed))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EntryListQuery {
+    pub limit: Option<usize>,           // Entries to list, newest first (default: 50, max: 200)
+}
+
+impl From<FeedError> for AppError {
+    fn from(error: FeedError) -> Self {
+        match error {
+            FeedError::NotFound(_) => AppError::NotFound(error.to_string()),
+            FeedError::Invalid(_) => AppError::BadRequest(error.to_string()),
+            FeedError::Fetch(_) | FeedError::Io(_) => AppError::Internal(error.to_string()),
+        }
+    }
+}
+
+/// Subscriptions may only start workflows that exist
+async fn ensure_workflow_exists(state: &AppState, workflow_id: Option<&str>) -> Result<(), AppError> {
+    match workflow_id.map(str::trim).filter(|id| !id.is_empty()) {
+        Some(id) if state.workflow_storage.get(id).await.is_none() => {
+            Err(AppError::BadRequest(format!("Workflow {} not found", id)))
+        }
+        _ => Ok(()),
+    }
+}
+
+async fn list_feeds() -> Json<Vec<FeedSubscription>> {
+    Json(FeedStore::global().list())
+}
+
+async fn create_feed(
+    State(state): State<AppState>,
+    Json(request): Json<CreateFeedRequest>,
+) -> Result<Json<FeedSubscription>, AppError> {
+    ensure_workflow_exists(&state, request.workflow_id.as_deref()).await?;
+    Ok(Json(FeedStore::global().create(request)?))
+}
+
+async fn get_feed(Path(id): Path<String>) -> Result<Json<FeedSubscription>, AppError> {
+    FeedStore::global()
+        .get(&id)
+        .map(Json)
+        .ok_or_else(|| FeedError::NotFound(id).into())
+}
+
+/// Change a subscription's URL, interval, workflow or enabled flag
+async fn update_feed(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(request): Json<UpdateFeedRequest>,
+) -> Result<Json<FeedSubscription>, AppError> {
+    ensure_workflow_exists(&state, request.workflow_id.as_deref()).await?;
+    Ok(Json(FeedStore::global().update(&id, request)?))
+}
+
+/// Remove a subscription and its stored entries
+async fn delete_feed(Path(id): Path<String>) -> Result<Json<bool>, AppError> {
+    Ok(Json(FeedStore::global().remove(&id)?))
+}
+
+async fn list_entries(
+    Path(id): Path<String>,
+    Query(query): Query<EntryListQuery>,
+) -> Result<Json<Vec<FeedEntry>>, AppError> {
+    let store = FeedStore::global();
+    store.get(&id).ok_or_else(|| FeedError::NotFound(id.clone()))?;
+    let limit = query.limit.unwrap_or(50).min(feeds::MAX_ENTRIES_PER_FEED);
+    Ok(Json(store.entries(&id, limit)))
+}
+
+/// Read the feed now instead of waiting for its interval; returns the new entries
+async fn check_feed(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<FeedEntry>>, AppError> {
+    let fetcher = HttpFetcher::new()?;
+    let added = feeds::check(&FeedStore::global(), &fetcher, &state.workflow_engine, &id).await?;
+    Ok(Json(added))
+}
@@ -0,0 +1,69 @@
+//! Goal mode routes
+//! Start a goal run, report each plan/act/evaluate phase the agent ran, and
+//! follow or cancel the run
+
+use axum::{
+    extract::{Path, Query},
+    response::Json,
+    routing::{get, post},
+    Router,
+};
+use serde::Deserialize;
+
+use crate::cli_agent::goal::{GoalError, PhaseReport, StartGoalRequest};
+use crate::cli_agent::{GoalRun, GoalStore};
+use crate::error::AppError;
+
+/// API routes for goal runs
+pub fn goal_routes() -> Router<crate::AppState> {
+    Router::new()
+        .route("/goals", get(list_goals).post(start_goal))
+        .route("/goals/:id", get(get_goal).delete(delete_goal))
+        .route("/goals/:id/report", post(report_phase))
+        .route("/goals/:id/cancel", post(cancel_goal))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListGoalsQuery {
+    pub session_id: Option<String>,
+}
+
+impl From<GoalError> for AppError {
+    fn from(error: GoalError) -> Self {
+        match error {
+            GoalError::NotFound(_) => AppError::NotFound(error.to_string()),
+            GoalError::Invalid(message) => AppError::BadRequest(message),
+            GoalError::Io(_) => AppError::Internal(error.to_string()),
+        }
+    }
+}
+
+pub async fn list_goals(Query(query): Query<ListGoalsQuery>) -> Json<Vec<GoalRun>> {
+    Json(GoalStore::global().list(query.session_id.as_deref()))
+}
+
+pub async fn start_goal(Json(request): Json<StartGoalRequest>) -> Result<Json<GoalRun>, AppError> {
+    Ok(Json(GoalStore::global().start(request)?))
+}
+
+pub async fn get_goal(Path(id): Path<String>) -> Result<Json<GoalRun>, AppError> {
+    GoalStore::global()
+        .get(&id)
+        .map(Json)
+        .ok_or_else(|| GoalError::NotFound(id).into())
+}
+
+pub async fn report_phase(
+    Path(id): Path<String>,
+    Json(report): Json<PhaseReport>,
+) -> Result<Json<GoalRun>, AppError> {
+    Ok(Json(GoalStore::global().report(&id, report)?))
+}
+
+pub async fn cancel_goal(Path(id): Path<String>) -> Result<Json<GoalRun>, AppError> {
+    Ok(Json(GoalStore::global().cancel(&id)?))
+}
+
+pub async fn delete_goal(Path(id): Path<String>) -> Json<bool> {
+    Json(GoalStore::global().delete(&id))
+}
@@ -0,0 +1,242 @@
+//! Local API routes
+//! Management of local API clients, and the OpenAI-compatible endpoints
+//! they call. The `/v1` routes sit outside the launch-token middleware and
+//! authenticate clients by their own tokens instead.
+
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{
+        sse::{Event as SseEvent, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    routing::{get, post},
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::convert::Infallible;
+use tokio::sync::mpsc;
+use tokio_stream::{wrappers::ReceiverStream, StreamExt};
+
+use crate::config::SettingsStore;
+use crate::error::AppError;
+use crate::local_api::{self, ApiClient, ClientStore, Completion, CreateClientRequest, LocalApiError, UpdateClientRequest, MODEL_ID};
+use crate::AppState;
+
+/// Client management, behind the launch token
+pub fn local_api_routes() -> Router<AppState> {
+    Router::new()
+        .route("/local-api/clients", get(list_clients).post(create_client))
+        .route("/local-api/clients/:id", get(get_client).put(update_client).delete(delete_client))
+        .route("/local-api/clients/:id/token", post(rotate_token))
+}
+
+/// OpenAI-compatible endpoints for registered clients
+pub fn openai_routes() -> Router<AppState> {
+    Router::new()
+        .route("/v1/models", get(list_models))
+        .route("/v1/chat/completions", post(chat_completions))
+}
+
+impl From<LocalApiError> for AppError {
+    fn from(error: LocalApiError) -> Self {
+        match error {
+            LocalApiError::NotFound(_) => AppError::NotFound(error.to_string()),
+            LocalApiError::Invalid(_) => AppError::BadRequest(error.to_string()),
+            LocalApiError::Io(_) => AppError::Internal(error.to_string()),
+        }
+    }
+}
+
+/// A new client with its token, which is only ever shown here
+#[derive(Debug, Serialize)]
+struct ClientWithToken {
+    #[serde(flatten)]
+    client: ApiClient,
+    token: String,
+}
+
+async fn list_clients() -> Json<Vec<ApiClient>> {
+    Json(ClientStore::global().list())
+}
+
+async fn create_client(Json(request): Json<CreateClientRequest>) -> Result<Json<ClientWithToken>, AppError> {
+    let (client, token) = ClientStore::global().create(request)?;
+    Ok(Json(ClientWithToken { client, token }))
+}
+
+async fn get_client(Path(id): Path<String>) -> Result<Json<ApiClient>, AppError> {
+    ClientStore::global()
+        .get(&id)
+        .map(Json)
+        .ok_or_else(|| LocalApiError::NotFound(id).into())
+}
+
+/// Change a client's name, provider, model, permissions or enabled flag
+async fn update_client(
+    Path(id): Path<String>,
+    Json(request): Json<UpdateClientRequest>,
+) -> Result<Json<ApiClient>, AppError> {
+    Ok(Json(ClientStore::global().update(&id, request)?))
+}
+
+async fn delete_client(Path(id): Path<String>) -> Result<StatusCode, AppError> {
+    if !ClientStore::global().remove(&id)? {
+        return Err(LocalApiError::NotFound(id).into());
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Issue a new token for a client; the old one stops working
+async fn rotate_token(Path(id): Path<String>) -> Result<Json<ClientWithToken>, AppError> {
+    let (client, token) = ClientStore::global().rotate_token(&id)?;
+    Ok(Json(ClientWithToken { client, token }))
+}
+
+/// Error in the shape OpenAI clients expect
+struct OpenAiError {
+    status: StatusCode,
+    kind: &'static str,
+    message: String,
+}
+
+impl OpenAiError {
+    fn new(status: StatusCode, kind: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            kind,
+            message: message.into(),
+        }
+    }
+
+    fn body(&self) -> Value {
+        json!({ "error": { "message": self.message, "type": self.kind, "code": Value::Null } })
+    }
+}
+
+impl From<AppError> for OpenAiError {
+    fn from(error: AppError) -> Self {
+        match error {
+            AppError::BadRequest(message) => Self::new(StatusCode::BAD_REQUEST, "invalid_request_error", message),
+            AppError::NotFound(message) => Self::new(StatusCode::NOT_FOUND, "invalid_request_error", message),
+            error => {
+                tracing::error!("Local API request failed: {}", error);
+                Self::new(StatusCode::INTERNAL_SERVER_ERROR, "server_error", error.to_string())
+            }
+        }
+    }
+}
+
+impl IntoResponse for OpenAiError {
+    fn into_response(self) -> Response {
+        (self.status, Json(self.body())).into_response()
+    }
+}
+
+/// The client presenting the request's bearer token
+fn authenticate(headers: &HeaderMap) -> Result<ApiClient, OpenAiError> {
+    if !SettingsStore::global().get().local_api.enabled {
+        return Err(OpenAiError::new(
+            StatusCode::NOT_FOUND,
+            "invalid_request_error",
+            "The local API is disabled; enable local_api in the settings",
+        ));
+    }
+    crate::auth::presented_token(headers, None)
+        .and_then(|token| ClientStore::global().authenticate(&token))
+        .ok_or_else(|| OpenAiError::new(StatusCode::UNAUTHORIZED, "invalid_request_error", "Invalid API token"))
+}
+
+async fn list_models(headers: HeaderMap) -> Result<Json<Value>, OpenAiError> {
+    authenticate(&headers)?;
+    Ok(Json(json!({
+        "object": "list",
+        "data": [{ "id": MODEL_ID, "object": "model", "created": 0, "owned_by": "skhoot" }],
+    })))
+}
+
+/// Fields of a chat completion request that are used; sampling parameters
+/// and caller-defined tools are ignored
+#[derive(Debug, Deserialize)]
+struct ChatCompletionRequest {
+    messages: Vec<Value>,
+    #[serde(default)]
+    stream: bool,
+}
+
+async fn chat_completions(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<ChatCompletionRequest>,
+) -> Result<Response, OpenAiError> {
+    let client = authenticate(&headers)?;
+    if request.messages.is_empty() {
+        return Err(OpenAiError::new(StatusCode::BAD_REQUEST, "invalid_request_error", "messages is required"));
+    }
+    let id = format!("chatcmpl-{}", uuid::Uuid::new_v4().simple());
+    let created = chrono::Utc::now().timestamp();
+
+    if request.stream {
+        return Ok(stream_completion(state, client, request.messages, id, created).into_response());
+    }
+
+    let completion = local_api::complete(&state.ai_manager, &client, &request.messages).await?;
+    tracing::info!(
+        "Local API client {} answered after {} tool calls",
+        client.name,
+        completion.tool_calls
+    );
+    Ok(Json(json!({
+        "id": id,
+        "object": "chat.completion",
+        "created": created,
+        "model": MODEL_ID,
+        "choices": [{
+            "index": 0,
+            "message": { "role": "assistant", "content": completion.content },
+            "finish_reason": "stop",
+        }],
+        "usage": completion.usage,
+    }))
+    .into_response())
+}
+
+/// Stream the answer as `chat.completion.chunk` events. The agent only
+/// answers once its tools are done, so the content arrives in one chunk;
+/// keep-alive comments hold the connection open until then.
+fn stream_completion(
+    state: AppState,
+    client: ApiClient,
+    messages: Vec<Value>,
+    id: String,
+    created: i64,
+) -> Sse<impl futures::Stream<Item = Result<SseEvent, Infallible>>> {
+    let chunk = move |delta: Value, finish_reason: Option<&str>| {
+        json!({
+            "id": id,
+            "object": "chat.completion.chunk",
+            "created": created,
+            "model": MODEL_ID,
+            "choices": [{ "index": 0, "delta": delta, "finish_reason": finish_reason }],
+        })
+    };
+    let data = |value: &Value| SseEvent::default().data(value.to_string());
+
+    let (tx, rx) = mpsc::channel(4);
+    tokio::spawn(async move {
+        let _ = tx.send(data(&chunk(json!({ "role": "assistant", "content": "" }), None))).await;
+        let events = match local_api::complete(&state.ai_manager, &client, &messages).await {
+            Ok(Completion { content, .. }) => vec![
+                data(&chunk(json!({ "content": content }), None)),
+                data(&chunk(json!({}), Some("stop"))),
+            ],
+            Err(error) => vec![data(&OpenAiError::from(error).body())],
+        };
+        for event in events {
+            let _ = tx.send(event).await;
+        }
+        let _ = tx.send(SseEvent::default().data("[DONE]")).await;
+    });
+    Sse::new(ReceiverStream::new(rx).map(Ok)).keep_alive(KeepAlive::default())
+}
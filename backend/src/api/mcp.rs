@@ -0,0 +1,82 @@
+//! MCP server API routes
+//! Servers are configured in the `mcp` settings section; these routes show
+//! their connection state, reconnect them and call their tools directly.
+
+use axum::{
+    extract::Query,
+    response::Json,
+    routing::{get, post},
+    Router,
+};
+use serde::Deserialize;
+
+use crate::config::SettingsStore;
+use crate::error::AppError;
+use crate::mcp::{CallToolResult, McpError, McpManager, McpToolInfo, ResourceContents, ServerStatus};
+
+/// API routes for MCP servers
+pub fn mcp_routes() -> Router<crate::AppState> {
+    Router::new()
+        .route("/mcp/servers", get(list_servers))
+        .route("/mcp/servers/reconnect", post(reconnect_servers))
+        .route("/mcp/tools", get(list_tools))
+        .route("/mcp/tools/call", post(call_tool))
+        .route("/mcp/resources/read", get(read_resource))
+}
+
+/// Configured servers and whether they are connected
+pub async fn list_servers() -> Json<Vec<ServerStatus>> {
+    Json(McpManager::global().statuses().await)
+}
+
+/// Reconnect every configured server
+pub async fn reconnect_servers() -> Json<Vec<ServerStatus>> {
+    let manager = McpManager::global();
+    manager.connect_all(SettingsStore::global().get().mcp.servers).await;
+    Json(manager.statuses().await)
+}
+
+/// Tools of the connected servers, with the names agents call them by
+pub async fn list_tools() -> Json<Vec<McpToolInfo>> {
+    Json(McpManager::global().tools())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CallToolRequest {
+    /// Registered name (`mcp__<server>__<tool>`)
+    pub name: String,
+    #[serde(default)]
+    pub arguments: serde_json::Value,
+}
+
+/// Call a tool
+pub async fn call_tool(Json(request): Json<CallToolRequest>) -> Result<Json<CallToolResult>, AppError> {
+    let result = McpManager::global()
+        .call_tool(&request.name, request.arguments)
+        .await
+        .map_err(to_app_error)?;
+    Ok(Json(result))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReadResourceQuery {
+    pub server: String,
+    pub uri: String,
+}
+
+/// Read a resource from a server
+pub async fn read_resource(Query(query): Query<ReadResourceQuery>) -> Result<Json<Vec<ResourceContents>>, AppError> {
+    let contents = McpManager::global()
+        .read_resource(&query.server, &query.uri)
+        .await
+        .map_err(to_app_error)?;
+    Ok(Json(contents))
+}
+
+fn to_app_error(e: McpError) -> AppError {
+    match e {
+        McpError::UnknownTool(_) | McpError::NotConnected(_) => AppError::NotFound(e.to_string()),
+        McpError::Server { .. } | McpError::Config(_) => AppError::BadRequest(e.to_string()),
+        _ => AppError::Internal(e.to_string()),
+    }
+}
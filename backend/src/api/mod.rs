@@ -1,6 +1,29 @@
 pub mod search;
 pub mod disk;
+pub mod quotas;
 pub mod agents;
+pub mod goals;
 pub mod web_search;
 pub mod recent;
 pub mod workflows;
+pub mod feeds;
+pub mod checkpoints;
+pub mod refactor;
+pub mod prompt_templates;
+pub mod attachments;
+pub mod ai_health;
+pub mod ai_cache;
+pub mod events;
+pub mod config;
+pub mod archives;
+pub mod audio;
+pub mod conversations;
+pub mod mcp;
+pub mod code;
+pub mod plugins;
+pub mod tool_queue;
+pub mod admin;
+pub mod vault;
+pub mod stats;
+pub mod local_api;
+pub mod system;
@@ -0,0 +1,30 @@
+//! Plugin API routes
+//! Plugins are installed by adding a folder to the plugins directory; the
+//! registry picks them up on its own, these routes list them and force a scan.
+
+use axum::{
+    response::Json,
+    routing::{get, post},
+    Router,
+};
+
+use crate::plugins::{PluginInfo, PluginRegistry};
+
+/// API routes for agent tool plugins
+pub fn plugin_routes() -> Router<crate::AppState> {
+    Router::new()
+        .route("/plugins", get(list_plugins))
+        .route("/plugins/reload", post(reload_plugins))
+}
+
+/// Installed plugins, including ones whose manifest failed to load
+pub async fn list_plugins() -> Json<Vec<PluginInfo>> {
+    Json(PluginRegistry::global().plugins())
+}
+
+/// Rescan the plugins directory
+pub async fn reload_plugins() -> Json<Vec<PluginInfo>> {
+    let registry = PluginRegistry::global();
+    registry.reload();
+    Json(registry.plugins())
+}
@@ -0,0 +1,129 @@
+//! System prompt template API routes
+//! Edits the global, per-agent and per-conversation prompt templates
+
+use axum::{
+    extract::{Path, Query},
+    response::Json,
+    routing::get,
+    Router,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::cli_agent::prompt_templates::PromptTemplateSet;
+use crate::cli_agent::{PromptScope, PromptTemplate, PromptTemplateStore, SystemPrompt};
+use crate::error::AppError;
+
+/// API routes for prompt templates
+pub fn prompt_template_routes() -> Router<crate::AppState> {
+    Router::new()
+        .route("/prompt-templates", get(list_templates))
+        .route("/prompt-templates/resolve", get(resolve_template))
+        .route(
+            "/prompt-templates/global",
+            get(get_global).put(set_global).delete(delete_global),
+        )
+        .route(
+            "/prompt-templates/agents/:id",
+            get(get_agent).put(set_agent).delete(delete_agent),
+        )
+        .route(
+            "/prompt-templates/conversations/:id",
+            get(get_conversation).put(set_conversation).delete(delete_conversation),
+        )
+}
+
+/// Query for the effective template
+#[derive(Debug, Deserialize)]
+pub struct ResolveQuery {
+    pub agent_id: Option<String>,
+    pub conversation_id: Option<String>,
+}
+
+/// Effective template and the system prompt built from it
+#[derive(Debug, Serialize)]
+pub struct ResolvedPromptResponse {
+    pub template: PromptTemplate,
+    pub system_prompt: String,
+}
+
+/// Every stored template
+pub async fn list_templates() -> Json<PromptTemplateSet> {
+    Json(PromptTemplateStore::global().all())
+}
+
+/// Merge the templates that apply to an agent and conversation
+pub async fn resolve_template(Query(query): Query<ResolveQuery>) -> Json<ResolvedPromptResponse> {
+    let template = PromptTemplateStore::global()
+        .resolve(query.agent_id.as_deref(), query.conversation_id.as_deref());
+    let system_prompt = SystemPrompt::default_skhoot()
+        .with_template(template.clone())
+        .build();
+
+    Json(ResolvedPromptResponse {
+        template,
+        system_prompt,
+    })
+}
+
+pub async fn get_global() -> Result<Json<PromptTemplate>, AppError> {
+    get_scope(PromptScope::Global)
+}
+
+pub async fn set_global(Json(template): Json<PromptTemplate>) -> Result<Json<PromptTemplate>, AppError> {
+    set_scope(PromptScope::Global, template)
+}
+
+pub async fn delete_global() -> Result<Json<bool>, AppError> {
+    delete_scope(PromptScope::Global)
+}
+
+pub async fn get_agent(Path(id): Path<String>) -> Result<Json<PromptTemplate>, AppError> {
+    get_scope(PromptScope::Agent(id))
+}
+
+pub async fn set_agent(
+    Path(id): Path<String>,
+    Json(template): Json<PromptTemplate>,
+) -> Result<Json<PromptTemplate>, AppError> {
+    set_scope(PromptScope::Agent(id), template)
+}
+
+pub async fn delete_agent(Path(id): Path<String>) -> Result<Json<bool>, AppError> {
+    delete_scope(PromptScope::Agent(id))
+}
+
+pub async fn get_conversation(Path(id): Path<String>) -> Result<Json<PromptTemplate>, AppError> {
+    get_scope(PromptScope::Conversation(id))
+}
+
+pub async fn set_conversation(
+    Path(id): Path<String>,
+    Json(template): Json<PromptTemplate>,
+) -> Result<Json<PromptTemplate>, AppError> {
+    set_scope(PromptScope::Conversation(id), template)
+}
+
+pub async fn delete_conversation(Path(id): Path<String>) -> Result<Json<bool>, AppError> {
+    delete_scope(PromptScope::Conversation(id))
+}
+
+fn get_scope(scope: PromptScope) -> Result<Json<PromptTemplate>, AppError> {
+    PromptTemplateStore::global()
+        .get(&scope)
+        .map(Json)
+        .ok_or_else(|| AppError::NotFound(format!("No prompt template for {:?}", scope)))
+}
+
+fn set_scope(scope: PromptScope, template: PromptTemplate) -> Result<Json<PromptTemplate>, AppError> {
+    PromptTemplateStore::global()
+        .set(scope, template.clone())
+        .map_err(|e| AppError::Internal(format!("Failed to save prompt template: {}", e)))?;
+    Ok(Json(template))
+}
+
+fn delete_scope(scope: PromptScope) -> Result<Json<bool>, AppError> {
+    PromptTemplateStore::global()
+        .remove(&scope)
+        .map(Json)
+        .map_err(|e| AppError::Internal(format!("Failed to save prompt templates: {}", e)))
+}
@@ -0,0 +1,82 @@
+//! Storage quota API routes
+//! CRUD for per-directory storage quotas, and measuring one now
+
+use axum::{
+    extract::{Path, State},
+    routing::{get, post},
+    Json, Router,
+};
+
+use crate::error::AppError;
+use crate::storage_quotas::{self, CreateQuotaRequest, QuotaError, QuotaStore, StorageQuota, UpdateQuotaRequest};
+use crate::AppState;
+
+pub fn quota_routes() -> Router<AppState> {
+    Router::new()
+        .route("/disk/quotas", get(list_quotas).post(create_quota))
+        .route("/disk/quotas/:id", get(get_quota).put(update_quota).delete(delete_quota))
+        .route("/disk/quotas/:id/evaluate", post(evaluate_quota))
+}
+
+impl From<QuotaError> for AppError {
+    fn from(error: QuotaError) -> Self {
+        match error {
+            QuotaError::NotFound(_) => AppError::NotFound(error.to_string()),
+            QuotaError::Invalid(_) => AppError::BadRequest(error.to_string()),
+            QuotaError::Io(_) => AppError::Internal(error.to_string()),
+        }
+    }
+}
+
+/// Quotas may only start workflows that exist
+async fn ensure_workflow_exists(state: &AppState, workflow_id: Option<&str>) -> Result<(), AppError> {
+    match workflow_id.map(str::trim).filter(|id| !id.is_empty()) {
+        Some(id) if state.workflow_storage.get(id).await.is_none() => {
+            Err(AppError::BadRequest(format!("Workflow {} not found", id)))
+        }
+        _ => Ok(()),
+    }
+}
+
+async fn list_quotas() -> Json<Vec<StorageQuota>> {
+    Json(QuotaStore::global().list())
+}
+
+async fn create_quota(
+    State(state): State<AppState>,
+    Json(request): Json<CreateQuotaRequest>,
+) -> Result<Json<StorageQuota>, AppError> {
+    ensure_workflow_exists(&state, request.workflow_id.as_deref()).await?;
+    Ok(Json(QuotaStore::global().create(request)?))
+}
+
+async fn get_quota(Path(id): Path<String>) -> Result<Json<StorageQuota>, AppError> {
+    QuotaStore::global()
+        .get(&id)
+        .map(Json)
+        .ok_or_else(|| QuotaError::NotFound(id).into())
+}
+
+/// Change a quota's name, target, limit, workflow or enabled flag
+async fn update_quota(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(request): Json<UpdateQuotaRequest>,
+) -> Result<Json<StorageQuota>, AppError> {
+    ensure_workflow_exists(&state, request.workflow_id.as_deref()).await?;
+    Ok(Json(QuotaStore::global().update(&id, request)?))
+}
+
+async fn delete_quota(Path(id): Path<String>) -> Result<Json<bool>, AppError> {
+    Ok(Json(QuotaStore::global().remove(&id)?))
+}
+
+/// Measure the quota now instead of waiting for the monitor; alerts if it
+/// crossed its limit
+async fn evaluate_quota(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<StorageQuota>, AppError> {
+    let quota = storage_quotas::evaluate(&QuotaStore::global(), &state.workflow_engine, &id).await?;
+    Ok(Json(quota))
+}
@@ -5,7 +5,9 @@ use std::path::Path;
 use std::time::{SystemTime, UNIX_EPOCH};
 use walkdir::WalkDir;
 
+use crate::change_timeline::{ChangeTimeline, ChangeTimelineReport};
 use crate::error::AppError;
+use crate::file_history::{FileHistory, FileHistoryEntry};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct RecentFile {
@@ -58,6 +60,44 @@ pub async fn get_system_recent_files(
     Ok(Json(recent_files))
 }
 
+#[derive(Debug, Deserialize)]
+pub struct RecentOperationsQuery {
+    pub limit: Option<usize>,
+}
+
+/// Moves, copies, renames and deletions made through Skhoot, newest first
+pub async fn get_recent_operations(
+    Query(params): Query<RecentOperationsQuery>,
+) -> Result<Json<Vec<FileHistoryEntry>>, AppError> {
+    Ok(Json(FileHistory::global().recent(params.limit.unwrap_or(50))))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChangedFilesQuery {
+    pub hours: Option<u64>,
+    /// Comma-separated directories
+    pub paths: Option<String>,
+    /// Search workspace whose roots to cover
+    pub workspace: Option<String>,
+    pub limit: Option<usize>,
+}
+
+/// Files changed on disk in the last hours, by Skhoot or anything else,
+/// grouped by directory. Covers the given paths, else the named or default
+/// search workspace, else the home directory.
+pub async fn get_changed_files(
+    Query(params): Query<ChangedFilesQuery>,
+) -> Result<Json<ChangeTimelineReport>, AppError> {
+    let roots = crate::api::search::search_roots(None, params.paths.as_deref(), params.workspace.as_deref())?;
+    let since = chrono::Utc::now() - chrono::Duration::hours(params.hours.unwrap_or(24).min(24 * 365) as i64);
+    let limit = params.limit.unwrap_or(500).clamp(1, 5000);
+
+    let report = tokio::task::spawn_blocking(move || ChangeTimeline::global().changes(&roots, since, limit))
+        .await
+        .map_err(|e| AppError::Internal(format!("Change scan failed: {}", e)))?;
+    Ok(Json(report))
+}
+
 fn scan_directory(dir: &Path, seconds_limit: u64, now: u64, source: &str) -> Vec<RecentFile> {
     let mut results = Vec::new();
 
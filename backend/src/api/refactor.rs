@@ -0,0 +1,107 @@
+//! Find and replace API routes
+//! Previews a replacement across a directory as per-file diffs and applies it
+//! once the user approves, behind a checkpoint that reverts it.
+
+use axum::{
+    extract::Path,
+    response::Json,
+    routing::{get, post},
+    Router,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::cli_agent::{AppliedRefactor, CheckpointManager, RefactorError, RefactorPreview, RefactorStore, ReplaceSpec};
+use crate::error::AppError;
+
+/// API routes for find and replace
+pub fn refactor_routes() -> Router<crate::AppState> {
+    Router::new()
+        .route("/refactor/preview", post(preview_refactor))
+        .route("/refactor/:id", get(get_refactor).delete(discard_refactor))
+        .route("/refactor/:id/apply", post(apply_refactor))
+}
+
+/// Request body for a preview
+#[derive(Debug, Deserialize)]
+pub struct PreviewRequest {
+    /// Directory the replacement is scoped to
+    pub path: String,
+    #[serde(flatten)]
+    pub spec: ReplaceSpec,
+    /// Agent session to record the checkpoint in
+    pub session_id: Option<String>,
+}
+
+/// Plan a replacement without writing anything
+pub async fn preview_refactor(Json(request): Json<PreviewRequest>) -> Result<Json<RefactorPreview>, AppError> {
+    let root = std::fs::canonicalize(&request.path)
+        .map_err(|e| AppError::NotFound(format!("{}: {}", request.path, e)))?;
+    if !root.is_dir() {
+        return Err(AppError::BadRequest(format!("{} is not a directory", request.path)));
+    }
+
+    let preview = tokio::task::spawn_blocking(move || {
+        RefactorStore::global().preview(&root, request.spec, request.session_id)
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Preview task failed: {}", e)))?
+    .map_err(to_app_error)?;
+    Ok(Json(preview))
+}
+
+/// A preview that has not been applied yet
+pub async fn get_refactor(Path(id): Path<String>) -> Result<Json<RefactorPreview>, AppError> {
+    RefactorStore::global()
+        .get(&id)
+        .map(Json)
+        .ok_or_else(|| AppError::NotFound(format!("Preview not found: {}", id)))
+}
+
+#[derive(Debug, Serialize)]
+pub struct DiscardResponse {
+    pub discarded: bool,
+}
+
+/// Drop a preview without applying it
+pub async fn discard_refactor(Path(id): Path<String>) -> Json<DiscardResponse> {
+    Json(DiscardResponse {
+        discarded: RefactorStore::global().discard(&id),
+    })
+}
+
+/// Request body for applying a preview
+#[derive(Debug, Deserialize)]
+pub struct ApplyRequest {
+    /// Set once the user has reviewed the diff
+    #[serde(default)]
+    pub approved: bool,
+}
+
+/// Write a previewed replacement
+pub async fn apply_refactor(
+    Path(id): Path<String>,
+    Json(request): Json<ApplyRequest>,
+) -> Result<Json<AppliedRefactor>, AppError> {
+    if !request.approved {
+        return Err(AppError::BadRequest("Applying a replacement requires user approval".to_string()));
+    }
+
+    let applied = tokio::task::spawn_blocking(move || {
+        RefactorStore::global().apply(&id, &CheckpointManager::global())
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Apply task failed: {}", e)))?
+    .map_err(to_app_error)?;
+    Ok(Json(applied))
+}
+
+fn to_app_error(e: RefactorError) -> AppError {
+    match e {
+        RefactorError::NotFound(_) => AppError::NotFound(e.to_string()),
+        RefactorError::InvalidPattern(_)
+        | RefactorError::InvalidScope(_)
+        | RefactorError::Stale(_)
+        | RefactorError::TooManyFiles => AppError::BadRequest(e.to_string()),
+        RefactorError::Checkpoint(_) | RefactorError::Io(_) => AppError::Internal(e.to_string()),
+    }
+}
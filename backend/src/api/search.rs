@@ -1,27 +1,51 @@
 use axum::{
     extract::{Query, State, Path},
+    http::StatusCode,
+    response::sse::{Event as SseEvent, KeepAlive, Sse},
     response::Json,
     routing::{get, post},
     Router,
 };
+use futures::stream::Stream;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::convert::Infallible;
 use std::path::PathBuf;
+use tokio::sync::mpsc;
+use tokio_stream::{wrappers::ReceiverStream, StreamExt};
 
 use crate::search_engine::{
     SearchContext, SearchIntent, UnifiedSearchResults,
-    SearchMode, MergedSearchResult,
+    SearchMode, MergedSearchResult, RankingReport, SearchRanking,
+    FileMetadata, MetadataExtractor, merge_root_results,
+    SearchManager, CliEngine, CliConfig, CliSearchResult, SearchFilter, SearchQuery,
 };
 use crate::error::AppError;
+use crate::file_diff::{self, DiffError, DiffOptions, DiffTarget, FileDiff};
+use crate::file_follow::{self, FollowOptions};
+use crate::file_history::{FileHistory, OperationOrigin};
+use crate::file_preview::{self, FilePreview, PreviewError, PreviewOptions};
+use crate::file_range::{self, RangeError, ReadRange};
+use crate::filename_index::{self, FilenameIndexStatus};
+use crate::code_index::{merge_symbol_results, CodeIndex};
+use crate::file_tree::{self, DirectoryPage, ListOptions};
+use crate::file_transfer::{self, Collision, TransferError, TransferOutcome};
+use crate::recycle_bin::{self, DeleteError, DeleteOutcome};
 
 /// API endpoints for file search functionality
 pub fn search_routes() -> Router<crate::AppState> {
     Router::new()
         .route("/search/files", get(search_files))
+        .route("/search/files/stream", get(stream_search_files))
         .route("/search/documents", get(search_documents))
         .route("/search/content", get(search_content))
         .route("/search/suggest", post(get_search_suggestions))
         .route("/search/history", get(get_search_history))
+        .route("/search/feedback", post(record_search_feedback))
+        .route("/search/ranking", get(get_search_ranking).delete(reset_search_ranking))
+        .route("/search/metadata", post(get_search_metadata))
+        .route("/search/filename-index", get(get_filename_index_status))
+        .route("/search/filename-index/reconcile", post(reconcile_filename_index))
         .route("/search/active", get(get_active_searches))
         .route("/search/:search_id/cancel", post(cancel_search))
         .route("/search/config", get(get_search_config))
@@ -31,8 +55,17 @@ pub fn search_routes() -> Router<crate::AppState> {
         .route("/files/properties", post(show_file_properties))
         .route("/files/open-with", post(open_with_dialog))
         .route("/files/read", get(read_file_content))
+        .route("/files/tail", get(tail_file))
+        .route("/files/preview", get(preview_file))
+        .route("/files/diff", post(diff_files))
         .route("/files/list", get(list_directory_content))
+        .route("/files/tree", get(get_directory_tree))
         .route("/files/write", post(write_file_content))
+        .route("/files/delete", post(delete_file))
+        .route("/files/restore", post(restore_file))
+        .route("/files/move", post(move_file))
+        .route("/files/copy", post(copy_file))
+        .route("/files/rename", post(rename_file))
         .route("/shell/execute", post(execute_shell_command))
         .route("/files/image", get(read_image_file))
 }
@@ -42,14 +75,21 @@ pub fn search_routes() -> Router<crate::AppState> {
 /// Query parameters for file search
 #[derive(Debug, Deserialize)]
 pub struct FileSearchQuery {
-    pub q: String,                    // Search query
+    pub q: String,                    // Search query, with optional ext:, size:, modified:, path: filters and "phrases"
     pub mode: Option<String>,         // Search mode: rust, cli, hybrid, auto
     pub max_results: Option<usize>,   // Maximum number of results
     pub include_indices: Option<bool>, // Include character indices for highlighting
     pub file_types: Option<String>,   // Comma-separated file extensions
     pub exclude_dirs: Option<String>, // Comma-separated directories to exclude
     pub search_path: Option<String>,  // Custom search path (defaults to user home)
+    pub search_paths: Option<String>, // Comma-separated search roots, searched concurrently
+    pub workspace: Option<String>,    // Named set of search roots from settings
     pub unrestricted: Option<bool>,   // Enable deep search (hidden files, ignore .gitignore)
+    pub include_metadata: Option<bool>, // Read image/media/document metadata for results
+    pub respect_gitignore: Option<bool>, // Override whether .gitignore rules apply
+    pub respect_skhootignore: Option<bool>, // Override whether .skhootignore rules apply
+    pub include_network_mounts: Option<bool>, // Search network shares below the search path
+    pub include_removable_mounts: Option<bool>, // Search removable drives below the search path
 }
 
 /// Query parameters for content search
@@ -62,6 +102,8 @@ pub struct ContentSearchQuery {
     pub regex: Option<bool>,          // Use regex pattern
     pub file_types: Option<String>,   // Comma-separated file extensions
     pub search_path: Option<String>,  // Custom search path (defaults to user home)
+    pub respect_gitignore: Option<bool>, // Override whether .gitignore rules apply
+    pub respect_skhootignore: Option<bool>, // Override whether .skhootignore rules apply
 }
 
 /// Request body for search suggestions
@@ -83,6 +125,20 @@ pub struct SearchSuggestionResponse {
     pub confidence: f64,
 }
 
+/// A search result the user opened
+#[derive(Debug, Deserialize)]
+pub struct SearchFeedbackRequest {
+    pub path: String,
+    /// Search the result came from, when known
+    pub search_id: Option<String>,
+}
+
+/// Results to read metadata for
+#[derive(Debug, Deserialize)]
+pub struct SearchMetadataRequest {
+    pub paths: Vec<String>,
+}
+
 /// Search configuration response
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SearchConfigResponse {
@@ -93,154 +149,497 @@ pub struct SearchConfigResponse {
     pub cli_tools_available: HashMap<String, bool>,
 }
 
-/// File search endpoint - now uses true hybrid search by default
+/// File search endpoint - now uses true hybrid search by default. Several
+/// roots (`search_paths` or a workspace) are searched concurrently and their
+/// results merged.
 pub async fn search_files(
     Query(params): Query<FileSearchQuery>,
     State(state): State<crate::AppState>,
 ) -> Result<Json<UnifiedSearchResults>, AppError> {
-    use crate::search_engine::CliConfig;
-    
-    // Use custom search path if provided, otherwise default to user's home directory
-    let search_dir = if let Some(ref custom_path) = params.search_path {
-        resolve_path(custom_path)
+    let (query, roots) = parse_file_search(&params)?;
+    let max_results = params
+        .max_results
+        .unwrap_or_else(|| crate::config::SettingsStore::global().get().search.max_results);
+
+    let mut results = if let [root] = roots.as_slice() {
+        search_root(&params, &query, root.clone(), &state).await?
     } else {
-        // Default to user's home directory for broader search
-        dirs::home_dir()
-            .unwrap_or_else(|| std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")))
+        let start_time = std::time::Instant::now();
+        let searches = roots.iter().map(|root| async {
+            let result = search_root(&params, &query, root.clone(), &state)
+                .await
+                .map_err(|e| e.to_string());
+            (root.clone(), result)
+        });
+        let per_root = futures::future::join_all(searches).await;
+        let total_execution_time_ms = start_time.elapsed().as_millis() as u64;
+        merge_root_results(&params.q, per_root, max_results, total_execution_time_ms)
+            .map_err(AppError::Internal)?
     };
-    
-    // Ensure search directory is absolute (canonicalize if possible)
-    let search_dir = search_dir.canonicalize().unwrap_or(search_dir);
+    results.merged_results = with_metadata(results.merged_results, params.include_metadata).await;
+    Ok(Json(results))
+}
 
-    // Parse search mode
-    let mode = match params.mode.as_deref() {
-        Some("rust") => SearchMode::RustEngine,
-        Some("cli") => SearchMode::CliOnly,
-        Some("hybrid") => SearchMode::Hybrid,
-        Some("auto") | _ => SearchMode::Hybrid, // Default to hybrid for best results
-    };
+/// Parse a file search's query string and find the directories it covers.
+/// `path:` filters in the query take the place of the root parameters.
+fn parse_file_search(params: &FileSearchQuery) -> Result<(SearchQuery, Vec<PathBuf>), AppError> {
+    let query = SearchQuery::parse(&params.q)
+        .map_err(|e| AppError::BadRequest(format!("Invalid search query: {}", e)))?;
+    if query.paths.is_empty() {
+        let roots = search_roots(
+            params.search_path.as_deref(),
+            params.search_paths.as_deref(),
+            params.workspace.as_deref(),
+        )?;
+        return Ok((query, roots));
+    }
 
-    // Create search context
-    let context = SearchContext {
-        current_file: None,
-        recent_files: vec![],
-        project_type: detect_project_type(&search_dir).await,
-        search_intent: SearchIntent::FindFile,
-    };
+    let mut roots: Vec<PathBuf> = Vec::new();
+    for path in &query.paths {
+        let root = resolve_path(path);
+        let root = root.canonicalize().unwrap_or(root);
+        if !roots.contains(&root) {
+            roots.push(root);
+        }
+    }
+    Ok((query, roots))
+}
 
-    let start_time = std::time::Instant::now();
-    
-    // For hybrid mode, run both fuzzy and CLI searches in parallel
-    if matches!(mode, SearchMode::Hybrid | SearchMode::Auto) {
-        let mut cli_config = CliConfig::default();
-        // Propagate unrestricted flag to CLI config
-        if params.unrestricted.unwrap_or(false) {
-            cli_config.unrestricted = true;
+/// Directories a file search covers: the given paths, else the named or
+/// default workspace, else the home directory
+pub(crate) fn search_roots(
+    search_path: Option<&str>,
+    search_paths: Option<&str>,
+    workspace: Option<&str>,
+) -> Result<Vec<PathBuf>, AppError> {
+    let mut paths: Vec<String> = search_path.map(str::to_string).into_iter().collect();
+    paths.extend(
+        search_paths
+            .into_iter()
+            .flat_map(|p| p.split(','))
+            .map(|p| p.trim())
+            .filter(|p| !p.is_empty())
+            .map(str::to_string),
+    );
+
+    if paths.is_empty() {
+        let settings = crate::config::SettingsStore::global().get().search;
+        if let Some(name) = workspace.or(settings.default_workspace.as_deref()) {
+            let workspace = settings
+                .workspace(name)
+                .ok_or_else(|| AppError::BadRequest(format!("Unknown search workspace '{}'", name)))?;
+            paths = workspace.roots.clone();
         }
+    }
 
-        let cli_engine = crate::search_engine::CliEngine::new(search_dir.clone());
-        
-        // Parse query for CLI search (split by comma for multiple terms)
-        let keywords: Vec<&str> = params.q.split(',')
-            .map(|s| s.trim())
-            .filter(|s| !s.is_empty())
+    let mut roots: Vec<PathBuf> = Vec::new();
+    for path in paths.iter().filter(|p| !p.trim().is_empty()) {
+        // Canonicalize so the same directory given twice is searched once
+        let root = resolve_path(path);
+        let root = root.canonicalize().unwrap_or(root);
+        if !roots.contains(&root) {
+            roots.push(root);
+        }
+    }
+    if roots.is_empty() {
+        // Default to user's home directory for broader search
+        roots.push(
+            dirs::home_dir()
+                .unwrap_or_else(|| std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."))),
+        );
+    }
+    Ok(roots)
+}
+
+/// A file search of one root directory, split into its engine runs so they
+/// can be reported as each finishes
+struct RootSearch {
+    /// The search string as given, for reporting
+    query: String,
+    /// Words and phrases of the query, without its filters
+    text: String,
+    filter: SearchFilter,
+    search_dir: PathBuf,
+    mode: SearchMode,
+    max_results: usize,
+    context: SearchContext,
+    manager: SearchManager,
+    cli_config: CliConfig,
+    /// Comma-separated query terms, matched as filename globs by the CLI engine
+    keywords: Vec<String>,
+    /// Extensions results are restricted to; empty for any
+    extensions: Vec<String>,
+}
+
+impl RootSearch {
+    async fn new(params: &FileSearchQuery, query: &SearchQuery, search_dir: PathBuf, state: &crate::AppState) -> Self {
+        // Parse search mode, falling back to the configured defaults
+        let defaults = crate::config::SettingsStore::global().get().search;
+        let max_results = params.max_results.unwrap_or(defaults.max_results);
+        let mode = match params.mode.as_deref().unwrap_or(&defaults.default_mode) {
+            "rust" => SearchMode::RustEngine,
+            "cli" => SearchMode::CliOnly,
+            "hybrid" => SearchMode::Hybrid,
+            _ => SearchMode::Hybrid, // Auto uses hybrid for best results
+        };
+
+        // Create search context
+        let context = SearchContext {
+            current_file: None,
+            recent_files: vec![],
+            project_type: detect_project_type(&search_dir).await,
+            search_intent: SearchIntent::FindFile,
+        };
+
+        let manager = state.file_search_manager
+            .with_ignore_overrides(params.respect_gitignore, params.respect_skhootignore)
+            .with_mount_overrides(params.include_network_mounts, params.include_removable_mounts)
+            .with_filter(&query.filter);
+
+        let cli_config = CliConfig {
+            respect_gitignore: manager.config.cli_config.respect_gitignore,
+            respect_skhootignore: manager.config.cli_config.respect_skhootignore,
+            // Propagate unrestricted flag to CLI config
+            unrestricted: params.unrestricted.unwrap_or(false),
+            filter: query.filter.clone(),
+            ..CliConfig::default()
+        };
+
+        // Words split by comma for multiple terms, plus quoted phrases
+        let keywords = query.name_patterns();
+        let mut extensions = params.file_types.as_deref().map(split_list).unwrap_or_default();
+        extensions.extend(query.filter.extensions.iter().cloned());
+
+        Self {
+            query: params.q.clone(),
+            text: query.fuzzy_text(),
+            filter: query.filter.clone(),
+            search_dir,
+            mode,
+            max_results,
+            context,
+            manager,
+            cli_config,
+            keywords,
+            extensions,
+        }
+    }
+
+    /// Whether both engines run (hybrid and auto modes)
+    fn is_hybrid(&self) -> bool {
+        matches!(self.mode, SearchMode::Hybrid | SearchMode::Auto)
+    }
+
+    /// Glob search with the CLI tools (fd, find)
+    async fn cli(&self) -> anyhow::Result<CliSearchResult> {
+        let keywords: Vec<&str> = self.keywords.iter().map(String::as_str).collect();
+        let extensions: Vec<&str> = self.extensions.iter().map(String::as_str).collect();
+        CliEngine::new(self.search_dir.clone())
+            .search_files_with_globs(&keywords, &extensions, &self.search_dir, &self.cli_config)
+            .await
+    }
+
+    /// Fuzzy search with the search manager
+    async fn fuzzy(&self) -> anyhow::Result<UnifiedSearchResults> {
+        self.manager.search(&self.text, &self.search_dir, Some(self.context.clone())).await
+    }
+
+    /// CLI matches as results, ranked with the learned boosts. CLI glob
+    /// matches are exact, so they outrank fuzzy ones.
+    fn cli_hits(&self, cli_res: &CliSearchResult) -> Vec<MergedSearchResult> {
+        let mut hits: Vec<MergedSearchResult> = cli_res.files.iter()
+            .map(|f| MergedSearchResult {
+                path: f.path.clone(),
+                relevance_score: 1.0,
+                source_engine: format!("cli-glob ({})", cli_res.command_used),
+                file_type: std::path::Path::new(&f.path).extension()
+                    .and_then(|e| e.to_str())
+                    .unwrap_or("unknown")
+                    .to_string(),
+                size: None,
+                modified: None,
+                snippet: f.content.clone(),
+                line_number: f.line_number,
+                metadata: None,
+                root: None,
+            })
             .collect();
-        
-        // Parse file types if provided
-        let extensions: Vec<&str> = params.file_types
-            .as_ref()
-            .map(|ft| ft.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()).collect())
-            .unwrap_or_default();
-        
-        // Run both searches in parallel - always use glob search for CLI (more powerful)
-        let cli_future = cli_engine.search_files_with_globs(&keywords, &extensions, &search_dir, &cli_config);
-        let fuzzy_future = state.file_search_manager.search(&params.q, &search_dir, Some(context));
-        
-        let (cli_result, fuzzy_result) = tokio::join!(cli_future, fuzzy_future);
-        
-        let total_execution_time_ms = start_time.elapsed().as_millis() as u64;
-        
-        // Merge results from both searches
-        let mut merged_results: Vec<MergedSearchResult> = Vec::new();
+        self.manager.rank(&mut hits);
+        hits
+    }
+
+    /// Fuzzy matches with the requested extensions, scored slightly below
+    /// CLI matches
+    fn fuzzy_hits(&self, fuzzy_res: &UnifiedSearchResults) -> Vec<MergedSearchResult> {
+        fuzzy_res.merged_results.iter()
+            .filter(|r| {
+                self.extensions.is_empty()
+                    || self.extensions.iter().any(|e| e.eq_ignore_ascii_case(&r.file_type))
+            })
+            .map(|r| MergedSearchResult {
+                relevance_score: r.relevance_score * 0.9,
+                source_engine: format!("fuzzy ({})", r.source_engine),
+                root: None,
+                ..r.clone()
+            })
+            .collect()
+    }
+
+    /// Final results of a hybrid search: both engines' hits without
+    /// duplicates, with symbol matches, ranked and truncated
+    async fn merge(
+        &self,
+        cli_result: anyhow::Result<CliSearchResult>,
+        fuzzy_result: anyhow::Result<UnifiedSearchResults>,
+        state: &crate::AppState,
+        total_execution_time_ms: u64,
+    ) -> UnifiedSearchResults {
         let mut seen_paths: std::collections::HashSet<String> = std::collections::HashSet::new();
-        
-        // Add CLI results first (they're exact pattern matches, higher priority for document searches)
-        if let Ok(cli_res) = &cli_result {
-            for f in &cli_res.files {
-                if seen_paths.insert(f.path.clone()) {
-                    let path = std::path::Path::new(&f.path);
-                    merged_results.push(MergedSearchResult {
-                        path: f.path.clone(),
-                        relevance_score: 1.0, // CLI glob matches are exact
-                        source_engine: format!("cli-glob ({})", cli_res.command_used),
-                        file_type: path.extension()
-                            .and_then(|e| e.to_str())
-                            .unwrap_or("unknown")
-                            .to_string(),
-                        size: None,
-                        modified: None,
-                        snippet: f.content.clone(),
-                        line_number: f.line_number,
-                    });
-                }
-            }
-        }
-        
-        // Add fuzzy results (filter by extension if specified)
-        if let Ok(fuzzy_res) = &fuzzy_result {
-            for r in &fuzzy_res.merged_results {
-                // Skip if already found by CLI search
-                if seen_paths.contains(&r.path) {
-                    continue;
-                }
-                
-                // Filter by extension if specified
-                if !extensions.is_empty() {
-                    let file_ext = r.file_type.to_lowercase();
-                    if !extensions.iter().any(|e| e.to_lowercase() == file_ext) {
-                        continue;
-                    }
-                }
-                
-                if seen_paths.insert(r.path.clone()) {
-                    merged_results.push(MergedSearchResult {
-                        path: r.path.clone(),
-                        relevance_score: r.relevance_score * 0.9, // Slightly lower than CLI
-                        source_engine: format!("fuzzy ({})", r.source_engine),
-                        file_type: r.file_type.clone(),
-                        size: r.size,
-                        modified: r.modified,
-                        snippet: r.snippet.clone(),
-                        line_number: r.line_number,
-                    });
-                }
+        let mut merged_results: Vec<MergedSearchResult> = Vec::new();
+        // CLI results come first, so a path both engines found keeps its CLI score
+        let hits = cli_result.as_ref().map(|r| self.cli_hits(r)).unwrap_or_default();
+        let fuzzy_hits = fuzzy_result.as_ref().map(|r| self.fuzzy_hits(r)).unwrap_or_default();
+        for hit in hits.into_iter().chain(fuzzy_hits) {
+            if seen_paths.insert(hit.path.clone()) {
+                merged_results.push(hit);
             }
         }
-        
+
+        // Files defining a symbol the query names come before plain matches
+        self.merge_symbols(&mut merged_results, state).await;
+
         // Sort by relevance score
         merged_results.sort_by(|a, b| b.relevance_score.partial_cmp(&a.relevance_score).unwrap_or(std::cmp::Ordering::Equal));
-        
-        let unified = UnifiedSearchResults {
-            search_id: uuid::Uuid::new_v4().to_string(),
-            query: params.q.clone(),
+        merged_results.truncate(self.max_results);
+
+        // Reuse the fuzzy search's ID so feedback finds its history entry
+        let search_id = fuzzy_result.as_ref()
+            .map(|r| r.search_id.clone())
+            .unwrap_or_else(|_| uuid::Uuid::new_v4().to_string());
+        UnifiedSearchResults {
+            search_id,
+            query: self.query.clone(),
             mode: SearchMode::Hybrid,
             file_results: fuzzy_result.ok().and_then(|r| r.file_results),
             cli_results: cli_result.ok(),
             merged_results,
             total_execution_time_ms,
             suggestions: vec![],
+            roots: Vec::new(),
+        }
+    }
+
+    /// Single mode search (rust-only or cli-only)
+    async fn single(&self, state: &crate::AppState) -> Result<UnifiedSearchResults, AppError> {
+        let mut results = self.manager
+            .search(&self.text, &self.search_dir, Some(self.context.clone()))
+            .await
+            .map_err(|e| AppError::Internal(format!("Search failed: {}", e)))?;
+        self.merge_symbols(&mut results.merged_results, state).await;
+        results.merged_results.truncate(self.max_results);
+        Ok(results)
+    }
+
+    /// Add files defining a symbol the query names; the index doesn't know
+    /// the query's filters, so they are checked here
+    async fn merge_symbols(&self, results: &mut Vec<MergedSearchResult>, state: &crate::AppState) {
+        let mut symbols = CodeIndex::new(state.db.clone()).search_results(&self.text, &self.search_dir, self.max_results).await;
+        if !self.filter.is_empty() {
+            symbols.retain(|s| self.filter.matches_file(std::path::Path::new(&s.path)));
+        }
+        merge_symbol_results(symbols, results);
+    }
+}
+
+/// Non-empty, trimmed items of a comma-separated list
+fn split_list(list: &str) -> Vec<String> {
+    list.split(',')
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Search one root directory, up to `max_results` results without metadata
+async fn search_root(
+    params: &FileSearchQuery,
+    query: &SearchQuery,
+    search_dir: PathBuf,
+    state: &crate::AppState,
+) -> Result<UnifiedSearchResults, AppError> {
+    let search = RootSearch::new(params, query, search_dir, state).await;
+    if !search.is_hybrid() {
+        return search.single(state).await;
+    }
+
+    // For hybrid mode, run both fuzzy and CLI searches in parallel
+    let start_time = std::time::Instant::now();
+    let (cli_result, fuzzy_result) = tokio::join!(search.cli(), search.fuzzy());
+    let total_execution_time_ms = start_time.elapsed().as_millis() as u64;
+    Ok(search.merge(cli_result, fuzzy_result, state, total_execution_time_ms).await)
+}
+
+/// Results one engine found under a search root, sent as soon as the engine
+/// finishes
+#[derive(Debug, Serialize)]
+pub struct SearchResultBatch {
+    pub root: String,
+    /// `cli` or `fuzzy` for hybrid searches, else the search mode
+    pub engine: String,
+    /// Results not sent in an earlier batch, best first
+    pub results: Vec<MergedSearchResult>,
+    /// Time since the search started
+    pub elapsed_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Sends result batches on a streamed search, each path at most once
+struct BatchSender {
+    tx: mpsc::Sender<SseEvent>,
+    sent: std::sync::Mutex<std::collections::HashSet<String>>,
+    start_time: std::time::Instant,
+}
+
+impl BatchSender {
+    fn new(tx: mpsc::Sender<SseEvent>) -> Self {
+        Self {
+            tx,
+            sent: Default::default(),
+            start_time: std::time::Instant::now(),
+        }
+    }
+
+    /// Batch of the results that haven't been sent yet
+    fn batch(
+        &self,
+        root: &std::path::Path,
+        engine: &str,
+        results: Result<Vec<MergedSearchResult>, String>,
+        max_results: usize,
+    ) -> SearchResultBatch {
+        let (mut results, error) = match results {
+            Ok(results) => (results, None),
+            Err(error) => (Vec::new(), Some(error)),
         };
-        
-        return Ok(Json(unified));
+        let mut sent = self.sent.lock().unwrap_or_else(|e| e.into_inner());
+        results.retain(|r| sent.insert(r.path.clone()));
+        results.truncate(max_results);
+        SearchResultBatch {
+            root: root.to_string_lossy().to_string(),
+            engine: engine.to_string(),
+            results,
+            elapsed_ms: self.start_time.elapsed().as_millis() as u64,
+            error,
+        }
     }
 
-    // Single mode search (rust-only or cli-only)
-    let results = state.file_search_manager
-        .search(&params.q, &search_dir, Some(context))
-        .await
-        .map_err(|e| AppError::Internal(format!("Search failed: {}", e)))?;
+    async fn send(&self, search: &RootSearch, engine: &str, results: Result<Vec<MergedSearchResult>, String>) {
+        let batch = self.batch(&search.search_dir, engine, results, search.max_results);
+        // A closed channel means the client went away; the search still finishes
+        let _ = self.tx.send(sse_message("results", &batch)).await;
+    }
+}
 
-    Ok(Json(results))
+/// Named server-sent event carrying `data` as JSON
+fn sse_message(name: &str, data: &impl Serialize) -> SseEvent {
+    SseEvent::default()
+        .event(name)
+        .json_data(data)
+        .unwrap_or_else(|_| SseEvent::default().comment("unserializable message"))
+}
+
+/// File search that streams results as each engine produces them
+///
+/// Takes the same parameters as `/search/files`. A `results` message carrying
+/// a [`SearchResultBatch`] is sent whenever an engine finishes for a root,
+/// holding only paths not sent before. Once every engine is done, a
+/// `complete` message carries the final deduplicated and ranked results, in
+/// the same shape `/search/files` returns; clients replace the provisional
+/// list with it. A search that fails everywhere ends with an `error` message
+/// instead.
+pub async fn stream_search_files(
+    Query(params): Query<FileSearchQuery>,
+    State(state): State<crate::AppState>,
+) -> Result<Sse<impl Stream<Item = Result<SseEvent, Infallible>>>, AppError> {
+    let (query, roots) = parse_file_search(&params)?;
+    let (tx, rx) = mpsc::channel(16);
+    tokio::spawn(run_streamed_search(params, query, roots, state, tx));
+    Ok(Sse::new(ReceiverStream::new(rx).map(Ok)).keep_alive(KeepAlive::default()))
+}
+
+/// Search every root, sending batches and then the final results on `tx`
+async fn run_streamed_search(
+    params: FileSearchQuery,
+    query: SearchQuery,
+    roots: Vec<PathBuf>,
+    state: crate::AppState,
+    tx: mpsc::Sender<SseEvent>,
+) {
+    let batches = BatchSender::new(tx.clone());
+    let max_results = params
+        .max_results
+        .unwrap_or_else(|| crate::config::SettingsStore::global().get().search.max_results);
+
+    let searches = roots.iter().map(|root| async {
+        let result = stream_root(&params, &query, root.clone(), &state, &batches)
+            .await
+            .map_err(|e| e.to_string());
+        (root.clone(), result)
+    });
+    let mut per_root = futures::future::join_all(searches).await;
+    let total_execution_time_ms = batches.start_time.elapsed().as_millis() as u64;
+    let result = match per_root.pop() {
+        Some((_, result)) if per_root.is_empty() => result,
+        last => {
+            per_root.extend(last);
+            merge_root_results(&params.q, per_root, max_results, total_execution_time_ms)
+        }
+    };
+
+    let message = match result {
+        Ok(mut results) => {
+            results.merged_results = with_metadata(results.merged_results, params.include_metadata).await;
+            sse_message("complete", &results)
+        }
+        Err(error) => sse_message("error", &serde_json::json!({ "error": error })),
+    };
+    let _ = tx.send(message).await;
+}
+
+/// Search one root like [`search_root`], sending each engine's results as
+/// soon as it finishes
+async fn stream_root(
+    params: &FileSearchQuery,
+    query: &SearchQuery,
+    search_dir: PathBuf,
+    state: &crate::AppState,
+    batches: &BatchSender,
+) -> Result<UnifiedSearchResults, AppError> {
+    let search = RootSearch::new(params, query, search_dir, state).await;
+    if !search.is_hybrid() {
+        let results = search.single(state).await;
+        let hits = results.as_ref().map(|r| r.merged_results.clone()).map_err(|e| e.to_string());
+        batches.send(&search, search.mode.metrics_label(), hits).await;
+        return results;
+    }
+
+    let start_time = std::time::Instant::now();
+    let cli = async {
+        let result = search.cli().await;
+        let hits = result.as_ref().map(|r| search.cli_hits(r)).map_err(|e| e.to_string());
+        batches.send(&search, "cli", hits).await;
+        result
+    };
+    let fuzzy = async {
+        let result = search.fuzzy().await;
+        let hits = result.as_ref().map(|r| search.fuzzy_hits(r)).map_err(|e| e.to_string());
+        batches.send(&search, "fuzzy", hits).await;
+        result
+    };
+    let (cli_result, fuzzy_result) = tokio::join!(cli, fuzzy);
+    let total_execution_time_ms = start_time.elapsed().as_millis() as u64;
+    Ok(search.merge(cli_result, fuzzy_result, state, total_execution_time_ms).await)
 }
 
 /// Query parameters for document search (like Codex CLI)
@@ -249,6 +648,9 @@ pub struct DocumentSearchQuery {
     pub keywords: String,             // Comma-separated keywords to search for in filenames
     pub extensions: String,           // Comma-separated file extensions (pdf,pptx,doc)
     pub search_path: Option<String>,  // Custom search path (defaults to user home)
+    pub include_metadata: Option<bool>, // Read image/media/document metadata for results
+    pub respect_gitignore: Option<bool>, // Override whether .gitignore rules apply
+    pub respect_skhootignore: Option<bool>, // Override whether .skhootignore rules apply
 }
 
 /// Document search endpoint - uses CLI tools like Codex CLI
@@ -280,7 +682,13 @@ pub async fn search_documents(
         .filter(|s| !s.is_empty())
         .collect();
 
-    let cli_config = CliConfig::default();
+    let manager = state.file_search_manager
+        .with_ignore_overrides(params.respect_gitignore, params.respect_skhootignore);
+    let cli_config = CliConfig {
+        respect_gitignore: manager.config.cli_config.respect_gitignore,
+        respect_skhootignore: manager.config.cli_config.respect_skhootignore,
+        ..CliConfig::default()
+    };
     let cli_engine = crate::search_engine::CliEngine::new(search_dir.clone());
     
     // Run HYBRID search: both CLI glob search AND fuzzy search in parallel
@@ -297,7 +705,7 @@ pub async fn search_documents(
         project_type: detect_project_type(&search_dir).await,
         search_intent: SearchIntent::FindFile,
     };
-    let fuzzy_future = state.file_search_manager.search(&fuzzy_query, &search_dir, Some(context));
+    let fuzzy_future = manager.search(&fuzzy_query, &search_dir, Some(context));
     
     // Run both searches in parallel
     let (cli_result, fuzzy_result) = tokio::join!(cli_future, fuzzy_future);
@@ -325,6 +733,8 @@ pub async fn search_documents(
                     modified: None,
                     snippet: f.content.clone(),
                     line_number: f.line_number,
+                    metadata: None,
+                    root: None,
                 });
             }
         }
@@ -356,6 +766,8 @@ pub async fn search_documents(
                     modified: r.modified,
                     snippet: r.snippet.clone(),
                     line_number: r.line_number,
+                    metadata: r.metadata.clone(),
+                    root: None,
                 });
             }
         }
@@ -363,6 +775,7 @@ pub async fn search_documents(
     
     // Sort by relevance score
     merged_results.sort_by(|a, b| b.relevance_score.partial_cmp(&a.relevance_score).unwrap_or(std::cmp::Ordering::Equal));
+    let merged_results = with_metadata(merged_results, params.include_metadata).await;
     
     // Build suggestions
     let mut suggestions = Vec::new();
@@ -385,6 +798,7 @@ pub async fn search_documents(
         merged_results,
         total_execution_time_ms,
         suggestions,
+        roots: Vec::new(),
     };
 
     Ok(Json(unified))
@@ -414,6 +828,7 @@ pub async fn search_content(
     };
 
     let results = state.file_search_manager
+        .with_ignore_overrides(params.respect_gitignore, params.respect_skhootignore)
         .search_content(&params.q, &search_dir, Some(context))
         .await
         .map_err(|e| AppError::Internal(format!("Content search failed: {}", e)))?;
@@ -456,6 +871,102 @@ pub async fn get_search_history(
     Ok(Json(history))
 }
 
+/// Record that the user opened a search result, so later searches rank
+/// results from the same folders and file types higher
+pub async fn record_search_feedback(
+    State(state): State<crate::AppState>,
+    Json(request): Json<SearchFeedbackRequest>,
+) -> Result<StatusCode, AppError> {
+    state.file_search_manager
+        .record_selection(request.search_id.as_deref(), &request.path)
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to record search feedback: {}", e)))?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Read image, media and document metadata for results the frontend is
+/// showing. Paths without metadata map to null.
+pub async fn get_search_metadata(
+    Json(request): Json<SearchMetadataRequest>,
+) -> Result<Json<HashMap<String, Option<FileMetadata>>>, AppError> {
+    let metadata = tokio::task::spawn_blocking(move || {
+        let extractor = MetadataExtractor::global();
+        request.paths
+            .into_iter()
+            .map(|path| {
+                let metadata = extractor.extract(&resolve_path(&path));
+                (path, metadata)
+            })
+            .collect()
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Metadata extraction failed: {}", e)))?;
+    Ok(Json(metadata))
+}
+
+/// Add file metadata to results when the caller asked for it
+async fn with_metadata(mut results: Vec<MergedSearchResult>, include: Option<bool>) -> Vec<MergedSearchResult> {
+    if !include.unwrap_or(false) {
+        return results;
+    }
+    tokio::task::spawn_blocking(move || {
+        MetadataExtractor::global().enrich(&mut results);
+        results
+    })
+    .await
+    .unwrap_or_default()
+}
+
+/// Inspect the ranking weights learned from opened results
+pub async fn get_search_ranking() -> Result<Json<RankingReport>, AppError> {
+    Ok(Json(SearchRanking::global().report()))
+}
+
+/// Forget all learned ranking weights
+pub async fn reset_search_ranking() -> Result<StatusCode, AppError> {
+    SearchRanking::global()
+        .reset()
+        .map_err(|e| AppError::Internal(format!("Failed to reset search ranking: {}", e)))?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Roots and size of the filename index
+pub async fn get_filename_index_status(
+    State(state): State<crate::AppState>,
+) -> Result<Json<FilenameIndexStatus>, AppError> {
+    let index = state
+        .file_search_manager
+        .filename_index()
+        .ok_or_else(|| AppError::NotFound("Filename index is not running".to_string()))?;
+    Ok(Json(index.status()))
+}
+
+/// Walk every indexed root again in the background
+pub async fn reconcile_filename_index(
+    State(state): State<crate::AppState>,
+) -> Result<(StatusCode, Json<FilenameIndexStatus>), AppError> {
+    let index = state
+        .file_search_manager
+        .filename_index()
+        .cloned()
+        .ok_or_else(|| AppError::NotFound("Filename index is not running".to_string()))?;
+    let roots = filename_index::indexed_roots();
+    if roots.is_empty() {
+        return Err(AppError::BadRequest("The filename index is disabled in the search settings".to_string()));
+    }
+    {
+        let index = index.clone();
+        tokio::spawn(async move {
+            for root in roots {
+                if let Err(e) = index.reconcile(&root).await {
+                    tracing::warn!("Cannot index filenames under {}: {}", root.display(), e);
+                }
+            }
+        });
+    }
+    Ok((StatusCode::ACCEPTED, Json(index.status())))
+}
+
 /// Get active searches
 pub async fn get_active_searches(
     State(state): State<crate::AppState>,
@@ -881,46 +1392,219 @@ pub async fn open_with_dialog(
     }
 }
 
-/// Read file content endpoint
+/// Query parameters for reading a file; at most one of the line range,
+/// `head`, `tail` or the byte range may be given
+#[derive(Debug, Deserialize)]
+pub struct FileReadQuery {
+    pub path: String,
+    pub start_line: Option<usize>,
+    pub end_line: Option<usize>,
+    pub head: Option<usize>,
+    pub tail: Option<usize>,
+    /// Byte range
+    pub offset: Option<u64>,
+    pub limit: Option<u64>,
+    /// Most bytes of content returned (default and cap 8 MiB)
+    pub max_bytes: Option<usize>,
+}
+
+/// Most bytes `/files/read` returns
+const MAX_READ_BYTES: usize = 8 * 1024 * 1024;
+
+/// Read file content endpoint. Only the requested part is held in memory,
+/// up to `max_bytes`; `truncated` says whether it was cut there.
 pub async fn read_file_content(
-    Query(params): Query<HashMap<String, String>>,
+    Query(params): Query<FileReadQuery>,
 ) -> Result<Json<serde_json::Value>, AppError> {
-    let path_str = params.get("path")
-        .ok_or_else(|| AppError::BadRequest("Missing 'path' parameter".to_string()))?;
-    
-    let absolute_path = resolve_path(path_str);
-    
-    tracing::info!("Reading file content: {:?}", absolute_path);
-    
-    // Check if file exists
-    if !absolute_path.exists() {
-        return Err(AppError::NotFound(format!("File not found: {}", absolute_path.display())));
-    }
-    
-    // Check if it's a file (not a directory)
-    if !absolute_path.is_file() {
-        return Err(AppError::BadRequest(format!("Path is not a file: {}", absolute_path.display())));
-    }
-    
-    // Read file content
-    match tokio::fs::read_to_string(&absolute_path).await {
-        Ok(content) => {
-            Ok(Json(serde_json::json!({
-                "success": true,
-                "path": absolute_path.display().to_string(),
-                "content": content,
-                "size": content.len()
-            })))
-        }
-        Err(e) => {
-            tracing::error!("Failed to read file {:?}: {}", absolute_path, e);
-            Err(AppError::Internal(format!("Failed to read file: {}", e)))
-        }
+    let absolute_path = resolve_path(&params.path);
+    let range = ReadRange::from_params(
+        params.start_line,
+        params.end_line,
+        params.head,
+        params.tail,
+        params.offset,
+        params.limit,
+        ReadRange::Bytes { offset: 0, limit: None },
+    )
+    .map_err(|e| AppError::BadRequest(e.to_string()))?;
+    let max_bytes = params.max_bytes.unwrap_or(MAX_READ_BYTES).clamp(1, MAX_READ_BYTES);
+
+    tracing::info!("Reading file content: {:?} ({:?})", absolute_path, range);
+
+    let path = absolute_path.clone();
+    let read = tokio::task::spawn_blocking(move || file_range::read_range(&path, range, max_bytes))
+        .await
+        .map_err(|e| AppError::Internal(format!("Read task failed: {}", e)))?
+        .map_err(|e| match e {
+            RangeError::NotFound(_) => AppError::NotFound(e.to_string()),
+            RangeError::NotAFile(_) | RangeError::Binary(_) | RangeError::Invalid(_) => {
+                AppError::BadRequest(e.to_string())
+            }
+            RangeError::Io { .. } => {
+                tracing::error!("Failed to read file {:?}: {}", absolute_path, e);
+                AppError::Internal(e.to_string())
+            }
+        })?;
+
+    let mut body = serde_json::to_value(&read).map_err(|e| AppError::Internal(e.to_string()))?;
+    body["success"] = serde_json::json!(true);
+    body["path"] = serde_json::json!(absolute_path.display().to_string());
+    body["size"] = serde_json::json!(read.content.len());
+    Ok(Json(body))
+}
+
+/// Query parameters for following a file
+#[derive(Debug, Deserialize)]
+pub struct FileTailQuery {
+    pub path: String,
+    /// Regular expression; only matching lines are sent
+    pub pattern: Option<String>,
+    /// Stop at the first matching line (default true when a pattern is given)
+    pub until_match: Option<bool>,
+    /// Lines already at the end of the file to send first
+    pub lines: Option<usize>,
+    /// How long to follow the file (default 300, cap 3600)
+    pub max_duration_secs: Option<u64>,
+    /// Stop after sending this many lines (default 10000)
+    pub max_lines: Option<usize>,
+}
+
+const MAX_TAIL_SECS: u64 = 3600;
+
+/// Follow a file as it grows, like `tail -f`
+///
+/// Each line appended to the file, or only those matching `pattern`, is sent
+/// as a `line` message carrying a [`file_follow::FollowedLine`]. Following
+/// ends with an `end` message carrying a [`file_follow::FollowSummary`], or
+/// an `error` message if the file can no longer be read. Closing the stream
+/// stops following.
+pub async fn tail_file(
+    Query(params): Query<FileTailQuery>,
+) -> Result<Sse<impl Stream<Item = Result<SseEvent, Infallible>>>, AppError> {
+    let absolute_path = resolve_path(&params.path);
+    let metadata = tokio::fs::metadata(&absolute_path)
+        .await
+        .map_err(|_| AppError::NotFound(format!("File not found: {}", absolute_path.display())))?;
+    if !metadata.is_file() {
+        return Err(AppError::BadRequest(format!("Not a file: {}", absolute_path.display())));
     }
+    let pattern = match params.pattern.as_deref().filter(|p| !p.is_empty()) {
+        Some(pattern) => Some(
+            regex::Regex::new(pattern).map_err(|e| AppError::BadRequest(format!("Invalid pattern: {}", e)))?,
+        ),
+        None => None,
+    };
+    let options = FollowOptions {
+        until_match: params.until_match.unwrap_or(pattern.is_some()),
+        pattern,
+        initial_lines: params.lines.unwrap_or(0),
+        max_duration: std::time::Duration::from_secs(
+            params.max_duration_secs.unwrap_or(300).clamp(1, MAX_TAIL_SECS),
+        ),
+        max_lines: params.max_lines.unwrap_or(10_000).max(1),
+    };
+
+    tracing::info!("Following file: {:?}", absolute_path);
+
+    let (tx, rx) = mpsc::channel(64);
+    tokio::spawn(async move {
+        let (lines_tx, mut lines_rx) = mpsc::channel(64);
+        let forward = async {
+            while let Some(line) = lines_rx.recv().await {
+                if tx.send(sse_message("line", &line)).await.is_err() {
+                    // Dropping the receiver cancels the follow
+                    break;
+                }
+            }
+        };
+        let (result, ()) = tokio::join!(file_follow::follow(&absolute_path, &options, lines_tx), forward);
+        let message = match result {
+            Ok(summary) => sse_message("end", &summary),
+            Err(e) => sse_message("error", &serde_json::json!({ "error": e.to_string() })),
+        };
+        let _ = tx.send(message).await;
+    });
+    Ok(Sse::new(ReceiverStream::new(rx).map(Ok)).keep_alive(KeepAlive::default()))
+}
+
+/// Query parameters for a file preview
+#[derive(Debug, Deserialize)]
+pub struct FilePreviewQuery {
+    pub path: String,
+    /// 1-based line to centre on, e.g. a content search result's line_number
+    pub line: Option<usize>,
+    /// Lines on each side of `line`
+    pub context: Option<usize>,
+    /// Include syntax highlighting tokens (default true)
+    pub highlight: Option<bool>,
+    /// Text to mark in the returned lines
+    pub query: Option<String>,
+}
+
+/// Preview the lines around a match without downloading the whole file
+pub async fn preview_file(
+    Query(params): Query<FilePreviewQuery>,
+) -> Result<Json<FilePreview>, AppError> {
+    let absolute_path = resolve_path(&params.path);
+    let options = PreviewOptions {
+        line: params.line,
+        context: params.context.unwrap_or(file_preview::DEFAULT_CONTEXT_LINES),
+        highlight: params.highlight.unwrap_or(true),
+        query: params.query,
+    };
+
+    let result = tokio::task::spawn_blocking(move || file_preview::preview(&absolute_path, &options))
+        .await
+        .map_err(|e| AppError::Internal(format!("Preview task failed: {}", e)))?;
+    result.map(Json).map_err(|e| match e {
+        PreviewError::NotFound(_) => AppError::NotFound(e.to_string()),
+        PreviewError::NotAFile(_) | PreviewError::Binary(_) => AppError::BadRequest(e.to_string()),
+        PreviewError::Io { .. } => AppError::Internal(e.to_string()),
+    })
+}
+
+/// Request body for a file diff
+#[derive(Debug, Deserialize)]
+pub struct FileDiffRequest {
+    pub path: String,
+    /// File to compare with
+    pub other_path: Option<String>,
+    /// Content to compare with, e.g. what a write would put in the file
+    pub content: Option<String>,
+    /// Unchanged lines around each change
+    pub context: Option<usize>,
+    /// Also split changed lines into changed and unchanged words
+    #[serde(default)]
+    pub word_diff: bool,
+}
+
+/// Unified diff between two files, or a file and proposed content
+pub async fn diff_files(
+    Json(request): Json<FileDiffRequest>,
+) -> Result<Json<FileDiff>, AppError> {
+    let path = resolve_path(&request.path);
+    let other = request.other_path.as_deref().map(resolve_path);
+    let options = DiffOptions {
+        context: request.context.unwrap_or(file_diff::DEFAULT_CONTEXT_LINES),
+        word_diff: request.word_diff,
+    };
+
+    let result = tokio::task::spawn_blocking(move || match (&other, &request.content) {
+        (Some(other), None) => file_diff::diff(&path, DiffTarget::File(other), &options),
+        (None, Some(content)) => file_diff::diff(&path, DiffTarget::Content(content), &options),
+        _ => Err(DiffError::InvalidTarget),
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Diff task failed: {}", e)))?;
+    result.map(Json).map_err(|e| match e {
+        DiffError::NotFound(_) => AppError::NotFound(e.to_string()),
+        DiffError::NotAFile(_) | DiffError::TooLarge(_) | DiffError::InvalidTarget => AppError::BadRequest(e.to_string()),
+        DiffError::Io { .. } => AppError::Internal(e.to_string()),
+    })
 }
 
 /// Helper function to resolve paths with tilde expansion
-fn resolve_path(path_str: &str) -> PathBuf {
+pub(crate) fn resolve_path(path_str: &str) -> PathBuf {
     let path = PathBuf::from(path_str);
     if path.is_absolute() {
         return path;
@@ -1001,6 +1685,43 @@ pub async fn list_directory_content(
     })))
 }
 
+/// Query parameters for one level of the file explorer tree
+#[derive(Debug, Deserialize)]
+pub struct DirectoryTreeQuery {
+    pub path: String,
+    pub offset: Option<usize>,
+    pub limit: Option<usize>,
+    pub include_hidden: Option<bool>,
+}
+
+/// Directory tree endpoint: one page of a single directory level, so the
+/// explorer can expand folders lazily
+pub async fn get_directory_tree(
+    Query(params): Query<DirectoryTreeQuery>,
+) -> Result<Json<DirectoryPage>, AppError> {
+    let absolute_path = resolve_path(&params.path);
+
+    if !absolute_path.exists() {
+        return Err(AppError::NotFound(format!("Directory not found: {}", absolute_path.display())));
+    }
+    if !absolute_path.is_dir() {
+        return Err(AppError::BadRequest(format!("Path is not a directory: {}", absolute_path.display())));
+    }
+
+    let options = ListOptions {
+        offset: params.offset.unwrap_or(0),
+        limit: params.limit.unwrap_or(file_tree::DEFAULT_PAGE_SIZE),
+        include_hidden: params.include_hidden.unwrap_or(false),
+    };
+
+    let page = tokio::task::spawn_blocking(move || file_tree::list_level(&absolute_path, options))
+        .await
+        .map_err(|e| AppError::Internal(format!("Directory task failed: {}", e)))?
+        .map_err(|e| AppError::Internal(format!("Failed to read directory: {}", e)))?;
+
+    Ok(Json(page))
+}
+
 /// Request body for writing file content
 #[derive(Debug, Deserialize)]
 pub struct WriteFileRequest {
@@ -1046,12 +1767,150 @@ pub async fn write_file_content(
     })))
 }
 
+/// Request body for deleting a file
+#[derive(Debug, Deserialize)]
+pub struct DeleteFileRequest {
+    pub path: String,
+    /// Remove the file for good instead of moving it to the trash
+    pub permanent: Option<bool>,
+    /// Set once the user has confirmed a permanent deletion
+    pub approved: Option<bool>,
+}
+
+/// Delete file endpoint
+///
+/// Moves the file to the OS trash and returns its trash ID so the UI can
+/// offer undo through `/files/restore`.
+pub async fn delete_file(
+    Json(request): Json<DeleteFileRequest>,
+) -> Result<Json<DeleteOutcome>, AppError> {
+    let permanent = request.permanent.unwrap_or(false);
+    if permanent && !request.approved.unwrap_or(false) {
+        return Err(AppError::BadRequest(
+            "Permanent deletion requires user approval".to_string(),
+        ));
+    }
+
+    let absolute_path = resolve_path(&request.path);
+    tracing::info!("Deleting file: {:?} (permanent: {})", absolute_path, permanent);
+
+    let outcome = tokio::task::spawn_blocking(move || recycle_bin::delete(&absolute_path, permanent))
+        .await
+        .map_err(|e| AppError::Internal(format!("Delete task failed: {}", e)))?
+        .map_err(delete_error)?;
+
+    FileHistory::global().record_delete(OperationOrigin::User, &outcome);
+    Ok(Json(outcome))
+}
+
+/// Request body for restoring a trashed file
+#[derive(Debug, Deserialize)]
+pub struct RestoreFileRequest {
+    pub trash_id: String,
+}
+
+/// Restore file endpoint, undoing a `/files/delete`
+pub async fn restore_file(
+    Json(request): Json<RestoreFileRequest>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    let restored = tokio::task::spawn_blocking(move || recycle_bin::restore(&request.trash_id))
+        .await
+        .map_err(|e| AppError::Internal(format!("Restore task failed: {}", e)))?
+        .map_err(delete_error)?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "path": restored.display().to_string()
+    })))
+}
+
+fn delete_error(e: DeleteError) -> AppError {
+    match e {
+        DeleteError::NotFound(_) | DeleteError::NotInTrash(_) => AppError::NotFound(e.to_string()),
+        DeleteError::Protected(_) | DeleteError::RestoreCollision(_) | DeleteError::RestoreUnsupported => {
+            AppError::BadRequest(e.to_string())
+        }
+        DeleteError::Trash(_) | DeleteError::Io { .. } => AppError::Internal(e.to_string()),
+    }
+}
+
+/// Request body for moving or copying a file
+#[derive(Debug, Deserialize)]
+pub struct TransferFileRequest {
+    pub source: String,
+    pub destination: String,
+    /// What to do if the destination exists; aborts by default
+    #[serde(default)]
+    pub on_conflict: Collision,
+}
+
+/// Move file endpoint
+pub async fn move_file(
+    Json(request): Json<TransferFileRequest>,
+) -> Result<Json<TransferOutcome>, AppError> {
+    let source = resolve_path(&request.source);
+    let destination = resolve_path(&request.destination);
+    run_transfer(move || file_transfer::move_path(&source, &destination, request.on_conflict)).await
+}
+
+/// Copy file endpoint
+pub async fn copy_file(
+    Json(request): Json<TransferFileRequest>,
+) -> Result<Json<TransferOutcome>, AppError> {
+    let source = resolve_path(&request.source);
+    let destination = resolve_path(&request.destination);
+    run_transfer(move || file_transfer::copy_path(&source, &destination, request.on_conflict)).await
+}
+
+/// Request body for renaming a file
+#[derive(Debug, Deserialize)]
+pub struct RenameFileRequest {
+    pub path: String,
+    /// New file name, without a directory
+    pub new_name: String,
+    #[serde(default)]
+    pub on_conflict: Collision,
+}
+
+/// Rename file endpoint
+pub async fn rename_file(
+    Json(request): Json<RenameFileRequest>,
+) -> Result<Json<TransferOutcome>, AppError> {
+    let path = resolve_path(&request.path);
+    run_transfer(move || file_transfer::rename_path(&path, &request.new_name, request.on_conflict)).await
+}
+
+/// Run a blocking transfer and record it in the file history
+async fn run_transfer<F>(transfer: F) -> Result<Json<TransferOutcome>, AppError>
+where
+    F: FnOnce() -> Result<TransferOutcome, TransferError> + Send + 'static,
+{
+    let outcome = tokio::task::spawn_blocking(transfer)
+        .await
+        .map_err(|e| AppError::Internal(format!("File task failed: {}", e)))?
+        .map_err(|e| match e {
+            TransferError::NotFound(_) => AppError::NotFound(e.to_string()),
+            TransferError::Io { .. } => AppError::Internal(e.to_string()),
+            _ => AppError::BadRequest(e.to_string()),
+        })?;
+
+    FileHistory::global().record_transfer(OperationOrigin::User, &outcome);
+    Ok(Json(outcome))
+}
+
 /// Request body for shell execution
 #[derive(Debug, Deserialize)]
 pub struct ShellExecuteRequest {
     pub command: String,
     pub workdir: Option<String>,
     pub timeout_ms: Option<u64>,
+    /// Agent session the command runs for; its tool calls are queued and
+    /// rate limited together
+    pub session_id: Option<String>,
+    /// Environment profile to run with (settings default if unset)
+    pub environment_profile: Option<String>,
+    /// Attach JSON, CSV and table output as structured data (default on)
+    pub parse_output: Option<bool>,
 }
 
 /// Execute shell command endpoint
@@ -1059,8 +1918,11 @@ pub async fn execute_shell_command(
     State(state): State<crate::AppState>,
     Json(request): Json<ShellExecuteRequest>,
 ) -> Result<Json<serde_json::Value>, AppError> {
-    let workdir = request.workdir
-        .map(|s| resolve_path(&s))
+    // An explicit workdir is passed to the tool so it wins over the
+    // environment profile's default directory
+    let explicit_workdir = request.workdir.map(|s| resolve_path(&s));
+    let workdir = explicit_workdir
+        .clone()
         .unwrap_or_else(|| std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")));
     
     let timeout_ms = request.timeout_ms.unwrap_or(30000);
@@ -1076,6 +1938,10 @@ pub async fn execute_shell_command(
         max_output_size: 1024 * 1024,
         allow_writes: true,
         terminal_session_id: None,
+        session_id: request.session_id,
+        environment_profile: request.environment_profile,
+        parse_output: request.parse_output.unwrap_or(true),
+        ..Default::default()
     };
     
     let executor = AgentExecutor::with_config(executor_config)
@@ -1085,17 +1951,21 @@ pub async fn execute_shell_command(
         id: "http-shell".to_string(),
         name: "shell".to_string(),
         arguments: serde_json::json!({
-            "command": request.command
+            "command": request.command,
+            "workdir": explicit_workdir,
         }),
     };
     
     let result = executor.execute(&tool_call).await;
+    let metadata = result.metadata.unwrap_or_default();
 
     Ok(Json(serde_json::json!({
         "success": result.success,
         "output": result.output,
         "error": result.error,
-        "duration_ms": result.metadata.and_then(|m| m.duration_ms),
+        "duration_ms": metadata.duration_ms,
+        "structured": metadata.structured,
+        "attachments": metadata.attachments,
     })))
 }
 
@@ -1170,4 +2040,38 @@ mod tests {
         let suggestions = generate_query_suggestions("search for getUserData function", &SearchIntent::FindContent);
         assert!(suggestions.contains(&"getUserData".to_string()));
     }
+
+    fn result(path: &str) -> MergedSearchResult {
+        MergedSearchResult {
+            path: path.to_string(),
+            relevance_score: 1.0,
+            source_engine: "test".to_string(),
+            file_type: "rs".to_string(),
+            size: None,
+            modified: None,
+            snippet: None,
+            line_number: None,
+            metadata: None,
+            root: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_batches_skip_sent_paths() {
+        let (tx, _rx) = mpsc::channel(1);
+        let batches = BatchSender::new(tx);
+        let root = std::path::Path::new("/project");
+
+        let first = batches.batch(root, "cli", Ok(vec![result("/project/a.rs"), result("/project/b.rs")]), 10);
+        assert_eq!(first.results.len(), 2);
+
+        let second = batches.batch(root, "fuzzy", Ok(vec![result("/project/b.rs"), result("/project/c.rs")]), 10);
+        let paths: Vec<&str> = second.results.iter().map(|r| r.path.as_str()).collect();
+        assert_eq!(paths, vec!["/project/c.rs"]);
+        assert_eq!(second.engine, "fuzzy");
+
+        let failed = batches.batch(root, "cli", Err("fd not found".to_string()), 10);
+        assert!(failed.results.is_empty());
+        assert_eq!(failed.error.as_deref(), Some("fd not found"));
+    }
 }
@@ -0,0 +1,76 @@
+//! Run history statistics API routes
+//! Aggregated workflow run and agent trace figures for the dashboard
+
+use axum::{
+    extract::{Query, State},
+    routing::get,
+    Json, Router,
+};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use crate::cli_agent::TraceRecorder;
+use crate::context::ContextId;
+use crate::error::AppError;
+use crate::run_stats::{self, AgentStats, WorkflowStats};
+use crate::AppState;
+
+pub fn stats_routes() -> Router<AppState> {
+    Router::new()
+        .route("/stats/workflows", get(workflow_stats))
+        .route("/stats/agents", get(agent_stats))
+}
+
+#[derive(Debug, Deserialize)]
+struct WorkflowStatsQuery {
+    /// Days covered, ending today
+    days: Option<u32>,
+    workflow_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AgentStatsQuery {
+    days: Option<u32>,
+}
+
+/// Runs per day, success rates and durations of the workflow runs visible
+/// to the caller
+async fn workflow_stats(
+    State(state): State<AppState>,
+    ctx: ContextId,
+    Query(query): Query<WorkflowStatsQuery>,
+) -> Json<WorkflowStats> {
+    let runs = state.workflow_engine
+        .list_runs(&ctx, query.workflow_id.as_deref(), None)
+        .await;
+    let names: HashMap<String, String> = state.workflow_storage
+        .list()
+        .await
+        .into_iter()
+        .map(|workflow| (workflow.id, workflow.name))
+        .collect();
+
+    Json(run_stats::workflow_stats(
+        &runs,
+        &names,
+        run_stats::window_days(query.days),
+        chrono::Utc::now(),
+    ))
+}
+
+/// Tool usage and activity per agent, from the recorded traces
+async fn agent_stats(Query(query): Query<AgentStatsQuery>) -> Result<Json<AgentStats>, AppError> {
+    let days = run_stats::window_days(query.days);
+    let stats = tokio::task::spawn_blocking(move || {
+        let recorder = TraceRecorder::global();
+        let traces: Vec<_> = recorder
+            .sessions()
+            .into_iter()
+            .filter_map(|id| Some((id.clone(), recorder.read(&id, true).ok()??)))
+            .collect();
+        run_stats::agent_stats(&traces, days, chrono::Utc::now())
+    })
+    .await
+    .map_err(|e| AppError::Internal(e.to_string()))?;
+    Ok(Json(stats))
+}
@@ -0,0 +1,69 @@
+//! System process API routes
+//! Lists, inspects and stops processes for "what's eating my CPU?" answers
+
+use axum::{
+    extract::{Path, Query},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+
+use crate::error::AppError;
+use crate::processes::{self, KillOutcome, ProcessDetails, ProcessError, ProcessList, ProcessQuery};
+
+pub fn system_routes() -> Router<crate::AppState> {
+    Router::new()
+        .route("/system/processes", get(list_processes))
+        .route("/system/processes/:pid", get(inspect_process))
+        .route("/system/processes/:pid/kill", post(kill_process))
+}
+
+/// Request body for stopping a process
+#[derive(Debug, Deserialize)]
+pub struct KillProcessRequest {
+    /// Send SIGKILL instead of SIGTERM
+    #[serde(default)]
+    pub force: bool,
+    /// Set once the user has confirmed stopping a process Skhoot didn't start
+    #[serde(default)]
+    pub approved: bool,
+}
+
+impl From<ProcessError> for AppError {
+    fn from(e: ProcessError) -> Self {
+        match e {
+            ProcessError::NotFound(_) => AppError::NotFound(e.to_string()),
+            ProcessError::Protected(_) | ProcessError::ConfirmationRequired(_) | ProcessError::PermissionDenied(_) => {
+                AppError::BadRequest(e.to_string())
+            }
+            ProcessError::Unavailable(_) => AppError::Internal(e.to_string()),
+        }
+    }
+}
+
+/// Run a blocking process call off the async runtime
+async fn blocking<T: Send + 'static>(
+    f: impl FnOnce() -> Result<T, ProcessError> + Send + 'static,
+) -> Result<T, AppError> {
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| AppError::Internal(format!("Process task failed: {}", e)))?
+        .map_err(AppError::from)
+}
+
+/// Processes sorted by CPU use (or `sort`), optionally filtered by name
+async fn list_processes(Query(query): Query<ProcessQuery>) -> Result<Json<ProcessList>, AppError> {
+    blocking(move || processes::list(&query)).await.map(Json)
+}
+
+async fn inspect_process(Path(pid): Path<u32>) -> Result<Json<ProcessDetails>, AppError> {
+    blocking(move || processes::inspect(pid)).await.map(Json)
+}
+
+/// Stop a process; one Skhoot didn't start needs `approved`
+async fn kill_process(
+    Path(pid): Path<u32>,
+    Json(request): Json<KillProcessRequest>,
+) -> Result<Json<KillOutcome>, AppError> {
+    blocking(move || processes::kill(pid, request.force, request.approved)).await.map(Json)
+}
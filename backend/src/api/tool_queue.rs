@@ -0,0 +1,34 @@
+//! Agent tool queue API routes
+//! Shows how many tool calls of a session are running or waiting, and
+//! resumes sessions paused by loop detection
+
+use axum::{
+    extract::Path,
+    response::Json,
+    routing::{get, post},
+    Router,
+};
+
+use crate::cli_agent::{QueueStatus, ToolThrottle};
+use crate::error::AppError;
+
+/// API routes for agent tool queues
+pub fn tool_queue_routes() -> Router<crate::AppState> {
+    Router::new()
+        .route("/tool-queue/:session_id", get(get_queue))
+        .route("/tool-queue/:session_id/resume", post(resume_queue))
+}
+
+/// Queue state of a session
+pub async fn get_queue(Path(session_id): Path<String>) -> Json<QueueStatus> {
+    Json(ToolThrottle::global().status(&session_id))
+}
+
+/// Let a paused session run tools again
+pub async fn resume_queue(Path(session_id): Path<String>) -> Result<Json<QueueStatus>, AppError> {
+    let throttle = ToolThrottle::global();
+    if !throttle.resume(&session_id) {
+        return Err(AppError::BadRequest(format!("Session {} is not paused", session_id)));
+    }
+    Ok(Json(throttle.status(&session_id)))
+}
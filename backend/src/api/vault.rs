@@ -0,0 +1,110 @@
+//! Conversation vault routes
+//! Turn encryption at rest for conversations on or off, and unlock or lock
+//! it for the current run
+
+use axum::{extract::State, response::Json, routing::{get, post}, Router};
+use serde::{Deserialize, Serialize};
+
+use crate::conversation_search::ConversationIndex;
+use crate::error::AppError;
+use crate::vault::{ConversationVault, VaultStatus};
+use crate::AppState;
+
+/// API routes for the conversation vault
+pub fn vault_routes() -> Router<AppState> {
+    Router::new()
+        .route("/vault", get(get_status))
+        .route("/vault/enable", post(enable))
+        .route("/vault/disable", post(disable))
+        .route("/vault/unlock", post(unlock))
+        .route("/vault/lock", post(lock))
+        .route("/vault/seal", post(seal))
+        .route("/vault/open", post(open))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PassphraseRequest {
+    pub passphrase: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct VaultChangeResponse {
+    #[serde(flatten)]
+    pub status: VaultStatus,
+    /// Stored messages rewritten by the change
+    pub rewritten: usize,
+}
+
+pub async fn get_status() -> Json<VaultStatus> {
+    Json(ConversationVault::global().status())
+}
+
+/// Enable encryption and seal the messages stored so far
+pub async fn enable(
+    State(state): State<AppState>,
+    Json(request): Json<PassphraseRequest>,
+) -> Result<Json<VaultChangeResponse>, AppError> {
+    let vault = ConversationVault::global();
+    with_key_derivation(vault.clone(), move |vault| vault.enable(&request.passphrase)).await?;
+    let rewritten = ConversationIndex::new(state.db.clone()).rewrite_all(true).await?;
+    Ok(Json(VaultChangeResponse { status: vault.status(), rewritten }))
+}
+
+/// Decrypt the stored messages and disable encryption
+pub async fn disable(
+    State(state): State<AppState>,
+    Json(request): Json<PassphraseRequest>,
+) -> Result<Json<VaultChangeResponse>, AppError> {
+    let vault = ConversationVault::global();
+    let passphrase = request.passphrase.clone();
+    with_key_derivation(vault.clone(), move |vault| vault.unlock(&passphrase)).await?;
+    let rewritten = ConversationIndex::new(state.db.clone()).rewrite_all(false).await?;
+    with_key_derivation(vault.clone(), move |vault| vault.disable(&request.passphrase)).await?;
+    Ok(Json(VaultChangeResponse { status: vault.status(), rewritten }))
+}
+
+/// Cache the key until the vault is locked or the backend exits
+pub async fn unlock(Json(request): Json<PassphraseRequest>) -> Result<Json<VaultStatus>, AppError> {
+    let vault = ConversationVault::global();
+    with_key_derivation(vault.clone(), move |vault| vault.unlock(&request.passphrase)).await?;
+    Ok(Json(vault.status()))
+}
+
+pub async fn lock() -> Json<VaultStatus> {
+    let vault = ConversationVault::global();
+    vault.lock();
+    Json(vault.status())
+}
+
+/// Texts to seal or open
+#[derive(Debug, Deserialize, Serialize)]
+pub struct VaultTexts {
+    pub texts: Vec<String>,
+}
+
+/// Seal texts a client stores itself, such as memories; returned unchanged
+/// when encryption is off
+pub async fn seal(Json(request): Json<VaultTexts>) -> Result<Json<VaultTexts>, AppError> {
+    let vault = ConversationVault::global();
+    let texts = request.texts.iter().map(|text| vault.seal(text)).collect::<Result<_, _>>()?;
+    Ok(Json(VaultTexts { texts }))
+}
+
+/// Open texts sealed with [`seal`]; plain texts are returned as they are
+pub async fn open(Json(request): Json<VaultTexts>) -> Result<Json<VaultTexts>, AppError> {
+    let vault = ConversationVault::global();
+    let texts = request.texts.iter().map(|text| vault.open(text)).collect::<Result<_, _>>()?;
+    Ok(Json(VaultTexts { texts }))
+}
+
+/// Run a vault operation that derives the key off the async runtime, since
+/// Argon2 is deliberately slow
+async fn with_key_derivation<F>(vault: std::sync::Arc<ConversationVault>, f: F) -> Result<(), AppError>
+where
+    F: FnOnce(&ConversationVault) -> Result<(), crate::vault::VaultError> + Send + 'static,
+{
+    tokio::task::spawn_blocking(move || f(&vault))
+        .await
+        .map_err(|e| AppError::Internal(format!("Vault task failed: {}", e)))??;
+    Ok(())
+}
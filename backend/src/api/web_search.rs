@@ -1,21 +1,25 @@
 use axum::{
     extract::{Query, State},
     response::Json,
-    routing::get,
+    routing::{get, post},
     Router,
 };
+use serde_json::json;
 use serde::{Deserialize, Serialize};
 use scraper::{Html, Selector};
 use std::collections::HashMap;
 
 use crate::error::AppError;
-use crate::content_extraction::PageExtract;
+use crate::content_extraction::answer::{self, AnswerSource};
+use crate::content_extraction::{is_valid_context_name, ExtractionLimits, PageExtract, ScreenshotMode};
 
 /// API endpoints for web search functionality
 pub fn web_search_routes() -> Router<crate::AppState> {
     Router::new()
         .route("/search/web", get(web_search))
         .route("/browse", get(browse))
+        .route("/web/answer", post(web_answer))
+        .route("/web/cache", get(get_web_cache).delete(clear_web_cache))
 }
 
 /// Query parameters for web search
@@ -26,6 +30,9 @@ pub struct WebSearchQuery {
     pub search_type: Option<String>,    // Type: general, news, docs
     pub gather: Option<bool>,           // Whether to gather content from top results (default: false)
     pub gather_top: Option<usize>,      // Number of top results to gather from (default: 3, max: 5)
+    pub fetch_timeout_ms: Option<u64>,  // Timeout per page fetch when gathering (default: 15000, max: 60000)
+    pub render_timeout_ms: Option<u64>, // Timeout per WebView render (default: 30000, max: 120000)
+    pub budget_ms: Option<u64>,         // Time for search and gathering together (default: 120000, max: 300000)
 }
 
 /// Query parameters for browse endpoint
@@ -33,6 +40,39 @@ pub struct WebSearchQuery {
 pub struct BrowseQuery {
     pub url: String,                    // URL to browse and extract content from
     pub render: Option<bool>,           // Whether to enable WebView rendering for low-confidence pages (default: false)
+    pub depth: Option<usize>,           // Link depth to follow within the page's section (default: 0, max: 3)
+    pub max_pages: Option<usize>,       // Pages to read when depth > 0 (default: 10, max: 25)
+    pub screenshot: Option<ScreenshotMode>, // Attach a PNG of the rendered page: viewport or full_page
+    pub context: Option<String>,        // Named browsing context to render in (its cookies and storage)
+    pub fetch_timeout_ms: Option<u64>,  // Timeout per page fetch (default: 15000, max: 60000)
+    pub render_timeout_ms: Option<u64>, // Timeout per WebView render (default: 30000, max: 120000)
+    pub budget_ms: Option<u64>,         // Time for the whole request (default: 120000, max: 300000)
+}
+
+/// Request body for the answer endpoint
+#[derive(Debug, Deserialize)]
+pub struct WebAnswerRequest {
+    pub query: String,
+    pub provider: String,               // openai, anthropic or google
+    pub api_key: String,
+    pub model: Option<String>,          // Provider default when omitted
+    pub num_results: Option<usize>,     // Search results to consider (default: 5, max: 10)
+    pub gather_top: Option<usize>,      // Pages to read in full (default: 3, max: 5)
+    pub fetch_timeout_ms: Option<u64>,  // Timeout per page fetch (default: 15000, max: 60000)
+    pub render_timeout_ms: Option<u64>, // Timeout per WebView render (default: 30000, max: 120000)
+    pub budget_ms: Option<u64>,         // Time for search and gathering, not the answer (default: 120000, max: 300000)
+}
+
+/// Answer synthesized from web sources
+#[derive(Debug, Serialize)]
+pub struct WebAnswerResponse {
+    pub query: String,
+    /// Answer text with inline [n] citations of `sources`
+    pub answer: String,
+    pub sources: Vec<AnswerSource>,
+    pub search_time_ms: u64,
+    pub gather_time_ms: u64,
+    pub answer_time_ms: u64,
 }
 
 /// Web search result
@@ -85,6 +125,7 @@ pub async fn web_search(
     let search_type = params.search_type.as_deref().unwrap_or("general");
     let gather = params.gather.unwrap_or(false);
     let gather_top = params.gather_top.unwrap_or(3).min(5);
+    let limits = ExtractionLimits::from_request(params.fetch_timeout_ms, params.render_timeout_ms, params.budget_ms, None);
     
     tracing::info!(
         "Web search request - query: '{}', type: {}, num_results: {}, gather: {}, gather_top: {}",
@@ -103,15 +144,16 @@ pub async fn web_search(
         let mut system = state.content_extraction_system.lock().await;
         
         let gather_response = system
-            .search_and_gather(&params.q, num_results, gather_top)
+            .search_and_gather_with_limits(&params.q, num_results, gather_top, &limits)
             .await
             .map_err(|e| AppError::Internal(format!("Search and gather failed: {}", e)))?;
         
         tracing::info!(
-            "Search and gather completed - query: '{}', results: {}, gathered: {}, search_time: {}ms, gather_time: {}ms",
+            "Search and gather completed - query: '{}', results: {}, gathered: {}, skipped: {}, search_time: {}ms, gather_time: {}ms",
             gather_response.query,
             gather_response.search_results.len(),
             gather_response.gathered_pages.len(),
+            gather_response.skipped_urls.len(),
             gather_response.total_search_time_ms,
             gather_response.total_gather_time_ms
         );
@@ -169,6 +211,76 @@ pub async fn web_search(
     Ok(Json(serde_json::to_value(response).unwrap()))
 }
 
+/// Answer endpoint: search, read the top results and have the AI provider
+/// answer from them in one call
+/// 
+/// Runs search_and_gather, picks the chunks of gathered text most relevant to
+/// the query (search snippets stand in for pages that couldn't be read), and
+/// asks the provider for an answer citing them as [n]. The sources come back
+/// numbered, with `cited` set on those the answer refers to.
+pub async fn web_answer(
+    State(state): State<crate::AppState>,
+    Json(request): Json<WebAnswerRequest>,
+) -> Result<Json<WebAnswerResponse>, AppError> {
+    let query = request.query.trim();
+    if query.is_empty() {
+        return Err(AppError::BadRequest("query is required".to_string()));
+    }
+    if request.api_key.trim().is_empty() {
+        return Err(AppError::BadRequest("api_key is required".to_string()));
+    }
+    let num_results = request.num_results.unwrap_or(5).clamp(1, 10);
+    let gather_top = request.gather_top.unwrap_or(3).min(5);
+    let limits =
+        ExtractionLimits::from_request(request.fetch_timeout_ms, request.render_timeout_ms, request.budget_ms, None);
+
+    let gathered = {
+        let mut system = state.content_extraction_system.lock().await;
+        system
+            .search_and_gather_with_limits(query, num_results, gather_top, &limits)
+            .await
+            .map_err(|e| AppError::Internal(format!("Search and gather failed: {}", e)))?
+    };
+
+    let (mut sources, texts) = answer::number_sources(&gathered);
+    if sources.is_empty() {
+        return Err(AppError::NotFound(format!("No web results for '{}'", query)));
+    }
+    let chunks = answer::select_chunks(query, &texts, answer::CONTEXT_WORD_BUDGET);
+    let prompt = answer::build_prompt(query, &sources, &chunks);
+
+    let started = std::time::Instant::now();
+    let completion = state
+        .ai_manager
+        .complete(
+            &request.provider,
+            &request.api_key,
+            request.model.as_deref(),
+            answer::SYSTEM_PROMPT,
+            &prompt,
+        )
+        .await?;
+    let answer_time_ms = started.elapsed().as_millis() as u64;
+    let answer = answer::resolve_citations(&completion, &mut sources);
+
+    tracing::info!(
+        "Answered '{}' from {} sources ({} cited) in {}ms",
+        query,
+        sources.len(),
+        sources.iter().filter(|source| source.cited).count(),
+        answer_time_ms
+    );
+
+    Ok(Json(WebAnswerResponse {
+        query: query.to_string(),
+        answer,
+        sources,
+        search_time_ms: gathered.total_search_time_ms,
+        gather_time_ms: gathered.total_gather_time_ms,
+        answer_time_ms,
+    }))
+}
+
 /// Browse endpoint for content extraction
 /// 
 /// This endpoint:
@@ -182,6 +294,17 @@ pub async fn web_search(
 /// 
 /// * `url` - The URL to browse and extract content from (required)
 /// * `render` - Whether to enable WebView rendering for low-confidence pages (optional, default: false)
+/// * `depth` - Follow same-origin links under the page's directory this many levels deep and
+///   return the pages combined (optional, default: 0)
+/// * `max_pages` - Maximum pages read when crawling (optional, default: 10, max: 25)
+/// * `screenshot` - `viewport` or `full_page` to attach a screenshot of the rendered start
+///   page (optional); the extract is still returned when the screenshot fails
+/// * `context` - Name of a browsing context, e.g. one the user logged in with from the app;
+///   pages are then rendered with its cookies and not cached (optional)
+/// * `fetch_timeout_ms`, `render_timeout_ms` - Timeouts per page fetch and WebView render
+///   (optional, defaults 15s and 30s, capped at 60s and 120s)
+/// * `budget_ms` - Time for the whole request; a crawl returns the pages read when it runs
+///   out (optional, default: 120s, max: 300s)
 /// 
 /// # Returns
 /// 
@@ -196,18 +319,43 @@ pub async fn browse(
     State(state): State<crate::AppState>,
 ) -> Result<Json<PageExtract>, AppError> {
     let render = params.render.unwrap_or(false);
+    let depth = params.depth.unwrap_or(0);
+    let limits = ExtractionLimits::from_request(
+        params.fetch_timeout_ms,
+        params.render_timeout_ms,
+        params.budget_ms,
+        params.max_pages,
+    );
+    let context = params.context.as_deref().map(str::trim).filter(|c| !c.is_empty());
+    if let Some(context) = context {
+        if !is_valid_context_name(context) {
+            return Err(AppError::BadRequest(format!(
+                "Invalid browsing context '{}': use letters, digits, '-' and '_'",
+                context
+            )));
+        }
+    }
     
     tracing::info!(
-        "Browse request - url: '{}', render: {}",
+        "Browse request - url: '{}', render: {}, depth: {}, context: {:?}",
         params.url,
-        render
+        render,
+        depth,
+        context
     );
     
     // Get a lock on the content extraction system
     let mut system = state.content_extraction_system.lock().await;
     
-    // Call the browse method
-    let page_extract = system.browse(&params.url, render).await?;
+    // Call the browse method (crawl follows links when depth > 0)
+    let mut page_extract = system.crawl(&params.url, render, depth, context, &limits).await?;
+    
+    if let Some(mode) = params.screenshot {
+        match system.capture_screenshot(&page_extract.final_url, mode, context).await {
+            Ok(screenshot) => page_extract.screenshot = Some(screenshot),
+            Err(e) => tracing::warn!("Screenshot of {} failed: {}", page_extract.final_url, e),
+        }
+    }
     
     tracing::info!(
         "Browse completed - url: '{}', confidence: {:.2}, method: {:?}, time: {}ms",
@@ -220,6 +368,65 @@ pub async fn browse(
     Ok(Json(page_extract))
 }
 
+/// Query parameters for the web cache endpoints
+#[derive(Debug, Deserialize)]
+pub struct WebCacheQuery {
+    pub limit: Option<usize>,           // Entries to list (default: 100, max: 1000)
+    pub url: Option<String>,            // DELETE: only remove this URL
+}
+
+/// Inspect the content extraction caches
+pub async fn get_web_cache(
+    Query(params): Query<WebCacheQuery>,
+    State(state): State<crate::AppState>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    let limit = params.limit.unwrap_or(100).min(1000);
+
+    // Release the system lock before touching the database
+    let (memory, disk_cache) = {
+        let system = state.content_extraction_system.lock().await;
+        (system.memory_cache_stats(), system.disk_cache().cloned())
+    };
+
+    let (disk, entries) = match disk_cache {
+        Some(cache) => (Some(cache.stats().await?), cache.entries(limit).await?),
+        None => (None, Vec::new()),
+    };
+
+    Ok(Json(json!({
+        "memory": memory,
+        "disk": disk,
+        "entries": entries,
+    })))
+}
+
+/// Clear the content extraction caches, or a single URL with `?url=`
+pub async fn clear_web_cache(
+    Query(params): Query<WebCacheQuery>,
+    State(state): State<crate::AppState>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    let disk_cache = {
+        let mut system = state.content_extraction_system.lock().await;
+        match &params.url {
+            Some(url) => {
+                system.remove_from_memory_cache(url);
+            }
+            None => system.clear_memory_cache(),
+        }
+        system.disk_cache().cloned()
+    };
+
+    let removed = match (disk_cache, &params.url) {
+        (Some(cache), Some(url)) => cache.remove(url).await? as u64,
+        (Some(cache), None) => cache.clear().await?,
+        (None, _) => 0,
+    };
+
+    tracing::info!("Web cache cleared - url: {:?}, disk entries removed: {}", params.url, removed);
+
+    Ok(Json(json!({ "disk_entries_removed": removed })))
+}
+
 // ============================================================================
 // DuckDuckGo HTTP Scraping Implementation (Production)
 // ============================================================================
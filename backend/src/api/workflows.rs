@@ -1,30 +1,59 @@
-use axum::{extract::{State, Path}, Json, routing::{get, post, delete, put}, Router};
+use axum::{extract::{State, Path, Query}, Json, routing::{get, post, delete, put}, Router};
+use axum::http::header;
+use axum::response::{IntoResponse, Response};
+use serde::Deserialize;
 use serde_json::Value;
 use std::collections::HashMap;
 
 use crate::AppState;
+use crate::context::ContextId;
 use crate::error::AppError;
 use crate::workflows::types::*;
+use crate::workflows::expression::validate_steps;
+use crate::workflows::{ArtifactSource, RunArtifact, StepOutcome, WorkflowRun};
+use crate::workflows::webhook::payload_variables;
+use crate::workflows::import::{self, ImportError, ImportPreview, ImportSource};
+use crate::workflows::validation::{self, ValidationReport, ValidationRequest};
 
 pub fn workflow_routes() -> Router<AppState> {
     Router::new()
         .route("/workflows", get(list_workflows).post(create_workflow))
         .route("/workflows/:id", get(get_workflow).put(update_workflow).delete(delete_workflow))
+        .route("/workflows/:id/validate", post(validate_workflow))
         .route("/workflows/execute", post(execute_workflow))
+        .route("/workflows/import", post(install_workflow))
+        .route("/workflows/import/preview", post(preview_import))
         .route("/workflows/executions/:id", get(get_execution).put(update_execution).delete(cancel_execution))
         .route("/workflows/executions/active", get(list_active_executions))
+        .route("/workflows/runs", get(list_runs).post(start_run))
+        .route("/workflows/runs/:run_id", get(get_run))
+        .route("/workflows/runs/:run_id/steps", post(report_run_step))
+        .route("/workflows/runs/:run_id/cancel", post(cancel_run))
+        .route("/workflows/runs/:run_id/resume", post(resume_run))
+        .route("/workflows/runs/:run_id/approve", post(approve_run))
+        .route("/workflows/runs/:run_id/artifacts", get(list_artifacts).post(register_artifact))
+        .route("/workflows/runs/:run_id/artifacts/:artifact_id", get(download_artifact))
+}
+
+/// Inbound webhook routes. Mounted outside the API token layer because
+/// external callers authenticate with the hook's own `:token`.
+pub fn webhook_routes() -> Router<AppState> {
+    Router::new().route("/hooks/:token", post(webhook_trigger))
 }
 
 // ... existing code ...
 
 async fn update_execution(
     State(state): State<AppState>,
+    ctx: ContextId,
     Path(id): Path<String>,
-    Json(context): Json<ExecutionContext>,
+    Json(mut context): Json<ExecutionContext>,
 ) -> Result<Json<bool>, AppError> {
     if context.execution_id != id {
         return Err(AppError::BadRequest("Execution ID mismatch".to_string()));
     }
+    // The owning window can't be changed through an update
+    context.context_id = accessible_execution(&state, &ctx, &id).await?.context_id;
     state.workflow_engine.update_execution(context).await
         .map_err(|e| AppError::Internal(e))?;
     Ok(Json(true))
@@ -39,6 +68,7 @@ async fn create_workflow(
     State(state): State<AppState>,
     Json(request): Json<CreateWorkflowRequest>,
 ) -> Result<Json<Workflow>, AppError> {
+    validate_steps(&request.steps).map_err(AppError::BadRequest)?;
     let workflow = state.workflow_storage.create(request).await;
     Ok(Json(workflow))
 }
@@ -57,11 +87,25 @@ async fn update_workflow(
     Path(id): Path<String>,
     Json(workflow): Json<Workflow>,
 ) -> Result<Json<Workflow>, AppError> {
+    validate_steps(&workflow.steps).map_err(AppError::BadRequest)?;
     let updated = state.workflow_storage.update(&id, workflow).await
         .ok_or_else(|| AppError::NotFound(format!("Workflow {} not found", id)))?;
     Ok(Json(updated))
 }
 
+/// Check a workflow without running it; the optional body holds sample
+/// variables and trigger payload for resolving placeholders
+async fn validate_workflow(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    body: Option<Json<ValidationRequest>>,
+) -> Result<Json<ValidationReport>, AppError> {
+    let workflow = state.workflow_storage.get(&id).await
+        .ok_or_else(|| AppError::NotFound(format!("Workflow {} not found", id)))?;
+    let sample = body.map(|Json(sample)| sample).unwrap_or_default();
+    Ok(Json(validation::validate(&workflow, sample)))
+}
+
 async fn delete_workflow(
     State(state): State<AppState>,
     Path(id): Path<String>,
@@ -70,34 +114,266 @@ async fn delete_workflow(
     Ok(Json(success))
 }
 
+/// Import request: the source and, optionally, the digest of the reviewed bundle
+#[derive(Debug, Deserialize)]
+struct InstallWorkflowRequest {
+    #[serde(flatten)]
+    source: ImportSource,
+    #[serde(default)]
+    digest: Option<String>,
+}
+
+async fn preview_import(
+    State(state): State<AppState>,
+    Json(source): Json<ImportSource>,
+) -> Result<Json<ImportPreview>, AppError> {
+    let bundle = import::fetch_bundle(&source).await.map_err(import_error)?;
+    Ok(Json(import::preview(&state.workflow_storage, &source, &bundle).await))
+}
+
+async fn install_workflow(
+    State(state): State<AppState>,
+    Json(request): Json<InstallWorkflowRequest>,
+) -> Result<Json<Workflow>, AppError> {
+    let bundle = import::fetch_bundle(&request.source).await.map_err(import_error)?;
+    if request.digest.as_ref().is_some_and(|digest| *digest != bundle.digest) {
+        return Err(import_error(ImportError::Changed));
+    }
+    let workflow = import::install(&state.workflow_storage, &request.source, bundle)
+        .await
+        .map_err(import_error)?;
+    Ok(Json(workflow))
+}
+
+fn import_error(error: ImportError) -> AppError {
+    match error {
+        ImportError::Io(_) => AppError::Internal(error.to_string()),
+        _ => AppError::BadRequest(error.to_string()),
+    }
+}
+
 async fn execute_workflow(
     State(state): State<AppState>,
-    Json(request): Json<ExecuteWorkflowRequest>,
+    ctx: ContextId,
+    Json(mut request): Json<ExecuteWorkflowRequest>,
 ) -> Result<Json<ExecutionContext>, AppError> {
+    ensure_workflow_exists(&state, &request.workflow_id).await?;
+    request.context_id = ctx;
     let context = state.workflow_engine.execute(request).await
-        .map_err(|e| AppError::Internal(e))?;
+        .map_err(AppError::BadRequest)?;
     Ok(Json(context))
 }
 
 async fn get_execution(
     State(state): State<AppState>,
+    ctx: ContextId,
     Path(id): Path<String>,
 ) -> Result<Json<ExecutionContext>, AppError> {
-    let context = state.workflow_engine.get_execution(&id).await
-        .ok_or_else(|| AppError::NotFound(format!("Execution {} not found", id)))?;
+    let context = accessible_execution(&state, &ctx, &id).await?;
     Ok(Json(context))
 }
 
-async fn list_active_executions(State(state): State<AppState>) -> Result<Json<Vec<ExecutionContext>>, AppError> {
-    let executions = state.workflow_engine.list_active().await;
+async fn list_active_executions(
+    State(state): State<AppState>,
+    ctx: ContextId,
+) -> Result<Json<Vec<ExecutionContext>>, AppError> {
+    let executions = state.workflow_engine.list_active(&ctx).await;
     Ok(Json(executions))
 }
 
 async fn cancel_execution(
     State(state): State<AppState>,
+    ctx: ContextId,
     Path(id): Path<String>,
 ) -> Result<Json<bool>, AppError> {
+    if let Some(context) = state.workflow_engine.get_execution(&id).await {
+        if !ctx.can_access(&context.context_id) {
+            return Err(AppError::NotFound(format!("Execution {} not found", id)));
+        }
+    }
     state.workflow_engine.cancel(&id).await
         .map_err(|e| AppError::Internal(e))?;
     Ok(Json(true))
 }
+
+/// Filters for listing workflow runs
+#[derive(Debug, Deserialize)]
+struct RunListQuery {
+    workflow_id: Option<String>,
+    status: Option<WorkflowStatus>,
+}
+
+async fn list_runs(
+    State(state): State<AppState>,
+    ctx: ContextId,
+    Query(query): Query<RunListQuery>,
+) -> Result<Json<Vec<WorkflowRun>>, AppError> {
+    let runs = state.workflow_engine
+        .list_runs(&ctx, query.workflow_id.as_deref(), query.status)
+        .await;
+    Ok(Json(runs))
+}
+
+async fn start_run(
+    State(state): State<AppState>,
+    ctx: ContextId,
+    Json(mut request): Json<ExecuteWorkflowRequest>,
+) -> Result<Json<WorkflowRun>, AppError> {
+    ensure_workflow_exists(&state, &request.workflow_id).await?;
+    request.context_id = ctx;
+    let run = state.workflow_engine.start_run(request).await
+        .map_err(AppError::BadRequest)?;
+    Ok(Json(run))
+}
+
+/// Engine errors on start are input problems once the workflow is known to exist
+async fn ensure_workflow_exists(state: &AppState, workflow_id: &str) -> Result<(), AppError> {
+    state.workflow_storage.get(workflow_id).await
+        .map(|_| ())
+        .ok_or_else(|| AppError::NotFound(format!("Workflow {} not found", workflow_id)))
+}
+
+/// Executions started by another window are hidden from this one
+async fn accessible_execution(state: &AppState, ctx: &ContextId, id: &str) -> Result<ExecutionContext, AppError> {
+    state.workflow_engine.get_execution(id).await
+        .filter(|context| ctx.can_access(&context.context_id))
+        .ok_or_else(|| AppError::NotFound(format!("Execution {} not found", id)))
+}
+
+/// Runs started by another window are hidden from this one
+async fn accessible_run(state: &AppState, ctx: &ContextId, run_id: &str) -> Result<WorkflowRun, AppError> {
+    state.workflow_engine.get_run(run_id).await
+        .filter(|run| ctx.can_access(&run.context_id))
+        .ok_or_else(|| AppError::NotFound(format!("Run {} not found", run_id)))
+}
+
+async fn get_run(
+    State(state): State<AppState>,
+    ctx: ContextId,
+    Path(run_id): Path<String>,
+) -> Result<Json<WorkflowRun>, AppError> {
+    let run = accessible_run(&state, &ctx, &run_id).await?;
+    Ok(Json(run))
+}
+
+async fn report_run_step(
+    State(state): State<AppState>,
+    ctx: ContextId,
+    Path(run_id): Path<String>,
+    Json(outcome): Json<StepOutcome>,
+) -> Result<Json<WorkflowRun>, AppError> {
+    accessible_run(&state, &ctx, &run_id).await?;
+    let run = state.workflow_engine.report_step(&run_id, outcome).await
+        .map_err(AppError::BadRequest)?;
+    Ok(Json(run))
+}
+
+async fn cancel_run(
+    State(state): State<AppState>,
+    ctx: ContextId,
+    Path(run_id): Path<String>,
+) -> Result<Json<WorkflowRun>, AppError> {
+    accessible_run(&state, &ctx, &run_id).await?;
+    let run = state.workflow_engine.cancel_run(&run_id).await
+        .map_err(AppError::BadRequest)?;
+    Ok(Json(run))
+}
+
+async fn resume_run(
+    State(state): State<AppState>,
+    ctx: ContextId,
+    Path(run_id): Path<String>,
+) -> Result<Json<WorkflowRun>, AppError> {
+    accessible_run(&state, &ctx, &run_id).await?;
+    let run = state.workflow_engine.resume_run(&run_id).await
+        .map_err(AppError::BadRequest)?;
+    Ok(Json(run))
+}
+
+/// Input given along with an approval; merged into the run variables
+#[derive(Debug, Default, Deserialize)]
+struct ApproveRequest {
+    #[serde(default)]
+    variables: HashMap<String, Value>,
+}
+
+async fn approve_run(
+    State(state): State<AppState>,
+    ctx: ContextId,
+    Path(run_id): Path<String>,
+    body: Option<Json<ApproveRequest>>,
+) -> Result<Json<WorkflowRun>, AppError> {
+    accessible_run(&state, &ctx, &run_id).await?;
+    let request = body.map(|Json(request)| request).unwrap_or_default();
+    let run = state.workflow_engine.approve_run(&run_id, request.variables).await
+        .map_err(AppError::BadRequest)?;
+    Ok(Json(run))
+}
+
+async fn list_artifacts(
+    State(state): State<AppState>,
+    ctx: ContextId,
+    Path(run_id): Path<String>,
+) -> Result<Json<Vec<RunArtifact>>, AppError> {
+    let run = accessible_run(&state, &ctx, &run_id).await?;
+    Ok(Json(run.artifacts))
+}
+
+async fn register_artifact(
+    State(state): State<AppState>,
+    ctx: ContextId,
+    Path(run_id): Path<String>,
+    Json(source): Json<ArtifactSource>,
+) -> Result<Json<RunArtifact>, AppError> {
+    accessible_run(&state, &ctx, &run_id).await?;
+    let artifact = state.workflow_engine.register_artifact(&run_id, source).await
+        .map_err(AppError::BadRequest)?;
+    Ok(Json(artifact))
+}
+
+/// Stored copy of an artifact with its content type
+async fn download_artifact(
+    State(state): State<AppState>,
+    ctx: ContextId,
+    Path((run_id, artifact_id)): Path<(String, String)>,
+) -> Result<Response, AppError> {
+    accessible_run(&state, &ctx, &run_id).await?;
+    let (artifact, path) = state.workflow_engine.artifact(&run_id, &artifact_id).await
+        .ok_or_else(|| AppError::NotFound(format!("Artifact {} not found", artifact_id)))?;
+    let bytes = tokio::fs::read(&path).await
+        .map_err(|_| AppError::NotFound(format!("Artifact {} is no longer stored", artifact_id)))?;
+    let disposition = format!("attachment; filename=\"{}\"", artifact.name.replace('"', ""));
+    Ok((
+        [
+            (header::CONTENT_TYPE, artifact.mime_type),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        bytes,
+    )
+        .into_response())
+}
+
+/// Start a run of the workflow whose webhook trigger uses `token`.
+/// The request body is the trigger payload; fields of a JSON object body
+/// become run variables.
+async fn webhook_trigger(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+    body: Option<Json<Value>>,
+) -> Result<Json<WorkflowRun>, AppError> {
+    let workflow = state.workflow_storage.find_by_webhook_token(&token).await
+        .ok_or_else(|| AppError::NotFound("Unknown webhook".to_string()))?;
+
+    let payload = body.map(|Json(v)| v).unwrap_or(Value::Null);
+    let request = ExecuteWorkflowRequest {
+        workflow_id: workflow.id,
+        variables: payload_variables(&payload),
+        start_step_id: None,
+        trigger_payload: Some(payload),
+        context_id: ContextId::default(),
+    };
+
+    let run = state.workflow_engine.start_run(request).await
+        .map_err(AppError::BadRequest)?;
+    Ok(Json(run))
+}
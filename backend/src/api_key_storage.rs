@@ -1,23 +1,78 @@
-//! Secure API key storage with AES-256-GCM encryption and platform keychain integration
+//! Secure API key storage in the platform keychain
+//!
+//! Each provider's key is a separate keychain entry (Windows Credential
+//! Manager, macOS Keychain, Secret Service on Linux). Only non-secret
+//! metadata (active provider, last test time) is kept in the app data
+//! directory. Keys from the older AES-256-GCM encrypted file are migrated
+//! into the keychain the first time storage is opened.
 #![allow(dead_code)]
 
 use aes_gcm::{
-    aead::{Aead, KeyInit, OsRng},
+    aead::{Aead, KeyInit},
     Aes256Gcm, Nonce,
 };
 use anyhow::{Context, Result};
 use keyring::Entry;
-use rand::RngCore;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 const SERVICE_NAME: &str = "com.skhoot.app";
 const ENCRYPTION_KEY_NAME: &str = "encryption_key";
-const STORAGE_FILE: &str = "api_keys.json";
+const LEGACY_STORAGE_FILE: &str = "api_keys.json";
+const INDEX_FILE: &str = "api_key_index.json";
 
-/// Encrypted API key configuration
+/// Keychain account holding a provider's key
+fn account_name(provider: &str) -> String {
+    format!("api_key:{}", provider)
+}
+
+/// Key saved for `provider`, read straight from the keychain; lets the
+/// backend use keys the app stored without opening its data directory
+pub fn provider_key(provider: &str) -> Result<Option<String>> {
+    KeychainStore.get(&account_name(provider))
+}
+
+/// Backend holding secret values by account name
+pub trait SecretStore: Send + Sync {
+    /// Returns the secret, or None if the account has no entry
+    fn get(&self, account: &str) -> Result<Option<String>>;
+
+    fn set(&self, account: &str, secret: &str) -> Result<()>;
+
+    /// Removes the entry; missing entries are not an error
+    fn delete(&self, account: &str) -> Result<()>;
+}
+
+/// Platform keychain via the `keyring` crate
+pub struct KeychainStore;
+
+impl SecretStore for KeychainStore {
+    fn get(&self, account: &str) -> Result<Option<String>> {
+        let entry = Entry::new(SERVICE_NAME, account).context("Failed to create keyring entry")?;
+        match entry.get_password() {
+            Ok(secret) => Ok(Some(secret)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(e).context("Failed to read from keychain"),
+        }
+    }
+
+    fn set(&self, account: &str, secret: &str) -> Result<()> {
+        let entry = Entry::new(SERVICE_NAME, account).context("Failed to create keyring entry")?;
+        entry.set_password(secret).context("Failed to write to keychain")
+    }
+
+    fn delete(&self, account: &str) -> Result<()> {
+        let entry = Entry::new(SERVICE_NAME, account).context("Failed to create keyring entry")?;
+        match entry.delete_password() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(e).context("Failed to delete from keychain"),
+        }
+    }
+}
+
+/// Encrypted API key configuration (legacy `api_keys.json` format)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EncryptedKeyConfig {
     pub provider: String,
@@ -27,111 +82,147 @@ pub struct EncryptedKeyConfig {
     pub last_tested: Option<i64>,
 }
 
-/// API key storage with encryption
+/// Non-secret information about a stored key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyMetadata {
+    pub provider: String,
+    pub is_active: bool,
+    pub last_tested: Option<i64>,
+}
+
+/// Plaintext key as written by `export_keys`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportedKey {
+    pub provider: String,
+    pub api_key: String,
+    pub is_active: bool,
+    pub last_tested: Option<i64>,
+}
+
+/// API key storage backed by the platform keychain
 pub struct KeyStorage {
-    storage_path: PathBuf,
-    cipher: Aes256Gcm,
+    app_data_dir: PathBuf,
+    index_path: PathBuf,
+    secrets: Box<dyn SecretStore>,
 }
 
 impl KeyStorage {
-    /// Create a new KeyStorage instance
+    /// Create a new KeyStorage instance using the platform keychain
     pub fn new(app_data_dir: PathBuf) -> Result<Self> {
+        Self::with_store(app_data_dir, Box::new(KeychainStore))
+    }
+
+    /// Create a KeyStorage instance with a custom secret backend
+    pub fn with_store(app_data_dir: PathBuf, secrets: Box<dyn SecretStore>) -> Result<Self> {
         // Ensure the directory exists
         fs::create_dir_all(&app_data_dir)
             .context("Failed to create app data directory")?;
 
-        let storage_path = app_data_dir.join(STORAGE_FILE);
+        let storage = Self {
+            index_path: app_data_dir.join(INDEX_FILE),
+            app_data_dir,
+            secrets,
+        };
 
-        // Get or create encryption key from platform keychain
-        let encryption_key = Self::get_or_create_encryption_key()?;
-        let cipher = Aes256Gcm::new_from_slice(&encryption_key)
-            .context("Failed to create cipher")?;
+        // A failed migration leaves the legacy file in place for the next start
+        if let Err(e) = storage.migrate_legacy_storage() {
+            tracing::warn!("Failed to migrate API keys to the keychain: {:#}", e);
+        }
 
-        Ok(Self {
-            storage_path,
-            cipher,
-        })
+        Ok(storage)
     }
 
-    /// Get or create the encryption key from platform keychain
-    fn get_or_create_encryption_key() -> Result<Vec<u8>> {
-        let entry = Entry::new(SERVICE_NAME, ENCRYPTION_KEY_NAME)
-            .context("Failed to create keyring entry")?;
+    /// Moves keys from the encrypted `api_keys.json` file into the keychain
+    ///
+    /// The file and its encryption key are removed only after every key
+    /// has been written and read back. Returns the number of migrated keys.
+    fn migrate_legacy_storage(&self) -> Result<usize> {
+        let legacy_path = self.app_data_dir.join(LEGACY_STORAGE_FILE);
+        if !legacy_path.exists() {
+            return Ok(0);
+        }
 
-        // Try to get existing key
-        match entry.get_password() {
-            Ok(key_hex) => {
-                // Decode hex string to bytes
-                hex::decode(&key_hex).context("Failed to decode encryption key")
-            }
-            Err(_) => {
-                // Generate new key
-                let mut key = vec![0u8; 32]; // 256 bits
-                OsRng.fill_bytes(&mut key);
-
-                // Store in keychain as hex string
-                let key_hex = hex::encode(&key);
-                entry
-                    .set_password(&key_hex)
-                    .context("Failed to store encryption key in keychain")?;
-
-                Ok(key)
+        let content = fs::read_to_string(&legacy_path)
+            .context("Failed to read legacy storage file")?;
+        let legacy: HashMap<String, EncryptedKeyConfig> = serde_json::from_str(&content)
+            .context("Failed to parse legacy storage file")?;
+
+        let key_hex = self
+            .secrets
+            .get(ENCRYPTION_KEY_NAME)?
+            .context("Legacy encryption key not found in keychain")?;
+        let key = hex::decode(key_hex).context("Failed to decode encryption key")?;
+        let cipher = Aes256Gcm::new_from_slice(&key).context("Failed to create cipher")?;
+
+        let mut index = self.load_index()?;
+        for (provider, config) in &legacy {
+            let api_key = Self::decrypt_legacy_key(&cipher, &config.encrypted_key, &config.nonce)
+                .with_context(|| format!("Failed to decrypt legacy key for {}", provider))?;
+
+            let account = account_name(provider);
+            self.secrets.set(&account, &api_key)?;
+            if self.secrets.get(&account)?.as_deref() != Some(api_key.as_str()) {
+                anyhow::bail!("Keychain did not return the migrated key for {}", provider);
             }
-        }
-    }
 
-    /// Load all stored keys from disk
-    fn load_storage(&self) -> Result<HashMap<String, EncryptedKeyConfig>> {
-        if !self.storage_path.exists() {
-            return Ok(HashMap::new());
+            index.insert(
+                provider.clone(),
+                KeyMetadata {
+                    provider: provider.clone(),
+                    is_active: config.is_active,
+                    last_tested: config.last_tested,
+                },
+            );
         }
+        self.save_index(&index)?;
 
-        let content = fs::read_to_string(&self.storage_path)
-            .context("Failed to read storage file")?;
-
-        let storage: HashMap<String, EncryptedKeyConfig> = serde_json::from_str(&content)
-            .context("Failed to parse storage file")?;
+        fs::remove_file(&legacy_path).context("Failed to remove legacy storage file")?;
+        if let Err(e) = self.secrets.delete(ENCRYPTION_KEY_NAME) {
+            tracing::debug!("Failed to remove legacy encryption key: {:#}", e);
+        }
 
-        Ok(storage)
+        tracing::info!("Migrated {} API key(s) to the keychain", legacy.len());
+        Ok(legacy.len())
     }
 
-    /// Save all keys to disk
-    fn save_storage(&self, storage: &HashMap<String, EncryptedKeyConfig>) -> Result<()> {
-        let content = serde_json::to_string_pretty(storage)
-            .context("Failed to serialize storage")?;
+    /// Decrypt an API key from the legacy file format
+    fn decrypt_legacy_key(cipher: &Aes256Gcm, encrypted: &[u8], nonce_bytes: &[u8]) -> Result<String> {
+        if nonce_bytes.len() != 12 {
+            anyhow::bail!("Invalid nonce length: {}", nonce_bytes.len());
+        }
+        let nonce = Nonce::from_slice(nonce_bytes);
 
-        fs::write(&self.storage_path, content)
-            .context("Failed to write storage file")?;
+        let decrypted = cipher
+            .decrypt(nonce, encrypted)
+            .map_err(|e| anyhow::anyhow!("Decryption failed: {}", e))?;
 
-        Ok(())
+        String::from_utf8(decrypted).context("Invalid UTF-8 in decrypted key")
     }
 
-    /// Encrypt an API key
-    fn encrypt_key(&self, api_key: &str) -> Result<(Vec<u8>, Vec<u8>)> {
-        // Generate random nonce
-        let mut nonce_bytes = [0u8; 12];
-        OsRng.fill_bytes(&mut nonce_bytes);
-        let nonce = Nonce::from_slice(&nonce_bytes);
+    /// Load key metadata from disk
+    fn load_index(&self) -> Result<HashMap<String, KeyMetadata>> {
+        if !self.index_path.exists() {
+            return Ok(HashMap::new());
+        }
 
-        // Encrypt the key
-        let encrypted = self
-            .cipher
-            .encrypt(nonce, api_key.as_bytes())
-            .map_err(|e| anyhow::anyhow!("Encryption failed: {}", e))?;
+        let content = fs::read_to_string(&self.index_path)
+            .context("Failed to read key index")?;
 
-        Ok((encrypted, nonce_bytes.to_vec()))
+        let index: HashMap<String, KeyMetadata> = serde_json::from_str(&content)
+            .context("Failed to parse key index")?;
+
+        Ok(index)
     }
 
-    /// Decrypt an API key
-    fn decrypt_key(&self, encrypted: &[u8], nonce_bytes: &[u8]) -> Result<String> {
-        let nonce = Nonce::from_slice(nonce_bytes);
+    /// Save key metadata to disk
+    fn save_index(&self, index: &HashMap<String, KeyMetadata>) -> Result<()> {
+        let content = serde_json::to_string_pretty(index)
+            .context("Failed to serialize key index")?;
 
-        let decrypted = self
-            .cipher
-            .decrypt(nonce, encrypted)
-            .map_err(|e| anyhow::anyhow!("Decryption failed: {}", e))?;
+        fs::write(&self.index_path, content)
+            .context("Failed to write key index")?;
 
-        String::from_utf8(decrypted).context("Invalid UTF-8 in decrypted key")
+        Ok(())
     }
 
     /// Save an API key for a provider
@@ -141,109 +232,141 @@ impl KeyStorage {
             anyhow::bail!("API key cannot be empty");
         }
 
-        // Encrypt the key
-        let (encrypted_key, nonce) = self.encrypt_key(api_key)?;
-
-        // Load existing storage
-        let mut storage = self.load_storage()?;
-
-        // Create new config
-        let config = EncryptedKeyConfig {
-            provider: provider.to_string(),
-            encrypted_key,
-            nonce,
-            is_active,
-            last_tested: None,
-        };
+        self.secrets.set(&account_name(provider), api_key)?;
 
-        // Store the config
-        storage.insert(provider.to_string(), config);
-
-        // Save to disk
-        self.save_storage(&storage)?;
+        let mut index = self.load_index()?;
+        index.insert(
+            provider.to_string(),
+            KeyMetadata {
+                provider: provider.to_string(),
+                is_active,
+                last_tested: None,
+            },
+        );
+        self.save_index(&index)?;
 
         Ok(())
     }
 
     /// Load an API key for a provider
     pub fn load_key(&self, provider: &str) -> Result<String> {
-        let storage = self.load_storage()?;
-
-        let config = storage
-            .get(provider)
-            .ok_or_else(|| anyhow::anyhow!("No API key found for provider: {}", provider))?;
+        if !self.load_index()?.contains_key(provider) {
+            anyhow::bail!("No API key found for provider: {}", provider);
+        }
 
-        self.decrypt_key(&config.encrypted_key, &config.nonce)
+        self.secrets
+            .get(&account_name(provider))?
+            .ok_or_else(|| anyhow::anyhow!("API key for {} is missing from the keychain", provider))
     }
 
     /// Delete an API key for a provider
     pub fn delete_key(&self, provider: &str) -> Result<()> {
-        let mut storage = self.load_storage()?;
+        let mut index = self.load_index()?;
 
-        if storage.remove(provider).is_none() {
+        if index.remove(provider).is_none() {
             anyhow::bail!("No API key found for provider: {}", provider);
         }
 
-        self.save_storage(&storage)?;
+        self.secrets.delete(&account_name(provider))?;
+        self.save_index(&index)?;
 
         Ok(())
     }
 
     /// List all configured providers
     pub fn list_providers(&self) -> Result<Vec<String>> {
-        let storage = self.load_storage()?;
-        Ok(storage.keys().cloned().collect())
+        let index = self.load_index()?;
+        Ok(index.keys().cloned().collect())
     }
 
     /// Get the active provider
     pub fn get_active_provider(&self) -> Result<Option<String>> {
-        let storage = self.load_storage()?;
+        let index = self.load_index()?;
 
-        for (provider, config) in storage.iter() {
-            if config.is_active {
-                return Ok(Some(provider.clone()));
-            }
-        }
-
-        Ok(None)
+        Ok(index
+            .values()
+            .find(|metadata| metadata.is_active)
+            .map(|metadata| metadata.provider.clone()))
     }
 
     /// Set a provider as active
     pub fn set_active_provider(&self, provider: &str) -> Result<()> {
-        let mut storage = self.load_storage()?;
+        let mut index = self.load_index()?;
 
         // Check if provider exists
-        if !storage.contains_key(provider) {
+        if !index.contains_key(provider) {
             anyhow::bail!("Provider not found: {}", provider);
         }
 
-        // Deactivate all providers
-        for config in storage.values_mut() {
-            config.is_active = false;
-        }
-
-        // Activate the specified provider
-        if let Some(config) = storage.get_mut(provider) {
-            config.is_active = true;
+        for (name, metadata) in index.iter_mut() {
+            metadata.is_active = name == provider;
         }
 
-        self.save_storage(&storage)?;
+        self.save_index(&index)?;
 
         Ok(())
     }
 
     /// Update the last tested timestamp for a provider
     pub fn update_last_tested(&self, provider: &str, timestamp: i64) -> Result<()> {
-        let mut storage = self.load_storage()?;
+        let mut index = self.load_index()?;
 
-        if let Some(config) = storage.get_mut(provider) {
-            config.last_tested = Some(timestamp);
-            self.save_storage(&storage)?;
+        if let Some(metadata) = index.get_mut(provider) {
+            metadata.last_tested = Some(timestamp);
+            self.save_index(&index)?;
             Ok(())
         } else {
             anyhow::bail!("Provider not found: {}", provider);
         }
     }
+
+    /// Reads every stored key in plaintext
+    ///
+    /// Escape hatch for moving keys to another machine or password manager;
+    /// keys missing from the keychain are skipped.
+    pub fn export_keys(&self) -> Result<Vec<ExportedKey>> {
+        let mut exported = Vec::new();
+        let mut index: Vec<KeyMetadata> = self.load_index()?.into_values().collect();
+        index.sort_by(|a, b| a.provider.cmp(&b.provider));
+
+        for metadata in index {
+            match self.secrets.get(&account_name(&metadata.provider))? {
+                Some(api_key) => exported.push(ExportedKey {
+                    provider: metadata.provider,
+                    api_key,
+                    is_active: metadata.is_active,
+                    last_tested: metadata.last_tested,
+                }),
+                None => tracing::warn!("Skipping export of {}: key missing from keychain", metadata.provider),
+            }
+        }
+
+        Ok(exported)
+    }
+
+    /// Writes every stored key as plaintext JSON to `path`
+    ///
+    /// On Unix the file is created readable by the owner only. Returns the
+    /// number of exported keys.
+    pub fn export_keys_to_file(&self, path: &Path) -> Result<usize> {
+        let keys = self.export_keys()?;
+        let content = serde_json::to_string_pretty(&keys)
+            .context("Failed to serialize exported keys")?;
+
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+
+        let mut file = options.open(path).context("Failed to create export file")?;
+        std::io::Write::write_all(&mut file, content.as_bytes())
+            .context("Failed to write export file")?;
+
+        Ok(keys.len())
+    }
 }
 
 #[cfg(test)]
@@ -254,7 +377,7 @@ mod tests {
     #[test]
     fn test_save_and_load_key() {
         let temp_dir = TempDir::new().unwrap();
-        let storage = KeyStorage::new(temp_dir.path().to_path_buf()).unwrap();
+        let storage = KeyStorage::with_store(temp_dir.path().to_path_buf(), Box::new(MemoryStore::default())).unwrap();
 
         let provider = "openai";
         let api_key = "sk-test1234567890";
@@ -270,7 +393,7 @@ mod tests {
     #[test]
     fn test_delete_key() {
         let temp_dir = TempDir::new().unwrap();
-        let storage = KeyStorage::new(temp_dir.path().to_path_buf()).unwrap();
+        let storage = KeyStorage::with_store(temp_dir.path().to_path_buf(), Box::new(MemoryStore::default())).unwrap();
 
         let provider = "openai";
         let api_key = "sk-test1234567890";
@@ -286,7 +409,7 @@ mod tests {
     #[test]
     fn test_list_providers() {
         let temp_dir = TempDir::new().unwrap();
-        let storage = KeyStorage::new(temp_dir.path().to_path_buf()).unwrap();
+        let storage = KeyStorage::with_store(temp_dir.path().to_path_buf(), Box::new(MemoryStore::default())).unwrap();
 
         storage.save_key("openai", "sk-test1", true).unwrap();
         storage.save_key("anthropic", "sk-ant-test2", false).unwrap();
@@ -300,7 +423,7 @@ mod tests {
     #[test]
     fn test_active_provider() {
         let temp_dir = TempDir::new().unwrap();
-        let storage = KeyStorage::new(temp_dir.path().to_path_buf()).unwrap();
+        let storage = KeyStorage::with_store(temp_dir.path().to_path_buf(), Box::new(MemoryStore::default())).unwrap();
 
         storage.save_key("openai", "sk-test1", true).unwrap();
         storage.save_key("anthropic", "sk-ant-test2", false).unwrap();
@@ -313,4 +436,121 @@ mod tests {
         let active = storage.get_active_provider().unwrap();
         assert_eq!(active, Some("anthropic".to_string()));
     }
+
+    /// In-memory stand-in for the platform keychain
+    #[derive(Default, Clone)]
+    struct MemoryStore(std::sync::Arc<std::sync::Mutex<HashMap<String, String>>>);
+
+    impl SecretStore for MemoryStore {
+        fn get(&self, account: &str) -> Result<Option<String>> {
+            Ok(self.0.lock().unwrap().get(account).cloned())
+        }
+
+        fn set(&self, account: &str, secret: &str) -> Result<()> {
+            self.0.lock().unwrap().insert(account.to_string(), secret.to_string());
+            Ok(())
+        }
+
+        fn delete(&self, account: &str) -> Result<()> {
+            self.0.lock().unwrap().remove(account);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_keys_stored_per_provider() {
+        let temp_dir = TempDir::new().unwrap();
+        let secrets = MemoryStore::default();
+        let storage = KeyStorage::with_store(temp_dir.path().to_path_buf(), Box::new(secrets.clone())).unwrap();
+
+        storage.save_key("openai", "sk-test1", true).unwrap();
+        storage.save_key("anthropic", "sk-ant-test2", false).unwrap();
+
+        assert_eq!(secrets.get("api_key:openai").unwrap().as_deref(), Some("sk-test1"));
+        assert_eq!(storage.load_key("anthropic").unwrap(), "sk-ant-test2");
+
+        // Only metadata reaches the disk
+        let index = fs::read_to_string(temp_dir.path().join(INDEX_FILE)).unwrap();
+        assert!(!index.contains("sk-test1"));
+
+        storage.delete_key("openai").unwrap();
+        assert!(secrets.get("api_key:openai").unwrap().is_none());
+        assert!(storage.load_key("openai").is_err());
+    }
+
+    #[test]
+    fn test_migrates_legacy_file() {
+        use aes_gcm::aead::OsRng;
+        use rand::RngCore;
+
+        let temp_dir = TempDir::new().unwrap();
+        let secrets = MemoryStore::default();
+
+        // Write keys in the old encrypted format
+        let mut key = [0u8; 32];
+        OsRng.fill_bytes(&mut key);
+        secrets.set(ENCRYPTION_KEY_NAME, &hex::encode(key)).unwrap();
+        let cipher = Aes256Gcm::new_from_slice(&key).unwrap();
+        let nonce = [7u8; 12];
+        let encrypted_key = cipher.encrypt(Nonce::from_slice(&nonce), b"sk-legacy".as_ref()).unwrap();
+        let legacy = HashMap::from([(
+            "openai".to_string(),
+            EncryptedKeyConfig {
+                provider: "openai".to_string(),
+                encrypted_key,
+                nonce: nonce.to_vec(),
+                is_active: true,
+                last_tested: Some(42),
+            },
+        )]);
+        fs::write(
+            temp_dir.path().join(LEGACY_STORAGE_FILE),
+            serde_json::to_string(&legacy).unwrap(),
+        )
+        .unwrap();
+
+        let storage = KeyStorage::with_store(temp_dir.path().to_path_buf(), Box::new(secrets.clone())).unwrap();
+
+        assert_eq!(storage.load_key("openai").unwrap(), "sk-legacy");
+        assert_eq!(storage.get_active_provider().unwrap(), Some("openai".to_string()));
+        assert!(!temp_dir.path().join(LEGACY_STORAGE_FILE).exists());
+        assert!(secrets.get(ENCRYPTION_KEY_NAME).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_failed_migration_keeps_legacy_file() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join(LEGACY_STORAGE_FILE), "{}").unwrap();
+
+        // No encryption key in the keychain, so nothing can be decrypted
+        let storage = KeyStorage::with_store(temp_dir.path().to_path_buf(), Box::new(MemoryStore::default())).unwrap();
+
+        assert!(temp_dir.path().join(LEGACY_STORAGE_FILE).exists());
+        assert!(storage.list_providers().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_export_keys() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = KeyStorage::with_store(temp_dir.path().to_path_buf(), Box::new(MemoryStore::default())).unwrap();
+
+        storage.save_key("openai", "sk-test1", true).unwrap();
+        storage.save_key("anthropic", "sk-ant-test2", false).unwrap();
+
+        let export_path = temp_dir.path().join("export.json");
+        assert_eq!(storage.export_keys_to_file(&export_path).unwrap(), 2);
+
+        let exported: Vec<ExportedKey> =
+            serde_json::from_str(&fs::read_to_string(&export_path).unwrap()).unwrap();
+        assert_eq!(exported[0].provider, "anthropic");
+        assert_eq!(exported[1].api_key, "sk-test1");
+        assert!(exported[1].is_active);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&export_path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
 }
@@ -0,0 +1,597 @@
+//! Zip and tar archive inspection, extraction and creation
+//!
+//! Supports `.zip`, `.tar`, `.tar.gz` and `.tgz`. Extraction is guarded
+//! against archive bombs by [`ArchiveLimits`]: the entry count, the total
+//! extracted size and the compression ratio are capped. Sizes declared in the
+//! archive are checked up front, and the bytes actually written are counted
+//! too, since headers can lie. Entries with absolute paths or `..` components
+//! are rejected, and tar links are skipped rather than recreated.
+
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+
+/// Compression ratios are only checked past this many extracted bytes, so
+/// small, highly compressible files (text, sparse data) are fine
+const RATIO_CHECK_THRESHOLD: u64 = 16 * 1024 * 1024;
+
+/// Supported archive formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArchiveFormat {
+    Zip,
+    Tar,
+    TarGz,
+}
+
+impl ArchiveFormat {
+    /// Format implied by the file name
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_string_lossy().to_lowercase();
+        if name.ends_with(".zip") {
+            Some(ArchiveFormat::Zip)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(ArchiveFormat::TarGz)
+        } else if name.ends_with(".tar") {
+            Some(ArchiveFormat::Tar)
+        } else {
+            None
+        }
+    }
+}
+
+/// Caps applied when extracting (and listing) archives
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchiveLimits {
+    /// Most entries an archive may contain
+    pub max_entries: usize,
+    /// Most bytes an extraction may write
+    pub max_total_size: u64,
+    /// Highest allowed ratio of extracted bytes to archive size
+    pub max_ratio: u64,
+}
+
+impl Default for ArchiveLimits {
+    fn default() -> Self {
+        Self {
+            max_entries: 10_000,
+            max_total_size: 1024 * 1024 * 1024,
+            max_ratio: 100,
+        }
+    }
+}
+
+/// An entry in an archive
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ArchiveEntry {
+    pub path: String,
+    pub is_dir: bool,
+    /// Uncompressed size in bytes
+    pub size: u64,
+    /// Stored size; zip only
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compressed_size: Option<u64>,
+}
+
+/// Contents of an archive
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ArchiveListing {
+    pub archive: String,
+    pub format: ArchiveFormat,
+    pub entries: Vec<ArchiveEntry>,
+    /// Sum of the entries' uncompressed sizes
+    pub total_size: u64,
+}
+
+/// Result of an extraction
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ExtractOutcome {
+    pub destination: String,
+    /// Archive paths of the files and folders written
+    pub extracted: Vec<String>,
+    /// Links and other special entries that were not extracted
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub skipped: Vec<String>,
+    pub total_bytes: u64,
+}
+
+/// Result of creating an archive
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CreateOutcome {
+    pub archive: String,
+    pub format: ArchiveFormat,
+    /// Files added (folders not counted)
+    pub files: usize,
+    /// Uncompressed bytes added
+    pub total_bytes: u64,
+    /// Size of the archive file
+    pub archive_size: u64,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ArchiveError {
+    #[error("Path not found: {0}")]
+    NotFound(String),
+
+    #[error("Unsupported archive format: {0} (expected .zip, .tar, .tar.gz or .tgz)")]
+    UnsupportedFormat(String),
+
+    #[error("Archive already exists: {0}")]
+    AlreadyExists(String),
+
+    #[error("Entry not in archive: {0}")]
+    EntryNotFound(String),
+
+    #[error("Unsafe entry path: {0}")]
+    UnsafePath(String),
+
+    #[error("Archive has more than {0} entries")]
+    TooManyEntries(usize),
+
+    #[error("Extracting would write more than {limit} bytes")]
+    TooLarge { limit: u64 },
+
+    #[error("Compression ratio above {limit}:1, refusing to extract a possible archive bomb")]
+    SuspiciousRatio { limit: u64 },
+
+    #[error("Invalid archive: {0}")]
+    Invalid(String),
+
+    #[error("I/O error on {path}: {reason}")]
+    Io { path: String, reason: String },
+}
+
+/// List the entries of an archive
+pub fn list(archive: &Path, limits: &ArchiveLimits) -> Result<ArchiveListing, ArchiveError> {
+    let format = detect(archive)?;
+    let mut entries = Vec::new();
+
+    match format {
+        ArchiveFormat::Zip => {
+            let mut zip = open_zip(archive)?;
+            if zip.len() > limits.max_entries {
+                return Err(ArchiveError::TooManyEntries(limits.max_entries));
+            }
+            for i in 0..zip.len() {
+                let file = zip.by_index_raw(i).map_err(invalid)?;
+                entries.push(ArchiveEntry {
+                    path: file.name().to_string(),
+                    is_dir: file.is_dir(),
+                    size: file.size(),
+                    compressed_size: Some(file.compressed_size()),
+                });
+            }
+        }
+        ArchiveFormat::Tar | ArchiveFormat::TarGz => {
+            let mut tar = open_tar(archive, format)?;
+            for entry in tar.entries().map_err(|e| io_error(archive, e))? {
+                if entries.len() >= limits.max_entries {
+                    return Err(ArchiveError::TooManyEntries(limits.max_entries));
+                }
+                let entry = entry.map_err(|e| io_error(archive, e))?;
+                let path = entry.path().map_err(|e| io_error(archive, e))?;
+                entries.push(ArchiveEntry {
+                    path: path.to_string_lossy().into_owned(),
+                    is_dir: entry.header().entry_type().is_dir(),
+                    size: entry.header().size().unwrap_or(0),
+                    compressed_size: None,
+                });
+            }
+        }
+    }
+
+    Ok(ArchiveListing {
+        archive: archive.display().to_string(),
+        format,
+        total_size: entries.iter().map(|e| e.size).sum(),
+        entries,
+    })
+}
+
+/// Extract an archive into `destination`, or only the entries named in
+/// `selected` (a folder selects everything under it). Existing files are
+/// left alone unless `overwrite` is set.
+pub fn extract(
+    archive: &Path,
+    destination: &Path,
+    selected: &[String],
+    overwrite: bool,
+    limits: &ArchiveLimits,
+) -> Result<ExtractOutcome, ArchiveError> {
+    let format = detect(archive)?;
+    let archive_size = std::fs::metadata(archive).map_err(|e| io_error(archive, e))?.len();
+
+    // Check declared sizes before writing anything
+    let listing = list(archive, limits)?;
+    let wanted: Vec<&ArchiveEntry> = listing.entries.iter().filter(|e| is_selected(&e.path, selected)).collect();
+    for name in selected {
+        if !listing.entries.iter().any(|e| is_selected(&e.path, std::slice::from_ref(name))) {
+            return Err(ArchiveError::EntryNotFound(name.clone()));
+        }
+    }
+    let declared: u64 = wanted.iter().map(|e| e.size).sum();
+    let mut budget = Budget::new(archive_size, limits);
+    budget.check(declared)?;
+
+    std::fs::create_dir_all(destination).map_err(|e| io_error(destination, e))?;
+    let mut outcome = ExtractOutcome {
+        destination: destination.display().to_string(),
+        extracted: Vec::new(),
+        skipped: Vec::new(),
+        total_bytes: 0,
+    };
+
+    match format {
+        ArchiveFormat::Zip => {
+            let mut zip = open_zip(archive)?;
+            for i in 0..zip.len() {
+                let mut file = zip.by_index(i).map_err(invalid)?;
+                let name = file.name().to_string();
+                if !is_selected(&name, selected) {
+                    continue;
+                }
+                let relative = file
+                    .enclosed_name()
+                    .map(Path::to_path_buf)
+                    .ok_or_else(|| ArchiveError::UnsafePath(name.clone()))?;
+                let target = destination.join(relative);
+                if file.is_dir() {
+                    std::fs::create_dir_all(&target).map_err(|e| io_error(&target, e))?;
+                } else if write_entry(&mut file, &target, overwrite, &mut budget)? {
+                    outcome.skipped.push(name);
+                    continue;
+                }
+                outcome.extracted.push(name);
+            }
+        }
+        ArchiveFormat::Tar | ArchiveFormat::TarGz => {
+            let mut tar = open_tar(archive, format)?;
+            for entry in tar.entries().map_err(|e| io_error(archive, e))? {
+                let mut entry = entry.map_err(|e| io_error(archive, e))?;
+                let path = entry.path().map_err(|e| io_error(archive, e))?.into_owned();
+                let name = path.to_string_lossy().into_owned();
+                if !is_selected(&name, selected) {
+                    continue;
+                }
+                let relative = safe_relative(&path).ok_or_else(|| ArchiveError::UnsafePath(name.clone()))?;
+                let target = destination.join(relative);
+
+                let entry_type = entry.header().entry_type();
+                if entry_type.is_dir() {
+                    std::fs::create_dir_all(&target).map_err(|e| io_error(&target, e))?;
+                } else if !entry_type.is_file() || write_entry(&mut entry, &target, overwrite, &mut budget)? {
+                    outcome.skipped.push(name);
+                    continue;
+                }
+                outcome.extracted.push(name);
+            }
+        }
+    }
+
+    outcome.total_bytes = budget.written;
+    tracing::info!(
+        "Extracted {} entries ({} bytes) from {} to {}",
+        outcome.extracted.len(),
+        outcome.total_bytes,
+        archive.display(),
+        destination.display()
+    );
+    Ok(outcome)
+}
+
+/// Create an archive at `archive` from files and folders; each path is
+/// stored under its own name, folders recursively
+pub fn create(archive: &Path, paths: &[PathBuf], overwrite: bool) -> Result<CreateOutcome, ArchiveError> {
+    let format = detect(archive)?;
+    if archive.exists() && !overwrite {
+        return Err(ArchiveError::AlreadyExists(archive.display().to_string()));
+    }
+
+    // (path on disk, name in the archive)
+    let mut files: Vec<(PathBuf, String)> = Vec::new();
+    let mut dirs: Vec<(PathBuf, String)> = Vec::new();
+    for path in paths {
+        let metadata = std::fs::metadata(path).map_err(|_| ArchiveError::NotFound(path.display().to_string()))?;
+        let base = path.parent().unwrap_or_else(|| Path::new(""));
+        if !metadata.is_dir() {
+            files.push((path.clone(), archive_name(path, base)));
+            continue;
+        }
+        for entry in walkdir::WalkDir::new(path).sort_by_file_name() {
+            let entry = entry.map_err(|e| io_error(path, std::io::Error::other(e)))?;
+            // Don't add the archive to itself when it's created inside a folder being archived
+            if entry.path() == archive {
+                continue;
+            }
+            let name = archive_name(entry.path(), base);
+            if entry.file_type().is_dir() {
+                dirs.push((entry.path().to_path_buf(), name));
+            } else if entry.file_type().is_file() {
+                files.push((entry.path().to_path_buf(), name));
+            }
+        }
+    }
+
+    if let Some(parent) = archive.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent).map_err(|e| io_error(parent, e))?;
+    }
+    let out = File::create(archive).map_err(|e| io_error(archive, e))?;
+    let mut total_bytes = 0;
+
+    match format {
+        ArchiveFormat::Zip => {
+            let mut zip = zip::ZipWriter::new(out);
+            let options =
+                zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+            for (_, name) in &dirs {
+                zip.add_directory(name.as_str(), options).map_err(invalid)?;
+            }
+            for (path, name) in &files {
+                zip.start_file(name.as_str(), options).map_err(invalid)?;
+                let mut input = File::open(path).map_err(|e| io_error(path, e))?;
+                total_bytes += std::io::copy(&mut input, &mut zip).map_err(|e| io_error(path, e))?;
+            }
+            zip.finish().map_err(invalid)?;
+        }
+        ArchiveFormat::Tar => {
+            let mut tar = tar::Builder::new(out);
+            total_bytes = append_to_tar(&mut tar, &dirs, &files)?;
+            tar.into_inner().map_err(|e| io_error(archive, e))?;
+        }
+        ArchiveFormat::TarGz => {
+            let mut tar = tar::Builder::new(GzEncoder::new(out, Compression::default()));
+            total_bytes = append_to_tar(&mut tar, &dirs, &files)?;
+            let gz = tar.into_inner().map_err(|e| io_error(archive, e))?;
+            gz.finish().map_err(|e| io_error(archive, e))?;
+        }
+    }
+
+    let archive_size = std::fs::metadata(archive).map_err(|e| io_error(archive, e))?.len();
+    tracing::info!("Created {} with {} files", archive.display(), files.len());
+    Ok(CreateOutcome {
+        archive: archive.display().to_string(),
+        format,
+        files: files.len(),
+        total_bytes,
+        archive_size,
+    })
+}
+
+/// Add folders and files to a tar stream, returning the bytes added
+fn append_to_tar<W: Write>(
+    tar: &mut tar::Builder<W>,
+    dirs: &[(PathBuf, String)],
+    files: &[(PathBuf, String)],
+) -> Result<u64, ArchiveError> {
+    tar.follow_symlinks(false);
+    for (path, name) in dirs {
+        tar.append_dir(name, path).map_err(|e| io_error(path, e))?;
+    }
+    let mut total_bytes = 0;
+    for (path, name) in files {
+        let mut input = File::open(path).map_err(|e| io_error(path, e))?;
+        tar.append_file(name, &mut input).map_err(|e| io_error(path, e))?;
+        total_bytes += input.metadata().map(|m| m.len()).unwrap_or(0);
+    }
+    Ok(total_bytes)
+}
+
+/// Running total of extracted bytes, checked against the limits
+struct Budget {
+    archive_size: u64,
+    max_total_size: u64,
+    max_ratio: u64,
+    written: u64,
+}
+
+impl Budget {
+    fn new(archive_size: u64, limits: &ArchiveLimits) -> Self {
+        Self {
+            archive_size: archive_size.max(1),
+            max_total_size: limits.max_total_size,
+            max_ratio: limits.max_ratio,
+            written: 0,
+        }
+    }
+
+    /// Whether `total` extracted bytes stay within the limits
+    fn check(&self, total: u64) -> Result<(), ArchiveError> {
+        if total > self.max_total_size {
+            return Err(ArchiveError::TooLarge { limit: self.max_total_size });
+        }
+        if total > RATIO_CHECK_THRESHOLD && total / self.archive_size > self.max_ratio {
+            return Err(ArchiveError::SuspiciousRatio { limit: self.max_ratio });
+        }
+        Ok(())
+    }
+
+    /// Most bytes the whole extraction may write
+    fn cap(&self) -> u64 {
+        let by_ratio = self.archive_size.saturating_mul(self.max_ratio).max(RATIO_CHECK_THRESHOLD);
+        self.max_total_size.min(by_ratio)
+    }
+
+    /// Bytes that can still be written before a limit is hit
+    fn remaining(&self) -> u64 {
+        self.cap().saturating_sub(self.written)
+    }
+
+    /// Error for an extraction that ran past [`Budget::cap`]
+    fn exceeded(&self) -> ArchiveError {
+        if self.cap() == self.max_total_size {
+            ArchiveError::TooLarge { limit: self.max_total_size }
+        } else {
+            ArchiveError::SuspiciousRatio { limit: self.max_ratio }
+        }
+    }
+}
+
+/// Write one file entry, counting its real size against the budget.
+/// Returns `true` if it was skipped because the target exists.
+fn write_entry(reader: &mut impl Read, target: &Path, overwrite: bool, budget: &mut Budget) -> Result<bool, ArchiveError> {
+    if target.exists() && !overwrite {
+        return Ok(true);
+    }
+    if let Some(parent) = target.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| io_error(parent, e))?;
+    }
+
+    let mut out = File::create(target).map_err(|e| io_error(target, e))?;
+    // Read one byte past the budget to detect entries larger than declared
+    let mut limited = reader.take(budget.remaining() + 1);
+    let written = std::io::copy(&mut limited, &mut out).map_err(|e| io_error(target, e))?;
+    budget.written += written;
+
+    if limited.limit() == 0 {
+        drop(out);
+        let _ = std::fs::remove_file(target);
+        return Err(budget.exceeded());
+    }
+    Ok(false)
+}
+
+fn detect(archive: &Path) -> Result<ArchiveFormat, ArchiveError> {
+    ArchiveFormat::from_path(archive).ok_or_else(|| ArchiveError::UnsupportedFormat(archive.display().to_string()))
+}
+
+fn open_zip(archive: &Path) -> Result<zip::ZipArchive<File>, ArchiveError> {
+    let file = File::open(archive).map_err(|_| ArchiveError::NotFound(archive.display().to_string()))?;
+    zip::ZipArchive::new(file).map_err(invalid)
+}
+
+fn open_tar(archive: &Path, format: ArchiveFormat) -> Result<tar::Archive<Box<dyn Read>>, ArchiveError> {
+    let file = File::open(archive).map_err(|_| ArchiveError::NotFound(archive.display().to_string()))?;
+    let reader: Box<dyn Read> = match format {
+        ArchiveFormat::TarGz => Box::new(GzDecoder::new(file)),
+        _ => Box::new(file),
+    };
+    Ok(tar::Archive::new(reader))
+}
+
+/// Whether an entry is one of `selected` or inside a selected folder; an
+/// empty selection selects everything
+fn is_selected(entry: &str, selected: &[String]) -> bool {
+    let entry = entry.trim_end_matches('/');
+    selected.is_empty()
+        || selected.iter().any(|s| {
+            let s = s.trim_end_matches('/');
+            entry == s || entry.strip_prefix(s).is_some_and(|rest| rest.starts_with('/'))
+        })
+}
+
+/// `path` if it only contains normal components
+fn safe_relative(path: &Path) -> Option<PathBuf> {
+    let mut relative = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => relative.push(part),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    (!relative.as_os_str().is_empty()).then_some(relative)
+}
+
+/// Name of `path` inside an archive: relative to `base`, `/`-separated
+fn archive_name(path: &Path, base: &Path) -> String {
+    let relative = path.strip_prefix(base).unwrap_or(path);
+    relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn invalid(e: zip::result::ZipError) -> ArchiveError {
+    ArchiveError::Invalid(e.to_string())
+}
+
+fn io_error(path: &Path, e: std::io::Error) -> ArchiveError {
+    ArchiveError::Io {
+        path: path.display().to_string(),
+        reason: e.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn fixture(dir: &Path) -> PathBuf {
+        let project = dir.join("project");
+        std::fs::create_dir_all(project.join("src")).unwrap();
+        std::fs::write(project.join("README.md"), "# readme").unwrap();
+        std::fs::write(project.join("src").join("main.rs"), "fn main() {}").unwrap();
+        project
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for name in ["out.zip", "out.tar", "out.tar.gz"] {
+            let temp_dir = TempDir::new().unwrap();
+            let dir = temp_dir.path();
+            let project = fixture(&dir);
+            let archive = dir.join(name);
+
+            let created = create(&archive, std::slice::from_ref(&project), false).unwrap();
+            assert_eq!(created.files, 2);
+            assert!(matches!(create(&archive, &[project], false), Err(ArchiveError::AlreadyExists(_))));
+
+            let listing = list(&archive, &ArchiveLimits::default()).unwrap();
+            let paths: Vec<&str> = listing.entries.iter().map(|e| e.path.trim_end_matches('/')).collect();
+            assert!(paths.contains(&"project/src/main.rs"), "{}: {:?}", name, paths);
+            assert_eq!(listing.total_size, 20);
+
+            let out = dir.join("out");
+            let extracted = extract(&archive, &out, &["project/src".to_string()], false, &ArchiveLimits::default()).unwrap();
+            assert_eq!(std::fs::read_to_string(out.join("project/src/main.rs")).unwrap(), "fn main() {}");
+            assert!(!out.join("project/README.md").exists());
+            assert_eq!(extracted.total_bytes, 12);
+
+            assert!(matches!(
+                extract(&archive, &out, &["missing".to_string()], false, &ArchiveLimits::default()),
+                Err(ArchiveError::EntryNotFound(_))
+            ));
+
+            std::fs::remove_dir_all(dir).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_limits() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        std::fs::write(dir.join("zeros.bin"), vec![0u8; 4 * 1024 * 1024]).unwrap();
+        let archive = dir.join("bomb.zip");
+        create(&archive, &[dir.join("zeros.bin")], false).unwrap();
+
+        let small = ArchiveLimits {
+            max_total_size: 1024 * 1024,
+            ..Default::default()
+        };
+        assert!(matches!(
+            extract(&archive, &dir.join("out"), &[], false, &small),
+            Err(ArchiveError::TooLarge { .. })
+        ));
+
+        let few = ArchiveLimits {
+            max_entries: 0,
+            ..Default::default()
+        };
+        assert!(matches!(list(&archive, &few), Err(ArchiveError::TooManyEntries(0))));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_rejects_unsafe_paths() {
+        assert_eq!(safe_relative(Path::new("a/./b")), Some(PathBuf::from("a/b")));
+        assert_eq!(safe_relative(Path::new("../etc/passwd")), None);
+        assert_eq!(safe_relative(Path::new("/etc/passwd")), None);
+        assert!(is_selected("src/main.rs", &["src/".to_string()]));
+        assert!(!is_selected("srcs/main.rs", &["src".to_string()]));
+    }
+}
@@ -0,0 +1,432 @@
+//! Chat attachments
+//!
+//! Files dropped into a conversation are stored under `~/.skhoot/attachments`,
+//! their text is extracted (plain text, PDF, and images through OCR when
+//! `tesseract` is installed) and split into overlapping chunks the agent can
+//! read with the `read_attachment` tool.
+//!
+//! Layout per attachment: `<id>/meta.json`, `<id>/original` and `<id>/chunks.json`.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Arc;
+
+/// Largest accepted upload
+pub const MAX_ATTACHMENT_BYTES: usize = 25 * 1024 * 1024;
+/// Target chunk length in characters
+const CHUNK_CHARS: usize = 4000;
+/// Characters repeated at the start of the next chunk
+const CHUNK_OVERLAP: usize = 200;
+
+/// Broad type of an attachment
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AttachmentKind {
+    Text,
+    Pdf,
+    Image,
+    Other,
+}
+
+/// Result of text extraction
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ExtractionStatus {
+    Extracted,
+    /// No extractor for this type (or OCR unavailable)
+    Unsupported,
+    Failed { error: String },
+}
+
+/// Stored attachment metadata
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Attachment {
+    pub id: String,
+    pub file_name: String,
+    pub mime_type: String,
+    pub kind: AttachmentKind,
+    pub size_bytes: u64,
+    pub sha256: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub conversation_id: Option<String>,
+    pub extraction: ExtractionStatus,
+    /// Length of the extracted text in characters
+    pub text_chars: usize,
+    pub chunk_count: usize,
+    pub created_at: i64,
+}
+
+/// A slice of an attachment's extracted text
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AttachmentChunk {
+    pub index: usize,
+    /// Offset of the chunk in the extracted text, in characters
+    pub start_char: usize,
+    pub text: String,
+}
+
+/// Attachment errors
+#[derive(Debug, thiserror::Error)]
+pub enum AttachmentError {
+    #[error("Attachment not found: {0}")]
+    NotFound(String),
+
+    #[error("Attachment too large: {0} bytes (max {MAX_ATTACHMENT_BYTES})")]
+    TooLarge(usize),
+
+    #[error("Invalid attachment: {0}")]
+    Invalid(String),
+
+    #[error("Attachment storage error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// File-backed attachment store
+#[derive(Debug)]
+pub struct AttachmentStore {
+    root: PathBuf,
+    ocr_enabled: bool,
+}
+
+lazy_static::lazy_static! {
+    static ref GLOBAL_STORE: Arc<AttachmentStore> = Arc::new(
+        AttachmentStore::new(
+            dirs::home_dir()
+                .unwrap_or_else(|| PathBuf::from("."))
+                .join(".skhoot")
+                .join("attachments"),
+        )
+        .with_ocr(tesseract_available()),
+    );
+}
+
+impl AttachmentStore {
+    pub fn new(root: PathBuf) -> Self {
+        Self {
+            root,
+            ocr_enabled: false,
+        }
+    }
+
+    /// Extract text from images with `tesseract`
+    pub fn with_ocr(mut self, enabled: bool) -> Self {
+        self.ocr_enabled = enabled;
+        self
+    }
+
+    /// Shared store at `~/.skhoot/attachments`, with OCR if `tesseract` is installed
+    pub fn global() -> Arc<AttachmentStore> {
+        GLOBAL_STORE.clone()
+    }
+
+    /// Store an upload and extract its text
+    pub fn store(
+        &self,
+        file_name: &str,
+        bytes: &[u8],
+        conversation_id: Option<String>,
+    ) -> Result<Attachment, AttachmentError> {
+        if bytes.len() > MAX_ATTACHMENT_BYTES {
+            return Err(AttachmentError::TooLarge(bytes.len()));
+        }
+        let file_name = Path::new(file_name)
+            .file_name()
+            .and_then(|n| n.to_str())
+            .filter(|n| !n.is_empty())
+            .ok_or_else(|| AttachmentError::Invalid("Missing file name".to_string()))?
+            .to_string();
+
+        let mime_type = mime_guess::from_path(&file_name)
+            .first_raw()
+            .map(str::to_string)
+            .unwrap_or_else(|| sniff_mime(bytes).to_string());
+        let kind = kind_of(&mime_type, bytes);
+
+        let id = uuid::Uuid::new_v4().to_string();
+        let dir = self.root.join(&id);
+        std::fs::create_dir_all(&dir)?;
+        let original = dir.join("original");
+        std::fs::write(&original, bytes)?;
+
+        let (extraction, text) = match self.extract_text(kind, bytes, &original) {
+            Ok(Some(text)) => (ExtractionStatus::Extracted, text),
+            Ok(None) => (ExtractionStatus::Unsupported, String::new()),
+            Err(error) => (ExtractionStatus::Failed { error }, String::new()),
+        };
+        let chunks = chunk_text(&text, CHUNK_CHARS, CHUNK_OVERLAP);
+
+        let attachment = Attachment {
+            id,
+            file_name,
+            mime_type,
+            kind,
+            size_bytes: bytes.len() as u64,
+            sha256: hex::encode(Sha256::digest(bytes)),
+            conversation_id,
+            extraction,
+            text_chars: text.chars().count(),
+            chunk_count: chunks.len(),
+            created_at: chrono::Utc::now().timestamp(),
+        };
+
+        write_json(&dir.join("chunks.json"), &chunks)?;
+        write_json(&dir.join("meta.json"), &attachment)?;
+        tracing::info!(
+            "Stored attachment {} ({}, {} chunks)",
+            attachment.id,
+            attachment.file_name,
+            attachment.chunk_count
+        );
+
+        Ok(attachment)
+    }
+
+    /// Attachment metadata
+    pub fn get(&self, id: &str) -> Result<Attachment, AttachmentError> {
+        read_json(&self.attachment_dir(id)?.join("meta.json"))
+            .ok_or_else(|| AttachmentError::NotFound(id.to_string()))
+    }
+
+    /// Attachments, newest first, optionally only those of a conversation
+    pub fn list(&self, conversation_id: Option<&str>) -> Vec<Attachment> {
+        let mut attachments: Vec<Attachment> = std::fs::read_dir(&self.root)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter_map(|entry| read_json::<Attachment>(&entry.path().join("meta.json")))
+            .filter(|a| conversation_id.is_none_or(|id| a.conversation_id.as_deref() == Some(id)))
+            .collect();
+        attachments.sort_by_key(|a| std::cmp::Reverse(a.created_at));
+        attachments
+    }
+
+    /// Extracted text chunks of an attachment
+    pub fn chunks(&self, id: &str) -> Result<Vec<AttachmentChunk>, AttachmentError> {
+        let dir = self.attachment_dir(id)?;
+        if !dir.join("meta.json").exists() {
+            return Err(AttachmentError::NotFound(id.to_string()));
+        }
+        Ok(read_json(&dir.join("chunks.json")).unwrap_or_default())
+    }
+
+    /// One chunk of an attachment's extracted text
+    pub fn chunk(&self, id: &str, index: usize) -> Result<AttachmentChunk, AttachmentError> {
+        let chunks = self.chunks(id)?;
+        let count = chunks.len();
+        chunks.into_iter().nth(index).ok_or_else(|| {
+            AttachmentError::Invalid(format!("Chunk {} out of range ({} chunks)", index, count))
+        })
+    }
+
+    /// Original file content
+    pub fn read_original(&self, id: &str) -> Result<(Attachment, Vec<u8>), AttachmentError> {
+        let attachment = self.get(id)?;
+        let bytes = std::fs::read(self.attachment_dir(id)?.join("original"))?;
+        Ok((attachment, bytes))
+    }
+
+    /// Delete an attachment. Returns whether it existed.
+    pub fn delete(&self, id: &str) -> Result<bool, AttachmentError> {
+        let dir = self.attachment_dir(id)?;
+        if !dir.exists() {
+            return Ok(false);
+        }
+        std::fs::remove_dir_all(dir)?;
+        Ok(true)
+    }
+
+    fn attachment_dir(&self, id: &str) -> Result<PathBuf, AttachmentError> {
+        uuid::Uuid::parse_str(id).map_err(|_| AttachmentError::NotFound(id.to_string()))?;
+        Ok(self.root.join(id))
+    }
+
+    fn extract_text(&self, kind: AttachmentKind, bytes: &[u8], path: &Path) -> Result<Option<String>, String> {
+        match kind {
+            AttachmentKind::Text => {
+                let (text, _, _) = encoding_rs::UTF_8.decode(bytes);
+                Ok(Some(text.into_owned()))
+            }
+            AttachmentKind::Pdf => {
+                // pdf-extract panics on some malformed documents
+                let bytes = bytes.to_vec();
+                std::panic::catch_unwind(move || pdf_extract::extract_text_from_mem(&bytes))
+                    .map_err(|_| "PDF parser crashed".to_string())?
+                    .map(Some)
+                    .map_err(|e| format!("PDF extraction failed: {}", e))
+            }
+            AttachmentKind::Image if self.ocr_enabled => ocr(path).map(Some),
+            AttachmentKind::Image | AttachmentKind::Other => Ok(None),
+        }
+    }
+}
+
+/// Split text into chunks of about `size` characters overlapping by `overlap`,
+/// preferring to break at paragraph or line boundaries
+pub fn chunk_text(text: &str, size: usize, overlap: usize) -> Vec<AttachmentChunk> {
+    let chars: Vec<char> = text.chars().collect();
+    let overlap = overlap.min(size / 2);
+    let mut chunks = Vec::new();
+    let mut start = 0;
+
+    while start < chars.len() {
+        let mut end = (start + size).min(chars.len());
+        if end < chars.len() {
+            let window = &chars[start + size / 2..end];
+            let boundary = find_last(window, &['\n', '\n'])
+                .or_else(|| window.iter().rposition(|&c| c == '\n'))
+                .or_else(|| window.iter().rposition(|c| c.is_whitespace()));
+            if let Some(pos) = boundary {
+                end = start + size / 2 + pos + 1;
+            }
+        }
+
+        let text: String = chars[start..end].iter().collect();
+        if !text.trim().is_empty() {
+            chunks.push(AttachmentChunk {
+                index: chunks.len(),
+                start_char: start,
+                text,
+            });
+        }
+
+        if end == chars.len() {
+            break;
+        }
+        start = end.saturating_sub(overlap).max(start + 1);
+    }
+
+    chunks
+}
+
+/// Index of the last character of the last occurrence of `pattern`
+fn find_last(haystack: &[char], pattern: &[char]) -> Option<usize> {
+    haystack
+        .windows(pattern.len())
+        .rposition(|w| w == pattern)
+        .map(|i| i + pattern.len() - 1)
+}
+
+fn kind_of(mime_type: &str, bytes: &[u8]) -> AttachmentKind {
+    if mime_type == "application/pdf" {
+        AttachmentKind::Pdf
+    } else if mime_type.starts_with("image/") {
+        AttachmentKind::Image
+    } else if mime_type.starts_with("text/")
+        || matches!(mime_type, "application/json" | "application/xml" | "application/javascript" | "application/toml")
+        || std::str::from_utf8(&bytes[..bytes.len().min(8192)]).is_ok()
+    {
+        AttachmentKind::Text
+    } else {
+        AttachmentKind::Other
+    }
+}
+
+fn sniff_mime(bytes: &[u8]) -> &'static str {
+    match bytes {
+        [0x25, 0x50, 0x44, 0x46, ..] => "application/pdf",
+        [0x89, 0x50, 0x4E, 0x47, ..] => "image/png",
+        [0xFF, 0xD8, 0xFF, ..] => "image/jpeg",
+        [0x47, 0x49, 0x46, 0x38, ..] => "image/gif",
+        _ => "application/octet-stream",
+    }
+}
+
+fn tesseract_available() -> bool {
+    Command::new("tesseract")
+        .arg("--version")
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false)
+}
+
+fn ocr(path: &Path) -> Result<String, String> {
+    let output = Command::new("tesseract")
+        .arg(path)
+        .arg("stdout")
+        .output()
+        .map_err(|e| format!("Failed to run tesseract: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "OCR failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn read_json<T: serde::de::DeserializeOwned>(path: &Path) -> Option<T> {
+    let content = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn write_json<T: Serialize>(path: &Path, value: &T) -> std::io::Result<()> {
+    let content = serde_json::to_string_pretty(value)?;
+    std::fs::write(path, content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_store_text_attachment() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = AttachmentStore::new(temp_dir.path().to_path_buf());
+        let text = "line of notes\n".repeat(600);
+
+        let attachment = store
+            .store("../notes.txt", text.as_bytes(), Some("conv-1".to_string()))
+            .unwrap();
+        assert_eq!(attachment.file_name, "notes.txt");
+        assert_eq!(attachment.kind, AttachmentKind::Text);
+        assert_eq!(attachment.extraction, ExtractionStatus::Extracted);
+        assert!(attachment.chunk_count > 1);
+
+        let first = store.chunk(&attachment.id, 0).unwrap();
+        assert!(first.text.starts_with("line of notes"));
+        assert_eq!(store.list(Some("conv-1")).len(), 1);
+        assert!(store.list(Some("conv-2")).is_empty());
+
+        assert!(store.delete(&attachment.id).unwrap());
+        assert!(matches!(store.get(&attachment.id), Err(AttachmentError::NotFound(_))));
+    }
+
+    #[test]
+    fn test_images_without_ocr_are_stored_unextracted() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = AttachmentStore::new(temp_dir.path().to_path_buf());
+        let png = [0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A, 0x00];
+
+        let attachment = store.store("shot.png", &png, None).unwrap();
+        assert_eq!(attachment.kind, AttachmentKind::Image);
+        assert_eq!(attachment.extraction, ExtractionStatus::Unsupported);
+        assert_eq!(attachment.chunk_count, 0);
+        assert_eq!(store.read_original(&attachment.id).unwrap().1, png);
+    }
+
+    #[test]
+    fn test_chunks_overlap_and_cover_text() {
+        let text: String = (0..50).map(|i| format!("paragraph {}\n\n", i)).collect();
+        let chunks = chunk_text(&text, 100, 20);
+
+        assert!(chunks.len() > 1);
+        assert_eq!(chunks[0].start_char, 0);
+        for pair in chunks.windows(2) {
+            let end = pair[0].start_char + pair[0].text.chars().count();
+            assert!(pair[1].start_char < end, "chunks should overlap");
+            assert!(pair[0].text.ends_with('\n'), "chunks should break at line ends");
+        }
+        let last = chunks.last().unwrap();
+        assert_eq!(last.start_char + last.text.chars().count(), text.chars().count());
+    }
+
+    #[test]
+    fn test_ids_cannot_escape_store() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = AttachmentStore::new(temp_dir.path().join("store"));
+        assert!(matches!(store.get("../secret"), Err(AttachmentError::NotFound(_))));
+    }
+}
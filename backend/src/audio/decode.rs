@@ -0,0 +1,178 @@
+//! Decoding of incoming audio chunks
+//!
+//! Whisper expects 32-bit float samples at 16 kHz, mono. Chunks arrive as
+//! WAV files (the webview recorder's output) or raw little-endian PCM at the
+//! recorder's own rate, so they are downmixed and resampled here.
+
+use serde::{Deserialize, Serialize};
+use std::io::Cursor;
+
+use super::AudioError;
+
+/// Sample rate Whisper models are trained on
+pub const WHISPER_SAMPLE_RATE: u32 = 16_000;
+
+/// Encoding of the chunks sent to a session
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChunkFormat {
+    /// Complete WAV files; the header gives rate and channels
+    #[default]
+    Wav,
+    /// Raw signed 16-bit little-endian samples, channels interleaved
+    PcmS16le,
+    /// Raw 32-bit float little-endian samples, channels interleaved
+    PcmF32le,
+}
+
+/// Layout of raw PCM chunks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PcmSpec {
+    pub sample_rate: u32,
+    pub channels: u16,
+}
+
+impl Default for PcmSpec {
+    fn default() -> Self {
+        Self {
+            sample_rate: WHISPER_SAMPLE_RATE,
+            channels: 1,
+        }
+    }
+}
+
+/// Decode one chunk to 16 kHz mono samples
+pub fn decode_chunk(bytes: &[u8], format: ChunkFormat, spec: PcmSpec) -> Result<Vec<f32>, AudioError> {
+    let (interleaved, spec) = match format {
+        ChunkFormat::Wav => read_wav(bytes)?,
+        ChunkFormat::PcmS16le => (read_pcm::<2>(bytes, spec, |b| i16::from_le_bytes(b) as f32 / 32768.0)?, spec),
+        ChunkFormat::PcmF32le => (read_pcm::<4>(bytes, spec, f32::from_le_bytes)?, spec),
+    };
+    if spec.channels == 0 || spec.sample_rate == 0 {
+        return Err(AudioError::InvalidAudio("sample rate and channels must be positive".to_string()));
+    }
+    Ok(resample(&downmix(&interleaved, spec.channels), spec.sample_rate))
+}
+
+/// Encode 16 kHz mono samples as a 16-bit WAV file, for provider APIs
+pub fn encode_wav(samples: &[f32]) -> Result<Vec<u8>, AudioError> {
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate: WHISPER_SAMPLE_RATE,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let mut out = Cursor::new(Vec::new());
+    let mut writer = hound::WavWriter::new(&mut out, spec).map_err(|e| AudioError::Engine(e.to_string()))?;
+    for sample in samples {
+        let value = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+        writer.write_sample(value).map_err(|e| AudioError::Engine(e.to_string()))?;
+    }
+    writer.finalize().map_err(|e| AudioError::Engine(e.to_string()))?;
+    Ok(out.into_inner())
+}
+
+fn read_wav(bytes: &[u8]) -> Result<(Vec<f32>, PcmSpec), AudioError> {
+    let reader = hound::WavReader::new(Cursor::new(bytes)).map_err(|e| AudioError::InvalidAudio(e.to_string()))?;
+    let header = reader.spec();
+    let spec = PcmSpec {
+        sample_rate: header.sample_rate,
+        channels: header.channels,
+    };
+    let samples = match header.sample_format {
+        hound::SampleFormat::Float => reader.into_samples::<f32>().collect::<Result<Vec<_>, _>>(),
+        hound::SampleFormat::Int => {
+            let scale = (1i64 << (header.bits_per_sample.clamp(1, 32) - 1)) as f32;
+            reader
+                .into_samples::<i32>()
+                .map(|s| s.map(|s| s as f32 / scale))
+                .collect::<Result<Vec<_>, _>>()
+        }
+    }
+    .map_err(|e| AudioError::InvalidAudio(e.to_string()))?;
+    Ok((samples, spec))
+}
+
+fn read_pcm<const N: usize>(bytes: &[u8], spec: PcmSpec, sample: fn([u8; N]) -> f32) -> Result<Vec<f32>, AudioError> {
+    let frame = N * spec.channels.max(1) as usize;
+    if !bytes.len().is_multiple_of(frame) {
+        return Err(AudioError::InvalidAudio(format!(
+            "{} bytes is not a whole number of {}-byte frames",
+            bytes.len(),
+            frame
+        )));
+    }
+    Ok(bytes
+        .chunks_exact(N)
+        .map(|b| sample(b.try_into().expect("chunks_exact yields N bytes")))
+        .collect())
+}
+
+fn downmix(interleaved: &[f32], channels: u16) -> Vec<f32> {
+    if channels == 1 {
+        return interleaved.to_vec();
+    }
+    interleaved
+        .chunks(channels as usize)
+        .map(|frame| frame.iter().sum::<f32>() / frame.len() as f32)
+        .collect()
+}
+
+/// Linear interpolation; good enough for speech
+fn resample(samples: &[f32], from_rate: u32) -> Vec<f32> {
+    if from_rate == WHISPER_SAMPLE_RATE || samples.is_empty() {
+        return samples.to_vec();
+    }
+    let step = from_rate as f64 / WHISPER_SAMPLE_RATE as f64;
+    let len = (samples.len() as f64 / step).floor() as usize;
+    (0..len)
+        .map(|i| {
+            let pos = i as f64 * step;
+            let index = pos as usize;
+            let next = samples.get(index + 1).copied().unwrap_or(samples[index]);
+            let frac = (pos - index as f64) as f32;
+            samples[index] + (next - samples[index]) * frac
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wav_is_downmixed_and_resampled() {
+        let spec = hound::WavSpec {
+            channels: 2,
+            sample_rate: 48_000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut out = Cursor::new(Vec::new());
+        let mut writer = hound::WavWriter::new(&mut out, spec).unwrap();
+        for _ in 0..4800 {
+            writer.write_sample(16384i16).unwrap();
+            writer.write_sample(0i16).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let samples = decode_chunk(&out.into_inner(), ChunkFormat::Wav, PcmSpec::default()).unwrap();
+        // 0.1 s at 16 kHz, averaged across both channels
+        assert_eq!(samples.len(), 1600);
+        assert!(samples.iter().all(|s| (s - 0.25).abs() < 1e-3));
+
+        let round_trip = decode_chunk(&encode_wav(&samples).unwrap(), ChunkFormat::Wav, PcmSpec::default()).unwrap();
+        assert_eq!(round_trip.len(), 1600);
+    }
+
+    #[test]
+    fn test_raw_pcm() {
+        let bytes: Vec<u8> = [0i16, 16384, -16384].iter().flat_map(|s| s.to_le_bytes()).collect();
+        let samples = decode_chunk(&bytes, ChunkFormat::PcmS16le, PcmSpec::default()).unwrap();
+        assert_eq!(samples, vec![0.0, 0.5, -0.5]);
+
+        let stereo = PcmSpec { sample_rate: 16_000, channels: 2 };
+        assert!(decode_chunk(&bytes, ChunkFormat::PcmS16le, stereo).is_err());
+        assert!(decode_chunk(b"not a wav", ChunkFormat::Wav, PcmSpec::default()).is_err());
+    }
+}
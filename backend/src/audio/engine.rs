@@ -0,0 +1,233 @@
+//! Speech-to-text engines
+//!
+//! Local transcription runs whisper.cpp through `whisper-rs` and is only
+//! compiled with the `whisper` feature. Provider transcription posts WAV audio
+//! to an OpenAI-compatible `/audio/transcriptions` endpoint.
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use super::{decode::encode_wav, AudioError};
+use crate::config::TranscriptionSettings;
+
+const OPENAI_TRANSCRIPTION_URL: &str = "https://api.openai.com/v1/audio/transcriptions";
+
+/// Engine requested for a session
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EngineChoice {
+    Local,
+    Provider,
+    /// Local when a model is available, the provider otherwise
+    #[default]
+    Auto,
+}
+
+impl EngineChoice {
+    /// Choice from the `transcription.engine` setting
+    pub fn from_setting(value: &str) -> Self {
+        match value {
+            "local" => EngineChoice::Local,
+            "provider" => EngineChoice::Provider,
+            _ => EngineChoice::Auto,
+        }
+    }
+}
+
+/// Engine a session ended up with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EngineKind {
+    Local,
+    Provider,
+}
+
+/// Whisper-compatible transcription API
+#[derive(Debug, Clone)]
+pub struct ProviderStt {
+    pub url: String,
+    pub api_key: String,
+}
+
+impl ProviderStt {
+    pub fn new(url: Option<String>, api_key: String) -> Self {
+        Self {
+            url: url.unwrap_or_else(|| OPENAI_TRANSCRIPTION_URL.to_string()),
+            api_key,
+        }
+    }
+
+    async fn transcribe(&self, samples: &[f32], language: Option<&str>) -> Result<String, AudioError> {
+        let wav = encode_wav(samples)?;
+        let file = reqwest::multipart::Part::bytes(wav)
+            .file_name("recording.wav")
+            .mime_str("audio/wav")
+            .map_err(|e| AudioError::Provider(e.to_string()))?;
+        // Groq serves Whisper under its own model name
+        let model = if self.url.contains("groq") { "whisper-large-v3-turbo" } else { "whisper-1" };
+        let mut form = reqwest::multipart::Form::new().part("file", file).text("model", model);
+        if let Some(language) = language {
+            form = form.text("language", language.to_string());
+        }
+
+        let response = reqwest::Client::new()
+            .post(&self.url)
+            .bearer_auth(&self.api_key)
+            .multipart(form)
+            .timeout(std::time::Duration::from_secs(120))
+            .send()
+            .await
+            .map_err(|e| AudioError::Provider(e.to_string()))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(AudioError::Provider(format!("{} {}", status, body)));
+        }
+        let body: serde_json::Value = response.json().await.map_err(|e| AudioError::Provider(e.to_string()))?;
+        Ok(body["text"].as_str().unwrap_or_default().trim().to_string())
+    }
+}
+
+/// Engine used by a transcription session
+pub enum TranscriptionEngine {
+    #[cfg(feature = "whisper")]
+    Local(local::LocalWhisper),
+    Provider(ProviderStt),
+}
+
+impl TranscriptionEngine {
+    /// Pick an engine for `choice`. The provider is only usable when the
+    /// caller passed an API key.
+    pub fn select(
+        choice: EngineChoice,
+        settings: &TranscriptionSettings,
+        provider: Option<ProviderStt>,
+    ) -> Result<Self, AudioError> {
+        let local = || Self::local(&settings.model_path(), settings.threads);
+        match (choice, provider) {
+            (EngineChoice::Local, _) => local(),
+            (EngineChoice::Provider, Some(provider)) => Ok(Self::Provider(provider)),
+            (EngineChoice::Provider, None) => {
+                Err(AudioError::Provider("an API key is required for provider transcription".to_string()))
+            }
+            (EngineChoice::Auto, provider) => match local() {
+                Ok(engine) => Ok(engine),
+                Err(e) => provider.map(Self::Provider).ok_or(e),
+            },
+        }
+    }
+
+    #[cfg(feature = "whisper")]
+    fn local(model: &Path, threads: usize) -> Result<Self, AudioError> {
+        local::LocalWhisper::load(model, threads).map(Self::Local)
+    }
+
+    #[cfg(not(feature = "whisper"))]
+    fn local(_model: &Path, _threads: usize) -> Result<Self, AudioError> {
+        Err(AudioError::LocalUnavailable(
+            "this build does not include whisper.cpp (enable the `whisper` feature)".to_string(),
+        ))
+    }
+
+    pub fn kind(&self) -> EngineKind {
+        match self {
+            #[cfg(feature = "whisper")]
+            TranscriptionEngine::Local(_) => EngineKind::Local,
+            TranscriptionEngine::Provider(_) => EngineKind::Provider,
+        }
+    }
+
+    /// Whether re-transcribing as audio arrives is cheap enough for partial
+    /// transcripts; provider calls cost money per request, so they only run
+    /// on full segments and at the end
+    pub fn supports_partials(&self) -> bool {
+        self.kind() == EngineKind::Local
+    }
+
+    /// Transcribe 16 kHz mono samples
+    pub async fn transcribe(&self, samples: Vec<f32>, language: Option<&str>) -> Result<String, AudioError> {
+        if samples.is_empty() {
+            return Ok(String::new());
+        }
+        match self {
+            #[cfg(feature = "whisper")]
+            TranscriptionEngine::Local(whisper) => whisper.transcribe(samples, language).await,
+            TranscriptionEngine::Provider(provider) => provider.transcribe(&samples, language).await,
+        }
+    }
+}
+
+#[cfg(feature = "whisper")]
+mod local {
+    use std::path::{Path, PathBuf};
+    use std::sync::{Arc, Mutex};
+    use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
+
+    use super::AudioError;
+
+    lazy_static::lazy_static! {
+        /// Most recently loaded model; loading takes seconds, so it is shared
+        /// by all sessions using the same file
+        static ref LOADED: Mutex<Option<(PathBuf, Arc<WhisperContext>)>> = Mutex::new(None);
+    }
+
+    pub struct LocalWhisper {
+        context: Arc<WhisperContext>,
+        threads: i32,
+    }
+
+    impl LocalWhisper {
+        pub fn load(model: &Path, threads: usize) -> Result<Self, AudioError> {
+            if !model.is_file() {
+                return Err(AudioError::LocalUnavailable(format!("no Whisper model at {}", model.display())));
+            }
+            let threads = match threads {
+                0 => std::thread::available_parallelism().map(|n| n.get()).unwrap_or(4).min(8),
+                n => n,
+            } as i32;
+
+            let mut loaded = LOADED.lock().unwrap();
+            if let Some((path, context)) = loaded.as_ref().filter(|(path, _)| path == model) {
+                tracing::debug!("Reusing Whisper model {}", path.display());
+                return Ok(Self { context: context.clone(), threads });
+            }
+
+            let path = model
+                .to_str()
+                .ok_or_else(|| AudioError::LocalUnavailable(format!("invalid model path {}", model.display())))?;
+            let context = WhisperContext::new_with_params(path, WhisperContextParameters::default())
+                .map_err(|e| AudioError::LocalUnavailable(format!("failed to load {}: {}", model.display(), e)))?;
+            tracing::info!("Loaded Whisper model {}", model.display());
+            let context = Arc::new(context);
+            *loaded = Some((model.to_path_buf(), context.clone()));
+            Ok(Self { context, threads })
+        }
+
+        pub async fn transcribe(&self, samples: Vec<f32>, language: Option<&str>) -> Result<String, AudioError> {
+            let context = self.context.clone();
+            let threads = self.threads;
+            let language = language.map(str::to_string);
+            tokio::task::spawn_blocking(move || {
+                let mut state = context.create_state().map_err(|e| AudioError::Engine(e.to_string()))?;
+                let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
+                params.set_n_threads(threads);
+                params.set_language(Some(language.as_deref().unwrap_or("auto")));
+                params.set_print_progress(false);
+                params.set_print_realtime(false);
+                params.set_print_special(false);
+                params.set_print_timestamps(false);
+                state.full(params, &samples).map_err(|e| AudioError::Engine(e.to_string()))?;
+
+                let segments = state.full_n_segments().map_err(|e| AudioError::Engine(e.to_string()))?;
+                let mut text = String::new();
+                for i in 0..segments {
+                    let segment = state.full_get_segment_text(i).map_err(|e| AudioError::Engine(e.to_string()))?;
+                    text.push_str(&segment);
+                }
+                Ok(text.trim().to_string())
+            })
+            .await
+            .map_err(|e| AudioError::Engine(format!("transcription task failed: {}", e)))?
+        }
+    }
+}
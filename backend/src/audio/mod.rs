@@ -0,0 +1,40 @@
+//! Voice transcription
+//!
+//! The chat input records audio in the webview and sends it here in chunks
+//! (WAV or raw PCM). Each chunk is decoded to 16 kHz mono, appended to a
+//! [`TranscriptionSession`] and transcribed either locally with whisper.cpp
+//! (`whisper` feature) or by a Whisper-compatible provider API. Local sessions
+//! re-transcribe the pending audio as it grows and publish partial transcripts
+//! on the event bus (`audio` topic) so the input can show text while the user
+//! is still speaking.
+
+pub mod decode;
+pub mod engine;
+pub mod session;
+
+pub use decode::{decode_chunk, encode_wav, ChunkFormat, PcmSpec, WHISPER_SAMPLE_RATE};
+pub use engine::{EngineChoice, EngineKind, ProviderStt, TranscriptionEngine};
+pub use session::{CreateSessionOptions, SessionInfo, TranscriptUpdate, TranscriptionManager};
+
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum AudioError {
+    #[error("Transcription session not found: {0}")]
+    SessionNotFound(String),
+
+    #[error("Invalid audio: {0}")]
+    InvalidAudio(String),
+
+    #[error("Recording is longer than the {0} second limit")]
+    TooLong(u64),
+
+    #[error("Local transcription is unavailable: {0}")]
+    LocalUnavailable(String),
+
+    #[error("Transcription provider failed: {0}")]
+    Provider(String),
+
+    #[error("Transcription failed: {0}")]
+    Engine(String),
+}
@@ -0,0 +1,321 @@
+//! Transcription sessions
+//!
+//! A session buffers the audio of one recording. Audio is transcribed in
+//! segments of at most [`SEGMENT_SECS`]; finished segments are kept as
+//! committed text and only the pending tail is re-transcribed for partial
+//! transcripts.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{Mutex, RwLock};
+
+use super::decode::{decode_chunk, ChunkFormat, PcmSpec, WHISPER_SAMPLE_RATE};
+use super::engine::{EngineChoice, EngineKind, ProviderStt, TranscriptionEngine};
+use super::AudioError;
+use crate::config::SettingsStore;
+
+/// Longest stretch of audio transcribed in one go (Whisper's window)
+pub const SEGMENT_SECS: usize = 30;
+
+/// New audio needed before the pending tail is transcribed again
+const PARTIAL_INTERVAL_MS: usize = 1500;
+
+/// Longest recording a session accepts
+pub const MAX_RECORDING_SECS: u64 = 10 * 60;
+
+/// Sessions without new audio for this long are dropped
+const IDLE_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+const SEGMENT_SAMPLES: usize = SEGMENT_SECS * WHISPER_SAMPLE_RATE as usize;
+const PARTIAL_SAMPLES: usize = PARTIAL_INTERVAL_MS * WHISPER_SAMPLE_RATE as usize / 1000;
+
+/// How a new session should be set up
+#[derive(Debug, Clone, Default)]
+pub struct CreateSessionOptions {
+    /// Overrides the `transcription.engine` setting
+    pub engine: Option<EngineChoice>,
+    /// ISO 639-1 code; Whisper detects the language when unset
+    pub language: Option<String>,
+    pub format: ChunkFormat,
+    /// Layout of raw PCM chunks; ignored for WAV
+    pub pcm: PcmSpec,
+    /// Provider to use when transcription doesn't run locally
+    pub provider: Option<ProviderStt>,
+}
+
+/// A newly created session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionInfo {
+    pub session_id: String,
+    pub engine: EngineKind,
+    /// Whether partial transcripts are produced while recording
+    pub partials: bool,
+}
+
+/// Transcript of a session so far
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TranscriptUpdate {
+    pub session_id: String,
+    pub text: String,
+    pub is_final: bool,
+    /// Audio received so far
+    pub duration_ms: u64,
+}
+
+struct TranscriptionSession {
+    engine: TranscriptionEngine,
+    language: Option<String>,
+    format: ChunkFormat,
+    pcm: PcmSpec,
+    /// Text of finished segments
+    committed: String,
+    /// Samples not yet part of a finished segment
+    pending: Vec<f32>,
+    /// Latest transcript of `pending`
+    partial: String,
+    /// Length of `pending` when `partial` was produced
+    partial_samples: usize,
+    total_samples: usize,
+    last_activity: Instant,
+}
+
+impl TranscriptionSession {
+    fn text(&self) -> String {
+        join(&self.committed, &self.partial)
+    }
+
+    fn duration_ms(&self) -> u64 {
+        (self.total_samples as u64 * 1000) / WHISPER_SAMPLE_RATE as u64
+    }
+
+    async fn commit_full_segments(&mut self) -> Result<(), AudioError> {
+        while self.pending.len() >= SEGMENT_SAMPLES {
+            let segment: Vec<f32> = self.pending.drain(..SEGMENT_SAMPLES).collect();
+            let text = self.engine.transcribe(segment, self.language.as_deref()).await?;
+            self.committed = join(&self.committed, &text);
+            self.partial.clear();
+            self.partial_samples = 0;
+        }
+        Ok(())
+    }
+}
+
+fn join(a: &str, b: &str) -> String {
+    match (a.is_empty(), b.is_empty()) {
+        (true, _) => b.to_string(),
+        (_, true) => a.to_string(),
+        _ => format!("{} {}", a, b),
+    }
+}
+
+/// Open transcription sessions
+#[derive(Default)]
+pub struct TranscriptionManager {
+    sessions: RwLock<HashMap<String, Arc<Mutex<TranscriptionSession>>>>,
+}
+
+lazy_static::lazy_static! {
+    static ref GLOBAL_MANAGER: Arc<TranscriptionManager> = Arc::new(TranscriptionManager::default());
+}
+
+impl TranscriptionManager {
+    pub fn global() -> Arc<TranscriptionManager> {
+        GLOBAL_MANAGER.clone()
+    }
+
+    /// Start a session, choosing the engine from the options and settings
+    pub async fn create(&self, options: CreateSessionOptions) -> Result<SessionInfo, AudioError> {
+        let settings = SettingsStore::global().get().transcription;
+        let choice = options
+            .engine
+            .unwrap_or_else(|| EngineChoice::from_setting(&settings.engine));
+        let engine = TranscriptionEngine::select(choice, &settings, options.provider)?;
+        Ok(self.insert(engine, options.language, options.format, options.pcm).await)
+    }
+
+    async fn insert(
+        &self,
+        engine: TranscriptionEngine,
+        language: Option<String>,
+        format: ChunkFormat,
+        pcm: PcmSpec,
+    ) -> SessionInfo {
+        let info = SessionInfo {
+            session_id: uuid::Uuid::new_v4().to_string(),
+            engine: engine.kind(),
+            partials: engine.supports_partials(),
+        };
+        let session = TranscriptionSession {
+            engine,
+            language: language.filter(|l| !l.trim().is_empty()),
+            format,
+            pcm,
+            committed: String::new(),
+            pending: Vec::new(),
+            partial: String::new(),
+            partial_samples: 0,
+            total_samples: 0,
+            last_activity: Instant::now(),
+        };
+
+        let mut sessions = self.sessions.write().await;
+        let mut idle = Vec::new();
+        for (id, session) in sessions.iter() {
+            if session.try_lock().is_ok_and(|s| s.last_activity.elapsed() > IDLE_TIMEOUT) {
+                idle.push(id.clone());
+            }
+        }
+        for id in idle {
+            tracing::debug!("Dropping idle transcription session {}", id);
+            sessions.remove(&id);
+        }
+        sessions.insert(info.session_id.clone(), Arc::new(Mutex::new(session)));
+        info
+    }
+
+    async fn session(&self, id: &str) -> Result<Arc<Mutex<TranscriptionSession>>, AudioError> {
+        self.sessions
+            .read()
+            .await
+            .get(id)
+            .cloned()
+            .ok_or_else(|| AudioError::SessionNotFound(id.to_string()))
+    }
+
+    /// Add a chunk of audio, transcribing finished segments and, for local
+    /// engines, the pending tail. Publishes the transcript when it changes.
+    pub async fn append(&self, id: &str, chunk: &[u8]) -> Result<TranscriptUpdate, AudioError> {
+        let session = self.session(id).await?;
+        let mut session = session.lock().await;
+        let samples = decode_chunk(chunk, session.format, session.pcm)?;
+        if (session.total_samples + samples.len()) as u64 > MAX_RECORDING_SECS * WHISPER_SAMPLE_RATE as u64 {
+            return Err(AudioError::TooLong(MAX_RECORDING_SECS));
+        }
+        session.total_samples += samples.len();
+        session.pending.extend(samples);
+        session.last_activity = Instant::now();
+
+        let before = session.text();
+        session.commit_full_segments().await?;
+        if session.engine.supports_partials() && session.pending.len() >= session.partial_samples + PARTIAL_SAMPLES {
+            let partial = session
+                .engine
+                .transcribe(session.pending.clone(), session.language.as_deref())
+                .await?;
+            session.partial = partial;
+            session.partial_samples = session.pending.len();
+        }
+
+        let update = TranscriptUpdate {
+            session_id: id.to_string(),
+            text: session.text(),
+            is_final: false,
+            duration_ms: session.duration_ms(),
+        };
+        if update.text != before {
+            publish(&update);
+        }
+        Ok(update)
+    }
+
+    /// Transcribe the remaining audio and close the session
+    pub async fn finish(&self, id: &str) -> Result<TranscriptUpdate, AudioError> {
+        let session = self.session(id).await?;
+        let result = async {
+            let mut session = session.lock().await;
+            session.commit_full_segments().await?;
+            let pending = std::mem::take(&mut session.pending);
+            // The last partial is current if no audio arrived since
+            let tail = if session.partial_samples == pending.len() && !pending.is_empty() {
+                std::mem::take(&mut session.partial)
+            } else {
+                session.engine.transcribe(pending, session.language.as_deref()).await?
+            };
+            Ok(TranscriptUpdate {
+                session_id: id.to_string(),
+                text: join(&session.committed, &tail),
+                is_final: true,
+                duration_ms: session.duration_ms(),
+            })
+        }
+        .await;
+
+        self.sessions.write().await.remove(id);
+        if let Ok(update) = &result {
+            publish(update);
+        }
+        result
+    }
+
+    /// Discard a session without transcribing what is left
+    pub async fn cancel(&self, id: &str) -> bool {
+        self.sessions.write().await.remove(id).is_some()
+    }
+}
+
+fn publish(update: &TranscriptUpdate) {
+    crate::events::publish(crate::events::Event::Transcript {
+        session_id: update.session_id.clone(),
+        text: update.text.clone(),
+        is_final: update.is_final,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn silence(secs: f32) -> Vec<u8> {
+        let samples = (secs * WHISPER_SAMPLE_RATE as f32) as usize;
+        vec![0u8; samples * 2]
+    }
+
+    #[tokio::test]
+    async fn test_session_lifecycle() {
+        let manager = TranscriptionManager::default();
+        // Never reaches the network: no segment fills up and provider
+        // engines produce no partials
+        let engine = TranscriptionEngine::Provider(ProviderStt::new(
+            Some("http://127.0.0.1:9/v1/audio/transcriptions".to_string()),
+            "key".to_string(),
+        ));
+        let info = manager
+            .insert(engine, None, ChunkFormat::PcmS16le, PcmSpec::default())
+            .await;
+        assert_eq!(info.engine, EngineKind::Provider);
+        assert!(!info.partials);
+
+        let update = manager.append(&info.session_id, &silence(2.0)).await.unwrap();
+        assert_eq!(update.duration_ms, 2000);
+        assert_eq!(update.text, "");
+
+        // Odd byte counts aren't whole 16-bit samples
+        assert!(matches!(
+            manager.append(&info.session_id, &[0u8; 3]).await,
+            Err(AudioError::InvalidAudio(_))
+        ));
+
+        assert!(manager.cancel(&info.session_id).await);
+        assert!(matches!(
+            manager.finish(&info.session_id).await,
+            Err(AudioError::SessionNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_engine_selection() {
+        let settings = crate::config::TranscriptionSettings {
+            model_path: Some("/nonexistent/ggml-base.bin".to_string()),
+            ..Default::default()
+        };
+        let provider = || Some(ProviderStt::new(None, "key".to_string()));
+
+        let auto = TranscriptionEngine::select(EngineChoice::Auto, &settings, provider()).unwrap();
+        assert_eq!(auto.kind(), EngineKind::Provider);
+        assert!(TranscriptionEngine::select(EngineChoice::Auto, &settings, None).is_err());
+        assert!(TranscriptionEngine::select(EngineChoice::Local, &settings, provider()).is_err());
+        assert!(TranscriptionEngine::select(EngineChoice::Provider, &settings, None).is_err());
+    }
+}
@@ -0,0 +1,186 @@
+//! Per-launch API authentication
+//!
+//! The Tauri shell generates a random token at every launch and hands it to
+//! the backend sidecar through `SKHOOT_AUTH_TOKEN`. Routes behind
+//! [`require_token`] reject requests that don't present it, so other local
+//! processes and web pages can't drive the API. The same token protects the
+//! shell's own HTTP bridge, which the backend calls for page rendering.
+//!
+//! When the variable is not set (e.g. the backend started on its own with
+//! `cargo run`) authentication is disabled and a warning is logged.
+
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderMap, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Json, Response},
+};
+use serde_json::json;
+use std::sync::Arc;
+
+/// Environment variable carrying the token from the Tauri shell to the sidecar
+pub const AUTH_TOKEN_ENV: &str = "SKHOOT_AUTH_TOKEN";
+
+/// Query parameter accepted for clients that can't set headers
+/// (`EventSource`, `<img src>`)
+pub const AUTH_QUERY_PARAM: &str = "token";
+
+/// Token required by protected routes; `None` disables authentication
+#[derive(Clone, Default)]
+pub struct AuthToken {
+    token: Option<Arc<str>>,
+}
+
+impl AuthToken {
+    pub fn new(token: impl Into<String>) -> Self {
+        let token = token.into();
+        Self {
+            token: (!token.is_empty()).then(|| Arc::from(token)),
+        }
+    }
+
+    /// Token from `SKHOOT_AUTH_TOKEN`, or disabled when unset
+    pub fn from_env() -> Self {
+        Self::new(std::env::var(AUTH_TOKEN_ENV).unwrap_or_default())
+    }
+
+    /// Random 256-bit token for a new launch
+    pub fn generate() -> Self {
+        Self::new(format!(
+            "{}{}",
+            uuid::Uuid::new_v4().simple(),
+            uuid::Uuid::new_v4().simple()
+        ))
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.token.is_some()
+    }
+
+    pub fn value(&self) -> Option<&str> {
+        self.token.as_deref()
+    }
+
+    /// Whether `presented` grants access
+    pub fn verify(&self, presented: Option<&str>) -> bool {
+        match (&self.token, presented) {
+            (None, _) => true,
+            (Some(expected), Some(presented)) => constant_time_eq(expected.as_bytes(), presented.as_bytes()),
+            (Some(_), None) => false,
+        }
+    }
+}
+
+impl std::fmt::Debug for AuthToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AuthToken")
+            .field("enabled", &self.is_enabled())
+            .finish()
+    }
+}
+
+/// Token presented by a request: `Authorization: Bearer <token>`, or the
+/// `token` query parameter
+pub fn presented_token(headers: &HeaderMap, query: Option<&str>) -> Option<String> {
+    let from_header = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(|token| token.trim().to_string());
+
+    from_header.or_else(|| {
+        url::form_urlencoded::parse(query?.as_bytes())
+            .find(|(key, _)| key == AUTH_QUERY_PARAM)
+            .map(|(_, value)| value.into_owned())
+    })
+}
+
+/// Middleware rejecting requests without the launch token
+///
+/// CORS preflights carry no credentials and are let through.
+pub async fn require_token(State(auth): State<AuthToken>, request: Request, next: Next) -> Response {
+    if request.method() == Method::OPTIONS {
+        return next.run(request).await;
+    }
+
+    let presented = presented_token(request.headers(), request.uri().query());
+    if auth.verify(presented.as_deref()) {
+        return next.run(request).await;
+    }
+
+    tracing::warn!(
+        "Rejected unauthenticated request: {} {}",
+        request.method(),
+        request.uri().path()
+    );
+    (
+        StatusCode::UNAUTHORIZED,
+        Json(json!({ "error": "Missing or invalid auth token" })),
+    )
+        .into_response()
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, routing::get, Router};
+    use tower::Service;
+
+    fn app(auth: AuthToken) -> Router {
+        Router::new()
+            .route("/protected", get(|| async { "ok" }))
+            .route_layer(axum::middleware::from_fn_with_state(auth, require_token))
+            .route("/health", get(|| async { "ok" }))
+    }
+
+    async fn status(mut app: Router, request: axum::http::Request<Body>) -> StatusCode {
+        app.call(request).await.unwrap().status()
+    }
+
+    #[test]
+    fn test_verify() {
+        let auth = AuthToken::new("secret");
+        assert!(auth.verify(Some("secret")));
+        assert!(!auth.verify(Some("secreT")));
+        assert!(!auth.verify(Some("secret2")));
+        assert!(!auth.verify(None));
+
+        assert!(AuthToken::new("").verify(None));
+        assert_eq!(AuthToken::generate().value().unwrap().len(), 64);
+        assert_ne!(AuthToken::generate().value(), AuthToken::generate().value());
+    }
+
+    #[tokio::test]
+    async fn test_require_token() {
+        let auth = AuthToken::new("secret");
+        let get = |uri: &str| axum::http::Request::get(uri);
+
+        assert_eq!(status(app(auth.clone()), get("/protected").body(Body::empty()).unwrap()).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            status(
+                app(auth.clone()),
+                get("/protected").header("Authorization", "Bearer wrong").body(Body::empty()).unwrap()
+            )
+            .await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            status(
+                app(auth.clone()),
+                get("/protected").header("Authorization", "Bearer secret").body(Body::empty()).unwrap()
+            )
+            .await,
+            StatusCode::OK
+        );
+        assert_eq!(status(app(auth.clone()), get("/protected?token=secret").body(Body::empty()).unwrap()).await, StatusCode::OK);
+        assert_eq!(status(app(auth.clone()), get("/health").body(Body::empty()).unwrap()).await, StatusCode::OK);
+        assert_eq!(status(app(AuthToken::default()), get("/protected").body(Body::empty()).unwrap()).await, StatusCode::OK);
+    }
+}
@@ -0,0 +1,453 @@
+//! Command-line client for a running Skhoot backend
+//!
+//! Searches files, runs workflows and asks the agent questions without the
+//! desktop app, e.g. from scripts or over SSH. Results are printed as text,
+//! or as the backend's JSON with `--json`.
+//!
+//! The backend address defaults to the `server` settings. Requests carry the
+//! launch token from `SKHOOT_AUTH_TOKEN` when the backend requires one.
+//! Asking the agent goes through the local API, so it needs the token of a
+//! local API client in `SKHOOT_API_TOKEN`; with it, `workflow run` also
+//! answers the prompt steps of the run instead of leaving them to the app.
+
+use anyhow::{anyhow, bail, Context, Result};
+use clap::{Parser, Subcommand};
+use reqwest::{Client, RequestBuilder};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::time::Instant;
+
+use skhoot_backend::auth::AUTH_TOKEN_ENV;
+use skhoot_backend::config::SettingsStore;
+use skhoot_backend::search_engine::search_manager::UnifiedSearchResults;
+use skhoot_backend::workflows::{StepOutcome, Workflow, WorkflowRun, WorkflowStatus, WorkflowStep};
+
+/// Environment variable carrying a local API client token
+const API_TOKEN_ENV: &str = "SKHOOT_API_TOKEN";
+
+/// Command-line arguments for the Skhoot client
+#[derive(Parser)]
+#[command(name = "skhoot-cli")]
+#[command(about = "Search, run workflows and ask the agent through a running Skhoot backend")]
+#[command(version)]
+struct Args {
+    /// Backend address; defaults to the server host and port in the settings
+    #[arg(long, env = "SKHOOT_URL", global = true)]
+    url: Option<String>,
+
+    /// Launch token of the backend
+    #[arg(long, env = AUTH_TOKEN_ENV, global = true, hide_env_values = true)]
+    token: Option<String>,
+
+    /// Token of a local API client, used to ask the agent
+    #[arg(long, env = API_TOKEN_ENV, global = true, hide_env_values = true)]
+    api_token: Option<String>,
+
+    /// Print the backend's JSON instead of text
+    #[arg(long, global = true)]
+    json: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Search for files
+    Search {
+        /// Query, with optional ext:, size:, modified: and path: filters
+        query: Vec<String>,
+
+        /// Directory to search instead of the home directory
+        #[arg(short, long)]
+        path: Option<String>,
+
+        /// Named workspace to search
+        #[arg(short, long, conflicts_with = "path")]
+        workspace: Option<String>,
+
+        /// Maximum number of results
+        #[arg(short = 'n', long, default_value_t = 20)]
+        limit: usize,
+    },
+
+    /// Ask the agent a question
+    Ask {
+        question: Vec<String>,
+    },
+
+    /// List and run workflows
+    #[command(subcommand)]
+    Workflow(WorkflowCommand),
+}
+
+#[derive(Subcommand)]
+enum WorkflowCommand {
+    /// List the saved workflows
+    List,
+
+    /// Run a workflow and print the result
+    Run {
+        /// Workflow id or name
+        workflow: String,
+
+        /// Input variable as KEY=VALUE; VALUE is read as JSON when it parses
+        #[arg(long = "var", value_name = "KEY=VALUE", value_parser = parse_variable)]
+        variables: Vec<(String, Value)>,
+    },
+
+    /// Show a run
+    Status {
+        run_id: String,
+    },
+}
+
+/// Parse a `KEY=VALUE` variable
+fn parse_variable(arg: &str) -> Result<(String, Value), String> {
+    let (key, value) = arg
+        .split_once('=')
+        .ok_or_else(|| format!("expected KEY=VALUE, got '{}'", arg))?;
+    if key.trim().is_empty() {
+        return Err(format!("missing variable name in '{}'", arg));
+    }
+    let value = serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.to_string()));
+    Ok((key.trim().to_string(), value))
+}
+
+/// HTTP client for the backend API
+struct Backend {
+    http: Client,
+    url: String,
+    token: Option<String>,
+    api_token: Option<String>,
+}
+
+impl Backend {
+    fn new(args: &Args) -> Self {
+        let url = args.url.clone().unwrap_or_else(|| {
+            let server = SettingsStore::global().get().server;
+            format!("http://{}:{}", server.host, server.port)
+        });
+        Self {
+            http: Client::new(),
+            url: url.trim_end_matches('/').to_string(),
+            token: args.token.clone().filter(|t| !t.is_empty()),
+            api_token: args.api_token.clone().filter(|t| !t.is_empty()),
+        }
+    }
+
+    fn api(&self, path: &str) -> String {
+        format!("{}/api/v1{}", self.url, path)
+    }
+
+    fn authorized(&self, request: RequestBuilder) -> RequestBuilder {
+        match &self.token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+
+    async fn get<T: DeserializeOwned>(&self, path: &str, query: &[(&str, String)]) -> Result<T> {
+        send(self.authorized(self.http.get(self.api(path)).query(query))).await
+    }
+
+    async fn post<T: DeserializeOwned>(&self, path: &str, body: &impl Serialize) -> Result<T> {
+        send(self.authorized(self.http.post(self.api(path)).json(body))).await
+    }
+
+    /// The agent's answer to a single question
+    async fn ask(&self, question: &str) -> Result<String> {
+        let token = self.api_token.as_deref().ok_or_else(|| {
+            anyhow!("Asking the agent needs a local API client token in {} or --api-token", API_TOKEN_ENV)
+        })?;
+        let request = self
+            .http
+            .post(format!("{}/v1/chat/completions", self.url))
+            .bearer_auth(token)
+            .json(&json!({
+                "model": skhoot_backend::local_api::MODEL_ID,
+                "messages": [{ "role": "user", "content": question }],
+            }));
+        let response: Value = send(request).await?;
+        response["choices"][0]["message"]["content"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| anyhow!("The agent returned no answer"))
+    }
+}
+
+/// Send a request and decode the JSON response, turning error responses
+/// into their message
+async fn send<T: DeserializeOwned>(request: RequestBuilder) -> Result<T> {
+    let response = request.send().await.context("Could not reach the Skhoot backend")?;
+    let status = response.status();
+    let body = response.text().await?;
+    if !status.is_success() {
+        let message = serde_json::from_str::<Value>(&body)
+            .ok()
+            .and_then(|value| {
+                let error = &value["error"];
+                error.as_str().or_else(|| error["message"].as_str()).map(str::to_string)
+            })
+            .unwrap_or(body);
+        bail!("{} ({})", message, status);
+    }
+    serde_json::from_str(&body).context("Unexpected response from the backend")
+}
+
+fn print_json(value: &impl Serialize) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+async fn search(
+    backend: &Backend,
+    json: bool,
+    query: String,
+    path: Option<String>,
+    workspace: Option<String>,
+    limit: usize,
+) -> Result<()> {
+    let mut params = vec![("q", query), ("max_results", limit.to_string())];
+    params.extend(path.map(|p| ("search_path", p)));
+    params.extend(workspace.map(|w| ("workspace", w)));
+    let results: UnifiedSearchResults = backend.get("/search/files", &params).await?;
+    if json {
+        return print_json(&results);
+    }
+
+    for result in &results.merged_results {
+        match result.line_number {
+            Some(line) => println!("{:>5.2}  {}:{}", result.relevance_score, result.path, line),
+            None => println!("{:>5.2}  {}", result.relevance_score, result.path),
+        }
+        if let Some(snippet) = result.snippet.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+            println!("       {}", snippet);
+        }
+    }
+    println!(
+        "{} results in {} ms",
+        results.merged_results.len(),
+        results.total_execution_time_ms
+    );
+    Ok(())
+}
+
+/// Find a workflow by id, or by name ignoring case
+async fn find_workflow(backend: &Backend, key: &str) -> Result<Workflow> {
+    let workflows: Vec<Workflow> = backend.get("/workflows", &[]).await?;
+    workflows
+        .iter()
+        .find(|w| w.id == key)
+        .or_else(|| workflows.iter().find(|w| w.name.eq_ignore_ascii_case(key)))
+        .cloned()
+        .ok_or_else(|| anyhow!("No workflow with id or name '{}'", key))
+}
+
+/// Read a decision from the agent's answer the way the app does: a boolean
+/// `isValid`, `approved` or `decision` field of JSON in the answer, else
+/// whether it says yes
+fn decision_from_output(output: &str) -> bool {
+    let json_start = output.find(['{', '[']);
+    let json_end = output.rfind(['}', ']']);
+    if let (Some(start), Some(end)) = (json_start, json_end) {
+        if let Ok(parsed) = serde_json::from_str::<Value>(&output[start..=end]) {
+            for field in ["isValid", "approved", "decision"] {
+                if let Some(value) = parsed[field].as_bool() {
+                    return value;
+                }
+            }
+        }
+    }
+    let lower = output.to_lowercase();
+    ["yes", "true", "confirmed"].iter().any(|word| lower.contains(word))
+}
+
+/// Ask the agent the prompt of the run's current step and report the answer
+async fn run_prompt_step(backend: &Backend, run: &WorkflowRun, step: &WorkflowStep) -> Result<WorkflowRun> {
+    let prompt = run.current_prompt.clone().unwrap_or_else(|| step.prompt.clone());
+    eprintln!("Running step {}...", step.name);
+    let started = Instant::now();
+    let answer = backend.ask(&prompt).await;
+    let duration_ms = started.elapsed().as_millis() as u64;
+    let outcome = match answer {
+        Ok(output) => StepOutcome {
+            step_id: step.id.clone(),
+            success: true,
+            decision_result: step.decision.as_ref().map(|_| decision_from_output(&output)),
+            output,
+            error: None,
+            duration_ms,
+            artifacts: Vec::new(),
+        },
+        Err(error) => StepOutcome {
+            step_id: step.id.clone(),
+            success: false,
+            output: String::new(),
+            error: Some(error.to_string()),
+            duration_ms,
+            decision_result: None,
+            artifacts: Vec::new(),
+        },
+    };
+    backend.post(&format!("/workflows/runs/{}/steps", run.id), &outcome).await
+}
+
+async fn run_workflow(backend: &Backend, json: bool, key: String, variables: Vec<(String, Value)>) -> Result<WorkflowRun> {
+    let workflow = find_workflow(backend, &key).await?;
+    let request = json!({
+        "workflow_id": workflow.id,
+        "variables": variables.into_iter().collect::<HashMap<_, _>>(),
+    });
+    // Server-side steps have run by the time the run is returned; a run
+    // still going waits on a prompt step
+    let mut run: WorkflowRun = backend.post("/workflows/runs", &request).await?;
+    while run.status == WorkflowStatus::Running {
+        let Some(step) = run
+            .current_step_id
+            .as_deref()
+            .and_then(|id| workflow.steps.iter().find(|s| s.id == id))
+        else {
+            break;
+        };
+        if backend.api_token.is_none() {
+            eprintln!(
+                "Step {} needs the agent; set {} to run it from here, or continue the run in the app",
+                step.name, API_TOKEN_ENV
+            );
+            break;
+        }
+        run = run_prompt_step(backend, &run, step).await?;
+    }
+
+    if json {
+        print_json(&run)?;
+    } else {
+        print_run(&run, Some(&workflow));
+    }
+    Ok(run)
+}
+
+fn print_run(run: &WorkflowRun, workflow: Option<&Workflow>) {
+    let step_name = |id: &str| {
+        workflow
+            .and_then(|w| w.steps.iter().find(|s| s.id == id))
+            .map(|s| s.name.clone())
+            .unwrap_or_else(|| id.to_string())
+    };
+
+    println!("Run {} of {}: {:?}", run.id, run.workflow_id, run.status);
+    for attempt in &run.attempts {
+        let mark = if attempt.success { "ok" } else { "failed" };
+        print!("  {} [{}] {} ms", step_name(&attempt.step_id), mark, attempt.duration_ms);
+        match &attempt.error {
+            Some(error) => println!(" - {}", error),
+            None => println!(),
+        }
+    }
+    if let Some(approval) = &run.pending_approval {
+        println!("Waiting for approval of step {}", approval.step_name);
+    }
+    if let Some(error) = &run.error {
+        println!("Error: {}", error);
+    }
+    // The output of the last step that succeeded is usually the answer
+    if let Some(result) = run
+        .attempts
+        .iter()
+        .rev()
+        .filter(|a| a.success)
+        .find_map(|a| run.step_outputs.get(&a.step_id))
+    {
+        if !result.output.trim().is_empty() {
+            println!("\n{}", result.output.trim());
+        }
+    }
+}
+
+async fn run(args: Args) -> Result<bool> {
+    let backend = Backend::new(&args);
+    match args.command {
+        Command::Search { query, path, workspace, limit } => {
+            search(&backend, args.json, query.join(" "), path, workspace, limit).await?;
+        }
+        Command::Ask { question } => {
+            let question = question.join(" ");
+            if question.trim().is_empty() {
+                bail!("Nothing to ask");
+            }
+            let answer = backend.ask(&question).await?;
+            if args.json {
+                print_json(&json!({ "question": question, "answer": answer }))?;
+            } else {
+                println!("{}", answer);
+            }
+        }
+        Command::Workflow(WorkflowCommand::List) => {
+            let workflows: Vec<Workflow> = backend.get("/workflows", &[]).await?;
+            if args.json {
+                return print_json(&workflows).map(|_| true);
+            }
+            for workflow in &workflows {
+                println!(
+                    "{}  {} ({}, {} steps)",
+                    workflow.id,
+                    workflow.name,
+                    workflow.category,
+                    workflow.steps.len()
+                );
+            }
+        }
+        Command::Workflow(WorkflowCommand::Run { workflow, variables }) => {
+            let run = run_workflow(&backend, args.json, workflow, variables).await?;
+            return Ok(!matches!(run.status, WorkflowStatus::Failed | WorkflowStatus::Cancelled));
+        }
+        Command::Workflow(WorkflowCommand::Status { run_id }) => {
+            let run: WorkflowRun = backend.get(&format!("/workflows/runs/{}", run_id), &[]).await?;
+            if args.json {
+                print_json(&run)?;
+            } else {
+                print_run(&run, None);
+            }
+        }
+    }
+    Ok(true)
+}
+
+#[tokio::main]
+async fn main() {
+    match run(Args::parse()).await {
+        Ok(true) => {}
+        Ok(false) => std::process::exit(1),
+        Err(error) => {
+            eprintln!("Error: {:#}", error);
+            std::process::exit(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_variable() {
+        assert_eq!(parse_variable("count=3").unwrap(), ("count".to_string(), json!(3)));
+        assert_eq!(
+            parse_variable("folder=~/Documents").unwrap(),
+            ("folder".to_string(), json!("~/Documents"))
+        );
+        assert_eq!(parse_variable("query=a=b").unwrap().1, json!("a=b"));
+        assert!(parse_variable("novalue").is_err());
+        assert!(parse_variable("=3").is_err());
+    }
+
+    #[test]
+    fn test_decision_from_output() {
+        assert!(decision_from_output("Looks good: {\"approved\": true}"));
+        assert!(!decision_from_output("{\"isValid\": false, \"reason\": \"yes it failed\"}"));
+        assert!(decision_from_output("Yes, the tests pass."));
+        assert!(!decision_from_output("No."));
+    }
+}
@@ -0,0 +1,336 @@
+//! What changed on disk recently
+//!
+//! `file_history` only knows about operations made through Skhoot. The
+//! timeline also covers edits made by other programs: a watcher over the
+//! search workspaces' roots records creations, modifications and removals as
+//! they happen, and each query scans the requested roots for files whose
+//! modification time falls in the window. Removed files are only known from
+//! the watcher. Results are grouped by directory, most recent first.
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use crate::ignore_rules::IgnoreRules;
+
+/// Watcher observations kept before the oldest are dropped
+const MAX_OBSERVED: usize = 20_000;
+
+/// How long watcher observations are kept
+const OBSERVED_RETENTION_DAYS: i64 = 7;
+
+/// Entries a single scan visits before giving up
+const MAX_SCANNED_ENTRIES: usize = 200_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Created,
+    Modified,
+    Removed,
+}
+
+/// How a change was found
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeSource {
+    Watcher,
+    Scan,
+}
+
+/// A file that changed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChangedFile {
+    pub path: PathBuf,
+    pub name: String,
+    pub kind: ChangeKind,
+    pub changed_at: DateTime<Utc>,
+    /// `None` for removed files
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+    pub source: ChangeSource,
+}
+
+/// Changes inside one directory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DirectoryChanges {
+    pub directory: PathBuf,
+    pub latest: DateTime<Utc>,
+    pub files: Vec<ChangedFile>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangeTimelineReport {
+    pub since: DateTime<Utc>,
+    pub roots: Vec<PathBuf>,
+    pub total: usize,
+    /// Set when a scan stopped early or `limit` cut the list
+    pub truncated: bool,
+    pub directories: Vec<DirectoryChanges>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Observed {
+    kind: ChangeKind,
+    at: DateTime<Utc>,
+}
+
+/// Changes seen by the watcher
+#[derive(Debug, Default)]
+pub struct ChangeTimeline {
+    observed: RwLock<HashMap<PathBuf, Observed>>,
+}
+
+lazy_static::lazy_static! {
+    static ref GLOBAL_TIMELINE: Arc<ChangeTimeline> = Arc::new(ChangeTimeline::default());
+}
+
+impl ChangeTimeline {
+    pub fn global() -> Arc<ChangeTimeline> {
+        GLOBAL_TIMELINE.clone()
+    }
+
+    /// Note a change seen at `at`. A file created and then modified stays
+    /// created; one removed and then written again counts as created.
+    pub fn record(&self, path: PathBuf, kind: ChangeKind, at: DateTime<Utc>) {
+        let mut observed = self.observed.write().unwrap();
+        let kind = match (observed.get(&path).map(|o| o.kind), kind) {
+            (Some(ChangeKind::Created), ChangeKind::Modified) => ChangeKind::Created,
+            (Some(ChangeKind::Removed), ChangeKind::Modified) => ChangeKind::Created,
+            (_, kind) => kind,
+        };
+        observed.insert(path, Observed { kind, at });
+
+        if observed.len() > MAX_OBSERVED {
+            let cutoff = Utc::now() - Duration::days(OBSERVED_RETENTION_DAYS);
+            observed.retain(|_, o| o.at > cutoff);
+            if observed.len() > MAX_OBSERVED {
+                let mut times: Vec<_> = observed.values().map(|o| o.at).collect();
+                times.sort_unstable();
+                let oldest_kept = times[times.len() - MAX_OBSERVED];
+                observed.retain(|_, o| o.at >= oldest_kept);
+            }
+        }
+    }
+
+    /// Files under `roots` changed since `since`, at most `limit` of them
+    pub fn changes(&self, roots: &[PathBuf], since: DateTime<Utc>, limit: usize) -> ChangeTimelineReport {
+        let mut changes: HashMap<PathBuf, ChangedFile> = HashMap::new();
+        let mut truncated = false;
+
+        for root in roots {
+            let mut visited = 0;
+            for entry in IgnoreRules::SEARCH.walker(root).build().filter_map(|e| e.ok()) {
+                visited += 1;
+                if visited > MAX_SCANNED_ENTRIES {
+                    truncated = true;
+                    break;
+                }
+                if !entry.file_type().is_some_and(|t| t.is_file()) {
+                    continue;
+                }
+                let Ok(metadata) = entry.metadata() else { continue };
+                let Some(modified) = metadata.modified().ok().map(DateTime::<Utc>::from) else { continue };
+                if modified < since {
+                    continue;
+                }
+                let created = metadata.created().ok().map(DateTime::<Utc>::from).is_some_and(|c| c >= since);
+                let path = entry.into_path();
+                changes.insert(
+                    path.clone(),
+                    ChangedFile {
+                        name: file_name(&path),
+                        path,
+                        kind: if created { ChangeKind::Created } else { ChangeKind::Modified },
+                        changed_at: modified,
+                        size: Some(metadata.len()),
+                        source: ChangeSource::Scan,
+                    },
+                );
+            }
+        }
+
+        // The watcher knows about removals, and about creations on systems
+        // without birth times
+        for (path, observed) in self.observed.read().unwrap().iter() {
+            if observed.at < since || !roots.iter().any(|root| path.starts_with(root)) {
+                continue;
+            }
+            match observed.kind {
+                ChangeKind::Removed if !path.exists() => {
+                    changes.insert(
+                        path.clone(),
+                        ChangedFile {
+                            path: path.clone(),
+                            name: file_name(path),
+                            kind: ChangeKind::Removed,
+                            changed_at: observed.at,
+                            size: None,
+                            source: ChangeSource::Watcher,
+                        },
+                    );
+                }
+                ChangeKind::Created => {
+                    if let Some(change) = changes.get_mut(path) {
+                        change.kind = ChangeKind::Created;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let mut files: Vec<ChangedFile> = changes.into_values().collect();
+        files.sort_by(|a, b| b.changed_at.cmp(&a.changed_at).then_with(|| a.path.cmp(&b.path)));
+        let total = files.len();
+        if files.len() > limit {
+            files.truncate(limit);
+            truncated = true;
+        }
+
+        let mut directories: Vec<DirectoryChanges> = Vec::new();
+        for file in files {
+            let directory = file.path.parent().map(Path::to_path_buf).unwrap_or_default();
+            match directories.iter_mut().find(|d| d.directory == directory) {
+                Some(group) => group.files.push(file),
+                None => directories.push(DirectoryChanges {
+                    directory,
+                    latest: file.changed_at,
+                    files: vec![file],
+                }),
+            }
+        }
+
+        ChangeTimelineReport {
+            since,
+            roots: roots.to_vec(),
+            total,
+            truncated,
+            directories,
+        }
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default()
+}
+
+/// Roots of the configured search workspaces
+fn watched_roots() -> Vec<PathBuf> {
+    let mut roots: Vec<PathBuf> = Vec::new();
+    for workspace in crate::config::SettingsStore::global().get().search.workspaces {
+        for root in workspace.roots {
+            let root = match (root.strip_prefix('~'), dirs::home_dir()) {
+                (Some(rest), Some(home)) => home.join(rest.trim_start_matches('/')),
+                _ => PathBuf::from(&root),
+            };
+            let root = root.canonicalize().unwrap_or(root);
+            if root.is_dir() && !roots.contains(&root) {
+                roots.push(root);
+            }
+        }
+    }
+    roots
+}
+
+/// Record changes under the search workspaces' roots, re-watching when the
+/// workspaces change
+pub fn spawn_watcher(timeline: Arc<ChangeTimeline>) -> tokio::task::JoinHandle<()> {
+    use notify::{EventKind, RecursiveMode, Watcher};
+
+    tokio::spawn(async move {
+        let mut watched = Vec::new();
+        // Kept alive until the roots change
+        let mut _watcher: Option<notify::RecommendedWatcher> = None;
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(10));
+        loop {
+            interval.tick().await;
+            let roots = watched_roots();
+            if roots == watched {
+                continue;
+            }
+            watched = roots;
+            _watcher = None;
+            if watched.is_empty() {
+                continue;
+            }
+
+            let timeline = timeline.clone();
+            let created = notify::recommended_watcher(move |res: Result<notify::Event, notify::Error>| {
+                let Ok(event) = res else { return };
+                let kind = match event.kind {
+                    EventKind::Create(_) => ChangeKind::Created,
+                    EventKind::Modify(_) => ChangeKind::Modified,
+                    EventKind::Remove(_) => ChangeKind::Removed,
+                    _ => return,
+                };
+                for path in event.paths {
+                    if kind != ChangeKind::Removed && !path.is_file() || is_ignored(&path) {
+                        continue;
+                    }
+                    timeline.record(path, kind, Utc::now());
+                }
+            });
+            match created {
+                Ok(mut created) => {
+                    for root in &watched {
+                        if let Err(e) = created.watch(root, RecursiveMode::Recursive) {
+                            tracing::warn!("Cannot watch {} for changes: {}", root.display(), e);
+                        }
+                    }
+                    _watcher = Some(created);
+                }
+                Err(e) => tracing::warn!("Cannot watch search workspaces for changes: {}", e),
+            }
+        }
+    })
+}
+
+/// Git internals and files the ignore files exclude are not changes
+fn is_ignored(path: &Path) -> bool {
+    path.components().any(|c| c.as_os_str() == ".git")
+        || path
+            .parent()
+            .is_some_and(|parent| IgnoreRules::SEARCH.matcher(parent).is_ignored(path, false))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan_and_watcher_changes_grouped_by_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        std::fs::create_dir_all(root.join("docs")).unwrap();
+        std::fs::write(root.join("docs/today.md"), "new").unwrap();
+        std::fs::write(root.join("main.rs"), "fn main() {}").unwrap();
+        let old = std::fs::File::options().write(true).create(true).truncate(true).open(root.join("old.txt")).unwrap();
+        old.set_modified(std::time::SystemTime::now() - std::time::Duration::from_secs(3 * 24 * 3600))
+            .unwrap();
+
+        let timeline = ChangeTimeline::default();
+        let now = Utc::now();
+        timeline.record(root.join("docs/gone.md"), ChangeKind::Removed, now);
+        timeline.record(root.join("main.rs"), ChangeKind::Created, now);
+        timeline.record(root.join("main.rs"), ChangeKind::Modified, now);
+        timeline.record(PathBuf::from("/elsewhere/file.txt"), ChangeKind::Removed, now);
+
+        let report = timeline.changes(&[root.clone()], now - Duration::hours(24), 100);
+        assert_eq!(report.total, 3);
+        assert!(!report.truncated);
+        let docs = report.directories.iter().find(|d| d.directory == root.join("docs")).unwrap();
+        let mut names: Vec<_> = docs.files.iter().map(|f| (f.name.as_str(), f.kind)).collect();
+        names.sort_by_key(|(name, _)| *name);
+        assert_eq!(names[0], ("gone.md", ChangeKind::Removed));
+        assert_eq!(names[1].0, "today.md");
+        let top = report.directories.iter().find(|d| d.directory == root).unwrap();
+        assert_eq!(top.files.len(), 1);
+        assert_eq!((top.files[0].name.as_str(), top.files[0].kind), ("main.rs", ChangeKind::Created));
+
+        let limited = timeline.changes(&[root], now - Duration::hours(24), 1);
+        assert!(limited.truncated);
+        assert_eq!(limited.directories.iter().map(|d| d.files.len()).sum::<usize>(), 1);
+    }
+}
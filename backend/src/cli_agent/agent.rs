@@ -7,6 +7,7 @@ use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
 
 use super::instructions::SystemPrompt;
+use super::prompt_templates::PromptTemplate;
 use super::tools::{Tool, ToolCall, ToolRegistry, ToolResult};
 
 /// Agent configuration
@@ -30,6 +31,9 @@ pub struct AgentConfig {
     pub max_tool_calls_per_turn: u32,
     /// Optional terminal session ID for persistent shell
     pub terminal_session_id: Option<String>,
+    /// Environment profile for shell commands (settings default if unset)
+    #[serde(default)]
+    pub environment_profile: Option<String>,
 }
 
 impl Default for AgentConfig {
@@ -50,6 +54,7 @@ impl Default for AgentConfig {
             tool_timeout_ms: 30000,
             max_tool_calls_per_turn: 10,
             terminal_session_id: None,
+            environment_profile: None,
         }
     }
 }
@@ -134,7 +139,14 @@ impl Agent {
 
     /// Create a new agent with custom configuration
     pub fn with_config(id: String, config: AgentConfig) -> Self {
-        let tool_registry = ToolRegistry::with_tools(config.enabled_tools.clone());
+        let mut tool_registry = ToolRegistry::with_tools(config.enabled_tools.clone());
+        let external = crate::mcp::McpManager::global()
+            .tool_definitions()
+            .into_iter()
+            .chain(crate::plugins::PluginRegistry::global().tool_definitions());
+        for def in external {
+            tool_registry.register_external(def);
+        }
         let now = Instant::now();
 
         Self {
@@ -183,6 +195,19 @@ impl Agent {
         &self.system_prompt
     }
 
+    /// Apply user-defined prompt sections on top of the default prompt
+    pub fn set_prompt_template(&mut self, template: PromptTemplate) {
+        let project = self.system_prompt.project.take();
+        self.system_prompt = SystemPrompt::default_skhoot()
+            .with_template(template)
+            .with_project(project);
+    }
+
+    /// Describe the session's project in the system prompt
+    pub fn set_project_summary(&mut self, summary: Option<String>) {
+        self.system_prompt.project = summary;
+    }
+
     /// Build the complete system prompt with context
     pub fn build_system_prompt(&self) -> String {
         let os_info = std::env::consts::OS;
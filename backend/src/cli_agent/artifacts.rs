@@ -0,0 +1,147 @@
+//! Files and images returned by tools
+//!
+//! A tool result's text is what the model reads; attachments are what the
+//! user sees. An attachment points at a file on disk by path, so large
+//! artifacts never travel through the conversation. Small images also carry
+//! an inline data URL the UI can render without another request, and an
+//! attachment may cover just a byte range of a large file.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Images up to this size are inlined as a data URL
+pub const MAX_INLINE_IMAGE_BYTES: u64 = 256 * 1024;
+
+/// Largest binary content a tool may hand over to be saved as an artifact
+pub const MAX_ARTIFACT_BYTES: usize = 50 * 1024 * 1024;
+
+/// Part of a file, in bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ByteRange {
+    pub offset: u64,
+    pub length: u64,
+}
+
+/// A file a tool produced or pointed at
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolAttachment {
+    pub name: String,
+    pub mime_type: String,
+    pub path: String,
+    pub size_bytes: u64,
+    /// `data:` URL of a small image
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thumbnail: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub width: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub height: Option<u32>,
+    /// Set when only part of the file is meant
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub range: Option<ByteRange>,
+}
+
+impl ToolAttachment {
+    /// Describe the file at `path`, inlining it when it is a small image
+    pub fn from_path(path: &Path) -> std::io::Result<Self> {
+        let size_bytes = std::fs::metadata(path)?.len();
+        let mime_type = mime_guess::from_path(path)
+            .first_raw()
+            .unwrap_or("application/octet-stream")
+            .to_string();
+
+        let mut attachment = Self {
+            name: path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| path.display().to_string()),
+            mime_type,
+            path: path.display().to_string(),
+            size_bytes,
+            thumbnail: None,
+            width: None,
+            height: None,
+            range: None,
+        };
+        if attachment.is_image() {
+            if let Ok(size) = imagesize::size(path) {
+                attachment.width = Some(size.width as u32);
+                attachment.height = Some(size.height as u32);
+            }
+            if size_bytes <= MAX_INLINE_IMAGE_BYTES {
+                let data = std::fs::read(path)?;
+                attachment.thumbnail =
+                    Some(format!("data:{};base64,{}", attachment.mime_type, STANDARD.encode(data)));
+            }
+        }
+        Ok(attachment)
+    }
+
+    /// Save base64 content a tool returned (an MCP image, say) to the
+    /// artifacts directory and describe it
+    pub fn from_base64(data: &str, mime_type: &str) -> std::io::Result<Self> {
+        let invalid = |message: String| std::io::Error::new(std::io::ErrorKind::InvalidData, message);
+        if data.len() / 4 * 3 > MAX_ARTIFACT_BYTES {
+            return Err(invalid(format!("Artifact is larger than {} bytes", MAX_ARTIFACT_BYTES)));
+        }
+        let bytes = STANDARD.decode(data.trim()).map_err(|e| invalid(e.to_string()))?;
+
+        let extension = mime_guess::get_mime_extensions_str(mime_type)
+            .and_then(|extensions| extensions.first())
+            .unwrap_or(&"bin");
+        let dir = artifacts_dir();
+        std::fs::create_dir_all(&dir)?;
+        let path = dir.join(format!("{}.{}", uuid::Uuid::new_v4(), extension));
+        std::fs::write(&path, bytes)?;
+
+        let mut attachment = Self::from_path(&path)?;
+        attachment.mime_type = mime_type.to_string();
+        Ok(attachment)
+    }
+
+    /// Narrow the attachment to `length` bytes from `offset`
+    pub fn with_range(mut self, offset: u64, length: u64) -> Self {
+        let offset = offset.min(self.size_bytes);
+        self.range = Some(ByteRange { offset, length: length.min(self.size_bytes - offset) });
+        self
+    }
+
+    pub fn is_image(&self) -> bool {
+        self.mime_type.starts_with("image/")
+    }
+}
+
+/// Where artifacts tools hand over as content are written
+pub fn artifacts_dir() -> PathBuf {
+    std::env::temp_dir().join("skhoot-tool-artifacts")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 1x1 transparent PNG
+    const PIXEL_PNG: &str = "iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNkYPhfDwAChwGA60e6kgAAAABJRU5ErkJggg==";
+
+    #[test]
+    fn test_small_image_is_inlined() {
+        let attachment = ToolAttachment::from_base64(PIXEL_PNG, "image/png").unwrap();
+        assert!(attachment.path.ends_with(".png"));
+        assert_eq!((attachment.width, attachment.height), (Some(1), Some(1)));
+        assert_eq!(attachment.thumbnail.as_deref(), Some(format!("data:image/png;base64,{}", PIXEL_PNG).as_str()));
+        std::fs::remove_file(&attachment.path).unwrap();
+    }
+
+    #[test]
+    fn test_file_range_is_clamped() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("report.csv");
+        std::fs::write(&path, "size,path\n10,/tmp\n").unwrap();
+
+        let attachment = ToolAttachment::from_path(&path).unwrap().with_range(10, 1000);
+        assert_eq!(attachment.mime_type, "text/csv");
+        assert!(attachment.thumbnail.is_none());
+        assert_eq!(attachment.range, Some(ByteRange { offset: 10, length: 8 }));
+    }
+}
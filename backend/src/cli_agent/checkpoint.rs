@@ -0,0 +1,360 @@
+//! Checkpoints for agent file modifications
+//!
+//! Before `write_file` or `apply_patch` touches a file, its current content is
+//! copied into a content-addressed object store and recorded in a per-session
+//! checkpoint list. Reverting a checkpoint restores every file the agent
+//! modified since that checkpoint, newest first. Objects no checkpoint of
+//! any session refers to anymore are deleted after a revert.
+//!
+//! Layout under `~/.skhoot/checkpoints`:
+//! - `objects/<sha256>`: file contents
+//! - `sessions/<session_id>.json`: ordered list of checkpoints
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use super::executor::ExecutorError;
+use crate::json_store;
+
+/// State of one file before a modification
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileSnapshot {
+    pub path: PathBuf,
+    /// Hash of the stored content, or `None` if the file did not exist
+    pub hash: Option<String>,
+}
+
+/// Files captured before a single tool call modified them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub id: String,
+    pub session_id: String,
+    pub tool: String,
+    pub created_at: DateTime<Utc>,
+    pub files: Vec<FileSnapshot>,
+}
+
+/// Content-addressed checkpoint store
+#[derive(Debug)]
+pub struct CheckpointManager {
+    root: PathBuf,
+    /// Serializes read-modify-write cycles on session index files, and
+    /// storing objects against deleting unreferenced ones
+    lock: Mutex<()>,
+}
+
+lazy_static::lazy_static! {
+    static ref GLOBAL_MANAGER: Arc<CheckpointManager> = Arc::new(CheckpointManager::new(
+        dirs::home_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join(".skhoot")
+            .join("checkpoints"),
+    ));
+}
+
+impl CheckpointManager {
+    pub fn new(root: PathBuf) -> Self {
+        Self {
+            root,
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// Shared store at `~/.skhoot/checkpoints`
+    pub fn global() -> Arc<CheckpointManager> {
+        GLOBAL_MANAGER.clone()
+    }
+
+    /// Snapshot `paths` and append a checkpoint to the session
+    pub fn create(&self, session_id: &str, tool: &str, paths: &[PathBuf]) -> Result<Checkpoint, ExecutorError> {
+        let _guard = self.lock.lock().unwrap();
+        let mut files = Vec::new();
+        for path in paths {
+            if files.iter().any(|f: &FileSnapshot| &f.path == path) {
+                continue;
+            }
+            let hash = match std::fs::read(path) {
+                Ok(content) => Some(self.store_object(&content)?),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+                Err(e) => {
+                    return Err(ExecutorError::FileOperation(format!(
+                        "Failed to snapshot {}: {}",
+                        path.display(),
+                        e
+                    )))
+                }
+            };
+            files.push(FileSnapshot { path: path.clone(), hash });
+        }
+
+        let checkpoint = Checkpoint {
+            id: uuid::Uuid::new_v4().to_string(),
+            session_id: session_id.to_string(),
+            tool: tool.to_string(),
+            created_at: Utc::now(),
+            files,
+        };
+
+        let mut checkpoints = self.read_session(session_id)?;
+        checkpoints.push(checkpoint.clone());
+        self.write_session(session_id, &checkpoints)?;
+
+        Ok(checkpoint)
+    }
+
+    /// Checkpoints of a session, oldest first
+    pub fn list(&self, session_id: &str) -> Result<Vec<Checkpoint>, ExecutorError> {
+        let _guard = self.lock.lock().unwrap();
+        self.read_session(session_id)
+    }
+
+    /// Undo every modification made since (and including) the given checkpoint.
+    /// Returns the restored paths.
+    pub fn revert_checkpoint(&self, session_id: &str, checkpoint_id: &str) -> Result<Vec<PathBuf>, ExecutorError> {
+        let _guard = self.lock.lock().unwrap();
+        let mut checkpoints = self.read_session(session_id)?;
+        let index = checkpoints
+            .iter()
+            .position(|c| c.id == checkpoint_id)
+            .ok_or_else(|| ExecutorError::InvalidArgument(format!("Checkpoint not found: {}", checkpoint_id)))?;
+
+        let restored = self.restore(&checkpoints[index..])?;
+        checkpoints.truncate(index);
+        self.write_session(session_id, &checkpoints)?;
+        self.prune_objects();
+
+        Ok(restored)
+    }
+
+    /// Undo every modification the agent made in the session
+    pub fn revert_session(&self, session_id: &str) -> Result<Vec<PathBuf>, ExecutorError> {
+        let _guard = self.lock.lock().unwrap();
+        let checkpoints = self.read_session(session_id)?;
+
+        let restored = self.restore(&checkpoints)?;
+        self.write_session(session_id, &[])?;
+        self.prune_objects();
+
+        Ok(restored)
+    }
+
+    /// Restore files from the newest checkpoint back to the oldest so each file
+    /// ends up with the content it had before the first checkpoint touching it
+    fn restore(&self, checkpoints: &[Checkpoint]) -> Result<Vec<PathBuf>, ExecutorError> {
+        let mut restored: Vec<PathBuf> = Vec::new();
+
+        for checkpoint in checkpoints.iter().rev() {
+            for file in &checkpoint.files {
+                match &file.hash {
+                    Some(hash) => {
+                        let content = std::fs::read(self.object_path(hash)).map_err(|e| {
+                            ExecutorError::FileOperation(format!("Missing checkpoint object {}: {}", hash, e))
+                        })?;
+                        if let Some(parent) = file.path.parent() {
+                            std::fs::create_dir_all(parent).map_err(|e| {
+                                ExecutorError::FileOperation(format!("Failed to create directory: {}", e))
+                            })?;
+                        }
+                        std::fs::write(&file.path, content).map_err(|e| {
+                            ExecutorError::FileOperation(format!("Failed to restore {}: {}", file.path.display(), e))
+                        })?;
+                    }
+                    None => {
+                        if file.path.exists() {
+                            std::fs::remove_file(&file.path).map_err(|e| {
+                                ExecutorError::FileOperation(format!("Failed to remove {}: {}", file.path.display(), e))
+                            })?;
+                        }
+                    }
+                }
+                if !restored.contains(&file.path) {
+                    restored.push(file.path.clone());
+                }
+            }
+        }
+
+        Ok(restored)
+    }
+
+    /// Write `content` under its hash through a temporary file, so an
+    /// interrupted write never leaves a truncated object behind
+    fn store_object(&self, content: &[u8]) -> Result<String, ExecutorError> {
+        let hash = hex::encode(Sha256::digest(content));
+        let path = self.object_path(&hash);
+        if !path.exists() {
+            let objects = self.root.join("objects");
+            std::fs::create_dir_all(&objects)
+                .map_err(|e| ExecutorError::FileOperation(format!("Failed to create checkpoint store: {}", e)))?;
+            let write = || -> std::io::Result<()> {
+                let mut file = tempfile::NamedTempFile::new_in(&objects)?;
+                file.write_all(content)?;
+                file.as_file().sync_all()?;
+                file.persist(&path).map_err(|e| e.error)?;
+                Ok(())
+            };
+            write().map_err(|e| ExecutorError::FileOperation(format!("Failed to store checkpoint object: {}", e)))?;
+        }
+        Ok(hash)
+    }
+
+    /// Delete objects no checkpoint of any session refers to. Sessions
+    /// share objects, so every index is read; the caller holds `lock`.
+    fn prune_objects(&self) {
+        let Ok(sessions) = std::fs::read_dir(self.root.join("sessions")) else {
+            return;
+        };
+        let mut referenced = HashSet::new();
+        for entry in sessions.flatten() {
+            let checkpoints: Vec<Checkpoint> = match std::fs::read_to_string(entry.path())
+                .map_err(|e| e.to_string())
+                .and_then(|content| serde_json::from_str(&content).map_err(|e| e.to_string()))
+            {
+                Ok(checkpoints) => checkpoints,
+                Err(e) => {
+                    // An index that can't be read may still refer to any object
+                    tracing::warn!("Not pruning checkpoint objects, cannot read {}: {}", entry.path().display(), e);
+                    return;
+                }
+            };
+            referenced.extend(checkpoints.into_iter().flat_map(|c| c.files).filter_map(|f| f.hash));
+        }
+
+        let Ok(objects) = std::fs::read_dir(self.root.join("objects")) else {
+            return;
+        };
+        for entry in objects.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            // Temporary files of writes in progress don't look like hashes
+            let is_object = name.len() == 64 && name.chars().all(|c| c.is_ascii_hexdigit());
+            if is_object && !referenced.contains(&name) {
+                if let Err(e) = std::fs::remove_file(entry.path()) {
+                    tracing::warn!("Failed to delete checkpoint object {}: {}", name, e);
+                }
+            }
+        }
+    }
+
+    fn object_path(&self, hash: &str) -> PathBuf {
+        self.root.join("objects").join(hash)
+    }
+
+    fn session_path(&self, session_id: &str) -> PathBuf {
+        let safe: String = session_id
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+            .collect();
+        self.root.join("sessions").join(format!("{}.json", safe))
+    }
+
+    fn read_session(&self, session_id: &str) -> Result<Vec<Checkpoint>, ExecutorError> {
+        match std::fs::read_to_string(self.session_path(session_id)) {
+            Ok(content) => serde_json::from_str(&content)
+                .map_err(|e| ExecutorError::FileOperation(format!("Corrupt checkpoint index: {}", e))),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(ExecutorError::FileOperation(format!("Failed to read checkpoints: {}", e))),
+        }
+    }
+
+    fn write_session(&self, session_id: &str, checkpoints: &[Checkpoint]) -> Result<(), ExecutorError> {
+        let path = self.session_path(session_id);
+        if checkpoints.is_empty() {
+            let _ = std::fs::remove_file(&path);
+            return Ok(());
+        }
+        json_store::save(&path, checkpoints)
+            .map_err(|e| ExecutorError::FileOperation(format!("Failed to write checkpoints: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_revert_checkpoint_restores_previous_content() {
+        let temp_dir = TempDir::new().unwrap();
+        let manager = CheckpointManager::new(temp_dir.path().join("store"));
+        let file = temp_dir.path().join("main.rs");
+        std::fs::write(&file, "v1").unwrap();
+
+        let first = manager.create("s1", "write_file", &[file.clone()]).unwrap();
+        std::fs::write(&file, "v2").unwrap();
+        manager.create("s1", "write_file", &[file.clone()]).unwrap();
+        std::fs::write(&file, "v3").unwrap();
+
+        let restored = manager.revert_checkpoint("s1", &first.id).unwrap();
+        assert_eq!(restored, vec![file.clone()]);
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "v1");
+        assert!(manager.list("s1").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_revert_session_removes_created_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let manager = CheckpointManager::new(temp_dir.path().join("store"));
+        let existing = temp_dir.path().join("a.txt");
+        let created = temp_dir.path().join("new/b.txt");
+        std::fs::write(&existing, "keep").unwrap();
+
+        manager.create("s1", "apply_patch", &[existing.clone(), created.clone()]).unwrap();
+        std::fs::write(&existing, "changed").unwrap();
+        std::fs::create_dir_all(created.parent().unwrap()).unwrap();
+        std::fs::write(&created, "new").unwrap();
+
+        manager.revert_session("s1").unwrap();
+        assert_eq!(std::fs::read_to_string(&existing).unwrap(), "keep");
+        assert!(!created.exists());
+    }
+
+    #[test]
+    fn test_revert_deletes_unreferenced_objects() {
+        let temp_dir = TempDir::new().unwrap();
+        let manager = CheckpointManager::new(temp_dir.path().join("store"));
+        let objects = temp_dir.path().join("store/objects");
+        let object_count = || std::fs::read_dir(&objects).unwrap().count();
+        let file = temp_dir.path().join("main.rs");
+        let shared = temp_dir.path().join("shared.rs");
+        std::fs::write(&file, "v1").unwrap();
+        std::fs::write(&shared, "shared").unwrap();
+
+        manager.create("s2", "write_file", &[shared.clone()]).unwrap();
+        manager.create("s1", "write_file", &[file.clone()]).unwrap();
+        std::fs::write(&file, "v2").unwrap();
+        let second = manager.create("s1", "write_file", &[file.clone(), shared.clone()]).unwrap();
+        std::fs::write(&file, "v3").unwrap();
+        assert_eq!(object_count(), 3);
+
+        // "v2" was only referenced by the reverted checkpoint
+        manager.revert_checkpoint("s1", &second.id).unwrap();
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "v2");
+        assert_eq!(object_count(), 2);
+
+        // "shared" is still referenced by the other session
+        manager.revert_session("s1").unwrap();
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "v1");
+        assert_eq!(object_count(), 1);
+
+        manager.revert_session("s2").unwrap();
+        assert_eq!(object_count(), 0);
+    }
+
+    #[test]
+    fn test_identical_content_is_stored_once() {
+        let temp_dir = TempDir::new().unwrap();
+        let manager = CheckpointManager::new(temp_dir.path().join("store"));
+        let a = temp_dir.path().join("a.txt");
+        let b = temp_dir.path().join("b.txt");
+        std::fs::write(&a, "same").unwrap();
+        std::fs::write(&b, "same").unwrap();
+
+        let checkpoint = manager.create("s1", "write_file", &[a, b]).unwrap();
+        assert_eq!(checkpoint.files[0].hash, checkpoint.files[1].hash);
+        assert_eq!(std::fs::read_dir(temp_dir.path().join("store/objects")).unwrap().count(), 1);
+    }
+}
@@ -0,0 +1,23 @@
+//! Clipboard access for the agent
+//!
+//! The backend runs as a sidecar without access to the desktop clipboard, so
+//! the `clipboard` tool goes through a [`ClipboardProvider`] supplied by the
+//! host (the Tauri shell). Executors without a provider report the tool as
+//! unavailable.
+
+use std::sync::Arc;
+
+/// System clipboard as seen by the agent
+pub trait ClipboardProvider: Send + Sync {
+    /// Current text content
+    fn read_text(&self) -> Result<String, String>;
+
+    /// Replace the clipboard content with `text`
+    fn write_text(&self, text: &str) -> Result<(), String>;
+
+    /// Replace the clipboard content with an image, given as encoded PNG bytes
+    fn write_image(&self, png: &[u8]) -> Result<(), String>;
+}
+
+/// Shared handle to a clipboard provider
+pub type SharedClipboard = Arc<dyn ClipboardProvider>;
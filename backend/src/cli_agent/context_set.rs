@@ -0,0 +1,547 @@
+//! Pinned context per conversation
+//!
+//! The user can pin files and folders to a conversation. Pins in `include`
+//! mode have their contents added to every request as a system message,
+//! summarized to an outline when the set outgrows its byte budget;
+//! `priority` pins are only listed, as places the agent should look first.
+//! File contents are cached until [`spawn_context_watcher`] sees them change.
+//! Sets persist to `~/.skhoot/context_sets.json`.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use crate::ignore_rules::IgnoreRules;
+use crate::json_store;
+
+/// Budget of a set that doesn't set one
+pub const DEFAULT_BUDGET_BYTES: usize = 32 * 1024;
+/// Largest budget a set may ask for
+pub const MAX_BUDGET_BYTES: usize = 256 * 1024;
+/// Files read from one pinned folder
+const MAX_FOLDER_FILES: usize = 50;
+/// Bytes read from one file; longer files are summarized from their start
+const MAX_FILE_READ_BYTES: u64 = 1024 * 1024;
+/// Files left with a smaller share of the budget are only named
+const MIN_FILE_SHARE: usize = 128;
+
+/// Lines kept in summaries because they declare something
+const OUTLINE_PREFIXES: &[&str] = &[
+    "pub ", "fn ", "struct ", "enum ", "trait ", "impl ", "mod ", "class ", "def ", "async ", "function ",
+    "export ", "interface ", "type ", "const ", "# ", "## ", "### ",
+];
+
+#[derive(Debug, thiserror::Error)]
+pub enum ContextSetError {
+    #[error("Pinned path not found: {0}")]
+    NotFound(String),
+
+    #[error("{0}")]
+    Invalid(String),
+
+    #[error("Failed to save context set: {0}")]
+    Io(String),
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PinMode {
+    /// Contents are added to every request
+    #[default]
+    Include,
+    /// Only listed, as the first place to look
+    Priority,
+}
+
+/// A file or folder pinned to a conversation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContextPin {
+    pub id: String,
+    pub path: PathBuf,
+    pub is_dir: bool,
+    pub mode: PinMode,
+    pub pinned_at: i64,
+}
+
+/// Pins of one conversation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContextSet {
+    #[serde(default)]
+    pub pins: Vec<ContextPin>,
+    pub budget_bytes: usize,
+}
+
+impl Default for ContextSet {
+    fn default() -> Self {
+        Self {
+            pins: Vec::new(),
+            budget_bytes: DEFAULT_BUDGET_BYTES,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PinRequest {
+    pub path: PathBuf,
+    #[serde(default)]
+    pub mode: Option<PinMode>,
+}
+
+/// A file as it went into the rendered context
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RenderedFile {
+    pub path: PathBuf,
+    /// Size of the file's text
+    pub bytes: usize,
+    /// Bytes of it included
+    pub included_bytes: usize,
+    pub summarized: bool,
+}
+
+/// The system message built from a set
+#[derive(Debug, Clone, Serialize)]
+pub struct RenderedContext {
+    pub text: String,
+    pub files: Vec<RenderedFile>,
+}
+
+pub struct ContextSetStore {
+    path: PathBuf,
+    sets: RwLock<HashMap<String, ContextSet>>,
+    /// File text read for rendering; `None` for binary files
+    cache: RwLock<HashMap<PathBuf, Option<Arc<String>>>>,
+}
+
+lazy_static::lazy_static! {
+    static ref GLOBAL_STORE: Arc<ContextSetStore> = Arc::new(ContextSetStore::new(
+        dirs::home_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join(".skhoot")
+            .join("context_sets.json"),
+    ));
+}
+
+impl ContextSetStore {
+    /// Open the store at `path`; see [`json_store::load`]
+    pub fn new(path: PathBuf) -> Self {
+        let sets = json_store::load(&path);
+        Self {
+            path,
+            sets: RwLock::new(sets),
+            cache: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Shared store at `~/.skhoot/context_sets.json`
+    pub fn global() -> Arc<ContextSetStore> {
+        GLOBAL_STORE.clone()
+    }
+
+    /// The conversation's set; empty if nothing was pinned
+    pub fn get(&self, session_id: &str) -> ContextSet {
+        self.sets.read().unwrap().get(session_id).cloned().unwrap_or_default()
+    }
+
+    /// Pin a file or folder; pinning a path again changes its mode
+    pub fn pin(&self, session_id: &str, request: PinRequest) -> Result<ContextPin, ContextSetError> {
+        let path = request
+            .path
+            .canonicalize()
+            .map_err(|_| ContextSetError::Invalid(format!("No such file or folder: {}", request.path.display())))?;
+        let mode = request.mode.unwrap_or_default();
+
+        let mut sets = self.sets.write().unwrap();
+        let set = sets.entry(session_id.to_string()).or_default();
+        let pin = match set.pins.iter_mut().find(|pin| pin.path == path) {
+            Some(pin) => {
+                pin.mode = mode;
+                pin.clone()
+            }
+            None => {
+                let pin = ContextPin {
+                    id: uuid::Uuid::new_v4().to_string(),
+                    is_dir: path.is_dir(),
+                    path,
+                    mode,
+                    pinned_at: chrono::Utc::now().timestamp(),
+                };
+                set.pins.push(pin.clone());
+                pin
+            }
+        };
+        self.save(&sets)?;
+        Ok(pin)
+    }
+
+    pub fn set_mode(&self, session_id: &str, pin_id: &str, mode: PinMode) -> Result<ContextPin, ContextSetError> {
+        let mut sets = self.sets.write().unwrap();
+        let pin = sets
+            .get_mut(session_id)
+            .and_then(|set| set.pins.iter_mut().find(|pin| pin.id == pin_id))
+            .ok_or_else(|| ContextSetError::NotFound(pin_id.to_string()))?;
+        pin.mode = mode;
+        let pin = pin.clone();
+        self.save(&sets)?;
+        Ok(pin)
+    }
+
+    /// Remove a pin. Returns whether it existed.
+    pub fn unpin(&self, session_id: &str, pin_id: &str) -> Result<bool, ContextSetError> {
+        let mut sets = self.sets.write().unwrap();
+        let Some(set) = sets.get_mut(session_id) else {
+            return Ok(false);
+        };
+        let before = set.pins.len();
+        set.pins.retain(|pin| pin.id != pin_id);
+        if set.pins.len() == before {
+            return Ok(false);
+        }
+        if set.pins.is_empty() && set.budget_bytes == DEFAULT_BUDGET_BYTES {
+            sets.remove(session_id);
+        }
+        self.save(&sets)?;
+        Ok(true)
+    }
+
+    pub fn set_budget(&self, session_id: &str, budget_bytes: usize) -> Result<ContextSet, ContextSetError> {
+        if budget_bytes == 0 || budget_bytes > MAX_BUDGET_BYTES {
+            return Err(ContextSetError::Invalid(format!(
+                "budget_bytes must be between 1 and {}",
+                MAX_BUDGET_BYTES
+            )));
+        }
+        let mut sets = self.sets.write().unwrap();
+        let set = sets.entry(session_id.to_string()).or_default();
+        set.budget_bytes = budget_bytes;
+        let set = set.clone();
+        self.save(&sets)?;
+        Ok(set)
+    }
+
+    /// Every pinned path, with whether it's a folder
+    pub fn watched_paths(&self) -> Vec<(PathBuf, bool)> {
+        let mut paths: Vec<(PathBuf, bool)> = self
+            .sets
+            .read()
+            .unwrap()
+            .values()
+            .flat_map(|set| set.pins.iter().map(|pin| (pin.path.clone(), pin.is_dir)))
+            .collect();
+        paths.sort();
+        paths.dedup();
+        paths
+    }
+
+    /// Forget the cached text of a changed file
+    pub fn invalidate(&self, path: &Path) {
+        self.cache.write().unwrap().remove(path);
+    }
+
+    /// Text of a file, from the cache when it hasn't changed
+    fn read(&self, path: &Path) -> Option<Arc<String>> {
+        if let Some(cached) = self.cache.read().unwrap().get(path) {
+            return cached.clone();
+        }
+        let text = read_text(path).map(Arc::new);
+        self.cache.write().unwrap().insert(path.to_path_buf(), text.clone());
+        text
+    }
+
+    /// Files the `include` pins of a set stand for, in pin order
+    fn included_files(set: &ContextSet) -> Vec<PathBuf> {
+        let mut files: Vec<PathBuf> = Vec::new();
+        for pin in set.pins.iter().filter(|pin| pin.mode == PinMode::Include) {
+            if !pin.is_dir {
+                files.push(pin.path.clone());
+                continue;
+            }
+            let mut found: Vec<PathBuf> = IgnoreRules::SEARCH
+                .walker(&pin.path)
+                .build()
+                .flatten()
+                .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+                .filter(|entry| !entry.path().components().any(|c| c.as_os_str() == ".git"))
+                .map(|entry| entry.into_path())
+                .collect();
+            found.sort();
+            files.extend(found.into_iter().take(MAX_FOLDER_FILES));
+        }
+        let mut seen = std::collections::HashSet::new();
+        files.retain(|file| seen.insert(file.clone()));
+        files
+    }
+
+    /// System message for a conversation; `None` when nothing is pinned
+    pub fn render(&self, session_id: &str) -> Option<RenderedContext> {
+        let set = self.get(session_id);
+        if set.pins.is_empty() {
+            return None;
+        }
+
+        let texts: Vec<(PathBuf, Arc<String>)> = Self::included_files(&set)
+            .into_iter()
+            .filter_map(|path| self.read(&path).map(|text| (path, text)))
+            .collect();
+
+        // Smallest files first, each taking at most an even share of what's
+        // left, so the budget isn't spent on the first large file
+        let mut allowances = vec![0; texts.len()];
+        let mut order: Vec<usize> = (0..texts.len()).collect();
+        order.sort_by_key(|&i| texts[i].1.len());
+        let mut remaining = set.budget_bytes;
+        for (done, &i) in order.iter().enumerate() {
+            let share = remaining / (texts.len() - done);
+            allowances[i] = texts[i].1.len().min(share);
+            remaining -= allowances[i];
+        }
+
+        let mut text = String::new();
+        let mut files = Vec::new();
+        if !texts.is_empty() {
+            text.push_str(
+                "The user pinned these files to this conversation. Their current contents follow; \
+                 summarized files only show their start and outline, so read them when you need the rest.\n",
+            );
+        }
+        for ((path, content), allowance) in texts.iter().zip(allowances) {
+            if allowance < MIN_FILE_SHARE && allowance < content.len() {
+                text.push_str(&format!("\n<file path=\"{}\" omitted=\"over budget\" />\n", path.display()));
+                files.push(RenderedFile { path: path.clone(), bytes: content.len(), included_bytes: 0, summarized: true });
+                continue;
+            }
+            let summarized = allowance < content.len();
+            let body = if summarized { summarize(content, allowance) } else { content.to_string() };
+            text.push_str(&format!(
+                "\n<file path=\"{}\"{}>\n{}\n</file>\n",
+                path.display(),
+                if summarized { " summarized=\"true\"" } else { "" },
+                body.trim_end()
+            ));
+            files.push(RenderedFile { path: path.clone(), bytes: content.len(), included_bytes: body.len(), summarized });
+        }
+
+        let priority: Vec<&ContextPin> = set.pins.iter().filter(|pin| pin.mode == PinMode::Priority).collect();
+        if !priority.is_empty() {
+            if !text.is_empty() {
+                text.push('\n');
+            }
+            text.push_str("The user pinned these paths; look at them first when they're relevant:\n");
+            for pin in priority {
+                let suffix = if pin.is_dir { "/" } else { "" };
+                text.push_str(&format!("- {}{}\n", pin.path.display(), suffix));
+            }
+        }
+
+        Some(RenderedContext { text: text.trim_end().to_string(), files })
+    }
+
+    fn save(&self, sets: &HashMap<String, ContextSet>) -> Result<(), ContextSetError> {
+        json_store::save(&self.path, sets).map_err(|e| ContextSetError::Io(e.to_string()))
+    }
+}
+
+/// Text of a file, or `None` if it's binary or unreadable
+fn read_text(path: &Path) -> Option<String> {
+    use std::io::Read;
+
+    let mut bytes = Vec::new();
+    std::fs::File::open(path)
+        .ok()?
+        .take(MAX_FILE_READ_BYTES)
+        .read_to_end(&mut bytes)
+        .ok()?;
+    if crate::file_preview::is_binary(&bytes) {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// Cut `text` to at most `max` bytes on a char boundary
+fn truncate(text: &str, max: usize) -> &str {
+    let mut end = max.min(text.len());
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    &text[..end]
+}
+
+/// Shorten `content` to about `max_bytes`: its first lines, then the lines
+/// further down that declare something, with their line numbers
+pub fn summarize(content: &str, max_bytes: usize) -> String {
+    if content.len() <= max_bytes {
+        return content.to_string();
+    }
+    let lines: Vec<&str> = content.lines().collect();
+    let marker_room = 64;
+    let budget = max_bytes.saturating_sub(marker_room);
+
+    let mut head = String::new();
+    let mut head_lines = 0;
+    for line in &lines {
+        if head.len() + line.len() + 1 > budget / 2 {
+            break;
+        }
+        head.push_str(line);
+        head.push('\n');
+        head_lines += 1;
+    }
+    if head_lines == 0 {
+        head = truncate(content, budget / 2).to_string();
+        head.push('\n');
+    }
+
+    let mut outline = String::new();
+    for (number, line) in lines.iter().enumerate().skip(head_lines.max(1)) {
+        let trimmed = line.trim_start();
+        if !OUTLINE_PREFIXES.iter().any(|prefix| trimmed.starts_with(prefix)) {
+            continue;
+        }
+        let entry = format!("{}: {}\n", number + 1, truncate(line.trim_end(), 160));
+        if head.len() + outline.len() + entry.len() > budget {
+            break;
+        }
+        outline.push_str(&entry);
+    }
+
+    let mut summary = head;
+    summary.push_str(&format!("[... {} lines in total; outline of the rest:]\n", lines.len()));
+    summary.push_str(&outline);
+    summary
+}
+
+/// Drop the cached text of pinned files when they change. The watched set
+/// follows the pins as they change.
+pub fn spawn_context_watcher(store: Arc<ContextSetStore>) -> tokio::task::JoinHandle<()> {
+    use notify::{EventKind, RecursiveMode, Watcher};
+
+    tokio::spawn(async move {
+        let mut watched = Vec::new();
+        // Kept alive until the pins change
+        let mut _watcher: Option<notify::RecommendedWatcher> = None;
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(2));
+        loop {
+            interval.tick().await;
+            let paths = store.watched_paths();
+            if paths == watched {
+                continue;
+            }
+            watched = paths;
+            _watcher = None;
+            if watched.is_empty() {
+                continue;
+            }
+
+            let target = store.clone();
+            let created = notify::recommended_watcher(move |res: Result<notify::Event, notify::Error>| {
+                let Ok(event) = res else { return };
+                if matches!(event.kind, EventKind::Access(_)) {
+                    return;
+                }
+                for path in event.paths {
+                    target.invalidate(&path);
+                }
+            });
+            match created {
+                Ok(mut created) => {
+                    for (path, is_dir) in &watched {
+                        let mode = if *is_dir { RecursiveMode::Recursive } else { RecursiveMode::NonRecursive };
+                        if let Err(e) = created.watch(path, mode) {
+                            tracing::warn!("Cannot watch pinned path {}: {}", path.display(), e);
+                        }
+                    }
+                    _watcher = Some(created);
+                }
+                Err(e) => tracing::warn!("Cannot watch pinned paths: {}", e),
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pin(store: &ContextSetStore, path: &Path, mode: PinMode) -> ContextPin {
+        store
+            .pin("conv-1", PinRequest { path: path.to_path_buf(), mode: Some(mode) })
+            .unwrap()
+    }
+
+    #[test]
+    fn test_pins_persist() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("notes.md");
+        std::fs::write(&file, "# Notes").unwrap();
+        let store = ContextSetStore::new(dir.path().join("sets.json"));
+
+        let pinned = pin(&store, &file, PinMode::Include);
+        assert!(!pinned.is_dir);
+        // Pinning again only changes the mode
+        let again = pin(&store, &file, PinMode::Priority);
+        assert_eq!((again.id.as_str(), again.mode), (pinned.id.as_str(), PinMode::Priority));
+        assert!(store.pin("conv-1", PinRequest { path: dir.path().join("missing"), mode: None }).is_err());
+        store.set_budget("conv-1", 4096).unwrap();
+        assert!(store.set_budget("conv-1", MAX_BUDGET_BYTES + 1).is_err());
+
+        let reopened = ContextSetStore::new(dir.path().join("sets.json"));
+        let set = reopened.get("conv-1");
+        assert_eq!((set.pins.len(), set.budget_bytes), (1, 4096));
+        assert!(reopened.get("conv-2").pins.is_empty());
+
+        assert!(reopened.unpin("conv-1", &pinned.id).unwrap());
+        assert!(!reopened.unpin("conv-1", &pinned.id).unwrap());
+        assert!(reopened.render("conv-1").is_none());
+    }
+
+    #[test]
+    fn test_render_budget_and_refresh() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("src");
+        std::fs::create_dir(&src).unwrap();
+        std::fs::write(src.join("small.rs"), "fn small() {}\n").unwrap();
+        let big: String = (0..400)
+            .map(|i| if i % 50 == 0 { format!("pub fn item_{}() {{\n", i) } else { format!("    let x{} = {};\n", i, i) })
+            .collect();
+        std::fs::write(src.join("big.rs"), &big).unwrap();
+        std::fs::write(src.join("image.bin"), [0u8, 1, 2, 3]).unwrap();
+        let docs = dir.path().join("docs");
+        std::fs::create_dir(&docs).unwrap();
+
+        let store = ContextSetStore::new(dir.path().join("sets.json"));
+        pin(&store, &src, PinMode::Include);
+        pin(&store, &docs, PinMode::Priority);
+        store.set_budget("conv-1", 2048).unwrap();
+
+        let rendered = store.render("conv-1").unwrap();
+        let names: Vec<(String, bool)> = rendered
+            .files
+            .iter()
+            .map(|f| (f.path.file_name().unwrap().to_string_lossy().to_string(), f.summarized))
+            .collect();
+        assert_eq!(names, vec![("big.rs".to_string(), true), ("small.rs".to_string(), false)]);
+        assert!(rendered.files.iter().map(|f| f.included_bytes).sum::<usize>() <= 2048);
+        assert!(rendered.text.contains("fn small() {}"));
+        assert!(rendered.text.contains("351: pub fn item_350() {"));
+        assert!(rendered.text.contains(&format!("- {}/", docs.canonicalize().unwrap().display())));
+
+        // Cached until the watcher reports a change
+        let small = src.join("small.rs").canonicalize().unwrap();
+        std::fs::write(&small, "fn changed() {}\n").unwrap();
+        assert!(store.render("conv-1").unwrap().text.contains("fn small() {}"));
+        store.invalidate(&small);
+        assert!(store.render("conv-1").unwrap().text.contains("fn changed() {}"));
+    }
+
+    #[test]
+    fn test_summarize() {
+        let content = "line one\nline two\nfn helper() {}\nplain\nstruct Thing;\n";
+        assert_eq!(summarize(content, 1000), content);
+
+        let long = format!("{}{}", "intro line\n".repeat(40), "fn late() {}\n");
+        let summary = summarize(&long, 200);
+        assert!(summary.len() <= 200, "{}", summary.len());
+        assert!(summary.starts_with("intro line\n"));
+        assert!(summary.ends_with("41: fn late() {}\n"));
+    }
+}
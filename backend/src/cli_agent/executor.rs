@@ -8,13 +8,49 @@ use std::time::{Duration, Instant};
 use tokio::time::timeout;
 
 use crate::cli_bridge::{CliBridge, CliError};
-use crate::search_engine::{CliEngine, CliConfig};
-use std::collections::HashMap;
+use crate::cli_bridge::environment::{shell_invocation, shell_kind};
+use crate::cli_bridge::pty::ShellKind;
+use crate::search_engine::{CliEngine, CliConfig, CliFileMatch, CliSearchResult};
+use std::collections::{BTreeMap, HashMap};
 use crate::terminal::TerminalManager;
 use super::tools::{Tool, ToolCall, ToolResult, ToolResultMetadata};
-use super::apply_patch::apply_patch;
+use super::artifacts::ToolAttachment;
+use super::apply_patch::{apply_patch, parse_patch, Hunk};
+use super::workspace::Workspace;
+use super::output_parser::parse_output;
+use super::lint::{self, Formatter, Linter};
+use super::refactor::{RefactorError, RefactorStore, ReplaceSpec};
+use super::jobs::{JobError, JobManager, DEFAULT_OUTPUT_LINES};
+use super::mailbox::Mailbox;
+use super::session_vars::SessionVars;
+use super::git::GitRepo;
+use super::checkpoint::CheckpointManager;
+use super::clipboard::SharedClipboard;
+use super::screen::{CaptureTarget, SharedScreenCapture};
+use super::http_request::{self, HttpRequest, HttpRequestError};
+use super::test_runner::{self, TestRunner};
+use super::throttle::ToolThrottle;
+use super::trace::{TraceEvent, TraceRecorder};
+use crate::attachments::{AttachmentError, AttachmentStore};
+use crate::archives::{self, ArchiveError, ArchiveLimits};
+use crate::file_diff::{self, DiffError, DiffOptions, DiffTarget};
+use crate::file_follow::{self, FollowOptions, StopReason};
+use crate::file_range::{self, RangeError, ReadRange};
+use crate::processes::{self, ProcessError, ProcessInfo, ProcessQuery, ProcessSort};
+use crate::file_history::{FileHistory, OperationOrigin};
+use crate::file_transfer::{self, Collision, TransferError, TransferKind};
+use crate::recycle_bin::{self, DeleteError};
+use crate::scaffold::{self, ScaffoldError, TemplateLibrary};
+use crate::lsp::{CodePosition, LspError, LspManager};
+use crate::mcp::ToolContent;
+use crate::secrets::SecretScanner;
 use std::sync::Arc;
 
+/// Longest a tail_file call may follow a file
+const MAX_TAIL_SECS: u64 = 600;
+/// Most lines a tail_file call returns
+const MAX_TAIL_LINES: u64 = 1000;
+
 /// Tool execution configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExecutorConfig {
@@ -28,6 +64,47 @@ pub struct ExecutorConfig {
     pub allow_writes: bool,
     /// Optional terminal session ID for persistent shell
     pub terminal_session_id: Option<String>,
+    /// Root directory that file tools are confined to (no sandbox if unset)
+    #[serde(default)]
+    pub workspace_root: Option<PathBuf>,
+    /// User-granted permission to access files outside the workspace root
+    #[serde(default)]
+    pub allow_workspace_escape: bool,
+    /// Whether the agent may create git commits
+    #[serde(default = "default_allow_git_commits")]
+    pub allow_git_commits: bool,
+    /// Agent session ID; file modifications are checkpointed when set
+    #[serde(default)]
+    pub session_id: Option<String>,
+    /// User-granted permission to delete files without going through the trash
+    #[serde(default)]
+    pub allow_permanent_delete: bool,
+    /// User-granted permission to kill processes the agent didn't start
+    #[serde(default)]
+    pub allow_process_kill: bool,
+    /// User-granted permission to read and write the system clipboard
+    #[serde(default)]
+    pub allow_clipboard: bool,
+    /// User-granted permission to capture the screen
+    #[serde(default)]
+    pub allow_screen_capture: bool,
+    /// Domains the user allowed `http_request` to reach, subdomains included
+    #[serde(default)]
+    pub allowed_http_domains: Vec<String>,
+    /// Environment profile for shell commands (settings default if unset)
+    #[serde(default)]
+    pub environment_profile: Option<String>,
+    /// Attach JSON, CSV and table output of shell commands as structured data
+    #[serde(default = "default_parse_output")]
+    pub parse_output: bool,
+}
+
+fn default_allow_git_commits() -> bool {
+    true
+}
+
+fn default_parse_output() -> bool {
+    true
 }
 
 impl Default for ExecutorConfig {
@@ -38,6 +115,17 @@ impl Default for ExecutorConfig {
             max_output_size: 1024 * 1024, // 1MB
             allow_writes: true,
             terminal_session_id: None,
+            workspace_root: None,
+            allow_workspace_escape: false,
+            allow_git_commits: true,
+            session_id: None,
+            allow_permanent_delete: false,
+            allow_process_kill: false,
+            allow_clipboard: false,
+            allow_screen_capture: false,
+            allowed_http_domains: Vec::new(),
+            environment_profile: None,
+            parse_output: true,
         }
     }
 }
@@ -50,6 +138,20 @@ pub struct AgentExecutor {
     terminal_manager: Option<TerminalManager>,
     /// Executor configuration
     config: ExecutorConfig,
+    /// Store for undoing file modifications
+    checkpoints: Arc<CheckpointManager>,
+    /// Files attached to conversations
+    attachments: Arc<AttachmentStore>,
+    /// System clipboard, when the host provides one
+    clipboard: Option<SharedClipboard>,
+    /// Screen capture, when the host provides it
+    screen: Option<SharedScreenCapture>,
+    /// Per-session queue and rate limits for tool calls
+    throttle: Arc<ToolThrottle>,
+    /// Variables of agent sessions
+    vars: Arc<SessionVars>,
+    /// Where calls of a session are recorded
+    traces: Arc<TraceRecorder>,
 }
 
 impl AgentExecutor {
@@ -59,6 +161,13 @@ impl AgentExecutor {
             cli_bridge: CliBridge::new(),
             terminal_manager: None,
             config: ExecutorConfig::default(),
+            checkpoints: CheckpointManager::global(),
+            attachments: AttachmentStore::global(),
+            clipboard: None,
+            screen: None,
+            throttle: ToolThrottle::global(),
+            vars: SessionVars::global(),
+            traces: TraceRecorder::global(),
         }
     }
 
@@ -68,6 +177,13 @@ impl AgentExecutor {
             cli_bridge: CliBridge::new(),
             terminal_manager: None,
             config,
+            checkpoints: CheckpointManager::global(),
+            attachments: AttachmentStore::global(),
+            clipboard: None,
+            screen: None,
+            throttle: ToolThrottle::global(),
+            vars: SessionVars::global(),
+            traces: TraceRecorder::global(),
         }
     }
 
@@ -77,22 +193,180 @@ impl AgentExecutor {
         self
     }
 
+    /// Use a specific checkpoint store instead of the shared one
+    pub fn with_checkpoint_manager(mut self, manager: Arc<CheckpointManager>) -> Self {
+        self.checkpoints = manager;
+        self
+    }
+
+    /// Give the agent access to the system clipboard (still gated by
+    /// `allow_clipboard`)
+    pub fn with_clipboard(mut self, clipboard: SharedClipboard) -> Self {
+        self.clipboard = Some(clipboard);
+        self
+    }
+
+    /// Use a specific tool call throttle (defaults to the global one)
+    pub fn with_throttle(mut self, throttle: Arc<ToolThrottle>) -> Self {
+        self.throttle = throttle;
+        self
+    }
+
+    /// Let the agent capture the screen (still gated by
+    /// `allow_screen_capture`)
+    pub fn with_screen_capture(mut self, screen: SharedScreenCapture) -> Self {
+        self.screen = Some(screen);
+        self
+    }
+
+    /// Use a specific attachment store (defaults to the global one)
+    pub fn with_attachment_store(mut self, store: Arc<AttachmentStore>) -> Self {
+        self.attachments = store;
+        self
+    }
+
+    /// Use a specific session variable store (defaults to the global one)
+    pub fn with_session_vars(mut self, vars: Arc<SessionVars>) -> Self {
+        self.vars = vars;
+        self
+    }
+
+    /// Use a specific trace recorder (defaults to the global one)
+    pub fn with_trace_recorder(mut self, recorder: Arc<TraceRecorder>) -> Self {
+        self.traces = recorder;
+        self
+    }
+
     /// Set the working directory
     pub fn set_working_directory(&mut self, path: PathBuf) {
         self.config.working_directory = path;
     }
 
-    /// Execute a tool call
+    /// Execute a tool call; calls of a session are recorded in its trace.
+    /// Secrets in the result are redacted unless the session turned that off.
     pub async fn execute(&self, tool_call: &ToolCall) -> ToolResult {
+        let session_id = self.config.session_id.as_deref();
+        let recorder = &self.traces;
+        if let Some(session_id) = session_id {
+            recorder.record(session_id, TraceEvent::ToolCall { call: tool_call.clone() });
+        }
+        let mut result = self.execute_call(tool_call).await;
+        let scanner = SecretScanner::global();
+        if scanner.enabled_for(session_id) {
+            Self::redact_secrets(&scanner, &mut result);
+        }
+        // Secret variables are hidden even with redaction turned off
+        if let Some(session_id) = session_id {
+            let vars = &self.vars;
+            vars.redact(session_id, &mut result.output);
+            if let Some(error) = &mut result.error {
+                vars.redact(session_id, error);
+            }
+        }
+        if let Some(session_id) = session_id {
+            recorder.record(
+                session_id,
+                TraceEvent::ToolResult {
+                    tool: tool_call.name.clone(),
+                    result: result.clone(),
+                },
+            );
+        }
+        result
+    }
+
+    /// Replace secrets in a result's output, error and structured data,
+    /// noting what was found in its metadata
+    fn redact_secrets(scanner: &SecretScanner, result: &mut ToolResult) {
+        let mut findings: BTreeMap<String, usize> = BTreeMap::new();
+        let output = scanner.redact(&result.output);
+        let error = result.error.as_deref().map(|error| scanner.redact(error));
+        for redaction in std::iter::once(&output).chain(error.as_ref()) {
+            for (kind, count) in &redaction.findings {
+                *findings.entry(kind.to_string()).or_insert(0) += count;
+            }
+        }
+        result.output = output.text;
+        result.error = error.map(|error| error.text);
+
+        let metadata = result.metadata.get_or_insert_with(Default::default);
+        // Structured data is parsed from the output and holds the same secrets
+        if let Some(structured) = &mut metadata.structured {
+            if let Ok(mut value) = serde_json::to_value(&*structured) {
+                if scanner.redact_value(&mut value) > 0 {
+                    metadata.structured = serde_json::from_value(value).ok();
+                }
+            }
+        }
+        if !findings.is_empty() {
+            metadata.redacted_secrets = Some(findings);
+        }
+    }
+
+    async fn execute_call(&self, tool_call: &ToolCall) -> ToolResult {
         let start = Instant::now();
+
+        // Calls of a session wait in its queue; held until the call finishes
+        let _permit = match &self.config.session_id {
+            Some(session_id) => match self.throttle.acquire(session_id, tool_call).await {
+                Ok(permit) => Some(permit),
+                Err(e) => return Self::tool_result(tool_call, Err(ExecutorError::Throttled(e.to_string())), start),
+            },
+            None => None,
+        };
         
         let tool = match tool_call.name.as_str() {
             "shell" => Tool::Shell,
             "read_file" => Tool::ReadFile,
             "write_file" => Tool::WriteFile,
+            "delete_file" => Tool::DeleteFile,
+            "move_file" => Tool::MoveFile,
+            "copy_file" => Tool::CopyFile,
+            "rename_file" => Tool::RenameFile,
+            "archive" => Tool::Archive,
+            "clipboard" => Tool::Clipboard,
+            "capture_screen" => Tool::CaptureScreen,
+            "http_request" => Tool::HttpRequest,
             "list_directory" => Tool::ListDirectory,
             "search_files" => Tool::SearchFiles,
             "apply_patch" => Tool::ApplyPatch,
+            "diff_files" => Tool::DiffFiles,
+            "run_tests" => Tool::RunTests,
+            "lint" => Tool::Lint,
+            "format" => Tool::Format,
+            "replace_in_files" => Tool::ReplaceInFiles,
+            "go_to_definition" => Tool::GoToDefinition,
+            "find_references" => Tool::FindReferences,
+            "document_symbols" => Tool::DocumentSymbols,
+            "git_status" => Tool::GitStatus,
+            "git_diff" => Tool::GitDiff,
+            "git_commit" => Tool::GitCommit,
+            "git_checkout_branch" => Tool::GitCheckoutBranch,
+            "git_log" => Tool::GitLog,
+            "list_checkpoints" => Tool::ListCheckpoints,
+            "revert_checkpoint" => Tool::RevertCheckpoint,
+            "revert_session" => Tool::RevertSession,
+            "list_attachments" => Tool::ListAttachments,
+            "read_attachment" => Tool::ReadAttachment,
+            "read_terminal" => Tool::ReadTerminal,
+            "job_status" => Tool::JobStatus,
+            "job_output" => Tool::JobOutput,
+            "job_cancel" => Tool::JobCancel,
+            "send_to_agent" => Tool::SendToAgent,
+            "read_inbox" => Tool::ReadInbox,
+            "set_var" => Tool::SetVar,
+            "get_var" => Tool::GetVar,
+            "tail_file" => Tool::TailFile,
+            "processes" => Tool::Processes,
+            "create_from_template" => Tool::CreateFromTemplate,
+            name if crate::mcp::is_mcp_tool(name) => {
+                let result = self.execute_mcp(tool_call).await;
+                return Self::tool_result(tool_call, result, start);
+            }
+            name if crate::plugins::is_plugin_tool(name) => {
+                let result = self.execute_plugin(tool_call).await;
+                return Self::tool_result(tool_call, result, start);
+            }
             _ => {
                 return ToolResult {
                     tool_call_id: tool_call.id.clone(),
@@ -105,14 +379,66 @@ impl AgentExecutor {
         };
 
         let result = match tool {
-            Tool::Shell => self.execute_shell(tool_call).await,
+            Tool::Shell => self.execute_shell(tool_call).await.map(|(output, metadata)| {
+                let structured = self.config.parse_output.then(|| parse_output(&output)).flatten();
+                let metadata = match (metadata, structured) {
+                    (metadata, None) => metadata,
+                    (metadata, structured) => Some(ToolResultMetadata { structured, ..metadata.unwrap_or_default() }),
+                };
+                (output, metadata)
+            }),
             Tool::ReadFile => self.execute_read_file(tool_call).await,
             Tool::WriteFile => self.execute_write_file(tool_call).await,
+            Tool::DeleteFile => self.execute_delete_file(tool_call).await,
+            Tool::MoveFile
+            | Tool::CopyFile
+            | Tool::RenameFile => self.execute_transfer(tool, tool_call).await,
+            Tool::Archive => self.execute_archive(tool_call).await,
+            Tool::Clipboard => self.execute_clipboard(tool_call).await,
+            Tool::CaptureScreen => self.execute_capture_screen(tool_call).await,
+            Tool::HttpRequest => self.execute_http_request(tool_call).await,
             Tool::ListDirectory => self.execute_list_directory(tool_call).await,
             Tool::SearchFiles => self.execute_search_files(tool_call).await,
             Tool::ApplyPatch => self.execute_apply_patch(tool_call).await,
+            Tool::DiffFiles => self.execute_diff_files(tool_call).await,
+            Tool::RunTests => self.execute_run_tests(tool_call).await,
+            Tool::Lint => self.execute_lint(tool_call).await,
+            Tool::Format => self.execute_format(tool_call).await,
+            Tool::ReplaceInFiles => self.execute_replace_in_files(tool_call).await,
+            Tool::GoToDefinition | Tool::FindReferences | Tool::DocumentSymbols => {
+                self.execute_code_intelligence(tool, tool_call).await
+            }
+            Tool::GitStatus
+            | Tool::GitDiff
+            | Tool::GitCommit
+            | Tool::GitCheckoutBranch
+            | Tool::GitLog => self.execute_git(tool, tool_call).await,
+            Tool::ListCheckpoints
+            | Tool::RevertCheckpoint
+            | Tool::RevertSession => self.execute_checkpoint_tool(tool, tool_call).await,
+            Tool::ListAttachments => self.execute_list_attachments().await,
+            Tool::ReadAttachment => self.execute_read_attachment(tool_call).await,
+            Tool::ReadTerminal => self.execute_read_terminal(tool_call).await,
+            Tool::JobStatus
+            | Tool::JobOutput
+            | Tool::JobCancel => self.execute_job_tool(tool, tool_call).await,
+            Tool::SendToAgent
+            | Tool::ReadInbox => self.execute_mail_tool(tool, tool_call),
+            Tool::SetVar
+            | Tool::GetVar => self.execute_var_tool(tool, tool_call),
+            Tool::TailFile => self.execute_tail_file(tool_call).await,
+            Tool::Processes => self.execute_processes(tool_call).await,
+            Tool::CreateFromTemplate => self.execute_create_from_template(tool_call).await,
         };
 
+        Self::tool_result(tool_call, result, start)
+    }
+
+    fn tool_result(
+        tool_call: &ToolCall,
+        result: Result<(String, Option<ToolResultMetadata>), ExecutorError>,
+        start: Instant,
+    ) -> ToolResult {
         let duration_ms = start.elapsed().as_millis() as u64;
 
         match result {
@@ -129,7 +455,7 @@ impl AgentExecutor {
             Err(e) => ToolResult {
                 tool_call_id: tool_call.id.clone(),
                 success: false,
-                output: String::new(),
+                output: e.partial_output().unwrap_or_default().to_string(),
                 error: Some(e.to_string()),
                 metadata: Some(ToolResultMetadata {
                     duration_ms: Some(duration_ms),
@@ -139,6 +465,65 @@ impl AgentExecutor {
         }
     }
 
+    /// Forward a call to the MCP server that provides the tool
+    async fn execute_mcp(
+        &self,
+        tool_call: &ToolCall,
+    ) -> Result<(String, Option<ToolResultMetadata>), ExecutorError> {
+        let result = crate::mcp::McpManager::global()
+            .call_tool(&tool_call.name, tool_call.arguments.clone())
+            .await
+            .map_err(|e| ExecutorError::Mcp(e.to_string()))?;
+        if result.is_error {
+            return Err(ExecutorError::Mcp(result.text()));
+        }
+
+        // Images, audio and binary resources are saved so the UI can show them
+        let attachments: Vec<ToolAttachment> = result
+            .content
+            .iter()
+            .filter_map(|part| match part {
+                ToolContent::Image { data, mime_type } | ToolContent::Audio { data, mime_type } => {
+                    Some((data, mime_type.as_str()))
+                }
+                ToolContent::Resource { resource } => resource
+                    .blob
+                    .as_ref()
+                    .map(|blob| (blob, resource.mime_type.as_deref().unwrap_or("application/octet-stream"))),
+                ToolContent::Text { .. } => None,
+            })
+            .filter_map(|(data, mime_type)| match ToolAttachment::from_base64(data, mime_type) {
+                Ok(attachment) => Some(attachment),
+                Err(e) => {
+                    tracing::warn!("Failed to save {} content of {}: {}", mime_type, tool_call.name, e);
+                    None
+                }
+            })
+            .collect();
+        let metadata = (!attachments.is_empty()).then(|| ToolResultMetadata { attachments, ..Default::default() });
+        Ok((result.text(), metadata))
+    }
+
+    /// Run a plugin tool; plugins that modify files need `allow_writes`
+    async fn execute_plugin(
+        &self,
+        tool_call: &ToolCall,
+    ) -> Result<(String, Option<ToolResultMetadata>), ExecutorError> {
+        let output = crate::plugins::PluginRegistry::global()
+            .call_tool(
+                &tool_call.name,
+                tool_call.arguments.clone(),
+                &self.config.working_directory,
+                self.config.allow_writes,
+            )
+            .await
+            .map_err(|e| match e {
+                crate::plugins::PluginError::PermissionDenied(reason) => ExecutorError::PermissionDenied(reason),
+                e => ExecutorError::Plugin(e.to_string()),
+            })?;
+        Ok((output, None))
+    }
+
     /// Execute a shell command
     async fn execute_shell(
         &self,
@@ -146,12 +531,22 @@ impl AgentExecutor {
     ) -> Result<(String, Option<ToolResultMetadata>), ExecutorError> {
         let args = &tool_call.arguments;
         
-        let command = args.get("command")
+        let requested = args.get("command")
             .and_then(|v| v.as_str())
             .ok_or_else(|| ExecutorError::MissingArgument("command".to_string()))?;
+        // `${name}` of the session's variables, quoted for the shell that
+        // runs the command; the command as written is what gets shown, so
+        // secret values stay out of job lists and events
+        let interpolate = |shell: ShellKind| match &self.config.session_id {
+            Some(session_id) => self.vars.interpolate(session_id, requested, shell),
+            None => requested.to_string(),
+        };
         
-        // If we have a terminal session and manager, use persistent shell
-        if let (Some(manager), Some(session_id)) = (&self.terminal_manager, &self.config.terminal_session_id) {
+        let background = args.get("background").and_then(|v| v.as_bool()).unwrap_or(false);
+
+        // If we have a terminal session and manager, use persistent shell;
+        // background jobs always get their own process
+        if let (false, Some(manager), Some(session_id)) = (background, &self.terminal_manager, &self.config.terminal_session_id) {
             // Check if session is active/exists
             // IMPORTANT: get_session returns Option<Arc<TerminalSession>>
             // If the session is HIBERNATED, it returns None by default unless we restore it first.
@@ -170,6 +565,9 @@ impl AgentExecutor {
                 .map(|s| self.resolve_path(s))
                 .unwrap_or_else(|| self.config.working_directory.clone());
 
+            let persistent_shell = if cfg!(target_os = "windows") { ShellKind::PowerShell } else { ShellKind::Posix };
+            let command = interpolate(persistent_shell);
+
             // Write command to PTY (append newline)
             // We prepend a directory change to ensure we execute in the requested context
             // Note: We use Set-Location -LiteralPath on Windows to handle special characters in paths
@@ -220,10 +618,21 @@ impl AgentExecutor {
         }
 
         // Fallback to ephemeral execution if no persistent session
-        
+
+        let profile = crate::config::SettingsStore::global()
+            .get()
+            .environments
+            .resolve(self.config.environment_profile.as_deref())
+            .map_err(ExecutorError::InvalidArgument)?
+            .cloned()
+            .unwrap_or_default();
+        let interpolated = interpolate(shell_kind(profile.shell.as_deref()));
+        let command = interpolated.as_str();
+
         let workdir = args.get("workdir")
             .and_then(|v| v.as_str())
             .map(|s| self.resolve_path(s))
+            .or_else(|| profile.working_directory())
             .unwrap_or_else(|| self.config.working_directory.clone());
         
         let timeout_ms = args.get("timeout_ms")
@@ -237,16 +646,27 @@ impl AgentExecutor {
         }
 
         // Use absolute path for shell to avoid PATH issues in some environments
-        let (program, cmd_args) = if cfg!(target_os = "windows") {
-            ("cmd".to_string(), vec!["/C".to_string(), command.to_string()])
-        } else {
-            ("/bin/sh".to_string(), vec!["-c".to_string(), command.to_string()])
-        };
+        let (program, cmd_args) = shell_invocation(profile.shell.as_deref(), command);
+
+        if background {
+            let job = JobManager::global()
+                .start(self.config.session_id.clone(), requested, program, cmd_args, workdir.clone(), profile.variables())
+                .await
+                .map_err(job_error)?;
+            let output = format!(
+                "Started background job {}. Check on it with job_status and job_output, or stop it with job_cancel.",
+                job.id
+            );
+            return Ok((output, Some(ToolResultMetadata {
+                working_directory: Some(workdir.to_string_lossy().to_string()),
+                ..Default::default()
+            })));
+        }
 
         // Execute command with timeout
         let handle = timeout(
             Duration::from_millis(timeout_ms),
-            self.cli_bridge.execute_command(program, cmd_args, Some(workdir.clone())),
+            self.cli_bridge.execute_command_with_env(program, cmd_args, Some(workdir.clone()), profile.variables()),
         )
         .await
         .map_err(|_| ExecutorError::Timeout(timeout_ms))?
@@ -271,9 +691,28 @@ impl AgentExecutor {
             combined_output.push_str("\n... [output truncated]");
         }
 
+        let limit_check = self.cli_bridge.check_resource_limits(&handle.session_id).await;
+
+        if let Ok(Some(status)) = self.cli_bridge.try_wait(&handle.session_id).await {
+            if !status.success() {
+                crate::events::publish(crate::events::Event::CommandFailed {
+                    session_id: self.config.session_id.clone(),
+                    command: requested.to_string(),
+                    exit_code: status.code(),
+                });
+            }
+        }
+
         // Cleanup session
         let _ = self.cli_bridge.terminate_session(handle.session_id).await;
 
+        if let Err(CliError::ResourceLimitExceeded(reason)) = limit_check {
+            return Err(ExecutorError::ResourceLimitExceeded {
+                reason,
+                partial_output: combined_output,
+            });
+        }
+
         Ok((combined_output, Some(ToolResultMetadata {
             working_directory: Some(workdir.to_string_lossy().to_string()),
             ..Default::default()
@@ -291,41 +730,229 @@ impl AgentExecutor {
             .and_then(|v| v.as_str())
             .ok_or_else(|| ExecutorError::MissingArgument("path".to_string()))?;
         
-        let path = self.resolve_path(path_str);
-        
-        let start_line = args.get("start_line")
-            .and_then(|v| v.as_u64())
-            .map(|v| v as usize)
-            .unwrap_or(1);
-        
-        let end_line = args.get("end_line")
-            .and_then(|v| v.as_u64())
-            .map(|v| v as usize);
+        let path = self.resolve_sandboxed_path(path_str)?;
+
+        let usize_arg = |name: &str| args.get(name).and_then(|v| v.as_u64()).map(|v| v as usize);
+        let range = ReadRange::from_params(
+            usize_arg("start_line"),
+            usize_arg("end_line"),
+            usize_arg("head"),
+            usize_arg("tail"),
+            args.get("offset").and_then(|v| v.as_u64()),
+            args.get("limit").and_then(|v| v.as_u64()),
+            ReadRange::Lines { start: 1, end: None },
+        )
+        .map_err(|e| ExecutorError::InvalidArgument(e.to_string()))?;
 
-        // Read file
-        let content = tokio::fs::read_to_string(&path).await
-            .map_err(|e| ExecutorError::FileOperation(format!("Failed to read {}: {}", path.display(), e)))?;
+        // Images and other binary files are shown to the user instead
+        if mime_guess::from_path(&path).first_raw().is_some_and(|m| m.starts_with("image/")) {
+            return Self::binary_file_result(&path);
+        }
+        let max_bytes = self.config.max_output_size;
+        let read_path = path.clone();
+        let read = tokio::task::spawn_blocking(move || file_range::read_range(&read_path, range, max_bytes))
+            .await
+            .map_err(|e| ExecutorError::FileOperation(format!("Read task failed: {}", e)))?;
+        let read = match read {
+            Ok(read) => read,
+            Err(RangeError::Binary(_)) => return Self::binary_file_result(&path),
+            Err(e) => return Err(ExecutorError::FileOperation(e.to_string())),
+        };
 
-        // Apply line range
-        let lines: Vec<&str> = content.lines().collect();
-        let total_lines = lines.len();
-        
-        let start_idx = start_line.saturating_sub(1).min(total_lines);
-        let end_idx = end_line.map(|e| e.min(total_lines)).unwrap_or(total_lines);
-        
-        let selected_lines: Vec<&str> = lines[start_idx..end_idx].to_vec();
-        let output = selected_lines.join("\n");
+        // Byte ranges are returned as they are; lines lose their endings
+        let mut output = match range {
+            ReadRange::Bytes { .. } => read.content.clone(),
+            _ => read.content.lines().collect::<Vec<_>>().join("\n"),
+        };
+        if read.truncated {
+            let note = match (range, read.end_line) {
+                (ReadRange::Tail(_), _) => format!(
+                    "[tail truncated at {} bytes; it starts at byte {} of {}]\n",
+                    max_bytes, read.offset, read.file_size
+                ),
+                (ReadRange::Bytes { .. }, _) | (_, None) => format!(
+                    "\n... [content truncated at {} bytes of {}; continue with offset={}]",
+                    max_bytes, read.file_size, read.offset + read.length
+                ),
+                (_, Some(line)) => format!(
+                    "\n... [content truncated at {} bytes of {}; continue with start_line={}]",
+                    max_bytes, read.file_size, line
+                ),
+            };
+            // The kept end of a tail is what was asked for; say so up front
+            if matches!(range, ReadRange::Tail(_)) {
+                output.insert_str(0, &note);
+            } else {
+                output.push_str(&note);
+            }
+        }
 
-        // Truncate if too large
-        let final_output = if output.len() > self.config.max_output_size {
-            let mut truncated = output[..self.config.max_output_size].to_string();
-            truncated.push_str("\n... [content truncated]");
-            truncated
+        // A large file is attached too, narrowed to the part that was read
+        let metadata = if read.file_size > max_bytes as u64 {
+            ToolAttachment::from_path(&path)
+                .ok()
+                .map(|attachment| ToolResultMetadata {
+                    attachments: vec![attachment.with_range(read.offset, read.length)],
+                    ..Default::default()
+                })
         } else {
-            output
+            None
+        };
+
+        Ok((output, metadata))
+    }
+
+    /// Follow a file and return the lines appended to it
+    async fn execute_tail_file(
+        &self,
+        tool_call: &ToolCall,
+    ) -> Result<(String, Option<ToolResultMetadata>), ExecutorError> {
+        let args = &tool_call.arguments;
+        let path_str = args.get("path")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ExecutorError::MissingArgument("path".to_string()))?;
+        let path = self.resolve_sandboxed_path(path_str)?;
+
+        let pattern = match args.get("pattern").and_then(|v| v.as_str()).filter(|p| !p.is_empty()) {
+            Some(pattern) => Some(regex::Regex::new(pattern)
+                .map_err(|e| ExecutorError::InvalidArgument(format!("Invalid pattern: {}", e)))?),
+            None => None,
+        };
+        let timeout_secs = args.get("timeout_secs").and_then(|v| v.as_u64()).unwrap_or(60).clamp(1, MAX_TAIL_SECS);
+        let options = FollowOptions {
+            until_match: args.get("until_match").and_then(|v| v.as_bool()).unwrap_or(pattern.is_some()),
+            pattern,
+            initial_lines: args.get("lines").and_then(|v| v.as_u64()).unwrap_or(0) as usize,
+            max_duration: Duration::from_secs(timeout_secs),
+            max_lines: args.get("max_lines").and_then(|v| v.as_u64()).unwrap_or(100).clamp(1, MAX_TAIL_LINES) as usize,
+        };
+
+        let (tx, mut rx) = tokio::sync::mpsc::channel::<file_follow::FollowedLine>(64);
+        let collect = async {
+            let mut lines = Vec::new();
+            while let Some(line) = rx.recv().await {
+                lines.push(line.text);
+            }
+            lines
+        };
+        let (summary, lines) = tokio::join!(file_follow::follow(&path, &options, tx), collect);
+        let summary = summary.map_err(|e| ExecutorError::FileOperation(e.to_string()))?;
+
+        let seconds = summary.elapsed_ms as f64 / 1000.0;
+        let reason = match summary.reason {
+            StopReason::Matched => format!("pattern matched after {:.1}s", seconds),
+            StopReason::MaxLines => format!("reached {} lines after {:.1}s", options.max_lines, seconds),
+            StopReason::Timeout if options.until_match => format!("no line matched within {}s", timeout_secs),
+            StopReason::Timeout | StopReason::Cancelled => format!("followed for {:.1}s", seconds),
+        };
+        let mut output = lines.join("\n");
+        if output.len() > self.config.max_output_size {
+            let mut end = self.config.max_output_size;
+            while !output.is_char_boundary(end) {
+                end -= 1;
+            }
+            output.truncate(end);
+            output.push_str("\n... [output truncated]");
+        }
+        if !output.is_empty() {
+            output.push('\n');
+        }
+        output.push_str(&format!("[{}; {} lines]", reason, summary.lines));
+        Ok((output, None))
+    }
+
+    /// List, inspect or kill processes
+    async fn execute_processes(
+        &self,
+        tool_call: &ToolCall,
+    ) -> Result<(String, Option<ToolResultMetadata>), ExecutorError> {
+        let args = &tool_call.arguments;
+        let action = args.get("action").and_then(|v| v.as_str()).unwrap_or("list").to_string();
+        let pid = args.get("pid").and_then(|v| v.as_u64()).map(|pid| pid as u32);
+        let process_error = |e: ProcessError| match e {
+            ProcessError::NotFound(_) | ProcessError::Protected(_) => ExecutorError::InvalidArgument(e.to_string()),
+            ProcessError::ConfirmationRequired(_) | ProcessError::PermissionDenied(_) => {
+                ExecutorError::PermissionDenied(e.to_string())
+            }
+            ProcessError::Unavailable(_) => ExecutorError::FileOperation(e.to_string()),
         };
 
-        Ok((final_output, None))
+        let output = match action.as_str() {
+            "list" => {
+                let sort = match args.get("sort").and_then(|v| v.as_str()).unwrap_or("cpu") {
+                    "cpu" => ProcessSort::Cpu,
+                    "memory" => ProcessSort::Memory,
+                    "pid" => ProcessSort::Pid,
+                    "name" => ProcessSort::Name,
+                    other => return Err(ExecutorError::InvalidArgument(format!("Unknown sort: {}", other))),
+                };
+                let query = ProcessQuery {
+                    filter: args.get("filter").and_then(|v| v.as_str()).map(String::from),
+                    sort,
+                    limit: Some(args.get("limit").and_then(|v| v.as_u64()).unwrap_or(15) as usize),
+                };
+                let list = tokio::task::spawn_blocking(move || processes::list(&query))
+                    .await
+                    .map_err(|e| ExecutorError::FileOperation(format!("Process task failed: {}", e)))?
+                    .map_err(process_error)?;
+                let mut output = format!(
+                    "{} of {} processes ({} matched) on {} cores; CPU 100% = one core\n{:>7} {:>6} {:>9} {:<10} {}\n",
+                    list.processes.len(),
+                    list.total,
+                    list.matched,
+                    list.cpu_count,
+                    "PID",
+                    "CPU%",
+                    "MEM",
+                    "USER",
+                    "COMMAND"
+                );
+                for process in &list.processes {
+                    output.push_str(&process_row(process));
+                }
+                output
+            }
+            "inspect" => {
+                let pid = pid.ok_or_else(|| ExecutorError::MissingArgument("pid".to_string()))?;
+                let details = tokio::task::spawn_blocking(move || processes::inspect(pid))
+                    .await
+                    .map_err(|e| ExecutorError::FileOperation(format!("Process task failed: {}", e)))?
+                    .map_err(process_error)?;
+                serde_json::to_string_pretty(&details).map_err(|e| ExecutorError::FileOperation(e.to_string()))?
+            }
+            "kill" => {
+                let pid = pid.ok_or_else(|| ExecutorError::MissingArgument("pid".to_string()))?;
+                let force = args.get("force").and_then(|v| v.as_bool()).unwrap_or(false);
+                let confirmed = self.config.allow_process_kill;
+                let outcome = tokio::task::spawn_blocking(move || processes::kill(pid, force, confirmed))
+                    .await
+                    .map_err(|e| ExecutorError::FileOperation(format!("Process task failed: {}", e)))?
+                    .map_err(process_error)?;
+                format!("Sent {} to {} (PID {})", outcome.signal, outcome.name, outcome.pid)
+            }
+            other => {
+                return Err(ExecutorError::InvalidArgument(format!(
+                    "Unknown action '{}'; use list, inspect or kill",
+                    other
+                )))
+            }
+        };
+        Ok((output, None))
+    }
+
+    /// Describe a file that isn't text and attach it for the user
+    fn binary_file_result(path: &Path) -> Result<(String, Option<ToolResultMetadata>), ExecutorError> {
+        let attachment = ToolAttachment::from_path(path)
+            .map_err(|e| ExecutorError::FileOperation(format!("Failed to read {}: {}", path.display(), e)))?;
+        let mut output = format!("[{}: {}, {} bytes", attachment.name, attachment.mime_type, attachment.size_bytes);
+        if let (Some(width), Some(height)) = (attachment.width, attachment.height) {
+            output.push_str(&format!(", {}x{}", width, height));
+        }
+        output.push_str(" - not text; attached for the user to view]");
+        Ok((output, Some(ToolResultMetadata {
+            attachments: vec![attachment],
+            ..Default::default()
+        })))
     }
 
     /// Execute write_file tool
@@ -351,7 +978,8 @@ impl AgentExecutor {
             .and_then(|v| v.as_str())
             .unwrap_or("overwrite");
 
-        let path = self.resolve_path(path_str);
+        let path = self.resolve_sandboxed_path(path_str)?;
+        self.create_checkpoint("write_file", std::slice::from_ref(&path))?;
 
         // Ensure parent directory exists
         if let Some(parent) = path.parent() {
@@ -382,107 +1010,475 @@ impl AgentExecutor {
         Ok((format!("Successfully wrote {} bytes to {}", content.len(), path.display()), None))
     }
 
-    /// Execute list_directory tool
-    async fn execute_list_directory(
+    /// Execute delete_file tool
+    async fn execute_delete_file(
         &self,
         tool_call: &ToolCall,
     ) -> Result<(String, Option<ToolResultMetadata>), ExecutorError> {
+        if !self.config.allow_writes {
+            return Err(ExecutorError::PermissionDenied("Write operations are disabled".to_string()));
+        }
+
         let args = &tool_call.arguments;
-        
+
         let path_str = args.get("path")
             .and_then(|v| v.as_str())
             .ok_or_else(|| ExecutorError::MissingArgument("path".to_string()))?;
-        
-        let depth = args.get("depth")
-            .and_then(|v| v.as_u64())
-            .unwrap_or(1) as usize;
-        
-        let include_hidden = args.get("include_hidden")
+
+        let permanent = args.get("permanent")
             .and_then(|v| v.as_bool())
             .unwrap_or(false);
+        if permanent && !self.config.allow_permanent_delete {
+            return Err(ExecutorError::PermissionDenied(
+                "Permanent deletion requires user approval; omit `permanent` to move the file to the trash".to_string(),
+            ));
+        }
 
-        let path = self.resolve_path(path_str);
+        let path = self.resolve_sandboxed_entry(path_str)?;
+        if permanent && path.is_file() {
+            self.create_checkpoint("delete_file", std::slice::from_ref(&path))?;
+        }
 
-        let entries = self.list_dir_recursive(&path, depth, include_hidden, 0).await?;
-        
-        Ok((entries.join("\n"), None))
+        let outcome = tokio::task::spawn_blocking(move || recycle_bin::delete(&path, permanent))
+            .await
+            .map_err(|e| ExecutorError::FileOperation(format!("Delete task failed: {}", e)))?
+            .map_err(|e| match e {
+                DeleteError::NotFound(_) => ExecutorError::InvalidArgument(e.to_string()),
+                DeleteError::Protected(_) => ExecutorError::PermissionDenied(e.to_string()),
+                e => ExecutorError::FileOperation(e.to_string()),
+            })?;
+        FileHistory::global().record_delete(OperationOrigin::Agent, &outcome);
+
+        let output = serde_json::to_string_pretty(&outcome)
+            .map_err(|e| ExecutorError::FileOperation(format!("Failed to serialize result: {}", e)))?;
+        Ok((output, None))
     }
 
-    /// Recursively list directory contents (non-recursive implementation to avoid boxing)
-    async fn list_dir_recursive(
+    /// Execute move_file, copy_file and rename_file tools
+    async fn execute_transfer(
         &self,
-        path: &Path,
-        max_depth: usize,
-        include_hidden: bool,
-        _current_depth: usize,
-    ) -> Result<Vec<String>, ExecutorError> {
-        let mut entries = Vec::new();
-        let mut stack: Vec<(PathBuf, usize)> = vec![(path.to_path_buf(), 0)];
-
-        while let Some((current_path, depth)) = stack.pop() {
-            if depth > max_depth {
-                continue;
-            }
+        tool: Tool,
+        tool_call: &ToolCall,
+    ) -> Result<(String, Option<ToolResultMetadata>), ExecutorError> {
+        if !self.config.allow_writes {
+            return Err(ExecutorError::PermissionDenied("Write operations are disabled".to_string()));
+        }
 
-            let indent = "  ".repeat(depth);
+        let args = &tool_call.arguments;
+        let arg = |name: &str| {
+            args.get(name)
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| ExecutorError::MissingArgument(name.to_string()))
+        };
 
-            let mut dir = tokio::fs::read_dir(&current_path).await
-                .map_err(|e| ExecutorError::FileOperation(format!("Failed to read directory {}: {}", current_path.display(), e)))?;
+        let collision: Collision = match args.get("on_conflict") {
+            Some(value) => serde_json::from_value(value.clone()).map_err(|_| {
+                ExecutorError::InvalidArgument(format!(
+                    "on_conflict must be 'abort', 'overwrite' or 'rename', got {}",
+                    value
+                ))
+            })?,
+            None => Collision::default(),
+        };
 
-            let mut dir_entries = Vec::new();
-            while let Some(entry) = dir.next_entry().await
-                .map_err(|e| ExecutorError::FileOperation(format!("Failed to read entry: {}", e)))? 
-            {
-                let name = entry.file_name().to_string_lossy().to_string();
-                
-                // Skip hidden files if not requested
-                if !include_hidden && name.starts_with('.') {
-                    continue;
-                }
+        // A symlink source is moved or renamed itself, not its target
+        let (kind, source, destination, new_name) = if tool == Tool::RenameFile {
+            let new_name = arg("new_name")?.to_string();
+            if !file_transfer::is_plain_name(&new_name) {
+                return Err(ExecutorError::InvalidArgument(TransferError::InvalidName(new_name).to_string()));
+            }
+            let source = self.resolve_sandboxed_entry(arg("path")?)?;
+            let destination = source.with_file_name(&new_name);
+            (TransferKind::Rename, source, destination, new_name)
+        } else {
+            let kind = if tool == Tool::MoveFile { TransferKind::Move } else { TransferKind::Copy };
+            let source = match kind {
+                TransferKind::Copy => self.resolve_sandboxed_path(arg("source")?)?,
+                _ => self.resolve_sandboxed_entry(arg("source")?)?,
+            };
+            let destination = self.resolve_sandboxed_path(arg("destination")?)?;
+            (kind, source, destination, String::new())
+        };
 
-                let metadata = entry.metadata().await
-                    .map_err(|e| ExecutorError::FileOperation(format!("Failed to get metadata: {}", e)))?;
+        // Snapshot what the transfer changes so it can be reverted; a renamed
+        // destination isn't known in advance
+        if source.is_file() {
+            let mut paths = Vec::new();
+            if kind != TransferKind::Copy {
+                paths.push(source.clone());
+            }
+            if collision != Collision::Rename {
+                paths.push(destination.clone());
+            }
+            self.create_checkpoint(tool.name(), &paths)?;
+        }
 
-                let type_indicator = if metadata.is_dir() { "/" } else { "" };
-                let size = if metadata.is_file() {
-                    format!(" ({} bytes)", metadata.len())
-                } else {
-                    String::new()
-                };
+        let outcome = tokio::task::spawn_blocking(move || match kind {
+            TransferKind::Move => file_transfer::move_path(&source, &destination, collision),
+            TransferKind::Copy => file_transfer::copy_path(&source, &destination, collision),
+            TransferKind::Rename => file_transfer::rename_path(&source, &new_name, collision),
+        })
+        .await
+        .map_err(|e| ExecutorError::FileOperation(format!("File task failed: {}", e)))?
+        .map_err(|e| match e {
+            TransferError::Io { .. } => ExecutorError::FileOperation(e.to_string()),
+            e => ExecutorError::InvalidArgument(e.to_string()),
+        })?;
+        FileHistory::global().record_transfer(OperationOrigin::Agent, &outcome);
+
+        let output = serde_json::to_string_pretty(&outcome)
+            .map_err(|e| ExecutorError::FileOperation(format!("Failed to serialize result: {}", e)))?;
+        Ok((output, None))
+    }
 
-                dir_entries.push((name.clone(), format!("{}{}{}{}", indent, name, type_indicator, size), metadata.is_dir(), entry.path()));
-            }
+    /// Execute archive tool
+    async fn execute_archive(
+        &self,
+        tool_call: &ToolCall,
+    ) -> Result<(String, Option<ToolResultMetadata>), ExecutorError> {
+        let args = &tool_call.arguments;
+        let arg = |name: &str| {
+            args.get(name)
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| ExecutorError::MissingArgument(name.to_string()))
+        };
+        let list_arg = |name: &str| -> Vec<String> {
+            args.get(name)
+                .and_then(|v| v.as_array())
+                .map(|a| a.iter().filter_map(|p| p.as_str().map(String::from)).collect())
+                .unwrap_or_default()
+        };
 
-            // Sort entries
-            dir_entries.sort_by(|a, b| a.0.cmp(&b.0));
+        let action = arg("action")?;
+        let archive = self.resolve_sandboxed_path(arg("path")?)?;
+        let overwrite = args.get("overwrite").and_then(|v| v.as_bool()).unwrap_or(false);
+        if action != "list" && !self.config.allow_writes {
+            return Err(ExecutorError::PermissionDenied("Write operations are disabled".to_string()));
+        }
 
-            for (_, formatted, is_dir, entry_path) in dir_entries {
-                entries.push(formatted);
-                
-                // Add directories to stack for processing
-                if is_dir && depth < max_depth {
-                    stack.push((entry_path, depth + 1));
+        let output = match action {
+            "list" => {
+                let listing = tokio::task::spawn_blocking(move || archives::list(&archive, &ArchiveLimits::default()))
+                    .await
+                    .map_err(|e| ExecutorError::FileOperation(format!("Archive task failed: {}", e)))?
+                    .map_err(archive_error)?;
+                serde_json::to_string_pretty(&listing)
+            }
+            "extract" => {
+                let destination = self.resolve_sandboxed_path(arg("destination")?)?;
+                let entries = list_arg("entries");
+                let outcome = tokio::task::spawn_blocking(move || {
+                    archives::extract(&archive, &destination, &entries, overwrite, &ArchiveLimits::default())
+                })
+                .await
+                .map_err(|e| ExecutorError::FileOperation(format!("Archive task failed: {}", e)))?
+                .map_err(archive_error)?;
+                serde_json::to_string_pretty(&outcome)
+            }
+            "create" => {
+                let paths = list_arg("paths")
+                    .iter()
+                    .map(|p| self.resolve_sandboxed_path(p))
+                    .collect::<Result<Vec<_>, _>>()?;
+                if paths.is_empty() {
+                    return Err(ExecutorError::MissingArgument("paths".to_string()));
                 }
+                let outcome = tokio::task::spawn_blocking(move || archives::create(&archive, &paths, overwrite))
+                    .await
+                    .map_err(|e| ExecutorError::FileOperation(format!("Archive task failed: {}", e)))?
+                    .map_err(archive_error)?;
+                serde_json::to_string_pretty(&outcome)
+            }
+            other => {
+                return Err(ExecutorError::InvalidArgument(format!(
+                    "action must be 'list', 'extract' or 'create', got '{}'",
+                    other
+                )))
             }
         }
+        .map_err(|e| ExecutorError::FileOperation(format!("Failed to serialize result: {}", e)))?;
 
-        Ok(entries)
+        Ok((output, None))
     }
 
-    /// Execute search_files tool
-    async fn execute_search_files(
+    /// List project templates, or create a project from one
+    async fn execute_create_from_template(
         &self,
         tool_call: &ToolCall,
     ) -> Result<(String, Option<ToolResultMetadata>), ExecutorError> {
         let args = &tool_call.arguments;
-        
-        let pattern = args.get("pattern")
+        let library = TemplateLibrary::global();
+
+        let Some(name) = args.get("template").and_then(|v| v.as_str()) else {
+            let output = serde_json::to_string_pretty(&library.list())
+                .map_err(|e| ExecutorError::FileOperation(format!("Failed to serialize result: {}", e)))?;
+            return Ok((output, None));
+        };
+        if !self.config.allow_writes {
+            return Err(ExecutorError::PermissionDenied("Write operations are disabled".to_string()));
+        }
+
+        let destination = args
+            .get("destination")
             .and_then(|v| v.as_str())
-            .ok_or_else(|| ExecutorError::MissingArgument("pattern".to_string()))?;
-        
-        let path_str = args.get("path")
+            .ok_or_else(|| ExecutorError::MissingArgument("destination".to_string()))?;
+        let destination = self.resolve_sandboxed_path(destination)?;
+        let variables = template_variables(args.get("variables"))?;
+        let overwrite = args.get("overwrite").and_then(|v| v.as_bool()).unwrap_or(false);
+
+        let template = library.get(name).map_err(scaffold_error)?;
+        let outcome = tokio::task::spawn_blocking(move || scaffold::create(&template, &destination, &variables, overwrite))
+            .await
+            .map_err(|e| ExecutorError::FileOperation(format!("Template task failed: {}", e)))?
+            .map_err(scaffold_error)?;
+        let output = serde_json::to_string_pretty(&outcome)
+            .map_err(|e| ExecutorError::FileOperation(format!("Failed to serialize result: {}", e)))?;
+        Ok((output, None))
+    }
+
+    /// Execute clipboard tool
+    async fn execute_clipboard(
+        &self,
+        tool_call: &ToolCall,
+    ) -> Result<(String, Option<ToolResultMetadata>), ExecutorError> {
+        if !self.config.allow_clipboard {
+            return Err(ExecutorError::PermissionDenied(
+                "Clipboard access requires user approval".to_string(),
+            ));
+        }
+        let clipboard = self.clipboard.clone().ok_or_else(|| {
+            ExecutorError::Clipboard("The clipboard is not available in this environment".to_string())
+        })?;
+
+        let args = &tool_call.arguments;
+        let arg = |name: &str| {
+            args.get(name)
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| ExecutorError::MissingArgument(name.to_string()))
+        };
+
+        match arg("action")? {
+            "read" => {
+                let text = tokio::task::spawn_blocking(move || clipboard.read_text())
+                    .await
+                    .map_err(|e| ExecutorError::Clipboard(format!("Clipboard task failed: {}", e)))?
+                    .map_err(ExecutorError::Clipboard)?;
+                if text.len() <= self.config.max_output_size {
+                    return Ok((text, None));
+                }
+                let mut end = self.config.max_output_size;
+                while !text.is_char_boundary(end) {
+                    end -= 1;
+                }
+                Ok((format!("{}\n... [clipboard content truncated]", &text[..end]), None))
+            }
+            "write_text" => {
+                let text = arg("text")?.to_string();
+                let len = text.len();
+                tokio::task::spawn_blocking(move || clipboard.write_text(&text))
+                    .await
+                    .map_err(|e| ExecutorError::Clipboard(format!("Clipboard task failed: {}", e)))?
+                    .map_err(ExecutorError::Clipboard)?;
+                Ok((format!("Copied {} bytes of text to the clipboard", len), None))
+            }
+            "write_image" => {
+                let path = self.resolve_sandboxed_path(arg("path")?)?;
+                let png = tokio::fs::read(&path)
+                    .await
+                    .map_err(|e| ExecutorError::FileOperation(format!("Failed to read {}: {}", path.display(), e)))?;
+                tokio::task::spawn_blocking(move || clipboard.write_image(&png))
+                    .await
+                    .map_err(|e| ExecutorError::Clipboard(format!("Clipboard task failed: {}", e)))?
+                    .map_err(ExecutorError::Clipboard)?;
+                Ok((format!("Copied image {} to the clipboard", path.display()), None))
+            }
+            other => Err(ExecutorError::InvalidArgument(format!(
+                "action must be 'read', 'write_text' or 'write_image', got '{}'",
+                other
+            ))),
+        }
+    }
+
+    /// Execute capture_screen tool
+    async fn execute_capture_screen(
+        &self,
+        tool_call: &ToolCall,
+    ) -> Result<(String, Option<ToolResultMetadata>), ExecutorError> {
+        if !self.config.allow_screen_capture {
+            return Err(ExecutorError::PermissionDenied(
+                "Screen capture requires user approval".to_string(),
+            ));
+        }
+        let screen = self.screen.clone().ok_or_else(|| {
+            ExecutorError::ScreenCapture("Screen capture is not available in this environment".to_string())
+        })?;
+
+        let mut args = tool_call.arguments.clone();
+        if args.get("target").is_none() {
+            args["target"] = serde_json::json!("screen");
+        }
+        let target: CaptureTarget = serde_json::from_value(args)
+            .map_err(|e| ExecutorError::InvalidArgument(format!("Invalid capture target: {}", e)))?;
+
+        let capture_target = target.clone();
+        let png = tokio::task::spawn_blocking(move || screen.capture(&capture_target))
+            .await
+            .map_err(|e| ExecutorError::ScreenCapture(format!("Capture task failed: {}", e)))?
+            .map_err(ExecutorError::ScreenCapture)?;
+
+        let dir = super::artifacts::artifacts_dir();
+        let path = dir.join(format!("screenshot-{}.png", chrono::Utc::now().format("%Y%m%d-%H%M%S%.3f")));
+        let save = async {
+            tokio::fs::create_dir_all(&dir).await?;
+            tokio::fs::write(&path, &png).await?;
+            ToolAttachment::from_path(&path)
+        };
+        let attachment = save
+            .await
+            .map_err(|e| ExecutorError::FileOperation(format!("Failed to save screenshot: {}", e)))?;
+
+        let what = match &target {
+            CaptureTarget::Screen { monitor: Some(index) } => format!("monitor {}", index),
+            CaptureTarget::Screen { monitor: None } => "the screen".to_string(),
+            CaptureTarget::Window { title: Some(title) } => format!("window \"{}\"", title),
+            CaptureTarget::Window { title: None } => "the frontmost window".to_string(),
+            CaptureTarget::Region { x, y, width, height } => format!("region {}x{} at ({}, {})", width, height, x, y),
+        };
+        let mut output = format!("Captured {}", what);
+        if let (Some(width), Some(height)) = (attachment.width, attachment.height) {
+            output.push_str(&format!(" ({}x{})", width, height));
+        }
+        output.push_str(&format!(" to {}. The screenshot is attached for the user.", path.display()));
+
+        Ok((output, Some(ToolResultMetadata {
+            attachments: vec![attachment],
+            ..Default::default()
+        })))
+    }
+
+    /// Execute http_request tool
+    async fn execute_http_request(
+        &self,
+        tool_call: &ToolCall,
+    ) -> Result<(String, Option<ToolResultMetadata>), ExecutorError> {
+        if self.config.allowed_http_domains.is_empty() {
+            return Err(ExecutorError::PermissionDenied(
+                "HTTP requests require the user to allow domains for this session".to_string(),
+            ));
+        }
+        let request: HttpRequest = serde_json::from_value(tool_call.arguments.clone())
+            .map_err(|e| ExecutorError::InvalidArgument(format!("Invalid HTTP request: {}", e)))?;
+
+        let response = http_request::send(&request, &self.config.allowed_http_domains)
+            .await
+            .map_err(|e| match e {
+                HttpRequestError::Invalid(message) => ExecutorError::InvalidArgument(message),
+                HttpRequestError::NotAllowed(message) => ExecutorError::PermissionDenied(message),
+                HttpRequestError::Failed(message) => ExecutorError::Http(message),
+            })?;
+        Ok((response.render(), None))
+    }
+
+    /// Execute list_directory tool
+    async fn execute_list_directory(
+        &self,
+        tool_call: &ToolCall,
+    ) -> Result<(String, Option<ToolResultMetadata>), ExecutorError> {
+        let args = &tool_call.arguments;
+        
+        let path_str = args.get("path")
             .and_then(|v| v.as_str())
-            .unwrap_or(".");
+            .ok_or_else(|| ExecutorError::MissingArgument("path".to_string()))?;
+        
+        let depth = args.get("depth")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(1) as usize;
+        
+        let include_hidden = args.get("include_hidden")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
+        let path = self.resolve_sandboxed_path(path_str)?;
+
+        let entries = self.list_dir_recursive(&path, depth, include_hidden, 0).await?;
+        
+        Ok((entries.join("\n"), None))
+    }
+
+    /// Recursively list directory contents (non-recursive implementation to avoid boxing)
+    async fn list_dir_recursive(
+        &self,
+        path: &Path,
+        max_depth: usize,
+        include_hidden: bool,
+        _current_depth: usize,
+    ) -> Result<Vec<String>, ExecutorError> {
+        let mut entries = Vec::new();
+        let mut stack: Vec<(PathBuf, usize)> = vec![(path.to_path_buf(), 0)];
+        let workspace = self.workspace()?;
+
+        while let Some((current_path, depth)) = stack.pop() {
+            if depth > max_depth {
+                continue;
+            }
+
+            let indent = "  ".repeat(depth);
+
+            let mut dir = tokio::fs::read_dir(&current_path).await
+                .map_err(|e| ExecutorError::FileOperation(format!("Failed to read directory {}: {}", current_path.display(), e)))?;
+
+            let mut dir_entries = Vec::new();
+            while let Some(entry) = dir.next_entry().await
+                .map_err(|e| ExecutorError::FileOperation(format!("Failed to read entry: {}", e)))? 
+            {
+                let name = entry.file_name().to_string_lossy().to_string();
+                
+                // Skip hidden files if not requested
+                if !include_hidden && name.starts_with('.') {
+                    continue;
+                }
+
+                let metadata = entry.metadata().await
+                    .map_err(|e| ExecutorError::FileOperation(format!("Failed to get metadata: {}", e)))?;
+
+                let type_indicator = if metadata.is_dir() { "/" } else { "" };
+                let size = if metadata.is_file() {
+                    format!(" ({} bytes)", metadata.len())
+                } else {
+                    String::new()
+                };
+
+                dir_entries.push((name.clone(), format!("{}{}{}{}", indent, name, type_indicator, size), metadata.is_dir(), entry.path()));
+            }
+
+            // Sort entries
+            dir_entries.sort_by(|a, b| a.0.cmp(&b.0));
+
+            for (_, formatted, is_dir, entry_path) in dir_entries {
+                entries.push(formatted);
+                
+                // Add directories to stack for processing, without following
+                // symlinks that lead out of the workspace
+                let escapes = workspace.as_ref().is_some_and(|ws| !ws.escape_allowed() && !ws.contains(&entry_path));
+                if is_dir && depth < max_depth && !escapes {
+                    stack.push((entry_path, depth + 1));
+                }
+            }
+        }
+
+        Ok(entries)
+    }
+
+    /// Execute search_files tool
+    async fn execute_search_files(
+        &self,
+        tool_call: &ToolCall,
+    ) -> Result<(String, Option<ToolResultMetadata>), ExecutorError> {
+        let args = &tool_call.arguments;
+        
+        let pattern = args.get("pattern")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ExecutorError::MissingArgument("pattern".to_string()))?;
         
         let search_type = args.get("search_type")
             .and_then(|v| v.as_str())
@@ -492,7 +1488,8 @@ impl AgentExecutor {
             .and_then(|v| v.as_u64())
             .unwrap_or(100) as usize;
 
-        let path = self.resolve_path(path_str);
+        let roots = self.search_roots(args)?;
+        let path = roots[0].clone();
         
         // Use CliEngine for smarter search
         let mut config = CliConfig::default();
@@ -500,11 +1497,24 @@ impl AgentExecutor {
         config.max_results = if max_results < 1000 { 1000 } else { max_results };
         config.timeout_seconds = 15; // Reasonable timeout
 
-        let engine = CliEngine::new(path.clone());
-        
-        let result = match search_type {
-            "content" => engine.search_content(pattern, &config).await,
-            _ => engine.search_files(pattern, &config).await
+        // Roots are searched concurrently
+        let searches = roots.iter().map(|root| {
+            let engine = CliEngine::new(root.clone());
+            let config = &config;
+            async move {
+                match search_type {
+                    "content" => engine.search_content(pattern, config).await,
+                    _ => engine.search_files(pattern, config).await
+                }
+            }
+        });
+        let results = futures::future::join_all(searches).await;
+        let multi_root = roots.len() > 1;
+        let mut failed_roots = Vec::new();
+        let result = if multi_root {
+            merge_root_matches(&roots, results, &mut failed_roots)
+        } else {
+            results.into_iter().next().expect("one root")
         };
 
         match result {
@@ -538,7 +1548,9 @@ impl AgentExecutor {
                     let mut clusters: HashMap<String, usize> = HashMap::new();
                     for file in &search_result.files {
                         let path_parts: Vec<&str> = file.path.split('/').collect();
-                        let key = if path_parts.len() > 0 {
+                        let key = if multi_root {
+                            root_of(&roots, &file.path)
+                        } else if !path_parts.is_empty() {
                             if path_parts[0] == "." && path_parts.len() > 1 {
                                 path_parts[1].to_string()
                             } else {
@@ -577,6 +1589,11 @@ impl AgentExecutor {
                     out
                 };
                 
+                let mut output = output;
+                for failure in &failed_roots {
+                    output.push_str(&format!("\nNot searched: {}", failure));
+                }
+
                 Ok((output, Some(ToolResultMetadata {
                     working_directory: Some(path.to_string_lossy().to_string()),
                     ..Default::default()
@@ -588,6 +1605,47 @@ impl AgentExecutor {
         }
     }
 
+    /// Roots for search_files: `path` and `paths`, else the `workspace` from
+    /// search settings, else the working directory
+    fn search_roots(&self, args: &serde_json::Value) -> Result<Vec<PathBuf>, ExecutorError> {
+        let mut paths = Vec::new();
+        for key in ["path", "paths"] {
+            match args.get(key) {
+                Some(serde_json::Value::String(path)) => paths.push(path.clone()),
+                Some(serde_json::Value::Array(list)) => {
+                    for path in list {
+                        let path = path.as_str().ok_or_else(|| {
+                            ExecutorError::InvalidArgument(format!("{} must be a string or a list of strings", key))
+                        })?;
+                        paths.push(path.to_string());
+                    }
+                }
+                _ => {}
+            }
+        }
+        if paths.is_empty() {
+            if let Some(name) = args.get("workspace").and_then(|v| v.as_str()) {
+                let settings = crate::config::SettingsStore::global().get().search;
+                let workspace = settings
+                    .workspace(name)
+                    .ok_or_else(|| ExecutorError::InvalidArgument(format!("Unknown search workspace '{}'", name)))?;
+                paths = workspace.roots.clone();
+            }
+        }
+        if paths.is_empty() {
+            paths.push(".".to_string());
+        }
+
+        let mut roots = Vec::new();
+        for path in &paths {
+            let root = self.resolve_sandboxed_path(path)?;
+            if !roots.contains(&root) {
+                roots.push(root);
+            }
+        }
+        Ok(roots)
+    }
+
     /// Execute apply_patch tool
     async fn execute_apply_patch(
         &self,
@@ -603,6 +1661,27 @@ impl AgentExecutor {
             .and_then(|v| v.as_str())
             .ok_or_else(|| ExecutorError::MissingArgument("patch".to_string()))?;
 
+        // Reject patches touching files outside the workspace and checkpoint
+        // the affected files before applying anything
+        if self.config.workspace_root.is_some() || self.config.session_id.is_some() {
+            let parsed = parse_patch(patch_content)
+                .map_err(|e| ExecutorError::InvalidArgument(format!("Invalid patch: {}", e)))?;
+            let mut affected = Vec::new();
+            for hunk in &parsed.hunks {
+                affected.push(hunk.resolve_path(&self.config.working_directory));
+                if let Hunk::UpdateFile { move_path: Some(dest), .. } = hunk {
+                    affected.push(self.config.working_directory.join(dest));
+                }
+            }
+
+            if let Some(workspace) = self.workspace()? {
+                for path in &affected {
+                    workspace.check(path)?;
+                }
+            }
+            self.create_checkpoint("apply_patch", &affected)?;
+        }
+
         // Switch to the working directory to apply the patch correctly
         let original_dir = std::env::current_dir().map_err(|e| ExecutorError::FileOperation(e.to_string()))?;
         std::env::set_current_dir(&self.config.working_directory)
@@ -625,6 +1704,683 @@ impl AgentExecutor {
         }
     }
 
+    /// Execute one of the git tools, returning its result as JSON
+    async fn execute_git(
+        &self,
+        tool: Tool,
+        tool_call: &ToolCall,
+    ) -> Result<(String, Option<ToolResultMetadata>), ExecutorError> {
+        let args = &tool_call.arguments;
+
+        let repo_path = match args.get("repo_path").and_then(|v| v.as_str()) {
+            Some(p) => self.resolve_sandboxed_path(p)?,
+            None => self.resolve_sandboxed_path(&self.config.working_directory.to_string_lossy())?,
+        };
+        let repo = GitRepo::new(&repo_path);
+
+        let path_arg = args.get("path").and_then(|v| v.as_str());
+
+        let value = match tool {
+            Tool::GitStatus => serde_json::to_value(repo.status().await?),
+            Tool::GitDiff => {
+                let staged = args.get("staged").and_then(|v| v.as_bool()).unwrap_or(false);
+                serde_json::to_value(repo.diff(staged, path_arg, self.config.max_output_size).await?)
+            }
+            Tool::GitLog => {
+                let max_count = args.get("max_count")
+                    .and_then(|v| v.as_u64())
+                    .unwrap_or(20) as usize;
+                serde_json::to_value(repo.log(max_count, path_arg).await?)
+            }
+            Tool::GitCommit => {
+                if !self.config.allow_writes || !self.config.allow_git_commits {
+                    return Err(ExecutorError::PermissionDenied("Git commits are disabled".to_string()));
+                }
+                let message = args.get("message")
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| ExecutorError::MissingArgument("message".to_string()))?;
+                let paths: Vec<String> = args.get("paths")
+                    .and_then(|v| v.as_array())
+                    .map(|a| a.iter().filter_map(|p| p.as_str().map(String::from)).collect())
+                    .unwrap_or_default();
+                if let Some(workspace) = self.workspace()? {
+                    for path in &paths {
+                        workspace.check(&repo_path.join(path))?;
+                    }
+                }
+                let all = args.get("all").and_then(|v| v.as_bool()).unwrap_or(false);
+                serde_json::to_value(repo.commit(message, &paths, all).await?)
+            }
+            Tool::GitCheckoutBranch => {
+                if !self.config.allow_writes {
+                    return Err(ExecutorError::PermissionDenied("Write operations are disabled".to_string()));
+                }
+                let branch = args.get("branch")
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| ExecutorError::MissingArgument("branch".to_string()))?;
+                let create = args.get("create").and_then(|v| v.as_bool()).unwrap_or(false);
+                serde_json::to_value(repo.checkout_branch(branch, create).await?)
+            }
+            _ => return Err(ExecutorError::InvalidArgument(format!("Not a git tool: {}", tool.name()))),
+        }
+        .map_err(|e| ExecutorError::Git(format!("Failed to serialize result: {}", e)))?;
+
+        let output = serde_json::to_string_pretty(&value)
+            .map_err(|e| ExecutorError::Git(format!("Failed to serialize result: {}", e)))?;
+
+        Ok((output, Some(ToolResultMetadata {
+            working_directory: Some(repo_path.to_string_lossy().to_string()),
+            ..Default::default()
+        })))
+    }
+
+    /// Run the project's tests and report structured results
+    async fn execute_run_tests(
+        &self,
+        tool_call: &ToolCall,
+    ) -> Result<(String, Option<ToolResultMetadata>), ExecutorError> {
+        let args = &tool_call.arguments;
+
+        let dir = match args.get("path").and_then(|v| v.as_str()) {
+            Some(p) => self.resolve_sandboxed_path(p)?,
+            None => self.resolve_sandboxed_path(&self.config.working_directory.to_string_lossy())?,
+        };
+        let runner = match args.get("runner").and_then(|v| v.as_str()) {
+            Some(name) => Some(TestRunner::parse(name).ok_or_else(|| {
+                ExecutorError::InvalidArgument(format!("Unknown test runner: {}", name))
+            })?),
+            None => None,
+        };
+        let filter = args.get("filter").and_then(|v| v.as_str()).filter(|f| !f.trim().is_empty());
+        let timeout_ms = args.get("timeout_ms")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(test_runner::DEFAULT_TIMEOUT_MS);
+
+        let (root, runner, base) = test_runner::resolve(&dir, runner)?;
+        let command = runner.command(&base, filter);
+        let profile = crate::config::SettingsStore::global()
+            .get()
+            .environments
+            .resolve(self.config.environment_profile.as_deref())
+            .map_err(ExecutorError::InvalidArgument)?
+            .cloned()
+            .unwrap_or_default();
+
+        let run = test_runner::run(runner, command, root, profile.shell.as_deref(), profile.variables(), timeout_ms).await?;
+        if !run.passed {
+            crate::events::publish(crate::events::Event::CommandFailed {
+                session_id: self.config.session_id.clone(),
+                command: run.command.clone(),
+                exit_code: run.exit_code,
+            });
+        }
+
+        let output = serde_json::to_string_pretty(&run)
+            .map_err(|e| ExecutorError::TestRun(format!("Failed to serialize result: {}", e)))?;
+        Ok((output, Some(ToolResultMetadata {
+            exit_code: run.exit_code,
+            working_directory: Some(run.directory.to_string_lossy().to_string()),
+            ..Default::default()
+        })))
+    }
+
+    /// Project root and languages for the lint and format tools
+    fn lint_project(&self, args: &serde_json::Value) -> Result<(PathBuf, Vec<super::project::ProjectKind>), ExecutorError> {
+        let dir = match args.get("path").and_then(|v| v.as_str()) {
+            Some(p) => self.resolve_sandboxed_path(p)?,
+            None => self.resolve_sandboxed_path(&self.config.working_directory.to_string_lossy())?,
+        };
+        let project = super::project::ProjectDetector::new().detect(&dir).ok_or_else(|| {
+            ExecutorError::InvalidArgument(format!("No Rust, Node or Python project found at {}", dir.display()))
+        })?;
+        Ok((project.root, project.kinds))
+    }
+
+    /// Run the project's linters, optionally applying their fixes
+    async fn execute_lint(
+        &self,
+        tool_call: &ToolCall,
+    ) -> Result<(String, Option<ToolResultMetadata>), ExecutorError> {
+        let args = &tool_call.arguments;
+        let fix = args.get("fix").and_then(|v| v.as_bool()).unwrap_or(false);
+        if fix && !self.config.allow_writes {
+            return Err(ExecutorError::PermissionDenied("Write operations are disabled".to_string()));
+        }
+        let timeout_ms = args.get("timeout_ms").and_then(|v| v.as_u64()).unwrap_or(lint::DEFAULT_TIMEOUT_MS);
+
+        let (root, kinds) = self.lint_project(args)?;
+        let linters = match args.get("tool").and_then(|v| v.as_str()) {
+            Some(name) => vec![Linter::parse(name)
+                .ok_or_else(|| ExecutorError::InvalidArgument(format!("Unknown linter: {}", name)))?],
+            None => Linter::detect(&root, &kinds),
+        };
+        if linters.is_empty() {
+            return Err(ExecutorError::InvalidArgument(format!(
+                "No linter is configured for the project at {}",
+                root.display()
+            )));
+        }
+        let profile = crate::config::SettingsStore::global()
+            .get()
+            .environments
+            .resolve(self.config.environment_profile.as_deref())
+            .map_err(ExecutorError::InvalidArgument)?
+            .cloned()
+            .unwrap_or_default();
+
+        let mut runs = Vec::new();
+        for linter in linters {
+            if fix {
+                // Fixes only touch files with diagnostics, so those are the ones to snapshot
+                let check = lint::lint(linter, &root, false, profile.shell.as_deref(), profile.variables(), timeout_ms).await?;
+                let paths = lint::absolute_paths(&root, check.diagnostics.iter().map(|d| d.file.as_str()));
+                if !paths.is_empty() {
+                    self.create_checkpoint("lint", &paths)?;
+                }
+            }
+            runs.push(lint::lint(linter, &root, fix, profile.shell.as_deref(), profile.variables(), timeout_ms).await?);
+        }
+
+        let output = serde_json::to_string_pretty(&serde_json::json!({
+            "directory": root,
+            "fix": fix,
+            "errors": runs.iter().map(|r| r.errors).sum::<usize>(),
+            "warnings": runs.iter().map(|r| r.warnings).sum::<usize>(),
+            "runs": runs,
+        }))
+        .map_err(|e| ExecutorError::Lint(format!("Failed to serialize result: {}", e)))?;
+        Ok((output, Some(ToolResultMetadata {
+            exit_code: runs.iter().filter_map(|r| r.exit_code).find(|code| *code != 0).or(Some(0)),
+            working_directory: Some(root.to_string_lossy().to_string()),
+            ..Default::default()
+        })))
+    }
+
+    /// Check the project's formatting, optionally rewriting the files
+    async fn execute_format(
+        &self,
+        tool_call: &ToolCall,
+    ) -> Result<(String, Option<ToolResultMetadata>), ExecutorError> {
+        let args = &tool_call.arguments;
+        let apply = args.get("apply").and_then(|v| v.as_bool()).unwrap_or(false);
+        if apply && !self.config.allow_writes {
+            return Err(ExecutorError::PermissionDenied("Write operations are disabled".to_string()));
+        }
+        let timeout_ms = args.get("timeout_ms").and_then(|v| v.as_u64()).unwrap_or(lint::DEFAULT_TIMEOUT_MS);
+
+        let (root, kinds) = self.lint_project(args)?;
+        let formatters = match args.get("tool").and_then(|v| v.as_str()) {
+            Some(name) => vec![Formatter::parse(name)
+                .ok_or_else(|| ExecutorError::InvalidArgument(format!("Unknown formatter: {}", name)))?],
+            None => Formatter::detect(&root, &kinds),
+        };
+        if formatters.is_empty() {
+            return Err(ExecutorError::InvalidArgument(format!(
+                "No formatter is configured for the project at {}",
+                root.display()
+            )));
+        }
+        let profile = crate::config::SettingsStore::global()
+            .get()
+            .environments
+            .resolve(self.config.environment_profile.as_deref())
+            .map_err(ExecutorError::InvalidArgument)?
+            .cloned()
+            .unwrap_or_default();
+
+        let mut runs = Vec::new();
+        for formatter in formatters {
+            let mut run = lint::check_format(formatter, &root, profile.shell.as_deref(), profile.variables(), timeout_ms).await?;
+            if apply && !run.files.is_empty() {
+                let paths = lint::absolute_paths(&root, run.files.iter().map(|f| f.file.as_str()));
+                self.create_checkpoint("format", &paths)?;
+                lint::apply_format(&mut run, &root, profile.shell.as_deref(), profile.variables(), timeout_ms).await?;
+            }
+            runs.push(run);
+        }
+
+        let output = serde_json::to_string_pretty(&serde_json::json!({
+            "directory": root,
+            "apply": apply,
+            "unformatted": runs.iter().map(|r| r.files.len()).sum::<usize>(),
+            "runs": runs,
+        }))
+        .map_err(|e| ExecutorError::Lint(format!("Failed to serialize result: {}", e)))?;
+        Ok((output, Some(ToolResultMetadata {
+            working_directory: Some(root.to_string_lossy().to_string()),
+            ..Default::default()
+        })))
+    }
+
+    /// Diff a file against another file or proposed content
+    async fn execute_diff_files(
+        &self,
+        tool_call: &ToolCall,
+    ) -> Result<(String, Option<ToolResultMetadata>), ExecutorError> {
+        let args = &tool_call.arguments;
+        let path = args.get("path")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ExecutorError::MissingArgument("path".to_string()))?;
+        let path = self.resolve_sandboxed_path(path)?;
+        let other = match args.get("other_path").and_then(|v| v.as_str()) {
+            Some(other) => Some(self.resolve_sandboxed_path(other)?),
+            None => None,
+        };
+        let content = args.get("content").and_then(|v| v.as_str()).map(String::from);
+        let options = DiffOptions {
+            context: args.get("context").and_then(|v| v.as_u64()).map_or(file_diff::DEFAULT_CONTEXT_LINES, |c| c as usize),
+            word_diff: args.get("word_diff").and_then(|v| v.as_bool()).unwrap_or(false),
+        };
+
+        let result = tokio::task::spawn_blocking(move || match (&other, &content) {
+            (Some(other), None) => file_diff::diff(&path, DiffTarget::File(other), &options),
+            (None, Some(content)) => file_diff::diff(&path, DiffTarget::Content(content), &options),
+            _ => Err(DiffError::InvalidTarget),
+        })
+        .await
+        .map_err(|e| ExecutorError::FileOperation(format!("Diff task failed: {}", e)))?
+        .map_err(|e| match e {
+            DiffError::Io { .. } => ExecutorError::FileOperation(e.to_string()),
+            e => ExecutorError::InvalidArgument(e.to_string()),
+        })?;
+
+        let output = serde_json::to_string_pretty(&result)
+            .map_err(|e| ExecutorError::FileOperation(format!("Failed to serialize result: {}", e)))?;
+        Ok((output, None))
+    }
+
+    /// Plan a find and replace; the user applies it through the refactor API
+    async fn execute_replace_in_files(
+        &self,
+        tool_call: &ToolCall,
+    ) -> Result<(String, Option<ToolResultMetadata>), ExecutorError> {
+        let args = &tool_call.arguments;
+        let arg = |name: &str| {
+            args.get(name)
+                .and_then(|v| v.as_str())
+                .map(String::from)
+                .ok_or_else(|| ExecutorError::MissingArgument(name.to_string()))
+        };
+        let list_arg = |name: &str| -> Vec<String> {
+            args.get(name)
+                .and_then(|v| v.as_array())
+                .map(|a| a.iter().filter_map(|p| p.as_str().map(String::from)).collect())
+                .unwrap_or_default()
+        };
+
+        let spec = ReplaceSpec {
+            pattern: arg("pattern")?,
+            replacement: arg("replacement")?,
+            regex: args.get("regex").and_then(|v| v.as_bool()).unwrap_or(false),
+            case_sensitive: args.get("case_sensitive").and_then(|v| v.as_bool()).unwrap_or(true),
+            include: list_arg("include"),
+            exclude: list_arg("exclude"),
+        };
+        let root = match args.get("path").and_then(|v| v.as_str()) {
+            Some(path) => self.resolve_sandboxed_path(path)?,
+            None => self.resolve_sandboxed_path(&self.config.working_directory.to_string_lossy())?,
+        };
+        if !root.is_dir() {
+            return Err(ExecutorError::InvalidArgument(format!("{} is not a directory", root.display())));
+        }
+
+        let session_id = self.config.session_id.clone();
+        let preview = tokio::task::spawn_blocking(move || RefactorStore::global().preview(&root, spec, session_id))
+            .await
+            .map_err(|e| ExecutorError::FileOperation(format!("Replace task failed: {}", e)))?
+            .map_err(|e| match e {
+                RefactorError::Io(_) => ExecutorError::FileOperation(e.to_string()),
+                e => ExecutorError::InvalidArgument(e.to_string()),
+            })?;
+
+        let output = serde_json::to_string_pretty(&serde_json::json!({
+            "preview_id": preview.id,
+            "status": if preview.files.is_empty() { "no matches" } else { "awaiting user approval" },
+            "files_changed": preview.files.len(),
+            "files_scanned": preview.files_scanned,
+            "total_replacements": preview.total_replacements,
+            "files": preview.files,
+        }))
+        .map_err(|e| ExecutorError::FileOperation(format!("Failed to serialize result: {}", e)))?;
+        Ok((output, None))
+    }
+
+    /// Execute go_to_definition, find_references or document_symbols
+    async fn execute_code_intelligence(
+        &self,
+        tool: Tool,
+        tool_call: &ToolCall,
+    ) -> Result<(String, Option<ToolResultMetadata>), ExecutorError> {
+        let args = &tool_call.arguments;
+        let path = args.get("path")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ExecutorError::MissingArgument("path".to_string()))?;
+        let file = self.resolve_sandboxed_path(path)?;
+        let manager = LspManager::global();
+
+        let value = if tool == Tool::DocumentSymbols {
+            serde_json::to_value(manager.document_symbols(&file).await.map_err(lsp_error)?)
+        } else {
+            let position = CodePosition {
+                line: args.get("line")
+                    .and_then(|v| v.as_u64())
+                    .ok_or_else(|| ExecutorError::MissingArgument("line".to_string()))? as u32,
+                column: args.get("column").and_then(|v| v.as_u64()).map(|c| c as u32),
+                symbol: args.get("symbol").and_then(|v| v.as_str()).map(str::to_string),
+            };
+            let locations = if tool == Tool::FindReferences {
+                let include_declaration = args.get("include_declaration").and_then(|v| v.as_bool()).unwrap_or(false);
+                manager.references(&file, &position, include_declaration).await
+            } else {
+                manager.definition(&file, &position).await
+            };
+            serde_json::to_value(locations.map_err(lsp_error)?)
+        }
+        .map_err(|e| ExecutorError::CodeIntelligence(format!("Failed to serialize result: {}", e)))?;
+
+        let output = serde_json::to_string_pretty(&value)
+            .map_err(|e| ExecutorError::CodeIntelligence(format!("Failed to serialize result: {}", e)))?;
+        Ok((output, None))
+    }
+
+    /// Snapshot files before a tool modifies them (no-op outside an agent session)
+    fn create_checkpoint(&self, tool: &str, paths: &[PathBuf]) -> Result<(), ExecutorError> {
+        if let Some(session_id) = &self.config.session_id {
+            self.checkpoints.create(session_id, tool, paths)?;
+        }
+        Ok(())
+    }
+
+    /// Execute list_checkpoints, revert_checkpoint or revert_session
+    async fn execute_checkpoint_tool(
+        &self,
+        tool: Tool,
+        tool_call: &ToolCall,
+    ) -> Result<(String, Option<ToolResultMetadata>), ExecutorError> {
+        let session_id = self.config.session_id.as_deref()
+            .ok_or_else(|| ExecutorError::InvalidArgument("Checkpoints require an agent session".to_string()))?;
+
+        if tool != Tool::ListCheckpoints && !self.config.allow_writes {
+            return Err(ExecutorError::PermissionDenied("Write operations are disabled".to_string()));
+        }
+
+        let value = match tool {
+            Tool::ListCheckpoints => serde_json::to_value(self.checkpoints.list(session_id)?),
+            Tool::RevertCheckpoint => {
+                let checkpoint_id = tool_call.arguments.get("checkpoint_id")
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| ExecutorError::MissingArgument("checkpoint_id".to_string()))?;
+                serde_json::to_value(self.checkpoints.revert_checkpoint(session_id, checkpoint_id)?)
+            }
+            Tool::RevertSession => serde_json::to_value(self.checkpoints.revert_session(session_id)?),
+            _ => return Err(ExecutorError::InvalidArgument(format!("Not a checkpoint tool: {}", tool.name()))),
+        }
+        .map_err(|e| ExecutorError::FileOperation(format!("Failed to serialize result: {}", e)))?;
+
+        let output = serde_json::to_string_pretty(&value)
+            .map_err(|e| ExecutorError::FileOperation(format!("Failed to serialize result: {}", e)))?;
+
+        Ok((output, None))
+    }
+
+    /// Execute list_attachments for the current conversation
+    async fn execute_list_attachments(&self) -> Result<(String, Option<ToolResultMetadata>), ExecutorError> {
+        let session_id = self.config.session_id.as_deref()
+            .ok_or_else(|| ExecutorError::InvalidArgument("Attachments require an agent session".to_string()))?;
+
+        let attachments = self.attachments.list(Some(session_id));
+        if attachments.is_empty() {
+            return Ok(("No files are attached to this conversation.".to_string(), None));
+        }
+
+        let lines: Vec<String> = attachments
+            .iter()
+            .map(|a| format!(
+                "{} - {} ({}, {} bytes, {} chunks)",
+                a.id, a.file_name, a.mime_type, a.size_bytes, a.chunk_count
+            ))
+            .collect();
+        Ok((lines.join("\n"), None))
+    }
+
+    /// Execute read_attachment
+    async fn execute_read_attachment(&self, tool_call: &ToolCall) -> Result<(String, Option<ToolResultMetadata>), ExecutorError> {
+        let attachment_id = tool_call.arguments.get("attachment_id")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ExecutorError::MissingArgument("attachment_id".to_string()))?;
+        let index = tool_call.arguments.get("chunk")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(0) as usize;
+
+        let attachment = self.attachments.get(attachment_id).map_err(attachment_error)?;
+        if let (Some(session_id), Some(owner)) = (&self.config.session_id, &attachment.conversation_id) {
+            if session_id != owner {
+                return Err(ExecutorError::PermissionDenied(format!(
+                    "Attachment {} belongs to another conversation",
+                    attachment_id
+                )));
+            }
+        }
+        if attachment.chunk_count == 0 {
+            return Ok((
+                format!("No text could be extracted from {} ({}).", attachment.file_name, attachment.mime_type),
+                None,
+            ));
+        }
+
+        let chunk = self.attachments.chunk(attachment_id, index).map_err(attachment_error)?;
+        let mut output = format!(
+            "[{} - chunk {} of {}]\n{}",
+            attachment.file_name,
+            index + 1,
+            attachment.chunk_count,
+            chunk.text
+        );
+        if index + 1 < attachment.chunk_count {
+            output.push_str(&format!("\n[Read chunk {} for more]", index + 1));
+        }
+        Ok((output, None))
+    }
+
+    /// Check on, read or stop a background job of this session
+    async fn execute_job_tool(
+        &self,
+        tool: Tool,
+        tool_call: &ToolCall,
+    ) -> Result<(String, Option<ToolResultMetadata>), ExecutorError> {
+        let jobs = JobManager::global();
+        let session_id = self.config.session_id.as_deref();
+        let job_id = tool_call.arguments.get("job_id").and_then(|v| v.as_str());
+
+        let output = match (tool, job_id) {
+            (Tool::JobStatus, None) => serde_json::to_string_pretty(&jobs.list(session_id).await),
+            (Tool::JobStatus, Some(id)) => serde_json::to_string_pretty(&jobs.status(session_id, id).await.map_err(job_error)?),
+            (Tool::JobCancel, Some(id)) => serde_json::to_string_pretty(&jobs.cancel(session_id, id).await.map_err(job_error)?),
+            (Tool::JobOutput, Some(id)) => {
+                let lines = tool_call.arguments.get("lines")
+                    .and_then(|v| v.as_u64())
+                    .map_or(DEFAULT_OUTPUT_LINES, |n| n as usize);
+                let output = jobs.output(session_id, id, lines).await.map_err(job_error)?;
+
+                let job = &output.job;
+                let mut text = format!("[job {} {}", job.id, job.state.as_str());
+                if let Some(code) = job.exit_code {
+                    text.push_str(&format!(" (exit code {})", code));
+                }
+                if let Some(error) = &job.error {
+                    text.push_str(&format!(", {}", error));
+                }
+                text.push_str(&format!(" - last {} of {} lines]\n", output.lines.len(), output.total_lines));
+                text.push_str(&output.lines.join("\n"));
+                return Ok((text, None));
+            }
+            _ => return Err(ExecutorError::MissingArgument("job_id".to_string())),
+        };
+        output
+            .map(|json| (json, None))
+            .map_err(|e| ExecutorError::InvalidArgument(e.to_string()))
+    }
+
+    /// Message another agent session or read this session's inbox
+    fn execute_mail_tool(
+        &self,
+        tool: Tool,
+        tool_call: &ToolCall,
+    ) -> Result<(String, Option<ToolResultMetadata>), ExecutorError> {
+        let session_id = self.config.session_id.as_deref().ok_or_else(|| {
+            ExecutorError::PermissionDenied("Messaging other agents needs an agent session".to_string())
+        })?;
+        let mailbox = Mailbox::global();
+        let args = &tool_call.arguments;
+
+        let output = if tool == Tool::SendToAgent {
+            let to = args.get("to")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| ExecutorError::MissingArgument("to".to_string()))?;
+            let message = args.get("message")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| ExecutorError::MissingArgument("message".to_string()))?;
+            let reply_to = args.get("reply_to").and_then(|v| v.as_str()).filter(|id| !id.is_empty());
+            let mail = mailbox
+                .send(session_id, to, message, reply_to)
+                .map_err(|e| ExecutorError::InvalidArgument(e.to_string()))?;
+            serde_json::to_string_pretty(&mail)
+        } else {
+            let mut inbox = serde_json::json!({ "messages": mailbox.read(session_id) });
+            if args.get("include_sent").and_then(|v| v.as_bool()).unwrap_or(false) {
+                inbox["sent"] = serde_json::json!(mailbox.sent(session_id));
+            }
+            serde_json::to_string_pretty(&inbox)
+        };
+        output
+            .map(|json| (json, None))
+            .map_err(|e| ExecutorError::InvalidArgument(e.to_string()))
+    }
+
+    /// Set or read a variable of this session
+    fn execute_var_tool(
+        &self,
+        tool: Tool,
+        tool_call: &ToolCall,
+    ) -> Result<(String, Option<ToolResultMetadata>), ExecutorError> {
+        let session_id = self.config.session_id.as_deref().ok_or_else(|| {
+            ExecutorError::PermissionDenied("Variables need an agent session".to_string())
+        })?;
+        let vars = &self.vars;
+        let args = &tool_call.arguments;
+        let name = args.get("name").and_then(|v| v.as_str()).filter(|n| !n.is_empty());
+
+        let output = if tool == Tool::SetVar {
+            let name = name.ok_or_else(|| ExecutorError::MissingArgument("name".to_string()))?;
+            let value = match args.get("value") {
+                Some(serde_json::Value::String(value)) => value.clone(),
+                Some(value) if !value.is_null() => value.to_string(),
+                _ => return Err(ExecutorError::MissingArgument("value".to_string())),
+            };
+            let secret = args.get("secret").and_then(|v| v.as_bool()).unwrap_or(false);
+            let var = vars
+                .set(session_id, name, &value, secret)
+                .map_err(|e| ExecutorError::InvalidArgument(e.to_string()))?;
+            if var.secret {
+                format!("Saved secret {}; use it in shell commands as ${{{}}}", var.name, var.name)
+            } else {
+                format!("Saved {}", var.name)
+            }
+        } else {
+            match name {
+                Some(name) => {
+                    let var = vars
+                        .get(session_id, name)
+                        .map_err(|e| ExecutorError::InvalidArgument(e.to_string()))?;
+                    match (var.secret, var.value) {
+                        (false, Some(value)) => value,
+                        _ => format!("{} is secret; use it in shell commands as ${{{}}}", var.name, var.name),
+                    }
+                }
+                None => serde_json::to_string_pretty(&vars.list(session_id))
+                    .map_err(|e| ExecutorError::InvalidArgument(e.to_string()))?,
+            }
+        };
+        Ok((output, None))
+    }
+
+    async fn execute_read_terminal(&self, tool_call: &ToolCall) -> Result<(String, Option<ToolResultMetadata>), ExecutorError> {
+        let manager = self.terminal_manager.as_ref().ok_or_else(|| {
+            ExecutorError::PermissionDenied("No user terminals are available".to_string())
+        })?;
+        let lines = tool_call.arguments.get("lines")
+            .and_then(|v| v.as_u64())
+            .map_or(crate::terminal::DEFAULT_SHARED_LINES, |n| n as usize);
+
+        let session_id = match tool_call.arguments.get("session_id").and_then(|v| v.as_str()) {
+            Some(id) => {
+                if !manager.is_shared_with_agent(id).await {
+                    return Err(ExecutorError::PermissionDenied(format!(
+                        "Terminal {} is not shared; the user can share it from the terminal panel",
+                        id
+                    )));
+                }
+                id.to_string()
+            }
+            None => {
+                let shared = manager.shared_session_ids().await;
+                match shared.as_slice() {
+                    [] => return Err(ExecutorError::PermissionDenied(
+                        "No terminals are shared; the user can share one from the terminal panel".to_string(),
+                    )),
+                    [id] => id.clone(),
+                    ids => return Err(ExecutorError::InvalidArgument(format!(
+                        "Several terminals are shared, pass session_id: {}",
+                        ids.join(", ")
+                    ))),
+                }
+            }
+        };
+
+        let scrollback = manager
+            .read_shared(&session_id, lines)
+            .await
+            .map_err(ExecutorError::InvalidArgument)?;
+        let mut output = format!("[terminal {} - last {} lines", session_id, scrollback.lines.len());
+        if scrollback.redacted_lines > 0 {
+            output.push_str(&format!(", {} redacted", scrollback.redacted_lines));
+        }
+        output.push_str("]\n");
+        if scrollback.truncated {
+            output.push_str("[earlier output omitted]\n");
+        }
+        output.push_str(&scrollback.lines.join("\n"));
+        Ok((output, None))
+    }
+
+    /// Workspace sandbox for this executor, if a root is configured
+    fn workspace(&self) -> Result<Option<Workspace>, ExecutorError> {
+        self.config
+            .workspace_root
+            .as_ref()
+            .map(|root| Workspace::new(root).map(|ws| ws.with_escape_allowed(self.config.allow_workspace_escape)))
+            .transpose()
+    }
+
+    /// Resolve a path and make sure it stays inside the workspace
+    fn resolve_sandboxed_path(&self, path_str: &str) -> Result<PathBuf, ExecutorError> {
+        let path = self.resolve_path(path_str);
+        match self.workspace()? {
+            Some(workspace) => workspace.check(&path),
+            None => Ok(path),
+        }
+    }
+
+    /// Like `resolve_sandboxed_path`, but a symlink stays the link itself
+    /// instead of its target
+    fn resolve_sandboxed_entry(&self, path_str: &str) -> Result<PathBuf, ExecutorError> {
+        let path = self.resolve_path(path_str);
+        match self.workspace()? {
+            Some(workspace) => workspace.check_entry(&path),
+            None => Ok(path),
+        }
+    }
+
     /// Resolve a path relative to working directory
     pub fn resolve_path(&self, path_str: &str) -> PathBuf {
         let path = Path::new(path_str);
@@ -701,11 +2457,31 @@ impl Default for ToolResultMetadata {
             exit_code: None,
             duration_ms: None,
             working_directory: None,
+            structured: None,
+            redacted_secrets: None,
+            attachments: Vec::new(),
         }
     }
 }
 
 /// Executor errors
+/// A line of the processes tool's listing
+fn process_row(process: &ProcessInfo) -> String {
+    let mut command = process.cmdline.clone();
+    if command.chars().count() > 100 {
+        command = command.chars().take(97).collect::<String>() + "...";
+    }
+    format!(
+        "{:>7} {:>6.1} {:>9} {:<10} {}{}\n",
+        process.pid,
+        process.cpu_percent,
+        crate::storage_quotas::format_bytes(process.memory_bytes),
+        process.user.as_deref().unwrap_or("-"),
+        command,
+        if process.child { "  (started by Skhoot)" } else { "" }
+    )
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum ExecutorError {
     #[error("Missing required argument: {0}")]
@@ -725,6 +2501,182 @@ pub enum ExecutorError {
     
     #[error("CLI bridge error: {0}")]
     CliBridge(#[from] CliError),
+
+    #[error("Git error: {0}")]
+    Git(String),
+
+    #[error("Clipboard error: {0}")]
+    Clipboard(String),
+
+    #[error("Screen capture error: {0}")]
+    ScreenCapture(String),
+
+    #[error("HTTP request failed: {0}")]
+    Http(String),
+
+    #[error("Test run failed: {0}")]
+    TestRun(String),
+
+    #[error("Lint failed: {0}")]
+    Lint(String),
+
+    #[error("Code intelligence failed: {0}")]
+    CodeIntelligence(String),
+
+    #[error("MCP tool failed: {0}")]
+    Mcp(String),
+
+    #[error("Plugin error: {0}")]
+    Plugin(String),
+
+    #[error("Tool call not run: {0}")]
+    Throttled(String),
+
+    #[error("Resource limit exceeded: {reason}")]
+    ResourceLimitExceeded { reason: String, partial_output: String },
+}
+
+fn attachment_error(e: AttachmentError) -> ExecutorError {
+    match e {
+        AttachmentError::NotFound(_) | AttachmentError::Invalid(_) | AttachmentError::TooLarge(_) => {
+            ExecutorError::InvalidArgument(e.to_string())
+        }
+        AttachmentError::Io(_) => ExecutorError::FileOperation(e.to_string()),
+    }
+}
+
+fn job_error(e: JobError) -> ExecutorError {
+    match e {
+        JobError::NotFound(_) => ExecutorError::InvalidArgument(e.to_string()),
+        JobError::TooManyJobs(_) => ExecutorError::Throttled(e.to_string()),
+        JobError::Cli(e) => ExecutorError::CliBridge(e),
+    }
+}
+
+fn archive_error(e: ArchiveError) -> ExecutorError {
+    match e {
+        ArchiveError::Io { .. } => ExecutorError::FileOperation(e.to_string()),
+        _ => ExecutorError::InvalidArgument(e.to_string()),
+    }
+}
+
+fn lsp_error(e: LspError) -> ExecutorError {
+    match e {
+        LspError::Disabled | LspError::Unsupported(_) | LspError::InvalidPosition(_) => {
+            ExecutorError::InvalidArgument(e.to_string())
+        }
+        LspError::Io(_) => ExecutorError::FileOperation(e.to_string()),
+        _ => ExecutorError::CodeIntelligence(e.to_string()),
+    }
+}
+
+fn scaffold_error(e: ScaffoldError) -> ExecutorError {
+    match e {
+        ScaffoldError::Io { .. } => ExecutorError::FileOperation(e.to_string()),
+        _ => ExecutorError::InvalidArgument(e.to_string()),
+    }
+}
+
+/// Combine the matches of several roots into one list with absolute paths.
+/// Roots take turns so each one's best matches make the top of the list;
+/// roots whose search failed are noted in `failed`.
+fn merge_root_matches(
+    roots: &[PathBuf],
+    results: Vec<anyhow::Result<CliSearchResult>>,
+    failed: &mut Vec<String>,
+) -> anyhow::Result<CliSearchResult> {
+    let mut per_root = Vec::new();
+    let mut first_error = None;
+    let mut commands = Vec::new();
+    let mut execution_time_ms = 0;
+    for (root, result) in roots.iter().zip(results) {
+        match result {
+            Ok(result) => {
+                commands.push(result.command_used);
+                execution_time_ms = execution_time_ms.max(result.execution_time_ms);
+                let files: Vec<CliFileMatch> = result
+                    .files
+                    .into_iter()
+                    .map(|file| CliFileMatch {
+                        path: root.join(file.path.trim_start_matches("./")).to_string_lossy().to_string(),
+                        ..file
+                    })
+                    .collect();
+                per_root.push(files.into_iter());
+            }
+            Err(e) => {
+                failed.push(format!("{} ({})", root.display(), e));
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+    if per_root.is_empty() {
+        return Err(first_error.unwrap_or_else(|| anyhow::anyhow!("no search roots")));
+    }
+
+    let mut files = Vec::new();
+    let mut seen = std::collections::HashSet::new();
+    loop {
+        let mut any = false;
+        for matches in per_root.iter_mut() {
+            if let Some(file) = matches.next() {
+                any = true;
+                // Overlapping roots find the same file twice
+                let key = (file.path.clone(), file.line_number);
+                if seen.insert(key) {
+                    files.push(file);
+                }
+            }
+        }
+        if !any {
+            break;
+        }
+    }
+    commands.dedup();
+    Ok(CliSearchResult {
+        total_results: files.len(),
+        files,
+        command_used: commands.join(", "),
+        execution_time_ms,
+    })
+}
+
+/// Root a multi-root match was found under
+fn root_of(roots: &[PathBuf], path: &str) -> String {
+    roots
+        .iter()
+        .filter(|root| Path::new(path).starts_with(root))
+        .max_by_key(|root| root.as_os_str().len())
+        .map(|root| root.to_string_lossy().to_string())
+        .unwrap_or_else(|| "other".to_string())
+}
+
+/// Template variables from a JSON object; numbers and booleans become text
+fn template_variables(value: Option<&serde_json::Value>) -> Result<BTreeMap<String, String>, ExecutorError> {
+    let Some(value) = value.filter(|v| !v.is_null()) else {
+        return Ok(BTreeMap::new());
+    };
+    let object = value
+        .as_object()
+        .ok_or_else(|| ExecutorError::InvalidArgument("variables must be an object".to_string()))?;
+    object
+        .iter()
+        .map(|(name, value)| match value {
+            serde_json::Value::String(s) => Ok((name.clone(), s.clone())),
+            serde_json::Value::Bool(_) | serde_json::Value::Number(_) => Ok((name.clone(), value.to_string())),
+            _ => Err(ExecutorError::InvalidArgument(format!("variable '{}' must be a string, number or boolean", name))),
+        })
+        .collect()
+}
+
+impl ExecutorError {
+    /// Output captured before the failure, if any
+    pub fn partial_output(&self) -> Option<&str> {
+        match self {
+            ExecutorError::ResourceLimitExceeded { partial_output, .. } => Some(partial_output),
+            _ => None,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -773,4 +2725,341 @@ mod tests {
             assert_eq!(path_home, home);
         }
     }
+
+    #[derive(Default)]
+    struct MemoryClipboard(std::sync::Mutex<String>);
+
+    impl super::super::clipboard::ClipboardProvider for MemoryClipboard {
+        fn read_text(&self) -> Result<String, String> {
+            Ok(self.0.lock().unwrap().clone())
+        }
+
+        fn write_text(&self, text: &str) -> Result<(), String> {
+            *self.0.lock().unwrap() = text.to_string();
+            Ok(())
+        }
+
+        fn write_image(&self, _png: &[u8]) -> Result<(), String> {
+            Err("images not supported".to_string())
+        }
+    }
+
+    fn clipboard_call(arguments: serde_json::Value) -> ToolCall {
+        ToolCall {
+            id: "call-1".to_string(),
+            name: "clipboard".to_string(),
+            arguments,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_clipboard_requires_permission() {
+        let clipboard = Arc::new(MemoryClipboard::default());
+        let denied = AgentExecutor::new().with_clipboard(clipboard.clone());
+        let result = denied.execute(&clipboard_call(serde_json::json!({ "action": "read" }))).await;
+        assert!(!result.success);
+        assert!(result.error.unwrap().contains("requires user approval"));
+
+        let allowed = AgentExecutor::with_config(ExecutorConfig {
+            allow_clipboard: true,
+            ..Default::default()
+        })
+        .with_clipboard(clipboard);
+        let write = allowed
+            .execute(&clipboard_call(serde_json::json!({ "action": "write_text", "text": "summary" })))
+            .await;
+        assert!(write.success, "{:?}", write.error);
+        let read = allowed.execute(&clipboard_call(serde_json::json!({ "action": "read" }))).await;
+        assert_eq!(read.output, "summary");
+    }
+
+    struct FixedScreen;
+
+    impl super::super::screen::ScreenCapture for FixedScreen {
+        fn capture(&self, target: &CaptureTarget) -> Result<Vec<u8>, String> {
+            if let CaptureTarget::Window { title: Some(title) } = target {
+                return Err(format!("No window matches '{}'", title));
+            }
+            // 1x1 transparent PNG
+            use base64::{engine::general_purpose::STANDARD, Engine};
+            STANDARD
+                .decode("iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNkYPhfDwAChwGA60e6kgAAAABJRU5ErkJggg==")
+                .map_err(|e| e.to_string())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_capture_screen_requires_permission() {
+        let call = |arguments: serde_json::Value| ToolCall {
+            id: "call-1".to_string(),
+            name: "capture_screen".to_string(),
+            arguments,
+        };
+        let denied = AgentExecutor::new().with_screen_capture(Arc::new(FixedScreen));
+        let result = denied.execute(&call(serde_json::json!({ "target": "screen" }))).await;
+        assert!(result.error.unwrap().contains("requires user approval"));
+
+        let allowed = AgentExecutor::with_config(ExecutorConfig {
+            allow_screen_capture: true,
+            ..Default::default()
+        })
+        .with_screen_capture(Arc::new(FixedScreen));
+        let result = allowed
+            .execute(&call(serde_json::json!({ "target": "region", "x": 0, "y": 0, "width": 1, "height": 1 })))
+            .await;
+        assert!(result.success, "{:?}", result.error);
+        let attachment = &result.metadata.unwrap().attachments[0];
+        assert_eq!((attachment.mime_type.as_str(), attachment.width), ("image/png", Some(1)));
+        std::fs::remove_file(&attachment.path).unwrap();
+
+        let missing = allowed.execute(&call(serde_json::json!({ "target": "window", "title": "Settings" }))).await;
+        assert!(missing.error.unwrap().contains("No window matches"));
+        let invalid = allowed.execute(&call(serde_json::json!({ "target": "region", "x": 0 }))).await;
+        assert!(invalid.error.unwrap().contains("Invalid capture target"));
+    }
+
+    #[tokio::test]
+    async fn test_http_request_requires_allowed_domain() {
+        let call = ToolCall {
+            id: "call-1".to_string(),
+            name: "http_request".to_string(),
+            arguments: serde_json::json!({ "url": "https://api.example.com/items" }),
+        };
+        let denied = AgentExecutor::new().execute(&call).await;
+        assert!(denied.error.unwrap().contains("allow domains"));
+
+        let executor = AgentExecutor::with_config(ExecutorConfig {
+            allowed_http_domains: vec!["github.com".to_string()],
+            ..Default::default()
+        });
+        let other = executor.execute(&call).await;
+        assert!(other.error.unwrap().contains("api.example.com is not on this session's allowed domains"));
+        let invalid = executor
+            .execute(&ToolCall { arguments: serde_json::json!({ "method": "GET" }), ..call })
+            .await;
+        assert!(invalid.error.unwrap().contains("Invalid HTTP request"));
+    }
+
+    #[tokio::test]
+    async fn test_read_terminal_requires_sharing() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let manager = TerminalManager::new(2, 60, 5, temp_dir.path().to_path_buf());
+        let executor = AgentExecutor::new().with_terminal_manager(manager);
+        let call = |arguments: serde_json::Value| ToolCall {
+            id: "call-1".to_string(),
+            name: "read_terminal".to_string(),
+            arguments,
+        };
+
+        let none_shared = executor.execute(&call(serde_json::json!({}))).await;
+        assert!(!none_shared.success);
+        assert!(none_shared.error.unwrap().contains("No terminals are shared"));
+
+        let not_shared = executor.execute(&call(serde_json::json!({ "session_id": "abc" }))).await;
+        assert!(!not_shared.success);
+        assert!(not_shared.error.unwrap().contains("not shared"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_rename_file_keeps_symlinks() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let workspace = temp_dir.path().join("workspace");
+        std::fs::create_dir_all(workspace.join("docs")).unwrap();
+        std::fs::write(workspace.join("target.txt"), "content").unwrap();
+        std::os::unix::fs::symlink(workspace.join("target.txt"), workspace.join("docs/link")).unwrap();
+        let executor = AgentExecutor::with_config(ExecutorConfig {
+            working_directory: workspace.clone(),
+            workspace_root: Some(workspace.clone()),
+            ..Default::default()
+        })
+        .with_checkpoint_manager(Arc::new(CheckpointManager::new(temp_dir.path().join("checkpoints"))));
+        let rename = |new_name: &str| ToolCall {
+            id: "call-1".to_string(),
+            name: "rename_file".to_string(),
+            arguments: serde_json::json!({ "path": "docs/link", "new_name": new_name }),
+        };
+
+        let escaped = executor.execute(&rename("../escaped")).await;
+        assert!(escaped.error.unwrap().contains("../escaped"));
+
+        let renamed = executor.execute(&rename("renamed")).await;
+        assert!(renamed.success, "{:?}", renamed.error);
+        assert!(workspace.join("docs/renamed").symlink_metadata().unwrap().file_type().is_symlink());
+        assert_eq!(std::fs::read_to_string(workspace.join("target.txt")).unwrap(), "content");
+    }
+
+    #[tokio::test]
+    async fn test_read_file_ranges() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let log: String = (1..=500).map(|i| format!("entry {}\n", i)).collect();
+        std::fs::write(temp_dir.path().join("app.log"), &log).unwrap();
+        let executor = AgentExecutor::with_config(ExecutorConfig {
+            working_directory: temp_dir.path().to_path_buf(),
+            max_output_size: 64,
+            ..Default::default()
+        });
+        let read = |arguments: serde_json::Value| {
+            let call = ToolCall {
+                id: "call-1".to_string(),
+                name: "read_file".to_string(),
+                arguments,
+            };
+            let executor = &executor;
+            async move { executor.execute(&call).await }
+        };
+
+        let tail = read(serde_json::json!({ "path": "app.log", "tail": 2 })).await;
+        assert_eq!(tail.output, "entry 499\nentry 500");
+        let head = read(serde_json::json!({ "path": "app.log", "start_line": 10, "end_line": 11 })).await;
+        assert_eq!(head.output, "entry 10\nentry 11");
+
+        let whole = read(serde_json::json!({ "path": "app.log" })).await;
+        assert!(whole.output.starts_with("entry 1\nentry 2\n"));
+        assert!(whole.output.contains("continue with start_line="), "{}", whole.output);
+        assert_eq!(whole.metadata.unwrap().attachments.len(), 1);
+
+        let both = read(serde_json::json!({ "path": "app.log", "head": 5, "tail": 5 })).await;
+        assert!(both.error.unwrap().contains("only one"));
+    }
+
+    #[tokio::test]
+    async fn test_tail_file() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("build.log"), "compiling\nerror: oops\n").unwrap();
+        let executor = AgentExecutor::with_config(ExecutorConfig {
+            working_directory: temp_dir.path().to_path_buf(),
+            ..Default::default()
+        });
+        let tail = |arguments: serde_json::Value| {
+            let call = ToolCall {
+                id: "call-1".to_string(),
+                name: "tail_file".to_string(),
+                arguments,
+            };
+            let executor = &executor;
+            async move { executor.execute(&call).await }
+        };
+
+        let matched = tail(serde_json::json!({ "path": "build.log", "pattern": "^error", "lines": 10 })).await;
+        assert!(matched.output.starts_with("error: oops\n[pattern matched after "), "{}", matched.output);
+        let waited = tail(serde_json::json!({ "path": "build.log", "pattern": "^Finished", "timeout_secs": 1 })).await;
+        assert_eq!(waited.output, "[no line matched within 1s; 0 lines]");
+        let invalid = tail(serde_json::json!({ "path": "build.log", "pattern": "(" })).await;
+        assert!(invalid.error.unwrap().contains("Invalid pattern"));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_processes_tool() {
+        let mut child = std::process::Command::new("sleep").arg("41").spawn().unwrap();
+        let executor = AgentExecutor::with_config(ExecutorConfig::default());
+        let call = |arguments: serde_json::Value| ToolCall {
+            id: "call-1".to_string(),
+            name: "processes".to_string(),
+            arguments,
+        };
+
+        let listed = executor.execute(&call(serde_json::json!({ "action": "list", "filter": "sleep 41" }))).await;
+        assert!(listed.output.contains(&child.id().to_string()), "{}", listed.output);
+        assert!(listed.output.contains("(started by Skhoot)"));
+
+        // A sleep whose shell already exited isn't ours, so stopping it needs confirmation
+        let output = std::process::Command::new("sh")
+            .args(["-c", "sleep 42 >/dev/null 2>&1 & echo $!"])
+            .output()
+            .unwrap();
+        let orphan: u32 = String::from_utf8_lossy(&output.stdout).trim().parse().unwrap();
+        let refused = executor.execute(&call(serde_json::json!({ "action": "kill", "pid": orphan }))).await;
+        assert!(refused.error.unwrap().contains("confirmation"));
+        processes::kill(orphan, true, true).unwrap();
+
+        let killed = executor.execute(&call(serde_json::json!({ "action": "kill", "pid": child.id() }))).await;
+        assert!(killed.output.starts_with("Sent SIGTERM to sleep"), "{:?}", killed.error);
+        child.wait().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_session_vars() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let vars = Arc::new(SessionVars::new());
+        let vault = Arc::new(crate::vault::ConversationVault::new(dir.path().join("vault.json")));
+        let traces = TraceRecorder::with_vault(dir.path().join("traces"), vault).with_session_vars(vars.clone());
+        let session_id = "test-vars";
+        let executor = AgentExecutor::with_config(ExecutorConfig {
+            session_id: Some(session_id.to_string()),
+            ..Default::default()
+        })
+        .with_session_vars(vars.clone())
+        .with_trace_recorder(Arc::new(traces));
+        let call = |name: &str, arguments: serde_json::Value| ToolCall {
+            id: "call-1".to_string(),
+            name: name.to_string(),
+            arguments,
+        };
+
+        let set = executor
+            .execute(&call("set_var", serde_json::json!({ "name": "token", "value": "tok-4821", "secret": true })))
+            .await;
+        assert!(set.success, "{:?}", set.error);
+        executor
+            .execute(&call("set_var", serde_json::json!({ "name": "region", "value": "eu-west-1" })))
+            .await;
+
+        let region = executor.execute(&call("get_var", serde_json::json!({ "name": "region" }))).await;
+        assert_eq!(region.output, "eu-west-1");
+        let token = executor.execute(&call("get_var", serde_json::json!({ "name": "token" }))).await;
+        assert!(token.output.contains("${token}") && !token.output.contains("tok-4821"));
+        let listed = executor.execute(&call("get_var", serde_json::json!({}))).await;
+        assert!(listed.output.contains("eu-west-1") && !listed.output.contains("tok-4821"));
+
+        let missing = executor.execute(&call("get_var", serde_json::json!({ "name": "nope" }))).await;
+        assert!(!missing.success);
+        let no_session = AgentExecutor::new().execute(&call("get_var", serde_json::json!({}))).await;
+        assert!(no_session.error.unwrap().contains("agent session"));
+
+        assert_eq!(vars.list(session_id).len(), 2);
+        assert!(SessionVars::global().list(session_id).is_empty());
+        let trace = std::fs::read_to_string(dir.path().join("traces").join("test-vars.jsonl")).unwrap();
+        assert!(trace.contains("eu-west-1") && !trace.contains("tok-4821"));
+    }
+
+    #[test]
+    fn test_merge_root_matches() {
+        let file = |path: &str| CliFileMatch {
+            path: path.to_string(),
+            line_number: None,
+            content: None,
+            match_type: crate::search_engine::CliMatchType::FileName,
+        };
+        let found = |paths: &[&str]| {
+            Ok(CliSearchResult {
+                files: paths.iter().map(|p| file(p)).collect(),
+                command_used: "fd".to_string(),
+                execution_time_ms: 3,
+                total_results: paths.len(),
+            })
+        };
+        let roots = [PathBuf::from("/home/u"), PathBuf::from("/mnt/drive"), PathBuf::from("/home/u/p")];
+        let mut failed = Vec::new();
+        let merged = merge_root_matches(
+            &roots,
+            vec![
+                found(&["./a.txt", "p/b.txt", "c.txt"]),
+                Err(anyhow::anyhow!("not mounted")),
+                found(&["b.txt"]),
+            ],
+            &mut failed,
+        )
+        .unwrap();
+
+        let paths: Vec<_> = merged.files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, ["/home/u/a.txt", "/home/u/p/b.txt", "/home/u/c.txt"]);
+        assert_eq!(merged.command_used, "fd");
+        assert_eq!(failed, ["/mnt/drive (not mounted)"]);
+        assert_eq!(root_of(&roots, "/home/u/p/b.txt"), "/home/u/p");
+
+        let mut failed = Vec::new();
+        assert!(merge_root_matches(&roots[1..2], vec![Err(anyhow::anyhow!("gone"))], &mut failed).is_err());
+    }
 }
@@ -0,0 +1,277 @@
+//! Conversation export and import
+//!
+//! A `ConversationArchive` is a portable snapshot of an agent conversation:
+//! metadata plus every message with its tool calls and attachments. It can be
+//! written as a versioned JSON archive (which can be imported again as a
+//! read-only session) or rendered as a Markdown transcript for reading.
+
+use chrono::DateTime;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::session::{AgentMessage, AgentSession, MessageRole};
+
+/// Current archive format version
+pub const ARCHIVE_VERSION: u32 = 1;
+
+/// Export file format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Json,
+    Markdown,
+}
+
+impl ExportFormat {
+    /// File extension without the dot
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Json => "json",
+            ExportFormat::Markdown => "md",
+        }
+    }
+
+    /// MIME type of the rendered export
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Json => "application/json",
+            ExportFormat::Markdown => "text/markdown; charset=utf-8",
+        }
+    }
+}
+
+/// Archive errors
+#[derive(Debug, thiserror::Error)]
+pub enum ArchiveError {
+    #[error("Invalid conversation archive: {0}")]
+    Invalid(String),
+
+    #[error("Unsupported archive version {0} (newest supported is {ARCHIVE_VERSION})")]
+    UnsupportedVersion(u32),
+}
+
+/// Information about an exported conversation
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConversationMetadata {
+    /// ID of the session the conversation was exported from
+    pub session_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub working_directory: Option<String>,
+    pub created_at: u64,
+    pub last_activity: u64,
+    /// Additional application-specific fields
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+/// Portable snapshot of a conversation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConversationArchive {
+    pub version: u32,
+    pub exported_at: u64,
+    pub metadata: ConversationMetadata,
+    pub messages: Vec<AgentMessage>,
+}
+
+impl ConversationArchive {
+    pub fn new(metadata: ConversationMetadata, messages: Vec<AgentMessage>) -> Self {
+        Self {
+            version: ARCHIVE_VERSION,
+            exported_at: chrono::Utc::now().timestamp().max(0) as u64,
+            metadata,
+            messages,
+        }
+    }
+
+    /// Snapshot an agent session
+    pub fn from_session(session: &AgentSession) -> Self {
+        Self::new(
+            ConversationMetadata {
+                session_id: session.id.clone(),
+                working_directory: Some(session.agent.config.working_directory.clone()),
+                created_at: session.created_at,
+                last_activity: session.last_activity,
+                ..Default::default()
+            },
+            session.messages().to_vec(),
+        )
+    }
+
+    /// Parse a JSON archive
+    pub fn from_json(json: &str) -> Result<Self, ArchiveError> {
+        let archive: Self = serde_json::from_str(json).map_err(|e| ArchiveError::Invalid(e.to_string()))?;
+        if archive.version > ARCHIVE_VERSION {
+            return Err(ArchiveError::UnsupportedVersion(archive.version));
+        }
+        Ok(archive)
+    }
+
+    /// Serialize as a pretty-printed JSON archive
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+
+    /// Render in the given format
+    pub fn render(&self, format: ExportFormat) -> String {
+        match format {
+            ExportFormat::Json => self.to_json(),
+            ExportFormat::Markdown => self.to_markdown(),
+        }
+    }
+
+    /// Suggested file name for the export
+    pub fn file_name(&self, format: ExportFormat) -> String {
+        let base = self.metadata.title.as_deref().unwrap_or(&self.metadata.session_id);
+        let safe: String = base
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '-' })
+            .collect();
+        format!("conversation-{}.{}", safe.trim_matches('-'), format.extension())
+    }
+
+    /// Render a human-readable Markdown transcript
+    pub fn to_markdown(&self) -> String {
+        let meta = &self.metadata;
+        let mut out = format!(
+            "# {}\n\n",
+            meta.title.as_deref().unwrap_or("Conversation")
+        );
+
+        out.push_str(&format!("- Session: `{}`\n", meta.session_id));
+        if let Some(provider) = &meta.provider {
+            let model = meta.model.as_deref().map(|m| format!(" / {}", m)).unwrap_or_default();
+            out.push_str(&format!("- Model: {}{}\n", provider, model));
+        }
+        if let Some(dir) = &meta.working_directory {
+            out.push_str(&format!("- Working directory: `{}`\n", dir));
+        }
+        out.push_str(&format!("- Started: {}\n", format_timestamp(meta.created_at)));
+        out.push_str(&format!("- Exported: {}\n", format_timestamp(self.exported_at)));
+
+        for message in &self.messages {
+            out.push_str("\n---\n\n");
+            let heading = match message.role {
+                MessageRole::System => "System".to_string(),
+                MessageRole::User => "User".to_string(),
+                MessageRole::Assistant => "Assistant".to_string(),
+                MessageRole::Tool => match &message.tool_call_id {
+                    Some(id) => format!("Tool result `{}`", id),
+                    None => "Tool result".to_string(),
+                },
+            };
+            out.push_str(&format!("### {} · {}\n\n", heading, format_timestamp(message.timestamp)));
+
+            if !message.content.trim().is_empty() {
+                if message.role == MessageRole::Tool {
+                    out.push_str(&fenced("", &message.content));
+                } else {
+                    out.push_str(message.content.trim_end());
+                    out.push('\n');
+                }
+            }
+
+            for call in message.tool_calls.iter().flatten() {
+                out.push_str(&format!("\n**Tool call** `{}` (`{}`)\n\n", call.name, call.id));
+                let args = serde_json::to_string_pretty(&call.arguments).unwrap_or_default();
+                out.push_str(&fenced("json", &args));
+            }
+
+            if !message.attachments.is_empty() {
+                out.push_str("\nAttachments:\n");
+                for attachment in &message.attachments {
+                    match &attachment.path {
+                        Some(path) => out.push_str(&format!("- {} (`{}`)\n", attachment.name, path)),
+                        None => out.push_str(&format!("- {}\n", attachment.name)),
+                    }
+                }
+            }
+        }
+
+        out
+    }
+}
+
+fn format_timestamp(secs: u64) -> String {
+    DateTime::from_timestamp(secs as i64, 0)
+        .map(|dt| dt.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+        .unwrap_or_else(|| secs.to_string())
+}
+
+/// Code block whose fence is longer than any backtick run in `content`
+fn fenced(lang: &str, content: &str) -> String {
+    let longest_run = content
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or(0);
+    let fence = "`".repeat(longest_run.max(2) + 1);
+    format!("{}{}\n{}\n{}\n", fence, lang, content.trim_end(), fence)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli_agent::session::MessageAttachment;
+    use crate::cli_agent::tools::ToolCall;
+
+    fn archive() -> ConversationArchive {
+        let mut user = AgentMessage::user("List the files".to_string());
+        user.attachments.push(MessageAttachment {
+            name: "notes.txt".to_string(),
+            path: Some("/tmp/notes.txt".to_string()),
+            ..Default::default()
+        });
+        let call = ToolCall {
+            id: "call-1".to_string(),
+            name: "list_directory".to_string(),
+            arguments: serde_json::json!({"path": "."}),
+        };
+        ConversationArchive::new(
+            ConversationMetadata {
+                session_id: "s1".to_string(),
+                title: Some("File listing".to_string()),
+                ..Default::default()
+            },
+            vec![
+                user,
+                AgentMessage::assistant_with_tools(String::new(), vec![call]),
+                AgentMessage::tool_result("call-1".to_string(), "a.txt\n```b```".to_string()),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_json_roundtrip_and_version_check() {
+        let archive = archive();
+        let parsed = ConversationArchive::from_json(&archive.to_json()).unwrap();
+        assert_eq!(parsed.messages.len(), 3);
+        assert_eq!(parsed.messages[0].attachments[0].name, "notes.txt");
+        assert_eq!(parsed.messages[1].tool_calls.as_ref().unwrap()[0].name, "list_directory");
+
+        let mut future = serde_json::to_value(&archive).unwrap();
+        future["version"] = serde_json::json!(ARCHIVE_VERSION + 1);
+        assert!(matches!(
+            ConversationArchive::from_json(&future.to_string()),
+            Err(ArchiveError::UnsupportedVersion(_))
+        ));
+    }
+
+    #[test]
+    fn test_markdown_transcript() {
+        let markdown = archive().to_markdown();
+        assert!(markdown.starts_with("# File listing\n"));
+        assert!(markdown.contains("### User"));
+        assert!(markdown.contains("- notes.txt (`/tmp/notes.txt`)"));
+        assert!(markdown.contains("**Tool call** `list_directory` (`call-1`)"));
+        // Tool output containing backticks gets a longer fence
+        assert!(markdown.contains("````\na.txt\n```b```\n````"));
+        assert_eq!(archive().file_name(ExportFormat::Markdown), "conversation-File-listing.md");
+    }
+}
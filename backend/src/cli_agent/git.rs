@@ -0,0 +1,343 @@
+//! Git helpers for the agent's git tool family
+//!
+//! Shells out to the `git` binary and parses its porcelain output into
+//! structured results that are returned to the model as JSON.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+
+use super::executor::ExecutorError;
+
+/// Field separator used in `git log` formats
+const FIELD_SEP: char = '\x1f';
+
+/// Status of a single changed file
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GitFileStatus {
+    pub path: String,
+    /// Original path for renames and copies
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub original_path: Option<String>,
+    /// Staged status code (`M`, `A`, `D`, `R`, `?`, ...)
+    pub index: String,
+    /// Unstaged status code
+    pub worktree: String,
+}
+
+/// Result of `git_status`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitStatus {
+    pub branch: Option<String>,
+    pub upstream: Option<String>,
+    pub ahead: u32,
+    pub behind: u32,
+    pub clean: bool,
+    pub files: Vec<GitFileStatus>,
+}
+
+/// Per-file line counts in a diff
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GitDiffFile {
+    pub path: String,
+    /// `None` for binary files
+    pub additions: Option<u32>,
+    pub deletions: Option<u32>,
+}
+
+/// Result of `git_diff`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitDiff {
+    pub staged: bool,
+    pub files: Vec<GitDiffFile>,
+    pub diff: String,
+    pub truncated: bool,
+}
+
+/// A commit as reported by `git_log` and `git_commit`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitCommitInfo {
+    pub hash: String,
+    pub author: String,
+    pub email: String,
+    pub date: String,
+    pub subject: String,
+}
+
+/// Result of `git_checkout_branch`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitCheckout {
+    pub branch: String,
+    pub created: bool,
+}
+
+/// A git working tree the agent operates on
+pub struct GitRepo {
+    path: PathBuf,
+}
+
+impl GitRepo {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Current branch, upstream tracking info and changed files
+    pub async fn status(&self) -> Result<GitStatus, ExecutorError> {
+        let output = self.run(&["status", "--porcelain=v1", "--branch", "--untracked-files=all"]).await?;
+        Ok(parse_status(&output))
+    }
+
+    /// Diff of the working tree (or the index when `staged`), optionally limited to a path
+    pub async fn diff(&self, staged: bool, path: Option<&str>, max_bytes: usize) -> Result<GitDiff, ExecutorError> {
+        let mut numstat_args = vec!["diff", "--numstat"];
+        let mut diff_args = vec!["diff"];
+        if staged {
+            numstat_args.push("--cached");
+            diff_args.push("--cached");
+        }
+        if let Some(path) = path {
+            numstat_args.extend(["--", path]);
+            diff_args.extend(["--", path]);
+        }
+
+        let files = parse_numstat(&self.run(&numstat_args).await?);
+        let mut diff = self.run(&diff_args).await?;
+        let truncated = diff.len() > max_bytes;
+        if truncated {
+            let mut end = max_bytes;
+            while !diff.is_char_boundary(end) {
+                end -= 1;
+            }
+            diff.truncate(end);
+        }
+
+        Ok(GitDiff { staged, files, diff, truncated })
+    }
+
+    /// Stage the given paths (or all changes) and commit them
+    pub async fn commit(&self, message: &str, paths: &[String], all: bool) -> Result<GitCommitInfo, ExecutorError> {
+        if message.trim().is_empty() {
+            return Err(ExecutorError::InvalidArgument("Commit message cannot be empty".to_string()));
+        }
+
+        if all {
+            self.run(&["add", "--all"]).await?;
+        } else if !paths.is_empty() {
+            let mut args = vec!["add", "--"];
+            args.extend(paths.iter().map(String::as_str));
+            self.run(&args).await?;
+        }
+
+        self.run(&["commit", "-m", message]).await?;
+        self.log(1, None)
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| ExecutorError::Git("Commit created but not found in log".to_string()))
+    }
+
+    /// Switch to a branch, creating it first if requested
+    pub async fn checkout_branch(&self, branch: &str, create: bool) -> Result<GitCheckout, ExecutorError> {
+        if branch.starts_with('-') {
+            return Err(ExecutorError::InvalidArgument(format!("Invalid branch name: {}", branch)));
+        }
+
+        // `switch` only takes branches; `checkout` would restore a file of
+        // the same name when no such branch exists
+        if create {
+            self.run(&["switch", "-c", branch]).await?;
+        } else {
+            self.run(&["switch", branch]).await?;
+        }
+
+        Ok(GitCheckout { branch: branch.to_string(), created: create })
+    }
+
+    /// Most recent commits, optionally limited to those touching a path
+    pub async fn log(&self, max_count: usize, path: Option<&str>) -> Result<Vec<GitCommitInfo>, ExecutorError> {
+        let format = format!("--format=%H{0}%an{0}%ae{0}%aI{0}%s", FIELD_SEP);
+        let count = format!("--max-count={}", max_count);
+        let mut args = vec!["log", format.as_str(), count.as_str()];
+        if let Some(path) = path {
+            args.extend(["--", path]);
+        }
+        Ok(parse_log(&self.run(&args).await?))
+    }
+
+    async fn run(&self, args: &[&str]) -> Result<String, ExecutorError> {
+        let output = Command::new("git")
+            .args(args)
+            .current_dir(&self.path)
+            .env("GIT_TERMINAL_PROMPT", "0")
+            .output()
+            .await
+            .map_err(|e| ExecutorError::Git(format!("Failed to run git: {}", e)))?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let message = if stderr.trim().is_empty() { stdout } else { stderr };
+            return Err(ExecutorError::Git(format!("git {} failed: {}", args[0], message.trim())));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+}
+
+/// Parse `git status --porcelain=v1 --branch`
+fn parse_status(output: &str) -> GitStatus {
+    let mut status = GitStatus {
+        branch: None,
+        upstream: None,
+        ahead: 0,
+        behind: 0,
+        clean: true,
+        files: Vec::new(),
+    };
+
+    for line in output.lines() {
+        if let Some(header) = line.strip_prefix("## ") {
+            parse_branch_header(header, &mut status);
+            continue;
+        }
+        if line.len() < 4 {
+            continue;
+        }
+
+        let index = line[0..1].to_string();
+        let worktree = line[1..2].to_string();
+        let path_part = &line[3..];
+        let (original_path, path) = match path_part.split_once(" -> ") {
+            Some((from, to)) => (Some(from.to_string()), to.to_string()),
+            None => (None, path_part.to_string()),
+        };
+
+        status.files.push(GitFileStatus { path, original_path, index, worktree });
+    }
+
+    status.clean = status.files.is_empty();
+    status
+}
+
+/// Parse a branch header like `main...origin/main [ahead 1, behind 2]`
+fn parse_branch_header(header: &str, status: &mut GitStatus) {
+    let (refs, tracking) = match header.split_once(" [") {
+        Some((refs, rest)) => (refs, Some(rest.trim_end_matches(']'))),
+        None => (header, None),
+    };
+
+    let refs = refs.strip_prefix("No commits yet on ").unwrap_or(refs);
+    match refs.split_once("...") {
+        Some((branch, upstream)) => {
+            status.branch = Some(branch.to_string());
+            status.upstream = Some(upstream.to_string());
+        }
+        None if refs.starts_with("HEAD (no branch)") => {}
+        None => status.branch = Some(refs.to_string()),
+    }
+
+    for part in tracking.into_iter().flat_map(|t| t.split(", ")) {
+        if let Some(n) = part.strip_prefix("ahead ") {
+            status.ahead = n.parse().unwrap_or(0);
+        } else if let Some(n) = part.strip_prefix("behind ") {
+            status.behind = n.parse().unwrap_or(0);
+        }
+    }
+}
+
+/// Parse `git diff --numstat`
+fn parse_numstat(output: &str) -> Vec<GitDiffFile> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(3, '\t');
+            let additions = parts.next()?;
+            let deletions = parts.next()?;
+            let path = parts.next()?;
+            Some(GitDiffFile {
+                path: path.to_string(),
+                additions: additions.parse().ok(),
+                deletions: deletions.parse().ok(),
+            })
+        })
+        .collect()
+}
+
+/// Parse `git log` output produced with [`FIELD_SEP`]-separated fields
+fn parse_log(output: &str) -> Vec<GitCommitInfo> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.splitn(5, FIELD_SEP).collect();
+            if fields.len() < 5 {
+                return None;
+            }
+            Some(GitCommitInfo {
+                hash: fields[0].to_string(),
+                author: fields[1].to_string(),
+                email: fields[2].to_string(),
+                date: fields[3].to_string(),
+                subject: fields[4].to_string(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_status() {
+        let output = "## main...origin/main [ahead 2, behind 1]\n M src/lib.rs\nA  new.rs\nR  old.rs -> renamed.rs\n?? notes.txt\n";
+        let status = parse_status(output);
+
+        assert_eq!(status.branch.as_deref(), Some("main"));
+        assert_eq!(status.upstream.as_deref(), Some("origin/main"));
+        assert_eq!((status.ahead, status.behind), (2, 1));
+        assert!(!status.clean);
+        assert_eq!(status.files.len(), 4);
+        assert_eq!(status.files[0].worktree, "M");
+        assert_eq!(status.files[2].original_path.as_deref(), Some("old.rs"));
+        assert_eq!(status.files[3].index, "?");
+    }
+
+    #[test]
+    fn test_parse_numstat_handles_binary_files() {
+        let files = parse_numstat("3\t1\tsrc/main.rs\n-\t-\tlogo.png\n");
+        assert_eq!(files[0].additions, Some(3));
+        assert_eq!(files[1].additions, None);
+        assert_eq!(files[1].path, "logo.png");
+    }
+
+    #[tokio::test]
+    async fn test_commit_and_log_roundtrip() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let repo = GitRepo::new(temp_dir.path());
+        if repo.run(&["init", "-q"]).await.is_err() {
+            return; // git not installed
+        }
+        repo.run(&["config", "user.email", "agent@example.com"]).await.unwrap();
+        repo.run(&["config", "user.name", "Agent"]).await.unwrap();
+
+        std::fs::write(temp_dir.path().join("file.txt"), "hello").unwrap();
+        assert_eq!(repo.status().await.unwrap().files.len(), 1);
+
+        let commit = repo.commit("Add file", &[], true).await.unwrap();
+        assert_eq!(commit.subject, "Add file");
+        assert!(repo.status().await.unwrap().clean);
+
+        repo.checkout_branch("feature", true).await.unwrap();
+        assert_eq!(repo.status().await.unwrap().branch.as_deref(), Some("feature"));
+        assert_eq!(repo.log(10, None).await.unwrap().len(), 1);
+
+        // A name that is only a file leaves the file's edits alone
+        std::fs::write(temp_dir.path().join("file.txt"), "edited").unwrap();
+        assert!(repo.checkout_branch("file.txt", false).await.is_err());
+        assert_eq!(std::fs::read_to_string(temp_dir.path().join("file.txt")).unwrap(), "edited");
+    }
+}
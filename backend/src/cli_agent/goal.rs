@@ -0,0 +1,565 @@
+//! Goal mode
+//!
+//! Given a goal and its success criteria, the agent works in iterations of
+//! plan → act → evaluate until every criterion is met or the iteration
+//! budget runs out. The loop runs in the client's agent session; a
+//! [`GoalRun`] holds its state: it hands out the prompt for the next phase,
+//! records what each phase produced and, after every evaluation, decides
+//! whether to stop. Each change publishes a progress event, and runs persist
+//! to `~/.skhoot/goals/` so they survive a restart.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+
+use crate::json_store;
+
+/// Iterations allowed when the request doesn't set a budget
+pub const DEFAULT_MAX_ITERATIONS: u32 = 10;
+/// Largest budget a run may ask for
+pub const MAX_ITERATIONS: u32 = 50;
+
+#[derive(Debug, thiserror::Error)]
+pub enum GoalError {
+    #[error("Goal run not found: {0}")]
+    NotFound(String),
+
+    #[error("{0}")]
+    Invalid(String),
+
+    #[error("Failed to save goal run: {0}")]
+    Io(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GoalPhase {
+    Plan,
+    Act,
+    Evaluate,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GoalStatus {
+    Running,
+    /// Every criterion was met
+    Succeeded,
+    /// The iteration budget ran out first
+    Exhausted,
+    Failed,
+    Cancelled,
+}
+
+/// Verdict on one success criterion
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CriterionResult {
+    pub met: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+}
+
+/// One plan → act → evaluate cycle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GoalIteration {
+    pub number: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plan: Option<String>,
+    /// What the agent reported doing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub actions: Option<String>,
+    /// Verdicts in the order of the run's criteria; empty until evaluated
+    #[serde(default)]
+    pub evaluation: Vec<CriterionResult>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    pub started_at: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<i64>,
+}
+
+/// Request to start working toward a goal
+#[derive(Debug, Clone, Deserialize)]
+pub struct StartGoalRequest {
+    pub goal: String,
+    pub criteria: Vec<String>,
+    #[serde(default)]
+    pub max_iterations: Option<u32>,
+    /// Agent session doing the work
+    #[serde(default)]
+    pub session_id: Option<String>,
+}
+
+/// What the client got from running the current phase
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PhaseReport {
+    pub phase: Option<GoalPhase>,
+    /// The agent's reply for the phase
+    #[serde(default)]
+    pub output: String,
+    /// Verdicts for an evaluation; parsed from `output` when omitted
+    #[serde(default)]
+    pub criteria: Option<Vec<CriterionResult>>,
+    /// Set when the phase couldn't be carried out; fails the run
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+/// Persistent state of one goal
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GoalRun {
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    pub goal: String,
+    pub criteria: Vec<String>,
+    pub max_iterations: u32,
+    pub status: GoalStatus,
+    /// Phase the client should run next
+    pub phase: GoalPhase,
+    pub iterations: Vec<GoalIteration>,
+    /// Prompt for the next phase; `None` once the run is over
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub current_prompt: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub started_at: i64,
+    pub updated_at: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub completed_at: Option<i64>,
+}
+
+/// Verdicts the evaluation prompt asks for
+#[derive(Debug, Deserialize)]
+struct Verdict {
+    criteria: Vec<CriterionResult>,
+    #[serde(default)]
+    summary: Option<String>,
+}
+
+impl GoalRun {
+    pub fn new(request: StartGoalRequest) -> Result<Self, GoalError> {
+        let goal = request.goal.trim().to_string();
+        if goal.is_empty() {
+            return Err(GoalError::Invalid("Goal is empty".to_string()));
+        }
+        let criteria: Vec<String> = request
+            .criteria
+            .iter()
+            .map(|c| c.trim().to_string())
+            .filter(|c| !c.is_empty())
+            .collect();
+        if criteria.is_empty() {
+            return Err(GoalError::Invalid("At least one success criterion is required".to_string()));
+        }
+        let max_iterations = request.max_iterations.unwrap_or(DEFAULT_MAX_ITERATIONS);
+        if !(1..=MAX_ITERATIONS).contains(&max_iterations) {
+            return Err(GoalError::Invalid(format!(
+                "max_iterations must be between 1 and {}",
+                MAX_ITERATIONS
+            )));
+        }
+
+        let now = chrono::Utc::now().timestamp();
+        let mut run = Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            session_id: request.session_id,
+            goal,
+            criteria,
+            max_iterations,
+            status: GoalStatus::Running,
+            phase: GoalPhase::Plan,
+            iterations: vec![GoalIteration::new(1, now)],
+            current_prompt: None,
+            error: None,
+            started_at: now,
+            updated_at: now,
+            completed_at: None,
+        };
+        run.current_prompt = Some(run.prompt());
+        Ok(run)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.status != GoalStatus::Running
+    }
+
+    /// Number of the iteration in progress, or the last one
+    pub fn iteration(&self) -> u32 {
+        self.iterations.len() as u32
+    }
+
+    /// Criteria met by the latest evaluation
+    pub fn criteria_met(&self) -> usize {
+        self.iterations
+            .iter()
+            .rev()
+            .find(|i| !i.evaluation.is_empty())
+            .map_or(0, |i| i.evaluation.iter().filter(|c| c.met).count())
+    }
+
+    /// Record the outcome of the current phase and move to the next one.
+    /// An evaluation that meets every criterion ends the run; otherwise a new
+    /// iteration starts, unless the budget is spent.
+    pub fn record(&mut self, report: PhaseReport) -> Result<(), GoalError> {
+        if self.is_finished() {
+            return Err(GoalError::Invalid(format!("Goal run {} already finished", self.id)));
+        }
+        if let Some(phase) = report.phase.filter(|p| *p != self.phase) {
+            return Err(GoalError::Invalid(format!(
+                "Expected a report for the {:?} phase, got {:?}",
+                self.phase, phase
+            )));
+        }
+
+        let now = chrono::Utc::now().timestamp();
+        self.updated_at = now;
+        if let Some(error) = report.error {
+            self.finish(GoalStatus::Failed, now);
+            self.error = Some(error);
+            return Ok(());
+        }
+
+        let output = report.output.trim().to_string();
+        match self.phase {
+            GoalPhase::Plan => {
+                self.current_iteration().plan = Some(output);
+                self.phase = GoalPhase::Act;
+            }
+            GoalPhase::Act => {
+                self.current_iteration().actions = Some(output);
+                self.phase = GoalPhase::Evaluate;
+            }
+            GoalPhase::Evaluate => {
+                let (evaluation, summary) = match report.criteria {
+                    Some(criteria) => (criteria, (!output.is_empty()).then_some(output)),
+                    None => {
+                        let verdict = parse_verdict(&output).ok_or_else(|| {
+                            GoalError::Invalid("The evaluation has no JSON verdict".to_string())
+                        })?;
+                        (verdict.criteria, verdict.summary)
+                    }
+                };
+                if evaluation.len() != self.criteria.len() {
+                    return Err(GoalError::Invalid(format!(
+                        "The evaluation covers {} criteria, expected {}",
+                        evaluation.len(),
+                        self.criteria.len()
+                    )));
+                }
+
+                let all_met = evaluation.iter().all(|c| c.met);
+                let iteration = self.current_iteration();
+                iteration.evaluation = evaluation;
+                iteration.summary = summary;
+                iteration.finished_at = Some(now);
+
+                if all_met {
+                    self.finish(GoalStatus::Succeeded, now);
+                } else if self.iteration() >= self.max_iterations {
+                    self.finish(GoalStatus::Exhausted, now);
+                } else {
+                    let next = self.iteration() + 1;
+                    self.iterations.push(GoalIteration::new(next, now));
+                    self.phase = GoalPhase::Plan;
+                }
+            }
+        }
+
+        if !self.is_finished() {
+            self.current_prompt = Some(self.prompt());
+        }
+        Ok(())
+    }
+
+    /// Stop working toward the goal
+    pub fn cancel(&mut self) -> Result<(), GoalError> {
+        if self.is_finished() {
+            return Err(GoalError::Invalid(format!("Goal run {} already finished", self.id)));
+        }
+        self.finish(GoalStatus::Cancelled, chrono::Utc::now().timestamp());
+        Ok(())
+    }
+
+    fn finish(&mut self, status: GoalStatus, now: i64) {
+        self.status = status;
+        self.current_prompt = None;
+        self.completed_at = Some(now);
+        self.updated_at = now;
+    }
+
+    fn current_iteration(&mut self) -> &mut GoalIteration {
+        self.iterations.last_mut().expect("a goal run always has an iteration")
+    }
+
+    /// Prompt for the current phase
+    fn prompt(&self) -> String {
+        let iteration = self.iterations.last().expect("a goal run always has an iteration");
+        let criteria = self
+            .criteria
+            .iter()
+            .enumerate()
+            .map(|(i, c)| format!("{}. {}", i + 1, c))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let header = format!(
+            "## Goal\n\n{}\n\n## Success criteria\n\n{}\n\nIteration {} of {}.",
+            self.goal, criteria, iteration.number, self.max_iterations
+        );
+
+        match self.phase {
+            GoalPhase::Plan => {
+                let previous = self.iterations.iter().rev().nth(1).map(|last| {
+                    let unmet = self
+                        .criteria
+                        .iter()
+                        .zip(&last.evaluation)
+                        .filter(|(_, result)| !result.met)
+                        .map(|(criterion, result)| match &result.notes {
+                            Some(notes) => format!("- {} ({})", criterion, notes),
+                            None => format!("- {}", criterion),
+                        })
+                        .collect::<Vec<_>>()
+                        .join("\n");
+                    format!("\n\nThe last evaluation found these criteria unmet:\n{}", unmet)
+                });
+                format!(
+                    "{}{}\n\nWrite a short plan for this iteration: the next concrete steps toward the \
+                     criteria that aren't met yet. Don't carry it out yet.",
+                    header,
+                    previous.unwrap_or_default()
+                )
+            }
+            GoalPhase::Act => format!(
+                "{}\n\nCarry out this plan, using tools as needed, then summarize what you did:\n\n{}",
+                header,
+                iteration.plan.as_deref().unwrap_or("")
+            ),
+            GoalPhase::Evaluate => format!(
+                "{}\n\nCheck the current state against each success criterion; verify rather than \
+                 assume. Reply with only this JSON, one entry per criterion in order:\n\
+                 {{\"criteria\": [{{\"met\": true, \"notes\": \"evidence\"}}], \"summary\": \"progress so far\"}}",
+                header
+            ),
+        }
+    }
+}
+
+impl GoalIteration {
+    fn new(number: u32, now: i64) -> Self {
+        Self {
+            number,
+            plan: None,
+            actions: None,
+            evaluation: Vec::new(),
+            summary: None,
+            started_at: now,
+            finished_at: None,
+        }
+    }
+}
+
+/// Verdict JSON in a model reply, bare or in a code fence
+fn parse_verdict(output: &str) -> Option<Verdict> {
+    let start = output.find('{')?;
+    let end = output.rfind('}')?;
+    serde_json::from_str(output.get(start..=end)?).ok()
+}
+
+/// Persistent goal runs
+pub struct GoalStore {
+    directory: PathBuf,
+    runs: RwLock<HashMap<String, GoalRun>>,
+}
+
+lazy_static::lazy_static! {
+    static ref GLOBAL_STORE: Arc<GoalStore> = Arc::new(GoalStore::new(
+        dirs::home_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join(".skhoot")
+            .join("goals"),
+    ));
+}
+
+impl GoalStore {
+    /// Open the store in `directory`, loading the runs saved there
+    pub fn new(directory: PathBuf) -> Self {
+        let mut runs = HashMap::new();
+        for entry in std::fs::read_dir(&directory).into_iter().flatten().flatten() {
+            let run = std::fs::read_to_string(entry.path())
+                .ok()
+                .and_then(|content| serde_json::from_str::<GoalRun>(&content).ok());
+            if let Some(run) = run {
+                runs.insert(run.id.clone(), run);
+            }
+        }
+        Self {
+            directory,
+            runs: RwLock::new(runs),
+        }
+    }
+
+    /// Shared store in `~/.skhoot/goals`
+    pub fn global() -> Arc<GoalStore> {
+        GLOBAL_STORE.clone()
+    }
+
+    pub fn start(&self, request: StartGoalRequest) -> Result<GoalRun, GoalError> {
+        let run = GoalRun::new(request)?;
+        self.save(&run)?;
+        self.runs.write().unwrap().insert(run.id.clone(), run.clone());
+        publish(&run);
+        Ok(run)
+    }
+
+    pub fn get(&self, id: &str) -> Option<GoalRun> {
+        self.runs.read().unwrap().get(id).cloned()
+    }
+
+    /// Runs of a session, or all runs, newest first
+    pub fn list(&self, session_id: Option<&str>) -> Vec<GoalRun> {
+        let mut runs: Vec<GoalRun> = self
+            .runs
+            .read()
+            .unwrap()
+            .values()
+            .filter(|run| session_id.is_none() || run.session_id.as_deref() == session_id)
+            .cloned()
+            .collect();
+        runs.sort_by_key(|r| std::cmp::Reverse(r.started_at));
+        runs
+    }
+
+    pub fn report(&self, id: &str, report: PhaseReport) -> Result<GoalRun, GoalError> {
+        self.update(id, |run| run.record(report))
+    }
+
+    pub fn cancel(&self, id: &str) -> Result<GoalRun, GoalError> {
+        self.update(id, GoalRun::cancel)
+    }
+
+    /// Forget a run. Returns whether it existed.
+    pub fn delete(&self, id: &str) -> bool {
+        let removed = self.runs.write().unwrap().remove(id).is_some();
+        if removed {
+            let _ = std::fs::remove_file(self.path(id));
+        }
+        removed
+    }
+
+    fn update(&self, id: &str, change: impl FnOnce(&mut GoalRun) -> Result<(), GoalError>) -> Result<GoalRun, GoalError> {
+        let mut runs = self.runs.write().unwrap();
+        let run = runs.get_mut(id).ok_or_else(|| GoalError::NotFound(id.to_string()))?;
+        // Apply to a copy so a rejected report leaves the run untouched
+        let mut updated = run.clone();
+        change(&mut updated)?;
+        self.save(&updated)?;
+        *run = updated.clone();
+        publish(&updated);
+        Ok(updated)
+    }
+
+    fn path(&self, id: &str) -> PathBuf {
+        self.directory.join(format!("{}.json", id))
+    }
+
+    fn save(&self, run: &GoalRun) -> Result<(), GoalError> {
+        json_store::save(&self.path(&run.id), run).map_err(|e| GoalError::Io(e.to_string()))
+    }
+}
+
+fn publish(run: &GoalRun) {
+    crate::events::publish(crate::events::Event::GoalProgress {
+        goal_id: run.id.clone(),
+        session_id: run.session_id.clone(),
+        iteration: run.iteration(),
+        phase: run.phase,
+        status: run.status,
+        criteria_met: run.criteria_met(),
+        criteria_total: run.criteria.len(),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(max_iterations: u32) -> StartGoalRequest {
+        StartGoalRequest {
+            goal: "Make the test suite pass".to_string(),
+            criteria: vec!["cargo test passes".to_string(), "no new warnings".to_string()],
+            max_iterations: Some(max_iterations),
+            session_id: Some("s1".to_string()),
+        }
+    }
+
+    fn report(output: &str) -> PhaseReport {
+        PhaseReport {
+            output: output.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_iterates_until_criteria_met() {
+        let mut run = GoalRun::new(request(3)).unwrap();
+        assert!(run.current_prompt.as_deref().unwrap().contains("Iteration 1 of 3"));
+
+        run.record(report("Fix the parser test")).unwrap();
+        assert_eq!(run.phase, GoalPhase::Act);
+        assert!(run.current_prompt.as_deref().unwrap().contains("Fix the parser test"));
+        run.record(report("Edited parser.rs")).unwrap();
+
+        // Malformed or incomplete verdicts are rejected without changing the run
+        assert!(run.record(report("looks good")).is_err());
+        assert!(run.record(report(r#"{"criteria": [{"met": true}]}"#)).is_err());
+        assert_eq!(run.phase, GoalPhase::Evaluate);
+
+        run.record(report(
+            "```json\n{\"criteria\": [{\"met\": true}, {\"met\": false, \"notes\": \"2 warnings\"}], \"summary\": \"tests pass\"}\n```",
+        ))
+        .unwrap();
+        assert_eq!((run.status, run.phase, run.iteration()), (GoalStatus::Running, GoalPhase::Plan, 2));
+        assert_eq!(run.criteria_met(), 1);
+        let prompt = run.current_prompt.as_deref().unwrap();
+        assert!(prompt.contains("- no new warnings (2 warnings)") && !prompt.contains("- cargo test passes"));
+
+        run.record(report("Fix warnings")).unwrap();
+        assert!(run
+            .record(PhaseReport { phase: Some(GoalPhase::Evaluate), ..Default::default() })
+            .is_err());
+        run.record(report("Removed unused imports")).unwrap();
+        run.record(PhaseReport {
+            criteria: Some(vec![CriterionResult { met: true, notes: None }; 2]),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(run.status, GoalStatus::Succeeded);
+        assert!(run.current_prompt.is_none() && run.completed_at.is_some());
+        assert!(run.record(report("more")).is_err());
+    }
+
+    #[test]
+    fn test_budget_and_store() {
+        assert!(GoalRun::new(request(0)).is_err());
+        assert!(GoalRun::new(StartGoalRequest { criteria: vec![" ".to_string()], ..request(1) }).is_err());
+
+        let dir = tempfile::tempdir().unwrap();
+        let store = GoalStore::new(dir.path().to_path_buf());
+        let run = store.start(request(1)).unwrap();
+        store.report(&run.id, report("plan")).unwrap();
+        store.report(&run.id, report("act")).unwrap();
+        let unmet = r#"{"criteria": [{"met": false}, {"met": true}]}"#;
+        let finished = store.report(&run.id, report(unmet)).unwrap();
+        assert_eq!(finished.status, GoalStatus::Exhausted);
+        assert!(matches!(store.cancel(&run.id), Err(GoalError::Invalid(_))));
+
+        // Runs are reloaded from disk
+        let reopened = GoalStore::new(dir.path().to_path_buf());
+        assert_eq!(reopened.list(Some("s1"))[0].status, GoalStatus::Exhausted);
+        assert!(reopened.list(Some("other")).is_empty());
+        assert!(reopened.delete(&run.id));
+        assert!(matches!(reopened.report(&run.id, report("x")), Err(GoalError::NotFound(_))));
+    }
+}
@@ -0,0 +1,267 @@
+//! HTTP requests for the agent
+//!
+//! The `http_request` tool lets the agent call web APIs without a bespoke tool
+//! per integration. Only hosts on the session's domain allowlist can be
+//! reached, every URL goes through the SSRF validator, redirects are reported
+//! rather than followed, and response bodies are capped.
+
+use futures::StreamExt;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::time::Duration;
+use url::Url;
+
+use crate::content_extraction::SsrfValidator;
+
+/// Response bytes kept; the rest of the body is dropped
+pub const MAX_RESPONSE_BYTES: usize = 256 * 1024;
+
+/// Time allowed for the whole request, body included
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Headers never shown to the agent
+const HIDDEN_HEADERS: &[&str] = &["set-cookie"];
+
+#[derive(Debug, thiserror::Error)]
+pub enum HttpRequestError {
+    #[error("{0}")]
+    Invalid(String),
+
+    #[error("{0}")]
+    NotAllowed(String),
+
+    #[error("{0}")]
+    Failed(String),
+}
+
+/// Arguments of the `http_request` tool
+#[derive(Debug, Clone, Deserialize)]
+pub struct HttpRequest {
+    pub url: String,
+    /// GET or POST (default: GET)
+    #[serde(default)]
+    pub method: Option<String>,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// Sent as-is when a string, as JSON otherwise
+    #[serde(default)]
+    pub body: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub reason: Option<String>,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+    /// Bytes received before the body was cut off, when it was
+    pub truncated_at: Option<usize>,
+}
+
+impl HttpResponse {
+    /// Status line, headers and body as shown to the agent
+    pub fn render(&self) -> String {
+        let mut output = format!("HTTP {}", self.status);
+        if let Some(reason) = &self.reason {
+            output.push(' ');
+            output.push_str(reason);
+        }
+        output.push('\n');
+        for (name, value) in &self.headers {
+            output.push_str(&format!("{}: {}\n", name, value));
+        }
+        output.push('\n');
+        output.push_str(&self.body);
+        if let Some(bytes) = self.truncated_at {
+            output.push_str(&format!("\n... [response truncated at {} bytes]", bytes));
+        }
+        output
+    }
+}
+
+/// Whether `host` is an allowed domain or a subdomain of one
+pub fn domain_allowed(host: &str, allowlist: &[String]) -> bool {
+    let host = host.trim_end_matches('.').to_ascii_lowercase();
+    allowlist.iter().any(|entry| {
+        let domain = entry.trim().trim_start_matches("*.").trim_end_matches('.').to_ascii_lowercase();
+        !domain.is_empty() && (host == domain || host.ends_with(&format!(".{}", domain)))
+    })
+}
+
+/// Pretty-print JSON bodies; other text is returned as-is
+pub fn format_body(bytes: &[u8], content_type: Option<&str>) -> String {
+    let looks_like_json = content_type.is_some_and(|ct| ct.contains("json"))
+        || matches!(bytes.iter().find(|b| !b.is_ascii_whitespace()), Some(b'{') | Some(b'['));
+    if looks_like_json {
+        if let Ok(value) = serde_json::from_slice::<serde_json::Value>(bytes) {
+            if let Ok(pretty) = serde_json::to_string_pretty(&value) {
+                return pretty;
+            }
+        }
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(text) => text.to_string(),
+        Err(e) if e.error_len().is_none() => {
+            // A multi-byte character cut off by truncation
+            String::from_utf8_lossy(&bytes[..e.valid_up_to()]).to_string()
+        }
+        Err(_) => format!(
+            "[{} bytes of binary content{}]",
+            bytes.len(),
+            content_type.map(|ct| format!(" ({})", ct)).unwrap_or_default()
+        ),
+    }
+}
+
+/// Check the request against the allowlist and the SSRF rules, then send it
+pub async fn send(request: &HttpRequest, allowlist: &[String]) -> Result<HttpResponse, HttpRequestError> {
+    let method = match request.method.as_deref().map(str::to_ascii_uppercase).as_deref() {
+        None | Some("GET") => reqwest::Method::GET,
+        Some("POST") => reqwest::Method::POST,
+        Some(other) => {
+            return Err(HttpRequestError::Invalid(format!(
+                "Unsupported method '{}': use GET or POST",
+                other
+            )))
+        }
+    };
+
+    let url = Url::parse(&request.url)
+        .map_err(|e| HttpRequestError::Invalid(format!("Invalid URL '{}': {}", request.url, e)))?;
+    let host = url
+        .host_str()
+        .ok_or_else(|| HttpRequestError::Invalid(format!("URL has no host: {}", request.url)))?;
+    if !domain_allowed(host, allowlist) {
+        return Err(HttpRequestError::NotAllowed(format!(
+            "{} is not on this session's allowed domains",
+            host
+        )));
+    }
+    SsrfValidator::validate_url(&url)
+        .await
+        .map_err(|e| HttpRequestError::NotAllowed(e.to_string()))?;
+
+    let client = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .timeout(REQUEST_TIMEOUT)
+        .user_agent(concat!("Skhoot/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .map_err(|e| HttpRequestError::Failed(e.to_string()))?;
+
+    let mut builder = client.request(method, url);
+    for (name, value) in &request.headers {
+        builder = builder.header(name.as_str(), value.as_str());
+    }
+    builder = match &request.body {
+        None | Some(serde_json::Value::Null) => builder,
+        Some(serde_json::Value::String(text)) => builder.body(text.clone()),
+        Some(value) => {
+            let has_content_type = request.headers.keys().any(|name| name.eq_ignore_ascii_case("content-type"));
+            let builder = builder.body(value.to_string());
+            if has_content_type {
+                builder
+            } else {
+                builder.header(reqwest::header::CONTENT_TYPE, "application/json")
+            }
+        }
+    };
+
+    let response = builder
+        .send()
+        .await
+        .map_err(|e| HttpRequestError::Failed(e.to_string()))?;
+
+    let status = response.status();
+    let headers: Vec<(String, String)> = response
+        .headers()
+        .iter()
+        .filter(|(name, _)| !HIDDEN_HEADERS.contains(&name.as_str()))
+        .map(|(name, value)| (name.to_string(), String::from_utf8_lossy(value.as_bytes()).to_string()))
+        .collect();
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+
+    let mut bytes = Vec::new();
+    let mut truncated_at = None;
+    let mut stream = response.bytes_stream();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|e| HttpRequestError::Failed(format!("Failed to read response: {}", e)))?;
+        let room = MAX_RESPONSE_BYTES - bytes.len();
+        if chunk.len() > room {
+            bytes.extend_from_slice(&chunk[..room]);
+            truncated_at = Some(MAX_RESPONSE_BYTES);
+            break;
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+
+    Ok(HttpResponse {
+        status: status.as_u16(),
+        reason: status.canonical_reason().map(str::to_string),
+        headers,
+        body: format_body(&bytes, content_type.as_deref()),
+        truncated_at,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_domain_allowed() {
+        let allowlist = vec!["api.github.com".to_string(), "*.example.org".to_string()];
+        assert!(domain_allowed("api.github.com", &allowlist));
+        assert!(domain_allowed("API.GitHub.com.", &allowlist));
+        assert!(domain_allowed("uploads.api.github.com", &allowlist));
+        assert!(!domain_allowed("github.com", &allowlist));
+        assert!(!domain_allowed("evilapi.github.com", &allowlist));
+        assert!(domain_allowed("www.example.org", &allowlist));
+        assert!(!domain_allowed("example.com", &allowlist));
+        assert!(!domain_allowed("api.github.com", &[]));
+    }
+
+    #[test]
+    fn test_format_body() {
+        assert_eq!(
+            format_body(br#"{"ok":true,"items":[1]}"#, Some("application/json; charset=utf-8")),
+            "{\n  \"items\": [\n    1\n  ],\n  \"ok\": true\n}"
+        );
+        assert_eq!(format_body(b"  [1]", None), "[\n  1\n]");
+        // Truncated JSON is shown as text
+        assert_eq!(format_body(br#"{"ok":tr"#, Some("application/json")), r#"{"ok":tr"#);
+        assert_eq!(format_body("caf\u{e9}".as_bytes(), Some("text/plain")), "caf\u{e9}");
+        assert_eq!(format_body(&"caf\u{e9}".as_bytes()[..4], None), "caf");
+        assert_eq!(
+            format_body(&[0x89, b'P', b'N', b'G', 0, 0, 0xff, 0xfe, 0, 1], Some("image/png")),
+            "[10 bytes of binary content (image/png)]"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_send_checks_allowlist_and_ssrf() {
+        let request = |url: &str| HttpRequest {
+            url: url.to_string(),
+            method: None,
+            headers: BTreeMap::new(),
+            body: None,
+        };
+        let allowlist = vec!["localhost".to_string(), "127.0.0.1".to_string()];
+
+        let err = send(&request("https://example.com/"), &allowlist).await.unwrap_err();
+        assert!(matches!(err, HttpRequestError::NotAllowed(_)), "{}", err);
+        // Allowed by the user but still blocked as a private address
+        let err = send(&request("http://127.0.0.1:8080/"), &allowlist).await.unwrap_err();
+        assert!(matches!(err, HttpRequestError::NotAllowed(_)), "{}", err);
+        let err = send(&request("not a url"), &allowlist).await.unwrap_err();
+        assert!(matches!(err, HttpRequestError::Invalid(_)), "{}", err);
+
+        let mut delete = request("https://example.com/");
+        delete.method = Some("delete".to_string());
+        let err = send(&delete, &["example.com".to_string()]).await.unwrap_err();
+        assert!(err.to_string().contains("Unsupported method"));
+    }
+}
@@ -4,6 +4,8 @@
 
 use serde::{Deserialize, Serialize};
 
+use super::prompt_templates::PromptTemplate;
+
 /// System prompt configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SystemPrompt {
@@ -15,6 +17,12 @@ pub struct SystemPrompt {
     pub safety_rules: String,
     /// Output formatting instructions
     pub output_format: String,
+    /// User-defined behavior, constraints and negative prompts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<PromptTemplate>,
+    /// Summary of the detected project
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
 }
 
 impl SystemPrompt {
@@ -25,15 +33,36 @@ impl SystemPrompt {
             tool_guidelines: TOOL_GUIDELINES.to_string(),
             safety_rules: SAFETY_RULES.to_string(),
             output_format: OUTPUT_FORMAT.to_string(),
+            template: None,
+            project: None,
         }
     }
 
+    /// Add user-defined sections from a prompt template
+    pub fn with_template(mut self, template: PromptTemplate) -> Self {
+        self.template = if template.is_empty() { None } else { Some(template) };
+        self
+    }
+
+    /// Describe the project the session works in
+    pub fn with_project(mut self, summary: Option<String>) -> Self {
+        self.project = summary;
+        self
+    }
+
     /// Build the complete system prompt
     pub fn build(&self) -> String {
-        format!(
+        let mut prompt = format!(
             "{}\n\n{}\n\n{}\n\n{}",
             self.base, self.tool_guidelines, self.safety_rules, self.output_format
-        )
+        );
+        if let Some(template) = &self.template {
+            prompt = format!("{}\n\n{}", prompt, template.render());
+        }
+        if let Some(project) = &self.project {
+            prompt = format!("{}\n\n{}", prompt, project);
+        }
+        prompt
     }
 
     /// Build with custom working directory context
@@ -173,4 +202,32 @@ mod tests {
         assert!(built.contains("/home/user/project"));
         assert!(built.contains("Linux x86_64"));
     }
+
+    #[test]
+    fn test_system_prompt_with_template() {
+        let template = PromptTemplate {
+            constraints: vec!["Reply in English".to_string()],
+            negative_prompts: vec!["Never delete files".to_string()],
+            ..Default::default()
+        };
+        let built = SystemPrompt::default_skhoot().with_template(template).build();
+
+        assert!(built.contains("- Reply in English"));
+        assert!(built.ends_with("- Never delete files"));
+    }
+
+    #[test]
+    fn test_system_prompt_with_project() {
+        let template = PromptTemplate {
+            constraints: vec!["Reply in English".to_string()],
+            ..Default::default()
+        };
+        let built = SystemPrompt::default_skhoot()
+            .with_project(Some("## Project\n- Type: Rust".to_string()))
+            .with_template(template)
+            .build();
+
+        assert!(built.contains("- Reply in English\n\n## Project"));
+        assert!(built.ends_with("- Type: Rust"));
+    }
 }
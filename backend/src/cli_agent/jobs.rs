@@ -0,0 +1,350 @@
+//! Background jobs for long-running shell commands
+//!
+//! Builds, test suites and dev servers outlast a tool call's timeout. The
+//! shell tool can start such a command as a job instead: the call returns a
+//! job ID right away and the agent checks on the job with the job_status,
+//! job_output and job_cancel tools. Jobs belong to the agent session that
+//! started them and keep running across its turns.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use crate::cli_bridge::{CliBridge, CliError};
+
+/// Jobs one session may have running at once
+pub const MAX_RUNNING_JOBS: usize = 8;
+
+/// Finished jobs kept per session; older ones are dropped with their output
+pub const MAX_FINISHED_JOBS: usize = 20;
+
+/// Output lines returned when the agent doesn't ask for a number
+pub const DEFAULT_OUTPUT_LINES: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    Running,
+    Succeeded,
+    Failed,
+    Cancelled,
+}
+
+impl JobState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobState::Running => "running",
+            JobState::Succeeded => "succeeded",
+            JobState::Failed => "failed",
+            JobState::Cancelled => "cancelled",
+        }
+    }
+}
+
+/// What the agent sees of a job
+#[derive(Debug, Clone, Serialize)]
+pub struct JobInfo {
+    pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    pub command: String,
+    pub working_directory: String,
+    pub state: JobState,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    /// Set when the job was killed for exceeding a resource limit
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub started_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+/// Tail of a job's combined stdout and stderr
+#[derive(Debug, Clone, Serialize)]
+pub struct JobOutput {
+    pub job: JobInfo,
+    pub lines: Vec<String>,
+    pub total_lines: usize,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum JobError {
+    #[error("Job not found: {0}")]
+    NotFound(String),
+
+    #[error("Session already has {0} running jobs; wait for one or cancel it")]
+    TooManyJobs(usize),
+
+    #[error(transparent)]
+    Cli(#[from] CliError),
+}
+
+struct Job {
+    info: JobInfo,
+    /// CLI bridge session running the command
+    bridge_session: String,
+    /// Output captured before a cancelled job's process was released
+    final_output: Option<Vec<String>>,
+}
+
+/// Jobs of all agent sessions
+pub struct JobManager {
+    bridge: CliBridge,
+    jobs: RwLock<HashMap<String, Job>>,
+}
+
+lazy_static::lazy_static! {
+    static ref GLOBAL_JOBS: Arc<JobManager> = Arc::new(JobManager::new());
+}
+
+impl JobManager {
+    pub fn new() -> Self {
+        Self {
+            bridge: CliBridge::new(),
+            jobs: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Shared manager, so jobs outlive the executor that started them
+    pub fn global() -> Arc<JobManager> {
+        GLOBAL_JOBS.clone()
+    }
+
+    /// Start `program args` as a job of `session_id`; `command` is the
+    /// command line as the agent wrote it
+    pub async fn start(
+        &self,
+        session_id: Option<String>,
+        command: &str,
+        program: String,
+        args: Vec<String>,
+        cwd: PathBuf,
+        env: Vec<(String, String)>,
+    ) -> Result<JobInfo, JobError> {
+        let mut jobs = self.jobs.write().await;
+        for job in jobs.values_mut() {
+            self.refresh(job).await;
+        }
+        let running = jobs
+            .values()
+            .filter(|job| job.info.session_id == session_id && job.info.state == JobState::Running)
+            .count();
+        if running >= MAX_RUNNING_JOBS {
+            return Err(JobError::TooManyJobs(running));
+        }
+
+        let handle = self
+            .bridge
+            .execute_command_with_env(program, args, Some(cwd.clone()), env)
+            .await?;
+        let info = JobInfo {
+            id: uuid::Uuid::new_v4().to_string(),
+            session_id,
+            command: command.to_string(),
+            working_directory: cwd.to_string_lossy().to_string(),
+            state: JobState::Running,
+            exit_code: None,
+            error: None,
+            started_at: Utc::now(),
+            finished_at: None,
+        };
+        jobs.insert(
+            info.id.clone(),
+            Job {
+                info: info.clone(),
+                bridge_session: handle.session_id,
+                final_output: None,
+            },
+        );
+        self.prune(&mut jobs, info.session_id.as_deref()).await;
+        Ok(info)
+    }
+
+    /// Current state of a job
+    pub async fn status(&self, session_id: Option<&str>, job_id: &str) -> Result<JobInfo, JobError> {
+        let mut jobs = self.jobs.write().await;
+        let job = Self::find(&mut jobs, session_id, job_id)?;
+        self.refresh(job).await;
+        Ok(job.info.clone())
+    }
+
+    /// Jobs of a session, oldest first
+    pub async fn list(&self, session_id: Option<&str>) -> Vec<JobInfo> {
+        let mut jobs = self.jobs.write().await;
+        let mut infos = Vec::new();
+        for job in jobs.values_mut().filter(|job| job.info.session_id.as_deref() == session_id) {
+            self.refresh(job).await;
+            infos.push(job.info.clone());
+        }
+        infos.sort_by_key(|info| info.started_at);
+        infos
+    }
+
+    /// The last `lines` lines of a job's output
+    pub async fn output(&self, session_id: Option<&str>, job_id: &str, lines: usize) -> Result<JobOutput, JobError> {
+        let mut jobs = self.jobs.write().await;
+        let job = Self::find(&mut jobs, session_id, job_id)?;
+        self.refresh(job).await;
+
+        let all = match &job.final_output {
+            Some(output) => output.clone(),
+            None => self.read_lines(&job.bridge_session).await?,
+        };
+        let total_lines = all.len();
+        Ok(JobOutput {
+            job: job.info.clone(),
+            lines: all[total_lines.saturating_sub(lines)..].to_vec(),
+            total_lines,
+        })
+    }
+
+    /// Stop a running job, keeping the output it produced; finished jobs
+    /// are left as they are
+    pub async fn cancel(&self, session_id: Option<&str>, job_id: &str) -> Result<JobInfo, JobError> {
+        let mut jobs = self.jobs.write().await;
+        let job = Self::find(&mut jobs, session_id, job_id)?;
+        self.refresh(job).await;
+        if job.info.state == JobState::Running {
+            job.final_output = Some(self.read_lines(&job.bridge_session).await?);
+            self.bridge.terminate_session(job.bridge_session.clone()).await?;
+            job.info.state = JobState::Cancelled;
+            job.info.finished_at = Some(Utc::now());
+        }
+        Ok(job.info.clone())
+    }
+
+    fn find<'a>(
+        jobs: &'a mut HashMap<String, Job>,
+        session_id: Option<&str>,
+        job_id: &str,
+    ) -> Result<&'a mut Job, JobError> {
+        // Other sessions' jobs are reported as missing
+        jobs.get_mut(job_id)
+            .filter(|job| job.info.session_id.as_deref() == session_id)
+            .ok_or_else(|| JobError::NotFound(job_id.to_string()))
+    }
+
+    /// Record the exit of a job whose process has finished
+    async fn refresh(&self, job: &mut Job) {
+        if job.info.state != JobState::Running {
+            return;
+        }
+        let Ok(Some(status)) = self.bridge.try_wait(&job.bridge_session).await else {
+            return;
+        };
+        job.info.exit_code = status.code();
+        job.info.state = if status.success() { JobState::Succeeded } else { JobState::Failed };
+        job.info.finished_at = Some(Utc::now());
+        if let Err(CliError::ResourceLimitExceeded(reason)) = self.bridge.check_resource_limits(&job.bridge_session).await {
+            job.info.error = Some(reason);
+        }
+        if job.info.state == JobState::Failed {
+            crate::events::publish(crate::events::Event::CommandFailed {
+                session_id: job.info.session_id.clone(),
+                command: job.info.command.clone(),
+                exit_code: job.info.exit_code,
+            });
+        }
+    }
+
+    /// Stdout and stderr lines in the order they were produced
+    async fn read_lines(&self, bridge_session: &str) -> Result<Vec<String>, CliError> {
+        let mut output = self.bridge.read_output(bridge_session.to_string()).await?;
+        output.sort_by_key(|line| line.timestamp);
+        Ok(output.into_iter().map(|line| line.content).collect())
+    }
+
+    /// Drop the oldest finished jobs of a session past [`MAX_FINISHED_JOBS`]
+    async fn prune(&self, jobs: &mut HashMap<String, Job>, session_id: Option<&str>) {
+        let mut finished: Vec<(DateTime<Utc>, String)> = jobs
+            .values()
+            .filter(|job| job.info.session_id.as_deref() == session_id && job.info.state != JobState::Running)
+            .map(|job| (job.info.started_at, job.info.id.clone()))
+            .collect();
+        if finished.len() <= MAX_FINISHED_JOBS {
+            return;
+        }
+        finished.sort();
+        for (_, id) in &finished[..finished.len() - MAX_FINISHED_JOBS] {
+            if let Some(job) = jobs.remove(id) {
+                // Cancelled jobs already released their bridge session
+                let _ = self.bridge.terminate_session(job.bridge_session).await;
+            }
+        }
+    }
+}
+
+impl Default for JobManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    async fn start(manager: &JobManager, session: &str, command: &str) -> JobInfo {
+        manager
+            .start(
+                Some(session.to_string()),
+                command,
+                "/bin/sh".to_string(),
+                vec!["-c".to_string(), command.to_string()],
+                std::env::temp_dir(),
+                Vec::new(),
+            )
+            .await
+            .unwrap()
+    }
+
+    async fn wait_until_finished(manager: &JobManager, session: &str, id: &str) -> JobInfo {
+        for _ in 0..100 {
+            let info = manager.status(Some(session), id).await.unwrap();
+            if info.state != JobState::Running {
+                return info;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        panic!("job {} did not finish", id);
+    }
+
+    #[tokio::test]
+    async fn test_job_runs_in_background() {
+        let manager = JobManager::new();
+        let job = start(&manager, "s1", "echo building; sleep 0.3; echo done; exit 3").await;
+        assert_eq!(job.state, JobState::Running);
+
+        let finished = wait_until_finished(&manager, "s1", &job.id).await;
+        assert_eq!(finished.state, JobState::Failed);
+        assert_eq!(finished.exit_code, Some(3));
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let output = manager.output(Some("s1"), &job.id, 1).await.unwrap();
+        assert_eq!(output.total_lines, 2);
+        assert_eq!(output.lines, ["done"]);
+
+        // Jobs are private to the session that started them
+        assert!(matches!(manager.status(Some("s2"), &job.id).await, Err(JobError::NotFound(_))));
+        assert!(manager.list(Some("s2")).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_cancel_keeps_output() {
+        let manager = JobManager::new();
+        let job = start(&manager, "s1", "echo started; sleep 30").await;
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        let cancelled = manager.cancel(Some("s1"), &job.id).await.unwrap();
+        assert_eq!(cancelled.state, JobState::Cancelled);
+
+        let output = manager.output(Some("s1"), &job.id, DEFAULT_OUTPUT_LINES).await.unwrap();
+        assert_eq!(output.lines, ["started"]);
+        assert_eq!(manager.cancel(Some("s1"), &job.id).await.unwrap().state, JobState::Cancelled);
+    }
+}
@@ -0,0 +1,560 @@
+//! Linters and formatters for the agent's `lint` and `format` tools
+//!
+//! Picks the tools a project uses (clippy and rustfmt for Rust, eslint and
+//! prettier for Node when configured, ruff and black for Python), runs them
+//! with machine-readable output and turns the result into diagnostics with
+//! file, line, severity and message. Applying fixes is left to the executor,
+//! which checks `allow_writes` and checkpoints the files first.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use super::executor::ExecutorError;
+use super::project::{node_package_manager, python_runner, read_toml, ProjectKind};
+use super::test_runner::run_command;
+
+/// Time allowed for one linter or formatter run
+pub const DEFAULT_TIMEOUT_MS: u64 = 5 * 60 * 1000;
+
+/// Diagnostics reported per linter; the rest are only counted
+const MAX_DIAGNOSTICS: usize = 200;
+
+/// Lines of output kept when a tool's output can't be read
+const ERROR_TAIL_LINES: usize = 20;
+
+const ESLINT_CONFIGS: &[&str] = &[
+    "eslint.config.js", "eslint.config.mjs", "eslint.config.cjs", "eslint.config.ts",
+    ".eslintrc", ".eslintrc.js", ".eslintrc.cjs", ".eslintrc.json", ".eslintrc.yml", ".eslintrc.yaml",
+];
+
+const PRETTIER_CONFIGS: &[&str] = &[
+    ".prettierrc", ".prettierrc.json", ".prettierrc.js", ".prettierrc.cjs", ".prettierrc.mjs",
+    ".prettierrc.yml", ".prettierrc.yaml", "prettier.config.js", "prettier.config.cjs", "prettier.config.mjs",
+];
+
+lazy_static::lazy_static! {
+    static ref RUSTFMT_DIFF: Regex = Regex::new(r"^Diff in (.+?)(?: at line (\d+)|:(\d+)):").unwrap();
+    static ref BLACK_REFORMAT: Regex = Regex::new(r"^would reformat (.+)$").unwrap();
+    static ref RUFF_REFORMAT: Regex = Regex::new(r"^Would reformat: (.+)$").unwrap();
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Error,
+    Warning,
+    Info,
+}
+
+/// A problem a linter reported
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Diagnostic {
+    /// Relative to the project root when inside it
+    pub file: String,
+    pub line: u32,
+    pub column: u32,
+    pub severity: Severity,
+    pub message: String,
+    /// Lint or rule name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Linter {
+    Clippy,
+    Eslint,
+    Ruff,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Formatter {
+    Rustfmt,
+    Prettier,
+    Black,
+    Ruff,
+}
+
+/// One linter's run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinterRun {
+    pub linter: Linter,
+    pub command: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    pub errors: usize,
+    pub warnings: usize,
+    pub diagnostics: Vec<Diagnostic>,
+    /// Whether diagnostics past the first ones were left out
+    #[serde(default)]
+    pub truncated: bool,
+    /// End of the output when it couldn't be read as diagnostics
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// A file a formatter would change
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UnformattedFile {
+    pub file: String,
+    /// First line that differs, when the formatter says
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line: Option<u32>,
+}
+
+/// One formatter's check, and its rewrite when applied
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FormatterRun {
+    pub formatter: Formatter,
+    pub command: String,
+    pub files: Vec<UnformattedFile>,
+    /// Whether the files were rewritten
+    pub applied: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+fn package_json(root: &Path) -> Option<serde_json::Value> {
+    serde_json::from_str(&std::fs::read_to_string(root.join("package.json")).ok()?).ok()
+}
+
+/// Whether a Node project configures or depends on `tool`
+fn node_uses(root: &Path, tool: &str, configs: &[&str]) -> bool {
+    if configs.iter().any(|config| root.join(config).exists()) {
+        return true;
+    }
+    let Some(package) = package_json(root) else {
+        return false;
+    };
+    package.get(format!("{}Config", tool)).is_some()
+        || ["dependencies", "devDependencies"]
+            .iter()
+            .any(|section| package.get(section).and_then(|deps| deps.get(tool)).is_some())
+}
+
+fn python_tool_configured(root: &Path, tool: &str) -> bool {
+    read_toml(&root.join("pyproject.toml")).is_some_and(|p| p.get("tool").and_then(|t| t.get(tool)).is_some())
+        || root.join(format!("{}.toml", tool)).exists()
+}
+
+/// Command that runs a Node project's local binary
+fn node_exec(root: &Path, binary: &str) -> String {
+    match node_package_manager(root) {
+        "pnpm" => format!("pnpm exec {}", binary),
+        "yarn" => format!("yarn {}", binary),
+        "bun" => format!("bunx {}", binary),
+        _ => format!("npx --no-install {}", binary),
+    }
+}
+
+/// Path relative to `root` when it's inside it
+fn relative(root: &Path, file: &str) -> String {
+    let path = Path::new(file);
+    let path = if path.is_absolute() { path.to_path_buf() } else { root.join(path) };
+    path.strip_prefix(root)
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_else(|_| file.to_string())
+}
+
+fn tail(text: &str) -> Option<String> {
+    let lines: Vec<&str> = text.trim_end().lines().collect();
+    let tail = lines[lines.len().saturating_sub(ERROR_TAIL_LINES)..].join("\n");
+    Some(tail).filter(|tail| !tail.trim().is_empty())
+}
+
+impl Linter {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "clippy" => Some(Self::Clippy),
+            "eslint" => Some(Self::Eslint),
+            "ruff" => Some(Self::Ruff),
+            _ => None,
+        }
+    }
+
+    /// Linters for a project's languages
+    pub fn detect(root: &Path, kinds: &[ProjectKind]) -> Vec<Self> {
+        kinds
+            .iter()
+            .filter_map(|kind| match kind {
+                ProjectKind::Rust => Some(Self::Clippy),
+                ProjectKind::Node => node_uses(root, "eslint", ESLINT_CONFIGS).then_some(Self::Eslint),
+                ProjectKind::Python => Some(Self::Ruff),
+            })
+            .collect()
+    }
+
+    pub fn command(&self, root: &Path, fix: bool) -> String {
+        match (self, fix) {
+            (Self::Clippy, false) => "cargo clippy --workspace --all-targets --message-format=json".to_string(),
+            (Self::Clippy, true) => {
+                "cargo clippy --workspace --all-targets --message-format=json --fix --allow-dirty --allow-staged"
+                    .to_string()
+            }
+            (Self::Eslint, fix) => {
+                format!("{} . --format json{}", node_exec(root, "eslint"), if fix { " --fix" } else { "" })
+            }
+            (Self::Ruff, fix) => format!(
+                "{}ruff check . --output-format=json{}",
+                python_runner(root),
+                if fix { " --fix" } else { "" }
+            ),
+        }
+    }
+
+    /// Diagnostics in the tool's JSON output, or `None` if there is none
+    pub fn parse_output(&self, stdout: &str, root: &Path) -> Option<Vec<Diagnostic>> {
+        match self {
+            Self::Clippy => parse_cargo_messages(stdout, root),
+            Self::Eslint => parse_eslint(stdout, root),
+            Self::Ruff => parse_ruff(stdout, root),
+        }
+    }
+}
+
+impl Formatter {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "rustfmt" => Some(Self::Rustfmt),
+            "prettier" => Some(Self::Prettier),
+            "black" => Some(Self::Black),
+            "ruff" => Some(Self::Ruff),
+            _ => None,
+        }
+    }
+
+    /// Formatters for a project's languages
+    pub fn detect(root: &Path, kinds: &[ProjectKind]) -> Vec<Self> {
+        kinds
+            .iter()
+            .filter_map(|kind| match kind {
+                ProjectKind::Rust => Some(Self::Rustfmt),
+                ProjectKind::Node => node_uses(root, "prettier", PRETTIER_CONFIGS).then_some(Self::Prettier),
+                ProjectKind::Python if python_tool_configured(root, "black") => Some(Self::Black),
+                ProjectKind::Python => Some(Self::Ruff),
+            })
+            .collect()
+    }
+
+    /// Command listing the files that aren't formatted
+    pub fn check_command(&self, root: &Path) -> String {
+        match self {
+            Self::Rustfmt => "cargo fmt --all -- --check".to_string(),
+            Self::Prettier => format!("{} --list-different .", node_exec(root, "prettier")),
+            Self::Black => format!("{}black --check .", python_runner(root)),
+            Self::Ruff => format!("{}ruff format --check .", python_runner(root)),
+        }
+    }
+
+    /// Command rewriting the files
+    pub fn apply_command(&self, root: &Path) -> String {
+        match self {
+            Self::Rustfmt => "cargo fmt --all".to_string(),
+            Self::Prettier => format!("{} --write .", node_exec(root, "prettier")),
+            Self::Black => format!("{}black .", python_runner(root)),
+            Self::Ruff => format!("{}ruff format .", python_runner(root)),
+        }
+    }
+
+    /// Files the check output names
+    pub fn parse_check(&self, output: &str, root: &Path) -> Vec<UnformattedFile> {
+        let mut files: Vec<UnformattedFile> = Vec::new();
+        for line in output.lines().map(str::trim_end) {
+            let found = match self {
+                Self::Rustfmt => RUSTFMT_DIFF.captures(line).map(|captures| {
+                    let line = captures.get(2).or(captures.get(3)).and_then(|m| m.as_str().parse().ok());
+                    (captures[1].to_string(), line)
+                }),
+                Self::Prettier => (!line.is_empty() && !line.starts_with('[')).then(|| (line.to_string(), None)),
+                Self::Black => BLACK_REFORMAT.captures(line).map(|captures| (captures[1].to_string(), None)),
+                Self::Ruff => RUFF_REFORMAT.captures(line).map(|captures| (captures[1].to_string(), None)),
+            };
+            if let Some((file, line)) = found {
+                let file = relative(root, &file);
+                if !files.iter().any(|existing| existing.file == file) {
+                    files.push(UnformattedFile { file, line });
+                }
+            }
+        }
+        files
+    }
+}
+
+/// `--message-format=json` lines from cargo; primary spans only, each
+/// diagnostic once even when several targets report it
+fn parse_cargo_messages(stdout: &str, root: &Path) -> Option<Vec<Diagnostic>> {
+    let mut seen_json = false;
+    let mut seen = HashSet::new();
+    let mut diagnostics = Vec::new();
+    for line in stdout.lines() {
+        let Ok(value) = serde_json::from_str::<serde_json::Value>(line) else {
+            continue;
+        };
+        seen_json = true;
+        if value.get("reason").and_then(|r| r.as_str()) != Some("compiler-message") {
+            continue;
+        }
+        let Some(message) = value.get("message") else {
+            continue;
+        };
+        let severity = match message.get("level").and_then(|l| l.as_str()) {
+            Some("error") | Some("error: internal compiler error") => Severity::Error,
+            Some("warning") => Severity::Warning,
+            _ => Severity::Info,
+        };
+        let Some(span) = message
+            .get("spans")
+            .and_then(|s| s.as_array())
+            .and_then(|spans| spans.iter().find(|span| span.get("is_primary").and_then(|p| p.as_bool()) == Some(true)))
+        else {
+            // Summaries such as "3 warnings emitted"
+            continue;
+        };
+        let diagnostic = Diagnostic {
+            file: relative(root, span.get("file_name").and_then(|f| f.as_str()).unwrap_or_default()),
+            line: span.get("line_start").and_then(|l| l.as_u64()).unwrap_or(0) as u32,
+            column: span.get("column_start").and_then(|c| c.as_u64()).unwrap_or(0) as u32,
+            severity,
+            message: message.get("message").and_then(|m| m.as_str()).unwrap_or_default().to_string(),
+            code: message.get("code").and_then(|c| c.get("code")).and_then(|c| c.as_str()).map(str::to_string),
+        };
+        if seen.insert((diagnostic.file.clone(), diagnostic.line, diagnostic.column, diagnostic.message.clone())) {
+            diagnostics.push(diagnostic);
+        }
+    }
+    seen_json.then_some(diagnostics)
+}
+
+fn parse_eslint(stdout: &str, root: &Path) -> Option<Vec<Diagnostic>> {
+    let files: Vec<serde_json::Value> = serde_json::from_str(stdout.trim()).ok()?;
+    let mut diagnostics = Vec::new();
+    for file in &files {
+        let path = relative(root, file.get("filePath").and_then(|p| p.as_str()).unwrap_or_default());
+        for message in file.get("messages").and_then(|m| m.as_array()).into_iter().flatten() {
+            diagnostics.push(Diagnostic {
+                file: path.clone(),
+                line: message.get("line").and_then(|l| l.as_u64()).unwrap_or(0) as u32,
+                column: message.get("column").and_then(|c| c.as_u64()).unwrap_or(0) as u32,
+                severity: match message.get("severity").and_then(|s| s.as_u64()) {
+                    Some(2) => Severity::Error,
+                    Some(1) => Severity::Warning,
+                    _ => Severity::Info,
+                },
+                message: message.get("message").and_then(|m| m.as_str()).unwrap_or_default().to_string(),
+                code: message.get("ruleId").and_then(|r| r.as_str()).map(str::to_string),
+            });
+        }
+    }
+    Some(diagnostics)
+}
+
+fn parse_ruff(stdout: &str, root: &Path) -> Option<Vec<Diagnostic>> {
+    let items: Vec<serde_json::Value> = serde_json::from_str(stdout.trim()).ok()?;
+    Some(
+        items
+            .iter()
+            .map(|item| {
+                let code = item.get("code").and_then(|c| c.as_str()).map(str::to_string);
+                let location = item.get("location");
+                Diagnostic {
+                    file: relative(root, item.get("filename").and_then(|f| f.as_str()).unwrap_or_default()),
+                    line: location.and_then(|l| l.get("row")).and_then(|r| r.as_u64()).unwrap_or(0) as u32,
+                    column: location.and_then(|l| l.get("column")).and_then(|c| c.as_u64()).unwrap_or(0) as u32,
+                    // Ruff rules have no severity; only syntax errors lack a code
+                    severity: if code.is_some() { Severity::Warning } else { Severity::Error },
+                    message: item.get("message").and_then(|m| m.as_str()).unwrap_or_default().to_string(),
+                    code,
+                }
+            })
+            .collect(),
+    )
+}
+
+/// Run a linter in `root`
+pub async fn lint(
+    linter: Linter,
+    root: &Path,
+    fix: bool,
+    shell: Option<&str>,
+    env: Vec<(String, String)>,
+    timeout_ms: u64,
+) -> Result<LinterRun, ExecutorError> {
+    let command = linter.command(root, fix);
+    let output = run_command(&command, root, shell, env, timeout_ms).await?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    let (mut diagnostics, error) = match linter.parse_output(&stdout, root) {
+        Some(diagnostics) => (diagnostics, None),
+        None => {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            (Vec::new(), tail(&format!("{}\n{}", stdout, stderr)))
+        }
+    };
+    let errors = diagnostics.iter().filter(|d| d.severity == Severity::Error).count();
+    let warnings = diagnostics.iter().filter(|d| d.severity == Severity::Warning).count();
+    // Errors first, so truncation drops warnings
+    diagnostics.sort_by_key(|d| d.severity != Severity::Error);
+    let truncated = diagnostics.len() > MAX_DIAGNOSTICS;
+    diagnostics.truncate(MAX_DIAGNOSTICS);
+
+    Ok(LinterRun {
+        linter,
+        command,
+        exit_code: output.status.code(),
+        errors,
+        warnings,
+        diagnostics,
+        truncated,
+        error,
+    })
+}
+
+/// List the files a formatter would change in `root`
+pub async fn check_format(
+    formatter: Formatter,
+    root: &Path,
+    shell: Option<&str>,
+    env: Vec<(String, String)>,
+    timeout_ms: u64,
+) -> Result<FormatterRun, ExecutorError> {
+    let command = formatter.check_command(root);
+    let output = run_command(&command, root, shell, env, timeout_ms).await?;
+    let text = format!(
+        "{}\n{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let files = formatter.parse_check(&text, root);
+    // The checks exit non-zero when files need formatting, and otherwise
+    // only when the formatter itself failed
+    let error = (!output.status.success() && files.is_empty()).then(|| tail(&text)).flatten();
+    Ok(FormatterRun { formatter, command, files, applied: false, error })
+}
+
+/// Rewrite the files of a checked run
+pub async fn apply_format(
+    run: &mut FormatterRun,
+    root: &Path,
+    shell: Option<&str>,
+    env: Vec<(String, String)>,
+    timeout_ms: u64,
+) -> Result<(), ExecutorError> {
+    let command = run.formatter.apply_command(root);
+    let output = run_command(&command, root, shell, env, timeout_ms).await?;
+    if output.status.success() {
+        run.applied = true;
+    } else {
+        run.error = tail(&String::from_utf8_lossy(&output.stderr));
+    }
+    run.command = command;
+    Ok(())
+}
+
+/// Absolute paths of the files named in diagnostics or format results
+pub fn absolute_paths<'a>(root: &Path, files: impl Iterator<Item = &'a str>) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = files.map(|file| root.join(file)).collect();
+    paths.sort();
+    paths.dedup();
+    paths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_clippy_messages() {
+        let root = Path::new("/work/app");
+        let warning = r#"{"reason":"compiler-message","message":{"message":"unused variable: `x`","code":{"code":"unused_variables"},"level":"warning","spans":[{"file_name":"src/main.rs","line_start":2,"column_start":9,"is_primary":true}]}}"#;
+        let summary = r#"{"reason":"compiler-message","message":{"message":"1 warning emitted","code":null,"level":"warning","spans":[]}}"#;
+        let error = r#"{"reason":"compiler-message","message":{"message":"mismatched types","code":{"code":"E0308"},"level":"error","spans":[{"file_name":"/work/app/src/lib.rs","line_start":7,"column_start":5,"is_primary":true}]}}"#;
+        let stdout = [warning, summary, warning, error, r#"{"reason":"build-finished","success":false}"#].join("\n");
+
+        let diagnostics = Linter::Clippy.parse_output(&stdout, root).unwrap();
+        assert_eq!(
+            diagnostics,
+            vec![
+                Diagnostic {
+                    file: "src/main.rs".to_string(),
+                    line: 2,
+                    column: 9,
+                    severity: Severity::Warning,
+                    message: "unused variable: `x`".to_string(),
+                    code: Some("unused_variables".to_string()),
+                },
+                Diagnostic {
+                    file: "src/lib.rs".to_string(),
+                    line: 7,
+                    column: 5,
+                    severity: Severity::Error,
+                    message: "mismatched types".to_string(),
+                    code: Some("E0308".to_string()),
+                },
+            ]
+        );
+        assert!(Linter::Clippy.parse_output("error: no such command: `clippy`", root).is_none());
+    }
+
+    #[test]
+    fn test_parse_eslint_and_ruff() {
+        let root = Path::new("/work/app");
+        let eslint = r#"[{"filePath":"/work/app/src/a.js","messages":[{"ruleId":"no-unused-vars","severity":1,"message":"'x' is defined but never used.","line":1,"column":7},{"ruleId":null,"severity":2,"message":"Parsing error","line":3,"column":1}]},{"filePath":"/work/app/src/b.js","messages":[]}]"#;
+        let diagnostics = Linter::Eslint.parse_output(eslint, root).unwrap();
+        let summary: Vec<(&str, u32, Severity, Option<&str>)> = diagnostics
+            .iter()
+            .map(|d| (d.file.as_str(), d.line, d.severity, d.code.as_deref()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("src/a.js", 1, Severity::Warning, Some("no-unused-vars")),
+                ("src/a.js", 3, Severity::Error, None),
+            ]
+        );
+
+        let ruff = r#"[{"code":"F401","message":"`os` imported but unused","filename":"/work/app/tool.py","location":{"row":1,"column":8},"fix":null}]"#;
+        let diagnostics = Linter::Ruff.parse_output(ruff, root).unwrap();
+        assert_eq!((diagnostics[0].file.as_str(), diagnostics[0].column), ("tool.py", 8));
+        assert!(Linter::Ruff.parse_output("ruff: command not found", root).is_none());
+    }
+
+    #[test]
+    fn test_parse_format_checks() {
+        let root = Path::new("/work/app");
+        let rustfmt = "Diff in /work/app/src/main.rs at line 3:\n-fn main(){}\nDiff in /work/app/src/main.rs:9:\nDiff in /work/app/src/lib.rs:1:\n";
+        assert_eq!(
+            Formatter::Rustfmt.parse_check(rustfmt, root),
+            vec![
+                UnformattedFile { file: "src/main.rs".to_string(), line: Some(3) },
+                UnformattedFile { file: "src/lib.rs".to_string(), line: Some(1) },
+            ]
+        );
+        let files = |formatter: Formatter, output: &str| -> Vec<String> {
+            formatter.parse_check(output, root).into_iter().map(|f| f.file).collect()
+        };
+        assert_eq!(files(Formatter::Prettier, "src/a.ts\n[warn] Code style issues found\n"), vec!["src/a.ts"]);
+        assert_eq!(
+            files(Formatter::Black, "would reformat /work/app/tool.py\nOh no! 1 file would be reformatted.\n"),
+            vec!["tool.py"]
+        );
+        assert_eq!(files(Formatter::Ruff, "Would reformat: tool.py\n1 file would be reformatted\n"), vec!["tool.py"]);
+    }
+
+    #[test]
+    fn test_detect_tools() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("package.json"), r#"{"devDependencies": {"eslint": "^9"}}"#).unwrap();
+        std::fs::write(dir.path().join("pnpm-lock.yaml"), "").unwrap();
+        std::fs::write(dir.path().join("pyproject.toml"), "[tool.black]\n").unwrap();
+        let kinds = [ProjectKind::Rust, ProjectKind::Node, ProjectKind::Python];
+
+        assert_eq!(Linter::detect(dir.path(), &kinds), vec![Linter::Clippy, Linter::Eslint, Linter::Ruff]);
+        assert_eq!(Formatter::detect(dir.path(), &kinds), vec![Formatter::Rustfmt, Formatter::Black]);
+        assert_eq!(Linter::Eslint.command(dir.path(), true), "pnpm exec eslint . --format json --fix");
+        assert_eq!(Formatter::Black.check_command(dir.path()), "black --check .");
+    }
+}
@@ -0,0 +1,280 @@
+//! Messages between agent sessions
+//!
+//! Agents running in parallel coordinate through the send_to_agent and
+//! read_inbox tools. Every session has an inbox; messages land in it in the
+//! order they were sent and carry a `read_at` receipt the sender can check.
+//! Two guards keep agents from answering each other forever: a reply chain
+//! stops after [`MAX_THREAD_DEPTH`] messages, and two sessions may exchange
+//! at most [`MAX_PAIR_MESSAGES`] messages per [`PAIR_WINDOW_SECS`].
+
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+/// Replies allowed in one thread before it is cut off
+pub const MAX_THREAD_DEPTH: u32 = 8;
+/// Messages two sessions may exchange within the window, both ways together
+pub const MAX_PAIR_MESSAGES: usize = 20;
+pub const PAIR_WINDOW_SECS: i64 = 300;
+/// Messages kept per inbox; the oldest read ones go first
+pub const MAX_INBOX_MESSAGES: usize = 200;
+/// Longest message an agent may send
+pub const MAX_MESSAGE_LEN: usize = 16 * 1024;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AgentMail {
+    pub id: String,
+    /// Delivery order within the recipient's inbox
+    pub seq: u64,
+    pub from: String,
+    pub to: String,
+    pub content: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reply_to: Option<String>,
+    /// Position in its reply chain; 0 for a new thread
+    pub depth: u32,
+    pub sent_at: DateTime<Utc>,
+    /// Read receipt, set when the recipient reads the message
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub read_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum MailError {
+    #[error("{0}")]
+    Invalid(String),
+
+    #[error("Message {0} was not sent to this session")]
+    UnknownReply(String),
+
+    #[error("This thread already has {0} replies; stop replying and report back to the user instead")]
+    ThreadTooDeep(u32),
+
+    #[error("{0} messages were exchanged with {1} in the last {2} seconds; stop messaging this agent for now")]
+    TooManyMessages(usize, String, i64),
+}
+
+#[derive(Default)]
+struct Inbox {
+    messages: Vec<AgentMail>,
+    next_seq: u64,
+}
+
+#[derive(Default)]
+struct State {
+    inboxes: HashMap<String, Inbox>,
+    /// Send times per unordered session pair
+    exchanges: HashMap<(String, String), VecDeque<DateTime<Utc>>>,
+}
+
+/// Inboxes of all agent sessions
+pub struct Mailbox {
+    state: Mutex<State>,
+}
+
+lazy_static::lazy_static! {
+    static ref GLOBAL_MAILBOX: Arc<Mailbox> = Arc::new(Mailbox::new());
+}
+
+fn pair(a: &str, b: &str) -> (String, String) {
+    if a <= b {
+        (a.to_string(), b.to_string())
+    } else {
+        (b.to_string(), a.to_string())
+    }
+}
+
+impl Mailbox {
+    pub fn new() -> Self {
+        Self { state: Mutex::new(State::default()) }
+    }
+
+    /// Shared mailbox, so messages reach sessions served by other executors
+    pub fn global() -> Arc<Mailbox> {
+        GLOBAL_MAILBOX.clone()
+    }
+
+    /// Deliver `content` from one session to another; `reply_to` names a
+    /// message the sender received
+    pub fn send(
+        &self,
+        from: &str,
+        to: &str,
+        content: &str,
+        reply_to: Option<&str>,
+    ) -> Result<AgentMail, MailError> {
+        let to = to.trim();
+        if to.is_empty() {
+            return Err(MailError::Invalid("Recipient session is required".to_string()));
+        }
+        if to == from {
+            return Err(MailError::Invalid("An agent cannot message itself".to_string()));
+        }
+        if content.trim().is_empty() {
+            return Err(MailError::Invalid("Message is empty".to_string()));
+        }
+        if content.len() > MAX_MESSAGE_LEN {
+            return Err(MailError::Invalid(format!("Message is longer than {} bytes", MAX_MESSAGE_LEN)));
+        }
+
+        let now = Utc::now();
+        let mut state = self.state.lock().unwrap();
+
+        let depth = match reply_to {
+            Some(parent_id) => {
+                let parent = state
+                    .inboxes
+                    .get(from)
+                    .and_then(|inbox| inbox.messages.iter().find(|m| m.id == parent_id))
+                    .ok_or_else(|| MailError::UnknownReply(parent_id.to_string()))?;
+                parent.depth + 1
+            }
+            None => 0,
+        };
+        if depth >= MAX_THREAD_DEPTH {
+            return Err(MailError::ThreadTooDeep(depth));
+        }
+
+        let window_start = now - Duration::seconds(PAIR_WINDOW_SECS);
+        let exchanges = state.exchanges.entry(pair(from, to)).or_default();
+        while exchanges.front().is_some_and(|sent| *sent < window_start) {
+            exchanges.pop_front();
+        }
+        if exchanges.len() >= MAX_PAIR_MESSAGES {
+            return Err(MailError::TooManyMessages(exchanges.len(), to.to_string(), PAIR_WINDOW_SECS));
+        }
+        exchanges.push_back(now);
+
+        let inbox = state.inboxes.entry(to.to_string()).or_default();
+        inbox.next_seq += 1;
+        let mail = AgentMail {
+            id: format!("msg-{}", uuid::Uuid::new_v4()),
+            seq: inbox.next_seq,
+            from: from.to_string(),
+            to: to.to_string(),
+            content: content.to_string(),
+            reply_to: reply_to.map(str::to_string),
+            depth,
+            sent_at: now,
+            read_at: None,
+        };
+        inbox.messages.push(mail.clone());
+        while inbox.messages.len() > MAX_INBOX_MESSAGES {
+            let oldest = inbox.messages.iter().position(|m| m.read_at.is_some()).unwrap_or(0);
+            inbox.messages.remove(oldest);
+        }
+        drop(state);
+
+        crate::events::publish(crate::events::Event::AgentMessage {
+            message_id: mail.id.clone(),
+            from: mail.from.clone(),
+            to: mail.to.clone(),
+        });
+        Ok(mail)
+    }
+
+    /// Every message in a session's inbox, in delivery order
+    pub fn inbox(&self, session_id: &str) -> Vec<AgentMail> {
+        let state = self.state.lock().unwrap();
+        state
+            .inboxes
+            .get(session_id)
+            .map(|inbox| inbox.messages.clone())
+            .unwrap_or_default()
+    }
+
+    /// Unread messages of a session, in delivery order, marked as read
+    pub fn read(&self, session_id: &str) -> Vec<AgentMail> {
+        let now = Utc::now();
+        let mut state = self.state.lock().unwrap();
+        let Some(inbox) = state.inboxes.get_mut(session_id) else {
+            return Vec::new();
+        };
+        inbox
+            .messages
+            .iter_mut()
+            .filter(|m| m.read_at.is_none())
+            .map(|m| {
+                m.read_at = Some(now);
+                m.clone()
+            })
+            .collect()
+    }
+
+    /// Messages a session sent that are still in their recipients' inboxes,
+    /// with their read receipts
+    pub fn sent(&self, session_id: &str) -> Vec<AgentMail> {
+        let state = self.state.lock().unwrap();
+        let mut sent: Vec<AgentMail> = state
+            .inboxes
+            .values()
+            .flat_map(|inbox| inbox.messages.iter())
+            .filter(|m| m.from == session_id)
+            .cloned()
+            .collect();
+        sent.sort_by_key(|m| m.sent_at);
+        sent
+    }
+
+    /// Drop a session's inbox and its exchange counters
+    pub fn forget(&self, session_id: &str) {
+        let mut state = self.state.lock().unwrap();
+        state.inboxes.remove(session_id);
+        state.exchanges.retain(|(a, b), _| a != session_id && b != session_id);
+    }
+}
+
+impl Default for Mailbox {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delivery_order_and_receipts() {
+        let mailbox = Mailbox::new();
+        let first = mailbox.send("planner", "coder", "write the parser", None).unwrap();
+        let second = mailbox.send("reviewer", "coder", "tests are failing", None).unwrap();
+        assert_eq!((first.seq, second.seq), (1, 2));
+        assert!(mailbox.send("coder", "coder", "hi", None).is_err());
+
+        let read = mailbox.read("coder");
+        assert_eq!(read.iter().map(|m| m.content.as_str()).collect::<Vec<_>>(), ["write the parser", "tests are failing"]);
+        assert!(mailbox.read("coder").is_empty());
+
+        let sent = mailbox.sent("planner");
+        assert_eq!(sent.len(), 1);
+        assert!(sent[0].read_at.is_some());
+    }
+
+    #[test]
+    fn test_ping_pong_is_cut_off() {
+        let mailbox = Mailbox::new();
+        let mut last = mailbox.send("a", "b", "ping", None).unwrap();
+        let (mut from, mut to) = ("b", "a");
+        let error = loop {
+            match mailbox.send(from, to, "pong", Some(&last.id)) {
+                Ok(mail) => last = mail,
+                Err(e) => break e,
+            }
+            std::mem::swap(&mut from, &mut to);
+        };
+        assert!(matches!(error, MailError::ThreadTooDeep(MAX_THREAD_DEPTH)));
+
+        // Starting new threads doesn't get around the pair budget
+        let error = loop {
+            if let Err(e) = mailbox.send("a", "b", "ping", None) {
+                break e;
+            }
+        };
+        assert!(matches!(error, MailError::TooManyMessages(MAX_PAIR_MESSAGES, _, _)));
+        assert!(mailbox.send("a", "c", "hello", None).is_ok());
+
+        assert!(matches!(mailbox.send("c", "a", "re", Some("msg-unknown")), Err(MailError::UnknownReply(_))));
+    }
+}
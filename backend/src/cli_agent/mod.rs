@@ -7,16 +7,55 @@
 //! for native integration with Skhoot's conversation UI.
 
 pub mod agent;
+pub mod artifacts;
+pub mod checkpoint;
+pub mod clipboard;
+pub mod context_set;
 pub mod executor;
+pub mod export;
+pub mod git;
+pub mod goal;
+pub mod http_request;
 pub mod instructions;
+pub mod jobs;
+pub mod lint;
+pub mod mailbox;
+pub mod output_parser;
+pub mod project;
+pub mod prompt_templates;
+pub mod refactor;
 pub mod response;
+pub mod screen;
 pub mod session;
+pub mod session_vars;
+pub mod test_runner;
+pub mod throttle;
 pub mod tools;
+pub mod trace;
 pub mod apply_patch;
+pub mod workspace;
 
 pub use agent::{Agent, AgentConfig, AgentState};
+pub use artifacts::{ByteRange, ToolAttachment};
+pub use checkpoint::{Checkpoint, CheckpointManager};
+pub use clipboard::{ClipboardProvider, SharedClipboard};
+pub use context_set::{ContextPin, ContextSet, ContextSetStore, PinMode};
 pub use executor::{AgentExecutor, ExecutorConfig};
+pub use export::{ConversationArchive, ConversationMetadata, ExportFormat};
+pub use git::GitRepo;
+pub use goal::{GoalRun, GoalStore};
 pub use instructions::SystemPrompt;
+pub use jobs::{JobInfo, JobManager, JobState};
+pub use mailbox::{AgentMail, Mailbox};
+pub use output_parser::StructuredOutput;
+pub use project::{ProjectDetector, ProjectProfile};
+pub use prompt_templates::{PromptScope, PromptTemplate, PromptTemplateStore};
+pub use refactor::{AppliedRefactor, RefactorError, RefactorPreview, RefactorStore, ReplaceSpec};
 pub use response::{AgentResponse, ToolCallResult};
-pub use session::{AgentSession, AgentSessionManager, SessionStatus};
+pub use screen::{CaptureTarget, ScreenCapture, SharedScreenCapture};
+pub use session::{AgentSession, AgentSessionManager, DispatchOutcome, MessageDispatcher, SessionStatus};
+pub use session_vars::{SessionVar, SessionVars, VarError};
+pub use throttle::{QueueStatus, ThrottleError, ToolThrottle};
 pub use tools::{Tool, ToolCall, ToolDefinition, ToolRegistry, ToolResult, ToolResultMetadata};
+pub use trace::{TraceEntry, TraceError, TraceEvent, TraceRecorder};
+pub use workspace::Workspace;
@@ -0,0 +1,248 @@
+//! Structured views of shell output
+//!
+//! Detects JSON (including JSON Lines), CSV/TSV and column-aligned tables
+//! such as the output of `df`, `ps` or `ls -l`, so the model gets rows and
+//! columns instead of re-parsing text. Output that doesn't clearly match one
+//! of these shapes is left alone.
+
+use serde::{Deserialize, Serialize};
+
+/// Larger outputs are not parsed
+pub const MAX_PARSED_BYTES: usize = 256 * 1024;
+
+/// Rows kept in a parsed table
+pub const MAX_ROWS: usize = 500;
+
+/// Shell output parsed into data
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "format", rename_all = "snake_case")]
+pub enum StructuredOutput {
+    Json {
+        value: serde_json::Value,
+    },
+    /// Comma or tab separated values
+    Csv {
+        headers: Vec<String>,
+        rows: Vec<Vec<String>>,
+        /// Whether rows past [`MAX_ROWS`] were left out
+        truncated: bool,
+    },
+    /// Whitespace-aligned columns; `headers` is empty when the output has
+    /// no header line
+    Table {
+        headers: Vec<String>,
+        rows: Vec<Vec<String>>,
+        truncated: bool,
+    },
+}
+
+/// Structured form of `output`, if it is JSON, CSV or an aligned table
+pub fn parse_output(output: &str) -> Option<StructuredOutput> {
+    if output.len() > MAX_PARSED_BYTES {
+        return None;
+    }
+    let trimmed = output.trim();
+    if trimmed.is_empty() {
+        return None;
+    }
+    parse_json(trimmed)
+        .or_else(|| parse_csv(trimmed))
+        .or_else(|| parse_table(trimmed))
+}
+
+fn parse_json(text: &str) -> Option<StructuredOutput> {
+    if !text.starts_with(['{', '[']) {
+        return None;
+    }
+    if let Ok(value) = serde_json::from_str(text) {
+        return Some(StructuredOutput::Json { value });
+    }
+
+    // JSON Lines: one object per line
+    let values = text
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).ok().filter(|v| v.is_object()))
+        .collect::<Option<Vec<_>>>()?;
+    (values.len() > 1).then_some(StructuredOutput::Json { value: serde_json::Value::Array(values) })
+}
+
+fn parse_csv(text: &str) -> Option<StructuredOutput> {
+    let lines: Vec<&str> = text.lines().filter(|line| !line.trim().is_empty()).collect();
+    if lines.len() < 2 {
+        return None;
+    }
+    let delimiter = [',', '\t'].into_iter().find(|d| lines[0].contains(*d))?;
+
+    let mut records = lines.iter().map(|line| split_delimited(line, delimiter));
+    let headers = records.next().filter(|headers| headers.iter().all(|h| !h.is_empty()))?;
+    let mut rows = Vec::new();
+    for record in records {
+        // Every record has the header's field count, or it isn't CSV
+        if record.len() != headers.len() {
+            return None;
+        }
+        rows.push(record);
+    }
+    let truncated = rows.len() > MAX_ROWS;
+    rows.truncate(MAX_ROWS);
+    Some(StructuredOutput::Csv { headers, rows, truncated })
+}
+
+/// Fields of a delimited line; double quotes group fields and `""` escapes a
+/// quote inside them
+fn split_delimited(line: &str, delimiter: char) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.trim_end_matches('\r').chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            c if c == delimiter && !quoted => fields.push(std::mem::take(&mut field).trim().to_string()),
+            c => field.push(c),
+        }
+    }
+    fields.push(field.trim().to_string());
+    fields
+}
+
+fn parse_table(text: &str) -> Option<StructuredOutput> {
+    let mut lines: Vec<Vec<char>> = text
+        .lines()
+        .map(|line| line.trim_end().chars().collect::<Vec<_>>())
+        .filter(|line| !line.is_empty())
+        .collect();
+    // `ls -l` starts with a "total N" summary line
+    if lines.first().is_some_and(|line| line.starts_with(&['t', 'o', 't', 'a', 'l', ' '])) {
+        lines.remove(0);
+    }
+    if lines.len() < 3 || lines.iter().any(|line| line.contains(&'\t')) {
+        return None;
+    }
+
+    // Columns are runs of positions that are not blank on every line
+    let width = lines.iter().map(Vec::len).max()?;
+    let blank: Vec<bool> = (0..width)
+        .map(|i| lines.iter().all(|line| line.get(i).is_none_or(|c| *c == ' ')))
+        .collect();
+    let mut spans = Vec::new();
+    let mut start = None;
+    for (i, is_blank) in blank.iter().chain(std::iter::once(&true)).enumerate() {
+        match (start, is_blank) {
+            (None, false) => start = Some(i),
+            (Some(s), true) => {
+                spans.push((s, i));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if spans.len() < 2 {
+        return None;
+    }
+
+    let cells: Vec<Vec<String>> = lines
+        .iter()
+        .map(|line| {
+            spans
+                .iter()
+                .map(|&(s, e)| line[s.min(line.len())..e.min(line.len())].iter().collect::<String>().trim().to_string())
+                .collect()
+        })
+        .collect();
+
+    // A header line has no purely numeric cell; without one every line is data
+    let has_header = cells[0].iter().all(|cell| !cell.is_empty() && cell.parse::<f64>().is_err());
+    let (mut headers, mut rows) = if has_header {
+        (cells[0].clone(), cells[1..].to_vec())
+    } else {
+        (Vec::new(), cells)
+    };
+
+    // Header words split by a blank column ("Mounted on") are one column
+    // when the rows leave the right-hand part empty
+    let mut col = 1;
+    while has_header && col < headers.len() {
+        if rows.iter().all(|row| row[col].is_empty()) {
+            let word = headers.remove(col);
+            headers[col - 1] = format!("{} {}", headers[col - 1], word);
+            for row in rows.iter_mut() {
+                row.remove(col);
+            }
+        } else {
+            col += 1;
+        }
+    }
+    // Text that happens to line up leaves many cells empty; real tables
+    // fill nearly all of them
+    let columns = rows.first().map_or(0, Vec::len);
+    let empty = rows.iter().flatten().filter(|cell| cell.is_empty()).count();
+    if columns < 2 || rows.iter().any(|row| row[0].is_empty()) || empty * 10 > rows.len() * columns {
+        return None;
+    }
+
+    let truncated = rows.len() > MAX_ROWS;
+    rows.truncate(MAX_ROWS);
+    Some(StructuredOutput::Table { headers, rows, truncated })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detects_json_and_json_lines() {
+        assert_eq!(
+            parse_output("{\"name\": \"skhoot\", \"version\": 2}\n"),
+            Some(StructuredOutput::Json { value: serde_json::json!({ "name": "skhoot", "version": 2 }) })
+        );
+        assert_eq!(
+            parse_output("{\"id\": 1}\n{\"id\": 2}\n"),
+            Some(StructuredOutput::Json { value: serde_json::json!([{ "id": 1 }, { "id": 2 }]) })
+        );
+        assert_eq!(parse_output("[not json"), None);
+    }
+
+    #[test]
+    fn test_detects_csv() {
+        let parsed = parse_output("name,size\n\"a, b.txt\",12\nc.txt,\"3\"\n");
+        assert_eq!(
+            parsed,
+            Some(StructuredOutput::Csv {
+                headers: vec!["name".into(), "size".into()],
+                rows: vec![vec!["a, b.txt".into(), "12".into()], vec!["c.txt".into(), "3".into()]],
+                truncated: false,
+            })
+        );
+        assert_eq!(parse_csv("a,b\n1,2,3\n"), None);
+    }
+
+    #[test]
+    fn test_detects_aligned_tables() {
+        let df = "\
+Filesystem      Size  Used Avail Use% Mounted on
+/dev/nvme0n1p2  468G  201G  244G  46% /
+tmpfs            16G  2.1M   16G   1% /run
+/dev/nvme0n1p1  511M   61M  451M  12% /boot/efi
+";
+        let Some(StructuredOutput::Table { headers, rows, .. }) = parse_output(df) else {
+            panic!("df output should parse as a table");
+        };
+        assert_eq!(headers, ["Filesystem", "Size", "Used", "Avail", "Use%", "Mounted on"]);
+        assert_eq!(rows[1], ["tmpfs", "16G", "2.1M", "16G", "1%", "/run"]);
+
+        let ls = "\
+total 16
+-rw-r--r-- 1 me staff  120 Jan  2 10:00 Cargo.toml
+drwxr-xr-x 4 me staff 4096 Jan  2 10:00 src
+";
+        assert!(parse_output(ls).is_none(), "two rows are too few to call a table");
+
+        assert_eq!(parse_output("Compiling skhoot\nFinished dev profile in 3.2s\ndone"), None);
+    }
+}
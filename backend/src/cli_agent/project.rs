@@ -0,0 +1,441 @@
+//! Project Detection
+//!
+//! Recognizes Rust, Node and Python projects around a session's working
+//! directory. The resulting profile enables the tools a coding session needs,
+//! lists the project's build and test commands (from `Cargo.toml`, the
+//! `package.json` scripts and `pyproject.toml`) and adds a project summary to
+//! the system prompt.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use super::tools::Tool;
+
+/// Directories searched upwards from the working directory
+const DEFAULT_MAX_DEPTH: usize = 6;
+
+/// `package.json` scripts listed; the rest are left for the agent to read
+const MAX_SCRIPTS: usize = 12;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProjectKind {
+    Rust,
+    Node,
+    Python,
+}
+
+impl ProjectKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ProjectKind::Rust => "Rust",
+            ProjectKind::Node => "Node",
+            ProjectKind::Python => "Python",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CommandPurpose {
+    Build,
+    Test,
+    Lint,
+    Format,
+    Run,
+    /// Any other `package.json` script
+    Script,
+}
+
+/// A command the project defines or its tooling implies
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProjectCommand {
+    pub kind: ProjectKind,
+    pub purpose: CommandPurpose,
+    /// Script name, or the purpose for implied commands
+    pub name: String,
+    /// Shell command, run from the project root
+    pub command: String,
+}
+
+/// What was detected about a session's project
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProjectProfile {
+    pub root: PathBuf,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub kinds: Vec<ProjectKind>,
+    pub commands: Vec<ProjectCommand>,
+    /// Whether the project is in a git repository
+    pub git: bool,
+}
+
+impl ProjectProfile {
+    /// Tools a session in this project should have
+    pub fn relevant_tools(&self) -> Vec<Tool> {
+        let mut tools = vec![
+            Tool::Shell,
+            Tool::ReadFile,
+            Tool::WriteFile,
+            Tool::ApplyPatch,
+            Tool::DiffFiles,
+            Tool::ListDirectory,
+            Tool::SearchFiles,
+            Tool::RunTests,
+            Tool::Lint,
+            Tool::Format,
+            Tool::ReplaceInFiles,
+            Tool::GoToDefinition,
+            Tool::FindReferences,
+            Tool::DocumentSymbols,
+            // Builds and test runs are long enough to run as jobs
+            Tool::JobStatus,
+            Tool::JobOutput,
+            Tool::JobCancel,
+            Tool::ListCheckpoints,
+            Tool::RevertCheckpoint,
+        ];
+        if self.git {
+            tools.extend([Tool::GitStatus, Tool::GitDiff, Tool::GitLog, Tool::GitCommit, Tool::GitCheckoutBranch]);
+        }
+        tools
+    }
+
+    /// Enable the relevant tools that `enabled` is missing
+    pub fn enable_tools(&self, enabled: &mut Vec<Tool>) {
+        for tool in self.relevant_tools() {
+            if !enabled.contains(&tool) {
+                enabled.push(tool);
+            }
+        }
+    }
+
+    /// Section added to the system prompt
+    pub fn summary(&self) -> String {
+        let kinds: Vec<&str> = self.kinds.iter().map(ProjectKind::label).collect();
+        let mut summary = format!("## Project\n- Type: {}", kinds.join(" + "));
+        if let Some(name) = &self.name {
+            summary.push_str(&format!("\n- Name: {}", name));
+        }
+        summary.push_str(&format!("\n- Root: {}", self.root.display()));
+        if self.git {
+            summary.push_str("\n- Version control: git");
+        }
+        if !self.commands.is_empty() {
+            summary.push_str("\n\nCommands (run from the project root; prefer them to guessing):");
+            for command in &self.commands {
+                summary.push_str(&format!("\n- {} ({}): `{}`", command.name, command.kind.label(), command.command));
+            }
+        }
+        summary
+    }
+}
+
+/// Finds the project a working directory belongs to
+#[derive(Debug, Clone)]
+pub struct ProjectDetector {
+    max_depth: usize,
+    /// Never treated as a project root, nor searched above
+    stop_at: Option<PathBuf>,
+}
+
+impl Default for ProjectDetector {
+    fn default() -> Self {
+        Self {
+            max_depth: DEFAULT_MAX_DEPTH,
+            stop_at: dirs::home_dir(),
+        }
+    }
+}
+
+impl ProjectDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Directories searched upwards, the working directory included
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Profile of the nearest project at or above `dir`, if any
+    pub fn detect(&self, dir: &Path) -> Option<ProjectProfile> {
+        let dir = dir.canonicalize().ok()?;
+        let root = dir
+            .ancestors()
+            .take(self.max_depth)
+            .take_while(|candidate| self.stop_at.as_deref() != Some(*candidate))
+            .find(|candidate| !detect_kinds(candidate).is_empty())?
+            .to_path_buf();
+
+        let kinds = detect_kinds(&root);
+        let mut name = None;
+        let mut commands = Vec::new();
+        for kind in &kinds {
+            let (kind_name, kind_commands) = match kind {
+                ProjectKind::Rust => rust_project(&root),
+                ProjectKind::Node => node_project(&root),
+                ProjectKind::Python => python_project(&root),
+            };
+            name = name.or(kind_name);
+            commands.extend(kind_commands);
+        }
+
+        Some(ProjectProfile {
+            git: root.ancestors().any(|ancestor| ancestor.join(".git").exists()),
+            root,
+            name,
+            kinds,
+            commands,
+        })
+    }
+}
+
+fn detect_kinds(dir: &Path) -> Vec<ProjectKind> {
+    let has = |file: &str| dir.join(file).is_file();
+    let mut kinds = Vec::new();
+    if has("Cargo.toml") {
+        kinds.push(ProjectKind::Rust);
+    }
+    if has("package.json") {
+        kinds.push(ProjectKind::Node);
+    }
+    if ["pyproject.toml", "setup.py", "setup.cfg", "requirements.txt"].iter().any(|file| has(file)) {
+        kinds.push(ProjectKind::Python);
+    }
+    kinds
+}
+
+pub(crate) fn read_toml(path: &Path) -> Option<toml::Value> {
+    toml::from_str(&std::fs::read_to_string(path).ok()?).ok()
+}
+
+/// Package manager a Node project's lockfile belongs to
+pub(crate) fn node_package_manager(root: &Path) -> &'static str {
+    if root.join("pnpm-lock.yaml").exists() {
+        "pnpm"
+    } else if root.join("yarn.lock").exists() {
+        "yarn"
+    } else if root.join("bun.lockb").exists() || root.join("bun.lock").exists() {
+        "bun"
+    } else {
+        "npm"
+    }
+}
+
+/// Prefix that runs a tool in a Python project's environment (`uv run `,
+/// `poetry run ` or nothing)
+pub(crate) fn python_runner(root: &Path) -> &'static str {
+    if root.join("uv.lock").exists() {
+        "uv run "
+    } else if root.join("poetry.lock").exists() {
+        "poetry run "
+    } else {
+        ""
+    }
+}
+
+fn implied(kind: ProjectKind, purpose: CommandPurpose, name: &str, command: String) -> ProjectCommand {
+    ProjectCommand {
+        kind,
+        purpose,
+        name: name.to_string(),
+        command,
+    }
+}
+
+fn rust_project(root: &Path) -> (Option<String>, Vec<ProjectCommand>) {
+    let manifest = read_toml(&root.join("Cargo.toml"));
+    let manifest = manifest.as_ref();
+    let name = manifest
+        .and_then(|m| m.get("package")?.get("name")?.as_str())
+        .map(str::to_string);
+    let workspace = manifest.is_some_and(|m| m.get("workspace").is_some());
+    let scope = if workspace { " --workspace" } else { "" };
+
+    let kind = ProjectKind::Rust;
+    let mut commands = vec![
+        implied(kind, CommandPurpose::Build, "build", format!("cargo build{}", scope)),
+        implied(kind, CommandPurpose::Test, "test", format!("cargo test{}", scope)),
+        implied(kind, CommandPurpose::Lint, "lint", format!("cargo clippy{} --all-targets", scope)),
+        implied(kind, CommandPurpose::Format, "format", "cargo fmt --all".to_string()),
+    ];
+    let has_binary = root.join("src/main.rs").is_file() || manifest.is_some_and(|m| m.get("bin").is_some());
+    if has_binary {
+        commands.push(implied(kind, CommandPurpose::Run, "run", "cargo run".to_string()));
+    }
+    (name, commands)
+}
+
+fn script_purpose(script: &str) -> CommandPurpose {
+    let base = script.split(':').next().unwrap_or(script);
+    match base {
+        "build" | "compile" => CommandPurpose::Build,
+        "test" | "tests" | "e2e" => CommandPurpose::Test,
+        "lint" | "typecheck" | "check" => CommandPurpose::Lint,
+        "format" | "fmt" | "prettier" => CommandPurpose::Format,
+        "dev" | "start" | "serve" | "preview" => CommandPurpose::Run,
+        _ => CommandPurpose::Script,
+    }
+}
+
+fn node_project(root: &Path) -> (Option<String>, Vec<ProjectCommand>) {
+    let package: Option<serde_json::Value> = std::fs::read_to_string(root.join("package.json"))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok());
+    let name = package
+        .as_ref()
+        .and_then(|p| p.get("name")?.as_str())
+        .map(str::to_string);
+
+    let manager = node_package_manager(root);
+
+    let mut scripts: Vec<(&String, CommandPurpose)> = package
+        .as_ref()
+        .and_then(|p| p.get("scripts")?.as_object())
+        .map(|scripts| scripts.keys().map(|script| (script, script_purpose(script))).collect())
+        .unwrap_or_default();
+    // Well-known scripts first, then the rest by name
+    scripts.sort_by_key(|(_, purpose)| *purpose == CommandPurpose::Script);
+
+    let commands = scripts
+        .into_iter()
+        .take(MAX_SCRIPTS)
+        .map(|(script, purpose)| {
+            let command = match (manager, script.as_str()) {
+                ("npm", "test" | "start") => format!("npm {}", script),
+                ("npm" | "bun", _) => format!("{} run {}", manager, script),
+                _ => format!("{} {}", manager, script),
+            };
+            implied(ProjectKind::Node, purpose, script, command)
+        })
+        .collect();
+    (name, commands)
+}
+
+fn python_project(root: &Path) -> (Option<String>, Vec<ProjectCommand>) {
+    let pyproject = read_toml(&root.join("pyproject.toml"));
+    let pyproject = pyproject.as_ref();
+    let name = pyproject
+        .and_then(|p| {
+            p.get("project")
+                .and_then(|project| project.get("name"))
+                .or_else(|| p.get("tool")?.get("poetry")?.get("name"))?
+                .as_str()
+        })
+        .map(str::to_string);
+    let tool = |name: &str| pyproject.and_then(|p| p.get("tool")?.get(name)).is_some();
+
+    let runner = python_runner(root);
+
+    let kind = ProjectKind::Python;
+    let mut commands = Vec::new();
+    if pyproject.is_some_and(|p| p.get("build-system").is_some()) {
+        commands.push(implied(kind, CommandPurpose::Build, "build", format!("{}python -m build", runner)));
+    }
+    let uses_pytest = tool("pytest")
+        || ["pytest.ini", "conftest.py", "tests/conftest.py"].iter().any(|file| root.join(file).exists());
+    let test = if uses_pytest || root.join("tests").is_dir() {
+        "pytest"
+    } else {
+        "python -m unittest"
+    };
+    commands.push(implied(kind, CommandPurpose::Test, "test", format!("{}{}", runner, test)));
+    let uses_ruff = tool("ruff") || root.join("ruff.toml").exists();
+    if uses_ruff {
+        commands.push(implied(kind, CommandPurpose::Lint, "lint", format!("{}ruff check .", runner)));
+    }
+    if tool("black") {
+        commands.push(implied(kind, CommandPurpose::Format, "format", format!("{}black .", runner)));
+    } else if uses_ruff {
+        commands.push(implied(kind, CommandPurpose::Format, "format", format!("{}ruff format .", runner)));
+    }
+    (name, commands)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commands(profile: &ProjectProfile) -> Vec<(&str, &str)> {
+        profile.commands.iter().map(|c| (c.name.as_str(), c.command.as_str())).collect()
+    }
+
+    #[test]
+    fn test_detect_rust_workspace_from_subdirectory() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("Cargo.toml"), "[workspace]\nmembers = [\"app\"]\n").unwrap();
+        std::fs::create_dir(dir.path().join(".git")).unwrap();
+        let nested = dir.path().join("app/src");
+        std::fs::create_dir_all(&nested).unwrap();
+
+        let profile = ProjectDetector::new().detect(&nested).unwrap();
+        assert_eq!(profile.root, dir.path().canonicalize().unwrap());
+        assert_eq!(profile.kinds, vec![ProjectKind::Rust]);
+        assert!(profile.git);
+        assert_eq!(
+            commands(&profile),
+            vec![
+                ("build", "cargo build --workspace"),
+                ("test", "cargo test --workspace"),
+                ("lint", "cargo clippy --workspace --all-targets"),
+                ("format", "cargo fmt --all"),
+            ]
+        );
+        assert!(profile.relevant_tools().contains(&Tool::GitDiff));
+
+        let mut enabled = vec![Tool::ReadFile];
+        profile.enable_tools(&mut enabled);
+        assert_eq!(enabled[0], Tool::ReadFile);
+        assert!(enabled.contains(&Tool::Shell) && enabled.contains(&Tool::GitStatus));
+    }
+
+    #[test]
+    fn test_detect_node_and_python() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("package.json"),
+            r#"{"name": "web-app", "scripts": {"release": "x", "dev": "vite", "test": "vitest", "build": "vite build"}}"#,
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("pnpm-lock.yaml"), "").unwrap();
+        std::fs::write(
+            dir.path().join("pyproject.toml"),
+            "[project]\nname = \"tools\"\n\n[build-system]\nrequires = []\n\n[tool.ruff]\n",
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("uv.lock"), "").unwrap();
+
+        let profile = ProjectDetector::new().detect(dir.path()).unwrap();
+        assert_eq!(profile.kinds, vec![ProjectKind::Node, ProjectKind::Python]);
+        assert_eq!(profile.name.as_deref(), Some("web-app"));
+        assert!(!profile.relevant_tools().contains(&Tool::GitCommit));
+        assert_eq!(
+            commands(&profile),
+            vec![
+                ("build", "pnpm build"),
+                ("dev", "pnpm dev"),
+                ("test", "pnpm test"),
+                ("release", "pnpm release"),
+                ("build", "uv run python -m build"),
+                ("test", "uv run python -m unittest"),
+                ("lint", "uv run ruff check ."),
+                ("format", "uv run ruff format ."),
+            ]
+        );
+
+        let summary = profile.summary();
+        assert!(summary.starts_with("## Project\n- Type: Node + Python\n- Name: web-app\n"));
+        assert!(summary.contains("- dev (Node): `pnpm dev`"));
+    }
+
+    #[test]
+    fn test_no_project() {
+        let dir = tempfile::tempdir().unwrap();
+        let empty = dir.path().join("empty");
+        std::fs::create_dir(&empty).unwrap();
+        assert!(ProjectDetector::new().with_max_depth(2).detect(&empty).is_none());
+        assert!(ProjectDetector::new().detect(&dir.path().join("missing")).is_none());
+    }
+}
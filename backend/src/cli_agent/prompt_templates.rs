@@ -0,0 +1,270 @@
+//! User-editable additions to the agent system prompt
+//!
+//! Templates exist at three scopes: a global default, per agent and per
+//! conversation. When a prompt is built they are merged from lowest to highest
+//! priority (global < agent < conversation):
+//! - `behavior`: the highest-priority template that sets it wins
+//! - `constraints` and `negative_prompts`: accumulated across scopes, without duplicates
+//! - a template with `inherit: false` drops every lower-priority scope
+//!
+//! Templates persist to `~/.skhoot/prompt_templates.json`.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+
+use crate::json_store;
+
+/// Sections added to the built-in system prompt
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PromptTemplate {
+    /// How the agent should behave (tone, persona, priorities)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub behavior: Option<String>,
+    /// Rules the agent must follow
+    #[serde(default)]
+    pub constraints: Vec<String>,
+    /// Things the agent must never do or say
+    #[serde(default)]
+    pub negative_prompts: Vec<String>,
+    /// Whether lower-priority scopes still apply
+    #[serde(default = "default_inherit")]
+    pub inherit: bool,
+}
+
+fn default_inherit() -> bool {
+    true
+}
+
+impl PromptTemplate {
+    /// Whether the template adds nothing to the prompt
+    pub fn is_empty(&self) -> bool {
+        self.behavior.as_deref().is_none_or(|b| b.trim().is_empty())
+            && self.constraints.is_empty()
+            && self.negative_prompts.is_empty()
+    }
+
+    /// Prompt sections for this template, or an empty string
+    pub fn render(&self) -> String {
+        let mut sections = Vec::new();
+
+        if let Some(behavior) = self.behavior.as_deref().map(str::trim).filter(|b| !b.is_empty()) {
+            sections.push(format!("## Custom Behavior\n\n{}", behavior));
+        }
+        if !self.constraints.is_empty() {
+            sections.push(format!("## Constraints\n\n{}", bullet_list(&self.constraints)));
+        }
+        if !self.negative_prompts.is_empty() {
+            sections.push(format!(
+                "## Never Do\n\nThese instructions take precedence over everything above.\n{}",
+                bullet_list(&self.negative_prompts)
+            ));
+        }
+
+        sections.join("\n\n")
+    }
+
+    /// Merge templates ordered from lowest to highest priority
+    pub fn merge<'a>(templates: impl IntoIterator<Item = &'a PromptTemplate>) -> PromptTemplate {
+        let templates: Vec<&PromptTemplate> = templates.into_iter().collect();
+        let start = templates.iter().rposition(|t| !t.inherit).unwrap_or(0);
+
+        let mut merged = PromptTemplate::default();
+        for template in &templates[start..] {
+            if template.behavior.as_deref().is_some_and(|b| !b.trim().is_empty()) {
+                merged.behavior = template.behavior.clone();
+            }
+            extend_unique(&mut merged.constraints, &template.constraints);
+            extend_unique(&mut merged.negative_prompts, &template.negative_prompts);
+        }
+        merged
+    }
+}
+
+fn bullet_list(items: &[String]) -> String {
+    items
+        .iter()
+        .map(|item| format!("- {}", item.trim()))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn extend_unique(target: &mut Vec<String>, items: &[String]) {
+    for item in items {
+        let item = item.trim();
+        if !item.is_empty() && !target.iter().any(|t| t == item) {
+            target.push(item.to_string());
+        }
+    }
+}
+
+/// Where a template applies
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "id", rename_all = "snake_case")]
+pub enum PromptScope {
+    Global,
+    Agent(String),
+    Conversation(String),
+}
+
+/// Every stored template
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PromptTemplateSet {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub global: Option<PromptTemplate>,
+    #[serde(default)]
+    pub agents: HashMap<String, PromptTemplate>,
+    #[serde(default)]
+    pub conversations: HashMap<String, PromptTemplate>,
+}
+
+/// Persistent prompt template store
+#[derive(Debug)]
+pub struct PromptTemplateStore {
+    path: PathBuf,
+    templates: RwLock<PromptTemplateSet>,
+}
+
+lazy_static::lazy_static! {
+    static ref GLOBAL_STORE: Arc<PromptTemplateStore> = Arc::new(PromptTemplateStore::new(
+        dirs::home_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join(".skhoot")
+            .join("prompt_templates.json"),
+    ));
+}
+
+impl PromptTemplateStore {
+    /// Open the store at `path`; see [`json_store::load`]
+    pub fn new(path: PathBuf) -> Self {
+        let templates = json_store::load(&path);
+        Self {
+            path,
+            templates: RwLock::new(templates),
+        }
+    }
+
+    /// Shared store at `~/.skhoot/prompt_templates.json`
+    pub fn global() -> Arc<PromptTemplateStore> {
+        GLOBAL_STORE.clone()
+    }
+
+    /// All stored templates
+    pub fn all(&self) -> PromptTemplateSet {
+        self.templates.read().unwrap().clone()
+    }
+
+    /// Template stored for a scope
+    pub fn get(&self, scope: &PromptScope) -> Option<PromptTemplate> {
+        let templates = self.templates.read().unwrap();
+        match scope {
+            PromptScope::Global => templates.global.clone(),
+            PromptScope::Agent(id) => templates.agents.get(id).cloned(),
+            PromptScope::Conversation(id) => templates.conversations.get(id).cloned(),
+        }
+    }
+
+    /// Store the template for a scope, replacing any previous one
+    pub fn set(&self, scope: PromptScope, template: PromptTemplate) -> std::io::Result<()> {
+        let mut templates = self.templates.write().unwrap();
+        match scope {
+            PromptScope::Global => templates.global = Some(template),
+            PromptScope::Agent(id) => {
+                templates.agents.insert(id, template);
+            }
+            PromptScope::Conversation(id) => {
+                templates.conversations.insert(id, template);
+            }
+        }
+        self.save(&templates)
+    }
+
+    /// Remove the template for a scope. Returns whether one existed.
+    pub fn remove(&self, scope: &PromptScope) -> std::io::Result<bool> {
+        let mut templates = self.templates.write().unwrap();
+        let removed = match scope {
+            PromptScope::Global => templates.global.take().is_some(),
+            PromptScope::Agent(id) => templates.agents.remove(id).is_some(),
+            PromptScope::Conversation(id) => templates.conversations.remove(id).is_some(),
+        };
+        if removed {
+            self.save(&templates)?;
+        }
+        Ok(removed)
+    }
+
+    /// Effective template for an agent and conversation
+    pub fn resolve(&self, agent_id: Option<&str>, conversation_id: Option<&str>) -> PromptTemplate {
+        let templates = self.templates.read().unwrap();
+        let levels = [
+            templates.global.as_ref(),
+            agent_id.and_then(|id| templates.agents.get(id)),
+            conversation_id.and_then(|id| templates.conversations.get(id)),
+        ];
+        PromptTemplate::merge(levels.into_iter().flatten())
+    }
+
+    fn save(&self, templates: &PromptTemplateSet) -> std::io::Result<()> {
+        json_store::save(&self.path, templates)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn template(behavior: Option<&str>, constraints: &[&str], negative: &[&str]) -> PromptTemplate {
+        PromptTemplate {
+            behavior: behavior.map(String::from),
+            constraints: constraints.iter().map(|s| s.to_string()).collect(),
+            negative_prompts: negative.iter().map(|s| s.to_string()).collect(),
+            inherit: true,
+        }
+    }
+
+    #[test]
+    fn test_resolve_merges_scopes_by_priority() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = PromptTemplateStore::new(temp_dir.path().join("templates.json"));
+        store.set(PromptScope::Global, template(Some("Be formal"), &["Cite sources"], &["Never use emojis"])).unwrap();
+        store.set(PromptScope::Agent("coder".into()), template(None, &["Write tests", "Cite sources"], &[])).unwrap();
+        store.set(PromptScope::Conversation("c1".into()), template(Some("Be casual"), &[], &["Never run sudo"])).unwrap();
+
+        let resolved = store.resolve(Some("coder"), Some("c1"));
+        assert_eq!(resolved.behavior.as_deref(), Some("Be casual"));
+        assert_eq!(resolved.constraints, vec!["Cite sources", "Write tests"]);
+        assert_eq!(resolved.negative_prompts, vec!["Never use emojis", "Never run sudo"]);
+
+        let global_only = store.resolve(Some("other"), None);
+        assert_eq!(global_only.behavior.as_deref(), Some("Be formal"));
+
+        // Persisted across reloads
+        let reloaded = PromptTemplateStore::new(temp_dir.path().join("templates.json"));
+        assert_eq!(reloaded.resolve(Some("coder"), Some("c1")), resolved);
+    }
+
+    #[test]
+    fn test_non_inheriting_template_drops_lower_scopes() {
+        let global = template(Some("Be formal"), &["Cite sources"], &["Never use emojis"]);
+        let mut conversation = template(None, &["Answer in French"], &[]);
+        conversation.inherit = false;
+
+        let merged = PromptTemplate::merge([&global, &conversation]);
+        assert_eq!(merged.behavior, None);
+        assert_eq!(merged.constraints, vec!["Answer in French"]);
+        assert!(merged.negative_prompts.is_empty());
+    }
+
+    #[test]
+    fn test_render_sections() {
+        assert_eq!(PromptTemplate::default().render(), "");
+
+        let rendered = template(Some("Be brief"), &["Use metric units"], &["Never guess file paths"]).render();
+        assert!(rendered.contains("## Custom Behavior\n\nBe brief"));
+        assert!(rendered.contains("- Use metric units"));
+        assert!(rendered.contains("## Never Do"));
+        assert!(rendered.contains("- Never guess file paths"));
+    }
+}
@@ -0,0 +1,375 @@
+//! Workspace-wide find and replace
+//!
+//! A replacement is planned first: every matching file under the scope is
+//! rewritten in memory and the result kept as a preview with one unified diff
+//! per file. Nothing touches the disk until the preview is applied, which
+//! snapshots the files in a checkpoint so the whole operation can be reverted.
+//! Applying refuses files that changed since the preview was made.
+
+use chrono::{DateTime, Duration, Utc};
+use ignore::overrides::OverrideBuilder;
+use regex::{NoExpand, Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use similar::TextDiff;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use super::checkpoint::{Checkpoint, CheckpointManager};
+use crate::ignore_rules::IgnoreRules;
+
+/// Files larger than this are not rewritten
+const MAX_FILE_BYTES: u64 = 2 * 1024 * 1024;
+
+/// Most files one replacement may change
+pub const MAX_CHANGED_FILES: usize = 500;
+
+/// How long an unapplied preview is kept
+const PREVIEW_TTL_MINUTES: i64 = 60;
+
+#[derive(Debug, thiserror::Error)]
+pub enum RefactorError {
+    #[error("Invalid pattern: {0}")]
+    InvalidPattern(String),
+
+    #[error("Invalid scope: {0}")]
+    InvalidScope(String),
+
+    #[error("Preview not found: {0}")]
+    NotFound(String),
+
+    #[error("{0} changed since the preview was made")]
+    Stale(String),
+
+    #[error("More than {MAX_CHANGED_FILES} files would change; narrow the scope")]
+    TooManyFiles,
+
+    #[error("Checkpoint failed: {0}")]
+    Checkpoint(String),
+
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// What to replace and where
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplaceSpec {
+    pub pattern: String,
+    pub replacement: String,
+    /// Treat `pattern` as a regular expression; `$1` and `${name}` in the
+    /// replacement refer to its groups
+    #[serde(default)]
+    pub regex: bool,
+    #[serde(default = "default_case_sensitive")]
+    pub case_sensitive: bool,
+    /// Globs relative to the root selecting the files (all files if empty)
+    #[serde(default)]
+    pub include: Vec<String>,
+    /// Globs relative to the root excluding files
+    #[serde(default)]
+    pub exclude: Vec<String>,
+}
+
+fn default_case_sensitive() -> bool {
+    true
+}
+
+impl ReplaceSpec {
+    fn compile(&self) -> Result<Regex, RefactorError> {
+        if self.pattern.is_empty() {
+            return Err(RefactorError::InvalidPattern("the pattern is empty".to_string()));
+        }
+        let source = if self.regex { self.pattern.clone() } else { regex::escape(&self.pattern) };
+        RegexBuilder::new(&source)
+            .case_insensitive(!self.case_sensitive)
+            .build()
+            .map_err(|e| RefactorError::InvalidPattern(e.to_string()))
+    }
+
+    /// Rewritten content and the number of replacements, if anything matched
+    fn apply_to(&self, regex: &Regex, content: &str) -> Option<(String, usize)> {
+        let count = regex.find_iter(content).count();
+        if count == 0 {
+            return None;
+        }
+        let replaced = if self.regex {
+            regex.replace_all(content, self.replacement.as_str())
+        } else {
+            regex.replace_all(content, NoExpand(&self.replacement))
+        };
+        Some((replaced.into_owned(), count))
+    }
+}
+
+/// One file a preview changes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileChange {
+    pub path: PathBuf,
+    pub replacements: usize,
+    /// Unified diff against the current content
+    pub diff: String,
+}
+
+/// A planned replacement awaiting approval
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RefactorPreview {
+    pub id: String,
+    pub root: PathBuf,
+    pub spec: ReplaceSpec,
+    /// Agent session whose checkpoints record the change, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    pub files: Vec<FileChange>,
+    pub total_replacements: usize,
+    pub files_scanned: usize,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Outcome of applying a preview
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppliedRefactor {
+    pub id: String,
+    pub files: Vec<PathBuf>,
+    pub total_replacements: usize,
+    /// Revert through the checkpoint to undo the replacement
+    pub checkpoint: Checkpoint,
+}
+
+#[derive(Debug)]
+struct PendingFile {
+    path: PathBuf,
+    original_hash: String,
+    content: String,
+}
+
+#[derive(Debug)]
+struct PendingRefactor {
+    preview: RefactorPreview,
+    files: Vec<PendingFile>,
+}
+
+/// Previews waiting to be applied or discarded
+#[derive(Debug, Default)]
+pub struct RefactorStore {
+    pending: Mutex<HashMap<String, PendingRefactor>>,
+}
+
+lazy_static::lazy_static! {
+    static ref GLOBAL_STORE: Arc<RefactorStore> = Arc::new(RefactorStore::default());
+}
+
+fn content_hash(content: &[u8]) -> String {
+    hex::encode(Sha256::digest(content))
+}
+
+impl RefactorStore {
+    pub fn global() -> Arc<RefactorStore> {
+        GLOBAL_STORE.clone()
+    }
+
+    /// Plan a replacement under `root` and keep it until it is applied
+    pub fn preview(
+        &self,
+        root: &Path,
+        spec: ReplaceSpec,
+        session_id: Option<String>,
+    ) -> Result<RefactorPreview, RefactorError> {
+        let regex = spec.compile()?;
+        let mut overrides = OverrideBuilder::new(root);
+        for glob in &spec.include {
+            overrides.add(glob).map_err(|e| RefactorError::InvalidScope(e.to_string()))?;
+        }
+        for glob in &spec.exclude {
+            overrides
+                .add(&format!("!{}", glob))
+                .map_err(|e| RefactorError::InvalidScope(e.to_string()))?;
+        }
+        let overrides = overrides.build().map_err(|e| RefactorError::InvalidScope(e.to_string()))?;
+
+        let mut files = Vec::new();
+        let mut files_scanned = 0;
+        let walker = IgnoreRules::SEARCH.walker(root).overrides(overrides).build();
+        for entry in walker.filter_map(|e| e.ok()) {
+            if !entry.file_type().is_some_and(|t| t.is_file())
+                || entry.metadata().ok().is_none_or(|m| m.len() > MAX_FILE_BYTES)
+            {
+                continue;
+            }
+            let bytes = std::fs::read(entry.path())?;
+            // Binary and non-UTF-8 files are left alone
+            let Ok(content) = std::str::from_utf8(&bytes) else {
+                continue;
+            };
+            if content.contains('\0') {
+                continue;
+            }
+            files_scanned += 1;
+            if let Some((replaced, replacements)) = spec.apply_to(&regex, content) {
+                if files.len() == MAX_CHANGED_FILES {
+                    return Err(RefactorError::TooManyFiles);
+                }
+                files.push((entry.into_path(), content_hash(&bytes), content.to_string(), replaced, replacements));
+            }
+        }
+
+        let changes = files
+            .iter()
+            .map(|(path, _, original, replaced, replacements)| {
+                let name = path.strip_prefix(root).unwrap_or(path).to_string_lossy().to_string();
+                FileChange {
+                    path: path.clone(),
+                    replacements: *replacements,
+                    diff: TextDiff::from_lines(original, replaced)
+                        .unified_diff()
+                        .context_radius(3)
+                        .header(&format!("a/{}", name), &format!("b/{}", name))
+                        .to_string(),
+                }
+            })
+            .collect::<Vec<_>>();
+        let preview = RefactorPreview {
+            id: uuid::Uuid::new_v4().to_string(),
+            root: root.to_path_buf(),
+            spec,
+            session_id,
+            total_replacements: changes.iter().map(|c| c.replacements).sum(),
+            files: changes,
+            files_scanned,
+            created_at: Utc::now(),
+        };
+        let pending = PendingRefactor {
+            preview: preview.clone(),
+            files: files
+                .into_iter()
+                .map(|(path, original_hash, _, content, _)| PendingFile { path, original_hash, content })
+                .collect(),
+        };
+
+        let mut store = self.pending.lock().unwrap();
+        let cutoff = Utc::now() - Duration::minutes(PREVIEW_TTL_MINUTES);
+        store.retain(|_, p| p.preview.created_at > cutoff);
+        store.insert(preview.id.clone(), pending);
+        Ok(preview)
+    }
+
+    pub fn get(&self, id: &str) -> Option<RefactorPreview> {
+        self.pending.lock().unwrap().get(id).map(|p| p.preview.clone())
+    }
+
+    pub fn discard(&self, id: &str) -> bool {
+        self.pending.lock().unwrap().remove(id).is_some()
+    }
+
+    /// Write a preview's changes, checkpointing the files first. Previews
+    /// without a session are checkpointed under their own ID.
+    pub fn apply(&self, id: &str, checkpoints: &CheckpointManager) -> Result<AppliedRefactor, RefactorError> {
+        let pending = self
+            .pending
+            .lock()
+            .unwrap()
+            .remove(id)
+            .ok_or_else(|| RefactorError::NotFound(id.to_string()))?;
+
+        for file in &pending.files {
+            let unchanged = std::fs::read(&file.path).is_ok_and(|bytes| content_hash(&bytes) == file.original_hash);
+            if !unchanged {
+                return Err(RefactorError::Stale(file.path.display().to_string()));
+            }
+        }
+
+        let paths: Vec<PathBuf> = pending.files.iter().map(|f| f.path.clone()).collect();
+        let session_id = pending.preview.session_id.as_deref().unwrap_or(id);
+        let checkpoint = checkpoints
+            .create(session_id, "replace_in_files", &paths)
+            .map_err(|e| RefactorError::Checkpoint(e.to_string()))?;
+        for file in &pending.files {
+            std::fs::write(&file.path, &file.content)?;
+        }
+
+        Ok(AppliedRefactor {
+            id: id.to_string(),
+            files: paths,
+            total_replacements: pending.preview.total_replacements,
+            checkpoint,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(pattern: &str, replacement: &str) -> ReplaceSpec {
+        ReplaceSpec {
+            pattern: pattern.to_string(),
+            replacement: replacement.to_string(),
+            regex: false,
+            case_sensitive: true,
+            include: Vec::new(),
+            exclude: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_literal_and_regex_replacement() {
+        let literal = spec("a.b", "$1");
+        let regex = literal.compile().unwrap();
+        assert_eq!(literal.apply_to(&regex, "a.b axb a.b"), Some(("$1 axb $1".to_string(), 2)));
+
+        let groups = ReplaceSpec { regex: true, ..spec(r"fn (\w+)_old", "fn ${1}_new") };
+        let regex = groups.compile().unwrap();
+        assert_eq!(groups.apply_to(&regex, "fn parse_old() {}"), Some(("fn parse_new() {}".to_string(), 1)));
+        assert_eq!(groups.apply_to(&regex, "nothing here"), None);
+
+        let insensitive = ReplaceSpec { case_sensitive: false, ..spec("config", "settings") };
+        let regex = insensitive.compile().unwrap();
+        assert_eq!(insensitive.apply_to(&regex, "Config config").unwrap().1, 2);
+        assert!(matches!(ReplaceSpec { regex: true, ..spec("(", "") }.compile(), Err(RefactorError::InvalidPattern(_))));
+    }
+
+    #[test]
+    fn test_preview_apply_and_revert() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("project");
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(root.join("src/lib.rs"), "fn load_config() {}\nfn main() { load_config(); }\n").unwrap();
+        std::fs::write(root.join("README.md"), "Call load_config first.\n").unwrap();
+
+        let store = RefactorStore::default();
+        let scoped = ReplaceSpec { include: vec!["*.rs".to_string()], ..spec("load_config", "read_settings") };
+        let preview = store.preview(&root, scoped, None).unwrap();
+        assert_eq!((preview.files.len(), preview.total_replacements), (1, 2));
+        let diff = &preview.files[0].diff;
+        assert!(diff.starts_with("--- a/src/lib.rs\n+++ b/src/lib.rs\n"));
+        assert!(diff.contains("\n-fn load_config() {}\n") && diff.contains("\n+fn read_settings() {}\n"));
+        assert!(std::fs::read_to_string(root.join("src/lib.rs")).unwrap().contains("load_config"));
+
+        let checkpoints = CheckpointManager::new(dir.path().join("checkpoints"));
+        let applied = store.apply(&preview.id, &checkpoints).unwrap();
+        assert_eq!(applied.checkpoint.session_id, preview.id);
+        assert_eq!(
+            std::fs::read_to_string(root.join("src/lib.rs")).unwrap(),
+            "fn read_settings() {}\nfn main() { read_settings(); }\n"
+        );
+        assert_eq!(std::fs::read_to_string(root.join("README.md")).unwrap(), "Call load_config first.\n");
+        assert!(matches!(store.apply(&preview.id, &checkpoints), Err(RefactorError::NotFound(_))));
+
+        checkpoints.revert_session(&preview.id).unwrap();
+        assert!(std::fs::read_to_string(root.join("src/lib.rs")).unwrap().starts_with("fn load_config"));
+    }
+
+    #[test]
+    fn test_stale_preview_is_refused() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.txt"), "old value\n").unwrap();
+
+        let store = RefactorStore::default();
+        let preview = store.preview(dir.path(), spec("old", "new"), None).unwrap();
+        std::fs::write(dir.path().join("a.txt"), "old value, edited\n").unwrap();
+
+        let checkpoints = CheckpointManager::new(dir.path().join(".checkpoints"));
+        assert!(matches!(store.apply(&preview.id, &checkpoints), Err(RefactorError::Stale(_))));
+        assert_eq!(std::fs::read_to_string(dir.path().join("a.txt")).unwrap(), "old value, edited\n");
+    }
+}
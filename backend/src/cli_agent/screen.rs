@@ -0,0 +1,37 @@
+//! Screen capture for the agent
+//!
+//! Like the clipboard, the screen belongs to the desktop host rather than the
+//! backend sidecar, so the `capture_screen` tool goes through a
+//! [`ScreenCapture`] supplied by the Tauri shell. Captures are saved as PNG
+//! files and returned as image attachments.
+
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// What to capture
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "target", rename_all = "snake_case")]
+pub enum CaptureTarget {
+    /// A whole monitor; the primary one unless an index is given
+    Screen {
+        #[serde(default)]
+        monitor: Option<usize>,
+    },
+    /// A window whose title or app name contains `title`; the frontmost
+    /// window of another app when unset
+    Window {
+        #[serde(default)]
+        title: Option<String>,
+    },
+    /// A rectangle in screen coordinates
+    Region { x: i32, y: i32, width: u32, height: u32 },
+}
+
+/// Desktop screen as seen by the agent
+pub trait ScreenCapture: Send + Sync {
+    /// Capture `target` as encoded PNG bytes
+    fn capture(&self, target: &CaptureTarget) -> Result<Vec<u8>, String>;
+}
+
+/// Shared handle to a screen capture provider
+pub type SharedScreenCapture = Arc<dyn ScreenCapture>;
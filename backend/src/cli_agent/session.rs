@@ -4,13 +4,19 @@
 //! and tool call tracking.
 
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
-use tokio::sync::RwLock;
+use tokio::sync::{Mutex, RwLock};
 
 use super::agent::{Agent, AgentConfig, AgentState};
+use super::context_set::ContextSetStore;
+use super::export::ConversationArchive;
+use super::mailbox::{AgentMail, Mailbox};
+use super::project::{ProjectDetector, ProjectProfile};
+use super::prompt_templates::PromptTemplateStore;
 use super::tools::{ToolCall, ToolResult};
+use crate::context::ContextId;
 
 /// A message in the agent conversation
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -27,10 +33,25 @@ pub struct AgentMessage {
     /// Tool call ID this message is responding to (for tool role)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tool_call_id: Option<String>,
+    /// Files attached to the message
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<MessageAttachment>,
     /// Timestamp
     pub timestamp: u64,
 }
 
+/// File attached to a message
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MessageAttachment {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size_bytes: Option<u64>,
+}
+
 /// Message role
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -50,6 +71,7 @@ impl AgentMessage {
             content,
             tool_calls: None,
             tool_call_id: None,
+            attachments: Vec::new(),
             timestamp: current_timestamp(),
         }
     }
@@ -62,6 +84,7 @@ impl AgentMessage {
             content,
             tool_calls: None,
             tool_call_id: None,
+            attachments: Vec::new(),
             timestamp: current_timestamp(),
         }
     }
@@ -74,6 +97,7 @@ impl AgentMessage {
             content,
             tool_calls: Some(tool_calls),
             tool_call_id: None,
+            attachments: Vec::new(),
             timestamp: current_timestamp(),
         }
     }
@@ -86,6 +110,7 @@ impl AgentMessage {
             content,
             tool_calls: None,
             tool_call_id: Some(tool_call_id),
+            attachments: Vec::new(),
             timestamp: current_timestamp(),
         }
     }
@@ -98,6 +123,7 @@ impl AgentMessage {
             content,
             tool_calls: None,
             tool_call_id: None,
+            attachments: Vec::new(),
             timestamp: current_timestamp(),
         }
     }
@@ -119,25 +145,54 @@ pub struct AgentSession {
     pub created_at: u64,
     /// Last activity time
     pub last_activity: u64,
+    /// Imported sessions can be read but not continued
+    read_only: bool,
+    /// Project detected in the working directory
+    pub project: Option<ProjectProfile>,
 }
 
 impl AgentSession {
-    /// Create a new session
-    pub fn new(id: String, config: AgentConfig) -> Self {
-        let agent = Agent::with_config(format!("agent-{}", id), config);
+    /// Create a new session. When the working directory is in a Rust, Node or
+    /// Python project, the project's tools are enabled and its summary and
+    /// commands go into the system prompt.
+    pub fn new(id: String, mut config: AgentConfig) -> Self {
+        let project = ProjectDetector::new().detect(std::path::Path::new(&config.working_directory));
+        if let Some(project) = &project {
+            project.enable_tools(&mut config.enabled_tools);
+        }
+        let mut agent = Agent::with_config(format!("agent-{}", id), config);
+        agent.set_project_summary(project.as_ref().map(ProjectProfile::summary));
+        agent.set_prompt_template(PromptTemplateStore::global().resolve(None, Some(&id)));
         let now = current_timestamp();
         
         Self {
             id,
             agent,
+            project,
             messages: Vec::new(),
             pending_tool_calls: HashMap::new(),
             tool_results: HashMap::new(),
             created_at: now,
             last_activity: now,
+            read_only: false,
         }
     }
 
+    /// Recreate an exported conversation as a read-only session
+    pub fn from_archive(id: String, archive: ConversationArchive) -> Self {
+        let mut session = Self::new(id, AgentConfig::default());
+        session.messages = archive.messages;
+        session.created_at = archive.metadata.created_at;
+        session.last_activity = archive.metadata.last_activity;
+        session.read_only = true;
+        session
+    }
+
+    /// Whether the session was imported and can't receive new messages
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
     /// Initialize the session
     pub fn initialize(&mut self) -> Result<(), super::agent::AgentError> {
         self.agent.initialize()
@@ -209,9 +264,15 @@ impl AgentSession {
         &self.messages
     }
 
-    /// Get messages for API request (formatted for AI provider)
+    /// Get messages for API request (formatted for AI provider). Files the
+    /// user pinned to the conversation follow the leading system messages.
     pub fn messages_for_api(&self) -> Vec<AgentMessage> {
-        self.messages.clone()
+        let mut messages = self.messages.clone();
+        if let Some(context) = ContextSetStore::global().render(&self.id) {
+            let at = messages.iter().take_while(|m| m.role == MessageRole::System).count();
+            messages.insert(at, AgentMessage::system(context.text));
+        }
+        messages
     }
 
     /// Check if there are pending tool calls
@@ -237,6 +298,16 @@ impl AgentSession {
         self.touch();
     }
 
+    /// Messages other agent sessions sent to this one, in delivery order
+    pub fn inbox(&self) -> Vec<AgentMail> {
+        Mailbox::global().inbox(&self.id)
+    }
+
+    /// Unread messages from other agent sessions, marked as read
+    pub fn read_inbox(&self) -> Vec<AgentMail> {
+        Mailbox::global().read(&self.id)
+    }
+
     /// Get message count
     pub fn message_count(&self) -> usize {
         self.messages.len()
@@ -264,6 +335,10 @@ pub struct SessionStatus {
     pub created_at: u64,
     pub last_activity: u64,
     pub config: AgentConfig,
+    #[serde(default)]
+    pub read_only: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<ProjectProfile>,
 }
 
 impl From<&AgentSession> for SessionStatus {
@@ -276,31 +351,173 @@ impl From<&AgentSession> for SessionStatus {
             created_at: session.created_at,
             last_activity: session.last_activity,
             config: session.agent.config.clone(),
+            read_only: session.read_only,
+            project: session.project.clone(),
         }
     }
 }
 
-/// Manages multiple agent sessions
+/// Result of dispatching an inbound message
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum DispatchOutcome {
+    /// The message was delivered to the session
+    Delivered { message_id: String },
+    /// The idempotency key was already delivered; nothing was sent
+    Duplicate { message_id: String },
+}
+
+impl DispatchOutcome {
+    /// ID of the message stored for the idempotency key
+    pub fn message_id(&self) -> &str {
+        match self {
+            DispatchOutcome::Delivered { message_id } | DispatchOutcome::Duplicate { message_id } => message_id,
+        }
+    }
+}
+
+/// Idempotency keys delivered to one session, oldest first
+#[derive(Default)]
+struct DeliveredKeys {
+    order: VecDeque<String>,
+    message_ids: HashMap<String, String>,
+}
+
+/// Routes inbound messages to sessions exactly once
+///
+/// Deliveries to the same session run one at a time, and a message whose
+/// idempotency key was already delivered to that session is dropped. Keys are
+/// only recorded once delivery succeeds, so a failed delivery can be retried.
+pub struct MessageDispatcher {
+    /// Per-session delivery locks
+    locks: Mutex<HashMap<String, Arc<Mutex<DeliveredKeys>>>>,
+    /// Keys remembered per session before the oldest are forgotten
+    max_keys_per_session: usize,
+}
+
+impl MessageDispatcher {
+    /// Default number of idempotency keys remembered per session
+    pub const DEFAULT_MAX_KEYS: usize = 1024;
+
+    pub fn new() -> Self {
+        Self::with_max_keys(Self::DEFAULT_MAX_KEYS)
+    }
+
+    pub fn with_max_keys(max_keys_per_session: usize) -> Self {
+        Self {
+            locks: Mutex::new(HashMap::new()),
+            max_keys_per_session: max_keys_per_session.max(1),
+        }
+    }
+
+    /// Deliver a message to a session unless its key was already delivered.
+    /// `deliver` returns the ID of the stored message.
+    pub async fn dispatch<F, Fut, E>(
+        &self,
+        session_id: &str,
+        idempotency_key: &str,
+        deliver: F,
+    ) -> Result<DispatchOutcome, E>
+    where
+        F: FnOnce() -> Fut,
+        Fut: std::future::Future<Output = Result<String, E>>,
+    {
+        let session_lock = self
+            .locks
+            .lock()
+            .await
+            .entry(session_id.to_string())
+            .or_default()
+            .clone();
+        let mut delivered = session_lock.lock().await;
+
+        if let Some(message_id) = delivered.message_ids.get(idempotency_key) {
+            tracing::info!(
+                "Dropped duplicate message {} for session {} (already delivered as {})",
+                idempotency_key,
+                session_id,
+                message_id
+            );
+            return Ok(DispatchOutcome::Duplicate {
+                message_id: message_id.clone(),
+            });
+        }
+
+        let message_id = deliver().await?;
+        tracing::debug!(
+            "Delivered message {} to session {} as {}",
+            idempotency_key,
+            session_id,
+            message_id
+        );
+
+        delivered.order.push_back(idempotency_key.to_string());
+        delivered
+            .message_ids
+            .insert(idempotency_key.to_string(), message_id.clone());
+        while delivered.order.len() > self.max_keys_per_session {
+            if let Some(oldest) = delivered.order.pop_front() {
+                delivered.message_ids.remove(&oldest);
+            }
+        }
+
+        Ok(DispatchOutcome::Delivered { message_id })
+    }
+
+    /// Forget the delivery history of a closed session
+    pub async fn forget_session(&self, session_id: &str) {
+        self.locks.lock().await.remove(session_id);
+    }
+}
+
+impl Default for MessageDispatcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Sessions and defaults belonging to one window or conversation
+pub struct ConversationContext {
+    pub id: ContextId,
+    /// Default configuration for new sessions in this context
+    pub default_config: AgentConfig,
+    sessions: HashMap<String, AgentSession>,
+}
+
+impl ConversationContext {
+    fn new(id: ContextId, default_config: AgentConfig) -> Self {
+        Self {
+            id,
+            default_config,
+            sessions: HashMap::new(),
+        }
+    }
+}
+
+/// Manages agent sessions, isolated per conversation context
+///
+/// Session IDs only need to be unique within a context: two windows creating
+/// `session-1` get two independent sessions with their own history.
 pub struct AgentSessionManager {
-    sessions: Arc<RwLock<HashMap<String, AgentSession>>>,
-    /// Default configuration for new sessions
+    contexts: Arc<RwLock<HashMap<ContextId, ConversationContext>>>,
+    /// Default configuration for new contexts
     default_config: AgentConfig,
+    /// Deduplicates and serializes inbound user messages
+    dispatcher: MessageDispatcher,
 }
 
 impl AgentSessionManager {
     /// Create a new session manager
     pub fn new() -> Self {
-        Self {
-            sessions: Arc::new(RwLock::new(HashMap::new())),
-            default_config: AgentConfig::default(),
-        }
+        Self::with_default_config(AgentConfig::default())
     }
 
     /// Create with custom default configuration
     pub fn with_default_config(config: AgentConfig) -> Self {
         Self {
-            sessions: Arc::new(RwLock::new(HashMap::new())),
+            contexts: Arc::new(RwLock::new(HashMap::new())),
             default_config: config,
+            dispatcher: MessageDispatcher::new(),
         }
     }
 
@@ -309,20 +526,45 @@ impl AgentSessionManager {
         self.default_config = config;
     }
 
+    /// Set the default configuration for new sessions in one context
+    pub async fn set_context_config(&self, ctx: &ContextId, config: AgentConfig) {
+        let mut contexts = self.contexts.write().await;
+        match contexts.get_mut(ctx) {
+            Some(context) => context.default_config = config,
+            None => {
+                contexts.insert(ctx.clone(), ConversationContext::new(ctx.clone(), config));
+            }
+        }
+    }
+
+    /// Default configuration for new sessions in a context
+    pub async fn context_config(&self, ctx: &ContextId) -> AgentConfig {
+        let contexts = self.contexts.read().await;
+        contexts
+            .get(ctx)
+            .map(|c| c.default_config.clone())
+            .unwrap_or_else(|| self.default_config.clone())
+    }
+
     /// Create a new session
-    pub async fn create_session(&self, id: String) -> Result<SessionStatus, SessionError> {
-        self.create_session_with_config(id, self.default_config.clone()).await
+    pub async fn create_session(&self, ctx: &ContextId, id: String) -> Result<SessionStatus, SessionError> {
+        let config = self.context_config(ctx).await;
+        self.create_session_with_config(ctx, id, config).await
     }
 
     /// Create a session with custom configuration
     pub async fn create_session_with_config(
         &self,
+        ctx: &ContextId,
         id: String,
         config: AgentConfig,
     ) -> Result<SessionStatus, SessionError> {
-        let mut sessions = self.sessions.write().await;
-        
-        if sessions.contains_key(&id) {
+        let mut contexts = self.contexts.write().await;
+        let context = contexts
+            .entry(ctx.clone())
+            .or_insert_with(|| ConversationContext::new(ctx.clone(), self.default_config.clone()));
+
+        if context.sessions.contains_key(&id) {
             return Err(SessionError::AlreadyExists(id));
         }
 
@@ -330,81 +572,188 @@ impl AgentSessionManager {
         session.initialize().map_err(|e| SessionError::InitializationFailed(e.to_string()))?;
         
         let status = SessionStatus::from(&session);
-        sessions.insert(id, session);
+        context.sessions.insert(id, session);
         
         Ok(status)
     }
 
     /// Get a session by ID
-    pub async fn get_session(&self, id: &str) -> Option<SessionStatus> {
-        let sessions = self.sessions.read().await;
-        sessions.get(id).map(SessionStatus::from)
+    pub async fn get_session(&self, ctx: &ContextId, id: &str) -> Option<SessionStatus> {
+        let contexts = self.contexts.read().await;
+        contexts.get(ctx)?.sessions.get(id).map(SessionStatus::from)
     }
 
     /// Check if a session exists
-    pub async fn has_session(&self, id: &str) -> bool {
-        let sessions = self.sessions.read().await;
-        sessions.contains_key(id)
+    pub async fn has_session(&self, ctx: &ContextId, id: &str) -> bool {
+        let contexts = self.contexts.read().await;
+        contexts.get(ctx).is_some_and(|c| c.sessions.contains_key(id))
     }
 
     /// Execute a function with mutable access to a session
-    pub async fn with_session<F, R>(&self, id: &str, f: F) -> Result<R, SessionError>
+    pub async fn with_session<F, R>(&self, ctx: &ContextId, id: &str, f: F) -> Result<R, SessionError>
     where
         F: FnOnce(&mut AgentSession) -> R,
     {
-        let mut sessions = self.sessions.write().await;
-        let session = sessions.get_mut(id)
-            .ok_or_else(|| SessionError::NotFound(id.to_string()))?;
-        Ok(f(session))
+        let mut contexts = self.contexts.write().await;
+        let session = find_session(&mut contexts, ctx, id)?;
+        let before = session.state();
+        let result = f(session);
+        publish_state_change(id, before, session.state());
+        Ok(result)
     }
 
     /// Execute an async function with mutable access to a session
-    pub async fn with_session_async<F, Fut, R>(&self, id: &str, f: F) -> Result<R, SessionError>
+    pub async fn with_session_async<F, Fut, R>(&self, ctx: &ContextId, id: &str, f: F) -> Result<R, SessionError>
     where
         F: FnOnce(&mut AgentSession) -> Fut,
         Fut: std::future::Future<Output = R>,
     {
-        let mut sessions = self.sessions.write().await;
-        let session = sessions.get_mut(id)
-            .ok_or_else(|| SessionError::NotFound(id.to_string()))?;
-        Ok(f(session).await)
+        let mut contexts = self.contexts.write().await;
+        let session = find_session(&mut contexts, ctx, id)?;
+        let before = session.state();
+        let result = f(session).await;
+        publish_state_change(id, before, session.state());
+        Ok(result)
+    }
+
+    /// Add a user message to a session at most once per idempotency key
+    pub async fn send_user_message(
+        &self,
+        ctx: &ContextId,
+        id: &str,
+        idempotency_key: &str,
+        content: String,
+    ) -> Result<DispatchOutcome, SessionError> {
+        self.dispatcher
+            .dispatch(&dispatch_key(ctx, id), idempotency_key, || async {
+                let mut contexts = self.contexts.write().await;
+                let session = find_session(&mut contexts, ctx, id)?;
+                if session.is_read_only() {
+                    return Err(SessionError::ReadOnly(id.to_string()));
+                }
+                Ok(session.add_user_message(content).id)
+            })
+            .await
+    }
+
+    /// Snapshot a session for export
+    pub async fn export_session(&self, ctx: &ContextId, id: &str) -> Result<ConversationArchive, SessionError> {
+        let contexts = self.contexts.read().await;
+        contexts.get(ctx)
+            .and_then(|c| c.sessions.get(id))
+            .map(ConversationArchive::from_session)
+            .ok_or_else(|| SessionError::NotFound(id.to_string()))
+    }
+
+    /// Recreate an exported conversation as a new read-only session
+    pub async fn import_session(
+        &self,
+        ctx: &ContextId,
+        archive: ConversationArchive,
+    ) -> Result<SessionStatus, SessionError> {
+        let id = format!("imported-{}", generate_id());
+        let mut session = AgentSession::from_archive(id.clone(), archive);
+        session.initialize().map_err(|e| SessionError::InitializationFailed(e.to_string()))?;
+
+        let status = SessionStatus::from(&session);
+        self.contexts.write().await
+            .entry(ctx.clone())
+            .or_insert_with(|| ConversationContext::new(ctx.clone(), self.default_config.clone()))
+            .sessions
+            .insert(id, session);
+        Ok(status)
     }
 
     /// Remove a session
-    pub async fn remove_session(&self, id: &str) -> Result<(), SessionError> {
-        let mut sessions = self.sessions.write().await;
-        sessions.remove(id)
+    pub async fn remove_session(&self, ctx: &ContextId, id: &str) -> Result<(), SessionError> {
+        let mut contexts = self.contexts.write().await;
+        contexts.get_mut(ctx)
+            .and_then(|c| c.sessions.remove(id))
             .ok_or_else(|| SessionError::NotFound(id.to_string()))?;
+        self.dispatcher.forget_session(&dispatch_key(ctx, id)).await;
+        super::throttle::ToolThrottle::global().forget(id);
+        Mailbox::global().forget(id);
+        super::session_vars::SessionVars::global().forget(id);
         Ok(())
     }
 
-    /// List all sessions
-    pub async fn list_sessions(&self) -> Vec<SessionStatus> {
-        let sessions = self.sessions.read().await;
-        sessions.values().map(SessionStatus::from).collect()
+    /// List the sessions of a context
+    pub async fn list_sessions(&self, ctx: &ContextId) -> Vec<SessionStatus> {
+        let contexts = self.contexts.read().await;
+        contexts.get(ctx)
+            .map(|c| c.sessions.values().map(SessionStatus::from).collect())
+            .unwrap_or_default()
+    }
+
+    /// Drop a context with all its sessions (e.g. when its window closes).
+    /// Returns the number of sessions removed.
+    pub async fn close_context(&self, ctx: &ContextId) -> usize {
+        let Some(context) = self.contexts.write().await.remove(ctx) else {
+            return 0;
+        };
+        for id in context.sessions.keys() {
+            self.dispatcher.forget_session(&dispatch_key(ctx, id)).await;
+        }
+        context.sessions.len()
     }
 
-    /// Get active session count
+    /// Get active session count across all contexts
     pub async fn active_count(&self) -> usize {
-        let sessions = self.sessions.read().await;
-        sessions.values()
+        let contexts = self.contexts.read().await;
+        contexts.values()
+            .flat_map(|c| c.sessions.values())
             .filter(|s| s.state().is_active())
             .count()
     }
 
     /// Cleanup inactive sessions older than the specified duration
     pub async fn cleanup_inactive(&self, max_idle: Duration) {
-        let mut sessions = self.sessions.write().await;
+        let mut contexts = self.contexts.write().await;
         let now = current_timestamp();
         let max_idle_secs = max_idle.as_secs();
         
-        sessions.retain(|_, session| {
-            let idle_secs = now - session.last_activity;
-            idle_secs < max_idle_secs || session.state().is_active()
+        for context in contexts.values_mut() {
+            context.sessions.retain(|_, session| {
+                let idle_secs = now - session.last_activity;
+                idle_secs < max_idle_secs || session.state().is_active()
+            });
+        }
+    }
+}
+
+fn find_session<'a>(
+    contexts: &'a mut HashMap<ContextId, ConversationContext>,
+    ctx: &ContextId,
+    id: &str,
+) -> Result<&'a mut AgentSession, SessionError> {
+    contexts.get_mut(ctx)
+        .and_then(|c| c.sessions.get_mut(id))
+        .ok_or_else(|| SessionError::NotFound(id.to_string()))
+}
+
+/// Publish when a session finishes a message or fails, so the UI and desktop
+/// notifications can report it
+fn publish_state_change(id: &str, before: AgentState, after: AgentState) {
+    if before != after {
+        super::trace::TraceRecorder::global()
+            .record(id, super::trace::TraceEvent::StateChange { from: before, to: after });
+    }
+    let busy = matches!(before, AgentState::Processing | AgentState::ExecutingTool);
+    let finished = busy && after == AgentState::Ready;
+    let failed = before != AgentState::Error && after == AgentState::Error;
+    if finished || failed {
+        crate::events::publish(crate::events::Event::AgentSession {
+            session_id: id.to_string(),
+            state: after,
         });
     }
 }
 
+/// Dispatcher key of a session; session IDs are only unique per context
+fn dispatch_key(ctx: &ContextId, id: &str) -> String {
+    format!("{}/{}", ctx, id)
+}
+
 impl Default for AgentSessionManager {
     fn default() -> Self {
         Self::new()
@@ -425,6 +774,9 @@ pub enum SessionError {
     
     #[error("Session is not active")]
     NotActive,
+
+    #[error("Session is read-only: {0}")]
+    ReadOnly(String),
     
     #[error("Internal error: {0}")]
     Internal(String),
@@ -483,22 +835,125 @@ mod tests {
     #[tokio::test]
     async fn test_session_manager() {
         let manager = AgentSessionManager::new();
+        let ctx = ContextId::default();
         
         // Create session
-        let status = manager.create_session("session-1".to_string()).await.unwrap();
+        let status = manager.create_session(&ctx, "session-1".to_string()).await.unwrap();
         assert_eq!(status.id, "session-1");
         assert_eq!(status.state, AgentState::Ready);
         
         // Check exists
-        assert!(manager.has_session("session-1").await);
-        assert!(!manager.has_session("session-2").await);
+        assert!(manager.has_session(&ctx, "session-1").await);
+        assert!(!manager.has_session(&ctx, "session-2").await);
         
         // List sessions
-        let sessions = manager.list_sessions().await;
+        let sessions = manager.list_sessions(&ctx).await;
         assert_eq!(sessions.len(), 1);
         
         // Remove session
-        manager.remove_session("session-1").await.unwrap();
-        assert!(!manager.has_session("session-1").await);
+        manager.remove_session(&ctx, "session-1").await.unwrap();
+        assert!(!manager.has_session(&ctx, "session-1").await);
+    }
+
+    #[tokio::test]
+    async fn test_imported_session_is_read_only() {
+        let manager = AgentSessionManager::new();
+        let ctx = ContextId::default();
+        manager.create_session(&ctx, "session-1".to_string()).await.unwrap();
+        manager.send_user_message(&ctx, "session-1", "k1", "Hello".to_string()).await.unwrap();
+
+        let archive = manager.export_session(&ctx, "session-1").await.unwrap();
+        let json = archive.to_json();
+        let imported = manager
+            .import_session(&ctx, ConversationArchive::from_json(&json).unwrap())
+            .await
+            .unwrap();
+
+        assert!(imported.read_only);
+        assert_eq!(imported.message_count, 1);
+        assert!(matches!(
+            manager.send_user_message(&ctx, &imported.id, "k2", "More".to_string()).await,
+            Err(SessionError::ReadOnly(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_messages_are_delivered_once() {
+        let manager = Arc::new(AgentSessionManager::new());
+        let ctx = ContextId::default();
+        manager.create_session(&ctx, "session-1".to_string()).await.unwrap();
+        manager.create_session(&ctx, "session-2".to_string()).await.unwrap();
+
+        let sends = (0..8).map(|i| {
+            let manager = manager.clone();
+            let session = if i % 2 == 0 { "session-1" } else { "session-2" };
+            tokio::spawn(async move {
+                manager.send_user_message(&ContextId::default(), session, "msg-1", "Hello".to_string()).await
+            })
+        });
+        let outcomes: Vec<DispatchOutcome> = futures::future::join_all(sends)
+            .await
+            .into_iter()
+            .map(|r| r.unwrap().unwrap())
+            .collect();
+
+        let delivered = outcomes
+            .iter()
+            .filter(|o| matches!(o, DispatchOutcome::Delivered { .. }))
+            .count();
+        assert_eq!(delivered, 2);
+        for id in ["session-1", "session-2"] {
+            assert_eq!(manager.get_session(&ctx, id).await.unwrap().message_count, 1);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_contexts_are_isolated() {
+        let manager = AgentSessionManager::new();
+        let window_a = ContextId::parse("window-a").unwrap();
+        let window_b = ContextId::parse("window-b").unwrap();
+
+        let mut config_b = AgentConfig::default();
+        config_b.model = "model-b".to_string();
+        manager.set_context_config(&window_b, config_b).await;
+
+        manager.create_session(&window_a, "session-1".to_string()).await.unwrap();
+        let status_b = manager.create_session(&window_b, "session-1".to_string()).await.unwrap();
+        assert_eq!(status_b.config.model, "model-b");
+
+        // The same idempotency key is a different message in each window
+        for ctx in [&window_a, &window_b] {
+            let outcome = manager.send_user_message(ctx, "session-1", "k1", "Hello".to_string()).await.unwrap();
+            assert!(matches!(outcome, DispatchOutcome::Delivered { .. }));
+        }
+        manager.send_user_message(&window_a, "session-1", "k2", "Again".to_string()).await.unwrap();
+
+        assert_eq!(manager.get_session(&window_a, "session-1").await.unwrap().message_count, 2);
+        assert_eq!(manager.get_session(&window_b, "session-1").await.unwrap().message_count, 1);
+        assert!(manager.list_sessions(&ContextId::default()).await.is_empty());
+
+        assert_eq!(manager.close_context(&window_a).await, 1);
+        assert!(!manager.has_session(&window_a, "session-1").await);
+        assert!(manager.has_session(&window_b, "session-1").await);
+    }
+
+    #[tokio::test]
+    async fn test_failed_delivery_can_be_retried() {
+        let dispatcher = MessageDispatcher::with_max_keys(1);
+
+        let failed: Result<DispatchOutcome, String> = dispatcher
+            .dispatch("s", "k1", || async { Err("offline".to_string()) })
+            .await;
+        assert!(failed.is_err());
+
+        let retried: Result<DispatchOutcome, String> = dispatcher
+            .dispatch("s", "k1", || async { Ok("m1".to_string()) })
+            .await;
+        assert_eq!(retried.unwrap(), DispatchOutcome::Delivered { message_id: "m1".to_string() });
+
+        // Only the most recent key is remembered
+        dispatcher.dispatch("s", "k2", || async { Ok::<_, String>("m2".to_string()) }).await.unwrap();
+        let outcome = dispatcher.dispatch("s", "k1", || async { Ok::<_, String>("m3".to_string()) }).await;
+        assert_eq!(outcome.unwrap().message_id(), "m3");
     }
 }
@@ -0,0 +1,347 @@
+//! Variables of agent sessions
+//!
+//! An agent keeps values it needs across tool calls (API endpoints, paths,
+//! tokens) with the set_var and get_var tools, or the user sets them from
+//! the UI. `${name}` in a shell command is replaced by the session's value,
+//! quoted as a single word for the shell, before the command runs; names
+//! the session doesn't define are left to the shell, so `${HOME}` still
+//! works.
+//!
+//! Secret values are never shown to the model: get_var answers with a
+//! placeholder, and the value is replaced by `[REDACTED:${name}]` wherever
+//! it appears in tool output and the session's trace. Variables live in
+//! memory only and are gone when the backend restarts.
+
+use crate::cli_bridge::pty::ShellKind;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+
+/// Variables a session may hold
+pub const MAX_VARS_PER_SESSION: usize = 100;
+/// Longest value, in bytes
+pub const MAX_VALUE_LEN: usize = 16 * 1024;
+const MAX_NAME_LEN: usize = 64;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionVar {
+    pub name: String,
+    /// `None` in listings when the value is secret
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    pub secret: bool,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl SessionVar {
+    /// Copy safe to show, with a secret value left out
+    pub fn masked(&self) -> SessionVar {
+        SessionVar {
+            value: if self.secret { None } else { self.value.clone() },
+            ..self.clone()
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum VarError {
+    #[error("Variable {0} is not set")]
+    NotFound(String),
+
+    #[error("{0}")]
+    Invalid(String),
+}
+
+/// Variables of all agent sessions
+pub struct SessionVars {
+    sessions: Mutex<HashMap<String, BTreeMap<String, SessionVar>>>,
+}
+
+lazy_static::lazy_static! {
+    static ref GLOBAL_VARS: Arc<SessionVars> = Arc::new(SessionVars::new());
+}
+
+fn validate_name(name: &str) -> Result<(), VarError> {
+    let mut chars = name.chars();
+    let valid = chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && name.len() <= MAX_NAME_LEN;
+    if valid {
+        Ok(())
+    } else {
+        Err(VarError::Invalid(format!(
+            "Invalid variable name '{}'; use letters, digits and underscores, up to {} characters",
+            name, MAX_NAME_LEN
+        )))
+    }
+}
+
+impl SessionVars {
+    pub fn new() -> Self {
+        Self { sessions: Mutex::new(HashMap::new()) }
+    }
+
+    /// Shared store, so values set through the API reach the executor
+    pub fn global() -> Arc<SessionVars> {
+        GLOBAL_VARS.clone()
+    }
+
+    /// Set a variable, replacing any value it had
+    pub fn set(&self, session_id: &str, name: &str, value: &str, secret: bool) -> Result<SessionVar, VarError> {
+        validate_name(name)?;
+        if value.len() > MAX_VALUE_LEN {
+            return Err(VarError::Invalid(format!("Value is longer than {} bytes", MAX_VALUE_LEN)));
+        }
+        let mut sessions = self.sessions.lock().unwrap();
+        let vars = sessions.entry(session_id.to_string()).or_default();
+        if !vars.contains_key(name) && vars.len() >= MAX_VARS_PER_SESSION {
+            return Err(VarError::Invalid(format!(
+                "A session can hold at most {} variables; remove some first",
+                MAX_VARS_PER_SESSION
+            )));
+        }
+        let var = SessionVar {
+            name: name.to_string(),
+            value: Some(value.to_string()),
+            secret,
+            updated_at: Utc::now(),
+        };
+        vars.insert(name.to_string(), var.clone());
+        Ok(var)
+    }
+
+    /// A variable with its value, secret or not
+    pub fn get(&self, session_id: &str, name: &str) -> Result<SessionVar, VarError> {
+        self.sessions
+            .lock()
+            .unwrap()
+            .get(session_id)
+            .and_then(|vars| vars.get(name))
+            .cloned()
+            .ok_or_else(|| VarError::NotFound(name.to_string()))
+    }
+
+    /// Variables of a session by name, secret values left out
+    pub fn list(&self, session_id: &str) -> Vec<SessionVar> {
+        self.sessions
+            .lock()
+            .unwrap()
+            .get(session_id)
+            .map(|vars| vars.values().map(SessionVar::masked).collect())
+            .unwrap_or_default()
+    }
+
+    pub fn remove(&self, session_id: &str, name: &str) -> Result<(), VarError> {
+        let mut sessions = self.sessions.lock().unwrap();
+        sessions
+            .get_mut(session_id)
+            .and_then(|vars| vars.remove(name))
+            .map(|_| ())
+            .ok_or_else(|| VarError::NotFound(name.to_string()))
+    }
+
+    /// Drop every variable of a session
+    pub fn forget(&self, session_id: &str) {
+        self.sessions.lock().unwrap().remove(session_id);
+    }
+
+    /// Replace `${name}` with the values of the session's variables, each
+    /// quoted so `shell` reads it as one literal word
+    pub fn interpolate(&self, session_id: &str, text: &str, shell: ShellKind) -> String {
+        let sessions = self.sessions.lock().unwrap();
+        let Some(vars) = sessions.get(session_id).filter(|vars| !vars.is_empty()) else {
+            return text.to_string();
+        };
+        let mut out = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(start) = rest.find("${") {
+            out.push_str(&rest[..start]);
+            let after = &rest[start + 2..];
+            let value = after
+                .find('}')
+                .and_then(|end| vars.get(&after[..end]).map(|var| (end, var)))
+                .and_then(|(end, var)| var.value.as_deref().map(|value| (end, value)));
+            match value {
+                Some((end, value)) => {
+                    out.push_str(&shell.quote(value));
+                    rest = &after[end + 1..];
+                }
+                None => {
+                    out.push_str("${");
+                    rest = after;
+                }
+            }
+        }
+        out.push_str(rest);
+        out
+    }
+
+    /// Secret values of a session with their names, longest first so a
+    /// value containing another is replaced whole
+    fn secrets(&self, session_id: &str) -> Vec<(String, String)> {
+        let sessions = self.sessions.lock().unwrap();
+        let mut secrets: Vec<(String, String)> = sessions
+            .get(session_id)
+            .into_iter()
+            .flat_map(|vars| vars.values())
+            .filter(|var| var.secret)
+            .filter_map(|var| Some((var.name.clone(), var.value.clone()?)))
+            .filter(|(_, value)| !value.is_empty())
+            .collect();
+        secrets.sort_by_key(|(_, value)| std::cmp::Reverse(value.len()));
+        secrets
+    }
+
+    /// Replace the session's secret values in `text`; returns how many
+    /// were replaced
+    pub fn redact(&self, session_id: &str, text: &mut String) -> usize {
+        redact_text(&self.secrets(session_id), text)
+    }
+
+    /// Replace the session's secret values in every string of `value`, and
+    /// the value argument of set_var calls that set a secret
+    pub fn redact_value(&self, session_id: &str, value: &mut Value) -> usize {
+        redact_json(&self.secrets(session_id), value)
+    }
+}
+
+impl Default for SessionVars {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn redact_text(secrets: &[(String, String)], text: &mut String) -> usize {
+    let mut count = 0;
+    for (name, value) in secrets {
+        let found = text.matches(value.as_str()).count();
+        if found > 0 {
+            *text = text.replace(value.as_str(), &format!("[REDACTED:${{{}}}]", name));
+            count += found;
+        }
+    }
+    count
+}
+
+fn redact_json(secrets: &[(String, String)], value: &mut Value) -> usize {
+    match value {
+        Value::String(text) => redact_text(secrets, text),
+        Value::Array(items) => items.iter_mut().map(|item| redact_json(secrets, item)).sum(),
+        Value::Object(object) => {
+            let mut count = 0;
+            // A secret being set isn't known to the store until the call has run
+            if object.get("name").and_then(Value::as_str) == Some("set_var") {
+                if let Some(arguments) = object.get_mut("arguments") {
+                    count += redact_set_var(arguments);
+                }
+            }
+            count + object.values_mut().map(|item| redact_json(secrets, item)).sum::<usize>()
+        }
+        _ => 0,
+    }
+}
+
+/// Hide the value of a set_var call's arguments when it sets a secret;
+/// arguments may be an object or its JSON text
+fn redact_set_var(arguments: &mut Value) -> usize {
+    if let Value::String(text) = arguments {
+        let Ok(mut parsed) = serde_json::from_str::<Value>(text) else {
+            return 0;
+        };
+        let count = redact_set_var(&mut parsed);
+        if count > 0 {
+            *text = parsed.to_string();
+        }
+        return count;
+    }
+    if arguments.get("secret").and_then(Value::as_bool) != Some(true) {
+        return 0;
+    }
+    let name = arguments.get("name").and_then(Value::as_str).unwrap_or("").to_string();
+    match arguments.get_mut("value") {
+        Some(value @ Value::String(_)) => {
+            *value = Value::String(format!("[REDACTED:${{{}}}]", name));
+            1
+        }
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_set_get_and_interpolate() {
+        let vars = SessionVars::new();
+        vars.set("s1", "API_URL", "https://api.example.com", false).unwrap();
+        vars.set("s1", "token", "tok-123", true).unwrap();
+        assert!(vars.set("s1", "2fast", "x", false).is_err());
+        assert!(vars.set("s1", "bad-name", "x", false).is_err());
+
+        assert_eq!(
+            vars.interpolate("s1", "curl -H 'Authorization: ${token}' ${API_URL}/v1 ${HOME} ${unclosed", ShellKind::Posix),
+            "curl -H 'Authorization: tok-123' https://api.example.com/v1 ${HOME} ${unclosed"
+        );
+        assert_eq!(vars.interpolate("s2", "echo ${token}", ShellKind::Posix), "echo ${token}");
+
+        let listed = vars.list("s1");
+        assert_eq!(listed.iter().map(|v| v.name.as_str()).collect::<Vec<_>>(), ["API_URL", "token"]);
+        assert_eq!(listed[1].value, None);
+        assert_eq!(vars.get("s1", "token").unwrap().value.as_deref(), Some("tok-123"));
+
+        vars.remove("s1", "token").unwrap();
+        assert!(matches!(vars.get("s1", "token"), Err(VarError::NotFound(_))));
+        vars.forget("s1");
+        assert!(vars.list("s1").is_empty());
+    }
+
+    #[test]
+    fn test_interpolated_values_stay_one_word() {
+        let vars = SessionVars::new();
+        let value = "x; echo pwned $(id) `id` && it's | tee";
+        vars.set("s1", "msg", value, false).unwrap();
+
+        let command = vars.interpolate("s1", "printf %s ${msg}", ShellKind::Posix);
+        assert_eq!(command, "printf %s 'x; echo pwned $(id) `id` && it'\\''s | tee'");
+        assert_eq!(
+            vars.interpolate("s1", "Write-Output ${msg}", ShellKind::Pwsh),
+            "Write-Output 'x; echo pwned $(id) `id` && it''s | tee'"
+        );
+
+        #[cfg(unix)]
+        {
+            let output = std::process::Command::new("/bin/sh").args(["-c", &command]).output().unwrap();
+            assert_eq!(String::from_utf8_lossy(&output.stdout), value);
+        }
+    }
+
+    #[test]
+    fn test_secrets_are_redacted() {
+        let vars = SessionVars::new();
+        vars.set("s1", "token", "tok-123", true).unwrap();
+        vars.set("s1", "user", "alice", false).unwrap();
+
+        let mut output = "logged in as alice with tok-123".to_string();
+        assert_eq!(vars.redact("s1", &mut output), 1);
+        assert_eq!(output, "logged in as alice with [REDACTED:${token}]");
+
+        let mut event = json!({
+            "kind": "model_response",
+            "content": "Saved tok-123",
+            "tool_calls": [
+                { "id": "1", "name": "set_var", "arguments": { "name": "db", "value": "pw-9", "secret": true } },
+                { "id": "2", "name": "set_var", "arguments": "{\"name\":\"key\",\"value\":\"k-1\",\"secret\":true}" },
+                { "id": "3", "name": "set_var", "arguments": { "name": "host", "value": "db.local" } },
+            ],
+        });
+        assert_eq!(vars.redact_value("s1", &mut event), 3);
+        assert_eq!(event["content"], "Saved [REDACTED:${token}]");
+        assert_eq!(event["tool_calls"][0]["arguments"]["value"], "[REDACTED:${db}]");
+        assert!(!event["tool_calls"][1]["arguments"].as_str().unwrap().contains("k-1"));
+        assert_eq!(event["tool_calls"][2]["arguments"]["value"], "db.local");
+    }
+}
@@ -0,0 +1,687 @@
+//! Test runs for the agent's `run_tests` tool
+//!
+//! Picks the test command of the project around a directory (cargo, npm,
+//! pnpm, yarn, bun, pytest or unittest), runs it to completion and parses
+//! pass/fail counts, failing test names and their messages out of the
+//! output, so an agent iterating on failures doesn't have to read raw logs.
+//! Output formats are recognized independently of the runner, since a
+//! `package.json` test script can start jest, vitest or mocha.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Output;
+use std::time::{Duration, Instant};
+use tokio::process::Command;
+
+use super::executor::ExecutorError;
+use super::project::{CommandPurpose, ProjectDetector, ProjectKind};
+use crate::cli_bridge::environment::shell_invocation;
+use crate::cli_bridge::CliError;
+
+/// Time allowed for a run unless the call asks for more or less
+pub const DEFAULT_TIMEOUT_MS: u64 = 10 * 60 * 1000;
+
+/// Failing tests reported; the rest are only counted
+const MAX_FAILURES: usize = 50;
+
+/// Lines of a failure message kept
+const MAX_MESSAGE_LINES: usize = 12;
+
+/// Lines of output returned when the run didn't pass
+const OUTPUT_TAIL_LINES: usize = 40;
+
+/// Compiler errors reported when the tests didn't build
+const MAX_BUILD_ERRORS: usize = 5;
+
+lazy_static::lazy_static! {
+    static ref ANSI: Regex = Regex::new(r"\x1b\[[0-9;?]*[A-Za-z]").unwrap();
+    static ref LIBTEST_RESULT: Regex =
+        Regex::new(r"^test result: \w+\. (\d+) passed; (\d+) failed; (\d+) ignored").unwrap();
+    static ref LIBTEST_FAILED: Regex = Regex::new(r"^test (.+?) \.\.\. FAILED").unwrap();
+    static ref PYTEST_SUMMARY: Regex = Regex::new(r"^=*\s*((?:\d+ \w+,? ?)+) in [\d.]+s").unwrap();
+    static ref PYTEST_FAILED: Regex = Regex::new(r"^(?:FAILED|ERROR) (\S+)(?: - (.*))?$").unwrap();
+    static ref JS_TESTS: Regex = Regex::new(r"^\s*Tests:?\s+(.*\d+ (?:passed|failed).*)$").unwrap();
+    static ref JEST_FAILED: Regex = Regex::new(r"^\s*● (.+)$").unwrap();
+    static ref VITEST_FAILED: Regex = Regex::new(r"^\s*(?:FAIL|×)\s+(.+ > .+?)(?:\s+\d+ms)?$").unwrap();
+    static ref UNITTEST_RAN: Regex = Regex::new(r"^Ran (\d+) tests? in").unwrap();
+    static ref UNITTEST_COUNT: Regex = Regex::new(r"(\w+)=(\d+)").unwrap();
+    static ref UNITTEST_FAILED: Regex = Regex::new(r"^(?:FAIL|ERROR): (\S+) \((.+)\)").unwrap();
+    static ref MOCHA_COUNT: Regex = Regex::new(r"^\s+(\d+) (passing|failing|pending)").unwrap();
+    static ref MOCHA_FAILED: Regex = Regex::new(r"^\s+\d+\) (.+?):?$").unwrap();
+    static ref COUNT: Regex = Regex::new(r"(\d+) ([a-z]+)").unwrap();
+}
+
+/// Tools that run tests
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TestRunner {
+    Cargo,
+    Npm,
+    Pnpm,
+    Yarn,
+    Bun,
+    Pytest,
+    Unittest,
+}
+
+impl TestRunner {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "cargo" => Some(Self::Cargo),
+            "npm" => Some(Self::Npm),
+            "pnpm" => Some(Self::Pnpm),
+            "yarn" => Some(Self::Yarn),
+            "bun" => Some(Self::Bun),
+            "pytest" => Some(Self::Pytest),
+            "unittest" => Some(Self::Unittest),
+            _ => None,
+        }
+    }
+
+    /// Runner of a project test command
+    fn of_command(command: &str) -> Option<Self> {
+        if command.contains("pytest") {
+            return Some(Self::Pytest);
+        }
+        if command.contains("unittest") {
+            return Some(Self::Unittest);
+        }
+        command.split_whitespace().next().and_then(Self::parse)
+    }
+
+    fn kind(&self) -> ProjectKind {
+        match self {
+            Self::Cargo => ProjectKind::Rust,
+            Self::Npm | Self::Pnpm | Self::Yarn | Self::Bun => ProjectKind::Node,
+            Self::Pytest | Self::Unittest => ProjectKind::Python,
+        }
+    }
+
+    /// Command used when the project doesn't name one
+    fn default_command(&self) -> &'static str {
+        match self {
+            Self::Cargo => "cargo test",
+            Self::Npm => "npm test",
+            Self::Pnpm => "pnpm test",
+            Self::Yarn => "yarn test",
+            Self::Bun => "bun run test",
+            Self::Pytest => "pytest",
+            Self::Unittest => "python -m unittest",
+        }
+    }
+
+    /// `base` with the arguments that select tests matching `filter`
+    pub fn command(&self, base: &str, filter: Option<&str>) -> String {
+        let mut command = base.to_string();
+        if *self == Self::Pytest {
+            // Summary lines naming every failure and error
+            command.push_str(" -rfE");
+        }
+        if let Some(filter) = filter {
+            let filter = quote(filter);
+            match self {
+                Self::Npm => command.push_str(&format!(" -- {}", filter)),
+                Self::Pytest => command.push_str(&format!(" -k {}", filter)),
+                _ => command.push_str(&format!(" {}", filter)),
+            }
+        }
+        command
+    }
+}
+
+/// Quote a shell argument unless it's plainly safe
+fn quote(arg: &str) -> String {
+    let safe = !arg.is_empty()
+        && arg.chars().all(|c| c.is_ascii_alphanumeric() || "_-.:/=@".contains(c));
+    if safe {
+        arg.to_string()
+    } else if cfg!(target_os = "windows") {
+        format!("\"{}\"", arg.replace('"', "\\\""))
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+/// Pass/fail counts of a run
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TestCounts {
+    pub passed: usize,
+    pub failed: usize,
+    pub skipped: usize,
+    pub total: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TestFailure {
+    pub name: String,
+    /// Assertion or panic message, when the output has one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// What a test run's output says
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ParsedTestOutput {
+    /// `None` when no known summary format was found
+    pub counts: Option<TestCounts>,
+    pub failures: Vec<TestFailure>,
+    /// Compiler errors, when the tests didn't build
+    pub build_errors: Vec<String>,
+}
+
+/// Result of the `run_tests` tool
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestRun {
+    pub runner: TestRunner,
+    pub command: String,
+    pub directory: PathBuf,
+    /// Whether the command exited successfully with no failed tests
+    pub passed: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    /// `None` when the output's format wasn't recognized; see `output_tail`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub counts: Option<TestCounts>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub failures: Vec<TestFailure>,
+    /// Whether failures past the first ones were left out
+    #[serde(default)]
+    pub failures_truncated: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub build_errors: Vec<String>,
+    /// Last lines of output, when the run didn't pass
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_tail: Option<String>,
+    pub duration_ms: u64,
+}
+
+/// The directory to run in, the runner and the base test command for `dir`.
+/// The project's own test command is used unless `runner` asks for another.
+pub fn resolve(dir: &Path, runner: Option<TestRunner>) -> Result<(PathBuf, TestRunner, String), ExecutorError> {
+    let project = ProjectDetector::new().detect(dir);
+    let tests: Vec<(TestRunner, &str, &str)> = project
+        .iter()
+        .flat_map(|project| &project.commands)
+        .filter(|command| command.purpose == CommandPurpose::Test)
+        .filter_map(|command| {
+            TestRunner::of_command(&command.command).map(|r| (r, command.name.as_str(), command.command.as_str()))
+        })
+        .collect();
+
+    let found = match runner {
+        Some(runner) => tests
+            .iter()
+            .find(|(candidate, _, _)| *candidate == runner)
+            .map(|(_, _, command)| (runner, command.to_string()))
+            .or_else(|| {
+                // Another Node package manager or Python test tool than the project's
+                tests
+                    .iter()
+                    .any(|(candidate, _, _)| candidate.kind() == runner.kind())
+                    .then(|| (runner, runner.default_command().to_string()))
+            }),
+        // The script called `test` before `test:unit` and the like
+        None => tests
+            .iter()
+            .find(|(_, name, _)| *name == "test")
+            .or_else(|| tests.first())
+            .map(|(runner, _, command)| (*runner, command.to_string())),
+    };
+
+    match (found, runner) {
+        (Some((runner, command)), _) => {
+            let root = project.map(|project| project.root).unwrap_or_else(|| dir.to_path_buf());
+            Ok((root, runner, command))
+        }
+        (None, Some(runner)) => Ok((dir.to_path_buf(), runner, runner.default_command().to_string())),
+        (None, None) => Err(ExecutorError::InvalidArgument(format!(
+            "No test command found for {}; pass `runner` (cargo, npm, pnpm, yarn, bun, pytest or unittest)",
+            dir.display()
+        ))),
+    }
+}
+
+/// Run `command` in `dir` through `shell` and parse its output
+pub async fn run(
+    runner: TestRunner,
+    command: String,
+    dir: PathBuf,
+    shell: Option<&str>,
+    env: Vec<(String, String)>,
+    timeout_ms: u64,
+) -> Result<TestRun, ExecutorError> {
+    let start = Instant::now();
+    let output = run_command(&command, &dir, shell, env, timeout_ms).await?;
+    let duration_ms = start.elapsed().as_millis() as u64;
+
+    let mut text = String::from_utf8_lossy(&output.stdout).to_string();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !stderr.trim().is_empty() {
+        text.push('\n');
+        text.push_str(&stderr);
+    }
+    let text = ANSI.replace_all(&text, "").to_string();
+    let parsed = parse_output(&text);
+
+    let passed = output.status.success() && parsed.counts.as_ref().is_none_or(|counts| counts.failed == 0);
+    let output_tail = (!passed).then(|| {
+        let lines: Vec<&str> = text.trim_end().lines().collect();
+        lines[lines.len().saturating_sub(OUTPUT_TAIL_LINES)..].join("\n")
+    });
+    let failures_truncated = parsed.failures.len() > MAX_FAILURES;
+    let mut failures = parsed.failures;
+    failures.truncate(MAX_FAILURES);
+
+    Ok(TestRun {
+        runner,
+        command,
+        directory: dir,
+        passed,
+        exit_code: output.status.code(),
+        counts: parsed.counts,
+        failures,
+        failures_truncated,
+        build_errors: parsed.build_errors,
+        output_tail,
+        duration_ms,
+    })
+}
+
+/// Run `command` through `shell` in `dir` and wait for it to exit. Tools
+/// that check for CI or a terminal are asked for plain, non-interactive output.
+pub(crate) async fn run_command(
+    command: &str,
+    dir: &Path,
+    shell: Option<&str>,
+    env: Vec<(String, String)>,
+    timeout_ms: u64,
+) -> Result<Output, ExecutorError> {
+    let (program, args) = shell_invocation(shell, command);
+    let child = Command::new(&program)
+        .args(&args)
+        .current_dir(dir)
+        .envs(env)
+        .env("CI", "1")
+        .env("NO_COLOR", "1")
+        .kill_on_drop(true)
+        .output();
+    tokio::time::timeout(Duration::from_millis(timeout_ms), child)
+        .await
+        .map_err(|_| ExecutorError::Timeout(timeout_ms))?
+        .map_err(|e| ExecutorError::CliBridge(CliError::SpawnFailed(format!("{}: {}", command, e))))
+}
+
+/// Counts and failures from the output of any supported test tool
+pub fn parse_output(output: &str) -> ParsedTestOutput {
+    let lines: Vec<&str> = output.lines().collect();
+    let mut parsed = parse_libtest(&lines)
+        .or_else(|| parse_pytest(&lines))
+        .or_else(|| parse_js(&lines))
+        .or_else(|| parse_unittest(&lines))
+        .or_else(|| parse_mocha(&lines))
+        .unwrap_or_default();
+    if parsed.counts.is_none() {
+        parsed.build_errors = rust_build_errors(&lines);
+    }
+    parsed
+}
+
+fn count(captures: &regex::Captures, group: usize) -> usize {
+    captures[group].parse().unwrap_or(0)
+}
+
+/// Counts written as "3 passed, 1 failed" or "1 failed | 4 passed (5)"
+fn counts_from_words(text: &str) -> TestCounts {
+    let mut counts = TestCounts::default();
+    let mut total = None;
+    for captures in COUNT.captures_iter(text) {
+        let n = count(&captures, 1);
+        match &captures[2] {
+            "passed" | "passing" | "xfailed" => counts.passed += n,
+            "failed" | "failing" | "error" | "errors" | "xpassed" => counts.failed += n,
+            "skipped" | "pending" | "todo" | "deselected" => counts.skipped += n,
+            "total" => total = Some(n),
+            _ => {}
+        }
+    }
+    counts.total = total.unwrap_or(counts.passed + counts.failed + counts.skipped);
+    counts
+}
+
+fn push_failure(failures: &mut Vec<TestFailure>, name: &str, message: Option<String>) {
+    let name = name.trim();
+    if !name.is_empty() && !failures.iter().any(|failure| failure.name == name) {
+        failures.push(TestFailure {
+            name: name.to_string(),
+            message: message.filter(|message| !message.is_empty()),
+        });
+    }
+}
+
+/// Keep the first lines of a message
+fn clip(lines: &[&str]) -> String {
+    lines
+        .iter()
+        .take(MAX_MESSAGE_LINES)
+        .copied()
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string()
+}
+
+/// Rust's libtest, as printed by `cargo test`; one summary per test binary
+fn parse_libtest(lines: &[&str]) -> Option<ParsedTestOutput> {
+    let mut counts: Option<TestCounts> = None;
+    let mut failures = Vec::new();
+    for line in lines {
+        if let Some(captures) = LIBTEST_RESULT.captures(line) {
+            let counts = counts.get_or_insert_with(TestCounts::default);
+            counts.passed += count(&captures, 1);
+            counts.failed += count(&captures, 2);
+            counts.skipped += count(&captures, 3);
+        } else if let Some(captures) = LIBTEST_FAILED.captures(line) {
+            push_failure(&mut failures, &captures[1], None);
+        }
+    }
+    let mut counts = counts?;
+    counts.total = counts.passed + counts.failed + counts.skipped;
+
+    // Captured output of each failure, between "---- name stdout ----" and
+    // the next section
+    for failure in &mut failures {
+        let header = format!("---- {} stdout ----", failure.name);
+        let Some(start) = lines.iter().position(|line| line.trim() == header) else {
+            continue;
+        };
+        let body: Vec<&str> = lines[start + 1..]
+            .iter()
+            .take_while(|line| !line.starts_with("---- ") && line.trim() != "failures:")
+            .filter(|line| !line.starts_with("note: run with `RUST_BACKTRACE"))
+            .copied()
+            .collect();
+        let message = clip(&body);
+        failure.message = Some(message).filter(|message| !message.is_empty());
+    }
+
+    Some(ParsedTestOutput { counts: Some(counts), failures, build_errors: Vec::new() })
+}
+
+fn parse_pytest(lines: &[&str]) -> Option<ParsedTestOutput> {
+    let summary = lines.iter().rev().find_map(|line| PYTEST_SUMMARY.captures(line.trim()))?;
+    let counts = counts_from_words(&summary[1]);
+    let mut failures = Vec::new();
+    for line in lines {
+        if let Some(captures) = PYTEST_FAILED.captures(line) {
+            push_failure(&mut failures, &captures[1], captures.get(2).map(|m| m.as_str().to_string()));
+        }
+    }
+    Some(ParsedTestOutput { counts: Some(counts), failures, build_errors: Vec::new() })
+}
+
+/// Jest ("Tests: 1 failed, 4 passed, 5 total") and vitest ("Tests  1 failed | 4 passed (5)")
+fn parse_js(lines: &[&str]) -> Option<ParsedTestOutput> {
+    let summary = lines.iter().rev().find_map(|line| JS_TESTS.captures(line))?;
+    let counts = counts_from_words(&summary[1]);
+
+    let mut failures = Vec::new();
+    for (index, line) in lines.iter().enumerate() {
+        if let Some(captures) = JEST_FAILED.captures(line) {
+            if captures[1].starts_with("Test suite failed to run") {
+                continue;
+            }
+            // Jest prints the assertion after a blank line
+            let body: Vec<&str> = lines[index + 1..]
+                .iter()
+                .skip_while(|line| line.trim().is_empty())
+                .take_while(|line| !line.trim().is_empty())
+                .map(|line| line.trim())
+                .collect();
+            push_failure(&mut failures, &captures[1], Some(clip(&body)));
+        }
+    }
+    if failures.is_empty() {
+        for line in lines {
+            if let Some(captures) = VITEST_FAILED.captures(line) {
+                push_failure(&mut failures, &captures[1], None);
+            }
+        }
+    }
+    Some(ParsedTestOutput { counts: Some(counts), failures, build_errors: Vec::new() })
+}
+
+fn parse_unittest(lines: &[&str]) -> Option<ParsedTestOutput> {
+    let ran = lines.iter().find_map(|line| UNITTEST_RAN.captures(line))?;
+    let total = count(&ran, 1);
+    let mut counts = TestCounts { total, ..Default::default() };
+    if let Some(result) = lines.iter().rev().find(|line| line.starts_with("FAILED") || line.starts_with("OK")) {
+        for captures in UNITTEST_COUNT.captures_iter(result) {
+            let n = count(&captures, 2);
+            match &captures[1] {
+                "failures" | "errors" | "unexpected_successes" => counts.failed += n,
+                "skipped" | "expected_failures" => counts.skipped += n,
+                _ => {}
+            }
+        }
+    }
+    counts.passed = total.saturating_sub(counts.failed + counts.skipped);
+
+    let mut failures = Vec::new();
+    for (index, line) in lines.iter().enumerate() {
+        let Some(captures) = UNITTEST_FAILED.captures(line) else {
+            continue;
+        };
+        // Python 3.11+ prints the full name in parentheses
+        let name = if captures[2].ends_with(&captures[1]) {
+            captures[2].to_string()
+        } else {
+            format!("{}.{}", &captures[2], &captures[1])
+        };
+        // The exception is the last line of the traceback
+        let traceback: Vec<&str> = lines[index + 1..]
+            .iter()
+            .skip_while(|line| line.starts_with("-----"))
+            .take_while(|line| !line.starts_with("=====") && !line.starts_with("-----"))
+            .copied()
+            .collect();
+        let message = traceback
+            .iter()
+            .rev()
+            .find(|line| !line.trim().is_empty())
+            .map(|line| line.trim().to_string());
+        push_failure(&mut failures, &name, message);
+    }
+    Some(ParsedTestOutput { counts: Some(counts), failures, build_errors: Vec::new() })
+}
+
+fn parse_mocha(lines: &[&str]) -> Option<ParsedTestOutput> {
+    let mut counts: Option<TestCounts> = None;
+    let mut failing_at = None;
+    for (index, line) in lines.iter().enumerate() {
+        if let Some(captures) = MOCHA_COUNT.captures(line) {
+            let counts = counts.get_or_insert_with(TestCounts::default);
+            let n = count(&captures, 1);
+            match &captures[2] {
+                "passing" => counts.passed = n,
+                "failing" => {
+                    counts.failed = n;
+                    failing_at = Some(index);
+                }
+                _ => counts.skipped = n,
+            }
+        }
+    }
+    let mut counts = counts?;
+    counts.total = counts.passed + counts.failed + counts.skipped;
+
+    let mut failures = Vec::new();
+    if let Some(start) = failing_at {
+        for line in &lines[start + 1..] {
+            if let Some(captures) = MOCHA_FAILED.captures(line) {
+                push_failure(&mut failures, &captures[1], None);
+            }
+        }
+    }
+    Some(ParsedTestOutput { counts: Some(counts), failures, build_errors: Vec::new() })
+}
+
+/// `error[E…]: …` blocks from rustc, up to the blank line after each
+fn rust_build_errors(lines: &[&str]) -> Vec<String> {
+    let mut errors = Vec::new();
+    for (index, line) in lines.iter().enumerate() {
+        if !(line.starts_with("error[") || line.starts_with("error: ")) || line.starts_with("error: could not compile") {
+            continue;
+        }
+        let block: Vec<&str> = lines[index..]
+            .iter()
+            .take_while(|line| !line.trim().is_empty())
+            .copied()
+            .collect();
+        errors.push(clip(&block));
+        if errors.len() == MAX_BUILD_ERRORS {
+            break;
+        }
+    }
+    errors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(parsed: &ParsedTestOutput) -> Vec<&str> {
+        parsed.failures.iter().map(|f| f.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_parse_cargo_output() {
+        let output = "\
+running 3 tests
+test parser::tests::test_ok ... ok
+test parser::tests::test_eq ... FAILED
+test parser::tests::test_slow ... ignored
+
+failures:
+
+---- parser::tests::test_eq stdout ----
+
+thread 'parser::tests::test_eq' panicked at src/parser.rs:10:5:
+assertion `left == right` failed
+  left: 1
+ right: 2
+note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace
+
+
+failures:
+    parser::tests::test_eq
+
+test result: FAILED. 1 passed; 1 failed; 1 ignored; 0 measured; 0 filtered out; finished in 0.00s
+
+running 2 tests
+test src/lib.rs - doc (line 3) ... ok
+test src/lib.rs - other (line 9) ... ok
+
+test result: ok. 2 passed; 0 failed; 0 ignored; 0 measured; 0 filtered out; finished in 0.10s
+";
+        let parsed = parse_output(output);
+        assert_eq!(parsed.counts, Some(TestCounts { passed: 3, failed: 1, skipped: 1, total: 5 }));
+        assert_eq!(names(&parsed), vec!["parser::tests::test_eq"]);
+        assert_eq!(
+            parsed.failures[0].message.as_deref(),
+            Some("thread 'parser::tests::test_eq' panicked at src/parser.rs:10:5:\nassertion `left == right` failed\n  left: 1\n right: 2")
+        );
+
+        let broken = "   Compiling app v0.1.0\nerror[E0425]: cannot find value `x` in this scope\n --> src/lib.rs:2:5\n\nerror: could not compile `app`\n";
+        let parsed = parse_output(broken);
+        assert!(parsed.counts.is_none());
+        assert_eq!(parsed.build_errors, vec!["error[E0425]: cannot find value `x` in this scope\n --> src/lib.rs:2:5"]);
+    }
+
+    #[test]
+    fn test_parse_python_output() {
+        let pytest = "\
+tests/test_math.py .F.s
+=========================== short test summary info ============================
+FAILED tests/test_math.py::test_div - ZeroDivisionError: division by zero
+ERROR tests/test_db.py::test_conn
+=============== 1 failed, 2 passed, 1 skipped, 1 error in 0.12s ================
+";
+        let parsed = parse_output(pytest);
+        assert_eq!(parsed.counts, Some(TestCounts { passed: 2, failed: 2, skipped: 1, total: 5 }));
+        assert_eq!(names(&parsed), vec!["tests/test_math.py::test_div", "tests/test_db.py::test_conn"]);
+        assert_eq!(parsed.failures[0].message.as_deref(), Some("ZeroDivisionError: division by zero"));
+
+        let unittest = "\
+.F.
+======================================================================
+FAIL: test_add (test_calc.CalcTest.test_add)
+----------------------------------------------------------------------
+Traceback (most recent call last):
+  File \"test_calc.py\", line 6, in test_add
+    self.assertEqual(1 + 1, 3)
+AssertionError: 2 != 3
+
+----------------------------------------------------------------------
+Ran 3 tests in 0.001s
+
+FAILED (failures=1)
+";
+        let parsed = parse_output(unittest);
+        assert_eq!(parsed.counts, Some(TestCounts { passed: 2, failed: 1, skipped: 0, total: 3 }));
+        assert_eq!(names(&parsed), vec!["test_calc.CalcTest.test_add"]);
+        assert_eq!(parsed.failures[0].message.as_deref(), Some("AssertionError: 2 != 3"));
+    }
+
+    #[test]
+    fn test_parse_js_output() {
+        let jest = "\
+FAIL src/sum.test.js
+  ● math › adds numbers
+
+    expect(received).toBe(expected)
+    Expected: 3
+
+    at Object.<anonymous> (src/sum.test.js:4:20)
+
+Tests:       1 failed, 1 skipped, 4 passed, 6 total
+";
+        let parsed = parse_output(jest);
+        assert_eq!(parsed.counts, Some(TestCounts { passed: 4, failed: 1, skipped: 1, total: 6 }));
+        assert_eq!(names(&parsed), vec!["math › adds numbers"]);
+        assert_eq!(parsed.failures[0].message.as_deref(), Some("expect(received).toBe(expected)\nExpected: 3"));
+
+        let vitest = " FAIL  src/sum.test.ts > math > adds numbers\n\n Test Files  1 failed (1)\n      Tests  1 failed | 4 passed (5)\n";
+        let parsed = parse_output(vitest);
+        assert_eq!(parsed.counts, Some(TestCounts { passed: 4, failed: 1, skipped: 0, total: 5 }));
+        assert_eq!(names(&parsed), vec!["src/sum.test.ts > math > adds numbers"]);
+
+        let mocha = "  3 passing (12ms)\n  1 failing\n\n  1) Array\n       #indexOf():\n     AssertionError\n";
+        let parsed = parse_output(mocha);
+        assert_eq!(parsed.counts, Some(TestCounts { passed: 3, failed: 1, skipped: 0, total: 4 }));
+        assert_eq!(names(&parsed), vec!["Array"]);
+
+        assert_eq!(parse_output("nothing to see"), ParsedTestOutput::default());
+    }
+
+    #[test]
+    fn test_resolve_and_build_commands() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("package.json"),
+            r#"{"scripts": {"test:e2e": "playwright test", "test": "vitest"}}"#,
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("yarn.lock"), "").unwrap();
+
+        let (root, runner, command) = resolve(dir.path(), None).unwrap();
+        assert_eq!((root, runner, command.as_str()), (dir.path().canonicalize().unwrap(), TestRunner::Yarn, "yarn test"));
+        let (_, runner, command) = resolve(dir.path(), Some(TestRunner::Npm)).unwrap();
+        assert_eq!((runner, command.as_str()), (TestRunner::Npm, "npm test"));
+        assert!(resolve(&dir.path().join("missing"), None).is_err());
+
+        assert_eq!(TestRunner::Npm.command("npm test", Some("adds")), "npm test -- adds");
+        assert_eq!(TestRunner::Pytest.command("uv run pytest", Some("div and not slow")), "uv run pytest -rfE -k 'div and not slow'");
+        assert_eq!(TestRunner::Cargo.command("cargo test --workspace", Some("parser::")), "cargo test --workspace parser::");
+    }
+}
@@ -0,0 +1,336 @@
+//! Per-session throttling of agent tool calls
+//!
+//! A model stuck in a loop can fire dozens of shell or search calls a second.
+//! Every call of a session passes through its queue before it runs:
+//! - at most `max_concurrent` calls run at once, the rest wait their turn
+//! - each tool has a calls-per-minute budget; calls over budget wait for a
+//!   slot, up to `max_wait_secs`
+//! - the same tool with the same arguments `loop_threshold` times in a row
+//!   pauses the session until the user resumes it, and publishes a warning
+//!   on the event bus (`agents` topic)
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use super::tools::ToolCall;
+use crate::config::{SettingsStore, ToolLimitSettings};
+
+/// Window the per-minute budgets apply to
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Error, PartialEq)]
+pub enum ThrottleError {
+    #[error("Session is paused: {0}. Resume it to run more tools.")]
+    Paused(String),
+
+    #[error("Rate limit for {tool} reached ({per_minute} calls per minute)")]
+    RateLimited { tool: String, per_minute: u32 },
+}
+
+/// Why a session was paused
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LoopWarning {
+    pub tool: String,
+    pub repeats: u32,
+    pub message: String,
+}
+
+/// Queue state of a session
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct QueueStatus {
+    pub session_id: String,
+    pub running: usize,
+    pub queued: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub paused: Option<LoopWarning>,
+}
+
+struct SessionQueue {
+    slots: Arc<Semaphore>,
+    /// Start times of recent calls by rate limit key
+    recent: HashMap<String, VecDeque<Instant>>,
+    /// Last call and how many times in a row it was made
+    last_call: Option<(String, Value)>,
+    repeats: u32,
+    paused: Option<LoopWarning>,
+    running: usize,
+    queued: usize,
+}
+
+impl SessionQueue {
+    fn new(max_concurrent: usize) -> Self {
+        Self {
+            slots: Arc::new(Semaphore::new(max_concurrent.max(1))),
+            recent: HashMap::new(),
+            last_call: None,
+            repeats: 0,
+            paused: None,
+            running: 0,
+            queued: 0,
+        }
+    }
+}
+
+/// Held while a tool call runs; frees its slot when dropped
+pub struct ToolPermit {
+    queue: Arc<Mutex<SessionQueue>>,
+    _slot: OwnedSemaphorePermit,
+}
+
+impl Drop for ToolPermit {
+    fn drop(&mut self) {
+        let mut queue = self.queue.lock().unwrap();
+        queue.running = queue.running.saturating_sub(1);
+    }
+}
+
+/// Tool call queues of all sessions
+pub struct ToolThrottle {
+    limits: RwLock<ToolLimitSettings>,
+    sessions: Mutex<HashMap<String, Arc<Mutex<SessionQueue>>>>,
+}
+
+lazy_static::lazy_static! {
+    static ref GLOBAL_THROTTLE: Arc<ToolThrottle> =
+        Arc::new(ToolThrottle::new(SettingsStore::global().get().tool_limits));
+}
+
+/// Calls sharing a rate limit: MCP and plugin tools are budgeted together
+pub fn rate_key(tool: &str) -> &str {
+    if crate::mcp::is_mcp_tool(tool) {
+        "mcp"
+    } else if crate::plugins::is_plugin_tool(tool) {
+        "plugin"
+    } else {
+        tool
+    }
+}
+
+impl ToolThrottle {
+    pub fn new(limits: ToolLimitSettings) -> Self {
+        Self {
+            limits: RwLock::new(limits),
+            sessions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn global() -> Arc<ToolThrottle> {
+        GLOBAL_THROTTLE.clone()
+    }
+
+    /// Apply new limits; concurrency changes affect sessions started later
+    pub fn set_limits(&self, limits: ToolLimitSettings) {
+        *self.limits.write().unwrap() = limits;
+    }
+
+    fn queue(&self, session_id: &str) -> Arc<Mutex<SessionQueue>> {
+        let max_concurrent = self.limits.read().unwrap().max_concurrent;
+        self.sessions
+            .lock()
+            .unwrap()
+            .entry(session_id.to_string())
+            .or_insert_with(|| Arc::new(Mutex::new(SessionQueue::new(max_concurrent))))
+            .clone()
+    }
+
+    /// Wait for the call's turn. Fails if the session is paused, the call
+    /// completes a loop, or no rate limit slot frees up in time.
+    pub async fn acquire(&self, session_id: &str, call: &ToolCall) -> Result<ToolPermit, ThrottleError> {
+        let limits = self.limits.read().unwrap().clone();
+        let queue = self.queue(session_id);
+
+        {
+            let mut state = queue.lock().unwrap();
+            if let Some(warning) = &state.paused {
+                return Err(ThrottleError::Paused(warning.message.clone()));
+            }
+
+            let same = state
+                .last_call
+                .as_ref()
+                .is_some_and(|(name, args)| *name == call.name && *args == call.arguments);
+            state.repeats = if same { state.repeats + 1 } else { 1 };
+            state.last_call = Some((call.name.clone(), call.arguments.clone()));
+
+            if limits.loop_threshold > 0 && state.repeats >= limits.loop_threshold {
+                let warning = LoopWarning {
+                    tool: call.name.clone(),
+                    repeats: state.repeats,
+                    message: format!(
+                        "{} was called {} times in a row with the same arguments",
+                        call.name, state.repeats
+                    ),
+                };
+                tracing::warn!("Pausing agent session {}: {}", session_id, warning.message);
+                crate::events::publish(crate::events::Event::ToolLoopDetected {
+                    session_id: session_id.to_string(),
+                    tool: warning.tool.clone(),
+                    repeats: warning.repeats,
+                });
+                state.paused = Some(warning.clone());
+                return Err(ThrottleError::Paused(warning.message));
+            }
+            state.queued += 1;
+        }
+
+        let result = self.wait_for_turn(&queue, &limits, &call.name).await;
+        let mut state = queue.lock().unwrap();
+        state.queued -= 1;
+        let slot = result?;
+        state.running += 1;
+        drop(state);
+
+        Ok(ToolPermit { queue, _slot: slot })
+    }
+
+    async fn wait_for_turn(
+        &self,
+        queue: &Arc<Mutex<SessionQueue>>,
+        limits: &ToolLimitSettings,
+        tool: &str,
+    ) -> Result<OwnedSemaphorePermit, ThrottleError> {
+        let key = rate_key(tool);
+        let per_minute = limits.per_minute(key);
+        let deadline = Instant::now() + Duration::from_secs(limits.max_wait_secs);
+
+        if per_minute > 0 {
+            loop {
+                let wait = {
+                    let mut state = queue.lock().unwrap();
+                    let now = Instant::now();
+                    let recent = state.recent.entry(key.to_string()).or_default();
+                    while recent.front().is_some_and(|t| now.duration_since(*t) >= RATE_WINDOW) {
+                        recent.pop_front();
+                    }
+                    if recent.len() < per_minute as usize {
+                        recent.push_back(now);
+                        break;
+                    }
+                    RATE_WINDOW - now.duration_since(recent[0])
+                };
+                if Instant::now() + wait > deadline {
+                    return Err(ThrottleError::RateLimited {
+                        tool: key.to_string(),
+                        per_minute,
+                    });
+                }
+                tokio::time::sleep(wait).await;
+            }
+        }
+
+        let slots = queue.lock().unwrap().slots.clone();
+        slots.acquire_owned().await.map_err(|_| ThrottleError::Paused("session was closed".to_string()))
+    }
+
+    /// Let a paused session run tools again
+    pub fn resume(&self, session_id: &str) -> bool {
+        let Some(queue) = self.sessions.lock().unwrap().get(session_id).cloned() else {
+            return false;
+        };
+        let mut state = queue.lock().unwrap();
+        state.repeats = 0;
+        state.last_call = None;
+        state.paused.take().is_some()
+    }
+
+    pub fn status(&self, session_id: &str) -> QueueStatus {
+        let queue = self.sessions.lock().unwrap().get(session_id).cloned();
+        let (running, queued, paused) = match queue {
+            Some(queue) => {
+                let state = queue.lock().unwrap();
+                (state.running, state.queued, state.paused.clone())
+            }
+            None => (0, 0, None),
+        };
+        QueueStatus {
+            session_id: session_id.to_string(),
+            running,
+            queued,
+            paused,
+        }
+    }
+
+    /// Drop the queue of a finished session
+    pub fn forget(&self, session_id: &str) {
+        if let Some(queue) = self.sessions.lock().unwrap().remove(session_id) {
+            queue.lock().unwrap().slots.close();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn call(name: &str, args: Value) -> ToolCall {
+        ToolCall {
+            id: "call".to_string(),
+            name: name.to_string(),
+            arguments: args,
+        }
+    }
+
+    fn limits() -> ToolLimitSettings {
+        ToolLimitSettings {
+            max_concurrent: 1,
+            default_per_minute: 0,
+            per_minute: HashMap::from([("shell".to_string(), 2)]),
+            max_wait_secs: 0,
+            loop_threshold: 3,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_loop_detection_pauses_session() {
+        let throttle = ToolThrottle::new(limits());
+        let read = call("read_file", json!({ "path": "a.txt" }));
+
+        drop(throttle.acquire("s1", &read).await.unwrap());
+        drop(throttle.acquire("s1", &read).await.unwrap());
+        assert!(matches!(throttle.acquire("s1", &read).await, Err(ThrottleError::Paused(_))));
+        assert_eq!(throttle.status("s1").paused.unwrap().repeats, 3);
+
+        // Other calls wait too, and other sessions are unaffected
+        let other = call("read_file", json!({ "path": "b.txt" }));
+        assert!(throttle.acquire("s1", &other).await.is_err());
+        assert!(throttle.acquire("s2", &read).await.is_ok());
+
+        assert!(throttle.resume("s1"));
+        assert!(throttle.acquire("s1", &read).await.is_ok());
+        assert!(!throttle.resume("s1"));
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_and_concurrency() {
+        let throttle = Arc::new(ToolThrottle::new(limits()));
+        let first = throttle.acquire("s1", &call("shell", json!({ "command": "ls" }))).await.unwrap();
+        assert_eq!(throttle.status("s1").running, 1);
+
+        // The second call waits for the first to finish
+        let waiting = {
+            let throttle = throttle.clone();
+            tokio::spawn(async move { throttle.acquire("s1", &call("shell", json!({ "command": "pwd" }))).await.is_ok() })
+        };
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(throttle.status("s1").queued, 1);
+        drop(first);
+        assert!(waiting.await.unwrap());
+
+        // Two shell calls per minute, and no waiting allowed
+        assert_eq!(
+            throttle.acquire("s1", &call("shell", json!({ "command": "date" }))).await.err(),
+            Some(ThrottleError::RateLimited {
+                tool: "shell".to_string(),
+                per_minute: 2
+            })
+        );
+        assert!(throttle.acquire("s1", &call("list_directory", json!({}))).await.is_ok());
+        assert_eq!(rate_key("mcp__web__search"), "mcp");
+    }
+}
@@ -4,7 +4,10 @@
 //! Based on codex-main's tool architecture but simplified for Skhoot.
 
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+
+use super::artifacts::ToolAttachment;
+use super::output_parser::StructuredOutput;
 
 /// Tool definition with JSON schema for parameters
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -64,6 +67,15 @@ pub struct ToolResultMetadata {
     pub duration_ms: Option<u64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub working_directory: Option<String>,
+    /// Shell output parsed as JSON, CSV or a table
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub structured: Option<StructuredOutput>,
+    /// Secrets replaced with placeholders, by kind
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub redacted_secrets: Option<BTreeMap<String, usize>>,
+    /// Files and images to show the user next to the output
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<ToolAttachment>,
 }
 
 /// Available tool types
@@ -73,9 +85,46 @@ pub enum Tool {
     Shell,
     ReadFile,
     WriteFile,
+    DeleteFile,
+    MoveFile,
+    CopyFile,
+    RenameFile,
+    Archive,
+    Clipboard,
+    CaptureScreen,
+    HttpRequest,
     ListDirectory,
     SearchFiles,
     ApplyPatch,
+    DiffFiles,
+    RunTests,
+    Lint,
+    Format,
+    ReplaceInFiles,
+    GoToDefinition,
+    FindReferences,
+    DocumentSymbols,
+    GitStatus,
+    GitDiff,
+    GitCommit,
+    GitCheckoutBranch,
+    GitLog,
+    ListCheckpoints,
+    RevertCheckpoint,
+    RevertSession,
+    ListAttachments,
+    ReadAttachment,
+    ReadTerminal,
+    JobStatus,
+    JobOutput,
+    JobCancel,
+    SendToAgent,
+    ReadInbox,
+    SetVar,
+    GetVar,
+    TailFile,
+    Processes,
+    CreateFromTemplate,
 }
 
 impl Tool {
@@ -85,9 +134,46 @@ impl Tool {
             Tool::Shell,
             Tool::ReadFile,
             Tool::WriteFile,
+            Tool::DeleteFile,
+            Tool::MoveFile,
+            Tool::CopyFile,
+            Tool::RenameFile,
+            Tool::Archive,
+            Tool::Clipboard,
+            Tool::CaptureScreen,
+            Tool::HttpRequest,
             Tool::ListDirectory,
             Tool::SearchFiles,
             Tool::ApplyPatch,
+            Tool::DiffFiles,
+            Tool::RunTests,
+            Tool::Lint,
+            Tool::Format,
+            Tool::ReplaceInFiles,
+            Tool::GoToDefinition,
+            Tool::FindReferences,
+            Tool::DocumentSymbols,
+            Tool::GitStatus,
+            Tool::GitDiff,
+            Tool::GitCommit,
+            Tool::GitCheckoutBranch,
+            Tool::GitLog,
+            Tool::ListCheckpoints,
+            Tool::RevertCheckpoint,
+            Tool::RevertSession,
+            Tool::ListAttachments,
+            Tool::ReadAttachment,
+            Tool::ReadTerminal,
+            Tool::JobStatus,
+            Tool::JobOutput,
+            Tool::JobCancel,
+            Tool::SendToAgent,
+            Tool::ReadInbox,
+            Tool::SetVar,
+            Tool::GetVar,
+            Tool::TailFile,
+            Tool::Processes,
+            Tool::CreateFromTemplate,
         ]
     }
 
@@ -97,9 +183,46 @@ impl Tool {
             Tool::Shell => "shell",
             Tool::ReadFile => "read_file",
             Tool::WriteFile => "write_file",
+            Tool::DeleteFile => "delete_file",
+            Tool::MoveFile => "move_file",
+            Tool::CopyFile => "copy_file",
+            Tool::RenameFile => "rename_file",
+            Tool::Archive => "archive",
+            Tool::Clipboard => "clipboard",
+            Tool::CaptureScreen => "capture_screen",
+            Tool::HttpRequest => "http_request",
             Tool::ListDirectory => "list_directory",
             Tool::SearchFiles => "search_files",
             Tool::ApplyPatch => "apply_patch",
+            Tool::DiffFiles => "diff_files",
+            Tool::RunTests => "run_tests",
+            Tool::Lint => "lint",
+            Tool::Format => "format",
+            Tool::ReplaceInFiles => "replace_in_files",
+            Tool::GoToDefinition => "go_to_definition",
+            Tool::FindReferences => "find_references",
+            Tool::DocumentSymbols => "document_symbols",
+            Tool::GitStatus => "git_status",
+            Tool::GitDiff => "git_diff",
+            Tool::GitCommit => "git_commit",
+            Tool::GitCheckoutBranch => "git_checkout_branch",
+            Tool::GitLog => "git_log",
+            Tool::ListCheckpoints => "list_checkpoints",
+            Tool::RevertCheckpoint => "revert_checkpoint",
+            Tool::RevertSession => "revert_session",
+            Tool::ListAttachments => "list_attachments",
+            Tool::ReadAttachment => "read_attachment",
+            Tool::ReadTerminal => "read_terminal",
+            Tool::JobStatus => "job_status",
+            Tool::JobOutput => "job_output",
+            Tool::JobCancel => "job_cancel",
+            Tool::SendToAgent => "send_to_agent",
+            Tool::ReadInbox => "read_inbox",
+            Tool::SetVar => "set_var",
+            Tool::GetVar => "get_var",
+            Tool::TailFile => "tail_file",
+            Tool::Processes => "processes",
+            Tool::CreateFromTemplate => "create_from_template",
         }
     }
 
@@ -109,9 +232,51 @@ impl Tool {
             Tool::Shell => Self::shell_definition(),
             Tool::ReadFile => Self::read_file_definition(),
             Tool::WriteFile => Self::write_file_definition(),
+            Tool::DeleteFile => Self::delete_file_definition(),
+            Tool::MoveFile => Self::transfer_definition(
+                "move_file",
+                "Move a file or directory to a new location.",
+            ),
+            Tool::CopyFile => Self::transfer_definition(
+                "copy_file",
+                "Copy a file or directory (recursively) to a new location.",
+            ),
+            Tool::RenameFile => Self::rename_file_definition(),
+            Tool::Archive => Self::archive_definition(),
+            Tool::Clipboard => Self::clipboard_definition(),
+            Tool::CaptureScreen => Self::capture_screen_definition(),
+            Tool::HttpRequest => Self::http_request_definition(),
             Tool::ListDirectory => Self::list_directory_definition(),
             Tool::SearchFiles => Self::search_files_definition(),
             Tool::ApplyPatch => Self::apply_patch_definition(),
+            Tool::DiffFiles => Self::diff_files_definition(),
+            Tool::RunTests => Self::run_tests_definition(),
+            Tool::Lint => Self::lint_definition(),
+            Tool::Format => Self::format_definition(),
+            Tool::ReplaceInFiles => Self::replace_in_files_definition(),
+            Tool::GoToDefinition | Tool::FindReferences => Self::code_position_definition(*self),
+            Tool::DocumentSymbols => Self::document_symbols_definition(),
+            Tool::GitStatus => Self::git_status_definition(),
+            Tool::GitDiff => Self::git_diff_definition(),
+            Tool::GitCommit => Self::git_commit_definition(),
+            Tool::GitCheckoutBranch => Self::git_checkout_branch_definition(),
+            Tool::GitLog => Self::git_log_definition(),
+            Tool::ListCheckpoints => Self::list_checkpoints_definition(),
+            Tool::RevertCheckpoint => Self::revert_checkpoint_definition(),
+            Tool::RevertSession => Self::revert_session_definition(),
+            Tool::ListAttachments => Self::list_attachments_definition(),
+            Tool::ReadAttachment => Self::read_attachment_definition(),
+            Tool::ReadTerminal => Self::read_terminal_definition(),
+            Tool::JobStatus => Self::job_status_definition(),
+            Tool::JobOutput => Self::job_output_definition(),
+            Tool::JobCancel => Self::job_cancel_definition(),
+            Tool::SendToAgent => Self::send_to_agent_definition(),
+            Tool::ReadInbox => Self::read_inbox_definition(),
+            Tool::SetVar => Self::set_var_definition(),
+            Tool::GetVar => Self::get_var_definition(),
+            Tool::TailFile => Self::tail_file_definition(),
+            Tool::Processes => Self::processes_definition(),
+            Tool::CreateFromTemplate => Self::create_from_template_definition(),
         }
     }
 }
@@ -175,6 +340,18 @@ impl Tool {
             },
         );
 
+        properties.insert(
+            "background".to_string(),
+            ParameterProperty {
+                prop_type: "boolean".to_string(),
+                description: Some(
+                    "Run the command as a background job and return its job_id right away. Use for builds, test suites and servers that may outlast the timeout."
+                        .to_string(),
+                ),
+                default: Some(serde_json::json!(false)),
+            },
+        );
+
         ToolDefinition {
             name: "shell".to_string(),
             description: "Execute a shell command and return its output. Use this for running terminal commands, scripts, or system operations.".to_string(),
@@ -221,10 +398,46 @@ impl Tool {
             },
         );
 
+        properties.insert(
+            "head".to_string(),
+            ParameterProperty {
+                prop_type: "number".to_string(),
+                description: Some("Read only the first N lines".to_string()),
+                default: None,
+            },
+        );
+
+        properties.insert(
+            "tail".to_string(),
+            ParameterProperty {
+                prop_type: "number".to_string(),
+                description: Some("Read only the last N lines, e.g. the latest entries of a log".to_string()),
+                default: None,
+            },
+        );
+
+        properties.insert(
+            "offset".to_string(),
+            ParameterProperty {
+                prop_type: "number".to_string(),
+                description: Some("Byte offset to start reading at, for paging through large files".to_string()),
+                default: None,
+            },
+        );
+
+        properties.insert(
+            "limit".to_string(),
+            ParameterProperty {
+                prop_type: "number".to_string(),
+                description: Some("Number of bytes to read from offset".to_string()),
+                default: None,
+            },
+        );
+
         ToolDefinition {
             name: "read_file".to_string(),
             description:
-                "Read the contents of a file. Can read entire file or specific line ranges."
+                "Read the contents of a file. Can read the entire file, a line range, the first or last N lines, or a byte range; use one of these for large files such as logs."
                     .to_string(),
             parameters: ToolParameters {
                 param_type: "object".to_string(),
@@ -279,7 +492,7 @@ impl Tool {
         }
     }
 
-    fn list_directory_definition() -> ToolDefinition {
+    fn delete_file_definition() -> ToolDefinition {
         let mut properties = HashMap::new();
 
         properties.insert(
@@ -287,55 +500,136 @@ impl Tool {
             ParameterProperty {
                 prop_type: "string".to_string(),
                 description: Some(
-                    "Path to the directory to list (absolute or relative)".to_string(),
+                    "Path to the file or directory to delete (absolute or relative to working directory)"
+                        .to_string(),
                 ),
                 default: None,
             },
         );
 
         properties.insert(
-            "depth".to_string(),
+            "permanent".to_string(),
             ParameterProperty {
-                prop_type: "number".to_string(),
+                prop_type: "boolean".to_string(),
                 description: Some(
-                    "Maximum depth to traverse. Defaults to 1 (immediate children only)."
+                    "Delete permanently instead of moving to the trash. Only works if the user approved permanent deletion."
                         .to_string(),
                 ),
-                default: Some(serde_json::json!(1)),
+                default: Some(serde_json::json!(false)),
+            },
+        );
+
+        ToolDefinition {
+            name: "delete_file".to_string(),
+            description: "Delete a file or directory by moving it to the system trash, so the user can restore it."
+                .to_string(),
+            parameters: ToolParameters {
+                param_type: "object".to_string(),
+                properties,
+                required: vec!["path".to_string()],
+            },
+        }
+    }
+
+    fn on_conflict_property() -> ParameterProperty {
+        ParameterProperty {
+            prop_type: "string".to_string(),
+            description: Some(
+                "What to do if the destination exists: 'abort' (default), 'overwrite', or 'rename' to pick a free name like 'file (1).txt'"
+                    .to_string(),
+            ),
+            default: Some(serde_json::json!("abort")),
+        }
+    }
+
+    fn transfer_definition(name: &str, description: &str) -> ToolDefinition {
+        let mut properties = HashMap::new();
+
+        properties.insert(
+            "source".to_string(),
+            ParameterProperty {
+                prop_type: "string".to_string(),
+                description: Some(
+                    "Path to the file or directory (absolute or relative to working directory)"
+                        .to_string(),
+                ),
+                default: None,
             },
         );
 
         properties.insert(
-            "include_hidden".to_string(),
+            "destination".to_string(),
             ParameterProperty {
-                prop_type: "boolean".to_string(),
+                prop_type: "string".to_string(),
                 description: Some(
-                    "Include hidden files (starting with '.'). Defaults to false.".to_string(),
+                    "Full destination path, including the file name".to_string(),
                 ),
-                default: Some(serde_json::json!(false)),
+                default: None,
             },
         );
 
+        properties.insert("on_conflict".to_string(), Self::on_conflict_property());
+
         ToolDefinition {
-            name: "list_directory".to_string(),
-            description: "List contents of a directory with file types and sizes.".to_string(),
+            name: name.to_string(),
+            description: description.to_string(),
             parameters: ToolParameters {
                 param_type: "object".to_string(),
                 properties,
-                required: vec!["path".to_string()],
+                required: vec!["source".to_string(), "destination".to_string()],
             },
         }
     }
 
-    fn search_files_definition() -> ToolDefinition {
+    fn rename_file_definition() -> ToolDefinition {
         let mut properties = HashMap::new();
 
         properties.insert(
-            "pattern".to_string(),
+            "path".to_string(),
             ParameterProperty {
                 prop_type: "string".to_string(),
                 description: Some(
-                    "Search pattern (glob pattern for filenames or regex for content)".to_string(),
+                    "Path to the file or directory to rename (absolute or relative to working directory)"
+                        .to_string(),
+                ),
+                default: None,
+            },
+        );
+
+        properties.insert(
+            "new_name".to_string(),
+            ParameterProperty {
+                prop_type: "string".to_string(),
+                description: Some(
+                    "New file name, without a directory; use move_file to change directories"
+                        .to_string(),
+                ),
+                default: None,
+            },
+        );
+
+        properties.insert("on_conflict".to_string(), Self::on_conflict_property());
+
+        ToolDefinition {
+            name: "rename_file".to_string(),
+            description: "Rename a file or directory in place.".to_string(),
+            parameters: ToolParameters {
+                param_type: "object".to_string(),
+                properties,
+                required: vec!["path".to_string(), "new_name".to_string()],
+            },
+        }
+    }
+
+    fn archive_definition() -> ToolDefinition {
+        let mut properties = HashMap::new();
+
+        properties.insert(
+            "action".to_string(),
+            ParameterProperty {
+                prop_type: "string".to_string(),
+                description: Some(
+                    "'list' the contents, 'extract' entries to a folder, or 'create' an archive".to_string(),
                 ),
                 default: None,
             },
@@ -346,69 +640,1214 @@ impl Tool {
             ParameterProperty {
                 prop_type: "string".to_string(),
                 description: Some(
-                    "Directory to search in. Defaults to current directory.".to_string(),
+                    "Archive file (.zip, .tar, .tar.gz or .tgz); for 'create', the extension picks the format"
+                        .to_string(),
                 ),
-                default: Some(serde_json::json!(".")),
+                default: None,
             },
         );
 
-        properties.insert("search_type".to_string(), ParameterProperty {
-            prop_type: "string".to_string(),
-            description: Some("Type of search: 'filename' (glob) or 'content' (regex). Defaults to 'filename'.".to_string()),
-            default: Some(serde_json::json!("filename")),
-        });
+        properties.insert(
+            "destination".to_string(),
+            ParameterProperty {
+                prop_type: "string".to_string(),
+                description: Some("Folder to extract into ('extract' only)".to_string()),
+                default: None,
+            },
+        );
 
         properties.insert(
-            "max_results".to_string(),
+            "entries".to_string(),
             ParameterProperty {
-                prop_type: "number".to_string(),
+                prop_type: "array".to_string(),
                 description: Some(
-                    "Maximum number of results to return. Defaults to 100.".to_string(),
+                    "Archive entries to extract; a folder selects everything under it. Omit to extract everything."
+                        .to_string(),
                 ),
-                default: Some(serde_json::json!(100)),
+                default: None,
+            },
+        );
+
+        properties.insert(
+            "paths".to_string(),
+            ParameterProperty {
+                prop_type: "array".to_string(),
+                description: Some("Files and folders to add ('create' only)".to_string()),
+                default: None,
+            },
+        );
+
+        properties.insert(
+            "overwrite".to_string(),
+            ParameterProperty {
+                prop_type: "boolean".to_string(),
+                description: Some(
+                    "Replace existing files when extracting, or an existing archive when creating".to_string(),
+                ),
+                default: Some(serde_json::json!(false)),
             },
         );
 
         ToolDefinition {
-            name: "search_files".to_string(),
-            description:
-                "Search for files by name pattern or content. Returns matching file paths."
-                    .to_string(),
+            name: "archive".to_string(),
+            description: "List, extract or create zip and tar archives. Extraction refuses archive bombs and unsafe paths."
+                .to_string(),
             parameters: ToolParameters {
                 param_type: "object".to_string(),
                 properties,
-                required: vec!["pattern".to_string()],
+                required: vec!["action".to_string(), "path".to_string()],
             },
         }
     }
-}
 
-/// Registry of available tools
-#[derive(Debug, Clone)]
-pub struct ToolRegistry {
-    tools: HashMap<String, ToolDefinition>,
-    enabled: Vec<Tool>,
-}
+    fn clipboard_definition() -> ToolDefinition {
+        let mut properties = HashMap::new();
 
-impl ToolRegistry {
-    /// Create a new registry with all tools enabled
-    pub fn new() -> Self {
-        Self::with_tools(Tool::all())
+        properties.insert(
+            "action".to_string(),
+            ParameterProperty {
+                prop_type: "string".to_string(),
+                description: Some(
+                    "'read' the clipboard text, 'write_text' to copy text, or 'write_image' to copy a PNG file"
+                        .to_string(),
+                ),
+                default: None,
+            },
+        );
+
+        properties.insert(
+            "text".to_string(),
+            ParameterProperty {
+                prop_type: "string".to_string(),
+                description: Some("Text to copy ('write_text' only)".to_string()),
+                default: None,
+            },
+        );
+
+        properties.insert(
+            "path".to_string(),
+            ParameterProperty {
+                prop_type: "string".to_string(),
+                description: Some("PNG image to copy ('write_image' only)".to_string()),
+                default: None,
+            },
+        );
+
+        ToolDefinition {
+            name: "clipboard".to_string(),
+            description: "Read or write the system clipboard. Only works if the user allowed clipboard access."
+                .to_string(),
+            parameters: ToolParameters {
+                param_type: "object".to_string(),
+                properties,
+                required: vec!["action".to_string()],
+            },
+        }
     }
 
-    /// Create a registry with specific tools enabled
-    pub fn with_tools(tools: Vec<Tool>) -> Self {
-        let mut registry = HashMap::new();
-        for tool in &tools {
-            let def = tool.definition();
-            registry.insert(def.name.clone(), def);
+    fn capture_screen_definition() -> ToolDefinition {
+        let mut properties = HashMap::new();
+        let mut property = |name: &str, prop_type: &str, description: &str| {
+            properties.insert(
+                name.to_string(),
+                ParameterProperty {
+                    prop_type: prop_type.to_string(),
+                    description: Some(description.to_string()),
+                    default: None,
+                },
+            );
+        };
+
+        property("target", "string", "'screen' for a whole monitor, 'window' for one window, or 'region' for a rectangle");
+        property("monitor", "integer", "Monitor index ('screen' only); the primary monitor if omitted");
+        property("title", "string", "Part of the window title or app name ('window' only); the frontmost window if omitted");
+        property("x", "integer", "Left edge in screen coordinates ('region' only)");
+        property("y", "integer", "Top edge in screen coordinates ('region' only)");
+        property("width", "integer", "Width in pixels ('region' only)");
+        property("height", "integer", "Height in pixels ('region' only)");
+
+        ToolDefinition {
+            name: "capture_screen".to_string(),
+            description: "Take a screenshot of the screen, a window or a region and attach it as an image. Only works if the user allowed screen capture."
+                .to_string(),
+            parameters: ToolParameters {
+                param_type: "object".to_string(),
+                properties,
+                required: vec!["target".to_string()],
+            },
         }
-        Self {
-            tools: registry,
-            enabled: tools,
+    }
+
+    fn http_request_definition() -> ToolDefinition {
+        let mut properties = HashMap::new();
+        let mut property = |name: &str, prop_type: &str, description: &str| {
+            properties.insert(
+                name.to_string(),
+                ParameterProperty {
+                    prop_type: prop_type.to_string(),
+                    description: Some(description.to_string()),
+                    default: None,
+                },
+            );
+        };
+
+        property("url", "string", "The http(s) URL to request");
+        property("method", "string", "'GET' or 'POST' (default: 'GET')");
+        property("headers", "object", "Request headers as name/value pairs");
+        property("body", "string", "Request body; objects and arrays are sent as JSON");
+
+        ToolDefinition {
+            name: "http_request".to_string(),
+            description: "Call a web API over HTTP and return the status, headers and body (JSON is pretty-printed, large bodies are truncated). Only works for domains the user allowed in this session; redirects are not followed."
+                .to_string(),
+            parameters: ToolParameters {
+                param_type: "object".to_string(),
+                properties,
+                required: vec!["url".to_string()],
+            },
+        }
+    }
+
+    fn diff_files_definition() -> ToolDefinition {
+        let mut properties = HashMap::new();
+        let mut property = |name: &str, prop_type: &str, description: &str| {
+            properties.insert(
+                name.to_string(),
+                ParameterProperty {
+                    prop_type: prop_type.to_string(),
+                    description: Some(description.to_string()),
+                    default: None,
+                },
+            );
+        };
+
+        property("path", "string", "File to compare");
+        property("other_path", "string", "File to compare it with");
+        property("content", "string", "Content to compare it with instead of another file, e.g. what you intend to write");
+        property("context", "integer", "Unchanged lines shown around each change (default: 3)");
+        property("word_diff", "boolean", "Also list changed lines split into changed and unchanged words (default: false)");
+
+        ToolDefinition {
+            name: "diff_files".to_string(),
+            description: "Show a unified diff between two files, or between a file and new content, with counts of added and removed lines. Binary files are only compared for equality. Use it to check what a write would change."
+                .to_string(),
+            parameters: ToolParameters {
+                param_type: "object".to_string(),
+                properties,
+                required: vec!["path".to_string()],
+            },
         }
     }
 
+    fn run_tests_definition() -> ToolDefinition {
+        let mut properties = HashMap::new();
+        let mut property = |name: &str, prop_type: &str, description: &str| {
+            properties.insert(
+                name.to_string(),
+                ParameterProperty {
+                    prop_type: prop_type.to_string(),
+                    description: Some(description.to_string()),
+                    default: None,
+                },
+            );
+        };
+
+        property("path", "string", "Directory in the project to test (default: the working directory)");
+        property(
+            "runner",
+            "string",
+            "'cargo', 'npm', 'pnpm', 'yarn', 'bun', 'pytest' or 'unittest' (default: the project's test command)",
+        );
+        property("filter", "string", "Only run tests whose name matches this (passed to the runner's name filter)");
+        property("timeout_ms", "integer", "Time allowed for the run in milliseconds (default: 600000)");
+
+        ToolDefinition {
+            name: "run_tests".to_string(),
+            description: "Run the project's test suite and return JSON with pass/fail/skip counts, the failing tests with their assertion messages, compiler errors if the tests didn't build, and the end of the output when the run failed. Prefer this to running test commands with shell."
+                .to_string(),
+            parameters: ToolParameters {
+                param_type: "object".to_string(),
+                properties,
+                required: vec![],
+            },
+        }
+    }
+
+    fn lint_definition() -> ToolDefinition {
+        let mut properties = HashMap::new();
+        let mut property = |name: &str, prop_type: &str, description: &str| {
+            properties.insert(
+                name.to_string(),
+                ParameterProperty {
+                    prop_type: prop_type.to_string(),
+                    description: Some(description.to_string()),
+                    default: None,
+                },
+            );
+        };
+
+        property("path", "string", "Directory in the project to lint (default: the working directory)");
+        property("tool", "string", "'clippy', 'eslint' or 'ruff' (default: the linters the project uses)");
+        property("fix", "boolean", "Apply the linters' automatic fixes (requires write access; default: false)");
+        property("timeout_ms", "integer", "Time allowed for each linter in milliseconds (default: 300000)");
+
+        ToolDefinition {
+            name: "lint".to_string(),
+            description: "Run the project's linters (clippy, eslint, ruff) and return JSON diagnostics with file, line, column, severity, message and rule code. With fix, applies automatic fixes and reports what remains."
+                .to_string(),
+            parameters: ToolParameters {
+                param_type: "object".to_string(),
+                properties,
+                required: vec![],
+            },
+        }
+    }
+
+    fn format_definition() -> ToolDefinition {
+        let mut properties = HashMap::new();
+        let mut property = |name: &str, prop_type: &str, description: &str| {
+            properties.insert(
+                name.to_string(),
+                ParameterProperty {
+                    prop_type: prop_type.to_string(),
+                    description: Some(description.to_string()),
+                    default: None,
+                },
+            );
+        };
+
+        property("path", "string", "Directory in the project to format (default: the working directory)");
+        property("tool", "string", "'rustfmt', 'prettier', 'black' or 'ruff' (default: the formatters the project uses)");
+        property("apply", "boolean", "Rewrite the unformatted files (requires write access; default: false, only check)");
+        property("timeout_ms", "integer", "Time allowed for each formatter in milliseconds (default: 300000)");
+
+        ToolDefinition {
+            name: "format".to_string(),
+            description: "Check formatting with the project's formatters (rustfmt, prettier, black, ruff) and return JSON listing the files that would change. With apply, rewrites them."
+                .to_string(),
+            parameters: ToolParameters {
+                param_type: "object".to_string(),
+                properties,
+                required: vec![],
+            },
+        }
+    }
+
+    fn replace_in_files_definition() -> ToolDefinition {
+        let mut properties = HashMap::new();
+        let mut property = |name: &str, prop_type: &str, description: &str| {
+            properties.insert(
+                name.to_string(),
+                ParameterProperty {
+                    prop_type: prop_type.to_string(),
+                    description: Some(description.to_string()),
+                    default: None,
+                },
+            );
+        };
+
+        property("pattern", "string", "Text to find, or a regular expression when regex is set");
+        property("replacement", "string", "Replacement text; with regex, $1 and ${name} insert captured groups");
+        property("regex", "boolean", "Treat pattern as a regular expression (default: false)");
+        property("case_sensitive", "boolean", "Match case (default: true)");
+        property("path", "string", "Directory to search (default: the working directory)");
+        property("include", "array", "Globs of the files to change, e.g. [\"*.rs\", \"src/**/*.ts\"] (default: all files)");
+        property("exclude", "array", "Globs of files to leave alone");
+
+        ToolDefinition {
+            name: "replace_in_files".to_string(),
+            description: "Find and replace across the files of a directory. Returns a preview with a unified diff per file and a preview ID; nothing is written until the user approves the preview, and the applied change can be reverted from its checkpoint."
+                .to_string(),
+            parameters: ToolParameters {
+                param_type: "object".to_string(),
+                properties,
+                required: vec!["pattern".to_string(), "replacement".to_string()],
+            },
+        }
+    }
+
+    fn code_position_definition(tool: Tool) -> ToolDefinition {
+        let mut properties = HashMap::new();
+        let mut property = |name: &str, prop_type: &str, description: &str| {
+            properties.insert(
+                name.to_string(),
+                ParameterProperty {
+                    prop_type: prop_type.to_string(),
+                    description: Some(description.to_string()),
+                    default: None,
+                },
+            );
+        };
+
+        property("path", "string", "Source file containing the symbol");
+        property("line", "integer", "Line of the symbol (1-based)");
+        property("symbol", "string", "Name of the symbol on that line; used to find the column");
+        property("column", "integer", "Column of the symbol (1-based); overrides symbol");
+        let description = if tool == Tool::FindReferences {
+            property("include_declaration", "boolean", "Also list the declaration itself (default: false)");
+            "Find every use of a symbol across the project with the language server, returning JSON locations (file, line, column, line preview). More precise than text search when renaming or changing a signature."
+        } else {
+            "Find where a symbol is defined with the language server, returning JSON locations (file, line, column, line preview). Follows imports, re-exports and methods that text search can't."
+        };
+
+        ToolDefinition {
+            name: tool.name().to_string(),
+            description: description.to_string(),
+            parameters: ToolParameters {
+                param_type: "object".to_string(),
+                properties,
+                required: vec!["path".to_string(), "line".to_string()],
+            },
+        }
+    }
+
+    fn document_symbols_definition() -> ToolDefinition {
+        let mut properties = HashMap::new();
+        properties.insert(
+            "path".to_string(),
+            ParameterProperty {
+                prop_type: "string".to_string(),
+                description: Some("Source file to outline".to_string()),
+                default: None,
+            },
+        );
+
+        ToolDefinition {
+            name: "document_symbols".to_string(),
+            description: "List the symbols declared in a source file (functions, types, methods, fields) with their kind and line range, nested by container, using the language server."
+                .to_string(),
+            parameters: ToolParameters {
+                param_type: "object".to_string(),
+                properties,
+                required: vec!["path".to_string()],
+            },
+        }
+    }
+
+    fn list_directory_definition() -> ToolDefinition {
+        let mut properties = HashMap::new();
+
+        properties.insert(
+            "path".to_string(),
+            ParameterProperty {
+                prop_type: "string".to_string(),
+                description: Some(
+                    "Path to the directory to list (absolute or relative)".to_string(),
+                ),
+                default: None,
+            },
+        );
+
+        properties.insert(
+            "depth".to_string(),
+            ParameterProperty {
+                prop_type: "number".to_string(),
+                description: Some(
+                    "Maximum depth to traverse. Defaults to 1 (immediate children only)."
+                        .to_string(),
+                ),
+                default: Some(serde_json::json!(1)),
+            },
+        );
+
+        properties.insert(
+            "include_hidden".to_string(),
+            ParameterProperty {
+                prop_type: "boolean".to_string(),
+                description: Some(
+                    "Include hidden files (starting with '.'). Defaults to false.".to_string(),
+                ),
+                default: Some(serde_json::json!(false)),
+            },
+        );
+
+        ToolDefinition {
+            name: "list_directory".to_string(),
+            description: "List contents of a directory with file types and sizes.".to_string(),
+            parameters: ToolParameters {
+                param_type: "object".to_string(),
+                properties,
+                required: vec!["path".to_string()],
+            },
+        }
+    }
+
+    fn search_files_definition() -> ToolDefinition {
+        let mut properties = HashMap::new();
+
+        properties.insert(
+            "pattern".to_string(),
+            ParameterProperty {
+                prop_type: "string".to_string(),
+                description: Some(
+                    "Search pattern (glob pattern for filenames or regex for content)".to_string(),
+                ),
+                default: None,
+            },
+        );
+
+        properties.insert(
+            "path".to_string(),
+            ParameterProperty {
+                prop_type: "string".to_string(),
+                description: Some(
+                    "Directory to search in. Defaults to current directory.".to_string(),
+                ),
+                default: Some(serde_json::json!(".")),
+            },
+        );
+
+        properties.insert(
+            "paths".to_string(),
+            ParameterProperty {
+                prop_type: "array".to_string(),
+                description: Some(
+                    "Several directories to search at once, e.g. a project and a mounted drive. Results are merged and shown with full paths.".to_string(),
+                ),
+                default: None,
+            },
+        );
+
+        properties.insert(
+            "workspace".to_string(),
+            ParameterProperty {
+                prop_type: "string".to_string(),
+                description: Some(
+                    "Name of a search workspace (a set of directories configured by the user) to search instead of path.".to_string(),
+                ),
+                default: None,
+            },
+        );
+
+        properties.insert("search_type".to_string(), ParameterProperty {
+            prop_type: "string".to_string(),
+            description: Some("Type of search: 'filename' (glob) or 'content' (regex). Defaults to 'filename'.".to_string()),
+            default: Some(serde_json::json!("filename")),
+        });
+
+        properties.insert(
+            "max_results".to_string(),
+            ParameterProperty {
+                prop_type: "number".to_string(),
+                description: Some(
+                    "Maximum number of results to return. Defaults to 100.".to_string(),
+                ),
+                default: Some(serde_json::json!(100)),
+            },
+        );
+
+        ToolDefinition {
+            name: "search_files".to_string(),
+            description:
+                "Search for files by name pattern or content. Returns matching file paths."
+                    .to_string(),
+            parameters: ToolParameters {
+                param_type: "object".to_string(),
+                properties,
+                required: vec!["pattern".to_string()],
+            },
+        }
+    }
+
+    fn repo_path_property() -> ParameterProperty {
+        ParameterProperty {
+            prop_type: "string".to_string(),
+            description: Some(
+                "Path inside the git repository. Defaults to the working directory.".to_string(),
+            ),
+            default: None,
+        }
+    }
+
+    fn git_status_definition() -> ToolDefinition {
+        let mut properties = HashMap::new();
+
+        properties.insert("repo_path".to_string(), Self::repo_path_property());
+
+        ToolDefinition {
+            name: "git_status".to_string(),
+            description: "Show the current branch, upstream ahead/behind counts and changed files of a git repository as JSON.".to_string(),
+            parameters: ToolParameters {
+                param_type: "object".to_string(),
+                properties,
+                required: vec![],
+            },
+        }
+    }
+
+    fn git_diff_definition() -> ToolDefinition {
+        let mut properties = HashMap::new();
+
+        properties.insert("repo_path".to_string(), Self::repo_path_property());
+
+        properties.insert(
+            "staged".to_string(),
+            ParameterProperty {
+                prop_type: "boolean".to_string(),
+                description: Some(
+                    "Show staged changes instead of unstaged ones. Defaults to false.".to_string(),
+                ),
+                default: Some(serde_json::json!(false)),
+            },
+        );
+
+        properties.insert(
+            "path".to_string(),
+            ParameterProperty {
+                prop_type: "string".to_string(),
+                description: Some("Limit the diff to this file or directory".to_string()),
+                default: None,
+            },
+        );
+
+        ToolDefinition {
+            name: "git_diff".to_string(),
+            description: "Show changes in a git repository as JSON with per-file line counts and the unified diff.".to_string(),
+            parameters: ToolParameters {
+                param_type: "object".to_string(),
+                properties,
+                required: vec![],
+            },
+        }
+    }
+
+    fn git_commit_definition() -> ToolDefinition {
+        let mut properties = HashMap::new();
+
+        properties.insert("repo_path".to_string(), Self::repo_path_property());
+
+        properties.insert(
+            "message".to_string(),
+            ParameterProperty {
+                prop_type: "string".to_string(),
+                description: Some("Commit message".to_string()),
+                default: None,
+            },
+        );
+
+        properties.insert(
+            "paths".to_string(),
+            ParameterProperty {
+                prop_type: "array".to_string(),
+                description: Some(
+                    "Files to stage before committing. Already staged changes are always included."
+                        .to_string(),
+                ),
+                default: None,
+            },
+        );
+
+        properties.insert(
+            "all".to_string(),
+            ParameterProperty {
+                prop_type: "boolean".to_string(),
+                description: Some(
+                    "Stage all changes, including untracked files. Defaults to false.".to_string(),
+                ),
+                default: Some(serde_json::json!(false)),
+            },
+        );
+
+        ToolDefinition {
+            name: "git_commit".to_string(),
+            description: "Stage files and create a git commit. Returns the new commit as JSON.".to_string(),
+            parameters: ToolParameters {
+                param_type: "object".to_string(),
+                properties,
+                required: vec!["message".to_string()],
+            },
+        }
+    }
+
+    fn git_checkout_branch_definition() -> ToolDefinition {
+        let mut properties = HashMap::new();
+
+        properties.insert("repo_path".to_string(), Self::repo_path_property());
+
+        properties.insert(
+            "branch".to_string(),
+            ParameterProperty {
+                prop_type: "string".to_string(),
+                description: Some("Name of the branch to switch to".to_string()),
+                default: None,
+            },
+        );
+
+        properties.insert(
+            "create".to_string(),
+            ParameterProperty {
+                prop_type: "boolean".to_string(),
+                description: Some("Create the branch first. Defaults to false.".to_string()),
+                default: Some(serde_json::json!(false)),
+            },
+        );
+
+        ToolDefinition {
+            name: "git_checkout_branch".to_string(),
+            description: "Switch to a git branch, optionally creating it.".to_string(),
+            parameters: ToolParameters {
+                param_type: "object".to_string(),
+                properties,
+                required: vec!["branch".to_string()],
+            },
+        }
+    }
+
+    fn git_log_definition() -> ToolDefinition {
+        let mut properties = HashMap::new();
+
+        properties.insert("repo_path".to_string(), Self::repo_path_property());
+
+        properties.insert(
+            "max_count".to_string(),
+            ParameterProperty {
+                prop_type: "number".to_string(),
+                description: Some("Maximum number of commits to return. Defaults to 20.".to_string()),
+                default: Some(serde_json::json!(20)),
+            },
+        );
+
+        properties.insert(
+            "path".to_string(),
+            ParameterProperty {
+                prop_type: "string".to_string(),
+                description: Some("Only show commits touching this file or directory".to_string()),
+                default: None,
+            },
+        );
+
+        ToolDefinition {
+            name: "git_log".to_string(),
+            description: "List recent git commits as JSON (hash, author, date, subject).".to_string(),
+            parameters: ToolParameters {
+                param_type: "object".to_string(),
+                properties,
+                required: vec![],
+            },
+        }
+    }
+
+    fn list_checkpoints_definition() -> ToolDefinition {
+        ToolDefinition {
+            name: "list_checkpoints".to_string(),
+            description: "List the file checkpoints created in this session, oldest first. Each write_file or apply_patch call creates one.".to_string(),
+            parameters: ToolParameters {
+                param_type: "object".to_string(),
+                properties: HashMap::new(),
+                required: vec![],
+            },
+        }
+    }
+
+    fn revert_checkpoint_definition() -> ToolDefinition {
+        let mut properties = HashMap::new();
+
+        properties.insert(
+            "checkpoint_id".to_string(),
+            ParameterProperty {
+                prop_type: "string".to_string(),
+                description: Some("ID of the checkpoint to revert to".to_string()),
+                default: None,
+            },
+        );
+
+        ToolDefinition {
+            name: "revert_checkpoint".to_string(),
+            description: "Undo the file changes made by a checkpoint and every checkpoint after it.".to_string(),
+            parameters: ToolParameters {
+                param_type: "object".to_string(),
+                properties,
+                required: vec!["checkpoint_id".to_string()],
+            },
+        }
+    }
+
+    fn revert_session_definition() -> ToolDefinition {
+        ToolDefinition {
+            name: "revert_session".to_string(),
+            description: "Undo every file change made in this session.".to_string(),
+            parameters: ToolParameters {
+                param_type: "object".to_string(),
+                properties: HashMap::new(),
+                required: vec![],
+            },
+        }
+    }
+
+    fn list_attachments_definition() -> ToolDefinition {
+        ToolDefinition {
+            name: "list_attachments".to_string(),
+            description: "List the files the user attached to this conversation, with their IDs and number of text chunks.".to_string(),
+            parameters: ToolParameters {
+                param_type: "object".to_string(),
+                properties: HashMap::new(),
+                required: vec![],
+            },
+        }
+    }
+
+    fn read_attachment_definition() -> ToolDefinition {
+        let mut properties = HashMap::new();
+
+        properties.insert(
+            "attachment_id".to_string(),
+            ParameterProperty {
+                prop_type: "string".to_string(),
+                description: Some("ID of the attachment, from list_attachments".to_string()),
+                default: None,
+            },
+        );
+
+        properties.insert(
+            "chunk".to_string(),
+            ParameterProperty {
+                prop_type: "integer".to_string(),
+                description: Some("Zero-based chunk to read; long files are split into several chunks".to_string()),
+                default: Some(serde_json::json!(0)),
+            },
+        );
+
+        ToolDefinition {
+            name: "read_attachment".to_string(),
+            description: "Read the extracted text of a file the user attached (text files, PDFs, and images when OCR is available).".to_string(),
+            parameters: ToolParameters {
+                param_type: "object".to_string(),
+                properties,
+                required: vec!["attachment_id".to_string()],
+            },
+        }
+    }
+
+    fn job_id_property(description: &str) -> ParameterProperty {
+        ParameterProperty {
+            prop_type: "string".to_string(),
+            description: Some(description.to_string()),
+            default: None,
+        }
+    }
+
+    fn job_status_definition() -> ToolDefinition {
+        let mut properties = HashMap::new();
+        properties.insert(
+            "job_id".to_string(),
+            Self::job_id_property("Job to check; omit to list all jobs of this session"),
+        );
+
+        ToolDefinition {
+            name: "job_status".to_string(),
+            description: "Check whether a background job started with shell(background=true) is still running, and its exit code once finished.".to_string(),
+            parameters: ToolParameters {
+                param_type: "object".to_string(),
+                properties,
+                required: vec![],
+            },
+        }
+    }
+
+    fn job_output_definition() -> ToolDefinition {
+        let mut properties = HashMap::new();
+        properties.insert("job_id".to_string(), Self::job_id_property("Job to read"));
+        properties.insert(
+            "lines".to_string(),
+            ParameterProperty {
+                prop_type: "integer".to_string(),
+                description: Some("Number of most recent output lines to read".to_string()),
+                default: Some(serde_json::json!(super::jobs::DEFAULT_OUTPUT_LINES)),
+            },
+        );
+
+        ToolDefinition {
+            name: "job_output".to_string(),
+            description: "Read the latest output of a background job, whether it is still running or finished.".to_string(),
+            parameters: ToolParameters {
+                param_type: "object".to_string(),
+                properties,
+                required: vec!["job_id".to_string()],
+            },
+        }
+    }
+
+    fn job_cancel_definition() -> ToolDefinition {
+        let mut properties = HashMap::new();
+        properties.insert("job_id".to_string(), Self::job_id_property("Job to stop"));
+
+        ToolDefinition {
+            name: "job_cancel".to_string(),
+            description: "Stop a running background job. Its output so far stays readable with job_output.".to_string(),
+            parameters: ToolParameters {
+                param_type: "object".to_string(),
+                properties,
+                required: vec!["job_id".to_string()],
+            },
+        }
+    }
+
+    fn send_to_agent_definition() -> ToolDefinition {
+        let mut properties = HashMap::new();
+        properties.insert(
+            "to".to_string(),
+            ParameterProperty {
+                prop_type: "string".to_string(),
+                description: Some("Session ID of the agent to message".to_string()),
+                default: None,
+            },
+        );
+        properties.insert(
+            "message".to_string(),
+            ParameterProperty {
+                prop_type: "string".to_string(),
+                description: Some("What to tell the other agent".to_string()),
+                default: None,
+            },
+        );
+        properties.insert(
+            "reply_to".to_string(),
+            ParameterProperty {
+                prop_type: "string".to_string(),
+                description: Some("ID of the received message this answers".to_string()),
+                default: None,
+            },
+        );
+
+        ToolDefinition {
+            name: "send_to_agent".to_string(),
+            description: "Send a message to another agent running in parallel. Only message when coordination is needed; long reply chains are cut off.".to_string(),
+            parameters: ToolParameters {
+                param_type: "object".to_string(),
+                properties,
+                required: vec!["to".to_string(), "message".to_string()],
+            },
+        }
+    }
+
+    fn read_inbox_definition() -> ToolDefinition {
+        let mut properties = HashMap::new();
+        properties.insert(
+            "include_sent".to_string(),
+            ParameterProperty {
+                prop_type: "boolean".to_string(),
+                description: Some("Also list the messages this agent sent and whether they were read".to_string()),
+                default: Some(serde_json::json!(false)),
+            },
+        );
+
+        ToolDefinition {
+            name: "read_inbox".to_string(),
+            description: "Read the unread messages other agents sent to this agent, oldest first.".to_string(),
+            parameters: ToolParameters {
+                param_type: "object".to_string(),
+                properties,
+                required: vec![],
+            },
+        }
+    }
+
+    fn set_var_definition() -> ToolDefinition {
+        let mut properties = HashMap::new();
+        properties.insert(
+            "name".to_string(),
+            ParameterProperty {
+                prop_type: "string".to_string(),
+                description: Some("Variable name: letters, digits and underscores".to_string()),
+                default: None,
+            },
+        );
+        properties.insert(
+            "value".to_string(),
+            ParameterProperty {
+                prop_type: "string".to_string(),
+                description: Some("Value to keep".to_string()),
+                default: None,
+            },
+        );
+        properties.insert(
+            "secret".to_string(),
+            ParameterProperty {
+                prop_type: "boolean".to_string(),
+                description: Some("Hide the value from output and traces, e.g. for tokens and passwords".to_string()),
+                default: Some(serde_json::json!(false)),
+            },
+        );
+
+        ToolDefinition {
+            name: "set_var".to_string(),
+            description: "Remember a value for the rest of this session, such as an API endpoint, path or token. Use it in shell commands as ${name}.".to_string(),
+            parameters: ToolParameters {
+                param_type: "object".to_string(),
+                properties,
+                required: vec!["name".to_string(), "value".to_string()],
+            },
+        }
+    }
+
+    fn get_var_definition() -> ToolDefinition {
+        let mut properties = HashMap::new();
+        properties.insert(
+            "name".to_string(),
+            ParameterProperty {
+                prop_type: "string".to_string(),
+                description: Some("Variable to read; omit to list every variable of the session".to_string()),
+                default: None,
+            },
+        );
+
+        ToolDefinition {
+            name: "get_var".to_string(),
+            description: "Read a variable saved with set_var or by the user. Secret values are not shown; use them in shell commands as ${name}.".to_string(),
+            parameters: ToolParameters {
+                param_type: "object".to_string(),
+                properties,
+                required: vec![],
+            },
+        }
+    }
+
+    fn tail_file_definition() -> ToolDefinition {
+        let mut properties = HashMap::new();
+        properties.insert(
+            "path".to_string(),
+            ParameterProperty {
+                prop_type: "string".to_string(),
+                description: Some("Path to the file to follow, e.g. a build or server log".to_string()),
+                default: None,
+            },
+        );
+        properties.insert(
+            "pattern".to_string(),
+            ParameterProperty {
+                prop_type: "string".to_string(),
+                description: Some("Regular expression; only matching lines are returned".to_string()),
+                default: None,
+            },
+        );
+        properties.insert(
+            "until_match".to_string(),
+            ParameterProperty {
+                prop_type: "boolean".to_string(),
+                description: Some("Stop at the first line matching pattern. Defaults to true when a pattern is given.".to_string()),
+                default: None,
+            },
+        );
+        properties.insert(
+            "lines".to_string(),
+            ParameterProperty {
+                prop_type: "number".to_string(),
+                description: Some("Lines already at the end of the file to include first".to_string()),
+                default: Some(serde_json::json!(0)),
+            },
+        );
+        properties.insert(
+            "timeout_secs".to_string(),
+            ParameterProperty {
+                prop_type: "number".to_string(),
+                description: Some("How long to follow the file, up to 600 seconds".to_string()),
+                default: Some(serde_json::json!(60)),
+            },
+        );
+        properties.insert(
+            "max_lines".to_string(),
+            ParameterProperty {
+                prop_type: "number".to_string(),
+                description: Some("Stop after returning this many lines, up to 1000".to_string()),
+                default: Some(serde_json::json!(100)),
+            },
+        );
+
+        ToolDefinition {
+            name: "tail_file".to_string(),
+            description: "Follow a file as it grows, like tail -f, and return the new lines. Use a pattern to wait for a line such as a build finishing or a server error.".to_string(),
+            parameters: ToolParameters {
+                param_type: "object".to_string(),
+                properties,
+                required: vec!["path".to_string()],
+            },
+        }
+    }
+
+    fn processes_definition() -> ToolDefinition {
+        let mut properties = HashMap::new();
+        properties.insert(
+            "action".to_string(),
+            ParameterProperty {
+                prop_type: "string".to_string(),
+                description: Some(
+                    "'list' running processes, 'inspect' one by pid, or 'kill' one by pid".to_string(),
+                ),
+                default: Some(serde_json::json!("list")),
+            },
+        );
+        properties.insert(
+            "filter".to_string(),
+            ParameterProperty {
+                prop_type: "string".to_string(),
+                description: Some("List only processes whose name or command line contains this text".to_string()),
+                default: None,
+            },
+        );
+        properties.insert(
+            "sort".to_string(),
+            ParameterProperty {
+                prop_type: "string".to_string(),
+                description: Some("'cpu', 'memory', 'pid' or 'name'".to_string()),
+                default: Some(serde_json::json!("cpu")),
+            },
+        );
+        properties.insert(
+            "limit".to_string(),
+            ParameterProperty {
+                prop_type: "number".to_string(),
+                description: Some("Most processes listed".to_string()),
+                default: Some(serde_json::json!(15)),
+            },
+        );
+        properties.insert(
+            "pid".to_string(),
+            ParameterProperty {
+                prop_type: "number".to_string(),
+                description: Some("Process to inspect or kill".to_string()),
+                default: None,
+            },
+        );
+        properties.insert(
+            "force".to_string(),
+            ParameterProperty {
+                prop_type: "boolean".to_string(),
+                description: Some("Kill with SIGKILL instead of asking the process to exit".to_string()),
+                default: Some(serde_json::json!(false)),
+            },
+        );
+
+        ToolDefinition {
+            name: "processes".to_string(),
+            description: "List running processes with their CPU and memory use, inspect one, or kill one. \
+                Processes started by you can be killed freely; killing any other process only works if the user approved it."
+                .to_string(),
+            parameters: ToolParameters {
+                param_type: "object".to_string(),
+                properties,
+                required: vec!["action".to_string()],
+            },
+        }
+    }
+
+    fn create_from_template_definition() -> ToolDefinition {
+        let mut properties = HashMap::new();
+
+        properties.insert(
+            "template".to_string(),
+            ParameterProperty {
+                prop_type: "string".to_string(),
+                description: Some(
+                    "Template to use (built in: rust-cli, python-package, node-app); omit to list all templates and their variables"
+                        .to_string(),
+                ),
+                default: None,
+            },
+        );
+
+        properties.insert(
+            "destination".to_string(),
+            ParameterProperty {
+                prop_type: "string".to_string(),
+                description: Some("Folder to create the project in; created if missing".to_string()),
+                default: None,
+            },
+        );
+
+        properties.insert(
+            "variables".to_string(),
+            ParameterProperty {
+                prop_type: "object".to_string(),
+                description: Some("Values for the template's variables, e.g. {\"name\": \"my-app\"}".to_string()),
+                default: None,
+            },
+        );
+
+        properties.insert(
+            "overwrite".to_string(),
+            ParameterProperty {
+                prop_type: "boolean".to_string(),
+                description: Some("Replace files that already exist in the destination".to_string()),
+                default: Some(serde_json::json!(false)),
+            },
+        );
+
+        ToolDefinition {
+            name: "create_from_template".to_string(),
+            description: "Create a new project from a template: renders the template's files with the given variables into a folder.".to_string(),
+            parameters: ToolParameters {
+                param_type: "object".to_string(),
+                properties,
+                required: vec![],
+            },
+        }
+    }
+
+    fn read_terminal_definition() -> ToolDefinition {
+        let mut properties = HashMap::new();
+
+        properties.insert(
+            "session_id".to_string(),
+            ParameterProperty {
+                prop_type: "string".to_string(),
+                description: Some("Terminal session to read; may be omitted when only one terminal is shared".to_string()),
+                default: None,
+            },
+        );
+
+        properties.insert(
+            "lines".to_string(),
+            ParameterProperty {
+                prop_type: "integer".to_string(),
+                description: Some("Number of most recent lines to read (at most 1000)".to_string()),
+                default: Some(serde_json::json!(crate::terminal::DEFAULT_SHARED_LINES)),
+            },
+        );
+
+        ToolDefinition {
+            name: "read_terminal".to_string(),
+            description: "Read the recent output of a terminal the user has shared with you, e.g. to see an error they ran into. Lines that look like secrets are redacted.".to_string(),
+            parameters: ToolParameters {
+                param_type: "object".to_string(),
+                properties,
+                required: vec![],
+            },
+        }
+    }
+}
+
+/// Registry of available tools
+#[derive(Debug, Clone)]
+pub struct ToolRegistry {
+    tools: HashMap<String, ToolDefinition>,
+    enabled: Vec<Tool>,
+}
+
+impl ToolRegistry {
+    /// Create a new registry with all tools enabled
+    pub fn new() -> Self {
+        Self::with_tools(Tool::all())
+    }
+
+    /// Create a registry with specific tools enabled
+    pub fn with_tools(tools: Vec<Tool>) -> Self {
+        let mut registry = HashMap::new();
+        for tool in &tools {
+            let def = tool.definition();
+            registry.insert(def.name.clone(), def);
+        }
+        Self {
+            tools: registry,
+            enabled: tools,
+        }
+    }
+
+    /// Add a tool that isn't built in, such as one provided by an MCP
+    /// server; calls to it are handled by the executor by name
+    pub fn register_external(&mut self, def: ToolDefinition) {
+        self.tools.entry(def.name.clone()).or_insert(def);
+    }
+
     /// Get a tool definition by name
     pub fn get(&self, name: &str) -> Option<&ToolDefinition> {
         self.tools.get(name)
@@ -0,0 +1,453 @@
+//! Agent run traces
+//!
+//! Every model request and response, tool call and result, and session state
+//! change of an agent session is appended with a timestamp to
+//! `~/.skhoot/traces/<session_id>.jsonl`, one JSON object per line. Tool
+//! calls the executor runs for a session are recorded here directly; the
+//! chat loop in the UI reports its model traffic through the trace API.
+//!
+//! With `traces.redact_file_contents` set, file contents (written content,
+//! patches, file reads and tool output in model requests) are replaced by
+//! their size before they are written. Downloads can be redacted the same way.
+//! Secrets such as API keys are replaced with placeholders before writing
+//! unless the session turned secret redaction off; values of the session's
+//! secret variables always are.
+//!
+//! When conversation encryption is enabled, each line is sealed by the
+//! conversation vault. Nothing is recorded and sealed traces can't be read
+//! while the vault is locked.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use super::agent::AgentState;
+use super::session_vars::SessionVars;
+use super::tools::{ToolCall, ToolResult};
+use crate::config::SettingsStore;
+use crate::secrets::SecretScanner;
+use crate::vault::{self, ConversationVault, VaultError};
+
+/// Tool arguments that hold file contents
+const CONTENT_ARGUMENTS: &[&str] = &["content", "patch", "text"];
+
+/// Tools whose output is file contents
+const CONTENT_TOOLS: &[&str] = &["read_file", "read_attachment", "read_terminal", "clipboard"];
+
+/// Something that happened in an agent run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TraceEvent {
+    /// A request to the model; `messages` is the conversation sent
+    ModelRequest {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        provider: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        model: Option<String>,
+        #[serde(default)]
+        messages: Value,
+        /// Names of the tools offered
+        #[serde(default)]
+        tools: Vec<String>,
+    },
+    /// The model's answer
+    ModelResponse {
+        #[serde(default)]
+        content: String,
+        #[serde(default)]
+        tool_calls: Vec<ToolCall>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        usage: Option<Value>,
+    },
+    ToolCall {
+        call: ToolCall,
+    },
+    ToolResult {
+        tool: String,
+        result: ToolResult,
+    },
+    StateChange {
+        from: AgentState,
+        to: AgentState,
+    },
+    Error {
+        message: String,
+    },
+}
+
+/// A recorded event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TraceEntry {
+    /// Position in the session's trace, from 1
+    pub seq: u64,
+    pub timestamp: DateTime<Utc>,
+    pub session_id: String,
+    #[serde(flatten)]
+    pub event: TraceEvent,
+}
+
+impl TraceEvent {
+    /// Copy with file contents replaced by their size
+    pub fn redacted(&self) -> TraceEvent {
+        let mut event = self.clone();
+        match &mut event {
+            TraceEvent::ToolCall { call } => redact_arguments(&mut call.arguments),
+            TraceEvent::ModelResponse { tool_calls, .. } => {
+                for call in tool_calls {
+                    redact_arguments(&mut call.arguments);
+                }
+            }
+            TraceEvent::ToolResult { tool, result } if CONTENT_TOOLS.contains(&tool.as_str()) => {
+                result.output = placeholder(&result.output);
+            }
+            TraceEvent::ModelRequest { messages, .. } => {
+                for message in messages.as_array_mut().into_iter().flatten() {
+                    if message.get("role").and_then(|r| r.as_str()) == Some("tool") {
+                        if let Some(Value::String(content)) = message.get_mut("content") {
+                            *content = placeholder(content);
+                        }
+                    }
+                    for call in message
+                        .get_mut("toolCalls")
+                        .and_then(|c| c.as_array_mut())
+                        .into_iter()
+                        .flatten()
+                    {
+                        if let Some(arguments) = call.get_mut("arguments") {
+                            redact_arguments(arguments);
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+        event
+    }
+
+    /// Copy with every secret in its text replaced by a placeholder
+    pub fn without_secrets(&self, scanner: &SecretScanner) -> TraceEvent {
+        let Ok(mut value) = serde_json::to_value(self) else {
+            return self.clone();
+        };
+        if scanner.redact_value(&mut value) == 0 {
+            return self.clone();
+        }
+        serde_json::from_value(value).unwrap_or_else(|_| self.clone())
+    }
+
+    /// Copy with the values of the session's secret variables replaced
+    pub fn without_secret_vars(&self, session_id: &str, vars: &SessionVars) -> TraceEvent {
+        let Ok(mut value) = serde_json::to_value(self) else {
+            return self.clone();
+        };
+        if vars.redact_value(session_id, &mut value) == 0 {
+            return self.clone();
+        }
+        serde_json::from_value(value).unwrap_or_else(|_| self.clone())
+    }
+}
+
+fn redact_arguments(arguments: &mut Value) {
+    let Some(arguments) = arguments.as_object_mut() else {
+        return;
+    };
+    for key in CONTENT_ARGUMENTS {
+        if let Some(Value::String(value)) = arguments.get_mut(*key) {
+            *value = placeholder(value);
+        }
+    }
+}
+
+fn placeholder(content: &str) -> String {
+    format!("[redacted {} bytes]", content.len())
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum TraceError {
+    #[error("Trace file error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("{0}")]
+    Vault(#[from] VaultError),
+}
+
+/// Appends trace entries to per-session JSONL files
+pub struct TraceRecorder {
+    root: PathBuf,
+    vault: Arc<ConversationVault>,
+    /// Variables whose secret values are kept out of traces
+    vars: Arc<SessionVars>,
+    /// Next sequence number by session; serializes appends
+    next_seq: Mutex<HashMap<String, u64>>,
+}
+
+lazy_static::lazy_static! {
+    static ref GLOBAL_RECORDER: Arc<TraceRecorder> = Arc::new(TraceRecorder::new(
+        dirs::home_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join(".skhoot")
+            .join("traces"),
+    ));
+}
+
+impl TraceRecorder {
+    pub fn new(root: PathBuf) -> Self {
+        Self::with_vault(root, ConversationVault::global())
+    }
+
+    pub fn with_vault(root: PathBuf, vault: Arc<ConversationVault>) -> Self {
+        Self {
+            root,
+            vault,
+            vars: SessionVars::global(),
+            next_seq: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Use a specific variable store (defaults to the global one)
+    pub fn with_session_vars(mut self, vars: Arc<SessionVars>) -> Self {
+        self.vars = vars;
+        self
+    }
+
+    /// Shared recorder writing to `~/.skhoot/traces`
+    pub fn global() -> Arc<TraceRecorder> {
+        GLOBAL_RECORDER.clone()
+    }
+
+    fn path(&self, session_id: &str) -> Option<PathBuf> {
+        let valid = !session_id.is_empty()
+            && session_id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+            && !session_id.starts_with('.');
+        valid.then(|| self.root.join(format!("{}.jsonl", session_id)))
+    }
+
+    /// Record an event if tracing is enabled; failures are only logged
+    pub fn record(&self, session_id: &str, event: TraceEvent) {
+        let settings = SettingsStore::global().get().traces;
+        if !settings.enabled {
+            return;
+        }
+        let event = if settings.redact_file_contents { event.redacted() } else { event };
+        let scanner = SecretScanner::global();
+        let event = if scanner.enabled_for(Some(session_id)) {
+            event.without_secrets(&scanner)
+        } else {
+            event
+        };
+        let event = event.without_secret_vars(session_id, &self.vars);
+        if let Err(e) = self.append(session_id, event) {
+            tracing::debug!("Failed to record trace for session {}: {}", session_id, e);
+        }
+    }
+
+    /// Append an event to the session's trace
+    pub fn append(&self, session_id: &str, event: TraceEvent) -> Result<TraceEntry, TraceError> {
+        let path = self
+            .path(session_id)
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, "invalid session id"))?;
+
+        let mut next_seq = self.next_seq.lock().unwrap();
+        let seq = match next_seq.get(session_id) {
+            Some(seq) => *seq,
+            // Continue a trace left by an earlier run
+            None => std::fs::read_to_string(&path)
+                .map(|text| text.lines().count() as u64 + 1)
+                .unwrap_or(1),
+        };
+        let entry = TraceEntry {
+            seq,
+            timestamp: Utc::now(),
+            session_id: session_id.to_string(),
+            event,
+        };
+
+        std::fs::create_dir_all(&self.root)?;
+        let mut file = std::fs::OpenOptions::new().create(true).append(true).open(&path)?;
+        let line = serde_json::to_string(&entry).map_err(std::io::Error::other)?;
+        let mut line = self.vault.seal(&line)?;
+        line.push('\n');
+        file.write_all(line.as_bytes())?;
+        next_seq.insert(session_id.to_string(), seq + 1);
+        Ok(entry)
+    }
+
+    /// Entries of a session's trace, oldest first; `None` if there is none.
+    /// Fails with [`VaultError::Locked`] if the trace is sealed and the vault
+    /// is locked.
+    pub fn read(&self, session_id: &str, redact: bool) -> Result<Option<Vec<TraceEntry>>, TraceError> {
+        let Some(path) = self.path(session_id) else {
+            return Ok(None);
+        };
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let mut entries = Vec::new();
+        for line in text.lines() {
+            let line = if vault::is_sealed(line) { self.vault.open(line)? } else { line.to_string() };
+            let Ok(mut entry) = serde_json::from_str::<TraceEntry>(&line) else {
+                continue;
+            };
+            if redact {
+                entry.event = entry.event.redacted();
+            }
+            entries.push(entry);
+        }
+        Ok(Some(entries))
+    }
+
+    /// IDs of the sessions with a recorded trace
+    pub fn sessions(&self) -> Vec<String> {
+        let Ok(entries) = std::fs::read_dir(&self.root) else {
+            return Vec::new();
+        };
+        let mut sessions: Vec<String> = entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let path = entry.path();
+                let stem = path.file_stem()?.to_str()?.to_string();
+                (path.extension()? == "jsonl" && self.path(&stem).is_some()).then_some(stem)
+            })
+            .collect();
+        sessions.sort();
+        sessions
+    }
+
+    /// Delete a session's trace; returns whether there was one
+    pub fn delete(&self, session_id: &str) -> bool {
+        let Some(path) = self.path(session_id) else {
+            return false;
+        };
+        self.next_seq.lock().unwrap().remove(session_id);
+        std::fs::remove_file(path).is_ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    fn write_call() -> ToolCall {
+        ToolCall {
+            id: "call-1".to_string(),
+            name: "write_file".to_string(),
+            arguments: json!({ "path": "notes.txt", "content": "secret plans" }),
+        }
+    }
+
+    #[test]
+    fn test_append_and_read() {
+        let dir = TempDir::new().unwrap();
+        let vault = Arc::new(ConversationVault::new(dir.path().join("vault.json")));
+        let recorder = TraceRecorder::with_vault(dir.path().to_path_buf(), vault.clone());
+
+        recorder
+            .append("s1", TraceEvent::StateChange { from: AgentState::Ready, to: AgentState::Processing })
+            .unwrap();
+        let entry = recorder.append("s1", TraceEvent::ToolCall { call: write_call() }).unwrap();
+        assert_eq!(entry.seq, 2);
+
+        let entries = recorder.read("s1", false).unwrap().unwrap();
+        assert_eq!(entries.len(), 2);
+        assert!(matches!(&entries[1].event, TraceEvent::ToolCall { call } if call.arguments["content"] == "secret plans"));
+
+        // A new recorder continues the numbering
+        let recorder = TraceRecorder::with_vault(dir.path().to_path_buf(), vault);
+        let entry = recorder.append("s1", TraceEvent::Error { message: "boom".to_string() }).unwrap();
+        assert_eq!(entry.seq, 3);
+
+        assert!(recorder.read("missing", false).unwrap().is_none());
+        assert!(recorder.append("../escape", TraceEvent::Error { message: String::new() }).is_err());
+        std::fs::write(dir.path().join("notes.txt"), "").unwrap();
+        assert_eq!(recorder.sessions(), vec!["s1"]);
+        assert!(recorder.delete("s1"));
+        assert!(recorder.read("s1", false).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_sealed_traces() {
+        let dir = TempDir::new().unwrap();
+        let vault = Arc::new(ConversationVault::new(dir.path().join("vault.json")));
+        let recorder = TraceRecorder::with_vault(dir.path().join("traces"), vault.clone());
+
+        vault.enable("correct horse").unwrap();
+        recorder.append("s1", TraceEvent::ToolCall { call: write_call() }).unwrap();
+        let stored = std::fs::read_to_string(dir.path().join("traces").join("s1.jsonl")).unwrap();
+        assert!(vault::is_sealed(&stored) && !stored.contains("secret plans"));
+        let entries = recorder.read("s1", false).unwrap().unwrap();
+        assert!(matches!(&entries[0].event, TraceEvent::ToolCall { call } if call.arguments["content"] == "secret plans"));
+
+        vault.lock();
+        assert!(matches!(recorder.read("s1", false), Err(TraceError::Vault(VaultError::Locked))));
+        assert!(matches!(
+            recorder.append("s1", TraceEvent::Error { message: "boom".to_string() }),
+            Err(TraceError::Vault(VaultError::Locked))
+        ));
+    }
+
+    #[test]
+    fn test_redaction() {
+        let TraceEvent::ToolCall { call } = (TraceEvent::ToolCall { call: write_call() }).redacted() else {
+            unreachable!()
+        };
+        assert_eq!(call.arguments["content"], "[redacted 12 bytes]");
+        assert_eq!(call.arguments["path"], "notes.txt");
+
+        let request = TraceEvent::ModelRequest {
+            provider: None,
+            model: None,
+            messages: json!([
+                { "role": "user", "content": "Summarize notes.txt" },
+                { "role": "tool", "content": "file body" },
+            ]),
+            tools: vec![],
+        };
+        let TraceEvent::ModelRequest { messages, .. } = request.redacted() else {
+            unreachable!()
+        };
+        assert_eq!(messages[0]["content"], "Summarize notes.txt");
+        assert_eq!(messages[1]["content"], "[redacted 9 bytes]");
+
+        let result = TraceEvent::ToolResult {
+            tool: "list_directory".to_string(),
+            result: ToolResult {
+                tool_call_id: "call-2".to_string(),
+                success: true,
+                output: "a.txt".to_string(),
+                error: None,
+                metadata: None,
+            },
+        };
+        let TraceEvent::ToolResult { result, .. } = result.redacted() else {
+            unreachable!()
+        };
+        assert_eq!(result.output, "a.txt");
+    }
+
+    #[test]
+    fn test_without_secrets() {
+        let request = TraceEvent::ModelRequest {
+            provider: Some("openai".to_string()),
+            model: None,
+            messages: json!([
+                { "role": "tool", "content": "OPENAI_API_KEY=sk-abcdefghijklmnopqrstuvwxyz" },
+            ]),
+            tools: vec!["shell".to_string()],
+        };
+        let TraceEvent::ModelRequest { messages, provider, .. } = request.without_secrets(&SecretScanner::new()) else {
+            unreachable!()
+        };
+        assert_eq!(messages[0]["content"], "OPENAI_API_KEY=[REDACTED:openai_key]");
+        assert_eq!(provider.as_deref(), Some("openai"));
+    }
+}
@@ -0,0 +1,231 @@
+//! Workspace Sandbox
+//!
+//! Confines agent file tools to a project root directory. Paths are
+//! canonicalized (resolving `..` and symlinks) before the containment check,
+//! so a symlink inside the workspace cannot be used to reach files outside it,
+//! even one whose target doesn't exist yet.
+
+use std::path::{Component, Path, PathBuf};
+
+use super::executor::ExecutorError;
+
+/// Symlinks followed while resolving one path, as the OS limits it
+const MAX_SYMLINK_HOPS: usize = 40;
+
+/// A root directory that agent file operations must stay within
+#[derive(Debug, Clone)]
+pub struct Workspace {
+    root: PathBuf,
+    allow_escape: bool,
+}
+
+impl Workspace {
+    /// Create a workspace rooted at `root`. The root must exist.
+    pub fn new(root: &Path) -> Result<Self, ExecutorError> {
+        let root = root.canonicalize().map_err(|e| {
+            ExecutorError::InvalidArgument(format!(
+                "Workspace root {} is not accessible: {}",
+                root.display(),
+                e
+            ))
+        })?;
+
+        Ok(Self {
+            root,
+            allow_escape: false,
+        })
+    }
+
+    /// Allow paths outside the root. This is the per-session escape hatch the
+    /// user must grant explicitly.
+    pub fn with_escape_allowed(mut self, allowed: bool) -> Self {
+        self.allow_escape = allowed;
+        self
+    }
+
+    /// Canonical workspace root
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Whether the user granted access outside the root
+    pub fn escape_allowed(&self) -> bool {
+        self.allow_escape
+    }
+
+    /// Canonicalize `path` and ensure it lies inside the workspace.
+    ///
+    /// Paths that don't exist yet (e.g. files about to be written) are resolved
+    /// through their closest existing ancestor.
+    pub fn check(&self, path: &Path) -> Result<PathBuf, ExecutorError> {
+        let resolved = Self::canonicalize_lenient(path)?;
+
+        if self.allow_escape || resolved.starts_with(&self.root) {
+            Ok(resolved)
+        } else {
+            Err(ExecutorError::PermissionDenied(format!(
+                "{} is outside the workspace root {}. Ask the user to grant access outside the workspace for this session.",
+                path.display(),
+                self.root.display()
+            )))
+        }
+    }
+
+    /// Like [`check`](Self::check), but leaves the last component unresolved
+    /// so a symlink names the link itself rather than its target, for
+    /// operations on the entry such as deleting it
+    pub fn check_entry(&self, path: &Path) -> Result<PathBuf, ExecutorError> {
+        match (path.parent(), path.file_name()) {
+            (Some(parent), Some(name)) if !parent.as_os_str().is_empty() => {
+                let parent = self.check(parent)?;
+                Ok(parent.join(name))
+            }
+            _ => self.check(path),
+        }
+    }
+
+    /// Whether `path` resolves to a location inside the workspace
+    pub fn contains(&self, path: &Path) -> bool {
+        Self::canonicalize_lenient(path)
+            .map(|p| p.starts_with(&self.root))
+            .unwrap_or(false)
+    }
+
+    fn canonicalize_lenient(path: &Path) -> Result<PathBuf, ExecutorError> {
+        Self::resolve(path, 0)
+    }
+
+    fn resolve(path: &Path, hops: usize) -> Result<PathBuf, ExecutorError> {
+        if let Ok(canonical) = path.canonicalize() {
+            return Ok(canonical);
+        }
+
+        // Walk up to the nearest ancestor that exists, or is a symlink whose
+        // target doesn't, remembering the missing tail
+        let mut existing = path.to_path_buf();
+        let mut tail: Vec<std::ffi::OsString> = Vec::new();
+        loop {
+            if existing.symlink_metadata().is_ok() {
+                break;
+            }
+            match (existing.file_name(), existing.parent()) {
+                (Some(name), Some(parent)) => {
+                    tail.push(name.to_os_string());
+                    existing = parent.to_path_buf();
+                }
+                _ => break,
+            }
+        }
+
+        // A dangling symlink: carry on from where it points
+        if existing.symlink_metadata().is_ok_and(|m| m.file_type().is_symlink()) {
+            if hops >= MAX_SYMLINK_HOPS {
+                return Err(ExecutorError::FileOperation(format!(
+                    "Too many levels of symbolic links in {}",
+                    path.display()
+                )));
+            }
+            let target = std::fs::read_link(&existing).map_err(|e| {
+                ExecutorError::FileOperation(format!("Failed to resolve {}: {}", existing.display(), e))
+            })?;
+            let mut target = match existing.parent() {
+                Some(parent) if target.is_relative() => parent.join(target),
+                _ => target,
+            };
+            target.extend(tail.iter().rev());
+            return Self::resolve(&target, hops + 1);
+        }
+
+        let mut resolved = existing.canonicalize().map_err(|e| {
+            ExecutorError::FileOperation(format!("Failed to resolve {}: {}", path.display(), e))
+        })?;
+
+        for part in tail.iter().rev() {
+            let component = Path::new(part);
+            if matches!(component.components().next(), Some(Component::ParentDir)) {
+                return Err(ExecutorError::PermissionDenied(format!(
+                    "Path {} contains '..' after a missing directory",
+                    path.display()
+                )));
+            }
+            resolved.push(part);
+        }
+
+        Ok(resolved)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_paths_inside_root_allowed() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("a.txt"), "a").unwrap();
+        let workspace = Workspace::new(temp_dir.path()).unwrap();
+
+        assert!(workspace.check(&temp_dir.path().join("a.txt")).is_ok());
+        assert!(workspace.check(&temp_dir.path().join("new/dir/b.txt")).is_ok());
+    }
+
+    #[test]
+    fn test_parent_traversal_rejected() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("project");
+        std::fs::create_dir_all(&root).unwrap();
+        let workspace = Workspace::new(&root).unwrap();
+
+        let result = workspace.check(&root.join("../outside.txt"));
+        assert!(matches!(result, Err(ExecutorError::PermissionDenied(_))));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_escape_rejected() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("project");
+        let outside = temp_dir.path().join("secret");
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::create_dir_all(&outside).unwrap();
+        std::fs::write(outside.join("key"), "secret").unwrap();
+        std::os::unix::fs::symlink(&outside, root.join("link")).unwrap();
+
+        let workspace = Workspace::new(&root).unwrap();
+        assert!(workspace.check(&root.join("link/key")).is_err());
+        assert!(workspace.check(&root.join("link/new.txt")).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_dangling_symlink_escape_rejected() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("project");
+        let outside = temp_dir.path().join("outside");
+        std::fs::create_dir_all(&root).unwrap();
+        std::os::unix::fs::symlink(outside.join("new"), root.join("link")).unwrap();
+        std::os::unix::fs::symlink(outside.join("dir"), root.join("dir_link")).unwrap();
+        std::os::unix::fs::symlink("missing.txt", root.join("inner")).unwrap();
+
+        let workspace = Workspace::new(&root).unwrap();
+        assert!(workspace.check(&root.join("link")).is_err());
+        assert!(workspace.check(&root.join("dir_link/file.txt")).is_err());
+        let root = workspace.root().to_path_buf();
+        assert_eq!(workspace.check(&root.join("inner")).unwrap(), root.join("missing.txt"));
+
+        // Deleting works on the link itself, wherever it points
+        assert_eq!(workspace.check_entry(&root.join("link")).unwrap(), root.join("link"));
+        assert!(workspace.check_entry(&root.join("dir_link/file.txt")).is_err());
+    }
+
+    #[test]
+    fn test_escape_hatch_allows_outside_paths() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("project");
+        std::fs::create_dir_all(&root).unwrap();
+        let workspace = Workspace::new(&root).unwrap().with_escape_allowed(true);
+
+        assert!(workspace.check(&temp_dir.path().join("other.txt")).is_ok());
+    }
+}
@@ -0,0 +1,150 @@
+//! Shell environment profiles
+//!
+//! A profile describes the environment commands should see: extra variables,
+//! directories prepended to PATH, an optional Python virtualenv, a default
+//! working directory and the shell to run them with. Profiles are defined in
+//! the `environments` section of the settings and picked per agent session or
+//! terminal.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::path::PathBuf;
+
+use super::pty::ShellKind;
+
+/// Named set of environment settings applied when spawning processes
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EnvironmentProfile {
+    pub name: String,
+    /// Variables set for every process
+    pub env: BTreeMap<String, String>,
+    /// Directories put in front of PATH, in order
+    pub path_prepend: Vec<String>,
+    /// Python virtualenv to activate
+    pub virtualenv: Option<String>,
+    /// Working directory used when a command doesn't name one
+    pub cwd: Option<String>,
+    /// Shell to run commands with (sh, bash, zsh, fish, pwsh, powershell, cmd)
+    pub shell: Option<String>,
+}
+
+impl EnvironmentProfile {
+    /// Variables to set on a spawned process, with PATH and VIRTUAL_ENV
+    /// resolved against the current environment
+    pub fn variables(&self) -> Vec<(String, String)> {
+        let mut vars: Vec<(String, String)> = self
+            .env
+            .iter()
+            .filter(|(key, _)| !is_path_var(key))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+
+        let mut prepend: Vec<PathBuf> = self.path_prepend.iter().map(|p| expand_home(p)).collect();
+        if let Some(venv) = &self.virtualenv {
+            let venv = expand_home(venv);
+            let bin = if cfg!(target_os = "windows") { "Scripts" } else { "bin" };
+            prepend.insert(0, venv.join(bin));
+            vars.push(("VIRTUAL_ENV".to_string(), venv.to_string_lossy().to_string()));
+        }
+
+        let path_override = self.env.iter().find(|(key, _)| is_path_var(key)).map(|(_, value)| value);
+        if !prepend.is_empty() || path_override.is_some() {
+            let base: Vec<PathBuf> = path_override
+                .map(OsString::from)
+                .or_else(|| std::env::var_os("PATH"))
+                .map(|path| std::env::split_paths(&path).collect())
+                .unwrap_or_default();
+            if let Ok(path) = std::env::join_paths(prepend.into_iter().chain(base)) {
+                vars.push(("PATH".to_string(), path.to_string_lossy().to_string()));
+            }
+        }
+        vars
+    }
+
+    /// Default working directory, with `~` expanded
+    pub fn working_directory(&self) -> Option<PathBuf> {
+        self.cwd.as_deref().map(expand_home)
+    }
+}
+
+/// Program and arguments that run `command` through `shell`, or through the
+/// platform shell when none is given
+pub fn shell_invocation(shell: Option<&str>, command: &str) -> (String, Vec<String>) {
+    let shell = shell_program(shell);
+    let args = ShellKind::of(&shell).command_args(command);
+    (shell, args)
+}
+
+/// Kind of the shell [`shell_invocation`] runs commands through
+pub fn shell_kind(shell: Option<&str>) -> ShellKind {
+    ShellKind::of(&shell_program(shell))
+}
+
+fn shell_program(shell: Option<&str>) -> String {
+    shell.map(str::to_string).unwrap_or_else(|| {
+        if cfg!(target_os = "windows") { "cmd".to_string() } else { "/bin/sh".to_string() }
+    })
+}
+
+/// PATH is spelled `Path` on Windows and matched case-insensitively there
+fn is_path_var(key: &str) -> bool {
+    if cfg!(target_os = "windows") {
+        key.eq_ignore_ascii_case("PATH")
+    } else {
+        key == "PATH"
+    }
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix('~'), dirs::home_dir()) {
+        (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with('/') || rest.starts_with('\\') => {
+            home.join(rest.trim_start_matches(['/', '\\']))
+        }
+        _ => PathBuf::from(path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_variables_prepend_path_and_virtualenv() {
+        let profile = EnvironmentProfile {
+            name: "py".to_string(),
+            env: BTreeMap::from([
+                ("RUST_LOG".to_string(), "debug".to_string()),
+                ("PATH".to_string(), "/usr/bin".to_string()),
+            ]),
+            path_prepend: vec!["/opt/tools/bin".to_string()],
+            virtualenv: Some("/work/.venv".to_string()),
+            ..Default::default()
+        };
+
+        let vars: BTreeMap<String, String> = profile.variables().into_iter().collect();
+        assert_eq!(vars["RUST_LOG"], "debug");
+        assert_eq!(vars["VIRTUAL_ENV"], "/work/.venv");
+        if cfg!(unix) {
+            assert_eq!(vars["PATH"], "/work/.venv/bin:/opt/tools/bin:/usr/bin");
+        }
+    }
+
+    #[test]
+    fn test_empty_profile_leaves_environment_alone() {
+        let profile = EnvironmentProfile::default();
+        assert!(profile.variables().is_empty());
+        assert_eq!(profile.working_directory(), None);
+    }
+
+    #[test]
+    fn test_shell_invocation() {
+        assert_eq!(
+            shell_invocation(Some("/bin/zsh"), "ls"),
+            ("/bin/zsh".to_string(), vec!["-c".to_string(), "ls".to_string()])
+        );
+        assert_eq!(shell_invocation(Some("pwsh"), "ls").1, vec!["-NoLogo", "-NoProfile", "-Command", "ls"]);
+        assert_eq!(shell_invocation(Some("C:\\Windows\\System32\\cmd.exe"), "dir").1, vec!["/C", "dir"]);
+    }
+}
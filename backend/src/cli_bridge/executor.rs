@@ -1,41 +1,22 @@
 //! Command execution with security sandboxing
 
 use super::error::CliError;
-use super::types::{CommandHandle, ProcessHandle, TerminalOutput, SecurityConfig, ProcessType, PtyProcessHandle};
+use super::types::{CommandHandle, ProcessHandle, TerminalOutput, SecurityConfig, ProcessType, PtyProcessHandle, ResourceLimits};
 use super::pty::PtySession;
+use super::policy::{PolicyAction, PolicyStore};
 use std::collections::HashMap;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tokio::sync::{Mutex, RwLock};
 use tokio::process::{Command};
 use std::process::Stdio;
 use tracing::{debug, warn, info};
 
-/// Dangerous command patterns that require explicit confirmation
-const DANGEROUS_PATTERNS: &[&str] = &[
-    "rm -rf /",
-    "rm -rf /*",
-    "dd if=",
-    "mkfs",
-    "fdisk",
-    "parted",
-    "> /dev/",
-    "chmod -R 777",
-    "chown -R",
-    ":(){ :|:& };:",  // Fork bomb
-];
-
-/// Commands that are blocked entirely
-const BLOCKED_COMMANDS: &[&str] = &[
-    "reboot",
-    "shutdown",
-    "halt",
-    "poweroff",
-];
-
 /// Executes commands with security sandboxing and monitoring
 pub struct CommandExecutor {
     processes: Arc<RwLock<HashMap<String, ProcessType>>>,
     security_config: Arc<RwLock<SecurityConfig>>,
+    policy_store: Arc<PolicyStore>,
 }
 
 impl CommandExecutor {
@@ -44,6 +25,7 @@ impl CommandExecutor {
         Self {
             processes: Arc::new(RwLock::new(HashMap::new())),
             security_config: Arc::new(RwLock::new(SecurityConfig::default())),
+            policy_store: PolicyStore::global(),
         }
     }
 
@@ -56,9 +38,21 @@ impl CommandExecutor {
         Self {
             processes: Arc::new(RwLock::new(HashMap::new())),
             security_config: Arc::new(RwLock::new(config)),
+            policy_store: PolicyStore::global(),
         }
     }
 
+    /// Use a specific command policy store instead of the global one
+    pub fn with_policy_store(mut self, store: Arc<PolicyStore>) -> Self {
+        self.policy_store = store;
+        self
+    }
+
+    /// Get the command policy store used for validation
+    pub fn policy_store(&self) -> Arc<PolicyStore> {
+        self.policy_store.clone()
+    }
+
     /// Get the current security configuration
     pub async fn get_security_config(&self) -> SecurityConfig {
         self.security_config.read().await.clone()
@@ -85,31 +79,50 @@ impl CommandExecutor {
     pub async fn validate_command(&self, cmd: &str, args: &[String]) -> Result<(), CliError> {
         let config = self.security_config.read().await;
 
-        // Check if command is blocked
-        if BLOCKED_COMMANDS.contains(&cmd) {
-            return Err(CliError::DangerousCommand(format!(
-                "Command '{}' is blocked for security reasons",
-                cmd
-            )));
-        }
-
-        // Build full command string for pattern matching
+        // Build full command string for policy matching
         let full_command = format!("{} {}", cmd, args.join(" "));
 
-        // Check for dangerous patterns
-        for pattern in DANGEROUS_PATTERNS {
-            if full_command.contains(pattern) {
-                let error_msg = format!(
-                    "Command contains dangerous pattern: {}. User confirmation required.",
-                    pattern
-                );
-                
-                // Even with sandbox disabled, dangerous commands require confirmation
-                if !config.sandbox_enabled {
-                    warn!("Dangerous command detected with sandbox disabled: {}", full_command);
+        // First matching policy rule decides (policy file is reloaded if it changed)
+        let policy = self.policy_store.get();
+        if let Some(rule) = policy.evaluate(full_command.trim_end()) {
+            match rule.action {
+                PolicyAction::Allow => {
+                    debug!("Command allowed by policy rule {}: {}", rule.id, full_command);
+                }
+                PolicyAction::Deny => {
+                    return Err(CliError::DangerousCommand(format!(
+                        "Command '{}' is blocked by policy rule '{}'",
+                        cmd, rule.pattern
+                    )));
+                }
+                PolicyAction::RequireConfirmation => {
+                    // Even with sandbox disabled, dangerous commands require confirmation
+                    if !config.sandbox_enabled {
+                        warn!("Dangerous command detected with sandbox disabled: {}", full_command);
+                    }
+
+                    return Err(CliError::DangerousCommand(format!(
+                        "Command contains dangerous pattern: {}. User confirmation required.",
+                        rule.pattern
+                    )));
+                }
+            }
+        } else {
+            // Commands no rule covers follow the configured security policy
+            match crate::config::SettingsStore::global().get().security.unmatched_command_action {
+                PolicyAction::Allow => {}
+                PolicyAction::Deny => {
+                    return Err(CliError::DangerousCommand(format!(
+                        "Command '{}' is not allowed by the command policy",
+                        cmd
+                    )));
+                }
+                PolicyAction::RequireConfirmation => {
+                    return Err(CliError::DangerousCommand(format!(
+                        "Command '{}' matches no policy rule. User confirmation required.",
+                        cmd
+                    )));
                 }
-                
-                return Err(CliError::DangerousCommand(error_msg));
             }
         }
 
@@ -135,6 +148,18 @@ impl CommandExecutor {
         cmd: String,
         args: Vec<String>,
         cwd: Option<std::path::PathBuf>,
+    ) -> Result<CommandHandle, CliError> {
+        self.spawn_command_with_env(session_id, cmd, args, cwd, Vec::new()).await
+    }
+
+    /// Spawn a command with security sandboxing and extra environment variables
+    pub async fn spawn_command_with_env(
+        &self,
+        session_id: String,
+        cmd: String,
+        args: Vec<String>,
+        cwd: Option<std::path::PathBuf>,
+        env: Vec<(String, String)>,
     ) -> Result<CommandHandle, CliError> {
         let config = self.security_config.read().await;
         
@@ -151,6 +176,16 @@ impl CommandExecutor {
         if let Some(dir) = cwd {
             command.current_dir(dir);
         }
+        command.envs(env);
+
+        let limits = config.resource_limits.clone();
+
+        // Apply CPU and memory limits in the child before exec
+        #[cfg(unix)]
+        if limits.max_cpu_secs.is_some() || limits.max_memory_bytes.is_some() {
+            super::limits::set_rlimits(&mut command, limits.max_cpu_secs, limits.max_memory_bytes);
+            debug!("Applied rlimits: cpu={:?}s memory={:?} bytes", limits.max_cpu_secs, limits.max_memory_bytes);
+        }
 
         // Apply platform-specific sandboxing if enabled
         if config.sandbox_enabled {
@@ -186,6 +221,18 @@ impl CommandExecutor {
             }
         })?;
 
+        // Windows has no pre-exec hook; limit the child through a job object
+        #[cfg(windows)]
+        {
+            if limits.max_cpu_secs.is_some() || limits.max_memory_bytes.is_some() {
+                if let Err(e) = super::limits::assign_job(&child, limits.max_cpu_secs, limits.max_memory_bytes) {
+                    let _ = child.kill().await;
+                    return Err(CliError::SpawnFailed(format!("cannot apply resource limits: {}", e)));
+                }
+                debug!("Applied job object limits: cpu={:?}s memory={:?} bytes", limits.max_cpu_secs, limits.max_memory_bytes);
+            }
+        }
+
         let pid = child.id();
         debug!("Process spawned with PID: {:?} (sandbox_enabled={})", pid, config.sandbox_enabled);
 
@@ -203,37 +250,80 @@ impl CommandExecutor {
         })?;
 
         // Create process handle with stdin
-        let process_handle = ProcessHandle::new(child).with_stdin(stdin);
+        let process_handle = ProcessHandle::new(child).with_stdin(stdin).with_limits(limits.clone());
 
         // Start streaming output in background tasks
         let stdout_buffer = process_handle.stdout_buffer.clone();
         let stderr_buffer = process_handle.stderr_buffer.clone();
+        let output_bytes = Arc::new(AtomicUsize::new(0));
 
         // Spawn stdout reader task
-        tokio::spawn(async move {
-            use tokio::io::{AsyncBufReadExt, BufReader};
-            let reader = BufReader::new(stdout);
-            let mut lines = reader.lines();
-            
-            while let Ok(Some(line)) = lines.next_line().await {
-                let output = TerminalOutput::stdout(line);
-                let mut buffer = stdout_buffer.lock().await;
-                buffer.push(output);
-            }
-        });
+        {
+            let child = process_handle.child.clone();
+            let violation = process_handle.limit_violation.clone();
+            let output_bytes = output_bytes.clone();
+            let max_output_bytes = limits.max_output_bytes;
+            tokio::spawn(async move {
+                use tokio::io::{AsyncBufReadExt, BufReader};
+                let reader = BufReader::new(stdout);
+                let mut lines = reader.lines();
+
+                while let Ok(Some(line)) = lines.next_line().await {
+                    let total = output_bytes.fetch_add(line.len() + 1, Ordering::Relaxed) + line.len() + 1;
+                    if max_output_bytes.is_some_and(|max| total > max) {
+                        Self::record_violation(&child, &violation, &stdout_buffer, format!(
+                            "output exceeded {} bytes", max_output_bytes.unwrap_or_default()
+                        )).await;
+                        break;
+                    }
+                    let output = TerminalOutput::stdout(line);
+                    let mut buffer = stdout_buffer.lock().await;
+                    buffer.push(output);
+                }
+            });
+        }
 
         // Spawn stderr reader task
-        tokio::spawn(async move {
-            use tokio::io::{AsyncBufReadExt, BufReader};
-            let reader = BufReader::new(stderr);
-            let mut lines = reader.lines();
-            
-            while let Ok(Some(line)) = lines.next_line().await {
-                let output = TerminalOutput::stderr(line);
-                let mut buffer = stderr_buffer.lock().await;
-                buffer.push(output);
-            }
-        });
+        {
+            let child = process_handle.child.clone();
+            let violation = process_handle.limit_violation.clone();
+            let output_bytes = output_bytes.clone();
+            let max_output_bytes = limits.max_output_bytes;
+            tokio::spawn(async move {
+                use tokio::io::{AsyncBufReadExt, BufReader};
+                let reader = BufReader::new(stderr);
+                let mut lines = reader.lines();
+
+                while let Ok(Some(line)) = lines.next_line().await {
+                    let total = output_bytes.fetch_add(line.len() + 1, Ordering::Relaxed) + line.len() + 1;
+                    if max_output_bytes.is_some_and(|max| total > max) {
+                        Self::record_violation(&child, &violation, &stderr_buffer, format!(
+                            "output exceeded {} bytes", max_output_bytes.unwrap_or_default()
+                        )).await;
+                        break;
+                    }
+                    let output = TerminalOutput::stderr(line);
+                    let mut buffer = stderr_buffer.lock().await;
+                    buffer.push(output);
+                }
+            });
+        }
+
+        // Spawn wall-clock watchdog
+        if let Some(max_runtime_secs) = limits.max_runtime_secs {
+            let child = process_handle.child.clone();
+            let violation = process_handle.limit_violation.clone();
+            let stderr_buffer = process_handle.stderr_buffer.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(tokio::time::Duration::from_secs(max_runtime_secs)).await;
+                let still_running = matches!(child.lock().await.try_wait(), Ok(None));
+                if still_running {
+                    Self::record_violation(&child, &violation, &stderr_buffer, format!(
+                        "runtime exceeded {}s", max_runtime_secs
+                    )).await;
+                }
+            });
+        }
 
         // Store process handle
         {
@@ -250,6 +340,80 @@ impl CommandExecutor {
         Ok(handle)
     }
 
+    /// Kill a process that exceeded a resource limit, keeping the output captured so far
+    async fn record_violation(
+        child: &Arc<Mutex<tokio::process::Child>>,
+        violation: &Arc<Mutex<Option<String>>>,
+        buffer: &Arc<Mutex<Vec<TerminalOutput>>>,
+        reason: String,
+    ) {
+        {
+            let mut violation = violation.lock().await;
+            if violation.is_some() {
+                return;
+            }
+            *violation = Some(reason.clone());
+        }
+
+        warn!("Killing process: {}", reason);
+        buffer.lock().await.push(TerminalOutput::system(format!(
+            "[process killed: {}]", reason
+        )));
+        let _ = child.lock().await.start_kill();
+    }
+
+    /// Return `ResourceLimitExceeded` if the command was killed for exceeding a limit,
+    /// by the executor or, for CPU and memory, by the OS.
+    /// Output captured before the kill is still available through `read_output`.
+    pub async fn check_resource_limits(&self, handle: &CommandHandle) -> Result<(), CliError> {
+        let processes = self.processes.read().await;
+        let process = processes
+            .get(&handle.session_id)
+            .ok_or_else(|| CliError::SessionNotFound(handle.session_id.clone()))?;
+
+        if let ProcessType::Regular(proc_handle) = process {
+            let exited = proc_handle.child.lock().await.try_wait().ok().flatten();
+            if let Some(status) = exited {
+                let limits = &proc_handle.limits;
+                let stderr = proc_handle.stderr_buffer.lock().await.clone();
+                if let Some(reason) = super::limits::exit_violation(&status, limits.max_cpu_secs, limits.max_memory_bytes, &stderr) {
+                    Self::record_violation(&proc_handle.child, &proc_handle.limit_violation, &proc_handle.stderr_buffer, reason).await;
+                }
+            }
+            if let Some(reason) = proc_handle.limit_violation.lock().await.clone() {
+                return Err(CliError::ResourceLimitExceeded(reason));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Exit status of a regular command, or `None` while it is still running
+    pub async fn try_wait(
+        &self,
+        handle: &CommandHandle,
+    ) -> Result<Option<std::process::ExitStatus>, CliError> {
+        let processes = self.processes.read().await;
+        let process = processes
+            .get(&handle.session_id)
+            .ok_or_else(|| CliError::SessionNotFound(handle.session_id.clone()))?;
+
+        match process {
+            ProcessType::Regular(proc_handle) => {
+                let mut child = proc_handle.child.lock().await;
+                child.try_wait().map_err(|e| CliError::Io(e.to_string()))
+            }
+            ProcessType::Pty(_) => Err(CliError::InvalidState(
+                "Exit status is not tracked for PTY sessions".to_string()
+            )),
+        }
+    }
+
+    /// Get the resource limits applied to newly spawned commands
+    pub async fn get_resource_limits(&self) -> ResourceLimits {
+        self.security_config.read().await.resource_limits.clone()
+    }
+
     /// Spawn a command with PTY (pseudo-terminal) support for full terminal emulation
     pub async fn spawn_command_pty(
         &self,
@@ -542,4 +706,79 @@ mod tests {
         let result = executor.validate_command("rm", &["-rf".to_string(), "/".to_string()]).await;
         assert!(matches!(result, Err(CliError::DangerousCommand(_))));
     }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_runtime_limit_kills_process() {
+        let mut config = SecurityConfig::default();
+        config.resource_limits.max_runtime_secs = Some(1);
+        let executor = CommandExecutor::with_config(config);
+
+        let handle = executor.spawn_command(
+            "runtime-limit".to_string(),
+            "sleep".to_string(),
+            vec!["10".to_string()],
+            None,
+        ).await.unwrap();
+
+        tokio::time::sleep(tokio::time::Duration::from_millis(1500)).await;
+
+        let result = executor.check_resource_limits(&handle).await;
+        assert!(matches!(result, Err(CliError::ResourceLimitExceeded(_))));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_cpu_limit_is_reported() {
+        let mut config = SecurityConfig::default();
+        config.resource_limits.max_cpu_secs = Some(1);
+        let executor = CommandExecutor::with_config(config);
+
+        let handle = executor.spawn_command(
+            "cpu-limit".to_string(),
+            "sh".to_string(),
+            vec!["-c".to_string(), "echo started; while :; do :; done".to_string()],
+            None,
+        ).await.unwrap();
+
+        let mut exited = false;
+        for _ in 0..50 {
+            tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+            if executor.try_wait(&handle).await.unwrap().is_some() {
+                exited = true;
+                break;
+            }
+        }
+        assert!(exited);
+
+        let result = executor.check_resource_limits(&handle).await;
+        assert!(matches!(result, Err(CliError::ResourceLimitExceeded(reason)) if reason.contains("CPU")));
+
+        let output = executor.read_output(&handle).await.unwrap();
+        assert!(output.iter().any(|line| line.content == "started"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_output_limit_keeps_partial_output() {
+        let mut config = SecurityConfig::default();
+        config.resource_limits.max_output_bytes = Some(100);
+        let executor = CommandExecutor::with_config(config);
+
+        let handle = executor.spawn_command(
+            "output-limit".to_string(),
+            "yes".to_string(),
+            vec![],
+            None,
+        ).await.unwrap();
+
+        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+
+        let result = executor.check_resource_limits(&handle).await;
+        assert!(matches!(result, Err(CliError::ResourceLimitExceeded(_))));
+
+        let output = executor.read_output(&handle).await.unwrap();
+        assert!(!output.is_empty());
+        assert!(output.len() <= 51);
+    }
 }
@@ -0,0 +1,149 @@
+//! CPU and memory limits for spawned processes
+//!
+//! On Unix the limits are set with `setrlimit` in the child before it execs,
+//! so the command starts already bound by them. On Windows the child is assigned to a job object right after it is
+//! spawned, with a per-process user time limit and a committed memory limit.
+//! Processes the child starts inherit the job. The job handle is closed once
+//! the child is assigned; the job lives on until its last process exits.
+//!
+//! The kernel enforces these limits itself, so the executor only learns of
+//! them from how the process exited; [`exit_violation`] reads that back.
+
+use super::types::TerminalOutput;
+use std::process::ExitStatus;
+
+/// Have `command` set its CPU time and address space limits before exec
+#[cfg(unix)]
+pub fn set_rlimits(
+    command: &mut tokio::process::Command,
+    max_cpu_secs: Option<u64>,
+    max_memory_bytes: Option<u64>,
+) {
+    if max_cpu_secs.is_none() && max_memory_bytes.is_none() {
+        return;
+    }
+    // SAFETY: the closure only calls setrlimit, which is async-signal-safe
+    unsafe {
+        command.pre_exec(move || {
+            use nix::sys::resource::{setrlimit, Resource};
+            if let Some(secs) = max_cpu_secs {
+                // SIGXCPU at the soft limit, SIGKILL a second later if
+                // the process ignores it
+                setrlimit(Resource::RLIMIT_CPU, secs, secs.saturating_add(1))?;
+            }
+            if let Some(bytes) = max_memory_bytes {
+                setrlimit(Resource::RLIMIT_AS, bytes, bytes)?;
+            }
+            Ok(())
+        });
+    }
+}
+
+/// Put `child` in a job object limiting its CPU time and committed memory
+#[cfg(windows)]
+pub fn assign_job(
+    child: &tokio::process::Child,
+    max_cpu_secs: Option<u64>,
+    max_memory_bytes: Option<u64>,
+) -> std::io::Result<()> {
+    use windows_sys::Win32::Foundation::CloseHandle;
+    use windows_sys::Win32::System::JobObjects::{
+        AssignProcessToJobObject, CreateJobObjectW, JobObjectExtendedLimitInformation,
+        SetInformationJobObject, JOBOBJECT_EXTENDED_LIMIT_INFORMATION, JOB_OBJECT_LIMIT_PROCESS_MEMORY,
+        JOB_OBJECT_LIMIT_PROCESS_TIME,
+    };
+
+    let Some(process) = child.raw_handle() else {
+        // Already exited
+        return Ok(());
+    };
+
+    // SAFETY: the limit structure is plain integers, so all-zero is valid;
+    // the job handle is checked before use and closed on every path.
+    unsafe {
+        let mut info: JOBOBJECT_EXTENDED_LIMIT_INFORMATION = std::mem::zeroed();
+        if let Some(secs) = max_cpu_secs {
+            info.BasicLimitInformation.LimitFlags |= JOB_OBJECT_LIMIT_PROCESS_TIME;
+            // In 100-nanosecond ticks
+            info.BasicLimitInformation.PerProcessUserTimeLimit =
+                i64::try_from(secs.saturating_mul(10_000_000)).unwrap_or(i64::MAX);
+        }
+        if let Some(bytes) = max_memory_bytes {
+            info.BasicLimitInformation.LimitFlags |= JOB_OBJECT_LIMIT_PROCESS_MEMORY;
+            info.ProcessMemoryLimit = usize::try_from(bytes).unwrap_or(usize::MAX);
+        }
+
+        let job = CreateJobObjectW(std::ptr::null(), std::ptr::null());
+        if job.is_null() {
+            return Err(std::io::Error::last_os_error());
+        }
+        let assigned = SetInformationJobObject(
+            job,
+            JobObjectExtendedLimitInformation,
+            &info as *const _ as *const core::ffi::c_void,
+            std::mem::size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as u32,
+        ) != 0
+            && AssignProcessToJobObject(job, process as _) != 0;
+        let result = if assigned { Ok(()) } else { Err(std::io::Error::last_os_error()) };
+        CloseHandle(job);
+        result
+    }
+}
+
+/// The limit a process that exited with `status` ran into, if any
+///
+/// CPU limits end the process with SIGXCPU or SIGKILL on Unix and with
+/// `ERROR_NOT_ENOUGH_QUOTA` on Windows. A memory limit only makes
+/// allocations fail, so a failed process counts as over it when it
+/// crashed the way failed allocations crash, or said it ran out of memory.
+pub fn exit_violation(
+    status: &ExitStatus,
+    max_cpu_secs: Option<u64>,
+    max_memory_bytes: Option<u64>,
+    stderr: &[TerminalOutput],
+) -> Option<String> {
+    if status.success() {
+        return None;
+    }
+    let (cpu_killed, crashed) = exit_cause(status);
+    if let Some(secs) = max_cpu_secs.filter(|_| cpu_killed) {
+        return Some(format!("CPU time exceeded {}s", secs));
+    }
+    let out_of_memory = stderr.iter().any(|line| reports_out_of_memory(&line.content));
+    if let Some(bytes) = max_memory_bytes.filter(|_| crashed || out_of_memory) {
+        return Some(format!("memory exceeded {} bytes", bytes));
+    }
+    None
+}
+
+/// Whether the process was stopped for CPU time, and whether it crashed
+#[cfg(unix)]
+fn exit_cause(status: &ExitStatus) -> (bool, bool) {
+    use nix::sys::signal::Signal;
+    use std::os::unix::process::ExitStatusExt;
+
+    match status.signal().and_then(|signal| Signal::try_from(signal).ok()) {
+        Some(Signal::SIGXCPU | Signal::SIGKILL) => (true, false),
+        Some(Signal::SIGABRT | Signal::SIGSEGV | Signal::SIGBUS) => (false, true),
+        _ => (false, false),
+    }
+}
+
+/// Whether the process was stopped for CPU time, and whether it crashed
+#[cfg(windows)]
+fn exit_cause(status: &ExitStatus) -> (bool, bool) {
+    use windows_sys::Win32::Foundation::{ERROR_NOT_ENOUGH_QUOTA, STATUS_NO_MEMORY};
+
+    match status.code() {
+        Some(code) if code as u32 == ERROR_NOT_ENOUGH_QUOTA => (true, false),
+        Some(code) if code == STATUS_NO_MEMORY => (false, true),
+        _ => (false, false),
+    }
+}
+
+fn reports_out_of_memory(line: &str) -> bool {
+    let line = line.to_ascii_lowercase();
+    ["out of memory", "cannot allocate memory", "memory allocation of", "memoryerror", "bad_alloc"]
+        .iter()
+        .any(|marker| line.contains(marker))
+}
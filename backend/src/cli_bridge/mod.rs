@@ -9,6 +9,9 @@ pub mod executor;
 pub mod error;
 pub mod types;
 pub mod pty;
+pub mod policy;
+pub mod environment;
+pub mod limits;
 
 #[cfg(test)]
 mod tests;
@@ -16,8 +19,10 @@ mod tests;
 pub use session::{SessionManager, SessionInfo, SessionState, CommandHistoryEntry};
 pub use executor::CommandExecutor;
 pub use error::{CliError, ErrorReport, ErrorSeverity};
-pub use types::{CommandHandle, CommandStatus, TerminalOutput, OutputType, SecurityConfig, ProcessType};
+pub use types::{CommandHandle, CommandStatus, TerminalOutput, OutputType, SecurityConfig, ResourceLimits, ProcessType};
 pub use pty::PtySession;
+pub use policy::{CommandPolicy, PolicyAction, PolicyError, PolicyRule, PolicyStore, PatternKind};
+pub use environment::EnvironmentProfile;
 
 use std::sync::Arc;
 use tokio::sync::RwLock;
@@ -44,6 +49,18 @@ impl CliBridge {
         cmd: String,
         args: Vec<String>,
         cwd: Option<std::path::PathBuf>,
+    ) -> Result<CommandHandle, CliError> {
+        self.execute_command_with_env(cmd, args, cwd, Vec::new()).await
+    }
+
+    /// Execute a command with extra environment variables (e.g. from an
+    /// [`EnvironmentProfile`])
+    pub async fn execute_command_with_env(
+        &self,
+        cmd: String,
+        args: Vec<String>,
+        cwd: Option<std::path::PathBuf>,
+        env: Vec<(String, String)>,
     ) -> Result<CommandHandle, CliError> {
         // Validate command
         self.executor.validate_command(&cmd, &args).await?;
@@ -55,7 +72,7 @@ impl CliBridge {
         };
 
         // Execute command with sandboxing
-        let handle = self.executor.spawn_command(session_id.clone(), cmd, args, cwd).await?;
+        let handle = self.executor.spawn_command_with_env(session_id.clone(), cmd, args, cwd, env).await?;
 
         // Register session
         {
@@ -136,6 +153,28 @@ impl CliBridge {
         self.executor.read_output(&session.command_handle).await
     }
 
+    /// Check whether a command was killed for exceeding its resource limits
+    pub async fn check_resource_limits(
+        &self,
+        session_id: &str,
+    ) -> Result<(), CliError> {
+        let manager = self.session_manager.read().await;
+        let session = manager.get_session(session_id)?;
+
+        self.executor.check_resource_limits(&session.command_handle).await
+    }
+
+    /// Exit status of a session's command, or `None` while it is still running
+    pub async fn try_wait(
+        &self,
+        session_id: &str,
+    ) -> Result<Option<std::process::ExitStatus>, CliError> {
+        let manager = self.session_manager.read().await;
+        let session = manager.get_session(session_id)?;
+
+        self.executor.try_wait(&session.command_handle).await
+    }
+
     /// Terminate a session
     pub async fn terminate_session(
         &self,
@@ -0,0 +1,359 @@
+//! User-editable command policy
+//!
+//! Rules are matched in order against the full command line and the first
+//! match decides whether the command is allowed, denied, or needs explicit
+//! user confirmation. The policy is persisted as JSON and reloaded
+//! automatically when the file changes on disk.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::SystemTime;
+use tracing::{info, warn};
+
+use crate::json_store;
+
+const POLICY_FILE: &str = "command_policy.json";
+
+/// What to do with a command matched by a rule
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyAction {
+    Allow,
+    Deny,
+    RequireConfirmation,
+}
+
+/// How a rule pattern is interpreted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PatternKind {
+    /// Matches if the command line contains the pattern
+    Substring,
+    /// Shell-style glob (`*`, `?`) matched against the whole command line
+    Glob,
+    /// Regular expression searched within the command line
+    Regex,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum PolicyError {
+    #[error("Rule {0} not found")]
+    NotFound(String),
+
+    #[error("{0}")]
+    Invalid(String),
+
+    #[error("Failed to save command policy: {0}")]
+    Io(String),
+}
+
+/// A single allow/deny/confirm rule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PolicyRule {
+    pub id: String,
+    pub pattern: String,
+    pub kind: PatternKind,
+    pub action: PolicyAction,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Compiled glob or regex pattern, built once per loaded rule
+    #[serde(skip)]
+    regex: OnceLock<Result<Regex, String>>,
+}
+
+impl PolicyRule {
+    pub fn new(pattern: &str, kind: PatternKind, action: PolicyAction) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            pattern: pattern.to_string(),
+            kind,
+            action,
+            description: None,
+            regex: OnceLock::new(),
+        }
+    }
+
+    /// The rule's pattern as a regex; `None` for substring rules
+    fn regex(&self) -> Option<&Result<Regex, String>> {
+        let source = match self.kind {
+            PatternKind::Substring => return None,
+            PatternKind::Glob => glob_to_regex(&self.pattern),
+            PatternKind::Regex => self.pattern.clone(),
+        };
+        Some(self.regex.get_or_init(|| Regex::new(&source).map_err(|e| e.to_string())))
+    }
+
+    /// Check the rule's pattern compiles
+    pub fn validate(&self) -> Result<(), String> {
+        if self.pattern.is_empty() {
+            return Err("Rule pattern cannot be empty".to_string());
+        }
+        match self.regex() {
+            Some(Err(e)) => {
+                let kind = if self.kind == PatternKind::Glob { "glob" } else { "regex" };
+                Err(format!("Invalid {} '{}': {}", kind, self.pattern, e))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Check whether the command line matches this rule
+    pub fn matches(&self, command_line: &str) -> bool {
+        match self.regex() {
+            None => command_line.contains(&self.pattern),
+            Some(regex) => regex.as_ref().is_ok_and(|re| re.is_match(command_line)),
+        }
+    }
+}
+
+/// Ordered set of command rules; the first matching rule wins
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandPolicy {
+    pub rules: Vec<PolicyRule>,
+}
+
+impl Default for CommandPolicy {
+    fn default() -> Self {
+        let mut rules = vec![PolicyRule {
+            id: "builtin-blocked-power".to_string(),
+            pattern: r"(^|[\s;&|/])(reboot|shutdown|halt|poweroff)(\s|$)".to_string(),
+            kind: PatternKind::Regex,
+            action: PolicyAction::Deny,
+            description: Some("System power commands are blocked".to_string()),
+            regex: OnceLock::new(),
+        }];
+
+        let dangerous = [
+            "rm -rf /",
+            "rm -rf /*",
+            "dd if=",
+            "mkfs",
+            "fdisk",
+            "parted",
+            "> /dev/",
+            "chmod -R 777",
+            "chown -R",
+            ":(){ :|:& };:", // Fork bomb
+        ];
+        for (i, pattern) in dangerous.iter().enumerate() {
+            rules.push(PolicyRule {
+                id: format!("builtin-dangerous-{}", i),
+                pattern: pattern.to_string(),
+                kind: PatternKind::Substring,
+                action: PolicyAction::RequireConfirmation,
+                description: Some("Potentially destructive command".to_string()),
+                regex: OnceLock::new(),
+            });
+        }
+
+        Self { rules }
+    }
+}
+
+impl CommandPolicy {
+    /// Find the first rule matching the command line
+    pub fn evaluate(&self, command_line: &str) -> Option<&PolicyRule> {
+        self.rules.iter().find(|rule| rule.matches(command_line))
+    }
+
+    /// Validate every rule in the policy
+    pub fn validate(&self) -> Result<(), String> {
+        for rule in &self.rules {
+            rule.validate()?;
+        }
+        Ok(())
+    }
+}
+
+/// Convert a shell-style glob into an anchored regex
+fn glob_to_regex(glob: &str) -> String {
+    let mut regex = String::from("^");
+    for c in glob.chars() {
+        match c {
+            '*' => regex.push_str(".*"),
+            '?' => regex.push('.'),
+            _ => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex.push('$');
+    regex
+}
+
+#[derive(Debug)]
+struct CachedPolicy {
+    modified: Option<SystemTime>,
+    policy: CommandPolicy,
+}
+
+/// File-backed policy store with hot reload on modification
+#[derive(Debug)]
+pub struct PolicyStore {
+    path: PathBuf,
+    cached: RwLock<CachedPolicy>,
+}
+
+lazy_static::lazy_static! {
+    static ref GLOBAL_STORE: Arc<PolicyStore> = Arc::new(PolicyStore::new(
+        dirs::home_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join(".skhoot")
+            .join(POLICY_FILE),
+    ));
+}
+
+impl PolicyStore {
+    /// Create a store backed by the given file. Falls back to the default policy
+    /// if the file is missing or invalid.
+    pub fn new(path: PathBuf) -> Self {
+        let (modified, policy) = Self::read(&path);
+        Self {
+            path,
+            cached: RwLock::new(CachedPolicy { modified, policy }),
+        }
+    }
+
+    /// Shared store at `~/.skhoot/command_policy.json`
+    pub fn global() -> Arc<PolicyStore> {
+        GLOBAL_STORE.clone()
+    }
+
+    /// Path of the backing policy file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Get the current policy, reloading it if the file changed on disk
+    pub fn get(&self) -> CommandPolicy {
+        let on_disk = Self::modified_time(&self.path);
+        {
+            let cached = self.cached.read().unwrap();
+            if cached.modified == on_disk {
+                return cached.policy.clone();
+            }
+        }
+
+        let (modified, policy) = Self::read(&self.path);
+        info!("Reloaded command policy from {}", self.path.display());
+        let mut cached = self.cached.write().unwrap();
+        cached.modified = modified;
+        cached.policy = policy.clone();
+        policy
+    }
+
+    /// Validate and persist a new policy
+    pub fn set(&self, policy: CommandPolicy) -> Result<(), PolicyError> {
+        policy.validate().map_err(PolicyError::Invalid)?;
+
+        json_store::save(&self.path, &policy).map_err(|e| PolicyError::Io(e.to_string()))?;
+
+        let mut cached = self.cached.write().unwrap();
+        cached.modified = Self::modified_time(&self.path);
+        cached.policy = policy;
+        Ok(())
+    }
+
+    /// Insert a rule at the front of the policy so it takes precedence
+    pub fn add_rule(&self, rule: PolicyRule) -> Result<CommandPolicy, PolicyError> {
+        rule.validate().map_err(PolicyError::Invalid)?;
+        let mut policy = self.get();
+        policy.rules.insert(0, rule);
+        self.set(policy.clone())?;
+        Ok(policy)
+    }
+
+    /// Remove a rule by ID
+    pub fn remove_rule(&self, id: &str) -> Result<CommandPolicy, PolicyError> {
+        let mut policy = self.get();
+        let before = policy.rules.len();
+        policy.rules.retain(|r| r.id != id);
+        if policy.rules.len() == before {
+            return Err(PolicyError::NotFound(id.to_string()));
+        }
+        self.set(policy.clone())?;
+        Ok(policy)
+    }
+
+    /// Restore the built-in default policy
+    pub fn reset(&self) -> Result<CommandPolicy, PolicyError> {
+        let policy = CommandPolicy::default();
+        self.set(policy.clone())?;
+        Ok(policy)
+    }
+
+    fn modified_time(path: &Path) -> Option<SystemTime> {
+        std::fs::metadata(path).and_then(|m| m.modified()).ok()
+    }
+
+    fn read(path: &Path) -> (Option<SystemTime>, CommandPolicy) {
+        let modified = Self::modified_time(path);
+        let policy = match std::fs::read_to_string(path) {
+            Ok(content) => match serde_json::from_str::<CommandPolicy>(&content) {
+                Ok(policy) if policy.validate().is_ok() => policy,
+                Ok(_) | Err(_) => {
+                    warn!("Invalid command policy at {}, using defaults", path.display());
+                    CommandPolicy::default()
+                }
+            },
+            Err(_) => CommandPolicy::default(),
+        };
+        (modified, policy)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_default_policy_blocks_power_commands() {
+        let policy = CommandPolicy::default();
+        let rule = policy.evaluate("/bin/sh -c reboot").unwrap();
+        assert_eq!(rule.action, PolicyAction::Deny);
+        assert!(policy.evaluate("echo rebooting").is_none());
+    }
+
+    #[test]
+    fn test_glob_rule_matches_whole_command() {
+        let rule = PolicyRule::new("git push*", PatternKind::Glob, PolicyAction::RequireConfirmation);
+        assert!(rule.matches("git push origin main"));
+        assert!(!rule.matches("echo git push"));
+    }
+
+    #[test]
+    fn test_allow_rule_takes_precedence_when_first() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = PolicyStore::new(temp_dir.path().join(POLICY_FILE));
+        let allow = PolicyRule::new("rm -rf /tmp/build", PatternKind::Substring, PolicyAction::Allow);
+        let policy = store.add_rule(allow).unwrap();
+
+        assert_eq!(policy.evaluate("rm -rf /tmp/build").unwrap().action, PolicyAction::Allow);
+        assert_eq!(policy.evaluate("rm -rf /").unwrap().action, PolicyAction::RequireConfirmation);
+    }
+
+    #[test]
+    fn test_invalid_regex_rejected() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = PolicyStore::new(temp_dir.path().join(POLICY_FILE));
+        let rule = PolicyRule::new("([", PatternKind::Regex, PolicyAction::Deny);
+        assert!(matches!(store.add_rule(rule), Err(PolicyError::Invalid(_))));
+        assert!(matches!(store.remove_rule("missing"), Err(PolicyError::NotFound(_))));
+    }
+
+    #[test]
+    fn test_store_reloads_when_file_changes() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join(POLICY_FILE);
+        let store = PolicyStore::new(path.clone());
+        assert!(store.get().evaluate("npm publish").is_none());
+
+        let policy = CommandPolicy {
+            rules: vec![PolicyRule::new("npm publish", PatternKind::Substring, PolicyAction::Deny)],
+        };
+        std::fs::write(&path, serde_json::to_string(&policy).unwrap()).unwrap();
+
+        assert_eq!(store.get().evaluate("npm publish").unwrap().action, PolicyAction::Deny);
+    }
+}
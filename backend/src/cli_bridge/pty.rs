@@ -2,6 +2,11 @@
 //!
 //! This module provides PTY session management for proper terminal emulation,
 //! including ANSI escape code support, terminal resizing, and interactive shell support.
+//!
+//! On Windows the PTY is a ConPTY. Output escape sequences are passed through
+//! untouched, interactive shells are started with UTF-8 console code pages,
+//! and the default shell is PowerShell Core when installed, then Windows
+//! PowerShell, then cmd.
 
 use super::error::CliError;
 use super::types::TerminalOutput;
@@ -15,10 +20,159 @@ use tracing::{debug, warn, error};
 const DEFAULT_COLS: u16 = 80;
 const DEFAULT_ROWS: u16 = 24;
 
+/// What the Enter key sends; ConPTY expects a carriage return
+const ENTER: &str = if cfg!(target_os = "windows") { "\r" } else { "\n" };
+
+/// Shell families that need their own startup flags
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShellKind {
+    /// PowerShell Core (pwsh)
+    Pwsh,
+    /// Windows PowerShell 5.x (powershell)
+    PowerShell,
+    Cmd,
+    /// sh, bash, zsh, fish and anything else
+    Posix,
+}
+
+impl ShellKind {
+    /// Kind of a shell program, from its file name
+    pub fn of(program: &str) -> Self {
+        // Windows paths may reach us on any platform, so split on both separators
+        let name = program.rsplit(['/', '\\']).next().unwrap_or_default().to_lowercase();
+        match name.trim_end_matches(".exe") {
+            "pwsh" => Self::Pwsh,
+            "powershell" => Self::PowerShell,
+            "cmd" => Self::Cmd,
+            _ => Self::Posix,
+        }
+    }
+
+    /// Arguments for running `command` non-interactively
+    pub fn command_args(self, command: &str) -> Vec<String> {
+        let flags: &[&str] = match self {
+            Self::Pwsh | Self::PowerShell => &["-NoLogo", "-NoProfile", "-Command"],
+            Self::Cmd => &["/C"],
+            Self::Posix => &["-c"],
+        };
+        flags.iter().map(|f| f.to_string()).chain(std::iter::once(command.to_string())).collect()
+    }
+
+    /// `arg` as one literal word for this shell; left as is when it holds
+    /// nothing the shell would interpret
+    pub fn quote(self, arg: &str) -> String {
+        let plain = !arg.is_empty() && arg.chars().all(|c| c.is_ascii_alphanumeric() || "_-.,:/=@+".contains(c));
+        if plain {
+            return arg.to_string();
+        }
+        match self {
+            Self::Pwsh | Self::PowerShell => format!("'{}'", arg.replace('\'', "''")),
+            // cmd expands %VAR% even inside quotes, so `%` is escaped with `^`
+            // outside them
+            Self::Cmd => format!("\"{}\"", arg.replace('"', "\"\"").replace('%', "\"^%\"")),
+            Self::Posix => format!("'{}'", arg.replace('\'', "'\\''")),
+        }
+    }
+
+    /// Arguments for starting an interactive shell with UTF-8 input and output
+    pub fn interactive_args(self) -> Vec<String> {
+        let args: &[&str] = match self {
+            Self::Pwsh | Self::PowerShell => &[
+                "-NoLogo",
+                "-NoProfile",
+                "-ExecutionPolicy",
+                "Bypass",
+                "-NoExit",
+                "-Command",
+                "[Console]::InputEncoding = [Console]::OutputEncoding = [System.Text.UTF8Encoding]::new($false)",
+            ],
+            Self::Cmd => &["/Q", "/K", "chcp 65001 >NUL"],
+            Self::Posix => &[],
+        };
+        args.iter().map(|a| a.to_string()).collect()
+    }
+}
+
+/// First of pwsh, powershell and cmd that `installed` reports as available
+pub fn detect_windows_shell(installed: impl Fn(&str) -> bool) -> &'static str {
+    ["pwsh.exe", "powershell.exe"]
+        .into_iter()
+        .find(|shell| installed(shell))
+        .unwrap_or("cmd.exe")
+}
+
+/// Shell for new interactive sessions: the detected Windows shell, or
+/// `$SHELL` falling back to bash and sh elsewhere
+pub fn default_shell() -> String {
+    if cfg!(target_os = "windows") {
+        detect_windows_shell(|name| find_on_path(name).is_some()).to_string()
+    } else {
+        std::env::var("SHELL").unwrap_or_else(|_| {
+            if std::path::Path::new("/bin/bash").exists() {
+                "/bin/bash".to_string()
+            } else {
+                "/bin/sh".to_string()
+            }
+        })
+    }
+}
+
+/// Full path of a program found in PATH
+pub fn find_on_path(name: &str) -> Option<std::path::PathBuf> {
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path)
+        .map(|dir| dir.join(name))
+        .find(|candidate| candidate.is_file())
+}
+
+/// Decodes a byte stream as UTF-8, holding back characters split across reads
+#[derive(Debug, Default)]
+pub struct Utf8Decoder {
+    pending: Vec<u8>,
+}
+
+impl Utf8Decoder {
+    /// Text decoded from the bytes received so far; an incomplete trailing
+    /// character waits for the next call and invalid bytes become U+FFFD
+    pub fn decode(&mut self, bytes: &[u8]) -> String {
+        self.pending.extend_from_slice(bytes);
+        let mut text = String::new();
+        let mut rest = self.pending.as_slice();
+        loop {
+            match std::str::from_utf8(rest) {
+                Ok(valid) => {
+                    text.push_str(valid);
+                    rest = &[];
+                    break;
+                }
+                Err(e) => {
+                    let (valid, after) = rest.split_at(e.valid_up_to());
+                    // SAFETY: from_utf8 reported these bytes as valid
+                    text.push_str(unsafe { std::str::from_utf8_unchecked(valid) });
+                    match e.error_len() {
+                        Some(len) => {
+                            text.push(char::REPLACEMENT_CHARACTER);
+                            rest = &after[len..];
+                        }
+                        None => {
+                            rest = after;
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+        self.pending = rest.to_vec();
+        text
+    }
+}
+
 /// PTY session wrapper for managing pseudo-terminal operations
 pub struct PtySession {
     /// The master PTY handle
     master: Arc<Mutex<Box<dyn MasterPty + Send>>>,
+    /// Input side of the PTY; it can only be taken from the master once
+    writer: Arc<Mutex<Box<dyn Write + Send>>>,
     /// The child process
     child: Box<dyn Child + Send + Sync>,
     /// Current terminal size
@@ -64,11 +218,17 @@ impl PtySession {
 
         // Build the command
         let mut command = CommandBuilder::new(cmd);
-        command.args(args);
-        
-        // On Windows, use -NoLogo -NoProfile -ExecutionPolicy Bypass for powershell
-        if cfg!(target_os = "windows") && cmd.contains("powershell.exe") {
-            command.args(&["-NoLogo", "-NoProfile", "-ExecutionPolicy", "Bypass"]);
+        if args.is_empty() {
+            // A bare shell is interactive; start it with UTF-8 code pages
+            command.args(ShellKind::of(cmd).interactive_args());
+        } else {
+            command.args(args);
+        }
+        command.env("TERM", "xterm-256color");
+        if cfg!(target_os = "windows") {
+            // Console programs such as the Python REPL otherwise use the ANSI code page
+            command.env("PYTHONIOENCODING", "utf-8");
+            command.env("PYTHONUTF8", "1");
         }
 
         if let Some(dir) = cwd {
@@ -85,10 +245,19 @@ impl PtySession {
             }
         })?;
 
+        // The slave handle must be closed for ConPTY to report EOF on exit
+        drop(pair.slave);
+
+        let writer = pair.master.take_writer().map_err(|e| {
+            error!("Failed to get PTY writer: {}", e);
+            CliError::Internal(format!("Failed to get PTY writer: {}", e))
+        })?;
+
         debug!("PTY session {} created successfully", session_id);
 
         Ok(Self {
             master: Arc::new(Mutex::new(pair.master)),
+            writer: Arc::new(Mutex::new(writer)),
             child,
             size,
             output_buffer: Arc::new(tokio::sync::Mutex::new(Vec::new())),
@@ -100,20 +269,12 @@ impl PtySession {
     pub fn write_input(&mut self, input: &str) -> Result<(), CliError> {
         debug!("Writing to PTY session {}: {}", self.session_id, input);
         
-        // Lock master (blocking)
-        let mut master = self.master.lock().map_err(|_| {
-            CliError::Internal("Failed to lock PTY master".to_string())
+        let mut writer = self.writer.lock().map_err(|_| {
+            CliError::Internal("Failed to lock PTY writer".to_string())
         })?;
         
-        let writer = master.take_writer().map_err(|e| {
-            error!("Failed to get PTY writer: {}", e);
-            CliError::Internal(format!("Failed to get PTY writer: {}", e))
-        })?;
-
-        let mut writer = writer;
-        
-        // Write input with newline
-        let input_with_newline = format!("{}\n", input);
+        // Write input followed by Enter
+        let input_with_newline = format!("{}{}", input, ENTER);
         writer.write_all(input_with_newline.as_bytes()).map_err(|e| {
             error!("Failed to write to PTY: {}", e);
             CliError::Io(format!("Failed to write to PTY: {}", e))
@@ -261,8 +422,8 @@ impl PtySession {
         let output_buffer = self.output_buffer.clone();
         let master_clone = self.master.clone();
         
-        // Clone the reader for the background task
-        tokio::spawn(async move {
+        // PTY reads block, so they get their own thread instead of a runtime worker
+        tokio::task::spawn_blocking(move || {
             let reader_result = {
                 let master = master_clone.lock().unwrap(); // Use unwrap in thread/task context
                 master.try_clone_reader()
@@ -277,6 +438,7 @@ impl PtySession {
             };
 
             let mut buffer = vec![0u8; 8192];
+            let mut decoder = Utf8Decoder::default();
             
             loop {
                 match reader.read(&mut buffer) {
@@ -286,8 +448,11 @@ impl PtySession {
                         break;
                     }
                     Ok(n) => {
-                        // Convert raw bytes to string, preserving ANSI codes
-                        let data = String::from_utf8_lossy(&buffer[..n]).to_string();
+                        // Decode across reads so split characters survive; ANSI codes are kept
+                        let data = decoder.decode(&buffer[..n]);
+                        if data.is_empty() {
+                            continue;
+                        }
                         
                         let output = TerminalOutput {
                             timestamp: chrono::Utc::now(),
@@ -296,12 +461,12 @@ impl PtySession {
                             ansi_formatted: true, // PTY output includes ANSI codes
                         };
 
-                        let mut buf = output_buffer.lock().await;
+                        let mut buf = output_buffer.blocking_lock();
                         buf.push(output);
                     }
                     Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
                         // No data available, wait a bit
-                        tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+                        std::thread::sleep(std::time::Duration::from_millis(10));
                     }
                     Err(e) => {
                         warn!("Error reading from PTY session {}: {}", session_id, e);
@@ -392,4 +557,93 @@ mod tests {
         assert!(result.is_ok());
         assert_eq!(session.get_size(), (100, 30));
     }
+
+    #[test]
+    fn test_shell_detection() {
+        assert_eq!(ShellKind::of("C:\\Program Files\\PowerShell\\7\\pwsh.exe"), ShellKind::Pwsh);
+        assert_eq!(ShellKind::of("powershell.exe"), ShellKind::PowerShell);
+        assert_eq!(ShellKind::of("CMD.EXE"), ShellKind::Cmd);
+        assert_eq!(ShellKind::of("/bin/zsh"), ShellKind::Posix);
+
+        assert_eq!(ShellKind::Posix.quote("eu-west-1"), "eu-west-1");
+        assert_eq!(ShellKind::Posix.quote("it's; rm -rf ~"), "'it'\\''s; rm -rf ~'");
+        assert_eq!(ShellKind::Pwsh.quote("a'b $(x)"), "'a''b $(x)'");
+        assert_eq!(ShellKind::Cmd.quote("50% \"off\" & more"), "\"50\"^%\" \"\"off\"\" & more\"");
+
+        assert_eq!(detect_windows_shell(|_| true), "pwsh.exe");
+        assert_eq!(detect_windows_shell(|name| name == "powershell.exe"), "powershell.exe");
+        assert_eq!(detect_windows_shell(|_| false), "cmd.exe");
+    }
+
+    #[test]
+    fn test_utf8_decoder_joins_split_characters() {
+        let bytes = "é→🦀".as_bytes();
+        let mut decoder = Utf8Decoder::default();
+        let mut text = String::new();
+        for byte in bytes {
+            text.push_str(&decoder.decode(&[*byte]));
+        }
+        assert_eq!(text, "é→🦀");
+
+        assert_eq!(decoder.decode(b"a\xffb"), "a\u{FFFD}b");
+    }
+
+    /// Read buffered output until `expected` shows up `times` times or a few
+    /// seconds pass
+    async fn wait_for_output(session: &PtySession, expected: &str, times: usize) -> bool {
+        for _ in 0..100 {
+            let output: String = session.get_buffered_output().await.into_iter().map(|o| o.content).collect();
+            if output.matches(expected).count() >= times {
+                return true;
+            }
+            tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        }
+        false
+    }
+
+    #[tokio::test]
+    async fn test_python_repl() {
+        let python = if cfg!(target_os = "windows") { "python.exe" } else { "python3" };
+        let Some(python) = find_on_path(python) else {
+            return;
+        };
+
+        let mut session = PtySession::new(
+            "test-repl".to_string(),
+            &python.to_string_lossy(),
+            &["-q".to_string()],
+            None,
+            Some(80),
+            Some(24),
+        ).unwrap();
+        let reader = session.start_output_reader();
+
+        assert!(wait_for_output(&session, ">>>", 1).await);
+        session.write_input("print('ok', 6 * 7, '\\u00e9')").unwrap();
+        assert!(wait_for_output(&session, "ok 42 é", 1).await);
+
+        session.resize(120, 40).unwrap();
+        session.write_input("exit()").unwrap();
+        assert_eq!(session.wait().unwrap(), Some(0));
+        reader.await.unwrap();
+    }
+
+    #[cfg(target_os = "windows")]
+    #[tokio::test]
+    async fn test_default_shell_runs_commands() {
+        let mut session = PtySession::new(
+            "test-shell".to_string(),
+            &default_shell(),
+            &[],
+            None,
+            Some(80),
+            Some(24),
+        ).unwrap();
+        let _reader = session.start_output_reader();
+
+        // Once echoed as typed, once as the command's output
+        session.write_input("echo skhoot-conpty").unwrap();
+        assert!(wait_for_output(&session, "skhoot-conpty", 2).await);
+        session.kill().unwrap();
+    }
 }
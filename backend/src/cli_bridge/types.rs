@@ -11,6 +11,8 @@ pub struct SecurityConfig {
     pub sandbox_enabled: bool,
     pub require_confirmation_for_dangerous: bool,
     pub dangerous_command_patterns: Vec<String>,
+    #[serde(default)]
+    pub resource_limits: ResourceLimits,
 }
 
 impl Default for SecurityConfig {
@@ -24,6 +26,32 @@ impl Default for SecurityConfig {
                 "mkfs".to_string(),
                 "fdisk".to_string(),
             ],
+            resource_limits: ResourceLimits::default(),
+        }
+    }
+}
+
+/// Per-command resource limits. `None` means unlimited.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourceLimits {
+    /// Wall-clock time before the process is killed
+    pub max_runtime_secs: Option<u64>,
+    /// CPU time limit (RLIMIT_CPU on Unix, a job object on Windows)
+    pub max_cpu_secs: Option<u64>,
+    /// Address space limit (RLIMIT_AS on Unix, committed memory of a job
+    /// object on Windows)
+    pub max_memory_bytes: Option<u64>,
+    /// Combined stdout/stderr bytes captured before the process is killed
+    pub max_output_bytes: Option<usize>,
+}
+
+impl Default for ResourceLimits {
+    fn default() -> Self {
+        Self {
+            max_runtime_secs: None,
+            max_cpu_secs: None,
+            max_memory_bytes: None,
+            max_output_bytes: Some(10 * 1024 * 1024), // 10MB
         }
     }
 }
@@ -124,6 +152,10 @@ pub struct ProcessHandle {
     pub stdin: Arc<Mutex<Option<tokio::process::ChildStdin>>>,
     pub stdout_buffer: Arc<Mutex<Vec<TerminalOutput>>>,
     pub stderr_buffer: Arc<Mutex<Vec<TerminalOutput>>>,
+    /// Set when the process was killed for exceeding a resource limit
+    pub limit_violation: Arc<Mutex<Option<String>>>,
+    /// Limits the process was started with
+    pub limits: ResourceLimits,
 }
 
 impl ProcessHandle {
@@ -133,9 +165,16 @@ impl ProcessHandle {
             stdin: Arc::new(Mutex::new(None)),
             stdout_buffer: Arc::new(Mutex::new(Vec::new())),
             stderr_buffer: Arc::new(Mutex::new(Vec::new())),
+            limit_violation: Arc::new(Mutex::new(None)),
+            limits: ResourceLimits::default(),
         }
     }
 
+    pub fn with_limits(mut self, limits: ResourceLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
     pub fn with_stdin(mut self, stdin: tokio::process::ChildStdin) -> Self {
         self.stdin = Arc::new(Mutex::new(Some(stdin)));
         self
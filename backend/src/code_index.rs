@@ -0,0 +1,566 @@
+// Code Structure Index
+// Functions, types, classes and imports of Rust, Python, JavaScript and
+// TypeScript files, parsed with tree-sitter and stored in the database so
+// symbol names can be looked up directly and searches for them rank the
+// defining file above files that merely mention the name
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tree_sitter::{Node, Parser, Query, QueryCursor, StreamingIterator};
+
+use crate::db::{CodeSymbolFilter, CodeSymbolRecord, Database};
+use crate::error::AppError;
+use crate::ignore_rules::IgnoreRules;
+use crate::search_engine::MergedSearchResult;
+
+/// Larger files are generated or vendored more often than not
+const MAX_FILE_BYTES: u64 = 1024 * 1024;
+
+/// Longest signature kept per symbol
+const MAX_SIGNATURE_CHARS: usize = 160;
+
+/// Largest page of symbol results
+const MAX_LIMIT: usize = 200;
+
+/// Scores given to symbol matches in file search, above the exact glob
+/// matches (1.0) plus the largest learned boost
+const EXACT_SYMBOL_SCORE: f64 = 2.0;
+const PREFIX_SYMBOL_SCORE: f64 = 1.4;
+
+/// Words in a search that say what kind of symbol is wanted
+const KIND_WORDS: &[(&str, &str)] = &[
+    ("function", "function"),
+    ("fn", "function"),
+    ("func", "function"),
+    ("def", "function"),
+    ("method", "method"),
+    ("class", "class"),
+    ("struct", "struct"),
+    ("enum", "enum"),
+    ("trait", "trait"),
+    ("interface", "interface"),
+    ("type", "type"),
+    ("module", "module"),
+    ("mod", "module"),
+    ("const", "constant"),
+    ("constant", "constant"),
+    ("macro", "macro"),
+    ("import", "import"),
+];
+
+/// Words in a search that are never the symbol being looked for
+const FILLER_WORDS: &[&str] = &[
+    "a", "an", "the", "find", "where", "is", "are", "defined", "definition", "declared", "of", "in", "for", "show",
+    "me", "named", "called", "search", "locate", "open", "go", "to", "get", "which", "file", "files", "code",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CodeLanguage {
+    Rust,
+    Python,
+    JavaScript,
+    TypeScript,
+    Tsx,
+}
+
+const RUST_QUERY: &str = r#"
+(function_item name: (identifier) @name) @function
+(function_signature_item name: (identifier) @name) @function
+(struct_item name: (type_identifier) @name) @struct
+(enum_item name: (type_identifier) @name) @enum
+(union_item name: (type_identifier) @name) @struct
+(trait_item name: (type_identifier) @name) @trait
+(type_item name: (type_identifier) @name) @type
+(mod_item name: (identifier) @name) @module
+(const_item name: (identifier) @name) @constant
+(static_item name: (identifier) @name) @constant
+(macro_definition name: (identifier) @name) @macro
+(use_declaration argument: (_) @name) @import
+"#;
+
+const PYTHON_QUERY: &str = r#"
+(function_definition name: (identifier) @name) @function
+(class_definition name: (identifier) @name) @class
+(import_statement name: (_) @name) @import
+(import_from_statement module_name: (_) @name) @import
+"#;
+
+const JAVASCRIPT_QUERY: &str = r#"
+(function_declaration name: (identifier) @name) @function
+(generator_function_declaration name: (identifier) @name) @function
+(class_declaration name: (_) @name) @class
+(method_definition name: (_) @name) @method
+(lexical_declaration
+  (variable_declarator name: (identifier) @name value: [(arrow_function) (function_expression)])) @function
+(variable_declaration
+  (variable_declarator name: (identifier) @name value: [(arrow_function) (function_expression)])) @function
+(import_statement source: (string) @name) @import
+"#;
+
+const TYPESCRIPT_QUERY: &str = r#"
+(function_signature name: (identifier) @name) @function
+(abstract_class_declaration name: (type_identifier) @name) @class
+(interface_declaration name: (type_identifier) @name) @interface
+(type_alias_declaration name: (type_identifier) @name) @type
+(enum_declaration name: (identifier) @name) @enum
+(internal_module name: (_) @name) @module
+"#;
+
+lazy_static::lazy_static! {
+    static ref RUST: Query = CodeLanguage::Rust.compile();
+    static ref PYTHON: Query = CodeLanguage::Python.compile();
+    static ref JAVASCRIPT: Query = CodeLanguage::JavaScript.compile();
+    static ref TYPESCRIPT: Query = CodeLanguage::TypeScript.compile();
+    static ref TSX: Query = CodeLanguage::Tsx.compile();
+}
+
+impl CodeLanguage {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "rs" => Some(Self::Rust),
+            "py" | "pyi" => Some(Self::Python),
+            "js" | "jsx" | "mjs" | "cjs" => Some(Self::JavaScript),
+            "ts" | "mts" | "cts" => Some(Self::TypeScript),
+            "tsx" => Some(Self::Tsx),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Rust => "rust",
+            Self::Python => "python",
+            Self::JavaScript => "javascript",
+            Self::TypeScript => "typescript",
+            Self::Tsx => "tsx",
+        }
+    }
+
+    fn grammar(&self) -> tree_sitter::Language {
+        match self {
+            Self::Rust => tree_sitter_rust::LANGUAGE.into(),
+            Self::Python => tree_sitter_python::LANGUAGE.into(),
+            Self::JavaScript => tree_sitter_javascript::LANGUAGE.into(),
+            Self::TypeScript => tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
+            Self::Tsx => tree_sitter_typescript::LANGUAGE_TSX.into(),
+        }
+    }
+
+    fn compile(&self) -> Query {
+        let source = match self {
+            Self::Rust => RUST_QUERY.to_string(),
+            Self::Python => PYTHON_QUERY.to_string(),
+            Self::JavaScript => JAVASCRIPT_QUERY.to_string(),
+            Self::TypeScript | Self::Tsx => format!("{}{}", JAVASCRIPT_QUERY, TYPESCRIPT_QUERY),
+        };
+        Query::new(&self.grammar(), &source).expect("valid symbol query")
+    }
+
+    fn query(&self) -> &'static Query {
+        match self {
+            Self::Rust => &RUST,
+            Self::Python => &PYTHON,
+            Self::JavaScript => &JAVASCRIPT,
+            Self::TypeScript => &TYPESCRIPT,
+            Self::Tsx => &TSX,
+        }
+    }
+}
+
+/// Name of the type, trait, class or module a definition sits in
+fn container_of(node: Node, source: &[u8]) -> Option<String> {
+    let mut parent = node.parent();
+    while let Some(current) = parent {
+        let field = match current.kind() {
+            "impl_item" => Some("type"),
+            "trait_item" | "mod_item" | "class_definition" | "class_declaration" | "abstract_class_declaration"
+            | "interface_declaration" | "internal_module" => Some("name"),
+            _ => None,
+        };
+        if let Some(name) = field.and_then(|f| current.child_by_field_name(f)) {
+            return name.utf8_text(source).ok().map(str::to_string);
+        }
+        parent = current.parent();
+    }
+    None
+}
+
+fn signature(node: Node, source: &str) -> String {
+    let text = &source[node.byte_range()];
+    let line = text.lines().next().unwrap_or_default().trim();
+    let line = line.trim_end_matches('{').trim_end();
+    if line.chars().count() > MAX_SIGNATURE_CHARS {
+        format!("{}…", line.chars().take(MAX_SIGNATURE_CHARS).collect::<String>())
+    } else {
+        line.to_string()
+    }
+}
+
+/// Definitions and imports in a source file, in file order
+pub fn extract_symbols(language: CodeLanguage, path: &str, source: &str) -> Vec<CodeSymbolRecord> {
+    let mut parser = Parser::new();
+    if parser.set_language(&language.grammar()).is_err() {
+        return Vec::new();
+    }
+    let Some(tree) = parser.parse(source, None) else {
+        return Vec::new();
+    };
+
+    let query = language.query();
+    let bytes = source.as_bytes();
+    let mut symbols = Vec::new();
+    let mut cursor = QueryCursor::new();
+    let mut matches = cursor.matches(query, tree.root_node(), bytes);
+    while let Some(m) = matches.next() {
+        let mut name = None;
+        let mut definition = None;
+        for capture in m.captures {
+            match query.capture_names()[capture.index as usize] {
+                "name" => name = Some(capture.node),
+                kind => definition = Some((kind, capture.node)),
+            }
+        }
+        let (Some(name), Some((kind, node))) = (name, definition) else {
+            continue;
+        };
+        let Ok(name) = name.utf8_text(bytes) else {
+            continue;
+        };
+        let container = container_of(node, bytes);
+        // Functions declared directly in impls, traits and classes are methods
+        let member_of = node.parent().and_then(|p| p.parent()).map(|p| p.kind());
+        let kind = match (kind, member_of) {
+            ("function", Some("impl_item" | "trait_item" | "class_definition")) => "method",
+            (kind, _) => kind,
+        };
+
+        symbols.push(CodeSymbolRecord {
+            path: path.to_string(),
+            name: name.trim_matches(|c| c == '"' || c == '\'').to_string(),
+            kind: kind.to_string(),
+            line: node.start_position().row as i64 + 1,
+            end_line: node.end_position().row as i64 + 1,
+            container: if kind == "import" { None } else { container },
+            signature: signature(node, source),
+        });
+    }
+    symbols.sort_by_key(|s| (s.line, s.kind == "import"));
+    symbols.dedup_by(|a, b| a.line == b.line && a.name == b.name);
+    symbols
+}
+
+/// Outcome of indexing a directory
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CodeIndexStats {
+    pub files_indexed: usize,
+    /// Unchanged since they were last indexed
+    pub files_unchanged: usize,
+    pub symbols: usize,
+}
+
+/// Totals of the whole index
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CodeIndexTotals {
+    pub files: i64,
+    pub symbols: i64,
+}
+
+/// A symbol lookup
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SymbolQuery {
+    /// Matched case-insensitively against names: exact, then prefix, then
+    /// substring
+    pub q: Option<String>,
+    pub kind: Option<String>,
+    /// Only files under this directory
+    pub path: Option<String>,
+    pub limit: Option<usize>,
+}
+
+/// Database-backed code structure index
+pub struct CodeIndex {
+    db: Database,
+}
+
+impl CodeIndex {
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+
+    /// Index a source file if it changed since it was last indexed; returns
+    /// its symbol count, or `None` if it was skipped
+    pub async fn index_file(&self, path: &Path) -> Result<Option<usize>, AppError> {
+        let Some(language) = CodeLanguage::from_path(path) else {
+            return Ok(None);
+        };
+        let metadata = tokio::fs::metadata(path).await?;
+        if metadata.len() > MAX_FILE_BYTES {
+            return Ok(None);
+        }
+        let modified = DateTime::<Utc>::from(metadata.modified()?);
+        let path_str = path.to_string_lossy().to_string();
+        // Stored to the microsecond
+        let indexed = self.db.code_file_modified(&path_str).await?;
+        if indexed.is_some_and(|at| at.timestamp_micros() == modified.timestamp_micros()) {
+            return Ok(None);
+        }
+
+        let bytes = tokio::fs::read(path).await?;
+        let source = String::from_utf8_lossy(&bytes);
+        let symbols = extract_symbols(language, &path_str, &source);
+        self.db.replace_code_symbols(&path_str, language.as_str(), modified, &symbols).await?;
+        Ok(Some(symbols.len()))
+    }
+
+    /// Index the source files under a directory, honouring ignore files
+    pub async fn index_directory(&self, root: &Path) -> Result<CodeIndexStats, AppError> {
+        let files: Vec<_> = IgnoreRules::SEARCH
+            .walker(root)
+            .build()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_some_and(|t| t.is_file()))
+            .map(|e| e.into_path())
+            .filter(|p| CodeLanguage::from_path(p).is_some())
+            .collect();
+
+        let mut stats = CodeIndexStats::default();
+        for path in files {
+            match self.index_file(&path).await {
+                Ok(Some(symbols)) => {
+                    stats.files_indexed += 1;
+                    stats.symbols += symbols;
+                }
+                Ok(None) => stats.files_unchanged += 1,
+                Err(e) => tracing::warn!("Failed to index symbols of {:?}: {}", path, e),
+            }
+        }
+        Ok(stats)
+    }
+
+    pub async fn remove_file(&self, path: &Path) -> Result<bool, AppError> {
+        self.db.delete_code_file(&path.to_string_lossy()).await
+    }
+
+    pub async fn totals(&self) -> Result<CodeIndexTotals, AppError> {
+        let (files, symbols) = self.db.code_index_totals().await?;
+        Ok(CodeIndexTotals { files, symbols })
+    }
+
+    pub async fn search(&self, query: &SymbolQuery) -> Result<Vec<CodeSymbolRecord>, AppError> {
+        let filter = CodeSymbolFilter {
+            name: query.q.as_deref().map(str::trim).filter(|q| !q.is_empty()).map(str::to_string),
+            kind: query.kind.clone(),
+            path_prefix: query.path.clone(),
+            exact_or_prefix: false,
+        };
+        self.db
+            .search_code_symbols(&filter, query.limit.unwrap_or(50).clamp(1, MAX_LIMIT))
+            .await
+    }
+
+    /// Definitions a file search names, as search results for `root`
+    pub async fn search_results(&self, search: &str, root: &Path, limit: usize) -> Vec<MergedSearchResult> {
+        let Some((names, kind)) = symbol_terms(search) else {
+            return Vec::new();
+        };
+        let mut results: Vec<MergedSearchResult> = Vec::new();
+        for name in names {
+            let filter = CodeSymbolFilter {
+                name: Some(name.clone()),
+                kind: kind.map(str::to_string),
+                path_prefix: Some(root.to_string_lossy().to_string()),
+                exact_or_prefix: true,
+            };
+            let symbols = match self.db.search_code_symbols(&filter, limit).await {
+                Ok(symbols) => symbols,
+                Err(e) => {
+                    tracing::debug!("Symbol lookup for search failed: {}", e);
+                    return Vec::new();
+                }
+            };
+            for symbol in symbols.into_iter().filter(|s| s.kind != "import" || kind == Some("import")) {
+                if results.iter().any(|r| r.path == symbol.path) {
+                    continue;
+                }
+                let exact = symbol.name.eq_ignore_ascii_case(&name);
+                results.push(MergedSearchResult {
+                    file_type: Path::new(&symbol.path)
+                        .extension()
+                        .and_then(|e| e.to_str())
+                        .unwrap_or("unknown")
+                        .to_string(),
+                    relevance_score: if exact { EXACT_SYMBOL_SCORE } else { PREFIX_SYMBOL_SCORE },
+                    source_engine: format!("symbol ({})", symbol.kind),
+                    size: None,
+                    modified: None,
+                    snippet: Some(symbol.signature),
+                    line_number: Some(symbol.line as usize),
+                    metadata: None,
+                    root: None,
+                    path: symbol.path,
+                });
+            }
+        }
+        results.truncate(limit);
+        results
+    }
+}
+
+/// Put symbol matches in a result list, replacing plain matches of the same
+/// files, and re-sort by score
+pub fn merge_symbol_results(symbols: Vec<MergedSearchResult>, results: &mut Vec<MergedSearchResult>) {
+    if symbols.is_empty() {
+        return;
+    }
+    results.retain(|r| !symbols.iter().any(|s| s.path == r.path));
+    results.extend(symbols);
+    results.sort_by(|a, b| b.relevance_score.partial_cmp(&a.relevance_score).unwrap_or(std::cmp::Ordering::Equal));
+}
+
+/// Symbol names and kind a search asks for. Words count as names when they
+/// look like identifiers (`parse_config`, `parseConfig`, `config::parse`) or
+/// when the search says what kind of symbol it wants ("the Parser class").
+pub fn symbol_terms(search: &str) -> Option<(Vec<String>, Option<&'static str>)> {
+    let words: Vec<&str> = search
+        .split(|c: char| !(c.is_alphanumeric() || c == '_' || c == ':' || c == '.'))
+        .map(|w| w.trim_matches(|c| c == ':' || c == '.'))
+        .filter(|w| !w.is_empty())
+        .collect();
+    let kind = words.iter().find_map(|w| {
+        KIND_WORDS
+            .iter()
+            .find(|(word, _)| w.eq_ignore_ascii_case(word))
+            .map(|(_, kind)| *kind)
+    });
+
+    let looks_like_identifier = |w: &str| {
+        w.contains('_')
+            || w.contains("::")
+            || w.chars().skip(1).any(|c| c.is_uppercase()) && w.chars().any(|c| c.is_lowercase())
+    };
+    let mut names: Vec<String> = Vec::new();
+    for word in &words {
+        let lower = word.to_ascii_lowercase();
+        if FILLER_WORDS.contains(&lower.as_str()) || KIND_WORDS.iter().any(|(w, _)| *w == lower) {
+            continue;
+        }
+        if kind.is_none() && !looks_like_identifier(word) {
+            continue;
+        }
+        // Qualified names match on their last segment
+        let name = word.rsplit("::").next().unwrap_or(word);
+        let name = name.rsplit('.').next().unwrap_or(name);
+        if name.chars().count() >= 3 && !names.iter().any(|n| n == name) {
+            names.push(name.to_string());
+        }
+    }
+    (!names.is_empty()).then_some((names, kind))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(symbols: &[CodeSymbolRecord]) -> Vec<(String, String, i64, Option<String>)> {
+        symbols
+            .iter()
+            .map(|s| (s.kind.clone(), s.name.clone(), s.line, s.container.clone()))
+            .collect()
+    }
+
+    fn row(kind: &str, name: &str, line: i64, container: Option<&str>) -> (String, String, i64, Option<String>) {
+        (kind.to_string(), name.to_string(), line, container.map(str::to_string))
+    }
+
+    #[test]
+    fn test_extract_rust_symbols() {
+        let source = "use std::path::Path;\n\npub struct Config {\n    path: String,\n}\n\nimpl Config {\n    pub fn parse_config(path: &Path) -> Self {\n        todo!()\n    }\n}\n\nfn main() {}\n";
+        let symbols = extract_symbols(CodeLanguage::Rust, "src/main.rs", source);
+        assert_eq!(
+            summary(&symbols),
+            vec![
+                row("import", "std::path::Path", 1, None),
+                row("struct", "Config", 3, None),
+                row("method", "parse_config", 8, Some("Config")),
+                row("function", "main", 13, None),
+            ]
+        );
+        assert_eq!(symbols[2].signature, "pub fn parse_config(path: &Path) -> Self");
+        assert_eq!(symbols[2].end_line, 10);
+    }
+
+    #[test]
+    fn test_extract_python_and_typescript_symbols() {
+        let python = "import os\nfrom pathlib import Path\n\nclass Loader:\n    def load(self):\n        pass\n\ndef parse_config(path):\n    pass\n";
+        assert_eq!(
+            summary(&extract_symbols(CodeLanguage::Python, "app.py", python)),
+            vec![
+                row("import", "os", 1, None),
+                row("import", "pathlib", 2, None),
+                row("class", "Loader", 4, None),
+                row("method", "load", 5, Some("Loader")),
+                row("function", "parse_config", 8, None),
+            ]
+        );
+
+        let typescript = "import { api } from './api';\n\nexport interface Settings { port: number }\n\nexport class Server {\n  start() {}\n}\n\nexport const parseConfig = (text: string) => JSON.parse(text);\n";
+        assert_eq!(
+            summary(&extract_symbols(CodeLanguage::TypeScript, "server.ts", typescript)),
+            vec![
+                row("import", "./api", 1, None),
+                row("interface", "Settings", 3, None),
+                row("class", "Server", 5, None),
+                row("method", "start", 6, Some("Server")),
+                row("function", "parseConfig", 9, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_symbol_terms() {
+        assert_eq!(
+            symbol_terms("find the parse_config function"),
+            Some((vec!["parse_config".to_string()], Some("function")))
+        );
+        assert_eq!(symbol_terms("Parser class"), Some((vec!["Parser".to_string()], Some("class"))));
+        assert_eq!(symbol_terms("where is config::load_settings"), Some((vec!["load_settings".to_string()], None)));
+        assert_eq!(symbol_terms("parseConfig"), Some((vec!["parseConfig".to_string()], None)));
+        assert_eq!(symbol_terms("invoice march"), None);
+        assert_eq!(symbol_terms("report.pdf"), None);
+    }
+
+    #[tokio::test]
+    async fn test_index_and_search() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("project")).unwrap();
+        let root = dir.path().join("project").canonicalize().unwrap();
+        std::fs::write(root.join("config.rs"), "pub fn parse_config() {}\npub fn parse_config_file() {}\n").unwrap();
+        std::fs::write(root.join("notes.md"), "parse_config is called at startup\n").unwrap();
+
+        let url = format!("sqlite://{}?mode=rwc", dir.path().join("code.db").display());
+        let db = Database::new(&url).await.unwrap();
+        let index = CodeIndex::new(db);
+        let stats = index.index_directory(&root).await.unwrap();
+        assert_eq!((stats.files_indexed, stats.symbols), (1, 2));
+        assert_eq!(index.index_directory(&root).await.unwrap().files_unchanged, 1);
+
+        let found = index
+            .search(&SymbolQuery { q: Some("config".to_string()), ..Default::default() })
+            .await
+            .unwrap();
+        assert_eq!(found.len(), 2);
+
+        let results = index.search_results("find the parse_config function", &root, 10).await;
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].relevance_score, EXACT_SYMBOL_SCORE);
+        assert_eq!(results[0].line_number, Some(1));
+
+        let mut merged = vec![MergedSearchResult { relevance_score: 1.0, ..results[0].clone() }];
+        merge_symbol_results(results, &mut merged);
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].source_engine, "symbol (function)");
+    }
+}
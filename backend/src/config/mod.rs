@@ -1,6 +1,10 @@
 //! Application configuration
 #![allow(dead_code)]
 
+pub mod settings;
+
+pub use settings::{HotkeyAction, HotkeySettings, McpSettings, NotificationSettings, PluginSettings, Settings, SettingsStore, ToolLimitSettings, TraceSettings, TranscriptionSettings};
+
 use anyhow::Result;
 use std::env;
 
@@ -11,6 +15,8 @@ pub struct AppConfig {
     pub host: String,
     pub index_paths: Vec<String>,
     pub excluded_patterns: Vec<String>,
+    /// Interval between scheduled disk scans in seconds (0 disables them)
+    pub disk_scan_interval_secs: u64,
 }
 
 impl AppConfig {
@@ -18,11 +24,12 @@ impl AppConfig {
         let home_dir = env::var("HOME").or_else(|_| env::var("USERPROFILE"))?;
         let data_dir = format!("{}/.skhoot", home_dir);
         std::fs::create_dir_all(&data_dir)?;
+        let server = SettingsStore::global().get().server;
         
         Ok(Self {
             database_url: format!("sqlite://{}/skhoot.db?mode=rwc", data_dir),
-            port: 3001,
-            host: "127.0.0.1".to_string(),
+            port: server.port,
+            host: server.host,
             index_paths: vec![
                 home_dir.clone(),
                 format!("{}/Documents", home_dir),
@@ -37,6 +44,10 @@ impl AppConfig {
                 "*.tmp".to_string(),
                 "*.log".to_string(),
             ],
+            disk_scan_interval_secs: env::var("SKHOOT_DISK_SCAN_INTERVAL_SECS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(24 * 60 * 60),
         })
     }
 }
@@ -0,0 +1,885 @@
+//! User-editable backend settings (`skhoot.toml`)
+//!
+//! Settings are grouped in sections (server, search, security, cache,
+//! providers, notifications, hotkey, transcription, mcp, lsp, plugins,
+//! tool_limits, traces, environments). Missing sections or keys fall back to
+//! defaults, the file is reloaded when it changes on disk, and an invalid edit
+//! keeps the last valid settings in effect. Sections can also be updated through the config API,
+//! which validates the result before rewriting the file.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::SystemTime;
+use tracing::{info, warn};
+
+use crate::cli_bridge::{EnvironmentProfile, PolicyAction};
+use crate::lsp::LspServerConfig;
+use crate::mcp::McpServerConfig;
+use crate::plugins::PluginLimits;
+
+const SETTINGS_FILE: &str = "skhoot.toml";
+
+/// Section names, in file order
+pub const SECTIONS: &[&str] = &["server", "search", "security", "cache", "providers", "notifications", "hotkey", "transcription", "mcp", "lsp", "plugins", "tool_limits", "traces", "environments", "local_api"];
+
+/// Sections that only take effect after a restart
+pub const RESTART_SECTIONS: &[&str] = &["server"];
+
+/// HTTP server binding
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerSettings {
+    pub host: String,
+    pub port: u16,
+}
+
+impl Default for ServerSettings {
+    fn default() -> Self {
+        Self {
+            host: "127.0.0.1".to_string(),
+            port: 3001,
+        }
+    }
+}
+
+/// Defaults for file searches that don't specify them
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SearchSettings {
+    /// rust, cli, hybrid or auto
+    pub default_mode: String,
+    pub max_results: usize,
+    /// Boost results from folders and file types the user opens often
+    pub learn_from_clicks: bool,
+    /// Named sets of directories searched together
+    pub workspaces: Vec<SearchWorkspace>,
+    /// Workspace searched when a search names no directory (home if unset)
+    pub default_workspace: Option<String>,
+    /// Keep an index of filenames under home and the workspaces so fuzzy
+    /// searches don't walk the disk
+    pub filename_index: bool,
+}
+
+impl Default for SearchSettings {
+    fn default() -> Self {
+        Self {
+            default_mode: "hybrid".to_string(),
+            max_results: 100,
+            learn_from_clicks: true,
+            workspaces: Vec::new(),
+            default_workspace: None,
+            filename_index: true,
+        }
+    }
+}
+
+impl SearchSettings {
+    pub fn workspace(&self, name: &str) -> Option<&SearchWorkspace> {
+        self.workspaces.iter().find(|w| w.name == name)
+    }
+}
+
+/// Directories searched together, e.g. home, a mounted drive and a project
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SearchWorkspace {
+    pub name: String,
+    /// Absolute or `~`-relative directories
+    pub roots: Vec<String>,
+}
+
+/// Security policy
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SecuritySettings {
+    /// Origins allowed to call the API; "*" allows any
+    pub allowed_origins: Vec<String>,
+    /// What to do with commands that match no command policy rule
+    pub unmatched_command_action: PolicyAction,
+    /// Replace secrets in tool output and traces with placeholders
+    pub redact_secrets: bool,
+}
+
+impl Default for SecuritySettings {
+    fn default() -> Self {
+        Self {
+            allowed_origins: vec!["*".to_string()],
+            unmatched_command_action: PolicyAction::Allow,
+            redact_secrets: true,
+        }
+    }
+}
+
+impl SecuritySettings {
+    pub fn allows_origin(&self, origin: &str) -> bool {
+        self.allowed_origins
+            .iter()
+            .any(|allowed| allowed == "*" || allowed.trim_end_matches('/') == origin)
+    }
+}
+
+/// Cache sizes and lifetimes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CacheSettings {
+    /// In-memory web extraction cache
+    pub web_max_mb: usize,
+    pub web_ttl_secs: u64,
+    /// Deterministic AI response cache
+    pub ai_response_enabled: bool,
+    pub ai_response_max_entries: usize,
+    pub ai_response_ttl_secs: u64,
+}
+
+impl Default for CacheSettings {
+    fn default() -> Self {
+        let ai = crate::ai::response_cache::ResponseCacheConfig::default();
+        Self {
+            web_max_mb: 100,
+            web_ttl_secs: 60 * 60,
+            ai_response_enabled: ai.enabled,
+            ai_response_max_entries: ai.max_entries,
+            ai_response_ttl_secs: ai.ttl.as_secs(),
+        }
+    }
+}
+
+impl CacheSettings {
+    pub fn ai_response_config(&self) -> crate::ai::response_cache::ResponseCacheConfig {
+        crate::ai::response_cache::ResponseCacheConfig {
+            enabled: self.ai_response_enabled,
+            ttl: std::time::Duration::from_secs(self.ai_response_ttl_secs),
+            max_entries: self.ai_response_max_entries,
+            ..Default::default()
+        }
+    }
+}
+
+/// AI provider health and failover
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProviderSettings {
+    /// Providers to fail over to when a request names none
+    pub fallbacks: Vec<String>,
+    pub error_rate_threshold: f32,
+    pub down_after_failures: u32,
+    pub cooldown_secs: u64,
+}
+
+impl Default for ProviderSettings {
+    fn default() -> Self {
+        let health = crate::ai::health::HealthConfig::default();
+        Self {
+            fallbacks: Vec::new(),
+            error_rate_threshold: health.error_rate_threshold,
+            down_after_failures: health.down_after_failures,
+            cooldown_secs: health.cooldown.as_secs(),
+        }
+    }
+}
+
+impl ProviderSettings {
+    pub fn health_config(&self) -> crate::ai::health::HealthConfig {
+        crate::ai::health::HealthConfig {
+            error_rate_threshold: self.error_rate_threshold,
+            down_after_failures: self.down_after_failures,
+            cooldown: std::time::Duration::from_secs(self.cooldown_secs),
+            ..Default::default()
+        }
+    }
+}
+
+/// Desktop notifications for finished agents and workflows
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NotificationSettings {
+    pub enabled: bool,
+    pub workflow_completed: bool,
+    pub workflow_failed: bool,
+    pub agent_completed: bool,
+    pub agent_failed: bool,
+    pub quota_exceeded: bool,
+    /// Local time ("HH:MM") at which quiet hours begin; no notifications are
+    /// shown until `quiet_hours_end`. Unset disables quiet hours.
+    pub quiet_hours_start: Option<String>,
+    pub quiet_hours_end: Option<String>,
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            workflow_completed: true,
+            workflow_failed: true,
+            agent_completed: true,
+            agent_failed: true,
+            quota_exceeded: true,
+            quiet_hours_start: None,
+            quiet_hours_end: None,
+        }
+    }
+}
+
+impl NotificationSettings {
+    /// Quiet hours as (start, end), if both ends are set and valid
+    pub fn quiet_hours(&self) -> Option<(chrono::NaiveTime, chrono::NaiveTime)> {
+        let start = parse_time(self.quiet_hours_start.as_deref()?).ok()?;
+        let end = parse_time(self.quiet_hours_end.as_deref()?).ok()?;
+        Some((start, end))
+    }
+
+    /// Whether `time` falls within quiet hours; a start after the end spans
+    /// midnight
+    pub fn is_quiet_at(&self, time: chrono::NaiveTime) -> bool {
+        match self.quiet_hours() {
+            Some((start, end)) if start <= end => start <= time && time < end,
+            Some((start, end)) => time >= start || time < end,
+            None => false,
+        }
+    }
+}
+
+fn parse_time(value: &str) -> Result<chrono::NaiveTime, chrono::ParseError> {
+    chrono::NaiveTime::parse_from_str(value.trim(), "%H:%M")
+}
+
+/// What the global hotkey does after bringing the window to the front
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HotkeyAction {
+    /// Only show and focus the main window
+    Show,
+    /// Start a new conversation
+    NewConversation,
+    /// Start or stop voice capture in the chat input
+    ToggleVoice,
+}
+
+/// System-wide shortcut that summons the app, registered by the desktop shell
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HotkeySettings {
+    pub enabled: bool,
+    /// Accelerator such as "CommandOrControl+Shift+Space"
+    pub binding: String,
+    pub action: HotkeyAction,
+}
+
+impl Default for HotkeySettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            binding: "CommandOrControl+Shift+Space".to_string(),
+            action: HotkeyAction::Show,
+        }
+    }
+}
+
+/// Speech-to-text for voice input
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TranscriptionSettings {
+    /// local, provider or auto (local when a model is available)
+    pub engine: String,
+    /// whisper.cpp model file (ggml); defaults to ~/.skhoot/models/ggml-base.bin
+    pub model_path: Option<String>,
+    /// Threads for local transcription; 0 picks one per core, up to 8
+    pub threads: usize,
+}
+
+impl Default for TranscriptionSettings {
+    fn default() -> Self {
+        Self {
+            engine: "auto".to_string(),
+            model_path: None,
+            threads: 0,
+        }
+    }
+}
+
+impl TranscriptionSettings {
+    pub fn model_path(&self) -> PathBuf {
+        match &self.model_path {
+            Some(path) => PathBuf::from(path),
+            None => dirs::home_dir()
+                .unwrap_or_else(|| PathBuf::from("."))
+                .join(".skhoot")
+                .join("models")
+                .join("ggml-base.bin"),
+        }
+    }
+}
+
+/// External tool servers (Model Context Protocol)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct McpSettings {
+    /// One `[[mcp.servers]]` table per server
+    pub servers: Vec<McpServerConfig>,
+}
+
+/// Language servers for code intelligence
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LspSettings {
+    pub enabled: bool,
+    /// One `[[lsp.servers]]` table per server; replaces the built-in server
+    /// for the same language
+    pub servers: Vec<LspServerConfig>,
+}
+
+impl Default for LspSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            servers: Vec::new(),
+        }
+    }
+}
+
+/// Agent tool plugins
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PluginSettings {
+    pub enabled: bool,
+    /// Directory of plugin folders; defaults to ~/.skhoot/plugins
+    pub directory: Option<String>,
+    /// Let plugins that declare write permission run at all
+    pub allow_write: bool,
+    /// Most a plugin's manifest may ask for; lower limits are kept
+    pub max_limits: PluginLimits,
+}
+
+impl Default for PluginSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            directory: None,
+            allow_write: true,
+            max_limits: PluginLimits::default(),
+        }
+    }
+}
+
+impl PluginSettings {
+    pub fn directory(&self) -> PathBuf {
+        match &self.directory {
+            Some(path) => PathBuf::from(path),
+            None => dirs::home_dir()
+                .unwrap_or_else(|| PathBuf::from("."))
+                .join(".skhoot")
+                .join("plugins"),
+        }
+    }
+}
+
+/// Throttling of agent tool calls, per session
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ToolLimitSettings {
+    /// Calls of one session that run at once; the rest wait in line
+    pub max_concurrent: usize,
+    /// Calls per minute for tools without their own limit (0 = unlimited)
+    pub default_per_minute: u32,
+    /// Calls per minute by tool name; MCP and plugin tools count as "mcp"
+    /// and "plugin"
+    pub per_minute: HashMap<String, u32>,
+    /// Longest a call waits for a rate limit slot before it fails
+    pub max_wait_secs: u64,
+    /// Identical calls in a row that pause the session (0 disables)
+    pub loop_threshold: u32,
+}
+
+impl Default for ToolLimitSettings {
+    fn default() -> Self {
+        Self {
+            max_concurrent: 4,
+            default_per_minute: 120,
+            per_minute: HashMap::from([("shell".to_string(), 30), ("search_files".to_string(), 60)]),
+            max_wait_secs: 30,
+            loop_threshold: 5,
+        }
+    }
+}
+
+impl ToolLimitSettings {
+    /// Calls per minute allowed for a rate limit key (0 = unlimited)
+    pub fn per_minute(&self, key: &str) -> u32 {
+        self.per_minute.get(key).copied().unwrap_or(self.default_per_minute)
+    }
+}
+
+/// Recording of agent run traces
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TraceSettings {
+    /// Write a trace file for every agent session
+    pub enabled: bool,
+    /// Leave file contents out of traces, keeping only their size
+    pub redact_file_contents: bool,
+}
+
+impl Default for TraceSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            redact_file_contents: false,
+        }
+    }
+}
+
+/// OpenAI-compatible chat completions endpoint for other local apps
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LocalApiSettings {
+    /// Serve `/v1/chat/completions` to registered clients
+    pub enabled: bool,
+    /// Model requests per completion that may call tools
+    pub max_tool_rounds: u32,
+    pub tool_timeout_secs: u64,
+}
+
+impl Default for LocalApiSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_tool_rounds: 8,
+            tool_timeout_secs: 60,
+        }
+    }
+}
+
+/// Shell environment profiles for agent commands and terminals
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EnvironmentSettings {
+    /// Profile used when an agent session or terminal doesn't pick one
+    pub default_profile: Option<String>,
+    /// One `[[environments.profiles]]` table per profile
+    pub profiles: Vec<EnvironmentProfile>,
+}
+
+impl EnvironmentSettings {
+    /// The profile named `name`, or the default profile when no name is given
+    pub fn resolve(&self, name: Option<&str>) -> Result<Option<&EnvironmentProfile>, String> {
+        match name.or(self.default_profile.as_deref()) {
+            Some(name) => self
+                .profiles
+                .iter()
+                .find(|p| p.name == name)
+                .map(Some)
+                .ok_or_else(|| format!("Unknown environment profile '{}'", name)),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Contents of `skhoot.toml`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    pub server: ServerSettings,
+    pub search: SearchSettings,
+    pub security: SecuritySettings,
+    pub cache: CacheSettings,
+    pub providers: ProviderSettings,
+    pub notifications: NotificationSettings,
+    pub hotkey: HotkeySettings,
+    pub transcription: TranscriptionSettings,
+    pub mcp: McpSettings,
+    pub lsp: LspSettings,
+    pub plugins: PluginSettings,
+    pub tool_limits: ToolLimitSettings,
+    pub traces: TraceSettings,
+    pub environments: EnvironmentSettings,
+    pub local_api: LocalApiSettings,
+}
+
+impl Settings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.server.host.trim().is_empty() {
+            return Err("server.host cannot be empty".to_string());
+        }
+        if self.server.port == 0 {
+            return Err("server.port must be between 1 and 65535".to_string());
+        }
+        if !["rust", "cli", "hybrid", "auto"].contains(&self.search.default_mode.as_str()) {
+            return Err(format!(
+                "search.default_mode must be rust, cli, hybrid or auto (got '{}')",
+                self.search.default_mode
+            ));
+        }
+        if self.search.max_results == 0 {
+            return Err("search.max_results must be at least 1".to_string());
+        }
+        let mut workspace_names = std::collections::HashSet::new();
+        for workspace in &self.search.workspaces {
+            if workspace.name.trim().is_empty() {
+                return Err("search.workspaces need a name".to_string());
+            }
+            if !workspace_names.insert(workspace.name.as_str()) {
+                return Err(format!("Duplicate search workspace '{}'", workspace.name));
+            }
+            if workspace.roots.iter().all(|root| root.trim().is_empty()) {
+                return Err(format!("search workspace '{}' has no roots", workspace.name));
+            }
+        }
+        if let Some(name) = &self.search.default_workspace {
+            if !workspace_names.contains(name.as_str()) {
+                return Err(format!("search.default_workspace '{}' is not a defined workspace", name));
+            }
+        }
+        if self.security.allowed_origins.is_empty() {
+            return Err("security.allowed_origins cannot be empty".to_string());
+        }
+        if self.cache.web_max_mb == 0 {
+            return Err("cache.web_max_mb must be at least 1".to_string());
+        }
+        if !(0.0..=1.0).contains(&self.providers.error_rate_threshold) {
+            return Err("providers.error_rate_threshold must be between 0 and 1".to_string());
+        }
+        if self.providers.down_after_failures == 0 {
+            return Err("providers.down_after_failures must be at least 1".to_string());
+        }
+        let quiet_hours = [
+            ("quiet_hours_start", &self.notifications.quiet_hours_start),
+            ("quiet_hours_end", &self.notifications.quiet_hours_end),
+        ];
+        for (key, value) in quiet_hours {
+            if let Some(value) = value.as_ref().filter(|v| parse_time(v).is_err()) {
+                return Err(format!("notifications.{} must be a time like 22:00 (got '{}')", key, value));
+            }
+        }
+        if self.notifications.quiet_hours_start.is_some() != self.notifications.quiet_hours_end.is_some() {
+            return Err("notifications.quiet_hours_start and quiet_hours_end must be set together".to_string());
+        }
+        if self.hotkey.binding.split('+').any(|key| key.trim().is_empty()) {
+            return Err(format!("hotkey.binding must be keys joined by '+' (got '{}')", self.hotkey.binding));
+        }
+        if !["local", "provider", "auto"].contains(&self.transcription.engine.as_str()) {
+            return Err(format!(
+                "transcription.engine must be local, provider or auto (got '{}')",
+                self.transcription.engine
+            ));
+        }
+        crate::mcp::validate_servers(&self.mcp.servers)?;
+        crate::lsp::validate_servers(&self.lsp.servers)?;
+        if self.tool_limits.max_concurrent == 0 {
+            return Err("tool_limits.max_concurrent must be at least 1".to_string());
+        }
+        let mut profile_names = std::collections::HashSet::new();
+        for profile in &self.environments.profiles {
+            if profile.name.trim().is_empty() {
+                return Err("environments.profiles need a name".to_string());
+            }
+            if !profile_names.insert(profile.name.as_str()) {
+                return Err(format!("Duplicate environment profile '{}'", profile.name));
+            }
+            if profile.shell.as_ref().is_some_and(|shell| shell.trim().is_empty()) {
+                return Err(format!("environments profile '{}' has an empty shell", profile.name));
+            }
+        }
+        if let Some(name) = &self.environments.default_profile {
+            if !profile_names.contains(name.as_str()) {
+                return Err(format!("environments.default_profile '{}' is not a defined profile", name));
+            }
+        }
+        if self.local_api.max_tool_rounds == 0 {
+            return Err("local_api.max_tool_rounds must be at least 1".to_string());
+        }
+        if self.local_api.tool_timeout_secs == 0 {
+            return Err("local_api.tool_timeout_secs must be at least 1".to_string());
+        }
+        Ok(())
+    }
+
+    /// A single section as JSON
+    pub fn section(&self, name: &str) -> Option<serde_json::Value> {
+        serde_json::to_value(self).ok()?.get(name).cloned()
+    }
+
+    /// Sections whose values differ from `other`
+    pub fn changed_sections(&self, other: &Settings) -> Vec<&'static str> {
+        SECTIONS
+            .iter()
+            .copied()
+            .filter(|name| self.section(name) != other.section(name))
+            .collect()
+    }
+
+    /// Copy with the given section's keys replaced; keys not in `patch` keep
+    /// their current values
+    pub fn with_section(&self, name: &str, patch: serde_json::Value) -> Result<Settings, String> {
+        if !SECTIONS.contains(&name) {
+            return Err(format!("Unknown config section '{}'", name));
+        }
+        let serde_json::Value::Object(patch) = patch else {
+            return Err(format!("Section '{}' must be an object", name));
+        };
+
+        let mut root = serde_json::to_value(self).map_err(|e| e.to_string())?;
+        let section = root
+            .get_mut(name)
+            .and_then(|v| v.as_object_mut())
+            .ok_or_else(|| format!("Unknown config section '{}'", name))?;
+        for (key, value) in patch {
+            section.insert(key, value);
+        }
+
+        let updated: Settings =
+            serde_json::from_value(root).map_err(|e| format!("Invalid {} settings: {}", name, e))?;
+        updated.validate()?;
+        Ok(updated)
+    }
+}
+
+#[derive(Debug)]
+struct CachedSettings {
+    modified: Option<SystemTime>,
+    settings: Settings,
+    /// Why the file on disk was rejected, if it was
+    error: Option<String>,
+}
+
+/// File-backed settings with hot reload on modification
+#[derive(Debug)]
+pub struct SettingsStore {
+    path: PathBuf,
+    cached: RwLock<CachedSettings>,
+}
+
+lazy_static::lazy_static! {
+    static ref GLOBAL_STORE: Arc<SettingsStore> = Arc::new(SettingsStore::new(
+        std::env::var("SKHOOT_CONFIG")
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| {
+                dirs::home_dir()
+                    .unwrap_or_else(|| PathBuf::from("."))
+                    .join(".skhoot")
+                    .join(SETTINGS_FILE)
+            }),
+    ));
+}
+
+impl SettingsStore {
+    /// Create a store backed by the given file. Falls back to the defaults if
+    /// the file is missing or invalid.
+    pub fn new(path: PathBuf) -> Self {
+        let modified = Self::modified_time(&path);
+        let (settings, error) = match Self::read(&path) {
+            Ok(settings) => (settings, None),
+            Err(e) => {
+                warn!("Ignoring invalid settings file {}: {}", path.display(), e);
+                (Settings::default(), Some(e))
+            }
+        };
+        Self {
+            path,
+            cached: RwLock::new(CachedSettings { modified, settings, error }),
+        }
+    }
+
+    /// Shared store at `~/.skhoot/skhoot.toml` (or `$SKHOOT_CONFIG`)
+    pub fn global() -> Arc<SettingsStore> {
+        GLOBAL_STORE.clone()
+    }
+
+    /// Path of the backing settings file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Get the current settings, reloading them if the file changed on disk
+    pub fn get(&self) -> Settings {
+        self.reload_if_changed();
+        self.cached.read().unwrap().settings.clone()
+    }
+
+    /// Why the file on disk was rejected, if the last load failed
+    pub fn last_error(&self) -> Option<String> {
+        self.cached.read().unwrap().error.clone()
+    }
+
+    /// Reload the file if it changed on disk, returning the sections whose
+    /// values changed. An invalid file keeps the previous settings.
+    pub fn reload_if_changed(&self) -> Vec<&'static str> {
+        let on_disk = Self::modified_time(&self.path);
+        if self.cached.read().unwrap().modified == on_disk {
+            return Vec::new();
+        }
+
+        let mut cached = self.cached.write().unwrap();
+        cached.modified = on_disk;
+        match Self::read(&self.path) {
+            Ok(settings) => {
+                let changed = settings.changed_sections(&cached.settings);
+                info!("Reloaded settings from {}", self.path.display());
+                cached.settings = settings;
+                cached.error = None;
+                changed
+            }
+            Err(e) => {
+                warn!("Keeping previous settings, {} is invalid: {}", self.path.display(), e);
+                cached.error = Some(e);
+                Vec::new()
+            }
+        }
+    }
+
+    /// Validate and persist an update to one section, returning the new settings
+    pub fn update_section(&self, name: &str, patch: serde_json::Value) -> Result<Settings, String> {
+        let updated = self.get().with_section(name, patch)?;
+        self.write(&updated)?;
+
+        let mut cached = self.cached.write().unwrap();
+        cached.modified = Self::modified_time(&self.path);
+        cached.settings = updated.clone();
+        cached.error = None;
+        Ok(updated)
+    }
+
+    /// Write via a temporary file so a crash never leaves a partial file
+    fn write(&self, settings: &Settings) -> Result<(), String> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let content = toml::to_string_pretty(settings).map_err(|e| e.to_string())?;
+        let tmp = self.path.with_extension("toml.tmp");
+        std::fs::write(&tmp, content).map_err(|e| e.to_string())?;
+        std::fs::rename(&tmp, &self.path).map_err(|e| e.to_string())
+    }
+
+    fn read(path: &Path) -> Result<Settings, String> {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Settings::default()),
+            Err(e) => return Err(e.to_string()),
+        };
+        let settings: Settings = toml::from_str(&content).map_err(|e| e.to_string())?;
+        settings.validate()?;
+        Ok(settings)
+    }
+
+    fn modified_time(path: &Path) -> Option<SystemTime> {
+        std::fs::metadata(path).and_then(|m| m.modified()).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_partial_file_uses_defaults() {
+        let settings: Settings = toml::from_str("[search]\nmax_results = 25\n").unwrap();
+        assert_eq!(settings.search.max_results, 25);
+        assert_eq!(settings.search.default_mode, "hybrid");
+        assert_eq!(settings.server, ServerSettings::default());
+        assert!(settings.security.allows_origin("http://localhost:1420"));
+
+        assert!(toml::from_str::<Settings>("[search]\nmax_result = 25\n").is_err());
+
+        let settings: Settings =
+            toml::from_str("[[mcp.servers]]\nname = \"files\"\ncommand = \"mcp-files\"\nargs = [\"/tmp\"]\n").unwrap();
+        assert_eq!(settings.mcp.servers[0].args, vec!["/tmp".to_string()]);
+        assert!(settings.mcp.servers[0].enabled);
+        assert!(settings.validate().is_ok());
+    }
+
+    #[test]
+    fn test_with_section_validates() {
+        let settings = Settings::default();
+
+        let updated = settings.with_section("search", json!({ "default_mode": "cli" })).unwrap();
+        assert_eq!(updated.search.default_mode, "cli");
+        assert_eq!(updated.search.max_results, 100);
+        assert_eq!(updated.changed_sections(&settings), vec!["search"]);
+
+        assert!(settings.with_section("search", json!({ "default_mode": "fast" })).is_err());
+        assert!(settings.with_section("server", json!({ "port": "high" })).is_err());
+        assert!(settings.with_section("search", json!({ "unknown": 1 })).is_err());
+        assert!(settings.with_section("hotkey", json!({ "action": "toggle_voice" })).is_ok());
+        assert!(settings.with_section("hotkey", json!({ "binding": "Ctrl++" })).is_err());
+        assert!(settings.with_section("hotkey", json!({ "binding": "" })).is_err());
+        assert!(settings.with_section("tool_limits", json!({ "max_concurrent": 0 })).is_err());
+        assert!(settings
+            .with_section("mcp", json!({ "servers": [{ "name": "web", "transport": "sse" }] }))
+            .is_err());
+        assert!(settings
+            .with_section("environments", json!({ "profiles": [{ "name": "py", "virtualenv": "~/.venv" }], "default_profile": "py" }))
+            .is_ok());
+        assert!(settings.with_section("environments", json!({ "default_profile": "py" })).is_err());
+        assert!(settings
+            .with_section("environments", json!({ "profiles": [{ "name": "py" }, { "name": "py" }] }))
+            .is_err());
+        assert!(settings
+            .with_section("search", json!({ "workspaces": [{ "name": "work", "roots": ["~/code", "/mnt/share"] }], "default_workspace": "work" }))
+            .is_ok());
+        assert!(settings.with_section("search", json!({ "default_workspace": "work" })).is_err());
+        assert!(settings
+            .with_section("search", json!({ "workspaces": [{ "name": "empty", "roots": [] }] }))
+            .is_err());
+        assert!(settings.with_section("database", json!({})).is_err());
+    }
+
+    #[test]
+    fn test_store_reload_and_update() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join(SETTINGS_FILE);
+        std::fs::write(&path, "[server]\nport = 4000\n").unwrap();
+
+        let store = SettingsStore::new(path.clone());
+        assert_eq!(store.get().server.port, 4000);
+
+        let updated = store
+            .update_section("security", json!({ "allowed_origins": ["http://localhost:1420"] }))
+            .unwrap();
+        assert!(!updated.security.allows_origin("http://evil.example"));
+        let on_disk: Settings = toml::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(on_disk, updated);
+
+        // An invalid edit keeps the previous settings
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        std::fs::write(&path, "[server]\nport = 0\n").unwrap();
+        assert!(store.reload_if_changed().is_empty());
+        assert_eq!(store.get().server.port, 4000);
+        assert!(store.last_error().is_some());
+
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        std::fs::write(&path, "[server]\nport = 4001\n").unwrap();
+        assert_eq!(store.reload_if_changed(), vec!["server", "security"]);
+        assert_eq!(store.get().server.port, 4001);
+    }
+
+    #[test]
+    fn test_quiet_hours() {
+        let at = |h, m| chrono::NaiveTime::from_hms_opt(h, m, 0).unwrap();
+        let settings = Settings::default();
+        assert!(!settings.notifications.is_quiet_at(at(23, 0)));
+
+        let overnight = settings
+            .with_section("notifications", json!({ "quiet_hours_start": "22:00", "quiet_hours_end": "07:30" }))
+            .unwrap();
+        assert!(overnight.notifications.is_quiet_at(at(23, 0)));
+        assert!(overnight.notifications.is_quiet_at(at(7, 0)));
+        assert!(!overnight.notifications.is_quiet_at(at(7, 30)));
+        assert!(!overnight.notifications.is_quiet_at(at(12, 0)));
+
+        let daytime = settings
+            .with_section("notifications", json!({ "quiet_hours_start": "09:00", "quiet_hours_end": "17:00" }))
+            .unwrap();
+        assert!(daytime.notifications.is_quiet_at(at(12, 0)));
+        assert!(!daytime.notifications.is_quiet_at(at(18, 0)));
+
+        assert!(settings.with_section("notifications", json!({ "quiet_hours_start": "25:00", "quiet_hours_end": "07:00" })).is_err());
+        assert!(settings.with_section("notifications", json!({ "quiet_hours_start": "22:00" })).is_err());
+
+        // Unset quiet hours are left out of the file
+        let content = toml::to_string_pretty(&settings).unwrap();
+        assert_eq!(toml::from_str::<Settings>(&content).unwrap(), settings);
+    }
+}
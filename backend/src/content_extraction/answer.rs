@@ -0,0 +1,313 @@
+// Answer Synthesis
+// Turns a search-and-gather response into numbered sources and query-relevant
+// chunks for an AI provider, and maps the [n] citations of its answer back to URLs
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use super::dedupe::url_key;
+use super::types::SearchGatherResponse;
+
+/// Words per chunk of page text
+pub const CHUNK_WORDS: usize = 180;
+
+/// Words repeated at the start of the next chunk, so sentences aren't cut off
+const CHUNK_OVERLAP_WORDS: usize = 30;
+
+/// Words of source text sent to the provider
+pub const CONTEXT_WORD_BUDGET: usize = 3000;
+
+pub const SYSTEM_PROMPT: &str = "You answer questions using only the numbered web sources provided. \
+Cite sources inline with their numbers in square brackets, like [1] or [2][3], right after the \
+statements they support. If the sources don't answer the question, say so. Don't list the sources \
+at the end; the user already sees them.";
+
+lazy_static::lazy_static! {
+    static ref CITATION: Regex = Regex::new(r"\[(\d+(?:\s*,\s*\d+)*)\]").unwrap();
+}
+
+/// A page or search result the answer may cite
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnswerSource {
+    /// Number used in the answer's [n] citations
+    pub number: usize,
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// Other URLs with the same content
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub alternates: Vec<String>,
+    /// Whether only the search snippet was available, not the page
+    #[serde(default)]
+    pub snippet_only: bool,
+    /// Whether the answer cites this source
+    #[serde(default)]
+    pub cited: bool,
+}
+
+/// A piece of a source's text picked for the prompt
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceChunk {
+    pub source: usize,
+    /// Position of the chunk within its source
+    pub position: usize,
+    pub text: String,
+}
+
+/// Split text into chunks of `words` words, each overlapping the previous one
+pub fn chunk_text(text: &str, words: usize, overlap: usize) -> Vec<String> {
+    let all: Vec<&str> = text.split_whitespace().collect();
+    let step = words.saturating_sub(overlap).max(1);
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    while start < all.len() {
+        let end = (start + words).min(all.len());
+        chunks.push(all[start..end].join(" "));
+        if end == all.len() {
+            break;
+        }
+        start += step;
+    }
+    chunks
+}
+
+/// Number the gathered pages, then the search results that weren't gathered
+/// (with their snippet as text). Returns the sources and the text of each.
+pub fn number_sources(response: &SearchGatherResponse) -> (Vec<AnswerSource>, Vec<String>) {
+    let mut sources = Vec::new();
+    let mut texts = Vec::new();
+    let mut seen = HashSet::new();
+
+    for page in &response.gathered_pages {
+        seen.insert(url_key(&page.final_url));
+        seen.extend(page.alternates.iter().map(|url| url_key(url)));
+        sources.push(AnswerSource {
+            number: sources.len() + 1,
+            url: page.final_url.clone(),
+            title: page.title.clone(),
+            alternates: page.alternates.clone(),
+            snippet_only: false,
+            cited: false,
+        });
+        texts.push(page.text.clone());
+    }
+
+    for result in &response.search_results {
+        if result.snippet.trim().is_empty() || !seen.insert(url_key(&result.url)) {
+            continue;
+        }
+        sources.push(AnswerSource {
+            number: sources.len() + 1,
+            url: result.url.clone(),
+            title: Some(result.title.clone()).filter(|title| !title.is_empty()),
+            alternates: Vec::new(),
+            snippet_only: true,
+            cited: false,
+        });
+        texts.push(result.snippet.clone());
+    }
+
+    (sources, texts)
+}
+
+fn query_terms(query: &str) -> Vec<String> {
+    let mut terms: Vec<String> = query
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|term| term.chars().count() > 2)
+        .map(str::to_lowercase)
+        .collect();
+    terms.sort();
+    terms.dedup();
+    terms
+}
+
+fn chunk_score(chunk: &str, terms: &[String]) -> usize {
+    let text = chunk.to_lowercase();
+    terms.iter().map(|term| text.matches(term.as_str()).count().min(3)).sum()
+}
+
+/// Pick the chunks most relevant to `query` within `budget` words. Every
+/// source contributes its best chunk first; the remaining budget goes to the
+/// highest-scoring chunks overall. `texts[i]` is the text of source `i + 1`.
+pub fn select_chunks(query: &str, texts: &[String], budget: usize) -> Vec<SourceChunk> {
+    let terms = query_terms(query);
+    let mut candidates: Vec<(usize, SourceChunk)> = texts
+        .iter()
+        .enumerate()
+        .flat_map(|(index, text)| {
+            let terms = &terms;
+            chunk_text(text, CHUNK_WORDS, CHUNK_OVERLAP_WORDS)
+                .into_iter()
+                .enumerate()
+                .map(move |(position, text)| {
+                    (chunk_score(&text, terms), SourceChunk { source: index + 1, position, text })
+                })
+        })
+        .collect();
+    // Best first; earlier chunks of a source win ties
+    candidates.sort_by(|(a_score, a), (b_score, b)| {
+        b_score
+            .cmp(a_score)
+            .then(a.position.cmp(&b.position))
+            .then(a.source.cmp(&b.source))
+    });
+
+    let mut selected: Vec<SourceChunk> = Vec::new();
+    let mut used = 0;
+    let mut take = |chunk: &SourceChunk, selected: &mut Vec<SourceChunk>| {
+        let words = chunk.text.split_whitespace().count();
+        if used + words > budget || selected.contains(chunk) {
+            return;
+        }
+        used += words;
+        selected.push(chunk.clone());
+    };
+
+    let mut represented = HashSet::new();
+    for (_, chunk) in &candidates {
+        if represented.insert(chunk.source) {
+            take(chunk, &mut selected);
+        }
+    }
+    for (_, chunk) in &candidates {
+        take(chunk, &mut selected);
+    }
+
+    selected.sort_by_key(|chunk| (chunk.source, chunk.position));
+    selected
+}
+
+/// Prompt asking for an answer to `query` from the selected chunks
+pub fn build_prompt(query: &str, sources: &[AnswerSource], chunks: &[SourceChunk]) -> String {
+    let mut prompt = format!("Question: {}\n\nSources:\n", query);
+    let mut current = None;
+    for chunk in chunks {
+        if current != Some(chunk.source) {
+            current = Some(chunk.source);
+            let source = &sources[chunk.source - 1];
+            prompt.push_str(&format!(
+                "\n[{}] {} ({})\n",
+                source.number,
+                source.title.as_deref().unwrap_or("Untitled"),
+                source.url
+            ));
+        }
+        prompt.push_str(&chunk.text);
+        prompt.push('\n');
+    }
+    prompt
+}
+
+/// Mark the sources the answer cites and drop citations of sources that
+/// don't exist
+pub fn resolve_citations(answer: &str, sources: &mut [AnswerSource]) -> String {
+    CITATION
+        .replace_all(answer, |captures: &regex::Captures| {
+            let numbers: Vec<usize> = captures[1]
+                .split(',')
+                .filter_map(|number| number.trim().parse().ok())
+                .filter(|number| (1..=sources.len()).contains(number))
+                .collect();
+            for number in &numbers {
+                sources[number - 1].cited = true;
+            }
+            numbers.iter().map(|number| format!("[{}]", number)).collect::<String>()
+        })
+        .trim()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::content_extraction::{ExtractionMethod, PageExtract, WebSearchResult};
+
+    fn words(prefix: &str, count: usize) -> String {
+        (0..count).map(|i| format!("{}{}", prefix, i)).collect::<Vec<_>>().join(" ")
+    }
+
+    #[test]
+    fn test_chunk_text() {
+        let chunks = chunk_text(&words("w", 10), 4, 1);
+        assert_eq!(chunks, vec!["w0 w1 w2 w3", "w3 w4 w5 w6", "w6 w7 w8 w9"]);
+        assert_eq!(chunk_text("one two", 4, 1), vec!["one two"]);
+        assert!(chunk_text("  ", 4, 1).is_empty());
+    }
+
+    #[test]
+    fn test_number_sources() {
+        let mut page = PageExtract::new(
+            "Rust is fast.".to_string(),
+            "https://rust-lang.org/".to_string(),
+            0.9,
+            ExtractionMethod::DensityHeuristic,
+        );
+        page.title = Some("Rust".to_string());
+        page.alternates = vec!["https://www.rust-lang.org/amp".to_string()];
+        let result = |url: &str, snippet: &str| WebSearchResult {
+            title: "Result".to_string(),
+            url: url.to_string(),
+            snippet: snippet.to_string(),
+            published_date: None,
+            relevance_score: 1.0,
+        };
+        let response = SearchGatherResponse {
+            query: "rust".to_string(),
+            search_results: vec![
+                result("https://rust-lang.org/?utm_source=ddg", "Gathered already"),
+                result("https://www.rust-lang.org/amp", "Alternate of the gathered page"),
+                result("https://doc.rust-lang.org/book", "The Rust book"),
+                result("https://example.com", ""),
+            ],
+            gathered_pages: vec![page],
+            total_search_time_ms: 0,
+            total_gather_time_ms: 0,
+            skipped_urls: Vec::new(),
+        };
+
+        let (sources, texts) = number_sources(&response);
+        let urls: Vec<(usize, &str, bool)> = sources.iter().map(|s| (s.number, s.url.as_str(), s.snippet_only)).collect();
+        assert_eq!(urls, vec![(1, "https://rust-lang.org/", false), (2, "https://doc.rust-lang.org/book", true)]);
+        assert_eq!(texts, vec!["Rust is fast.", "The Rust book"]);
+    }
+
+    #[test]
+    fn test_select_chunks_covers_every_source() {
+        let long = format!("{} tokio runtime scheduler {}", words("a", 400), words("b", 400));
+        let texts = vec![long, "Unrelated short text".to_string()];
+        let chunks = select_chunks("How does the tokio scheduler work?", &texts, 250);
+
+        let total: usize = chunks.iter().map(|c| c.text.split_whitespace().count()).sum();
+        assert!(total <= 250);
+        assert!(chunks[0].text.contains("tokio runtime scheduler"));
+        assert!(chunks.iter().any(|c| c.source == 2));
+    }
+
+    #[test]
+    fn test_build_prompt_and_citations() {
+        let source = |number: usize, url: &str| AnswerSource {
+            number,
+            url: url.to_string(),
+            title: None,
+            alternates: Vec::new(),
+            snippet_only: false,
+            cited: false,
+        };
+        let mut sources = vec![source(1, "https://a.dev"), source(2, "https://b.dev"), source(3, "https://c.dev")];
+        let chunks = vec![
+            SourceChunk { source: 1, position: 0, text: "Alpha".to_string() },
+            SourceChunk { source: 1, position: 2, text: "Beta".to_string() },
+            SourceChunk { source: 3, position: 0, text: "Gamma".to_string() },
+        ];
+        let prompt = build_prompt("why?", &sources, &chunks);
+        assert_eq!(
+            prompt,
+            "Question: why?\n\nSources:\n\n[1] Untitled (https://a.dev)\nAlpha\nBeta\n\n[3] Untitled (https://c.dev)\nGamma\n"
+        );
+
+        let answer = resolve_citations(" Because [1, 3]. Also [7] and [3][2].\n", &mut sources);
+        assert_eq!(answer, "Because [1][3]. Also  and [3][2].");
+        assert_eq!(sources.iter().map(|s| s.cited).collect::<Vec<_>>(), vec![true, true, true]);
+    }
+}
@@ -54,6 +54,16 @@ impl CacheManager {
         }
     }
 
+    /// Changes the size limit and TTL, evicting entries that no longer fit
+    pub fn set_limits(&mut self, max_size_bytes: usize, ttl: Duration) {
+        self.max_size_bytes = max_size_bytes;
+        self.ttl = ttl;
+        self.evict_expired();
+        while self.current_size_bytes > self.max_size_bytes && !self.cache.is_empty() {
+            self.evict_lru();
+        }
+    }
+
     /// Gets cached PageExtract if valid
     /// 
     /// Returns None if:
@@ -159,7 +169,7 @@ impl CacheManager {
     }
 
     /// Calculates URL hash for cache key
-    fn hash_url(url: &str) -> String {
+    pub(crate) fn hash_url(url: &str) -> String {
         let mut hasher = Sha256::new();
         hasher.update(url.as_bytes());
         format!("{:x}", hasher.finalize())
@@ -201,6 +211,17 @@ impl CacheManager {
         }
     }
 
+    /// Removes the entry for a URL, returning whether one existed
+    pub fn remove(&mut self, url: &str) -> bool {
+        match self.cache.remove(&Self::hash_url(url)) {
+            Some(entry) => {
+                self.current_size_bytes = self.current_size_bytes.saturating_sub(entry.size_bytes);
+                true
+            }
+            None => false,
+        }
+    }
+
     /// Clears all cache entries
     pub fn clear(&mut self) {
         self.cache.clear();
@@ -215,7 +236,7 @@ impl Default for CacheManager {
 }
 
 /// Cache statistics
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize)]
 pub struct CacheStats {
     pub entries: usize,
     pub size_bytes: usize,
@@ -247,6 +268,11 @@ mod tests {
             total_time_ms: 150,
             status: 200,
             content_type: Some("text/html".to_string()),
+            ocr_text: None,
+            crawled_pages: Vec::new(),
+            screenshot: None,
+            structured_data: Vec::new(),
+            alternates: Vec::new(),
         }
     }
 
@@ -6,6 +6,16 @@ use std::collections::HashMap;
 use std::time::Instant;
 
 use crate::content_extraction::ExtractionMethod;
+use crate::content_extraction::site_rules::SiteRules;
+
+/// Minimum words a site rule must yield before its result is trusted
+const MIN_RULE_WORDS: usize = 20;
+
+/// Weight of the site rule in the blended confidence
+const RULE_CONFIDENCE_WEIGHT: f32 = 0.7;
+
+/// Confidence bonus for content located by a user-defined selector
+const RULE_CONFIDENCE_BONUS: f32 = 0.2;
 
 /// Result from content extraction
 #[derive(Debug, Clone)]
@@ -74,6 +84,51 @@ impl MainContentExtractor {
         }
     }
 
+    /// Extracts main content using the site rule for `host`, if any
+    ///
+    /// Falls back to generic extraction when no rule matches the host or
+    /// the rule yields fewer than `MIN_RULE_WORDS` words. Otherwise the rule's
+    /// text is used, with a confidence blended from the rule result and the
+    /// generic extraction so a selector that only grabs a fragment of a good
+    /// page is not over-trusted.
+    pub fn extract_with_rules(html: &str, host: Option<&str>, rules: &SiteRules) -> ContentExtraction {
+        let generic = Self::extract(html);
+        let Some(rule) = host.and_then(|host| rules.for_host(host)) else {
+            return generic;
+        };
+
+        let start_time = Instant::now();
+        let text = match rule.extract(html) {
+            Some(text) => text,
+            None => {
+                tracing::debug!("Site rule for {} matched no content; using generic extraction", rule.domain);
+                return generic;
+            }
+        };
+        let word_count = text.split_whitespace().count();
+        if word_count < MIN_RULE_WORDS {
+            tracing::debug!(
+                "Site rule for {} yielded {} words; using generic extraction",
+                rule.domain,
+                word_count
+            );
+            return generic;
+        }
+
+        let rule_confidence = (Self::word_count_score(word_count) + RULE_CONFIDENCE_BONUS).min(1.0);
+        let confidence = (RULE_CONFIDENCE_WEIGHT * rule_confidence
+            + (1.0 - RULE_CONFIDENCE_WEIGHT) * generic.confidence)
+            .clamp(0.0, 1.0);
+
+        ContentExtraction {
+            text,
+            word_count,
+            confidence,
+            method: ExtractionMethod::SiteRule,
+            extraction_time_ms: generic.extraction_time_ms + start_time.elapsed().as_millis() as u64,
+        }
+    }
+
     /// Removes boilerplate elements and returns content elements
     fn remove_boilerplate(document: &Html) -> Vec<String> {
         let mut content_elements = Vec::new();
@@ -229,15 +284,7 @@ impl MainContentExtractor {
     /// Calculates confidence score based on word count and text/HTML ratio
     fn calculate_confidence(word_count: usize, text_size: usize, html_size: usize) -> f32 {
         // Base score from word count
-        let word_score = if word_count > 800 {
-            0.9
-        } else if word_count >= 300 {
-            0.7 + (word_count - 300) as f32 / 500.0 * 0.2
-        } else if word_count >= 120 {
-            0.5 + (word_count - 120) as f32 / 180.0 * 0.2
-        } else {
-            (word_count as f32 / 120.0) * 0.5
-        };
+        let word_score = Self::word_count_score(word_count);
         
         // Adjust based on text/HTML ratio
         let ratio = text_size as f32 / html_size.max(1) as f32;
@@ -251,6 +298,19 @@ impl MainContentExtractor {
         
         (word_score + ratio_adjustment).clamp(0.0, 1.0)
     }
+
+    /// Base confidence from word count alone (0.0-0.9)
+    pub(crate) fn word_count_score(word_count: usize) -> f32 {
+        if word_count > 800 {
+            0.9
+        } else if word_count >= 300 {
+            0.7 + (word_count - 300) as f32 / 500.0 * 0.2
+        } else if word_count >= 120 {
+            0.5 + (word_count - 120) as f32 / 180.0 * 0.2
+        } else {
+            (word_count as f32 / 120.0) * 0.5
+        }
+    }
 }
 
 #[cfg(test)]
@@ -386,4 +446,34 @@ mod tests {
         
         assert_eq!(normalized, "Multiple spaces and newlines");
     }
+
+    #[test]
+    fn test_extract_with_site_rules() {
+        use crate::content_extraction::site_rules::SiteRule;
+
+        let body = "<p>Rule selected sentence with several words in it.</p>".repeat(5);
+        let html = format!(
+            "<html><body><div class=\"story\">{}</div><aside><p>Unrelated sidebar text here.</p></aside></body></html>",
+            body
+        );
+        let rules = SiteRules {
+            rules: vec![SiteRule {
+                domain: "news.example".to_string(),
+                content: vec!["div.story".to_string()],
+                remove: Vec::new(),
+            }],
+        };
+
+        let extraction = MainContentExtractor::extract_with_rules(&html, Some("www.news.example"), &rules);
+        assert_eq!(extraction.method, ExtractionMethod::SiteRule);
+        assert!(!extraction.text.contains("sidebar"));
+        assert!(extraction.confidence > 0.0 && extraction.confidence <= 1.0);
+
+        // Other hosts and rules that select too little use generic extraction
+        let other = MainContentExtractor::extract_with_rules(&html, Some("other.example"), &rules);
+        assert_eq!(other.method, ExtractionMethod::DensityHeuristic);
+        let sparse = format!("<html><body><div class=\"story\"><p>Too short.</p></div>{}</body></html>", body);
+        let fallback = MainContentExtractor::extract_with_rules(&sparse, Some("news.example"), &rules);
+        assert_eq!(fallback.method, ExtractionMethod::DensityHeuristic);
+    }
 }
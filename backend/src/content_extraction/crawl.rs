@@ -0,0 +1,176 @@
+// Crawl helpers
+// Scope rules for following links and merging several pages into one extract
+
+use url::Url;
+
+use crate::content_extraction::{CrawledPage, PageExtract};
+
+/// Maximum link depth followed from the start page
+pub const MAX_CRAWL_DEPTH: usize = 3;
+
+/// Default and maximum number of pages in one crawl
+pub const DEFAULT_CRAWL_PAGES: usize = 10;
+pub const MAX_CRAWL_PAGES: usize = 25;
+
+/// Combined text stops growing past this many characters
+pub const MAX_CRAWL_CHARS: usize = 200_000;
+
+/// File extensions that are never worth following
+const SKIPPED_EXTENSIONS: &[&str] = &[
+    "png", "jpg", "jpeg", "gif", "webp", "svg", "ico", "bmp", "mp3", "mp4", "webm", "mov", "avi", "zip",
+    "tar", "gz", "tgz", "7z", "rar", "dmg", "exe", "msi", "deb", "rpm", "iso", "woff", "woff2", "ttf",
+    "css", "js",
+];
+
+/// The part of a site a crawl stays in
+///
+/// Links must share the start URL's origin and live under its directory, so
+/// crawling `https://example.com/docs/intro` follows `/docs/...` pages only.
+#[derive(Debug, Clone)]
+pub struct CrawlScope {
+    origin: url::Origin,
+    path_prefix: String,
+}
+
+impl CrawlScope {
+    pub fn new(start: &Url) -> Self {
+        let path = start.path();
+        let path_prefix = match path.rfind('/') {
+            Some(idx) => path[..=idx].to_string(),
+            None => "/".to_string(),
+        };
+        Self {
+            origin: start.origin(),
+            path_prefix,
+        }
+    }
+
+    /// Whether a link should be followed
+    pub fn contains(&self, url: &Url) -> bool {
+        if url.origin() != self.origin || !url.path().starts_with(&self.path_prefix) {
+            return false;
+        }
+        let extension = url
+            .path()
+            .rsplit('/')
+            .next()
+            .and_then(|name| name.rsplit_once('.'))
+            .map(|(_, ext)| ext.to_ascii_lowercase());
+        !extension.is_some_and(|ext| SKIPPED_EXTENSIONS.contains(&ext.as_str()))
+    }
+
+    /// In-scope links, in page order
+    pub fn filter_links(&self, links: &[String]) -> Vec<Url> {
+        links
+            .iter()
+            .filter_map(|link| Url::parse(link).ok())
+            .map(|mut url| {
+                url.set_fragment(None);
+                url
+            })
+            .filter(|url| self.contains(url))
+            .collect()
+    }
+}
+
+/// Merges crawled pages into one extract, starting with the first page
+///
+/// Each page becomes a section headed by its title and source URL. Pages
+/// that would push the text past `MAX_CRAWL_CHARS` are left out.
+pub fn combine_pages(mut pages: Vec<PageExtract>, total_time_ms: u64) -> Option<PageExtract> {
+    if pages.is_empty() {
+        return None;
+    }
+
+    let mut sections = Vec::new();
+    let mut crawled_pages = Vec::new();
+    let mut chars = 0;
+    let mut weighted_confidence = 0.0;
+    let (mut fetch_time_ms, mut extraction_time_ms) = (0, 0);
+
+    for page in &pages {
+        let heading = page.title.as_deref().unwrap_or(&page.final_url);
+        let section = format!("## {}\nSource: {}\n\n{}", heading, page.final_url, page.text.trim());
+        let section_chars = section.chars().count();
+        if !sections.is_empty() && chars + section_chars > MAX_CRAWL_CHARS {
+            tracing::debug!("Crawl text limit reached; leaving out {}", page.final_url);
+            continue;
+        }
+        chars += section_chars;
+        sections.push(section);
+
+        weighted_confidence += page.confidence * page.word_count.max(1) as f32;
+        fetch_time_ms += page.fetch_time_ms;
+        extraction_time_ms += page.extraction_time_ms;
+        crawled_pages.push(CrawledPage {
+            url: page.final_url.clone(),
+            title: page.title.clone(),
+            word_count: page.word_count,
+        });
+    }
+
+    let total_words: usize = crawled_pages.iter().map(|p| p.word_count.max(1)).sum();
+    let mut combined = pages.swap_remove(0);
+    combined.text = sections.join("\n\n---\n\n");
+    combined.word_count = combined.text.split_whitespace().count();
+    combined.confidence = weighted_confidence / total_words.max(1) as f32;
+    combined.fetch_time_ms = fetch_time_ms;
+    combined.extraction_time_ms = extraction_time_ms;
+    combined.total_time_ms = total_time_ms;
+    combined.crawled_pages = crawled_pages;
+    Some(combined)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::content_extraction::ExtractionMethod;
+
+    fn page(url: &str, title: &str, text: &str, confidence: f32) -> PageExtract {
+        let mut page = PageExtract::new(text.to_string(), url.to_string(), confidence, ExtractionMethod::DensityHeuristic);
+        page.title = Some(title.to_string());
+        page
+    }
+
+    #[test]
+    fn test_scope_stays_in_section() {
+        let scope = CrawlScope::new(&Url::parse("https://example.com/docs/intro").unwrap());
+        let links = vec![
+            "https://example.com/docs/install#linux".to_string(),
+            "https://example.com/docs/api/client".to_string(),
+            "https://example.com/blog/post".to_string(),
+            "https://other.com/docs/install".to_string(),
+            "http://example.com/docs/insecure".to_string(),
+            "https://example.com/docs/logo.png".to_string(),
+        ];
+
+        let urls: Vec<String> = scope.filter_links(&links).iter().map(|u| u.to_string()).collect();
+        assert_eq!(urls, vec![
+            "https://example.com/docs/install".to_string(),
+            "https://example.com/docs/api/client".to_string(),
+        ]);
+    }
+
+    #[test]
+    fn test_combine_pages() {
+        let combined = combine_pages(
+            vec![
+                page("https://example.com/docs/", "Docs", "Welcome to the docs", 0.9),
+                page("https://example.com/docs/install", "Install", "Run the installer", 0.5),
+            ],
+            1234,
+        )
+        .unwrap();
+
+        assert_eq!(combined.final_url, "https://example.com/docs/");
+        assert_eq!(combined.title.as_deref(), Some("Docs"));
+        assert!(combined.text.starts_with("## Docs\nSource: https://example.com/docs/\n\nWelcome to the docs"));
+        assert!(combined.text.contains("\n\n---\n\n## Install\nSource: https://example.com/docs/install\n\nRun the installer"));
+        assert_eq!(combined.crawled_pages.len(), 2);
+        assert_eq!(combined.total_time_ms, 1234);
+        // Weighted by word count (4 and 3): (0.9 * 4 + 0.5 * 3) / 7
+        assert!((combined.confidence - 5.1 / 7.0).abs() < 1e-4);
+
+        assert!(combine_pages(Vec::new(), 0).is_none());
+    }
+}
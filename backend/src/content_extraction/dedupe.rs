@@ -0,0 +1,337 @@
+// Gathered Page Deduplication
+// URL canonicalization and SimHash near-duplicate detection, so search_and_gather
+// returns distinct sources with AMP copies, mirrors and tracking links as alternates
+
+use url::Url;
+
+use super::types::PageExtract;
+
+/// Query parameters that only record where a visitor came from
+const TRACKING_PARAMS: &[&str] = &[
+    "fbclid", "gclid", "dclid", "gbraid", "wbraid", "msclkid", "yclid", "igshid", "mc_cid", "mc_eid",
+    "_ga", "_gl", "ref_src", "spm",
+];
+
+/// Prefixes of tracking parameter families (utm_source, pk_campaign, ...)
+const TRACKING_PREFIXES: &[&str] = &["utm_", "pk_", "hsa_"];
+
+/// Host prefixes of mobile, AMP and www mirrors of the same site
+const MIRROR_HOST_PREFIXES: &[&str] = &["www.", "amp.", "m.", "mobile."];
+
+/// Fingerprints this many bits apart or fewer belong to near-duplicate pages
+pub const NEAR_DUPLICATE_DISTANCE: u32 = 3;
+
+/// Shorter pages are only compared by URL; their fingerprints are too noisy
+const MIN_WORDS_FOR_SIMHASH: usize = 50;
+
+/// Words per shingle hashed into a fingerprint
+const SHINGLE_WORDS: usize = 3;
+
+fn is_tracking_param(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    TRACKING_PARAMS.contains(&name.as_str()) || TRACKING_PREFIXES.iter().any(|prefix| name.starts_with(prefix))
+}
+
+/// The page a Google AMP cache URL (`*.cdn.ampproject.org/c/s/host/path`) serves
+fn unwrap_amp_cache(url: &Url) -> Option<Url> {
+    if !url.host_str()?.ends_with(".cdn.ampproject.org") {
+        return None;
+    }
+    let path = url.path();
+    let rest = path.strip_prefix("/c/").or_else(|| path.strip_prefix("/v/"))?;
+    let (scheme, rest) = match rest.strip_prefix("s/") {
+        Some(rest) => ("https", rest),
+        None => ("http", rest),
+    };
+    let mut inner = Url::parse(&format!("{}://{}", scheme, rest)).ok()?;
+    inner.set_query(url.query());
+    Some(inner)
+}
+
+/// Normalize a URL without changing the page it points to: tracking
+/// parameters and the fragment are dropped and AMP cache URLs are unwrapped.
+/// Unparseable input is returned trimmed.
+pub fn canonicalize_url(raw: &str) -> String {
+    let Ok(mut url) = Url::parse(raw.trim()) else {
+        return raw.trim().to_string();
+    };
+    if let Some(inner) = unwrap_amp_cache(&url) {
+        url = inner;
+    }
+    url.set_fragment(None);
+
+    let params: Vec<(String, String)> = url
+        .query_pairs()
+        .filter(|(name, _)| !is_tracking_param(name))
+        .map(|(name, value)| (name.into_owned(), value.into_owned()))
+        .collect();
+    if params.is_empty() {
+        url.set_query(None);
+    } else {
+        url.query_pairs_mut().clear().extend_pairs(params);
+    }
+    url.to_string()
+}
+
+/// Comparison key for a URL: its canonical form without scheme, mirror host
+/// prefixes, AMP markers, parameter order or trailing slash. Two URLs with
+/// the same key are taken to be the same page.
+pub fn url_key(raw: &str) -> String {
+    let canonical = canonicalize_url(raw);
+    let Ok(url) = Url::parse(&canonical) else {
+        return canonical.to_lowercase();
+    };
+
+    let mut host = url.host_str().unwrap_or_default().to_string();
+    while let Some(stripped) = MIRROR_HOST_PREFIXES.iter().find_map(|prefix| host.strip_prefix(prefix)) {
+        host = stripped.to_string();
+    }
+
+    let segments: Vec<&str> = url
+        .path()
+        .split('/')
+        .filter(|segment| !segment.is_empty() && *segment != "amp")
+        .collect();
+    let mut path = segments.join("/");
+    if let Some(stripped) = path.strip_suffix(".amp") {
+        path = stripped.to_string();
+    }
+
+    let mut params: Vec<(String, String)> = url
+        .query_pairs()
+        .filter(|(name, value)| !(name == "amp" || (name == "outputType" && value == "amp")))
+        .map(|(name, value)| (name.into_owned(), value.into_owned()))
+        .collect();
+    params.sort();
+
+    if let Some(port) = url.port() {
+        host = format!("{}:{}", host, port);
+    }
+    let mut key = format!("{}/{}", host, path);
+    if !params.is_empty() {
+        let query: Vec<String> = params.iter().map(|(name, value)| format!("{}={}", name, value)).collect();
+        key.push('?');
+        key.push_str(&query.join("&"));
+    }
+    key
+}
+
+/// 64-bit FNV-1a with a final avalanche, so every bit depends on the input
+fn hash_shingle(shingle: &str) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in shingle.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    hash ^= hash >> 33;
+    hash
+}
+
+/// SimHash fingerprint of a text over its word shingles; similar texts get
+/// fingerprints a few bits apart
+pub fn simhash(text: &str) -> u64 {
+    let words: Vec<String> = text
+        .split_whitespace()
+        .map(|word| word.chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect::<String>())
+        .filter(|word| !word.is_empty())
+        .collect();
+    if words.is_empty() {
+        return 0;
+    }
+
+    let mut weights = [0i64; 64];
+    for shingle in words.windows(SHINGLE_WORDS.min(words.len())) {
+        let hash = hash_shingle(&shingle.join(" "));
+        for (bit, weight) in weights.iter_mut().enumerate() {
+            if hash >> bit & 1 == 1 {
+                *weight += 1;
+            } else {
+                *weight -= 1;
+            }
+        }
+    }
+
+    weights
+        .iter()
+        .enumerate()
+        .filter(|(_, weight)| **weight > 0)
+        .fold(0u64, |fingerprint, (bit, _)| fingerprint | 1 << bit)
+}
+
+/// Number of bits two fingerprints differ in
+pub fn hamming_distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+/// URL keys a page is known by: where it was fetched and the canonical URL it declares
+fn page_keys(page: &PageExtract) -> Vec<String> {
+    let mut keys = vec![url_key(&page.final_url)];
+    let declared = page.canonical_url.as_deref().and_then(|canonical| {
+        Url::parse(&page.final_url)
+            .and_then(|base| base.join(canonical))
+            .ok()
+    });
+    if let Some(declared) = declared {
+        let key = url_key(declared.as_str());
+        if !keys.contains(&key) {
+            keys.push(key);
+        }
+    }
+    keys
+}
+
+/// Fold pages that share a URL key or have near-identical text into the
+/// first (highest-ranked) copy, whose `alternates` then list the others
+pub fn dedupe_pages(pages: Vec<PageExtract>) -> Vec<PageExtract> {
+    struct Kept {
+        page: PageExtract,
+        keys: Vec<String>,
+        fingerprint: Option<u64>,
+    }
+
+    let mut kept: Vec<Kept> = Vec::new();
+    for page in pages {
+        let keys = page_keys(&page);
+        let fingerprint = (page.word_count >= MIN_WORDS_FOR_SIMHASH).then(|| simhash(&page.text));
+
+        let original = kept.iter_mut().find(|existing| {
+            existing.keys.iter().any(|key| keys.contains(key))
+                || matches!(
+                    (existing.fingerprint, fingerprint),
+                    (Some(a), Some(b)) if hamming_distance(a, b) <= NEAR_DUPLICATE_DISTANCE
+                )
+        });
+        match original {
+            Some(original) => {
+                let urls = std::iter::once(page.final_url).chain(page.alternates);
+                for url in urls {
+                    if url != original.page.final_url && !original.page.alternates.contains(&url) {
+                        original.page.alternates.push(url);
+                    }
+                }
+                for key in keys {
+                    if !original.keys.contains(&key) {
+                        original.keys.push(key);
+                    }
+                }
+            }
+            None => kept.push(Kept { page, keys, fingerprint }),
+        }
+    }
+
+    kept.into_iter().map(|kept| kept.page).collect()
+}
+
+/// Pick up to `limit` distinct URLs from ranked search results. Each comes
+/// with the later results that share its URL key, as alternates.
+pub fn distinct_urls<'a>(urls: impl IntoIterator<Item = &'a str>, limit: usize) -> Vec<(String, Vec<String>)> {
+    let mut picked: Vec<(String, String, Vec<String>)> = Vec::new();
+    for url in urls {
+        let key = url_key(url);
+        if let Some((_, _, alternates)) = picked.iter_mut().find(|(existing, _, _)| *existing == key) {
+            if !alternates.iter().any(|alternate| alternate == url) {
+                alternates.push(url.to_string());
+            }
+        } else if picked.len() < limit {
+            picked.push((key, canonicalize_url(url), Vec::new()));
+        }
+    }
+    picked.into_iter().map(|(_, url, alternates)| (url, alternates)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::content_extraction::ExtractionMethod;
+
+    fn page(url: &str, text: &str) -> PageExtract {
+        PageExtract::new(text.to_string(), url.to_string(), 0.8, ExtractionMethod::DensityHeuristic)
+    }
+
+    fn article(topic: &str) -> String {
+        (0..80)
+            .map(|i| format!("{} sentence number {} talks about {} in detail.", topic, i, i * 7 % 13))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    #[test]
+    fn test_canonicalize_url() {
+        assert_eq!(
+            canonicalize_url("https://example.com/post?id=4&utm_source=x&UTM_medium=y&fbclid=abc#comments"),
+            "https://example.com/post?id=4"
+        );
+        assert_eq!(canonicalize_url("https://example.com/a?gclid=1"), "https://example.com/a");
+        assert_eq!(
+            canonicalize_url("https://example-com.cdn.ampproject.org/c/s/example.com/news/story.amp?utm_campaign=z"),
+            "https://example.com/news/story.amp"
+        );
+        assert_eq!(canonicalize_url(" not a url "), "not a url");
+    }
+
+    #[test]
+    fn test_url_key() {
+        let key = url_key("https://www.example.com/news/story/");
+        assert_eq!(key, "example.com/news/story");
+        assert_eq!(url_key("http://example.com/news/story?utm_source=feed"), key);
+        assert_eq!(url_key("https://amp.example.com/news/story/amp"), key);
+        assert_eq!(url_key("https://m.example.com/amp/news/story.amp?amp=1"), key);
+        assert_eq!(url_key("https://example.com/news/story?outputType=amp"), key);
+        assert_ne!(url_key("https://example.com/news/other"), key);
+        assert_eq!(url_key("https://example.com/s?b=2&a=1"), url_key("https://example.com/s?a=1&b=2"));
+        assert_ne!(url_key("https://example.com:8443/s"), url_key("https://example.com/s"));
+    }
+
+    #[test]
+    fn test_simhash_near_duplicates() {
+        let original = article("Rust");
+        let edited = original.replacen("detail", "depth", 2) + " Share this article.";
+        let different = article("Gardening");
+
+        assert_eq!(simhash(&original), simhash(&original.to_uppercase()));
+        assert!(hamming_distance(simhash(&original), simhash(&edited)) <= NEAR_DUPLICATE_DISTANCE);
+        assert!(hamming_distance(simhash(&original), simhash(&different)) > NEAR_DUPLICATE_DISTANCE);
+        assert_eq!(simhash(""), 0);
+    }
+
+    #[test]
+    fn test_dedupe_pages() {
+        let mut amp = page("https://example.com/story/amp", "Short AMP copy");
+        amp.canonical_url = Some("/story".to_string());
+        let pages = vec![
+            page("https://example.com/story", "Short original"),
+            page("https://mirror.net/copy", &(article("Rust") + " Mirrored from example.com.")),
+            amp,
+            page("https://blog.dev/post", &article("Gardening")),
+            page("https://other.org/rust", &article("Rust")),
+        ];
+
+        let deduped = dedupe_pages(pages);
+        let urls: Vec<&str> = deduped.iter().map(|page| page.final_url.as_str()).collect();
+        assert_eq!(urls, vec!["https://example.com/story", "https://mirror.net/copy", "https://blog.dev/post"]);
+        assert_eq!(deduped[0].alternates, vec!["https://example.com/story/amp"]);
+        assert_eq!(deduped[1].alternates, vec!["https://other.org/rust"]);
+        assert!(deduped[2].alternates.is_empty());
+    }
+
+    #[test]
+    fn test_distinct_urls() {
+        let results = [
+            "https://www.example.com/a?utm_source=search",
+            "https://example.com/b",
+            "https://example.com/a/",
+            "https://example.com/c",
+            "https://example.com/b?fbclid=1",
+        ];
+        let picked = distinct_urls(results.iter().copied(), 2);
+        assert_eq!(
+            picked,
+            vec![
+                ("https://www.example.com/a".to_string(), vec!["https://example.com/a/".to_string()]),
+                ("https://example.com/b".to_string(), vec!["https://example.com/b?fbclid=1".to_string()]),
+            ]
+        );
+    }
+}
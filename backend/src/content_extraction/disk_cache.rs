@@ -0,0 +1,279 @@
+// Disk Cache
+// Persists extracted pages in SQLite so they survive backend restarts
+
+use chrono::Utc;
+use serde::Serialize;
+use std::time::Duration;
+
+use crate::content_extraction::cache_manager::CacheManager;
+use crate::content_extraction::http_fetcher::CacheValidators;
+use crate::content_extraction::PageExtract;
+use crate::db::{Database, WebCacheEntry};
+use crate::error::AppError;
+
+/// Result of a disk cache lookup
+#[derive(Debug, Clone)]
+pub enum DiskLookup {
+    /// Entry within its TTL
+    Fresh(PageExtract),
+
+    /// Entry past its TTL that can be revalidated with the origin server
+    Stale {
+        extract: PageExtract,
+        validators: CacheValidators,
+    },
+
+    /// Nothing usable cached
+    Miss,
+}
+
+/// Disk cache statistics
+#[derive(Debug, Clone, Serialize)]
+pub struct DiskCacheStats {
+    pub entries: i64,
+    pub size_bytes: i64,
+    pub max_size_bytes: usize,
+    pub ttl_secs: u64,
+}
+
+/// Disk Cache
+///
+/// Second cache layer behind the in-memory `CacheManager`:
+/// - TTL-based freshness (24 hours default)
+/// - Expired entries with ETag/Last-Modified are kept (7 days default) so a
+///   refetch can be a conditional request
+/// - LRU eviction by last access when the size limit is exceeded
+///
+/// Storage errors are logged and treated as cache misses; the cache never
+/// fails a page fetch.
+#[derive(Clone)]
+pub struct DiskCache {
+    db: Database,
+    ttl: Duration,
+    stale_retention: Duration,
+    max_size_bytes: usize,
+}
+
+impl DiskCache {
+    /// Creates a DiskCache with default settings
+    ///
+    /// Defaults:
+    /// - ttl: 24 hours
+    /// - stale_retention: 7 days
+    /// - max_size_bytes: 200MB
+    pub fn new(db: Database) -> Self {
+        Self::with_settings(
+            db,
+            Duration::from_secs(24 * 60 * 60),
+            Duration::from_secs(7 * 24 * 60 * 60),
+            200 * 1024 * 1024,
+        )
+    }
+
+    /// Creates a DiskCache with custom settings
+    pub fn with_settings(db: Database, ttl: Duration, stale_retention: Duration, max_size_bytes: usize) -> Self {
+        Self {
+            db,
+            ttl,
+            stale_retention,
+            max_size_bytes,
+        }
+    }
+
+    /// Looks up a URL, recording the access for LRU eviction
+    pub async fn lookup(&self, url: &str) -> DiskLookup {
+        let key = CacheManager::hash_url(url);
+        let (entry, json) = match self.db.get_web_cache_entry(&key).await {
+            Ok(Some(found)) => found,
+            Ok(None) => return DiskLookup::Miss,
+            Err(e) => {
+                tracing::warn!("Disk cache lookup failed for {}: {}", url, e);
+                return DiskLookup::Miss;
+            }
+        };
+
+        let extract: PageExtract = match serde_json::from_str(&json) {
+            Ok(extract) => extract,
+            Err(e) => {
+                // Written by an incompatible version; drop it
+                tracing::debug!("Discarding unreadable disk cache entry for {}: {}", url, e);
+                let _ = self.db.delete_web_cache_entry(&key).await;
+                return DiskLookup::Miss;
+            }
+        };
+
+        let age = (Utc::now() - entry.cached_at).to_std().unwrap_or_default();
+        let validators = CacheValidators {
+            etag: entry.etag,
+            last_modified: entry.last_modified,
+        };
+
+        if age < self.ttl {
+            if let Err(e) = self.db.touch_web_cache_entry(&key, Utc::now(), false).await {
+                tracing::debug!("Failed to record disk cache access for {}: {}", url, e);
+            }
+            DiskLookup::Fresh(extract)
+        } else if !validators.is_empty() && age < self.ttl + self.stale_retention {
+            DiskLookup::Stale { extract, validators }
+        } else {
+            let _ = self.db.delete_web_cache_entry(&key).await;
+            DiskLookup::Miss
+        }
+    }
+
+    /// Stores an extract with the response's revalidation headers, then
+    /// enforces expiry and the size limit
+    pub async fn store(&self, url: &str, extract: &PageExtract, validators: &CacheValidators) {
+        let json = match serde_json::to_string(extract) {
+            Ok(json) => json,
+            Err(e) => {
+                tracing::warn!("Failed to serialize extract for {}: {}", url, e);
+                return;
+            }
+        };
+        if json.len() > self.max_size_bytes {
+            return;
+        }
+
+        let now = Utc::now();
+        let entry = WebCacheEntry {
+            key: CacheManager::hash_url(url),
+            url: url.to_string(),
+            size_bytes: json.len() as i64,
+            etag: validators.etag.clone(),
+            last_modified: validators.last_modified.clone(),
+            cached_at: now,
+            last_accessed: now,
+        };
+
+        if let Err(e) = self.db.upsert_web_cache_entry(&entry, &json).await {
+            tracing::warn!("Failed to write disk cache entry for {}: {}", url, e);
+            return;
+        }
+        if let Err(e) = self.enforce_limits().await {
+            tracing::warn!("Disk cache eviction failed: {}", e);
+        }
+    }
+
+    /// Restarts the TTL of an entry the origin confirmed unchanged (304)
+    pub async fn mark_revalidated(&self, url: &str) {
+        let key = CacheManager::hash_url(url);
+        if let Err(e) = self.db.touch_web_cache_entry(&key, Utc::now(), true).await {
+            tracing::warn!("Failed to refresh disk cache entry for {}: {}", url, e);
+        }
+    }
+
+    /// Removes expired entries and evicts least recently used ones over the size limit
+    async fn enforce_limits(&self) -> Result<(), AppError> {
+        let now = Utc::now();
+        let ttl = chrono::Duration::from_std(self.ttl).unwrap_or_default();
+        let retention = chrono::Duration::from_std(self.stale_retention).unwrap_or_default();
+
+        let expired = self.db.delete_expired_web_cache(now - ttl, now - ttl - retention).await?;
+        let evicted = self.db.evict_web_cache_lru(self.max_size_bytes as i64).await?;
+        if expired + evicted > 0 {
+            tracing::debug!("Disk cache: removed {} expired and {} LRU entries", expired, evicted);
+        }
+        Ok(())
+    }
+
+    pub async fn stats(&self) -> Result<DiskCacheStats, AppError> {
+        let (entries, size_bytes) = self.db.web_cache_totals().await?;
+        Ok(DiskCacheStats {
+            entries,
+            size_bytes,
+            max_size_bytes: self.max_size_bytes,
+            ttl_secs: self.ttl.as_secs(),
+        })
+    }
+
+    /// Cached entries, most recently used first
+    pub async fn entries(&self, limit: usize) -> Result<Vec<WebCacheEntry>, AppError> {
+        self.db.list_web_cache_entries(limit).await
+    }
+
+    /// Removes the entry for one URL
+    pub async fn remove(&self, url: &str) -> Result<bool, AppError> {
+        self.db.delete_web_cache_entry(&CacheManager::hash_url(url)).await
+    }
+
+    /// Removes every entry, returning how many were deleted
+    pub async fn clear(&self) -> Result<u64, AppError> {
+        self.db.clear_web_cache().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::content_extraction::ExtractionMethod;
+
+    async fn cache(dir: &tempfile::TempDir, ttl: Duration, max_size_bytes: usize) -> DiskCache {
+        let url = format!("sqlite://{}?mode=rwc", dir.path().join("cache.db").display());
+        let db = Database::new(&url).await.unwrap();
+        DiskCache::with_settings(db, ttl, Duration::from_secs(3600), max_size_bytes)
+    }
+
+    fn extract(text: &str) -> PageExtract {
+        PageExtract::new(text.to_string(), "https://example.com".to_string(), 0.9, ExtractionMethod::DensityHeuristic)
+    }
+
+    #[tokio::test]
+    async fn test_store_and_lookup() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = cache(&dir, Duration::from_secs(60), 1024 * 1024).await;
+
+        assert!(matches!(cache.lookup("https://example.com/a").await, DiskLookup::Miss));
+        cache.store("https://example.com/a", &extract("hello"), &CacheValidators::default()).await;
+
+        match cache.lookup("https://example.com/a").await {
+            DiskLookup::Fresh(found) => assert_eq!(found.text, "hello"),
+            other => panic!("expected fresh entry, got {:?}", other),
+        }
+        assert_eq!(cache.stats().await.unwrap().entries, 1);
+
+        assert!(cache.remove("https://example.com/a").await.unwrap());
+        assert!(matches!(cache.lookup("https://example.com/a").await, DiskLookup::Miss));
+    }
+
+    #[tokio::test]
+    async fn test_expired_entries_need_validators() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = cache(&dir, Duration::ZERO, 1024 * 1024).await;
+
+        let validators = CacheValidators {
+            etag: Some("\"v1\"".to_string()),
+            last_modified: None,
+        };
+        cache.store("https://example.com/tagged", &extract("tagged"), &validators).await;
+        cache.store("https://example.com/plain", &extract("plain"), &CacheValidators::default()).await;
+
+        match cache.lookup("https://example.com/tagged").await {
+            DiskLookup::Stale { extract, validators: found } => {
+                assert_eq!(extract.text, "tagged");
+                assert_eq!(found, validators);
+            }
+            other => panic!("expected stale entry, got {:?}", other),
+        }
+        assert!(matches!(cache.lookup("https://example.com/plain").await, DiskLookup::Miss));
+    }
+
+    #[tokio::test]
+    async fn test_lru_eviction_by_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let entry_size = serde_json::to_string(&extract(&"x".repeat(100))).unwrap().len();
+        let cache = cache(&dir, Duration::from_secs(60), entry_size * 2 + 10).await;
+
+        cache.store("https://example.com/1", &extract(&"x".repeat(100)), &CacheValidators::default()).await;
+        cache.store("https://example.com/2", &extract(&"y".repeat(100)), &CacheValidators::default()).await;
+        // Touch the first entry so the second becomes least recently used
+        assert!(matches!(cache.lookup("https://example.com/1").await, DiskLookup::Fresh(_)));
+        cache.store("https://example.com/3", &extract(&"z".repeat(100)), &CacheValidators::default()).await;
+
+        assert!(matches!(cache.lookup("https://example.com/1").await, DiskLookup::Fresh(_)));
+        assert!(matches!(cache.lookup("https://example.com/2").await, DiskLookup::Miss));
+        assert!(matches!(cache.lookup("https://example.com/3").await, DiskLookup::Fresh(_)));
+
+        assert_eq!(cache.clear().await.unwrap(), 2);
+    }
+}
@@ -0,0 +1,443 @@
+// Document Extractor
+// Extracts text from non-HTML responses: PDF, plain text, JSON and RSS/Atom feeds
+
+use scraper::Html;
+use std::time::Instant;
+use xml::reader::{EventReader, XmlEvent};
+
+use crate::content_extraction::content_extractor::{ContentExtraction, MainContentExtractor};
+use crate::content_extraction::{ContentExtractionError, ExtractionMethod};
+
+/// Maximum characters of document text returned in a PageExtract
+pub const MAX_DOCUMENT_CHARS: usize = 100_000;
+
+/// Maximum number of feed entries rendered
+const MAX_FEED_ITEMS: usize = 50;
+
+/// Kind of document returned by the server
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocumentKind {
+    Html,
+    Pdf,
+    PlainText,
+    Json,
+    Feed,
+}
+
+impl DocumentKind {
+    /// Detects the document kind from the Content-Type header, sniffing the
+    /// body when the header is missing or generic
+    pub fn detect(content_type: Option<&str>, body: &[u8]) -> Self {
+        if body.starts_with(b"%PDF-") {
+            return DocumentKind::Pdf;
+        }
+
+        let mime = content_type
+            .and_then(|ct| ct.split(';').next())
+            .map(|ct| ct.trim().to_ascii_lowercase())
+            .unwrap_or_default();
+
+        match mime.as_str() {
+            "text/html" | "application/xhtml+xml" => DocumentKind::Html,
+            "application/pdf" => DocumentKind::Pdf,
+            "application/rss+xml" | "application/atom+xml" | "application/rdf+xml" => DocumentKind::Feed,
+            "application/json" | "text/json" => DocumentKind::Json,
+            m if m.ends_with("+json") => DocumentKind::Json,
+            "application/xml" | "text/xml" => {
+                if Self::looks_like_feed(body) {
+                    DocumentKind::Feed
+                } else {
+                    DocumentKind::PlainText
+                }
+            }
+            "text/plain" | "text/markdown" | "text/csv" => DocumentKind::PlainText,
+            _ => Self::sniff(body),
+        }
+    }
+
+    /// Guesses the kind of an untyped body, defaulting to HTML
+    fn sniff(body: &[u8]) -> Self {
+        let head = String::from_utf8_lossy(&body[..body.len().min(1024)]);
+        let trimmed = head.trim_start();
+
+        if (trimmed.starts_with('{') || trimmed.starts_with('['))
+            && serde_json::from_slice::<serde_json::Value>(body).is_ok()
+        {
+            DocumentKind::Json
+        } else if Self::looks_like_feed(body) {
+            DocumentKind::Feed
+        } else {
+            DocumentKind::Html
+        }
+    }
+
+    fn looks_like_feed(body: &[u8]) -> bool {
+        let head = String::from_utf8_lossy(&body[..body.len().min(1024)]).to_ascii_lowercase();
+        head.contains("<rss") || head.contains("<feed") || head.contains("<rdf:rdf")
+    }
+
+    /// Extraction method reported for this kind
+    pub fn method(&self) -> ExtractionMethod {
+        match self {
+            DocumentKind::Html => ExtractionMethod::DensityHeuristic,
+            DocumentKind::Pdf => ExtractionMethod::Pdf,
+            DocumentKind::PlainText => ExtractionMethod::PlainText,
+            DocumentKind::Json => ExtractionMethod::Json,
+            DocumentKind::Feed => ExtractionMethod::Feed,
+        }
+    }
+}
+
+/// Result from document extraction
+#[derive(Debug, Clone)]
+pub struct DocumentExtraction {
+    /// Extracted text and quality metrics
+    pub content: ContentExtraction,
+
+    /// Document title (feed title), if any
+    pub title: Option<String>,
+
+    /// Links found in the document (feed entry links)
+    pub links: Vec<String>,
+}
+
+/// Document Extractor
+///
+/// Turns non-HTML response bodies into readable text. Output is truncated to
+/// `MAX_DOCUMENT_CHARS`; the body itself is already bounded by the fetcher's
+/// size limit.
+pub struct DocumentExtractor;
+
+impl DocumentExtractor {
+    /// Extracts text from a document body of the given kind
+    ///
+    /// HTML is not handled here; use `MainContentExtractor` for it.
+    pub fn extract(
+        kind: DocumentKind,
+        body: &[u8],
+        url: &str,
+    ) -> Result<DocumentExtraction, ContentExtractionError> {
+        let start_time = Instant::now();
+
+        let (text, title, links) = match kind {
+            DocumentKind::Pdf => (Self::extract_pdf(body, url)?, None, Vec::new()),
+            DocumentKind::PlainText => (Self::decode(body).trim().to_string(), None, Vec::new()),
+            DocumentKind::Json => (Self::extract_json(body), None, Vec::new()),
+            DocumentKind::Feed => Self::extract_feed(body, url)?,
+            DocumentKind::Html => {
+                return Err(ContentExtractionError::ExtractionFailed {
+                    url: url.to_string(),
+                    reason: "HTML documents are handled by MainContentExtractor".to_string(),
+                })
+            }
+        };
+
+        let text = truncate_chars(text, MAX_DOCUMENT_CHARS);
+        let word_count = text.split_whitespace().count();
+
+        Ok(DocumentExtraction {
+            content: ContentExtraction {
+                confidence: Self::confidence(word_count),
+                word_count,
+                text,
+                method: kind.method(),
+                extraction_time_ms: start_time.elapsed().as_millis() as u64,
+            },
+            title,
+            links,
+        })
+    }
+
+    /// Confidence for document text
+    ///
+    /// The text is the document itself rather than a heuristic guess at the
+    /// main content, so any non-empty document scores at least 0.5.
+    fn confidence(word_count: usize) -> f32 {
+        if word_count == 0 {
+            0.0
+        } else {
+            MainContentExtractor::word_count_score(word_count).max(0.5)
+        }
+    }
+
+    fn decode(body: &[u8]) -> String {
+        // Strip a UTF-8 BOM before decoding
+        let body = body.strip_prefix(b"\xEF\xBB\xBF".as_slice()).unwrap_or(body);
+        String::from_utf8_lossy(body).to_string()
+    }
+
+    fn extract_pdf(body: &[u8], url: &str) -> Result<String, ContentExtractionError> {
+        // pdf-extract panics on some malformed files
+        let result = std::panic::catch_unwind(|| pdf_extract::extract_text_from_mem(body));
+
+        match result {
+            Ok(Ok(text)) => Ok(normalize_whitespace(&text)),
+            Ok(Err(e)) => Err(ContentExtractionError::ExtractionFailed {
+                url: url.to_string(),
+                reason: format!("Failed to extract PDF text: {}", e),
+            }),
+            Err(_) => Err(ContentExtractionError::ExtractionFailed {
+                url: url.to_string(),
+                reason: "PDF parser crashed on this document".to_string(),
+            }),
+        }
+    }
+
+    fn extract_json(body: &[u8]) -> String {
+        match serde_json::from_slice::<serde_json::Value>(body) {
+            Ok(value) => serde_json::to_string_pretty(&value).unwrap_or_default(),
+            // Invalid JSON is still worth returning as text
+            Err(_) => Self::decode(body).trim().to_string(),
+        }
+    }
+
+    fn extract_feed(
+        body: &[u8],
+        url: &str,
+    ) -> Result<(String, Option<String>, Vec<String>), ContentExtractionError> {
+        let feed = parse_feed(body).map_err(|reason| ContentExtractionError::ExtractionFailed {
+            url: url.to_string(),
+            reason: format!("Failed to parse feed: {}", reason),
+        })?;
+
+        let mut text = String::new();
+        if let Some(title) = &feed.title {
+            text.push_str(&format!("# {}\n", title));
+        }
+        if let Some(description) = &feed.description {
+            text.push_str(&format!("{}\n", description));
+        }
+
+        for item in &feed.items {
+            text.push_str(&format!("\n## {}\n", item.title.as_deref().unwrap_or("Untitled")));
+            if let Some(date) = &item.date {
+                text.push_str(&format!("{}\n", date));
+            }
+            if let Some(link) = &item.link {
+                text.push_str(&format!("{}\n", link));
+            }
+            if let Some(summary) = &item.summary {
+                text.push_str(&format!("\n{}\n", summary));
+            }
+        }
+
+        let links = feed.items.iter().filter_map(|item| item.link.clone()).collect();
+        Ok((text.trim().to_string(), feed.title, links))
+    }
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct Feed {
+    pub(crate) title: Option<String>,
+    pub(crate) description: Option<String>,
+    pub(crate) items: Vec<FeedItem>,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct FeedItem {
+    /// RSS `guid` or Atom `id`
+    pub(crate) guid: Option<String>,
+    pub(crate) title: Option<String>,
+    pub(crate) link: Option<String>,
+    pub(crate) date: Option<String>,
+    pub(crate) summary: Option<String>,
+}
+
+/// Parses RSS 2.0, RSS 1.0 (RDF) and Atom feeds
+pub(crate) fn parse_feed(body: &[u8]) -> Result<Feed, String> {
+    let mut feed = Feed::default();
+    let mut current: Option<FeedItem> = None;
+    let mut element = String::new();
+    let mut buffer = String::new();
+
+    for event in EventReader::new(body) {
+        match event.map_err(|e| e.to_string())? {
+            XmlEvent::StartElement { name, attributes, .. } => {
+                let local = name.local_name.to_ascii_lowercase();
+                if local == "item" || local == "entry" {
+                    current = Some(FeedItem::default());
+                } else if local == "link" {
+                    // Atom links carry the URL in href; prefer rel="alternate"
+                    let href = attributes.iter().find(|a| a.name.local_name == "href");
+                    let rel = attributes.iter().find(|a| a.name.local_name == "rel");
+                    if let (Some(item), Some(href)) = (current.as_mut(), href) {
+                        if rel.is_none_or(|r| r.value == "alternate") && item.link.is_none() {
+                            item.link = Some(href.value.clone());
+                        }
+                    }
+                }
+                element = local;
+                buffer.clear();
+            }
+            XmlEvent::Characters(text) | XmlEvent::CData(text) => buffer.push_str(&text),
+            XmlEvent::EndElement { name } => {
+                let local = name.local_name.to_ascii_lowercase();
+                let value = buffer.trim().to_string();
+                buffer.clear();
+
+                if local == "item" || local == "entry" {
+                    if let Some(item) = current.take() {
+                        if feed.items.len() < MAX_FEED_ITEMS {
+                            feed.items.push(item);
+                        }
+                    }
+                    continue;
+                }
+                if value.is_empty() || local != element {
+                    continue;
+                }
+
+                match (current.as_mut(), local.as_str()) {
+                    (Some(item), "title") => item.title = Some(html_to_text(&value)),
+                    (Some(item), "guid" | "id") if item.guid.is_none() => item.guid = Some(value),
+                    (Some(item), "link") if item.link.is_none() => item.link = Some(value),
+                    (Some(item), "pubdate" | "published" | "updated" | "date") if item.date.is_none() => {
+                        item.date = Some(value)
+                    }
+                    (Some(item), "description" | "summary" | "content" | "encoded") => {
+                        // Keep the longest body (content:encoded over description)
+                        let text = html_to_text(&value);
+                        if item.summary.as_ref().is_none_or(|s| s.len() < text.len()) {
+                            item.summary = Some(text);
+                        }
+                    }
+                    (None, "title") if feed.title.is_none() => feed.title = Some(html_to_text(&value)),
+                    (None, "description" | "subtitle") if feed.description.is_none() => {
+                        feed.description = Some(html_to_text(&value))
+                    }
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if feed.title.is_none() && feed.items.is_empty() {
+        return Err("no feed title or entries found".to_string());
+    }
+    Ok(feed)
+}
+
+/// Strips markup from HTML embedded in feed fields
+fn html_to_text(value: &str) -> String {
+    if !value.contains('<') {
+        return normalize_whitespace(value);
+    }
+    let fragment = Html::parse_fragment(value);
+    normalize_whitespace(&fragment.root_element().text().collect::<Vec<_>>().join(" "))
+}
+
+/// Collapses runs of spaces while keeping paragraph breaks
+fn normalize_whitespace(text: &str) -> String {
+    text.split("\n\n")
+        .map(|para| para.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|para| !para.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+fn truncate_chars(text: String, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((idx, _)) => text[..idx].to_string(),
+        None => text,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_from_content_type_and_body() {
+        assert_eq!(DocumentKind::detect(Some("application/pdf"), b"..."), DocumentKind::Pdf);
+        assert_eq!(DocumentKind::detect(Some("application/octet-stream"), b"%PDF-1.7"), DocumentKind::Pdf);
+        assert_eq!(DocumentKind::detect(Some("text/plain; charset=utf-8"), b"hi"), DocumentKind::PlainText);
+        assert_eq!(DocumentKind::detect(Some("application/ld+json"), b"{}"), DocumentKind::Json);
+        assert_eq!(DocumentKind::detect(None, b" {\"a\": 1}"), DocumentKind::Json);
+        assert_eq!(DocumentKind::detect(Some("text/xml"), b"<?xml?><rss version=\"2.0\">"), DocumentKind::Feed);
+        assert_eq!(DocumentKind::detect(Some("text/html"), b"<html></html>"), DocumentKind::Html);
+        assert_eq!(DocumentKind::detect(None, b"<html></html>"), DocumentKind::Html);
+    }
+
+    #[test]
+    fn test_extract_rss_feed() {
+        let rss = br#"<?xml version="1.0"?>
+            <rss version="2.0"><channel>
+                <title>Example Blog</title>
+                <description>Posts about things</description>
+                <item>
+                    <title>First post</title>
+                    <link>https://example.com/first</link>
+                    <pubDate>Mon, 06 Jan 2025 10:00:00 GMT</pubDate>
+                    <description><![CDATA[<p>Hello <b>world</b></p>]]></description>
+                </item>
+            </channel></rss>"#;
+
+        let result = DocumentExtractor::extract(DocumentKind::Feed, rss, "https://example.com/feed").unwrap();
+        assert_eq!(result.title.as_deref(), Some("Example Blog"));
+        assert_eq!(result.links, vec!["https://example.com/first".to_string()]);
+        assert!(result.content.text.starts_with("# Example Blog\nPosts about things\n"));
+        assert!(result.content.text.contains("## First post\nMon, 06 Jan 2025 10:00:00 GMT\nhttps://example.com/first"));
+        assert!(result.content.text.contains("Hello world"));
+        assert_eq!(result.content.method, ExtractionMethod::Feed);
+    }
+
+    #[test]
+    fn test_extract_atom_feed() {
+        let atom = br#"<feed xmlns="http://www.w3.org/2005/Atom">
+                <title>Atom Site</title>
+                <entry>
+                    <title>Entry</title>
+                    <link rel="self" href="https://example.com/self"/>
+                    <link href="https://example.com/entry"/>
+                    <updated>2025-01-06T10:00:00Z</updated>
+                    <summary>Summary text</summary>
+                </entry>
+            </feed>"#;
+
+        let result = DocumentExtractor::extract(DocumentKind::Feed, atom, "https://example.com/atom").unwrap();
+        assert_eq!(result.links, vec!["https://example.com/entry".to_string()]);
+        assert!(result.content.text.contains("Summary text"));
+    }
+
+    #[test]
+    fn test_parse_feed_entry_ids() {
+        let rss = br#"<rss version="2.0"><channel><title>Blog</title>
+                <item><guid isPermaLink="false">post-1</guid><title>One</title></item>
+            </channel></rss>"#;
+        let atom = br#"<feed xmlns="http://www.w3.org/2005/Atom"><id>urn:feed</id><title>Site</title>
+                <entry><id>urn:entry:1</id><title>Entry</title></entry>
+            </feed>"#;
+
+        assert_eq!(parse_feed(rss).unwrap().items[0].guid.as_deref(), Some("post-1"));
+        assert_eq!(parse_feed(atom).unwrap().items[0].guid.as_deref(), Some("urn:entry:1"));
+    }
+
+    #[test]
+    fn test_extract_json_and_text() {
+        let json = DocumentExtractor::extract(DocumentKind::Json, br#"{"name":"skhoot"}"#, "u").unwrap();
+        assert_eq!(json.content.text, "{\n  \"name\": \"skhoot\"\n}");
+        assert_eq!(json.content.method, ExtractionMethod::Json);
+        assert!(json.content.confidence >= 0.5);
+
+        let text = DocumentExtractor::extract(DocumentKind::PlainText, "\u{feff}line one\nline two\n".as_bytes(), "u").unwrap();
+        assert_eq!(text.content.text, "line one\nline two");
+        assert_eq!(text.content.word_count, 4);
+
+        let empty = DocumentExtractor::extract(DocumentKind::PlainText, b"   ", "u").unwrap();
+        assert_eq!(empty.content.confidence, 0.0);
+    }
+
+    #[test]
+    fn test_invalid_pdf_is_an_error() {
+        let result = DocumentExtractor::extract(DocumentKind::Pdf, b"%PDF-1.4 garbage", "https://example.com/a.pdf");
+        assert!(matches!(result, Err(ContentExtractionError::ExtractionFailed { .. })));
+    }
+
+    #[test]
+    fn test_text_is_truncated() {
+        let long = "a".repeat(MAX_DOCUMENT_CHARS + 10);
+        let result = DocumentExtractor::extract(DocumentKind::PlainText, long.as_bytes(), "u").unwrap();
+        assert_eq!(result.content.text.len(), MAX_DOCUMENT_CHARS);
+    }
+}
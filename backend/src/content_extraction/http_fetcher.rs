@@ -23,10 +23,58 @@ pub struct FetchResult {
     /// Downloaded HTML content
     pub html: String,
     
+    /// Raw response body (for non-HTML documents such as PDFs)
+    pub body: Vec<u8>,
+    
+    /// ETag header value, for cache revalidation
+    pub etag: Option<String>,
+    
+    /// Last-Modified header value, for cache revalidation
+    pub last_modified: Option<String>,
+    
     /// Time taken to fetch (milliseconds)
     pub fetch_time_ms: u64,
 }
 
+/// Accept header for page fetches
+const PAGE_ACCEPT: &str = "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8";
+
+/// Validators from a previous response, sent with conditional requests
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CacheValidators {
+    /// ETag, sent as If-None-Match
+    pub etag: Option<String>,
+    
+    /// Last-Modified, sent as If-Modified-Since
+    pub last_modified: Option<String>,
+}
+
+impl CacheValidators {
+    pub fn is_empty(&self) -> bool {
+        self.etag.is_none() && self.last_modified.is_none()
+    }
+}
+
+/// Result of a conditional fetch
+#[derive(Debug, Clone)]
+pub enum ConditionalFetch {
+    /// The server answered 304 Not Modified
+    NotModified,
+    
+    /// The page changed (or the server ignored the validators)
+    Fetched(FetchResult),
+}
+
+/// Response body and metadata before decoding
+struct RawBody {
+    final_url: String,
+    status: u16,
+    content_type: Option<String>,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    bytes: Vec<u8>,
+}
+
 /// HTTP Fetcher with size and timeout limits
 /// 
 /// This fetcher safely downloads web pages with:
@@ -83,6 +131,15 @@ impl HttpFetcher {
         })
     }
 
+    /// This fetcher with another timeout, sharing its connection pool
+    pub fn with_timeout(&self, timeout: Duration) -> Self {
+        Self {
+            client: self.client.clone(),
+            max_bytes: self.max_bytes,
+            timeout,
+        }
+    }
+
     /// Fetches a URL with streaming size limit
     /// 
     /// This method:
@@ -94,15 +151,78 @@ impl HttpFetcher {
     pub async fn fetch(&self, url: &Url) -> Result<FetchResult, ContentExtractionError> {
         let start_time = Instant::now();
 
+        let body = self.fetch_body(url, PAGE_ACCEPT, None).await?;
+        Ok(Self::page_result(body, start_time))
+    }
+
+    /// Fetches a URL, sending `validators` so an unchanged page costs a 304
+    pub async fn fetch_conditional(
+        &self,
+        url: &Url,
+        validators: &CacheValidators,
+    ) -> Result<ConditionalFetch, ContentExtractionError> {
+        let start_time = Instant::now();
+
+        let body = self.fetch_body(url, PAGE_ACCEPT, Some(validators)).await?;
+        if body.status == 304 {
+            return Ok(ConditionalFetch::NotModified);
+        }
+        Ok(ConditionalFetch::Fetched(Self::page_result(body, start_time)))
+    }
+
+    fn page_result(body: RawBody, start_time: Instant) -> FetchResult {
+        let html = String::from_utf8_lossy(&body.bytes).to_string();
+
+        let fetch_time_ms = start_time.elapsed().as_millis() as u64;
+
+        FetchResult {
+            final_url: body.final_url,
+            status: body.status,
+            content_type: body.content_type,
+            html,
+            body: body.bytes,
+            etag: body.etag,
+            last_modified: body.last_modified,
+            fetch_time_ms,
+        }
+    }
+
+    /// Fetches an image with the same SSRF and size protections as pages
+    /// 
+    /// Returns the raw bytes and the Content-Type header value.
+    pub async fn fetch_image(&self, url: &Url) -> Result<(Vec<u8>, Option<String>), ContentExtractionError> {
+        let body = self.fetch_body(url, "image/*,*/*;q=0.8", None).await?;
+        Ok((body.bytes, body.content_type))
+    }
+
+    /// Sends a GET request and streams the body, enforcing SSRF rules and size limit
+    /// 
+    /// With `validators`, a 304 response is returned with an empty body.
+    async fn fetch_body(
+        &self,
+        url: &Url,
+        accept: &str,
+        validators: Option<&CacheValidators>,
+    ) -> Result<RawBody, ContentExtractionError> {
         // Validate URL for SSRF
         SsrfValidator::validate_url(url).await?;
 
         // Send request
-        let response = self
+        let mut request = self
             .client
             .get(url.as_str())
-            .header("Accept", "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8")
-            .header("Accept-Language", "en-US,en;q=0.9")
+            .timeout(self.timeout)
+            .header("Accept", accept)
+            .header("Accept-Language", "en-US,en;q=0.9");
+        if let Some(validators) = validators {
+            if let Some(etag) = &validators.etag {
+                request = request.header("If-None-Match", etag);
+            }
+            if let Some(last_modified) = &validators.last_modified {
+                request = request.header("If-Modified-Since", last_modified);
+            }
+        }
+        let response = request
             .send()
             .await
             .map_err(|e| {
@@ -122,11 +242,27 @@ impl HttpFetcher {
         // Capture metadata before consuming body
         let final_url = response.url().to_string();
         let status = response.status().as_u16();
-        let content_type = response
-            .headers()
-            .get("content-type")
-            .and_then(|v| v.to_str().ok())
-            .map(|s| s.to_string());
+        let header = |name: &str| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(|s| s.to_string())
+        };
+        let content_type = header("content-type");
+        let etag = header("etag");
+        let last_modified = header("last-modified");
+
+        if status == 304 && validators.is_some() {
+            return Ok(RawBody {
+                final_url,
+                status,
+                content_type,
+                etag,
+                last_modified,
+                bytes: Vec::new(),
+            });
+        }
 
         // Check for HTTP errors
         if !response.status().is_success() {
@@ -171,18 +307,16 @@ impl HttpFetcher {
             body_chunks.push(chunk);
         }
 
-        // Combine chunks into single string
-        let body_bytes: Vec<u8> = body_chunks.into_iter().flat_map(|c| c.to_vec()).collect();
-        let html = String::from_utf8_lossy(&body_bytes).to_string();
+        // Combine chunks into a single buffer
+        let bytes: Vec<u8> = body_chunks.into_iter().flat_map(|c| c.to_vec()).collect();
 
-        let fetch_time_ms = start_time.elapsed().as_millis() as u64;
-
-        Ok(FetchResult {
+        Ok(RawBody {
             final_url,
             status,
             content_type,
-            html,
-            fetch_time_ms,
+            etag,
+            last_modified,
+            bytes,
         })
     }
 
@@ -227,6 +361,10 @@ mod tests {
         let fetcher = fetcher.unwrap();
         assert_eq!(fetcher.max_bytes, 5 * 1024 * 1024);
         assert_eq!(fetcher.timeout, Duration::from_secs(10));
+
+        let fetcher = fetcher.with_timeout(Duration::from_secs(3));
+        assert_eq!(fetcher.max_bytes, 5 * 1024 * 1024);
+        assert_eq!(fetcher.timeout, Duration::from_secs(3));
     }
 
     #[tokio::test]
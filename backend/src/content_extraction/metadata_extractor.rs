@@ -4,6 +4,7 @@
 use scraper::{Html, Selector};
 use serde_json::Value as JsonValue;
 
+use crate::content_extraction::structured_data;
 use crate::content_extraction::Metadata;
 
 /// Partial metadata from a single source
@@ -24,6 +25,10 @@ struct PartialMetadata {
 /// 1. Open Graph tags (highest priority)
 /// 2. JSON-LD structured data
 /// 3. Standard meta tags (lowest priority)
+/// 
+/// Typed entities (articles, products, recipes, events) declared in JSON-LD,
+/// microdata or OpenGraph/Twitter tags are extracted alongside, see
+/// `structured_data`.
 pub struct MetadataExtractor;
 
 impl MetadataExtractor {
@@ -40,7 +45,9 @@ impl MetadataExtractor {
         let meta_metadata = Self::extract_meta_tags(&document);
 
         // Merge with priority
-        Self::merge_with_priority(vec![og_metadata, jsonld_metadata, meta_metadata])
+        let mut metadata = Self::merge_with_priority(vec![og_metadata, jsonld_metadata, meta_metadata]);
+        metadata.structured_data = structured_data::extract(&document);
+        metadata
     }
 
     /// Extracts metadata from Open Graph tags
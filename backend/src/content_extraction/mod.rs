@@ -5,8 +5,17 @@ pub mod types;
 pub mod ssrf_validator;
 pub mod http_fetcher;
 pub mod metadata_extractor;
+pub mod structured_data;
 pub mod content_extractor;
+pub mod document_extractor;
 pub mod cache_manager;
+pub mod disk_cache;
+pub mod answer;
+pub mod crawl;
+pub mod dedupe;
+pub mod site_rules;
+pub mod ocr;
+pub mod politeness;
 pub mod system;
 pub mod tauri_bridge;
 
@@ -14,14 +23,20 @@ pub mod tauri_bridge;
 mod integration_tests;
 
 pub use types::{
-    PageExtract, ContentExtractionError, ExtractionMethod,
+    PageExtract, CrawledPage, ContentExtractionError, ExtractionMethod,
     Metadata, SearchGatherResponse, WebSearchResult,
-    RenderJob, RenderResult, RenderWait,
+    StructuredData, ArticleData, ProductData, RecipeData, EventData, Offer, Rating,
+    RenderJob, RenderResult, RenderWait, ScreenshotMode, ExtractionLimits, is_valid_context_name,
 };
 pub use ssrf_validator::SsrfValidator;
 pub use http_fetcher::HttpFetcher;
 pub use metadata_extractor::MetadataExtractor;
 pub use content_extractor::MainContentExtractor;
+pub use document_extractor::{DocumentExtractor, DocumentKind};
 pub use cache_manager::CacheManager;
+pub use disk_cache::DiskCache;
+pub use ocr::OcrExtractor;
+pub use site_rules::{SiteRule, SiteRules, SiteRulesStore};
+pub use politeness::{PolitenessConfig, SkipReason, SkippedUrl};
 pub use system::ContentExtractionSystem;
 pub use tauri_bridge::TauriBridge;
@@ -0,0 +1,207 @@
+// OCR stage for image-heavy pages
+// Recognizes text in a page's images when the HTML yields little content
+
+use std::process::Stdio;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use url::Url;
+
+use crate::content_extraction::{ExtractionMethod, HttpFetcher, PageExtract};
+
+/// Pages below this confidence get the OCR stage
+pub const OCR_CONFIDENCE_THRESHOLD: f32 = 0.3;
+
+/// Maximum number of images recognized per page
+pub const MAX_OCR_IMAGES: usize = 5;
+
+/// Maximum size of a single image sent to OCR
+const MAX_IMAGE_BYTES: usize = 5 * 1024 * 1024;
+
+/// Recognized text shorter than this is treated as noise
+const MIN_OCR_CHARS: usize = 3;
+
+/// OCR Extractor
+///
+/// Downloads the primary image and the first content images of a page and
+/// runs them through the `tesseract` CLI. Failures on individual images are
+/// logged and skipped so OCR never fails the page extraction.
+pub struct OcrExtractor;
+
+impl OcrExtractor {
+    /// Whether the `tesseract` binary can be run
+    pub async fn is_available() -> bool {
+        Command::new("tesseract")
+            .arg("--version")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .await
+            .map(|s| s.success())
+            .unwrap_or(false)
+    }
+
+    /// Runs OCR on the page images and merges the recognized text into `extract`
+    ///
+    /// Returns the number of images that produced text.
+    pub async fn enrich(extract: &mut PageExtract) -> usize {
+        let candidates = Self::candidate_images(extract);
+        if candidates.is_empty() || !Self::is_available().await {
+            return 0;
+        }
+
+        let fetcher = match HttpFetcher::with_limits(MAX_IMAGE_BYTES, std::time::Duration::from_secs(10)) {
+            Ok(f) => f,
+            Err(e) => {
+                tracing::warn!("OCR: failed to create image fetcher: {}", e);
+                return 0;
+            }
+        };
+
+        let mut texts = Vec::new();
+        for url in candidates {
+            let (bytes, _) = match fetcher.fetch_image(&url).await {
+                Ok(image) => image,
+                Err(e) => {
+                    tracing::debug!("OCR: skipping image {}: {}", url, e);
+                    continue;
+                }
+            };
+
+            match Self::recognize(&bytes).await {
+                Ok(text) if text.chars().filter(|c| c.is_alphanumeric()).count() >= MIN_OCR_CHARS => {
+                    texts.push(text);
+                }
+                Ok(_) => {}
+                Err(e) => tracing::debug!("OCR: recognition failed for {}: {}", url, e),
+            }
+        }
+
+        let recognized = texts.len();
+        merge_ocr_text(extract, &texts);
+        recognized
+    }
+
+    /// Runs `tesseract` on an encoded image, reading it from stdin
+    pub async fn recognize(image: &[u8]) -> Result<String, String> {
+        let mut child = Command::new("tesseract")
+            .args(["stdin", "stdout"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| format!("Failed to run tesseract: {}", e))?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin
+                .write_all(image)
+                .await
+                .map_err(|e| format!("Failed to send image to tesseract: {}", e))?;
+        }
+
+        let output = child
+            .wait_with_output()
+            .await
+            .map_err(|e| format!("tesseract failed: {}", e))?;
+        if !output.status.success() {
+            return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+
+    /// Images worth recognizing: primary image first, then content images,
+    /// skipping duplicates, inline data and vector formats
+    pub fn candidate_images(extract: &PageExtract) -> Vec<Url> {
+        let base = Url::parse(&extract.final_url).ok();
+        let mut seen = std::collections::HashSet::new();
+
+        extract
+            .primary_image
+            .iter()
+            .chain(extract.images.iter())
+            .filter_map(|src| match &base {
+                Some(base) => base.join(src).ok(),
+                None => Url::parse(src).ok(),
+            })
+            .filter(|url| matches!(url.scheme(), "http" | "https"))
+            .filter(|url| !url.path().to_ascii_lowercase().ends_with(".svg"))
+            .filter(|url| seen.insert(url.to_string()))
+            .take(MAX_OCR_IMAGES)
+            .collect()
+    }
+}
+
+/// Merges recognized image text into a page extract
+///
+/// When the page text is only the raw-HTML fallback it is replaced by the
+/// recognized text; otherwise the text is appended after the article.
+pub fn merge_ocr_text(extract: &mut PageExtract, texts: &[String]) {
+    if texts.is_empty() {
+        return;
+    }
+
+    let ocr_text = texts.join("\n\n");
+    if extract.extraction_method == ExtractionMethod::Fallback || extract.text.trim().is_empty() {
+        extract.text = ocr_text.clone();
+    } else {
+        extract.text = format!("{}\n\n[Text recognized in images]\n{}", extract.text.trim_end(), ocr_text);
+    }
+
+    extract.word_count = extract.text.split_whitespace().count();
+    extract.ocr_text = Some(ocr_text);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn extract(text: &str, method: ExtractionMethod) -> PageExtract {
+        PageExtract::new(text.to_string(), "https://example.com/post/".to_string(), 0.1, method)
+    }
+
+    #[test]
+    fn test_candidate_images_are_resolved_and_filtered() {
+        let mut page = extract("", ExtractionMethod::DensityHeuristic);
+        page.primary_image = Some("/hero.png".to_string());
+        page.images = vec![
+            "https://example.com/hero.png".to_string(),
+            "diagram.svg".to_string(),
+            "data:image/png;base64,AAAA".to_string(),
+            "shot.jpg".to_string(),
+        ];
+
+        let urls: Vec<String> = OcrExtractor::candidate_images(&page)
+            .iter()
+            .map(|u| u.to_string())
+            .collect();
+        assert_eq!(urls, vec![
+            "https://example.com/hero.png".to_string(),
+            "https://example.com/post/shot.jpg".to_string(),
+        ]);
+    }
+
+    #[test]
+    fn test_merge_appends_to_article_text() {
+        let mut page = extract("Short intro.", ExtractionMethod::DensityHeuristic);
+        merge_ocr_text(&mut page, &["Quarterly revenue grew".to_string()]);
+
+        assert!(page.text.starts_with("Short intro.\n\n[Text recognized in images]\n"));
+        assert_eq!(page.ocr_text.as_deref(), Some("Quarterly revenue grew"));
+        assert_eq!(page.word_count, page.text.split_whitespace().count());
+    }
+
+    #[test]
+    fn test_merge_replaces_raw_html_fallback() {
+        let mut page = extract("<html><body><img src=a.png></body></html>", ExtractionMethod::Fallback);
+        merge_ocr_text(&mut page, &["First slide".to_string(), "Second slide".to_string()]);
+
+        assert_eq!(page.text, "First slide\n\nSecond slide");
+        assert_eq!(page.word_count, 4);
+
+        let mut untouched = extract("Body", ExtractionMethod::DensityHeuristic);
+        merge_ocr_text(&mut untouched, &[]);
+        assert_eq!(untouched.text, "Body");
+        assert!(untouched.ocr_text.is_none());
+    }
+}
@@ -0,0 +1,450 @@
+// Crawl Politeness
+// robots.txt compliance and per-host rate limiting for the web fetcher
+
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use url::Url;
+
+/// Product token matched against robots.txt User-agent lines
+pub const ROBOTS_USER_AGENT: &str = "Skhoot";
+
+/// Largest robots.txt accepted (larger files are truncated, as crawlers do)
+const MAX_ROBOTS_BYTES: usize = 512 * 1024;
+
+/// Politeness settings for outbound page fetches
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PolitenessConfig {
+    /// Honor robots.txt rules (set to false to override)
+    pub respect_robots_txt: bool,
+
+    /// Sustained requests per second allowed per host
+    pub requests_per_second: f64,
+
+    /// Requests a host may receive in a burst before throttling kicks in
+    pub burst: u32,
+
+    /// Minimum delay between two requests to the same host (milliseconds)
+    pub politeness_delay_ms: u64,
+
+    /// How long fetched robots.txt rules are reused (seconds)
+    pub robots_cache_ttl_secs: u64,
+}
+
+impl Default for PolitenessConfig {
+    fn default() -> Self {
+        // Allow configuration via environment variables
+        let respect_robots_txt = std::env::var("SKHOOT_RESPECT_ROBOTS_TXT")
+            .ok()
+            .map(|v| !matches!(v.to_ascii_lowercase().as_str(), "0" | "false" | "no"))
+            .unwrap_or(true);
+
+        let requests_per_second = std::env::var("SKHOOT_FETCH_RATE_PER_SEC")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .filter(|r: &f64| *r > 0.0)
+            .unwrap_or(1.0);
+
+        let politeness_delay_ms = std::env::var("SKHOOT_FETCH_DELAY_MS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(250);
+
+        Self {
+            respect_robots_txt,
+            requests_per_second,
+            burst: 2,
+            politeness_delay_ms,
+            robots_cache_ttl_secs: 60 * 60,
+        }
+    }
+}
+
+// ============================================================================
+// RateLimiter - Per-host token bucket
+// ============================================================================
+
+#[derive(Debug)]
+struct Bucket {
+    /// Available tokens; negative values are reservations by waiting callers
+    tokens: f64,
+    last_refill: Instant,
+    /// Time the most recent request was (or will be) allowed to start
+    last_request: Option<Instant>,
+}
+
+/// Per-host token bucket rate limiter
+///
+/// Callers reserve a slot under the lock and sleep outside it, so
+/// concurrent fetches to one host are spread out instead of sent together.
+pub struct RateLimiter {
+    rate: f64,
+    burst: f64,
+    min_delay: Duration,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(requests_per_second: f64, burst: u32, min_delay: Duration) -> Self {
+        Self {
+            rate: requests_per_second.max(f64::MIN_POSITIVE),
+            burst: burst.max(1) as f64,
+            min_delay,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Reserves the next request slot for `host` and returns how long to wait
+    pub async fn reserve(&self, host: &str) -> Duration {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().await;
+        let bucket = buckets.entry(host.to_ascii_lowercase()).or_insert(Bucket {
+            tokens: self.burst,
+            last_refill: now,
+            last_request: None,
+        });
+
+        let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.rate).min(self.burst);
+        bucket.last_refill = now;
+
+        let token_wait = if bucket.tokens >= 1.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64((1.0 - bucket.tokens) / self.rate)
+        };
+        bucket.tokens -= 1.0;
+
+        let mut start = now + token_wait;
+        if let Some(last) = bucket.last_request {
+            start = start.max(last + self.min_delay);
+        }
+        bucket.last_request = Some(start);
+
+        start.saturating_duration_since(now)
+    }
+
+    /// Waits until a request to `host` is allowed, returning the time waited
+    pub async fn acquire(&self, host: &str) -> Duration {
+        let wait = self.reserve(host).await;
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+        wait
+    }
+}
+
+// ============================================================================
+// RobotsRules - Parsed robots.txt group
+// ============================================================================
+
+/// Allow/Disallow rules that apply to our user agent
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RobotsRules {
+    /// (allow, path pattern) pairs
+    rules: Vec<(bool, String)>,
+}
+
+impl RobotsRules {
+    /// Rules that allow everything (missing or unreadable robots.txt)
+    pub fn allow_all() -> Self {
+        Self::default()
+    }
+
+    /// Parses robots.txt, keeping the group for `user_agent` or else `*`
+    pub fn parse(content: &str, user_agent: &str) -> Self {
+        let agent = user_agent.to_ascii_lowercase();
+        let mut specific: Vec<(bool, String)> = Vec::new();
+        let mut wildcard: Vec<(bool, String)> = Vec::new();
+        let mut has_specific = false;
+
+        // Agents named by the current group, and whether its rules have started
+        let mut group_agents: Vec<String> = Vec::new();
+        let mut in_rules = false;
+
+        for line in content.lines() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let key = key.trim().to_ascii_lowercase();
+            let value = value.trim();
+
+            match key.as_str() {
+                "user-agent" => {
+                    if in_rules {
+                        group_agents.clear();
+                        in_rules = false;
+                    }
+                    let name = value.to_ascii_lowercase();
+                    if name.is_empty() {
+                        continue;
+                    }
+                    if name != "*" && agent.contains(&name) {
+                        has_specific = true;
+                    }
+                    group_agents.push(name);
+                }
+                "allow" | "disallow" => {
+                    in_rules = true;
+                    // An empty Disallow means allow everything
+                    if value.is_empty() {
+                        continue;
+                    }
+                    let rule = (key == "allow", value.to_string());
+                    if group_agents.iter().any(|a| a != "*" && agent.contains(a.as_str())) {
+                        specific.push(rule.clone());
+                    }
+                    if group_agents.iter().any(|a| a == "*") {
+                        wildcard.push(rule);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Self {
+            rules: if has_specific { specific } else { wildcard },
+        }
+    }
+
+    /// Whether `path` (including any query string) may be fetched
+    ///
+    /// The longest matching rule wins; Allow wins ties.
+    pub fn is_allowed(&self, path: &str) -> bool {
+        let mut best: Option<(usize, bool)> = None;
+        for (allow, pattern) in &self.rules {
+            if pattern_matches(pattern, path) {
+                let len = pattern.len();
+                let better = match best {
+                    None => true,
+                    Some((best_len, best_allow)) => len > best_len || (len == best_len && *allow && !best_allow),
+                };
+                if better {
+                    best = Some((len, *allow));
+                }
+            }
+        }
+        best.is_none_or(|(_, allow)| allow)
+    }
+}
+
+/// Matches a robots.txt path pattern supporting `*` and a trailing `$`
+fn pattern_matches(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(p) => (p, true),
+        None => (pattern, false),
+    };
+
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or("");
+    if !path.starts_with(first) {
+        return false;
+    }
+    let mut pos = first.len();
+    let rest: Vec<&str> = parts.collect();
+
+    for (i, part) in rest.iter().enumerate() {
+        let is_last = i == rest.len() - 1;
+        if is_last && anchored {
+            return path.len() >= pos + part.len() && path.ends_with(part);
+        }
+        match path[pos..].find(part) {
+            Some(idx) => pos += idx + part.len(),
+            None => return false,
+        }
+    }
+
+    !anchored || pos == path.len()
+}
+
+// ============================================================================
+// Politeness - robots.txt cache plus rate limiter
+// ============================================================================
+
+/// Why a URL was not fetched
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SkipReason {
+    /// robots.txt disallows the URL for our user agent
+    RobotsDisallowed,
+    /// The fetch or extraction failed
+    FetchFailed { error: String },
+    /// The request's time budget ran out before the page was read
+    BudgetExceeded,
+}
+
+/// A URL that search_and_gather did not gather
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkippedUrl {
+    pub url: String,
+    pub reason: SkipReason,
+}
+
+/// Shared politeness state for all fetches of a ContentExtractionSystem
+pub struct Politeness {
+    config: PolitenessConfig,
+    limiter: RateLimiter,
+    robots: Mutex<HashMap<String, (Arc<RobotsRules>, Instant)>>,
+    client: Client,
+}
+
+impl Politeness {
+    pub fn new(config: PolitenessConfig) -> Self {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(5))
+            .redirect(reqwest::redirect::Policy::limited(5))
+            .user_agent(ROBOTS_USER_AGENT)
+            .build()
+            .unwrap_or_default();
+
+        Self {
+            limiter: RateLimiter::new(
+                config.requests_per_second,
+                config.burst,
+                Duration::from_millis(config.politeness_delay_ms),
+            ),
+            config,
+            robots: Mutex::new(HashMap::new()),
+            client,
+        }
+    }
+
+    /// Checks robots.txt for `url`; always true when the override is set
+    pub async fn is_allowed(&self, url: &Url) -> bool {
+        if !self.config.respect_robots_txt {
+            return true;
+        }
+        let rules = self.robots_for(url).await;
+        let mut path = url.path().to_string();
+        if let Some(query) = url.query() {
+            path.push('?');
+            path.push_str(query);
+        }
+        rules.is_allowed(&path)
+    }
+
+    /// Waits for the host's rate limit, returning the time waited
+    pub async fn throttle(&self, url: &Url) -> Duration {
+        match url.host_str() {
+            Some(host) => self.limiter.acquire(host).await,
+            None => Duration::ZERO,
+        }
+    }
+
+    /// Cached robots.txt rules for the URL's origin, fetching them if needed
+    async fn robots_for(&self, url: &Url) -> Arc<RobotsRules> {
+        let origin = url.origin().ascii_serialization();
+        let ttl = Duration::from_secs(self.config.robots_cache_ttl_secs);
+
+        if let Some((rules, fetched_at)) = self.robots.lock().await.get(&origin) {
+            if fetched_at.elapsed() < ttl {
+                return Arc::clone(rules);
+            }
+        }
+
+        let rules = Arc::new(self.fetch_robots(&origin).await);
+        self.robots
+            .lock()
+            .await
+            .insert(origin, (Arc::clone(&rules), Instant::now()));
+        rules
+    }
+
+    /// Downloads and parses robots.txt; unreachable or missing files allow everything
+    async fn fetch_robots(&self, origin: &str) -> RobotsRules {
+        let robots_url = format!("{}/robots.txt", origin);
+        if let Some(host) = Url::parse(&robots_url).ok().as_ref().and_then(|u| u.host_str()) {
+            self.limiter.acquire(host).await;
+        }
+
+        let response = match self.client.get(&robots_url).send().await {
+            Ok(r) if r.status().is_success() => r,
+            Ok(r) => {
+                tracing::debug!("robots.txt at {} returned {}; allowing all", robots_url, r.status());
+                return RobotsRules::allow_all();
+            }
+            Err(e) => {
+                tracing::debug!("Failed to fetch {}: {}; allowing all", robots_url, e);
+                return RobotsRules::allow_all();
+            }
+        };
+
+        match response.bytes().await {
+            Ok(bytes) => {
+                let body = &bytes[..bytes.len().min(MAX_ROBOTS_BYTES)];
+                RobotsRules::parse(&String::from_utf8_lossy(body), ROBOTS_USER_AGENT)
+            }
+            Err(_) => RobotsRules::allow_all(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROBOTS: &str = "
+        # Example
+        User-agent: *
+        Disallow: /private/
+        Allow: /private/public-page
+        Disallow: /*.pdf$
+
+        User-agent: BadBot
+        Disallow: /
+    ";
+
+    #[test]
+    fn test_wildcard_group_rules() {
+        let rules = RobotsRules::parse(ROBOTS, ROBOTS_USER_AGENT);
+        assert!(rules.is_allowed("/"));
+        assert!(rules.is_allowed("/articles/1"));
+        assert!(!rules.is_allowed("/private/data"));
+        assert!(rules.is_allowed("/private/public-page"));
+        assert!(!rules.is_allowed("/files/report.pdf"));
+        assert!(rules.is_allowed("/files/report.pdf?download=1"));
+    }
+
+    #[test]
+    fn test_specific_group_replaces_wildcard() {
+        let robots = "User-agent: *\nDisallow: /\n\nUser-agent: skhoot\nUser-agent: other\nDisallow: /admin\n";
+        let rules = RobotsRules::parse(robots, ROBOTS_USER_AGENT);
+        assert!(rules.is_allowed("/blog"));
+        assert!(!rules.is_allowed("/admin/users"));
+
+        let bad = RobotsRules::parse(ROBOTS, "BadBot");
+        assert!(!bad.is_allowed("/anything"));
+        assert!(RobotsRules::parse("Disallow:", ROBOTS_USER_AGENT).is_allowed("/x"));
+    }
+
+    #[tokio::test]
+    async fn test_rate_limiter_spaces_requests_per_host() {
+        let limiter = RateLimiter::new(2.0, 2, Duration::from_millis(100));
+
+        // Burst of two, separated by the politeness delay
+        assert_eq!(limiter.reserve("example.com").await, Duration::ZERO);
+        let second = limiter.reserve("example.com").await;
+        assert!(second >= Duration::from_millis(90) && second <= Duration::from_millis(100));
+
+        // Bucket is empty: the third request waits for a refill
+        let third = limiter.reserve("example.com").await;
+        assert!(third >= Duration::from_millis(450), "waited {:?}", third);
+
+        // Other hosts are unaffected
+        assert_eq!(limiter.reserve("other.org").await, Duration::ZERO);
+    }
+
+    #[tokio::test]
+    async fn test_override_skips_robots_check() {
+        let politeness = Politeness::new(PolitenessConfig {
+            respect_robots_txt: false,
+            ..PolitenessConfig::default()
+        });
+        let url = Url::parse("https://example.invalid/private/").unwrap();
+        assert!(politeness.is_allowed(&url).await);
+    }
+}
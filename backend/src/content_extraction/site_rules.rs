@@ -0,0 +1,374 @@
+// Site Rules
+// Per-domain content selectors loaded from a user-editable rules file
+
+use scraper::{ElementRef, Html, Selector};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use std::time::SystemTime;
+
+/// Block elements whose text becomes a paragraph
+const BLOCK_SELECTOR: &str = "p, h1, h2, h3, h4, h5, h6, li, pre, blockquote, td, dt, dd";
+
+/// Extraction rule for one site
+///
+/// `content` and `remove` entries are CSS selectors, or XPath expressions
+/// starting with `/` (a subset: element steps, `*`, `[@attr]`,
+/// `[@attr='value']` and `[contains(@attr, 'value')]` predicates).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SiteRule {
+    /// Host the rule applies to, including its subdomains (`example.com`
+    /// matches `docs.example.com`)
+    pub domain: String,
+
+    /// Selectors for the main content, tried in order until one matches
+    pub content: Vec<String>,
+
+    /// Selectors for elements to drop from the content (ads, sidebars)
+    #[serde(default)]
+    pub remove: Vec<String>,
+}
+
+impl SiteRule {
+    /// Whether the rule applies to `host`
+    pub fn matches(&self, host: &str) -> bool {
+        let domain = self.domain.trim().trim_start_matches("*.").to_ascii_lowercase();
+        let host = host.to_ascii_lowercase();
+        !domain.is_empty() && (host == domain || host.ends_with(&format!(".{}", domain)))
+    }
+
+    /// Extracts the main content text, or None when no content selector matches
+    pub fn extract(&self, html: &str) -> Option<String> {
+        let mut document = Html::parse_document(html);
+
+        // Drop removed subtrees before selecting content
+        let removed: Vec<_> = self
+            .remove
+            .iter()
+            .filter_map(|s| parse_selector(s))
+            .flat_map(|selector| document.select(&selector).map(|e| e.id()).collect::<Vec<_>>())
+            .collect();
+        for id in removed {
+            if let Some(mut node) = document.tree.get_mut(id) {
+                node.detach();
+            }
+        }
+
+        for selector in self.content.iter().filter_map(|s| parse_selector(s)) {
+            let paragraphs: Vec<String> = document.select(&selector).flat_map(block_text).collect();
+            if !paragraphs.is_empty() {
+                return Some(paragraphs.join("\n\n"));
+            }
+        }
+        None
+    }
+}
+
+/// Paragraph texts of a content element, in document order
+fn block_text(container: ElementRef) -> Vec<String> {
+    let selector = Selector::parse(BLOCK_SELECTOR).expect("valid block selector");
+    let blocks: Vec<ElementRef> = container.select(&selector).collect();
+    if blocks.is_empty() {
+        let text = normalize(&container.text().collect::<Vec<_>>().join(" "));
+        return if text.is_empty() { Vec::new() } else { vec![text] };
+    }
+
+    // Skip blocks nested in another block (a <p> inside an <li>)
+    let ids: HashSet<_> = blocks.iter().map(|b| b.id()).collect();
+    blocks
+        .iter()
+        .filter(|block| {
+            !block
+                .ancestors()
+                .take_while(|a| a.id() != container.id())
+                .any(|a| ids.contains(&a.id()))
+        })
+        .map(|block| normalize(&block.text().collect::<Vec<_>>().join(" ")))
+        .filter(|text| !text.is_empty())
+        .collect()
+}
+
+fn normalize(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Parses a CSS selector or supported XPath expression
+fn parse_selector(expr: &str) -> Option<Selector> {
+    let css = if expr.trim_start().starts_with('/') {
+        match xpath_to_css(expr.trim()) {
+            Some(css) => css,
+            None => {
+                tracing::warn!("Unsupported XPath in site rule: {}", expr);
+                return None;
+            }
+        }
+    } else {
+        expr.to_string()
+    };
+
+    let parsed = Selector::parse(&css).map_err(|e| e.to_string());
+    match parsed {
+        Ok(selector) => Some(selector),
+        Err(e) => {
+            tracing::warn!("Invalid selector in site rule '{}': {}", expr, e);
+            None
+        }
+    }
+}
+
+/// Translates a simple XPath location path to a CSS selector
+pub fn xpath_to_css(xpath: &str) -> Option<String> {
+    let mut css = String::new();
+    let mut rest = xpath;
+
+    while !rest.is_empty() {
+        let combinator = if let Some(r) = rest.strip_prefix("//") {
+            rest = r;
+            " "
+        } else if let Some(r) = rest.strip_prefix('/') {
+            rest = r;
+            " > "
+        } else {
+            return None;
+        };
+
+        // Step runs to the next '/' outside a predicate
+        let mut depth = 0;
+        let mut quote = None;
+        let end = rest
+            .char_indices()
+            .find(|&(_, c)| {
+                match (quote, c) {
+                    (Some(q), c) if c == q => quote = None,
+                    (Some(_), _) => {}
+                    (None, '\'' | '"') => quote = Some(c),
+                    (None, '[') => depth += 1,
+                    (None, ']') => depth -= 1,
+                    (None, '/') if depth == 0 => return true,
+                    _ => {}
+                }
+                false
+            })
+            .map(|(i, _)| i)
+            .unwrap_or(rest.len());
+        let (step, remaining) = rest.split_at(end);
+        rest = remaining;
+
+        if !css.is_empty() {
+            css.push_str(combinator);
+        }
+        css.push_str(&xpath_step_to_css(step)?);
+    }
+
+    if css.is_empty() {
+        None
+    } else {
+        Some(css)
+    }
+}
+
+fn xpath_step_to_css(step: &str) -> Option<String> {
+    let (name, mut predicates) = match step.find('[') {
+        Some(i) => (&step[..i], &step[i..]),
+        None => (step, ""),
+    };
+    if name.is_empty() || !name.chars().all(|c| c == '*' || c.is_ascii_alphanumeric() || c == '-') {
+        return None;
+    }
+
+    let mut css = name.to_string();
+    while let Some(inner) = predicates.strip_prefix('[') {
+        let close = inner.find(']')?;
+        css.push_str(&xpath_predicate_to_css(inner[..close].trim())?);
+        predicates = &inner[close + 1..];
+    }
+    if !predicates.is_empty() {
+        return None;
+    }
+    Some(css)
+}
+
+fn xpath_predicate_to_css(predicate: &str) -> Option<String> {
+    if let Some(args) = predicate
+        .strip_prefix("contains(")
+        .and_then(|p| p.strip_suffix(')'))
+    {
+        let (attr, value) = args.split_once(',')?;
+        let attr = attr.trim().strip_prefix('@')?;
+        let value = unquote(value.trim())?;
+        return Some(format!("[{}*=\"{}\"]", attr, value));
+    }
+
+    let attr_expr = predicate.strip_prefix('@')?;
+    match attr_expr.split_once('=') {
+        Some((attr, value)) => Some(format!("[{}=\"{}\"]", attr.trim(), unquote(value.trim())?)),
+        None => Some(format!("[{}]", attr_expr.trim())),
+    }
+}
+
+fn unquote(value: &str) -> Option<&str> {
+    value
+        .strip_prefix('\'')
+        .and_then(|v| v.strip_suffix('\''))
+        .or_else(|| value.strip_prefix('"').and_then(|v| v.strip_suffix('"')))
+        .filter(|v| !v.contains('"'))
+}
+
+/// Contents of the rules file
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SiteRules {
+    #[serde(default)]
+    pub rules: Vec<SiteRule>,
+}
+
+impl SiteRules {
+    /// Most specific rule for `host` (longest matching domain)
+    pub fn for_host(&self, host: &str) -> Option<&SiteRule> {
+        self.rules
+            .iter()
+            .filter(|rule| rule.matches(host))
+            .max_by_key(|rule| rule.domain.len())
+    }
+}
+
+/// Site rules backed by `~/.skhoot/extraction_rules.json`
+///
+/// The file is re-read whenever its modification time changes, so edits
+/// apply to the next page without restarting the backend. A missing or
+/// invalid file means no rules.
+pub struct SiteRulesStore {
+    path: PathBuf,
+    cached: RwLock<(Option<SystemTime>, Arc<SiteRules>)>,
+}
+
+impl SiteRulesStore {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            cached: RwLock::new((None, Arc::new(SiteRules::default()))),
+        }
+    }
+
+    /// Store at the default location
+    pub fn default_path() -> PathBuf {
+        dirs::home_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join(".skhoot")
+            .join("extraction_rules.json")
+    }
+
+    /// Current rules, reloading the file if it changed
+    pub fn rules(&self) -> Arc<SiteRules> {
+        let modified = std::fs::metadata(&self.path).and_then(|m| m.modified()).ok();
+
+        if let Ok(cached) = self.cached.read() {
+            if cached.0 == modified {
+                return Arc::clone(&cached.1);
+            }
+        }
+
+        let rules = match modified {
+            Some(_) => match std::fs::read_to_string(&self.path)
+                .map_err(|e| e.to_string())
+                .and_then(|json| serde_json::from_str::<SiteRules>(&json).map_err(|e| e.to_string()))
+            {
+                Ok(rules) => {
+                    tracing::info!("Loaded {} site extraction rule(s) from {:?}", rules.rules.len(), self.path);
+                    rules
+                }
+                Err(e) => {
+                    tracing::warn!("Ignoring invalid site rules file {:?}: {}", self.path, e);
+                    SiteRules::default()
+                }
+            },
+            None => SiteRules::default(),
+        };
+
+        let rules = Arc::new(rules);
+        if let Ok(mut cached) = self.cached.write() {
+            *cached = (modified, Arc::clone(&rules));
+        }
+        rules
+    }
+}
+
+impl Default for SiteRulesStore {
+    fn default() -> Self {
+        Self::new(Self::default_path())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HTML: &str = r#"
+        <html><body>
+            <nav><p>Home About Contact Us Today</p></nav>
+            <div class="post-body main">
+                <h1>Rule based title</h1>
+                <p>First paragraph of the article.</p>
+                <div class="ad"><p>Buy now, limited offer!</p></div>
+                <ul><li><p>Nested item text</p></li></ul>
+            </div>
+        </body></html>
+    "#;
+
+    #[test]
+    fn test_domain_matching() {
+        let rules = SiteRules {
+            rules: vec![
+                SiteRule { domain: "example.com".to_string(), ..Default::default() },
+                SiteRule { domain: "docs.example.com".to_string(), ..Default::default() },
+            ],
+        };
+        assert_eq!(rules.for_host("docs.example.com").unwrap().domain, "docs.example.com");
+        assert_eq!(rules.for_host("www.example.com").unwrap().domain, "example.com");
+        assert!(rules.for_host("notexample.com").is_none());
+    }
+
+    #[test]
+    fn test_css_rule_with_removal() {
+        let rule = SiteRule {
+            domain: "example.com".to_string(),
+            content: vec!["article".to_string(), "div.post-body".to_string()],
+            remove: vec![".ad".to_string()],
+        };
+        let text = rule.extract(HTML).unwrap();
+        assert_eq!(text, "Rule based title\n\nFirst paragraph of the article.\n\nNested item text");
+    }
+
+    #[test]
+    fn test_xpath_rules() {
+        assert_eq!(xpath_to_css("//div[@class='post-body main']").unwrap(), "div[class=\"post-body main\"]");
+        assert_eq!(xpath_to_css("//div[contains(@class, 'post')]/h1").unwrap(), "div[class*=\"post\"] > h1");
+        assert_eq!(xpath_to_css("/html/body//*[@id]").unwrap(), "html > body *[id]");
+        assert!(xpath_to_css("//div[position()=1]").is_none());
+        assert!(xpath_to_css("//div/text()").is_none());
+
+        let rule = SiteRule {
+            domain: "example.com".to_string(),
+            content: vec!["//div[contains(@class, 'post-body')]".to_string()],
+            remove: vec!["//div[@class='ad']".to_string()],
+        };
+        assert!(!rule.extract(HTML).unwrap().contains("Buy now"));
+    }
+
+    #[test]
+    fn test_store_reloads_changed_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("rules.json");
+        let store = SiteRulesStore::new(path.clone());
+        assert!(store.rules().rules.is_empty());
+
+        std::fs::write(&path, r#"{"rules": [{"domain": "example.com", "content": ["main"]}]}"#).unwrap();
+        assert_eq!(store.rules().rules.len(), 1);
+
+        std::fs::write(&path, "not json").unwrap();
+        // Ensure a distinct modification time on coarse-grained filesystems
+        let later = SystemTime::now() + std::time::Duration::from_secs(2);
+        std::fs::File::options().write(true).open(&path).unwrap().set_modified(later).unwrap();
+        assert!(store.rules().rules.is_empty());
+    }
+}
@@ -0,0 +1,529 @@
+// Structured Data Extractor
+// Reads the articles, products, recipes and events a page declares about itself
+
+use scraper::{ElementRef, Html, Selector};
+use serde_json::{Map, Value as JsonValue};
+
+use crate::content_extraction::types::{
+    ArticleData, EventData, Offer, ProductData, Rating, RecipeData, StructuredData,
+};
+
+/// Most entities kept per page
+pub const MAX_STRUCTURED_ITEMS: usize = 20;
+
+/// Most ingredients, instructions or offers kept per entity
+const MAX_LIST_ITEMS: usize = 100;
+
+/// How deep JSON-LD and microdata are searched for entities
+const MAX_DEPTH: usize = 6;
+
+/// Extracts typed entities from a parsed page
+///
+/// Sources, in order: JSON-LD, microdata, then OpenGraph/Twitter tags. The
+/// tags only add an entity of a kind the other two didn't already declare,
+/// since they describe the same thing with fewer details.
+pub fn extract(document: &Html) -> Vec<StructuredData> {
+    let mut items = Vec::new();
+
+    if let Ok(selector) = Selector::parse("script[type='application/ld+json']") {
+        for script in document.select(&selector) {
+            if let Ok(json) = serde_json::from_str::<JsonValue>(script.inner_html().trim()) {
+                collect(&json, &mut items, 0);
+            }
+        }
+    }
+
+    if let Ok(selector) = Selector::parse("[itemscope][itemtype]:not([itemprop])") {
+        for element in document.select(&selector) {
+            collect(&microdata_item(element, 0), &mut items, 0);
+        }
+    }
+
+    for item in open_graph_items(document) {
+        if !items.iter().any(|existing| std::mem::discriminant(existing) == std::mem::discriminant(&item)) {
+            items.push(item);
+        }
+    }
+
+    items.truncate(MAX_STRUCTURED_ITEMS);
+    items
+}
+
+// ============================================================================
+// JSON-LD (microdata is converted to the same shape)
+// ============================================================================
+
+/// Finds entities in a JSON-LD value: arrays, `@graph`, and entities nested
+/// under `mainEntity` or item lists
+fn collect(value: &JsonValue, items: &mut Vec<StructuredData>, depth: usize) {
+    if depth > MAX_DEPTH || items.len() >= MAX_STRUCTURED_ITEMS {
+        return;
+    }
+    match value {
+        JsonValue::Array(values) => {
+            for value in values {
+                collect(value, items, depth + 1);
+            }
+        }
+        JsonValue::Object(obj) => {
+            if let Some(item) = to_structured(obj) {
+                items.push(item);
+            }
+            for key in ["@graph", "mainEntity", "itemListElement", "item"] {
+                if let Some(nested) = obj.get(key) {
+                    collect(nested, items, depth + 1);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn schema_types(obj: &Map<String, JsonValue>) -> Vec<String> {
+    let short = |t: &str| t.rsplit(['/', '#', ':']).next().unwrap_or(t).to_string();
+    match obj.get("@type") {
+        Some(JsonValue::String(t)) => vec![short(t)],
+        Some(JsonValue::Array(types)) => types.iter().filter_map(|t| t.as_str()).map(short).collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn to_structured(obj: &Map<String, JsonValue>) -> Option<StructuredData> {
+    let types = schema_types(obj);
+    let is = |pred: &dyn Fn(&str) -> bool| types.iter().any(|t| pred(t));
+
+    if is(&|t| matches!(t, "Product" | "ProductGroup" | "IndividualProduct" | "ProductModel")) {
+        Some(StructuredData::Product(product(obj)))
+    } else if is(&|t| t == "Recipe") {
+        Some(StructuredData::Recipe(recipe(obj)))
+    } else if is(&|t| t.ends_with("Event")) {
+        Some(StructuredData::Event(event(obj)))
+    } else if is(&|t| {
+        t.ends_with("Article") || matches!(t, "BlogPosting" | "LiveBlogPosting" | "SocialMediaPosting" | "Report")
+    }) {
+        Some(StructuredData::Article(article(obj)))
+    } else {
+        None
+    }
+}
+
+fn article(obj: &Map<String, JsonValue>) -> ArticleData {
+    ArticleData {
+        headline: field(obj, "headline").or_else(|| field(obj, "name")),
+        author: obj.get("author").and_then(name_of),
+        published: field(obj, "datePublished"),
+        modified: field(obj, "dateModified"),
+        section: field(obj, "articleSection"),
+        image: obj.get("image").and_then(url_of),
+    }
+}
+
+fn product(obj: &Map<String, JsonValue>) -> ProductData {
+    ProductData {
+        name: field(obj, "name"),
+        description: field(obj, "description"),
+        brand: obj.get("brand").and_then(name_of),
+        sku: field(obj, "sku").or_else(|| field(obj, "gtin13")).or_else(|| field(obj, "mpn")),
+        image: obj.get("image").and_then(url_of),
+        offers: obj.get("offers").map(offers).unwrap_or_default(),
+        rating: obj.get("aggregateRating").and_then(rating),
+    }
+}
+
+fn recipe(obj: &Map<String, JsonValue>) -> RecipeData {
+    let ingredients = obj.get("recipeIngredient").or_else(|| obj.get("ingredients"));
+    let mut instructions = Vec::new();
+    if let Some(value) = obj.get("recipeInstructions") {
+        collect_steps(value, &mut instructions, 0);
+    }
+    RecipeData {
+        name: field(obj, "name"),
+        author: obj.get("author").and_then(name_of),
+        description: field(obj, "description"),
+        prep_time: field(obj, "prepTime"),
+        cook_time: field(obj, "cookTime"),
+        total_time: field(obj, "totalTime"),
+        recipe_yield: field(obj, "recipeYield"),
+        ingredients: ingredients.map(strings).unwrap_or_default(),
+        instructions,
+        rating: obj.get("aggregateRating").and_then(rating),
+    }
+}
+
+fn event(obj: &Map<String, JsonValue>) -> EventData {
+    EventData {
+        name: field(obj, "name"),
+        description: field(obj, "description"),
+        start_date: field(obj, "startDate"),
+        end_date: field(obj, "endDate"),
+        location: obj.get("location").and_then(location),
+        organizer: obj.get("organizer").and_then(name_of),
+        status: field(obj, "eventStatus").map(|s| enum_name(&s)),
+        offers: obj.get("offers").map(offers).unwrap_or_default(),
+    }
+}
+
+fn offers(value: &JsonValue) -> Vec<Offer> {
+    let offer = |obj: &Map<String, JsonValue>| {
+        let spec = obj.get("priceSpecification").and_then(first).and_then(JsonValue::as_object);
+        Offer {
+            price: field(obj, "price")
+                .or_else(|| field(obj, "lowPrice"))
+                .or_else(|| spec.and_then(|spec| field(spec, "price"))),
+            high_price: field(obj, "highPrice"),
+            currency: field(obj, "priceCurrency").or_else(|| spec.and_then(|spec| field(spec, "priceCurrency"))),
+            availability: field(obj, "availability").map(|s| enum_name(&s)),
+            seller: obj.get("seller").and_then(name_of),
+            url: field(obj, "url"),
+        }
+    };
+    let mut offers: Vec<Offer> = match value {
+        JsonValue::Array(values) => values.iter().filter_map(JsonValue::as_object).map(offer).collect(),
+        JsonValue::Object(obj) => vec![offer(obj)],
+        _ => Vec::new(),
+    };
+    offers.retain(|o| o.price.is_some() || o.availability.is_some());
+    offers.truncate(MAX_LIST_ITEMS);
+    offers
+}
+
+fn rating(value: &JsonValue) -> Option<Rating> {
+    let obj = first(value)?.as_object()?;
+    let number = |key: &str| field(obj, key).and_then(|v| v.replace(',', ".").parse::<f32>().ok());
+    Some(Rating {
+        value: number("ratingValue")?,
+        best: number("bestRating"),
+        count: number("ratingCount").or_else(|| number("reviewCount")).map(|c| c as u64),
+    })
+}
+
+/// Flattens recipe instructions: text, HowToStep lists and HowToSections
+fn collect_steps(value: &JsonValue, steps: &mut Vec<String>, depth: usize) {
+    if depth > MAX_DEPTH || steps.len() >= MAX_LIST_ITEMS {
+        return;
+    }
+    match value {
+        JsonValue::String(text) => steps.extend(
+            text.lines()
+                .map(clean)
+                .filter(|line| !line.is_empty())
+                .take(MAX_LIST_ITEMS - steps.len()),
+        ),
+        JsonValue::Array(values) => {
+            for value in values {
+                collect_steps(value, steps, depth + 1);
+            }
+        }
+        JsonValue::Object(obj) => {
+            if let Some(list) = obj.get("itemListElement") {
+                collect_steps(list, steps, depth + 1);
+            } else if let Some(text) = field(obj, "text").or_else(|| field(obj, "name")) {
+                steps.push(text);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Venue name and address, or the URL of an online event
+fn location(value: &JsonValue) -> Option<String> {
+    let obj = match first(value)? {
+        JsonValue::Object(obj) => obj,
+        other => return text(other),
+    };
+    let address = match obj.get("address") {
+        Some(JsonValue::Object(address)) => {
+            let parts: Vec<String> = ["streetAddress", "addressLocality", "addressRegion", "postalCode", "addressCountry"]
+                .iter()
+                .filter_map(|key| address.get(*key).and_then(name_of))
+                .collect();
+            (!parts.is_empty()).then(|| parts.join(", "))
+        }
+        Some(other) => text(other),
+        None => None,
+    };
+    match (field(obj, "name"), address) {
+        (Some(name), Some(address)) if !address.contains(&name) => Some(format!("{}, {}", name, address)),
+        (name, address) => address.or(name).or_else(|| field(obj, "url")),
+    }
+}
+
+fn first(value: &JsonValue) -> Option<&JsonValue> {
+    match value {
+        JsonValue::Array(values) => values.first(),
+        other => Some(other),
+    }
+}
+
+fn clean(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// A scalar as text; numbers are kept as published
+fn text(value: &JsonValue) -> Option<String> {
+    match first(value)? {
+        JsonValue::String(s) => Some(clean(s)).filter(|s| !s.is_empty()),
+        JsonValue::Number(n) => Some(n.to_string()),
+        JsonValue::Object(obj) => obj.get("@value").and_then(text),
+        _ => None,
+    }
+}
+
+fn field(obj: &Map<String, JsonValue>, key: &str) -> Option<String> {
+    obj.get(key).and_then(text)
+}
+
+/// Name of a person, organization or brand, given as text or an object
+fn name_of(value: &JsonValue) -> Option<String> {
+    match first(value)? {
+        JsonValue::Object(obj) => field(obj, "name"),
+        other => text(other),
+    }
+}
+
+fn url_of(value: &JsonValue) -> Option<String> {
+    match first(value)? {
+        JsonValue::Object(obj) => field(obj, "url").or_else(|| field(obj, "contentUrl")),
+        other => text(other),
+    }
+}
+
+fn strings(value: &JsonValue) -> Vec<String> {
+    match value {
+        JsonValue::Array(values) => values.iter().filter_map(text).take(MAX_LIST_ITEMS).collect(),
+        other => text(other).into_iter().collect(),
+    }
+}
+
+/// `https://schema.org/InStock` -> `InStock`
+fn enum_name(value: &str) -> String {
+    value.rsplit('/').next().unwrap_or(value).to_string()
+}
+
+// ============================================================================
+// Microdata
+// ============================================================================
+
+/// Converts an `itemscope` element into the JSON-LD shape
+fn microdata_item(element: ElementRef, depth: usize) -> JsonValue {
+    let mut obj = Map::new();
+    if let Some(item_type) = element.value().attr("itemtype").and_then(|t| t.split_whitespace().next()) {
+        obj.insert("@type".to_string(), JsonValue::String(item_type.to_string()));
+    }
+    if depth < MAX_DEPTH {
+        microdata_properties(element, &mut obj, depth);
+    }
+    JsonValue::Object(obj)
+}
+
+/// Adds the `itemprop`s belonging to the item (not to nested items) to `obj`
+fn microdata_properties(parent: ElementRef, obj: &mut Map<String, JsonValue>, depth: usize) {
+    for child in parent.children().filter_map(ElementRef::wrap) {
+        let nested_scope = child.value().attr("itemscope").is_some();
+        if let Some(names) = child.value().attr("itemprop") {
+            let value = if nested_scope {
+                microdata_item(child, depth + 1)
+            } else {
+                JsonValue::String(microdata_value(child))
+            };
+            for name in names.split_whitespace() {
+                match obj.get_mut(name) {
+                    Some(JsonValue::Array(values)) => values.push(value.clone()),
+                    Some(existing) => *existing = JsonValue::Array(vec![existing.clone(), value.clone()]),
+                    None => {
+                        obj.insert(name.to_string(), value.clone());
+                    }
+                }
+            }
+        }
+        if !nested_scope {
+            microdata_properties(child, obj, depth);
+        }
+    }
+}
+
+fn microdata_value(element: ElementRef) -> String {
+    let el = element.value();
+    let attr = match el.name() {
+        "meta" => el.attr("content"),
+        "a" | "link" | "area" => el.attr("href"),
+        "img" | "audio" | "video" | "source" | "iframe" | "embed" => el.attr("src"),
+        "time" => el.attr("datetime"),
+        "data" | "meter" => el.attr("value"),
+        _ => el.attr("content"),
+    };
+    attr.map(str::to_string).unwrap_or_else(|| clean(&element.text().collect::<String>()))
+}
+
+// ============================================================================
+// OpenGraph / Twitter cards
+// ============================================================================
+
+fn open_graph_items(document: &Html) -> Vec<StructuredData> {
+    let Ok(selector) = Selector::parse("meta[content]") else {
+        return Vec::new();
+    };
+    let mut tags: Vec<(String, String)> = Vec::new();
+    for element in document.select(&selector) {
+        let el = element.value();
+        if let (Some(key), Some(content)) = (el.attr("property").or_else(|| el.attr("name")), el.attr("content")) {
+            let content = clean(content);
+            if !content.is_empty() {
+                tags.push((key.to_lowercase(), content));
+            }
+        }
+    }
+    let get = |key: &str| tags.iter().find(|(k, _)| k == key).map(|(_, v)| v.clone());
+    let any = |keys: &[&str]| keys.iter().find_map(|key| get(key));
+
+    let og_type = get("og:type").unwrap_or_default().to_lowercase();
+    let title = any(&["og:title", "twitter:title"]);
+    let image = any(&["og:image", "og:image:url", "twitter:image"]);
+    let mut items = Vec::new();
+
+    if og_type.starts_with("article") {
+        items.push(StructuredData::Article(ArticleData {
+            headline: title.clone(),
+            author: get("article:author"),
+            published: get("article:published_time"),
+            modified: get("article:modified_time"),
+            section: get("article:section"),
+            image: image.clone(),
+        }));
+    }
+
+    let twitter_price = (1..=4).find_map(|i| {
+        get(&format!("twitter:label{}", i))
+            .filter(|label| label.eq_ignore_ascii_case("price"))
+            .and_then(|_| get(&format!("twitter:data{}", i)))
+    });
+    let price = any(&["product:price:amount", "og:price:amount"]).or(twitter_price);
+    if og_type.starts_with("product") || og_type == "og:product" || price.is_some() {
+        let availability = any(&["product:availability", "og:availability"]);
+        let offers = if price.is_some() || availability.is_some() {
+            vec![Offer {
+                price,
+                currency: any(&["product:price:currency", "og:price:currency"]),
+                availability,
+                ..Default::default()
+            }]
+        } else {
+            Vec::new()
+        };
+        items.push(StructuredData::Product(ProductData {
+            name: title,
+            description: any(&["og:description", "twitter:description"]),
+            brand: get("product:brand"),
+            sku: get("product:retailer_item_id"),
+            image,
+            offers,
+            rating: None,
+        }));
+    }
+
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn extract_html(html: &str) -> Vec<StructuredData> {
+        extract(&Html::parse_document(html))
+    }
+
+    #[test]
+    fn test_jsonld_product_with_aggregate_offer() {
+        let items = extract_html(r#"
+            <script type="application/ld+json">
+            {"@context": "https://schema.org", "@graph": [
+                {"@type": "WebPage", "name": "Shop"},
+                {"@type": "Product", "name": "Trail Shoe", "brand": {"@type": "Brand", "name": "Acme"},
+                 "offers": {"@type": "AggregateOffer", "lowPrice": 89.5, "highPrice": "120",
+                            "priceCurrency": "EUR", "availability": "https://schema.org/InStock"},
+                 "aggregateRating": {"ratingValue": "4,6", "reviewCount": "132"}}
+            ]}
+            </script>
+            <meta property="og:type" content="product">
+            <meta property="product:price:amount" content="99">
+        "#);
+
+        // The OpenGraph product is the same one, with less detail
+        assert_eq!(items.len(), 1);
+        let StructuredData::Product(product) = &items[0] else { panic!("expected a product") };
+        assert_eq!(product.name.as_deref(), Some("Trail Shoe"));
+        assert_eq!(product.brand.as_deref(), Some("Acme"));
+        assert_eq!(product.offers[0].price.as_deref(), Some("89.5"));
+        assert_eq!(product.offers[0].high_price.as_deref(), Some("120"));
+        assert_eq!(product.offers[0].availability.as_deref(), Some("InStock"));
+        assert_eq!(product.rating, Some(Rating { value: 4.6, best: None, count: Some(132) }));
+    }
+
+    #[test]
+    fn test_jsonld_recipe_steps_and_event_location() {
+        let items = extract_html(r#"
+            <script type="application/ld+json">
+            [{"@type": "Recipe", "name": "Pancakes", "recipeYield": ["4", "4 servings"],
+              "recipeIngredient": ["2 eggs", "200 g flour"],
+              "recipeInstructions": [
+                {"@type": "HowToSection", "name": "Batter", "itemListElement": [
+                    {"@type": "HowToStep", "text": "Whisk the eggs."},
+                    {"@type": "HowToStep", "text": "Add the flour."}]},
+                {"@type": "HowToStep", "text": "Fry."}]},
+             {"@type": "MusicEvent", "name": "Concert", "startDate": "2025-06-01T20:00",
+              "eventStatus": "https://schema.org/EventScheduled",
+              "location": {"@type": "Place", "name": "Olympia",
+                           "address": {"streetAddress": "28 Bd des Capucines", "addressLocality": "Paris"}}}]
+            </script>
+        "#);
+
+        let StructuredData::Recipe(recipe) = &items[0] else { panic!("expected a recipe") };
+        assert_eq!(recipe.recipe_yield.as_deref(), Some("4"));
+        assert_eq!(recipe.ingredients, ["2 eggs", "200 g flour"]);
+        assert_eq!(recipe.instructions, ["Whisk the eggs.", "Add the flour.", "Fry."]);
+
+        let StructuredData::Event(event) = &items[1] else { panic!("expected an event") };
+        assert_eq!(event.status.as_deref(), Some("EventScheduled"));
+        assert_eq!(event.location.as_deref(), Some("Olympia, 28 Bd des Capucines, Paris"));
+    }
+
+    #[test]
+    fn test_microdata_product() {
+        let items = extract_html(r#"
+            <div itemscope itemtype="https://schema.org/Product">
+                <h1 itemprop="name">Desk   Lamp</h1>
+                <div itemprop="offers" itemscope itemtype="https://schema.org/Offer">
+                    <span itemprop="price" content="34.99">$34.99</span>
+                    <meta itemprop="priceCurrency" content="USD">
+                    <link itemprop="availability" href="https://schema.org/OutOfStock">
+                    <span itemprop="name">Not the product name</span>
+                </div>
+            </div>
+        "#);
+
+        let StructuredData::Product(product) = &items[0] else { panic!("expected a product") };
+        assert_eq!(product.name.as_deref(), Some("Desk Lamp"));
+        assert_eq!(product.offers.len(), 1);
+        assert_eq!(product.offers[0].price.as_deref(), Some("34.99"));
+        assert_eq!(product.offers[0].currency.as_deref(), Some("USD"));
+        assert_eq!(product.offers[0].availability.as_deref(), Some("OutOfStock"));
+    }
+
+    #[test]
+    fn test_open_graph_article_and_twitter_price() {
+        let items = extract_html(r#"
+            <meta property="og:type" content="article">
+            <meta property="og:title" content="Launch day">
+            <meta property="article:published_time" content="2024-03-01">
+            <meta name="twitter:label1" content="Price">
+            <meta name="twitter:data1" content="$12">
+        "#);
+
+        assert_eq!(items.len(), 2);
+        let StructuredData::Article(article) = &items[0] else { panic!("expected an article") };
+        assert_eq!(article.headline.as_deref(), Some("Launch day"));
+        assert_eq!(article.published.as_deref(), Some("2024-03-01"));
+        let StructuredData::Product(product) = &items[1] else { panic!("expected a product") };
+        assert_eq!(product.offers[0].price.as_deref(), Some("$12"));
+    }
+}
@@ -1,26 +1,37 @@
 // Content Extraction System Orchestrator
 // Orchestrates the complete extraction pipeline from URL to PageExtract
 
+use std::collections::HashSet;
+use std::sync::Arc;
 use std::time::Instant;
 use url::Url;
 
 use crate::content_extraction::{
     SsrfValidator, HttpFetcher, MetadataExtractor, MainContentExtractor,
     CacheManager, PageExtract, ContentExtractionError, TauriBridge,
-    RenderJob, RenderWait,
+    RenderJob, RenderWait, ScreenshotMode, DocumentExtractor, DocumentKind, ExtractionLimits,
 };
+use crate::cli_agent::artifacts::ToolAttachment;
+use crate::content_extraction::cache_manager::CacheStats;
+use crate::content_extraction::crawl;
+use crate::content_extraction::disk_cache::{DiskCache, DiskLookup};
+use crate::content_extraction::http_fetcher::{CacheValidators, ConditionalFetch, FetchResult};
+use crate::content_extraction::politeness::{Politeness, PolitenessConfig, SkipReason, SkippedUrl};
+use crate::content_extraction::site_rules::SiteRulesStore;
 
 /// Content Extraction System
 /// 
 /// Orchestrates the complete content extraction pipeline:
-/// 1. Cache check
+/// 1. Cache check (memory, then disk)
 /// 2. SSRF validation
-/// 3. HTTP fetch
-/// 4. Metadata extraction
-/// 5. Content extraction
-/// 6. Confidence scoring
-/// 7. Optional WebView rendering for low-confidence pages
-/// 8. Cache storage
+/// 3. robots.txt check and per-host rate limiting
+/// 4. HTTP fetch (conditional when revalidating a stale disk entry)
+/// 5. Metadata extraction
+/// 6. Content extraction (site rules first, then density heuristics)
+/// 7. Confidence scoring
+/// 8. Optional WebView rendering for low-confidence pages
+/// 9. Optional OCR of page images (`ocr` feature)
+/// 10. Cache storage (memory and disk)
 pub struct ContentExtractionSystem {
     ssrf_validator: SsrfValidator,
     http_fetcher: HttpFetcher,
@@ -28,6 +39,9 @@ pub struct ContentExtractionSystem {
     content_extractor: MainContentExtractor,
     cache_manager: CacheManager,
     tauri_bridge: Option<TauriBridge>,
+    politeness: Arc<Politeness>,
+    disk_cache: Option<DiskCache>,
+    site_rules: Arc<SiteRulesStore>,
 }
 
 impl ContentExtractionSystem {
@@ -40,6 +54,9 @@ impl ContentExtractionSystem {
             content_extractor: MainContentExtractor,
             cache_manager: CacheManager::new(),
             tauri_bridge: TauriBridge::new(None).ok(),
+            politeness: Arc::new(Politeness::new(PolitenessConfig::default())),
+            disk_cache: None,
+            site_rules: Arc::new(SiteRulesStore::default()),
         }
     }
 
@@ -58,6 +75,9 @@ impl ContentExtractionSystem {
                 std::time::Duration::from_secs(cache_ttl_secs),
             ),
             tauri_bridge: TauriBridge::new(None).ok(),
+            politeness: Arc::new(Politeness::new(PolitenessConfig::default())),
+            disk_cache: None,
+            site_rules: Arc::new(SiteRulesStore::default()),
         }
     }
     
@@ -70,9 +90,56 @@ impl ContentExtractionSystem {
             content_extractor: MainContentExtractor,
             cache_manager: CacheManager::new(),
             tauri_bridge: TauriBridge::new(Some(tauri_url)).ok(),
+            politeness: Arc::new(Politeness::new(PolitenessConfig::default())),
+            disk_cache: None,
+            site_rules: Arc::new(SiteRulesStore::default()),
         }
     }
 
+    /// Replaces the robots.txt and rate limiting settings
+    pub fn with_politeness(mut self, config: PolitenessConfig) -> Self {
+        self.politeness = Arc::new(Politeness::new(config));
+        self
+    }
+
+    /// Persists extracted pages to a disk cache behind the memory cache
+    pub fn with_disk_cache(mut self, disk_cache: DiskCache) -> Self {
+        self.disk_cache = Some(disk_cache);
+        self
+    }
+
+    /// Loads site extraction rules from a custom file instead of
+    /// `~/.skhoot/extraction_rules.json`
+    pub fn with_site_rules(mut self, store: SiteRulesStore) -> Self {
+        self.site_rules = Arc::new(store);
+        self
+    }
+
+    /// Changes the memory cache's size limit and TTL
+    pub fn set_cache_limits(&mut self, max_size_bytes: usize, ttl: std::time::Duration) {
+        self.cache_manager.set_limits(max_size_bytes, ttl);
+    }
+
+    /// Persistent cache layer, if configured
+    pub fn disk_cache(&self) -> Option<&DiskCache> {
+        self.disk_cache.as_ref()
+    }
+
+    /// In-memory cache statistics
+    pub fn memory_cache_stats(&self) -> CacheStats {
+        self.cache_manager.stats()
+    }
+
+    /// Clears the in-memory cache
+    pub fn clear_memory_cache(&mut self) {
+        self.cache_manager.clear();
+    }
+
+    /// Drops one URL from the in-memory cache
+    pub fn remove_from_memory_cache(&mut self, url: &str) -> bool {
+        self.cache_manager.remove(url)
+    }
+
     /// Browses a single URL and extracts content
     /// 
     /// This method:
@@ -102,15 +169,43 @@ impl ContentExtractionSystem {
         &mut self,
         url: &str,
         render: bool,
+    ) -> Result<PageExtract, ContentExtractionError> {
+        self.browse_with_limits(url, render, &ExtractionLimits::default()).await
+    }
+
+    /// Like `browse`, with the request's fetch and render timeouts
+    pub async fn browse_with_limits(
+        &mut self,
+        url: &str,
+        render: bool,
+        limits: &ExtractionLimits,
     ) -> Result<PageExtract, ContentExtractionError> {
         let total_start = Instant::now();
 
         // Step 1: Check cache first
-        if let Some(cached) = self.cache_manager.get(url) {
+        let cached = self.cache_manager.get(url);
+        crate::metrics::record_cache_lookup("web_memory", cached.is_some());
+        if let Some(cached) = cached {
             tracing::debug!("Cache hit for URL: {}", url);
             return Ok(cached);
         }
 
+        // Then the disk cache; stale entries are revalidated below
+        let mut stale = None;
+        if let Some(disk_cache) = &self.disk_cache {
+            let lookup = disk_cache.lookup(url).await;
+            crate::metrics::record_cache_lookup("web_disk", matches!(lookup, DiskLookup::Fresh(_)));
+            match lookup {
+                DiskLookup::Fresh(extract) => {
+                    tracing::debug!("Disk cache hit for URL: {}", url);
+                    self.cache_manager.put(url, extract.clone());
+                    return Ok(extract);
+                }
+                DiskLookup::Stale { extract, validators } => stale = Some((extract, validators)),
+                DiskLookup::Miss => {}
+            }
+        }
+
         // Step 2: Parse and validate URL
         let parsed_url = Url::parse(url).map_err(|_| ContentExtractionError::InvalidUrl {
             url: url.to_string(),
@@ -134,8 +229,26 @@ impl ContentExtractionSystem {
                 },
             })?;
 
+        // Honor robots.txt, then wait for the host's rate limit
+        if !self.politeness.is_allowed(&parsed_url).await {
+            tracing::info!("robots.txt disallows URL: {}", url);
+            return Err(ContentExtractionError::RobotsDisallowed {
+                url: url.to_string(),
+            });
+        }
+        let throttled = self.politeness.throttle(&parsed_url).await;
+        if !throttled.is_zero() {
+            tracing::debug!("Throttled {} for {}ms", url, throttled.as_millis());
+        }
+
         // Step 4: Fetch HTML with HTTP fetcher (errors propagate - no fallback)
-        let fetch_result = self.http_fetcher.fetch(&parsed_url).await.map_err(|e| {
+        let fetcher = self.http_fetcher.with_timeout(limits.fetch_timeout());
+        let fetched = match &stale {
+            Some((_, validators)) => fetcher.fetch_conditional(&parsed_url, validators).await,
+            None => fetcher.fetch(&parsed_url).await.map(ConditionalFetch::Fetched),
+        };
+        let fetched = fetched.map_err(|e| {
+            crate::metrics::inc_counter("skhoot_extractions_total", &[("outcome", "fetch_error")]);
             match &e {
                 ContentExtractionError::FetchTimeout { url, timeout_ms } => {
                     tracing::warn!("Fetch timeout for URL {} after {}ms", url, timeout_ms);
@@ -153,11 +266,41 @@ impl ContentExtractionSystem {
             e
         })?;
 
+        let fetch_result = match (fetched, stale) {
+            (ConditionalFetch::Fetched(result), _) => result,
+            (ConditionalFetch::NotModified, Some((extract, _))) => {
+                tracing::debug!("Cached copy of {} is still valid (304)", url);
+                if let Some(disk_cache) = &self.disk_cache {
+                    disk_cache.mark_revalidated(url).await;
+                }
+                self.cache_manager.put(url, extract.clone());
+                return Ok(extract);
+            }
+            (ConditionalFetch::NotModified, None) => {
+                return Err(ContentExtractionError::HttpError {
+                    url: url.to_string(),
+                    status: 304,
+                });
+            }
+        };
+        let validators = CacheValidators {
+            etag: fetch_result.etag.clone(),
+            last_modified: fetch_result.last_modified.clone(),
+        };
+
+        // PDF, plain text, JSON and feeds have dedicated extractors
+        let kind = DocumentKind::detect(fetch_result.content_type.as_deref(), &fetch_result.body);
+        if kind != DocumentKind::Html {
+            return self.extract_document(url, kind, fetch_result, total_start).await;
+        }
+
         // Step 5: Extract metadata (graceful degradation - continue on failure)
         let metadata = MetadataExtractor::extract(&fetch_result.html);
 
         // Step 6: Extract main content (graceful degradation - use raw HTML on failure)
-        let content_extraction = MainContentExtractor::extract(&fetch_result.html);
+        let site_rules = self.site_rules.rules();
+        let content_extraction =
+            MainContentExtractor::extract_with_rules(&fetch_result.html, parsed_url.host_str(), &site_rules);
         
         // If extraction produced no text, fall back to raw HTML
         let (final_text, final_confidence, final_method) = if content_extraction.text.is_empty() {
@@ -182,7 +325,7 @@ impl ContentExtractionSystem {
                 );
                 
                 // Attempt WebView rendering
-                match self.render_if_needed(url, &fetch_result.html).await {
+                match self.render_if_needed(url, &fetch_result.html, limits.render_timeout_ms).await {
                     Ok((rendered_html, render_time_ms)) => {
                         tracing::info!(
                             "WebView rendering completed in {}ms. Re-extracting content...",
@@ -191,7 +334,11 @@ impl ContentExtractionSystem {
                         
                         // Re-extract metadata and content from rendered HTML
                         let _rendered_metadata = MetadataExtractor::extract(&rendered_html);
-                        let rendered_extraction = MainContentExtractor::extract(&rendered_html);
+                        let rendered_extraction = MainContentExtractor::extract_with_rules(
+                            &rendered_html,
+                            parsed_url.host_str(),
+                            &site_rules,
+                        );
                         
                         // Check if rendered extraction is better
                         if rendered_extraction.confidence > final_confidence {
@@ -233,7 +380,8 @@ impl ContentExtractionSystem {
             };
 
         // Step 8: Build PageExtract
-        let page_extract = PageExtract {
+        #[allow(unused_mut)]
+        let mut page_extract = PageExtract {
             text: final_text,
             word_count: content_extraction.word_count,
             final_url: fetch_result.final_url.clone(),
@@ -252,17 +400,307 @@ impl ContentExtractionSystem {
             total_time_ms: total_start.elapsed().as_millis() as u64,
             status: fetch_result.status,
             content_type: fetch_result.content_type,
+            ocr_text: None,
+            crawled_pages: Vec::new(),
+            screenshot: None,
+            structured_data: metadata.structured_data,
+            alternates: Vec::new(),
         };
 
-        // Step 9: Cache the result (only if successful and not needing render)
+        // Step 9: Recognize text in images when the HTML yields little content
+        #[cfg(feature = "ocr")]
+        if page_extract.confidence < crate::content_extraction::ocr::OCR_CONFIDENCE_THRESHOLD {
+            let ocr_start = Instant::now();
+            let recognized = crate::content_extraction::OcrExtractor::enrich(&mut page_extract).await;
+            if recognized > 0 {
+                tracing::info!(
+                    "OCR recognized text in {} image(s) for URL {} in {}ms",
+                    recognized,
+                    url,
+                    ocr_start.elapsed().as_millis()
+                );
+            }
+            page_extract.total_time_ms = total_start.elapsed().as_millis() as u64;
+        }
+
+        // Step 10: Cache the result (only if successful and not needing render)
         // Don't cache low-confidence results that would benefit from rendering
         if page_extract.confidence >= 0.3 {
-            self.cache_manager.put(url, page_extract.clone());
+            self.store_in_cache(url, &page_extract, &validators).await;
         }
 
+        record_extraction_metrics(&page_extract);
         Ok(page_extract)
     }
     
+    /// Browses a URL and follows in-scope links up to `depth` levels deep
+    /// 
+    /// Links are followed breadth-first when they share the start page's
+    /// origin and directory (see `CrawlScope`), until `max_pages` pages have
+    /// been read. The pages are merged into one extract whose
+    /// `crawled_pages` lists the sources. Failures on linked pages are logged
+    /// and skipped; a failure on the start page is returned.
+    /// 
+    /// With `depth` 0 this is the same as `browse`, or `browse_in_context`
+    /// when a browsing context is given.
+    /// 
+    /// `limits` sets the page count and the timeouts. When its time budget
+    /// runs out the pages read so far are returned; running out on the start
+    /// page is an error.
+    pub async fn crawl(
+        &mut self,
+        url: &str,
+        render: bool,
+        depth: usize,
+        context: Option<&str>,
+        limits: &ExtractionLimits,
+    ) -> Result<PageExtract, ContentExtractionError> {
+        let depth = depth.min(crawl::MAX_CRAWL_DEPTH);
+        let max_pages = limits.max_pages.clamp(1, crawl::MAX_CRAWL_PAGES);
+        let deadline = tokio::time::Instant::now() + limits.budget();
+        let budget_exceeded = |url: &str| ContentExtractionError::BudgetExceeded {
+            url: url.to_string(),
+            budget_ms: limits.budget_ms,
+        };
+
+        let total_start = Instant::now();
+        let root = tokio::time::timeout_at(deadline, self.browse_page(url, render, context, limits))
+            .await
+            .map_err(|_| budget_exceeded(url))??;
+        if depth == 0 || max_pages == 1 {
+            return Ok(root);
+        }
+        let start_url = Url::parse(&root.final_url).map_err(|_| ContentExtractionError::InvalidUrl {
+            url: root.final_url.clone(),
+        })?;
+        let scope = crawl::CrawlScope::new(&start_url);
+
+        let mut visited: HashSet<String> = HashSet::new();
+        visited.insert(start_url.to_string());
+        if let Ok(requested) = Url::parse(url) {
+            visited.insert(requested.to_string());
+        }
+
+        let mut frontier = scope.filter_links(&root.links);
+        let mut pages = vec![root];
+
+        for level in 1..=depth {
+            let mut next = Vec::new();
+            for link in frontier {
+                if pages.len() >= max_pages {
+                    break;
+                }
+                if !visited.insert(link.to_string()) {
+                    continue;
+                }
+
+                let browsed = tokio::time::timeout_at(deadline, self.browse_page(link.as_str(), render, context, limits))
+                    .await
+                    .map_err(|_| budget_exceeded(link.as_str()))
+                    .and_then(|browsed| browsed);
+                match browsed {
+                    Err(e @ ContentExtractionError::BudgetExceeded { .. }) => {
+                        tracing::info!("Crawl of {} stopped: {}", url, e);
+                        break;
+                    }
+                    Ok(page) => {
+                        // Redirects can land outside the scope or on a page already read
+                        let redirected = page.final_url != link.as_str();
+                        if redirected
+                            && !(Url::parse(&page.final_url).is_ok_and(|u| scope.contains(&u))
+                                && visited.insert(page.final_url.clone()))
+                        {
+                            tracing::debug!("Crawl skipped {}: redirected to {}", link, page.final_url);
+                            continue;
+                        }
+
+                        tracing::debug!("Crawled {} (depth {}): {} words", link, level, page.word_count);
+                        if level < depth {
+                            next.extend(scope.filter_links(&page.links));
+                        }
+                        pages.push(page);
+                    }
+                    Err(e) => tracing::warn!("Crawl skipped {}: {}", link, e),
+                }
+            }
+            if pages.len() >= max_pages || next.is_empty() || tokio::time::Instant::now() >= deadline {
+                break;
+            }
+            frontier = next;
+        }
+
+        tracing::info!("Crawl of {} read {} page(s)", url, pages.len());
+        crawl::combine_pages(pages, total_start.elapsed().as_millis() as u64).ok_or_else(|| {
+            ContentExtractionError::ExtractionFailed {
+                url: url.to_string(),
+                reason: "No pages crawled".to_string(),
+            }
+        })
+    }
+
+    async fn browse_page(
+        &mut self,
+        url: &str,
+        render: bool,
+        context: Option<&str>,
+        limits: &ExtractionLimits,
+    ) -> Result<PageExtract, ContentExtractionError> {
+        match context {
+            Some(context) => self.browse_in_context(url, context, limits.render_timeout_ms).await,
+            None => self.browse_with_limits(url, render, limits).await,
+        }
+    }
+
+    /// Browses a URL inside a named browsing context
+    /// 
+    /// The page is always rendered in the WebView, which carries the
+    /// context's cookies and storage, e.g. a session the user logged in to
+    /// once. A plain HTTP fetch wouldn't be authenticated, so there is no
+    /// fallback to one. Results are not cached: they may be private to the
+    /// account and would otherwise be served to requests without the context.
+    pub async fn browse_in_context(
+        &mut self,
+        url: &str,
+        context: &str,
+        render_timeout_ms: u64,
+    ) -> Result<PageExtract, ContentExtractionError> {
+        let total_start = Instant::now();
+        let parsed_url = Url::parse(url).map_err(|_| ContentExtractionError::InvalidUrl {
+            url: url.to_string(),
+        })?;
+        SsrfValidator::validate_url(&parsed_url).await?;
+        if !self.politeness.is_allowed(&parsed_url).await {
+            return Err(ContentExtractionError::RobotsDisallowed {
+                url: url.to_string(),
+            });
+        }
+        self.politeness.throttle(&parsed_url).await;
+
+        let bridge = self.available_bridge(url).await?;
+        let job = RenderJob::with_timeout(url.to_string(), render_timeout_ms)
+            .with_wait(RenderWait::Load)
+            .with_context(context);
+        let result = bridge.render_page(job).await?;
+        tracing::info!(
+            "Rendered {} in browsing context '{}' in {}ms",
+            url,
+            context,
+            result.elapsed_ms
+        );
+
+        let metadata = MetadataExtractor::extract(&result.html);
+        let final_host = Url::parse(&result.final_url).ok().and_then(|u| u.host_str().map(str::to_string));
+        let extraction = MainContentExtractor::extract_with_rules(
+            &result.html,
+            final_host.as_deref(),
+            &self.site_rules.rules(),
+        );
+
+        let page_extract = PageExtract {
+            text: extraction.text,
+            word_count: extraction.word_count,
+            final_url: result.final_url.clone(),
+            title: metadata.title.or_else(|| Some(result.title.clone()).filter(|t| !t.is_empty())),
+            description: metadata.description,
+            author: metadata.author,
+            published_date: metadata.published_date,
+            canonical_url: metadata.canonical_url,
+            primary_image: metadata.primary_image,
+            images: metadata.images,
+            links: self.extract_links(&result.html, &result.final_url),
+            confidence: extraction.confidence,
+            extraction_method: crate::content_extraction::ExtractionMethod::BrowserRender,
+            fetch_time_ms: result.elapsed_ms,
+            extraction_time_ms: extraction.extraction_time_ms,
+            total_time_ms: total_start.elapsed().as_millis() as u64,
+            status: 200,
+            content_type: Some("text/html".to_string()),
+            ocr_text: None,
+            crawled_pages: Vec::new(),
+            screenshot: None,
+            structured_data: metadata.structured_data,
+            alternates: Vec::new(),
+        };
+        record_extraction_metrics(&page_extract);
+        Ok(page_extract)
+    }
+
+    /// Builds a PageExtract for a non-HTML document
+    /// 
+    /// Rendering and OCR don't apply to these documents; the fetcher's size
+    /// limit bounds the body and the extracted text is capped by the extractor.
+    async fn extract_document(
+        &mut self,
+        url: &str,
+        kind: DocumentKind,
+        fetch_result: FetchResult,
+        total_start: Instant,
+    ) -> Result<PageExtract, ContentExtractionError> {
+        let final_url = fetch_result.final_url.clone();
+        let validators = CacheValidators {
+            etag: fetch_result.etag.clone(),
+            last_modified: fetch_result.last_modified.clone(),
+        };
+        let body = fetch_result.body;
+        let extraction_url = final_url.clone();
+
+        // PDF parsing is CPU-bound
+        let extraction = tokio::task::spawn_blocking(move || {
+            DocumentExtractor::extract(kind, &body, &extraction_url)
+        })
+        .await
+        .map_err(|e| ContentExtractionError::ExtractionFailed {
+            url: url.to_string(),
+            reason: format!("Document extraction task failed: {}", e),
+        })?
+        .map_err(|e| {
+            tracing::warn!("Document extraction failed for URL {}: {}", url, e);
+            e
+        })?;
+
+        let content = extraction.content;
+        let page_extract = PageExtract {
+            text: content.text,
+            word_count: content.word_count,
+            final_url,
+            title: extraction.title,
+            description: None,
+            author: None,
+            published_date: None,
+            canonical_url: None,
+            primary_image: None,
+            images: Vec::new(),
+            links: extraction.links,
+            confidence: content.confidence,
+            extraction_method: content.method,
+            fetch_time_ms: fetch_result.fetch_time_ms,
+            extraction_time_ms: content.extraction_time_ms,
+            total_time_ms: total_start.elapsed().as_millis() as u64,
+            status: fetch_result.status,
+            content_type: fetch_result.content_type,
+            ocr_text: None,
+            crawled_pages: Vec::new(),
+            screenshot: None,
+            structured_data: Vec::new(),
+            alternates: Vec::new(),
+        };
+
+        if page_extract.confidence >= 0.3 {
+            self.store_in_cache(url, &page_extract, &validators).await;
+        }
+
+        record_extraction_metrics(&page_extract);
+        Ok(page_extract)
+    }
+
+    /// Stores an extract in the memory cache and, if configured, on disk
+    async fn store_in_cache(&mut self, url: &str, page_extract: &PageExtract, validators: &CacheValidators) {
+        self.cache_manager.put(url, page_extract.clone());
+        if let Some(disk_cache) = &self.disk_cache {
+            disk_cache.store(url, page_extract, validators).await;
+        }
+    }
+
     /// Extract links from HTML content
     fn extract_links(&self, html: &str, base_url: &str) -> Vec<String> {
         use scraper::{Html, Selector};
@@ -294,7 +732,12 @@ impl ContentExtractionSystem {
                 }
                 
                 // Resolve relative URLs
-                if let Ok(resolved) = base.join(href) {
+                if let Ok(mut resolved) = base.join(href) {
+                    // Only http(s) pages; fragments point into the same page
+                    if !matches!(resolved.scheme(), "http" | "https") {
+                        continue;
+                    }
+                    resolved.set_fragment(None);
                     let resolved_str = resolved.to_string();
                     
                     // Add unique links
@@ -322,6 +765,7 @@ impl ContentExtractionSystem {
     /// # Arguments
     /// * `url` - The URL to render
     /// * `original_html` - The original HTML (for fallback)
+    /// * `timeout_ms` - How long the WebView gets to render the page
     /// 
     /// # Returns
     /// * `Ok((rendered_html, render_time_ms))` - Successfully rendered HTML and time taken
@@ -330,25 +774,12 @@ impl ContentExtractionSystem {
         &self,
         url: &str,
         _original_html: &str,
+        timeout_ms: u64,
     ) -> Result<(String, u64), ContentExtractionError> {
-        // Check if Tauri bridge is available
-        let bridge = self.tauri_bridge.as_ref().ok_or_else(|| {
-            ContentExtractionError::RenderFailed {
-                url: url.to_string(),
-                reason: "Tauri bridge not available".to_string(),
-            }
-        })?;
-        
-        // Check if Tauri is reachable
-        if !bridge.is_available().await {
-            return Err(ContentExtractionError::RenderFailed {
-                url: url.to_string(),
-                reason: "Tauri frontend not reachable".to_string(),
-            });
-        }
+        let bridge = self.available_bridge(url).await?;
         
         // Create render job
-        let job = RenderJob::new(url.to_string())
+        let job = RenderJob::with_timeout(url.to_string(), timeout_ms)
             .with_wait(RenderWait::DomContentLoaded);
         
         tracing::debug!(
@@ -371,6 +802,67 @@ impl ContentExtractionSystem {
         Ok((result.html, result.elapsed_ms))
     }
 
+    /// Renders a page in the WebView and saves a PNG screenshot of it
+    /// 
+    /// The screenshot is written to the tool artifacts directory and returned
+    /// as an attachment the UI can show next to the extract.
+    pub async fn capture_screenshot(
+        &self,
+        url: &str,
+        mode: ScreenshotMode,
+        context: Option<&str>,
+    ) -> Result<ToolAttachment, ContentExtractionError> {
+        let bridge = self.available_bridge(url).await?;
+        let render_failed = |reason: String| ContentExtractionError::RenderFailed {
+            url: url.to_string(),
+            reason,
+        };
+        
+        // Wait for images and stylesheets so the page looks finished
+        let mut job = RenderJob::new(url.to_string())
+            .with_wait(RenderWait::Load)
+            .with_screenshot(mode);
+        if let Some(context) = context {
+            job = job.with_context(context);
+        }
+        let result = bridge.render_page(job).await?;
+        
+        let data = result
+            .screenshot
+            .ok_or_else(|| render_failed("Renderer returned no screenshot".to_string()))?;
+        let attachment = ToolAttachment::from_base64(&data, "image/png")
+            .map_err(|e| render_failed(format!("Failed to save screenshot: {}", e)))?;
+        
+        tracing::info!(
+            "Captured {:?} screenshot of {} in {}ms: {}",
+            mode,
+            url,
+            result.elapsed_ms,
+            attachment.path
+        );
+        Ok(attachment)
+    }
+
+    /// The Tauri bridge, if configured and reachable
+    async fn available_bridge(&self, url: &str) -> Result<&TauriBridge, ContentExtractionError> {
+        // Check if Tauri bridge is available
+        let bridge = self.tauri_bridge.as_ref().ok_or_else(|| {
+            ContentExtractionError::RenderFailed {
+                url: url.to_string(),
+                reason: "Tauri bridge not available".to_string(),
+            }
+        })?;
+        
+        // Check if Tauri is reachable
+        if !bridge.is_available().await {
+            return Err(ContentExtractionError::RenderFailed {
+                url: url.to_string(),
+                reason: "Tauri frontend not reachable".to_string(),
+            });
+        }
+        Ok(bridge)
+    }
+
     /// Searches and gathers content from top results
     /// 
     /// This method:
@@ -399,16 +891,35 @@ impl ContentExtractionSystem {
         query: &str,
         num_results: usize,
         gather_top: usize,
+    ) -> Result<crate::content_extraction::SearchGatherResponse, ContentExtractionError> {
+        self.search_and_gather_with_limits(query, num_results, gather_top, &ExtractionLimits::default())
+            .await
+    }
+
+    /// Like `search_and_gather`, within the request's timeouts and time
+    /// budget. Pages still being read when the budget runs out are skipped;
+    /// running out during the search itself is an error. `max_pages` further
+    /// caps `gather_top`.
+    pub async fn search_and_gather_with_limits(
+        &mut self,
+        query: &str,
+        num_results: usize,
+        gather_top: usize,
+        limits: &ExtractionLimits,
     ) -> Result<crate::content_extraction::SearchGatherResponse, ContentExtractionError> {
         use tokio::sync::Semaphore;
-        use std::sync::Arc;
         
-        let _total_start = Instant::now();
+        let deadline = tokio::time::Instant::now() + limits.budget();
         
         // Step 1: Call existing web_search() to get search results (now with racing!)
         let search_start = Instant::now();
         
-        let search_results = self.perform_search(query, num_results).await?;
+        let search_results = tokio::time::timeout_at(deadline, self.perform_search(query, num_results, limits))
+            .await
+            .map_err(|_| ContentExtractionError::BudgetExceeded {
+                url: format!("search:{}", query),
+                budget_ms: limits.budget_ms,
+            })??;
         
         let search_time_ms = search_start.elapsed().as_millis() as u64;
         
@@ -419,13 +930,14 @@ impl ContentExtractionSystem {
             search_time_ms
         );
         
-        // Step 2: Extract top N URLs (max 5)
-        let gather_limit = gather_top.min(5);
-        let urls_to_gather: Vec<String> = search_results
-            .iter()
-            .take(gather_limit)
-            .map(|result| result.url.clone())
-            .collect();
+        // Step 2: Extract top N distinct URLs (max 5); results that only
+        // differ by tracking parameters, AMP markers or www/m. mirrors become
+        // alternates of the first one
+        let gather_limit = gather_top.min(5).min(limits.max_pages);
+        let urls_to_gather = super::dedupe::distinct_urls(
+            search_results.iter().map(|result| result.url.as_str()),
+            gather_limit,
+        );
         
         tracing::info!(
             "📥 Gathering content from {} URLs concurrently (max: 5)",
@@ -438,32 +950,58 @@ impl ContentExtractionSystem {
         
         let mut tasks = Vec::new();
         
-        for url in urls_to_gather {
+        for (url, alternates) in urls_to_gather {
             let semaphore = Arc::clone(&semaphore);
+            let politeness = Arc::clone(&self.politeness);
+            let disk_cache = self.disk_cache.clone();
+            let site_rules = Arc::clone(&self.site_rules);
             let url_clone = url.clone();
+            let limits = *limits;
             
             // Spawn a task for each URL on the tokio runtime (uses all cores)
             let task = tokio::spawn(async move {
                 // Acquire semaphore permit
-                let _permit = semaphore.acquire().await.unwrap();
+                let Ok(_permit) = tokio::time::timeout_at(deadline, semaphore.acquire()).await else {
+                    tracing::info!("⏱️ Skipped {} (time budget ran out)", url_clone);
+                    return Err(SkippedUrl { url: url_clone, reason: SkipReason::BudgetExceeded });
+                };
                 
                 tracing::debug!("📄 Gathering content from: {}", url_clone);
                 
                 // Create a new ContentExtractionSystem for this task
-                // (since we can't share &mut across tasks)
+                // (since we can't share &mut across tasks), sharing the
+                // robots.txt cache, rate limiter and disk cache
                 let mut system = ContentExtractionSystem::new();
+                system.politeness = politeness;
+                system.disk_cache = disk_cache;
+                system.site_rules = site_rules;
                 
                 // Browse the URL (with render ENABLED for quality)
                 // We use parallel execution to maintain speed
-                match system.browse(&url_clone, true).await {
-                    Ok(page_extract) => {
+                let browsed = tokio::time::timeout_at(deadline, system.browse_with_limits(&url_clone, true, &limits))
+                    .await
+                    .unwrap_or(Err(ContentExtractionError::BudgetExceeded {
+                        url: url_clone.clone(),
+                        budget_ms: limits.budget_ms,
+                    }));
+                match browsed {
+                    Ok(mut page_extract) => {
                         tracing::info!(
                             "✅ Gathered from {}: {} words, confidence: {:.2} (via WebView)",
                             url_clone,
                             page_extract.word_count,
                             page_extract.confidence
                         );
-                        Some(page_extract)
+                        page_extract.alternates = alternates;
+                        Ok(page_extract)
+                    }
+                    Err(ContentExtractionError::RobotsDisallowed { .. }) => {
+                        tracing::info!("🤖 Skipped {} (disallowed by robots.txt)", url_clone);
+                        Err(SkippedUrl { url: url_clone, reason: SkipReason::RobotsDisallowed })
+                    }
+                    Err(ContentExtractionError::BudgetExceeded { .. }) => {
+                        tracing::info!("⏱️ Skipped {} (time budget ran out)", url_clone);
+                        Err(SkippedUrl { url: url_clone, reason: SkipReason::BudgetExceeded })
                     }
                     Err(e) => {
                         // Step 4: Implement gathering resilience
@@ -473,7 +1011,7 @@ impl ContentExtractionSystem {
                             url_clone,
                             e
                         );
-                        None
+                        Err(SkippedUrl { url: url_clone, reason: SkipReason::FetchFailed { error: e.to_string() } })
                     }
                 }
             });
@@ -485,14 +1023,16 @@ impl ContentExtractionSystem {
         let results = futures::future::join_all(tasks).await;
         
         let mut gathered_pages = Vec::new();
+        let mut skipped_urls = Vec::new();
         
         for result in results {
             match result {
-                Ok(Some(page_extract)) => {
+                Ok(Ok(page_extract)) => {
                     gathered_pages.push(page_extract);
                 }
-                Ok(None) => {
-                    // URL failed, already logged
+                Ok(Err(skipped)) => {
+                    // Already logged
+                    skipped_urls.push(skipped);
                 }
                 Err(e) => {
                     tracing::error!("Task join error: {}", e);
@@ -500,12 +1040,18 @@ impl ContentExtractionSystem {
             }
         }
         
+        // Fold near-duplicate content (mirrors, AMP copies, pages naming the
+        // same canonical URL) into the highest-ranked copy
+        let gathered_count = gathered_pages.len();
+        let gathered_pages = super::dedupe::dedupe_pages(gathered_pages);
+        
         let gather_time_ms = gather_start.elapsed().as_millis() as u64;
         
         tracing::info!(
-            "Gathering completed: {}/{} URLs successful in {}ms",
-            gathered_pages.len(),
+            "Gathering completed: {}/{} URLs successful ({} distinct) in {}ms",
+            gathered_count,
             gather_limit,
+            gathered_pages.len(),
             gather_time_ms
         );
         
@@ -516,6 +1062,7 @@ impl ContentExtractionSystem {
             gathered_pages,
             total_search_time_ms: search_time_ms,
             total_gather_time_ms: gather_time_ms,
+            skipped_urls,
         })
     }
     
@@ -525,6 +1072,7 @@ impl ContentExtractionSystem {
         &self,
         query: &str,
         num_results: usize,
+        limits: &ExtractionLimits,
     ) -> Result<Vec<crate::content_extraction::WebSearchResult>, ContentExtractionError> {
         // Check if WebView is available
         let webview_available = if let Some(ref tauri_bridge) = self.tauri_bridge {
@@ -538,20 +1086,20 @@ impl ContentExtractionSystem {
             
             // Use WebView directly - it's more reliable than HTTP
             // But if it fails (e.g. 0ms render issue), fallback to HTTP
-            match self.perform_webview_search(query, num_results).await {
+            match self.perform_webview_search(query, num_results, limits.render_timeout_ms).await {
                 Ok(results) => Ok(results),
                 Err(e) => {
                     tracing::warn!(
                         "⚠️ WebView search failed: {}. Falling back to HTTP search.", 
                         e
                     );
-                    self.perform_http_search(query, num_results).await
+                    self.perform_http_search(query, num_results, limits.fetch_timeout()).await
                 }
             }
         } else {
             // WebView not available, fall back to HTTP
             tracing::warn!("⚠️ WebView not available, falling back to HTTP search");
-            self.perform_http_search(query, num_results).await
+            self.perform_http_search(query, num_results, limits.fetch_timeout()).await
         }
     }
     
@@ -560,6 +1108,7 @@ impl ContentExtractionSystem {
         &self,
         query: &str,
         num_results: usize,
+        timeout: std::time::Duration,
     ) -> Result<Vec<crate::content_extraction::WebSearchResult>, ContentExtractionError> {
         use std::collections::HashMap;
         
@@ -569,10 +1118,10 @@ impl ContentExtractionSystem {
         form_data.insert("b", ""); // Start index
         form_data.insert("kl", "wt-wt"); // Region: worldwide
         
-        // Create HTTP client with proper headers and longer timeout
+        // Create HTTP client with proper headers and the request's timeout
         let client = reqwest::Client::builder()
-            .timeout(std::time::Duration::from_secs(30))
-            .connect_timeout(std::time::Duration::from_secs(10))
+            .timeout(timeout)
+            .connect_timeout(timeout.min(std::time::Duration::from_secs(10)))
             .build()
             .map_err(|e| ContentExtractionError::ExtractionFailed {
                 url: format!("search:{}", query),
@@ -652,6 +1201,7 @@ impl ContentExtractionSystem {
         &self,
         query: &str,
         num_results: usize,
+        timeout_ms: u64,
     ) -> Result<Vec<crate::content_extraction::WebSearchResult>, ContentExtractionError> {
         let tauri_bridge = self.tauri_bridge.as_ref().ok_or_else(|| {
             ContentExtractionError::RenderFailed {
@@ -672,8 +1222,10 @@ impl ContentExtractionSystem {
         let job = RenderJob {
             job_id: format!("search_{}", uuid::Uuid::new_v4()),
             url: search_url.clone(),
-            timeout_ms: 30000, // 30 second timeout
+            timeout_ms,
             wait: RenderWait::Load, // Wait for page load (lite version loads fast)
+            screenshot: None,
+            context: None,
         };
         
         // Render the search page
@@ -872,6 +1424,17 @@ impl ContentExtractionSystem {
     }
 }
 
+/// Counts a successful extraction and its latency by extraction method
+fn record_extraction_metrics(page_extract: &PageExtract) {
+    let method = page_extract.extraction_method.to_string();
+    crate::metrics::inc_counter("skhoot_extractions_total", &[("outcome", "ok")]);
+    crate::metrics::observe_duration(
+        "skhoot_extraction_duration_seconds",
+        &[("method", &method)],
+        std::time::Duration::from_millis(page_extract.total_time_ms),
+    );
+}
+
 impl Default for ContentExtractionSystem {
     fn default() -> Self {
         Self::new()
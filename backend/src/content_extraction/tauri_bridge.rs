@@ -8,6 +8,9 @@
 
 use crate::content_extraction::{RenderJob, RenderResult, ContentExtractionError};
 
+/// Time the frontend gets past a render job's timeout to report back
+const RENDER_RESPONSE_GRACE_MS: u64 = 5_000;
+
 /// Tauri Command Bridge
 /// 
 /// Provides methods to call Tauri commands from the backend via HTTP.
@@ -19,6 +22,9 @@ pub struct TauriBridge {
     
     /// HTTP client for making requests
     client: reqwest::Client,
+
+    /// Launch token required by the Tauri HTTP bridge
+    auth: crate::auth::AuthToken,
 }
 
 impl TauriBridge {
@@ -28,7 +34,7 @@ impl TauriBridge {
     /// * `tauri_url` - Base URL where Tauri frontend is running (default: "http://localhost:1420")
     pub fn new(tauri_url: Option<String>) -> Result<Self, ContentExtractionError> {
         let client = reqwest::Client::builder()
-            .timeout(std::time::Duration::from_secs(60)) // Long timeout for rendering
+            .timeout(std::time::Duration::from_secs(60)) // Long timeout for rendering; render_page sets its own
             .build()
             .map_err(|e| ContentExtractionError::RenderFailed {
                 url: "tauri_bridge".to_string(),
@@ -38,6 +44,7 @@ impl TauriBridge {
         Ok(Self {
             tauri_url: tauri_url.unwrap_or_else(|| "http://localhost:1420".to_string()),
             client,
+            auth: crate::auth::AuthToken::from_env(),
         })
     }
     
@@ -64,14 +71,27 @@ impl TauriBridge {
             job.url
         );
         
-        // Send POST request to Tauri frontend
-        let response = self.client
+        // Send POST request to Tauri frontend, giving up shortly after the
+        // job's own timeout
+        let mut request = self
+            .client
             .post(&url)
-            .json(&job)
+            .timeout(std::time::Duration::from_millis(job.timeout_ms + RENDER_RESPONSE_GRACE_MS))
+            .json(&job);
+        if let Some(token) = self.auth.value() {
+            request = request.bearer_auth(token);
+        }
+        let response = request
             .send()
             .await
             .map_err(|e| {
                 tracing::error!("Failed to send render request to Tauri: {}", e);
+                if e.is_timeout() {
+                    return ContentExtractionError::RenderTimeout {
+                        url: job.url.clone(),
+                        timeout_ms: job.timeout_ms,
+                    };
+                }
                 ContentExtractionError::RenderFailed {
                     url: job.url.clone(),
                     reason: format!("Failed to communicate with Tauri frontend: {}", e),
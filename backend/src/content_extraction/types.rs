@@ -3,6 +3,8 @@
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
+use crate::cli_agent::artifacts::ToolAttachment;
+
 // ============================================================================
 // PageExtract - Complete structured output from content extraction
 // ============================================================================
@@ -80,6 +82,46 @@ pub struct PageExtract {
     /// Content-Type header value
     #[serde(skip_serializing_if = "Option::is_none")]
     pub content_type: Option<String>,
+    
+    // OCR
+    /// Text recognized in the page images (set when the OCR stage ran)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ocr_text: Option<String>,
+    
+    // Crawl
+    /// Pages merged into this extract when browsing with a crawl depth
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub crawled_pages: Vec<CrawledPage>,
+    
+    // Screenshot
+    /// PNG of the rendered page, when browse was asked for one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub screenshot: Option<ToolAttachment>,
+    
+    // Structured data
+    /// Articles, products, recipes and events the page declares
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub structured_data: Vec<StructuredData>,
+    
+    // Deduplication
+    /// Other URLs serving the same page (AMP copies, mirrors, tracking
+    /// links), folded into this one when gathering search results
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub alternates: Vec<String>,
+}
+
+/// A page included in a multi-page (crawled) extract
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrawledPage {
+    /// Final URL of the page
+    pub url: String,
+    
+    /// Page title
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    
+    /// Word count of the page text
+    pub word_count: usize,
 }
 
 impl PageExtract {
@@ -111,6 +153,11 @@ impl PageExtract {
             total_time_ms: 0,
             status: 200,
             content_type: None,
+            ocr_text: None,
+            crawled_pages: Vec::new(),
+            screenshot: None,
+            structured_data: Vec::new(),
+            alternates: Vec::new(),
         }
     }
 }
@@ -134,6 +181,21 @@ pub enum ExtractionMethod {
     
     /// Fallback method (raw HTML when extraction fails)
     Fallback,
+    
+    /// Text layer of a PDF document
+    Pdf,
+    
+    /// Plain text response (text/plain, markdown, CSV)
+    PlainText,
+    
+    /// Pretty-printed JSON response
+    Json,
+    
+    /// RSS or Atom feed entries
+    Feed,
+    
+    /// User-defined selectors for the page's site
+    SiteRule,
 }
 
 impl fmt::Display for ExtractionMethod {
@@ -143,6 +205,11 @@ impl fmt::Display for ExtractionMethod {
             ExtractionMethod::ReadabilityAlgorithm => write!(f, "readability_algorithm"),
             ExtractionMethod::BrowserRender => write!(f, "browser_render"),
             ExtractionMethod::Fallback => write!(f, "fallback"),
+            ExtractionMethod::Pdf => write!(f, "pdf"),
+            ExtractionMethod::PlainText => write!(f, "plain_text"),
+            ExtractionMethod::Json => write!(f, "json"),
+            ExtractionMethod::Feed => write!(f, "feed"),
+            ExtractionMethod::SiteRule => write!(f, "site_rule"),
         }
     }
 }
@@ -180,6 +247,140 @@ pub struct Metadata {
     
     /// All images found
     pub images: Vec<String>,
+    
+    /// Entities the page describes about itself
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub structured_data: Vec<StructuredData>,
+}
+
+// ============================================================================
+// StructuredData - Typed entities from JSON-LD, OpenGraph and microdata
+// ============================================================================
+
+/// An entity a page declares in JSON-LD, OpenGraph/Twitter tags or microdata
+/// 
+/// Lets agents read facts like a product's price or a recipe's ingredients
+/// as data instead of guessing them from the page text.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StructuredData {
+    Article(ArticleData),
+    Product(ProductData),
+    Recipe(RecipeData),
+    Event(EventData),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ArticleData {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub headline: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub published: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub section: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProductData {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub brand: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sku: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub offers: Vec<Offer>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rating: Option<Rating>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RecipeData {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// ISO 8601 durations, as published (e.g. `PT20M`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prep_time: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cook_time: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_time: Option<String>,
+    /// Servings or amount made
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recipe_yield: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ingredients: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub instructions: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rating: Option<Rating>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EventData {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_date: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_date: Option<String>,
+    /// Venue name and address, or the URL of an online event
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub organizer: Option<String>,
+    /// e.g. `EventScheduled`, `EventCancelled`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub offers: Vec<Offer>,
+}
+
+/// A price something is sold at
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Offer {
+    /// Price as published; the low end of a price range
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub price: Option<String>,
+    /// High end of a price range
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub high_price: Option<String>,
+    /// ISO 4217 code
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub currency: Option<String>,
+    /// e.g. `InStock`, `OutOfStock`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub availability: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seller: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+}
+
+/// Aggregate review rating
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Rating {
+    pub value: f32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub best: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub count: Option<u64>,
 }
 
 // ============================================================================
@@ -203,6 +404,15 @@ pub struct RenderJob {
     
     /// Wait condition before extracting content
     pub wait: RenderWait,
+    
+    /// Screenshot to take once the page is ready
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub screenshot: Option<ScreenshotMode>,
+    
+    /// Named browsing context whose cookies and storage the page sees;
+    /// the default WebView storage when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<String>,
 }
 
 impl RenderJob {
@@ -213,6 +423,8 @@ impl RenderJob {
             url,
             timeout_ms: 30000, // 30 seconds default
             wait: RenderWait::DomContentLoaded,
+            screenshot: None,
+            context: None,
         }
     }
     
@@ -223,6 +435,8 @@ impl RenderJob {
             url,
             timeout_ms,
             wait: RenderWait::DomContentLoaded,
+            screenshot: None,
+            context: None,
         }
     }
     
@@ -231,6 +445,46 @@ impl RenderJob {
         self.wait = wait;
         self
     }
+    
+    /// Also takes a screenshot of the page
+    pub fn with_screenshot(mut self, mode: ScreenshotMode) -> Self {
+        self.screenshot = Some(mode);
+        self
+    }
+    
+    /// Renders in a named browsing context, e.g. one the user logged in with
+    pub fn with_context(mut self, context: impl Into<String>) -> Self {
+        self.context = Some(context.into());
+        self
+    }
+}
+
+/// Longest browsing context name
+pub const MAX_CONTEXT_NAME_LEN: usize = 64;
+
+/// Whether `name` can name a browsing context
+/// 
+/// Context names become directory names on the Tauri side, so only ASCII
+/// letters, digits, `-` and `_` are allowed.
+pub fn is_valid_context_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= MAX_CONTEXT_NAME_LEN
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+// ============================================================================
+// ScreenshotMode - Part of a rendered page to capture
+// ============================================================================
+
+/// Part of a rendered page to capture as a screenshot
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScreenshotMode {
+    /// What fits in the render window
+    Viewport,
+    
+    /// The whole document, scrolled area included
+    FullPage,
 }
 
 // ============================================================================
@@ -290,6 +544,92 @@ pub struct RenderResult {
     
     /// Time elapsed for rendering (milliseconds)
     pub elapsed_ms: u64,
+    
+    /// Base64-encoded PNG, when the job asked for a screenshot
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub screenshot: Option<String>,
+}
+
+// ============================================================================
+// ExtractionLimits - Per-request timeouts and budget
+// ============================================================================
+
+/// Default fetch and render timeouts
+pub const DEFAULT_FETCH_TIMEOUT_MS: u64 = 15_000;
+pub const DEFAULT_RENDER_TIMEOUT_MS: u64 = 30_000;
+
+/// Default time for a whole browse or search-and-gather request
+pub const DEFAULT_BUDGET_MS: u64 = 120_000;
+
+/// Shortest timeout a request may ask for
+pub const MIN_TIMEOUT_MS: u64 = 1_000;
+
+/// Longest a request may hold the extraction system
+pub const MAX_FETCH_TIMEOUT_MS: u64 = 60_000;
+pub const MAX_RENDER_TIMEOUT_MS: u64 = 120_000;
+pub const MAX_BUDGET_MS: u64 = 300_000;
+
+/// Timeouts, time budget and page limit for one browse or search-and-gather
+/// request
+/// 
+/// Requests choose their own limits within caps that keep one caller from
+/// tying up the backend; see [`ExtractionLimits::from_request`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExtractionLimits {
+    /// Timeout of each HTTP fetch
+    pub fetch_timeout_ms: u64,
+    
+    /// Timeout of each WebView render
+    pub render_timeout_ms: u64,
+    
+    /// Time for the whole request; pages not read by then are left out
+    pub budget_ms: u64,
+    
+    /// Pages read when crawling or gathering
+    pub max_pages: usize,
+}
+
+impl Default for ExtractionLimits {
+    fn default() -> Self {
+        Self {
+            fetch_timeout_ms: DEFAULT_FETCH_TIMEOUT_MS,
+            render_timeout_ms: DEFAULT_RENDER_TIMEOUT_MS,
+            budget_ms: DEFAULT_BUDGET_MS,
+            max_pages: crate::content_extraction::crawl::DEFAULT_CRAWL_PAGES,
+        }
+    }
+}
+
+impl ExtractionLimits {
+    /// Limits asked for by an API request, defaulted and clamped to the caps
+    pub fn from_request(
+        fetch_timeout_ms: Option<u64>,
+        render_timeout_ms: Option<u64>,
+        budget_ms: Option<u64>,
+        max_pages: Option<usize>,
+    ) -> Self {
+        let defaults = Self::default();
+        Self {
+            fetch_timeout_ms: fetch_timeout_ms
+                .unwrap_or(defaults.fetch_timeout_ms)
+                .clamp(MIN_TIMEOUT_MS, MAX_FETCH_TIMEOUT_MS),
+            render_timeout_ms: render_timeout_ms
+                .unwrap_or(defaults.render_timeout_ms)
+                .clamp(MIN_TIMEOUT_MS, MAX_RENDER_TIMEOUT_MS),
+            budget_ms: budget_ms.unwrap_or(defaults.budget_ms).clamp(MIN_TIMEOUT_MS, MAX_BUDGET_MS),
+            max_pages: max_pages
+                .unwrap_or(defaults.max_pages)
+                .clamp(1, crate::content_extraction::crawl::MAX_CRAWL_PAGES),
+        }
+    }
+    
+    pub fn fetch_timeout(&self) -> std::time::Duration {
+        std::time::Duration::from_millis(self.fetch_timeout_ms)
+    }
+    
+    pub fn budget(&self) -> std::time::Duration {
+        std::time::Duration::from_millis(self.budget_ms)
+    }
 }
 
 // ============================================================================
@@ -361,6 +701,20 @@ pub enum ContentExtractionError {
         /// Invalid URL string
         url: String,
     },
+    
+    /// robots.txt disallows fetching the URL
+    RobotsDisallowed {
+        /// Disallowed URL
+        url: String,
+    },
+    
+    /// The request's time budget ran out
+    BudgetExceeded {
+        /// URL being read when the budget ran out
+        url: String,
+        /// Budget in milliseconds
+        budget_ms: u64,
+    },
 }
 
 impl fmt::Display for ContentExtractionError {
@@ -390,6 +744,12 @@ impl fmt::Display for ContentExtractionError {
             ContentExtractionError::InvalidUrl { url } => {
                 write!(f, "Invalid URL: '{}'", url)
             }
+            ContentExtractionError::RobotsDisallowed { url } => {
+                write!(f, "robots.txt disallows URL '{}'", url)
+            }
+            ContentExtractionError::BudgetExceeded { url, budget_ms } => {
+                write!(f, "Time budget of {}ms ran out while reading URL '{}'", budget_ms, url)
+            }
         }
     }
 }
@@ -441,6 +801,10 @@ pub struct SearchGatherResponse {
     
     /// Time taken for gathering content (milliseconds)
     pub total_gather_time_ms: u64,
+    
+    /// Top results that were not gathered (robots.txt or fetch failure)
+    #[serde(default)]
+    pub skipped_urls: Vec<crate::content_extraction::politeness::SkippedUrl>,
 }
 
 #[cfg(test)]
@@ -483,6 +847,43 @@ mod tests {
         assert!(matches!(job.wait, RenderWait::Selector { .. }));
     }
 
+    #[test]
+    fn test_render_job_screenshot_serialization() {
+        let job = RenderJob::new("https://example.com".to_string());
+        assert!(serde_json::to_value(&job).unwrap()["screenshot"].is_null());
+
+        let job = job.with_screenshot(ScreenshotMode::FullPage);
+        assert_eq!(serde_json::to_value(&job).unwrap()["screenshot"], "full_page");
+    }
+
+    #[test]
+    fn test_context_names() {
+        assert!(is_valid_context_name("work-github_2"));
+        assert!(!is_valid_context_name(""));
+        assert!(!is_valid_context_name("../cookies"));
+        assert!(!is_valid_context_name("my account"));
+        assert!(!is_valid_context_name(&"a".repeat(MAX_CONTEXT_NAME_LEN + 1)));
+
+        let job = RenderJob::new("https://example.com".to_string()).with_context("work");
+        assert_eq!(serde_json::to_value(&job).unwrap()["context"], "work");
+    }
+
+    #[test]
+    fn test_extraction_limits_from_request() {
+        assert_eq!(ExtractionLimits::from_request(None, None, None, None), ExtractionLimits::default());
+
+        let limits = ExtractionLimits::from_request(Some(5_000), Some(10), Some(u64::MAX), Some(0));
+        assert_eq!(limits.fetch_timeout_ms, 5_000);
+        assert_eq!(limits.render_timeout_ms, MIN_TIMEOUT_MS);
+        assert_eq!(limits.budget_ms, MAX_BUDGET_MS);
+        assert_eq!(limits.max_pages, 1);
+
+        let limits = ExtractionLimits::from_request(Some(600_000), Some(600_000), None, Some(1_000));
+        assert_eq!(limits.fetch_timeout_ms, MAX_FETCH_TIMEOUT_MS);
+        assert_eq!(limits.render_timeout_ms, MAX_RENDER_TIMEOUT_MS);
+        assert_eq!(limits.max_pages, crate::content_extraction::crawl::MAX_CRAWL_PAGES);
+    }
+
     #[test]
     fn test_extraction_method_display() {
         assert_eq!(ExtractionMethod::DensityHeuristic.to_string(), "density_heuristic");
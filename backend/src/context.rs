@@ -0,0 +1,106 @@
+//! Conversation contexts
+//!
+//! Every Skhoot window identifies itself with a UUID sent in the
+//! `X-Skhoot-Context` header. Agent sessions, terminal sessions and workflow
+//! executions remember the context that created them, so two windows don't see
+//! (or write into) each other's sessions and histories. Requests without the
+//! header belong to the shared `default` context.
+
+use axum::{
+    extract::FromRequestParts,
+    http::{request::Parts, StatusCode},
+};
+use serde::{Deserialize, Serialize};
+
+/// Header carrying the window's context ID
+pub const CONTEXT_HEADER: &str = "x-skhoot-context";
+
+const MAX_CONTEXT_ID_LEN: usize = 64;
+
+/// Identifies the window or conversation a request comes from
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ContextId(String);
+
+impl ContextId {
+    pub const DEFAULT: &'static str = "default";
+
+    /// Context with the given ID; `None` if the ID is empty, too long or
+    /// contains characters other than ASCII letters, digits, `-` and `_`
+    pub fn parse(id: &str) -> Option<Self> {
+        let valid = !id.is_empty()
+            && id.len() <= MAX_CONTEXT_ID_LEN
+            && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        valid.then(|| Self(id.to_string()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn is_default(&self) -> bool {
+        self.0 == Self::DEFAULT
+    }
+
+    /// Whether something created in `owner` is visible from this context.
+    /// The default context sees everything, so single-window setups and
+    /// clients that don't send the header keep working.
+    pub fn can_access(&self, owner: &ContextId) -> bool {
+        self.is_default() || self == owner
+    }
+}
+
+impl Default for ContextId {
+    fn default() -> Self {
+        Self(Self::DEFAULT.to_string())
+    }
+}
+
+impl std::fmt::Display for ContextId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[axum::async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for ContextId {
+    type Rejection = (StatusCode, String);
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let Some(value) = parts.headers.get(CONTEXT_HEADER) else {
+            return Ok(Self::default());
+        };
+        value
+            .to_str()
+            .ok()
+            .and_then(Self::parse)
+            .ok_or_else(|| (StatusCode::BAD_REQUEST, format!("Invalid {} header", CONTEXT_HEADER)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            ContextId::parse("3f2b8c1e-7a4d-4e1b-9c2f-0d5e6a7b8c9d").unwrap().as_str(),
+            "3f2b8c1e-7a4d-4e1b-9c2f-0d5e6a7b8c9d"
+        );
+        assert!(ContextId::parse("").is_none());
+        assert!(ContextId::parse("../etc").is_none());
+        assert!(ContextId::parse(&"a".repeat(65)).is_none());
+        assert!(ContextId::default().is_default());
+    }
+
+    #[test]
+    fn test_can_access() {
+        let a = ContextId::parse("window-a").unwrap();
+        let b = ContextId::parse("window-b").unwrap();
+        assert!(a.can_access(&a));
+        assert!(!a.can_access(&b));
+        assert!(!a.can_access(&ContextId::default()));
+        assert!(ContextId::default().can_access(&b));
+    }
+}
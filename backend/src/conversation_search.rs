@@ -0,0 +1,494 @@
+// Conversation Search
+// Full-text index over agent conversation messages, so users can find what
+// was said or run in an earlier session
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use std::sync::Arc;
+
+use crate::db::{ConversationFilter, ConversationMessageRecord, ConversationSearchHit, ConversationSessionRecord, Database};
+use crate::error::AppError;
+use crate::vault::{ConversationVault, VaultError};
+
+/// Markers wrapped around matches in snippets
+pub const HIGHLIGHT_START: &str = "<mark>";
+pub const HIGHLIGHT_END: &str = "</mark>";
+
+/// Largest page of results
+const MAX_LIMIT: usize = 100;
+
+/// A tool call made in a message
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessageToolCall {
+    pub name: String,
+    #[serde(default)]
+    pub arguments: serde_json::Value,
+}
+
+/// A message to index
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConversationMessage {
+    pub id: String,
+    pub role: String,
+    #[serde(default)]
+    pub content: String,
+    #[serde(default)]
+    pub tool_calls: Vec<MessageToolCall>,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// A search over indexed conversations
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ConversationQuery {
+    /// Words must all appear; "quoted text" must appear as a phrase and a
+    /// trailing `*` matches word prefixes
+    pub q: String,
+    pub session_id: Option<String>,
+    pub agent_id: Option<String>,
+    pub role: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub limit: Option<usize>,
+    #[serde(default)]
+    pub offset: usize,
+}
+
+/// A word or quoted phrase of a user query
+#[derive(Debug, Clone, PartialEq)]
+struct QueryTerm {
+    text: String,
+    /// Trailing `*`: matches words starting with `text`
+    prefix: bool,
+}
+
+/// Words and "quoted phrases" of a user query
+fn query_terms(input: &str) -> Vec<QueryTerm> {
+    let mut terms = Vec::new();
+    let mut rest = input.trim();
+
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix('"') {
+            let end = after.find('"').unwrap_or(after.len());
+            let phrase = after[..end].trim();
+            if !phrase.is_empty() {
+                terms.push(QueryTerm { text: phrase.to_string(), prefix: false });
+            }
+            rest = after.get(end + 1..).unwrap_or("").trim_start();
+        } else {
+            let end = rest.find(|c: char| c.is_whitespace() || c == '"').unwrap_or(rest.len());
+            let word = &rest[..end];
+            match word.strip_suffix('*') {
+                Some(prefix) if !prefix.is_empty() => terms.push(QueryTerm { text: prefix.to_string(), prefix: true }),
+                _ => {
+                    let word = word.trim_matches('*');
+                    if !word.is_empty() {
+                        terms.push(QueryTerm { text: word.to_string(), prefix: false });
+                    }
+                }
+            }
+            rest = rest[end..].trim_start();
+        }
+    }
+    terms
+}
+
+/// Translate a user query into an FTS5 expression. Every word and phrase is
+/// quoted, so punctuation like `rm -rf` or `a.txt` never reads as syntax.
+pub fn fts_query(input: &str) -> Option<String> {
+    let terms: Vec<String> = query_terms(input)
+        .iter()
+        .map(|term| {
+            let quoted = format!("\"{}\"", term.text.replace('"', "\"\""));
+            if term.prefix { format!("{}*", quoted) } else { quoted }
+        })
+        .collect();
+    (!terms.is_empty()).then(|| terms.join(" "))
+}
+
+/// Characters of the snippet shown before the first match
+const SNIPPET_BEFORE: usize = 40;
+/// Characters of the snippet shown from the first match on
+const SNIPPET_AFTER: usize = 100;
+
+/// Case-insensitive character ranges where `terms` occur in `text`
+fn term_matches(text: &[char], terms: &[Vec<char>]) -> Vec<(usize, usize)> {
+    let lower: Vec<char> = text.iter().map(|c| c.to_lowercase().next().unwrap_or(*c)).collect();
+    let mut matches = Vec::new();
+    for term in terms.iter().filter(|t| !t.is_empty()) {
+        let mut start = 0;
+        while start + term.len() <= lower.len() {
+            if lower[start..start + term.len()] == term[..] {
+                matches.push((start, start + term.len()));
+                start += term.len();
+            } else {
+                start += 1;
+            }
+        }
+    }
+    matches.sort();
+    matches
+}
+
+/// Excerpt around the first match with every match wrapped in `highlight`
+fn snippet(text: &[char], matches: &[(usize, usize)], highlight: (&str, &str)) -> String {
+    let first = matches.first().map_or(0, |m| m.0);
+    let from = first.saturating_sub(SNIPPET_BEFORE);
+    let to = (first + SNIPPET_AFTER).min(text.len());
+
+    let mut out = String::new();
+    if from > 0 {
+        out.push('…');
+    }
+    let mut at = from;
+    for &(start, end) in matches.iter().filter(|(start, end)| *start >= from && *end <= to) {
+        // Skip matches overlapping one already highlighted
+        if start < at {
+            continue;
+        }
+        out.extend(&text[at..start]);
+        out.push_str(highlight.0);
+        out.extend(&text[start..end]);
+        out.push_str(highlight.1);
+        at = end;
+    }
+    out.extend(&text[at..to]);
+    if to < text.len() {
+        out.push('…');
+    }
+    out
+}
+
+/// Searchable text for a message's tool calls
+fn tool_call_text(calls: &[MessageToolCall]) -> String {
+    calls
+        .iter()
+        .map(|call| match &call.arguments {
+            serde_json::Value::Null => call.name.clone(),
+            arguments => format!("{} {}", call.name, arguments),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// A message of an indexed conversation; text is `None` while the
+/// conversation vault is locked
+#[derive(Debug, Clone, Serialize)]
+pub struct StoredMessage {
+    pub id: String,
+    pub role: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub agent_id: Option<String>,
+    pub content: Option<String>,
+    pub tool_calls: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// The indexed messages of a session
+#[derive(Debug, Clone, Serialize)]
+pub struct ConversationTranscript {
+    pub session_id: String,
+    /// Whether message text is hidden because the vault is locked
+    pub locked: bool,
+    pub messages: Vec<StoredMessage>,
+}
+
+/// Conversation search index stored in the backend database. Message text
+/// is sealed by the conversation vault when encryption is enabled.
+#[derive(Clone)]
+pub struct ConversationIndex {
+    db: Database,
+    vault: Arc<ConversationVault>,
+}
+
+impl ConversationIndex {
+    pub fn new(db: Database) -> Self {
+        Self::with_vault(db, ConversationVault::global())
+    }
+
+    pub fn with_vault(db: Database, vault: Arc<ConversationVault>) -> Self {
+        Self { db, vault }
+    }
+
+    /// Index a session's messages, replacing what was indexed for it before
+    pub async fn index_session(
+        &self,
+        session_id: &str,
+        agent_id: Option<&str>,
+        messages: &[ConversationMessage],
+    ) -> Result<usize, AppError> {
+        let records: Vec<ConversationMessageRecord> = messages
+            .iter()
+            .filter(|m| !m.content.trim().is_empty() || !m.tool_calls.is_empty())
+            .map(|m| {
+                Ok(ConversationMessageRecord {
+                id: m.id.clone(),
+                session_id: session_id.to_string(),
+                agent_id: agent_id.map(str::to_string),
+                role: m.role.clone(),
+                content: self.vault.seal(&m.content)?,
+                tool_calls: self.vault.seal(&tool_call_text(&m.tool_calls))?,
+                created_at: m.timestamp,
+            })
+            })
+            .collect::<Result<_, VaultError>>()?;
+        self.db.replace_conversation_messages(session_id, &records).await?;
+        Ok(records.len())
+    }
+
+    pub async fn remove_session(&self, session_id: &str) -> Result<u64, AppError> {
+        self.db.delete_conversation_session(session_id).await
+    }
+
+    pub async fn remove_agent(&self, agent_id: &str) -> Result<u64, AppError> {
+        self.db.delete_conversation_agent(agent_id).await
+    }
+
+    pub async fn search(&self, query: &ConversationQuery) -> Result<Vec<ConversationSearchHit>, AppError> {
+        let fts = fts_query(&query.q).ok_or_else(|| AppError::BadRequest("Search query is empty".to_string()))?;
+        let filter = ConversationFilter {
+            session_id: query.session_id.clone(),
+            agent_id: query.agent_id.clone(),
+            role: query.role.clone(),
+            from: query.from,
+            to: query.to,
+        };
+        let limit = query.limit.unwrap_or(20).clamp(1, MAX_LIMIT);
+        if self.vault.is_enabled() {
+            return self.search_sealed(&query.q, &filter, limit, query.offset).await;
+        }
+        self.db
+            .search_conversation_messages(&fts, &filter, (HIGHLIGHT_START, HIGHLIGHT_END), limit, query.offset)
+            .await
+    }
+
+    /// Search by decrypting messages, since the full-text index only holds
+    /// sealed text. Terms match anywhere in words, not just at their start.
+    async fn search_sealed(
+        &self,
+        q: &str,
+        filter: &ConversationFilter,
+        limit: usize,
+        offset: usize,
+    ) -> Result<Vec<ConversationSearchHit>, AppError> {
+        if !self.vault.status().unlocked {
+            return Err(VaultError::Locked.into());
+        }
+        let terms: Vec<Vec<char>> = query_terms(q)
+            .iter()
+            .map(|term| term.text.to_lowercase().chars().collect())
+            .collect();
+
+        let mut hits = Vec::new();
+        for record in self.db.conversation_messages(filter).await? {
+            let content: Vec<char> = self.vault.open(&record.content)?.chars().collect();
+            let tool_calls: Vec<char> = self.vault.open(&record.tool_calls)?.chars().collect();
+            let in_content = term_matches(&content, &terms);
+            let in_tools = term_matches(&tool_calls, &terms);
+            let all_found = terms.iter().all(|term| {
+                let term = std::slice::from_ref(term);
+                !term_matches(&content, term).is_empty() || !term_matches(&tool_calls, term).is_empty()
+            });
+            if !all_found {
+                continue;
+            }
+            let (text, matches) = if in_content.is_empty() { (&tool_calls, &in_tools) } else { (&content, &in_content) };
+            hits.push(ConversationSearchHit {
+                message_id: record.id,
+                session_id: record.session_id,
+                agent_id: record.agent_id,
+                role: record.role,
+                created_at: record.created_at,
+                snippet: snippet(text, matches, (HIGHLIGHT_START, HIGHLIGHT_END)),
+                rank: -((in_content.len() + in_tools.len()) as f64),
+            });
+        }
+
+        hits.sort_by(|a, b| a.rank.total_cmp(&b.rank).then(b.created_at.cmp(&a.created_at)));
+        Ok(hits.into_iter().skip(offset).take(limit).collect())
+    }
+
+    /// Indexed sessions, most recently active first; available while locked
+    pub async fn sessions(&self) -> Result<Vec<ConversationSessionRecord>, AppError> {
+        self.db.list_conversation_sessions().await
+    }
+
+    /// Messages of a session, with their text hidden while the vault is locked
+    pub async fn transcript(&self, session_id: &str) -> Result<ConversationTranscript, AppError> {
+        let filter = ConversationFilter {
+            session_id: Some(session_id.to_string()),
+            ..Default::default()
+        };
+        let records = self.db.conversation_messages(&filter).await?;
+        if records.is_empty() {
+            return Err(AppError::NotFound(format!("Conversation not found: {}", session_id)));
+        }
+
+        let mut locked = false;
+        let mut messages = Vec::with_capacity(records.len());
+        for record in records {
+            let (content, tool_calls) = match (self.vault.open(&record.content), self.vault.open(&record.tool_calls)) {
+                (Ok(content), Ok(tool_calls)) => (Some(content), Some(tool_calls)),
+                (Err(VaultError::Locked), _) | (_, Err(VaultError::Locked)) => {
+                    locked = true;
+                    (None, None)
+                }
+                (Err(e), _) | (_, Err(e)) => return Err(e.into()),
+            };
+            messages.push(StoredMessage {
+                id: record.id,
+                role: record.role,
+                agent_id: record.agent_id,
+                content,
+                tool_calls,
+                created_at: record.created_at,
+            });
+        }
+
+        Ok(ConversationTranscript {
+            session_id: session_id.to_string(),
+            locked,
+            messages,
+        })
+    }
+
+    /// Rewrite every stored message sealed (`seal`) or in plain text. Run
+    /// after enabling encryption, and before disabling it while the key is
+    /// still available. Returns the number of messages rewritten.
+    pub async fn rewrite_all(&self, seal: bool) -> Result<usize, AppError> {
+        let mut rewritten = 0;
+        for record in self.db.conversation_messages(&ConversationFilter::default()).await? {
+            let convert = |stored: &str| -> Result<String, VaultError> {
+                let text = self.vault.open(stored)?;
+                if seal { self.vault.seal(&text) } else { Ok(text) }
+            };
+            let content = convert(&record.content)?;
+            let tool_calls = convert(&record.tool_calls)?;
+            if content != record.content || tool_calls != record.tool_calls {
+                self.db
+                    .update_conversation_text(&record.session_id, &record.id, &content, &tool_calls)
+                    .await?;
+                rewritten += 1;
+            }
+        }
+        Ok(rewritten)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use serde_json::json;
+
+    async fn index(dir: &tempfile::TempDir) -> ConversationIndex {
+        let url = format!("sqlite://{}?mode=rwc", dir.path().join("conversations.db").display());
+        ConversationIndex::new(Database::new(&url).await.unwrap())
+    }
+
+    fn message(id: &str, role: &str, content: &str, day: u32) -> ConversationMessage {
+        ConversationMessage {
+            id: id.to_string(),
+            role: role.to_string(),
+            content: content.to_string(),
+            tool_calls: Vec::new(),
+            timestamp: Utc.with_ymd_and_hms(2026, 3, day, 12, 0, 0).unwrap(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_index() {
+        let dir = tempfile::tempdir().unwrap();
+        let url = format!("sqlite://{}?mode=rwc", dir.path().join("conversations.db").display());
+        let db = Database::new(&url).await.unwrap();
+        let vault = Arc::new(ConversationVault::new(dir.path().join("vault.json")));
+        let index = ConversationIndex::with_vault(db.clone(), vault.clone());
+
+        index.index_session("s1", None, &[message("m1", "user", "Where is my tax return?", 10)]).await.unwrap();
+        vault.enable("correct horse").unwrap();
+        assert_eq!(index.rewrite_all(true).await.unwrap(), 1);
+        index.index_session("s2", None, &[message("m1", "user", "Tax deadline reminder", 11)]).await.unwrap();
+
+        // Nothing readable is left in the database
+        let stored = db.conversation_messages(&ConversationFilter::default()).await.unwrap();
+        assert!(stored.iter().all(|m| crate::vault::is_sealed(&m.content) && !m.content.contains("tax return")));
+
+        let query = ConversationQuery { q: "tax".to_string(), ..Default::default() };
+        let hits = index.search(&query).await.unwrap();
+        assert_eq!(hits.len(), 2);
+        assert!(hits[0].snippet.contains("<mark>Tax</mark>"), "{}", hits[0].snippet);
+        let both = ConversationQuery { q: "tax return".to_string(), ..Default::default() };
+        assert_eq!(index.search(&both).await.unwrap()[0].session_id, "s1");
+        assert_eq!(index.transcript("s1").await.unwrap().messages[0].content.as_deref(), Some("Where is my tax return?"));
+
+        // Locked: sessions are listed, their text is not
+        vault.lock();
+        assert_eq!(index.sessions().await.unwrap().len(), 2);
+        let transcript = index.transcript("s1").await.unwrap();
+        assert!(transcript.locked && transcript.messages[0].content.is_none());
+        assert!(matches!(index.search(&query).await, Err(AppError::Vault(VaultError::Locked))));
+        assert!(index.index_session("s3", None, &[message("m1", "user", "Hi", 12)]).await.is_err());
+
+        vault.unlock("correct horse").unwrap();
+        assert_eq!(index.rewrite_all(false).await.unwrap(), 2);
+        vault.disable("correct horse").unwrap();
+        assert_eq!(index.search(&query).await.unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_fts_query() {
+        assert_eq!(fts_query("disk usage").unwrap(), r#""disk" "usage""#);
+        assert_eq!(fts_query(r#""rm -rf" build"#).unwrap(), r#""rm -rf" "build""#);
+        assert_eq!(fts_query("carg* a.txt").unwrap(), r#""carg"* "a.txt""#);
+        assert_eq!(fts_query(r#"say "unclosed"#).unwrap(), r#""say" "unclosed""#);
+        assert!(fts_query("  \"\" * ").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_index_and_search() {
+        let dir = tempfile::tempdir().unwrap();
+        let index = index(&dir).await;
+
+        let mut ran = message("m2", "assistant", "Cleaning up the build folder", 10);
+        ran.tool_calls.push(MessageToolCall {
+            name: "shell".to_string(),
+            arguments: json!({ "command": "rm -rf target/debug" }),
+        });
+        index
+            .index_session("s1", Some("agent-1"), &[message("m1", "user", "Free some disk space", 10), ran])
+            .await
+            .unwrap();
+        index
+            .index_session("s2", None, &[message("m1", "user", "What is using my disk?", 20)])
+            .await
+            .unwrap();
+
+        // Tool call arguments are searchable, with the match highlighted
+        let hits = index
+            .search(&ConversationQuery { q: "\"rm -rf\"".to_string(), ..Default::default() })
+            .await
+            .unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].message_id, "m2");
+        assert!(hits[0].snippet.contains("<mark>rm -rf</mark>"), "{}", hits[0].snippet);
+
+        let query = |q: &str| ConversationQuery { q: q.to_string(), ..Default::default() };
+        assert_eq!(index.search(&query("disk")).await.unwrap().len(), 2);
+        let recent = ConversationQuery {
+            from: Some(Utc.with_ymd_and_hms(2026, 3, 15, 0, 0, 0).unwrap()),
+            ..query("disk")
+        };
+        let hits = index.search(&recent).await.unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].session_id, "s2");
+        let by_agent = ConversationQuery { agent_id: Some("agent-1".to_string()), ..query("disk") };
+        assert_eq!(index.search(&by_agent).await.unwrap()[0].session_id, "s1");
+
+        // Reindexing replaces a session's messages
+        index.index_session("s2", None, &[message("m1", "user", "Hello", 20)]).await.unwrap();
+        assert_eq!(index.search(&query("disk")).await.unwrap().len(), 1);
+        assert_eq!(index.remove_agent("agent-1").await.unwrap(), 2);
+        assert!(index.search(&query("disk")).await.unwrap().is_empty());
+        assert!(index.search(&query("  ")).await.is_err());
+    }
+}
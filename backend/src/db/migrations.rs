@@ -0,0 +1,361 @@
+//! Schema versioning for the SQLite database
+//!
+//! Migrations are the `NNN_description.sql` files in `backend/migrations`,
+//! embedded at build time and applied in version order on startup. A
+//! migration must never be edited once released; schema changes go in a new
+//! file.
+//!
+//! Before applying migrations to an existing database, a copy is written to
+//! `backups/` next to it, and the applied migrations are checked against the
+//! embedded ones: a changed migration, or one this build doesn't know (the
+//! database was written by a newer version), stops startup instead of
+//! migrating further.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::migrate::Migrator;
+use sqlx::{Row, SqlitePool};
+use std::path::{Path, PathBuf};
+
+/// Migrations shipped with this build
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+/// Folder next to the database holding pre-migration backups
+pub const BACKUP_DIR: &str = "backups";
+
+/// Backups kept per database; older ones are deleted
+pub const MAX_BACKUPS: usize = 5;
+
+#[derive(Debug, thiserror::Error)]
+pub enum SchemaError {
+    #[error("Migration {0} was changed after it was applied to this database")]
+    Modified(i64),
+
+    #[error("Database has migration {0}, which this build doesn't know; it was last opened by a newer version of Skhoot")]
+    Unknown(i64),
+
+    #[error("Migration {0} failed partway; restore a backup from the {BACKUP_DIR} folder next to the database")]
+    Failed(i64),
+
+    #[error("Backup before migrating failed: {0}")]
+    Backup(String),
+
+    #[error(transparent)]
+    Database(#[from] sqlx::Error),
+
+    #[error(transparent)]
+    Migrate(#[from] sqlx::migrate::MigrateError),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MigrationState {
+    Applied,
+    Pending,
+    /// Applied, but the embedded file no longer matches
+    Modified,
+    /// Started but did not complete
+    Failed,
+    /// Applied by a build that had a migration this one lacks
+    Unknown,
+}
+
+/// A migration and whether it is applied
+#[derive(Debug, Clone, Serialize)]
+pub struct MigrationInfo {
+    pub version: i64,
+    pub description: String,
+    pub state: MigrationState,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub installed_on: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub execution_time_ms: Option<i64>,
+}
+
+/// A pre-migration copy of the database
+#[derive(Debug, Clone, Serialize)]
+pub struct BackupInfo {
+    pub path: String,
+    pub size_bytes: u64,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Schema version, migration history and health of the database
+#[derive(Debug, Clone, Serialize)]
+pub struct SchemaStatus {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub database_path: Option<String>,
+    /// Highest applied migration
+    pub current_version: Option<i64>,
+    /// Highest migration shipped with this build
+    pub latest_version: Option<i64>,
+    pub migrations: Vec<MigrationInfo>,
+    /// Result of `PRAGMA quick_check`; `["ok"]` for a healthy database
+    pub integrity: Vec<String>,
+    pub backups: Vec<BackupInfo>,
+}
+
+/// A row of sqlx's `_sqlx_migrations` table
+#[derive(Debug, Clone)]
+pub struct AppliedRow {
+    pub version: i64,
+    pub success: bool,
+    pub checksum: Vec<u8>,
+    pub installed_on: String,
+    pub execution_time_ns: i64,
+}
+
+/// Bring the schema up to date with `migrator`, backing up `database_path`
+/// first if it already has applied migrations. Returns the backup written,
+/// if any.
+pub async fn migrate(
+    pool: &SqlitePool,
+    migrator: &Migrator,
+    database_path: Option<&Path>,
+) -> Result<Option<PathBuf>, SchemaError> {
+    let applied = applied_migrations(pool).await?;
+    check_applied(migrator, &applied)?;
+
+    let pending: Vec<i64> = migrator
+        .iter()
+        .map(|m| m.version)
+        .filter(|version| !applied.iter().any(|row| row.version == *version))
+        .collect();
+    if pending.is_empty() {
+        return Ok(None);
+    }
+
+    let mut backup_path = None;
+    if let (false, Some(path)) = (applied.is_empty(), database_path) {
+        let version = applied.iter().map(|row| row.version).max().unwrap_or(0);
+        let path = backup(pool, path, version).await?;
+        tracing::info!("Backed up database to {} before migrating", path.display());
+        backup_path = Some(path);
+    }
+
+    migrator.run(pool).await?;
+    tracing::info!("Applied database migrations {:?}", pending);
+    Ok(backup_path)
+}
+
+/// Fail if an applied migration is unknown to `migrator`, was changed since,
+/// or did not complete
+pub fn check_applied(migrator: &Migrator, applied: &[AppliedRow]) -> Result<(), SchemaError> {
+    for row in applied {
+        let Some(migration) = migrator.iter().find(|m| m.version == row.version) else {
+            return Err(SchemaError::Unknown(row.version));
+        };
+        if !row.success {
+            return Err(SchemaError::Failed(row.version));
+        }
+        if *migration.checksum != *row.checksum {
+            return Err(SchemaError::Modified(row.version));
+        }
+    }
+    Ok(())
+}
+
+/// Rows of the migrations table; empty for a database never migrated
+pub async fn applied_migrations(pool: &SqlitePool) -> Result<Vec<AppliedRow>, sqlx::Error> {
+    let exists = sqlx::query("SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = '_sqlx_migrations'")
+        .fetch_optional(pool)
+        .await?
+        .is_some();
+    if !exists {
+        return Ok(Vec::new());
+    }
+
+    let rows = sqlx::query(
+        "SELECT version, success, checksum, installed_on, execution_time FROM _sqlx_migrations ORDER BY version",
+    )
+    .fetch_all(pool)
+    .await?;
+    rows.iter()
+        .map(|row| {
+            Ok(AppliedRow {
+                version: row.try_get("version")?,
+                success: row.try_get("success")?,
+                checksum: row.try_get("checksum")?,
+                installed_on: row.try_get("installed_on")?,
+                execution_time_ns: row.try_get("execution_time")?,
+            })
+        })
+        .collect()
+}
+
+/// Problems reported by `PRAGMA quick_check`, or `["ok"]`
+pub async fn integrity_check(pool: &SqlitePool) -> Result<Vec<String>, sqlx::Error> {
+    let rows = sqlx::query("PRAGMA quick_check").fetch_all(pool).await?;
+    rows.iter().map(|row| row.try_get::<String, _>(0)).collect()
+}
+
+/// Schema status of the database at `database_path`
+pub async fn status(
+    pool: &SqlitePool,
+    migrator: &Migrator,
+    database_path: Option<&Path>,
+) -> Result<SchemaStatus, sqlx::Error> {
+    let applied = applied_migrations(pool).await?;
+
+    let mut migrations: Vec<MigrationInfo> = migrator
+        .iter()
+        .map(|migration| {
+            let row = applied.iter().find(|row| row.version == migration.version);
+            let state = match row {
+                None => MigrationState::Pending,
+                Some(row) if !row.success => MigrationState::Failed,
+                Some(row) if *row.checksum != *migration.checksum => MigrationState::Modified,
+                Some(_) => MigrationState::Applied,
+            };
+            MigrationInfo {
+                version: migration.version,
+                description: migration.description.to_string(),
+                state,
+                installed_on: row.map(|row| row.installed_on.clone()),
+                execution_time_ms: row.map(|row| row.execution_time_ns / 1_000_000),
+            }
+        })
+        .collect();
+    for row in applied.iter().filter(|row| !migrator.version_exists(row.version)) {
+        migrations.push(MigrationInfo {
+            version: row.version,
+            description: String::new(),
+            state: MigrationState::Unknown,
+            installed_on: Some(row.installed_on.clone()),
+            execution_time_ms: Some(row.execution_time_ns / 1_000_000),
+        });
+    }
+    migrations.sort_by_key(|m| m.version);
+
+    Ok(SchemaStatus {
+        database_path: database_path.map(|p| p.display().to_string()),
+        current_version: applied.iter().filter(|row| row.success).map(|row| row.version).max(),
+        latest_version: migrator.iter().map(|m| m.version).max(),
+        migrations,
+        integrity: integrity_check(pool).await?,
+        backups: database_path.map(list_backups).unwrap_or_default(),
+    })
+}
+
+/// Copy the database into its backup folder, as of schema `version`, and
+/// drop the oldest backups past [`MAX_BACKUPS`]
+pub async fn backup(pool: &SqlitePool, database_path: &Path, version: i64) -> Result<PathBuf, SchemaError> {
+    let dir = backup_dir(database_path);
+    std::fs::create_dir_all(&dir).map_err(|e| SchemaError::Backup(format!("{}: {}", dir.display(), e)))?;
+
+    let path = dir.join(format!(
+        "{}-v{}-{}.db",
+        file_stem(database_path),
+        version,
+        Utc::now().format("%Y%m%d%H%M%S%3f")
+    ));
+    // VACUUM INTO gives a consistent copy even with WAL pages outstanding
+    sqlx::query("VACUUM INTO ?")
+        .bind(path.to_string_lossy().to_string())
+        .execute(pool)
+        .await
+        .map_err(|e| SchemaError::Backup(e.to_string()))?;
+
+    for old in list_backups(database_path).into_iter().skip(MAX_BACKUPS) {
+        let _ = std::fs::remove_file(&old.path);
+    }
+    Ok(path)
+}
+
+/// Backups of the database, newest first
+pub fn list_backups(database_path: &Path) -> Vec<BackupInfo> {
+    let prefix = format!("{}-v", file_stem(database_path));
+    let Ok(entries) = std::fs::read_dir(backup_dir(database_path)) else {
+        return Vec::new();
+    };
+    let mut backups: Vec<BackupInfo> = entries
+        .flatten()
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with(&prefix))
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            Some(BackupInfo {
+                path: entry.path().display().to_string(),
+                size_bytes: metadata.len(),
+                created_at: metadata.modified().ok()?.into(),
+            })
+        })
+        .collect();
+    // Names end in a timestamp, so they sort by age
+    backups.sort_by(|a, b| b.path.cmp(&a.path));
+    backups
+}
+
+fn backup_dir(database_path: &Path) -> PathBuf {
+    database_path.parent().unwrap_or(Path::new(".")).join(BACKUP_DIR)
+}
+
+fn file_stem(database_path: &Path) -> String {
+    database_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_else(|| "database".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::borrow::Cow;
+
+    fn first_migrations(count: usize) -> Migrator {
+        Migrator {
+            migrations: Cow::Owned(MIGRATOR.migrations[..count].to_vec()),
+            ignore_missing: false,
+            locking: true,
+        }
+    }
+
+    async fn open(path: &Path) -> SqlitePool {
+        SqlitePool::connect(&format!("sqlite://{}?mode=rwc", path.display())).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_backs_up_before_upgrading() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("skhoot.db");
+        let pool = open(&path).await;
+
+        // A new database is migrated without a backup
+        let old = first_migrations(1);
+        assert!(migrate(&pool, &old, Some(&path)).await.unwrap().is_none());
+        assert!(migrate(&pool, &old, Some(&path)).await.unwrap().is_none());
+
+        let backup = migrate(&pool, &MIGRATOR, Some(&path)).await.unwrap().expect("upgrade writes a backup");
+        assert!(backup.file_name().unwrap().to_string_lossy().starts_with("skhoot-v1-"));
+
+        let status = status(&pool, &MIGRATOR, Some(&path)).await.unwrap();
+        assert_eq!(status.current_version, status.latest_version);
+        assert!(status.migrations.iter().all(|m| m.state == MigrationState::Applied));
+        assert_eq!(status.integrity, ["ok"]);
+        assert_eq!(status.backups.len(), 1);
+
+        // The backup is a database at the old version
+        let restored = open(&backup).await;
+        let applied = applied_migrations(&restored).await.unwrap();
+        assert_eq!(applied.iter().map(|row| row.version).collect::<Vec<_>>(), [1]);
+    }
+
+    #[tokio::test]
+    async fn test_refuses_unknown_and_modified_migrations() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("skhoot.db");
+        let pool = open(&path).await;
+        migrate(&pool, &MIGRATOR, Some(&path)).await.unwrap();
+
+        // An older build doesn't know the latest migration
+        let older = first_migrations(MIGRATOR.migrations.len() - 1);
+        assert!(matches!(migrate(&pool, &older, Some(&path)).await, Err(SchemaError::Unknown(_))));
+
+        sqlx::query("UPDATE _sqlx_migrations SET checksum = x'00' WHERE version = 1")
+            .execute(&pool)
+            .await
+            .unwrap();
+        assert!(matches!(migrate(&pool, &MIGRATOR, Some(&path)).await, Err(SchemaError::Modified(1))));
+        let status = status(&pool, &MIGRATOR, Some(&path)).await.unwrap();
+        assert_eq!(status.migrations[0].state, MigrationState::Modified);
+    }
+}
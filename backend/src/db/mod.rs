@@ -0,0 +1,1024 @@
+pub mod migrations;
+
+use sqlx::sqlite::SqliteConnectOptions;
+use sqlx::{SqlitePool, Row};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use crate::error::AppError;
+
+use migrations::{SchemaStatus, MIGRATOR};
+
+#[derive(Clone)]
+pub struct Database {
+    pool: SqlitePool,
+    /// Database file; `None` for in-memory databases
+    path: Option<PathBuf>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FileRecord {
+    pub id: String,
+    pub path: String,
+    pub name: String,
+    pub size: i64,
+    pub modified_at: DateTime<Utc>,
+    pub content_hash: String,
+    pub mime_type: String,
+    pub indexed_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ContentChunk {
+    pub id: String,
+    pub file_id: String,
+    pub chunk_index: i32,
+    pub content: String,
+    pub embedding: Option<Vec<f32>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DiskSnapshotRecord {
+    pub id: String,
+    pub scan_id: String,
+    pub path: String,
+    pub size: i64,
+    pub file_count: i64,
+    pub captured_at: DateTime<Utc>,
+}
+
+/// Metadata of a persisted web page extract
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WebCacheEntry {
+    pub key: String,
+    pub url: String,
+    pub size_bytes: i64,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub cached_at: DateTime<Utc>,
+    pub last_accessed: DateTime<Utc>,
+}
+
+/// A conversation message in the search index
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConversationMessageRecord {
+    pub id: String,
+    pub session_id: String,
+    pub agent_id: Option<String>,
+    pub role: String,
+    pub content: String,
+    /// Tool calls as searchable text, one per line
+    pub tool_calls: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A session in the conversation index
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConversationSessionRecord {
+    pub session_id: String,
+    pub agent_id: Option<String>,
+    pub message_count: i64,
+    pub first_message_at: DateTime<Utc>,
+    pub last_message_at: DateTime<Utc>,
+}
+
+/// Restrictions on a conversation search
+#[derive(Debug, Clone, Default)]
+pub struct ConversationFilter {
+    pub session_id: Option<String>,
+    pub agent_id: Option<String>,
+    pub role: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+}
+
+/// A message matching a conversation search
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConversationSearchHit {
+    pub message_id: String,
+    pub session_id: String,
+    pub agent_id: Option<String>,
+    pub role: String,
+    pub created_at: DateTime<Utc>,
+    /// Matching excerpt with matches wrapped in the requested markers
+    pub snippet: String,
+    /// BM25 score; lower is a better match
+    pub rank: f64,
+}
+
+/// A definition or import in the code structure index
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CodeSymbolRecord {
+    pub path: String,
+    pub name: String,
+    pub kind: String,
+    /// 1-based
+    pub line: i64,
+    pub end_line: i64,
+    pub container: Option<String>,
+    pub signature: String,
+}
+
+/// A file in the filename index
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct FilenameRecord {
+    pub path: String,
+    /// Indexed root the file is under
+    pub root: String,
+    pub size: i64,
+    pub modified_at: Option<DateTime<Utc>>,
+}
+
+/// Restrictions on a code symbol query
+#[derive(Debug, Clone, Default)]
+pub struct CodeSymbolFilter {
+    /// Matched case-insensitively: exact names first, then prefixes, then
+    /// names containing it unless `exact_or_prefix` is set
+    pub name: Option<String>,
+    pub kind: Option<String>,
+    /// Only files under this directory
+    pub path_prefix: Option<String>,
+    pub exact_or_prefix: bool,
+}
+
+impl Database {
+    pub async fn new(database_url: &str) -> Result<Self, AppError> {
+        let options = SqliteConnectOptions::from_str(database_url)?;
+        let pool = SqlitePool::connect_with(options.clone()).await?;
+        let path = Some(options.get_filename().into_owned()).filter(|path| path.is_file());
+
+        let integrity = migrations::integrity_check(&pool).await?;
+        if integrity != ["ok"] {
+            tracing::error!("Database integrity check failed: {}", integrity.join("; "));
+        }
+        migrations::migrate(&pool, &MIGRATOR, path.as_deref()).await?;
+
+        Ok(Self { pool, path })
+    }
+
+    /// Database file, unless the database is in memory
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Schema version, migrations, integrity and backups
+    pub async fn schema_status(&self) -> Result<SchemaStatus, AppError> {
+        Ok(migrations::status(&self.pool, &MIGRATOR, self.path()).await?)
+    }
+
+    pub async fn is_healthy(&self) -> bool {
+        sqlx::query("SELECT 1").fetch_one(&self.pool).await.is_ok()
+    }
+
+    pub async fn insert_file(&self, file: &FileRecord) -> Result<(), AppError> {
+        sqlx::query(
+            r#"
+            INSERT OR REPLACE INTO files 
+            (id, path, name, size, modified_at, content_hash, mime_type, indexed_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            "#
+        )
+        .bind(&file.id)
+        .bind(&file.path)
+        .bind(&file.name)
+        .bind(file.size)
+        .bind(file.modified_at.to_rfc3339())
+        .bind(&file.content_hash)
+        .bind(&file.mime_type)
+        .bind(file.indexed_at.to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+        
+        Ok(())
+    }
+
+    pub async fn insert_chunk(&self, chunk: &ContentChunk) -> Result<(), AppError> {
+        let embedding_blob = chunk.embedding.as_ref().map(|e| {
+            e.iter().flat_map(|f| f.to_le_bytes()).collect::<Vec<u8>>()
+        });
+
+        sqlx::query(
+            r#"
+            INSERT OR REPLACE INTO content_chunks 
+            (id, file_id, chunk_index, content, embedding)
+            VALUES (?, ?, ?, ?, ?)
+            "#
+        )
+        .bind(&chunk.id)
+        .bind(&chunk.file_id)
+        .bind(chunk.chunk_index)
+        .bind(&chunk.content)
+        .bind(embedding_blob)
+        .execute(&self.pool)
+        .await?;
+        
+        Ok(())
+    }
+
+    pub async fn search_by_content(&self, query: &str, limit: usize) -> Result<Vec<FileRecord>, AppError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT DISTINCT f.id, f.path, f.name, f.size, f.modified_at, f.content_hash, f.mime_type, f.indexed_at
+            FROM files f
+            JOIN content_chunks c ON f.id = c.file_id
+            WHERE c.content LIKE ?
+            ORDER BY f.modified_at DESC
+            LIMIT ?
+            "#
+        )
+        .bind(format!("%{}%", query))
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let files = rows.into_iter().map(|row| FileRecord {
+            id: row.get("id"),
+            path: row.get("path"),
+            name: row.get("name"),
+            size: row.get("size"),
+            modified_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("modified_at")).unwrap().with_timezone(&Utc),
+            content_hash: row.get("content_hash"),
+            mime_type: row.get("mime_type"),
+            indexed_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("indexed_at")).unwrap().with_timezone(&Utc),
+        }).collect();
+
+        Ok(files)
+    }
+
+    pub async fn get_file_by_path(&self, path: &str) -> Result<Option<FileRecord>, AppError> {
+        let row = sqlx::query(
+            "SELECT id, path, name, size, modified_at, content_hash, mime_type, indexed_at FROM files WHERE path = ?"
+        )
+        .bind(path)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        if let Some(row) = row {
+            Ok(Some(FileRecord {
+                id: row.get("id"),
+                path: row.get("path"),
+                name: row.get("name"),
+                size: row.get("size"),
+                modified_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("modified_at")).unwrap().with_timezone(&Utc),
+                content_hash: row.get("content_hash"),
+                mime_type: row.get("mime_type"),
+                indexed_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("indexed_at")).unwrap().with_timezone(&Utc),
+            }))
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub async fn get_chunks_by_file_id(&self, file_id: &str) -> Result<Vec<ContentChunk>, AppError> {
+        let rows = sqlx::query(
+            "SELECT id, file_id, chunk_index, content, embedding FROM content_chunks WHERE file_id = ? ORDER BY chunk_index"
+        )
+        .bind(file_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let chunks = rows.into_iter().map(|row| {
+            let embedding = row.get::<Option<Vec<u8>>, _>("embedding").map(|blob| {
+                blob.chunks_exact(4)
+                    .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+                    .collect()
+            });
+
+            ContentChunk {
+                id: row.get("id"),
+                file_id: row.get("file_id"),
+                chunk_index: row.get("chunk_index"),
+                content: row.get("content"),
+                embedding,
+            }
+        }).collect();
+
+        Ok(chunks)
+    }
+
+    pub async fn insert_disk_snapshots(&self, snapshots: &[DiskSnapshotRecord]) -> Result<(), AppError> {
+        let mut tx = self.pool.begin().await?;
+
+        for snapshot in snapshots {
+            sqlx::query(
+                r#"
+                INSERT INTO disk_snapshots
+                (id, scan_id, path, size, file_count, captured_at)
+                VALUES (?, ?, ?, ?, ?, ?)
+                "#
+            )
+            .bind(&snapshot.id)
+            .bind(&snapshot.scan_id)
+            .bind(&snapshot.path)
+            .bind(snapshot.size)
+            .bind(snapshot.file_count)
+            .bind(snapshot.captured_at.to_rfc3339())
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Snapshots captured since `since`, of `path_prefix` and the paths
+    /// below it when given
+    pub async fn get_disk_snapshots_since(
+        &self,
+        since: DateTime<Utc>,
+        path_prefix: Option<&str>,
+    ) -> Result<Vec<DiskSnapshotRecord>, AppError> {
+        let prefix = path_prefix.map(|path| path.trim_end_matches(std::path::MAIN_SEPARATOR));
+        let rows = sqlx::query(
+            r#"
+            SELECT id, scan_id, path, size, file_count, captured_at
+            FROM disk_snapshots
+            WHERE captured_at >= ?1
+              AND (?2 IS NULL OR path = ?2 OR path LIKE ?3 ESCAPE '\')
+            ORDER BY captured_at ASC
+            "#
+        )
+        .bind(since.to_rfc3339())
+        .bind(prefix)
+        .bind(prefix.map(|path| format!("{}{}%", escape_like(path), std::path::MAIN_SEPARATOR)))
+        .fetch_all(&self.pool)
+        .await?;
+
+        let snapshots = rows.into_iter().map(|row| DiskSnapshotRecord {
+            id: row.get("id"),
+            scan_id: row.get("scan_id"),
+            path: row.get("path"),
+            size: row.get("size"),
+            file_count: row.get("file_count"),
+            captured_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("captured_at")).unwrap().with_timezone(&Utc),
+        }).collect();
+
+        Ok(snapshots)
+    }
+
+    pub async fn get_latest_disk_snapshot_time(&self) -> Result<Option<DateTime<Utc>>, AppError> {
+        let row = sqlx::query("SELECT MAX(captured_at) AS captured_at FROM disk_snapshots")
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(row
+            .get::<Option<String>, _>("captured_at")
+            .and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
+            .map(|d| d.with_timezone(&Utc)))
+    }
+
+    pub async fn delete_disk_snapshots_before(&self, before: DateTime<Utc>) -> Result<u64, AppError> {
+        let result = sqlx::query("DELETE FROM disk_snapshots WHERE captured_at < ?")
+            .bind(before.to_rfc3339())
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    pub async fn upsert_web_cache_entry(&self, entry: &WebCacheEntry, extract_json: &str) -> Result<(), AppError> {
+        sqlx::query(
+            r#"
+            INSERT OR REPLACE INTO web_cache
+            (key, url, extract, size_bytes, etag, last_modified, cached_at, last_accessed)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            "#
+        )
+        .bind(&entry.key)
+        .bind(&entry.url)
+        .bind(extract_json)
+        .bind(entry.size_bytes)
+        .bind(&entry.etag)
+        .bind(&entry.last_modified)
+        .bind(sortable_time(entry.cached_at))
+        .bind(sortable_time(entry.last_accessed))
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Entry metadata and the serialized extract
+    pub async fn get_web_cache_entry(&self, key: &str) -> Result<Option<(WebCacheEntry, String)>, AppError> {
+        let row = sqlx::query(
+            r#"
+            SELECT key, url, extract, size_bytes, etag, last_modified, cached_at, last_accessed
+            FROM web_cache WHERE key = ?
+            "#
+        )
+        .bind(key)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|row| (web_cache_entry(&row), row.get("extract"))))
+    }
+
+    /// Records an access; `revalidated` also restarts the entry's TTL
+    pub async fn touch_web_cache_entry(&self, key: &str, at: DateTime<Utc>, revalidated: bool) -> Result<(), AppError> {
+        let query = if revalidated {
+            "UPDATE web_cache SET last_accessed = ?1, cached_at = ?1 WHERE key = ?2"
+        } else {
+            "UPDATE web_cache SET last_accessed = ?1 WHERE key = ?2"
+        };
+        sqlx::query(query)
+            .bind(sortable_time(at))
+            .bind(key)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Most recently used entries first
+    pub async fn list_web_cache_entries(&self, limit: usize) -> Result<Vec<WebCacheEntry>, AppError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT key, url, size_bytes, etag, last_modified, cached_at, last_accessed
+            FROM web_cache
+            ORDER BY last_accessed DESC
+            LIMIT ?
+            "#
+        )
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(web_cache_entry).collect())
+    }
+
+    /// Number of entries and their total size in bytes
+    pub async fn web_cache_totals(&self) -> Result<(i64, i64), AppError> {
+        let row = sqlx::query("SELECT COUNT(*) AS entries, COALESCE(SUM(size_bytes), 0) AS size FROM web_cache")
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok((row.get("entries"), row.get("size")))
+    }
+
+    pub async fn delete_web_cache_entry(&self, key: &str) -> Result<bool, AppError> {
+        let result = sqlx::query("DELETE FROM web_cache WHERE key = ?")
+            .bind(key)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn clear_web_cache(&self) -> Result<u64, AppError> {
+        let result = sqlx::query("DELETE FROM web_cache").execute(&self.pool).await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Deletes entries cached before `expired_before`, keeping those with
+    /// revalidation headers until `retain_validated_until`
+    pub async fn delete_expired_web_cache(
+        &self,
+        expired_before: DateTime<Utc>,
+        retain_validated_until: DateTime<Utc>,
+    ) -> Result<u64, AppError> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM web_cache
+            WHERE cached_at < ?
+              AND ((etag IS NULL AND last_modified IS NULL) OR cached_at < ?)
+            "#
+        )
+        .bind(sortable_time(expired_before))
+        .bind(sortable_time(retain_validated_until))
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Deletes least recently used entries until the cache fits in `max_bytes`
+    pub async fn evict_web_cache_lru(&self, max_bytes: i64) -> Result<u64, AppError> {
+        let (_, mut total) = self.web_cache_totals().await?;
+        if total <= max_bytes {
+            return Ok(0);
+        }
+
+        let rows = sqlx::query("SELECT key, size_bytes FROM web_cache ORDER BY last_accessed ASC")
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut tx = self.pool.begin().await?;
+        let mut evicted = 0;
+        for row in rows {
+            if total <= max_bytes {
+                break;
+            }
+            sqlx::query("DELETE FROM web_cache WHERE key = ?")
+                .bind(row.get::<String, _>("key"))
+                .execute(&mut *tx)
+                .await?;
+            total -= row.get::<i64, _>("size_bytes");
+            evicted += 1;
+        }
+        tx.commit().await?;
+
+        Ok(evicted)
+    }
+
+    /// Replace the indexed messages of a session
+    pub async fn replace_conversation_messages(
+        &self,
+        session_id: &str,
+        messages: &[ConversationMessageRecord],
+    ) -> Result<(), AppError> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM conversation_messages WHERE session_id = ?")
+            .bind(session_id)
+            .execute(&mut *tx)
+            .await?;
+        for message in messages {
+            sqlx::query(
+                r#"
+                INSERT OR IGNORE INTO conversation_messages
+                (session_id, id, agent_id, role, content, tool_calls, created_at)
+                VALUES (?, ?, ?, ?, ?, ?, ?)
+                "#
+            )
+            .bind(session_id)
+            .bind(&message.id)
+            .bind(&message.agent_id)
+            .bind(&message.role)
+            .bind(&message.content)
+            .bind(&message.tool_calls)
+            .bind(sortable_time(message.created_at))
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    pub async fn delete_conversation_session(&self, session_id: &str) -> Result<u64, AppError> {
+        let result = sqlx::query("DELETE FROM conversation_messages WHERE session_id = ?")
+            .bind(session_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    pub async fn delete_conversation_agent(&self, agent_id: &str) -> Result<u64, AppError> {
+        let result = sqlx::query("DELETE FROM conversation_messages WHERE agent_id = ?")
+            .bind(agent_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Indexed sessions, most recently active first
+    pub async fn list_conversation_sessions(&self) -> Result<Vec<ConversationSessionRecord>, AppError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT session_id, MAX(agent_id) AS agent_id, COUNT(*) AS message_count,
+                   MIN(created_at) AS first_message_at, MAX(created_at) AS last_message_at
+            FROM conversation_messages
+            GROUP BY session_id
+            ORDER BY last_message_at DESC
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| ConversationSessionRecord {
+                session_id: row.get("session_id"),
+                agent_id: row.get("agent_id"),
+                message_count: row.get("message_count"),
+                first_message_at: parse_time(&row.get::<String, _>("first_message_at")),
+                last_message_at: parse_time(&row.get::<String, _>("last_message_at")),
+            })
+            .collect())
+    }
+
+    /// Indexed messages passing `filter`, oldest first
+    pub async fn conversation_messages(
+        &self,
+        filter: &ConversationFilter,
+    ) -> Result<Vec<ConversationMessageRecord>, AppError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, session_id, agent_id, role, content, tool_calls, created_at
+            FROM conversation_messages
+            WHERE (?1 IS NULL OR session_id = ?1)
+              AND (?2 IS NULL OR agent_id = ?2)
+              AND (?3 IS NULL OR role = ?3)
+              AND (?4 IS NULL OR created_at >= ?4)
+              AND (?5 IS NULL OR created_at < ?5)
+            ORDER BY created_at, rowid
+            "#
+        )
+        .bind(&filter.session_id)
+        .bind(&filter.agent_id)
+        .bind(&filter.role)
+        .bind(filter.from.map(sortable_time))
+        .bind(filter.to.map(sortable_time))
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| ConversationMessageRecord {
+                id: row.get("id"),
+                session_id: row.get("session_id"),
+                agent_id: row.get("agent_id"),
+                role: row.get("role"),
+                content: row.get("content"),
+                tool_calls: row.get("tool_calls"),
+                created_at: parse_time(&row.get::<String, _>("created_at")),
+            })
+            .collect())
+    }
+
+    /// Replace the stored text of an indexed message
+    pub async fn update_conversation_text(
+        &self,
+        session_id: &str,
+        id: &str,
+        content: &str,
+        tool_calls: &str,
+    ) -> Result<(), AppError> {
+        sqlx::query("UPDATE conversation_messages SET content = ?, tool_calls = ? WHERE session_id = ? AND id = ?")
+            .bind(content)
+            .bind(tool_calls)
+            .bind(session_id)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// When an indexed source file was last modified, if it is indexed
+    pub async fn code_file_modified(&self, path: &str) -> Result<Option<DateTime<Utc>>, AppError> {
+        let row = sqlx::query("SELECT modified_at FROM code_files WHERE path = ?")
+            .bind(path)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(|row| parse_time(&row.get::<String, _>("modified_at"))))
+    }
+
+    /// Replace the symbols of a source file
+    pub async fn replace_code_symbols(
+        &self,
+        path: &str,
+        language: &str,
+        modified_at: DateTime<Utc>,
+        symbols: &[CodeSymbolRecord],
+    ) -> Result<(), AppError> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM code_symbols WHERE path = ?")
+            .bind(path)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("INSERT OR REPLACE INTO code_files (path, language, modified_at, indexed_at) VALUES (?, ?, ?, ?)")
+            .bind(path)
+            .bind(language)
+            .bind(sortable_time(modified_at))
+            .bind(sortable_time(Utc::now()))
+            .execute(&mut *tx)
+            .await?;
+        for symbol in symbols {
+            sqlx::query(
+                r#"
+                INSERT INTO code_symbols (path, name, kind, line, end_line, container, signature)
+                VALUES (?, ?, ?, ?, ?, ?, ?)
+                "#
+            )
+            .bind(path)
+            .bind(&symbol.name)
+            .bind(&symbol.kind)
+            .bind(symbol.line)
+            .bind(symbol.end_line)
+            .bind(&symbol.container)
+            .bind(&symbol.signature)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Drop a source file from the code index
+    pub async fn delete_code_file(&self, path: &str) -> Result<bool, AppError> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM code_symbols WHERE path = ?")
+            .bind(path)
+            .execute(&mut *tx)
+            .await?;
+        let result = sqlx::query("DELETE FROM code_files WHERE path = ?")
+            .bind(path)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Indexed source files and symbols
+    pub async fn code_index_totals(&self) -> Result<(i64, i64), AppError> {
+        let row = sqlx::query(
+            "SELECT (SELECT COUNT(*) FROM code_files) AS files, (SELECT COUNT(*) FROM code_symbols) AS symbols"
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok((row.get("files"), row.get("symbols")))
+    }
+
+    /// Indexed filename roots and when each was last walked in full
+    pub async fn filename_roots(&self) -> Result<Vec<(String, DateTime<Utc>)>, AppError> {
+        let rows = sqlx::query("SELECT root, reconciled_at FROM filename_roots")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| (row.get("root"), parse_time(&row.get::<String, _>("reconciled_at"))))
+            .collect())
+    }
+
+    /// Every file in the filename index
+    pub async fn filename_records(&self) -> Result<Vec<FilenameRecord>, AppError> {
+        let rows = sqlx::query("SELECT path, root, size, modified_at FROM filename_index")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.iter().map(filename_record).collect())
+    }
+
+    /// Replace the indexed files of a root after walking it
+    pub async fn replace_filename_root(
+        &self,
+        root: &str,
+        files: &[FilenameRecord],
+        reconciled_at: DateTime<Utc>,
+    ) -> Result<(), AppError> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM filename_index WHERE root = ?")
+            .bind(root)
+            .execute(&mut *tx)
+            .await?;
+        for file in files {
+            insert_filename(&mut tx, file).await?;
+        }
+        sqlx::query("INSERT OR REPLACE INTO filename_roots (root, reconciled_at) VALUES (?, ?)")
+            .bind(root)
+            .bind(sortable_time(reconciled_at))
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Apply watcher changes: add or update `upserts`, then drop `removed`
+    /// paths and everything below them
+    pub async fn update_filenames(&self, upserts: &[FilenameRecord], removed: &[String]) -> Result<(), AppError> {
+        let mut tx = self.pool.begin().await?;
+        for file in upserts {
+            insert_filename(&mut tx, file).await?;
+        }
+        for path in removed {
+            let below = format!("{}{}%", escape_like(path), std::path::MAIN_SEPARATOR);
+            sqlx::query("DELETE FROM filename_index WHERE path = ? OR path LIKE ? ESCAPE '\\'")
+                .bind(path)
+                .bind(below)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Drop a root that is no longer indexed
+    pub async fn delete_filename_root(&self, root: &str) -> Result<(), AppError> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM filename_index WHERE root = ?")
+            .bind(root)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM filename_roots WHERE root = ?")
+            .bind(root)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Symbols matching a filter, best name matches first
+    pub async fn search_code_symbols(
+        &self,
+        filter: &CodeSymbolFilter,
+        limit: usize,
+    ) -> Result<Vec<CodeSymbolRecord>, AppError> {
+        let name = filter.name.as_deref().map(escape_like);
+        let path_prefix = filter.path_prefix.as_deref().map(|p| {
+            let p = p.trim_end_matches(std::path::MAIN_SEPARATOR);
+            format!("{}{}%", escape_like(p), std::path::MAIN_SEPARATOR)
+        });
+        let rows = sqlx::query(
+            r#"
+            SELECT path, name, kind, line, end_line, container, signature
+            FROM code_symbols
+            WHERE (?1 IS NULL OR name LIKE (CASE WHEN ?4 THEN '' ELSE '%' END) || ?1 || '%' ESCAPE '\')
+              AND (?2 IS NULL OR kind = ?2)
+              AND (?3 IS NULL OR path LIKE ?3 ESCAPE '\')
+            ORDER BY
+              CASE WHEN ?1 IS NULL THEN 0
+                   WHEN name LIKE ?1 ESCAPE '\' THEN 0
+                   WHEN name LIKE ?1 || '%' ESCAPE '\' THEN 1
+                   ELSE 2 END,
+              kind = 'import',
+              length(name), path, line
+            LIMIT ?5
+            "#
+        )
+        .bind(name)
+        .bind(&filter.kind)
+        .bind(path_prefix)
+        .bind(filter.exact_or_prefix)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| CodeSymbolRecord {
+                path: row.get("path"),
+                name: row.get("name"),
+                kind: row.get("kind"),
+                line: row.get("line"),
+                end_line: row.get("end_line"),
+                container: row.get("container"),
+                signature: row.get("signature"),
+            })
+            .collect())
+    }
+
+    /// Best matches first for an FTS5 `MATCH` expression
+    pub async fn search_conversation_messages(
+        &self,
+        fts_query: &str,
+        filter: &ConversationFilter,
+        highlight: (&str, &str),
+        limit: usize,
+        offset: usize,
+    ) -> Result<Vec<ConversationSearchHit>, AppError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT m.id, m.session_id, m.agent_id, m.role, m.created_at,
+                   snippet(conversation_fts, -1, ?2, ?3, '…', 16) AS snippet,
+                   bm25(conversation_fts) AS rank
+            FROM conversation_fts
+            JOIN conversation_messages m ON m.rowid = conversation_fts.rowid
+            WHERE conversation_fts MATCH ?1
+              AND (?4 IS NULL OR m.session_id = ?4)
+              AND (?5 IS NULL OR m.agent_id = ?5)
+              AND (?6 IS NULL OR m.role = ?6)
+              AND (?7 IS NULL OR m.created_at >= ?7)
+              AND (?8 IS NULL OR m.created_at < ?8)
+            ORDER BY rank
+            LIMIT ?9 OFFSET ?10
+            "#
+        )
+        .bind(fts_query)
+        .bind(highlight.0)
+        .bind(highlight.1)
+        .bind(&filter.session_id)
+        .bind(&filter.agent_id)
+        .bind(&filter.role)
+        .bind(filter.from.map(sortable_time))
+        .bind(filter.to.map(sortable_time))
+        .bind(limit as i64)
+        .bind(offset as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| ConversationSearchHit {
+                message_id: row.get("id"),
+                session_id: row.get("session_id"),
+                agent_id: row.get("agent_id"),
+                role: row.get("role"),
+                created_at: parse_time(&row.get::<String, _>("created_at")),
+                snippet: row.get("snippet"),
+                rank: row.get("rank"),
+            })
+            .collect())
+    }
+}
+
+fn parse_time(value: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|d| d.with_timezone(&Utc))
+        .unwrap_or_default()
+}
+
+/// Escape `%`, `_` and `\` for a `LIKE ... ESCAPE '\'` pattern
+fn escape_like(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
+
+/// Fixed-width timestamps so text comparison matches time order
+fn sortable_time(at: DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(chrono::SecondsFormat::Micros, true)
+}
+
+fn filename_record(row: &sqlx::sqlite::SqliteRow) -> FilenameRecord {
+    FilenameRecord {
+        path: row.get("path"),
+        root: row.get("root"),
+        size: row.get("size"),
+        modified_at: row.get::<Option<String>, _>("modified_at").map(|t| parse_time(&t)),
+    }
+}
+
+async fn insert_filename(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    file: &FilenameRecord,
+) -> Result<(), AppError> {
+    sqlx::query("INSERT OR REPLACE INTO filename_index (path, root, size, modified_at) VALUES (?, ?, ?, ?)")
+        .bind(&file.path)
+        .bind(&file.root)
+        .bind(file.size)
+        .bind(file.modified_at.map(sortable_time))
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
+}
+
+fn web_cache_entry(row: &sqlx::sqlite::SqliteRow) -> WebCacheEntry {
+    let parse = |column: &str| parse_time(&row.get::<String, _>(column));
+    WebCacheEntry {
+        key: row.get("key"),
+        url: row.get("url"),
+        size_bytes: row.get("size_bytes"),
+        etag: row.get("etag"),
+        last_modified: row.get("last_modified"),
+        cached_at: parse("cached_at"),
+        last_accessed: parse("last_accessed"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::MAIN_SEPARATOR;
+
+    fn snapshot(path: &str) -> DiskSnapshotRecord {
+        DiskSnapshotRecord {
+            id: uuid::Uuid::new_v4().to_string(),
+            scan_id: "scan".to_string(),
+            path: path.to_string(),
+            size: 1,
+            file_count: 1,
+            captured_at: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_disk_snapshots_match_whole_path_components() {
+        let dir = tempfile::tempdir().unwrap();
+        let url = format!("sqlite://{}?mode=rwc", dir.path().join("disk.db").display());
+        let db = Database::new(&url).await.unwrap();
+
+        let path = |parts: &[&str]| parts.iter().map(|part| format!("{}{}", MAIN_SEPARATOR, part)).collect::<String>();
+        db.insert_disk_snapshots(&[
+            snapshot(&path(&["home", "a"])),
+            snapshot(&path(&["home", "a", "docs"])),
+            snapshot(&path(&["home", "ab"])),
+            snapshot(&path(&["home", "a_b"])),
+        ])
+        .await
+        .unwrap();
+
+        let since = Utc::now() - chrono::Duration::hours(1);
+        let paths = |snapshots: Vec<DiskSnapshotRecord>| {
+            let mut paths: Vec<String> = snapshots.into_iter().map(|s| s.path).collect();
+            paths.sort();
+            paths
+        };
+        let under_a = db.get_disk_snapshots_since(since, Some(&path(&["home", "a"]))).await.unwrap();
+        assert_eq!(paths(under_a), [path(&["home", "a"]), path(&["home", "a", "docs"])]);
+
+        // `_` is a LIKE wildcard unless escaped
+        let under_a_b = db.get_disk_snapshots_since(since, Some(&path(&["home", "a_b"]))).await.unwrap();
+        assert_eq!(paths(under_a_b), [path(&["home", "a_b"])]);
+
+        assert_eq!(db.get_disk_snapshots_since(since, None).await.unwrap().len(), 4);
+    }
+}
@@ -1,4 +1,6 @@
+use super::categorizer::FileCategorizer;
 use super::types::*;
+use crate::mounts::MountGuard;
 use anyhow::{Context, Result};
 use chrono::Utc;
 use std::collections::HashMap;
@@ -19,13 +21,35 @@ impl DiskAnalyzer {
     pub fn analyze(&self) -> Result<DiskAnalysisReport> {
         let mut analyzed_paths = Vec::new();
         let mut all_entries: Vec<(PathBuf, u64)> = Vec::new();
+        let mut skipped_mounts = Vec::new();
+        let mut cleanup_candidates = Vec::new();
+        let categorizer = FileCategorizer::new(&dirs::home_dir().unwrap_or_default());
 
         for path in &self.config.paths {
-            let analysis = self.analyze_path(path)?;
+            let guard = MountGuard::new(path, &self.config.mounts);
+            skipped_mounts.extend(guard.skipped().iter().cloned());
+            if !guard.allows(path) {
+                // The path itself is on a mount that didn't answer
+                analyzed_paths.push(PathAnalysis {
+                    path: path.clone(),
+                    size: 0,
+                    file_count: 0,
+                    dir_count: 0,
+                });
+                continue;
+            }
+            let analysis = self.analyze_path(path, &guard)?;
             analyzed_paths.push(analysis.clone());
             
             // Collect entries for top consumers
-            self.collect_entries(path, &mut all_entries)?;
+            self.collect_entries(path, &guard, &mut all_entries)?;
+
+            cleanup_candidates.extend(
+                categorizer
+                    .find_candidates(path, self.config.ignore_rules, self.config.max_depth, &guard)
+                    .into_iter()
+                    .filter(|c| c.size > 0),
+            );
         }
 
         let total_size: u64 = analyzed_paths.iter().map(|p| p.size).sum();
@@ -50,19 +74,20 @@ impl DiskAnalyzer {
             total_size,
             analyzed_paths,
             top_consumers,
-            cleanup_candidates: vec![],
+            cleanup_candidates,
             categories: HashMap::new(),
             timestamp: Utc::now(),
+            skipped_mounts,
         })
     }
 
     /// Analyze a single path
-    fn analyze_path(&self, path: &Path) -> Result<PathAnalysis> {
+    fn analyze_path(&self, path: &Path, guard: &MountGuard) -> Result<PathAnalysis> {
         let mut total_size = 0u64;
         let mut file_count = 0usize;
         let mut dir_count = 0usize;
 
-        let walker = self.create_walker(path);
+        let walker = self.create_walker(path, guard);
 
         for entry in walker {
             let entry = entry.context("Failed to read directory entry")?;
@@ -85,24 +110,21 @@ impl DiskAnalyzer {
         })
     }
 
-    /// Create a WalkDir walker with configured options
-    fn create_walker(&self, path: &Path) -> WalkDir {
-        let mut walker = WalkDir::new(path);
-
-        if let Some(max_depth) = self.config.max_depth {
-            walker = walker.max_depth(max_depth);
-        }
-
-        if !self.config.follow_symlinks {
-            walker = walker.follow_links(false);
-        }
-
-        walker
+    /// Create a walker with configured options and ignore rules that stays
+    /// out of the mounts `guard` skips
+    fn create_walker(&self, path: &Path, guard: &MountGuard) -> ignore::Walk {
+        let guard = guard.clone();
+        self.config
+            .ignore_rules
+            .walker(path)
+            .max_depth(self.config.max_depth)
+            .filter_entry(move |entry| guard.allows(entry.path()))
+            .build()
     }
 
     /// Collect all entries with their sizes for top consumer calculation
-    fn collect_entries(&self, path: &Path, entries: &mut Vec<(PathBuf, u64)>) -> Result<()> {
-        let walker = self.create_walker(path);
+    fn collect_entries(&self, path: &Path, guard: &MountGuard, entries: &mut Vec<(PathBuf, u64)>) -> Result<()> {
+        let walker = self.create_walker(path, guard);
 
         for entry in walker {
             let entry = entry.context("Failed to read directory entry")?;
@@ -144,6 +166,31 @@ impl DiskAnalyzer {
     }
 }
 
+/// Storage category of a file, by extension
+pub fn file_category(path: &Path) -> &'static str {
+    let extension = path.extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+    
+    match extension.as_str() {
+        // Documents
+        "pdf" | "doc" | "docx" | "txt" | "rtf" | "odt" | "xls" | "xlsx" | "ppt" | "pptx" => "Documents",
+        // Images
+        "jpg" | "jpeg" | "png" | "gif" | "bmp" | "svg" | "webp" | "ico" | "tiff" | "raw" => "Images",
+        // Videos
+        "mp4" | "avi" | "mkv" | "mov" | "wmv" | "flv" | "webm" | "m4v" => "Videos",
+        // Audio
+        "mp3" | "wav" | "flac" | "aac" | "ogg" | "wma" | "m4a" => "Audio",
+        // Code
+        "rs" | "js" | "ts" | "py" | "java" | "cpp" | "c" | "h" | "go" | "rb" | "php" | "swift" | "kt" => "Code",
+        // Archives
+        "zip" | "tar" | "gz" | "rar" | "7z" | "bz2" | "xz" => "Archives",
+        // Other
+        _ => "Other",
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,264 @@
+use super::analyzer::file_category;
+use super::types::*;
+use chrono::{DateTime, Duration, Utc};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fs::Metadata;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+/// Files a scan records; past this it only sums sizes
+pub const MAX_CATALOG_FILES: usize = 1_000_000;
+
+/// Default and largest page sizes
+const DEFAULT_PAGE: usize = 100;
+const MAX_PAGE: usize = 1000;
+
+/// A file as recorded during a scan, kept small
+#[derive(Debug, Clone)]
+struct CatalogEntry {
+    path: PathBuf,
+    size: u64,
+    modified: Option<DateTime<Utc>>,
+    accessed: Option<DateTime<Utc>>,
+    owner: Option<u32>,
+}
+
+/// Every file a scan walked, for filtering after the fact
+#[derive(Debug, Default)]
+pub struct FileCatalog {
+    entries: Vec<CatalogEntry>,
+    truncated: bool,
+}
+
+lazy_static::lazy_static! {
+    static ref OWNER_NAMES: Mutex<HashMap<u32, Option<String>>> = Mutex::new(HashMap::new());
+}
+
+#[cfg(unix)]
+fn owner_id(metadata: &Metadata) -> Option<u32> {
+    use std::os::unix::fs::MetadataExt;
+    Some(metadata.uid())
+}
+
+#[cfg(not(unix))]
+fn owner_id(_metadata: &Metadata) -> Option<u32> {
+    None
+}
+
+#[cfg(unix)]
+fn lookup_owner(uid: u32) -> Option<String> {
+    nix::unistd::User::from_uid(nix::unistd::Uid::from_raw(uid))
+        .ok()
+        .flatten()
+        .map(|user| user.name)
+}
+
+#[cfg(not(unix))]
+fn lookup_owner(_uid: u32) -> Option<String> {
+    None
+}
+
+/// User name for an ID, or the ID itself if it has none
+fn owner_name(uid: u32) -> String {
+    OWNER_NAMES
+        .lock()
+        .unwrap()
+        .entry(uid)
+        .or_insert_with(|| lookup_owner(uid))
+        .clone()
+        .unwrap_or_else(|| uid.to_string())
+}
+
+fn time(value: std::io::Result<SystemTime>) -> Option<DateTime<Utc>> {
+    value.ok().map(DateTime::<Utc>::from)
+}
+
+fn extension(path: &Path) -> String {
+    path.extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
+
+impl FileCatalog {
+    pub fn add(&mut self, path: PathBuf, metadata: &Metadata) {
+        if self.entries.len() >= MAX_CATALOG_FILES {
+            self.truncated = true;
+            return;
+        }
+        self.entries.push(CatalogEntry {
+            path,
+            size: metadata.len(),
+            modified: time(metadata.modified()),
+            accessed: time(metadata.accessed()),
+            owner: owner_id(metadata),
+        });
+    }
+
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// Combined size of the files `matches` accepts
+    pub fn size_matching(&self, matches: impl Fn(&Path) -> bool) -> u64 {
+        self.entries.iter().filter(|e| matches(&e.path)).map(|e| e.size).sum()
+    }
+
+    /// Matching files, sorted, from `query.offset`; also returns how many
+    /// matched and their combined size
+    pub fn query(&self, query: &FileQuery, now: DateTime<Utc>) -> (Vec<CatalogFile>, usize, u64) {
+        let extensions: Vec<String> = query
+            .extensions
+            .iter()
+            .flat_map(|list| list.split(','))
+            .map(|e| e.trim().trim_start_matches('.').to_lowercase())
+            .filter(|e| !e.is_empty())
+            .collect();
+        let category = query.category.as_deref().map(str::trim).filter(|c| !c.is_empty());
+        let owner = query.owner.as_deref().map(str::trim).filter(|o| !o.is_empty());
+        let prefix = query.path.as_deref().filter(|p| !p.trim().is_empty()).map(Path::new);
+        let older_than = query.older_than_days.map(|days| now - Duration::days(days as i64));
+        let newer_than = query.newer_than_days.map(|days| now - Duration::days(days as i64));
+        let age = |entry: &CatalogEntry| match query.age_by {
+            AgeBasis::Modified => entry.modified,
+            AgeBasis::Accessed => entry.accessed,
+        };
+
+        let mut matches: Vec<&CatalogEntry> = self
+            .entries
+            .iter()
+            .filter(|e| query.min_size.is_none_or(|min| e.size >= min))
+            .filter(|e| query.max_size.is_none_or(|max| e.size <= max))
+            .filter(|e| older_than.is_none_or(|cutoff| age(e).is_some_and(|at| at <= cutoff)))
+            .filter(|e| newer_than.is_none_or(|cutoff| age(e).is_some_and(|at| at >= cutoff)))
+            .filter(|e| extensions.is_empty() || extensions.contains(&extension(&e.path)))
+            .filter(|e| category.is_none_or(|c| file_category(&e.path).eq_ignore_ascii_case(c)))
+            .filter(|e| prefix.is_none_or(|p| e.path.starts_with(p)))
+            .filter(|e| {
+                owner.is_none_or(|o| {
+                    e.owner.is_some_and(|uid| uid.to_string() == o || owner_name(uid) == o)
+                })
+            })
+            .collect();
+
+        let total = matches.len();
+        let total_size = matches.iter().map(|e| e.size).sum();
+
+        let descending = match query.order {
+            Some(order) => order == SortOrder::Desc,
+            None => !matches!(query.sort, FileSort::Name | FileSort::Path),
+        };
+        let name = |e: &CatalogEntry| e.path.file_name().map(|n| n.to_string_lossy().to_lowercase());
+        matches.sort_by(|a, b| {
+            let ordering = match query.sort {
+                FileSort::Size => a.size.cmp(&b.size),
+                FileSort::Modified => a.modified.cmp(&b.modified),
+                FileSort::Accessed => a.accessed.cmp(&b.accessed),
+                FileSort::Name => name(a).cmp(&name(b)),
+                FileSort::Path => Ordering::Equal,
+            };
+            let ordering = if descending { ordering.reverse() } else { ordering };
+            ordering.then_with(|| a.path.cmp(&b.path))
+        });
+        if query.sort == FileSort::Path && descending {
+            matches.reverse();
+        }
+
+        let limit = query.limit.unwrap_or(DEFAULT_PAGE).clamp(1, MAX_PAGE);
+        let files = matches
+            .into_iter()
+            .skip(query.offset.unwrap_or(0))
+            .take(limit)
+            .map(|e| CatalogFile {
+                name: e.path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default(),
+                size: e.size,
+                extension: extension(&e.path),
+                category: file_category(&e.path).to_string(),
+                modified: e.modified,
+                accessed: e.accessed,
+                owner: e.owner.map(owner_name),
+                path: e.path.clone(),
+            })
+            .collect();
+        (files, total, total_size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn write(dir: &TempDir, name: &str, size: usize, days_old: u64) -> PathBuf {
+        let path = dir.path().join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, vec![b'x'; size]).unwrap();
+        let file = fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(SystemTime::now() - std::time::Duration::from_secs(days_old * 24 * 3600))
+            .unwrap();
+        path
+    }
+
+    #[test]
+    fn test_filter_sort_and_paginate() {
+        let dir = TempDir::new().unwrap();
+        let paths = [
+            write(&dir, "movies/old.mkv", 5000, 400),
+            write(&dir, "movies/new.mp4", 8000, 2),
+            write(&dir, "movies/small.mp4", 10, 500),
+            write(&dir, "docs/report.pdf", 3000, 800),
+        ];
+        let mut catalog = FileCatalog::default();
+        for path in &paths {
+            catalog.add(path.clone(), &fs::metadata(path).unwrap());
+        }
+        let now = Utc::now();
+
+        // Videos over 1 KB untouched for a year
+        let query = FileQuery {
+            min_size: Some(1000),
+            older_than_days: Some(365),
+            category: Some("videos".to_string()),
+            ..Default::default()
+        };
+        let (files, total, total_size) = catalog.query(&query, now);
+        assert_eq!((total, total_size), (1, 5000));
+        assert_eq!(files[0].name, "old.mkv");
+        assert_eq!(files[0].category, "Videos");
+
+        // Largest first by default, paged
+        let query = FileQuery { offset: Some(1), limit: Some(2), ..Default::default() };
+        let (files, total, _) = catalog.query(&query, now);
+        assert_eq!(total, 4);
+        let names: Vec<&str> = files.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, ["old.mkv", "report.pdf"]);
+
+        let query = FileQuery {
+            extensions: Some(".MP4, pdf".to_string()),
+            sort: FileSort::Name,
+            ..Default::default()
+        };
+        let names: Vec<String> = catalog.query(&query, now).0.into_iter().map(|f| f.name).collect();
+        assert_eq!(names, ["new.mp4", "report.pdf", "small.mp4"]);
+
+        let query = FileQuery {
+            path: Some(dir.path().join("movies").to_string_lossy().to_string()),
+            sort: FileSort::Modified,
+            order: Some(SortOrder::Asc),
+            ..Default::default()
+        };
+        let names: Vec<String> = catalog.query(&query, now).0.into_iter().map(|f| f.name).collect();
+        assert_eq!(names, ["small.mp4", "old.mkv", "new.mp4"]);
+
+        #[cfg(unix)]
+        {
+            let me = fs::metadata(&paths[0]).map(|m| std::os::unix::fs::MetadataExt::uid(&m)).unwrap();
+            let query = FileQuery { owner: Some(me.to_string()), ..Default::default() };
+            assert_eq!(catalog.query(&query, now).1, 4);
+            let query = FileQuery { owner: Some("no-such-user-here".to_string()), ..Default::default() };
+            assert_eq!(catalog.query(&query, now).1, 0);
+        }
+    }
+}
@@ -0,0 +1,378 @@
+use super::types::*;
+use crate::ignore_rules::IgnoreRules;
+use crate::mounts::MountGuard;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Removes a directory's contents, leaving the directory in place
+#[cfg(windows)]
+const CLEAR_CONTENTS: &str = "Remove-Item -Recurse -Force \"{path}\\*\"";
+#[cfg(not(windows))]
+const CLEAR_CONTENTS: &str = "rm -rf \"{path}\"/*";
+
+/// Removes entries not modified in the last week, so files in use survive
+#[cfg(windows)]
+const CLEAR_STALE: &str = "Get-ChildItem \"{path}\" | Where-Object LastWriteTime -lt (Get-Date).AddDays(-7) | Remove-Item -Recurse -Force";
+#[cfg(not(windows))]
+const CLEAR_STALE: &str = "find \"{path}\" -mindepth 1 -maxdepth 1 -mtime +7 -exec rm -rf {} +";
+
+/// A kind of directory that can be reclaimed, such as a package manager's
+/// cache, and how to do it safely
+#[derive(Debug, Clone)]
+pub struct CleanupRecognizer {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub category: CleanupCategory,
+    pub safety_level: SafetyLevel,
+    pub description: &'static str,
+    pub consequence: &'static str,
+    /// Shell command that reclaims the space; `{path}` is the matched
+    /// directory and `{parent}` the directory containing it
+    pub command: &'static str,
+    /// Where the directory lives for the current user
+    pub locations: Vec<PathBuf>,
+    /// Matches any directory with this name next to this file, e.g. a
+    /// `target` directory beside `Cargo.toml`
+    pub beside: Option<(&'static str, &'static str)>,
+}
+
+impl CleanupRecognizer {
+    fn matches(&self, dir: &Path) -> bool {
+        if self.locations.iter().any(|location| location == dir) {
+            return true;
+        }
+        match (self.beside, dir.file_name(), dir.parent()) {
+            (Some((name, marker)), Some(file_name), Some(parent)) => {
+                file_name == name && parent.join(marker).is_file()
+            }
+            _ => false,
+        }
+    }
+
+    /// The cleanup command for a directory this recognizer matched
+    pub fn command_for(&self, dir: &Path) -> String {
+        let parent = dir.parent().unwrap_or(dir);
+        self.command
+            .replace("{path}", &dir.display().to_string())
+            .replace("{parent}", &parent.display().to_string())
+    }
+}
+
+/// Recognizes application caches, package-manager stores, build output and
+/// temporary directories that can be cleaned up
+#[derive(Debug, Clone)]
+pub struct FileCategorizer {
+    recognizers: Arc<Vec<CleanupRecognizer>>,
+}
+
+impl FileCategorizer {
+    /// Recognizers for the locations used by the user whose home is `home`
+    pub fn new(home: &Path) -> Self {
+        Self {
+            recognizers: Arc::new(recognizers(home)),
+        }
+    }
+
+    /// The recognizer a directory falls under, if any
+    pub fn recognize(&self, dir: &Path) -> Option<&CleanupRecognizer> {
+        self.recognizers.iter().find(|r| r.matches(dir))
+    }
+
+    /// Every known cache and temporary location that exists, with its size
+    pub fn known_locations(&self, guard: &MountGuard) -> Vec<CleanupCandidate> {
+        let mut candidates = Vec::new();
+        for recognizer in self.recognizers.iter() {
+            for location in &recognizer.locations {
+                if location.is_dir() && guard.allows(location) {
+                    candidates.push(candidate(recognizer, location, guard));
+                }
+            }
+        }
+        candidates
+    }
+
+    /// Recognized directories below `root`. The walk doesn't descend into a
+    /// directory once it is recognized.
+    pub fn find_candidates(
+        &self,
+        root: &Path,
+        rules: IgnoreRules,
+        max_depth: Option<usize>,
+        guard: &MountGuard,
+    ) -> Vec<CleanupCandidate> {
+        if let Some(recognizer) = self.recognize(root) {
+            return vec![candidate(recognizer, root, guard)];
+        }
+
+        let found: Arc<Mutex<Vec<(PathBuf, usize)>>> = Arc::default();
+        let categorizer = self.clone();
+        let entry_guard = guard.clone();
+        let recorded = found.clone();
+        let walker = rules
+            .walker(root)
+            .max_depth(max_depth)
+            .filter_entry(move |entry| {
+                if !entry_guard.allows(entry.path()) {
+                    return false;
+                }
+                if !entry.file_type().is_some_and(|t| t.is_dir()) {
+                    return true;
+                }
+                match categorizer.recognizers.iter().position(|r| r.matches(entry.path())) {
+                    Some(index) => {
+                        recorded.lock().unwrap().push((entry.path().to_path_buf(), index));
+                        false
+                    }
+                    None => true,
+                }
+            })
+            .build();
+        for _ in walker {}
+
+        let found = std::mem::take(&mut *found.lock().unwrap());
+        found
+            .into_iter()
+            .map(|(path, index)| candidate(&self.recognizers[index], &path, guard))
+            .collect()
+    }
+}
+
+fn candidate(recognizer: &CleanupRecognizer, dir: &Path, guard: &MountGuard) -> CleanupCandidate {
+    // Caches are counted in full, whatever ignore files say
+    let guard = guard.clone();
+    let size = IgnoreRules::NONE
+        .walker(dir)
+        .filter_entry(move |entry| guard.allows(entry.path()))
+        .build()
+        .filter_map(|e| e.ok())
+        .filter_map(|e| e.metadata().ok())
+        .filter(|m| m.is_file())
+        .map(|m| m.len())
+        .sum();
+
+    CleanupCandidate {
+        path: dir.to_path_buf(),
+        size,
+        category: recognizer.category.clone(),
+        safety_level: recognizer.safety_level.clone(),
+        description: recognizer.description.to_string(),
+        estimated_savings: size,
+        name: recognizer.name.to_string(),
+        consequence: recognizer.consequence.to_string(),
+        recognizer: Some(recognizer.id.to_string()),
+        cleanup_command: Some(recognizer.command_for(dir)),
+    }
+}
+
+/// Locations by platform, relative to the home directory unless absolute
+fn platform_paths(
+    home: &Path,
+    linux: &[&str],
+    macos: &[&str],
+    windows: &[&str],
+) -> Vec<PathBuf> {
+    let paths = if cfg!(target_os = "windows") {
+        windows
+    } else if cfg!(target_os = "macos") {
+        macos
+    } else {
+        linux
+    };
+    paths.iter().map(|path| home.join(path)).collect()
+}
+
+fn recognizers(home: &Path) -> Vec<CleanupRecognizer> {
+    let mut temp_dirs = vec![std::env::temp_dir()];
+    if cfg!(unix) && !temp_dirs.contains(&PathBuf::from("/var/tmp")) {
+        temp_dirs.push(PathBuf::from("/var/tmp"));
+    }
+
+    vec![
+        CleanupRecognizer {
+            id: "npm-cache",
+            name: "npm Cache",
+            category: CleanupCategory::Cache,
+            safety_level: SafetyLevel::Safe,
+            description: "Packages npm downloaded for past installs",
+            consequence: "Safe to remove. Packages are downloaded again when next installed.",
+            command: "npm cache clean --force",
+            locations: platform_paths(
+                home,
+                &[".npm/_cacache"],
+                &[".npm/_cacache"],
+                &["AppData/Local/npm-cache/_cacache"],
+            ),
+            beside: None,
+        },
+        CleanupRecognizer {
+            id: "yarn-cache",
+            name: "Yarn Cache",
+            category: CleanupCategory::Cache,
+            safety_level: SafetyLevel::Safe,
+            description: "Packages Yarn downloaded for past installs",
+            consequence: "Safe to remove. Packages are downloaded again when next installed.",
+            command: "yarn cache clean",
+            locations: platform_paths(
+                home,
+                &[".cache/yarn", ".yarn/berry/cache"],
+                &["Library/Caches/Yarn", ".yarn/berry/cache"],
+                &["AppData/Local/Yarn/Cache", ".yarn/berry/cache"],
+            ),
+            beside: None,
+        },
+        CleanupRecognizer {
+            id: "pnpm-store",
+            name: "pnpm Store",
+            category: CleanupCategory::Cache,
+            safety_level: SafetyLevel::Safe,
+            description: "pnpm's content-addressed package store",
+            consequence: "Only packages no project references are removed.",
+            command: "pnpm store prune",
+            locations: platform_paths(
+                home,
+                &[".local/share/pnpm/store"],
+                &["Library/pnpm/store"],
+                &["AppData/Local/pnpm/store"],
+            ),
+            beside: None,
+        },
+        CleanupRecognizer {
+            id: "pip-cache",
+            name: "pip Cache",
+            category: CleanupCategory::Cache,
+            safety_level: SafetyLevel::Safe,
+            description: "Wheels and downloads pip kept from past installs",
+            consequence: "Safe to remove. Packages are downloaded again when next installed.",
+            command: "pip cache purge",
+            locations: platform_paths(
+                home,
+                &[".cache/pip"],
+                &["Library/Caches/pip"],
+                &["AppData/Local/pip/Cache"],
+            ),
+            beside: None,
+        },
+        CleanupRecognizer {
+            id: "cargo-target",
+            name: "Rust Build Output",
+            category: CleanupCategory::Projects,
+            safety_level: SafetyLevel::Safe,
+            description: "Compiled artifacts in a Cargo project's target directory",
+            consequence: "Safe to remove. The next build compiles the project from scratch.",
+            command: "cargo clean --manifest-path \"{parent}/Cargo.toml\"",
+            locations: Vec::new(),
+            beside: Some(("target", "Cargo.toml")),
+        },
+        CleanupRecognizer {
+            id: "docker-images",
+            name: "Docker Images and Layers",
+            category: CleanupCategory::AppData,
+            safety_level: SafetyLevel::Maybe,
+            description: "Docker image layers, container filesystems and build cache",
+            consequence: "Stopped containers, dangling images and the build cache are removed. Images in use are kept.",
+            command: "docker system prune",
+            locations: if cfg!(target_os = "linux") {
+                vec![PathBuf::from("/var/lib/docker/overlay2")]
+            } else {
+                platform_paths(
+                    home,
+                    &[],
+                    &["Library/Containers/com.docker.docker/Data/vms"],
+                    &["AppData/Local/Docker/wsl"],
+                )
+            },
+            beside: None,
+        },
+        CleanupRecognizer {
+            id: "browser-cache",
+            name: "Browser Cache",
+            category: CleanupCategory::Cache,
+            safety_level: SafetyLevel::Safe,
+            description: "Web pages and media cached by a browser",
+            consequence: "Safe to remove with the browser closed. Sites load slower until the cache refills.",
+            command: CLEAR_CONTENTS,
+            locations: platform_paths(
+                home,
+                &[
+                    ".cache/google-chrome",
+                    ".cache/chromium",
+                    ".cache/BraveSoftware",
+                    ".cache/microsoft-edge",
+                    ".cache/mozilla/firefox",
+                ],
+                &[
+                    "Library/Caches/Google/Chrome",
+                    "Library/Caches/Chromium",
+                    "Library/Caches/BraveSoftware",
+                    "Library/Caches/Microsoft Edge",
+                    "Library/Caches/Firefox",
+                    "Library/Caches/com.apple.Safari",
+                ],
+                &[
+                    "AppData/Local/Google/Chrome/User Data/Default/Cache",
+                    "AppData/Local/Chromium/User Data/Default/Cache",
+                    "AppData/Local/BraveSoftware/Brave-Browser/User Data/Default/Cache",
+                    "AppData/Local/Microsoft/Edge/User Data/Default/Cache",
+                ],
+            ),
+            beside: None,
+        },
+        CleanupRecognizer {
+            id: "os-temp",
+            name: "Temporary Files",
+            category: CleanupCategory::Temporary,
+            safety_level: SafetyLevel::Safe,
+            description: "The system's temporary directory",
+            consequence: "Only entries untouched for a week are removed, so running programs keep their files.",
+            command: CLEAR_STALE,
+            locations: temp_dirs,
+            beside: None,
+        },
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mounts::MountPolicy;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_recognizes_caches_and_build_output() {
+        let home = TempDir::new().unwrap();
+        let pip = platform_paths(home.path(), &[".cache/pip"], &["Library/Caches/pip"], &["AppData/Local/pip/Cache"])
+            .remove(0);
+        fs::create_dir_all(pip.join("http")).unwrap();
+        fs::write(pip.join("http/wheel"), vec![0u8; 300]).unwrap();
+
+        let project = home.path().join("code/app");
+        fs::create_dir_all(project.join("target/debug")).unwrap();
+        fs::write(project.join("Cargo.toml"), "[package]").unwrap();
+        fs::write(project.join("target/debug/app"), vec![0u8; 1000]).unwrap();
+        // Not a Cargo project
+        fs::create_dir_all(home.path().join("code/site/target")).unwrap();
+
+        let categorizer = FileCategorizer::new(home.path());
+        let guard = MountGuard::new(home.path(), &MountPolicy::default());
+        let mut found = categorizer.find_candidates(home.path(), IgnoreRules::DISK_USAGE, None, &guard);
+        found.sort_by(|a, b| a.path.cmp(&b.path));
+
+        assert_eq!(found.len(), 2);
+        let target = found.iter().find(|c| c.recognizer.as_deref() == Some("cargo-target")).unwrap();
+        assert_eq!(target.path, project.join("target"));
+        assert_eq!(target.size, 1000);
+        assert_eq!(target.category, CleanupCategory::Projects);
+        assert_eq!(
+            target.cleanup_command.as_deref(),
+            Some(format!("cargo clean --manifest-path \"{}/Cargo.toml\"", project.display()).as_str())
+        );
+        let cache = found.iter().find(|c| c.recognizer.as_deref() == Some("pip-cache")).unwrap();
+        assert_eq!((cache.size, cache.safety_level.clone()), (300, SafetyLevel::Safe));
+        assert_eq!(cache.cleanup_command.as_deref(), Some("pip cache purge"));
+
+        let known = categorizer.known_locations(&guard);
+        assert!(known.iter().any(|c| c.path == pip));
+        assert!(categorizer.recognize(&project).is_none());
+    }
+}
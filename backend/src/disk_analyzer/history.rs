@@ -0,0 +1,275 @@
+use super::types::*;
+use crate::db::{Database, DiskSnapshotRecord};
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Runs periodic disk scans in the background and stores per-path size
+/// snapshots so growth can be charted over time
+pub struct DiskScanScheduler {
+    db: Database,
+    config: ScheduledScanConfig,
+}
+
+impl DiskScanScheduler {
+    pub fn new(db: Database, config: ScheduledScanConfig) -> Self {
+        Self { db, config }
+    }
+
+    /// Spawn the background scan loop. Returns `None` when scheduling is disabled.
+    pub fn spawn(self) -> Option<tokio::task::JoinHandle<()>> {
+        if !self.config.enabled || self.config.interval_secs == 0 || self.config.paths.is_empty() {
+            tracing::info!("Scheduled disk analysis disabled");
+            return None;
+        }
+
+        Some(tokio::spawn(async move {
+            let period = tokio::time::Duration::from_secs(self.config.interval_secs);
+
+            // Resume the schedule from the last stored scan so restarts don't rescan immediately
+            let first_delay = match self.db.get_latest_disk_snapshot_time().await {
+                Ok(Some(last)) => (last + Duration::seconds(self.config.interval_secs as i64) - Utc::now())
+                    .to_std()
+                    .unwrap_or_default(),
+                _ => tokio::time::Duration::ZERO,
+            };
+
+            let mut interval = tokio::time::interval_at(tokio::time::Instant::now() + first_delay, period);
+            loop {
+                interval.tick().await;
+                match self.run_once().await {
+                    Ok(count) => tracing::info!("Scheduled disk scan stored {} snapshots", count),
+                    Err(e) => tracing::warn!("Scheduled disk scan failed: {}", e),
+                }
+            }
+        }))
+    }
+
+    /// Scan all configured paths once, store the snapshots and prune old history
+    pub async fn run_once(&self) -> Result<usize> {
+        let config = self.config.clone();
+        let snapshots =
+            tokio::task::spawn_blocking(move || collect_snapshots(&config, Utc::now())).await?;
+
+        let scan_id = uuid::Uuid::new_v4().to_string();
+        let records: Vec<DiskSnapshotRecord> = snapshots
+            .iter()
+            .map(|s| DiskSnapshotRecord {
+                id: uuid::Uuid::new_v4().to_string(),
+                scan_id: scan_id.clone(),
+                path: s.path.to_string_lossy().to_string(),
+                size: s.size as i64,
+                file_count: s.file_count as i64,
+                captured_at: s.captured_at,
+            })
+            .collect();
+
+        self.db.insert_disk_snapshots(&records).await?;
+
+        let cutoff = Utc::now() - Duration::days(self.config.retention_days);
+        self.db.delete_disk_snapshots_before(cutoff).await?;
+
+        Ok(records.len())
+    }
+}
+
+/// Walk each configured root once and attribute every file size to the root and
+/// to each of its ancestors up to `snapshot_depth` levels below the root
+pub fn collect_snapshots(config: &ScheduledScanConfig, captured_at: DateTime<Utc>) -> Vec<DiskSnapshot> {
+    let mut snapshots = Vec::new();
+
+    for root in &config.paths {
+        if !root.exists() {
+            continue;
+        }
+
+        let mut totals: HashMap<PathBuf, (u64, usize)> = HashMap::new();
+        totals.insert(root.clone(), (0, 0));
+
+        for entry in config.ignore_rules.walker(root).build().filter_map(|e| e.ok())
+        {
+            let metadata = match entry.metadata() {
+                Ok(m) => m,
+                Err(_) => continue,
+            };
+            if !metadata.is_file() {
+                continue;
+            }
+
+            let size = metadata.len();
+            for ancestor in tracked_ancestors(root, entry.path(), config.snapshot_depth) {
+                let total = totals.entry(ancestor).or_insert((0, 0));
+                total.0 += size;
+                total.1 += 1;
+            }
+        }
+
+        for (path, (size, file_count)) in totals {
+            if &path == root || size >= config.min_snapshot_size {
+                snapshots.push(DiskSnapshot {
+                    path,
+                    size,
+                    file_count,
+                    captured_at,
+                });
+            }
+        }
+    }
+
+    snapshots.sort_by(|a, b| a.path.cmp(&b.path));
+    snapshots
+}
+
+/// The root plus each directory between the root and `file` up to `depth` levels deep
+pub(super) fn tracked_ancestors(root: &Path, file: &Path, depth: usize) -> Vec<PathBuf> {
+    let mut ancestors = vec![root.to_path_buf()];
+    let relative = match file.strip_prefix(root) {
+        Ok(r) => r,
+        Err(_) => return ancestors,
+    };
+
+    // The last component is the file itself
+    let components: Vec<_> = relative.components().collect();
+    let dir_components = components.len().saturating_sub(1);
+
+    let mut current = root.to_path_buf();
+    for component in components.iter().take(dir_components.min(depth)) {
+        current = current.join(component);
+        ancestors.push(current.clone());
+    }
+
+    ancestors
+}
+
+/// Group snapshots by path and compute growth between the first and last point,
+/// sorted by absolute growth (largest change first)
+pub fn compute_trends(snapshots: &[DiskSnapshot]) -> Vec<DiskTrend> {
+    let mut by_path: HashMap<&Path, Vec<&DiskSnapshot>> = HashMap::new();
+    for snapshot in snapshots {
+        by_path.entry(snapshot.path.as_path()).or_default().push(snapshot);
+    }
+
+    let mut trends: Vec<DiskTrend> = by_path
+        .into_iter()
+        .map(|(path, mut points)| {
+            points.sort_by_key(|p| p.captured_at);
+            let first_size = points.first().map(|p| p.size).unwrap_or(0);
+            let last_size = points.last().map(|p| p.size).unwrap_or(0);
+            let growth_bytes = last_size as i64 - first_size as i64;
+
+            DiskTrend {
+                path: path.to_path_buf(),
+                first_size,
+                last_size,
+                growth_bytes,
+                growth_percentage: if first_size > 0 {
+                    (growth_bytes as f64 / first_size as f64) * 100.0
+                } else {
+                    0.0
+                },
+                points: points
+                    .iter()
+                    .map(|p| TrendPoint {
+                        captured_at: p.captured_at,
+                        size: p.size,
+                    })
+                    .collect(),
+            }
+        })
+        .collect();
+
+    trends.sort_by(|a, b| {
+        b.growth_bytes
+            .unsigned_abs()
+            .cmp(&a.growth_bytes.unsigned_abs())
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    trends
+}
+
+impl From<DiskSnapshotRecord> for DiskSnapshot {
+    fn from(record: DiskSnapshotRecord) -> Self {
+        Self {
+            path: PathBuf::from(record.path),
+            size: record.size.max(0) as u64,
+            file_count: record.file_count.max(0) as usize,
+            captured_at: record.captured_at,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_collect_snapshots_attributes_sizes_to_ancestors() {
+        let temp_dir = TempDir::new().unwrap();
+        let nested = temp_dir.path().join("project").join("node_modules");
+        fs::create_dir_all(&nested).unwrap();
+        fs::write(nested.join("lib.js"), "A".repeat(100)).unwrap();
+        fs::write(temp_dir.path().join("project").join("main.rs"), "A".repeat(20)).unwrap();
+
+        let config = ScheduledScanConfig {
+            paths: vec![temp_dir.path().to_path_buf()],
+            snapshot_depth: 2,
+            min_snapshot_size: 0,
+            ..Default::default()
+        };
+
+        let snapshots = collect_snapshots(&config, Utc::now());
+        let size_of = |p: &Path| snapshots.iter().find(|s| s.path == p).map(|s| s.size);
+
+        assert_eq!(size_of(temp_dir.path()), Some(120));
+        assert_eq!(size_of(&temp_dir.path().join("project")), Some(120));
+        assert_eq!(size_of(&nested), Some(100));
+    }
+
+    #[test]
+    fn test_collect_snapshots_skips_small_directories() {
+        let temp_dir = TempDir::new().unwrap();
+        let small = temp_dir.path().join("small");
+        fs::create_dir_all(&small).unwrap();
+        fs::write(small.join("file.txt"), "tiny").unwrap();
+
+        let config = ScheduledScanConfig {
+            paths: vec![temp_dir.path().to_path_buf()],
+            min_snapshot_size: 1024,
+            ..Default::default()
+        };
+
+        let snapshots = collect_snapshots(&config, Utc::now());
+        assert_eq!(snapshots.len(), 1);
+        assert_eq!(snapshots[0].path, temp_dir.path());
+    }
+
+    #[test]
+    fn test_compute_trends_orders_by_growth() {
+        let start = Utc::now() - Duration::days(7);
+        let end = Utc::now();
+        let snapshot = |path: &str, size: u64, at: DateTime<Utc>| DiskSnapshot {
+            path: PathBuf::from(path),
+            size,
+            file_count: 1,
+            captured_at: at,
+        };
+
+        let snapshots = vec![
+            snapshot("/home/node_modules", 1000, start),
+            snapshot("/home/node_modules", 5000, end),
+            snapshot("/home/docs", 2000, start),
+            snapshot("/home/docs", 1500, end),
+        ];
+
+        let trends = compute_trends(&snapshots);
+        assert_eq!(trends.len(), 2);
+        assert_eq!(trends[0].path, PathBuf::from("/home/node_modules"));
+        assert_eq!(trends[0].growth_bytes, 4000);
+        assert_eq!(trends[0].growth_percentage, 400.0);
+        assert_eq!(trends[1].growth_bytes, -500);
+        assert_eq!(trends[1].points.len(), 2);
+    }
+}
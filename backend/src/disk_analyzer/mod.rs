@@ -1,12 +1,24 @@
 // Disk Analyzer Module
 // Provides disk space analysis functionality with directory scanning,
-// file categorization, and cleanup candidate identification
+// file categorization, cleanup candidate identification, and scheduled
+// scans with size trend history, incremental treemap scans and a volume
+// list with SMART health, queries over the files the last scan found, and
+// recognizers for caches, build output and temporary files
 
 mod analyzer;
+mod categorizer;
+mod catalog;
+mod history;
+mod treemap;
 mod types;
+mod volumes;
 
 #[cfg(test)]
 mod tests;
 
-pub use analyzer::DiskAnalyzer;
+pub use analyzer::{file_category, DiskAnalyzer};
+pub use categorizer::FileCategorizer;
+pub use history::{collect_snapshots, compute_trends, DiskScanScheduler};
+pub use treemap::{TreemapBuilder, TreemapScan, TreemapScans};
 pub use types::*;
+pub use volumes::{is_removable_device, list_volumes};
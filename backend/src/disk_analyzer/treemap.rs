@@ -0,0 +1,633 @@
+use super::analyzer::file_category;
+use super::catalog::FileCatalog;
+use super::history::tracked_ancestors;
+use super::types::*;
+use std::collections::HashMap;
+use std::fs::Metadata;
+use std::path::{Path, PathBuf};
+use crate::events::Event;
+use crate::mounts::{MountGuard, SkippedMount};
+use ignore::WalkState;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Files walked between updates of the shared tree
+const BATCH_FILES: usize = 2000;
+/// Longest a batch is held back, so slow volumes still show progress
+const BATCH_INTERVAL: Duration = Duration::from_millis(250);
+/// Finished scans kept around for the UI
+const MAX_FINISHED_SCANS: usize = 8;
+/// Time between progress events on the event bus
+const PROGRESS_EVENT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Sizes below one tracked directory
+#[derive(Debug, Default)]
+struct DirTotals {
+    size: u64,
+    file_count: usize,
+    categories: HashMap<&'static str, u64>,
+    /// Largest files directly in the directory; the rest are only summed
+    files: Vec<(PathBuf, u64)>,
+    untracked_files: (u64, usize),
+}
+
+impl DirTotals {
+    fn dominant_category(&self) -> &'static str {
+        self.categories
+            .iter()
+            .max_by_key(|(name, size)| (**size, std::cmp::Reverse(**name)))
+            .map(|(name, _)| *name)
+            .unwrap_or("Other")
+    }
+}
+
+/// Accumulates file sizes into a tree of directories down to `depth`
+#[derive(Debug)]
+pub struct TreemapBuilder {
+    config: TreemapConfig,
+    dirs: HashMap<PathBuf, DirTotals>,
+    files_scanned: usize,
+    bytes_scanned: u64,
+}
+
+impl TreemapBuilder {
+    pub fn new(config: TreemapConfig) -> Self {
+        let mut dirs = HashMap::new();
+        dirs.insert(config.root.clone(), DirTotals::default());
+        Self {
+            config,
+            dirs,
+            files_scanned: 0,
+            bytes_scanned: 0,
+        }
+    }
+
+    pub fn add_file(&mut self, path: &Path, size: u64) {
+        let category = file_category(path);
+        let ancestors = tracked_ancestors(&self.config.root, path, self.config.depth);
+        let parent_tracked = ancestors.len() <= self.config.depth
+            && ancestors.last().map(PathBuf::as_path) == path.parent();
+
+        for ancestor in &ancestors {
+            let totals = self.dirs.entry(ancestor.clone()).or_default();
+            totals.size += size;
+            totals.file_count += 1;
+            *totals.categories.entry(category).or_insert(0) += size;
+        }
+
+        // Files directly in a tracked directory get their own node
+        if parent_tracked {
+            let keep = self.config.top_n.max(1);
+            let totals = self.dirs.get_mut(ancestors.last().unwrap()).unwrap();
+            totals.files.push((path.to_path_buf(), size));
+            if totals.files.len() >= keep * 2 {
+                totals.files.sort_by_key(|(_, size)| std::cmp::Reverse(*size));
+                for (_, size) in totals.files.drain(keep..) {
+                    totals.untracked_files.0 += size;
+                    totals.untracked_files.1 += 1;
+                }
+            }
+        }
+
+        self.files_scanned += 1;
+        self.bytes_scanned += size;
+    }
+
+    pub fn files_scanned(&self) -> usize {
+        self.files_scanned
+    }
+
+    pub fn bytes_scanned(&self) -> u64 {
+        self.bytes_scanned
+    }
+
+    /// The tree as accumulated so far, with children pruned to `top_n`
+    pub fn snapshot(&self) -> TreemapNode {
+        let mut subdirs: HashMap<&Path, Vec<&Path>> = HashMap::new();
+        for path in self.dirs.keys() {
+            if *path != self.config.root {
+                if let Some(parent) = path.parent() {
+                    subdirs.entry(parent).or_default().push(path);
+                }
+            }
+        }
+        self.dir_node(&self.config.root, &subdirs)
+    }
+
+    fn dir_node(&self, path: &Path, subdirs: &HashMap<&Path, Vec<&Path>>) -> TreemapNode {
+        let totals = &self.dirs[path];
+        let mut children: Vec<TreemapNode> = subdirs
+            .get(path)
+            .into_iter()
+            .flatten()
+            .map(|dir| self.dir_node(dir, subdirs))
+            .collect();
+        children.extend(totals.files.iter().map(|(file, size)| TreemapNode {
+            path: file.clone(),
+            name: node_name(file),
+            kind: TreemapNodeKind::File,
+            size: *size,
+            file_count: 1,
+            category: file_category(file).to_string(),
+            children: Vec::new(),
+        }));
+        children.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.name.cmp(&b.name)));
+
+        let (mut other_size, mut other_count) = totals.untracked_files;
+        let mut other_items = other_count;
+        let keep = self.config.top_n.max(1);
+        if children.len() > keep {
+            for pruned in children.drain(keep..) {
+                other_size += pruned.size;
+                other_count += pruned.file_count;
+                other_items += 1;
+            }
+        }
+        if other_size > 0 {
+            children.push(TreemapNode {
+                path: path.to_path_buf(),
+                name: format!("{} more items", other_items),
+                kind: TreemapNodeKind::Other,
+                size: other_size,
+                file_count: other_count,
+                category: "Other".to_string(),
+                children: Vec::new(),
+            });
+        }
+
+        TreemapNode {
+            path: path.to_path_buf(),
+            name: node_name(path),
+            kind: TreemapNodeKind::Directory,
+            size: totals.size,
+            file_count: totals.file_count,
+            category: totals.dominant_category().to_string(),
+            children,
+        }
+    }
+}
+
+fn node_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+/// A treemap scan running in the background. Walker threads read
+/// directories in parallel, taking work from each other when their own runs
+/// out, and hand files to the scan's thread, which adds them to the tree.
+pub struct TreemapScan {
+    id: String,
+    started: Instant,
+    cancelled: AtomicBool,
+    paths_visited: AtomicU64,
+    state: Mutex<ScanState>,
+}
+
+struct ScanState {
+    builder: TreemapBuilder,
+    /// Every file walked, for queries once the scan is done
+    catalog: FileCatalog,
+    status: TreemapScanStatus,
+    error: Option<String>,
+    elapsed: Option<Duration>,
+    skipped_mounts: Vec<SkippedMount>,
+}
+
+impl ScanState {
+    fn add(&mut self, batch: &mut Vec<(PathBuf, Metadata)>) {
+        for (path, metadata) in batch.drain(..) {
+            self.builder.add_file(&path, metadata.len());
+            self.catalog.add(path, &metadata);
+        }
+    }
+}
+
+impl TreemapScan {
+    pub fn progress(&self) -> TreemapProgress {
+        let state = self.state.lock().unwrap();
+        let elapsed = state.elapsed.unwrap_or_else(|| self.started.elapsed());
+        TreemapProgress {
+            scan_id: self.id.clone(),
+            status: state.status,
+            files_scanned: state.builder.files_scanned(),
+            bytes_scanned: state.builder.bytes_scanned(),
+            paths_per_sec: self.rate(elapsed),
+            elapsed_ms: elapsed.as_millis() as u64,
+            error: state.error.clone(),
+            root: state.builder.snapshot(),
+        }
+    }
+
+    /// Status and counters, without building the tree
+    pub fn summary(&self) -> TreemapScanSummary {
+        let state = self.state.lock().unwrap();
+        let elapsed = state.elapsed.unwrap_or_else(|| self.started.elapsed());
+        TreemapScanSummary {
+            scan_id: self.id.clone(),
+            root: state.builder.config.root.clone(),
+            status: state.status,
+            files_scanned: state.builder.files_scanned(),
+            bytes_scanned: state.builder.bytes_scanned(),
+            paths_visited: self.paths_visited.load(Ordering::Relaxed),
+            paths_per_sec: self.rate(elapsed),
+            elapsed_ms: elapsed.as_millis() as u64,
+            error: state.error.clone(),
+            skipped_mounts: state.skipped_mounts.clone(),
+        }
+    }
+
+    fn rate(&self, elapsed: Duration) -> f64 {
+        let secs = elapsed.as_secs_f64();
+        if secs > 0.0 {
+            self.paths_visited.load(Ordering::Relaxed) as f64 / secs
+        } else {
+            0.0
+        }
+    }
+
+    /// Tell event stream subscribers how far the scan got
+    fn publish_progress(&self) {
+        let summary = self.summary();
+        crate::events::publish(Event::DiskScanProgress {
+            scan_id: summary.scan_id,
+            root: summary.root.display().to_string(),
+            status: summary.status,
+            files_scanned: summary.files_scanned,
+            bytes_scanned: summary.bytes_scanned,
+            paths_per_sec: summary.paths_per_sec,
+            elapsed_ms: summary.elapsed_ms,
+            error: summary.error,
+        });
+    }
+
+    /// Files the scan has recorded so far that match `query`
+    pub fn query_files(&self, query: &FileQuery) -> FileQueryPage {
+        let state = self.state.lock().unwrap();
+        let (files, total, total_size) = state.catalog.query(query, chrono::Utc::now());
+        FileQueryPage {
+            scan_id: self.id.clone(),
+            root: state.builder.config.root.clone(),
+            status: state.status,
+            catalog_truncated: state.catalog.is_truncated(),
+            total,
+            total_size,
+            offset: query.offset.unwrap_or(0),
+            files,
+        }
+    }
+
+    /// Combined size of the files a complete scan recorded that `matches`
+    /// accepts; `None` while scanning, or when the scan stopped early or
+    /// stopped recording files
+    pub fn catalog_size(&self, matches: impl Fn(&Path) -> bool) -> Option<u64> {
+        let state = self.state.lock().unwrap();
+        if state.status != TreemapScanStatus::Complete || state.catalog.is_truncated() {
+            return None;
+        }
+        Some(state.catalog.size_matching(matches))
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    fn is_finished(&self) -> bool {
+        self.state.lock().unwrap().status != TreemapScanStatus::Scanning
+    }
+
+    fn finish(&self, status: TreemapScanStatus, error: Option<String>) {
+        {
+            let mut state = self.state.lock().unwrap();
+            state.status = status;
+            state.error = error;
+            state.elapsed = Some(self.started.elapsed());
+        }
+        self.publish_progress();
+    }
+
+    /// Walk the root in parallel, publishing sizes to the shared tree in
+    /// batches
+    fn run(&self, config: &TreemapConfig) {
+        if !config.root.is_dir() {
+            self.finish(
+                TreemapScanStatus::Failed,
+                Some(format!("{} is not a directory", config.root.display())),
+            );
+            return;
+        }
+        self.walk(config, MountGuard::new(&config.root, &config.mounts));
+    }
+
+    /// Walk the root, leaving out the mounts `guard` skips
+    fn walk(&self, config: &TreemapConfig, guard: MountGuard) {
+        self.state.lock().unwrap().skipped_mounts = guard.skipped().to_vec();
+        if !guard.allows(&config.root) {
+            self.finish(
+                TreemapScanStatus::Failed,
+                Some(format!("{} is on a mount that didn't respond", config.root.display())),
+            );
+            return;
+        }
+
+        let (sender, receiver) = mpsc::sync_channel::<(PathBuf, Metadata)>(BATCH_FILES * 4);
+        let walker = config
+            .ignore_rules
+            .walker(&config.root)
+            .threads(config.threads)
+            .filter_entry(move |entry| guard.allows(entry.path()))
+            .build_parallel();
+
+        std::thread::scope(|scope| {
+            scope.spawn(move || {
+                walker.run(|| {
+                    let sender = sender.clone();
+                    Box::new(move |entry| {
+                        if self.cancelled.load(Ordering::Relaxed) {
+                            return WalkState::Quit;
+                        }
+                        let Ok(entry) = entry else {
+                            return WalkState::Continue;
+                        };
+                        self.paths_visited.fetch_add(1, Ordering::Relaxed);
+                        match entry.metadata() {
+                            Ok(metadata) if metadata.is_file() => {
+                                if sender.send((entry.into_path(), metadata)).is_err() {
+                                    return WalkState::Quit;
+                                }
+                                WalkState::Continue
+                            }
+                            _ => WalkState::Continue,
+                        }
+                    })
+                });
+            });
+
+            // The walkers drop their senders when they are done or cancelled
+            let mut batch: Vec<(PathBuf, Metadata)> = Vec::with_capacity(BATCH_FILES);
+            let mut last_flush = Instant::now();
+            let mut last_event = Instant::now();
+            loop {
+                let received = receiver.recv_timeout(BATCH_INTERVAL);
+                let done = matches!(received, Err(RecvTimeoutError::Disconnected));
+                if let Ok(file) = received {
+                    batch.push(file);
+                }
+                if done || batch.len() >= BATCH_FILES || last_flush.elapsed() >= BATCH_INTERVAL {
+                    self.state.lock().unwrap().add(&mut batch);
+                    last_flush = Instant::now();
+                }
+                if done {
+                    break;
+                }
+                if last_event.elapsed() >= PROGRESS_EVENT_INTERVAL {
+                    self.publish_progress();
+                    last_event = Instant::now();
+                }
+            }
+        });
+
+        if self.cancelled.load(Ordering::Relaxed) {
+            self.finish(TreemapScanStatus::Cancelled, None);
+        } else {
+            self.finish(TreemapScanStatus::Complete, None);
+        }
+    }
+}
+
+/// Treemap scans the UI started, by ID
+#[derive(Default)]
+pub struct TreemapScans {
+    scans: Mutex<Vec<Arc<TreemapScan>>>,
+}
+
+lazy_static::lazy_static! {
+    static ref GLOBAL_SCANS: Arc<TreemapScans> = Arc::new(TreemapScans::default());
+}
+
+impl TreemapScans {
+    pub fn global() -> Arc<TreemapScans> {
+        GLOBAL_SCANS.clone()
+    }
+
+    /// Start scanning on a blocking thread; progress is available right away
+    pub fn start(&self, config: TreemapConfig) -> Arc<TreemapScan> {
+        let scan = Arc::new(TreemapScan {
+            id: uuid::Uuid::new_v4().to_string(),
+            started: Instant::now(),
+            cancelled: AtomicBool::new(false),
+            paths_visited: AtomicU64::new(0),
+            state: Mutex::new(ScanState {
+                builder: TreemapBuilder::new(config.clone()),
+                catalog: FileCatalog::default(),
+                status: TreemapScanStatus::Scanning,
+                error: None,
+                elapsed: None,
+                skipped_mounts: Vec::new(),
+            }),
+        });
+
+        {
+            let mut scans = self.scans.lock().unwrap();
+            let mut finished = scans.iter().filter(|s| s.is_finished()).count();
+            scans.retain(|s| {
+                let drop = finished >= MAX_FINISHED_SCANS && s.is_finished();
+                if drop {
+                    finished -= 1;
+                }
+                !drop
+            });
+            scans.push(scan.clone());
+        }
+
+        let worker = scan.clone();
+        tokio::task::spawn_blocking(move || worker.run(&config));
+        scan
+    }
+
+    /// Every scan kept, most recent first
+    pub fn list(&self) -> Vec<TreemapScanSummary> {
+        self.scans.lock().unwrap().iter().rev().map(|s| s.summary()).collect()
+    }
+
+    pub fn get(&self, id: &str) -> Option<Arc<TreemapScan>> {
+        self.scans.lock().unwrap().iter().find(|s| s.id == id).cloned()
+    }
+
+    /// The most recent complete scan of a root containing `path` that
+    /// started less than `max_age` ago
+    pub fn covering(&self, path: &Path, max_age: Duration) -> Option<Arc<TreemapScan>> {
+        self.scans
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .filter(|s| s.started.elapsed() < max_age)
+            .find(|s| {
+                let state = s.state.lock().unwrap();
+                state.status == TreemapScanStatus::Complete && path.starts_with(&state.builder.config.root)
+            })
+            .cloned()
+    }
+
+    /// The most recently started scan that didn't fail
+    pub fn latest(&self) -> Option<Arc<TreemapScan>> {
+        self.scans
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .find(|s| s.state.lock().unwrap().status != TreemapScanStatus::Failed)
+            .cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mounts::{Mount, MountKind, SkipReason};
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn builder(root: &Path, depth: usize, top_n: usize) -> TreemapBuilder {
+        TreemapBuilder::new(TreemapConfig {
+            root: root.to_path_buf(),
+            depth,
+            top_n,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_snapshot_nests_and_prunes() {
+        let root = Path::new("/data");
+        let mut builder = builder(root, 1, 2);
+        builder.add_file(Path::new("/data/movie.mp4"), 500);
+        builder.add_file(Path::new("/data/notes.txt"), 10);
+        builder.add_file(Path::new("/data/code/main.rs"), 30);
+        builder.add_file(Path::new("/data/code/deep/lib.rs"), 70);
+        builder.add_file(Path::new("/data/photos/a.jpg"), 5);
+
+        let tree = builder.snapshot();
+        assert_eq!(tree.size, 615);
+        assert_eq!(tree.file_count, 5);
+        assert_eq!(tree.category, "Videos");
+
+        // movie.mp4 and code/ are kept, notes.txt and photos/ are merged
+        let names: Vec<&str> = tree.children.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["movie.mp4", "code", "2 more items"]);
+        assert_eq!(tree.children[2].kind, TreemapNodeKind::Other);
+        assert_eq!(tree.children[2].size, 15);
+
+        // Below the depth limit, content is summed into the directory
+        let code = &tree.children[1];
+        assert_eq!(code.size, 100);
+        assert_eq!(code.category, "Code");
+        assert!(code.children.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_scan_runs_in_background() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join("sub")).unwrap();
+        fs::write(dir.path().join("a.txt"), "hello").unwrap();
+        fs::write(dir.path().join("sub/b.txt"), "world!").unwrap();
+
+        let scans = TreemapScans::default();
+        let scan = scans.start(TreemapConfig {
+            root: dir.path().to_path_buf(),
+            ..Default::default()
+        });
+        assert!(scans.get(&scan.progress().scan_id).is_some());
+
+        let progress = loop {
+            let progress = scan.progress();
+            if progress.status != TreemapScanStatus::Scanning {
+                break progress;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        };
+        assert_eq!(progress.status, TreemapScanStatus::Complete);
+        assert_eq!(progress.files_scanned, 2);
+        assert_eq!(progress.root.size, 11);
+        assert_eq!(progress.root.children.len(), 2);
+        let summary = scan.summary();
+        // The root, sub/ and both files
+        assert_eq!(summary.paths_visited, 4);
+        assert_eq!((summary.files_scanned, summary.bytes_scanned), (2, 11));
+        assert_eq!(scans.list(), vec![summary]);
+
+        let missing = scans.start(TreemapConfig {
+            root: dir.path().join("missing"),
+            ..Default::default()
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(missing.progress().status, TreemapScanStatus::Failed);
+    }
+
+    fn scan(config: &TreemapConfig) -> TreemapScan {
+        TreemapScan {
+            id: "test".to_string(),
+            started: Instant::now(),
+            cancelled: AtomicBool::new(false),
+            paths_visited: AtomicU64::new(0),
+            state: Mutex::new(ScanState {
+                builder: TreemapBuilder::new(config.clone()),
+                catalog: FileCatalog::default(),
+                status: TreemapScanStatus::Scanning,
+                error: None,
+                elapsed: None,
+                skipped_mounts: Vec::new(),
+            }),
+        }
+    }
+
+    #[test]
+    fn test_cancelled_scan_stops_walking() {
+        let dir = TempDir::new().unwrap();
+        for i in 0..50 {
+            fs::write(dir.path().join(format!("{}.txt", i)), "x").unwrap();
+        }
+        let config = TreemapConfig {
+            root: dir.path().to_path_buf(),
+            threads: 2,
+            ..Default::default()
+        };
+        let scan = scan(&config);
+        scan.cancel();
+        scan.run(&config);
+
+        let summary = scan.summary();
+        assert_eq!(summary.status, TreemapScanStatus::Cancelled);
+        assert_eq!(summary.files_scanned, 0);
+    }
+
+    #[test]
+    fn test_scan_leaves_out_skipped_mounts() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join("share")).unwrap();
+        fs::write(dir.path().join("a.txt"), "hello").unwrap();
+        fs::write(dir.path().join("share/b.txt"), "world!").unwrap();
+        let config = TreemapConfig {
+            root: dir.path().to_path_buf(),
+            ..Default::default()
+        };
+        let share = Mount {
+            mount_point: dir.path().join("share"),
+            device: "server:/export".to_string(),
+            file_system: "nfs".to_string(),
+            kind: MountKind::Network,
+        };
+        let scan = scan(&config);
+        scan.walk(&config, MountGuard::with_mounts(dir.path(), &config.mounts, &[share]));
+
+        let summary = scan.summary();
+        assert_eq!(summary.status, TreemapScanStatus::Complete);
+        assert_eq!((summary.files_scanned, summary.bytes_scanned), (1, 5));
+        assert_eq!(summary.skipped_mounts.len(), 1);
+        assert_eq!(summary.skipped_mounts[0].mount_point, dir.path().join("share"));
+        assert_eq!(summary.skipped_mounts[0].reason, SkipReason::Excluded);
+    }
+}
@@ -1,3 +1,5 @@
+use crate::ignore_rules::IgnoreRules;
+use crate::mounts::{MountPolicy, SkippedMount};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -11,6 +13,17 @@ pub struct DiskAnalysisConfig {
     pub exclude_patterns: Vec<String>,
     pub min_size_threshold: u64,
     pub categorization_rules: HashMap<String, Vec<String>>,
+    /// Ignore files the scan honours; by default only `.skhootignore`
+    #[serde(default = "default_ignore_rules")]
+    pub ignore_rules: IgnoreRules,
+    /// Network and removable mounts below the paths are left out unless
+    /// opted into
+    #[serde(default)]
+    pub mounts: MountPolicy,
+}
+
+fn default_ignore_rules() -> IgnoreRules {
+    IgnoreRules::DISK_USAGE
 }
 
 impl Default for DiskAnalysisConfig {
@@ -22,6 +35,8 @@ impl Default for DiskAnalysisConfig {
             exclude_patterns: vec![],
             min_size_threshold: 0,
             categorization_rules: HashMap::new(),
+            ignore_rules: IgnoreRules::DISK_USAGE,
+            mounts: MountPolicy::default(),
         }
     }
 }
@@ -34,6 +49,9 @@ pub struct DiskAnalysisReport {
     pub cleanup_candidates: Vec<CleanupCandidate>,
     pub categories: HashMap<String, CategorySummary>,
     pub timestamp: DateTime<Utc>,
+    /// Mounts below the analyzed paths that were not scanned
+    #[serde(default)]
+    pub skipped_mounts: Vec<SkippedMount>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -59,6 +77,17 @@ pub struct CleanupCandidate {
     pub safety_level: SafetyLevel,
     pub description: String,
     pub estimated_savings: u64,
+    #[serde(default)]
+    pub name: String,
+    /// What removing it means for the user
+    #[serde(default)]
+    pub consequence: String,
+    /// ID of the `CleanupRecognizer` that found it
+    #[serde(default)]
+    pub recognizer: Option<String>,
+    /// Shell command that reclaims the space safely
+    #[serde(default)]
+    pub cleanup_command: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -84,3 +113,267 @@ pub struct CategorySummary {
     pub file_count: usize,
     pub safety_level: SafetyLevel,
 }
+
+/// Configuration for the periodic background disk scan
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledScanConfig {
+    pub enabled: bool,
+    pub interval_secs: u64,
+    pub paths: Vec<PathBuf>,
+    /// How many directory levels below each root get their own snapshot
+    pub snapshot_depth: usize,
+    /// Directories smaller than this are not recorded (roots are always recorded)
+    pub min_snapshot_size: u64,
+    /// Snapshots older than this are pruned after each scan
+    pub retention_days: i64,
+    /// Ignore files the scan honours; by default only `.skhootignore`
+    #[serde(default = "default_ignore_rules")]
+    pub ignore_rules: IgnoreRules,
+}
+
+impl Default for ScheduledScanConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_secs: 24 * 60 * 60,
+            paths: vec![],
+            snapshot_depth: 2,
+            min_snapshot_size: 100 * 1024 * 1024,
+            retention_days: 90,
+            ignore_rules: IgnoreRules::DISK_USAGE,
+        }
+    }
+}
+
+/// Size of a single path at a point in time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiskSnapshot {
+    pub path: PathBuf,
+    pub size: u64,
+    pub file_count: usize,
+    pub captured_at: DateTime<Utc>,
+}
+
+/// Growth of a path between its first and last snapshot in a period
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiskTrend {
+    pub path: PathBuf,
+    pub first_size: u64,
+    pub last_size: u64,
+    pub growth_bytes: i64,
+    pub growth_percentage: f64,
+    pub points: Vec<TrendPoint>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrendPoint {
+    pub captured_at: DateTime<Utc>,
+    pub size: u64,
+}
+
+/// Options for a treemap scan
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TreemapConfig {
+    pub root: PathBuf,
+    /// Directory levels below the root that get their own node; deeper
+    /// content is summed into its ancestor at this depth
+    pub depth: usize,
+    /// Children kept per node; the rest are merged into one "other" node
+    pub top_n: usize,
+    /// Ignore files the scan honours; by default only `.skhootignore`
+    #[serde(default = "default_ignore_rules")]
+    pub ignore_rules: IgnoreRules,
+    /// Walker threads; 0 picks one per CPU
+    #[serde(default)]
+    pub threads: usize,
+    /// Which network and removable mounts below the root are walked
+    #[serde(default)]
+    pub mounts: MountPolicy,
+}
+
+impl Default for TreemapConfig {
+    fn default() -> Self {
+        Self {
+            root: PathBuf::new(),
+            depth: 3,
+            top_n: 20,
+            ignore_rules: IgnoreRules::DISK_USAGE,
+            threads: 0,
+            mounts: MountPolicy::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TreemapNodeKind {
+    Directory,
+    File,
+    /// Children pruned by `top_n`, merged together
+    Other,
+}
+
+/// A rectangle of the treemap: a directory, a file or pruned leftovers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TreemapNode {
+    pub path: PathBuf,
+    pub name: String,
+    pub kind: TreemapNodeKind,
+    pub size: u64,
+    pub file_count: usize,
+    /// File category taking up the most space in the node
+    pub category: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<TreemapNode>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TreemapScanStatus {
+    Scanning,
+    Complete,
+    Cancelled,
+    Failed,
+}
+
+/// State of a treemap scan; `root` grows while the scan runs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TreemapProgress {
+    pub scan_id: String,
+    pub status: TreemapScanStatus,
+    pub files_scanned: usize,
+    pub bytes_scanned: u64,
+    /// Files and directories visited per second
+    pub paths_per_sec: f64,
+    pub elapsed_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub root: TreemapNode,
+}
+
+/// State of a treemap scan without its tree, for polling and listing
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TreemapScanSummary {
+    pub scan_id: String,
+    pub root: PathBuf,
+    pub status: TreemapScanStatus,
+    pub files_scanned: usize,
+    pub bytes_scanned: u64,
+    /// Files and directories visited, including ones without a size
+    pub paths_visited: u64,
+    pub paths_per_sec: f64,
+    pub elapsed_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Mounts below the root the scan left out
+    #[serde(default)]
+    pub skipped_mounts: Vec<SkippedMount>,
+}
+
+/// SMART self-assessment of the drive behind a volume
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SmartStatus {
+    Passed,
+    Failing,
+}
+
+/// A mounted volume
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VolumeInfo {
+    pub name: String,
+    pub mount_point: String,
+    pub device: String,
+    /// e.g. ext4, apfs, NTFS; empty when unknown
+    pub file_system: String,
+    pub total_bytes: u64,
+    pub used_bytes: u64,
+    pub free_bytes: u64,
+    pub usage_percentage: f64,
+    pub removable: bool,
+    /// `None` when the platform or our privileges don't allow reading SMART data
+    pub smart_status: Option<SmartStatus>,
+}
+
+/// What time a file's age is measured from
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AgeBasis {
+    #[default]
+    Modified,
+    Accessed,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FileSort {
+    #[default]
+    Size,
+    Modified,
+    Accessed,
+    Name,
+    Path,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+/// Filters over the files of a scan, e.g. videos over 1 GB not modified in
+/// a year
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FileQuery {
+    pub min_size: Option<u64>,
+    pub max_size: Option<u64>,
+    /// Untouched for at least this many days
+    pub older_than_days: Option<u64>,
+    /// Touched within this many days
+    pub newer_than_days: Option<u64>,
+    #[serde(default)]
+    pub age_by: AgeBasis,
+    /// Comma-separated, with or without the dot
+    pub extensions: Option<String>,
+    /// Category name as in the storage breakdown, e.g. "Videos"
+    pub category: Option<String>,
+    /// User name or numeric ID
+    pub owner: Option<String>,
+    /// Only files under this directory
+    pub path: Option<String>,
+    #[serde(default)]
+    pub sort: FileSort,
+    /// Largest, newest or last first unless given; names and paths A to Z
+    pub order: Option<SortOrder>,
+    pub offset: Option<usize>,
+    pub limit: Option<usize>,
+}
+
+/// A file recorded by a scan
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CatalogFile {
+    pub path: PathBuf,
+    pub name: String,
+    pub size: u64,
+    pub extension: String,
+    pub category: String,
+    pub modified: Option<DateTime<Utc>>,
+    pub accessed: Option<DateTime<Utc>>,
+    pub owner: Option<String>,
+}
+
+/// One page of the files matching a query
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileQueryPage {
+    pub scan_id: String,
+    pub root: PathBuf,
+    pub status: TreemapScanStatus,
+    /// The scan stopped recording files after its limit
+    pub catalog_truncated: bool,
+    pub total: usize,
+    /// Bytes taken by all matching files, not just this page
+    pub total_size: u64,
+    pub offset: usize,
+    pub files: Vec<CatalogFile>,
+}
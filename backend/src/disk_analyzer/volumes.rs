@@ -0,0 +1,287 @@
+//! Mounted volumes with capacity, filesystem and health
+//!
+//! Mirrors the desktop shell's disk list so agents and workflows can query
+//! storage through the API. SMART health comes from `smartctl` on Linux and
+//! `diskutil` on macOS, and is left out when the tool is missing or needs
+//! privileges we don't have.
+
+use super::types::{SmartStatus, VolumeInfo};
+use std::collections::HashMap;
+use std::process::Command;
+
+/// Volumes of real devices, in the order the system lists them
+pub fn list_volumes() -> Result<Vec<VolumeInfo>, String> {
+    let mut volumes = platform_volumes()?;
+    let mut health: HashMap<String, Option<SmartStatus>> = HashMap::new();
+    for volume in &mut volumes {
+        volume.smart_status = *health
+            .entry(volume.device.clone())
+            .or_insert_with(|| smart_status(&volume.device));
+    }
+    Ok(volumes)
+}
+
+#[cfg_attr(target_os = "windows", allow(dead_code))]
+fn run(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    Some(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn volume(device: &str, mount_point: &str, file_system: &str, total: u64, free: u64) -> VolumeInfo {
+    let used = total.saturating_sub(free);
+    let name = match mount_point.trim_end_matches(['/', '\\']).rsplit(['/', '\\']).next() {
+        Some(last) if !last.is_empty() => last.to_string(),
+        _ => mount_point.to_string(),
+    };
+    VolumeInfo {
+        name,
+        mount_point: mount_point.to_string(),
+        device: device.to_string(),
+        file_system: file_system.to_string(),
+        total_bytes: total,
+        used_bytes: used,
+        free_bytes: free,
+        usage_percentage: if total > 0 { (used as f64 / total as f64) * 100.0 } else { 0.0 },
+        removable: false,
+        smart_status: None,
+    }
+}
+
+/// Parse `df -B1 --output=source,fstype,size,avail,target`
+pub(super) fn parse_linux_df(output: &str) -> Vec<VolumeInfo> {
+    output
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            if parts.len() < 5 {
+                return None;
+            }
+            let (device, fs, mount) = (parts[0], parts[1], parts[4..].join(" "));
+            if !device.starts_with("/dev/") || device.starts_with("/dev/loop") {
+                return None;
+            }
+            let total: u64 = parts[2].parse().ok()?;
+            let free: u64 = parts[3].parse().unwrap_or(0);
+            (total > 0).then(|| volume(device, &mount, fs, total, free))
+        })
+        .collect()
+}
+
+/// Parse `wmic logicaldisk get caption,drivetype,filesystem,freespace,size /format:csv`,
+/// whose columns are Node,Caption,DriveType,FileSystem,FreeSpace,Size
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+pub(super) fn parse_windows_wmic(output: &str) -> Vec<VolumeInfo> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let parts: Vec<&str> = line.trim().split(',').collect();
+            if parts.len() < 6 {
+                return None;
+            }
+            let total: u64 = parts[5].trim().parse().ok()?;
+            let free: u64 = parts[4].trim().parse().unwrap_or(0);
+            let caption = parts[1].trim();
+            let mut info = volume(caption, &format!("{}\\", caption), parts[3].trim(), total, free);
+            info.name = caption.to_string();
+            // Drive type 2 is a removable disk
+            info.removable = parts[2].trim() == "2";
+            (total > 0).then_some(info)
+        })
+        .collect()
+}
+
+/// Parse `smartctl -H`; ATA drives report PASSED/FAILED, NVMe and SCSI OK
+pub(super) fn parse_smartctl(output: &str) -> Option<SmartStatus> {
+    let line = output
+        .lines()
+        .find(|l| l.contains("overall-health") || l.contains("SMART Health Status"))?;
+    let verdict = line.rsplit(':').next()?.trim();
+    Some(match verdict {
+        "PASSED" | "OK" => SmartStatus::Passed,
+        _ => SmartStatus::Failing,
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn platform_volumes() -> Result<Vec<VolumeInfo>, String> {
+    let output = Command::new("df")
+        .args(["-B1", "--output=source,fstype,size,avail,target"])
+        .output()
+        .map_err(|e| format!("Failed to run df: {}", e))?;
+    let mut volumes = parse_linux_df(&String::from_utf8_lossy(&output.stdout));
+    for volume in &mut volumes {
+        volume.removable = linux_removable(&volume.device);
+    }
+    Ok(volumes)
+}
+
+/// The whole-disk block device behind a partition, e.g. nvme0n1 for nvme0n1p2
+#[cfg(target_os = "linux")]
+fn linux_block_device(device: &str) -> Option<std::path::PathBuf> {
+    let resolved = std::fs::canonicalize(device).ok()?;
+    let sys = std::fs::canonicalize(format!("/sys/class/block/{}", resolved.file_name()?.to_str()?)).ok()?;
+    if sys.join("partition").exists() {
+        sys.parent().map(|p| p.to_path_buf())
+    } else {
+        Some(sys)
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn linux_removable(device: &str) -> bool {
+    linux_block_device(device)
+        .and_then(|disk| std::fs::read_to_string(disk.join("removable")).ok())
+        .map(|v| v.trim() == "1")
+        .unwrap_or(false)
+}
+
+/// Whether a block device such as `/dev/sdb1` sits on a removable disk;
+/// only known on Linux
+pub fn is_removable_device(device: &str) -> bool {
+    #[cfg(target_os = "linux")]
+    {
+        device.starts_with("/dev/") && linux_removable(device)
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = device;
+        false
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn smart_status(device: &str) -> Option<SmartStatus> {
+    let disk = linux_block_device(device)?;
+    let path = format!("/dev/{}", disk.file_name()?.to_str()?);
+    parse_smartctl(&run("smartctl", &["-H", &path])?)
+}
+
+#[cfg(target_os = "macos")]
+fn platform_volumes() -> Result<Vec<VolumeInfo>, String> {
+    let output = Command::new("df")
+        .args(["-k"])
+        .output()
+        .map_err(|e| format!("Failed to run df: {}", e))?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    let mut volumes = Vec::new();
+    for line in stdout.lines().skip(1) {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        if parts.len() < 9 || !parts[0].starts_with("/dev/disk") {
+            continue;
+        }
+        let mount = parts[8..].join(" ");
+        if mount != "/" && !mount.starts_with("/Volumes/") {
+            continue;
+        }
+        let total: u64 = parts[1].parse::<u64>().unwrap_or(0) * 1024;
+        let free: u64 = parts[3].parse::<u64>().unwrap_or(0) * 1024;
+        if total == 0 {
+            continue;
+        }
+
+        let info = run("diskutil", &["info", &mount]).unwrap_or_default();
+        let field = |key: &str| {
+            info.lines()
+                .find_map(|l| l.trim().strip_prefix(key).map(|v| v.trim().to_string()))
+        };
+        let mut volume = volume(parts[0], &mount, "", total, free);
+        if mount == "/" {
+            volume.name = "Macintosh HD".to_string();
+        }
+        volume.file_system = field("Type (Bundle):").unwrap_or_default();
+        volume.removable = field("Removable Media:").is_some_and(|v| v == "Removable")
+            || field("Device Location:").is_some_and(|v| v == "External");
+        volumes.push(volume);
+    }
+    Ok(volumes)
+}
+
+#[cfg(target_os = "macos")]
+fn smart_status(device: &str) -> Option<SmartStatus> {
+    let info = run("diskutil", &["info", device])?;
+    let status = info
+        .lines()
+        .find_map(|l| l.trim().strip_prefix("SMART Status:").map(str::trim))?;
+    match status {
+        "Verified" => Some(SmartStatus::Passed),
+        "Failing" => Some(SmartStatus::Failing),
+        _ => None,
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn platform_volumes() -> Result<Vec<VolumeInfo>, String> {
+    let output = Command::new("wmic")
+        .args(["logicaldisk", "get", "caption,drivetype,filesystem,freespace,size", "/format:csv"])
+        .output()
+        .map_err(|e| format!("Failed to run wmic: {}", e))?;
+    Ok(parse_windows_wmic(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Windows reports failure prediction per physical drive, which can't be
+/// matched to a drive letter without WMI associations
+#[cfg(target_os = "windows")]
+fn smart_status(_device: &str) -> Option<SmartStatus> {
+    None
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+fn platform_volumes() -> Result<Vec<VolumeInfo>, String> {
+    Ok(Vec::new())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+fn smart_status(_device: &str) -> Option<SmartStatus> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_linux_df() {
+        let output = "\
+Filesystem     Type     1B-blocks         Avail Mounted on
+/dev/nvme0n1p2 ext4  500000000000  200000000000 /
+tmpfs          tmpfs   8000000000    8000000000 /dev/shm
+/dev/loop3     squashfs  60000000             0 /snap/core/1
+/dev/sdb1      vfat   32000000000   31000000000 /media/me/USB STICK
+";
+        let volumes = parse_linux_df(output);
+        assert_eq!(volumes.len(), 2);
+        assert_eq!(volumes[0].name, "/");
+        assert_eq!(volumes[0].file_system, "ext4");
+        assert_eq!(volumes[0].used_bytes, 300_000_000_000);
+        assert_eq!(volumes[0].usage_percentage, 60.0);
+        assert_eq!(volumes[1].mount_point, "/media/me/USB STICK");
+        assert_eq!(volumes[1].name, "USB STICK");
+    }
+
+    #[test]
+    fn test_parse_windows_wmic() {
+        let output = "\r\nNode,Caption,DriveType,FileSystem,FreeSpace,Size\r\n\
+PC,C:,3,NTFS,100,400\r\n\
+PC,D:,5,,,\r\n\
+PC,E:,2,FAT32,10,20\r\n";
+        let volumes = parse_windows_wmic(output);
+        assert_eq!(volumes.len(), 2);
+        assert_eq!(volumes[0].mount_point, "C:\\");
+        assert_eq!(volumes[0].file_system, "NTFS");
+        assert!(!volumes[0].removable);
+        assert_eq!(volumes[1].name, "E:");
+        assert!(volumes[1].removable);
+    }
+
+    #[test]
+    fn test_parse_smartctl() {
+        let ata = "=== START OF READ SMART DATA SECTION ===\nSMART overall-health self-assessment test result: PASSED\n";
+        assert_eq!(parse_smartctl(ata), Some(SmartStatus::Passed));
+        let failing = "SMART overall-health self-assessment test result: FAILED!\n";
+        assert_eq!(parse_smartctl(failing), Some(SmartStatus::Failing));
+        assert_eq!(parse_smartctl("SMART Health Status: OK\n"), Some(SmartStatus::Passed));
+        assert_eq!(parse_smartctl("Permission denied\n"), None);
+    }
+}
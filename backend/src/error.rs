@@ -9,12 +9,16 @@ use axum::{
 use serde_json::json;
 use thiserror::Error;
 
+use crate::vault::VaultError;
+
 #[derive(Error, Debug)]
 pub enum AppError {
     #[error("Database error: {0}")]
     Database(#[from] sqlx::Error),
     #[error("Migration error: {0}")]
     Migration(#[from] sqlx::migrate::MigrateError),
+    #[error("Schema error: {0}")]
+    Schema(#[from] crate::db::migrations::SchemaError),
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
     #[error("HTTP client error: {0}")]
@@ -25,6 +29,8 @@ pub enum AppError {
     Notify(#[from] notify::Error),
     #[error("Anyhow error: {0}")]
     Anyhow(#[from] anyhow::Error),
+    #[error("{0}")]
+    Vault(#[from] crate::vault::VaultError),
     #[error("Bad request: {0}")]
     BadRequest(String),
     #[error("Not found: {0}")]
@@ -38,6 +44,17 @@ impl IntoResponse for AppError {
         let (status, error_message) = match &self {
             AppError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg.clone()),
             AppError::NotFound(msg) => (StatusCode::NOT_FOUND, msg.clone()),
+            AppError::Vault(e) => match e {
+                VaultError::Locked => (StatusCode::LOCKED, e.to_string()),
+                VaultError::WrongPassphrase => (StatusCode::UNAUTHORIZED, e.to_string()),
+                VaultError::NotEnabled | VaultError::AlreadyEnabled | VaultError::WeakPassphrase(_) => {
+                    (StatusCode::BAD_REQUEST, e.to_string())
+                }
+                VaultError::Corrupt(_) | VaultError::Io(_) => {
+                    tracing::error!("Vault error: {}", e);
+                    (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+                }
+            },
             _ => {
                 tracing::error!("Internal error: {:?}", self);
                 (StatusCode::INTERNAL_SERVER_ERROR, "Internal server error".to_string())
@@ -0,0 +1,272 @@
+//! Backend-wide event bus
+//!
+//! Modules publish typed events to a global broadcast channel; the
+//! `/api/v1/events` SSE stream forwards them to the UI so it doesn't have to
+//! poll each subsystem. Publishing never blocks and is a no-op when nobody is
+//! subscribed; subscribers that fall too far behind lose the oldest events and
+//! are told how many they missed.
+
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::sync::broadcast;
+
+/// Events buffered per subscriber before the oldest are dropped
+const CHANNEL_CAPACITY: usize = 1024;
+
+/// Event published by a backend module
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event {
+    /// An agent definition was created, updated or deleted
+    AgentChanged { agent_id: String, action: String },
+    /// An agent execution started or changed status
+    AgentExecution {
+        agent_id: String,
+        execution_id: String,
+        status: String,
+    },
+    /// An agent session finished processing a message or hit an error
+    AgentSession {
+        session_id: String,
+        state: crate::cli_agent::AgentState,
+    },
+    /// An agent session repeated a tool call and was paused
+    ToolLoopDetected {
+        session_id: String,
+        tool: String,
+        repeats: u32,
+    },
+    /// An agent session sent a message to another session's inbox
+    AgentMessage {
+        message_id: String,
+        from: String,
+        to: String,
+    },
+    /// A goal-mode run moved to another phase or finished
+    GoalProgress {
+        goal_id: String,
+        session_id: Option<String>,
+        iteration: u32,
+        phase: crate::cli_agent::goal::GoalPhase,
+        status: crate::cli_agent::goal::GoalStatus,
+        criteria_met: usize,
+        criteria_total: usize,
+    },
+    /// A workflow execution context changed
+    WorkflowExecution {
+        workflow_id: String,
+        execution_id: String,
+        status: crate::workflows::WorkflowStatus,
+        current_step_id: Option<String>,
+    },
+    /// A tracked workflow run changed
+    WorkflowRun {
+        workflow_id: String,
+        run_id: String,
+        status: crate::workflows::WorkflowStatus,
+        current_step_id: Option<String>,
+    },
+    /// A workflow run paused at a step that needs the user's approval
+    WorkflowApprovalNeeded {
+        workflow_id: String,
+        run_id: String,
+        approval: crate::workflows::ApprovalRequest,
+    },
+    /// A terminal session was created, closed, hibernated, restored or archived
+    TerminalSession { session_id: String, action: String },
+    /// New output is available; `cursor` is the read cursor after it
+    TerminalOutput { session_id: String, cursor: usize },
+    /// Full index started
+    IndexingStarted { paths: Vec<String> },
+    /// Full index finished
+    IndexingCompleted { duration_ms: u64, error: Option<String> },
+    /// A file search finished
+    SearchCompleted {
+        search_id: String,
+        query: String,
+        results: usize,
+        error: Option<String>,
+    },
+    /// The chat client switched AI providers
+    ProviderFailover { from: String, to: String, reason: String },
+    /// Settings sections changed (file edit or config API)
+    ConfigChanged { sections: Vec<String>, restart_required: bool },
+    /// The transcript of a voice recording changed or is final
+    Transcript {
+        session_id: String,
+        text: String,
+        is_final: bool,
+    },
+    /// A file in the workspace of a `file_saved` agent hook was written
+    FileSaved { path: String },
+    /// A shell command run by an agent exited with an error
+    CommandFailed {
+        session_id: Option<String>,
+        command: String,
+        exit_code: Option<i32>,
+    },
+    /// A subscribed RSS/Atom feed has a new entry
+    FeedEntry {
+        subscription_id: String,
+        entry_id: String,
+        title: Option<String>,
+        link: Option<String>,
+    },
+    /// A file was moved, copied, renamed or deleted
+    FileOperation {
+        operation: crate::file_history::FileOperation,
+        source: String,
+        destination: Option<String>,
+    },
+    /// A treemap scan made progress or finished; sent about once a second
+    DiskScanProgress {
+        scan_id: String,
+        root: String,
+        status: crate::disk_analyzer::TreemapScanStatus,
+        files_scanned: usize,
+        bytes_scanned: u64,
+        paths_per_sec: f64,
+        elapsed_ms: u64,
+        error: Option<String>,
+    },
+    /// A storage quota went over its limit, or back below it
+    StorageQuota {
+        quota_id: String,
+        name: String,
+        size: u64,
+        limit_bytes: u64,
+        exceeded: bool,
+    },
+}
+
+impl Event {
+    /// Topic used to filter the event stream
+    pub fn topic(&self) -> &'static str {
+        match self {
+            Event::AgentChanged { .. }
+            | Event::AgentExecution { .. }
+            | Event::AgentSession { .. }
+            | Event::ToolLoopDetected { .. }
+            | Event::GoalProgress { .. }
+            | Event::AgentMessage { .. } => "agents",
+            Event::WorkflowExecution { .. }
+            | Event::WorkflowRun { .. }
+            | Event::WorkflowApprovalNeeded { .. } => "workflows",
+            Event::TerminalSession { .. } | Event::TerminalOutput { .. } | Event::CommandFailed { .. } => "terminal",
+            Event::IndexingStarted { .. } | Event::IndexingCompleted { .. } => "indexer",
+            Event::SearchCompleted { .. } => "search",
+            Event::ProviderFailover { .. } => "ai",
+            Event::ConfigChanged { .. } => "config",
+            Event::FileOperation { .. } | Event::FileSaved { .. } => "files",
+            Event::DiskScanProgress { .. } | Event::StorageQuota { .. } => "disk",
+            Event::Transcript { .. } => "audio",
+            Event::FeedEntry { .. } => "feeds",
+        }
+    }
+}
+
+/// Event with its sequence number and publish time
+#[derive(Debug, Clone, Serialize)]
+pub struct EventEnvelope {
+    pub id: u64,
+    pub topic: &'static str,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    #[serde(flatten)]
+    pub event: Event,
+}
+
+/// Broadcast channel shared by publishers and SSE subscribers
+pub struct EventBus {
+    sender: broadcast::Sender<EventEnvelope>,
+    next_id: AtomicU64,
+}
+
+impl EventBus {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+        Self {
+            sender,
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    /// Publishes an event, returning its sequence number
+    pub fn publish(&self, event: Event) -> u64 {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let envelope = EventEnvelope {
+            id,
+            topic: event.topic(),
+            timestamp: chrono::Utc::now(),
+            event,
+        };
+        // Err only means there are no subscribers
+        let _ = self.sender.send(envelope);
+        id
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<EventEnvelope> {
+        self.sender.subscribe()
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new(CHANNEL_CAPACITY)
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref GLOBAL_BUS: EventBus = EventBus::default();
+}
+
+/// The process-wide event bus
+pub fn bus() -> &'static EventBus {
+    &GLOBAL_BUS
+}
+
+/// Publishes an event on the global bus
+pub fn publish(event: Event) -> u64 {
+    GLOBAL_BUS.publish(event)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_publish_and_subscribe() {
+        let bus = EventBus::new(16);
+        // Events published before subscribing are not delivered
+        bus.publish(Event::IndexingStarted { paths: vec![] });
+
+        let mut rx = bus.subscribe();
+        let id = bus.publish(Event::TerminalSession {
+            session_id: "s1".to_string(),
+            action: "created".to_string(),
+        });
+
+        let envelope = rx.recv().await.unwrap();
+        assert_eq!(envelope.id, id);
+        assert_eq!(envelope.topic, "terminal");
+
+        let json = serde_json::to_value(&envelope).unwrap();
+        assert_eq!(json["type"], "terminal_session");
+        assert_eq!(json["session_id"], "s1");
+        assert_eq!(json["action"], "created");
+    }
+
+    #[tokio::test]
+    async fn test_lagging_subscriber() {
+        let bus = EventBus::new(2);
+        let mut rx = bus.subscribe();
+        for cursor in 0..4 {
+            bus.publish(Event::TerminalOutput {
+                session_id: "s1".to_string(),
+                cursor,
+            });
+        }
+
+        assert!(matches!(rx.recv().await, Err(broadcast::error::RecvError::Lagged(2))));
+        assert_eq!(rx.recv().await.unwrap().event, Event::TerminalOutput { session_id: "s1".to_string(), cursor: 2 });
+    }
+}
@@ -0,0 +1,570 @@
+//! RSS/Atom feed subscriptions
+//!
+//! The poller checks every enabled subscription once its interval has
+//! passed, using conditional requests so unchanged feeds cost a 304. Entries
+//! are deduplicated by their guid (or link) and stored; each new entry
+//! publishes [`Event::FeedEntry`] and, when the subscription names a
+//! workflow, starts a run of it with the entry's fields as variables, e.g.
+//! to summarize every new post of a blog.
+//!
+//! The first check of a subscription only records the entries already in
+//! the feed, so subscribing doesn't start a run per old post. Subscriptions
+//! and entries persist to `~/.skhoot/feeds.json`.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+
+use crate::content_extraction::document_extractor::{parse_feed, Feed, FeedItem};
+use crate::content_extraction::http_fetcher::{CacheValidators, ConditionalFetch};
+use crate::content_extraction::HttpFetcher;
+use crate::events::Event;
+use crate::json_store::{self, non_empty};
+use crate::workflows::{ExecuteWorkflowRequest, WorkflowEngine};
+
+/// Check interval of a subscription that doesn't set one
+pub const DEFAULT_INTERVAL_SECS: u64 = 1800;
+/// Shortest check interval allowed
+pub const MIN_INTERVAL_SECS: u64 = 300;
+/// Entries kept per subscription; the oldest go first
+pub const MAX_ENTRIES_PER_FEED: usize = 200;
+/// Workflow runs one check may start; further new entries are only stored
+pub const MAX_RUNS_PER_CHECK: usize = 10;
+/// How often the poller looks for subscriptions that are due
+const POLL_TICK_SECS: u64 = 60;
+
+/// A feed being watched
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FeedSubscription {
+    pub id: String,
+    pub url: String,
+    /// Feed title, once it has been read
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    pub interval_secs: u64,
+    /// Workflow started for every new entry
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workflow_id: Option<String>,
+    pub enabled: bool,
+    pub created_at: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_checked_at: Option<i64>,
+    /// Last time the feed was read; unset until the first successful check
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_success_at: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub etag: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_modified: Option<String>,
+}
+
+/// An entry read from a subscription
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FeedEntry {
+    pub id: String,
+    pub subscription_id: String,
+    /// The entry's guid, link or title, whichever comes first
+    pub key: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub link: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub published: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    pub fetched_at: i64,
+}
+
+/// Fields of a new subscription
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreateFeedRequest {
+    pub url: String,
+    #[serde(default)]
+    pub interval_secs: Option<u64>,
+    #[serde(default)]
+    pub workflow_id: Option<String>,
+    #[serde(default)]
+    pub enabled: Option<bool>,
+}
+
+/// Changes to a subscription; `workflow_id` is cleared with an empty string
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct UpdateFeedRequest {
+    pub url: Option<String>,
+    pub interval_secs: Option<u64>,
+    pub workflow_id: Option<String>,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum FeedError {
+    #[error("Feed not found: {0}")]
+    NotFound(String),
+
+    #[error("{0}")]
+    Invalid(String),
+
+    #[error("Failed to read feed: {0}")]
+    Fetch(String),
+
+    #[error("Failed to save feeds: {0}")]
+    Io(String),
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct FeedState {
+    subscriptions: Vec<FeedSubscription>,
+    entries: Vec<FeedEntry>,
+}
+
+/// Persistent feed subscriptions and their entries
+pub struct FeedStore {
+    path: PathBuf,
+    state: RwLock<FeedState>,
+}
+
+lazy_static::lazy_static! {
+    static ref GLOBAL_STORE: Arc<FeedStore> = Arc::new(FeedStore::new(
+        dirs::home_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join(".skhoot")
+            .join("feeds.json"),
+    ));
+}
+
+impl FeedStore {
+    /// Open the store at `path`; see [`json_store::load`]
+    pub fn new(path: PathBuf) -> Self {
+        let state = json_store::load(&path);
+        Self {
+            path,
+            state: RwLock::new(state),
+        }
+    }
+
+    /// Shared store at `~/.skhoot/feeds.json`
+    pub fn global() -> Arc<FeedStore> {
+        GLOBAL_STORE.clone()
+    }
+
+    pub fn list(&self) -> Vec<FeedSubscription> {
+        self.state.read().unwrap().subscriptions.clone()
+    }
+
+    pub fn get(&self, id: &str) -> Option<FeedSubscription> {
+        self.state
+            .read()
+            .unwrap()
+            .subscriptions
+            .iter()
+            .find(|s| s.id == id)
+            .cloned()
+    }
+
+    pub fn create(&self, request: CreateFeedRequest) -> Result<FeedSubscription, FeedError> {
+        let subscription = FeedSubscription {
+            id: uuid::Uuid::new_v4().to_string(),
+            url: check_url(&request.url)?,
+            title: None,
+            interval_secs: check_interval(request.interval_secs.unwrap_or(DEFAULT_INTERVAL_SECS))?,
+            workflow_id: non_empty(request.workflow_id),
+            enabled: request.enabled.unwrap_or(true),
+            created_at: chrono::Utc::now().timestamp(),
+            last_checked_at: None,
+            last_success_at: None,
+            last_error: None,
+            etag: None,
+            last_modified: None,
+        };
+        let mut state = self.state.write().unwrap();
+        if state.subscriptions.iter().any(|s| s.url == subscription.url) {
+            return Err(FeedError::Invalid(format!("Already subscribed to {}", subscription.url)));
+        }
+        state.subscriptions.push(subscription.clone());
+        self.save(&state)?;
+        Ok(subscription)
+    }
+
+    pub fn update(&self, id: &str, request: UpdateFeedRequest) -> Result<FeedSubscription, FeedError> {
+        let mut state = self.state.write().unwrap();
+        let FeedState { subscriptions, entries } = &mut *state;
+        let subscription = subscriptions
+            .iter_mut()
+            .find(|s| s.id == id)
+            .ok_or_else(|| FeedError::NotFound(id.to_string()))?;
+
+        if let Some(url) = request.url {
+            let url = check_url(&url)?;
+            if url != subscription.url {
+                // A different feed: start over, without runs for its current entries
+                subscription.url = url;
+                subscription.title = None;
+                subscription.last_success_at = None;
+                subscription.etag = None;
+                subscription.last_modified = None;
+                entries.retain(|e| e.subscription_id != id);
+            }
+        }
+        if let Some(interval) = request.interval_secs {
+            subscription.interval_secs = check_interval(interval)?;
+        }
+        if let Some(workflow_id) = request.workflow_id {
+            subscription.workflow_id = non_empty(Some(workflow_id));
+        }
+        if let Some(enabled) = request.enabled {
+            subscription.enabled = enabled;
+        }
+        let subscription = subscription.clone();
+        self.save(&state)?;
+        Ok(subscription)
+    }
+
+    /// Remove a subscription and its entries. Returns whether it existed.
+    pub fn remove(&self, id: &str) -> Result<bool, FeedError> {
+        let mut state = self.state.write().unwrap();
+        let before = state.subscriptions.len();
+        state.subscriptions.retain(|s| s.id != id);
+        if state.subscriptions.len() == before {
+            return Ok(false);
+        }
+        state.entries.retain(|e| e.subscription_id != id);
+        self.save(&state)?;
+        Ok(true)
+    }
+
+    /// Stored entries of a subscription, newest first
+    pub fn entries(&self, id: &str, limit: usize) -> Vec<FeedEntry> {
+        self.state
+            .read()
+            .unwrap()
+            .entries
+            .iter()
+            .rev()
+            .filter(|e| e.subscription_id == id)
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+
+    /// Enabled subscriptions whose interval has passed at `now`
+    pub fn due(&self, now: i64) -> Vec<FeedSubscription> {
+        self.state
+            .read()
+            .unwrap()
+            .subscriptions
+            .iter()
+            .filter(|s| s.enabled)
+            .filter(|s| s.last_checked_at.is_none_or(|last| now - last >= s.interval_secs as i64))
+            .cloned()
+            .collect()
+    }
+
+    /// Store the entries of a freshly read feed and return the new ones,
+    /// oldest first. Nothing counts as new on a subscription's first read.
+    fn ingest(&self, id: &str, feed: Feed, validators: CacheValidators) -> Result<Vec<FeedEntry>, FeedError> {
+        let now = chrono::Utc::now().timestamp();
+        let mut state = self.state.write().unwrap();
+        let FeedState { subscriptions, entries } = &mut *state;
+        let subscription = subscriptions
+            .iter_mut()
+            .find(|s| s.id == id)
+            .ok_or_else(|| FeedError::NotFound(id.to_string()))?;
+        let first_read = subscription.last_success_at.is_none();
+        subscription.last_checked_at = Some(now);
+        subscription.last_success_at = Some(now);
+        subscription.last_error = None;
+        subscription.etag = validators.etag;
+        subscription.last_modified = validators.last_modified;
+        if feed.title.is_some() {
+            subscription.title = feed.title;
+        }
+
+        let mut seen: HashSet<String> = entries
+            .iter()
+            .filter(|e| e.subscription_id == id)
+            .map(|e| e.key.clone())
+            .collect();
+        // Feeds list the newest entries first
+        let mut added = Vec::new();
+        for item in feed.items.into_iter().rev() {
+            let Some(entry) = new_entry(id, item, now) else {
+                continue;
+            };
+            if seen.insert(entry.key.clone()) {
+                added.push(entry);
+            }
+        }
+        entries.extend(added.iter().cloned());
+
+        let stored = entries.iter().filter(|e| e.subscription_id == id).count();
+        let mut excess = stored.saturating_sub(MAX_ENTRIES_PER_FEED);
+        entries.retain(|e| {
+            if excess > 0 && e.subscription_id == id {
+                excess -= 1;
+                return false;
+            }
+            true
+        });
+
+        self.save(&state)?;
+        Ok(if first_read { Vec::new() } else { added })
+    }
+
+    /// Note a check that found the feed unchanged or failed
+    fn record_check(&self, id: &str, error: Option<String>) {
+        let mut state = self.state.write().unwrap();
+        if let Some(subscription) = state.subscriptions.iter_mut().find(|s| s.id == id) {
+            subscription.last_checked_at = Some(chrono::Utc::now().timestamp());
+            subscription.last_error = error;
+        }
+        if let Err(e) = self.save(&state) {
+            tracing::warn!("{}", e);
+        }
+    }
+
+    fn save(&self, state: &FeedState) -> Result<(), FeedError> {
+        json_store::save(&self.path, state).map_err(|e| FeedError::Io(e.to_string()))
+    }
+}
+
+fn new_entry(subscription_id: &str, item: FeedItem, now: i64) -> Option<FeedEntry> {
+    let key = item
+        .guid
+        .clone()
+        .or_else(|| item.link.clone())
+        .or_else(|| item.title.clone())?;
+    Some(FeedEntry {
+        id: uuid::Uuid::new_v4().to_string(),
+        subscription_id: subscription_id.to_string(),
+        key,
+        title: item.title,
+        link: item.link,
+        published: item.date,
+        summary: item.summary,
+        fetched_at: now,
+    })
+}
+
+fn check_url(url: &str) -> Result<String, FeedError> {
+    let parsed = url::Url::parse(url.trim()).map_err(|_| FeedError::Invalid(format!("Invalid feed URL: {}", url)))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(FeedError::Invalid(format!("Feed URL must use http or https: {}", url)));
+    }
+    Ok(parsed.to_string())
+}
+
+fn check_interval(interval_secs: u64) -> Result<u64, FeedError> {
+    if interval_secs < MIN_INTERVAL_SECS {
+        return Err(FeedError::Invalid(format!(
+            "Check interval must be at least {} seconds",
+            MIN_INTERVAL_SECS
+        )));
+    }
+    Ok(interval_secs)
+}
+
+/// Run variables for an entry: its fields plus `feed_title` and `feed_url`
+pub fn entry_variables(subscription: &FeedSubscription, entry: &FeedEntry) -> HashMap<String, Value> {
+    let fields = json!({
+        "title": entry.title,
+        "link": entry.link,
+        "published": entry.published,
+        "summary": entry.summary,
+        "guid": entry.key,
+        "feed_title": subscription.title,
+        "feed_url": subscription.url,
+    });
+    match fields {
+        Value::Object(map) => map.into_iter().collect(),
+        _ => HashMap::new(),
+    }
+}
+
+/// Read a subscription's feed now, store new entries and start its
+/// workflow for them. Returns the new entries.
+pub async fn check(
+    store: &FeedStore,
+    fetcher: &HttpFetcher,
+    engine: &WorkflowEngine,
+    id: &str,
+) -> Result<Vec<FeedEntry>, FeedError> {
+    let subscription = store.get(id).ok_or_else(|| FeedError::NotFound(id.to_string()))?;
+    let url = url::Url::parse(&subscription.url).map_err(|e| FeedError::Invalid(e.to_string()))?;
+    let validators = CacheValidators {
+        etag: subscription.etag.clone(),
+        last_modified: subscription.last_modified.clone(),
+    };
+
+    let fetched = match fetcher.fetch_conditional(&url, &validators).await {
+        Ok(ConditionalFetch::Fetched(result)) => result,
+        Ok(ConditionalFetch::NotModified) => {
+            store.record_check(id, None);
+            return Ok(Vec::new());
+        }
+        Err(e) => {
+            store.record_check(id, Some(e.to_string()));
+            return Err(FeedError::Fetch(e.to_string()));
+        }
+    };
+    let feed = match parse_feed(&fetched.body) {
+        Ok(feed) => feed,
+        Err(e) => {
+            let error = format!("Not an RSS or Atom feed: {}", e);
+            store.record_check(id, Some(error.clone()));
+            return Err(FeedError::Fetch(error));
+        }
+    };
+    let validators = CacheValidators {
+        etag: fetched.etag,
+        last_modified: fetched.last_modified,
+    };
+    let added = store.ingest(id, feed, validators)?;
+
+    let subscription = store.get(id).unwrap_or(subscription);
+    for entry in &added {
+        crate::events::publish(Event::FeedEntry {
+            subscription_id: subscription.id.clone(),
+            entry_id: entry.id.clone(),
+            title: entry.title.clone(),
+            link: entry.link.clone(),
+        });
+    }
+    if let Some(workflow_id) = &subscription.workflow_id {
+        if added.len() > MAX_RUNS_PER_CHECK {
+            tracing::warn!(
+                "Feed {} has {} new entries; starting workflow {} for the latest {}",
+                subscription.url,
+                added.len(),
+                workflow_id,
+                MAX_RUNS_PER_CHECK
+            );
+        }
+        for entry in added.iter().skip(added.len().saturating_sub(MAX_RUNS_PER_CHECK)) {
+            let request = ExecuteWorkflowRequest {
+                workflow_id: workflow_id.clone(),
+                variables: entry_variables(&subscription, entry),
+                start_step_id: None,
+                trigger_payload: Some(json!(entry)),
+                context_id: Default::default(),
+            };
+            match engine.start_run(request).await {
+                Ok(run) => tracing::info!("Feed entry {} started workflow run {}", entry.key, run.id),
+                Err(e) => tracing::warn!("Feed entry {} could not start workflow {}: {}", entry.key, workflow_id, e),
+            }
+        }
+    }
+    Ok(added)
+}
+
+/// Check due subscriptions every minute
+pub fn spawn_feed_poller(store: Arc<FeedStore>, engine: Arc<WorkflowEngine>) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let fetcher = match HttpFetcher::new() {
+            Ok(fetcher) => fetcher,
+            Err(e) => {
+                tracing::error!("Feed poller disabled: {}", e);
+                return;
+            }
+        };
+        let mut ticker = tokio::time::interval(std::time::Duration::from_secs(POLL_TICK_SECS));
+        loop {
+            ticker.tick().await;
+            for subscription in store.due(chrono::Utc::now().timestamp()) {
+                match check(&store, &fetcher, &engine, &subscription.id).await {
+                    Ok(added) if !added.is_empty() => {
+                        tracing::info!("Feed {} has {} new entries", subscription.url, added.len())
+                    }
+                    Ok(_) => {}
+                    Err(e) => tracing::warn!("Feed {}: {}", subscription.url, e),
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(guid: &str, title: &str) -> FeedItem {
+        FeedItem {
+            guid: Some(guid.to_string()),
+            title: Some(title.to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn feed(items: Vec<FeedItem>) -> Feed {
+        Feed {
+            title: Some("Blog".to_string()),
+            description: None,
+            items,
+        }
+    }
+
+    #[test]
+    fn test_first_read_primes_then_new_entries_are_deduplicated() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = FeedStore::new(dir.path().join("feeds.json"));
+        let subscription = store
+            .create(CreateFeedRequest {
+                url: "https://example.com/feed.xml".to_string(),
+                interval_secs: None,
+                workflow_id: Some("summarize".to_string()),
+                enabled: None,
+            })
+            .unwrap();
+        let id = subscription.id.as_str();
+
+        let added = store.ingest(id, feed(vec![item("2", "Second"), item("1", "First")]), CacheValidators::default());
+        assert!(added.unwrap().is_empty());
+
+        let added = store
+            .ingest(
+                id,
+                feed(vec![item("4", "Fourth"), item("3", "Third"), item("2", "Second")]),
+                CacheValidators::default(),
+            )
+            .unwrap();
+        let titles: Vec<_> = added.iter().filter_map(|e| e.title.as_deref()).collect();
+        assert_eq!(titles, ["Third", "Fourth"]);
+        assert_eq!(store.entries(id, 10).len(), 4);
+        assert_eq!(store.get(id).unwrap().title.as_deref(), Some("Blog"));
+
+        // Entries and subscriptions survive a restart
+        let reopened = FeedStore::new(dir.path().join("feeds.json"));
+        assert_eq!(reopened.entries(id, 1)[0].title.as_deref(), Some("Fourth"));
+        let variables = entry_variables(&reopened.get(id).unwrap(), &reopened.entries(id, 1)[0]);
+        assert_eq!(variables["feed_title"], "Blog");
+        assert_eq!(variables["guid"], "4");
+    }
+
+    #[test]
+    fn test_subscription_validation_and_due() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = FeedStore::new(dir.path().join("feeds.json"));
+        let request = |url: &str, interval_secs| CreateFeedRequest {
+            url: url.to_string(),
+            interval_secs,
+            workflow_id: None,
+            enabled: None,
+        };
+        assert!(matches!(store.create(request("file:///etc/passwd", None)), Err(FeedError::Invalid(_))));
+        assert!(matches!(store.create(request("https://example.com/rss", Some(10))), Err(FeedError::Invalid(_))));
+
+        let subscription = store.create(request("https://example.com/rss", None)).unwrap();
+        assert!(store.create(request("https://example.com/rss", None)).is_err());
+        assert_eq!(store.due(0).len(), 1);
+
+        store.record_check(&subscription.id, Some("timeout".to_string()));
+        let now = chrono::Utc::now().timestamp();
+        assert!(store.due(now).is_empty());
+        assert_eq!(store.due(now + DEFAULT_INTERVAL_SECS as i64).len(), 1);
+        assert_eq!(store.get(&subscription.id).unwrap().last_error.as_deref(), Some("timeout"));
+    }
+}
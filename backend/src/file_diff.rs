@@ -0,0 +1,303 @@
+//! Unified diffs between two files, or a file and proposed content
+//!
+//! Used to show what a write would change before it happens. Binary files
+//! are only compared for equality. With word diffs, each changed line also
+//! comes split into segments marking the words that differ.
+
+use serde::{Deserialize, Serialize};
+use similar::{ChangeTag, TextDiff};
+use std::path::Path;
+
+use crate::file_preview;
+
+/// Unchanged lines around each change when the caller doesn't say
+pub const DEFAULT_CONTEXT_LINES: usize = 3;
+
+/// Most unchanged lines a caller can ask for around each change
+pub const MAX_CONTEXT_LINES: usize = 100;
+
+/// Files larger than this are not diffed
+const MAX_FILE_BYTES: u64 = 10 * 1024 * 1024;
+
+#[derive(Debug, thiserror::Error)]
+pub enum DiffError {
+    #[error("File not found: {0}")]
+    NotFound(String),
+
+    #[error("Path is not a file: {0}")]
+    NotAFile(String),
+
+    #[error("File is too large to diff: {0}")]
+    TooLarge(String),
+
+    #[error("Give either another file or content to compare with")]
+    InvalidTarget,
+
+    #[error("Failed to read {path}: {reason}")]
+    Io { path: String, reason: String },
+}
+
+/// What the file is compared with
+#[derive(Debug, Clone)]
+pub enum DiffTarget<'a> {
+    File(&'a Path),
+    Content(&'a str),
+}
+
+#[derive(Debug, Clone)]
+pub struct DiffOptions {
+    pub context: usize,
+    pub word_diff: bool,
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        Self {
+            context: DEFAULT_CONTEXT_LINES,
+            word_diff: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LineKind {
+    Context,
+    Added,
+    Removed,
+}
+
+/// Part of a line; `changed` marks the words that differ
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WordSegment {
+    pub text: String,
+    pub changed: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffLine {
+    pub kind: LineKind,
+    /// 1-based line in the old file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old_line: Option<usize>,
+    /// 1-based line in the new file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_line: Option<usize>,
+    pub segments: Vec<WordSegment>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffHunk {
+    pub old_start: usize,
+    pub old_lines: usize,
+    pub new_start: usize,
+    pub new_lines: usize,
+    pub lines: Vec<DiffLine>,
+}
+
+/// Differences between two versions of a file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileDiff {
+    pub old_path: String,
+    /// `None` when comparing with provided content
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_path: Option<String>,
+    /// Whether the old file exists; a missing one diffs as empty
+    pub old_exists: bool,
+    pub binary: bool,
+    pub identical: bool,
+    pub old_size: u64,
+    pub new_size: u64,
+    pub additions: usize,
+    pub deletions: usize,
+    /// `None` for binary files
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unified_diff: Option<String>,
+    /// Changed lines split into words, when asked for
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub hunks: Vec<DiffHunk>,
+}
+
+fn read_file(path: &Path) -> Result<Vec<u8>, DiffError> {
+    let display = path.display().to_string();
+    let io_error = |e: std::io::Error| DiffError::Io { path: display.clone(), reason: e.to_string() };
+    let metadata = std::fs::metadata(path).map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => DiffError::NotFound(display.clone()),
+        _ => io_error(e),
+    })?;
+    if !metadata.is_file() {
+        return Err(DiffError::NotAFile(display));
+    }
+    if metadata.len() > MAX_FILE_BYTES {
+        return Err(DiffError::TooLarge(display));
+    }
+    std::fs::read(path).map_err(io_error)
+}
+
+/// Binary, or text that isn't UTF-8 and can't be diffed as lines
+fn is_binary(bytes: &[u8]) -> bool {
+    file_preview::is_binary(bytes) || std::str::from_utf8(bytes).is_err()
+}
+
+/// Diff the file at `path` against `target`. When the target is content, a
+/// missing file counts as empty so new files can be previewed too.
+pub fn diff(path: &Path, target: DiffTarget<'_>, options: &DiffOptions) -> Result<FileDiff, DiffError> {
+    let (old, old_exists) = match (read_file(path), &target) {
+        (Ok(bytes), _) => (bytes, true),
+        (Err(DiffError::NotFound(_)), DiffTarget::Content(_)) => (Vec::new(), false),
+        (Err(e), _) => return Err(e),
+    };
+    let (new, new_path) = match target {
+        DiffTarget::File(other) => (read_file(other)?, Some(other.display().to_string())),
+        DiffTarget::Content(content) => (content.as_bytes().to_vec(), None),
+    };
+
+    let mut result = FileDiff {
+        old_path: path.display().to_string(),
+        new_path,
+        old_exists,
+        binary: is_binary(&old) || is_binary(&new),
+        identical: old == new,
+        old_size: old.len() as u64,
+        new_size: new.len() as u64,
+        additions: 0,
+        deletions: 0,
+        unified_diff: None,
+        hunks: Vec::new(),
+    };
+    if result.binary {
+        return Ok(result);
+    }
+
+    // Both sides were checked to be UTF-8 above
+    let old = String::from_utf8_lossy(&old);
+    let new = String::from_utf8_lossy(&new);
+    let text_diff = TextDiff::from_lines(old.as_ref(), new.as_ref());
+    for change in text_diff.iter_all_changes() {
+        match change.tag() {
+            ChangeTag::Insert => result.additions += 1,
+            ChangeTag::Delete => result.deletions += 1,
+            ChangeTag::Equal => {}
+        }
+    }
+
+    let context = options.context.min(MAX_CONTEXT_LINES);
+    let old_name = format!("a/{}", result.old_path.trim_start_matches('/'));
+    let new_name = format!("b/{}", result.new_path.as_deref().unwrap_or(&result.old_path).trim_start_matches('/'));
+    result.unified_diff = Some(
+        text_diff
+            .unified_diff()
+            .context_radius(context)
+            .header(&old_name, &new_name)
+            .to_string(),
+    );
+
+    if options.word_diff {
+        result.hunks = word_hunks(&text_diff, context);
+    }
+    Ok(result)
+}
+
+/// Hunks whose lines are split into changed and unchanged words
+fn word_hunks<'a>(text_diff: &'a TextDiff<'a, 'a, 'a, str>, context: usize) -> Vec<DiffHunk> {
+    text_diff
+        .grouped_ops(context)
+        .iter()
+        .map(|group| {
+            let old_range = group.first().map_or(0, |op| op.old_range().start)..group.last().map_or(0, |op| op.old_range().end);
+            let new_range = group.first().map_or(0, |op| op.new_range().start)..group.last().map_or(0, |op| op.new_range().end);
+            let lines = group
+                .iter()
+                .flat_map(|op| text_diff.iter_inline_changes(op))
+                .map(|change| DiffLine {
+                    kind: match change.tag() {
+                        ChangeTag::Equal => LineKind::Context,
+                        ChangeTag::Insert => LineKind::Added,
+                        ChangeTag::Delete => LineKind::Removed,
+                    },
+                    old_line: change.old_index().map(|i| i + 1),
+                    new_line: change.new_index().map(|i| i + 1),
+                    segments: change
+                        .iter_strings_lossy()
+                        .map(|(changed, text)| WordSegment {
+                            text: text.trim_end_matches(['\r', '\n']).to_string(),
+                            changed,
+                        })
+                        .filter(|segment| !segment.text.is_empty())
+                        .collect(),
+                })
+                .collect();
+            DiffHunk {
+                old_start: old_range.start + 1,
+                old_lines: old_range.len(),
+                new_start: new_range.start + 1,
+                new_lines: new_range.len(),
+                lines,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write(dir: &TempDir, name: &str, content: &[u8]) -> std::path::PathBuf {
+        let path = dir.path().join(name);
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_file_against_file_and_content() {
+        let temp_dir = TempDir::new().unwrap();
+        let content: String = (1..=20).map(|i| format!("line {}\n", i)).collect();
+        let old = write(&temp_dir, "old.txt", content.as_bytes());
+        let new = write(&temp_dir, "new.txt", content.replace("line 10\n", "line ten\n").as_bytes());
+
+        let result = diff(&old, DiffTarget::File(&new), &DiffOptions { context: 1, word_diff: false }).unwrap();
+        assert!(!result.identical && !result.binary);
+        assert_eq!((result.additions, result.deletions), (1, 1));
+        let unified = result.unified_diff.unwrap();
+        assert!(unified.contains("@@ -9,3 +9,3 @@\n line 9\n-line 10\n+line ten\n line 11\n"));
+        assert!(result.hunks.is_empty());
+
+        let same = diff(&old, DiffTarget::Content(&content), &DiffOptions::default()).unwrap();
+        assert!(same.identical);
+        assert_eq!(same.unified_diff.as_deref(), Some(""));
+
+        // A file that doesn't exist yet diffs as empty against content
+        let created = diff(&temp_dir.path().join("missing.txt"), DiffTarget::Content("a\nb\n"), &DiffOptions::default()).unwrap();
+        assert!(!created.old_exists);
+        assert_eq!((created.additions, created.deletions), (2, 0));
+        assert!(matches!(
+            diff(&temp_dir.path().join("missing.txt"), DiffTarget::File(&old), &DiffOptions::default()),
+            Err(DiffError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_word_diff_and_binary() {
+        let temp_dir = TempDir::new().unwrap();
+        let old = write(&temp_dir, "config.rs", b"fn main() {\n    let port = 8080;\n}\n");
+
+        let options = DiffOptions { word_diff: true, ..Default::default() };
+        let result = diff(&old, DiffTarget::Content("fn main() {\n    let port = 9090;\n}\n"), &options).unwrap();
+        assert_eq!(result.hunks.len(), 1);
+        let hunk = &result.hunks[0];
+        assert_eq!((hunk.old_start, hunk.old_lines, hunk.new_start, hunk.new_lines), (1, 3, 1, 3));
+        let removed = hunk.lines.iter().find(|l| l.kind == LineKind::Removed).unwrap();
+        assert_eq!(removed.old_line, Some(2));
+        let changed: Vec<&str> = removed.segments.iter().filter(|s| s.changed).map(|s| s.text.as_str()).collect();
+        assert_eq!(changed, vec!["8080;"]);
+        let added = hunk.lines.iter().find(|l| l.kind == LineKind::Added).unwrap();
+        assert_eq!(added.new_line, Some(2));
+        assert_eq!(added.segments.iter().map(|s| s.text.as_str()).collect::<String>(), "    let port = 9090;");
+
+        let image = write(&temp_dir, "logo.png", &[0x89, b'P', b'N', b'G', 0, 1, 2]);
+        let result = diff(&image, DiffTarget::File(&old), &DiffOptions::default()).unwrap();
+        assert!(result.binary && !result.identical);
+        assert_eq!(result.unified_diff, None);
+    }
+}
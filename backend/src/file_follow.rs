@@ -0,0 +1,292 @@
+//! Following files as they grow, like `tail -f`
+//!
+//! [`follow`] starts at the end of a file, optionally after its last few
+//! lines, and sends each line appended to it, keeping only lines matching
+//! the pattern when one is given. It stops at the first match when asked
+//! to, or once the line or time budget is spent. The file is polled rather
+//! than watched so it keeps working on network mounts and across log
+//! rotation: a file that shrinks is followed again from its start.
+
+use regex::Regex;
+use serde::Serialize;
+use std::io::SeekFrom;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tokio::sync::mpsc;
+
+use crate::file_range::{self, RangeError, ReadRange};
+
+/// Bytes kept of each line; longer lines are cut
+pub const MAX_LINE_BYTES: usize = 16 * 1024;
+
+/// Most lines sent from before the follow started
+pub const MAX_INITIAL_LINES: usize = 1000;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Bytes read per poll, so a burst of output is taken in steps
+const READ_CHUNK_BYTES: u64 = 1024 * 1024;
+
+#[derive(Debug, Clone)]
+pub struct FollowOptions {
+    /// Only lines matching are sent
+    pub pattern: Option<Regex>,
+    /// Lines already in the file to start with
+    pub initial_lines: usize,
+    pub max_duration: Duration,
+    /// Stop after sending this many lines
+    pub max_lines: usize,
+    /// Stop after the first line matching `pattern`
+    pub until_match: bool,
+}
+
+/// A line of the followed file
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FollowedLine {
+    pub text: String,
+    /// Byte offset of the line in the file
+    pub offset: u64,
+}
+
+/// Why following stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StopReason {
+    Matched,
+    MaxLines,
+    Timeout,
+    /// The receiver went away
+    Cancelled,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FollowSummary {
+    pub reason: StopReason,
+    pub lines: usize,
+    pub elapsed_ms: u64,
+}
+
+struct Follower<'a> {
+    options: &'a FollowOptions,
+    tx: mpsc::Sender<FollowedLine>,
+    sent: usize,
+}
+
+impl Follower<'_> {
+    /// Send `raw` if it passes the filter; `Some` when following should stop
+    async fn line(&mut self, raw: &[u8], offset: u64) -> Option<StopReason> {
+        let raw = raw.strip_suffix(b"\n").unwrap_or(raw);
+        let raw = raw.strip_suffix(b"\r").unwrap_or(raw);
+        let mut text = String::from_utf8_lossy(&raw[..raw.len().min(MAX_LINE_BYTES)]).into_owned();
+        if raw.len() > MAX_LINE_BYTES {
+            text.push_str(" [line truncated]");
+        }
+        let matched = match &self.options.pattern {
+            Some(pattern) if !pattern.is_match(&text) => return None,
+            Some(_) => true,
+            None => false,
+        };
+        if self.tx.send(FollowedLine { text, offset }).await.is_err() {
+            return Some(StopReason::Cancelled);
+        }
+        self.sent += 1;
+        if matched && self.options.until_match {
+            Some(StopReason::Matched)
+        } else if self.sent >= self.options.max_lines {
+            Some(StopReason::MaxLines)
+        } else {
+            None
+        }
+    }
+}
+
+/// Follow the file at `path`, sending its new lines on `tx`
+pub async fn follow(
+    path: &Path,
+    options: &FollowOptions,
+    tx: mpsc::Sender<FollowedLine>,
+) -> Result<FollowSummary, RangeError> {
+    let started = Instant::now();
+    let display = path.display().to_string();
+    let io_error = |e: std::io::Error| RangeError::Io { path: display.clone(), reason: e.to_string() };
+    let mut follower = Follower { options, tx, sent: 0 };
+    let summary = |reason: StopReason, sent: usize| FollowSummary {
+        reason,
+        lines: sent,
+        elapsed_ms: started.elapsed().as_millis() as u64,
+    };
+
+    // Checks the file exists and is text, and gives where following starts
+    let initial_lines = options.initial_lines.min(MAX_INITIAL_LINES);
+    let seed = {
+        let path = path.to_path_buf();
+        tokio::task::spawn_blocking(move || {
+            file_range::read_range(&path, ReadRange::Tail(initial_lines), MAX_INITIAL_LINES * 256)
+        })
+        .await
+        .map_err(|e| RangeError::Io { path: display.clone(), reason: e.to_string() })??
+    };
+    let mut offset = seed.offset;
+    for line in seed.content.split_inclusive('\n') {
+        if let Some(reason) = follower.line(line.as_bytes(), offset).await {
+            return Ok(summary(reason, follower.sent));
+        }
+        offset += line.len() as u64;
+    }
+    let mut pos = seed.file_size;
+
+    let deadline = started + options.max_duration;
+    // A line still being written, and where it starts
+    let mut partial: Vec<u8> = Vec::new();
+    let mut line_start = pos;
+    let mut buf = Vec::new();
+    loop {
+        if follower.tx.is_closed() {
+            return Ok(summary(StopReason::Cancelled, follower.sent));
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            return Ok(summary(StopReason::Timeout, follower.sent));
+        }
+        // Missing for a moment while a log is rotated; keep waiting
+        let len = tokio::fs::metadata(path).await.map(|m| m.len()).unwrap_or(pos);
+        if len < pos {
+            pos = 0;
+            partial.clear();
+        }
+        if len > pos {
+            let mut file = tokio::fs::File::open(path).await.map_err(io_error)?;
+            file.seek(SeekFrom::Start(pos)).await.map_err(io_error)?;
+            buf.clear();
+            file.take((len - pos).min(READ_CHUNK_BYTES))
+                .read_to_end(&mut buf)
+                .await
+                .map_err(io_error)?;
+
+            for chunk in buf.split_inclusive(|&b| b == b'\n') {
+                if partial.is_empty() {
+                    line_start = pos;
+                }
+                pos += chunk.len() as u64;
+                let room = (MAX_LINE_BYTES + 1).saturating_sub(partial.len());
+                if !chunk.ends_with(b"\n") {
+                    partial.extend_from_slice(&chunk[..chunk.len().min(room)]);
+                    continue;
+                }
+                let stop = if partial.is_empty() {
+                    follower.line(chunk, line_start).await
+                } else {
+                    partial.extend_from_slice(&chunk[..chunk.len().min(room)]);
+                    let line = std::mem::take(&mut partial);
+                    follower.line(&line, line_start).await
+                };
+                if let Some(reason) = stop {
+                    return Ok(summary(reason, follower.sent));
+                }
+            }
+            continue;
+        }
+        tokio::time::sleep(POLL_INTERVAL.min(deadline - now)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn options() -> FollowOptions {
+        FollowOptions {
+            pattern: None,
+            initial_lines: 0,
+            max_duration: Duration::from_secs(5),
+            max_lines: 100,
+            until_match: false,
+        }
+    }
+
+    async fn collect(path: &Path, options: FollowOptions) -> (FollowSummary, Vec<FollowedLine>) {
+        let (tx, mut rx) = mpsc::channel(16);
+        let lines = async {
+            let mut lines = Vec::new();
+            while let Some(line) = rx.recv().await {
+                lines.push(line);
+            }
+            lines
+        };
+        let (summary, lines) = tokio::join!(follow(path, &options, tx), lines);
+        (summary.unwrap(), lines)
+    }
+
+    #[tokio::test]
+    async fn test_follow_until_match() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("build.log");
+        std::fs::write(&path, "compiling a\ncompiling b\n").unwrap();
+
+        let writer_path = path.clone();
+        let writer = tokio::spawn(async move {
+            for line in ["compiling c\n", "warning: unused\n", "Finished in 3s\n", "never seen\n"] {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                let mut file = std::fs::OpenOptions::new().append(true).open(&writer_path).unwrap();
+                // Written in two parts to exercise partial lines
+                let (a, b) = line.split_at(4);
+                file.write_all(a.as_bytes()).unwrap();
+                file.flush().unwrap();
+                tokio::time::sleep(Duration::from_millis(300)).await;
+                file.write_all(b.as_bytes()).unwrap();
+            }
+        });
+
+        let (summary, lines) = collect(
+            &path,
+            FollowOptions {
+                pattern: Some(Regex::new("^(Finished|error)").unwrap()),
+                initial_lines: 1,
+                until_match: true,
+                ..options()
+            },
+        )
+        .await;
+        assert_eq!(summary.reason, StopReason::Matched);
+        let texts: Vec<&str> = lines.iter().map(|l| l.text.as_str()).collect();
+        assert_eq!(texts, ["Finished in 3s"]);
+        assert_eq!(lines[0].offset, "compiling a\ncompiling b\ncompiling c\nwarning: unused\n".len() as u64);
+        writer.abort();
+    }
+
+    #[tokio::test]
+    async fn test_budgets_and_rotation() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("server.log");
+        std::fs::write(&path, "one\ntwo\nthree\n").unwrap();
+
+        let (summary, lines) = collect(&path, FollowOptions { initial_lines: 3, max_lines: 2, ..options() }).await;
+        assert_eq!(summary.reason, StopReason::MaxLines);
+        assert_eq!(lines.iter().map(|l| l.text.as_str()).collect::<Vec<_>>(), ["one", "two"]);
+
+        let writer_path = path.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(400)).await;
+            std::fs::write(&writer_path, "fresh\n").unwrap();
+        });
+        let (summary, lines) = collect(
+            &path,
+            FollowOptions { max_duration: Duration::from_millis(1200), ..options() },
+        )
+        .await;
+        assert_eq!(summary.reason, StopReason::Timeout);
+        assert_eq!(lines, [FollowedLine { text: "fresh".to_string(), offset: 0 }]);
+
+        assert!(matches!(
+            collect_err(&dir.path().join("missing.log")).await,
+            RangeError::NotFound(_)
+        ));
+    }
+
+    async fn collect_err(path: &Path) -> RangeError {
+        let (tx, _rx) = mpsc::channel(1);
+        follow(path, &options(), tx).await.unwrap_err()
+    }
+}
@@ -0,0 +1,165 @@
+//! Recent file operations
+//!
+//! Moves, copies, renames and deletions made through the files API or by the
+//! agent are recorded here so the recent-activity view can show them. Each
+//! entry is also published on the event bus.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::{Arc, RwLock};
+
+use crate::events::{self, Event};
+use crate::file_transfer::{TransferKind, TransferOutcome};
+use crate::recycle_bin::DeleteOutcome;
+
+/// Entries kept before the oldest are dropped
+const MAX_ENTRIES: usize = 1000;
+
+/// Kind of file operation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileOperation {
+    Move,
+    Copy,
+    Rename,
+    Delete,
+}
+
+impl From<TransferKind> for FileOperation {
+    fn from(kind: TransferKind) -> Self {
+        match kind {
+            TransferKind::Move => FileOperation::Move,
+            TransferKind::Copy => FileOperation::Copy,
+            TransferKind::Rename => FileOperation::Rename,
+        }
+    }
+}
+
+/// Who performed an operation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationOrigin {
+    /// The UI, through the files API
+    User,
+    /// An agent tool call
+    Agent,
+}
+
+/// A recorded file operation
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FileHistoryEntry {
+    pub id: String,
+    pub operation: FileOperation,
+    pub origin: OperationOrigin,
+    pub source: String,
+    /// Where the file ended up; `None` for deletions
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub destination: Option<String>,
+    /// Set for deletions that can be undone from the trash
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trash_id: Option<String>,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Bounded in-memory log of file operations, newest last
+#[derive(Debug, Default)]
+pub struct FileHistory {
+    entries: RwLock<VecDeque<FileHistoryEntry>>,
+}
+
+lazy_static::lazy_static! {
+    static ref GLOBAL_HISTORY: Arc<FileHistory> = Arc::new(FileHistory::default());
+}
+
+impl FileHistory {
+    /// Shared process-wide history
+    pub fn global() -> Arc<FileHistory> {
+        GLOBAL_HISTORY.clone()
+    }
+
+    /// Record a move, copy or rename
+    pub fn record_transfer(&self, origin: OperationOrigin, outcome: &TransferOutcome) -> FileHistoryEntry {
+        self.record(FileHistoryEntry {
+            id: uuid::Uuid::new_v4().to_string(),
+            operation: outcome.kind.into(),
+            origin,
+            source: outcome.source.clone(),
+            destination: Some(outcome.destination.clone()),
+            trash_id: None,
+            timestamp: Utc::now(),
+        })
+    }
+
+    /// Record a deletion
+    pub fn record_delete(&self, origin: OperationOrigin, outcome: &DeleteOutcome) -> FileHistoryEntry {
+        self.record(FileHistoryEntry {
+            id: uuid::Uuid::new_v4().to_string(),
+            operation: FileOperation::Delete,
+            origin,
+            source: outcome.path.clone(),
+            destination: None,
+            trash_id: outcome.trash_id.clone(),
+            timestamp: Utc::now(),
+        })
+    }
+
+    /// Most recent entries first
+    pub fn recent(&self, limit: usize) -> Vec<FileHistoryEntry> {
+        let entries = self.entries.read().unwrap_or_else(|e| e.into_inner());
+        entries.iter().rev().take(limit).cloned().collect()
+    }
+
+    fn record(&self, entry: FileHistoryEntry) -> FileHistoryEntry {
+        {
+            let mut entries = self.entries.write().unwrap_or_else(|e| e.into_inner());
+            if entries.len() >= MAX_ENTRIES {
+                entries.pop_front();
+            }
+            entries.push_back(entry.clone());
+        }
+
+        events::publish(Event::FileOperation {
+            operation: entry.operation,
+            source: entry.source.clone(),
+            destination: entry.destination.clone(),
+        });
+        entry
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recent_is_newest_first_and_bounded() {
+        let history = FileHistory::default();
+        for i in 0..MAX_ENTRIES + 5 {
+            history.record_transfer(
+                OperationOrigin::User,
+                &TransferOutcome {
+                    kind: TransferKind::Copy,
+                    source: format!("/tmp/{}", i),
+                    destination: format!("/tmp/{}-copy", i),
+                    overwritten: false,
+                },
+            );
+        }
+        history.record_delete(
+            OperationOrigin::Agent,
+            &DeleteOutcome {
+                path: "/tmp/gone".to_string(),
+                permanent: false,
+                restore_path: Some("/tmp/gone".to_string()),
+                trash_id: Some("trash-1".to_string()),
+            },
+        );
+
+        let recent = history.recent(2);
+        assert_eq!(recent[0].operation, FileOperation::Delete);
+        assert_eq!(recent[0].trash_id.as_deref(), Some("trash-1"));
+        assert_eq!(recent[1].source, format!("/tmp/{}", MAX_ENTRIES + 4));
+        assert_eq!(history.recent(usize::MAX).len(), MAX_ENTRIES);
+    }
+}
@@ -0,0 +1,416 @@
+//! Bounded file previews with syntax highlighting hints
+//!
+//! Content search results point at a line; [`preview`] returns the lines
+//! around it so the frontend can show the match in context without fetching
+//! the whole file. Lines carry token ranges classified with syntect's
+//! grammars into a small set of kinds the frontend maps to colours, plus the
+//! ranges matching the search query. All ranges are character offsets.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+use syntect::parsing::{ParseState, Scope, ScopeStack, SyntaxReference, SyntaxSet};
+
+/// Lines shown on each side of the focus line when the caller doesn't say
+pub const DEFAULT_CONTEXT_LINES: usize = 10;
+
+/// Most lines a caller can ask for on each side
+pub const MAX_CONTEXT_LINES: usize = 200;
+
+/// Characters kept of each line; longer lines are cut
+const MAX_LINE_CHARS: usize = 1000;
+
+/// Lines before the window parsed to get the highlighter into the right
+/// state (inside a block comment, say). Further down we start fresh.
+const MAX_LEAD_LINES: usize = 5000;
+
+/// Bytes checked for NUL to tell binary files apart
+pub const BINARY_SNIFF_BYTES: usize = 8192;
+
+lazy_static::lazy_static! {
+    static ref SYNTAX_SET: SyntaxSet = SyntaxSet::load_defaults_newlines();
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum PreviewError {
+    #[error("File not found: {0}")]
+    NotFound(String),
+
+    #[error("Path is not a file: {0}")]
+    NotAFile(String),
+
+    #[error("Cannot preview binary file: {0}")]
+    Binary(String),
+
+    #[error("Failed to read {path}: {reason}")]
+    Io { path: String, reason: String },
+}
+
+/// Coarse token class the frontend can colour
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenKind {
+    Comment,
+    String,
+    Number,
+    Constant,
+    Keyword,
+    Operator,
+    Function,
+    Type,
+    Tag,
+    Attribute,
+    Variable,
+    Punctuation,
+}
+
+/// Character range within a line
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Highlighted token within a line
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HighlightToken {
+    pub start: usize,
+    pub end: usize,
+    pub kind: TokenKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PreviewLine {
+    /// 1-based line number
+    pub number: usize,
+    pub text: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tokens: Vec<HighlightToken>,
+    /// Ranges matching the query
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub matches: Vec<Span>,
+    /// Whether the line was cut at MAX_LINE_CHARS
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+}
+
+/// A window of lines from a file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FilePreview {
+    pub path: String,
+    /// Name of the syntax used for highlighting
+    pub language: Option<String>,
+    /// Line the window is centred on
+    pub focus_line: Option<usize>,
+    pub lines: Vec<PreviewLine>,
+    /// Whether the file continues after the last line
+    pub has_more: bool,
+}
+
+/// Which part of a file to preview
+#[derive(Debug, Clone)]
+pub struct PreviewOptions {
+    /// 1-based line to centre on; the start of the file when unset
+    pub line: Option<usize>,
+    pub context: usize,
+    pub highlight: bool,
+    /// Text to mark in the returned lines, case-insensitively
+    pub query: Option<String>,
+}
+
+impl Default for PreviewOptions {
+    fn default() -> Self {
+        Self {
+            line: None,
+            context: DEFAULT_CONTEXT_LINES,
+            highlight: true,
+            query: None,
+        }
+    }
+}
+
+/// Whether `bytes`, the start of a file, look binary: a NUL in the first
+/// [`BINARY_SNIFF_BYTES`]
+pub fn is_binary(bytes: &[u8]) -> bool {
+    bytes.iter().take(BINARY_SNIFF_BYTES).any(|b| *b == 0)
+}
+
+/// Read the lines around `options.line` from the file at `path`
+pub fn preview(path: &Path, options: &PreviewOptions) -> Result<FilePreview, PreviewError> {
+    let display = path.display().to_string();
+    if !path.exists() {
+        return Err(PreviewError::NotFound(display));
+    }
+    if !path.is_file() {
+        return Err(PreviewError::NotAFile(display));
+    }
+    let io_error = |e: std::io::Error| PreviewError::Io { path: display.clone(), reason: e.to_string() };
+
+    let mut reader = BufReader::new(File::open(path).map_err(io_error)?);
+    if is_binary(reader.fill_buf().map_err(io_error)?) {
+        return Err(PreviewError::Binary(display.clone()));
+    }
+
+    let context = options.context.min(MAX_CONTEXT_LINES);
+    let (first, last) = match options.line {
+        Some(line) => (line.max(1).saturating_sub(context).max(1), line.max(1) + context),
+        None => (1, 1 + 2 * context),
+    };
+
+    let mut highlighter = if options.highlight { Highlighter::for_file(path, &mut reader) } else { None };
+    let matcher = options
+        .query
+        .as_deref()
+        .filter(|q| !q.trim().is_empty())
+        .and_then(|q| Regex::new(&format!("(?i){}", regex::escape(q.trim()))).ok());
+
+    let mut lines = Vec::new();
+    let mut buffer = Vec::new();
+    let mut number = 0;
+    let mut has_more = false;
+    loop {
+        buffer.clear();
+        if reader.by_ref().take(1 << 20).read_until(b'\n', &mut buffer).map_err(io_error)? == 0 {
+            break;
+        }
+        // Skip the rest of a line longer than the read cap
+        if !buffer.ends_with(b"\n") && buffer.len() == 1 << 20 {
+            let mut rest = Vec::new();
+            reader.read_until(b'\n', &mut rest).map_err(io_error)?;
+        }
+        number += 1;
+        if number > last {
+            has_more = true;
+            break;
+        }
+        let raw = String::from_utf8_lossy(&buffer);
+        let content = raw.trim_end_matches(['\n', '\r']);
+        let truncated = content.chars().count() > MAX_LINE_CHARS;
+        let text: String = content.chars().take(MAX_LINE_CHARS).collect();
+
+        let in_lead = number < first;
+        if in_lead && number + MAX_LEAD_LINES < first {
+            continue;
+        }
+        let tokens = match highlighter.as_mut() {
+            Some(h) => h.tokens(&text, !in_lead),
+            None => Vec::new(),
+        };
+        if in_lead {
+            continue;
+        }
+        let matches = matcher
+            .as_ref()
+            .map(|re| {
+                re.find_iter(&text)
+                    .map(|m| Span { start: char_offset(&text, m.start()), end: char_offset(&text, m.end()) })
+                    .collect()
+            })
+            .unwrap_or_default();
+        lines.push(PreviewLine { number, text, tokens, matches, truncated });
+    }
+
+    Ok(FilePreview {
+        path: display,
+        language: highlighter.map(|h| h.syntax.name.clone()),
+        focus_line: options.line,
+        lines,
+        has_more,
+    })
+}
+
+fn char_offset(text: &str, byte: usize) -> usize {
+    text[..byte].chars().count()
+}
+
+/// Incremental syntect parser for one file
+struct Highlighter {
+    syntax: &'static SyntaxReference,
+    state: ParseState,
+    stack: ScopeStack,
+}
+
+impl Highlighter {
+    /// Pick a grammar by file name, falling back to the first line
+    /// (shebangs, modelines)
+    fn for_file(path: &Path, reader: &mut BufReader<File>) -> Option<Self> {
+        let by_name = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .and_then(|e| SYNTAX_SET.find_syntax_by_extension(e))
+            .or_else(|| {
+                path.file_name()
+                    .and_then(|n| n.to_str())
+                    .and_then(|n| SYNTAX_SET.find_syntax_by_extension(n))
+            });
+        let syntax = by_name.or_else(|| {
+            let head = reader.fill_buf().ok()?;
+            let first_line = head.split(|b| *b == b'\n').next()?;
+            SYNTAX_SET.find_syntax_by_first_line(&String::from_utf8_lossy(first_line))
+        })?;
+        if syntax.name == "Plain Text" {
+            return None;
+        }
+        Some(Self { syntax, state: ParseState::new(syntax), stack: ScopeStack::new() })
+    }
+
+    fn restart(&mut self) {
+        self.state = ParseState::new(self.syntax);
+        self.stack = ScopeStack::new();
+    }
+
+    /// Parse the next line, returning its tokens when `collect` is set
+    fn tokens(&mut self, text: &str, collect: bool) -> Vec<HighlightToken> {
+        let line = format!("{}\n", text);
+        let ops = match self.state.parse_line(&line, &SYNTAX_SET) {
+            Ok(ops) => ops,
+            Err(_) => {
+                self.restart();
+                return Vec::new();
+            }
+        };
+
+        let mut tokens: Vec<HighlightToken> = Vec::new();
+        let mut push = |stack: &ScopeStack, from: usize, to: usize| {
+            let to = to.min(text.len());
+            if !collect || from >= to {
+                return;
+            }
+            if let Some(kind) = classify(stack) {
+                let (start, end) = (char_offset(text, from), char_offset(text, to));
+                match tokens.last_mut() {
+                    Some(last) if last.kind == kind && last.end == start => last.end = end,
+                    _ => tokens.push(HighlightToken { start, end, kind }),
+                }
+            }
+        };
+        let mut position = 0;
+        for (offset, op) in ops {
+            push(&self.stack, position, offset);
+            position = offset;
+            if self.stack.apply(&op).is_err() {
+                self.stack = ScopeStack::new();
+            }
+        }
+        push(&self.stack, position, text.len());
+        tokens
+    }
+}
+
+/// Kind of the innermost scope we have a class for. Everything inside a
+/// comment or string, delimiters included, takes that kind.
+fn classify(stack: &ScopeStack) -> Option<TokenKind> {
+    let scopes = stack.as_slice();
+    scopes
+        .iter()
+        .filter_map(|scope| kind_of(*scope))
+        .find(|kind| matches!(kind, TokenKind::Comment | TokenKind::String))
+        .or_else(|| scopes.iter().rev().find_map(|scope| kind_of(*scope)))
+}
+
+fn kind_of(scope: Scope) -> Option<TokenKind> {
+    let name = scope.build_string();
+    let kind = match name.as_str() {
+        n if n.starts_with("comment") => TokenKind::Comment,
+        n if n.starts_with("string") => TokenKind::String,
+        n if n.starts_with("constant.numeric") => TokenKind::Number,
+        n if n.starts_with("constant") => TokenKind::Constant,
+        n if n.starts_with("keyword.operator") => TokenKind::Operator,
+        n if n.starts_with("support.type") || n.starts_with("entity.name.type")
+            || n.starts_with("entity.name.class") || n.starts_with("entity.name.struct")
+            || n.starts_with("entity.name.enum") => TokenKind::Type,
+        n if n.starts_with("keyword") || n.starts_with("storage") => TokenKind::Keyword,
+        n if n.starts_with("entity.name.function") || n.starts_with("support.function")
+            || n.starts_with("variable.function") => TokenKind::Function,
+        n if n.starts_with("entity.name.tag") => TokenKind::Tag,
+        n if n.starts_with("entity.other.attribute-name") => TokenKind::Attribute,
+        n if n.starts_with("variable") => TokenKind::Variable,
+        n if n.starts_with("punctuation") => TokenKind::Punctuation,
+        _ => return None,
+    };
+    Some(kind)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write(dir: &TempDir, name: &str, content: &str) -> std::path::PathBuf {
+        let path = dir.path().join(name);
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_window_around_line_with_matches() {
+        let temp_dir = TempDir::new().unwrap();
+        let content: String = (1..=50).map(|i| format!("line {}\n", i)).collect();
+        let path = write(&temp_dir, "notes.txt", &content);
+
+        let options = PreviewOptions { line: Some(20), context: 2, query: Some("LINE 2".to_string()), ..Default::default() };
+        let result = preview(&path, &options).unwrap();
+        let numbers: Vec<usize> = result.lines.iter().map(|l| l.number).collect();
+        assert_eq!(numbers, vec![18, 19, 20, 21, 22]);
+        assert_eq!(result.lines[2].text, "line 20");
+        assert_eq!(result.lines[2].matches, vec![Span { start: 0, end: 6 }]);
+        assert!(result.lines[0].matches.is_empty());
+        assert!(result.has_more);
+        assert_eq!(result.language, None);
+
+        // Window at the end of the file
+        let options = PreviewOptions { line: Some(50), context: 3, ..Default::default() };
+        let result = preview(&path, &options).unwrap();
+        assert_eq!(result.lines.first().unwrap().number, 47);
+        assert_eq!(result.lines.len(), 4);
+        assert!(!result.has_more);
+    }
+
+    #[test]
+    fn test_highlighting_keeps_state_from_earlier_lines() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = write(&temp_dir, "main.rs", "/* start\n still comment\n*/\nfn main() { let x = 42; }\n");
+
+        let options = PreviewOptions { line: Some(2), context: 0, ..Default::default() };
+        let result = preview(&path, &options).unwrap();
+        assert_eq!(result.language.as_deref(), Some("Rust"));
+        assert_eq!(result.lines.len(), 1);
+        assert_eq!(
+            result.lines[0].tokens,
+            vec![HighlightToken { start: 0, end: 14, kind: TokenKind::Comment }]
+        );
+
+        let options = PreviewOptions { line: Some(4), context: 0, ..Default::default() };
+        let line = &preview(&path, &options).unwrap().lines[0];
+        let kind_at = |offset: usize| line.tokens.iter().find(|t| t.start <= offset && offset < t.end).map(|t| t.kind);
+        assert_eq!(kind_at(0), Some(TokenKind::Keyword));
+        assert_eq!(kind_at(3), Some(TokenKind::Function));
+        assert_eq!(kind_at(20), Some(TokenKind::Number));
+    }
+
+    #[test]
+    fn test_binary_and_missing_files_are_rejected() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("blob.bin");
+        std::fs::write(&path, [0x7f, b'E', b'L', b'F', 0, 0, 1]).unwrap();
+        assert!(matches!(preview(&path, &PreviewOptions::default()), Err(PreviewError::Binary(_))));
+        assert!(matches!(
+            preview(&temp_dir.path().join("missing.rs"), &PreviewOptions::default()),
+            Err(PreviewError::NotFound(_))
+        ));
+        assert!(matches!(preview(temp_dir.path(), &PreviewOptions::default()), Err(PreviewError::NotAFile(_))));
+    }
+
+    #[test]
+    fn test_long_lines_are_truncated() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = write(&temp_dir, "min.js", &format!("{}\nnext\n", "a".repeat(5000)));
+        let result = preview(&path, &PreviewOptions::default()).unwrap();
+        assert_eq!(result.lines.len(), 2);
+        assert!(result.lines[0].truncated);
+        assert_eq!(result.lines[0].text.len(), MAX_LINE_CHARS);
+        assert_eq!(result.lines[1].text, "next");
+    }
+}
@@ -0,0 +1,362 @@
+//! Bounded reads of parts of large files
+//!
+//! Logs and data dumps can run to hundreds of megabytes, far more than a
+//! tool result or API response should carry. [`read_range`] streams through
+//! the file and keeps at most `max_bytes` of the requested part: lines
+//! before a line range are skipped without being stored, and a tail is
+//! found by scanning backwards from the end in chunks.
+
+use serde::Serialize;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use crate::file_preview::{is_binary, BINARY_SNIFF_BYTES};
+
+/// Chunk size when scanning backwards for a tail
+const TAIL_CHUNK_BYTES: usize = 64 * 1024;
+
+#[derive(Debug, thiserror::Error)]
+pub enum RangeError {
+    #[error("File not found: {0}")]
+    NotFound(String),
+
+    #[error("Path is not a file: {0}")]
+    NotAFile(String),
+
+    #[error("Not a text file: {0}")]
+    Binary(String),
+
+    #[error("{0}")]
+    Invalid(String),
+
+    #[error("Failed to read {path}: {reason}")]
+    Io { path: String, reason: String },
+}
+
+/// Which part of a file to read
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReadRange {
+    /// 1-based lines, `end` inclusive; to the end of the file when unset
+    Lines { start: usize, end: Option<usize> },
+    /// The first lines of the file
+    Head(usize),
+    /// The last lines of the file
+    Tail(usize),
+    /// Bytes from `offset`; to the end of the file when `limit` is unset
+    Bytes { offset: u64, limit: Option<u64> },
+}
+
+impl ReadRange {
+    /// Range from request parameters; at most one way of choosing a range
+    /// may be used, and `default` applies when none is
+    pub fn from_params(
+        start_line: Option<usize>,
+        end_line: Option<usize>,
+        head: Option<usize>,
+        tail: Option<usize>,
+        offset: Option<u64>,
+        limit: Option<u64>,
+        default: ReadRange,
+    ) -> Result<ReadRange, RangeError> {
+        let lines = start_line.is_some() || end_line.is_some();
+        let bytes = offset.is_some() || limit.is_some();
+        let chosen = [lines, head.is_some(), tail.is_some(), bytes].iter().filter(|set| **set).count();
+        if chosen > 1 {
+            return Err(RangeError::Invalid(
+                "Use only one of start_line/end_line, head, tail or offset/limit".to_string(),
+            ));
+        }
+        let range = if lines {
+            ReadRange::Lines { start: start_line.unwrap_or(1).max(1), end: end_line }
+        } else if let Some(n) = head {
+            ReadRange::Head(n)
+        } else if let Some(n) = tail {
+            ReadRange::Tail(n)
+        } else if bytes {
+            ReadRange::Bytes { offset: offset.unwrap_or(0), limit }
+        } else {
+            default
+        };
+        if let ReadRange::Lines { start, end: Some(end) } = range {
+            if end < start {
+                return Err(RangeError::Invalid(format!("end_line {} is before start_line {}", end, start)));
+            }
+        }
+        Ok(range)
+    }
+}
+
+/// Part of a file that was read
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct RangeRead {
+    pub content: String,
+    /// Byte offset of the content in the file
+    pub offset: u64,
+    /// Bytes of the file the content covers
+    pub length: u64,
+    pub file_size: u64,
+    /// First and last line of the content, for ranges counted from the
+    /// start of the file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_line: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_line: Option<usize>,
+    /// Whether the content was cut at the byte limit
+    pub truncated: bool,
+    /// Whether the file continues after the content
+    pub has_more: bool,
+}
+
+/// Read `range` of the text file at `path`, keeping at most `max_bytes`
+pub fn read_range(path: &Path, range: ReadRange, max_bytes: usize) -> Result<RangeRead, RangeError> {
+    let display = path.display().to_string();
+    if !path.exists() {
+        return Err(RangeError::NotFound(display));
+    }
+    if !path.is_file() {
+        return Err(RangeError::NotAFile(display));
+    }
+    let io_error = |e: io::Error| RangeError::Io { path: display.clone(), reason: e.to_string() };
+
+    let mut file = File::open(path).map_err(io_error)?;
+    let file_size = file.metadata().map_err(io_error)?.len();
+    let mut sniff = Vec::with_capacity(BINARY_SNIFF_BYTES);
+    (&mut file).take(BINARY_SNIFF_BYTES as u64).read_to_end(&mut sniff).map_err(io_error)?;
+    if is_binary(&sniff) {
+        return Err(RangeError::Binary(display));
+    }
+    file.seek(SeekFrom::Start(0)).map_err(io_error)?;
+
+    let mut read = match range {
+        ReadRange::Lines { start, end } => read_lines(file, start, end, max_bytes),
+        ReadRange::Head(0) => Ok(RangeRead { has_more: file_size > 0, ..Default::default() }),
+        ReadRange::Head(n) => read_lines(file, 1, Some(n), max_bytes),
+        ReadRange::Tail(n) => read_tail(file, file_size, n, max_bytes),
+        ReadRange::Bytes { offset, limit } => read_bytes(file, file_size, offset, limit, max_bytes),
+    }
+    .map_err(io_error)?;
+    read.file_size = file_size;
+    Ok(read)
+}
+
+/// Read up to and including the next newline, keeping at most `keep` bytes
+/// in `out`; returns the bytes consumed, 0 at the end of the file
+fn next_line<R: BufRead>(reader: &mut R, out: &mut Vec<u8>, keep: usize) -> io::Result<u64> {
+    let mut consumed = 0;
+    loop {
+        let buf = reader.fill_buf()?;
+        if buf.is_empty() {
+            return Ok(consumed);
+        }
+        let (len, done) = match buf.iter().position(|&b| b == b'\n') {
+            Some(i) => (i + 1, true),
+            None => (buf.len(), false),
+        };
+        let room = keep.saturating_sub(out.len());
+        out.extend_from_slice(&buf[..len.min(room)]);
+        reader.consume(len);
+        consumed += len as u64;
+        if done {
+            return Ok(consumed);
+        }
+    }
+}
+
+fn read_lines(file: File, start: usize, end: Option<usize>, max_bytes: usize) -> io::Result<RangeRead> {
+    let mut reader = BufReader::new(file);
+    let mut offset = 0;
+    let mut skipped = Vec::new();
+    for _ in 1..start {
+        let consumed = next_line(&mut reader, &mut skipped, 0)?;
+        if consumed == 0 {
+            return Ok(RangeRead { offset, ..Default::default() });
+        }
+        offset += consumed;
+    }
+
+    let mut out = Vec::new();
+    let mut read = RangeRead { offset, ..Default::default() };
+    let mut line = start;
+    while end.is_none_or(|end| line <= end) {
+        let kept_before = out.len();
+        let consumed = next_line(&mut reader, &mut out, max_bytes)?;
+        if consumed == 0 {
+            break;
+        }
+        read.start_line.get_or_insert(line);
+        read.end_line = Some(line);
+        read.length += (out.len() - kept_before) as u64;
+        if ((out.len() - kept_before) as u64) < consumed {
+            read.truncated = true;
+            break;
+        }
+        line += 1;
+    }
+    read.has_more = read.truncated || !reader.fill_buf()?.is_empty();
+    read.content = into_text(out);
+    Ok(read)
+}
+
+/// Offset where the last `n` lines start, not counting a final newline as
+/// the start of an empty line
+fn tail_offset(file: &mut File, file_size: u64, n: usize) -> io::Result<u64> {
+    if n == 0 {
+        return Ok(file_size);
+    }
+    let mut buf = vec![0; TAIL_CHUNK_BYTES];
+    let mut pos = file_size;
+    let mut newlines = 0;
+    while pos > 0 {
+        let len = TAIL_CHUNK_BYTES.min(pos as usize);
+        pos -= len as u64;
+        file.seek(SeekFrom::Start(pos))?;
+        file.read_exact(&mut buf[..len])?;
+        for i in (0..len).rev() {
+            let at = pos + i as u64;
+            if buf[i] == b'\n' && at + 1 < file_size {
+                newlines += 1;
+                if newlines == n {
+                    return Ok(at + 1);
+                }
+            }
+        }
+    }
+    Ok(0)
+}
+
+fn read_tail(mut file: File, file_size: u64, n: usize, max_bytes: usize) -> io::Result<RangeRead> {
+    let mut offset = tail_offset(&mut file, file_size, n)?;
+    let mut truncated = false;
+    // Too long to keep whole: keep the end, from the first line boundary
+    if file_size - offset > max_bytes as u64 {
+        truncated = true;
+        offset = file_size - max_bytes as u64;
+        file.seek(SeekFrom::Start(offset))?;
+        let mut reader = BufReader::new(&mut file);
+        let skipped = next_line(&mut reader, &mut Vec::new(), 0)?;
+        if offset + skipped < file_size {
+            offset += skipped;
+        }
+    }
+    file.seek(SeekFrom::Start(offset))?;
+    let mut out = Vec::with_capacity((file_size - offset) as usize);
+    file.take(file_size - offset).read_to_end(&mut out)?;
+    Ok(RangeRead {
+        offset,
+        length: out.len() as u64,
+        truncated,
+        content: into_text(out),
+        ..Default::default()
+    })
+}
+
+fn read_bytes(
+    mut file: File,
+    file_size: u64,
+    offset: u64,
+    limit: Option<u64>,
+    max_bytes: usize,
+) -> io::Result<RangeRead> {
+    let offset = offset.min(file_size);
+    let available = file_size - offset;
+    let wanted = limit.unwrap_or(available).min(available);
+    let take = wanted.min(max_bytes as u64);
+    file.seek(SeekFrom::Start(offset))?;
+    let mut out = Vec::with_capacity(take as usize);
+    file.take(take).read_to_end(&mut out)?;
+    Ok(RangeRead {
+        offset,
+        length: out.len() as u64,
+        truncated: take < wanted,
+        has_more: offset + (out.len() as u64) < file_size,
+        content: into_text(out),
+        ..Default::default()
+    })
+}
+
+/// Decode as UTF-8, dropping a character cut off at the end and replacing
+/// invalid sequences
+fn into_text(mut bytes: Vec<u8>) -> String {
+    if let Err(e) = std::str::from_utf8(&bytes) {
+        if e.error_len().is_none() {
+            bytes.truncate(e.valid_up_to());
+        }
+    }
+    match String::from_utf8(bytes) {
+        Ok(text) => text,
+        Err(e) => String::from_utf8_lossy(e.as_bytes()).into_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log_file(lines: usize) -> (tempfile::TempDir, std::path::PathBuf) {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("app.log");
+        let content: String = (1..=lines).map(|i| format!("line {}\n", i)).collect();
+        std::fs::write(&path, content).unwrap();
+        (dir, path)
+    }
+
+    #[test]
+    fn test_line_ranges() {
+        let (_dir, path) = log_file(1000);
+
+        let read = read_range(&path, ReadRange::Lines { start: 10, end: Some(12) }, 1024).unwrap();
+        assert_eq!(read.content, "line 10\nline 11\nline 12\n");
+        assert_eq!((read.start_line, read.end_line), (Some(10), Some(12)));
+        assert_eq!(read.offset, "line 1\n".len() as u64 * 9);
+        assert!(read.has_more && !read.truncated);
+
+        let head = read_range(&path, ReadRange::Head(2), 1024).unwrap();
+        assert_eq!(head.content, "line 1\nline 2\n");
+
+        let tail = read_range(&path, ReadRange::Tail(2), 1024).unwrap();
+        assert_eq!(tail.content, "line 999\nline 1000\n");
+        assert!(!tail.has_more && !tail.truncated);
+        assert_eq!(tail.offset + tail.length, tail.file_size);
+
+        let past_end = read_range(&path, ReadRange::Lines { start: 2000, end: None }, 1024).unwrap();
+        assert!(past_end.content.is_empty() && past_end.start_line.is_none());
+    }
+
+    #[test]
+    fn test_byte_limit_bounds_reads() {
+        let (_dir, path) = log_file(100_000);
+
+        let whole = read_range(&path, ReadRange::Lines { start: 1, end: None }, 20).unwrap();
+        assert_eq!(whole.content, "line 1\nline 2\nline 3");
+        assert_eq!(whole.end_line, Some(3));
+        assert!(whole.truncated && whole.has_more);
+
+        // The end of an oversized tail is kept, from a line boundary
+        let tail = read_range(&path, ReadRange::Tail(50_000), 30).unwrap();
+        assert_eq!(tail.content, "line 99999\nline 100000\n");
+        assert!(tail.truncated);
+
+        let bytes = read_range(&path, ReadRange::Bytes { offset: 7, limit: Some(100) }, 14).unwrap();
+        assert_eq!(bytes.content, "line 2\nline 3\n");
+        assert!(bytes.truncated && bytes.has_more);
+    }
+
+    #[test]
+    fn test_params_and_binary() {
+        let lines = ReadRange::Lines { start: 1, end: None };
+        assert_eq!(
+            ReadRange::from_params(None, None, None, Some(5), None, None, lines).unwrap(),
+            ReadRange::Tail(5)
+        );
+        assert_eq!(ReadRange::from_params(None, None, None, None, None, None, lines).unwrap(), lines);
+        assert!(ReadRange::from_params(Some(3), None, Some(5), None, None, None, lines).is_err());
+        assert!(ReadRange::from_params(Some(5), Some(2), None, None, None, None, lines).is_err());
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("blob.bin");
+        std::fs::write(&path, [0x89, b'P', b'N', b'G', 0, 0, 1]).unwrap();
+        assert!(matches!(read_range(&path, lines, 1024), Err(RangeError::Binary(_))));
+        assert!(matches!(read_range(dir.path(), lines, 1024), Err(RangeError::NotAFile(_))));
+    }
+}
@@ -0,0 +1,305 @@
+//! Move, copy and rename with collision handling
+//!
+//! Shared by the `/api/v1/files/{move,copy,rename}` endpoints and the
+//! matching agent tools. When the destination already exists the caller
+//! chooses a [`Collision`] policy: abort (the default), overwrite it, or pick
+//! a free name by adding a ` (n)` suffix.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Highest ` (n)` suffix tried before giving up
+const MAX_SUFFIX: u32 = 999;
+
+/// Kind of transfer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransferKind {
+    Move,
+    Copy,
+    Rename,
+}
+
+/// What to do when the destination already exists
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Collision {
+    /// Fail without touching anything
+    #[default]
+    Abort,
+    /// Replace the existing file or directory
+    Overwrite,
+    /// Pick a free name such as `report (1).pdf`
+    Rename,
+}
+
+/// Result of a transfer
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TransferOutcome {
+    pub kind: TransferKind,
+    pub source: String,
+    /// Final destination, which differs from the requested one after a
+    /// [`Collision::Rename`]
+    pub destination: String,
+    /// Whether an existing destination was replaced
+    pub overwritten: bool,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum TransferError {
+    #[error("Path not found: {0}")]
+    NotFound(String),
+
+    #[error("Destination already exists: {0}")]
+    AlreadyExists(String),
+
+    #[error("Invalid file name: {0}")]
+    InvalidName(String),
+
+    #[error("Cannot {kind} {path} into itself")]
+    IntoItself { kind: &'static str, path: String },
+
+    #[error("Failed to {kind} {path}: {reason}")]
+    Io {
+        kind: &'static str,
+        path: String,
+        reason: String,
+    },
+}
+
+/// Move `source` to `destination`, falling back to copy-and-delete across
+/// file systems
+pub fn move_path(source: &Path, destination: &Path, collision: Collision) -> Result<TransferOutcome, TransferError> {
+    transfer(TransferKind::Move, source, destination, collision)
+}
+
+/// Copy a file or directory tree from `source` to `destination`
+pub fn copy_path(source: &Path, destination: &Path, collision: Collision) -> Result<TransferOutcome, TransferError> {
+    transfer(TransferKind::Copy, source, destination, collision)
+}
+
+/// Give `source` a new file name in the same directory
+pub fn rename_path(source: &Path, new_name: &str, collision: Collision) -> Result<TransferOutcome, TransferError> {
+    if !is_plain_name(new_name) {
+        return Err(TransferError::InvalidName(new_name.to_string()));
+    }
+
+    let parent = source.parent().unwrap_or_else(|| Path::new(""));
+    transfer(TransferKind::Rename, source, &parent.join(new_name), collision)
+}
+
+/// Whether `name` names an entry in the same directory: no separators, and
+/// not `.` or `..`
+pub fn is_plain_name(name: &str) -> bool {
+    !name.is_empty() && name != "." && name != ".." && !name.contains(['/', '\\'])
+}
+
+fn transfer(
+    kind: TransferKind,
+    source: &Path,
+    destination: &Path,
+    collision: Collision,
+) -> Result<TransferOutcome, TransferError> {
+    let verb = verb(kind);
+    let metadata =
+        std::fs::symlink_metadata(source).map_err(|_| TransferError::NotFound(source.display().to_string()))?;
+
+    if metadata.is_dir() && is_within(destination, source) {
+        return Err(TransferError::IntoItself {
+            kind: verb,
+            path: source.display().to_string(),
+        });
+    }
+
+    let (destination, overwritten) = if std::fs::symlink_metadata(destination).is_err() {
+        (destination.to_path_buf(), false)
+    } else {
+        match collision {
+            Collision::Abort => return Err(TransferError::AlreadyExists(destination.display().to_string())),
+            Collision::Rename => (free_name(destination)?, false),
+            Collision::Overwrite => {
+                // Replacing the source itself, or a directory containing it,
+                // would destroy what we're about to transfer
+                if is_within(source, destination) {
+                    return Err(TransferError::AlreadyExists(destination.display().to_string()));
+                }
+                remove(destination).map_err(|e| io_error(verb, destination, e))?;
+                (destination.to_path_buf(), true)
+            }
+        }
+    };
+
+    if let Some(parent) = destination.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent).map_err(|e| io_error(verb, parent, e))?;
+    }
+
+    match kind {
+        TransferKind::Copy => copy_recursive(source, &destination),
+        TransferKind::Move | TransferKind::Rename => match std::fs::rename(source, &destination) {
+            Err(e) if e.kind() == std::io::ErrorKind::CrossesDevices => {
+                copy_recursive(source, &destination).and_then(|_| remove(source))
+            }
+            result => result,
+        },
+    }
+    .map_err(|e| io_error(verb, source, e))?;
+
+    tracing::info!("{}: {} -> {}", verb, source.display(), destination.display());
+    Ok(TransferOutcome {
+        kind,
+        source: source.display().to_string(),
+        destination: destination.display().to_string(),
+        overwritten,
+    })
+}
+
+/// First `name (n).ext` next to `path` that doesn't exist
+fn free_name(path: &Path) -> Result<PathBuf, TransferError> {
+    let parent = path.parent().unwrap_or_else(|| Path::new(""));
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .ok_or_else(|| TransferError::InvalidName(path.display().to_string()))?;
+
+    // Keep the extension, but treat dotfiles like `.env` as having none
+    let (stem, extension) = match file_name.rfind('.') {
+        Some(i) if i > 0 => file_name.split_at(i),
+        _ => (file_name.as_str(), ""),
+    };
+
+    (1..=MAX_SUFFIX)
+        .map(|n| parent.join(format!("{} ({}){}", stem, n, extension)))
+        .find(|candidate| std::fs::symlink_metadata(candidate).is_err())
+        .ok_or_else(|| TransferError::AlreadyExists(path.display().to_string()))
+}
+
+fn copy_recursive(source: &Path, destination: &Path) -> std::io::Result<()> {
+    if !std::fs::symlink_metadata(source)?.is_dir() {
+        return std::fs::copy(source, destination).map(|_| ());
+    }
+
+    for entry in walkdir::WalkDir::new(source) {
+        let entry = entry.map_err(std::io::Error::other)?;
+        let relative = entry.path().strip_prefix(source).map_err(std::io::Error::other)?;
+        let target = destination.join(relative);
+        if entry.file_type().is_dir() {
+            std::fs::create_dir_all(&target)?;
+        } else {
+            std::fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
+
+fn remove(path: &Path) -> std::io::Result<()> {
+    if std::fs::symlink_metadata(path)?.is_dir() {
+        std::fs::remove_dir_all(path)
+    } else {
+        std::fs::remove_file(path)
+    }
+}
+
+/// Whether `path` is `dir` or inside it, comparing resolved paths
+fn is_within(path: &Path, dir: &Path) -> bool {
+    resolve(path).starts_with(resolve(dir))
+}
+
+/// Canonical form of `path`, which may not exist yet: the nearest existing
+/// ancestor is resolved and the remaining components appended
+fn resolve(path: &Path) -> PathBuf {
+    for ancestor in path.ancestors() {
+        if let Ok(resolved) = ancestor.canonicalize() {
+            let rest = path.strip_prefix(ancestor).unwrap_or(Path::new(""));
+            return resolved.join(rest);
+        }
+    }
+    path.to_path_buf()
+}
+
+fn verb(kind: TransferKind) -> &'static str {
+    match kind {
+        TransferKind::Move => "move",
+        TransferKind::Copy => "copy",
+        TransferKind::Rename => "rename",
+    }
+}
+
+fn io_error(kind: &'static str, path: &Path, e: std::io::Error) -> TransferError {
+    TransferError::Io {
+        kind,
+        path: path.display().to_string(),
+        reason: e.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_collision_policies() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        std::fs::write(dir.join("a.txt"), "a").unwrap();
+        std::fs::write(dir.join("b.txt"), "b").unwrap();
+
+        assert!(matches!(
+            copy_path(&dir.join("a.txt"), &dir.join("b.txt"), Collision::Abort),
+            Err(TransferError::AlreadyExists(_))
+        ));
+
+        let renamed = copy_path(&dir.join("a.txt"), &dir.join("b.txt"), Collision::Rename).unwrap();
+        assert_eq!(renamed.destination, dir.join("b (1).txt").display().to_string());
+        let renamed = copy_path(&dir.join("a.txt"), &dir.join("b.txt"), Collision::Rename).unwrap();
+        assert_eq!(renamed.destination, dir.join("b (2).txt").display().to_string());
+
+        let overwritten = move_path(&dir.join("a.txt"), &dir.join("b.txt"), Collision::Overwrite).unwrap();
+        assert!(overwritten.overwritten);
+        assert!(!dir.join("a.txt").exists());
+        assert_eq!(std::fs::read_to_string(dir.join("b.txt")).unwrap(), "a");
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_copy_and_move_directories() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        std::fs::create_dir_all(dir.join("src").join("nested")).unwrap();
+        std::fs::write(dir.join("src").join("nested").join("file.txt"), "data").unwrap();
+
+        copy_path(&dir.join("src"), &dir.join("copy"), Collision::Abort).unwrap();
+        assert_eq!(std::fs::read_to_string(dir.join("copy").join("nested").join("file.txt")).unwrap(), "data");
+
+        assert!(matches!(
+            move_path(&dir.join("src"), &dir.join("src").join("nested").join("inner"), Collision::Abort),
+            Err(TransferError::IntoItself { .. })
+        ));
+
+        move_path(&dir.join("src"), &dir.join("moved").join("src"), Collision::Abort).unwrap();
+        assert!(!dir.join("src").exists());
+        assert!(dir.join("moved").join("src").join("nested").join("file.txt").exists());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_rename() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        std::fs::write(dir.join(".env"), "x").unwrap();
+        std::fs::write(dir.join("notes.md"), "y").unwrap();
+
+        assert!(matches!(
+            rename_path(&dir.join("notes.md"), "../escape.md", Collision::Abort),
+            Err(TransferError::InvalidName(_))
+        ));
+
+        let outcome = rename_path(&dir.join("notes.md"), ".env", Collision::Rename).unwrap();
+        assert_eq!(outcome.kind, TransferKind::Rename);
+        assert_eq!(outcome.destination, dir.join(".env (1)").display().to_string());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
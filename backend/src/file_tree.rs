@@ -0,0 +1,211 @@
+//! One level of a directory for the file explorer
+//!
+//! The explorer expands folders lazily, so [`list_level`] returns a single
+//! directory level, one page at a time. Entries are sorted using only the
+//! file types reported by `read_dir`; size and modification time are then
+//! fetched for the requested page alone, keeping large folders cheap to open.
+//! Each entry also says whether git would ignore it, based on the
+//! `.gitignore` files between the repository root and the directory (see
+//! [`IgnoreRules::GIT`]).
+
+use crate::ignore_rules::{IgnoreMatcher, IgnoreRules};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Entries per page when the caller doesn't say
+pub const DEFAULT_PAGE_SIZE: usize = 200;
+
+/// Largest page a caller can ask for
+pub const MAX_PAGE_SIZE: usize = 1000;
+
+/// Kind of directory entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntryKind {
+    File,
+    Directory,
+    Symlink,
+}
+
+/// A file or folder in a directory listing
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TreeEntry {
+    pub name: String,
+    pub path: String,
+    pub kind: EntryKind,
+    /// Size in bytes; `None` for directories
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+    /// Modification time in seconds since the Unix epoch
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub modified: Option<u64>,
+    /// Whether the repository's .gitignore rules exclude this entry
+    pub git_ignored: bool,
+}
+
+/// A page of one directory level
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DirectoryPage {
+    pub path: String,
+    pub entries: Vec<TreeEntry>,
+    /// Entries in the directory, across all pages
+    pub total: usize,
+    pub offset: usize,
+    /// Offset of the next page, if there is one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_offset: Option<usize>,
+}
+
+/// Which part of a directory to list
+#[derive(Debug, Clone, Copy)]
+pub struct ListOptions {
+    pub offset: usize,
+    pub limit: usize,
+    pub include_hidden: bool,
+}
+
+impl Default for ListOptions {
+    fn default() -> Self {
+        Self {
+            offset: 0,
+            limit: DEFAULT_PAGE_SIZE,
+            include_hidden: false,
+        }
+    }
+}
+
+/// List one page of the entries directly inside `dir`, folders first
+pub fn list_level(dir: &Path, options: ListOptions) -> std::io::Result<DirectoryPage> {
+    let mut names: Vec<(String, PathBuf, bool)> = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if !options.include_hidden && name.starts_with('.') {
+                return None;
+            }
+            // Follows symlinks so links to folders sort with folders
+            let is_dir = entry.file_type().ok()?.is_dir() || entry.path().is_dir();
+            Some((name, entry.path(), is_dir))
+        })
+        .collect();
+
+    names.sort_by(|a, b| {
+        b.2.cmp(&a.2)
+            .then_with(|| a.0.to_lowercase().cmp(&b.0.to_lowercase()))
+            .then_with(|| a.0.cmp(&b.0))
+    });
+
+    let total = names.len();
+    let limit = options.limit.clamp(1, MAX_PAGE_SIZE);
+    let offset = options.offset.min(total);
+    let end = (offset + limit).min(total);
+
+    let ignore_rules = IgnoreRules::GIT.matcher(dir);
+    let entries = names[offset..end]
+        .iter()
+        .map(|(name, path, is_dir)| stat_entry(name, path, *is_dir, &ignore_rules))
+        .collect();
+
+    Ok(DirectoryPage {
+        path: dir.display().to_string(),
+        entries,
+        total,
+        offset,
+        next_offset: (end < total).then_some(end),
+    })
+}
+
+fn stat_entry(name: &str, path: &Path, is_dir: bool, ignore_rules: &IgnoreMatcher) -> TreeEntry {
+    let link_metadata = std::fs::symlink_metadata(path).ok();
+    let is_symlink = link_metadata.as_ref().is_some_and(|m| m.file_type().is_symlink());
+    // Report the target's size and time for symlinks, falling back to the
+    // link itself when it is broken
+    let metadata = if is_symlink {
+        std::fs::metadata(path).ok().or(link_metadata)
+    } else {
+        link_metadata
+    };
+
+    TreeEntry {
+        name: name.to_string(),
+        path: path.display().to_string(),
+        kind: if is_symlink {
+            EntryKind::Symlink
+        } else if is_dir {
+            EntryKind::Directory
+        } else {
+            EntryKind::File
+        },
+        size: metadata.as_ref().filter(|_| !is_dir).map(|m| m.len()),
+        modified: metadata
+            .and_then(|m| m.modified().ok())
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|d| d.as_secs()),
+        git_ignored: ignore_rules.is_ignored(path, is_dir),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn names(page: &DirectoryPage) -> Vec<&str> {
+        page.entries.iter().map(|e| e.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_sorting_and_pagination() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        std::fs::create_dir(dir.join("zeta")).unwrap();
+        std::fs::create_dir(dir.join("Alpha")).unwrap();
+        std::fs::write(dir.join("b.txt"), "12345").unwrap();
+        std::fs::write(dir.join("a.txt"), "").unwrap();
+        std::fs::write(dir.join(".hidden"), "").unwrap();
+
+        let first = list_level(&dir, ListOptions { limit: 3, ..Default::default() }).unwrap();
+        assert_eq!(names(&first), vec!["Alpha", "zeta", "a.txt"]);
+        assert_eq!(first.total, 4);
+        assert_eq!(first.next_offset, Some(3));
+        assert_eq!(first.entries[0].kind, EntryKind::Directory);
+        assert_eq!(first.entries[0].size, None);
+
+        let second = list_level(&dir, ListOptions { offset: 3, limit: 3, ..Default::default() }).unwrap();
+        assert_eq!(names(&second), vec!["b.txt"]);
+        assert_eq!(second.entries[0].size, Some(5));
+        assert!(second.entries[0].modified.is_some());
+        assert_eq!(second.next_offset, None);
+
+        let all = list_level(&dir, ListOptions { include_hidden: true, ..Default::default() }).unwrap();
+        assert_eq!(all.total, 5);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_git_ignore_status() {
+        let temp_repo = TempDir::new().unwrap();
+        let repo = temp_repo.path();
+        std::fs::create_dir_all(repo.join(".git")).unwrap();
+        std::fs::write(repo.join(".gitignore"), "target/\n*.log\n").unwrap();
+        std::fs::create_dir_all(repo.join("src")).unwrap();
+        std::fs::write(repo.join("src").join(".gitignore"), "!keep.log\n").unwrap();
+        std::fs::write(repo.join("src").join("debug.log"), "").unwrap();
+        std::fs::write(repo.join("src").join("keep.log"), "").unwrap();
+        std::fs::write(repo.join("src").join("main.rs"), "").unwrap();
+        std::fs::create_dir_all(repo.join("target")).unwrap();
+
+        let root = list_level(&repo, ListOptions::default()).unwrap();
+        let target = root.entries.iter().find(|e| e.name == "target").unwrap();
+        assert!(target.git_ignored);
+
+        let src = list_level(&repo.join("src"), ListOptions::default()).unwrap();
+        let ignored = |name: &str| src.entries.iter().find(|e| e.name == name).unwrap().git_ignored;
+        assert!(ignored("debug.log"));
+        assert!(!ignored("keep.log"));
+        assert!(!ignored("main.rs"));
+
+        std::fs::remove_dir_all(repo).unwrap();
+    }
+}
@@ -0,0 +1,663 @@
+//! Filename index for instant file search
+//!
+//! Walking the home directory for every fuzzy search takes seconds on a
+//! large disk. This index keeps every file under the indexed roots (home
+//! and the search workspaces) in memory, so a search only scores the paths
+//! under its directory. The index is stored in the database so it is usable
+//! straight after a restart, kept current by a filesystem watcher, and
+//! rebuilt from a full walk of each root every half hour to catch what the
+//! watcher missed.
+//!
+//! Files are indexed the way a default file search walks: ignore files,
+//! hidden files, the default exclude patterns and mounts are honoured. A
+//! search with other settings still walks.
+
+use chrono::{DateTime, Utc};
+use ignore::overrides::{Override, OverrideBuilder};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+
+use crate::db::{Database, FilenameRecord};
+use crate::error::AppError;
+use crate::ignore_rules::{IgnoreCache, IgnoreRules};
+use crate::mounts::MountGuard;
+use crate::search_engine::file_search::walk_builder;
+use crate::search_engine::{FileSearchConfig, IndexedCandidate};
+
+/// Files kept per root; the rest of a larger root is left to walking
+const MAX_INDEXED_FILES: usize = 1_000_000;
+
+/// How often each root is walked in full
+const RECONCILE_INTERVAL_MINS: i64 = 30;
+
+/// Watcher events queued before the maintainer gives up on them and
+/// reconciles instead
+const EVENT_QUEUE: usize = 4096;
+
+#[derive(Debug, Clone, Copy)]
+struct IndexedFile {
+    size: u64,
+    modified: Option<DateTime<Utc>>,
+}
+
+#[derive(Clone)]
+struct RootState {
+    reconciled_at: DateTime<Utc>,
+    truncated: bool,
+    rules: WalkRules,
+}
+
+/// What a default walk of a root leaves out besides ignore files
+#[derive(Clone)]
+struct WalkRules {
+    /// Mounts the walk stayed out of
+    guard: MountGuard,
+    /// Default exclude patterns, rooted at the root
+    excludes: Option<Override>,
+}
+
+impl WalkRules {
+    fn new(root: &Path) -> Self {
+        let config = FileSearchConfig::default();
+        let mut builder = OverrideBuilder::new(root);
+        for exclude in &config.exclude_patterns {
+            if builder.add(&format!("!{exclude}")).is_err() {
+                tracing::debug!("Bad default exclude pattern {}", exclude);
+            }
+        }
+        Self { guard: MountGuard::new(root, &config.mounts), excludes: builder.build().ok() }
+    }
+
+    /// Whether a walk of `root` would skip `path` for being hidden,
+    /// excluded or on a mount left out
+    fn skips(&self, root: &Path, path: &Path, is_dir: bool) -> bool {
+        let Ok(relative) = path.strip_prefix(root) else {
+            return true;
+        };
+        if relative.components().any(|c| c.as_os_str().to_string_lossy().starts_with('.')) {
+            return true;
+        }
+        path.ancestors().take_while(|a| *a != root).any(|ancestor| {
+            let ancestor_is_dir = ancestor != path || is_dir;
+            !self.guard.allows(ancestor)
+                || self.excludes.as_ref().is_some_and(|o| o.matched(ancestor, ancestor_is_dir).is_ignore())
+        })
+    }
+}
+
+#[derive(Default)]
+struct IndexState {
+    roots: BTreeMap<PathBuf, RootState>,
+    /// Full path to file
+    files: BTreeMap<String, IndexedFile>,
+}
+
+/// A root of the filename index
+#[derive(Debug, Clone, Serialize)]
+pub struct IndexedRoot {
+    pub root: PathBuf,
+    pub files: usize,
+    pub reconciled_at: DateTime<Utc>,
+    /// The root held more than the index keeps
+    pub truncated: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FilenameIndexStatus {
+    pub enabled: bool,
+    pub roots: Vec<IndexedRoot>,
+    pub files: usize,
+    pub reconciling: bool,
+}
+
+/// In-memory filename index, backed by the database when one is given
+pub struct FilenameIndex {
+    db: Option<Database>,
+    state: RwLock<IndexState>,
+    reconciling: AtomicBool,
+}
+
+impl FilenameIndex {
+    pub fn new(db: Option<Database>) -> Self {
+        Self {
+            db,
+            state: RwLock::new(IndexState::default()),
+            reconciling: AtomicBool::new(false),
+        }
+    }
+
+    /// Read the index saved by a previous run
+    pub async fn load(&self) -> Result<usize, AppError> {
+        let Some(db) = &self.db else {
+            return Ok(0);
+        };
+        let roots = db.filename_roots().await?;
+        let records = db.filename_records().await?;
+
+        let roots = tokio::task::spawn_blocking(move || {
+            roots
+                .into_iter()
+                .map(|(root, reconciled_at)| {
+                    let root = PathBuf::from(root);
+                    let rules = WalkRules::new(&root);
+                    (root, RootState { reconciled_at, truncated: false, rules })
+                })
+                .collect::<BTreeMap<_, _>>()
+        })
+        .await
+        .map_err(|e| AppError::Internal(e.to_string()))?;
+
+        let mut state = self.state.write().unwrap();
+        state.roots = roots;
+        state.files = records
+            .into_iter()
+            .map(|record| (record.path, IndexedFile { size: record.size.max(0) as u64, modified: record.modified_at }))
+            .collect();
+        // Only a walk that hit the cap saves this many files
+        let full: Vec<PathBuf> = state
+            .roots
+            .keys()
+            .filter(|root| count_below(&state.files, root) >= MAX_INDEXED_FILES)
+            .cloned()
+            .collect();
+        for root in full {
+            if let Some(root) = state.roots.get_mut(&root) {
+                root.truncated = true;
+            }
+        }
+        Ok(state.files.len())
+    }
+
+    /// Whether searches with this config may use the index instead of
+    /// walking: it must skip exactly what the index skips
+    pub fn serves(config: &FileSearchConfig) -> bool {
+        let indexed = FileSearchConfig::default();
+        config.ignore_rules() == indexed.ignore_rules()
+            && config.include_hidden == indexed.include_hidden
+            && config.follow_symlinks == indexed.follow_symlinks
+            && config.exclude_patterns == indexed.exclude_patterns
+            && config.include_patterns.is_empty()
+            && config.mounts == indexed.mounts
+    }
+
+    /// Files under `search_dir` that could match the fuzzy `query`, or
+    /// `None` when no indexed root covers the directory
+    pub fn candidates(&self, search_dir: &Path, query: &str) -> Option<Vec<IndexedCandidate>> {
+        let search_dir = search_dir.canonicalize().ok()?;
+        let state = self.state.read().unwrap();
+        let (root, root_state) = state.roots.iter().find(|(root, _)| search_dir.starts_with(root))?;
+        let skipped = search_dir != *root
+            && (root_state.rules.skips(root, &search_dir, true)
+                || IgnoreRules::SEARCH.matcher(search_dir.parent()?).is_ignored(&search_dir, true));
+        if root_state.truncated || skipped {
+            return None;
+        }
+
+        let prefix = format!("{}{}", search_dir.display(), std::path::MAIN_SEPARATOR);
+        let alternatives = prefilter(query);
+        let candidates = state
+            .files
+            .range(prefix.clone()..)
+            .take_while(|(path, _)| path.starts_with(&prefix))
+            .filter(|(path, _)| might_match(&path[prefix.len()..], &alternatives))
+            .map(|(path, file)| IndexedCandidate {
+                path: PathBuf::from(path),
+                size: Some(file.size),
+                modified: file.modified,
+            })
+            .collect();
+        Some(candidates)
+    }
+
+    /// Walk `root` and make it the index's view of that root
+    pub async fn reconcile(&self, root: &Path) -> Result<IndexedRoot, AppError> {
+        self.reconciling.store(true, Ordering::Relaxed);
+        let walked = {
+            let root = root.to_path_buf();
+            tokio::task::spawn_blocking(move || walk_root(&root)).await
+        };
+        self.reconciling.store(false, Ordering::Relaxed);
+        let (files, rules, truncated) = walked.map_err(|e| AppError::Internal(e.to_string()))??;
+
+        let reconciled_at = Utc::now();
+        let records = files
+            .iter()
+            .map(|(path, file)| record(root, path, file))
+            .collect::<Vec<_>>();
+        {
+            let mut state = self.state.write().unwrap();
+            remove_below(&mut state.files, root);
+            state.files.extend(files.into_iter().map(|(path, file)| (path.to_string_lossy().to_string(), file)));
+            state.roots.insert(root.to_path_buf(), RootState { reconciled_at, truncated, rules });
+        }
+        if let Some(db) = &self.db {
+            db.replace_filename_root(&root.to_string_lossy(), &records, reconciled_at).await?;
+        }
+
+        tracing::debug!("Indexed {} filenames under {}", records.len(), root.display());
+        Ok(IndexedRoot { root: root.to_path_buf(), files: records.len(), reconciled_at, truncated })
+    }
+
+    /// Bring changed paths up to date. Directories in `walk_dirs` were
+    /// created or moved in, so everything below them is indexed too.
+    pub async fn refresh(&self, paths: Vec<PathBuf>, walk_dirs: bool) -> Result<(), AppError> {
+        let roots: Vec<(PathBuf, WalkRules)> = {
+            let state = self.state.read().unwrap();
+            state.roots.iter().map(|(root, s)| (root.clone(), s.rules.clone())).collect()
+        };
+        if roots.is_empty() {
+            return Ok(());
+        }
+
+        let (upserts, removed) = tokio::task::spawn_blocking(move || changed_files(&roots, paths, walk_dirs))
+            .await
+            .map_err(|e| AppError::Internal(e.to_string()))?;
+        if upserts.is_empty() && removed.is_empty() {
+            return Ok(());
+        }
+
+        {
+            let mut state = self.state.write().unwrap();
+            for path in &removed {
+                state.files.remove(&path.to_string_lossy().to_string());
+                remove_below(&mut state.files, path);
+            }
+            for (_, path, file) in &upserts {
+                state.files.insert(path.to_string_lossy().to_string(), *file);
+            }
+        }
+        if let Some(db) = &self.db {
+            let records: Vec<_> = upserts.iter().map(|(root, path, file)| record(root, path, file)).collect();
+            let removed: Vec<_> = removed.iter().map(|p| p.to_string_lossy().to_string()).collect();
+            db.update_filenames(&records, &removed).await?;
+        }
+        Ok(())
+    }
+
+    /// Forget a root that is no longer indexed
+    pub async fn remove_root(&self, root: &Path) -> Result<(), AppError> {
+        {
+            let mut state = self.state.write().unwrap();
+            state.roots.remove(root);
+            remove_below(&mut state.files, root);
+        }
+        if let Some(db) = &self.db {
+            db.delete_filename_root(&root.to_string_lossy()).await?;
+        }
+        Ok(())
+    }
+
+    /// Indexed roots and when each was last walked in full
+    pub fn roots(&self) -> Vec<(PathBuf, DateTime<Utc>)> {
+        let state = self.state.read().unwrap();
+        state.roots.iter().map(|(root, s)| (root.clone(), s.reconciled_at)).collect()
+    }
+
+    pub fn status(&self) -> FilenameIndexStatus {
+        let state = self.state.read().unwrap();
+        let roots: Vec<IndexedRoot> = state
+            .roots
+            .iter()
+            .map(|(root, s)| IndexedRoot {
+                root: root.clone(),
+                files: count_below(&state.files, root),
+                reconciled_at: s.reconciled_at,
+                truncated: s.truncated,
+            })
+            .collect();
+        FilenameIndexStatus {
+            enabled: crate::config::SettingsStore::global().get().search.filename_index,
+            files: state.files.len(),
+            roots,
+            reconciling: self.reconciling.load(Ordering::Relaxed),
+        }
+    }
+}
+
+fn record(root: &Path, path: &Path, file: &IndexedFile) -> FilenameRecord {
+    FilenameRecord {
+        path: path.to_string_lossy().to_string(),
+        root: root.to_string_lossy().to_string(),
+        size: file.size as i64,
+        modified_at: file.modified,
+    }
+}
+
+fn indexed_file(metadata: &std::fs::Metadata) -> IndexedFile {
+    IndexedFile {
+        size: metadata.len(),
+        modified: metadata.modified().ok().map(DateTime::<Utc>::from),
+    }
+}
+
+fn count_below(files: &BTreeMap<String, IndexedFile>, dir: &Path) -> usize {
+    let prefix = format!("{}{}", dir.display(), std::path::MAIN_SEPARATOR);
+    files.range(prefix.clone()..).take_while(|(path, _)| path.starts_with(&prefix)).count()
+}
+
+/// Drop every file below `dir`
+fn remove_below(files: &mut BTreeMap<String, IndexedFile>, dir: &Path) {
+    let prefix = format!("{}{}", dir.display(), std::path::MAIN_SEPARATOR);
+    let below: Vec<String> = files
+        .range(prefix.clone()..)
+        .take_while(|(path, _)| path.starts_with(&prefix))
+        .map(|(path, _)| path.clone())
+        .collect();
+    for path in below {
+        files.remove(&path);
+    }
+}
+
+type WalkedRoot = (Vec<(PathBuf, IndexedFile)>, WalkRules, bool);
+
+fn walk_root(root: &Path) -> Result<WalkedRoot, AppError> {
+    let rules = WalkRules::new(root);
+    let files = walk_files(root, &rules.guard)?;
+    let truncated = files.len() > MAX_INDEXED_FILES;
+    Ok((files.into_iter().take(MAX_INDEXED_FILES).collect(), rules, truncated))
+}
+
+/// Files below `dir` as a default file search walk finds them
+fn walk_files(dir: &Path, guard: &MountGuard) -> Result<Vec<(PathBuf, IndexedFile)>, AppError> {
+    let walker = walk_builder(dir, &FileSearchConfig::default(), guard)
+        .map_err(|e| AppError::Internal(e.to_string()))?
+        .build();
+    Ok(walker
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+        .take(MAX_INDEXED_FILES + 1)
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            Some((entry.into_path(), indexed_file(&metadata)))
+        })
+        .collect())
+}
+
+type ChangedFiles = (Vec<(PathBuf, PathBuf, IndexedFile)>, Vec<PathBuf>);
+
+/// Files to add or update, with their roots, and paths to drop along with
+/// everything below them
+fn changed_files(roots: &[(PathBuf, WalkRules)], paths: Vec<PathBuf>, walk_dirs: bool) -> ChangedFiles {
+    let mut upserts = Vec::new();
+    let mut removed = Vec::new();
+    let mut ignored = IgnoreCache::new(IgnoreRules::SEARCH);
+    for path in paths {
+        let Some((root, rules)) = roots.iter().find(|(root, _)| path.starts_with(root) && path != *root) else {
+            continue;
+        };
+        let Ok(metadata) = std::fs::metadata(&path) else {
+            removed.push(path);
+            continue;
+        };
+        if rules.skips(root, &path, metadata.is_dir()) || ignored.is_ignored(&path) {
+            continue;
+        }
+        if metadata.is_file() {
+            upserts.push((root.clone(), path, indexed_file(&metadata)));
+        } else if metadata.is_dir() && walk_dirs {
+            for (file, indexed) in walk_files(&path, &rules.guard).unwrap_or_default() {
+                if !rules.skips(root, &file, false) {
+                    upserts.push((root.clone(), file, indexed));
+                }
+            }
+        }
+    }
+    (upserts, removed)
+}
+
+/// Lower-cased characters each whitespace-separated word of each
+/// comma-separated alternative must contain in order
+fn prefilter(query: &str) -> Vec<Vec<Vec<char>>> {
+    query
+        .split(',')
+        .map(|alternative| {
+            alternative
+                .split_whitespace()
+                .map(|word| word.chars().filter(|c| *c != '\\').flat_map(char::to_lowercase).collect())
+                .collect::<Vec<Vec<char>>>()
+        })
+        .filter(|words| !words.is_empty())
+        .collect()
+}
+
+/// Cheap check that a relative path can match one of the alternatives
+/// before the fuzzy matcher scores it. Never rejects what the matcher would
+/// accept: non-ASCII characters on either side are let through, since the
+/// matcher folds accents.
+fn might_match(relative_path: &str, alternatives: &[Vec<Vec<char>>]) -> bool {
+    if alternatives.is_empty() || !relative_path.is_ascii() {
+        return true;
+    }
+    let haystack = relative_path.to_ascii_lowercase();
+    alternatives.iter().any(|words| {
+        words.iter().all(|word| {
+            if !word.iter().all(char::is_ascii) {
+                return true;
+            }
+            let mut chars = haystack.chars();
+            word.iter().all(|c| chars.any(|h| h == *c))
+        })
+    })
+}
+
+/// Home and the search workspaces' roots, without roots inside others
+pub fn indexed_roots() -> Vec<PathBuf> {
+    let settings = crate::config::SettingsStore::global().get().search;
+    if !settings.filename_index {
+        return Vec::new();
+    }
+    let mut roots: Vec<PathBuf> = dirs::home_dir().into_iter().collect();
+    for workspace in &settings.workspaces {
+        for root in &workspace.roots {
+            roots.push(match (root.strip_prefix('~'), dirs::home_dir()) {
+                (Some(rest), Some(home)) => home.join(rest.trim_start_matches('/')),
+                _ => PathBuf::from(root),
+            });
+        }
+    }
+    let mut roots: Vec<PathBuf> = roots
+        .into_iter()
+        .filter_map(|root| root.canonicalize().ok())
+        .filter(|root| root.is_dir())
+        .collect();
+    roots.sort();
+    roots.dedup();
+    let all = roots.clone();
+    roots.retain(|root| !all.iter().any(|other| other != root && root.starts_with(other)));
+    roots
+}
+
+/// Load the saved index, then keep it current: apply watcher events as
+/// they come and walk roots that are new or due every minute
+pub fn spawn_maintainer(index: Arc<FilenameIndex>) -> tokio::task::JoinHandle<()> {
+    use notify::{EventKind, RecursiveMode, Watcher};
+
+    tokio::spawn(async move {
+        match index.load().await {
+            Ok(files) => tracing::info!("Loaded {} indexed filenames", files),
+            Err(e) => tracing::warn!("Cannot load the filename index: {}", e),
+        }
+
+        let (tx, mut rx) = tokio::sync::mpsc::channel::<(Vec<PathBuf>, bool)>(EVENT_QUEUE);
+        let overflowed = Arc::new(AtomicBool::new(false));
+        let mut watched = Vec::new();
+        // Kept alive until the roots change
+        let mut _watcher: Option<notify::RecommendedWatcher> = None;
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(60));
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                Some((paths, walk_dirs)) = rx.recv() => {
+                    let mut batches = vec![(paths, walk_dirs)];
+                    while let Ok(batch) = rx.try_recv() {
+                        batches.push(batch);
+                    }
+                    for (paths, walk_dirs) in batches {
+                        if let Err(e) = index.refresh(paths, walk_dirs).await {
+                            tracing::warn!("Cannot update the filename index: {}", e);
+                        }
+                    }
+                    continue;
+                }
+            }
+
+            let roots = indexed_roots();
+            for (root, _) in index.roots() {
+                if !roots.contains(&root) {
+                    if let Err(e) = index.remove_root(&root).await {
+                        tracing::warn!("Cannot drop {} from the filename index: {}", root.display(), e);
+                    }
+                }
+            }
+            let missed_events = overflowed.swap(false, Ordering::Relaxed);
+            let due = Utc::now() - chrono::Duration::minutes(RECONCILE_INTERVAL_MINS);
+            let reconciled = index.roots();
+            for root in &roots {
+                let fresh = reconciled.iter().any(|(r, at)| r == root && *at > due);
+                if fresh && !missed_events {
+                    continue;
+                }
+                if let Err(e) = index.reconcile(root).await {
+                    tracing::warn!("Cannot index filenames under {}: {}", root.display(), e);
+                }
+            }
+
+            if roots == watched {
+                continue;
+            }
+            watched = roots;
+            _watcher = None;
+            if watched.is_empty() {
+                continue;
+            }
+
+            let tx = tx.clone();
+            let overflowed = overflowed.clone();
+            let created = notify::recommended_watcher(move |res: Result<notify::Event, notify::Error>| {
+                let Ok(event) = res else { return };
+                let walk_dirs = match event.kind {
+                    EventKind::Create(_) | EventKind::Modify(notify::event::ModifyKind::Name(_)) => true,
+                    EventKind::Modify(_) | EventKind::Remove(_) => false,
+                    _ => return,
+                };
+                if tx.try_send((event.paths, walk_dirs)).is_err() {
+                    overflowed.store(true, Ordering::Relaxed);
+                }
+            });
+            match created {
+                Ok(mut created) => {
+                    for root in &watched {
+                        if let Err(e) = created.watch(root, RecursiveMode::Recursive) {
+                            tracing::warn!("Cannot watch {} for the filename index: {}", root.display(), e);
+                        }
+                    }
+                    _watcher = Some(created);
+                }
+                Err(e) => tracing::warn!("Cannot watch indexed roots for changes: {}", e),
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn paths(candidates: Option<Vec<IndexedCandidate>>, root: &Path) -> Vec<String> {
+        let mut paths: Vec<String> = candidates
+            .unwrap()
+            .into_iter()
+            .map(|c| c.path.strip_prefix(root).unwrap().to_string_lossy().replace('\\', "/"))
+            .collect();
+        paths.sort();
+        paths
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_refresh_and_candidates() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::create_dir_all(root.join("node_modules/pkg")).unwrap();
+        fs::create_dir_all(root.join(".cache")).unwrap();
+        fs::write(root.join("src/main.rs"), "fn main() {}").unwrap();
+        fs::write(root.join("src/lib.rs"), "").unwrap();
+        fs::write(root.join("README.md"), "").unwrap();
+        fs::write(root.join("node_modules/pkg/index.js"), "").unwrap();
+        fs::write(root.join(".cache/main.rs"), "").unwrap();
+        fs::write(root.join(".skhootignore"), "secret/\n").unwrap();
+        fs::create_dir_all(root.join("secret")).unwrap();
+        fs::write(root.join("secret/main.rs"), "").unwrap();
+
+        let index = FilenameIndex::new(None);
+        assert!(index.candidates(&root, "main").is_none());
+        let indexed = index.reconcile(&root).await.unwrap();
+        assert_eq!(indexed.files, 3);
+
+        assert_eq!(paths(index.candidates(&root, "main"), &root), vec!["src/main.rs"]);
+        assert_eq!(paths(index.candidates(&root, "MAIN, readme"), &root), vec!["README.md", "src/main.rs"]);
+        assert_eq!(paths(index.candidates(&root.join("src"), ""), &root), vec!["src/lib.rs", "src/main.rs"]);
+        assert!(index.candidates(&root.join(".cache"), "main").is_none());
+
+        fs::remove_dir_all(root.join("src")).unwrap();
+        fs::create_dir_all(root.join("docs/guide")).unwrap();
+        fs::write(root.join("docs/guide/main.md"), "").unwrap();
+        fs::write(root.join("secret/other.rs"), "").unwrap();
+        index
+            .refresh(vec![root.join("src"), root.join("docs"), root.join("secret/other.rs")], true)
+            .await
+            .unwrap();
+        assert_eq!(paths(index.candidates(&root, "main"), &root), vec!["docs/guide/main.md"]);
+        assert_eq!(index.status().files, 2);
+
+        index.remove_root(&root).await.unwrap();
+        assert!(index.candidates(&root, "main").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_index_survives_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        fs::create_dir_all(root.join("a")).unwrap();
+        fs::write(root.join("a/report.pdf"), "pdf").unwrap();
+        fs::write(root.join("a/notes.txt"), "").unwrap();
+        let data = tempfile::tempdir().unwrap();
+        let url = format!("sqlite://{}?mode=rwc", data.path().join("files.db").display());
+        let db = Database::new(&url).await.unwrap();
+
+        let index = FilenameIndex::new(Some(db.clone()));
+        index.reconcile(&root).await.unwrap();
+        fs::remove_file(root.join("a/notes.txt")).unwrap();
+        index.refresh(vec![root.join("a/notes.txt")], false).await.unwrap();
+
+        let restarted = FilenameIndex::new(Some(db));
+        assert_eq!(restarted.load().await.unwrap(), 1);
+        let candidates = restarted.candidates(&root, "report").unwrap();
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].size, Some(3));
+        assert!(candidates[0].modified.is_some());
+    }
+
+    #[test]
+    fn test_prefilter_never_rejects_fuzzy_matches() {
+        let alternatives = prefilter("mn rs, readme");
+        assert!(might_match("src/main.rs", &alternatives));
+        assert!(might_match("docs/README.md", &alternatives));
+        assert!(!might_match("src/lib.rs", &alternatives));
+        assert!(might_match("café/notes.txt", &prefilter("cafe")));
+        assert!(might_match("anything", &prefilter("é")));
+        assert!(might_match("anything", &prefilter(" , ")));
+    }
+
+    #[test]
+    fn test_serves_only_default_walks() {
+        let mut config = FileSearchConfig { max_results: 10, ..Default::default() };
+        assert!(FilenameIndex::serves(&config));
+        config.include_hidden = true;
+        assert!(!FilenameIndex::serves(&config));
+    }
+}
@@ -0,0 +1,283 @@
+//! Ignore rules shared by every scanner
+//!
+//! Search, indexing and the disk analyzer all walk the filesystem. They use
+//! the same [`IgnoreRules`] to decide what to skip: git's rules (`.gitignore`,
+//! `.ignore`, `.git/info/exclude` and the user's global excludes file) inside
+//! git repositories, and `.skhootignore` files anywhere. A `.skhootignore`
+//! uses gitignore syntax and applies to its directory and everything below,
+//! so `~/.skhootignore` covers the whole home directory.
+//!
+//! Each scanner picks its defaults: search and indexing honour everything,
+//! while disk usage only honours `.skhootignore`, since build output that git
+//! ignores is exactly what users want to see there. Callers can override
+//! either kind of rule per request.
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::WalkBuilder;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Name of Skhoot's own ignore file
+pub const SKHOOTIGNORE_FILE: &str = ".skhootignore";
+
+/// Which ignore files a scan honours
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IgnoreRules {
+    /// `.gitignore`, `.ignore` and `.git/info/exclude` inside git repositories
+    pub gitignore: bool,
+    /// The global excludes file from git's `core.excludesFile`
+    pub global_git_excludes: bool,
+    /// `.skhootignore` files
+    pub skhootignore: bool,
+}
+
+impl IgnoreRules {
+    /// File search and content indexing
+    pub const SEARCH: Self = Self { gitignore: true, global_git_excludes: true, skhootignore: true };
+
+    /// Disk usage scans, which still count what git ignores
+    pub const DISK_USAGE: Self = Self { gitignore: false, global_git_excludes: false, skhootignore: true };
+
+    /// Git's rules only, as `git status` sees them
+    pub const GIT: Self = Self { gitignore: true, global_git_excludes: true, skhootignore: false };
+
+    /// Nothing is ignored
+    pub const NONE: Self = Self { gitignore: false, global_git_excludes: false, skhootignore: false };
+
+    /// Apply per-request overrides; `respect_gitignore` covers the global
+    /// excludes file too
+    pub fn with_overrides(self, respect_gitignore: Option<bool>, respect_skhootignore: Option<bool>) -> Self {
+        Self {
+            gitignore: respect_gitignore.unwrap_or(self.gitignore),
+            global_git_excludes: respect_gitignore.unwrap_or(self.global_git_excludes),
+            skhootignore: respect_skhootignore.unwrap_or(self.skhootignore),
+        }
+    }
+
+    /// Set up a walker to honour these rules. Hidden files and symlinks are
+    /// left to the caller.
+    pub fn configure<'a>(&self, builder: &'a mut WalkBuilder) -> &'a mut WalkBuilder {
+        builder
+            .git_ignore(self.gitignore)
+            .git_exclude(self.gitignore)
+            .ignore(self.gitignore)
+            .git_global(self.global_git_excludes)
+            .require_git(true)
+            .parents(true);
+        if self.skhootignore {
+            builder.add_custom_ignore_filename(SKHOOTIGNORE_FILE);
+        }
+        builder
+    }
+
+    /// Walker over `root` honouring these rules, including hidden files and
+    /// without following symlinks
+    pub fn walker(&self, root: &Path) -> WalkBuilder {
+        let mut builder = WalkBuilder::new(root);
+        builder.hidden(false).follow_links(false);
+        self.configure(&mut builder);
+        builder
+    }
+
+    /// Rules that apply to entries directly inside `dir`, for checking
+    /// single paths without walking
+    pub fn matcher(&self, dir: &Path) -> IgnoreMatcher {
+        IgnoreMatcher::for_directory(*self, dir)
+    }
+}
+
+/// Compiled ignore files that apply inside one directory
+#[derive(Debug, Clone)]
+pub struct IgnoreMatcher {
+    /// Deepest first; within a directory `.skhootignore` wins over `.ignore`,
+    /// which wins over `.gitignore`
+    matchers: Vec<Gitignore>,
+}
+
+impl IgnoreMatcher {
+    fn for_directory(rules: IgnoreRules, dir: &Path) -> Self {
+        let dir = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+        let repo_root = dir.ancestors().find(|a| a.join(".git").exists()).map(Path::to_path_buf);
+        let in_repo = |a: &Path| repo_root.as_deref().is_some_and(|root| a.starts_with(root));
+
+        let mut matchers = Vec::new();
+        for ancestor in dir.ancestors() {
+            let mut names = Vec::new();
+            if rules.skhootignore {
+                names.push(SKHOOTIGNORE_FILE);
+            }
+            if rules.gitignore && in_repo(ancestor) {
+                names.extend([".ignore", ".gitignore"]);
+            }
+            for name in names {
+                let file = ancestor.join(name);
+                if file.is_file() {
+                    let (matcher, error) = Gitignore::new(&file);
+                    if let Some(e) = error {
+                        tracing::debug!("Ignoring bad pattern in {}: {}", file.display(), e);
+                    }
+                    if !matcher.is_empty() {
+                        matchers.push(matcher);
+                    }
+                }
+            }
+        }
+
+        if let Some(root) = repo_root.as_deref() {
+            let exclude = root.join(".git").join("info").join("exclude");
+            if rules.gitignore && exclude.is_file() {
+                let mut builder = GitignoreBuilder::new(root);
+                builder.add(exclude);
+                if let Ok(matcher) = builder.build() {
+                    matchers.push(matcher);
+                }
+            }
+            if rules.global_git_excludes {
+                let (matcher, _) = GitignoreBuilder::new(root).build_global();
+                if !matcher.is_empty() {
+                    matchers.push(matcher);
+                }
+            }
+        }
+
+        Self { matchers }
+    }
+
+    /// Whether `path`, an entry of this matcher's directory or below it, is
+    /// ignored. Deeper files override shallower ones, as in git.
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        let path = path
+            .parent()
+            .and_then(|parent| parent.canonicalize().ok())
+            .zip(path.file_name())
+            .map(|(parent, name)| parent.join(name))
+            .unwrap_or_else(|| path.to_path_buf());
+
+        for matcher in &self.matchers {
+            if !path.starts_with(matcher.path()) {
+                continue;
+            }
+            let matched = matcher.matched_path_or_any_parents(&path, is_dir);
+            if matched.is_ignore() {
+                return true;
+            }
+            if matched.is_whitelist() {
+                return false;
+            }
+        }
+        false
+    }
+}
+
+/// Checks paths scattered across many directories, such as results from
+/// external tools or filesystem events, building each directory's matcher
+/// once
+#[derive(Debug)]
+pub struct IgnoreCache {
+    rules: IgnoreRules,
+    matchers: HashMap<PathBuf, IgnoreMatcher>,
+}
+
+impl IgnoreCache {
+    pub fn new(rules: IgnoreRules) -> Self {
+        Self { rules, matchers: HashMap::new() }
+    }
+
+    pub fn is_ignored(&mut self, path: &Path) -> bool {
+        let Some(parent) = path.parent() else {
+            return false;
+        };
+        let rules = self.rules;
+        self.matchers
+            .entry(parent.to_path_buf())
+            .or_insert_with(|| rules.matcher(parent))
+            .is_ignored(path, path.is_dir())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn walk(rules: IgnoreRules, root: &Path) -> Vec<String> {
+        let mut files: Vec<String> = rules
+            .walker(root)
+            .build()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_some_and(|t| t.is_file()))
+            .map(|e| e.path().strip_prefix(root).unwrap().to_string_lossy().replace('\\', "/"))
+            .collect();
+        files.sort();
+        files
+    }
+
+    fn project() -> TempDir {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        std::fs::create_dir_all(root.join(".git")).unwrap();
+        std::fs::create_dir_all(root.join("target")).unwrap();
+        std::fs::create_dir_all(root.join("data")).unwrap();
+        std::fs::write(root.join(".gitignore"), "target/\n").unwrap();
+        std::fs::write(root.join(".skhootignore"), "data/\n").unwrap();
+        std::fs::write(root.join("main.rs"), "").unwrap();
+        std::fs::write(root.join("target").join("app"), "").unwrap();
+        std::fs::write(root.join("data").join("dump.sql"), "").unwrap();
+        temp_dir
+    }
+
+    #[test]
+    fn test_walkers_honour_selected_rules() {
+        let temp_dir = project();
+        let root = temp_dir.path();
+
+        assert_eq!(walk(IgnoreRules::SEARCH, root), vec![".gitignore", ".skhootignore", "main.rs"]);
+        assert_eq!(
+            walk(IgnoreRules::DISK_USAGE, root),
+            vec![".gitignore", ".skhootignore", "main.rs", "target/app"]
+        );
+        assert_eq!(
+            walk(IgnoreRules::SEARCH.with_overrides(None, Some(false)), root),
+            vec![".gitignore", ".skhootignore", "data/dump.sql", "main.rs"]
+        );
+    }
+
+    #[test]
+    fn test_matcher_agrees_with_walker() {
+        let temp_dir = project();
+        let root = temp_dir.path();
+        std::fs::write(root.join(".skhootignore"), "data/\n*.sql\n").unwrap();
+        std::fs::create_dir_all(root.join("db")).unwrap();
+        std::fs::write(root.join("db").join(".skhootignore"), "!schema.sql\n").unwrap();
+        std::fs::write(root.join("db").join("schema.sql"), "").unwrap();
+        std::fs::write(root.join("db").join("seed.sql"), "").unwrap();
+
+        let mut cache = IgnoreCache::new(IgnoreRules::SEARCH);
+        assert!(cache.is_ignored(&root.join("target")));
+        assert!(cache.is_ignored(&root.join("target").join("app")));
+        assert!(cache.is_ignored(&root.join("data").join("dump.sql")));
+        assert!(cache.is_ignored(&root.join("db").join("seed.sql")));
+        assert!(!cache.is_ignored(&root.join("db").join("schema.sql")));
+        assert!(!cache.is_ignored(&root.join("main.rs")));
+
+        let walked = walk(IgnoreRules::SEARCH, root);
+        assert!(walked.contains(&"db/schema.sql".to_string()));
+        assert!(!walked.contains(&"db/seed.sql".to_string()));
+
+        let git_only = IgnoreRules::GIT.matcher(root);
+        assert!(git_only.is_ignored(&root.join("target"), true));
+        assert!(!git_only.is_ignored(&root.join("data"), true));
+    }
+
+    #[test]
+    fn test_gitignore_needs_a_repository() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        std::fs::write(root.join(".gitignore"), "*.log\n").unwrap();
+        std::fs::write(root.join("app.log"), "").unwrap();
+
+        assert!(!IgnoreRules::SEARCH.matcher(root).is_ignored(&root.join("app.log"), false));
+        assert_eq!(walk(IgnoreRules::SEARCH, root), vec![".gitignore", "app.log"]);
+    }
+}
@@ -1,10 +1,10 @@
 //! File indexer for content extraction and database storage
 #![allow(dead_code)]
 
-use walkdir::WalkDir;
 use notify::{Watcher, RecursiveMode, Event, EventKind};
 use tokio::sync::mpsc;
 use std::path::Path;
+use std::sync::Arc;
 use std::fs;
 use std::time::SystemTime;
 use chrono::{DateTime, Utc};
@@ -12,13 +12,16 @@ use sha2::{Sha256, Digest};
 use uuid::Uuid;
 use mime_guess::from_path;
 use encoding_rs::UTF_8;
+use crate::code_index::CodeIndex;
 use crate::db::{Database, FileRecord, ContentChunk};
 use crate::error::AppError;
 use crate::config::AppConfig;
+use crate::ignore_rules::IgnoreRules;
 
 #[derive(Clone)]
 pub struct FileIndexer {
     db: Database,
+    code_index: Arc<CodeIndex>,
     config: AppConfig,
     is_running: std::sync::Arc<std::sync::atomic::AtomicBool>,
 }
@@ -28,6 +31,7 @@ impl FileIndexer {
         let config = AppConfig::new()?;
         
         Ok(Self {
+            code_index: Arc::new(CodeIndex::new(db.clone())),
             db,
             config,
             is_running: std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false)),
@@ -40,24 +44,36 @@ impl FileIndexer {
 
     pub async fn start_full_index(&self) -> Result<(), AppError> {
         self.is_running.store(true, std::sync::atomic::Ordering::Relaxed);
+        crate::events::publish(crate::events::Event::IndexingStarted {
+            paths: self.config.index_paths.clone(),
+        });
+        let started = std::time::Instant::now();
         
+        let mut result = Ok(());
         for path in &self.config.index_paths {
             if Path::new(path).exists() {
-                self.index_directory(path).await?;
+                result = self.index_directory(path).await;
+                if result.is_err() {
+                    break;
+                }
             }
         }
         
         self.is_running.store(false, std::sync::atomic::Ordering::Relaxed);
-        Ok(())
+        crate::events::publish(crate::events::Event::IndexingCompleted {
+            duration_ms: started.elapsed().as_millis() as u64,
+            error: result.as_ref().err().map(|e| e.to_string()),
+        });
+        result
     }
 
     async fn index_directory(&self, root_path: &str) -> Result<(), AppError> {
-        for entry in WalkDir::new(root_path)
-            .follow_links(false)
-            .into_iter()
+        for entry in IgnoreRules::SEARCH
+            .walker(Path::new(root_path))
+            .build()
             .filter_map(|e| e.ok())
         {
-            if entry.file_type().is_file() {
+            if entry.file_type().is_some_and(|t| t.is_file()) {
                 let path = entry.path();
                 
                 if self.should_exclude_file(path) {
@@ -67,6 +83,9 @@ impl FileIndexer {
                 if let Err(e) = self.index_file(path).await {
                     tracing::warn!("Failed to index file {:?}: {}", path, e);
                 }
+                if let Err(e) = self.code_index.index_file(path).await {
+                    tracing::warn!("Failed to index symbols of {:?}: {}", path, e);
+                }
             }
         }
         Ok(())
@@ -237,10 +256,20 @@ impl FileIndexer {
                 match event.kind {
                     EventKind::Create(_) | EventKind::Modify(_) => {
                         for path in event.paths {
-                            if path.is_file() && !indexer.should_exclude_file(&path) {
+                            if path.is_file() && !indexer.should_exclude_file(&path) && !is_ignored(&path) {
                                 if let Err(e) = indexer.index_file(&path).await {
                                     tracing::warn!("Failed to index modified file {:?}: {}", path, e);
                                 }
+                                if let Err(e) = indexer.code_index.index_file(&path).await {
+                                    tracing::warn!("Failed to index symbols of {:?}: {}", path, e);
+                                }
+                            }
+                        }
+                    }
+                    EventKind::Remove(_) => {
+                        for path in event.paths {
+                            if let Err(e) = indexer.code_index.remove_file(&path).await {
+                                tracing::warn!("Failed to drop symbols of {:?}: {}", path, e);
                             }
                         }
                     }
@@ -252,3 +281,10 @@ impl FileIndexer {
         Ok(())
     }
 }
+
+/// Whether the ignore files around `path` keep it out of the index. Checked
+/// per event rather than cached, so edits to ignore files apply immediately.
+fn is_ignored(path: &Path) -> bool {
+    path.parent()
+        .is_some_and(|parent| IgnoreRules::SEARCH.matcher(parent).is_ignored(path, false))
+}
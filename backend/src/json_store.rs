@@ -0,0 +1,98 @@
+//! JSON files behind the small persistent stores in `~/.skhoot`
+//!
+//! A missing file loads as the default value. A file that exists but can't be
+//! read or parsed is moved aside to `<name>.corrupt-<timestamp>` first, so the
+//! next save doesn't overwrite what the user had. Saves write a temporary
+//! file next to the target and rename it into place.
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Contents of the store at `path`, or the default if there is none
+pub fn load<T: DeserializeOwned + Default>(path: &Path) -> T {
+    let error = match std::fs::read_to_string(path) {
+        Ok(content) => match serde_json::from_str(&content) {
+            Ok(value) => return value,
+            Err(e) => e.to_string(),
+        },
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return T::default(),
+        Err(e) => e.to_string(),
+    };
+
+    let backup = backup_path(path);
+    match std::fs::rename(path, &backup) {
+        Ok(()) => tracing::warn!(
+            "Cannot load {} ({}); moved it to {} and starting empty",
+            path.display(),
+            error,
+            backup.display()
+        ),
+        Err(e) => tracing::error!(
+            "Cannot load {} ({}) or move it aside ({}); starting empty",
+            path.display(),
+            error,
+            e
+        ),
+    }
+    T::default()
+}
+
+/// Replace the store at `path` with `value`
+pub fn save<T: Serialize + ?Sized>(path: &Path, value: &T) -> std::io::Result<()> {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    std::fs::create_dir_all(parent)?;
+    let content = serde_json::to_vec_pretty(value)?;
+    let mut file = tempfile::NamedTempFile::new_in(parent)?;
+    file.write_all(&content)?;
+    file.as_file().sync_all()?;
+    file.persist(path).map_err(|e| e.error)?;
+    Ok(())
+}
+
+/// Trimmed text, or `None` if it's blank
+pub fn non_empty(value: Option<String>) -> Option<String> {
+    value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty())
+}
+
+fn backup_path(path: &Path) -> PathBuf {
+    let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    path.with_file_name(format!("{}.corrupt-{}", name, chrono::Utc::now().format("%Y%m%d%H%M%S")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_load_and_save() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join("store.json");
+        let empty: HashMap<String, u32> = load(&path);
+        assert!(empty.is_empty());
+
+        save(&path, &HashMap::from([("a".to_string(), 1u32)])).unwrap();
+        let loaded: HashMap<String, u32> = load(&path);
+        assert_eq!(loaded["a"], 1);
+        assert_eq!(std::fs::read_dir(path.parent().unwrap()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_unparsable_file_is_kept() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("store.json");
+        std::fs::write(&path, "{ not json").unwrap();
+
+        let loaded: Vec<String> = load(&path);
+        assert!(loaded.is_empty());
+        assert!(!path.exists());
+        let backup = std::fs::read_dir(dir.path()).unwrap().next().unwrap().unwrap().path();
+        assert!(backup.file_name().unwrap().to_string_lossy().starts_with("store.json.corrupt-"));
+        assert_eq!(std::fs::read_to_string(backup).unwrap(), "{ not json");
+    }
+}
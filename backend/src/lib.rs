@@ -8,10 +8,47 @@ pub mod disk_analyzer;
 pub mod terminal;
 pub mod api_key_storage;
 pub mod ai;
+pub mod attachments;
 pub mod kiro_bridge;
 pub mod error;
 pub mod workflows;
+pub mod run_stats;
+pub mod local_api;
 pub mod content_extraction;
+pub mod db;
+pub mod metrics;
+pub mod events;
+pub mod config;
+pub mod auth;
+pub mod context;
+pub mod archives;
+pub mod audio;
+pub mod change_timeline;
+pub mod code_index;
+pub mod conversation_search;
+pub mod file_diff;
+pub mod file_follow;
+pub mod filename_index;
+pub mod file_history;
+pub mod file_preview;
+pub mod file_range;
+pub mod file_transfer;
+pub mod file_tree;
+pub mod ignore_rules;
+pub mod json_store;
+pub mod lsp;
+pub mod mcp;
+pub mod notifications;
+pub mod plugins;
+pub mod recycle_bin;
+pub mod scaffold;
+pub mod storage_quotas;
+pub mod vault;
+pub mod agent_hooks;
+pub mod feeds;
+pub mod secrets;
+pub mod mounts;
+pub mod processes;
 
 // Re-export commonly used types
 pub use search_engine::{FileSearchEngine, FileSearchConfig, FileMatch};
@@ -0,0 +1,445 @@
+//! Registered clients of the local API and what they may do
+//!
+//! Tokens are shown once, when a client is created; only their SHA-256 is
+//! kept. Clients persist to `~/.skhoot/local_api_clients.json`; when each was
+//! last used is only kept in memory until the next change or the periodic
+//! flush, so requests don't rewrite the file.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+
+use super::LocalApiError;
+use crate::cli_agent::{ExecutorConfig, Tool, ToolRegistry};
+use crate::json_store::{self, non_empty};
+
+/// Prefix of client tokens, so they are recognizable in config files
+const TOKEN_PREFIX: &str = "skh-";
+
+/// Providers the agent can run on
+const PROVIDERS: &[&str] = &["openai", "anthropic", "google"];
+
+/// How often last-used times are written out
+const USAGE_FLUSH_SECS: u64 = 60;
+
+/// Tools offered when a client doesn't list any: looking, not touching
+const READ_TOOLS: &[Tool] = &[
+    Tool::SearchFiles,
+    Tool::ListDirectory,
+    Tool::ReadFile,
+    Tool::DiffFiles,
+    Tool::GitStatus,
+    Tool::GitDiff,
+    Tool::GitLog,
+    Tool::DocumentSymbols,
+    Tool::GoToDefinition,
+    Tool::FindReferences,
+];
+
+/// Tools a client's model is offered and the executor permissions they run
+/// with
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClientPermissions {
+    /// Built-in, MCP and plugin tool names
+    pub tools: Vec<String>,
+    /// Let tools create, change and delete files and commit to git
+    pub allow_writes: bool,
+    /// Directory file tools are confined to and commands run in; the home
+    /// directory, unconfined, when unset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub workspace_root: Option<String>,
+    /// Domains `http_request` may reach, subdomains included
+    pub allowed_http_domains: Vec<String>,
+}
+
+impl Default for ClientPermissions {
+    fn default() -> Self {
+        Self {
+            tools: READ_TOOLS.iter().map(|tool| tool.name().to_string()).collect(),
+            allow_writes: false,
+            workspace_root: None,
+            allowed_http_domains: Vec::new(),
+        }
+    }
+}
+
+impl ClientPermissions {
+    pub fn allows(&self, tool: &str) -> bool {
+        self.tools.iter().any(|name| name == tool)
+    }
+
+    /// Registry holding just the allowed tools, with MCP and plugin tools
+    /// that are currently available
+    pub fn registry(&self) -> ToolRegistry {
+        let builtin = Tool::all().into_iter().filter(|tool| self.allows(tool.name())).collect();
+        let mut registry = ToolRegistry::with_tools(builtin);
+        let external = crate::mcp::McpManager::global()
+            .tool_definitions()
+            .into_iter()
+            .chain(crate::plugins::PluginRegistry::global().tool_definitions());
+        for def in external.filter(|def| self.allows(&def.name)) {
+            registry.register_external(def);
+        }
+        registry
+    }
+
+    /// Executor settings for tool calls of the trace session `session_id`
+    pub fn executor_config(&self, session_id: &str, timeout_secs: u64) -> ExecutorConfig {
+        let root = self.workspace_root.as_deref().map(expand);
+        ExecutorConfig {
+            default_timeout_ms: timeout_secs * 1000,
+            working_directory: root
+                .clone()
+                .or_else(dirs::home_dir)
+                .unwrap_or_else(|| PathBuf::from(".")),
+            allow_writes: self.allow_writes,
+            allow_git_commits: self.allow_writes,
+            workspace_root: root,
+            session_id: Some(session_id.to_string()),
+            allowed_http_domains: self.allowed_http_domains.clone(),
+            ..Default::default()
+        }
+    }
+
+    fn validate(mut self) -> Result<Self, LocalApiError> {
+        self.tools = self.tools.iter().map(|t| t.trim().to_string()).filter(|t| !t.is_empty()).collect();
+        let mut seen = std::collections::HashSet::new();
+        self.tools.retain(|name| seen.insert(name.clone()));
+        if let Some(unknown) = self.tools.iter().find(|name| !is_known_tool(name)) {
+            return Err(LocalApiError::Invalid(format!("Unknown tool: {}", unknown)));
+        }
+        self.workspace_root = non_empty(self.workspace_root);
+        if let Some(root) = &self.workspace_root {
+            if !expand(root).is_absolute() {
+                return Err(LocalApiError::Invalid(format!(
+                    "Workspace root must be an absolute path: {}",
+                    root
+                )));
+            }
+        }
+        Ok(self)
+    }
+}
+
+fn is_known_tool(name: &str) -> bool {
+    Tool::all().iter().any(|tool| tool.name() == name)
+        || crate::mcp::is_mcp_tool(name)
+        || crate::plugins::is_plugin_tool(name)
+}
+
+fn expand(path: &str) -> PathBuf {
+    match (path.strip_prefix('~'), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest.trim_start_matches(['/', '\\'])),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// An app allowed to use the local API
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApiClient {
+    pub id: String,
+    pub name: String,
+    pub token_sha256: String,
+    /// Last characters of the token, to tell tokens apart
+    pub token_hint: String,
+    pub provider: String,
+    /// Provider model; its default fast model when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub permissions: ClientPermissions,
+    pub enabled: bool,
+    pub created_at: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_used_at: Option<i64>,
+}
+
+impl ApiClient {
+    /// Trace session the client's tool calls are recorded under
+    pub fn session_id(&self) -> String {
+        format!("local-api-{}", self.id)
+    }
+}
+
+/// Fields of a new client
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreateClientRequest {
+    pub name: String,
+    pub provider: String,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub permissions: Option<ClientPermissions>,
+}
+
+/// Changes to a client; `model` is cleared with an empty string
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct UpdateClientRequest {
+    pub name: Option<String>,
+    pub provider: Option<String>,
+    pub model: Option<String>,
+    pub permissions: Option<ClientPermissions>,
+    pub enabled: Option<bool>,
+}
+
+/// Persistent local API clients
+pub struct ClientStore {
+    path: PathBuf,
+    clients: RwLock<Vec<ApiClient>>,
+    /// Last-used times changed since the file was written
+    usage_dirty: AtomicBool,
+}
+
+lazy_static::lazy_static! {
+    static ref GLOBAL_STORE: Arc<ClientStore> = Arc::new(ClientStore::new(
+        dirs::home_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join(".skhoot")
+            .join("local_api_clients.json"),
+    ));
+}
+
+impl ClientStore {
+    /// Open the store at `path`; see [`json_store::load`]
+    pub fn new(path: PathBuf) -> Self {
+        let clients = json_store::load(&path);
+        Self {
+            path,
+            clients: RwLock::new(clients),
+            usage_dirty: AtomicBool::new(false),
+        }
+    }
+
+    pub fn global() -> Arc<ClientStore> {
+        GLOBAL_STORE.clone()
+    }
+
+    pub fn list(&self) -> Vec<ApiClient> {
+        self.clients.read().unwrap().clone()
+    }
+
+    pub fn get(&self, id: &str) -> Option<ApiClient> {
+        self.clients.read().unwrap().iter().find(|c| c.id == id).cloned()
+    }
+
+    /// Register a client. Returns it with its token, which isn't stored.
+    pub fn create(&self, request: CreateClientRequest) -> Result<(ApiClient, String), LocalApiError> {
+        let name = non_empty(Some(request.name))
+            .ok_or_else(|| LocalApiError::Invalid("A client needs a name".to_string()))?;
+        let token = new_token();
+        let client = ApiClient {
+            id: uuid::Uuid::new_v4().to_string(),
+            name,
+            token_sha256: token_hash(&token),
+            token_hint: token_hint(&token),
+            provider: check_provider(request.provider)?,
+            model: non_empty(request.model),
+            permissions: request.permissions.unwrap_or_default().validate()?,
+            enabled: true,
+            created_at: chrono::Utc::now().timestamp(),
+            last_used_at: None,
+        };
+
+        let mut clients = self.clients.write().unwrap();
+        clients.push(client.clone());
+        self.save(&clients)?;
+        Ok((client, token))
+    }
+
+    pub fn update(&self, id: &str, request: UpdateClientRequest) -> Result<ApiClient, LocalApiError> {
+        let mut clients = self.clients.write().unwrap();
+        let client = clients
+            .iter_mut()
+            .find(|c| c.id == id)
+            .ok_or_else(|| LocalApiError::NotFound(id.to_string()))?;
+
+        if let Some(name) = non_empty(request.name) {
+            client.name = name;
+        }
+        if let Some(provider) = request.provider {
+            client.provider = check_provider(provider)?;
+        }
+        if let Some(model) = request.model {
+            client.model = non_empty(Some(model));
+        }
+        if let Some(permissions) = request.permissions {
+            client.permissions = permissions.validate()?;
+        }
+        if let Some(enabled) = request.enabled {
+            client.enabled = enabled;
+        }
+        let client = client.clone();
+        self.save(&clients)?;
+        Ok(client)
+    }
+
+    /// Replace a client's token. Returns the client with the new token.
+    pub fn rotate_token(&self, id: &str) -> Result<(ApiClient, String), LocalApiError> {
+        let mut clients = self.clients.write().unwrap();
+        let client = clients
+            .iter_mut()
+            .find(|c| c.id == id)
+            .ok_or_else(|| LocalApiError::NotFound(id.to_string()))?;
+        let token = new_token();
+        client.token_sha256 = token_hash(&token);
+        client.token_hint = token_hint(&token);
+        let client = client.clone();
+        self.save(&clients)?;
+        Ok((client, token))
+    }
+
+    /// Remove a client. Returns whether it existed.
+    pub fn remove(&self, id: &str) -> Result<bool, LocalApiError> {
+        let mut clients = self.clients.write().unwrap();
+        let before = clients.len();
+        clients.retain(|c| c.id != id);
+        if clients.len() == before {
+            return Ok(false);
+        }
+        self.save(&clients)?;
+        Ok(true)
+    }
+
+    /// The enabled client holding `token`, marked as used now. The mark is
+    /// written out by [`ClientStore::flush_usage`].
+    pub fn authenticate(&self, token: &str) -> Option<ApiClient> {
+        let hash = token_hash(token);
+        let mut clients = self.clients.write().unwrap();
+        let client = clients.iter_mut().find(|c| c.enabled && c.token_sha256 == hash)?;
+        client.last_used_at = Some(chrono::Utc::now().timestamp());
+        self.usage_dirty.store(true, Ordering::Relaxed);
+        Some(client.clone())
+    }
+
+    /// Write out last-used times that changed since the last save
+    pub fn flush_usage(&self) -> Result<(), LocalApiError> {
+        if !self.usage_dirty.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+        let clients = self.clients.read().unwrap();
+        self.save(&clients)
+    }
+
+    fn save(&self, clients: &[ApiClient]) -> Result<(), LocalApiError> {
+        json_store::save(&self.path, clients).map_err(|e| LocalApiError::Io(e.to_string()))?;
+        self.usage_dirty.store(false, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+/// Write out when clients were last used every [`USAGE_FLUSH_SECS`]
+pub fn spawn_usage_flusher(store: Arc<ClientStore>) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(std::time::Duration::from_secs(USAGE_FLUSH_SECS));
+        loop {
+            ticker.tick().await;
+            if let Err(e) = store.flush_usage() {
+                tracing::debug!("Failed to record use of API clients: {}", e);
+            }
+        }
+    })
+}
+
+fn new_token() -> String {
+    format!(
+        "{}{}{}",
+        TOKEN_PREFIX,
+        uuid::Uuid::new_v4().simple(),
+        uuid::Uuid::new_v4().simple()
+    )
+}
+
+fn token_hash(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+fn token_hint(token: &str) -> String {
+    token[token.len().saturating_sub(4)..].to_string()
+}
+
+fn check_provider(provider: String) -> Result<String, LocalApiError> {
+    let provider = provider.trim().to_lowercase();
+    if !PROVIDERS.contains(&provider.as_str()) {
+        return Err(LocalApiError::Invalid(format!(
+            "Provider must be one of {} (got '{}')",
+            PROVIDERS.join(", "),
+            provider
+        )));
+    }
+    Ok(provider)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn request(permissions: Option<ClientPermissions>) -> CreateClientRequest {
+        CreateClientRequest {
+            name: "Editor".to_string(),
+            provider: "OpenAI".to_string(),
+            model: None,
+            permissions,
+        }
+    }
+
+    #[test]
+    fn test_create_and_authenticate() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("clients.json");
+        let store = ClientStore::new(path.clone());
+
+        let (client, token) = store.create(request(None)).unwrap();
+        assert!(token.starts_with(TOKEN_PREFIX));
+        assert_eq!(client.provider, "openai");
+        assert!(client.permissions.allows("search_files"));
+        assert!(!client.permissions.allows("shell"));
+        assert!(!std::fs::read_to_string(&path).unwrap().contains(&token));
+
+        let store = ClientStore::new(path.clone());
+        let saved = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(store.authenticate(&token).unwrap().id, client.id);
+        assert!(store.authenticate("skh-wrong").is_none());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), saved);
+        store.flush_usage().unwrap();
+        assert!(ClientStore::new(path).get(&client.id).unwrap().last_used_at.is_some());
+
+        let (_, rotated) = store.rotate_token(&client.id).unwrap();
+        assert!(store.authenticate(&token).is_none());
+        assert!(store.authenticate(&rotated).is_some());
+
+        let disable = UpdateClientRequest { enabled: Some(false), ..Default::default() };
+        store.update(&client.id, disable).unwrap();
+        assert!(store.authenticate(&rotated).is_none());
+    }
+
+    #[test]
+    fn test_permissions_map_to_executor() {
+        let dir = TempDir::new().unwrap();
+        let store = ClientStore::new(dir.path().join("clients.json"));
+        let unknown = ClientPermissions { tools: vec!["launch_rockets".to_string()], ..Default::default() };
+        assert!(store.create(request(Some(unknown))).is_err());
+        let relative = ClientPermissions { workspace_root: Some("projects".to_string()), ..Default::default() };
+        assert!(store.create(request(Some(relative))).is_err());
+
+        let permissions = ClientPermissions {
+            tools: vec!["shell".to_string(), "read_file".to_string()],
+            allow_writes: false,
+            workspace_root: Some(dir.path().display().to_string()),
+            allowed_http_domains: Vec::new(),
+        };
+        let (client, _) = store.create(request(Some(permissions))).unwrap();
+        let registry = client.permissions.registry();
+        assert!(registry.is_enabled("shell"));
+        assert!(!registry.is_enabled("write_file"));
+
+        let config = client.permissions.executor_config(&client.session_id(), 30);
+        assert!(!config.allow_writes && !config.allow_git_commits);
+        assert_eq!(config.working_directory, dir.path());
+        assert_eq!(config.workspace_root.as_deref(), Some(dir.path()));
+        assert_eq!(config.default_timeout_ms, 30_000);
+    }
+}
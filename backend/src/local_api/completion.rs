@@ -0,0 +1,158 @@
+//! Chat completions answered by the agent
+//!
+//! The caller's conversation goes to the client's provider with the allowed
+//! tools offered. Tool calls are run and their results sent back until the
+//! model answers without calling tools, or `local_api.max_tool_rounds` is
+//! reached and it is asked once more with no tools offered.
+
+use serde_json::{json, Value};
+
+use super::ApiClient;
+use crate::ai::{self, AIManager, ChatTurn, TokenUsage};
+use crate::cli_agent::instructions::get_provider_prompt;
+use crate::cli_agent::{AgentExecutor, ToolCall, ToolRegistry, ToolResult, TraceEvent, TraceRecorder};
+use crate::config::SettingsStore;
+use crate::error::AppError;
+
+/// The agent's answer to a conversation
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Completion {
+    pub content: String,
+    /// Summed over every model request
+    pub usage: TokenUsage,
+    pub tool_calls: usize,
+}
+
+/// Split the caller's system messages off the conversation; they are added
+/// to the agent's own system prompt
+fn split_system(messages: &[Value]) -> (Vec<String>, Vec<Value>) {
+    let (system, conversation): (Vec<&Value>, Vec<&Value>) =
+        messages.iter().partition(|m| m["role"] == "system" || m["role"] == "developer");
+    (
+        system.into_iter().map(ai::chat::message_text).filter(|t| !t.is_empty()).collect(),
+        conversation.into_iter().cloned().collect(),
+    )
+}
+
+fn system_prompt(provider: &str, working_directory: &str, instructions: &[String]) -> String {
+    let prompt = get_provider_prompt(provider).build_with_context(working_directory, std::env::consts::OS);
+    if instructions.is_empty() {
+        return prompt;
+    }
+    format!("{}\n\n## Instructions from the calling application\n{}", prompt, instructions.join("\n\n"))
+}
+
+/// Content of the `tool` message answering a call
+fn tool_message(call: &ToolCall, result: &ToolResult) -> Value {
+    let content = match (&result.error, result.success) {
+        (Some(error), false) if result.output.is_empty() => format!("Error: {}", error),
+        (Some(error), false) => format!("{}\n\nError: {}", result.output, error),
+        _ => result.output.clone(),
+    };
+    json!({ "role": "tool", "tool_call_id": call.id, "content": content })
+}
+
+fn denied(call: &ToolCall) -> ToolResult {
+    ToolResult {
+        tool_call_id: call.id.clone(),
+        success: false,
+        output: String::new(),
+        error: Some(format!("Tool {} is not allowed for this client", call.name)),
+        metadata: None,
+    }
+}
+
+/// Answer `messages`, an OpenAI-format conversation, as `client`
+pub async fn complete(ai: &AIManager, client: &ApiClient, messages: &[Value]) -> Result<Completion, AppError> {
+    let settings = SettingsStore::global().get().local_api;
+    let api_key = crate::api_key_storage::provider_key(&client.provider)?
+        .ok_or_else(|| AppError::BadRequest(format!("No API key is saved for {}", client.provider)))?;
+    let model = client
+        .model
+        .as_deref()
+        .or_else(|| ai::default_model(&client.provider))
+        .unwrap_or_default();
+
+    let session_id = client.session_id();
+    let config = client.permissions.executor_config(&session_id, settings.tool_timeout_secs);
+    let (instructions, mut conversation) = split_system(messages);
+    let system = system_prompt(&client.provider, &config.working_directory.to_string_lossy(), &instructions);
+    let executor = AgentExecutor::with_config(config);
+    let registry = client.permissions.registry();
+    let no_tools = ToolRegistry::with_tools(Vec::new());
+    let recorder = TraceRecorder::global();
+
+    let mut completion = Completion::default();
+    for round in 0..=settings.max_tool_rounds {
+        let tools = if round < settings.max_tool_rounds { &registry } else { &no_tools };
+        recorder.record(
+            &session_id,
+            TraceEvent::ModelRequest {
+                provider: Some(client.provider.clone()),
+                model: Some(model.to_string()),
+                messages: Value::Array(conversation.clone()),
+                tools: tools.definitions().iter().map(|def| def.name.clone()).collect(),
+            },
+        );
+        let turn: ChatTurn = ai.chat(&client.provider, &api_key, model, &system, &conversation, tools).await?;
+        recorder.record(
+            &session_id,
+            TraceEvent::ModelResponse {
+                content: turn.content.clone(),
+                tool_calls: turn.tool_calls.clone(),
+                usage: serde_json::to_value(turn.usage).ok(),
+            },
+        );
+        completion.usage.add(turn.usage);
+
+        // Calls made after the tools were withdrawn are dropped
+        if turn.tool_calls.is_empty() || round == settings.max_tool_rounds {
+            completion.content = turn.content;
+            break;
+        }
+        conversation.push(turn.to_message());
+        for call in &turn.tool_calls {
+            // The model may name a tool it wasn't offered
+            let result = if tools.is_enabled(&call.name) {
+                executor.execute(call).await
+            } else {
+                denied(call)
+            };
+            completion.tool_calls += 1;
+            conversation.push(tool_message(call, &result));
+        }
+    }
+    Ok(completion)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_system() {
+        let messages = vec![
+            json!({ "role": "system", "content": "Answer in French." }),
+            json!({ "role": "user", "content": "Where is my resume?" }),
+        ];
+        let (instructions, conversation) = split_system(&messages);
+        assert_eq!(instructions, vec!["Answer in French."]);
+        assert_eq!(conversation.len(), 1);
+
+        let prompt = system_prompt("openai", "/home/me", &instructions);
+        assert!(prompt.contains("/home/me"));
+        assert!(prompt.ends_with("Answer in French."));
+    }
+
+    #[test]
+    fn test_tool_message() {
+        let call = ToolCall {
+            id: "call_1".to_string(),
+            name: "shell".to_string(),
+            arguments: json!({ "command": "ls" }),
+        };
+        let message = tool_message(&call, &denied(&call));
+        assert_eq!(message["tool_call_id"], "call_1");
+        assert_eq!(message["content"], "Error: Tool shell is not allowed for this client");
+    }
+}
@@ -0,0 +1,38 @@
+//! OpenAI-compatible local API
+//!
+//! Editors and command-line tools that speak the OpenAI chat completions
+//! protocol can use Skhoot as a model: `POST /v1/chat/completions` runs the
+//! conversation through the Skhoot agent, which searches files, reads them
+//! and runs commands with its own tools before answering. The caller only
+//! sees the final answer; tools the caller sends in the request are ignored.
+//!
+//! Every caller is a registered [`ApiClient`] presenting its own token as
+//! `Authorization: Bearer <token>`. A client names the provider and model
+//! the agent runs on, using the key saved in the app for that provider,
+//! and its [`ClientPermissions`](clients::ClientPermissions) decide which
+//! tools the model is offered and whether they may change files. The
+//! endpoint is off until `local_api.enabled` is set. Tool calls are recorded
+//! in the trace of the session `local-api-<client id>`.
+
+pub mod clients;
+pub mod completion;
+
+pub use clients::{ApiClient, ClientStore, CreateClientRequest, UpdateClientRequest};
+pub use completion::{complete, Completion};
+
+use thiserror::Error;
+
+/// Model name the endpoint answers as
+pub const MODEL_ID: &str = "skhoot";
+
+#[derive(Debug, Error)]
+pub enum LocalApiError {
+    #[error("API client not found: {0}")]
+    NotFound(String),
+
+    #[error("{0}")]
+    Invalid(String),
+
+    #[error("Failed to save API clients: {0}")]
+    Io(String),
+}
@@ -0,0 +1,344 @@
+//! Client for one language server and workspace root
+
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+use super::connection::Connection;
+use super::{char_column, symbol_kind_name, utf16_offset, CodeLocation, CodeSymbol, LspError, LspServerConfig};
+
+/// Server is still loading the project (`ContentModified`, `ServerNotInitialized`)
+const RETRY_CODES: &[i64] = &[-32801, -32002];
+
+/// Attempts of a request the server answered with a retry code
+const MAX_ATTEMPTS: u32 = 5;
+
+const RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// Opened document, re-sent when the file changes on disk
+struct OpenDocument {
+    version: i32,
+    modified: Option<SystemTime>,
+}
+
+/// An initialized session with a server
+pub struct LspClient {
+    config: LspServerConfig,
+    root: PathBuf,
+    connection: Connection,
+    capabilities: Value,
+    documents: Mutex<HashMap<PathBuf, OpenDocument>>,
+}
+
+fn file_uri(path: &Path) -> Result<String, LspError> {
+    url::Url::from_file_path(path)
+        .map(|url| url.to_string())
+        .map_err(|_| LspError::InvalidPosition(format!("{} is not an absolute path", path.display())))
+}
+
+fn uri_path(uri: &str) -> Option<PathBuf> {
+    url::Url::parse(uri).ok()?.to_file_path().ok()
+}
+
+/// Protocol line/character of a position
+fn position(value: &Value) -> (u32, u32) {
+    let get = |key: &str| value.get(key).and_then(|v| v.as_u64()).unwrap_or(0) as u32;
+    (get("line"), get("character"))
+}
+
+impl LspClient {
+    /// Start the server for `root` and run the handshake
+    pub async fn start(config: LspServerConfig, root: PathBuf) -> Result<Self, LspError> {
+        let connection = Connection::spawn(&config, &root)?;
+        let root_uri = file_uri(&root)?;
+        let name = root.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+
+        let result = connection
+            .request(
+                "initialize",
+                json!({
+                    "processId": std::process::id(),
+                    "clientInfo": { "name": "skhoot", "version": env!("CARGO_PKG_VERSION") },
+                    "rootUri": root_uri,
+                    "workspaceFolders": [{ "uri": root_uri, "name": name }],
+                    "capabilities": {
+                        "textDocument": {
+                            "synchronization": { "didSave": false },
+                            "definition": { "linkSupport": true },
+                            "references": {},
+                            "documentSymbol": { "hierarchicalDocumentSymbolSupport": true },
+                        },
+                        "workspace": { "workspaceFolders": true, "configuration": true },
+                        "window": { "workDoneProgress": true },
+                    },
+                }),
+            )
+            .await?;
+        connection.notify("initialized", json!({}))?;
+
+        tracing::info!("Started {} language server for {}", config.language, root.display());
+        Ok(Self {
+            capabilities: result.get("capabilities").cloned().unwrap_or(Value::Null),
+            config,
+            root,
+            connection,
+            documents: Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub fn language(&self) -> &str {
+        &self.config.language
+    }
+
+    pub fn command(&self) -> &str {
+        &self.config.command
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn is_running(&self) -> bool {
+        !self.connection.is_closed()
+    }
+
+    pub fn open_documents(&self) -> usize {
+        self.documents.lock().unwrap().len()
+    }
+
+    fn supports(&self, provider: &str) -> bool {
+        self.capabilities
+            .get(provider)
+            .is_some_and(|value| !matches!(value, Value::Null | Value::Bool(false)))
+    }
+
+    /// Send a request, retrying while the server is still loading the project
+    async fn request(&self, method: &str, params: Value) -> Result<Value, LspError> {
+        let mut attempt = 1;
+        loop {
+            match self.connection.request(method, params.clone()).await {
+                Err(LspError::Server { code, .. }) if RETRY_CODES.contains(&code) && attempt < MAX_ATTEMPTS => {
+                    attempt += 1;
+                    tokio::time::sleep(RETRY_DELAY).await;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Open the document, or send its new text if it changed on disk since;
+    /// returns the text
+    async fn sync(&self, path: &Path) -> Result<String, LspError> {
+        let text = tokio::fs::read_to_string(path).await?;
+        let modified = tokio::fs::metadata(path).await.ok().and_then(|m| m.modified().ok());
+        let uri = file_uri(path)?;
+
+        let mut documents = self.documents.lock().unwrap();
+        match documents.get_mut(path) {
+            Some(document) if document.modified == modified => {}
+            Some(document) => {
+                document.version += 1;
+                document.modified = modified;
+                self.connection.notify(
+                    "textDocument/didChange",
+                    json!({
+                        "textDocument": { "uri": uri, "version": document.version },
+                        "contentChanges": [{ "text": text }],
+                    }),
+                )?;
+            }
+            None => {
+                self.connection.notify(
+                    "textDocument/didOpen",
+                    json!({
+                        "textDocument": { "uri": uri, "languageId": self.language_id(path), "version": 1, "text": text },
+                    }),
+                )?;
+                documents.insert(path.to_path_buf(), OpenDocument { version: 1, modified });
+            }
+        }
+        Ok(text)
+    }
+
+    /// Language identifier of a file; TypeScript servers tell the dialects apart
+    fn language_id(&self, path: &Path) -> String {
+        let extension = path.extension().and_then(|e| e.to_str()).unwrap_or_default().to_ascii_lowercase();
+        match (self.config.language.as_str(), extension.as_str()) {
+            ("typescript", "tsx") => "typescriptreact".to_string(),
+            ("typescript", "js" | "mjs" | "cjs") => "javascript".to_string(),
+            ("typescript", "jsx") => "javascriptreact".to_string(),
+            (language, _) => language.to_string(),
+        }
+    }
+
+    /// Request parameters for a 1-based line and column in `path`
+    async fn position_params(&self, path: &Path, line: u32, column: u32) -> Result<Value, LspError> {
+        let text = self.sync(path).await?;
+        let line_text = text
+            .lines()
+            .nth(line.saturating_sub(1) as usize)
+            .filter(|_| line > 0)
+            .ok_or_else(|| LspError::InvalidPosition(format!("{} has no line {}", path.display(), line)))?;
+        Ok(json!({
+            "textDocument": { "uri": file_uri(path)? },
+            "position": { "line": line - 1, "character": utf16_offset(line_text, column.max(1)) },
+        }))
+    }
+
+    /// Where the symbol at a position is defined
+    pub async fn definition(&self, path: &Path, line: u32, column: u32) -> Result<Vec<CodeLocation>, LspError> {
+        if !self.supports("definitionProvider") {
+            return Err(LspError::Unsupported(format!("go to definition in {}", self.config.language)));
+        }
+        let params = self.position_params(path, line, column).await?;
+        let result = self.request("textDocument/definition", params).await?;
+        self.locations(&result).await
+    }
+
+    /// Uses of the symbol at a position
+    pub async fn references(
+        &self,
+        path: &Path,
+        line: u32,
+        column: u32,
+        include_declaration: bool,
+    ) -> Result<Vec<CodeLocation>, LspError> {
+        if !self.supports("referencesProvider") {
+            return Err(LspError::Unsupported(format!("find references in {}", self.config.language)));
+        }
+        let mut params = self.position_params(path, line, column).await?;
+        params["context"] = json!({ "includeDeclaration": include_declaration });
+        let result = self.request("textDocument/references", params).await?;
+        self.locations(&result).await
+    }
+
+    /// Symbols declared in a document, nested when the server supports it
+    pub async fn document_symbols(&self, path: &Path) -> Result<Vec<CodeSymbol>, LspError> {
+        if !self.supports("documentSymbolProvider") {
+            return Err(LspError::Unsupported(format!("document symbols in {}", self.config.language)));
+        }
+        let text = self.sync(path).await?;
+        let lines: Vec<&str> = text.lines().collect();
+        let result = self
+            .request("textDocument/documentSymbol", json!({ "textDocument": { "uri": file_uri(path)? } }))
+            .await?;
+        Ok(result
+            .as_array()
+            .map(|symbols| symbols.iter().filter_map(|s| to_symbol(s, &lines)).collect())
+            .unwrap_or_default())
+    }
+
+    /// Locations of a `Location | Location[] | LocationLink[] | null` result,
+    /// with line previews
+    async fn locations(&self, result: &Value) -> Result<Vec<CodeLocation>, LspError> {
+        let items = match result {
+            Value::Array(items) => items.clone(),
+            Value::Null => Vec::new(),
+            single => vec![single.clone()],
+        };
+
+        let mut files: HashMap<PathBuf, Option<String>> = HashMap::new();
+        let mut locations = Vec::new();
+        for item in &items {
+            let uri = item.get("uri").or_else(|| item.get("targetUri")).and_then(|u| u.as_str());
+            let range = item.get("targetSelectionRange").or_else(|| item.get("range"));
+            let (Some(path), Some(range)) = (uri.and_then(uri_path), range) else {
+                continue;
+            };
+            if !files.contains_key(&path) {
+                files.insert(path.clone(), tokio::fs::read_to_string(&path).await.ok());
+            }
+            let text = files[&path].as_deref().unwrap_or_default();
+            let (start_line, start_char) = position(&range["start"]);
+            let (end_line, end_char) = position(&range["end"]);
+            let line_at = |line: u32| text.lines().nth(line as usize).unwrap_or_default();
+
+            let location = CodeLocation {
+                file: self.display_path(&path),
+                line: start_line + 1,
+                column: char_column(line_at(start_line), start_char),
+                end_line: end_line + 1,
+                end_column: char_column(line_at(end_line), end_char),
+                preview: Some(line_at(start_line).trim().to_string()).filter(|p| !p.is_empty()),
+            };
+            if !locations.contains(&location) {
+                locations.push(location);
+            }
+        }
+        Ok(locations)
+    }
+
+    /// Path relative to the root when it's inside it
+    fn display_path(&self, path: &Path) -> String {
+        path.strip_prefix(&self.root).unwrap_or(path).to_string_lossy().to_string()
+    }
+
+    /// Ask the server to exit
+    pub async fn shutdown(&self) {
+        if self.connection.request("shutdown", Value::Null).await.is_ok() {
+            let _ = self.connection.notify("exit", Value::Null);
+        }
+    }
+}
+
+/// A `DocumentSymbol` or `SymbolInformation`
+fn to_symbol(value: &Value, lines: &[&str]) -> Option<CodeSymbol> {
+    let name = value.get("name")?.as_str()?.to_string();
+    let kind = symbol_kind_name(value.get("kind").and_then(|k| k.as_u64()).unwrap_or(0)).to_string();
+    let range = value
+        .get("selectionRange")
+        .or_else(|| value.get("range"))
+        .or_else(|| value.pointer("/location/range"))?;
+    let full_range = value.get("range").or_else(|| value.pointer("/location/range")).unwrap_or(range);
+    let (line, character) = position(&range["start"]);
+    let (end_line, _) = position(&full_range["end"]);
+
+    Some(CodeSymbol {
+        name,
+        kind,
+        detail: value.get("detail").and_then(|d| d.as_str()).map(str::to_string).filter(|d| !d.is_empty()),
+        line: line + 1,
+        column: char_column(lines.get(line as usize).copied().unwrap_or_default(), character),
+        end_line: end_line + 1,
+        container: value.get("containerName").and_then(|c| c.as_str()).map(str::to_string).filter(|c| !c.is_empty()),
+        children: value
+            .get("children")
+            .and_then(|c| c.as_array())
+            .map(|children| children.iter().filter_map(|c| to_symbol(c, lines)).collect())
+            .unwrap_or_default(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_document_symbols() {
+        let lines = ["struct Point {", "    x: i32,", "}"];
+        let nested = json!({
+            "name": "Point", "kind": 23, "detail": "",
+            "range": { "start": { "line": 0, "character": 0 }, "end": { "line": 2, "character": 1 } },
+            "selectionRange": { "start": { "line": 0, "character": 7 }, "end": { "line": 0, "character": 12 } },
+            "children": [{
+                "name": "x", "kind": 8, "detail": "i32",
+                "range": { "start": { "line": 1, "character": 4 }, "end": { "line": 1, "character": 10 } },
+                "selectionRange": { "start": { "line": 1, "character": 4 }, "end": { "line": 1, "character": 5 } },
+            }],
+        });
+        let symbol = to_symbol(&nested, &lines).unwrap();
+        assert_eq!((symbol.kind.as_str(), symbol.line, symbol.column, symbol.end_line), ("struct", 1, 8, 3));
+        assert_eq!(symbol.detail, None);
+        assert_eq!(symbol.children[0].detail.as_deref(), Some("i32"));
+        assert_eq!((symbol.children[0].line, symbol.children[0].column), (2, 5));
+
+        let flat = json!({
+            "name": "x", "kind": 8, "containerName": "Point",
+            "location": { "uri": "file:///a.rs", "range": { "start": { "line": 1, "character": 4 }, "end": { "line": 1, "character": 10 } } },
+        });
+        let symbol = to_symbol(&flat, &lines).unwrap();
+        assert_eq!((symbol.container.as_deref(), symbol.line, symbol.column), (Some("Point"), 2, 5));
+    }
+}
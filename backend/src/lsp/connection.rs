@@ -0,0 +1,305 @@
+//! JSON-RPC over stdio with a language server
+//!
+//! Messages are framed with a `Content-Length` header rather than one per
+//! line as MCP servers do. Servers send requests of their own while they
+//! work (configuration, progress tokens, capability registration); those get
+//! neutral answers so the server never waits on us.
+
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+
+use super::{LspError, LspServerConfig};
+
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// Largest message accepted from a server
+const MAX_MESSAGE_BYTES: usize = 64 * 1024 * 1024;
+
+type Pending = Arc<Mutex<HashMap<u64, oneshot::Sender<Result<Value, LspError>>>>>;
+
+/// Request/response channel to one server process
+pub(crate) struct Connection {
+    language: String,
+    outgoing: mpsc::UnboundedSender<Value>,
+    pending: Pending,
+    next_id: AtomicU64,
+    timeout: Duration,
+    closed: Arc<AtomicBool>,
+    tasks: Vec<JoinHandle<()>>,
+    /// Killed when the connection drops
+    _child: Option<tokio::process::Child>,
+}
+
+/// Frame a message for the wire
+pub(crate) fn encode(message: &Value) -> Vec<u8> {
+    let body = message.to_string();
+    let mut frame = format!("Content-Length: {}\r\n\r\n", body.len()).into_bytes();
+    frame.extend_from_slice(body.as_bytes());
+    frame
+}
+
+/// Read one framed message; `None` at end of stream
+pub(crate) async fn read_message<R: AsyncBufRead + Unpin>(reader: &mut R) -> Result<Option<Value>, LspError> {
+    let mut length = None;
+    let mut header = String::new();
+    loop {
+        header.clear();
+        if reader.read_line(&mut header).await? == 0 {
+            return Ok(None);
+        }
+        let line = header.trim_end();
+        if line.is_empty() {
+            if length.is_some() {
+                break;
+            }
+            continue;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                length = Some(
+                    value
+                        .trim()
+                        .parse::<usize>()
+                        .map_err(|_| LspError::Transport(format!("invalid header: {}", line)))?,
+                );
+            }
+        }
+    }
+
+    let length = length.unwrap_or_default();
+    if length > MAX_MESSAGE_BYTES {
+        return Err(LspError::Transport(format!("message of {} bytes is too large", length)));
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).await?;
+    serde_json::from_slice(&body)
+        .map(Some)
+        .map_err(|e| LspError::InvalidResponse(e.to_string()))
+}
+
+impl Connection {
+    /// Start the server process in `root`
+    pub(crate) fn spawn(config: &LspServerConfig, root: &std::path::Path) -> Result<Self, LspError> {
+        let mut child = tokio::process::Command::new(&config.command)
+            .args(&config.args)
+            .envs(&config.env)
+            .current_dir(root)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| LspError::Transport(format!("failed to start {}: {}", config.command, e)))?;
+
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        let stdout = child.stdout.take().expect("stdout is piped");
+        let stderr = child.stderr.take().expect("stderr is piped");
+
+        let (outgoing, mut receiver) = mpsc::unbounded_channel::<Value>();
+        let pending: Pending = Arc::default();
+        let closed = Arc::new(AtomicBool::new(false));
+
+        let writer = tokio::spawn(async move {
+            while let Some(message) = receiver.recv().await {
+                if stdin.write_all(&encode(&message)).await.is_err() || stdin.flush().await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        let reader = {
+            let language = config.language.clone();
+            let pending = pending.clone();
+            let outgoing = outgoing.clone();
+            let closed = closed.clone();
+            tokio::spawn(async move {
+                let mut stdout = BufReader::new(stdout);
+                let reason = loop {
+                    match read_message(&mut stdout).await {
+                        Ok(Some(message)) => deliver(&language, &pending, &outgoing, message),
+                        Ok(None) => break "process exited".to_string(),
+                        Err(e) => break e.to_string(),
+                    }
+                };
+                tracing::warn!("Language server for {} disconnected: {}", language, reason);
+                closed.store(true, Ordering::Relaxed);
+                for (_, sender) in pending.lock().unwrap().drain() {
+                    let _ = sender.send(Err(LspError::Closed));
+                }
+            })
+        };
+
+        let language = config.language.clone();
+        let logger = tokio::spawn(async move {
+            let mut lines = BufReader::new(stderr).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                tracing::debug!("[lsp:{}] {}", language, line);
+            }
+        });
+
+        Ok(Self {
+            language: config.language.clone(),
+            outgoing,
+            pending,
+            next_id: AtomicU64::new(1),
+            timeout: config.timeout(),
+            closed,
+            tasks: vec![writer, reader, logger],
+            _child: Some(child),
+        })
+    }
+
+    /// Whether the server went away
+    pub(crate) fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::Relaxed)
+    }
+
+    /// Send a request and wait for its result
+    pub(crate) async fn request(&self, method: &str, params: Value) -> Result<Value, LspError> {
+        if self.is_closed() {
+            return Err(LspError::Closed);
+        }
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (tx, rx) = oneshot::channel();
+        self.pending.lock().unwrap().insert(id, tx);
+
+        let message = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+        if self.outgoing.send(message).is_err() {
+            self.pending.lock().unwrap().remove(&id);
+            return Err(LspError::Closed);
+        }
+
+        match tokio::time::timeout(self.timeout, rx).await {
+            Ok(Ok(result)) => result,
+            Ok(Err(_)) => Err(LspError::Closed),
+            Err(_) => {
+                self.pending.lock().unwrap().remove(&id);
+                let _ = self.notify("$/cancelRequest", json!({ "id": id }));
+                Err(LspError::Timeout(format!("{} ({})", method, self.language)))
+            }
+        }
+    }
+
+    /// Send a notification; there is no reply
+    pub(crate) fn notify(&self, method: &str, params: Value) -> Result<(), LspError> {
+        self.outgoing
+            .send(json!({ "jsonrpc": "2.0", "method": method, "params": params }))
+            .map_err(|_| LspError::Closed)
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
+
+/// Route a message from the server
+fn deliver(language: &str, pending: &Pending, outgoing: &mpsc::UnboundedSender<Value>, message: Value) {
+    let id = message.get("id").cloned();
+    let method = message.get("method").and_then(|m| m.as_str());
+
+    match (id, method) {
+        (Some(id), None) => {
+            let Some(sender) = id.as_u64().and_then(|id| pending.lock().unwrap().remove(&id)) else {
+                tracing::debug!("Language server for {} answered unknown request {}", language, id);
+                return;
+            };
+            let result = match message.get("error") {
+                Some(error) => Err(LspError::Server {
+                    code: error.get("code").and_then(|c| c.as_i64()).unwrap_or_default(),
+                    message: error
+                        .get("message")
+                        .and_then(|m| m.as_str())
+                        .unwrap_or("unknown error")
+                        .to_string(),
+                }),
+                None => Ok(message.get("result").cloned().unwrap_or(Value::Null)),
+            };
+            let _ = sender.send(result);
+        }
+        (Some(id), Some(method)) => {
+            let _ = outgoing.send(reply_to_server(id, method, message.get("params")));
+        }
+        (None, Some("window/logMessage")) | (None, Some("window/showMessage")) => {
+            let text = message.pointer("/params/message").and_then(|m| m.as_str()).unwrap_or_default();
+            tracing::debug!("[lsp:{}] {}", language, text);
+        }
+        (None, Some(_)) => {}
+        (None, None) => {
+            tracing::debug!("Language server for {} sent an invalid message", language);
+        }
+    }
+}
+
+/// Answer a request the server sent us
+fn reply_to_server(id: Value, method: &str, params: Option<&Value>) -> Value {
+    let result = match method {
+        // No client-side settings; one null per requested item
+        "workspace/configuration" => {
+            let items = params
+                .and_then(|p| p.get("items"))
+                .and_then(|i| i.as_array())
+                .map_or(0, |items| items.len());
+            Value::Array(vec![Value::Null; items])
+        }
+        "window/workDoneProgress/create"
+        | "client/registerCapability"
+        | "client/unregisterCapability"
+        | "window/showMessageRequest"
+        | "workspace/semanticTokens/refresh"
+        | "workspace/inlayHint/refresh"
+        | "workspace/codeLens/refresh"
+        | "workspace/diagnostic/refresh" => Value::Null,
+        // Queries never edit files
+        "workspace/applyEdit" => json!({ "applied": false }),
+        _ => {
+            return json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": METHOD_NOT_FOUND, "message": format!("Unsupported method: {}", method) },
+            })
+        }
+    };
+    json!({ "jsonrpc": "2.0", "id": id, "result": result })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_framing_round_trip() {
+        let first = json!({ "jsonrpc": "2.0", "id": 1, "result": { "name": "é" } });
+        let second = json!({ "jsonrpc": "2.0", "method": "initialized", "params": {} });
+        let mut bytes = encode(&first);
+        bytes.extend(b"Content-Type: application/vscode-jsonrpc; charset=utf-8\r\n".iter());
+        bytes.extend(encode(&second));
+
+        let mut reader = BufReader::new(bytes.as_slice());
+        assert_eq!(read_message(&mut reader).await.unwrap(), Some(first));
+        assert_eq!(read_message(&mut reader).await.unwrap(), Some(second));
+        assert_eq!(read_message(&mut reader).await.unwrap(), None);
+    }
+
+    #[test]
+    fn test_replies_to_server_requests() {
+        let params = json!({ "items": [{ "section": "rust-analyzer" }, { "section": "files" }] });
+        let reply = reply_to_server(json!(3), "workspace/configuration", Some(&params));
+        assert_eq!(reply["result"], json!([null, null]));
+        assert_eq!(reply_to_server(json!(4), "client/registerCapability", None)["result"], Value::Null);
+        assert_eq!(
+            reply_to_server(json!(5), "workspace/unknown", None)["error"]["code"],
+            json!(METHOD_NOT_FOUND)
+        );
+    }
+}
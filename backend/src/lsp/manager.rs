@@ -0,0 +1,174 @@
+//! Running language servers, one per language and project root
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use super::client::LspClient;
+use super::{effective_servers, find_symbol, CodeLocation, CodeSymbol, LspError, LspServerConfig};
+use crate::config::SettingsStore;
+
+/// A running server
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LspServerStatus {
+    pub language: String,
+    pub command: String,
+    pub root: PathBuf,
+    pub running: bool,
+    pub open_documents: usize,
+}
+
+/// Started servers, keyed by language and root
+#[derive(Default)]
+pub struct LspManager {
+    clients: Mutex<HashMap<(String, PathBuf), Arc<LspClient>>>,
+}
+
+lazy_static::lazy_static! {
+    static ref GLOBAL_MANAGER: Arc<LspManager> = Arc::new(LspManager::default());
+}
+
+/// Nearest ancestor of `file` holding one of the server's root markers, or
+/// the file's directory
+pub fn find_root(file: &Path, config: &LspServerConfig) -> PathBuf {
+    let dir = file.parent().unwrap_or(file);
+    dir.ancestors()
+        .find(|ancestor| config.root_markers.iter().any(|marker| ancestor.join(marker).exists()))
+        .unwrap_or(dir)
+        .to_path_buf()
+}
+
+impl LspManager {
+    pub fn global() -> Arc<LspManager> {
+        GLOBAL_MANAGER.clone()
+    }
+
+    /// Client for a file, starting its server if needed
+    pub async fn client_for(&self, file: &Path) -> Result<Arc<LspClient>, LspError> {
+        let settings = SettingsStore::global().get().lsp;
+        if !settings.enabled {
+            return Err(LspError::Disabled);
+        }
+        let config = effective_servers(&settings.servers)
+            .into_iter()
+            .find(|server| server.handles(file))
+            .ok_or_else(|| LspError::Unsupported(file.display().to_string()))?;
+        let root = find_root(file, &config);
+        let key = (config.language.clone(), root.clone());
+
+        // Held while starting so concurrent queries share one server
+        let mut clients = self.clients.lock().await;
+        if let Some(client) = clients.get(&key).filter(|c| c.is_running()) {
+            return Ok(client.clone());
+        }
+        let client = Arc::new(LspClient::start(config, root).await?);
+        clients.insert(key, client.clone());
+        Ok(client)
+    }
+
+    pub async fn statuses(&self) -> Vec<LspServerStatus> {
+        let mut statuses: Vec<_> = self
+            .clients
+            .lock()
+            .await
+            .values()
+            .map(|client| LspServerStatus {
+                language: client.language().to_string(),
+                command: client.command().to_string(),
+                root: client.root().to_path_buf(),
+                running: client.is_running(),
+                open_documents: client.open_documents(),
+            })
+            .collect();
+        statuses.sort_by(|a, b| (&a.language, &a.root).cmp(&(&b.language, &b.root)));
+        statuses
+    }
+
+    /// Stop every server; they start again on the next query
+    pub async fn shutdown_all(&self) -> usize {
+        let clients: Vec<_> = self.clients.lock().await.drain().map(|(_, client)| client).collect();
+        futures::future::join_all(clients.iter().map(|client| client.shutdown())).await;
+        clients.len()
+    }
+
+    /// Where the symbol at a position is defined
+    pub async fn definition(&self, file: &Path, position: &CodePosition) -> Result<Vec<CodeLocation>, LspError> {
+        let (line, column) = position.resolve(file).await?;
+        self.client_for(file).await?.definition(file, line, column).await
+    }
+
+    /// Uses of the symbol at a position
+    pub async fn references(
+        &self,
+        file: &Path,
+        position: &CodePosition,
+        include_declaration: bool,
+    ) -> Result<Vec<CodeLocation>, LspError> {
+        let (line, column) = position.resolve(file).await?;
+        self.client_for(file).await?.references(file, line, column, include_declaration).await
+    }
+
+    /// Symbols declared in a file
+    pub async fn document_symbols(&self, file: &Path) -> Result<Vec<CodeSymbol>, LspError> {
+        self.client_for(file).await?.document_symbols(file).await
+    }
+}
+
+/// A 1-based line, with either a column or the name of a symbol on that line
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CodePosition {
+    pub line: u32,
+    #[serde(default)]
+    pub column: Option<u32>,
+    #[serde(default)]
+    pub symbol: Option<String>,
+}
+
+impl CodePosition {
+    /// Line and column, looking the symbol up when no column is given
+    pub async fn resolve(&self, file: &Path) -> Result<(u32, u32), LspError> {
+        if let Some(column) = self.column {
+            return Ok((self.line, column));
+        }
+        let symbol = self
+            .symbol
+            .as_deref()
+            .filter(|s| !s.trim().is_empty())
+            .ok_or_else(|| LspError::InvalidPosition("give a column or a symbol name".to_string()))?;
+        let text = tokio::fs::read_to_string(file).await?;
+        let line = text
+            .lines()
+            .nth(self.line.saturating_sub(1) as usize)
+            .filter(|_| self.line > 0)
+            .ok_or_else(|| LspError::InvalidPosition(format!("{} has no line {}", file.display(), self.line)))?;
+        let column = find_symbol(line, symbol.trim()).ok_or_else(|| {
+            LspError::InvalidPosition(format!("'{}' is not on line {} of {}", symbol, self.line, file.display()))
+        })?;
+        Ok((self.line, column))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_root_and_symbol_position() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("Cargo.toml"), "[package]\nname = \"demo\"\n").unwrap();
+        std::fs::create_dir_all(dir.path().join("src/util")).unwrap();
+        let file = dir.path().join("src/util/mod.rs");
+        std::fs::write(&file, "use crate::parse;\n\npub fn run() { parse_all(); parse(); }\n").unwrap();
+
+        let rust = effective_servers(&[]).into_iter().find(|s| s.language == "rust").unwrap();
+        assert_eq!(find_root(&file, &rust), dir.path());
+
+        let position = CodePosition { line: 3, column: None, symbol: Some("parse".to_string()) };
+        assert_eq!(position.resolve(&file).await.unwrap(), (3, 29));
+        let missing = CodePosition { line: 2, column: None, symbol: Some("parse".to_string()) };
+        assert!(matches!(missing.resolve(&file).await, Err(LspError::InvalidPosition(_))));
+        assert!(CodePosition { line: 9, column: Some(1), symbol: None }.resolve(&file).await.is_ok());
+    }
+}
@@ -0,0 +1,297 @@
+//! Language server client for code intelligence
+//!
+//! Starts a language server (rust-analyzer, typescript-language-server,
+//! pyright, or any configured in the `lsp` settings section) the first time
+//! a file of its language is queried, one per language and project root, and
+//! keeps it running for later queries. Go to definition, find references and
+//! document symbols are exposed as agent tools and under `/api/v1/code`.
+//!
+//! Positions are 1-based lines and character columns everywhere outside this
+//! module; conversion to the protocol's 0-based UTF-16 offsets happens here.
+
+pub mod client;
+pub mod connection;
+pub mod manager;
+
+pub use client::LspClient;
+pub use manager::{CodePosition, LspManager, LspServerStatus};
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::time::Duration;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum LspError {
+    #[error("Code intelligence is disabled in settings")]
+    Disabled,
+
+    #[error("No language server is configured for {0}")]
+    Unsupported(String),
+
+    #[error("Language server transport error: {0}")]
+    Transport(String),
+
+    #[error("Language server closed the connection")]
+    Closed,
+
+    #[error("Language server request timed out: {0}")]
+    Timeout(String),
+
+    #[error("Language server error {code}: {message}")]
+    Server { code: i64, message: String },
+
+    #[error("Invalid language server response: {0}")]
+    InvalidResponse(String),
+
+    #[error("Invalid position: {0}")]
+    InvalidPosition(String),
+
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// One language server
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LspServerConfig {
+    /// Language identifier sent to the server, e.g. "rust" or "typescript";
+    /// a configured server replaces the built-in one for the same language
+    pub language: String,
+    pub command: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub env: HashMap<String, String>,
+    /// File extensions the server handles, without the dot
+    pub extensions: Vec<String>,
+    /// Files marking the project root; the nearest ancestor holding one is
+    /// the server's workspace
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub root_markers: Vec<String>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Per-request timeout; the first request after start also waits for
+    /// the server to load the project
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_timeout_secs() -> u64 {
+    60
+}
+
+impl LspServerConfig {
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_secs.max(1))
+    }
+
+    pub fn handles(&self, path: &Path) -> bool {
+        path.extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|ext| self.extensions.iter().any(|e| e.eq_ignore_ascii_case(ext)))
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.language.trim().is_empty() {
+            return Err("lsp servers need a language".to_string());
+        }
+        if self.command.trim().is_empty() {
+            return Err(format!("lsp server for '{}' needs a command", self.language));
+        }
+        if self.extensions.iter().all(|e| e.trim().is_empty()) {
+            return Err(format!("lsp server for '{}' needs at least one extension", self.language));
+        }
+        Ok(())
+    }
+}
+
+fn builtin(language: &str, command: &str, args: &[&str], extensions: &[&str], root_markers: &[&str]) -> LspServerConfig {
+    LspServerConfig {
+        language: language.to_string(),
+        command: command.to_string(),
+        args: args.iter().map(|a| a.to_string()).collect(),
+        env: HashMap::new(),
+        extensions: extensions.iter().map(|e| e.to_string()).collect(),
+        root_markers: root_markers.iter().map(|m| m.to_string()).collect(),
+        enabled: true,
+        timeout_secs: default_timeout_secs(),
+    }
+}
+
+/// Servers used when the settings don't replace them
+pub fn builtin_servers() -> Vec<LspServerConfig> {
+    vec![
+        builtin("rust", "rust-analyzer", &[], &["rs"], &["Cargo.toml"]),
+        builtin(
+            "typescript",
+            "typescript-language-server",
+            &["--stdio"],
+            &["ts", "tsx", "js", "jsx", "mjs", "cjs"],
+            &["tsconfig.json", "jsconfig.json", "package.json"],
+        ),
+        builtin(
+            "python",
+            "pyright-langserver",
+            &["--stdio"],
+            &["py", "pyi"],
+            &["pyproject.toml", "setup.py", "setup.cfg", "requirements.txt"],
+        ),
+    ]
+}
+
+/// Built-in servers with the configured ones applied on top
+pub fn effective_servers(configured: &[LspServerConfig]) -> Vec<LspServerConfig> {
+    let mut servers: Vec<LspServerConfig> = builtin_servers()
+        .into_iter()
+        .filter(|builtin| !configured.iter().any(|c| c.language == builtin.language))
+        .collect();
+    servers.extend(configured.iter().cloned());
+    servers.retain(|server| server.enabled);
+    servers
+}
+
+/// Check configured servers: each must be valid and languages unique
+pub fn validate_servers(servers: &[LspServerConfig]) -> Result<(), String> {
+    let mut languages = HashSet::new();
+    for server in servers {
+        server.validate()?;
+        if !languages.insert(server.language.as_str()) {
+            return Err(format!("lsp server for '{}' is configured more than once", server.language));
+        }
+    }
+    Ok(())
+}
+
+/// A place in a file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CodeLocation {
+    pub file: String,
+    pub line: u32,
+    pub column: u32,
+    pub end_line: u32,
+    pub end_column: u32,
+    /// The text of the line, trimmed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preview: Option<String>,
+}
+
+/// A symbol declared in a document
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CodeSymbol {
+    pub name: String,
+    /// "function", "struct", "method", ...
+    pub kind: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    pub line: u32,
+    pub column: u32,
+    pub end_line: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub container: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<CodeSymbol>,
+}
+
+/// Name of a protocol `SymbolKind`
+pub fn symbol_kind_name(kind: u64) -> &'static str {
+    match kind {
+        1 => "file",
+        2 => "module",
+        3 => "namespace",
+        4 => "package",
+        5 => "class",
+        6 => "method",
+        7 => "property",
+        8 => "field",
+        9 => "constructor",
+        10 => "enum",
+        11 => "interface",
+        12 => "function",
+        13 => "variable",
+        14 => "constant",
+        15 => "string",
+        16 => "number",
+        17 => "boolean",
+        18 => "array",
+        19 => "object",
+        20 => "key",
+        21 => "null",
+        22 => "enum_member",
+        23 => "struct",
+        24 => "event",
+        25 => "operator",
+        26 => "type_parameter",
+        _ => "unknown",
+    }
+}
+
+/// 0-based UTF-16 offset of a 1-based character column in `line`
+pub fn utf16_offset(line: &str, column: u32) -> u32 {
+    line.chars()
+        .take(column.saturating_sub(1) as usize)
+        .map(|c| c.len_utf16() as u32)
+        .sum()
+}
+
+/// 1-based character column of a 0-based UTF-16 offset in `line`
+pub fn char_column(line: &str, offset: u32) -> u32 {
+    let mut units = 0;
+    let mut column = 1;
+    for c in line.chars() {
+        if units >= offset {
+            break;
+        }
+        units += c.len_utf16() as u32;
+        column += 1;
+    }
+    column
+}
+
+/// 1-based column of the first whole-word occurrence of `symbol` in `line`
+pub fn find_symbol(line: &str, symbol: &str) -> Option<u32> {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    line.match_indices(symbol)
+        .find(|(start, _)| {
+            let before = line[..*start].chars().next_back();
+            let after = line[start + symbol.len()..].chars().next();
+            !before.is_some_and(is_word) && !after.is_some_and(is_word)
+        })
+        .map(|(start, _)| line[..start].chars().count() as u32 + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_effective_servers() {
+        let mut rust = builtin("rust", "ra-multiplex", &[], &["rs"], &["Cargo.toml"]);
+        let servers = effective_servers(std::slice::from_ref(&rust));
+        assert_eq!(servers.len(), 3);
+        assert_eq!(servers.iter().find(|s| s.language == "rust").unwrap().command, "ra-multiplex");
+        assert!(servers.iter().any(|s| s.handles(Path::new("src/App.TSX"))));
+
+        rust.enabled = false;
+        assert!(!effective_servers(&[rust.clone()]).iter().any(|s| s.language == "rust"));
+        assert!(validate_servers(&[rust.clone(), rust]).is_err());
+        assert!(validate_servers(&[builtin("go", "gopls", &[], &[], &[])]).is_err());
+    }
+
+    #[test]
+    fn test_position_conversion() {
+        let line = "let é = \"😀\"; value";
+        let column = find_symbol(line, "value").unwrap();
+        assert_eq!(column, 14);
+        // The emoji is two UTF-16 units
+        assert_eq!(utf16_offset(line, column), 14);
+        assert_eq!(char_column(line, 14), column);
+        assert_eq!(find_symbol("values value", "value"), Some(8));
+        assert_eq!(find_symbol("values", "value"), None);
+    }
+}
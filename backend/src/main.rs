@@ -12,21 +12,58 @@ use tracing::info;
 
 mod ai;
 mod api;
+mod attachments;
 mod cli_agent;
 mod cli_bridge;
 mod cli_engine;
 mod db;
+mod disk_analyzer;
 mod indexer;
 mod search;
 mod search_engine;
 mod kiro_bridge;
+mod metrics;
+mod events;
 mod config;
+mod auth;
+mod context;
+mod archives;
+mod audio;
+mod change_timeline;
+mod code_index;
+mod conversation_search;
+mod file_diff;
+mod filename_index;
+mod file_history;
+mod file_follow;
+mod file_preview;
+mod file_range;
+mod file_transfer;
+mod file_tree;
+mod ignore_rules;
+mod json_store;
+mod lsp;
+mod mcp;
+mod notifications;
+mod plugins;
+mod recycle_bin;
+mod scaffold;
+mod storage_quotas;
+mod vault;
+mod agent_hooks;
+mod feeds;
+mod secrets;
+mod mounts;
+mod processes;
 mod error;
 mod terminal;
 mod content_extraction;
 mod workflows;
+mod run_stats;
+mod local_api;
+mod api_key_storage;
 
-use config::AppConfig;
+use config::{AppConfig, SettingsStore};
 use error::AppError;
 use db::Database;
 use ai::AIManager;
@@ -38,10 +75,11 @@ use content_extraction::ContentExtractionSystem;
 
 #[derive(Clone)]
 pub struct AppState {
-    #[allow(dead_code)]
     config: Arc<AppConfig>,
     db: Database,
     ai_manager: AIManager,
+    provider_health: Arc<ai::ProviderHealth>,
+    ai_response_cache: Arc<ai::ResponseCache>,
     indexer: FileIndexer,
     search_engine: SearchEngine,
     file_search_manager: SearchManager,
@@ -103,6 +141,30 @@ async fn start_indexing(State(state): State<AppState>) -> Result<StatusCode, App
     Ok(StatusCode::ACCEPTED)
 }
 
+/// Prometheus scrape endpoint; gauges are sampled at scrape time
+async fn metrics_handler(State(state): State<AppState>) -> impl axum::response::IntoResponse {
+    let terminal = state.terminal_manager.get_stats().await;
+    metrics::set_gauge("skhoot_terminal_sessions", &[("state", "active")], terminal.active as f64);
+    metrics::set_gauge("skhoot_terminal_sessions", &[("state", "stale")], terminal.stale as f64);
+    metrics::set_gauge("skhoot_terminal_ptys_open", &[], terminal.total as f64);
+    metrics::set_gauge("skhoot_terminal_sessions_hibernated", &[], terminal.hibernated as f64);
+
+    let running = state
+        .file_search_manager
+        .get_active_searches()
+        .await
+        .iter()
+        .filter(|handle| matches!(handle.status, search_engine::SearchStatus::Running))
+        .count();
+    metrics::set_gauge("skhoot_active_searches", &[], running as f64);
+    metrics::update_cache_hit_ratios(&["web_memory", "web_disk", "ai_response"]);
+
+    (
+        [(axum::http::header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        metrics::global().render(),
+    )
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     tracing_subscriber::fmt()
@@ -112,17 +174,38 @@ async fn main() -> anyhow::Result<()> {
     info!("Starting Skhoot Backend v{}", env!("CARGO_PKG_VERSION"));
 
     let config = Arc::new(AppConfig::new()?);
+    let settings = SettingsStore::global().get();
+    info!("Loaded settings from {}", SettingsStore::global().path().display());
     let db = Database::new(&config.database_url).await?;
     let ai_manager = AIManager::new();
     let indexer = FileIndexer::new(db.clone()).await?;
     let search_engine = SearchEngine::new(db.clone(), ai_manager.clone()).await?;
+
+    // Schedule periodic disk scans for usage trend history
+    disk_analyzer::DiskScanScheduler::new(
+        db.clone(),
+        disk_analyzer::ScheduledScanConfig {
+            interval_secs: config.disk_scan_interval_secs,
+            paths: dirs::home_dir().into_iter().collect(),
+            ..Default::default()
+        },
+    )
+    .spawn();
     
     // Initialize the new file search manager
     let working_dir = std::env::current_dir()?;
-    let file_search_manager = SearchManagerFactory::create_ai_optimized(working_dir);
+    let filename_index = Arc::new(filename_index::FilenameIndex::new(Some(db.clone())));
+    let file_search_manager =
+        SearchManagerFactory::create_ai_optimized(working_dir).with_filename_index(filename_index.clone());
     
     // Initialize content extraction system
-    let content_extraction_system = Arc::new(tokio::sync::Mutex::new(ContentExtractionSystem::new()));
+    let mut content_extraction_system =
+        ContentExtractionSystem::new().with_disk_cache(content_extraction::DiskCache::new(db.clone()));
+    content_extraction_system.set_cache_limits(
+        settings.cache.web_max_mb * 1024 * 1024,
+        std::time::Duration::from_secs(settings.cache.web_ttl_secs),
+    );
+    let content_extraction_system = Arc::new(tokio::sync::Mutex::new(content_extraction_system));
     
     // Initialize terminal manager
     let terminal_manager = TerminalManager::default();
@@ -131,6 +214,55 @@ async fn main() -> anyhow::Result<()> {
     let workflow_storage = Arc::new(workflows::WorkflowStorage::new());
     workflow_storage.init_defaults().await;
     let workflow_engine = Arc::new(workflows::WorkflowEngine::new(workflow_storage.clone()));
+
+    // Delete workflow run artifacts their retention policy no longer covers
+    workflows::artifacts::spawn_pruner(workflow_engine.clone());
+
+    // Show finished agents and workflows as desktop notifications
+    notifications::Notifier::new(None)
+        .with_workflow_storage(workflow_storage.clone())
+        .spawn();
+
+    // Start agents bound to app events, and watch the workspaces their
+    // file-saved hooks name
+    api::agents::spawn_hook_dispatcher();
+    agent_hooks::spawn_file_watcher(agent_hooks::HookStore::global());
+
+    // Index filenames under home and the search workspaces so fuzzy search
+    // doesn't walk the disk
+    filename_index::spawn_maintainer(filename_index);
+
+    // Record changes under the search workspaces for the recent-changes timeline
+    change_timeline::spawn_watcher(change_timeline::ChangeTimeline::global());
+
+    // Re-read files pinned to conversations when they change
+    cli_agent::context_set::spawn_context_watcher(cli_agent::ContextSetStore::global());
+
+    // Poll subscribed RSS/Atom feeds; new entries can start workflows
+    feeds::spawn_feed_poller(feeds::FeedStore::global(), workflow_engine.clone());
+
+    // Measure storage quotas periodically; crossing one alerts and can start
+    // a workflow
+    storage_quotas::spawn_quota_monitor(storage_quotas::QuotaStore::global(), workflow_engine.clone());
+
+    // Write out when local API clients were last used
+    local_api::clients::spawn_usage_flusher(local_api::ClientStore::global());
+
+    // Connect to the configured MCP servers; their tools become available to
+    // agents created afterwards
+    {
+        let servers = settings.mcp.servers.clone();
+        tokio::spawn(async move { mcp::McpManager::global().connect_all(servers).await });
+    }
+
+    // Pick up plugins as they are installed, edited or removed
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(2));
+        loop {
+            interval.tick().await;
+            plugins::PluginRegistry::global().reload_if_changed();
+        }
+    });
     
     // Spawn background task to cleanup stale sessions every 5 minutes
     {
@@ -145,9 +277,11 @@ async fn main() -> anyhow::Result<()> {
     }
 
     let state = AppState {
-        config,
+        config: config.clone(),
         db,
         ai_manager,
+        provider_health: Arc::new(ai::ProviderHealth::new(settings.providers.health_config())),
+        ai_response_cache: Arc::new(ai::ResponseCache::new(settings.cache.ai_response_config())),
         indexer,
         search_engine,
         file_search_manager,
@@ -157,23 +291,78 @@ async fn main() -> anyhow::Result<()> {
         workflow_storage,
     };
 
+    // Apply edits to skhoot.toml while running
+    {
+        let state = state.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(2));
+            loop {
+                interval.tick().await;
+                let changed = SettingsStore::global().reload_if_changed();
+                if !changed.is_empty() {
+                    let settings = SettingsStore::global().get();
+                    api::config::settings_changed(&state, &settings, changed).await;
+                }
+            }
+        });
+    }
+
+    let auth = auth::AuthToken::from_env();
+    if !auth.is_enabled() {
+        tracing::warn!("{} is not set; API authentication is disabled", auth::AUTH_TOKEN_ENV);
+    }
+
     let app = Router::new()
-        .route("/health", get(health_check))
+        .route("/metrics", get(metrics_handler))
         .route("/api/v1/ping", get(|| async { "pong" }))
         .route("/api/v1/ai/detect-provider", post(detect_provider))
         .route("/api/v1/search", get(search_files))
         .route("/api/v1/index/start", post(start_indexing))
         .nest("/api/v1", api::search::search_routes())
         .nest("/api/v1", api::disk::disk_routes())
+        .nest("/api/v1", api::quotas::quota_routes())
         .nest("/api/v1", api::agents::agent_routes())
+        .nest("/api/v1", api::goals::goal_routes())
         .nest("/api/v1", api::web_search::web_search_routes())
         .nest("/api/v1", api::workflows::workflow_routes())
+        .nest("/api/v1", api::feeds::feed_routes())
+        .nest("/api/v1", api::checkpoints::checkpoint_routes())
+        .nest("/api/v1", api::refactor::refactor_routes())
+        .nest("/api/v1", api::prompt_templates::prompt_template_routes())
+        .nest("/api/v1", api::attachments::attachment_routes())
+        .nest("/api/v1", api::ai_health::ai_health_routes())
+        .nest("/api/v1", api::ai_cache::ai_cache_routes())
+        .nest("/api/v1", api::events::event_routes())
+        .nest("/api/v1", api::config::config_routes())
+        .nest("/api/v1", api::archives::archive_routes())
+        .nest("/api/v1", api::audio::audio_routes())
+        .nest("/api/v1", api::conversations::conversation_routes())
+        .nest("/api/v1", api::mcp::mcp_routes())
+        .nest("/api/v1", api::code::code_routes())
+        .nest("/api/v1", api::plugins::plugin_routes())
+        .nest("/api/v1", api::tool_queue::tool_queue_routes())
+        .nest("/api/v1", api::admin::admin_routes())
+        .nest("/api/v1", api::vault::vault_routes())
+        .nest("/api/v1", api::stats::stats_routes())
+        .nest("/api/v1", api::system::system_routes())
+        .nest("/api/v1", api::local_api::local_api_routes())
         .route("/api/v1/recent/system", get(api::recent::get_system_recent_files))
+        .route("/api/v1/recent/operations", get(api::recent::get_recent_operations))
+        .route("/api/v1/recent/changes", get(api::recent::get_changed_files))
         .nest("/api/v1/terminal", terminal::terminal_routes().with_state(terminal_manager))
+        .route_layer(axum::middleware::from_fn_with_state(auth, auth::require_token))
+        .route("/health", get(health_check))
+        .nest("/api/v1", api::workflows::webhook_routes())
+        .merge(api::local_api::openai_routes())
         .with_state(state)
+        .layer(axum::middleware::from_fn(metrics::track_http))
         .layer(
             CorsLayer::new()
-                .allow_origin(tower_http::cors::Any)
+                .allow_origin(tower_http::cors::AllowOrigin::predicate(|origin, _| {
+                    origin
+                        .to_str()
+                        .is_ok_and(|origin| SettingsStore::global().get().security.allows_origin(origin))
+                }))
                 .allow_methods([
                     axum::http::Method::GET,
                     axum::http::Method::POST,
@@ -181,11 +370,14 @@ async fn main() -> anyhow::Result<()> {
                     axum::http::Method::DELETE,
                     axum::http::Method::OPTIONS
                 ])
-                .allow_headers([axum::http::header::CONTENT_TYPE]),
+                .allow_headers([
+                    axum::http::header::CONTENT_TYPE,
+                    axum::http::header::AUTHORIZATION,
+                    axum::http::HeaderName::from_static(context::CONTEXT_HEADER),
+                ]),
         );
 
-    let port = 3001;
-    let addr = format!("127.0.0.1:{}", port);
+    let addr = format!("{}:{}", config.host, config.port);
     info!("Backend server listening on {}", addr);
 
     let listener = tokio::net::TcpListener::bind(&addr).await?;
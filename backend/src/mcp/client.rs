@@ -0,0 +1,199 @@
+//! Client for one MCP server
+
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+
+use super::connection::{connect_sse, connect_stdio, Connection};
+use super::protocol::{
+    CallToolResult, InitializeResult, McpResource, McpTool, ReadResourceResult, ResourceContents, ResourcesPage,
+    ServerInfo, ToolsPage, PROTOCOL_VERSION,
+};
+use super::{McpError, McpServerConfig, McpTransport};
+
+/// An initialized session with a server
+pub struct McpClient {
+    connection: Connection,
+    server_info: Option<ServerInfo>,
+    capabilities: Value,
+}
+
+impl McpClient {
+    /// Connect using the configured transport and run the handshake
+    pub async fn connect(config: &McpServerConfig) -> Result<Self, McpError> {
+        let connection = match config.transport {
+            McpTransport::Stdio => connect_stdio(config).await?,
+            McpTransport::Sse => connect_sse(config).await?,
+        };
+        Self::initialize(connection).await
+    }
+
+    pub(crate) async fn initialize(connection: Connection) -> Result<Self, McpError> {
+        let result: InitializeResult = parse(
+            connection
+                .request(
+                    "initialize",
+                    json!({
+                        "protocolVersion": PROTOCOL_VERSION,
+                        "capabilities": {},
+                        "clientInfo": { "name": "skhoot", "version": env!("CARGO_PKG_VERSION") },
+                    }),
+                )
+                .await?,
+        )?;
+        if result.protocol_version != PROTOCOL_VERSION {
+            tracing::debug!(
+                "MCP server negotiated protocol {} instead of {}",
+                result.protocol_version,
+                PROTOCOL_VERSION
+            );
+        }
+        connection.notify("notifications/initialized", json!({}))?;
+
+        Ok(Self {
+            connection,
+            server_info: result.server_info,
+            capabilities: result.capabilities,
+        })
+    }
+
+    pub fn server_info(&self) -> Option<&ServerInfo> {
+        self.server_info.as_ref()
+    }
+
+    fn supports(&self, capability: &str) -> bool {
+        self.capabilities.get(capability).is_some()
+    }
+
+    /// All tools the server offers
+    pub async fn list_tools(&self) -> Result<Vec<McpTool>, McpError> {
+        if !self.supports("tools") {
+            return Ok(Vec::new());
+        }
+        let mut tools = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let page: ToolsPage = parse(self.connection.request("tools/list", cursor_params(&cursor)).await?)?;
+            tools.extend(page.tools);
+            match page.next_cursor {
+                Some(next) if !next.is_empty() => cursor = Some(next),
+                _ => return Ok(tools),
+            }
+        }
+    }
+
+    /// All resources the server offers
+    pub async fn list_resources(&self) -> Result<Vec<McpResource>, McpError> {
+        if !self.supports("resources") {
+            return Ok(Vec::new());
+        }
+        let mut resources = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let page: ResourcesPage =
+                parse(self.connection.request("resources/list", cursor_params(&cursor)).await?)?;
+            resources.extend(page.resources);
+            match page.next_cursor {
+                Some(next) if !next.is_empty() => cursor = Some(next),
+                _ => return Ok(resources),
+            }
+        }
+    }
+
+    /// Call a tool by its name on the server
+    pub async fn call_tool(&self, name: &str, arguments: Value) -> Result<CallToolResult, McpError> {
+        let arguments = if arguments.is_null() { json!({}) } else { arguments };
+        parse(
+            self.connection
+                .request("tools/call", json!({ "name": name, "arguments": arguments }))
+                .await?,
+        )
+    }
+
+    /// Read a resource
+    pub async fn read_resource(&self, uri: &str) -> Result<Vec<ResourceContents>, McpError> {
+        let result: ReadResourceResult = parse(self.connection.request("resources/read", json!({ "uri": uri })).await?)?;
+        Ok(result.contents)
+    }
+}
+
+fn cursor_params(cursor: &Option<String>) -> Value {
+    match cursor {
+        Some(cursor) => json!({ "cursor": cursor }),
+        None => json!({}),
+    }
+}
+
+fn parse<T: DeserializeOwned>(value: Value) -> Result<T, McpError> {
+    serde_json::from_value(value).map_err(|e| McpError::InvalidResponse(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcp::protocol::ToolContent;
+    use std::time::Duration;
+
+    /// A server with one `echo` tool, two pages long
+    fn fake_server() -> Connection {
+        let (connection, mut outgoing, inbox) = Connection::channel("fake", Duration::from_secs(5));
+        tokio::spawn(async move {
+            while let Some(message) = outgoing.recv().await {
+                let Some(id) = message.get("id").cloned() else { continue };
+                let params = &message["params"];
+                let result = match message["method"].as_str().unwrap_or_default() {
+                    "initialize" => json!({
+                        "protocolVersion": PROTOCOL_VERSION,
+                        "capabilities": { "tools": {} },
+                        "serverInfo": { "name": "fake", "version": "1.0" },
+                    }),
+                    "tools/list" if params.get("cursor").is_none() => json!({
+                        "tools": [{ "name": "echo", "inputSchema": { "type": "object" } }],
+                        "nextCursor": "2",
+                    }),
+                    "tools/list" => json!({
+                        "tools": [{ "name": "fail", "description": "Always fails" }],
+                    }),
+                    "tools/call" if params["name"] == "echo" => json!({
+                        "content": [{ "type": "text", "text": params["arguments"]["text"] }],
+                    }),
+                    "tools/call" => json!({
+                        "content": [{ "type": "text", "text": "boom" }],
+                        "isError": true,
+                    }),
+                    _ => {
+                        inbox.deliver(json!({
+                            "jsonrpc": "2.0",
+                            "id": id,
+                            "error": { "code": -32601, "message": "not found" },
+                        }));
+                        continue;
+                    }
+                };
+                inbox.deliver(json!({ "jsonrpc": "2.0", "id": id, "result": result }));
+            }
+        });
+        connection
+    }
+
+    #[tokio::test]
+    async fn test_client_against_fake_server() {
+        let client = McpClient::initialize(fake_server()).await.unwrap();
+        assert_eq!(client.server_info().unwrap().name, "fake");
+
+        let tools = client.list_tools().await.unwrap();
+        let names: Vec<_> = tools.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, vec!["echo", "fail"]);
+        // The server has no resources capability, so nothing is requested
+        assert!(client.list_resources().await.unwrap().is_empty());
+
+        let result = client.call_tool("echo", json!({ "text": "hi" })).await.unwrap();
+        assert_eq!(result.content, vec![ToolContent::Text { text: "hi".to_string() }]);
+        assert!(!result.is_error);
+        assert!(client.call_tool("fail", Value::Null).await.unwrap().is_error);
+
+        assert!(matches!(
+            client.read_resource("file:///x").await,
+            Err(McpError::Server { code: -32601, .. })
+        ));
+    }
+}
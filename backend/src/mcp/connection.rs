@@ -0,0 +1,364 @@
+//! JSON-RPC connections to MCP servers
+//!
+//! A [`Connection`] sends requests as JSON values on a channel and matches
+//! responses by ID. Transports only move messages: stdio writes one JSON
+//! message per line to the server process, SSE posts messages to the
+//! endpoint announced on the event stream and reads replies from the stream.
+//! Both hand incoming messages to an [`Inbox`].
+
+use futures::StreamExt;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+
+use super::protocol::METHOD_NOT_FOUND;
+use super::{McpError, McpServerConfig};
+
+type Pending = Arc<Mutex<HashMap<u64, oneshot::Sender<Result<Value, McpError>>>>>;
+
+/// Request/response channel to one server
+pub(crate) struct Connection {
+    outgoing: mpsc::UnboundedSender<Value>,
+    pending: Pending,
+    next_id: AtomicU64,
+    timeout: Duration,
+    tasks: Vec<JoinHandle<()>>,
+    /// Server process for stdio servers; killed when the connection drops
+    _child: Option<tokio::process::Child>,
+}
+
+/// Receives messages from a server
+#[derive(Clone)]
+pub(crate) struct Inbox {
+    server: String,
+    pending: Pending,
+    outgoing: mpsc::UnboundedSender<Value>,
+}
+
+impl Connection {
+    /// A connection whose outgoing messages arrive on the returned receiver;
+    /// the transport feeds incoming messages to the returned inbox
+    pub(crate) fn channel(server: &str, timeout: Duration) -> (Self, mpsc::UnboundedReceiver<Value>, Inbox) {
+        let (outgoing, receiver) = mpsc::unbounded_channel();
+        let pending: Pending = Arc::default();
+        let inbox = Inbox {
+            server: server.to_string(),
+            pending: pending.clone(),
+            outgoing: outgoing.clone(),
+        };
+        let connection = Self {
+            outgoing,
+            pending,
+            next_id: AtomicU64::new(1),
+            timeout,
+            tasks: Vec::new(),
+            _child: None,
+        };
+        (connection, receiver, inbox)
+    }
+
+    /// Send a request and wait for its result
+    pub(crate) async fn request(&self, method: &str, params: Value) -> Result<Value, McpError> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (tx, rx) = oneshot::channel();
+        self.pending.lock().unwrap().insert(id, tx);
+
+        let message = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+        if self.outgoing.send(message).is_err() {
+            self.pending.lock().unwrap().remove(&id);
+            return Err(McpError::Closed);
+        }
+
+        match tokio::time::timeout(self.timeout, rx).await {
+            Ok(Ok(result)) => result,
+            Ok(Err(_)) => Err(McpError::Closed),
+            Err(_) => {
+                self.pending.lock().unwrap().remove(&id);
+                Err(McpError::Timeout(method.to_string()))
+            }
+        }
+    }
+
+    /// Send a notification; there is no reply
+    pub(crate) fn notify(&self, method: &str, params: Value) -> Result<(), McpError> {
+        self.outgoing
+            .send(json!({ "jsonrpc": "2.0", "method": method, "params": params }))
+            .map_err(|_| McpError::Closed)
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
+
+impl Inbox {
+    /// Route a message from the server
+    pub(crate) fn deliver(&self, message: Value) {
+        let id = message.get("id").cloned();
+        let method = message.get("method").and_then(|m| m.as_str());
+
+        match (id, method) {
+            // Response to one of our requests
+            (Some(id), None) => {
+                let Some(sender) = id.as_u64().and_then(|id| self.pending.lock().unwrap().remove(&id)) else {
+                    tracing::debug!("MCP server {} answered unknown request {}", self.server, id);
+                    return;
+                };
+                let result = match message.get("error") {
+                    Some(error) => Err(McpError::Server {
+                        code: error.get("code").and_then(|c| c.as_i64()).unwrap_or_default(),
+                        message: error
+                            .get("message")
+                            .and_then(|m| m.as_str())
+                            .unwrap_or("unknown error")
+                            .to_string(),
+                    }),
+                    None => Ok(message.get("result").cloned().unwrap_or(Value::Null)),
+                };
+                let _ = sender.send(result);
+            }
+            // Request from the server; only pings are supported
+            (Some(id), Some(method)) => {
+                let reply = if method == "ping" {
+                    json!({ "jsonrpc": "2.0", "id": id, "result": {} })
+                } else {
+                    json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "error": { "code": METHOD_NOT_FOUND, "message": format!("Unsupported method: {}", method) },
+                    })
+                };
+                let _ = self.outgoing.send(reply);
+            }
+            (None, Some(method)) => {
+                tracing::debug!("MCP server {} sent notification {}", self.server, method);
+            }
+            (None, None) => {
+                tracing::debug!("MCP server {} sent an invalid message", self.server);
+            }
+        }
+    }
+
+    /// Fail every request still waiting for an answer
+    pub(crate) fn close(&self, reason: &str) {
+        tracing::warn!("MCP server {} disconnected: {}", self.server, reason);
+        for (_, sender) in self.pending.lock().unwrap().drain() {
+            let _ = sender.send(Err(McpError::Closed));
+        }
+    }
+}
+
+/// Start a stdio server and connect to it
+pub(crate) async fn connect_stdio(config: &McpServerConfig) -> Result<Connection, McpError> {
+    let command = config
+        .command
+        .as_deref()
+        .ok_or_else(|| McpError::Config(format!("Server {} has no command", config.name)))?;
+
+    let mut child = tokio::process::Command::new(command)
+        .args(&config.args)
+        .envs(&config.env)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| McpError::Transport(format!("failed to start {}: {}", command, e)))?;
+
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let stdout = child.stdout.take().expect("stdout is piped");
+    let stderr = child.stderr.take().expect("stderr is piped");
+
+    let (mut connection, mut outgoing, inbox) = Connection::channel(&config.name, config.timeout());
+
+    let writer = tokio::spawn(async move {
+        while let Some(message) = outgoing.recv().await {
+            let mut line = message.to_string();
+            line.push('\n');
+            if stdin.write_all(line.as_bytes()).await.is_err() || stdin.flush().await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let reader = tokio::spawn(async move {
+        let mut lines = BufReader::new(stdout).lines();
+        loop {
+            match lines.next_line().await {
+                Ok(Some(line)) if line.trim().is_empty() => {}
+                Ok(Some(line)) => match serde_json::from_str(&line) {
+                    Ok(message) => inbox.deliver(message),
+                    Err(e) => tracing::debug!("Ignoring non-JSON output from MCP server: {}", e),
+                },
+                Ok(None) => break inbox.close("process exited"),
+                Err(e) => break inbox.close(&e.to_string()),
+            }
+        }
+    });
+
+    let name = config.name.clone();
+    let logger = tokio::spawn(async move {
+        let mut lines = BufReader::new(stderr).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            tracing::debug!("[mcp:{}] {}", name, line);
+        }
+    });
+
+    connection.tasks = vec![writer, reader, logger];
+    connection._child = Some(child);
+    Ok(connection)
+}
+
+/// Connect to a server over HTTP with server-sent events
+pub(crate) async fn connect_sse(config: &McpServerConfig) -> Result<Connection, McpError> {
+    let url = config
+        .url
+        .as_deref()
+        .ok_or_else(|| McpError::Config(format!("Server {} has no url", config.name)))?;
+    let base = url::Url::parse(url).map_err(|e| McpError::Config(format!("Invalid url {}: {}", url, e)))?;
+
+    let client = reqwest::Client::new();
+    let response = client
+        .get(base.clone())
+        .header(reqwest::header::ACCEPT, "text/event-stream")
+        .send()
+        .await
+        .map_err(|e| McpError::Transport(e.to_string()))?;
+    if !response.status().is_success() {
+        return Err(McpError::Transport(format!("{} returned {}", url, response.status())));
+    }
+
+    let (mut connection, mut outgoing, inbox) = Connection::channel(&config.name, config.timeout());
+    let (endpoint_tx, endpoint_rx) = oneshot::channel::<String>();
+
+    let reader = tokio::spawn(async move {
+        let mut endpoint_tx = Some(endpoint_tx);
+        let mut parser = SseParser::default();
+        let mut stream = response.bytes_stream();
+        while let Some(chunk) = stream.next().await {
+            let chunk = match chunk {
+                Ok(chunk) => chunk,
+                Err(e) => return inbox.close(&e.to_string()),
+            };
+            for (event, data) in parser.push(&String::from_utf8_lossy(&chunk)) {
+                match event.as_str() {
+                    "endpoint" => {
+                        if let Some(tx) = endpoint_tx.take() {
+                            let _ = tx.send(data);
+                        }
+                    }
+                    "message" => match serde_json::from_str(&data) {
+                        Ok(message) => inbox.deliver(message),
+                        Err(e) => tracing::debug!("Ignoring invalid MCP event: {}", e),
+                    },
+                    _ => {}
+                }
+            }
+        }
+        inbox.close("event stream ended");
+    });
+
+    let endpoint = match tokio::time::timeout(config.timeout(), endpoint_rx).await {
+        Ok(Ok(endpoint)) => base
+            .join(endpoint.trim())
+            .map_err(|e| McpError::Transport(format!("invalid endpoint {}: {}", endpoint, e)))?,
+        _ => {
+            reader.abort();
+            return Err(McpError::Transport(format!("{} announced no message endpoint", url)));
+        }
+    };
+
+    let writer = tokio::spawn(async move {
+        while let Some(message) = outgoing.recv().await {
+            let result = client.post(endpoint.clone()).json(&message).send().await;
+            match result {
+                Ok(response) if response.status().is_success() => {}
+                Ok(response) => tracing::warn!("MCP endpoint {} returned {}", endpoint, response.status()),
+                Err(e) => tracing::warn!("Failed to post to MCP endpoint {}: {}", endpoint, e),
+            }
+        }
+    });
+
+    connection.tasks = vec![reader, writer];
+    Ok(connection)
+}
+
+/// Splits a server-sent event stream into (event, data) pairs
+#[derive(Default)]
+pub(crate) struct SseParser {
+    buffer: String,
+}
+
+impl SseParser {
+    pub(crate) fn push(&mut self, text: &str) -> Vec<(String, String)> {
+        self.buffer.push_str(&text.replace("\r\n", "\n"));
+        let mut events = Vec::new();
+        while let Some(end) = self.buffer.find("\n\n") {
+            let block: String = self.buffer.drain(..end + 2).collect();
+            let mut event = "message".to_string();
+            let mut data = Vec::new();
+            for line in block.lines() {
+                if let Some(value) = line.strip_prefix("event:") {
+                    event = value.trim().to_string();
+                } else if let Some(value) = line.strip_prefix("data:") {
+                    data.push(value.strip_prefix(' ').unwrap_or(value));
+                }
+            }
+            if !data.is_empty() {
+                events.push((event, data.join("\n")));
+            }
+        }
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sse_parser() {
+        let mut parser = SseParser::default();
+        assert!(parser.push("event: endpoint\r\ndata: /messages?session=1").is_empty());
+        let events = parser.push("\r\n\r\n: keep-alive\n\ndata: {\"a\":\ndata: 1}\n\n");
+        assert_eq!(
+            events,
+            vec![
+                ("endpoint".to_string(), "/messages?session=1".to_string()),
+                ("message".to_string(), "{\"a\":\n1}".to_string()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_requests_are_matched_by_id() {
+        let (connection, mut outgoing, inbox) = Connection::channel("test", Duration::from_secs(5));
+        tokio::spawn(async move {
+            while let Some(message) = outgoing.recv().await {
+                let Some(id) = message.get("id").cloned() else { continue };
+                if message["method"] == "fail" {
+                    inbox.deliver(json!({ "jsonrpc": "2.0", "id": id, "error": { "code": -1, "message": "nope" } }));
+                } else {
+                    inbox.deliver(json!({ "jsonrpc": "2.0", "id": id, "result": message["params"] }));
+                }
+            }
+        });
+
+        let result = connection.request("echo", json!({ "x": 1 })).await.unwrap();
+        assert_eq!(result, json!({ "x": 1 }));
+        assert!(matches!(
+            connection.request("fail", json!({})).await,
+            Err(McpError::Server { code: -1, .. })
+        ));
+    }
+}
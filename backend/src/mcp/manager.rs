@@ -0,0 +1,324 @@
+//! Connected MCP servers and their tools
+
+use futures::future::join_all;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use super::client::McpClient;
+use super::protocol::{CallToolResult, McpResource, McpTool, ResourceContents, ServerInfo};
+use super::{qualified_name, McpError, McpServerConfig, McpTransport};
+use crate::cli_agent::tools::{ParameterProperty, ToolDefinition, ToolParameters};
+
+/// A server tool as registered for the agent
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpToolInfo {
+    /// Name the agent calls it by
+    pub name: String,
+    pub server: String,
+    pub tool: McpTool,
+}
+
+/// Connection state of a configured server
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerStatus {
+    pub name: String,
+    pub transport: McpTransport,
+    pub enabled: bool,
+    pub connected: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub server_info: Option<ServerInfo>,
+    pub tools: usize,
+    pub resources: Vec<McpResource>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+struct ServerEntry {
+    config: McpServerConfig,
+    client: Option<Arc<McpClient>>,
+    tools: Vec<McpTool>,
+    resources: Vec<McpResource>,
+    error: Option<String>,
+}
+
+/// All configured servers
+#[derive(Default)]
+pub struct McpManager {
+    servers: RwLock<HashMap<String, ServerEntry>>,
+    /// Tools of connected servers, readable without awaiting so agents can
+    /// build their registries synchronously
+    tools: std::sync::RwLock<Vec<McpToolInfo>>,
+}
+
+lazy_static::lazy_static! {
+    static ref GLOBAL_MANAGER: Arc<McpManager> = Arc::new(McpManager::default());
+}
+
+impl McpManager {
+    pub fn global() -> Arc<McpManager> {
+        GLOBAL_MANAGER.clone()
+    }
+
+    /// Replace all connections with the given servers. Servers that fail to
+    /// connect are kept with their error so the settings UI can show it.
+    pub async fn connect_all(&self, configs: Vec<McpServerConfig>) {
+        let mut servers = self.servers.write().await;
+        servers.clear();
+
+        let entries = join_all(configs.into_iter().map(connect_server)).await;
+        let mut tools = Vec::new();
+        for entry in entries {
+            for tool in &entry.tools {
+                let name = qualified_name(&entry.config.name, &tool.name);
+                if tools.iter().any(|t: &McpToolInfo| t.name == name) {
+                    tracing::warn!("Skipping MCP tool {}: name clashes with another tool", name);
+                    continue;
+                }
+                tools.push(McpToolInfo {
+                    name,
+                    server: entry.config.name.clone(),
+                    tool: tool.clone(),
+                });
+            }
+            servers.insert(entry.config.name.clone(), entry);
+        }
+
+        tracing::info!(
+            "MCP: {} of {} servers connected, {} tools",
+            servers.values().filter(|s| s.client.is_some()).count(),
+            servers.len(),
+            tools.len()
+        );
+        *self.tools.write().unwrap() = tools;
+    }
+
+    /// Tools of all connected servers
+    pub fn tools(&self) -> Vec<McpToolInfo> {
+        self.tools.read().unwrap().clone()
+    }
+
+    /// Definitions to add to an agent's tool registry
+    pub fn tool_definitions(&self) -> Vec<ToolDefinition> {
+        self.tools.read().unwrap().iter().map(tool_definition).collect()
+    }
+
+    pub async fn statuses(&self) -> Vec<ServerStatus> {
+        let mut statuses: Vec<_> = self
+            .servers
+            .read()
+            .await
+            .values()
+            .map(|entry| ServerStatus {
+                name: entry.config.name.clone(),
+                transport: entry.config.transport,
+                enabled: entry.config.enabled,
+                connected: entry.client.is_some(),
+                server_info: entry.client.as_ref().and_then(|c| c.server_info().cloned()),
+                tools: entry.tools.len(),
+                resources: entry.resources.clone(),
+                error: entry.error.clone(),
+            })
+            .collect();
+        statuses.sort_by(|a, b| a.name.cmp(&b.name));
+        statuses
+    }
+
+    async fn client(&self, server: &str) -> Result<Arc<McpClient>, McpError> {
+        self.servers
+            .read()
+            .await
+            .get(server)
+            .and_then(|entry| entry.client.clone())
+            .ok_or_else(|| McpError::NotConnected(server.to_string()))
+    }
+
+    /// Call a tool by the name it is registered under
+    pub async fn call_tool(&self, name: &str, arguments: Value) -> Result<CallToolResult, McpError> {
+        let (server, tool) = self
+            .tools
+            .read()
+            .unwrap()
+            .iter()
+            .find(|t| t.name == name)
+            .map(|t| (t.server.clone(), t.tool.name.clone()))
+            .ok_or_else(|| McpError::UnknownTool(name.to_string()))?;
+        self.client(&server).await?.call_tool(&tool, arguments).await
+    }
+
+    /// Read a resource from a server
+    pub async fn read_resource(&self, server: &str, uri: &str) -> Result<Vec<ResourceContents>, McpError> {
+        self.client(server).await?.read_resource(uri).await
+    }
+}
+
+async fn connect_server(config: McpServerConfig) -> ServerEntry {
+    let mut entry = ServerEntry {
+        config,
+        client: None,
+        tools: Vec::new(),
+        resources: Vec::new(),
+        error: None,
+    };
+    if !entry.config.enabled {
+        return entry;
+    }
+
+    let result = async {
+        let client = McpClient::connect(&entry.config).await?;
+        let tools = client.list_tools().await?;
+        let resources = client.list_resources().await?;
+        Ok::<_, McpError>((client, tools, resources))
+    }
+    .await;
+
+    match result {
+        Ok((client, tools, resources)) => {
+            tracing::info!("Connected to MCP server {} ({} tools)", entry.config.name, tools.len());
+            entry.client = Some(Arc::new(client));
+            entry.tools = tools;
+            entry.resources = resources;
+        }
+        Err(e) => {
+            tracing::warn!("Failed to connect to MCP server {}: {}", entry.config.name, e);
+            entry.error = Some(e.to_string());
+        }
+    }
+    entry
+}
+
+/// Map a tool's JSON Schema onto the registry's simpler parameter model
+fn tool_definition(info: &McpToolInfo) -> ToolDefinition {
+    let schema = &info.tool.input_schema;
+    let properties = schema
+        .get("properties")
+        .and_then(|p| p.as_object())
+        .map(|properties| {
+            properties
+                .iter()
+                .map(|(name, property)| (name.clone(), parameter_property(property)))
+                .collect()
+        })
+        .unwrap_or_default();
+    let required = schema
+        .get("required")
+        .and_then(|r| r.as_array())
+        .map(|r| r.iter().filter_map(|v| v.as_str().map(String::from)).collect())
+        .unwrap_or_default();
+
+    ToolDefinition {
+        name: info.name.clone(),
+        description: format!(
+            "[{}] {}",
+            info.server,
+            info.tool.description.as_deref().unwrap_or(&info.tool.name)
+        ),
+        parameters: ToolParameters {
+            param_type: "object".to_string(),
+            properties,
+            required,
+        },
+    }
+}
+
+fn parameter_property(schema: &Value) -> ParameterProperty {
+    let prop_type = match schema.get("type") {
+        Some(Value::String(t)) => t.clone(),
+        // ["string", "null"] and the like
+        Some(Value::Array(types)) => types
+            .iter()
+            .filter_map(|t| t.as_str())
+            .find(|t| *t != "null")
+            .unwrap_or("string")
+            .to_string(),
+        _ => "string".to_string(),
+    };
+    let mut description = schema.get("description").and_then(|d| d.as_str()).map(String::from);
+    if prop_type == "array" {
+        if let Some(items) = schema.pointer("/items/type").and_then(|t| t.as_str()) {
+            let note = format!("(array of {})", items);
+            description = Some(match description {
+                Some(d) => format!("{} {}", d, note),
+                None => note,
+            });
+        }
+    }
+    if let Some(values) = schema.get("enum").and_then(|e| e.as_array()) {
+        let values: Vec<_> = values.iter().map(|v| v.to_string()).collect();
+        let note = format!("One of: {}", values.join(", "));
+        description = Some(match description {
+            Some(d) => format!("{}. {}", d.trim_end_matches('.'), note),
+            None => note,
+        });
+    }
+
+    ParameterProperty {
+        prop_type,
+        description,
+        default: schema.get("default").cloned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_tool_definition_from_schema() {
+        let info = McpToolInfo {
+            name: qualified_name("web", "search"),
+            server: "web".to_string(),
+            tool: McpTool {
+                name: "search".to_string(),
+                description: Some("Search the web".to_string()),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "query": { "type": "string", "description": "Search terms" },
+                        "sites": { "type": "array", "items": { "type": "string" } },
+                        "safe": { "type": ["string", "null"], "enum": ["on", "off"], "default": "on" },
+                    },
+                    "required": ["query"],
+                }),
+            },
+        };
+
+        let def = tool_definition(&info);
+        assert_eq!(def.name, "mcp__web__search");
+        assert_eq!(def.description, "[web] Search the web");
+        assert_eq!(def.parameters.required, vec!["query".to_string()]);
+
+        let props = &def.parameters.properties;
+        assert_eq!(props["query"].prop_type, "string");
+        assert_eq!(props["sites"].prop_type, "array");
+        assert_eq!(props["sites"].description.as_deref(), Some("(array of string)"));
+        assert_eq!(props["safe"].prop_type, "string");
+        assert_eq!(props["safe"].description.as_deref(), Some("One of: \"on\", \"off\""));
+        assert_eq!(props["safe"].default, Some(json!("on")));
+    }
+
+    #[tokio::test]
+    async fn test_disabled_servers_are_not_started() {
+        let manager = McpManager::default();
+        let config: McpServerConfig = serde_json::from_value(json!({
+            "name": "off",
+            "command": "/nonexistent/mcp-server",
+            "enabled": false,
+        }))
+        .unwrap();
+        manager.connect_all(vec![config]).await;
+
+        let statuses = manager.statuses().await;
+        assert_eq!(statuses.len(), 1);
+        assert!(!statuses[0].connected);
+        assert!(statuses[0].error.is_none());
+        assert!(manager.tools().is_empty());
+        assert!(matches!(
+            manager.call_tool("mcp__off__x", json!({})).await,
+            Err(McpError::UnknownTool(_))
+        ));
+    }
+}
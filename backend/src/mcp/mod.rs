@@ -0,0 +1,189 @@
+//! Model Context Protocol client
+//!
+//! Connects to the MCP servers configured in the `mcp` settings section, over
+//! stdio (a child process speaking line-delimited JSON-RPC) or SSE (an HTTP
+//! event stream plus a POST endpoint). Each server's tools are registered in
+//! the agent's [`ToolRegistry`](crate::cli_agent::ToolRegistry) under
+//! `mcp__<server>__<tool>` so the model calls them like built-in tools, and
+//! the executor forwards those calls to the server.
+
+pub mod client;
+pub mod connection;
+pub mod manager;
+pub mod protocol;
+
+pub use client::McpClient;
+pub use manager::{McpManager, McpToolInfo, ServerStatus};
+pub use protocol::{CallToolResult, McpResource, McpTool, ResourceContents, ServerInfo, ToolContent};
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+use thiserror::Error;
+
+/// Prefix of the names MCP tools are registered under
+pub const TOOL_PREFIX: &str = "mcp__";
+
+/// Longest tool name model providers accept
+const MAX_TOOL_NAME_LEN: usize = 64;
+
+#[derive(Debug, Error)]
+pub enum McpError {
+    #[error("Invalid MCP server configuration: {0}")]
+    Config(String),
+
+    #[error("MCP transport error: {0}")]
+    Transport(String),
+
+    #[error("MCP server closed the connection")]
+    Closed,
+
+    #[error("MCP request timed out: {0}")]
+    Timeout(String),
+
+    #[error("MCP server error {code}: {message}")]
+    Server { code: i64, message: String },
+
+    #[error("Invalid MCP response: {0}")]
+    InvalidResponse(String),
+
+    #[error("MCP server not connected: {0}")]
+    NotConnected(String),
+
+    #[error("Unknown MCP tool: {0}")]
+    UnknownTool(String),
+}
+
+/// How Skhoot talks to a server
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum McpTransport {
+    #[default]
+    Stdio,
+    Sse,
+}
+
+/// One configured server
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct McpServerConfig {
+    /// Identifier used in tool names; letters, digits, `-` and `_`
+    pub name: String,
+    #[serde(default)]
+    pub transport: McpTransport,
+    /// Program to start (stdio)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub env: HashMap<String, String>,
+    /// Event stream URL (sse)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Per-request timeout
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_timeout_secs() -> u64 {
+    30
+}
+
+impl McpServerConfig {
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_secs.max(1))
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.name.is_empty() || !self.name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+            return Err(format!(
+                "mcp server name '{}' may only contain letters, digits, '-' and '_'",
+                self.name
+            ));
+        }
+        match self.transport {
+            McpTransport::Stdio if self.command.as_deref().is_none_or(|c| c.trim().is_empty()) => {
+                Err(format!("mcp server '{}' uses stdio and needs a command", self.name))
+            }
+            McpTransport::Sse if self.url.as_deref().is_none_or(|u| url::Url::parse(u).is_err()) => {
+                Err(format!("mcp server '{}' uses sse and needs a valid url", self.name))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Check a list of servers: each must be valid and names must be unique
+pub fn validate_servers(servers: &[McpServerConfig]) -> Result<(), String> {
+    let mut names = HashSet::new();
+    for server in servers {
+        server.validate()?;
+        if !names.insert(server.name.as_str()) {
+            return Err(format!("mcp server name '{}' is used more than once", server.name));
+        }
+    }
+    Ok(())
+}
+
+/// Name a server's tool is registered under
+pub fn qualified_name(server: &str, tool: &str) -> String {
+    let tool: String = tool
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    let mut name = format!("{}{}__{}", TOOL_PREFIX, server, tool);
+    name.truncate(MAX_TOOL_NAME_LEN);
+    name
+}
+
+/// Whether a tool name belongs to an MCP server
+pub fn is_mcp_tool(name: &str) -> bool {
+    name.starts_with(TOOL_PREFIX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stdio(name: &str) -> McpServerConfig {
+        McpServerConfig {
+            name: name.to_string(),
+            transport: McpTransport::Stdio,
+            command: Some("npx".to_string()),
+            args: vec![],
+            env: HashMap::new(),
+            url: None,
+            enabled: true,
+            timeout_secs: 30,
+        }
+    }
+
+    #[test]
+    fn test_qualified_name() {
+        assert_eq!(qualified_name("files", "read_file"), "mcp__files__read_file");
+        assert_eq!(qualified_name("web", "fetch.url"), "mcp__web__fetch_url");
+        assert_eq!(qualified_name("s", &"x".repeat(100)).len(), MAX_TOOL_NAME_LEN);
+        assert!(is_mcp_tool("mcp__files__read_file"));
+        assert!(!is_mcp_tool("read_file"));
+    }
+
+    #[test]
+    fn test_validate_servers() {
+        assert!(validate_servers(&[stdio("files"), stdio("git")]).is_ok());
+        assert!(validate_servers(&[stdio("files"), stdio("files")]).is_err());
+        assert!(validate_servers(&[stdio("my server")]).is_err());
+
+        let mut sse = stdio("remote");
+        sse.transport = McpTransport::Sse;
+        assert!(validate_servers(std::slice::from_ref(&sse)).is_err());
+        sse.url = Some("http://localhost:8080/sse".to_string());
+        assert!(validate_servers(&[sse]).is_ok());
+    }
+}
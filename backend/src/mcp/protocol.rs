@@ -0,0 +1,147 @@
+//! MCP message types
+//!
+//! Only the parts of the protocol Skhoot uses as a client: initialization,
+//! tool listing and calls, and resource listing and reads.
+
+use serde::{Deserialize, Serialize};
+
+/// Protocol revision requested during initialization
+pub const PROTOCOL_VERSION: &str = "2024-11-05";
+
+/// JSON-RPC error codes used when answering server requests
+pub const METHOD_NOT_FOUND: i64 = -32601;
+
+/// A tool offered by a server
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpTool {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// JSON Schema of the arguments
+    #[serde(default)]
+    pub input_schema: serde_json::Value,
+}
+
+/// A resource offered by a server
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpResource {
+    pub uri: String,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+}
+
+/// Contents of a resource read
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceContents {
+    pub uri: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    /// Base64-encoded binary content
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blob: Option<String>,
+}
+
+/// One part of a tool result
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ToolContent {
+    Text {
+        text: String,
+    },
+    Image {
+        data: String,
+        #[serde(rename = "mimeType")]
+        mime_type: String,
+    },
+    Audio {
+        data: String,
+        #[serde(rename = "mimeType")]
+        mime_type: String,
+    },
+    Resource {
+        resource: ResourceContents,
+    },
+}
+
+/// Result of `tools/call`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallToolResult {
+    #[serde(default)]
+    pub content: Vec<ToolContent>,
+    #[serde(default)]
+    pub is_error: bool,
+}
+
+impl CallToolResult {
+    /// Text for the agent; binary parts are summarized
+    pub fn text(&self) -> String {
+        self.content
+            .iter()
+            .map(|part| match part {
+                ToolContent::Text { text } => text.clone(),
+                ToolContent::Image { data, mime_type } | ToolContent::Audio { data, mime_type } => {
+                    format!("[{} content, {} base64 bytes]", mime_type, data.len())
+                }
+                ToolContent::Resource { resource } => match &resource.text {
+                    Some(text) => text.clone(),
+                    None => format!("[resource {}]", resource.uri),
+                },
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Page of `tools/list`
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ToolsPage {
+    #[serde(default)]
+    pub tools: Vec<McpTool>,
+    pub next_cursor: Option<String>,
+}
+
+/// Page of `resources/list`
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ResourcesPage {
+    #[serde(default)]
+    pub resources: Vec<McpResource>,
+    pub next_cursor: Option<String>,
+}
+
+/// Result of `resources/read`
+#[derive(Debug, Deserialize)]
+pub(crate) struct ReadResourceResult {
+    #[serde(default)]
+    pub contents: Vec<ResourceContents>,
+}
+
+/// Result of `initialize`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct InitializeResult {
+    #[serde(default)]
+    pub protocol_version: String,
+    #[serde(default)]
+    pub capabilities: serde_json::Value,
+    #[serde(default)]
+    pub server_info: Option<ServerInfo>,
+}
+
+/// Name and version a server reports
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ServerInfo {
+    pub name: String,
+    #[serde(default)]
+    pub version: String,
+}
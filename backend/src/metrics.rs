@@ -0,0 +1,338 @@
+//! Lightweight in-process metrics registry with Prometheus text export
+//!
+//! Subsystems record counters, gauges and latency histograms through the free
+//! functions below, which write to a global registry. `/metrics` renders the
+//! registry in the Prometheus text exposition format.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Upper bounds (seconds) of the latency histogram buckets
+const DURATION_BUCKETS: &[f64] = &[0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0];
+
+/// HELP text for the metrics the backend records
+const METRIC_HELP: &[(&str, &str)] = &[
+    ("skhoot_http_requests_total", "HTTP requests handled, by route and status"),
+    ("skhoot_http_request_duration_seconds", "HTTP request latency, by route"),
+    ("skhoot_searches_total", "File searches, by mode and outcome"),
+    ("skhoot_search_duration_seconds", "File search latency, by mode"),
+    ("skhoot_extractions_total", "Web page extractions, by outcome"),
+    ("skhoot_extraction_duration_seconds", "Web page extraction latency, by method"),
+    ("skhoot_ai_requests_total", "AI provider requests, by provider, operation and outcome"),
+    ("skhoot_ai_request_duration_seconds", "AI provider request latency, by provider and operation"),
+    ("skhoot_cache_lookups_total", "Cache lookups, by cache and result"),
+    ("skhoot_cache_hit_ratio", "Share of cache lookups that were hits since startup"),
+    ("skhoot_terminal_sessions", "Terminal sessions, by state"),
+    ("skhoot_terminal_ptys_open", "Open pseudo-terminals"),
+    ("skhoot_terminal_sessions_hibernated", "Terminal sessions hibernated to disk"),
+    ("skhoot_active_searches", "File searches currently running"),
+];
+
+type Labels = Vec<(String, String)>;
+
+#[derive(Debug, Clone)]
+struct Histogram {
+    /// Observations per bucket (not cumulative); the last slot is +Inf
+    buckets: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn new() -> Self {
+        Self {
+            buckets: vec![0; DURATION_BUCKETS.len() + 1],
+            sum: 0.0,
+            count: 0,
+        }
+    }
+
+    fn observe(&mut self, value: f64) {
+        let index = DURATION_BUCKETS
+            .iter()
+            .position(|bound| value <= *bound)
+            .unwrap_or(DURATION_BUCKETS.len());
+        self.buckets[index] += 1;
+        self.sum += value;
+        self.count += 1;
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Series {
+    Counter(f64),
+    Gauge(f64),
+    Histogram(Histogram),
+}
+
+impl Series {
+    fn type_name(&self) -> &'static str {
+        match self {
+            Series::Counter(_) => "counter",
+            Series::Gauge(_) => "gauge",
+            Series::Histogram(_) => "histogram",
+        }
+    }
+}
+
+/// Metric families keyed by name, each holding one series per label set
+#[derive(Default)]
+pub struct MetricsRegistry {
+    families: Mutex<BTreeMap<String, BTreeMap<Labels, Series>>>,
+}
+
+impl MetricsRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn inc_counter(&self, name: &str, labels: &[(&str, &str)]) {
+        self.add_counter(name, labels, 1.0);
+    }
+
+    pub fn add_counter(&self, name: &str, labels: &[(&str, &str)], value: f64) {
+        self.update(name, labels, || Series::Counter(0.0), |series| {
+            if let Series::Counter(total) = series {
+                *total += value;
+            }
+        });
+    }
+
+    pub fn set_gauge(&self, name: &str, labels: &[(&str, &str)], value: f64) {
+        self.update(name, labels, || Series::Gauge(0.0), |series| {
+            if let Series::Gauge(current) = series {
+                *current = value;
+            }
+        });
+    }
+
+    pub fn observe_duration(&self, name: &str, labels: &[(&str, &str)], duration: Duration) {
+        let seconds = duration.as_secs_f64();
+        self.update(name, labels, || Series::Histogram(Histogram::new()), |series| {
+            if let Series::Histogram(histogram) = series {
+                histogram.observe(seconds);
+            }
+        });
+    }
+
+    /// Current value of a counter series (0 when never incremented)
+    pub fn counter_value(&self, name: &str, labels: &[(&str, &str)]) -> f64 {
+        let families = self.families.lock().unwrap();
+        match families.get(name).and_then(|family| family.get(&owned_labels(labels))) {
+            Some(Series::Counter(total)) => *total,
+            _ => 0.0,
+        }
+    }
+
+    fn update(
+        &self,
+        name: &str,
+        labels: &[(&str, &str)],
+        create: impl FnOnce() -> Series,
+        apply: impl FnOnce(&mut Series),
+    ) {
+        let mut families = self.families.lock().unwrap();
+        let family = families.entry(name.to_string()).or_default();
+        apply(family.entry(owned_labels(labels)).or_insert_with(create));
+    }
+
+    /// Renders every metric in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let families = self.families.lock().unwrap();
+        let mut out = String::new();
+
+        for (name, series) in families.iter() {
+            let Some(first) = series.values().next() else {
+                continue;
+            };
+            if let Some((_, help)) = METRIC_HELP.iter().find(|(metric, _)| metric == name) {
+                let _ = writeln!(out, "# HELP {} {}", name, help);
+            }
+            let _ = writeln!(out, "# TYPE {} {}", name, first.type_name());
+
+            for (labels, value) in series {
+                match value {
+                    Series::Counter(v) | Series::Gauge(v) => {
+                        let _ = writeln!(out, "{}{} {}", name, format_labels(labels, None), format_value(*v));
+                    }
+                    Series::Histogram(histogram) => {
+                        let mut cumulative = 0;
+                        for (i, count) in histogram.buckets.iter().enumerate() {
+                            cumulative += count;
+                            let le = DURATION_BUCKETS
+                                .get(i)
+                                .map(|bound| format_value(*bound))
+                                .unwrap_or_else(|| "+Inf".to_string());
+                            let _ = writeln!(
+                                out,
+                                "{}_bucket{} {}",
+                                name,
+                                format_labels(labels, Some(&le)),
+                                cumulative
+                            );
+                        }
+                        let _ = writeln!(out, "{}_sum{} {}", name, format_labels(labels, None), format_value(histogram.sum));
+                        let _ = writeln!(out, "{}_count{} {}", name, format_labels(labels, None), histogram.count);
+                    }
+                }
+            }
+        }
+
+        out
+    }
+}
+
+fn owned_labels(labels: &[(&str, &str)]) -> Labels {
+    let mut owned: Labels = labels.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+    owned.sort();
+    owned
+}
+
+fn format_labels(labels: &Labels, le: Option<&str>) -> String {
+    let mut parts: Vec<String> = labels
+        .iter()
+        .map(|(key, value)| format!("{}=\"{}\"", key, escape_label_value(value)))
+        .collect();
+    if let Some(le) = le {
+        parts.push(format!("le=\"{}\"", le));
+    }
+    if parts.is_empty() {
+        String::new()
+    } else {
+        format!("{{{}}}", parts.join(","))
+    }
+}
+
+fn escape_label_value(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+fn format_value(value: f64) -> String {
+    if value.is_finite() && value.fract() == 0.0 && value.abs() < 1e15 {
+        format!("{}", value as i64)
+    } else {
+        format!("{}", value)
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref REGISTRY: MetricsRegistry = MetricsRegistry::new();
+}
+
+/// The process-wide registry
+pub fn global() -> &'static MetricsRegistry {
+    &REGISTRY
+}
+
+pub fn inc_counter(name: &str, labels: &[(&str, &str)]) {
+    REGISTRY.inc_counter(name, labels);
+}
+
+pub fn set_gauge(name: &str, labels: &[(&str, &str)], value: f64) {
+    REGISTRY.set_gauge(name, labels, value);
+}
+
+pub fn observe_duration(name: &str, labels: &[(&str, &str)], duration: Duration) {
+    REGISTRY.observe_duration(name, labels, duration);
+}
+
+/// Counts an AI provider request and observes its latency
+pub fn record_ai_request(provider: &str, operation: &str, success: bool, duration: Duration) {
+    let outcome = if success { "ok" } else { "error" };
+    REGISTRY.inc_counter(
+        "skhoot_ai_requests_total",
+        &[("provider", provider), ("operation", operation), ("outcome", outcome)],
+    );
+    REGISTRY.observe_duration(
+        "skhoot_ai_request_duration_seconds",
+        &[("provider", provider), ("operation", operation)],
+        duration,
+    );
+}
+
+/// Counts a cache lookup for the hit ratio of `cache`
+pub fn record_cache_lookup(cache: &str, hit: bool) {
+    REGISTRY.inc_counter("skhoot_cache_lookups_total", &[("cache", cache), ("result", if hit { "hit" } else { "miss" })]);
+}
+
+/// Refreshes `skhoot_cache_hit_ratio` from the lookup counters
+pub fn update_cache_hit_ratios(caches: &[&str]) {
+    for cache in caches {
+        let hits = REGISTRY.counter_value("skhoot_cache_lookups_total", &[("cache", cache), ("result", "hit")]);
+        let misses = REGISTRY.counter_value("skhoot_cache_lookups_total", &[("cache", cache), ("result", "miss")]);
+        if hits + misses > 0.0 {
+            REGISTRY.set_gauge("skhoot_cache_hit_ratio", &[("cache", cache)], hits / (hits + misses));
+        }
+    }
+}
+
+/// Middleware counting requests and latency per matched route
+pub async fn track_http(request: axum::extract::Request, next: axum::middleware::Next) -> axum::response::Response {
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<axum::extract::MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string());
+    let started = std::time::Instant::now();
+
+    let response = next.run(request).await;
+
+    let status = response.status().as_u16().to_string();
+    REGISTRY.inc_counter(
+        "skhoot_http_requests_total",
+        &[("method", &method), ("route", &route), ("status", &status)],
+    );
+    REGISTRY.observe_duration(
+        "skhoot_http_request_duration_seconds",
+        &[("method", &method), ("route", &route)],
+        started.elapsed(),
+    );
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_counters_and_gauges() {
+        let registry = MetricsRegistry::new();
+        registry.inc_counter("skhoot_http_requests_total", &[("route", "/health"), ("method", "GET")]);
+        registry.inc_counter("skhoot_http_requests_total", &[("method", "GET"), ("route", "/health")]);
+        registry.set_gauge("skhoot_terminal_ptys_open", &[], 3.0);
+        registry.set_gauge("skhoot_cache_hit_ratio", &[("cache", "quote\"d")], 0.25);
+
+        let text = registry.render();
+        assert!(text.contains("# HELP skhoot_http_requests_total HTTP requests handled, by route and status\n"));
+        assert!(text.contains("# TYPE skhoot_http_requests_total counter\n"));
+        assert!(text.contains("skhoot_http_requests_total{method=\"GET\",route=\"/health\"} 2\n"));
+        assert!(text.contains("# TYPE skhoot_terminal_ptys_open gauge\nskhoot_terminal_ptys_open 3\n"));
+        assert!(text.contains("skhoot_cache_hit_ratio{cache=\"quote\\\"d\"} 0.25\n"));
+    }
+
+    #[test]
+    fn test_render_histogram() {
+        let registry = MetricsRegistry::new();
+        registry.observe_duration("skhoot_search_duration_seconds", &[("mode", "auto")], Duration::from_millis(20));
+        registry.observe_duration("skhoot_search_duration_seconds", &[("mode", "auto")], Duration::from_secs(90));
+
+        let text = registry.render();
+        assert!(text.contains("# TYPE skhoot_search_duration_seconds histogram\n"));
+        assert!(text.contains("skhoot_search_duration_seconds_bucket{mode=\"auto\",le=\"0.01\"} 0\n"));
+        assert!(text.contains("skhoot_search_duration_seconds_bucket{mode=\"auto\",le=\"0.025\"} 1\n"));
+        assert!(text.contains("skhoot_search_duration_seconds_bucket{mode=\"auto\",le=\"60\"} 1\n"));
+        assert!(text.contains("skhoot_search_duration_seconds_bucket{mode=\"auto\",le=\"+Inf\"} 2\n"));
+        assert!(text.contains("skhoot_search_duration_seconds_count{mode=\"auto\"} 2\n"));
+    }
+
+    #[test]
+    fn test_counter_value() {
+        let registry = MetricsRegistry::new();
+        assert_eq!(registry.counter_value("skhoot_cache_lookups_total", &[("cache", "web")]), 0.0);
+        registry.inc_counter("skhoot_cache_lookups_total", &[("cache", "web")]);
+        assert_eq!(registry.counter_value("skhoot_cache_lookups_total", &[("cache", "web")]), 1.0);
+    }
+}
@@ -0,0 +1,397 @@
+//! Mount awareness for directory walks
+//!
+//! A walk from the home directory or `/` crosses every filesystem mounted
+//! below it. A stale NFS or SMB share blocks the first `readdir` for
+//! minutes, and a sleeping USB disk has to spin up; either stalls the
+//! scan or search that reached it. Pseudo filesystems such as `/proc`
+//! are never worth walking.
+//!
+//! [`MountGuard`] looks at the mounts below a walk's root before it
+//! starts. Virtual mounts are always skipped, network and removable mounts
+//! only walked when the [`MountPolicy`] opts in, and those that are walked
+//! must answer a probe within the policy's timeout. What was skipped, and
+//! why, is reported with the results instead of the walk hanging.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MountKind {
+    Local,
+    /// NFS, SMB, SSHFS and other remote filesystems
+    Network,
+    /// USB disks, SD cards and optical media
+    Removable,
+    /// Kernel and pseudo filesystems (`/proc`, `/sys`, cgroups, snaps)
+    Virtual,
+}
+
+/// A mounted filesystem
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Mount {
+    pub mount_point: PathBuf,
+    pub device: String,
+    pub file_system: String,
+    pub kind: MountKind,
+}
+
+/// Which mounts below a root a walk may enter
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MountPolicy {
+    pub include_network: bool,
+    pub include_removable: bool,
+    /// How long a network or removable mount gets to answer before it is
+    /// skipped as unresponsive
+    pub timeout_ms: u64,
+}
+
+impl Default for MountPolicy {
+    fn default() -> Self {
+        Self {
+            include_network: false,
+            include_removable: false,
+            timeout_ms: 2000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SkipReason {
+    /// The policy leaves this kind of mount out
+    Excluded,
+    /// The mount didn't answer within the timeout
+    Unresponsive,
+}
+
+/// A mount a walk left out
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SkippedMount {
+    pub mount_point: PathBuf,
+    pub kind: MountKind,
+    pub reason: SkipReason,
+}
+
+/// Mounts a walk from one root must not enter
+#[derive(Debug, Clone, Default)]
+pub struct MountGuard {
+    skip: HashSet<PathBuf>,
+    skipped: Vec<SkippedMount>,
+}
+
+impl MountGuard {
+    /// Check the mounts at and below `root` against `policy`; probes run in
+    /// parallel, so this takes at most the policy's timeout
+    pub fn new(root: &Path, policy: &MountPolicy) -> Self {
+        Self::with_mounts(root, policy, &list_mounts())
+    }
+
+    pub fn with_mounts(root: &Path, policy: &MountPolicy, mounts: &[Mount]) -> Self {
+        let mut guard = Self::default();
+        let mut probe = Vec::new();
+
+        // The root's own filesystem was asked for explicitly, so only its
+        // responsiveness is checked
+        if let Some(own) = containing_mount(root, mounts) {
+            if matches!(own.kind, MountKind::Network | MountKind::Removable) {
+                probe.push((root.to_path_buf(), own.kind));
+            }
+        }
+        for mount in mounts {
+            if mount.mount_point == root || !mount.mount_point.starts_with(root) {
+                continue;
+            }
+            let allowed = match mount.kind {
+                MountKind::Local => continue,
+                MountKind::Virtual => false,
+                MountKind::Network => policy.include_network,
+                MountKind::Removable => policy.include_removable,
+            };
+            if allowed {
+                probe.push((mount.mount_point.clone(), mount.kind));
+            } else {
+                guard.skip(mount.mount_point.clone(), mount.kind, SkipReason::Excluded);
+            }
+        }
+
+        let timeout = Duration::from_millis(policy.timeout_ms);
+        for (path, kind) in probe_all(probe, timeout) {
+            tracing::warn!("Skipping unresponsive {:?} mount {}", kind, path.display());
+            guard.skip(path, kind, SkipReason::Unresponsive);
+        }
+        guard
+    }
+
+    fn skip(&mut self, mount_point: PathBuf, kind: MountKind, reason: SkipReason) {
+        if self.skip.insert(mount_point.clone()) {
+            self.skipped.push(SkippedMount { mount_point, kind, reason });
+        }
+    }
+
+    /// Whether a walk may enter `path`; use as the walker's entry filter
+    pub fn allows(&self, path: &Path) -> bool {
+        self.skip.is_empty() || !self.skip.contains(path)
+    }
+
+    pub fn skipped(&self) -> &[SkippedMount] {
+        &self.skipped
+    }
+}
+
+/// The mount `path` lives on: the one with the longest matching mount point
+pub fn containing_mount<'a>(path: &Path, mounts: &'a [Mount]) -> Option<&'a Mount> {
+    mounts
+        .iter()
+        .filter(|m| path.starts_with(&m.mount_point))
+        .max_by_key(|m| m.mount_point.as_os_str().len())
+}
+
+/// Read each directory on its own thread; returns those that didn't answer
+/// in time. A hung thread is left behind, blocked in the kernel, rather
+/// than holding up the caller.
+fn probe_all(paths: Vec<(PathBuf, MountKind)>, timeout: Duration) -> Vec<(PathBuf, MountKind)> {
+    if paths.is_empty() {
+        return Vec::new();
+    }
+    let (tx, rx) = mpsc::channel();
+    for (index, (path, _)) in paths.iter().enumerate() {
+        let tx = tx.clone();
+        let path = path.clone();
+        std::thread::spawn(move || {
+            // An error is an answer too; the walk reports it as usual
+            let _ = std::fs::read_dir(&path).map(|mut entries| entries.next());
+            let _ = tx.send(index);
+        });
+    }
+    drop(tx);
+
+    let deadline = Instant::now() + timeout;
+    let mut answered = vec![false; paths.len()];
+    while let Some(left) = deadline.checked_duration_since(Instant::now()) {
+        match rx.recv_timeout(left) {
+            Ok(index) => answered[index] = true,
+            Err(_) => break,
+        }
+    }
+    paths
+        .into_iter()
+        .zip(answered)
+        .filter(|(_, answered)| !answered)
+        .map(|(path, _)| path)
+        .collect()
+}
+
+const NETWORK_FILE_SYSTEMS: &[&str] = &[
+    "nfs", "nfs4", "cifs", "smb3", "smbfs", "ncpfs", "afs", "afpfs", "9p", "ceph", "glusterfs", "lustre", "gpfs",
+    "davfs", "webdav", "sshfs", "fuse.sshfs", "fuse.rclone", "fuse.s3fs", "fuse.gcsfuse", "fuse.davfs",
+];
+
+const VIRTUAL_FILE_SYSTEMS: &[&str] = &[
+    "proc", "sysfs", "devtmpfs", "devpts", "devfs", "cgroup", "cgroup2", "securityfs", "debugfs", "tracefs",
+    "pstore", "bpf", "configfs", "fusectl", "mqueue", "hugetlbfs", "autofs", "binfmt_misc", "efivarfs", "nsfs",
+    "rpc_pipefs", "selinuxfs", "squashfs",
+];
+
+/// Kind of a mount from its filesystem type, device and mount point
+pub fn classify(file_system: &str, device: &str, mount_point: &Path) -> MountKind {
+    let file_system = file_system.to_ascii_lowercase();
+    if NETWORK_FILE_SYSTEMS.contains(&file_system.as_str()) || device.starts_with("//") {
+        return MountKind::Network;
+    }
+    if VIRTUAL_FILE_SYSTEMS.contains(&file_system.as_str()) {
+        return MountKind::Virtual;
+    }
+    let removable_dirs = ["/media", "/run/media", "/Volumes"];
+    if removable_dirs.iter().any(|dir| mount_point.starts_with(dir) && mount_point != Path::new(dir))
+        || crate::disk_analyzer::is_removable_device(device)
+    {
+        return MountKind::Removable;
+    }
+    MountKind::Local
+}
+
+/// Parse `/proc/self/mounts`; mount points escape spaces as `\040`
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+pub fn parse_proc_mounts(text: &str) -> Vec<Mount> {
+    text.lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let device = fields.next()?;
+            let mount_point = PathBuf::from(unescape_octal(fields.next()?));
+            let file_system = fields.next()?;
+            Some(Mount {
+                kind: classify(file_system, device, &mount_point),
+                mount_point,
+                device: device.to_string(),
+                file_system: file_system.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Parse macOS `mount` output: `<device> on <mount point> (<fs>, <options>)`
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+pub fn parse_macos_mount(text: &str) -> Vec<Mount> {
+    text.lines()
+        .filter_map(|line| {
+            let (device, rest) = line.split_once(" on ")?;
+            let (mount_point, options) = rest.rsplit_once(" (")?;
+            let file_system = options.split([',', ')']).next()?.trim();
+            let mount_point = PathBuf::from(mount_point);
+            Some(Mount {
+                kind: classify(file_system, device, &mount_point),
+                mount_point,
+                device: device.to_string(),
+                file_system: file_system.to_string(),
+            })
+        })
+        .collect()
+}
+
+fn unescape_octal(field: &str) -> String {
+    let mut out = String::with_capacity(field.len());
+    let mut chars = field.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            let digits: String = chars.clone().take(3).collect();
+            if digits.len() == 3 {
+                if let Ok(code) = u8::from_str_radix(&digits, 8) {
+                    out.push(code as char);
+                    chars.nth(2);
+                    continue;
+                }
+            }
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Mounted filesystems; empty if they can't be listed
+#[cfg(target_os = "linux")]
+pub fn list_mounts() -> Vec<Mount> {
+    std::fs::read_to_string("/proc/self/mounts")
+        .map(|text| parse_proc_mounts(&text))
+        .unwrap_or_default()
+}
+
+#[cfg(target_os = "macos")]
+pub fn list_mounts() -> Vec<Mount> {
+    std::process::Command::new("mount")
+        .output()
+        .map(|output| parse_macos_mount(&String::from_utf8_lossy(&output.stdout)))
+        .unwrap_or_default()
+}
+
+/// Drives with their Windows drive type: 2 removable, 4 network, 5 optical
+#[cfg(target_os = "windows")]
+pub fn list_mounts() -> Vec<Mount> {
+    let Ok(output) = std::process::Command::new("wmic")
+        .args(["logicaldisk", "get", "caption,drivetype,filesystem", "/format:csv"])
+        .output()
+    else {
+        return Vec::new();
+    };
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let parts: Vec<&str> = line.trim().split(',').collect();
+            if parts.len() < 4 || parts[1] == "Caption" {
+                return None;
+            }
+            let caption = parts[1].trim();
+            let kind = match parts[2].trim() {
+                "4" => MountKind::Network,
+                "2" | "5" => MountKind::Removable,
+                "" => return None,
+                _ => MountKind::Local,
+            };
+            Some(Mount {
+                mount_point: PathBuf::from(format!("{}\\", caption)),
+                device: caption.to_string(),
+                file_system: parts[3].trim().to_string(),
+                kind,
+            })
+        })
+        .collect()
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+pub fn list_mounts() -> Vec<Mount> {
+    Vec::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_classify() {
+        let mounts = parse_proc_mounts(
+            "/dev/nvme0n1p2 / ext4 rw,relatime 0 0\n\
+             proc /proc proc rw,nosuid 0 0\n\
+             nas:/export /home/u/nas nfs4 rw 0 0\n\
+             //server/share /mnt/my\\040share cifs rw 0 0\n\
+             /dev/sdb1 /media/u/USB vfat rw 0 0\n",
+        );
+        let kinds: Vec<_> = mounts.iter().map(|m| (m.mount_point.to_str().unwrap(), m.kind)).collect();
+        assert_eq!(
+            kinds,
+            [
+                ("/", MountKind::Local),
+                ("/proc", MountKind::Virtual),
+                ("/home/u/nas", MountKind::Network),
+                ("/mnt/my share", MountKind::Network),
+                ("/media/u/USB", MountKind::Removable),
+            ]
+        );
+
+        let mac = parse_macos_mount(
+            "/dev/disk3s1s1 on / (apfs, sealed, local, read-only, journaled)\n\
+             //u@nas._smb._tcp.local/home on /Volumes/home (smbfs, nodev, nosuid, mounted by u)\n",
+        );
+        assert_eq!(mac[0].kind, MountKind::Local);
+        assert_eq!((mac[1].mount_point.to_str().unwrap(), mac[1].kind), ("/Volumes/home", MountKind::Network));
+        assert_eq!(containing_mount(Path::new("/home/u/nas/a.txt"), &mounts).unwrap().file_system, "nfs4");
+    }
+
+    #[test]
+    fn test_guard_skips_excluded_mounts() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("share")).unwrap();
+        let mount = |path: PathBuf, kind| Mount {
+            mount_point: path,
+            device: "dev".to_string(),
+            file_system: "fs".to_string(),
+            kind,
+        };
+        let mounts = [
+            mount(PathBuf::from("/"), MountKind::Local),
+            mount(root.join("proc"), MountKind::Virtual),
+            mount(root.join("share"), MountKind::Network),
+            mount(root.join("usb"), MountKind::Removable),
+            mount(PathBuf::from("/elsewhere"), MountKind::Network),
+        ];
+
+        let guard = MountGuard::with_mounts(root, &MountPolicy::default(), &mounts);
+        assert!(guard.allows(root) && guard.allows(&root.join("docs")));
+        assert!(!guard.allows(&root.join("share")) && !guard.allows(&root.join("proc")));
+        assert_eq!(guard.skipped().len(), 3);
+        assert!(guard.skipped().iter().all(|s| s.reason == SkipReason::Excluded));
+
+        // Opted-in mounts are walked when they answer the probe
+        let policy = MountPolicy {
+            include_network: true,
+            ..Default::default()
+        };
+        let guard = MountGuard::with_mounts(root, &policy, &mounts);
+        assert!(guard.allows(&root.join("share")));
+        assert!(!guard.allows(&root.join("usb")));
+    }
+}
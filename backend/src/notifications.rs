@@ -0,0 +1,322 @@
+//! Desktop notifications for finished agents and workflows
+//!
+//! Agents and workflows often finish while Skhoot is minimized. The
+//! [`Notifier`] watches the event bus for completions, failures and storage
+//! quotas going over their limit, and
+//! forwards them to the Tauri shell's HTTP bridge (`/api/notify`), which shows
+//! them through the notification plugin. The `[notifications]` settings
+//! section picks which kinds are shown and can hold them back during quiet
+//! hours.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::cli_agent::AgentState;
+use crate::config::{NotificationSettings, SettingsStore};
+use crate::events::Event;
+use crate::workflows::{WorkflowStatus, WorkflowStorage};
+
+/// Where the Tauri shell's HTTP bridge listens
+const DEFAULT_BRIDGE_URL: &str = "http://localhost:1420";
+
+/// Finished runs remembered so a repeated status update isn't shown twice
+const RECENT_CAPACITY: usize = 256;
+
+/// What a notification reports
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    WorkflowCompleted,
+    WorkflowFailed,
+    AgentCompleted,
+    AgentFailed,
+    QuotaExceeded,
+}
+
+impl NotificationKind {
+    /// Whether the user wants this kind of notification
+    pub fn is_enabled(self, settings: &NotificationSettings) -> bool {
+        match self {
+            NotificationKind::WorkflowCompleted => settings.workflow_completed,
+            NotificationKind::WorkflowFailed => settings.workflow_failed,
+            NotificationKind::AgentCompleted => settings.agent_completed,
+            NotificationKind::AgentFailed => settings.agent_failed,
+            NotificationKind::QuotaExceeded => settings.quota_exceeded,
+        }
+    }
+}
+
+/// Notification sent to the Tauri shell
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Notification {
+    pub kind: NotificationKind,
+    pub title: String,
+    pub body: String,
+}
+
+/// The kind of notification an event calls for, if it reports something
+/// that finished or a quota that is over its limit
+pub fn finished(event: &Event) -> Option<NotificationKind> {
+    match event {
+        Event::WorkflowExecution { status, .. } | Event::WorkflowRun { status, .. } => match status {
+            WorkflowStatus::Completed => Some(NotificationKind::WorkflowCompleted),
+            WorkflowStatus::Failed => Some(NotificationKind::WorkflowFailed),
+            _ => None,
+        },
+        Event::AgentExecution { status, .. } => match status.as_str() {
+            "completed" => Some(NotificationKind::AgentCompleted),
+            "failed" => Some(NotificationKind::AgentFailed),
+            _ => None,
+        },
+        Event::AgentSession { state, .. } => match state {
+            AgentState::Ready => Some(NotificationKind::AgentCompleted),
+            AgentState::Error => Some(NotificationKind::AgentFailed),
+            _ => None,
+        },
+        Event::StorageQuota { exceeded: true, .. } => Some(NotificationKind::QuotaExceeded),
+        _ => None,
+    }
+}
+
+/// Whether a notification of `kind` should be shown at local time `now`
+pub fn should_notify(settings: &NotificationSettings, kind: NotificationKind, now: chrono::NaiveTime) -> bool {
+    settings.enabled && kind.is_enabled(settings) && !settings.is_quiet_at(now)
+}
+
+/// Run, execution or session an event is about
+fn subject(event: &Event) -> Option<String> {
+    match event {
+        Event::WorkflowExecution { execution_id, .. } => Some(format!("workflow-execution/{}", execution_id)),
+        Event::WorkflowRun { run_id, .. } => Some(format!("workflow-run/{}", run_id)),
+        Event::AgentExecution { execution_id, .. } => Some(format!("agent-execution/{}", execution_id)),
+        Event::AgentSession { session_id, .. } => Some(format!("agent-session/{}", session_id)),
+        Event::StorageQuota { quota_id, .. } => Some(format!("storage-quota/{}", quota_id)),
+        _ => None,
+    }
+}
+
+/// Forwards finished agents and workflows, and exceeded storage quotas, to
+/// the desktop shell
+pub struct Notifier {
+    bridge_url: String,
+    client: reqwest::Client,
+    auth: crate::auth::AuthToken,
+    /// Used to show workflow names instead of IDs
+    workflows: Option<Arc<WorkflowStorage>>,
+    /// Subjects already notified for their current outcome, oldest first
+    notified: VecDeque<String>,
+}
+
+impl Notifier {
+    /// Create a notifier for the bridge at `bridge_url` (default: "http://localhost:1420")
+    pub fn new(bridge_url: Option<String>) -> Self {
+        Self {
+            bridge_url: bridge_url.unwrap_or_else(|| DEFAULT_BRIDGE_URL.to_string()),
+            client: reqwest::Client::builder()
+                .timeout(std::time::Duration::from_secs(5))
+                .build()
+                .unwrap_or_default(),
+            auth: crate::auth::AuthToken::from_env(),
+            workflows: None,
+            notified: VecDeque::new(),
+        }
+    }
+
+    pub fn with_workflow_storage(mut self, storage: Arc<WorkflowStorage>) -> Self {
+        self.workflows = Some(storage);
+        self
+    }
+
+    /// Watch the global event bus until it closes
+    pub fn spawn(mut self) -> tokio::task::JoinHandle<()> {
+        let mut events = crate::events::bus().subscribe();
+        tokio::spawn(async move {
+            loop {
+                match events.recv().await {
+                    Ok(envelope) => self.handle(&envelope.event).await,
+                    Err(RecvError::Lagged(missed)) => {
+                        tracing::debug!("Notifier fell behind, skipped {} events", missed);
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        })
+    }
+
+    async fn handle(&mut self, event: &Event) {
+        let Some(subject) = subject(event) else {
+            return;
+        };
+        let Some(kind) = finished(event) else {
+            // Running again (e.g. a resumed run) or back under its quota, so its next
+            // outcome is new
+            self.notified.retain(|s| *s != subject);
+            return;
+        };
+        if self.notified.contains(&subject) {
+            return;
+        }
+        self.notified.push_back(subject);
+        if self.notified.len() > RECENT_CAPACITY {
+            self.notified.pop_front();
+        }
+
+        let settings = SettingsStore::global().get().notifications;
+        if !should_notify(&settings, kind, chrono::Local::now().time()) {
+            return;
+        }
+
+        let notification = self.describe(kind, event).await;
+        if let Err(e) = self.send(&notification).await {
+            // The desktop shell isn't running when the backend is started on its own
+            tracing::debug!("Could not show notification '{}': {}", notification.title, e);
+        }
+    }
+
+    async fn describe(&self, kind: NotificationKind, event: &Event) -> Notification {
+        let title = match kind {
+            NotificationKind::WorkflowCompleted => "Workflow completed",
+            NotificationKind::WorkflowFailed => "Workflow failed",
+            NotificationKind::AgentCompleted => "Agent finished",
+            NotificationKind::AgentFailed => "Agent failed",
+            NotificationKind::QuotaExceeded => "Storage quota exceeded",
+        };
+
+        let body = match event {
+            Event::WorkflowExecution { workflow_id, current_step_id, .. }
+            | Event::WorkflowRun { workflow_id, current_step_id, .. } => {
+                let name = match &self.workflows {
+                    Some(storage) => storage.get(workflow_id).await.map(|w| w.name),
+                    None => None,
+                };
+                let name = name.unwrap_or_else(|| workflow_id.clone());
+                match (kind, current_step_id) {
+                    (NotificationKind::WorkflowFailed, Some(step)) => format!("\"{}\" failed at step {}", name, step),
+                    (NotificationKind::WorkflowFailed, None) => format!("\"{}\" failed", name),
+                    _ => format!("\"{}\" finished", name),
+                }
+            }
+            Event::AgentExecution { agent_id, .. } if kind == NotificationKind::AgentFailed => {
+                format!("Agent {} stopped with an error", agent_id)
+            }
+            Event::AgentExecution { agent_id, .. } => format!("Agent {} finished its task", agent_id),
+            Event::AgentSession { session_id, .. } if kind == NotificationKind::AgentFailed => {
+                format!("Session {} stopped with an error", session_id)
+            }
+            Event::AgentSession { session_id, .. } => format!("Session {} is waiting for you", session_id),
+            Event::StorageQuota { name, size, limit_bytes, .. } => format!(
+                "\"{}\" takes {}, over its {} limit",
+                name,
+                crate::storage_quotas::format_bytes(*size),
+                crate::storage_quotas::format_bytes(*limit_bytes)
+            ),
+            _ => String::new(),
+        };
+
+        Notification {
+            kind,
+            title: title.to_string(),
+            body,
+        }
+    }
+
+    async fn send(&self, notification: &Notification) -> Result<(), String> {
+        let mut request = self
+            .client
+            .post(format!("{}/api/notify", self.bridge_url))
+            .json(notification);
+        if let Some(token) = self.auth.value() {
+            request = request.bearer_auth(token);
+        }
+        let response = request.send().await.map_err(|e| e.to_string())?;
+        if !response.status().is_success() {
+            return Err(format!("bridge returned {}", response.status()));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run_event(status: WorkflowStatus) -> Event {
+        Event::WorkflowRun {
+            workflow_id: "wf".to_string(),
+            run_id: "run-1".to_string(),
+            status,
+            current_step_id: Some("build".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_finished_events() {
+        assert_eq!(finished(&run_event(WorkflowStatus::Completed)), Some(NotificationKind::WorkflowCompleted));
+        assert_eq!(finished(&run_event(WorkflowStatus::Failed)), Some(NotificationKind::WorkflowFailed));
+        assert_eq!(finished(&run_event(WorkflowStatus::Cancelled)), None);
+        assert_eq!(finished(&run_event(WorkflowStatus::Running)), None);
+
+        let execution = |status: &str| Event::AgentExecution {
+            agent_id: "a".to_string(),
+            execution_id: "e".to_string(),
+            status: status.to_string(),
+        };
+        assert_eq!(finished(&execution("failed")), Some(NotificationKind::AgentFailed));
+        assert_eq!(finished(&execution("running")), None);
+        assert_eq!(
+            finished(&Event::AgentSession {
+                session_id: "s".to_string(),
+                state: AgentState::Ready,
+            }),
+            Some(NotificationKind::AgentCompleted)
+        );
+        assert_eq!(finished(&Event::IndexingStarted { paths: vec![] }), None);
+
+        let quota = |exceeded| Event::StorageQuota {
+            quota_id: "q".to_string(),
+            name: "Downloads".to_string(),
+            size: 60,
+            limit_bytes: 50,
+            exceeded,
+        };
+        assert_eq!(finished(&quota(true)), Some(NotificationKind::QuotaExceeded));
+        assert_eq!(finished(&quota(false)), None);
+    }
+
+    #[tokio::test]
+    async fn test_repeated_outcomes_are_shown_once() {
+        // Nothing listens on this port, so sends fail quietly
+        let mut notifier = Notifier::new(Some("http://127.0.0.1:9".to_string()));
+        notifier.handle(&run_event(WorkflowStatus::Failed)).await;
+        notifier.handle(&run_event(WorkflowStatus::Failed)).await;
+        assert_eq!(notifier.notified.len(), 1);
+
+        // Resuming the run makes its next failure notable again
+        notifier.handle(&run_event(WorkflowStatus::Running)).await;
+        assert!(notifier.notified.is_empty());
+
+        let description = notifier.describe(NotificationKind::WorkflowFailed, &run_event(WorkflowStatus::Failed)).await;
+        assert_eq!(description.body, "\"wf\" failed at step build");
+    }
+
+    #[test]
+    fn test_preferences() {
+        let noon = chrono::NaiveTime::from_hms_opt(12, 0, 0).unwrap();
+        let mut settings = NotificationSettings::default();
+        assert!(should_notify(&settings, NotificationKind::AgentCompleted, noon));
+
+        settings.agent_completed = false;
+        assert!(!should_notify(&settings, NotificationKind::AgentCompleted, noon));
+        assert!(should_notify(&settings, NotificationKind::AgentFailed, noon));
+
+        settings.quiet_hours_start = Some("11:00".to_string());
+        settings.quiet_hours_end = Some("13:00".to_string());
+        assert!(!should_notify(&settings, NotificationKind::AgentFailed, noon));
+
+        settings.quiet_hours_start = None;
+        settings.enabled = false;
+        assert!(!should_notify(&settings, NotificationKind::WorkflowFailed, noon));
+    }
+}
@@ -0,0 +1,239 @@
+//! Plugin manifests (`plugin.toml`)
+//!
+//! ```toml
+//! name = "weather"
+//! version = "0.1.0"
+//! description = "Weather forecasts"
+//! command = "./weather.py"
+//!
+//! [permissions]
+//! env = ["OPENWEATHER_API_KEY"]
+//!
+//! [[tools]]
+//! name = "forecast"
+//! description = "Forecast for a city"
+//! parameters = { type = "object", required = ["city"], properties = { city = { type = "string" } } }
+//! ```
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use super::{qualified_name, PluginError, MAX_TOOL_NAME_LEN};
+use crate::cli_agent::tools::ToolParameters;
+
+/// File a plugin directory must contain
+pub const MANIFEST_FILE: &str = "plugin.toml";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PluginManifest {
+    /// Identifier used in tool names; lowercase letters, digits, `-` and `_`
+    pub name: String,
+    #[serde(default)]
+    pub version: String,
+    #[serde(default)]
+    pub description: String,
+    /// Executable to run, relative to the plugin directory or on PATH
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub permissions: PluginPermissions,
+    #[serde(default)]
+    pub limits: PluginLimits,
+    pub tools: Vec<PluginTool>,
+}
+
+/// What a plugin may do beyond computing an answer
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PluginPermissions {
+    /// Modifies files; only runs when the agent may write
+    pub write: bool,
+    /// Environment variables passed through from the backend; everything
+    /// else except PATH is withheld
+    pub env: Vec<String>,
+}
+
+/// Resource limits for one call
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PluginLimits {
+    pub timeout_secs: u64,
+    pub max_cpu_secs: Option<u64>,
+    pub max_memory_mb: Option<u64>,
+    /// Largest response accepted on stdout
+    pub max_output_kb: usize,
+}
+
+impl Default for PluginLimits {
+    fn default() -> Self {
+        Self {
+            timeout_secs: 30,
+            max_cpu_secs: Some(30),
+            max_memory_mb: Some(512),
+            max_output_kb: 1024,
+        }
+    }
+}
+
+impl PluginLimits {
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_secs.max(1))
+    }
+
+    /// These limits, lowered to `max` where they ask for more. A limit left
+    /// unset takes the maximum.
+    pub fn clamped(&self, max: &PluginLimits) -> PluginLimits {
+        let lowest = |value: Option<u64>, max: Option<u64>| match (value, max) {
+            (Some(value), Some(max)) => Some(value.min(max)),
+            (value, max) => value.or(max),
+        };
+        PluginLimits {
+            timeout_secs: self.timeout_secs.min(max.timeout_secs),
+            max_cpu_secs: lowest(self.max_cpu_secs, max.max_cpu_secs),
+            max_memory_mb: lowest(self.max_memory_mb, max.max_memory_mb),
+            max_output_kb: self.max_output_kb.min(max.max_output_kb),
+        }
+    }
+}
+
+/// A tool the plugin provides
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PluginTool {
+    pub name: String,
+    pub description: String,
+    pub parameters: ToolParameters,
+}
+
+impl PluginManifest {
+    /// Read and validate the manifest in a plugin directory
+    pub fn load(dir: &Path) -> Result<Self, PluginError> {
+        let path = dir.join(MANIFEST_FILE);
+        let text = std::fs::read_to_string(&path)
+            .map_err(|e| PluginError::Manifest(format!("cannot read {}: {}", path.display(), e)))?;
+        let manifest: PluginManifest =
+            toml::from_str(&text).map_err(|e| PluginError::Manifest(format!("{}: {}", path.display(), e)))?;
+        manifest.validate()?;
+        Ok(manifest)
+    }
+
+    pub fn validate(&self) -> Result<(), PluginError> {
+        let valid_name = |name: &str| {
+            !name.is_empty()
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
+        };
+        if !valid_name(&self.name) {
+            return Err(PluginError::Manifest(format!(
+                "plugin name '{}' may only contain lowercase letters, digits, '-' and '_'",
+                self.name
+            )));
+        }
+        if self.command.trim().is_empty() {
+            return Err(PluginError::Manifest(format!("plugin '{}' has no command", self.name)));
+        }
+        if self.tools.is_empty() {
+            return Err(PluginError::Manifest(format!("plugin '{}' declares no tools", self.name)));
+        }
+        for (i, tool) in self.tools.iter().enumerate() {
+            if !valid_name(&tool.name) {
+                return Err(PluginError::Manifest(format!(
+                    "tool name '{}' may only contain lowercase letters, digits, '-' and '_'",
+                    tool.name
+                )));
+            }
+            if qualified_name(&self.name, &tool.name).len() > MAX_TOOL_NAME_LEN {
+                return Err(PluginError::Manifest(format!(
+                    "plugin and tool names '{}' / '{}' are too long",
+                    self.name, tool.name
+                )));
+            }
+            if self.tools[..i].iter().any(|t| t.name == tool.name) {
+                return Err(PluginError::Manifest(format!("tool '{}' is declared twice", tool.name)));
+            }
+            if tool.parameters.param_type != "object" {
+                return Err(PluginError::Manifest(format!(
+                    "parameters of tool '{}' must have type \"object\"",
+                    tool.name
+                )));
+            }
+            if let Some(missing) = tool
+                .parameters
+                .required
+                .iter()
+                .find(|r| !tool.parameters.properties.contains_key(*r))
+            {
+                return Err(PluginError::Manifest(format!(
+                    "tool '{}' requires undeclared parameter '{}'",
+                    tool.name, missing
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Program to spawn: paths with a separator resolve against the plugin
+    /// directory, bare names are looked up on PATH
+    pub fn program(&self, dir: &Path) -> PathBuf {
+        let command = Path::new(&self.command);
+        if command.components().count() > 1 {
+            dir.join(command)
+        } else {
+            command.to_path_buf()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"
+name = "weather"
+command = "./weather.py"
+
+[[tools]]
+name = "forecast"
+description = "Forecast for a city"
+parameters = { type = "object", required = ["city"], properties = { city = { type = "string" } } }
+"#;
+
+    #[test]
+    fn test_parse_and_validate() {
+        let manifest: PluginManifest = toml::from_str(MANIFEST).unwrap();
+        assert!(manifest.validate().is_ok());
+        assert_eq!(manifest.limits, PluginLimits::default());
+        assert!(!manifest.permissions.write);
+        assert_eq!(manifest.program(Path::new("/plugins/weather")), PathBuf::from("/plugins/weather/weather.py"));
+
+        let mut bad = manifest.clone();
+        bad.name = "Weather App".to_string();
+        assert!(bad.validate().is_err());
+
+        let mut bad = manifest.clone();
+        bad.tools[0].parameters.required.push("days".to_string());
+        assert!(bad.validate().is_err());
+
+        let mut bad = manifest.clone();
+        bad.tools.push(manifest.tools[0].clone());
+        assert!(bad.validate().is_err());
+
+        assert!(toml::from_str::<PluginManifest>(&format!("{}\nunknown = 1", MANIFEST)).is_err());
+    }
+
+    #[test]
+    fn test_limits_clamped_to_host() {
+        let asked = PluginLimits { timeout_secs: 600, max_cpu_secs: None, max_memory_mb: Some(64), max_output_kb: 1 << 20 };
+        let clamped = asked.clamped(&PluginLimits::default());
+        assert_eq!(
+            clamped,
+            PluginLimits { timeout_secs: 30, max_cpu_secs: Some(30), max_memory_mb: Some(64), max_output_kb: 1024 }
+        );
+        let unbounded = PluginLimits { max_cpu_secs: None, max_memory_mb: None, ..Default::default() };
+        assert_eq!(asked.clamped(&unbounded).max_cpu_secs, None);
+    }
+}
@@ -0,0 +1,59 @@
+//! Agent tool plugins
+//!
+//! Each subdirectory of the plugins directory (`~/.skhoot/plugins` by
+//! default) holding a `plugin.toml` is a plugin: an executable plus a
+//! manifest declaring its tools and their parameter schemas. The tools are
+//! registered in the agent's [`ToolRegistry`](crate::cli_agent::ToolRegistry)
+//! as `plugin__<plugin>__<tool>`, and each call runs the executable with a
+//! cleared environment and resource limits, speaking JSON-RPC over stdio
+//! (see [`runner`]). The directory is rescanned while running, so adding,
+//! editing or removing a plugin takes effect without a restart.
+
+pub mod manifest;
+pub mod registry;
+pub mod runner;
+
+pub use manifest::{PluginLimits, PluginManifest, PluginPermissions, PluginTool, MANIFEST_FILE};
+pub use registry::{PluginInfo, PluginRegistry};
+
+use thiserror::Error;
+
+/// Prefix of the names plugin tools are registered under
+pub const TOOL_PREFIX: &str = "plugin__";
+
+/// Longest tool name model providers accept
+const MAX_TOOL_NAME_LEN: usize = 64;
+
+#[derive(Debug, Error)]
+pub enum PluginError {
+    #[error("Invalid plugin manifest: {0}")]
+    Manifest(String),
+
+    #[error("Unknown plugin tool: {0}")]
+    UnknownTool(String),
+
+    #[error("Plugin needs write permission: {0}")]
+    PermissionDenied(String),
+
+    #[error("Failed to start plugin: {0}")]
+    Spawn(String),
+
+    #[error("Plugin timed out after {0}s")]
+    Timeout(u64),
+
+    #[error("Invalid plugin response: {0}")]
+    Protocol(String),
+
+    #[error("Plugin tool failed: {0}")]
+    Failed(String),
+}
+
+/// Name a plugin's tool is registered under
+pub fn qualified_name(plugin: &str, tool: &str) -> String {
+    format!("{}{}__{}", TOOL_PREFIX, plugin, tool)
+}
+
+/// Whether a tool name belongs to a plugin
+pub fn is_plugin_tool(name: &str) -> bool {
+    name.starts_with(TOOL_PREFIX)
+}
@@ -0,0 +1,333 @@
+//! Installed plugins, rescanned when the plugins directory changes
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::SystemTime;
+
+use super::manifest::{PluginManifest, PluginPermissions, MANIFEST_FILE};
+use super::{qualified_name, runner, PluginError};
+use crate::cli_agent::tools::ToolDefinition;
+use crate::config::{PluginSettings, SettingsStore};
+
+/// An installed plugin, or a directory that failed to load
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginInfo {
+    pub name: String,
+    pub version: String,
+    pub description: String,
+    pub path: PathBuf,
+    /// Names the agent calls the tools by
+    pub tools: Vec<String>,
+    pub permissions: PluginPermissions,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+struct LoadedPlugin {
+    manifest: PluginManifest,
+    dir: PathBuf,
+}
+
+#[derive(Default)]
+struct RegistryState {
+    /// None when plugins are disabled
+    directory: Option<PathBuf>,
+    /// Host limits on what plugins may do
+    settings: PluginSettings,
+    /// Manifests and their modification times at the last scan
+    fingerprint: Vec<(PathBuf, Option<SystemTime>)>,
+    plugins: Vec<Arc<LoadedPlugin>>,
+    failed: Vec<PluginInfo>,
+}
+
+/// Plugins loaded from the plugins directory
+#[derive(Default)]
+pub struct PluginRegistry {
+    state: RwLock<RegistryState>,
+}
+
+lazy_static::lazy_static! {
+    static ref GLOBAL_REGISTRY: Arc<PluginRegistry> = {
+        let registry = PluginRegistry::default();
+        registry.configure(&SettingsStore::global().get().plugins);
+        Arc::new(registry)
+    };
+}
+
+impl PluginRegistry {
+    pub fn global() -> Arc<PluginRegistry> {
+        GLOBAL_REGISTRY.clone()
+    }
+
+    /// Registry scanning `directory`
+    pub fn new(directory: PathBuf) -> Self {
+        let registry = Self::default();
+        registry.set_directory(Some(directory));
+        registry
+    }
+
+    /// Apply the `plugins` settings section
+    pub fn configure(&self, settings: &PluginSettings) {
+        self.state.write().unwrap().settings = settings.clone();
+        self.set_directory(settings.enabled.then(|| settings.directory()));
+    }
+
+    fn set_directory(&self, directory: Option<PathBuf>) {
+        self.state.write().unwrap().directory = directory;
+        self.reload();
+    }
+
+    /// Rescan the directory
+    pub fn reload(&self) {
+        let directory = self.state.read().unwrap().directory.clone();
+        let fingerprint = directory.as_deref().map(fingerprint).unwrap_or_default();
+        let (plugins, failed) = directory.as_deref().map(load_all).unwrap_or_default();
+        if !plugins.is_empty() || !failed.is_empty() {
+            tracing::info!("Loaded {} plugins ({} failed)", plugins.len(), failed.len());
+        }
+
+        let mut state = self.state.write().unwrap();
+        state.fingerprint = fingerprint;
+        state.plugins = plugins;
+        state.failed = failed;
+    }
+
+    /// Rescan if a manifest was added, removed or modified since the last
+    /// scan. Returns whether it did.
+    pub fn reload_if_changed(&self) -> bool {
+        let changed = {
+            let state = self.state.read().unwrap();
+            match &state.directory {
+                Some(directory) => fingerprint(directory) != state.fingerprint,
+                None => false,
+            }
+        };
+        if changed {
+            self.reload();
+        }
+        changed
+    }
+
+    /// Loaded plugins followed by the ones that failed, by name
+    pub fn plugins(&self) -> Vec<PluginInfo> {
+        let state = self.state.read().unwrap();
+        let mut plugins: Vec<_> = state
+            .plugins
+            .iter()
+            .map(|plugin| PluginInfo {
+                name: plugin.manifest.name.clone(),
+                version: plugin.manifest.version.clone(),
+                description: plugin.manifest.description.clone(),
+                path: plugin.dir.clone(),
+                tools: plugin
+                    .manifest
+                    .tools
+                    .iter()
+                    .map(|tool| qualified_name(&plugin.manifest.name, &tool.name))
+                    .collect(),
+                permissions: plugin.manifest.permissions.clone(),
+                error: None,
+            })
+            .collect();
+        plugins.sort_by(|a, b| a.name.cmp(&b.name));
+        plugins.extend(state.failed.iter().cloned());
+        plugins
+    }
+
+    /// Definitions to add to an agent's tool registry
+    pub fn tool_definitions(&self) -> Vec<ToolDefinition> {
+        let state = self.state.read().unwrap();
+        state
+            .plugins
+            .iter()
+            .flat_map(|plugin| {
+                plugin.manifest.tools.iter().map(|tool| ToolDefinition {
+                    name: qualified_name(&plugin.manifest.name, &tool.name),
+                    description: tool.description.clone(),
+                    parameters: tool.parameters.clone(),
+                })
+            })
+            .collect()
+    }
+
+    /// Run a tool by the name it is registered under
+    pub async fn call_tool(
+        &self,
+        name: &str,
+        arguments: Value,
+        working_directory: &Path,
+        allow_writes: bool,
+    ) -> Result<String, PluginError> {
+        let (plugin, tool, settings) = {
+            let state = self.state.read().unwrap();
+            let (plugin, tool) = state
+                .plugins
+                .iter()
+                .find_map(|plugin| {
+                    plugin
+                        .manifest
+                        .tools
+                        .iter()
+                        .find(|tool| qualified_name(&plugin.manifest.name, &tool.name) == name)
+                        .map(|tool| (plugin.clone(), tool.name.clone()))
+                })
+                .ok_or_else(|| PluginError::UnknownTool(name.to_string()))?;
+            (plugin, tool, state.settings.clone())
+        };
+        if plugin.manifest.permissions.write && !settings.allow_write {
+            return Err(PluginError::PermissionDenied(format!(
+                "plugin '{}' modifies files and writing plugins are disabled in settings",
+                plugin.manifest.name
+            )));
+        }
+        if plugin.manifest.permissions.write && !allow_writes {
+            return Err(PluginError::PermissionDenied(format!(
+                "plugin '{}' modifies files and write operations are disabled",
+                plugin.manifest.name
+            )));
+        }
+        let limits = plugin.manifest.limits.clamped(&settings.max_limits);
+        runner::call_tool(&plugin.manifest, &plugin.dir, &tool, arguments, working_directory, &limits).await
+    }
+}
+
+fn plugin_dirs(directory: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(directory) else {
+        return Vec::new();
+    };
+    let mut dirs: Vec<_> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.join(MANIFEST_FILE).is_file())
+        .collect();
+    dirs.sort();
+    dirs
+}
+
+fn fingerprint(directory: &Path) -> Vec<(PathBuf, Option<SystemTime>)> {
+    plugin_dirs(directory)
+        .into_iter()
+        .map(|dir| {
+            let manifest = dir.join(MANIFEST_FILE);
+            let modified = std::fs::metadata(&manifest).and_then(|m| m.modified()).ok();
+            (manifest, modified)
+        })
+        .collect()
+}
+
+fn load_all(directory: &Path) -> (Vec<Arc<LoadedPlugin>>, Vec<PluginInfo>) {
+    let mut plugins: Vec<Arc<LoadedPlugin>> = Vec::new();
+    let mut failed = Vec::new();
+    for dir in plugin_dirs(directory) {
+        let result = PluginManifest::load(&dir).and_then(|manifest| {
+            if plugins.iter().any(|p| p.manifest.name == manifest.name) {
+                return Err(PluginError::Manifest(format!(
+                    "another plugin is already named '{}'",
+                    manifest.name
+                )));
+            }
+            Ok(manifest)
+        });
+        match result {
+            Ok(manifest) => plugins.push(Arc::new(LoadedPlugin { manifest, dir })),
+            Err(e) => {
+                tracing::warn!("Skipping plugin in {}: {}", dir.display(), e);
+                failed.push(PluginInfo {
+                    name: dir
+                        .file_name()
+                        .map(|n| n.to_string_lossy().into_owned())
+                        .unwrap_or_default(),
+                    version: String::new(),
+                    description: String::new(),
+                    path: dir,
+                    tools: Vec::new(),
+                    permissions: PluginPermissions::default(),
+                    error: Some(e.to_string()),
+                });
+            }
+        }
+    }
+    (plugins, failed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn install(root: &Path, dir: &str, manifest: &str) {
+        let dir = root.join(dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join(MANIFEST_FILE), manifest).unwrap();
+    }
+
+    fn manifest(name: &str, tool: &str, write: bool) -> String {
+        format!(
+            r#"
+name = "{name}"
+command = "./run"
+permissions = {{ write = {write} }}
+
+[[tools]]
+name = "{tool}"
+description = "A tool"
+parameters = {{ type = "object", properties = {{}} }}
+"#
+        )
+    }
+
+    #[tokio::test]
+    async fn test_load_and_hot_reload() {
+        let root = TempDir::new().unwrap();
+        install(root.path(), "notes", &manifest("notes", "search", false));
+        install(root.path(), "broken", "name = ");
+
+        let registry = PluginRegistry::new(root.path().to_path_buf());
+        let plugins = registry.plugins();
+        assert_eq!(plugins.len(), 2);
+        assert_eq!(plugins[0].tools, vec!["plugin__notes__search".to_string()]);
+        assert!(plugins[1].error.is_some());
+        assert_eq!(registry.tool_definitions()[0].name, "plugin__notes__search");
+        assert!(!registry.reload_if_changed());
+
+        install(root.path(), "files", &manifest("files", "tidy", true));
+        assert!(registry.reload_if_changed());
+        assert_eq!(registry.tool_definitions().len(), 2);
+
+        // Writing plugins are gated before anything is started
+        assert!(matches!(
+            registry
+                .call_tool("plugin__files__tidy", Value::Null, root.path(), false)
+                .await,
+            Err(PluginError::PermissionDenied(_))
+        ));
+        assert!(matches!(
+            registry
+                .call_tool("plugin__files__missing", Value::Null, root.path(), true)
+                .await,
+            Err(PluginError::UnknownTool(_))
+        ));
+
+        std::fs::remove_dir_all(root.path().join("files")).unwrap();
+        assert!(registry.reload_if_changed());
+        assert_eq!(registry.tool_definitions().len(), 1);
+
+        registry.configure(&PluginSettings {
+            directory: Some(root.path().display().to_string()),
+            allow_write: false,
+            ..Default::default()
+        });
+        install(root.path(), "files", &manifest("files", "tidy", true));
+        assert!(registry.reload_if_changed());
+        let refused = registry.call_tool("plugin__files__tidy", Value::Null, root.path(), true).await;
+        assert!(matches!(refused, Err(PluginError::PermissionDenied(reason)) if reason.contains("settings")));
+
+        registry.configure(&PluginSettings {
+            enabled: false,
+            ..Default::default()
+        });
+        assert!(registry.plugins().is_empty());
+    }
+}
@@ -0,0 +1,229 @@
+//! Running plugin tools
+//!
+//! Every call starts the plugin's executable in its directory with a cleared
+//! environment and the given resource limits, writes a single JSON-RPC
+//! request line to stdin and reads the response line from stdout:
+//!
+//! ```text
+//! → {"jsonrpc":"2.0","id":1,"method":"call_tool","params":{"tool":"forecast","arguments":{...},"working_directory":"..."}}
+//! ← {"jsonrpc":"2.0","id":1,"result":{"output":"Sunny, 21°C"}}
+//! ← {"jsonrpc":"2.0","id":1,"error":{"code":1,"message":"Unknown city"}}
+//! ```
+//!
+//! Lines on stdout that aren't the response are ignored; stderr is logged.
+
+use serde_json::{json, Value};
+use std::path::Path;
+use std::process::Stdio;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::process::Command;
+
+use super::manifest::{PluginLimits, PluginManifest};
+use super::PluginError;
+
+/// Variables every plugin gets
+const BASE_ENV: &[&str] = &["PATH", "SYSTEMROOT", "TEMP", "TMP", "TMPDIR"];
+
+/// Run one tool of a plugin installed in `dir`
+pub async fn call_tool(
+    manifest: &PluginManifest,
+    dir: &Path,
+    tool: &str,
+    arguments: Value,
+    working_directory: &Path,
+    limits: &PluginLimits,
+) -> Result<String, PluginError> {
+    let mut command = Command::new(manifest.program(dir));
+    command
+        .args(&manifest.args)
+        .current_dir(dir)
+        .env_clear()
+        .env("SKHOOT_PLUGIN_DIR", dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    for name in BASE_ENV.iter().copied().chain(manifest.permissions.env.iter().map(String::as_str)) {
+        if let Ok(value) = std::env::var(name) {
+            command.env(name, value);
+        }
+    }
+
+    let max_memory_bytes = limits.max_memory_mb.map(|mb| mb.saturating_mul(1024 * 1024));
+    // Apply CPU and memory limits in the child before exec
+    #[cfg(unix)]
+    crate::cli_bridge::limits::set_rlimits(&mut command, limits.max_cpu_secs, max_memory_bytes);
+
+    let mut child = command
+        .spawn()
+        .map_err(|e| PluginError::Spawn(format!("{}: {}", manifest.command, e)))?;
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let stdout = child.stdout.take().expect("stdout is piped");
+    let stderr = child.stderr.take().expect("stderr is piped");
+    #[cfg(windows)]
+    if limits.max_cpu_secs.is_some() || max_memory_bytes.is_some() {
+        crate::cli_bridge::limits::assign_job(&child, limits.max_cpu_secs, max_memory_bytes)
+            .map_err(|e| PluginError::Spawn(format!("cannot apply resource limits: {}", e)))?;
+    }
+
+    // Drained concurrently so a chatty plugin can't block on a full pipe
+    let log = tokio::spawn(async move {
+        let mut lines = BufReader::new(stderr).lines();
+        let mut log = String::new();
+        while let Ok(Some(line)) = lines.next_line().await {
+            if log.len() < 16 * 1024 {
+                log.push_str(&line);
+                log.push('\n');
+            }
+        }
+        log
+    });
+
+    let request = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "call_tool",
+        "params": {
+            "tool": tool,
+            "arguments": arguments,
+            "working_directory": working_directory,
+        },
+    });
+
+    let max_output = limits.max_output_kb as u64 * 1024;
+    let exchange = async {
+        let mut line = request.to_string();
+        line.push('\n');
+        stdin
+            .write_all(line.as_bytes())
+            .await
+            .map_err(|e| PluginError::Protocol(format!("cannot write request: {}", e)))?;
+        drop(stdin);
+
+        let mut lines = BufReader::new(stdout.take(max_output)).lines();
+        while let Some(line) = lines
+            .next_line()
+            .await
+            .map_err(|e| PluginError::Protocol(e.to_string()))?
+        {
+            let Ok(message) = serde_json::from_str::<Value>(&line) else {
+                continue;
+            };
+            if message.get("id") == Some(&json!(1)) {
+                return parse_response(&message);
+            }
+        }
+        Err(PluginError::Protocol("plugin exited without a response".to_string()))
+    };
+
+    let result = match tokio::time::timeout(limits.timeout(), exchange).await {
+        Ok(result) => result,
+        Err(_) => {
+            let _ = child.kill().await;
+            return Err(PluginError::Timeout(limits.timeout_secs));
+        }
+    };
+
+    let _ = child.kill().await;
+    let log = log.await.unwrap_or_default();
+    if !log.trim().is_empty() {
+        tracing::debug!("[plugin:{}] {}", manifest.name, log.trim_end());
+    }
+    match result {
+        Err(PluginError::Protocol(reason)) if !log.trim().is_empty() => {
+            Err(PluginError::Protocol(format!("{} ({})", reason, last_line(&log))))
+        }
+        result => result,
+    }
+}
+
+fn parse_response(message: &Value) -> Result<String, PluginError> {
+    if let Some(error) = message.get("error") {
+        return Err(PluginError::Failed(
+            error
+                .get("message")
+                .and_then(|m| m.as_str())
+                .unwrap_or("unknown error")
+                .to_string(),
+        ));
+    }
+    match message.get("result") {
+        Some(Value::String(output)) => Ok(output.clone()),
+        Some(result) => match result.get("output") {
+            Some(Value::String(output)) => Ok(output.clone()),
+            Some(output) => Ok(output.to_string()),
+            None => Ok(result.to_string()),
+        },
+        None => Err(PluginError::Protocol("response has neither result nor error".to_string())),
+    }
+}
+
+fn last_line(log: &str) -> &str {
+    log.trim_end().lines().last().unwrap_or_default()
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::plugins::manifest::{PluginLimits, PluginPermissions, PluginTool};
+    use crate::cli_agent::tools::ToolParameters;
+    use std::collections::HashMap;
+
+    fn shell_plugin(script: &str) -> PluginManifest {
+        PluginManifest {
+            name: "test".to_string(),
+            version: String::new(),
+            description: String::new(),
+            command: "sh".to_string(),
+            args: vec!["-c".to_string(), script.to_string()],
+            permissions: PluginPermissions::default(),
+            limits: PluginLimits {
+                timeout_secs: 2,
+                ..Default::default()
+            },
+            tools: vec![PluginTool {
+                name: "echo".to_string(),
+                description: String::new(),
+                parameters: ToolParameters {
+                    param_type: "object".to_string(),
+                    properties: HashMap::new(),
+                    required: vec![],
+                },
+            }],
+        }
+    }
+
+    async fn run(script: &str) -> Result<String, PluginError> {
+        let dir = tempfile::TempDir::new().unwrap();
+        let manifest = shell_plugin(script);
+        call_tool(&manifest, dir.path(), "echo", json!({}), dir.path(), &manifest.limits).await
+    }
+
+    #[tokio::test]
+    async fn test_call_tool() {
+        let output = run(r#"read request; echo "starting"; echo '{"jsonrpc":"2.0","id":1,"result":{"output":"hi"}}'"#)
+            .await
+            .unwrap();
+        assert_eq!(output, "hi");
+
+        // The environment is cleared
+        std::env::set_var("SKHOOT_PLUGIN_TEST_SECRET", "x");
+        let output = run(r#"read request; echo "{\"jsonrpc\":\"2.0\",\"id\":1,\"result\":\"[$SKHOOT_PLUGIN_TEST_SECRET]\"}""#)
+            .await
+            .unwrap();
+        assert_eq!(output, "[]");
+    }
+
+    #[tokio::test]
+    async fn test_call_tool_failures() {
+        let error = run(r#"read request; echo '{"jsonrpc":"2.0","id":1,"error":{"code":1,"message":"Unknown city"}}'"#)
+            .await
+            .unwrap_err();
+        assert!(matches!(error, PluginError::Failed(ref m) if m == "Unknown city"));
+
+        let error = run("read request; echo 'bad input' >&2; exit 1").await.unwrap_err();
+        assert!(matches!(error, PluginError::Protocol(ref m) if m.contains("bad input")));
+
+        assert!(matches!(run("sleep 10").await, Err(PluginError::Timeout(2))));
+    }
+}
@@ -0,0 +1,373 @@
+//! Processes running on the machine
+//!
+//! [`list`] reads every process twice with `sysinfo`, [`SAMPLE_INTERVAL`]
+//! apart, and reports CPU use the way `top` does: 100% is one core kept busy.
+//!
+//! [`kill`] stops a process. Processes the backend started, directly or
+//! through a shell, may be stopped freely. Anything else is only stopped
+//! once the caller says the user confirmed it, and the backend itself and
+//! init are never stopped.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+use sysinfo::{Pid, ProcessRefreshKind, ProcessStatus, ProcessesToUpdate, Signal, System, ThreadKind, UpdateKind, Users};
+
+/// Time between the two samples CPU use is worked out from
+pub const SAMPLE_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Processes listed when no limit is given
+pub const DEFAULT_LIMIT: usize = 25;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ProcessError {
+    #[error("No process with PID {0}")]
+    NotFound(u32),
+
+    #[error("{0}")]
+    Protected(String),
+
+    #[error("{0}")]
+    ConfirmationRequired(String),
+
+    #[error("Permission denied: {0}")]
+    PermissionDenied(String),
+
+    #[error("Failed to read processes: {0}")]
+    Unavailable(String),
+}
+
+/// A running process
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    pub parent_pid: Option<u32>,
+    pub name: String,
+    /// Full command line, or the name in brackets for kernel threads
+    pub cmdline: String,
+    pub user: Option<String>,
+    /// Running, sleeping, stopped, zombie...
+    pub state: Option<String>,
+    pub cpu_percent: f32,
+    /// Resident memory
+    pub memory_bytes: u64,
+    pub memory_percent: f32,
+    /// Started by the backend, directly or not
+    pub child: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProcessSort {
+    #[default]
+    Cpu,
+    Memory,
+    Pid,
+    Name,
+}
+
+/// Which processes [`list`] returns
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ProcessQuery {
+    /// Case-insensitive text the name or command line contains
+    pub filter: Option<String>,
+    #[serde(default)]
+    pub sort: ProcessSort,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProcessList {
+    pub processes: Vec<ProcessInfo>,
+    /// Processes matching the filter, before the limit
+    pub matched: usize,
+    /// Every process on the machine
+    pub total: usize,
+    pub cpu_count: usize,
+    pub total_memory_bytes: u64,
+}
+
+/// A process with what only a closer look tells
+#[derive(Debug, Clone, Serialize)]
+pub struct ProcessDetails {
+    #[serde(flatten)]
+    pub process: ProcessInfo,
+    pub executable: Option<PathBuf>,
+    pub working_directory: Option<PathBuf>,
+    pub threads: Option<u32>,
+    /// Direct children
+    pub children: Vec<ProcessInfo>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct KillOutcome {
+    pub pid: u32,
+    pub name: String,
+    /// `SIGTERM`, or `SIGKILL` when forced
+    pub signal: String,
+    pub child: bool,
+}
+
+/// What is read of every process
+fn refresh_kind() -> ProcessRefreshKind {
+    ProcessRefreshKind::nothing()
+        .with_cpu()
+        .with_memory()
+        .with_cmd(UpdateKind::OnlyIfNotSet)
+        .with_user(UpdateKind::OnlyIfNotSet)
+}
+
+/// Every process read twice, [`SAMPLE_INTERVAL`] apart, so CPU use is known
+fn sample(kind: ProcessRefreshKind) -> Result<System, ProcessError> {
+    if !sysinfo::IS_SUPPORTED_SYSTEM {
+        return Err(ProcessError::Unavailable("process listing isn't supported on this system".to_string()));
+    }
+    let mut system = System::new();
+    system.refresh_processes_specifics(ProcessesToUpdate::All, true, kind);
+    std::thread::sleep(SAMPLE_INTERVAL);
+    system.refresh_processes_specifics(ProcessesToUpdate::All, true, kind);
+    system.refresh_memory();
+    Ok(system)
+}
+
+/// The processes of `system`, leaving out the threads Linux lists as tasks
+fn collect(system: &System) -> Vec<ProcessInfo> {
+    let users = Users::new_with_refreshed_list();
+    let processes = system
+        .processes()
+        .values()
+        .filter(|p| p.thread_kind() != Some(ThreadKind::Userland))
+        .map(|p| {
+            let name = p.name().to_string_lossy().into_owned();
+            let cmdline = p.cmd().iter().map(|arg| arg.to_string_lossy()).collect::<Vec<_>>().join(" ");
+            ProcessInfo {
+                pid: p.pid().as_u32(),
+                parent_pid: p.parent().map(Pid::as_u32).filter(|&ppid| ppid != 0),
+                cmdline: if cmdline.is_empty() { format!("[{}]", name) } else { cmdline },
+                name,
+                user: p.user_id().and_then(|uid| users.get_user_by_id(uid)).map(|u| u.name().to_string()),
+                state: state_name(p.status()),
+                cpu_percent: p.cpu_usage(),
+                memory_bytes: p.memory(),
+                memory_percent: 0.0,
+                child: false,
+            }
+        })
+        .collect();
+    finish(processes, system.total_memory())
+}
+
+/// Every process, with CPU use measured over [`SAMPLE_INTERVAL`]. Blocks
+/// for that long.
+pub fn snapshot() -> Result<(Vec<ProcessInfo>, usize, u64), ProcessError> {
+    let system = sample(refresh_kind())?;
+    Ok((collect(&system), cpu_count(&system), system.total_memory()))
+}
+
+fn cpu_count(system: &System) -> usize {
+    match system.cpus().len() {
+        0 => std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+        count => count,
+    }
+}
+
+/// Fill in what needs every process: memory share and which were started
+/// by the backend
+fn finish(mut processes: Vec<ProcessInfo>, total_memory: u64) -> Vec<ProcessInfo> {
+    let parents: HashMap<u32, Option<u32>> = processes.iter().map(|p| (p.pid, p.parent_pid)).collect();
+    let own = std::process::id();
+    for process in &mut processes {
+        if total_memory > 0 {
+            process.memory_percent = process.memory_bytes as f32 / total_memory as f32 * 100.0;
+        }
+        process.child = descends_from(process.pid, own, &parents);
+    }
+    processes
+}
+
+/// Whether `ancestor` is a parent of `pid`, or a parent's parent and so on
+fn descends_from(pid: u32, ancestor: u32, parents: &HashMap<u32, Option<u32>>) -> bool {
+    let mut current = pid;
+    // Bounded in case PIDs were reused into a loop between reads
+    for _ in 0..parents.len() {
+        match parents.get(&current).copied().flatten() {
+            Some(parent) if parent == ancestor => return true,
+            Some(parent) if parent != current => current = parent,
+            _ => return false,
+        }
+    }
+    false
+}
+
+/// Filter, sort and cut `processes` as `query` asks
+pub fn select(mut processes: Vec<ProcessInfo>, query: &ProcessQuery) -> (Vec<ProcessInfo>, usize) {
+    if let Some(filter) = query.filter.as_deref().map(str::to_lowercase).filter(|f| !f.is_empty()) {
+        processes.retain(|p| p.name.to_lowercase().contains(&filter) || p.cmdline.to_lowercase().contains(&filter));
+    }
+    match query.sort {
+        ProcessSort::Cpu => processes.sort_by(|a, b| {
+            b.cpu_percent.total_cmp(&a.cpu_percent).then(b.memory_bytes.cmp(&a.memory_bytes))
+        }),
+        ProcessSort::Memory => processes.sort_by_key(|p| std::cmp::Reverse(p.memory_bytes)),
+        ProcessSort::Pid => processes.sort_by_key(|p| p.pid),
+        ProcessSort::Name => processes.sort_by_key(|p| p.name.to_lowercase()),
+    }
+    let matched = processes.len();
+    processes.truncate(query.limit.unwrap_or(DEFAULT_LIMIT));
+    (processes, matched)
+}
+
+/// Processes matching `query`. Blocks for [`SAMPLE_INTERVAL`].
+pub fn list(query: &ProcessQuery) -> Result<ProcessList, ProcessError> {
+    let (processes, cpu_count, total_memory_bytes) = snapshot()?;
+    let total = processes.len();
+    let (processes, matched) = select(processes, query);
+    Ok(ProcessList { processes, matched, total, cpu_count, total_memory_bytes })
+}
+
+/// One process and its children. Blocks for [`SAMPLE_INTERVAL`].
+pub fn inspect(pid: u32) -> Result<ProcessDetails, ProcessError> {
+    let kind = refresh_kind().with_exe(UpdateKind::OnlyIfNotSet).with_cwd(UpdateKind::OnlyIfNotSet);
+    let system = sample(kind)?;
+    let found = system.process(Pid::from_u32(pid)).ok_or(ProcessError::NotFound(pid))?;
+    let (executable, working_directory) = (found.exe().map(PathBuf::from), found.cwd().map(PathBuf::from));
+    let threads = found.tasks().map(|tasks| tasks.len() as u32);
+
+    let processes = collect(&system);
+    let process = processes.iter().find(|p| p.pid == pid).cloned().ok_or(ProcessError::NotFound(pid))?;
+    let children = processes.into_iter().filter(|p| p.parent_pid == Some(pid)).collect();
+    Ok(ProcessDetails { process, executable, working_directory, threads, children })
+}
+
+/// Stop a process, with SIGKILL when `force` is set. A process the backend
+/// didn't start is only stopped when `confirmed`.
+pub fn kill(pid: u32, force: bool, confirmed: bool) -> Result<KillOutcome, ProcessError> {
+    if pid <= 1 || pid == std::process::id() {
+        return Err(ProcessError::Protected(format!("Process {} can't be stopped from Skhoot", pid)));
+    }
+    let mut system = System::new();
+    system.refresh_processes_specifics(ProcessesToUpdate::All, true, ProcessRefreshKind::nothing());
+    let process = collect(&system).into_iter().find(|p| p.pid == pid).ok_or(ProcessError::NotFound(pid))?;
+    if !process.child && !confirmed {
+        return Err(ProcessError::ConfirmationRequired(format!(
+            "{} (PID {}) was not started by Skhoot; stopping it needs the user's confirmation",
+            process.name, pid
+        )));
+    }
+
+    let target = system.process(Pid::from_u32(pid)).ok_or(ProcessError::NotFound(pid))?;
+    // Windows has no SIGTERM; the process is terminated either way
+    let sent = if force { target.kill() } else { target.kill_with(Signal::Term).unwrap_or_else(|| target.kill()) };
+    if !sent {
+        return Err(ProcessError::PermissionDenied(format!("not allowed to stop process {}", pid)));
+    }
+    tracing::info!("Stopped process {} ({}), forced: {}", pid, process.name, force);
+    Ok(KillOutcome {
+        pid,
+        name: process.name,
+        signal: if force { "SIGKILL" } else { "SIGTERM" }.to_string(),
+        child: process.child,
+    })
+}
+
+/// Readable name of a process state
+pub fn state_name(status: ProcessStatus) -> Option<String> {
+    let name = match status {
+        ProcessStatus::Run => "running",
+        ProcessStatus::Sleep => "sleeping",
+        ProcessStatus::UninterruptibleDiskSleep | ProcessStatus::LockBlocked => "waiting",
+        ProcessStatus::Idle => "idle",
+        ProcessStatus::Stop | ProcessStatus::Tracing => "stopped",
+        ProcessStatus::Zombie => "zombie",
+        ProcessStatus::Dead => "dead",
+        ProcessStatus::Unknown(_) => return None,
+        other => return Some(other.to_string().to_lowercase()),
+    };
+    Some(name.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(pid: u32, parent_pid: Option<u32>, name: &str, cpu_percent: f32, memory_bytes: u64) -> ProcessInfo {
+        ProcessInfo {
+            pid,
+            parent_pid,
+            name: name.to_string(),
+            cmdline: format!("/usr/bin/{} --serve", name),
+            user: None,
+            state: None,
+            cpu_percent,
+            memory_bytes,
+            memory_percent: 0.0,
+            child: false,
+        }
+    }
+
+    #[test]
+    fn test_state_names() {
+        assert_eq!(state_name(ProcessStatus::Sleep).as_deref(), Some("sleeping"));
+        assert_eq!(state_name(ProcessStatus::UninterruptibleDiskSleep).as_deref(), Some("waiting"));
+        assert_eq!(state_name(ProcessStatus::Unknown(0)), None);
+    }
+
+    #[test]
+    fn test_select_and_descendants() {
+        let processes = vec![
+            process(10, Some(1), "postgres", 2.0, 500),
+            process(11, Some(10), "postgres", 40.0, 100),
+            process(20, Some(11), "node", 95.5, 900),
+            process(30, None, "Xorg", 0.0, 2000),
+        ];
+        let (top, matched) = select(processes.clone(), &ProcessQuery { limit: Some(2), ..Default::default() });
+        assert_eq!(matched, 4);
+        assert_eq!(top.iter().map(|p| p.pid).collect::<Vec<_>>(), [20, 11]);
+
+        let query = ProcessQuery { filter: Some("POST".to_string()), sort: ProcessSort::Memory, limit: None };
+        let (postgres, _) = select(processes.clone(), &query);
+        assert_eq!(postgres.iter().map(|p| p.pid).collect::<Vec<_>>(), [10, 11]);
+
+        let parents = processes.iter().map(|p| (p.pid, p.parent_pid)).collect();
+        assert!(descends_from(20, 10, &parents));
+        assert!(!descends_from(10, 20, &parents));
+        assert!(!descends_from(30, 10, &parents));
+    }
+
+    /// PID of a `sleep` started through a shell that has already exited, so
+    /// it is reparented away from the test
+    #[cfg(unix)]
+    fn spawn_orphan() -> u32 {
+        let output = std::process::Command::new("sh")
+            .args(["-c", "sleep 30 >/dev/null 2>&1 & echo $!"])
+            .output()
+            .unwrap();
+        String::from_utf8_lossy(&output.stdout).trim().parse().unwrap()
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_kill_needs_confirmation_unless_child() {
+        let mut child = std::process::Command::new("sleep").arg("30").spawn().unwrap();
+        let pid = child.id();
+
+        let (processes, cpu_count, _) = snapshot().unwrap();
+        assert!(cpu_count >= 1);
+        let sleeping = processes.iter().find(|p| p.pid == pid).unwrap();
+        assert!(sleeping.child);
+        assert_eq!(sleeping.cmdline, "sleep 30");
+
+        let outcome = kill(pid, false, false).unwrap();
+        assert_eq!((outcome.signal.as_str(), outcome.child), ("SIGTERM", true));
+        assert!(!child.wait().unwrap().success());
+
+        // A process whose shell exited is no longer ours and needs confirmation
+        let orphan = spawn_orphan();
+        assert!(matches!(kill(orphan, false, false), Err(ProcessError::ConfirmationRequired(_))));
+        let outcome = kill(orphan, true, true).unwrap();
+        assert_eq!((outcome.signal.as_str(), outcome.child), ("SIGKILL", false));
+        assert!(matches!(kill(1, true, true), Err(ProcessError::Protected(_))));
+        assert!(matches!(kill(std::process::id(), true, true), Err(ProcessError::Protected(_))));
+    }
+}
@@ -0,0 +1,203 @@
+//! Trash-aware file deletion
+//!
+//! Deleting moves files and folders to the OS trash (Recycle Bin on Windows,
+//! Trash on macOS, the freedesktop trash on Linux) so the UI can offer undo.
+//! Permanent deletion is an explicit opt-in; callers gate it behind user
+//! approval.
+//!
+//! Restoring needs to list the trash, which the OS only allows on Windows and
+//! Linux. On macOS deleted files are still recoverable from the Finder, but
+//! [`restore`] is unavailable.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Result of deleting a path
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DeleteOutcome {
+    /// Absolute path that was deleted
+    pub path: String,
+    /// Whether the path was removed instead of moved to the trash
+    pub permanent: bool,
+    /// Where [`restore`] puts the item back, when it can be restored
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub restore_path: Option<String>,
+    /// OS identifier of the item in the trash, passed to [`restore`]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trash_id: Option<String>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum DeleteError {
+    #[error("Path not found: {0}")]
+    NotFound(String),
+
+    #[error("Refusing to delete {0}")]
+    Protected(String),
+
+    #[error("Item is no longer in the trash: {0}")]
+    NotInTrash(String),
+
+    #[error("Cannot restore {0}: a file already exists at that location")]
+    RestoreCollision(String),
+
+    #[error("Restoring from the trash is not supported on this platform")]
+    RestoreUnsupported,
+
+    #[error("Trash error: {0}")]
+    Trash(String),
+
+    #[error("Failed to delete {path}: {reason}")]
+    Io { path: String, reason: String },
+}
+
+/// Move `path` to the trash, or remove it for good when `permanent` is set
+pub fn delete(path: &Path, permanent: bool) -> Result<DeleteOutcome, DeleteError> {
+    let path = absolute_path(path)?;
+    let display_path = path.display().to_string();
+
+    if path.parent().is_none() || dirs::home_dir().is_some_and(|home| home == path) {
+        return Err(DeleteError::Protected(display_path));
+    }
+
+    if permanent {
+        let metadata = std::fs::symlink_metadata(&path).map_err(|e| io_error(&path, e))?;
+        let removed = if metadata.is_dir() {
+            std::fs::remove_dir_all(&path)
+        } else {
+            std::fs::remove_file(&path)
+        };
+        removed.map_err(|e| io_error(&path, e))?;
+        tracing::info!("Permanently deleted {}", display_path);
+        return Ok(DeleteOutcome {
+            path: display_path,
+            permanent: true,
+            restore_path: None,
+            trash_id: None,
+        });
+    }
+
+    trash::delete(&path).map_err(|e| DeleteError::Trash(e.to_string()))?;
+    let trash_id = find_trashed(&path);
+    tracing::info!("Moved {} to the trash", display_path);
+
+    Ok(DeleteOutcome {
+        restore_path: trash_id.as_ref().map(|_| display_path.clone()),
+        path: display_path,
+        permanent: false,
+        trash_id,
+    })
+}
+
+/// Put a trashed item back where it was deleted from, returning that path
+#[cfg(any(
+    target_os = "windows",
+    all(unix, not(target_os = "macos"), not(target_os = "ios"), not(target_os = "android"))
+))]
+pub fn restore(trash_id: &str) -> Result<PathBuf, DeleteError> {
+    use trash::os_limited;
+
+    let item = os_limited::list()
+        .map_err(|e| DeleteError::Trash(e.to_string()))?
+        .into_iter()
+        .find(|item| item.id.to_string_lossy() == trash_id)
+        .ok_or_else(|| DeleteError::NotInTrash(trash_id.to_string()))?;
+    let original = item.original_path();
+
+    os_limited::restore_all([item]).map_err(|e| match e {
+        trash::Error::RestoreCollision { path, .. } => DeleteError::RestoreCollision(path.display().to_string()),
+        e => DeleteError::Trash(e.to_string()),
+    })?;
+    tracing::info!("Restored {} from the trash", original.display());
+    Ok(original)
+}
+
+#[cfg(not(any(
+    target_os = "windows",
+    all(unix, not(target_os = "macos"), not(target_os = "ios"), not(target_os = "android"))
+)))]
+pub fn restore(_trash_id: &str) -> Result<PathBuf, DeleteError> {
+    Err(DeleteError::RestoreUnsupported)
+}
+
+/// Trash ID of the most recently trashed item deleted from `path`
+#[cfg(any(
+    target_os = "windows",
+    all(unix, not(target_os = "macos"), not(target_os = "ios"), not(target_os = "android"))
+))]
+fn find_trashed(path: &Path) -> Option<String> {
+    match trash::os_limited::list() {
+        Ok(items) => items
+            .into_iter()
+            .filter(|item| item.original_path() == path)
+            .max_by_key(|item| item.time_deleted)
+            .map(|item| item.id.to_string_lossy().into_owned()),
+        Err(e) => {
+            tracing::warn!("Could not list the trash to find {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+#[cfg(not(any(
+    target_os = "windows",
+    all(unix, not(target_os = "macos"), not(target_os = "ios"), not(target_os = "android"))
+)))]
+fn find_trashed(_path: &Path) -> Option<String> {
+    None
+}
+
+/// Absolute path with its parent resolved; the final component is kept so a
+/// symlink is deleted rather than its target
+fn absolute_path(path: &Path) -> Result<PathBuf, DeleteError> {
+    let not_found = || DeleteError::NotFound(path.display().to_string());
+    std::fs::symlink_metadata(path).map_err(|_| not_found())?;
+
+    let absolute = std::path::absolute(path).map_err(|e| io_error(path, e))?;
+    match (absolute.parent(), absolute.file_name()) {
+        (Some(parent), Some(name)) => Ok(parent.canonicalize().map_err(|e| io_error(parent, e))?.join(name)),
+        _ => Ok(absolute),
+    }
+}
+
+fn io_error(path: &Path, e: std::io::Error) -> DeleteError {
+    DeleteError::Io {
+        path: path.display().to_string(),
+        reason: e.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_permanent_delete() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path().join("scratch");
+        std::fs::create_dir_all(dir.join("nested")).unwrap();
+        std::fs::write(dir.join("nested").join("file.txt"), "data").unwrap();
+
+        let outcome = delete(&dir.join("nested").join("file.txt"), true).unwrap();
+        assert!(outcome.permanent);
+        assert!(outcome.trash_id.is_none());
+        assert!(!dir.join("nested").join("file.txt").exists());
+
+        delete(&dir, true).unwrap();
+        assert!(!dir.exists());
+    }
+
+    #[test]
+    fn test_delete_missing_path() {
+        let temp_dir = TempDir::new().unwrap();
+        let missing = temp_dir.path().join("missing");
+        assert!(matches!(delete(&missing, false), Err(DeleteError::NotFound(_))));
+        assert!(matches!(delete(&missing, true), Err(DeleteError::NotFound(_))));
+    }
+
+    #[test]
+    fn test_refuses_root() {
+        assert!(matches!(delete(Path::new("/"), true), Err(DeleteError::Protected(_))));
+    }
+}
@@ -0,0 +1,398 @@
+//! Run history statistics
+//!
+//! Aggregates persisted workflow runs and agent traces into the figures a
+//! dashboard shows: runs per day, success and failure rates, average run
+//! durations and the tools each agent uses most. Days are UTC calendar days
+//! and every day of the window is listed, including days without activity.
+
+use chrono::{DateTime, Duration, NaiveDate, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::cli_agent::{TraceEntry, TraceEvent};
+use crate::workflows::{WorkflowRun, WorkflowStatus};
+
+/// Days covered when the request doesn't say
+pub const DEFAULT_DAYS: u32 = 30;
+pub const MAX_DAYS: u32 = 365;
+/// Tools listed per agent
+const TOP_TOOLS: usize = 10;
+
+/// Workflow runs started on one day, by how they ended
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RunDay {
+    pub date: NaiveDate,
+    pub total: usize,
+    pub completed: usize,
+    pub failed: usize,
+    pub cancelled: usize,
+}
+
+/// Run counts and timings of one workflow, or of all of them
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RunSummary {
+    pub runs: usize,
+    pub completed: usize,
+    pub failed: usize,
+    pub cancelled: usize,
+    /// Running or waiting for approval
+    pub active: usize,
+    /// Completed share of the runs that completed or failed
+    pub success_rate: Option<f64>,
+    /// Mean wall-clock time of completed runs
+    pub avg_duration_secs: Option<f64>,
+    pub last_run_at: Option<i64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkflowSummary {
+    pub workflow_id: String,
+    /// Name of the workflow, unless it was deleted
+    pub name: Option<String>,
+    #[serde(flatten)]
+    pub summary: RunSummary,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkflowStats {
+    pub days: u32,
+    #[serde(flatten)]
+    pub summary: RunSummary,
+    pub runs_per_day: Vec<RunDay>,
+    /// Workflows with runs in the window, most runs first
+    pub workflows: Vec<WorkflowSummary>,
+}
+
+/// Calls of one tool and how they went
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolUsage {
+    pub tool: String,
+    pub calls: usize,
+    pub failures: usize,
+    pub avg_duration_ms: Option<f64>,
+}
+
+/// Agent activity on one day
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AgentDay {
+    pub date: NaiveDate,
+    pub tool_calls: usize,
+    pub model_requests: usize,
+    /// Agents with any trace entry that day
+    pub active_agents: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AgentSummary {
+    /// Agent (session) the trace belongs to
+    pub agent_id: String,
+    pub tool_calls: usize,
+    pub tool_failures: usize,
+    pub model_requests: usize,
+    pub errors: usize,
+    pub last_active_at: Option<DateTime<Utc>>,
+    /// Most called tools first
+    pub top_tools: Vec<ToolUsage>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AgentStats {
+    pub days: u32,
+    pub tool_calls: usize,
+    pub tool_failures: usize,
+    pub model_requests: usize,
+    pub activity_per_day: Vec<AgentDay>,
+    /// Agents active in the window, most tool calls first
+    pub agents: Vec<AgentSummary>,
+    /// Tool usage across all agents, most called first
+    pub tools: Vec<ToolUsage>,
+}
+
+/// Clamp a requested window to 1..=MAX_DAYS days
+pub fn window_days(days: Option<u32>) -> u32 {
+    days.unwrap_or(DEFAULT_DAYS).clamp(1, MAX_DAYS)
+}
+
+/// First day of a window of `days` days ending today
+fn first_day(days: u32, now: DateTime<Utc>) -> NaiveDate {
+    now.date_naive() - Duration::days(i64::from(days) - 1)
+}
+
+fn day_of(timestamp: i64) -> Option<NaiveDate> {
+    Utc.timestamp_opt(timestamp, 0).single().map(|t| t.date_naive())
+}
+
+fn rate(part: usize, whole: usize) -> Option<f64> {
+    (whole > 0).then(|| part as f64 / whole as f64)
+}
+
+fn mean(values: &[f64]) -> Option<f64> {
+    (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64)
+}
+
+fn summarize(runs: &[&WorkflowRun]) -> RunSummary {
+    let count = |status: WorkflowStatus| runs.iter().filter(|r| r.status == status).count();
+    let completed = count(WorkflowStatus::Completed);
+    let failed = count(WorkflowStatus::Failed);
+    let durations: Vec<f64> = runs
+        .iter()
+        .filter(|r| r.status == WorkflowStatus::Completed)
+        .filter_map(|r| r.completed_at.map(|end| (end - r.started_at).max(0) as f64))
+        .collect();
+
+    RunSummary {
+        runs: runs.len(),
+        completed,
+        failed,
+        cancelled: count(WorkflowStatus::Cancelled),
+        active: count(WorkflowStatus::Running) + count(WorkflowStatus::Paused),
+        success_rate: rate(completed, completed + failed),
+        avg_duration_secs: mean(&durations),
+        last_run_at: runs.iter().map(|r| r.started_at).max(),
+    }
+}
+
+/// Statistics of the runs started in the last `days` days; `names` maps
+/// workflow IDs to names
+pub fn workflow_stats(
+    runs: &[WorkflowRun],
+    names: &HashMap<String, String>,
+    days: u32,
+    now: DateTime<Utc>,
+) -> WorkflowStats {
+    let first = first_day(days, now);
+    let in_window: Vec<&WorkflowRun> = runs
+        .iter()
+        .filter(|r| day_of(r.started_at).is_some_and(|day| day >= first))
+        .collect();
+
+    let mut per_day: BTreeMap<NaiveDate, RunDay> = first
+        .iter_days()
+        .take(days as usize)
+        .map(|date| (date, RunDay { date, ..Default::default() }))
+        .collect();
+    for run in &in_window {
+        if let Some(day) = day_of(run.started_at).and_then(|day| per_day.get_mut(&day)) {
+            day.total += 1;
+            match run.status {
+                WorkflowStatus::Completed => day.completed += 1,
+                WorkflowStatus::Failed => day.failed += 1,
+                WorkflowStatus::Cancelled => day.cancelled += 1,
+                _ => {}
+            }
+        }
+    }
+
+    let mut by_workflow: HashMap<&str, Vec<&WorkflowRun>> = HashMap::new();
+    for run in &in_window {
+        by_workflow.entry(run.workflow_id.as_str()).or_default().push(run);
+    }
+    let mut workflows: Vec<WorkflowSummary> = by_workflow
+        .into_iter()
+        .map(|(id, runs)| WorkflowSummary {
+            workflow_id: id.to_string(),
+            name: names.get(id).cloned(),
+            summary: summarize(&runs),
+        })
+        .collect();
+    workflows.sort_by(|a, b| b.summary.runs.cmp(&a.summary.runs).then_with(|| a.workflow_id.cmp(&b.workflow_id)));
+
+    WorkflowStats {
+        days,
+        summary: summarize(&in_window),
+        runs_per_day: per_day.into_values().collect(),
+        workflows,
+    }
+}
+
+#[derive(Default)]
+struct ToolTally {
+    calls: usize,
+    failures: usize,
+    durations: Vec<f64>,
+}
+
+fn tool_usage(tallies: HashMap<String, ToolTally>, limit: usize) -> Vec<ToolUsage> {
+    let mut usage: Vec<ToolUsage> = tallies
+        .into_iter()
+        .map(|(tool, tally)| ToolUsage {
+            tool,
+            calls: tally.calls,
+            failures: tally.failures,
+            avg_duration_ms: mean(&tally.durations),
+        })
+        .collect();
+    usage.sort_by(|a, b| b.calls.cmp(&a.calls).then_with(|| a.tool.cmp(&b.tool)));
+    usage.truncate(limit);
+    usage
+}
+
+/// Statistics of agent traces over the last `days` days. Tool calls are
+/// counted from their recorded results.
+pub fn agent_stats(traces: &[(String, Vec<TraceEntry>)], days: u32, now: DateTime<Utc>) -> AgentStats {
+    let first = first_day(days, now);
+    let mut per_day: BTreeMap<NaiveDate, AgentDay> = first
+        .iter_days()
+        .take(days as usize)
+        .map(|date| (date, AgentDay { date, ..Default::default() }))
+        .collect();
+    let mut all_tools: HashMap<String, ToolTally> = HashMap::new();
+    let mut agents = Vec::new();
+
+    for (agent_id, entries) in traces {
+        let entries: Vec<&TraceEntry> = entries.iter().filter(|e| e.timestamp.date_naive() >= first).collect();
+        if entries.is_empty() {
+            continue;
+        }
+
+        let mut tools: HashMap<String, ToolTally> = HashMap::new();
+        let (mut model_requests, mut errors) = (0, 0);
+        let mut active_days = Vec::new();
+        for entry in &entries {
+            let day = per_day.get_mut(&entry.timestamp.date_naive());
+            match &entry.event {
+                TraceEvent::ToolResult { tool, result } => {
+                    let duration = result.metadata.as_ref().and_then(|m| m.duration_ms);
+                    for tally in [tools.entry(tool.clone()).or_default(), all_tools.entry(tool.clone()).or_default()] {
+                        tally.calls += 1;
+                        tally.failures += usize::from(!result.success);
+                        tally.durations.extend(duration.map(|ms| ms as f64));
+                    }
+                    if let Some(day) = day {
+                        day.tool_calls += 1;
+                    }
+                }
+                TraceEvent::ModelRequest { .. } => {
+                    model_requests += 1;
+                    if let Some(day) = day {
+                        day.model_requests += 1;
+                    }
+                }
+                TraceEvent::Error { .. } => errors += 1,
+                _ => {}
+            }
+            active_days.push(entry.timestamp.date_naive());
+        }
+        active_days.dedup();
+        for day in active_days {
+            if let Some(day) = per_day.get_mut(&day) {
+                day.active_agents += 1;
+            }
+        }
+
+        agents.push(AgentSummary {
+            agent_id: agent_id.clone(),
+            tool_calls: tools.values().map(|t| t.calls).sum(),
+            tool_failures: tools.values().map(|t| t.failures).sum(),
+            model_requests,
+            errors,
+            last_active_at: entries.iter().map(|e| e.timestamp).max(),
+            top_tools: tool_usage(tools, TOP_TOOLS),
+        });
+    }
+    agents.sort_by(|a, b| b.tool_calls.cmp(&a.tool_calls).then_with(|| a.agent_id.cmp(&b.agent_id)));
+
+    AgentStats {
+        days,
+        tool_calls: all_tools.values().map(|t| t.calls).sum(),
+        tool_failures: all_tools.values().map(|t| t.failures).sum(),
+        model_requests: agents.iter().map(|a| a.model_requests).sum(),
+        activity_per_day: per_day.into_values().collect(),
+        agents,
+        tools: tool_usage(all_tools, usize::MAX),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli_agent::ToolResult;
+    use crate::workflows::{Workflow, WorkflowType};
+
+    fn run(workflow_id: &str, started_at: i64, status: WorkflowStatus, secs: i64) -> WorkflowRun {
+        let mut workflow = Workflow::new("test".to_string(), WorkflowType::Process);
+        workflow.id = workflow_id.to_string();
+        let mut run = WorkflowRun::new(&workflow, HashMap::new(), None);
+        run.started_at = started_at;
+        run.status = status;
+        run.completed_at = (status != WorkflowStatus::Running).then_some(started_at + secs);
+        run
+    }
+
+    fn tool_result(agent_id: &str, at: DateTime<Utc>, tool: &str, success: bool, ms: u64) -> TraceEntry {
+        TraceEntry {
+            seq: 1,
+            timestamp: at,
+            session_id: agent_id.to_string(),
+            event: TraceEvent::ToolResult {
+                tool: tool.to_string(),
+                result: ToolResult {
+                    tool_call_id: "c".to_string(),
+                    success,
+                    output: String::new(),
+                    error: None,
+                    metadata: serde_json::from_value(serde_json::json!({ "duration_ms": ms })).ok(),
+                },
+            },
+        }
+    }
+
+    #[test]
+    fn test_workflow_stats() {
+        let now = Utc.with_ymd_and_hms(2026, 3, 10, 12, 0, 0).unwrap();
+        let day = 86_400;
+        let today = now.timestamp();
+        let runs = vec![
+            run("build", today, WorkflowStatus::Completed, 30),
+            run("build", today - day, WorkflowStatus::Completed, 10),
+            run("build", today - day, WorkflowStatus::Failed, 5),
+            run("report", today, WorkflowStatus::Running, 0),
+            run("report", today - 10 * day, WorkflowStatus::Completed, 99),
+        ];
+        let names = HashMap::from([("build".to_string(), "Build".to_string())]);
+
+        let stats = workflow_stats(&runs, &names, 7, now);
+        assert_eq!(stats.runs_per_day.len(), 7);
+        assert_eq!(stats.runs_per_day[0].date, NaiveDate::from_ymd_opt(2026, 3, 4).unwrap());
+        let yesterday = &stats.runs_per_day[5];
+        assert_eq!((yesterday.total, yesterday.completed, yesterday.failed), (2, 1, 1));
+        assert_eq!(stats.runs_per_day[6].total, 2);
+
+        assert_eq!(stats.summary.runs, 4);
+        assert_eq!(stats.summary.active, 1);
+        assert_eq!(stats.summary.success_rate, Some(2.0 / 3.0));
+        assert_eq!(stats.summary.avg_duration_secs, Some(20.0));
+
+        assert_eq!(stats.workflows[0].workflow_id, "build");
+        assert_eq!(stats.workflows[0].name.as_deref(), Some("Build"));
+        assert_eq!(stats.workflows[1].summary.runs, 1);
+        assert_eq!(stats.workflows[1].summary.success_rate, None);
+    }
+
+    #[test]
+    fn test_agent_stats() {
+        let now = Utc.with_ymd_and_hms(2026, 3, 10, 12, 0, 0).unwrap();
+        let traces = vec![
+            (
+                "coder".to_string(),
+                vec![
+                    tool_result("coder", now, "read_file", true, 10),
+                    tool_result("coder", now, "read_file", true, 30),
+                    tool_result("coder", now - Duration::days(1), "shell", false, 500),
+                    tool_result("coder", now - Duration::days(40), "shell", true, 1),
+                ],
+            ),
+            ("idle".to_string(), vec![tool_result("idle", now - Duration::days(40), "shell", true, 1)]),
+        ];
+
+        let stats = agent_stats(&traces, 30, now);
+        assert_eq!(stats.agents.len(), 1);
+        assert_eq!((stats.tool_calls, stats.tool_failures), (3, 1));
+        let coder = &stats.agents[0];
+        assert_eq!(coder.top_tools[0].tool, "read_file");
+        assert_eq!(coder.top_tools[0].avg_duration_ms, Some(20.0));
+        assert_eq!(coder.top_tools[1].failures, 1);
+        let today = stats.activity_per_day.last().unwrap();
+        assert_eq!((today.tool_calls, today.active_agents), (2, 1));
+    }
+}
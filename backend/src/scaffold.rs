@@ -0,0 +1,761 @@
+//! Project scaffolding from templates
+//!
+//! A template is a set of files whose paths and contents use a subset of the
+//! Handlebars syntax: `{{name}}` inserts a variable, `{{#if name}}` /
+//! `{{#unless name}}` with an optional `{{else}}` include text conditionally,
+//! and `{{! ...}}` is a comment. Values are inserted as-is, without HTML
+//! escaping. A file can also carry a `when` condition (`name` or `!name`)
+//! that leaves it out entirely.
+//!
+//! A few templates are built in. User templates live in
+//! `~/.skhoot/templates/<name>/`: a `template.json` manifest describing the
+//! variables and file conditions, next to the files themselves.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+/// Manifest file of a user template
+pub const MANIFEST_FILE: &str = "template.json";
+
+/// Largest file a user template may contain
+const MAX_TEMPLATE_FILE_SIZE: u64 = 1024 * 1024;
+
+/// Most files a user template may contain
+const MAX_TEMPLATE_FILES: usize = 500;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ScaffoldError {
+    #[error("Template not found: {0}")]
+    NotFound(String),
+
+    #[error("Missing required variables: {}", .0.join(", "))]
+    MissingVariables(Vec<String>),
+
+    #[error("Template error in {file}: {reason}")]
+    Render { file: String, reason: String },
+
+    #[error("Unsafe path in template: {0}")]
+    UnsafePath(String),
+
+    #[error("File already exists: {0} (pass overwrite to replace it)")]
+    AlreadyExists(String),
+
+    #[error("Invalid template: {0}")]
+    Invalid(String),
+
+    #[error("I/O error on {path}: {reason}")]
+    Io { path: String, reason: String },
+}
+
+impl ScaffoldError {
+    fn io(path: &Path, error: std::io::Error) -> Self {
+        ScaffoldError::Io {
+            path: path.display().to_string(),
+            reason: error.to_string(),
+        }
+    }
+}
+
+/// A variable a template expects
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TemplateVariable {
+    pub name: String,
+    pub description: String,
+    /// Value used when none is given
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default: Option<String>,
+    /// Whether a value (or default) must be provided
+    pub required: bool,
+}
+
+/// A file of a template; `path` and `content` are rendered
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TemplateFile {
+    pub path: String,
+    pub content: String,
+    /// Variable deciding whether the file is created; `!name` negates it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub when: Option<String>,
+}
+
+/// A project template
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProjectTemplate {
+    pub name: String,
+    pub description: String,
+    pub variables: Vec<TemplateVariable>,
+    pub files: Vec<TemplateFile>,
+    /// Shipped with Skhoot rather than defined by the user
+    #[serde(default)]
+    pub builtin: bool,
+}
+
+/// Listing entry for a template, without file contents
+#[derive(Debug, Clone, Serialize)]
+pub struct TemplateSummary {
+    pub name: String,
+    pub description: String,
+    pub variables: Vec<TemplateVariable>,
+    pub builtin: bool,
+}
+
+/// `template.json` of a user template
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct TemplateManifest {
+    description: String,
+    variables: Vec<TemplateVariable>,
+    /// Template-relative file path to its `when` condition
+    conditions: BTreeMap<String, String>,
+}
+
+/// Files written by [`create`]
+#[derive(Debug, Clone, Serialize)]
+pub struct ScaffoldOutcome {
+    pub template: String,
+    pub destination: String,
+    /// Created files, relative to `destination`
+    pub files: Vec<String>,
+    /// Files left out by their `when` condition
+    pub skipped: Vec<String>,
+}
+
+/// Built-in and user templates
+pub struct TemplateLibrary {
+    user_dir: PathBuf,
+}
+
+lazy_static::lazy_static! {
+    static ref GLOBAL_LIBRARY: Arc<TemplateLibrary> = Arc::new(TemplateLibrary::new(
+        dirs::home_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join(".skhoot")
+            .join("templates"),
+    ));
+}
+
+impl TemplateLibrary {
+    /// Library reading user templates from `user_dir`
+    pub fn new(user_dir: PathBuf) -> Self {
+        Self { user_dir }
+    }
+
+    /// Shared library with user templates in `~/.skhoot/templates`
+    pub fn global() -> Arc<TemplateLibrary> {
+        GLOBAL_LIBRARY.clone()
+    }
+
+    /// All templates, user templates first; a user template hides a
+    /// built-in one of the same name. Unreadable user templates are skipped.
+    pub fn list(&self) -> Vec<TemplateSummary> {
+        let mut templates: Vec<ProjectTemplate> = self
+            .user_template_names()
+            .iter()
+            .filter_map(|name| self.load_user(name).ok())
+            .collect();
+        for builtin in builtin_templates() {
+            if !templates.iter().any(|t| t.name == builtin.name) {
+                templates.push(builtin);
+            }
+        }
+        templates
+            .into_iter()
+            .map(|t| TemplateSummary {
+                name: t.name,
+                description: t.description,
+                variables: t.variables,
+                builtin: t.builtin,
+            })
+            .collect()
+    }
+
+    /// Template called `name`
+    pub fn get(&self, name: &str) -> Result<ProjectTemplate, ScaffoldError> {
+        if self.user_template_names().iter().any(|n| n == name) {
+            return self.load_user(name);
+        }
+        builtin_templates()
+            .into_iter()
+            .find(|t| t.name == name)
+            .ok_or_else(|| ScaffoldError::NotFound(name.to_string()))
+    }
+
+    fn user_template_names(&self) -> Vec<String> {
+        let Ok(entries) = std::fs::read_dir(&self.user_dir) else {
+            return Vec::new();
+        };
+        let mut names: Vec<String> = entries
+            .flatten()
+            .filter(|entry| entry.path().join(MANIFEST_FILE).is_file())
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        names
+    }
+
+    fn load_user(&self, name: &str) -> Result<ProjectTemplate, ScaffoldError> {
+        let root = self.user_dir.join(name);
+        let manifest_path = root.join(MANIFEST_FILE);
+        let manifest = std::fs::read_to_string(&manifest_path).map_err(|e| ScaffoldError::io(&manifest_path, e))?;
+        let mut manifest: TemplateManifest = serde_json::from_str(&manifest)
+            .map_err(|e| ScaffoldError::Invalid(format!("{}: {}", manifest_path.display(), e)))?;
+
+        let mut files = Vec::new();
+        for entry in walkdir::WalkDir::new(&root).follow_links(false).sort_by_file_name() {
+            let entry = entry.map_err(|e| ScaffoldError::Invalid(e.to_string()))?;
+            if !entry.file_type().is_file() || entry.path() == manifest_path {
+                continue;
+            }
+            if files.len() == MAX_TEMPLATE_FILES {
+                return Err(ScaffoldError::Invalid(format!("{} has more than {} files", name, MAX_TEMPLATE_FILES)));
+            }
+            let path = template_path(entry.path(), &root);
+            let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+            if size > MAX_TEMPLATE_FILE_SIZE {
+                return Err(ScaffoldError::Invalid(format!("{} is larger than {} bytes", path, MAX_TEMPLATE_FILE_SIZE)));
+            }
+            let content = std::fs::read_to_string(entry.path())
+                .map_err(|e| ScaffoldError::Invalid(format!("{} is not a UTF-8 text file: {}", path, e)))?;
+            let when = manifest.conditions.remove(&path);
+            files.push(TemplateFile { path, content, when });
+        }
+        if let Some(path) = manifest.conditions.keys().next() {
+            return Err(ScaffoldError::Invalid(format!("condition for missing file {}", path)));
+        }
+
+        Ok(ProjectTemplate {
+            name: name.to_string(),
+            description: manifest.description,
+            variables: manifest.variables,
+            files,
+            builtin: false,
+        })
+    }
+}
+
+/// `/`-separated path of a template file relative to the template root
+fn template_path(path: &Path, root: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Template variables with defaults applied; fails if a required one is
+/// missing. Values for undeclared variables are kept.
+pub fn resolve_variables(
+    template: &ProjectTemplate,
+    provided: &BTreeMap<String, String>,
+) -> Result<BTreeMap<String, String>, ScaffoldError> {
+    let mut variables = BTreeMap::new();
+    for variable in &template.variables {
+        if let Some(default) = &variable.default {
+            variables.insert(variable.name.clone(), default.clone());
+        }
+    }
+    variables.extend(provided.iter().map(|(k, v)| (k.clone(), v.clone())));
+
+    let missing: Vec<String> = template
+        .variables
+        .iter()
+        .filter(|v| v.required && variables.get(&v.name).is_none_or(|value| value.trim().is_empty()))
+        .map(|v| v.name.clone())
+        .collect();
+    if !missing.is_empty() {
+        return Err(ScaffoldError::MissingVariables(missing));
+    }
+    Ok(variables)
+}
+
+/// Render the template into `destination`, creating directories as needed.
+/// Nothing is written if a target file exists and `overwrite` is false.
+pub fn create(
+    template: &ProjectTemplate,
+    destination: &Path,
+    provided: &BTreeMap<String, String>,
+    overwrite: bool,
+) -> Result<ScaffoldOutcome, ScaffoldError> {
+    let variables = resolve_variables(template, provided)?;
+
+    let mut rendered = Vec::new();
+    let mut skipped = Vec::new();
+    for file in &template.files {
+        let path = render(&file.path, &variables).map_err(|reason| ScaffoldError::Render {
+            file: file.path.clone(),
+            reason,
+        })?;
+        if !file.when.as_deref().is_none_or(|when| condition_holds(when, &variables)) {
+            skipped.push(path);
+            continue;
+        }
+        let relative = safe_relative(Path::new(&path)).ok_or_else(|| ScaffoldError::UnsafePath(path.clone()))?;
+        let content = render(&file.content, &variables).map_err(|reason| ScaffoldError::Render {
+            file: file.path.clone(),
+            reason,
+        })?;
+        rendered.push((path, relative, content));
+    }
+
+    if !overwrite {
+        if let Some((path, ..)) = rendered.iter().find(|(_, relative, _)| destination.join(relative).exists()) {
+            return Err(ScaffoldError::AlreadyExists(path.clone()));
+        }
+    }
+
+    let mut files = Vec::new();
+    for (path, relative, content) in rendered {
+        let target = destination.join(relative);
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| ScaffoldError::io(parent, e))?;
+        }
+        std::fs::write(&target, content).map_err(|e| ScaffoldError::io(&target, e))?;
+        files.push(path);
+    }
+
+    Ok(ScaffoldOutcome {
+        template: template.name.clone(),
+        destination: destination.display().to_string(),
+        files,
+        skipped,
+    })
+}
+
+/// `path` if it only contains normal components
+pub(crate) fn safe_relative(path: &Path) -> Option<PathBuf> {
+    let mut relative = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => relative.push(part),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    (!relative.as_os_str().is_empty()).then_some(relative)
+}
+
+/// Whether a `when` condition (`name` or `!name`) holds
+fn condition_holds(condition: &str, variables: &BTreeMap<String, String>) -> bool {
+    match condition.trim().strip_prefix('!') {
+        Some(name) => !is_truthy(variables.get(name.trim())),
+        None => is_truthy(variables.get(condition.trim())),
+    }
+}
+
+/// Missing, empty, `false` and `0` are false
+fn is_truthy(value: Option<&String>) -> bool {
+    value.is_some_and(|v| {
+        let v = v.trim();
+        !v.is_empty() && v != "false" && v != "0"
+    })
+}
+
+enum Token<'a> {
+    Text(&'a str),
+    Tag(&'a str),
+}
+
+enum Node {
+    Text(String),
+    Variable(String),
+    Conditional {
+        name: String,
+        negate: bool,
+        then: Vec<Node>,
+        otherwise: Vec<Node>,
+    },
+}
+
+/// Render a template string; fails on malformed tags and on variables
+/// without a value
+pub fn render(template: &str, variables: &BTreeMap<String, String>) -> Result<String, String> {
+    let tokens = tokenize(template)?;
+    let (nodes, _) = parse(&mut tokens.iter(), None)?;
+    let mut output = String::with_capacity(template.len());
+    write_nodes(&nodes, variables, &mut output)?;
+    Ok(output)
+}
+
+/// Split into text and tags. Block tags alone on their line take the line
+/// with them, as in Handlebars, so they don't leave blank lines behind.
+fn tokenize(template: &str) -> Result<Vec<Token<'_>>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = template;
+    // Only blanks since the last newline, and no tag
+    let mut line_start = true;
+
+    while let Some(start) = rest.find("{{") {
+        let after = &rest[start + 2..];
+        let end = after.find("}}").ok_or_else(|| "unclosed {{".to_string())?;
+        let tag = after[..end].trim();
+        let mut text = &rest[..start];
+        let mut next = &after[end + 2..];
+
+        let indent = text.rfind('\n').map_or(text, |i| &text[i + 1..]);
+        let own_line = (line_start || text.contains('\n')) && indent.trim_matches([' ', '\t']).is_empty();
+        let is_block = tag.starts_with(['#', '/', '!']) || tag == "else";
+        let mut standalone = false;
+        if is_block && own_line {
+            let trailing = next.trim_start_matches([' ', '\t']);
+            let line_end = trailing
+                .strip_prefix("\r\n")
+                .or_else(|| trailing.strip_prefix('\n'))
+                .or_else(|| trailing.is_empty().then_some(trailing));
+            if let Some(line_end) = line_end {
+                text = &text[..text.len() - indent.len()];
+                next = line_end;
+                standalone = true;
+            }
+        }
+
+        if !text.is_empty() {
+            tokens.push(Token::Text(text));
+        }
+        tokens.push(Token::Tag(tag));
+        line_start = standalone;
+        rest = next;
+    }
+
+    if !rest.is_empty() {
+        tokens.push(Token::Text(rest));
+    }
+    Ok(tokens)
+}
+
+/// Parse nodes up to the closing tag of `block`; returns the nodes and,
+/// after an `{{else}}`, the nodes of the else branch
+fn parse(tokens: &mut std::slice::Iter<Token>, block: Option<&str>) -> Result<(Vec<Node>, Option<Vec<Node>>), String> {
+    let mut nodes = Vec::new();
+    let mut otherwise: Option<Vec<Node>> = None;
+
+    while let Some(token) = tokens.next() {
+        let node = match token {
+            Token::Text(text) => Node::Text(text.to_string()),
+            Token::Tag(tag) if tag.starts_with('!') => continue,
+            Token::Tag(tag) if *tag == "else" => {
+                if block.is_none() || otherwise.is_some() {
+                    return Err("unexpected {{else}}".to_string());
+                }
+                otherwise = Some(Vec::new());
+                continue;
+            }
+            Token::Tag(tag) if tag.starts_with('/') => {
+                let closed = tag[1..].trim();
+                if Some(closed) != block {
+                    return Err(format!("unexpected {{{{/{}}}}}", closed));
+                }
+                return Ok((nodes, otherwise));
+            }
+            Token::Tag(tag) if tag.starts_with('#') => {
+                let (helper, name) = tag[1..].split_once(char::is_whitespace).unwrap_or((&tag[1..], ""));
+                let negate = match helper {
+                    "if" => false,
+                    "unless" => true,
+                    _ => return Err(format!("unsupported block {{{{#{}}}}}", helper)),
+                };
+                if name.trim().is_empty() {
+                    return Err(format!("{{{{#{}}}}} needs a variable", helper));
+                }
+                let (then, else_branch) = parse(tokens, Some(helper))?;
+                Node::Conditional {
+                    name: name.trim().to_string(),
+                    negate,
+                    then,
+                    otherwise: else_branch.unwrap_or_default(),
+                }
+            }
+            Token::Tag(tag) => Node::Variable(tag.to_string()),
+        };
+        otherwise.as_mut().unwrap_or(&mut nodes).push(node);
+    }
+
+    match block {
+        Some(block) => Err(format!("unclosed {{{{#{}}}}}", block)),
+        None => Ok((nodes, otherwise)),
+    }
+}
+
+fn write_nodes(nodes: &[Node], variables: &BTreeMap<String, String>, output: &mut String) -> Result<(), String> {
+    for node in nodes {
+        match node {
+            Node::Text(text) => output.push_str(text),
+            Node::Variable(name) => {
+                let value = variables.get(name).ok_or_else(|| format!("no value for variable '{}'", name))?;
+                output.push_str(value);
+            }
+            Node::Conditional { name, negate, then, otherwise } => {
+                let branch = if is_truthy(variables.get(name)) != *negate { then } else { otherwise };
+                write_nodes(branch, variables, output)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn variable(name: &str, description: &str, default: Option<&str>, required: bool) -> TemplateVariable {
+    TemplateVariable {
+        name: name.to_string(),
+        description: description.to_string(),
+        default: default.map(str::to_string),
+        required,
+    }
+}
+
+fn file(path: &str, content: &str, when: Option<&str>) -> TemplateFile {
+    TemplateFile {
+        path: path.to_string(),
+        content: content.to_string(),
+        when: when.map(str::to_string),
+    }
+}
+
+/// Templates shipped with Skhoot
+pub fn builtin_templates() -> Vec<ProjectTemplate> {
+    vec![
+        ProjectTemplate {
+            name: "rust-cli".to_string(),
+            description: "Rust command-line application built with Cargo".to_string(),
+            variables: vec![
+                variable("name", "Crate name", None, true),
+                variable("description", "One-line description", Some("A command-line tool"), false),
+                variable("author", "Author name", Some(""), false),
+                variable("clap", "Parse arguments with clap", Some("true"), false),
+            ],
+            files: vec![
+                file(
+                    "Cargo.toml",
+                    r#"[package]
+name = "{{name}}"
+version = "0.1.0"
+edition = "2021"
+description = "{{description}}"
+{{#if author}}
+authors = ["{{author}}"]
+{{/if}}
+
+[dependencies]
+{{#if clap}}
+clap = { version = "4", features = ["derive"] }
+{{/if}}
+"#,
+                    None,
+                ),
+                file(
+                    "src/main.rs",
+                    r#"{{#if clap}}
+use clap::Parser;
+
+/// {{description}}
+#[derive(Parser)]
+#[command(version, about)]
+struct Args {
+    /// Name to greet
+    #[arg(default_value = "world")]
+    name: String,
+}
+
+fn main() {
+    let args = Args::parse();
+    println!("Hello, {}!", args.name);
+}
+{{else}}
+fn main() {
+    println!("Hello, world!");
+}
+{{/if}}
+"#,
+                    None,
+                ),
+                file(".gitignore", "/target\n", None),
+                file("README.md", "# {{name}}\n\n{{description}}\n\n```sh\ncargo run\n```\n", None),
+            ],
+            builtin: true,
+        },
+        ProjectTemplate {
+            name: "python-package".to_string(),
+            description: "Python package with a pyproject.toml and pytest tests".to_string(),
+            variables: vec![
+                variable("name", "Distribution name", None, true),
+                variable("package", "Import name of the package", None, true),
+                variable("description", "One-line description", Some("A Python package"), false),
+                variable("python_version", "Minimum Python version", Some("3.10"), false),
+                variable("tests", "Add a pytest test suite", Some("true"), false),
+            ],
+            files: vec![
+                file(
+                    "pyproject.toml",
+                    r#"[build-system]
+requires = ["hatchling"]
+build-backend = "hatchling.build"
+
+[project]
+name = "{{name}}"
+version = "0.1.0"
+description = "{{description}}"
+readme = "README.md"
+requires-python = ">={{python_version}}"
+dependencies = []
+{{#if tests}}
+
+[project.optional-dependencies]
+dev = ["pytest"]
+{{/if}}
+"#,
+                    None,
+                ),
+                file("src/{{package}}/__init__.py", "\"\"\"{{description}}\"\"\"\n\n__version__ = \"0.1.0\"\n", None),
+                file(
+                    "tests/test_{{package}}.py",
+                    "import {{package}}\n\n\ndef test_version():\n    assert {{package}}.__version__\n",
+                    Some("tests"),
+                ),
+                file(".gitignore", "__pycache__/\n*.egg-info/\n.venv/\ndist/\n", None),
+                file("README.md", "# {{name}}\n\n{{description}}\n", None),
+            ],
+            builtin: true,
+        },
+        ProjectTemplate {
+            name: "node-app".to_string(),
+            description: "Node.js application, in JavaScript or TypeScript".to_string(),
+            variables: vec![
+                variable("name", "Package name", None, true),
+                variable("description", "One-line description", Some("A Node.js application"), false),
+                variable("typescript", "Use TypeScript", Some("false"), false),
+            ],
+            files: vec![
+                file(
+                    "package.json",
+                    r#"{
+  "name": "{{name}}",
+  "version": "0.1.0",
+  "description": "{{description}}",
+  "type": "module",
+{{#if typescript}}
+  "main": "dist/index.js",
+  "scripts": {
+    "build": "tsc",
+    "start": "node dist/index.js"
+  },
+  "devDependencies": {
+    "typescript": "^5.0.0"
+  }
+{{else}}
+  "main": "index.js",
+  "scripts": {
+    "start": "node index.js"
+  }
+{{/if}}
+}
+"#,
+                    None,
+                ),
+                file("index.js", "console.log('Hello from {{name}}');\n", Some("!typescript")),
+                file("src/index.ts", "console.log('Hello from {{name}}');\n", Some("typescript")),
+                file(
+                    "tsconfig.json",
+                    r#"{
+  "compilerOptions": {
+    "target": "ES2022",
+    "module": "NodeNext",
+    "moduleResolution": "NodeNext",
+    "outDir": "dist",
+    "strict": true
+  },
+  "include": ["src"]
+}
+"#,
+                    Some("typescript"),
+                ),
+                file(".gitignore", "node_modules/\n{{#if typescript}}dist/\n{{/if}}", None),
+                file("README.md", "# {{name}}\n\n{{description}}\n", None),
+            ],
+            builtin: true,
+        },
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn test_render_variables_and_conditionals() {
+        let variables = vars(&[("name", "demo"), ("tests", "true"), ("ci", "false")]);
+
+        assert_eq!(render("Hello {{ name }}{{! comment }}!", &variables).unwrap(), "Hello demo!");
+        assert_eq!(
+            render("a\n{{#if tests}}\nt\n{{else}}\nno t\n{{/if}}\n{{#unless ci}}\nno ci\n{{/unless}}\nb\n", &variables)
+                .unwrap(),
+            "a\nt\nno ci\nb\n"
+        );
+        assert_eq!(render("[{{#if missing}}x{{else}}y{{/if}}]", &variables).unwrap(), "[y]");
+
+        assert!(render("{{unknown}}", &variables).is_err());
+        assert!(render("{{#if tests}}open", &variables).is_err());
+        assert!(render("{{#each items}}{{/each}}", &variables).is_err());
+        assert!(render("{{/if}}", &variables).is_err());
+    }
+
+    #[test]
+    fn test_create_builtin_template() {
+        let dir = tempfile::tempdir().unwrap();
+        let library = TemplateLibrary::new(dir.path().join("templates"));
+        let template = library.get("python-package").unwrap();
+        let destination = dir.path().join("out");
+
+        assert!(matches!(
+            create(&template, &destination, &BTreeMap::new(), false),
+            Err(ScaffoldError::MissingVariables(names)) if names == ["name", "package"]
+        ));
+
+        let provided = vars(&[("name", "my-lib"), ("package", "my_lib"), ("tests", "false")]);
+        let outcome = create(&template, &destination, &provided, false).unwrap();
+        assert_eq!(outcome.skipped, ["tests/test_my_lib.py"]);
+        assert!(outcome.files.contains(&"src/my_lib/__init__.py".to_string()));
+        let pyproject = std::fs::read_to_string(destination.join("pyproject.toml")).unwrap();
+        assert!(pyproject.contains("name = \"my-lib\"") && pyproject.contains(">=3.10"));
+        assert!(!pyproject.contains("pytest"));
+
+        assert!(matches!(
+            create(&template, &destination, &provided, false),
+            Err(ScaffoldError::AlreadyExists(_))
+        ));
+        assert!(create(&template, &destination, &provided, true).is_ok());
+    }
+
+    #[test]
+    fn test_user_templates() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("templates").join("note");
+        std::fs::create_dir_all(root.join("docs")).unwrap();
+        std::fs::write(
+            root.join(MANIFEST_FILE),
+            r#"{"description": "A note", "variables": [{"name": "title", "required": true}], "conditions": {"docs/extra.md": "extra"}}"#,
+        )
+        .unwrap();
+        std::fs::write(root.join("{{title}}.md"), "# {{title}}\n").unwrap();
+        std::fs::write(root.join("docs").join("extra.md"), "extra\n").unwrap();
+
+        let library = TemplateLibrary::new(dir.path().join("templates"));
+        let names: Vec<String> = library.list().into_iter().map(|t| t.name).collect();
+        assert_eq!(names[0], "note");
+        assert!(names.contains(&"rust-cli".to_string()));
+
+        let template = library.get("note").unwrap();
+        let destination = dir.path().join("out");
+        let outcome = create(&template, &destination, &vars(&[("title", "ideas")]), false).unwrap();
+        assert_eq!(outcome.files, ["ideas.md"]);
+        assert_eq!(std::fs::read_to_string(destination.join("ideas.md")).unwrap(), "# ideas\n");
+
+        let escape = vars(&[("title", "../escape")]);
+        assert!(matches!(create(&template, &destination, &escape, false), Err(ScaffoldError::UnsafePath(_))));
+    }
+}
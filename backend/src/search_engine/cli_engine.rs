@@ -2,6 +2,8 @@
 #![allow(dead_code)]
 
 use anyhow::{Result, Context};
+use crate::ignore_rules::{IgnoreCache, IgnoreRules};
+use super::query::SearchFilter;
 use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 use std::process::Stdio;
@@ -34,6 +36,19 @@ pub struct CliConfig {
     pub timeout_seconds: u64,
     pub max_results: usize,
     pub unrestricted: bool, // Enable deep search (hidden files, ignore .gitignore)
+    /// Let rg and fd honour git's ignore files
+    #[serde(default = "default_true")]
+    pub respect_gitignore: bool,
+    /// Drop results covered by a `.skhootignore`, which rg and fd don't read
+    #[serde(default = "default_true")]
+    pub respect_skhootignore: bool,
+    /// Extension, size, time and phrase filters from the search query
+    #[serde(skip)]
+    pub filter: SearchFilter,
+}
+
+fn default_true() -> bool {
+    true
 }
 
 impl Default for CliConfig {
@@ -45,6 +60,9 @@ impl Default for CliConfig {
             timeout_seconds: 30,
             max_results: 1000,
             unrestricted: false,
+            respect_gitignore: true,
+            respect_skhootignore: true,
+            filter: SearchFilter::default(),
         }
     }
 }
@@ -100,12 +118,36 @@ impl CliEngine {
         match result {
             Ok(mut cli_result) => {
                 cli_result.execution_time_ms = execution_time_ms;
+                self.apply_skhootignore(&mut cli_result, config);
+                self.apply_filter(&mut cli_result, config);
                 Ok(cli_result)
             }
             Err(e) => Err(e),
         }
     }
 
+    /// Filter out files covered by a `.skhootignore`. The tools already
+    /// handle git's rules themselves.
+    fn apply_skhootignore(&self, result: &mut CliSearchResult, config: &CliConfig) {
+        if config.unrestricted || !config.respect_skhootignore {
+            return;
+        }
+        let mut cache = IgnoreCache::new(IgnoreRules { skhootignore: true, ..IgnoreRules::NONE });
+        result.files.retain(|file| !cache.is_ignored(&self.working_directory.join(&file.path)));
+        result.total_results = result.files.len();
+    }
+
+    /// Drop files the query's filters exclude. fd and find already applied
+    /// the size and time limits; ripgrep can't, and none of them check
+    /// phrases exactly.
+    fn apply_filter(&self, result: &mut CliSearchResult, config: &CliConfig) {
+        if config.filter.is_empty() {
+            return;
+        }
+        result.files.retain(|file| config.filter.matches_file(&self.working_directory.join(&file.path)));
+        result.total_results = result.files.len();
+    }
+
     /// Search for files with specific extensions and name patterns (like Codex CLI)
     /// Uses ripgrep with glob patterns: rg --files -g '*.pdf' -g '*deck*'
     pub async fn search_files_with_globs(
@@ -130,6 +172,8 @@ impl CliEngine {
         match result {
             Ok(mut cli_result) => {
                 cli_result.execution_time_ms = execution_time_ms;
+                self.apply_skhootignore(&mut cli_result, config);
+                self.apply_filter(&mut cli_result, config);
                 Ok(cli_result)
             }
             Err(e) => Err(e),
@@ -151,6 +195,8 @@ impl CliEngine {
         if config.unrestricted {
             cmd.arg("--hidden") // Search hidden files
                .arg("--no-ignore"); // Ignore .gitignore rules
+        } else if !config.respect_gitignore {
+            cmd.arg("--no-ignore");
         }
 
         // Add extension globs
@@ -215,6 +261,8 @@ impl CliEngine {
         if config.unrestricted {
             cmd.arg("--hidden") // Search hidden files
                .arg("--no-ignore"); // Ignore .gitignore rules
+        } else if !config.respect_gitignore {
+            cmd.arg("--no-ignore");
         }
 
         // Add extension filters
@@ -223,6 +271,8 @@ impl CliEngine {
             cmd.arg("-e").arg(ext);
         }
 
+        cmd.args(config.filter.fd_args());
+
         // Build regex pattern for name matching
         if !name_patterns.is_empty() {
             let pattern = name_patterns.join("|");
@@ -296,6 +346,8 @@ impl CliEngine {
             cmd.arg(")");
         }
 
+        cmd.args(config.filter.find_args());
+
         cmd.stdout(Stdio::piped())
            .stderr(Stdio::piped());
 
@@ -341,6 +393,8 @@ impl CliEngine {
         match result {
             Ok(mut cli_result) => {
                 cli_result.execution_time_ms = execution_time_ms;
+                self.apply_skhootignore(&mut cli_result, config);
+                self.apply_filter(&mut cli_result, config);
                 Ok(cli_result)
             }
             Err(e) => Err(e),
@@ -2,6 +2,9 @@
 #![allow(dead_code)]
 
 use anyhow::Result;
+use crate::ignore_rules::IgnoreRules;
+use crate::mounts::{MountGuard, MountPolicy, SkippedMount};
+use super::query::SearchFilter;
 use ignore::WalkBuilder;
 use ignore::overrides::OverrideBuilder;
 use serde::{Deserialize, Serialize};
@@ -24,10 +27,20 @@ pub struct FileSearchConfig {
     pub max_results: usize,
     pub threads: usize,
     pub respect_gitignore: bool,
+    /// Honour `.skhootignore` files
+    #[serde(default = "default_true")]
+    pub respect_skhootignore: bool,
     pub follow_symlinks: bool,
     pub include_hidden: bool,
     pub exclude_patterns: Vec<String>,
     pub include_patterns: Vec<String>,
+    /// Network and removable mounts below the search directory are left
+    /// out unless opted into
+    #[serde(default)]
+    pub mounts: MountPolicy,
+    /// Extension, size, time and phrase filters from the search query
+    #[serde(skip)]
+    pub filter: SearchFilter,
 }
 
 impl Default for FileSearchConfig {
@@ -36,6 +49,7 @@ impl Default for FileSearchConfig {
             max_results: 100,
             threads: 4,
             respect_gitignore: true,
+            respect_skhootignore: true,
             follow_symlinks: true,
             include_hidden: false,
             exclude_patterns: vec![
@@ -46,10 +60,23 @@ impl Default for FileSearchConfig {
                 "*.tmp".to_string(),
             ],
             include_patterns: vec![],
+            mounts: MountPolicy::default(),
+            filter: SearchFilter::default(),
         }
     }
 }
 
+fn default_true() -> bool {
+    true
+}
+
+impl FileSearchConfig {
+    /// Ignore files this search honours
+    pub fn ignore_rules(&self) -> IgnoreRules {
+        IgnoreRules::SEARCH.with_overrides(Some(self.respect_gitignore), Some(self.respect_skhootignore))
+    }
+}
+
 /// A single file match result
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileMatch {
@@ -72,6 +99,17 @@ pub struct FileSearchResults {
     pub search_time_ms: u64,
     pub query: String,
     pub truncated: bool,
+    /// Mounts below the search directory that were not searched
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skipped_mounts: Vec<SkippedMount>,
+}
+
+/// A file known to exist without walking, e.g. from the filename index
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexedCandidate {
+    pub path: std::path::PathBuf,
+    pub size: Option<u64>,
+    pub modified: Option<chrono::DateTime<chrono::Utc>>,
 }
 
 /// High-performance file search engine optimized for AI usage
@@ -95,13 +133,15 @@ impl FileSearchEngine {
     ) -> Result<FileSearchResults> {
         let start_time = std::time::Instant::now();
         
-        if query.trim().is_empty() {
+        // A filter-only query lists every file that passes the filter
+        if query.trim().is_empty() && self.config.filter.is_empty() {
             return Ok(FileSearchResults {
                 matches: vec![],
                 total_matches: 0,
                 search_time_ms: start_time.elapsed().as_millis() as u64,
                 query: query.to_string(),
                 truncated: false,
+                skipped_mounts: Vec::new(),
             });
         }
 
@@ -125,6 +165,44 @@ impl FileSearchEngine {
             search_time_ms,
             query: query.to_string(),
             truncated,
+            skipped_mounts: results.skipped_mounts,
+        })
+    }
+
+    /// Fuzzy search over known files instead of walking `search_dir`.
+    /// `candidates` must already be under it and pass the ignore rules.
+    pub async fn search_candidates(
+        &self,
+        query: &str,
+        search_dir: &Path,
+        candidates: Vec<IndexedCandidate>,
+        compute_indices: bool,
+    ) -> Result<FileSearchResults> {
+        let start_time = std::time::Instant::now();
+        let patterns = create_patterns(query);
+        let results = if patterns.is_empty() && self.config.filter.is_empty() {
+            InternalSearchResults {
+                matches: Vec::new(),
+                total_matches: 0,
+                skipped_mounts: Vec::new(),
+            }
+        } else {
+            let limit = NonZero::new(self.config.max_results).unwrap_or(NonZero::new(100).unwrap());
+            let search_dir = search_dir.to_path_buf();
+            let filter = self.config.filter.clone();
+            task::spawn_blocking(move || {
+                score_candidates(patterns, limit, &search_dir, candidates, &filter, compute_indices)
+            })
+            .await?
+        };
+
+        Ok(FileSearchResults {
+            truncated: results.matches.len() < results.total_matches,
+            matches: results.matches,
+            total_matches: results.total_matches,
+            search_time_ms: start_time.elapsed().as_millis() as u64,
+            query: query.to_string(),
+            skipped_mounts: Vec::new(),
         })
     }
 
@@ -142,10 +220,11 @@ impl FileSearchEngine {
         compute_indices: bool,
     ) -> Result<InternalSearchResults> {
         let patterns = create_patterns(pattern_text);
-        if patterns.is_empty() {
+        if patterns.is_empty() && self.config.filter.is_empty() {
             return Ok(InternalSearchResults {
                 matches: Vec::new(),
                 total_matches: 0,
+                skipped_mounts: Vec::new(),
             });
         }
         let limit = NonZero::new(self.config.max_results).unwrap_or(NonZero::new(100).unwrap());
@@ -172,6 +251,7 @@ impl FileSearchEngine {
 struct InternalSearchResults {
     matches: Vec<FileMatch>,
     total_matches: usize,
+    skipped_mounts: Vec<SkippedMount>,
 }
 
 /// Core search implementation (blocking)
@@ -196,36 +276,22 @@ fn run_file_search(
         })
         .collect();
 
-    // Setup directory walker
-    let mut walk_builder = WalkBuilder::new(search_directory);
-    walk_builder
-        .threads(worker_count.num_walk_builder_threads)
-        .hidden(!config.include_hidden)
-        .follow_links(config.follow_symlinks)
-        .require_git(false);
-
-    if !config.respect_gitignore {
-        walk_builder
-            .git_ignore(false)
-            .git_global(false)
-            .git_exclude(false)
-            .ignore(false)
-            .parents(false);
-    }
-
-    // Add exclude patterns
-    if !config.exclude_patterns.is_empty() {
-        let mut override_builder = OverrideBuilder::new(search_directory);
-        for exclude in &config.exclude_patterns {
-            let exclude_pattern = format!("!{exclude}");
-            override_builder.add(&exclude_pattern)?;
-        }
-        let override_matcher = override_builder.build()?;
-        walk_builder.overrides(override_matcher);
+    // Stay out of excluded and unresponsive mounts
+    let guard = MountGuard::new(search_directory, &config.mounts);
+    if !guard.allows(search_directory) {
+        return Ok(InternalSearchResults {
+            matches: Vec::new(),
+            total_matches: 0,
+            skipped_mounts: guard.skipped().to_vec(),
+        });
     }
 
-    let walker = walk_builder.build_parallel();
+    let mut walk_builder = walk_builder(search_directory, &config, &guard)?;
+    let walker = walk_builder
+        .threads(worker_count.num_walk_builder_threads)
+        .build_parallel();
     let index_counter = AtomicUsize::new(0);
+    let filter = &config.filter;
 
     // Run parallel file traversal
     walker.run(|| {
@@ -238,7 +304,10 @@ fn run_file_search(
         let cancel = cancel_flag.clone();
 
         Box::new(move |entry| {
-            if let Some(file_info) = get_file_info(&entry, search_directory) {
+            let file_info = get_file_info(&entry, search_directory).filter(|info| {
+                filter.is_empty() || filter.matches(Path::new(&info.full_path), info.file_size, info.modified)
+            });
+            if let Some(file_info) = file_info {
                 best_list.insert(file_info);
             }
 
@@ -256,6 +325,7 @@ fn run_file_search(
         return Ok(InternalSearchResults {
             matches: Vec::new(),
             total_matches: 0,
+            skipped_mounts: guard.skipped().to_vec(),
         });
     }
 
@@ -282,7 +352,84 @@ fn run_file_search(
     }
 
     // Convert to final results
-    let mut raw_matches: Vec<(u32, FileInfo)> = global_heap.into_iter().map(|r| r.0).collect();
+    let raw_matches: Vec<(u32, FileInfo)> = global_heap.into_iter().map(|r| r.0).collect();
+    let matches = into_file_matches(raw_matches, &patterns, compute_indices);
+
+    Ok(InternalSearchResults {
+        matches,
+        total_matches,
+        skipped_mounts: guard.skipped().to_vec(),
+    })
+}
+
+/// Directory walker honouring the config's ignore rules, hidden-file
+/// setting and exclude patterns, staying out of mounts `guard` skips
+pub(crate) fn walk_builder(search_directory: &Path, config: &FileSearchConfig, guard: &MountGuard) -> Result<WalkBuilder> {
+    let mut walk_builder = WalkBuilder::new(search_directory);
+    walk_builder
+        .hidden(!config.include_hidden)
+        .follow_links(config.follow_symlinks);
+    config.ignore_rules().configure(&mut walk_builder);
+
+    let entry_guard = guard.clone();
+    walk_builder.filter_entry(move |entry| entry_guard.allows(entry.path()));
+
+    // Add exclude patterns
+    if !config.exclude_patterns.is_empty() {
+        let mut override_builder = OverrideBuilder::new(search_directory);
+        for exclude in &config.exclude_patterns {
+            let exclude_pattern = format!("!{exclude}");
+            override_builder.add(&exclude_pattern)?;
+        }
+        let override_matcher = override_builder.build()?;
+        walk_builder.overrides(override_matcher);
+    }
+    Ok(walk_builder)
+}
+
+/// Score files already known to be under `search_directory`, e.g. from the
+/// filename index, the way a walk would
+fn score_candidates(
+    patterns: Vec<Pattern>,
+    limit: NonZero<usize>,
+    search_directory: &Path,
+    candidates: Vec<IndexedCandidate>,
+    filter: &SearchFilter,
+    compute_indices: bool,
+) -> InternalSearchResults {
+    let mut best = BestMatchesList::new(limit.get(), patterns.clone(), Matcher::new(Config::DEFAULT));
+    for candidate in candidates {
+        if !filter.is_empty() && !filter.matches(&candidate.path, candidate.size, candidate.modified) {
+            continue;
+        }
+        let path = candidate.path.as_path();
+        let Some(relative_path) = path.strip_prefix(search_directory).ok().and_then(|p| p.to_str()) else {
+            continue;
+        };
+        let (Some(file_name), Some(full_path)) = (path.file_name().and_then(|n| n.to_str()), path.to_str()) else {
+            continue;
+        };
+        best.insert(FileInfo {
+            full_path: full_path.to_string(),
+            relative_path: relative_path.to_string(),
+            file_name: file_name.to_string(),
+            file_size: candidate.size,
+            modified: candidate.modified,
+            file_type: path.extension().and_then(|ext| ext.to_str()).unwrap_or("unknown").to_string(),
+        });
+    }
+
+    let total_matches = best.num_matches;
+    let raw_matches: Vec<(u32, FileInfo)> = best.binary_heap.into_iter().map(|r| r.0).collect();
+    InternalSearchResults {
+        matches: into_file_matches(raw_matches, &patterns, compute_indices),
+        total_matches,
+        skipped_mounts: Vec::new(),
+    }
+}
+
+/// Best matches first, with highlight indices if asked for
+fn into_file_matches(mut raw_matches: Vec<(u32, FileInfo)>, patterns: &[Pattern], compute_indices: bool) -> Vec<FileMatch> {
     sort_matches(&mut raw_matches);
 
     let mut matcher = if compute_indices {
@@ -291,7 +438,7 @@ fn run_file_search(
         None
     };
 
-    let matches: Vec<FileMatch> = raw_matches
+    raw_matches
         .into_iter()
         .map(|(score, file_info)| {
             let indices = if compute_indices {
@@ -300,7 +447,7 @@ fn run_file_search(
                 let mut idx_vec: Vec<u32> = Vec::new();
                 if let Some(ref mut m) = matcher {
                     // Collect indices from all matching patterns
-                    for pattern in &patterns {
+                    for pattern in patterns {
                         pattern.indices(haystack, m, &mut idx_vec);
                     }
                 }
@@ -322,12 +469,7 @@ fn run_file_search(
                 indices,
             }
         })
-        .collect();
-
-    Ok(InternalSearchResults {
-        matches,
-        total_matches,
-    })
+        .collect()
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
@@ -404,10 +546,15 @@ impl BestMatchesList {
     fn insert(&mut self, file_info: FileInfo) {
         let haystack: Utf32Str<'_> = Utf32Str::new(&file_info.relative_path, &mut self.utf32buf);
         
-        // Try all patterns and take the best score (OR-style matching)
-        let best_score = self.patterns.iter()
-            .filter_map(|pattern| pattern.score(haystack, &mut self.matcher))
-            .max();
+        // Try all patterns and take the best score (OR-style matching).
+        // Without patterns the filter alone decided, so everything matches.
+        let best_score = if self.patterns.is_empty() {
+            Some(0)
+        } else {
+            self.patterns.iter()
+                .filter_map(|pattern| pattern.score(haystack, &mut self.matcher))
+                .max()
+        };
         
         if let Some(score) = best_score {
             self.num_matches += 1;
@@ -503,4 +650,23 @@ mod tests {
         assert!(results.matches.is_empty());
         assert_eq!(results.total_matches, 0);
     }
+
+    #[tokio::test]
+    async fn test_query_filter() {
+        let temp_dir = TempDir::new().unwrap();
+        let temp_path = temp_dir.path();
+        fs::write(temp_path.join("small.pdf"), "x").unwrap();
+        fs::write(temp_path.join("large.pdf"), "x".repeat(2048)).unwrap();
+        fs::write(temp_path.join("large.txt"), "x".repeat(2048)).unwrap();
+
+        let query = super::super::SearchQuery::parse("ext:pdf size:>1kb").unwrap();
+        let engine = FileSearchEngine::new(FileSearchConfig { filter: query.filter, ..Default::default() });
+
+        // Filters alone list every passing file
+        let results = engine.search("", temp_path, false).await.unwrap();
+        let names: Vec<&str> = results.matches.iter().map(|m| m.file_name.as_str()).collect();
+        assert_eq!(names, vec!["large.pdf"]);
+
+        assert!(engine.search("small", temp_path, false).await.unwrap().matches.is_empty());
+    }
 }
\ No newline at end of file
@@ -0,0 +1,443 @@
+//! Media and document metadata for search results
+//!
+//! Search results only carry size and modification time. This module reads
+//! what's inside binary files: image dimensions and EXIF tags, audio/video
+//! duration and codecs, and document page counts and authors. Reading is
+//! done on demand and cached per path, keyed by size and mtime so edited
+//! files are read again.
+//!
+//! Audio and video come from `ffprobe` when it's installed; without it only
+//! WAV files are understood.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use xml::reader::{EventReader, XmlEvent};
+
+use super::search_manager::MergedSearchResult;
+
+/// Paths remembered before the oldest entries are dropped
+const MAX_CACHE_ENTRIES: usize = 4096;
+/// Larger documents are not parsed for metadata
+const MAX_DOCUMENT_BYTES: u64 = 100 * 1024 * 1024;
+
+/// EXIF tags worth showing next to a photo
+const EXIF_TAGS: &[exif::Tag] = &[
+    exif::Tag::Make,
+    exif::Tag::Model,
+    exif::Tag::DateTimeOriginal,
+    exif::Tag::ExposureTime,
+    exif::Tag::FNumber,
+    exif::Tag::PhotographicSensitivity,
+    exif::Tag::FocalLength,
+    exif::Tag::Orientation,
+    exif::Tag::GPSLatitude,
+    exif::Tag::GPSLongitude,
+];
+
+/// What we know about a file's contents
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FileMetadata {
+    Image {
+        width: u32,
+        height: u32,
+        /// Selected EXIF tags, by tag name
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        exif: BTreeMap<String, String>,
+    },
+    Media {
+        duration_seconds: Option<f64>,
+        video_codec: Option<String>,
+        audio_codec: Option<String>,
+        width: Option<u32>,
+        height: Option<u32>,
+    },
+    Document {
+        page_count: Option<u32>,
+        author: Option<String>,
+        title: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone)]
+struct CacheEntry {
+    size: u64,
+    modified: Option<SystemTime>,
+    metadata: Option<FileMetadata>,
+    used: u64,
+}
+
+#[derive(Debug, Default)]
+struct Cache {
+    entries: HashMap<PathBuf, CacheEntry>,
+    /// Counter stamped on entries when used, to find the least recent
+    clock: u64,
+}
+
+/// Cached reader of file metadata
+#[derive(Debug, Default)]
+pub struct MetadataExtractor {
+    cache: Mutex<Cache>,
+}
+
+lazy_static::lazy_static! {
+    static ref GLOBAL_EXTRACTOR: Arc<MetadataExtractor> = Arc::new(MetadataExtractor::new());
+    static ref FFPROBE_AVAILABLE: bool = Command::new("ffprobe")
+        .arg("-version")
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false);
+}
+
+impl MetadataExtractor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Shared extractor used by the search endpoints
+    pub fn global() -> Arc<MetadataExtractor> {
+        GLOBAL_EXTRACTOR.clone()
+    }
+
+    /// Metadata of the file at `path`, or None when it isn't a file type
+    /// we understand or can't be read. Blocks on file I/O.
+    pub fn extract(&self, path: &Path) -> Option<FileMetadata> {
+        let stat = std::fs::metadata(path).ok().filter(|m| m.is_file())?;
+        let (size, modified) = (stat.len(), stat.modified().ok());
+
+        {
+            let mut cache = self.cache.lock().unwrap();
+            cache.clock += 1;
+            let clock = cache.clock;
+            if let Some(entry) = cache.entries.get_mut(path) {
+                if entry.size == size && entry.modified == modified {
+                    entry.used = clock;
+                    return entry.metadata.clone();
+                }
+            }
+        }
+
+        let metadata = read_metadata(path, size);
+
+        let mut cache = self.cache.lock().unwrap();
+        if cache.entries.len() >= MAX_CACHE_ENTRIES && !cache.entries.contains_key(path) {
+            if let Some(oldest) = cache.entries.iter().min_by_key(|(_, e)| e.used).map(|(p, _)| p.clone()) {
+                cache.entries.remove(&oldest);
+            }
+        }
+        let used = cache.clock;
+        cache.entries.insert(
+            path.to_path_buf(),
+            CacheEntry { size, modified, metadata: metadata.clone(), used },
+        );
+        metadata
+    }
+
+    /// Fill in the metadata field of each result
+    pub fn enrich(&self, results: &mut [MergedSearchResult]) {
+        for result in results {
+            if result.metadata.is_none() {
+                result.metadata = self.extract(Path::new(&result.path));
+            }
+        }
+    }
+}
+
+fn extension(path: &Path) -> String {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase())
+        .unwrap_or_default()
+}
+
+fn read_metadata(path: &Path, size: u64) -> Option<FileMetadata> {
+    match extension(path).as_str() {
+        "jpg" | "jpeg" | "png" | "gif" | "webp" | "bmp" | "tif" | "tiff" | "heic" | "heif" | "ico" => {
+            image_metadata(path)
+        }
+        "mp3" | "m4a" | "aac" | "flac" | "ogg" | "opus" | "wma" | "mp4" | "m4v" | "mov" | "mkv"
+        | "webm" | "avi" | "wmv" => ffprobe_metadata(path),
+        "wav" => ffprobe_metadata(path).or_else(|| wav_metadata(path)),
+        "pdf" if size <= MAX_DOCUMENT_BYTES => pdf_metadata(path),
+        "docx" | "xlsx" | "pptx" if size <= MAX_DOCUMENT_BYTES => office_metadata(path),
+        _ => None,
+    }
+}
+
+fn image_metadata(path: &Path) -> Option<FileMetadata> {
+    let dimensions = imagesize::size(path).ok()?;
+    let mut exif = BTreeMap::new();
+    if let Ok(data) = File::open(path)
+        .map_err(exif::Error::Io)
+        .and_then(|file| exif::Reader::new().read_from_container(&mut BufReader::new(file)))
+    {
+        for field in data.fields().filter(|f| f.ifd_num == exif::In::PRIMARY && EXIF_TAGS.contains(&f.tag)) {
+            let value = field.display_value().with_unit(&data).to_string();
+            exif.insert(field.tag.to_string(), value.trim_matches('"').to_string());
+        }
+    }
+    Some(FileMetadata::Image {
+        width: dimensions.width as u32,
+        height: dimensions.height as u32,
+        exif,
+    })
+}
+
+#[derive(Debug, Deserialize)]
+struct ProbeOutput {
+    #[serde(default)]
+    streams: Vec<ProbeStream>,
+    format: Option<ProbeFormat>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProbeStream {
+    codec_type: Option<String>,
+    codec_name: Option<String>,
+    width: Option<u32>,
+    height: Option<u32>,
+    /// Attached pictures (cover art) are reported as video streams
+    #[serde(default)]
+    disposition: HashMap<String, u8>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProbeFormat {
+    duration: Option<String>,
+}
+
+fn ffprobe_metadata(path: &Path) -> Option<FileMetadata> {
+    if !*FFPROBE_AVAILABLE {
+        return None;
+    }
+    let output = Command::new("ffprobe")
+        .args(["-v", "quiet", "-print_format", "json", "-show_format", "-show_streams"])
+        .arg(path)
+        .output()
+        .ok()
+        .filter(|o| o.status.success())?;
+    parse_ffprobe(&output.stdout)
+}
+
+pub(crate) fn parse_ffprobe(json: &[u8]) -> Option<FileMetadata> {
+    let probe: ProbeOutput = serde_json::from_slice(json).ok()?;
+    let video = probe.streams.iter().find(|s| {
+        s.codec_type.as_deref() == Some("video") && s.disposition.get("attached_pic").copied().unwrap_or(0) == 0
+    });
+    let audio = probe.streams.iter().find(|s| s.codec_type.as_deref() == Some("audio"));
+    Some(FileMetadata::Media {
+        duration_seconds: probe.format.and_then(|f| f.duration).and_then(|d| d.parse().ok()),
+        video_codec: video.and_then(|s| s.codec_name.clone()),
+        audio_codec: audio.and_then(|s| s.codec_name.clone()),
+        width: video.and_then(|s| s.width),
+        height: video.and_then(|s| s.height),
+    })
+}
+
+fn wav_metadata(path: &Path) -> Option<FileMetadata> {
+    let reader = hound::WavReader::open(path).ok()?;
+    let spec = reader.spec();
+    let codec = match spec.sample_format {
+        hound::SampleFormat::Int => format!("pcm_s{}", spec.bits_per_sample),
+        hound::SampleFormat::Float => format!("pcm_f{}", spec.bits_per_sample),
+    };
+    Some(FileMetadata::Media {
+        duration_seconds: Some(reader.duration() as f64 / spec.sample_rate as f64),
+        video_codec: None,
+        audio_codec: Some(codec),
+        width: None,
+        height: None,
+    })
+}
+
+fn pdf_metadata(path: &Path) -> Option<FileMetadata> {
+    // lopdf panics on some malformed files
+    let document = std::panic::catch_unwind(|| lopdf::Document::load(path)).ok()?.ok()?;
+    let info = document
+        .trailer
+        .get(b"Info")
+        .and_then(|info| document.dereference(info))
+        .and_then(|(_, info)| info.as_dict())
+        .ok();
+    let text = |key: &[u8]| {
+        info.and_then(|dict| dict.get_deref(key, &document).ok())
+            .and_then(|value| lopdf::decode_text_string(value).ok())
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    };
+    Some(FileMetadata::Document {
+        page_count: Some(document.get_pages().len() as u32),
+        author: text(b"Author"),
+        title: text(b"Title"),
+    })
+}
+
+/// Office Open XML files keep author and title in docProps/core.xml and
+/// page or slide counts in docProps/app.xml
+fn office_metadata(path: &Path) -> Option<FileMetadata> {
+    let mut archive = zip::ZipArchive::new(File::open(path).ok()?).ok()?;
+    let core = read_entry(&mut archive, "docProps/core.xml").map(|xml| xml_elements(&xml)).unwrap_or_default();
+    let app = read_entry(&mut archive, "docProps/app.xml").map(|xml| xml_elements(&xml)).unwrap_or_default();
+    Some(FileMetadata::Document {
+        page_count: app
+            .get("Pages")
+            .or_else(|| app.get("Slides"))
+            .and_then(|count| count.parse().ok()),
+        author: core.get("creator").cloned(),
+        title: core.get("title").cloned(),
+    })
+}
+
+fn read_entry(archive: &mut zip::ZipArchive<File>, name: &str) -> Option<String> {
+    let mut content = String::new();
+    archive.by_name(name).ok()?.read_to_string(&mut content).ok()?;
+    Some(content)
+}
+
+/// Text of each leaf element, by local name
+fn xml_elements(xml: &str) -> HashMap<String, String> {
+    let mut elements = HashMap::new();
+    let mut text = String::new();
+    for event in EventReader::new(xml.as_bytes()) {
+        match event {
+            Ok(XmlEvent::StartElement { .. }) => text.clear(),
+            Ok(XmlEvent::Characters(chars)) => text.push_str(&chars),
+            Ok(XmlEvent::EndElement { name }) => {
+                let value = text.trim();
+                if !value.is_empty() {
+                    elements.entry(name.local_name).or_insert_with(|| value.to_string());
+                }
+                text.clear();
+            }
+            Err(_) => break,
+            _ => {}
+        }
+    }
+    elements
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::TempDir;
+
+    /// Smallest PNG header imagesize needs: signature and IHDR chunk
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut bytes = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR".to_vec();
+        bytes.extend_from_slice(&width.to_be_bytes());
+        bytes.extend_from_slice(&height.to_be_bytes());
+        bytes.extend_from_slice(&[8, 6, 0, 0, 0, 0, 0, 0, 0]);
+        bytes
+    }
+
+    #[test]
+    fn test_image_dimensions_are_cached_until_modified() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("photo.png");
+        std::fs::write(&path, png(640, 480)).unwrap();
+
+        let extractor = MetadataExtractor::new();
+        let expected = FileMetadata::Image { width: 640, height: 480, exif: BTreeMap::new() };
+        assert_eq!(extractor.extract(&path), Some(expected.clone()));
+
+        // Same size and mtime: served from the cache
+        assert_eq!(extractor.cache.lock().unwrap().entries.len(), 1);
+        assert_eq!(extractor.extract(&path), Some(expected));
+
+        std::fs::write(&path, [png(1920, 1080), vec![0; 16]].concat()).unwrap();
+        assert_eq!(
+            extractor.extract(&path),
+            Some(FileMetadata::Image { width: 1920, height: 1080, exif: BTreeMap::new() })
+        );
+    }
+
+    #[test]
+    fn test_wav_duration() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("clip.wav");
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: 8000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        for _ in 0..16000 {
+            writer.write_sample(0i16).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        match wav_metadata(&path) {
+            Some(FileMetadata::Media { duration_seconds, audio_codec, .. }) => {
+                assert_eq!(duration_seconds, Some(2.0));
+                assert_eq!(audio_codec.as_deref(), Some("pcm_s16"));
+            }
+            other => panic!("unexpected metadata: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_ffprobe_skips_cover_art() {
+        let json = br#"{
+            "streams": [
+                {"codec_type": "audio", "codec_name": "mp3"},
+                {"codec_type": "video", "codec_name": "mjpeg", "width": 500, "height": 500,
+                 "disposition": {"attached_pic": 1}}
+            ],
+            "format": {"duration": "183.4"}
+        }"#;
+        assert_eq!(
+            parse_ffprobe(json),
+            Some(FileMetadata::Media {
+                duration_seconds: Some(183.4),
+                video_codec: None,
+                audio_codec: Some("mp3".to_string()),
+                width: None,
+                height: None,
+            })
+        );
+    }
+
+    #[test]
+    fn test_office_document_properties() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("report.docx");
+        let mut zip = zip::ZipWriter::new(File::create(&path).unwrap());
+        let options = zip::write::FileOptions::default();
+        zip.start_file("docProps/core.xml", options).unwrap();
+        zip.write_all(
+            br#"<cp:coreProperties xmlns:cp="cp" xmlns:dc="http://purl.org/dc/elements/1.1/">
+                <dc:title>Quarterly Report</dc:title><dc:creator>Ada</dc:creator>
+            </cp:coreProperties>"#,
+        )
+        .unwrap();
+        zip.start_file("docProps/app.xml", options).unwrap();
+        zip.write_all(b"<Properties><Pages>12</Pages></Properties>").unwrap();
+        zip.finish().unwrap();
+
+        assert_eq!(
+            MetadataExtractor::new().extract(&path),
+            Some(FileMetadata::Document {
+                page_count: Some(12),
+                author: Some("Ada".to_string()),
+                title: Some("Quarterly Report".to_string()),
+            })
+        );
+    }
+
+    #[test]
+    fn test_unknown_types_have_no_metadata() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("notes.txt");
+        std::fs::write(&path, "hello").unwrap();
+        assert_eq!(MetadataExtractor::new().extract(&path), None);
+        assert_eq!(MetadataExtractor::new().extract(&temp_dir.path().join("missing.png")), None);
+    }
+}
@@ -2,7 +2,15 @@ pub mod file_search;
 pub mod cli_engine;
 pub mod search_manager;
 pub mod ai_integration;
+pub mod ranking;
+pub mod metadata;
+pub mod multi_root;
+pub mod query;
 
 pub use file_search::*;
 pub use cli_engine::*;
-pub use search_manager::*;
\ No newline at end of file
+pub use search_manager::*;
+pub use ranking::{RankingReport, SearchRanking};
+pub use metadata::{FileMetadata, MetadataExtractor};
+pub use multi_root::{merge_root_results, RootSearchSummary};
+pub use query::{QueryError, SearchFilter, SearchQuery};
\ No newline at end of file
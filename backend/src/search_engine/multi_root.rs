@@ -0,0 +1,161 @@
+//! Searches across several root directories
+//!
+//! Each root is searched on its own, concurrently; the per-root results are
+//! then merged into one ranked list. Every merged result names the root it
+//! came from, and a summary per root reports its result count and any error
+//! so one unreachable drive doesn't fail the whole search.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use super::search_manager::{MergedSearchResult, SearchMode, UnifiedSearchResults};
+
+/// How the search of one root went
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RootSearchSummary {
+    pub root: String,
+    /// Results the root contributed before truncation
+    pub result_count: usize,
+    pub execution_time_ms: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Merge the results of searching each root into one ranked list of at most
+/// `max_results`. A path found under several overlapping roots is kept once,
+/// with its best score. Fails only if every root failed.
+pub fn merge_root_results(
+    query: &str,
+    results: Vec<(PathBuf, Result<UnifiedSearchResults, String>)>,
+    max_results: usize,
+    total_execution_time_ms: u64,
+) -> Result<UnifiedSearchResults, String> {
+    let mut summaries = Vec::new();
+    let mut best: HashMap<String, MergedSearchResult> = HashMap::new();
+    let mut search_id = None;
+    let mut mode = None;
+    let mut errors = Vec::new();
+
+    for (root, result) in results {
+        let root = root.to_string_lossy().to_string();
+        match result {
+            Ok(results) => {
+                summaries.push(RootSearchSummary {
+                    root: root.clone(),
+                    result_count: results.merged_results.len(),
+                    execution_time_ms: results.total_execution_time_ms,
+                    error: None,
+                });
+                // Feedback on a merged result is recorded against the first search
+                search_id.get_or_insert(results.search_id);
+                mode.get_or_insert(results.mode);
+                for mut result in results.merged_results {
+                    result.root = Some(root.clone());
+                    match best.get(&result.path) {
+                        Some(kept) if kept.relevance_score >= result.relevance_score => {}
+                        _ => {
+                            best.insert(result.path.clone(), result);
+                        }
+                    }
+                }
+            }
+            Err(error) => {
+                errors.push(format!("{}: {}", root, error));
+                summaries.push(RootSearchSummary {
+                    root,
+                    result_count: 0,
+                    execution_time_ms: 0,
+                    error: Some(error),
+                });
+            }
+        }
+    }
+
+    let Some(search_id) = search_id else {
+        return Err(format!("Search failed in every root ({})", errors.join("; ")));
+    };
+
+    let mut merged_results: Vec<MergedSearchResult> = best.into_values().collect();
+    merged_results.sort_by(|a, b| {
+        b.relevance_score
+            .partial_cmp(&a.relevance_score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    merged_results.truncate(max_results);
+
+    Ok(UnifiedSearchResults {
+        search_id,
+        query: query.to_string(),
+        mode: mode.unwrap_or(SearchMode::Hybrid),
+        file_results: None,
+        cli_results: None,
+        merged_results,
+        total_execution_time_ms,
+        suggestions: vec![],
+        roots: summaries,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(path: &str, score: f64) -> MergedSearchResult {
+        MergedSearchResult {
+            path: path.to_string(),
+            relevance_score: score,
+            source_engine: "test".to_string(),
+            file_type: "txt".to_string(),
+            size: None,
+            modified: None,
+            snippet: None,
+            line_number: None,
+            metadata: None,
+            root: None,
+        }
+    }
+
+    fn results(id: &str, merged: Vec<MergedSearchResult>) -> Result<UnifiedSearchResults, String> {
+        Ok(UnifiedSearchResults {
+            search_id: id.to_string(),
+            query: "notes".to_string(),
+            mode: SearchMode::Hybrid,
+            file_results: None,
+            cli_results: None,
+            merged_results: merged,
+            total_execution_time_ms: 5,
+            suggestions: vec![],
+            roots: vec![],
+        })
+    }
+
+    #[test]
+    fn test_merge_ranks_and_attributes() {
+        let merged = merge_root_results(
+            "notes",
+            vec![
+                (PathBuf::from("/home/u"), results("a", vec![result("/home/u/notes.txt", 0.6), result("/home/u/p/notes.md", 0.5)])),
+                (PathBuf::from("/mnt/drive"), Err("not mounted".to_string())),
+                (PathBuf::from("/home/u/p"), results("b", vec![result("/home/u/p/notes.md", 0.9), result("/home/u/p/old-notes", 0.2)])),
+            ],
+            2,
+            12,
+        )
+        .unwrap();
+
+        assert_eq!(merged.search_id, "a");
+        let paths: Vec<_> = merged.merged_results.iter().map(|r| (r.path.as_str(), r.root.as_deref())).collect();
+        assert_eq!(
+            paths,
+            [("/home/u/p/notes.md", Some("/home/u/p")), ("/home/u/notes.txt", Some("/home/u"))]
+        );
+        assert_eq!(merged.roots.len(), 3);
+        assert_eq!(merged.roots[0].result_count, 2);
+        assert_eq!(merged.roots[1].error.as_deref(), Some("not mounted"));
+
+        let failed = merge_root_results("notes", vec![(PathBuf::from("/mnt/drive"), Err("not mounted".to_string()))], 10, 1);
+        assert!(failed.unwrap_err().contains("/mnt/drive: not mounted"));
+    }
+}
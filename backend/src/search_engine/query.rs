@@ -0,0 +1,426 @@
+//! Query language for file search
+//!
+//! A search string mixes words with filters, e.g.
+//! `report ext:pdf,docx size:>10mb modified:<7d path:~/Projects "q3 budget"`.
+//! Words are fuzzy- or glob-matched against file names; filters become a
+//! [`SearchFilter`] that each engine applies its own way.
+
+use chrono::{DateTime, Duration, Local, NaiveDate, TimeZone, Utc};
+use std::path::Path;
+use thiserror::Error;
+
+/// Invalid query syntax, with the 1-based column of the offending token
+#[derive(Debug, Clone, PartialEq, Error)]
+#[error("{message} (column {column})")]
+pub struct QueryError {
+    pub column: usize,
+    pub message: String,
+}
+
+/// A parsed search string
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SearchQuery {
+    /// Plain words, matched against file names
+    pub terms: Vec<String>,
+    /// Directories from `path:` filters, as written
+    pub paths: Vec<String>,
+    pub filter: SearchFilter,
+}
+
+/// Restrictions on the files a search returns
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SearchFilter {
+    /// Lowercase extensions without the dot; any extension when empty
+    pub extensions: Vec<String>,
+    pub min_size: Option<u64>,
+    pub max_size: Option<u64>,
+    pub modified_after: Option<DateTime<Utc>>,
+    pub modified_before: Option<DateTime<Utc>>,
+    /// Quoted phrases the file name must contain, ignoring case
+    pub phrases: Vec<String>,
+}
+
+impl SearchQuery {
+    /// Parse a search string
+    ///
+    /// `key:value` tokens with a key other than `ext`, `size`, `modified` or
+    /// `path` are kept as words, so names like `notes:draft` still search.
+    pub fn parse(input: &str) -> Result<Self, QueryError> {
+        Self::parse_at(input, Utc::now())
+    }
+
+    /// Parse with ages like `modified:<7d` counted back from `now`
+    pub fn parse_at(input: &str, now: DateTime<Utc>) -> Result<Self, QueryError> {
+        let mut query = SearchQuery::default();
+        let mut rest = input;
+
+        loop {
+            rest = rest.trim_start();
+            if rest.is_empty() {
+                break;
+            }
+            let column = input[..input.len() - rest.len()].chars().count() + 1;
+            let error = |message: String| QueryError { column, message };
+
+            if let Some(after) = rest.strip_prefix('"') {
+                let (phrase, remainder) = quoted(after).ok_or_else(|| error("Unterminated quote".to_string()))?;
+                let phrase = phrase.trim();
+                if !phrase.is_empty() {
+                    query.filter.phrases.push(phrase.to_string());
+                }
+                rest = remainder;
+                continue;
+            }
+
+            let end = rest.find(|c: char| c.is_whitespace() || c == '"').unwrap_or(rest.len());
+            let word = &rest[..end];
+            rest = &rest[end..];
+
+            let Some((key, value)) = word.split_once(':').filter(|(key, _)| is_filter(key)) else {
+                query.terms.push(word.to_string());
+                continue;
+            };
+            let key = key.to_lowercase();
+            // A value may be quoted: path:"~/My Documents"
+            let value = match rest.strip_prefix('"') {
+                Some(after) if value.is_empty() => {
+                    let (value, remainder) = quoted(after)
+                        .ok_or_else(|| error(format!("Unterminated quote in '{}:' filter", key)))?;
+                    rest = remainder;
+                    value.trim().to_string()
+                }
+                _ => value.to_string(),
+            };
+            if value.is_empty() {
+                return Err(error(format!("'{}:' needs a value, e.g. {}", key, example(&key))));
+            }
+            query.apply(&key, &value, now).map_err(|message| error(format!("{} in '{}:{}'", message, key, value)))?;
+        }
+
+        if let (Some(min), Some(max)) = (query.filter.min_size, query.filter.max_size) {
+            if min > max {
+                return Err(QueryError { column: 1, message: "Size filters exclude every file".to_string() });
+            }
+        }
+        if let (Some(after), Some(before)) = (query.filter.modified_after, query.filter.modified_before) {
+            if after >= before {
+                return Err(QueryError { column: 1, message: "Modified filters exclude every file".to_string() });
+            }
+        }
+        Ok(query)
+    }
+
+    fn apply(&mut self, key: &str, value: &str, now: DateTime<Utc>) -> Result<(), String> {
+        let filter = &mut self.filter;
+        match key {
+            "ext" => {
+                for ext in value.split(',').map(|e| e.trim().trim_start_matches('.')) {
+                    if ext.is_empty() {
+                        return Err("Empty extension".to_string());
+                    }
+                    let ext = ext.to_lowercase();
+                    if !filter.extensions.contains(&ext) {
+                        filter.extensions.push(ext);
+                    }
+                }
+            }
+            "size" => {
+                let (min, max) = if let Some((low, high)) = value.split_once("..") {
+                    (Some(parse_size(low)?), Some(parse_size(high)?))
+                } else {
+                    match comparison(value) {
+                        ("<", size) => (None, Some(parse_size(size)?.saturating_sub(1))),
+                        ("<=", size) => (None, Some(parse_size(size)?)),
+                        (">", size) => (Some(parse_size(size)? + 1), None),
+                        ("=", size) => {
+                            let size = parse_size(size)?;
+                            (Some(size), Some(size))
+                        }
+                        // A bare size means at least that much
+                        (_, size) => (Some(parse_size(size)?), None),
+                    }
+                };
+                if let Some(min) = min {
+                    filter.min_size = Some(filter.min_size.map_or(min, |m| m.max(min)));
+                }
+                if let Some(max) = max {
+                    filter.max_size = Some(filter.max_size.map_or(max, |m| m.min(max)));
+                }
+            }
+            "modified" => {
+                let (op, when) = comparison(value);
+                let (after, before) = if let Some(date) = parse_date(when) {
+                    match op {
+                        "<" | "<=" => (None, Some(date)),
+                        ">" | ">=" => (Some(date), None),
+                        // A bare date is that whole day
+                        _ => (Some(date), Some(date + Duration::days(1))),
+                    }
+                } else {
+                    // Ages count back from now, so `<7d` is the newer side
+                    let since = now - parse_age(when)?;
+                    match op {
+                        ">" | ">=" => (None, Some(since)),
+                        _ => (Some(since), None),
+                    }
+                };
+                if let Some(after) = after {
+                    filter.modified_after = Some(filter.modified_after.map_or(after, |a| a.max(after)));
+                }
+                if let Some(before) = before {
+                    filter.modified_before = Some(filter.modified_before.map_or(before, |b| b.min(before)));
+                }
+            }
+            "path" => self.paths.push(value.to_string()),
+            _ => unreachable!("checked by is_filter"),
+        }
+        Ok(())
+    }
+
+    /// Text for the fuzzy engine: the words, or the phrases when there are
+    /// no words. Empty for a filter-only query.
+    pub fn fuzzy_text(&self) -> String {
+        if self.terms.is_empty() {
+            self.filter.phrases.join(" ")
+        } else {
+            self.terms.join(" ")
+        }
+    }
+
+    /// Name patterns for the CLI tools: every comma-separated word and phrase
+    pub fn name_patterns(&self) -> Vec<String> {
+        self.terms
+            .iter()
+            .flat_map(|term| term.split(','))
+            .map(str::trim)
+            .filter(|term| !term.is_empty())
+            .map(str::to_string)
+            .chain(self.filter.phrases.iter().cloned())
+            .collect()
+    }
+}
+
+impl SearchFilter {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Whether a file with this size and modification time passes. Files
+    /// whose size or time is unknown fail the filters that need them.
+    pub fn matches(&self, path: &Path, size: Option<u64>, modified: Option<DateTime<Utc>>) -> bool {
+        if !self.extensions.is_empty() {
+            let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("").to_lowercase();
+            if !self.extensions.contains(&ext) {
+                return false;
+            }
+        }
+        if !self.phrases.is_empty() {
+            let name = path.file_name().map(|n| n.to_string_lossy().to_lowercase()).unwrap_or_default();
+            if !self.phrases.iter().all(|phrase| name.contains(&phrase.to_lowercase())) {
+                return false;
+            }
+        }
+        if self.min_size.is_some() || self.max_size.is_some() {
+            let Some(size) = size else { return false };
+            if self.min_size.is_some_and(|min| size < min) || self.max_size.is_some_and(|max| size > max) {
+                return false;
+            }
+        }
+        if self.modified_after.is_some() || self.modified_before.is_some() {
+            let Some(modified) = modified else { return false };
+            if self.modified_after.is_some_and(|after| modified < after)
+                || self.modified_before.is_some_and(|before| modified >= before)
+            {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// [`matches`](Self::matches) for a file on disk
+    pub fn matches_file(&self, path: &Path) -> bool {
+        let metadata = std::fs::metadata(path).ok();
+        let modified = metadata.as_ref().and_then(|m| m.modified().ok()).map(DateTime::<Utc>::from);
+        self.matches(path, metadata.map(|m| m.len()), modified)
+    }
+
+    /// fd arguments for the size and time limits
+    pub fn fd_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if let Some(min) = self.min_size {
+            args.extend(["--size".to_string(), format!("+{}b", min)]);
+        }
+        if let Some(max) = self.max_size {
+            args.extend(["--size".to_string(), format!("-{}b", max)]);
+        }
+        if let Some(after) = self.modified_after {
+            args.extend(["--changed-within".to_string(), local_time(after)]);
+        }
+        if let Some(before) = self.modified_before {
+            args.extend(["--changed-before".to_string(), local_time(before)]);
+        }
+        args
+    }
+
+    /// find arguments for the size and time limits
+    pub fn find_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        // find's +N and -N are strict comparisons
+        if let Some(min) = self.min_size.filter(|min| *min > 0) {
+            args.extend(["-size".to_string(), format!("+{}c", min - 1)]);
+        }
+        if let Some(max) = self.max_size {
+            args.extend(["-size".to_string(), format!("-{}c", max + 1)]);
+        }
+        if let Some(after) = self.modified_after {
+            args.extend(["-newermt".to_string(), local_time(after)]);
+        }
+        if let Some(before) = self.modified_before {
+            args.extend(["!".to_string(), "-newermt".to_string(), local_time(before)]);
+        }
+        args
+    }
+}
+
+fn is_filter(key: &str) -> bool {
+    ["ext", "size", "modified", "path"].iter().any(|k| k.eq_ignore_ascii_case(key))
+}
+
+fn example(key: &str) -> &'static str {
+    match key {
+        "ext" => "ext:pdf",
+        "size" => "size:>10mb",
+        "modified" => "modified:<7d",
+        _ => "path:~/Projects",
+    }
+}
+
+/// Text up to the closing quote, and what follows it
+fn quoted(after_quote: &str) -> Option<(&str, &str)> {
+    let end = after_quote.find('"')?;
+    Some((&after_quote[..end], &after_quote[end + 1..]))
+}
+
+/// Leading comparison operator and the rest of a filter value
+fn comparison(value: &str) -> (&str, &str) {
+    for op in ["<=", ">=", "<", ">", "="] {
+        if let Some(rest) = value.strip_prefix(op) {
+            return (op, rest.trim());
+        }
+    }
+    ("", value.trim())
+}
+
+/// Bytes in a size like `10mb`, `1.5g` or `512` (binary units)
+fn parse_size(value: &str) -> Result<u64, String> {
+    let value = value.trim().to_lowercase();
+    let split = value.find(|c: char| !(c.is_ascii_digit() || c == '.')).unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: f64 = number
+        .parse()
+        .map_err(|_| format!("Invalid size '{}'; use a number with an optional unit, e.g. 10mb", value))?;
+    let unit: u64 = match unit {
+        "" | "b" => 1,
+        "k" | "kb" => 1 << 10,
+        "m" | "mb" => 1 << 20,
+        "g" | "gb" => 1 << 30,
+        "t" | "tb" => 1 << 40,
+        _ => return Err(format!("Unknown size unit '{}'; use b, kb, mb, gb or tb", unit)),
+    };
+    Ok((number * unit as f64) as u64)
+}
+
+/// Length of an age like `30min`, `12h`, `7d`, `2w`, `6mo` or `1y`
+fn parse_age(value: &str) -> Result<Duration, String> {
+    let split = value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: i64 = number.parse().map_err(|_| {
+        format!("Invalid age '{}'; use a number with a unit, e.g. 7d, or a date like 2024-01-31", value)
+    })?;
+    match unit.to_lowercase().as_str() {
+        "s" => Ok(Duration::seconds(number)),
+        "min" => Ok(Duration::minutes(number)),
+        "h" => Ok(Duration::hours(number)),
+        "d" => Ok(Duration::days(number)),
+        "w" => Ok(Duration::weeks(number)),
+        "mo" => Ok(Duration::days(number * 30)),
+        "y" => Ok(Duration::days(number * 365)),
+        "m" => Err("Ambiguous unit 'm'; use min for minutes or mo for months".to_string()),
+        unit => Err(format!("Unknown age unit '{}'; use s, min, h, d, w, mo or y", unit)),
+    }
+}
+
+/// Local midnight starting a `YYYY-MM-DD` date
+fn parse_date(value: &str) -> Option<DateTime<Utc>> {
+    let date = NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()?;
+    let midnight = Local.from_local_datetime(&date.and_hms_opt(0, 0, 0)?).earliest()?;
+    Some(midnight.with_timezone(&Utc))
+}
+
+/// Local time in the format fd and find accept
+fn local_time(time: DateTime<Utc>) -> String {
+    time.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_filters() {
+        let now = Utc::now();
+        let query = SearchQuery::parse_at(
+            r#"report ext:pdf,.DOCX size:>10mb modified:<7d path:"~/My Projects" "q3 budget" notes:draft"#,
+            now,
+        )
+        .unwrap();
+
+        assert_eq!(query.terms, vec!["report", "notes:draft"]);
+        assert_eq!(query.paths, vec!["~/My Projects"]);
+        assert_eq!(query.filter.extensions, vec!["pdf", "docx"]);
+        assert_eq!(query.filter.min_size, Some(10 * 1024 * 1024 + 1));
+        assert_eq!(query.filter.modified_after, Some(now - Duration::days(7)));
+        assert_eq!(query.filter.phrases, vec!["q3 budget"]);
+        assert_eq!(query.fuzzy_text(), "report notes:draft");
+        assert_eq!(query.name_patterns(), vec!["report", "notes:draft", "q3 budget"]);
+
+        let fd = query.filter.fd_args();
+        assert_eq!(&fd[..2], &["--size".to_string(), format!("+{}b", 10 * 1024 * 1024 + 1)]);
+        assert_eq!(fd[2], "--changed-within");
+
+        let matching = Path::new("/p/Q3 Budget report.pdf");
+        assert!(query.filter.matches(matching, Some(20 << 20), Some(now - Duration::days(1))));
+        assert!(!query.filter.matches(matching, Some(1 << 20), Some(now)));
+        assert!(!query.filter.matches(matching, Some(20 << 20), Some(now - Duration::days(8))));
+        assert!(!query.filter.matches(Path::new("/p/budget.pdf"), Some(20 << 20), Some(now)));
+        assert!(!query.filter.matches(matching, None, Some(now)));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = SearchQuery::parse("logs size:>10xb").unwrap_err();
+        assert_eq!(error.column, 6);
+        assert!(error.message.contains("Unknown size unit 'xb'"), "{}", error.message);
+
+        let error = SearchQuery::parse("modified:<7m").unwrap_err();
+        assert!(error.message.contains("min for minutes"), "{}", error.message);
+
+        assert!(SearchQuery::parse("ext:").unwrap_err().message.contains("e.g. ext:pdf"));
+        assert_eq!(SearchQuery::parse(r#"a "open phrase"#).unwrap_err().column, 3);
+        assert!(SearchQuery::parse("size:<1kb size:>1mb").is_err());
+    }
+
+    #[test]
+    fn test_size_and_date_ranges() {
+        let query = SearchQuery::parse("size:1kb..2kb modified:2024-03-01").unwrap();
+        assert_eq!((query.filter.min_size, query.filter.max_size), (Some(1024), Some(2048)));
+        let after = query.filter.modified_after.unwrap();
+        assert_eq!(query.filter.modified_before, Some(after + Duration::days(1)));
+        assert!(query.terms.is_empty());
+        assert_eq!(query.fuzzy_text(), "");
+
+        let find = query.filter.find_args();
+        assert_eq!(&find[..4], &["-size", "+1023c", "-size", "-2049c"]);
+        assert_eq!(find[6], "!");
+    }
+}
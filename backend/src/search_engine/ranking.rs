@@ -0,0 +1,307 @@
+//! Result ranking learned from clicks
+//!
+//! Every search result the user opens counts as a click for its extension
+//! and for the directories above it. Counts decay with a 30 day half-life, so
+//! yesterday's project outranks last year's. Results get a bounded boost from
+//! the counts, which lets frequently used folders rise above one-off matches
+//! in places like ~/Downloads without drowning out the match score itself.
+//!
+//! The home directory and its ancestors are never learned: every file sits
+//! below them, so a click there says nothing about where the user works.
+//! Learned weights persist to `~/.skhoot/search_ranking.json`.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use super::search_manager::MergedSearchResult;
+
+/// Days after which a click counts half
+const HALF_LIFE_DAYS: f64 = 30.0;
+/// Directory levels above a clicked file that are credited
+const DIRECTORY_LEVELS: usize = 4;
+/// Share of a directory's boost kept at each level further up
+const LEVEL_DISCOUNT: f64 = 0.6;
+/// Decayed clicks at which a key reaches half its maximum boost
+const SATURATION: f64 = 3.0;
+/// Largest boosts from extension and directory clicks
+const MAX_EXTENSION_BOOST: f64 = 0.1;
+const MAX_DIRECTORY_BOOST: f64 = 0.2;
+
+/// Decayed click count of one extension or directory
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ClickWeight {
+    pub clicks: f64,
+    pub last_click: DateTime<Utc>,
+}
+
+impl ClickWeight {
+    fn decayed(&self, now: DateTime<Utc>) -> f64 {
+        let days = (now - self.last_click).num_seconds().max(0) as f64 / 86_400.0;
+        self.clicks * 0.5f64.powf(days / HALF_LIFE_DAYS)
+    }
+
+    fn add_click(&mut self, now: DateTime<Utc>) {
+        self.clicks = self.decayed(now) + 1.0;
+        self.last_click = now;
+    }
+}
+
+/// Everything learned so far
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LearnedWeights {
+    pub total_clicks: u64,
+    #[serde(default)]
+    pub extensions: HashMap<String, ClickWeight>,
+    #[serde(default)]
+    pub directories: HashMap<String, ClickWeight>,
+}
+
+/// A learned weight as reported by the API
+#[derive(Debug, Clone, Serialize)]
+pub struct WeightReport {
+    pub key: String,
+    /// Clicks after decay
+    pub clicks: f64,
+    pub last_click: DateTime<Utc>,
+    /// Score added to results this weight applies to
+    pub boost: f64,
+}
+
+/// Learned weights, strongest first
+#[derive(Debug, Clone, Serialize)]
+pub struct RankingReport {
+    pub total_clicks: u64,
+    pub extensions: Vec<WeightReport>,
+    pub directories: Vec<WeightReport>,
+}
+
+fn saturate(clicks: f64) -> f64 {
+    clicks / (clicks + SATURATION)
+}
+
+fn extension_key(path: &Path) -> Option<String> {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase())
+}
+
+/// Click-learned ranking boosts
+#[derive(Debug)]
+pub struct SearchRanking {
+    path: PathBuf,
+    /// Directories too general to learn: home and everything above it
+    excluded: Vec<PathBuf>,
+    weights: RwLock<LearnedWeights>,
+}
+
+lazy_static::lazy_static! {
+    static ref GLOBAL_RANKING: Arc<SearchRanking> = {
+        let home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
+        Arc::new(SearchRanking::new(home.join(".skhoot").join("search_ranking.json"), &home))
+    };
+}
+
+impl SearchRanking {
+    /// Open the weights stored at `path`, starting empty if the file is
+    /// missing or unreadable
+    pub fn new(path: PathBuf, home: &Path) -> Self {
+        let weights = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Self {
+            path,
+            excluded: home.ancestors().map(Path::to_path_buf).collect(),
+            weights: RwLock::new(weights),
+        }
+    }
+
+    /// Shared ranking at `~/.skhoot/search_ranking.json`
+    pub fn global() -> Arc<SearchRanking> {
+        GLOBAL_RANKING.clone()
+    }
+
+    /// Directories credited for a file, nearest first
+    fn directories<'a>(&'a self, file: &'a Path) -> impl Iterator<Item = (usize, &'a Path)> + 'a {
+        file.ancestors()
+            .skip(1)
+            .take(DIRECTORY_LEVELS)
+            .enumerate()
+            .filter(|(_, dir)| !dir.as_os_str().is_empty() && !self.excluded.iter().any(|e| e == dir))
+    }
+
+    /// Learn from the user opening `path`
+    pub fn record_click(&self, path: &str) -> std::io::Result<()> {
+        self.record_click_at(path, Utc::now())
+    }
+
+    fn record_click_at(&self, path: &str, now: DateTime<Utc>) -> std::io::Result<()> {
+        let file = Path::new(path);
+        let mut weights = self.weights.write().unwrap();
+        let new_weight = || ClickWeight { clicks: 0.0, last_click: now };
+
+        weights.total_clicks += 1;
+        if let Some(extension) = extension_key(file) {
+            weights.extensions.entry(extension).or_insert_with(new_weight).add_click(now);
+        }
+        for (_, dir) in self.directories(file) {
+            weights
+                .directories
+                .entry(dir.to_string_lossy().into_owned())
+                .or_insert_with(new_weight)
+                .add_click(now);
+        }
+        self.save(&weights)
+    }
+
+    /// Score added to a result at `path`
+    fn boost_at(&self, weights: &LearnedWeights, path: &str, now: DateTime<Utc>) -> f64 {
+        let file = Path::new(path);
+        let extension = extension_key(file)
+            .and_then(|e| weights.extensions.get(&e))
+            .map(|w| saturate(w.decayed(now)))
+            .unwrap_or(0.0);
+        let directory = self
+            .directories(file)
+            .filter_map(|(level, dir)| {
+                let weight = weights.directories.get(dir.to_string_lossy().as_ref())?;
+                Some(saturate(weight.decayed(now)) * LEVEL_DISCOUNT.powi(level as i32))
+            })
+            .fold(0.0, f64::max);
+        extension * MAX_EXTENSION_BOOST + directory * MAX_DIRECTORY_BOOST
+    }
+
+    /// Apply learned boosts and sort best first. Scores stay within 0..=1:
+    /// match scores are scaled down by the largest possible boost first, so
+    /// results keep their order until clicks say otherwise.
+    pub fn rank(&self, results: &mut [MergedSearchResult]) {
+        let weights = self.weights.read().unwrap();
+        if weights.total_clicks > 0 {
+            let now = Utc::now();
+            let scale = 1.0 - MAX_EXTENSION_BOOST - MAX_DIRECTORY_BOOST;
+            for result in results.iter_mut() {
+                result.relevance_score = result.relevance_score * scale + self.boost_at(&weights, &result.path, now);
+            }
+        }
+        results.sort_by(|a, b| {
+            b.relevance_score
+                .partial_cmp(&a.relevance_score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+    }
+
+    /// Learned weights with their current boosts
+    pub fn report(&self) -> RankingReport {
+        let weights = self.weights.read().unwrap();
+        let now = Utc::now();
+        let list = |map: &HashMap<String, ClickWeight>, max_boost: f64| {
+            let mut list: Vec<WeightReport> = map
+                .iter()
+                .map(|(key, weight)| {
+                    let clicks = weight.decayed(now);
+                    WeightReport {
+                        key: key.clone(),
+                        clicks,
+                        last_click: weight.last_click,
+                        boost: saturate(clicks) * max_boost,
+                    }
+                })
+                .collect();
+            list.sort_by(|a, b| b.clicks.partial_cmp(&a.clicks).unwrap_or(std::cmp::Ordering::Equal));
+            list
+        };
+        RankingReport {
+            total_clicks: weights.total_clicks,
+            extensions: list(&weights.extensions, MAX_EXTENSION_BOOST),
+            directories: list(&weights.directories, MAX_DIRECTORY_BOOST),
+        }
+    }
+
+    /// Forget everything learned
+    pub fn reset(&self) -> std::io::Result<()> {
+        let mut weights = self.weights.write().unwrap();
+        *weights = LearnedWeights::default();
+        self.save(&weights)
+    }
+
+    fn save(&self, weights: &LearnedWeights) -> std::io::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let content = serde_json::to_string_pretty(weights)?;
+        std::fs::write(&self.path, content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn result(path: &str, score: f64) -> MergedSearchResult {
+        MergedSearchResult {
+            path: path.to_string(),
+            relevance_score: score,
+            source_engine: "test".to_string(),
+            file_type: "rs".to_string(),
+            size: None,
+            modified: None,
+            snippet: None,
+            line_number: None,
+            metadata: None,
+            root: None,
+        }
+    }
+
+    #[test]
+    fn test_clicks_lift_project_folders() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = temp_dir.path().join("ranking.json");
+        let ranking = SearchRanking::new(store.clone(), Path::new("/home/me"));
+
+        // Unlearned ranking keeps the match order
+        let mut results = vec![result("/home/me/Downloads/main.rs", 0.9), result("/home/me/code/app/src/main.rs", 0.8)];
+        ranking.rank(&mut results);
+        assert_eq!(results[0].path, "/home/me/Downloads/main.rs");
+        assert_eq!(results[0].relevance_score, 0.9);
+
+        for _ in 0..5 {
+            ranking.record_click("/home/me/code/app/src/lib.rs").unwrap();
+        }
+        ranking.rank(&mut results);
+        assert_eq!(results[0].path, "/home/me/code/app/src/main.rs");
+        assert!(results[0].relevance_score <= 1.0);
+
+        // Home itself is never learned
+        let report = ranking.report();
+        assert_eq!(report.total_clicks, 5);
+        assert_eq!(report.extensions[0].key, "rs");
+        let dirs: Vec<&str> = report.directories.iter().map(|d| d.key.as_str()).collect();
+        assert!(dirs.contains(&"/home/me/code/app/src"));
+        assert!(dirs.contains(&"/home/me/code"));
+        assert!(!dirs.contains(&"/home/me") && !dirs.contains(&"/home"));
+
+        // Weights persist, and reset clears them
+        let reopened = SearchRanking::new(store.clone(), Path::new("/home/me"));
+        assert_eq!(reopened.report().total_clicks, 5);
+        reopened.reset().unwrap();
+        assert_eq!(SearchRanking::new(store, Path::new("/home/me")).report().total_clicks, 0);
+    }
+
+    #[test]
+    fn test_clicks_decay() {
+        let temp_dir = TempDir::new().unwrap();
+        let ranking = SearchRanking::new(temp_dir.path().join("ranking.json"), Path::new("/home/me"));
+        let then = Utc::now() - chrono::Duration::days(60);
+        ranking.record_click_at("/work/report.pdf", then).unwrap();
+        ranking.record_click_at("/work/report.pdf", then).unwrap();
+
+        let weights = ranking.weights.read().unwrap();
+        let clicks = weights.directories["/work"].decayed(Utc::now());
+        assert!((clicks - 0.5).abs() < 0.01, "{}", clicks);
+        assert!(ranking.boost_at(&weights, "/work/notes.pdf", Utc::now()) < ranking.boost_at(&weights, "/work/notes.pdf", then));
+    }
+}
@@ -11,6 +11,10 @@ use uuid::Uuid;
 
 use super::file_search::{FileSearchEngine, FileSearchConfig, FileSearchResults};
 use super::cli_engine::{CliEngine, CliConfig, CliSearchResult};
+use super::ranking::SearchRanking;
+use super::metadata::FileMetadata;
+use super::multi_root::RootSearchSummary;
+use super::query::SearchFilter;
 
 /// Unified search manager that coordinates between different search engines
 /// and provides AI-optimized search capabilities
@@ -20,6 +24,9 @@ pub struct SearchManager {
     cli_engine: CliEngine,
     active_searches: Arc<RwLock<HashMap<String, SearchHandle>>>,
     search_history: Arc<RwLock<Vec<SearchHistoryEntry>>>,
+    ranking: Arc<SearchRanking>,
+    /// Known filenames fuzzy searches score instead of walking
+    filename_index: Option<Arc<crate::filename_index::FilenameIndex>>,
     pub config: SearchManagerConfig,
 }
 
@@ -62,6 +69,18 @@ pub enum SearchMode {
     Auto,
 }
 
+impl SearchMode {
+    /// Label value used in metrics
+    pub fn metrics_label(&self) -> &'static str {
+        match self {
+            SearchMode::RustEngine => "rust",
+            SearchMode::CliOnly => "cli",
+            SearchMode::Hybrid => "hybrid",
+            SearchMode::Auto => "auto",
+        }
+    }
+}
+
 /// Handle for tracking ongoing searches
 #[derive(Debug, Clone)]
 pub struct SearchHandle {
@@ -103,6 +122,9 @@ pub struct UnifiedSearchResults {
     pub merged_results: Vec<MergedSearchResult>,
     pub total_execution_time_ms: u64,
     pub suggestions: Vec<SearchSuggestion>,
+    /// Per-root summary of a search across several directories
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub roots: Vec<RootSearchSummary>,
 }
 
 /// A merged result from multiple search engines
@@ -116,6 +138,12 @@ pub struct MergedSearchResult {
     pub modified: Option<chrono::DateTime<chrono::Utc>>,
     pub snippet: Option<String>,
     pub line_number: Option<usize>,
+    /// Image, media or document details, filled in on request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<FileMetadata>,
+    /// Search root the result was found under, for multi-root searches
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub root: Option<String>,
 }
 
 /// Search suggestions for improving queries
@@ -155,10 +183,73 @@ impl SearchManager {
             cli_engine,
             active_searches: Arc::new(RwLock::new(HashMap::new())),
             search_history: Arc::new(RwLock::new(Vec::new())),
+            ranking: SearchRanking::global(),
+            filename_index: None,
             config,
         }
     }
 
+    /// Use `index` for fuzzy searches it covers
+    pub fn with_filename_index(mut self, index: Arc<crate::filename_index::FilenameIndex>) -> Self {
+        self.filename_index = Some(index);
+        self
+    }
+
+    pub fn filename_index(&self) -> Option<&Arc<crate::filename_index::FilenameIndex>> {
+        self.filename_index.as_ref()
+    }
+
+    /// Copy of this manager with per-request ignore overrides applied to
+    /// both engines. Search history and active searches stay shared.
+    pub fn with_ignore_overrides(&self, respect_gitignore: Option<bool>, respect_skhootignore: Option<bool>) -> Self {
+        if respect_gitignore.is_none() && respect_skhootignore.is_none() {
+            return self.clone();
+        }
+        let mut manager = self.clone();
+        let config = &mut manager.config;
+        if let Some(respect) = respect_gitignore {
+            config.file_search_config.respect_gitignore = respect;
+            config.cli_config.respect_gitignore = respect;
+        }
+        if let Some(respect) = respect_skhootignore {
+            config.file_search_config.respect_skhootignore = respect;
+            config.cli_config.respect_skhootignore = respect;
+        }
+        manager.file_search_engine = FileSearchEngine::new(config.file_search_config.clone());
+        manager
+    }
+
+    /// Copy of this manager whose fuzzy search may enter network or
+    /// removable mounts below the search directory
+    pub fn with_mount_overrides(&self, include_network: Option<bool>, include_removable: Option<bool>) -> Self {
+        if include_network.is_none() && include_removable.is_none() {
+            return self.clone();
+        }
+        let mut manager = self.clone();
+        let mounts = &mut manager.config.file_search_config.mounts;
+        if let Some(include) = include_network {
+            mounts.include_network = include;
+        }
+        if let Some(include) = include_removable {
+            mounts.include_removable = include;
+        }
+        manager.file_search_engine = FileSearchEngine::new(manager.config.file_search_config.clone());
+        manager
+    }
+
+    /// Copy of this manager whose engines only return files passing
+    /// `filter`
+    pub fn with_filter(&self, filter: &SearchFilter) -> Self {
+        if filter.is_empty() {
+            return self.clone();
+        }
+        let mut manager = self.clone();
+        manager.config.file_search_config.filter = filter.clone();
+        manager.config.cli_config.filter = filter.clone();
+        manager.file_search_engine = FileSearchEngine::new(manager.config.file_search_config.clone());
+        manager
+    }
+
     /// Perform a unified search using the configured strategy
     pub async fn search(
         &self,
@@ -188,31 +279,51 @@ impl SearchManager {
         }
 
         // Execute search based on mode
-        let (file_results, cli_results) = match mode {
-            SearchMode::RustEngine => {
-                let file_res = self.file_search_engine.search(query, search_dir, true).await?;
-                (Some(file_res), None)
-            }
-            SearchMode::CliOnly => {
-                let cli_res = self.cli_engine.search_files(query, &self.config.cli_config).await?;
-                (None, Some(cli_res))
-            }
-            SearchMode::Hybrid => {
-                let (file_res, cli_res) = tokio::try_join!(
-                    self.file_search_engine.search(query, search_dir, true),
-                    self.cli_engine.search_files(query, &self.config.cli_config)
-                )?;
-                (Some(file_res), Some(cli_res))
-            }
-            SearchMode::Auto => {
-                // Start with Rust engine, fall back to CLI if needed
-                match self.file_search_engine.search(query, search_dir, true).await {
-                    Ok(file_res) if !file_res.matches.is_empty() => (Some(file_res), None),
-                    _ => {
-                        let cli_res = self.cli_engine.search_files(query, &self.config.cli_config).await?;
-                        (None, Some(cli_res))
+        let engine_results: Result<_> = async {
+            Ok(match mode {
+                SearchMode::RustEngine => {
+                    let file_res = self.fuzzy_search(query, search_dir).await?;
+                    (Some(file_res), None)
+                }
+                SearchMode::CliOnly => {
+                    let cli_res = self.cli_engine.search_files(query, &self.config.cli_config).await?;
+                    (None, Some(cli_res))
+                }
+                SearchMode::Hybrid => {
+                    let (file_res, cli_res) = tokio::try_join!(
+                        self.fuzzy_search(query, search_dir),
+                        self.cli_engine.search_files(query, &self.config.cli_config)
+                    )?;
+                    (Some(file_res), Some(cli_res))
+                }
+                SearchMode::Auto => {
+                    // Start with Rust engine, fall back to CLI if needed
+                    match self.fuzzy_search(query, search_dir).await {
+                        Ok(file_res) if !file_res.matches.is_empty() => (Some(file_res), None),
+                        _ => {
+                            let cli_res = self.cli_engine.search_files(query, &self.config.cli_config).await?;
+                            (None, Some(cli_res))
+                        }
                     }
                 }
+            })
+        }
+        .await;
+        let (file_results, cli_results) = match engine_results {
+            Ok(results) => results,
+            Err(e) => {
+                crate::metrics::inc_counter("skhoot_searches_total", &[("mode", mode.metrics_label()), ("outcome", "error")]);
+                let mut active = self.active_searches.write().await;
+                if let Some(handle) = active.get_mut(&search_id) {
+                    handle.status = SearchStatus::Failed(e.to_string());
+                }
+                crate::events::publish(crate::events::Event::SearchCompleted {
+                    search_id: search_id.clone(),
+                    query: query.to_string(),
+                    results: 0,
+                    error: Some(e.to_string()),
+                });
+                return Err(e);
             }
         };
 
@@ -231,13 +342,15 @@ impl SearchManager {
                         merged_results: Vec::new(),
                         total_execution_time_ms: start_time.elapsed().as_millis() as u64,
                         suggestions: Vec::new(),
+                        roots: Vec::new(),
                     });
                 }
             }
         }
 
         // Merge results
-        let merged_results = self.merge_results(&file_results, &cli_results);
+        let mut merged_results = self.merge_results(&file_results, &cli_results);
+        self.rank(&mut merged_results);
 
         // Generate suggestions
         let suggestions = if self.config.enable_search_suggestions {
@@ -247,6 +360,8 @@ impl SearchManager {
         };
 
         let total_execution_time_ms = start_time.elapsed().as_millis() as u64;
+        crate::metrics::inc_counter("skhoot_searches_total", &[("mode", mode.metrics_label()), ("outcome", "ok")]);
+        crate::metrics::observe_duration("skhoot_search_duration_seconds", &[("mode", mode.metrics_label())], start_time.elapsed());
 
         // Update search status
         {
@@ -258,6 +373,12 @@ impl SearchManager {
 
         // Add to history
         self.add_to_history(&search_id, query, &mode, &merged_results, total_execution_time_ms).await;
+        crate::events::publish(crate::events::Event::SearchCompleted {
+            search_id: search_id.clone(),
+            query: query.to_string(),
+            results: merged_results.len(),
+            error: None,
+        });
 
         Ok(UnifiedSearchResults {
             search_id,
@@ -268,6 +389,7 @@ impl SearchManager {
             merged_results,
             total_execution_time_ms,
             suggestions,
+            roots: Vec::new(),
         })
     }
 
@@ -284,7 +406,8 @@ impl SearchManager {
         // For content search, prefer CLI tools
         let cli_results = self.cli_engine.search_content(query, &self.config.cli_config).await?;
 
-        let merged_results = self.merge_results(&None, &Some(cli_results.clone()));
+        let mut merged_results = self.merge_results(&None, &Some(cli_results.clone()));
+        self.rank(&mut merged_results);
         let suggestions = if self.config.enable_search_suggestions {
             self.generate_suggestions(query, &merged_results, context.as_ref()).await
         } else {
@@ -302,6 +425,7 @@ impl SearchManager {
             merged_results,
             total_execution_time_ms,
             suggestions,
+            roots: Vec::new(),
         })
     }
 
@@ -324,6 +448,30 @@ impl SearchManager {
         history.iter().rev().take(limit).cloned().collect()
     }
 
+    /// Record that the user opened a result, marking it in the search's
+    /// history entry and learning from it for later rankings
+    pub async fn record_selection(&self, search_id: Option<&str>, path: &str) -> Result<()> {
+        if let Some(search_id) = search_id {
+            let mut history = self.search_history.write().await;
+            if let Some(entry) = history.iter_mut().rev().find(|e| e.id == search_id) {
+                entry.user_selected_result = Some(path.to_string());
+            }
+        }
+        if crate::config::SettingsStore::global().get().search.learn_from_clicks {
+            self.ranking.record_click(path)?;
+        }
+        Ok(())
+    }
+
+    /// Apply click-learned boosts and sort results best first
+    pub fn rank(&self, results: &mut [MergedSearchResult]) {
+        if crate::config::SettingsStore::global().get().search.learn_from_clicks {
+            self.ranking.rank(results);
+        } else {
+            results.sort_by(|a, b| b.relevance_score.partial_cmp(&a.relevance_score).unwrap_or(std::cmp::Ordering::Equal));
+        }
+    }
+
     /// Analyze search patterns to detect when file search should be suggested
     pub async fn should_suggest_file_search(&self, prompt: &str) -> bool {
         // AI-driven detection of file search intent
@@ -367,6 +515,22 @@ impl SearchManager {
 
     // Private helper methods
 
+    /// Fuzzy search scoring the filename index's files when it covers
+    /// `search_dir`, walking the directory otherwise
+    async fn fuzzy_search(&self, query: &str, search_dir: &Path) -> Result<FileSearchResults> {
+        let index = self
+            .filename_index
+            .clone()
+            .filter(|_| crate::filename_index::FilenameIndex::serves(&self.config.file_search_config));
+        if let Some(index) = index {
+            let (dir, text) = (search_dir.to_path_buf(), query.to_string());
+            if let Some(candidates) = tokio::task::spawn_blocking(move || index.candidates(&dir, &text)).await? {
+                return self.file_search_engine.search_candidates(query, search_dir, candidates, true).await;
+            }
+        }
+        self.file_search_engine.search(query, search_dir, true).await
+    }
+
     async fn determine_search_mode(&self, query: &str, context: Option<&SearchContext>) -> SearchMode {
         match &self.config.default_search_mode {
             SearchMode::Auto => {
@@ -436,6 +600,8 @@ impl SearchManager {
                     modified: file_match.modified,
                     snippet: None,
                     line_number: None,
+                    metadata: None,
+                    root: None,
                 });
             }
         }
@@ -456,6 +622,8 @@ impl SearchManager {
                     modified: None,
                     snippet: cli_match.content.clone(),
                     line_number: cli_match.line_number,
+                    metadata: None,
+                    root: None,
                 });
             }
         }
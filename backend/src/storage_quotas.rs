@@ -0,0 +1,529 @@
+//! Storage quotas with alerts
+//!
+//! A quota caps the space taken by one directory (`~/Downloads < 50 GB`) or
+//! by every directory with a given name below some roots (`node_modules`
+//! across all projects < 20 GB). The monitor re-measures each enabled quota
+//! periodically. When a recent treemap scan covers a quota's directories,
+//! the sizes come from the files that scan recorded; otherwise the
+//! directories are walked.
+//!
+//! Crossing the limit publishes [`Event::StorageQuota`], which the
+//! notifier shows on the desktop, and starts the quota's workflow, if it
+//! names one. Dropping back below the limit publishes the event again so
+//! the next crossing alerts anew. Quotas persist to
+//! `~/.skhoot/storage_quotas.json`.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use crate::disk_analyzer::TreemapScans;
+use crate::events::Event;
+use crate::ignore_rules::IgnoreRules;
+use crate::json_store::{self, non_empty};
+use crate::mounts::{MountGuard, MountPolicy};
+use crate::workflows::{ExecuteWorkflowRequest, WorkflowEngine};
+
+/// How often a quota is re-measured
+pub const EVALUATION_INTERVAL_SECS: u64 = 900;
+/// Oldest treemap scan whose files are used instead of walking
+const SCAN_MAX_AGE_SECS: u64 = 3600;
+/// How often the monitor looks for quotas that are due
+const MONITOR_TICK_SECS: u64 = 60;
+
+/// What a quota measures
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum QuotaTarget {
+    /// Everything below one directory
+    Directory { path: String },
+    /// Every directory called `name` below the roots, nested ones counted once
+    Named { name: String, roots: Vec<String> },
+}
+
+impl QuotaTarget {
+    fn describe(&self) -> String {
+        match self {
+            QuotaTarget::Directory { path } => path.clone(),
+            QuotaTarget::Named { name, roots } => format!("{} under {}", name, roots.join(", ")),
+        }
+    }
+}
+
+/// How a quota's size was measured
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuotaSource {
+    /// From the files a treemap scan recorded
+    Scan,
+    Walk,
+}
+
+/// A limit on the space some directories may take
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StorageQuota {
+    pub id: String,
+    pub name: String,
+    pub target: QuotaTarget,
+    pub limit_bytes: u64,
+    /// Workflow started when the limit is crossed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workflow_id: Option<String>,
+    pub enabled: bool,
+    pub created_at: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_evaluated_at: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_size: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_source: Option<QuotaSource>,
+    /// When the size went over the limit; unset while under it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exceeded_since: Option<i64>,
+}
+
+/// Fields of a new quota
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreateQuotaRequest {
+    #[serde(default)]
+    pub name: Option<String>,
+    pub target: QuotaTarget,
+    pub limit_bytes: u64,
+    #[serde(default)]
+    pub workflow_id: Option<String>,
+    #[serde(default)]
+    pub enabled: Option<bool>,
+}
+
+/// Changes to a quota; `workflow_id` is cleared with an empty string
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct UpdateQuotaRequest {
+    pub name: Option<String>,
+    pub target: Option<QuotaTarget>,
+    pub limit_bytes: Option<u64>,
+    pub workflow_id: Option<String>,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum QuotaError {
+    #[error("Storage quota not found: {0}")]
+    NotFound(String),
+
+    #[error("{0}")]
+    Invalid(String),
+
+    #[error("Failed to save storage quotas: {0}")]
+    Io(String),
+}
+
+/// Persistent storage quotas
+pub struct QuotaStore {
+    path: PathBuf,
+    quotas: RwLock<Vec<StorageQuota>>,
+}
+
+lazy_static::lazy_static! {
+    static ref GLOBAL_STORE: Arc<QuotaStore> = Arc::new(QuotaStore::new(
+        dirs::home_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join(".skhoot")
+            .join("storage_quotas.json"),
+    ));
+}
+
+impl QuotaStore {
+    /// Open the store at `path`; see [`json_store::load`]
+    pub fn new(path: PathBuf) -> Self {
+        let quotas = json_store::load(&path);
+        Self {
+            path,
+            quotas: RwLock::new(quotas),
+        }
+    }
+
+    /// Shared store at `~/.skhoot/storage_quotas.json`
+    pub fn global() -> Arc<QuotaStore> {
+        GLOBAL_STORE.clone()
+    }
+
+    pub fn list(&self) -> Vec<StorageQuota> {
+        self.quotas.read().unwrap().clone()
+    }
+
+    pub fn get(&self, id: &str) -> Option<StorageQuota> {
+        self.quotas.read().unwrap().iter().find(|q| q.id == id).cloned()
+    }
+
+    pub fn create(&self, request: CreateQuotaRequest) -> Result<StorageQuota, QuotaError> {
+        let target = check_target(request.target)?;
+        let quota = StorageQuota {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: non_empty(request.name).unwrap_or_else(|| target.describe()),
+            target,
+            limit_bytes: check_limit(request.limit_bytes)?,
+            workflow_id: non_empty(request.workflow_id),
+            enabled: request.enabled.unwrap_or(true),
+            created_at: chrono::Utc::now().timestamp(),
+            last_evaluated_at: None,
+            last_size: None,
+            last_source: None,
+            exceeded_since: None,
+        };
+        let mut quotas = self.quotas.write().unwrap();
+        quotas.push(quota.clone());
+        self.save(&quotas)?;
+        Ok(quota)
+    }
+
+    pub fn update(&self, id: &str, request: UpdateQuotaRequest) -> Result<StorageQuota, QuotaError> {
+        let mut quotas = self.quotas.write().unwrap();
+        let quota = quotas
+            .iter_mut()
+            .find(|q| q.id == id)
+            .ok_or_else(|| QuotaError::NotFound(id.to_string()))?;
+
+        if let Some(name) = non_empty(request.name) {
+            quota.name = name;
+        }
+        if let Some(target) = request.target {
+            let target = check_target(target)?;
+            if target != quota.target {
+                // Measured afresh on the next evaluation
+                quota.target = target;
+                quota.last_evaluated_at = None;
+                quota.last_size = None;
+                quota.last_source = None;
+                quota.exceeded_since = None;
+            }
+        }
+        if let Some(limit) = request.limit_bytes {
+            quota.limit_bytes = check_limit(limit)?;
+        }
+        if let Some(workflow_id) = request.workflow_id {
+            quota.workflow_id = non_empty(Some(workflow_id));
+        }
+        if let Some(enabled) = request.enabled {
+            quota.enabled = enabled;
+        }
+        let quota = quota.clone();
+        self.save(&quotas)?;
+        Ok(quota)
+    }
+
+    /// Remove a quota. Returns whether it existed.
+    pub fn remove(&self, id: &str) -> Result<bool, QuotaError> {
+        let mut quotas = self.quotas.write().unwrap();
+        let before = quotas.len();
+        quotas.retain(|q| q.id != id);
+        if quotas.len() == before {
+            return Ok(false);
+        }
+        self.save(&quotas)?;
+        Ok(true)
+    }
+
+    /// Enabled quotas not measured in the last evaluation interval at `now`
+    pub fn due(&self, now: i64) -> Vec<StorageQuota> {
+        self.quotas
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|q| q.enabled)
+            .filter(|q| q.last_evaluated_at.is_none_or(|at| now - at >= EVALUATION_INTERVAL_SECS as i64))
+            .cloned()
+            .collect()
+    }
+
+    /// Store a measurement. Returns the quota and, when the size crossed the
+    /// limit in either direction, whether it is now over it.
+    fn record(
+        &self,
+        id: &str,
+        size: u64,
+        source: QuotaSource,
+        now: i64,
+    ) -> Result<(StorageQuota, Option<bool>), QuotaError> {
+        let mut quotas = self.quotas.write().unwrap();
+        let quota = quotas
+            .iter_mut()
+            .find(|q| q.id == id)
+            .ok_or_else(|| QuotaError::NotFound(id.to_string()))?;
+
+        let exceeded = size > quota.limit_bytes;
+        let crossed = (exceeded != quota.exceeded_since.is_some()).then_some(exceeded);
+        quota.last_evaluated_at = Some(now);
+        quota.last_size = Some(size);
+        quota.last_source = Some(source);
+        if crossed.is_some() {
+            quota.exceeded_since = exceeded.then_some(now);
+        }
+        let quota = quota.clone();
+        self.save(&quotas)?;
+        Ok((quota, crossed))
+    }
+
+    fn save(&self, quotas: &[StorageQuota]) -> Result<(), QuotaError> {
+        json_store::save(&self.path, quotas).map_err(|e| QuotaError::Io(e.to_string()))
+    }
+}
+
+fn check_limit(limit_bytes: u64) -> Result<u64, QuotaError> {
+    if limit_bytes == 0 {
+        return Err(QuotaError::Invalid("A quota's limit must be above zero".to_string()));
+    }
+    Ok(limit_bytes)
+}
+
+fn check_target(target: QuotaTarget) -> Result<QuotaTarget, QuotaError> {
+    match target {
+        QuotaTarget::Directory { path } => {
+            let path = non_empty(Some(path))
+                .ok_or_else(|| QuotaError::Invalid("A quota needs a directory".to_string()))?;
+            Ok(QuotaTarget::Directory { path })
+        }
+        QuotaTarget::Named { name, roots } => {
+            let name = non_empty(Some(name))
+                .filter(|n| !n.contains(['/', '\\']))
+                .ok_or_else(|| QuotaError::Invalid("A directory name is required, without slashes".to_string()))?;
+            let roots: Vec<String> = roots.into_iter().filter_map(|r| non_empty(Some(r))).collect();
+            if roots.is_empty() {
+                return Err(QuotaError::Invalid(format!("Say where to look for {} directories", name)));
+            }
+            Ok(QuotaTarget::Named { name, roots })
+        }
+    }
+}
+
+/// `~` expanded to the home directory
+fn expand(path: &str) -> PathBuf {
+    match (path.strip_prefix('~'), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest.trim_start_matches(['/', '\\'])),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Whether `file` lies in a directory called `name` below `root`
+fn inside_named(root: &Path, name: &str, file: &Path) -> bool {
+    let Ok(relative) = file.strip_prefix(root) else {
+        return false;
+    };
+    let mut components: Vec<Component> = relative.components().collect();
+    components.pop();
+    components.iter().any(|c| c.as_os_str() == name)
+}
+
+/// Size of a quota's target, from a recent scan where one covers it
+pub fn measure(target: &QuotaTarget, scans: &TreemapScans) -> (u64, QuotaSource) {
+    let (roots, name) = match target {
+        QuotaTarget::Directory { path } => (vec![expand(path)], None),
+        QuotaTarget::Named { name, roots } => (roots.iter().map(|r| expand(r)).collect(), Some(name.as_str())),
+    };
+    // A root inside another is already counted with it
+    let roots: Vec<PathBuf> = roots
+        .iter()
+        .filter(|root| !roots.iter().any(|other| other != *root && root.starts_with(other)))
+        .cloned()
+        .collect();
+
+    let max_age = Duration::from_secs(SCAN_MAX_AGE_SECS);
+    let mut total = 0;
+    let mut source = QuotaSource::Scan;
+    for root in roots {
+        let matches = |file: &Path| match name {
+            Some(name) => inside_named(&root, name, file),
+            None => file.starts_with(&root),
+        };
+        let scanned = scans.covering(&root, max_age).and_then(|scan| scan.catalog_size(matches));
+        total += match scanned {
+            Some(size) => size,
+            None => {
+                source = QuotaSource::Walk;
+                walk_size(&root, matches)
+            }
+        };
+    }
+    (total, source)
+}
+
+fn walk_size(root: &Path, matches: impl Fn(&Path) -> bool) -> u64 {
+    if !root.is_dir() {
+        return 0;
+    }
+    let guard = MountGuard::new(root, &MountPolicy::default());
+    IgnoreRules::DISK_USAGE
+        .walker(root)
+        .filter_entry(move |entry| guard.allows(entry.path()))
+        .build()
+        .filter_map(|e| e.ok())
+        .filter(|e| matches(e.path()))
+        .filter_map(|e| e.metadata().ok())
+        .filter(|m| m.is_file())
+        .map(|m| m.len())
+        .sum()
+}
+
+/// Sizes in the largest unit that keeps them above one
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+/// Variables a quota's workflow run starts with
+pub fn quota_variables(quota: &StorageQuota) -> HashMap<String, Value> {
+    let fields = json!({
+        "quota_id": quota.id,
+        "quota_name": quota.name,
+        "target": quota.target.describe(),
+        "size_bytes": quota.last_size,
+        "limit_bytes": quota.limit_bytes,
+        "size": quota.last_size.map(format_bytes),
+        "limit": format_bytes(quota.limit_bytes),
+    });
+    match fields {
+        Value::Object(map) => map.into_iter().collect(),
+        _ => HashMap::new(),
+    }
+}
+
+/// Measure a quota now, and alert when it crossed its limit
+pub async fn evaluate(store: &QuotaStore, engine: &WorkflowEngine, id: &str) -> Result<StorageQuota, QuotaError> {
+    let quota = store.get(id).ok_or_else(|| QuotaError::NotFound(id.to_string()))?;
+    let target = quota.target.clone();
+    let (size, source) = tokio::task::spawn_blocking(move || measure(&target, &TreemapScans::global()))
+        .await
+        .map_err(|e| QuotaError::Io(format!("Measuring failed: {}", e)))?;
+    let (quota, crossed) = store.record(id, size, source, chrono::Utc::now().timestamp())?;
+
+    let Some(exceeded) = crossed else {
+        return Ok(quota);
+    };
+    crate::events::publish(Event::StorageQuota {
+        quota_id: quota.id.clone(),
+        name: quota.name.clone(),
+        size,
+        limit_bytes: quota.limit_bytes,
+        exceeded,
+    });
+    if let (true, Some(workflow_id)) = (exceeded, &quota.workflow_id) {
+        let request = ExecuteWorkflowRequest {
+            workflow_id: workflow_id.clone(),
+            variables: quota_variables(&quota),
+            start_step_id: None,
+            trigger_payload: Some(json!(quota)),
+            context_id: Default::default(),
+        };
+        match engine.start_run(request).await {
+            Ok(run) => tracing::info!("Storage quota {} started workflow run {}", quota.name, run.id),
+            Err(e) => tracing::warn!("Storage quota {} could not start workflow {}: {}", quota.name, workflow_id, e),
+        }
+    }
+    Ok(quota)
+}
+
+/// Evaluate due quotas every minute
+pub fn spawn_quota_monitor(store: Arc<QuotaStore>, engine: Arc<WorkflowEngine>) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(std::time::Duration::from_secs(MONITOR_TICK_SECS));
+        loop {
+            ticker.tick().await;
+            for quota in store.due(chrono::Utc::now().timestamp()) {
+                match evaluate(&store, &engine, &quota.id).await {
+                    Ok(quota) if quota.exceeded_since.is_some() => tracing::info!(
+                        "Storage quota {} is over its limit: {} of {}",
+                        quota.name,
+                        format_bytes(quota.last_size.unwrap_or(0)),
+                        format_bytes(quota.limit_bytes)
+                    ),
+                    Ok(_) => {}
+                    Err(e) => tracing::warn!("Storage quota {}: {}", quota.name, e),
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_crossings_are_reported_once_each_way() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = QuotaStore::new(dir.path().join("quotas.json"));
+        let quota = store
+            .create(CreateQuotaRequest {
+                name: None,
+                target: QuotaTarget::Directory { path: " ~/Downloads ".to_string() },
+                limit_bytes: 100,
+                workflow_id: Some(" ".to_string()),
+                enabled: None,
+            })
+            .unwrap();
+        assert_eq!(quota.name, "~/Downloads");
+        assert_eq!(quota.workflow_id, None);
+        assert_eq!(store.due(0).len(), 1);
+
+        assert_eq!(store.record(&quota.id, 50, QuotaSource::Walk, 10).unwrap().1, None);
+        assert_eq!(store.record(&quota.id, 150, QuotaSource::Walk, 20).unwrap().1, Some(true));
+        let (over, crossed) = store.record(&quota.id, 160, QuotaSource::Scan, 30).unwrap();
+        assert_eq!((crossed, over.exceeded_since), (None, Some(20)));
+        assert_eq!(store.record(&quota.id, 90, QuotaSource::Walk, 40).unwrap().1, Some(false));
+        assert!(store.due(40).is_empty());
+
+        let reopened = QuotaStore::new(dir.path().join("quotas.json"));
+        assert_eq!(reopened.get(&quota.id).unwrap().last_size, Some(90));
+
+        assert!(store
+            .create(CreateQuotaRequest {
+                name: None,
+                target: QuotaTarget::Named { name: "a/b".to_string(), roots: vec!["~".to_string()] },
+                limit_bytes: 1,
+                workflow_id: None,
+                enabled: None,
+            })
+            .is_err());
+    }
+
+    #[test]
+    fn test_named_directories_are_measured_across_roots() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        for (path, size) in [
+            ("web/node_modules/react/index.js", 100),
+            ("web/node_modules/a/node_modules/b/index.js", 10),
+            ("api/node_modules/express/index.js", 40),
+            ("api/src/node_modules.rs", 1000),
+            ("api/src/main.js", 1000),
+        ] {
+            let file = root.join(path);
+            fs::create_dir_all(file.parent().unwrap()).unwrap();
+            fs::write(file, vec![0u8; size]).unwrap();
+        }
+
+        let scans = TreemapScans::default();
+        let target = QuotaTarget::Named {
+            name: "node_modules".to_string(),
+            roots: vec![root.display().to_string(), root.join("web").display().to_string()],
+        };
+        assert_eq!(measure(&target, &scans), (150, QuotaSource::Walk));
+
+        let target = QuotaTarget::Directory { path: root.join("api").display().to_string() };
+        assert_eq!(measure(&target, &scans), (2040, QuotaSource::Walk));
+        assert_eq!(format_bytes(1536), "1.5 KB");
+    }
+}
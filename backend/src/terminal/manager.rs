@@ -2,7 +2,9 @@
 
 use super::session::{TerminalSession, SessionConfig, SessionInfo};
 use super::snapshot::SessionSnapshot;
-use std::collections::HashMap;
+use super::sharing::{shared_scrollback, SharedScrollback};
+use crate::context::ContextId;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use std::path::PathBuf;
 use tokio::sync::RwLock;
@@ -13,6 +15,10 @@ use chrono::{Utc, Duration};
 pub struct TerminalManager {
     sessions: Arc<RwLock<HashMap<String, Arc<TerminalSession>>>>,
     snapshots: Arc<RwLock<HashMap<String, SessionSnapshot>>>,
+    /// Context (window) that created each session
+    owners: Arc<RwLock<HashMap<String, ContextId>>>,
+    /// Sessions whose output the user lets the agent read
+    shared_with_agent: Arc<RwLock<HashSet<String>>>,
     max_sessions: usize,
     session_timeout_mins: i64,
     hibernate_after_mins: i64,
@@ -62,6 +68,8 @@ impl TerminalManager {
         Self {
             sessions: Arc::new(RwLock::new(HashMap::new())),
             snapshots: Arc::new(RwLock::new(HashMap::new())),
+            owners: Arc::new(RwLock::new(HashMap::new())),
+            shared_with_agent: Arc::new(RwLock::new(HashSet::new())),
             max_sessions,
             session_timeout_mins,
             hibernate_after_mins,
@@ -125,6 +133,7 @@ impl TerminalManager {
         sessions.remove(session_id);
         
         tracing::info!("Hibernated session: {}", session_id);
+        publish_session_event(session_id, "hibernated");
         Ok(())
     }
     
@@ -156,7 +165,7 @@ impl TerminalManager {
                 .collect(),
         };
         
-        let session = TerminalSession::new(config)?;
+        let session = TerminalSession::with_id(session_id.to_string(), config)?;
         
         // Store session
         let mut sessions = self.sessions.write().await;
@@ -170,6 +179,7 @@ impl TerminalManager {
         SessionSnapshot::delete(session_id, &hibernated_path).await?;
         
         tracing::info!("Restored session: {}", session_id);
+        publish_session_event(session_id, "restored");
         Ok(())
     }
     
@@ -199,8 +209,17 @@ impl TerminalManager {
         ids
     }
     
-    /// Create a new terminal session
+    /// Create a new terminal session in the default context
     pub async fn create_session(&self, config: Option<SessionConfig>) -> Result<String, String> {
+        self.create_session_in(&ContextId::default(), config).await
+    }
+
+    /// Create a new terminal session owned by a context
+    pub async fn create_session_in(
+        &self,
+        ctx: &ContextId,
+        config: Option<SessionConfig>,
+    ) -> Result<String, String> {
         // First, try to cleanup stale sessions
         self.cleanup_stale_sessions().await;
         
@@ -225,7 +244,7 @@ impl TerminalManager {
             });
 
         // Create snapshot for tracking
-        let snapshot = SessionSnapshot::new(
+        let mut snapshot = SessionSnapshot::new(
             session_id.clone(),
             config.shell.clone(),
             initial_cwd,
@@ -233,17 +252,33 @@ impl TerminalManager {
             config.rows,
             "user".to_string(), // Default to user, can be updated
         );
+        // Keep profile variables so a restored session gets them back
+        snapshot.environment = config.env.iter().cloned().collect();
         
         let mut sessions = self.sessions.write().await;
         sessions.insert(session_id.clone(), Arc::new(session));
         
         let mut snapshots = self.snapshots.write().await;
         snapshots.insert(session_id.clone(), snapshot);
+
+        self.owners.write().await.insert(session_id.clone(), ctx.clone());
         
-        tracing::info!("Created terminal session: {}", session_id);
+        tracing::info!("Created terminal session {} in context {}", session_id, ctx);
+        publish_session_event(&session_id, "created");
         Ok(session_id)
     }
     
+    /// Context that created a session; sessions created before contexts
+    /// existed belong to the default context
+    pub async fn session_context(&self, session_id: &str) -> ContextId {
+        self.owners.read().await.get(session_id).cloned().unwrap_or_default()
+    }
+
+    /// Whether `ctx` may use a session
+    pub async fn can_access(&self, ctx: &ContextId, session_id: &str) -> bool {
+        ctx.can_access(&self.session_context(session_id).await)
+    }
+
     /// Get a session by ID
     pub async fn get_session(&self, session_id: &str) -> Option<Arc<TerminalSession>> {
         let sessions = self.sessions.read().await;
@@ -323,14 +358,103 @@ impl TerminalManager {
         let mut sessions = self.sessions.write().await;
         sessions.remove(session_id)
             .ok_or_else(|| format!("Session {} not found", session_id))?;
+        self.owners.write().await.remove(session_id);
+        self.shared_with_agent.write().await.remove(session_id);
         tracing::info!("Closed terminal session: {}", session_id);
+        publish_session_event(session_id, "closed");
         Ok(())
     }
     
     /// List all sessions
     pub async fn list_sessions(&self) -> Vec<SessionInfo> {
         let sessions = self.sessions.read().await;
-        sessions.values().map(|s| s.info()).collect()
+        let shared = self.shared_with_agent.read().await;
+        sessions.values().map(|s| Self::info_of(s, &shared)).collect()
+    }
+
+    /// List the sessions visible from a context
+    pub async fn list_sessions_in(&self, ctx: &ContextId) -> Vec<SessionInfo> {
+        let sessions = self.sessions.read().await;
+        let owners = self.owners.read().await;
+        let shared = self.shared_with_agent.read().await;
+        sessions.values()
+            .filter(|s| ctx.can_access(owners.get(&s.id).unwrap_or(&ContextId::default())))
+            .map(|s| Self::info_of(s, &shared))
+            .collect()
+    }
+
+    fn info_of(session: &TerminalSession, shared: &HashSet<String>) -> SessionInfo {
+        SessionInfo {
+            shared_with_agent: shared.contains(&session.id),
+            ..session.info()
+        }
+    }
+
+    /// Let the agent read a session's output, or stop it from doing so
+    pub async fn set_shared_with_agent(&self, session_id: &str, shared: bool) -> Result<(), String> {
+        if !self.get_all_session_ids().await.iter().any(|id| id == session_id) {
+            return Err(format!("Session {} not found", session_id));
+        }
+        let changed = if shared {
+            self.shared_with_agent.write().await.insert(session_id.to_string())
+        } else {
+            self.shared_with_agent.write().await.remove(session_id)
+        };
+        if changed {
+            tracing::info!("Agent access to session {}: {}", session_id, shared);
+            publish_session_event(session_id, if shared { "shared" } else { "unshared" });
+        }
+        Ok(())
+    }
+
+    /// Whether the user lets the agent read a session's output
+    pub async fn is_shared_with_agent(&self, session_id: &str) -> bool {
+        self.shared_with_agent.read().await.contains(session_id)
+    }
+
+    /// Sessions the agent may read, active ones first
+    pub async fn shared_session_ids(&self) -> Vec<String> {
+        let ids = self.get_all_session_ids().await;
+        let shared = self.shared_with_agent.read().await;
+        ids.into_iter()
+            .filter(|id| shared.contains(id))
+            .collect()
+    }
+
+    /// Recent output of a session for the agent, capped and redacted. Fails
+    /// unless the user shared the session.
+    pub async fn read_shared(&self, session_id: &str, max_lines: usize) -> Result<SharedScrollback, String> {
+        if !self.is_shared_with_agent(session_id).await {
+            return Err(format!("Session {} is not shared with the agent", session_id));
+        }
+
+        // Hibernated sessions only have their snapshot's history
+        if let Some(session) = self.get_session(session_id).await {
+            let (chunks, _) = session.read_from(0).await;
+            return Ok(shared_scrollback(session_id, &chunks, max_lines));
+        }
+        let snapshots = self.snapshots.read().await;
+        let snapshot = snapshots.get(session_id)
+            .ok_or_else(|| format!("Session {} not found", session_id))?;
+        Ok(shared_scrollback(session_id, &[snapshot.format_history()], max_lines))
+    }
+
+    /// Close every active session owned by a context (e.g. when its window
+    /// closes). Returns the number of sessions closed.
+    pub async fn close_context(&self, ctx: &ContextId) -> usize {
+        let owned: Vec<String> = self.owners.read().await
+            .iter()
+            .filter(|(_, owner)| *owner == ctx)
+            .map(|(id, _)| id.clone())
+            .collect();
+
+        let mut closed = 0;
+        for id in owned {
+            if self.close_session(&id).await.is_ok() {
+                closed += 1;
+            }
+        }
+        closed
     }
     
     /// Cleanup stale sessions
@@ -385,7 +509,10 @@ impl TerminalManager {
                 let hibernated_path = self.storage_path.join("hibernated");
                 let _ = SessionSnapshot::delete(&id, &hibernated_path).await;
                 
+                self.owners.write().await.remove(&id);
+                self.shared_with_agent.write().await.remove(&id);
                 tracing::info!("Archived stale session: {}", id);
+                publish_session_event(&id, "archived");
             }
         }
     }
@@ -405,23 +532,36 @@ impl TerminalManager {
         let active_count = sessions.values()
             .filter(|s| s.last_activity >= cutoff)
             .count();
+        let hibernated = self.snapshots.read().await
+            .keys()
+            .filter(|id| !sessions.contains_key(*id))
+            .count();
         
         SessionStats {
             total: sessions.len(),
             active: active_count,
             stale: sessions.len() - active_count,
+            hibernated,
             max_allowed: self.max_sessions,
             available: self.max_sessions.saturating_sub(sessions.len()),
         }
     }
 }
 
+fn publish_session_event(session_id: &str, action: &str) {
+    crate::events::publish(crate::events::Event::TerminalSession {
+        session_id: session_id.to_string(),
+        action: action.to_string(),
+    });
+}
+
 /// Session statistics
 #[derive(Debug, Clone, serde::Serialize)]
 pub struct SessionStats {
     pub total: usize,
     pub active: usize,
     pub stale: usize,
+    pub hibernated: usize,
     pub max_allowed: usize,
     pub available: usize,
 }
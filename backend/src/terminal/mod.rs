@@ -7,7 +7,9 @@ mod session;
 mod manager;
 mod routes;
 mod snapshot;
+mod sharing;
 
 pub use manager::TerminalManager;
 pub use session::SessionConfig;
 pub use routes::terminal_routes;
+pub use sharing::{SharedScrollback, DEFAULT_SHARED_LINES, MAX_SHARED_LINES};
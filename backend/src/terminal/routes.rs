@@ -4,12 +4,14 @@ use axum::{
     extract::{Path, State, Query},
     http::StatusCode,
     response::Json,
-    routing::{get, post, delete},
+    routing::{get, post, put, delete},
     Router,
 };
 use serde::{Deserialize, Serialize};
 use super::manager::TerminalManager;
 use super::session::SessionInfo;
+use crate::context::ContextId;
+use crate::cli_bridge::policy::{CommandPolicy, PatternKind, PolicyAction, PolicyError, PolicyRule, PolicyStore};
 
 /// Request to create a new terminal session
 #[derive(Debug, Deserialize)]
@@ -17,6 +19,8 @@ pub struct CreateSessionRequest {
     pub shell: Option<String>,
     pub cols: Option<u16>,
     pub rows: Option<u16>,
+    /// Environment profile to start the shell with (settings default if unset)
+    pub profile: Option<String>,
 }
 
 /// Response for session creation
@@ -44,6 +48,41 @@ pub struct ReadQuery {
     pub cursor: Option<usize>,
 }
 
+/// Request to change whether the agent may read a session
+#[derive(Debug, Deserialize)]
+pub struct AgentAccessRequest {
+    pub enabled: bool,
+}
+
+/// Whether the agent may read a session
+#[derive(Debug, Serialize)]
+pub struct AgentAccessResponse {
+    pub shared_with_agent: bool,
+}
+
+/// Request to add a command policy rule
+#[derive(Debug, Deserialize)]
+pub struct AddRuleRequest {
+    pub pattern: String,
+    pub kind: PatternKind,
+    pub action: PolicyAction,
+    pub description: Option<String>,
+}
+
+/// Request to evaluate a command against the policy
+#[derive(Debug, Deserialize)]
+pub struct EvaluateCommandRequest {
+    pub command: String,
+}
+
+/// Result of evaluating a command against the policy
+#[derive(Debug, Serialize)]
+pub struct EvaluateCommandResponse {
+    /// Action of the matching rule, `allow` when no rule matches
+    pub action: PolicyAction,
+    pub rule: Option<PolicyRule>,
+}
+
 /// Error response
 #[derive(Debug, Serialize)]
 pub struct ErrorResponse {
@@ -61,36 +100,58 @@ pub fn terminal_routes() -> Router<TerminalManager> {
         .route("/sessions/:id/read", get(read_from_session))
         .route("/sessions/:id/hibernate", post(hibernate_session))
         .route("/sessions/:id/restore", post(restore_session))
+        .route("/sessions/:id/agent-access", put(set_agent_access))
+        .route("/security", get(get_security_policy).put(set_security_policy))
+        .route("/security/rules", post(add_security_rule))
+        .route("/security/rules/:id", delete(remove_security_rule))
+        .route("/security/reset", post(reset_security_policy))
+        .route("/security/evaluate", post(evaluate_command))
+}
+
+/// Reject requests for sessions created by another context
+async fn ensure_access(
+    manager: &TerminalManager,
+    ctx: &ContextId,
+    session_id: &str,
+) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    if manager.can_access(ctx, session_id).await {
+        Ok(())
+    } else {
+        Err((
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse { error: format!("Session {} not found", session_id) }),
+        ))
+    }
 }
 
 /// Create a new terminal session
 async fn create_session(
     State(manager): State<TerminalManager>,
+    ctx: ContextId,
     Json(req): Json<CreateSessionRequest>,
 ) -> Result<Json<CreateSessionResponse>, (StatusCode, Json<ErrorResponse>)> {
     use super::session::SessionConfig;
+
+    let profile = crate::config::SettingsStore::global()
+        .get()
+        .environments
+        .resolve(req.profile.as_deref())
+        .map_err(|error| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error })))?
+        .cloned()
+        .unwrap_or_default();
     
     let config = SessionConfig {
-        shell: req.shell.unwrap_or_else(|| {
-            if cfg!(target_os = "windows") {
-                "powershell.exe".to_string()
-            } else {
-                std::env::var("SHELL").unwrap_or_else(|_| {
-                    if std::path::Path::new("/bin/bash").exists() {
-                        "/bin/bash".to_string()
-                    } else {
-                        "/bin/sh".to_string()
-                    }
-                })
-            }
-        }),
+        shell: req
+            .shell
+            .or(profile.shell.clone())
+            .unwrap_or_else(crate::cli_bridge::pty::default_shell),
         cols: req.cols.unwrap_or(80),
         rows: req.rows.unwrap_or(24),
-        cwd: None,
-        env: vec![],
+        cwd: profile.working_directory(),
+        env: profile.variables(),
     };
     
-    match manager.create_session(Some(config)).await {
+    match manager.create_session_in(&ctx, Some(config)).await {
         Ok(session_id) => Ok(Json(CreateSessionResponse { session_id })),
         Err(e) => Err((
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -99,18 +160,21 @@ async fn create_session(
     }
 }
 
-/// List all terminal sessions
+/// List the terminal sessions of the requesting context
 async fn list_sessions(
     State(manager): State<TerminalManager>,
+    ctx: ContextId,
 ) -> Json<Vec<SessionInfo>> {
-    Json(manager.list_sessions().await)
+    Json(manager.list_sessions_in(&ctx).await)
 }
 
 /// Close a terminal session
 async fn close_session(
     State(manager): State<TerminalManager>,
+    ctx: ContextId,
     Path(session_id): Path<String>,
 ) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    ensure_access(&manager, &ctx, &session_id).await?;
     match manager.close_session(&session_id).await {
         Ok(()) => Ok(StatusCode::NO_CONTENT),
         Err(e) => Err((
@@ -123,9 +187,11 @@ async fn close_session(
 /// Write to a terminal session
 async fn write_to_session(
     State(manager): State<TerminalManager>,
+    ctx: ContextId,
     Path(session_id): Path<String>,
     Json(req): Json<WriteRequest>,
 ) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    ensure_access(&manager, &ctx, &session_id).await?;
     match manager.write(&session_id, &req.data).await {
         Ok(()) => Ok(StatusCode::OK),
         Err(e) => Err((
@@ -138,11 +204,14 @@ async fn write_to_session(
 /// Read from a terminal session
 async fn read_from_session(
     State(manager): State<TerminalManager>,
+    ctx: ContextId,
     Path(session_id): Path<String>,
     Query(query): Query<ReadQuery>,
 ) -> Result<Json<ReadResponse>, (StatusCode, Json<ErrorResponse>)> {
     let start_index = query.cursor.unwrap_or(0);
     
+    ensure_access(&manager, &ctx, &session_id).await?;
+    
     match manager.read_from(&session_id, start_index).await {
         Ok((output, next_cursor)) => Ok(Json(ReadResponse { output, next_cursor })),
         Err(e) => Err((
@@ -162,8 +231,10 @@ async fn get_session_stats(
 /// Hibernate a session
 async fn hibernate_session(
     State(manager): State<TerminalManager>,
+    ctx: ContextId,
     Path(session_id): Path<String>,
 ) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    ensure_access(&manager, &ctx, &session_id).await?;
     match manager.hibernate_session(&session_id).await {
         Ok(()) => Ok(StatusCode::OK),
         Err(e) => Err((
@@ -176,8 +247,10 @@ async fn hibernate_session(
 /// Restore a hibernated session
 async fn restore_session(
     State(manager): State<TerminalManager>,
+    ctx: ContextId,
     Path(session_id): Path<String>,
 ) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    ensure_access(&manager, &ctx, &session_id).await?;
     match manager.restore_session(&session_id).await {
         Ok(()) => Ok(StatusCode::OK),
         Err(e) => Err((
@@ -186,3 +259,90 @@ async fn restore_session(
         )),
     }
 }
+
+/// Share a session's output with the agent, or stop sharing it
+async fn set_agent_access(
+    State(manager): State<TerminalManager>,
+    ctx: ContextId,
+    Path(session_id): Path<String>,
+    Json(req): Json<AgentAccessRequest>,
+) -> Result<Json<AgentAccessResponse>, (StatusCode, Json<ErrorResponse>)> {
+    ensure_access(&manager, &ctx, &session_id).await?;
+    match manager.set_shared_with_agent(&session_id, req.enabled).await {
+        Ok(()) => Ok(Json(AgentAccessResponse { shared_with_agent: req.enabled })),
+        Err(e) => Err((
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse { error: e }),
+        )),
+    }
+}
+
+fn policy_error(e: PolicyError) -> (StatusCode, Json<ErrorResponse>) {
+    let status = match e {
+        PolicyError::NotFound(_) => StatusCode::NOT_FOUND,
+        PolicyError::Invalid(_) => StatusCode::BAD_REQUEST,
+        PolicyError::Io(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    (status, Json(ErrorResponse { error: e.to_string() }))
+}
+
+/// Get the current command policy
+async fn get_security_policy() -> Json<CommandPolicy> {
+    Json(PolicyStore::global().get())
+}
+
+/// Replace the command policy
+async fn set_security_policy(
+    Json(policy): Json<CommandPolicy>,
+) -> Result<Json<CommandPolicy>, (StatusCode, Json<ErrorResponse>)> {
+    PolicyStore::global()
+        .set(policy.clone())
+        .map_err(policy_error)?;
+    Ok(Json(policy))
+}
+
+/// Add a rule with highest precedence
+async fn add_security_rule(
+    Json(req): Json<AddRuleRequest>,
+) -> Result<Json<CommandPolicy>, (StatusCode, Json<ErrorResponse>)> {
+    let mut rule = PolicyRule::new(&req.pattern, req.kind, req.action);
+    rule.description = req.description;
+
+    PolicyStore::global()
+        .add_rule(rule)
+        .map(Json)
+        .map_err(policy_error)
+}
+
+/// Remove a rule by ID
+async fn remove_security_rule(
+    Path(rule_id): Path<String>,
+) -> Result<Json<CommandPolicy>, (StatusCode, Json<ErrorResponse>)> {
+    PolicyStore::global()
+        .remove_rule(&rule_id)
+        .map(Json)
+        .map_err(policy_error)
+}
+
+/// Restore the built-in default policy
+async fn reset_security_policy() -> Result<Json<CommandPolicy>, (StatusCode, Json<ErrorResponse>)> {
+    PolicyStore::global()
+        .reset()
+        .map(Json)
+        .map_err(policy_error)
+}
+
+/// Check which rule (if any) a command would match
+async fn evaluate_command(
+    Json(req): Json<EvaluateCommandRequest>,
+) -> Json<EvaluateCommandResponse> {
+    let policy = PolicyStore::global().get();
+    let rule = policy.evaluate(&req.command).cloned();
+
+    Json(EvaluateCommandResponse {
+        action: rule.as_ref().map(|r| r.action).unwrap_or_else(|| {
+            crate::config::SettingsStore::global().get().security.unmatched_command_action
+        }),
+        rule,
+    })
+}
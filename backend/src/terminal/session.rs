@@ -1,6 +1,7 @@
 //! Terminal Session - Individual PTY session management
 
 use portable_pty::{native_pty_system, CommandBuilder, PtySize, Child};
+use crate::cli_bridge::pty::ShellKind;
 use std::io::{Read, Write};
 use std::sync::Arc;
 use std::path::PathBuf;
@@ -22,7 +23,7 @@ pub struct SessionConfig {
 impl Default for SessionConfig {
     fn default() -> Self {
         let shell = if cfg!(target_os = "windows") {
-            "powershell.exe".to_string()
+            crate::cli_bridge::pty::default_shell()
         } else {
             // Check if bash exists, fallback to sh
             if std::path::Path::new("/bin/bash").exists() {
@@ -63,7 +64,11 @@ pub struct TerminalSession {
 impl TerminalSession {
     /// Create a new terminal session
     pub fn new(config: SessionConfig) -> Result<Self, String> {
-        let id = Uuid::new_v4().to_string();
+        Self::with_id(Uuid::new_v4().to_string(), config)
+    }
+
+    /// Create a terminal session under an existing ID (e.g. when restoring)
+    pub fn with_id(id: String, config: SessionConfig) -> Result<Self, String> {
         let now = Utc::now();
         
         tracing::info!("Creating terminal session {} with shell: {}", id, config.shell);
@@ -86,15 +91,17 @@ impl TerminalSession {
             cmd.cwd(cwd);
         }
         
-        // On Windows, use -NoLogo -NoProfile -ExecutionPolicy Bypass for powershell
-        // This ensures a clean, fast startup and prevents policy blocks in prod
-        if cfg!(target_os = "windows") && config.shell.contains("powershell.exe") {
-            cmd.args(&["-NoLogo", "-NoProfile", "-ExecutionPolicy", "Bypass"]);
-        }
-
-        // Add --norc --noprofile to skip user's bashrc (avoids fancy prompts)
-        if config.shell.contains("bash") {
-            cmd.args(&["--norc", "--noprofile"]);
+        match ShellKind::of(&config.shell) {
+            // Add --norc --noprofile to skip user's bashrc (avoids fancy prompts)
+            ShellKind::Posix if config.shell.contains("bash") => {
+                cmd.args(["--norc", "--noprofile"]);
+            }
+            ShellKind::Posix => {}
+            // PowerShell and cmd start without profiles, with UTF-8 code
+            // pages and without execution policy blocks in prod
+            kind => {
+                cmd.args(kind.interactive_args());
+            }
         }
         for (key, value) in &config.env {
             cmd.env(key, value);
@@ -136,6 +143,13 @@ impl TerminalSession {
                             let keep = history.len() - 4000;
                             history.drain(0..keep);
                         }
+                        let cursor = history.len();
+                        drop(history);
+
+                        crate::events::publish(crate::events::Event::TerminalOutput {
+                            session_id: session_id.clone(),
+                            cursor,
+                        });
                     }
                     Err(e) => {
                         tracing::warn!("PTY read error for session {}: {}", session_id, e);
@@ -199,6 +213,7 @@ impl TerminalSession {
             rows: self.config.rows,
             created_at: self.created_at,
             last_activity: self.last_activity,
+            shared_with_agent: false,
         }
     }
 }
@@ -212,4 +227,6 @@ pub struct SessionInfo {
     pub rows: u16,
     pub created_at: DateTime<Utc>,
     pub last_activity: DateTime<Utc>,
+    /// Whether the user lets the agent read this session's output
+    pub shared_with_agent: bool,
 }
@@ -0,0 +1,309 @@
+//! Encryption at rest for stored conversations
+//!
+//! Optional. When enabled, message text kept by the backend is encrypted with
+//! XChaCha20-Poly1305 under a key derived from the user's passphrase with
+//! Argon2id. The passphrase itself is never stored: `~/.skhoot/vault.json`
+//! holds the salt, the KDF parameters and a verifier (a known value sealed
+//! with the key) used to check a passphrase on unlock.
+//!
+//! The key is cached in memory from unlock until [`ConversationVault::lock`]
+//! or the end of the process. While locked, sealed text can't be read or
+//! written; callers show what they can (session lists, timestamps) and hide
+//! the content.
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chacha20poly1305::aead::{Aead, KeyInit, OsRng};
+use chacha20poly1305::{AeadCore, XChaCha20Poly1305, XNonce};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+
+/// Prefix of sealed text; anything else is plain text
+pub const SEALED_PREFIX: &str = "enc:v1:";
+
+/// Shortest passphrase accepted when enabling encryption
+pub const MIN_PASSPHRASE_LEN: usize = 8;
+
+const SALT_LEN: usize = 16;
+const KEY_LEN: usize = 32;
+const NONCE_LEN: usize = 24;
+
+/// Plain text sealed as the verifier
+const VERIFIER_TEXT: &str = "skhoot-vault";
+
+#[derive(Debug, thiserror::Error)]
+pub enum VaultError {
+    #[error("Conversation encryption is not enabled")]
+    NotEnabled,
+
+    #[error("Conversation encryption is already enabled")]
+    AlreadyEnabled,
+
+    #[error("Conversations are locked; unlock them with your passphrase")]
+    Locked,
+
+    #[error("Wrong passphrase")]
+    WrongPassphrase,
+
+    #[error("Passphrase must be at least {0} characters")]
+    WeakPassphrase(usize),
+
+    #[error("Encrypted data is damaged: {0}")]
+    Corrupt(String),
+
+    #[error("Vault file error: {0}")]
+    Io(String),
+}
+
+/// Whether encryption is on and the key is available
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct VaultStatus {
+    pub enabled: bool,
+    pub unlocked: bool,
+}
+
+/// Contents of `vault.json`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct VaultConfig {
+    /// Base64 Argon2 salt
+    salt: String,
+    memory_kib: u32,
+    iterations: u32,
+    parallelism: u32,
+    /// [`VERIFIER_TEXT`] sealed with the key
+    verifier: String,
+}
+
+type Key = [u8; KEY_LEN];
+
+/// Passphrase-protected key for conversation text
+pub struct ConversationVault {
+    path: PathBuf,
+    config: RwLock<Option<VaultConfig>>,
+    key: RwLock<Option<Key>>,
+}
+
+lazy_static::lazy_static! {
+    static ref GLOBAL_VAULT: Arc<ConversationVault> = Arc::new(ConversationVault::new(
+        dirs::home_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join(".skhoot")
+            .join("vault.json"),
+    ));
+}
+
+impl ConversationVault {
+    /// Open the vault described by `path`; encryption is off if the file is
+    /// missing. The vault starts locked.
+    pub fn new(path: PathBuf) -> Self {
+        let config = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok());
+        Self {
+            path,
+            config: RwLock::new(config),
+            key: RwLock::new(None),
+        }
+    }
+
+    /// Shared vault at `~/.skhoot/vault.json`
+    pub fn global() -> Arc<ConversationVault> {
+        GLOBAL_VAULT.clone()
+    }
+
+    pub fn status(&self) -> VaultStatus {
+        VaultStatus {
+            enabled: self.is_enabled(),
+            unlocked: self.key.read().unwrap().is_some(),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.config.read().unwrap().is_some()
+    }
+
+    /// Turn encryption on with `passphrase`, leaving the vault unlocked.
+    /// Existing text stays plain until it is rewritten with [`Self::seal`].
+    pub fn enable(&self, passphrase: &str) -> Result<(), VaultError> {
+        if self.is_enabled() {
+            return Err(VaultError::AlreadyEnabled);
+        }
+        if passphrase.chars().count() < MIN_PASSPHRASE_LEN {
+            return Err(VaultError::WeakPassphrase(MIN_PASSPHRASE_LEN));
+        }
+
+        let mut salt = [0u8; SALT_LEN];
+        rand::RngCore::fill_bytes(&mut OsRng, &mut salt);
+        let params = Params::default();
+        let mut config = VaultConfig {
+            salt: STANDARD.encode(salt),
+            memory_kib: params.m_cost(),
+            iterations: params.t_cost(),
+            parallelism: params.p_cost(),
+            verifier: String::new(),
+        };
+        let key = derive_key(&config, passphrase)?;
+        config.verifier = seal_with(&key, VERIFIER_TEXT)?;
+
+        let json = serde_json::to_string_pretty(&config).map_err(|e| VaultError::Io(e.to_string()))?;
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| VaultError::Io(e.to_string()))?;
+        }
+        std::fs::write(&self.path, json).map_err(|e| VaultError::Io(e.to_string()))?;
+
+        *self.config.write().unwrap() = Some(config);
+        *self.key.write().unwrap() = Some(key);
+        Ok(())
+    }
+
+    /// Check `passphrase` and cache the key until [`Self::lock`]
+    pub fn unlock(&self, passphrase: &str) -> Result<(), VaultError> {
+        let config = self.config.read().unwrap().clone().ok_or(VaultError::NotEnabled)?;
+        let key = derive_key(&config, passphrase)?;
+        match open_with(&key, &config.verifier) {
+            Ok(text) if text == VERIFIER_TEXT => {}
+            _ => return Err(VaultError::WrongPassphrase),
+        }
+        *self.key.write().unwrap() = Some(key);
+        Ok(())
+    }
+
+    /// Forget the cached key
+    pub fn lock(&self) {
+        if let Some(key) = self.key.write().unwrap().as_mut() {
+            key.fill(0);
+        }
+        *self.key.write().unwrap() = None;
+    }
+
+    /// Turn encryption off. The vault must be unlocked, and sealed text must
+    /// already have been opened with [`Self::open`] and rewritten, since the
+    /// key is gone afterwards.
+    pub fn disable(&self, passphrase: &str) -> Result<(), VaultError> {
+        self.unlock(passphrase)?;
+        match std::fs::remove_file(&self.path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(VaultError::Io(e.to_string())),
+        }
+        *self.config.write().unwrap() = None;
+        self.lock();
+        Ok(())
+    }
+
+    /// Text as it should be stored: sealed when encryption is on, as-is
+    /// otherwise. Fails while locked.
+    pub fn seal(&self, text: &str) -> Result<String, VaultError> {
+        if !self.is_enabled() || is_sealed(text) {
+            return Ok(text.to_string());
+        }
+        let key = self.key.read().unwrap().ok_or(VaultError::Locked)?;
+        seal_with(&key, text)
+    }
+
+    /// Readable form of stored text; plain text is returned as-is
+    pub fn open(&self, stored: &str) -> Result<String, VaultError> {
+        if !is_sealed(stored) {
+            return Ok(stored.to_string());
+        }
+        let key = self.key.read().unwrap().ok_or(VaultError::Locked)?;
+        open_with(&key, stored)
+    }
+}
+
+/// Whether stored text was sealed by a vault
+pub fn is_sealed(text: &str) -> bool {
+    text.starts_with(SEALED_PREFIX)
+}
+
+fn derive_key(config: &VaultConfig, passphrase: &str) -> Result<Key, VaultError> {
+    let salt = STANDARD
+        .decode(&config.salt)
+        .map_err(|e| VaultError::Corrupt(format!("salt: {}", e)))?;
+    let params = Params::new(config.memory_kib, config.iterations, config.parallelism, Some(KEY_LEN))
+        .map_err(|e| VaultError::Corrupt(format!("KDF parameters: {}", e)))?;
+    let mut key = [0u8; KEY_LEN];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase.as_bytes(), &salt, &mut key)
+        .map_err(|e| VaultError::Corrupt(format!("key derivation: {}", e)))?;
+    Ok(key)
+}
+
+fn seal_with(key: &Key, text: &str) -> Result<String, VaultError> {
+    let cipher = XChaCha20Poly1305::new(key.into());
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, text.as_bytes())
+        .map_err(|_| VaultError::Corrupt("encryption failed".to_string()))?;
+    let mut sealed = nonce.to_vec();
+    sealed.extend_from_slice(&ciphertext);
+    Ok(format!("{}{}", SEALED_PREFIX, STANDARD.encode(sealed)))
+}
+
+fn open_with(key: &Key, stored: &str) -> Result<String, VaultError> {
+    let data = STANDARD
+        .decode(&stored[SEALED_PREFIX.len()..])
+        .map_err(|e| VaultError::Corrupt(e.to_string()))?;
+    if data.len() < NONCE_LEN {
+        return Err(VaultError::Corrupt("sealed text is too short".to_string()));
+    }
+    let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+    let plaintext = XChaCha20Poly1305::new(key.into())
+        .decrypt(XNonce::from_slice(nonce), ciphertext)
+        .map_err(|_| VaultError::Corrupt("authentication failed".to_string()))?;
+    String::from_utf8(plaintext).map_err(|e| VaultError::Corrupt(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seal_and_open() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("vault.json");
+        let vault = ConversationVault::new(path.clone());
+
+        // Without encryption text is stored as-is
+        assert_eq!(vault.seal("hello").unwrap(), "hello");
+        assert!(matches!(vault.enable("short"), Err(VaultError::WeakPassphrase(_))));
+
+        vault.enable("correct horse").unwrap();
+        let sealed = vault.seal("rm -rf ~/secrets").unwrap();
+        assert!(is_sealed(&sealed) && !sealed.contains("secrets"));
+        assert_ne!(sealed, vault.seal("rm -rf ~/secrets").unwrap(), "nonces are random");
+        assert_eq!(vault.open(&sealed).unwrap(), "rm -rf ~/secrets");
+        assert_eq!(vault.open("legacy plain text").unwrap(), "legacy plain text");
+
+        vault.lock();
+        assert!(matches!(vault.open(&sealed), Err(VaultError::Locked)));
+        assert!(matches!(vault.seal("new"), Err(VaultError::Locked)));
+
+        // A new process reads the config and needs the passphrase again
+        let reopened = ConversationVault::new(path);
+        assert_eq!(reopened.status(), VaultStatus { enabled: true, unlocked: false });
+        assert!(matches!(reopened.unlock("wrong horse"), Err(VaultError::WrongPassphrase)));
+        reopened.unlock("correct horse").unwrap();
+        assert_eq!(reopened.open(&sealed).unwrap(), "rm -rf ~/secrets");
+
+        let mut tampered = sealed.clone();
+        tampered.pop();
+        tampered.push(if sealed.ends_with('A') { 'B' } else { 'A' });
+        assert!(matches!(reopened.open(&tampered), Err(VaultError::Corrupt(_))));
+    }
+
+    #[test]
+    fn test_disable_removes_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("vault.json");
+        let vault = ConversationVault::new(path.clone());
+        assert!(matches!(vault.disable("anything"), Err(VaultError::NotEnabled)));
+
+        vault.enable("correct horse").unwrap();
+        assert!(matches!(vault.disable("wrong horse"), Err(VaultError::WrongPassphrase)));
+        vault.disable("correct horse").unwrap();
+        assert!(!path.exists());
+        assert_eq!(vault.status(), VaultStatus { enabled: false, unlocked: false });
+    }
+}
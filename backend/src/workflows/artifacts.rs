@@ -0,0 +1,234 @@
+//! Workflow run artifacts
+//!
+//! Files a run produces (reports, HTML pages, JSON exports) are registered
+//! with the run, either alongside a step outcome or on their own. Registering
+//! copies the file into `~/.skhoot/workflow_artifacts/<run_id>/`, so it stays
+//! downloadable after the original is moved or overwritten by the next run.
+//! Copies are deleted once the workflow's [`ArtifactRetention`] no longer
+//! covers their run.
+
+use super::engine::WorkflowEngine;
+use super::runs::WorkflowRun;
+use super::types::*;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Largest file accepted as an artifact
+pub const MAX_ARTIFACT_BYTES: u64 = 100 * 1024 * 1024;
+/// How often artifacts past their age limit are looked for
+const PRUNE_INTERVAL_SECS: u64 = 60 * 60;
+
+/// File stored for a workflow run
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RunArtifact {
+    pub id: String,
+    /// File name the artifact is downloaded as
+    pub name: String,
+    /// Step that produced the file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub step_id: Option<String>,
+    /// Where the file was registered from
+    pub source_path: String,
+    pub mime_type: String,
+    pub size: u64,
+    pub sha256: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub created_at: i64,
+}
+
+/// File a step asks to keep as an artifact of its run
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ArtifactSource {
+    /// Absolute path of the produced file
+    pub path: String,
+    /// Download name (the file name of `path` if unset)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub step_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+/// Copies of run artifacts on disk
+pub struct ArtifactStore {
+    root: PathBuf,
+}
+
+impl ArtifactStore {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    /// Copy `source` into the store for `run_id`
+    pub fn store(&self, run_id: &str, source: &ArtifactSource) -> Result<RunArtifact, String> {
+        let path = Path::new(&source.path);
+        if !path.is_absolute() {
+            return Err(format!("Artifact path must be absolute: {}", source.path));
+        }
+        let metadata = std::fs::metadata(path).map_err(|e| format!("Artifact {}: {}", source.path, e))?;
+        if !metadata.is_file() {
+            return Err(format!("Artifact {} is not a file", source.path));
+        }
+        if metadata.len() > MAX_ARTIFACT_BYTES {
+            return Err(format!("Artifact {} is larger than {} bytes", source.path, MAX_ARTIFACT_BYTES));
+        }
+
+        let name = source
+            .name
+            .as_deref()
+            .and_then(|name| Path::new(name).file_name())
+            .or_else(|| path.file_name())
+            .map(|name| name.to_string_lossy().to_string())
+            .ok_or_else(|| format!("Artifact {} has no file name", source.path))?;
+        let id = uuid::Uuid::new_v4().to_string();
+        let target = self.file_path(run_id, &id);
+        std::fs::create_dir_all(target.parent().unwrap_or(&self.root)).map_err(|e| e.to_string())?;
+        let bytes = std::fs::read(path).map_err(|e| format!("Artifact {}: {}", source.path, e))?;
+        std::fs::write(&target, &bytes).map_err(|e| format!("Failed to store artifact: {}", e))?;
+
+        Ok(RunArtifact {
+            id,
+            mime_type: mime_guess::from_path(&name).first_or_octet_stream().to_string(),
+            name,
+            step_id: source.step_id.clone(),
+            source_path: source.path.clone(),
+            size: bytes.len() as u64,
+            sha256: hex::encode(Sha256::digest(&bytes)),
+            description: source.description.clone(),
+            created_at: chrono::Utc::now().timestamp(),
+        })
+    }
+
+    /// Stored copy of an artifact
+    pub fn file_path(&self, run_id: &str, artifact_id: &str) -> PathBuf {
+        self.root.join(run_id).join(artifact_id)
+    }
+
+    /// Delete the stored copy of one artifact
+    pub fn remove(&self, run_id: &str, artifact_id: &str) {
+        let _ = std::fs::remove_file(self.file_path(run_id, artifact_id));
+    }
+
+    /// Delete every stored artifact of a run
+    pub fn remove_run(&self, run_id: &str) {
+        let _ = std::fs::remove_dir_all(self.root.join(run_id));
+    }
+}
+
+/// Finished runs of one workflow whose artifacts fall outside `retention`
+/// at unix time `now`. Runs are ranked newest first by start time; runs
+/// still in progress are always kept.
+pub fn expired_runs(runs: &[&WorkflowRun], retention: &ArtifactRetention, now: i64) -> Vec<String> {
+    let mut finished: Vec<&WorkflowRun> = runs.iter().copied().filter(|run| run.is_finished()).collect();
+    finished.sort_by_key(|run| std::cmp::Reverse(run.started_at));
+
+    let max_age_secs = retention.max_age_days.map(|days| i64::from(days) * 86_400);
+    finished
+        .into_iter()
+        .enumerate()
+        .filter(|(rank, run)| {
+            let too_many = retention.keep_runs.is_some_and(|keep| *rank >= keep);
+            let finished_at = run.completed_at.unwrap_or(run.updated_at);
+            let too_old = max_age_secs.is_some_and(|max| now - finished_at > max);
+            !run.artifacts.is_empty() && (too_many || too_old)
+        })
+        .map(|(_, run)| run.id.clone())
+        .collect()
+}
+
+/// Apply every workflow's retention policy now and then, so artifacts of
+/// workflows that stopped running still expire
+pub fn spawn_pruner(engine: Arc<WorkflowEngine>) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(std::time::Duration::from_secs(PRUNE_INTERVAL_SECS));
+        loop {
+            ticker.tick().await;
+            engine.prune_artifacts(None).await;
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn finished_run(id: &str, started_at: i64) -> WorkflowRun {
+        let mut run = WorkflowRun::new(&Workflow::new("test".to_string(), WorkflowType::Process), HashMap::new(), None);
+        run.id = id.to_string();
+        run.status = WorkflowStatus::Completed;
+        run.started_at = started_at;
+        run.completed_at = Some(started_at + 10);
+        run.artifacts.push(RunArtifact {
+            id: "a".to_string(),
+            name: "report.md".to_string(),
+            step_id: None,
+            source_path: "/tmp/report.md".to_string(),
+            mime_type: "text/markdown".to_string(),
+            size: 1,
+            sha256: String::new(),
+            description: None,
+            created_at: started_at,
+        });
+        run
+    }
+
+    #[test]
+    fn test_store_copies_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("report.html");
+        std::fs::write(&source, "<h1>done</h1>").unwrap();
+        let store = ArtifactStore::new(dir.path().join("artifacts"));
+
+        let artifact = store
+            .store(
+                "run-1",
+                &ArtifactSource {
+                    path: source.display().to_string(),
+                    step_id: Some("s1".to_string()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(artifact.name, "report.html");
+        assert_eq!(artifact.mime_type, "text/html");
+        assert_eq!(artifact.size, 13);
+
+        std::fs::write(&source, "overwritten").unwrap();
+        let stored = store.file_path("run-1", &artifact.id);
+        assert_eq!(std::fs::read_to_string(&stored).unwrap(), "<h1>done</h1>");
+
+        let relative = ArtifactSource { path: "report.html".to_string(), ..Default::default() };
+        assert!(store.store("run-1", &relative).is_err());
+
+        store.remove_run("run-1");
+        assert!(!stored.exists());
+    }
+
+    #[test]
+    fn test_expired_runs() {
+        let day = 86_400;
+        let now = 100 * day;
+        let runs: Vec<WorkflowRun> = (0..4).map(|i| finished_run(&format!("r{}", i), now - i * 20 * day)).collect();
+        let mut running = finished_run("live", now - 90 * day);
+        running.status = WorkflowStatus::Running;
+        let mut all: Vec<&WorkflowRun> = runs.iter().collect();
+        all.push(&running);
+
+        let by_count = ArtifactRetention { keep_runs: Some(2), max_age_days: None };
+        assert_eq!(expired_runs(&all, &by_count, now), vec!["r2", "r3"]);
+
+        let by_age = ArtifactRetention { keep_runs: None, max_age_days: Some(30) };
+        assert_eq!(expired_runs(&all, &by_age, now), vec!["r2", "r3"]);
+
+        let by_age = ArtifactRetention { keep_runs: None, max_age_days: Some(45) };
+        assert_eq!(expired_runs(&all, &by_age, now), vec!["r3"]);
+
+        let unlimited = ArtifactRetention { keep_runs: None, max_age_days: None };
+        assert!(expired_runs(&all, &unlimited, now).is_empty());
+    }
+}
@@ -2,11 +2,24 @@
 //!
 //! Handles workflow execution with tree-of-decision branching logic.
 
+use super::artifacts::{expired_runs, ArtifactSource, ArtifactStore, RunArtifact};
+use super::expression::{resolve_decision, EvalContext};
+use super::runs::{StepOutcome, WorkflowRun};
+use super::template::{prepare_variables, render_step_prompt};
 use super::types::*;
+use super::{scaffolding, tool_step, webhook};
+use crate::context::ContextId;
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
+/// Work a step does on the server instead of prompting the AI
+enum ServerAction {
+    Http(HttpRequestStep),
+    Scaffold(ScaffoldStep),
+    Tool(StepAction),
+}
+
 /// Workflow execution engine
 pub struct WorkflowEngine {
     /// Active executions
@@ -15,25 +28,39 @@ pub struct WorkflowEngine {
     storage: Arc<super::storage::WorkflowStorage>,
     /// Execution storage path
     execution_path: std::path::PathBuf,
+    /// Workflow runs with per-step state
+    runs: Arc<RwLock<HashMap<String, WorkflowRun>>>,
+    /// Run storage path
+    runs_path: std::path::PathBuf,
+    /// Stored copies of run artifacts
+    artifacts: ArtifactStore,
 }
 
 impl WorkflowEngine {
     pub fn new(storage: Arc<super::storage::WorkflowStorage>) -> Self {
         let home = dirs::home_dir().unwrap_or_else(|| std::path::PathBuf::from("."));
         let execution_path = home.join(".skhoot").join("workflow_executions");
+        let runs_path = home.join(".skhoot").join("workflow_runs");
         
         if !execution_path.exists() {
             let _ = std::fs::create_dir_all(&execution_path);
         }
+        if !runs_path.exists() {
+            let _ = std::fs::create_dir_all(&runs_path);
+        }
 
         let engine = Self {
             executions: Arc::new(RwLock::new(HashMap::new())),
             storage,
             execution_path,
+            runs: Arc::new(RwLock::new(HashMap::new())),
+            runs_path,
+            artifacts: ArtifactStore::new(home.join(".skhoot").join("workflow_artifacts")),
         };
 
-        // Load existing executions
+        // Load existing executions and runs
         let _ = engine.load_executions();
+        let _ = engine.load_runs();
         
         engine
     }
@@ -64,29 +91,57 @@ impl WorkflowEngine {
         std::fs::write(file_path, content)
     }
 
+    fn load_runs(&self) -> std::io::Result<()> {
+        if let Ok(entries) = std::fs::read_dir(&self.runs_path) {
+            let mut runs = futures::executor::block_on(self.runs.write());
+            for entry in entries.flatten() {
+                if entry.path().extension().and_then(|s| s.to_str()) == Some("json") {
+                    if let Ok(content) = std::fs::read_to_string(entry.path()) {
+                        if let Ok(run) = serde_json::from_str::<WorkflowRun>(&content) {
+                            runs.insert(run.id.clone(), run);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn save_run(&self, run: &WorkflowRun) -> std::io::Result<()> {
+        let file_path = self.runs_path.join(format!("{}.json", run.id));
+        let content = serde_json::to_string_pretty(run)?;
+        std::fs::write(file_path, content)
+    }
+
     /// Start workflow execution
     pub async fn execute(&self, request: ExecuteWorkflowRequest) -> Result<ExecutionContext, String> {
         let workflow = self.storage.get(&request.workflow_id).await
             .ok_or_else(|| format!("Workflow {} not found", request.workflow_id))?;
 
+        let variables = prepare_variables(&workflow, request.variables, request.trigger_payload)?;
         let execution_id = uuid::Uuid::new_v4().to_string();
         let first_step = request.start_step_id
             .or_else(|| workflow.steps.first().map(|s| s.id.clone()));
+        let step_results = HashMap::new();
+        let current_prompt = render_step_prompt(&workflow, first_step.as_deref(), &variables, &step_results);
 
         let context = ExecutionContext {
             workflow_id: workflow.id.clone(),
             execution_id: execution_id.clone(),
+            context_id: request.context_id,
             current_step_id: first_step,
-            variables: request.variables,
-            step_results: HashMap::new(),
+            variables,
+            step_results,
             started_at: chrono::Utc::now().timestamp(),
             completed_at: None,
             status: WorkflowStatus::Running,
             loop_state: None,
+            current_prompt,
         };
 
         self.executions.write().await.insert(execution_id.clone(), context.clone());
         let _ = self.save_execution(&context);
+        publish_execution(&context);
         
         // Update workflow status
         self.storage.update_status(&workflow.id, WorkflowStatus::Running).await;
@@ -126,18 +181,29 @@ impl WorkflowEngine {
         };
         context.step_results.insert(current_step_id.clone(), result);
 
+        let decision_result = resolve_decision(
+            current_step.decision.as_ref(),
+            &EvalContext {
+                variables: &context.variables,
+                steps: &context.step_results,
+                current_step_id: Some(&current_step_id),
+            },
+            decision_result,
+        )?;
+        if let Some(result) = context.step_results.get_mut(&current_step_id) {
+            result.decision_result = decision_result;
+        }
+
         // Determine next step using tree-of-decision logic
-        let next_step_id = if let Some(decision) = &current_step.decision {
-            match decision_result {
-                Some(true) => decision.true_branch.clone(),
-                Some(false) => decision.false_branch.clone(),
-                None => current_step.next_step.clone(),
-            }
-        } else {
-            current_step.next_step.clone()
-        };
+        let next_step_id = current_step.next_step_id(decision_result);
 
         context.current_step_id = next_step_id.clone();
+        context.current_prompt = render_step_prompt(
+            &workflow,
+            next_step_id.as_deref(),
+            &context.variables,
+            &context.step_results,
+        );
 
         // Check if workflow is complete
         if next_step_id.is_none() {
@@ -149,6 +215,8 @@ impl WorkflowEngine {
 
         let _ = self.save_execution(context);
 
+        publish_execution(context);
+
         Ok(next_step_id)
     }
 
@@ -156,6 +224,7 @@ impl WorkflowEngine {
     pub async fn update_execution(&self, context: ExecutionContext) -> Result<(), String> {
         let mut executions = self.executions.write().await;
         let _ = self.save_execution(&context);
+        publish_execution(&context);
         executions.insert(context.execution_id.clone(), context);
         Ok(())
     }
@@ -173,6 +242,7 @@ impl WorkflowEngine {
             context.completed_at = Some(chrono::Utc::now().timestamp());
             self.storage.update_status(&context.workflow_id, WorkflowStatus::Idle).await;
             let _ = self.save_execution(context);
+            publish_execution(context);
             Ok(())
         } else {
             // Check if it's on disk even if not in memory
@@ -184,6 +254,7 @@ impl WorkflowEngine {
                         context.completed_at = Some(chrono::Utc::now().timestamp());
                         self.storage.update_status(&context.workflow_id, WorkflowStatus::Idle).await;
                         let _ = self.save_execution(&context);
+                        publish_execution(&context);
                         return Ok(());
                     }
                 }
@@ -192,12 +263,319 @@ impl WorkflowEngine {
         }
     }
 
-    /// Get all active executions
-    pub async fn list_active(&self) -> Vec<ExecutionContext> {
+    /// Get the active executions visible from a context
+    pub async fn list_active(&self, ctx: &ContextId) -> Vec<ExecutionContext> {
         self.executions.read().await
             .values()
             .filter(|e| e.status == WorkflowStatus::Running)
+            .filter(|e| ctx.can_access(&e.context_id))
             .cloned()
             .collect()
     }
+
+    /// Start a new tracked run of a workflow
+    pub async fn start_run(&self, request: ExecuteWorkflowRequest) -> Result<WorkflowRun, String> {
+        let workflow = self.storage.get(&request.workflow_id).await
+            .ok_or_else(|| format!("Workflow {} not found", request.workflow_id))?;
+
+        let variables = prepare_variables(&workflow, request.variables, request.trigger_payload)?;
+        let mut run = WorkflowRun::new(&workflow, variables, request.start_step_id);
+        run.context_id = request.context_id;
+        self.runs.write().await.insert(run.id.clone(), run.clone());
+        let _ = self.save_run(&run);
+        publish_run(&run);
+
+        if run.status == WorkflowStatus::Running {
+            self.storage.update_status(&workflow.id, WorkflowStatus::Running).await;
+        }
+
+        self.run_server_steps(&run.id).await
+    }
+
+    /// Record the outcome of the current step of a run
+    pub async fn report_step(&self, run_id: &str, outcome: StepOutcome) -> Result<WorkflowRun, String> {
+        self.apply_outcome(run_id, outcome).await?;
+        self.run_server_steps(run_id).await
+    }
+
+    /// Execute HTTP, scaffold, tool and script steps server-side while the run's current
+    /// step is one, waiting out retry backoff between failed attempts. Steps
+    /// that need confirmation pause the run first.
+    async fn run_server_steps(&self, run_id: &str) -> Result<WorkflowRun, String> {
+        loop {
+            let (run, step, action) = {
+                let mut runs = self.runs.write().await;
+                let run = runs.get_mut(run_id)
+                    .ok_or_else(|| format!("Run {} not found", run_id))?;
+                if run.status != WorkflowStatus::Running {
+                    return Ok(run.clone());
+                }
+                let workflow = self.storage.get(&run.workflow_id).await
+                    .ok_or_else(|| "Workflow not found".to_string())?;
+                if let Some(approval) = run.request_approval(&workflow).cloned() {
+                    self.storage.update_status(&run.workflow_id, WorkflowStatus::Paused).await;
+                    let _ = self.save_run(run);
+                    publish_run(run);
+                    crate::events::publish(crate::events::Event::WorkflowApprovalNeeded {
+                        workflow_id: run.workflow_id.clone(),
+                        run_id: run.id.clone(),
+                        approval,
+                    });
+                    return Ok(run.clone());
+                }
+                let Some(step) = workflow.steps.iter()
+                    .find(|s| Some(&s.id) == run.current_step_id.as_ref())
+                    .cloned()
+                else {
+                    return Ok(run.clone());
+                };
+                let ctx = EvalContext {
+                    variables: &run.variables,
+                    steps: &run.step_outputs,
+                    current_step_id: Some(&step.id),
+                };
+                let action = if let Some(http) = &step.http {
+                    ServerAction::Http(webhook::render_request(http, &ctx))
+                } else if let Some(scaffold) = &step.scaffold {
+                    ServerAction::Scaffold(scaffolding::render_step(scaffold, &ctx))
+                } else if !step.action.is_prompt() {
+                    ServerAction::Tool(tool_step::render_action(&step.action, &ctx))
+                } else {
+                    return Ok(run.clone());
+                };
+                (run.clone(), step, action)
+            };
+
+            if let Some(retry_at) = run.next_retry_at_ms {
+                let wait_ms = retry_at - chrono::Utc::now().timestamp_millis();
+                if wait_ms > 0 {
+                    tokio::time::sleep(std::time::Duration::from_millis(wait_ms as u64)).await;
+                }
+            }
+
+            let outcome = match action {
+                ServerAction::Http(request) => webhook::send(&step, &request).await,
+                ServerAction::Scaffold(scaffold) => scaffolding::run(&step, &scaffold).await,
+                ServerAction::Tool(action) => tool_step::run(&step, &action).await,
+            };
+            self.apply_outcome(run_id, outcome).await?;
+        }
+    }
+
+    async fn apply_outcome(&self, run_id: &str, mut outcome: StepOutcome) -> Result<WorkflowRun, String> {
+        let mut runs = self.runs.write().await;
+        let run = runs.get_mut(run_id)
+            .ok_or_else(|| format!("Run {} not found", run_id))?;
+
+        let workflow = self.storage.get(&run.workflow_id).await
+            .ok_or_else(|| "Workflow not found".to_string())?;
+
+        // Files are copied before the outcome is recorded so a missing one
+        // rejects the report instead of silently losing the artifact
+        let sources = std::mem::take(&mut outcome.artifacts);
+        let mut stored: Vec<RunArtifact> = Vec::new();
+        for source in sources {
+            let source = ArtifactSource {
+                step_id: source.step_id.or_else(|| Some(outcome.step_id.clone())),
+                ..source
+            };
+            match self.artifacts.store(run_id, &source) {
+                Ok(artifact) => stored.push(artifact),
+                Err(e) => {
+                    stored.iter().for_each(|a| self.artifacts.remove(run_id, &a.id));
+                    return Err(e);
+                }
+            }
+        }
+        if let Err(e) = run.record_outcome(&workflow, outcome) {
+            stored.iter().for_each(|a| self.artifacts.remove(run_id, &a.id));
+            return Err(e);
+        }
+        run.artifacts.extend(stored);
+        run.refresh_prompt(&workflow);
+
+        match run.status {
+            WorkflowStatus::Completed => {
+                self.storage.update_status(&run.workflow_id, WorkflowStatus::Completed).await;
+                self.storage.increment_run_count(&run.workflow_id).await;
+            }
+            WorkflowStatus::Failed => {
+                self.storage.update_status(&run.workflow_id, WorkflowStatus::Failed).await;
+            }
+            _ => {}
+        }
+
+        let _ = self.save_run(run);
+
+        publish_run(run);
+        let run = run.clone();
+        drop(runs);
+
+        if run.is_finished() {
+            self.prune_artifacts(Some(&run.workflow_id)).await;
+        }
+        Ok(run)
+    }
+
+    /// Resume a failed or cancelled run from the step where it stopped
+    pub async fn resume_run(&self, run_id: &str) -> Result<WorkflowRun, String> {
+        let mut runs = self.runs.write().await;
+        let run = runs.get_mut(run_id)
+            .ok_or_else(|| format!("Run {} not found", run_id))?;
+
+        let workflow = self.storage.get(&run.workflow_id).await
+            .ok_or_else(|| "Workflow not found".to_string())?;
+
+        run.resume()?;
+        run.refresh_prompt(&workflow);
+        self.storage.update_status(&run.workflow_id, WorkflowStatus::Running).await;
+        let _ = self.save_run(run);
+        publish_run(run);
+        drop(runs);
+
+        self.run_server_steps(run_id).await
+    }
+
+    /// Continue a run paused for approval, with `input` merged into its
+    /// variables
+    pub async fn approve_run(
+        &self,
+        run_id: &str,
+        input: HashMap<String, serde_json::Value>,
+    ) -> Result<WorkflowRun, String> {
+        let mut runs = self.runs.write().await;
+        let run = runs.get_mut(run_id)
+            .ok_or_else(|| format!("Run {} not found", run_id))?;
+
+        let workflow = self.storage.get(&run.workflow_id).await
+            .ok_or_else(|| "Workflow not found".to_string())?;
+
+        run.approve(input)?;
+        run.refresh_prompt(&workflow);
+        self.storage.update_status(&run.workflow_id, WorkflowStatus::Running).await;
+        let _ = self.save_run(run);
+        publish_run(run);
+        drop(runs);
+
+        self.run_server_steps(run_id).await
+    }
+
+    /// Cancel a run
+    pub async fn cancel_run(&self, run_id: &str) -> Result<WorkflowRun, String> {
+        let mut runs = self.runs.write().await;
+        let run = runs.get_mut(run_id)
+            .ok_or_else(|| format!("Run {} not found", run_id))?;
+
+        run.cancel()?;
+        self.storage.update_status(&run.workflow_id, WorkflowStatus::Idle).await;
+        let _ = self.save_run(run);
+        publish_run(run);
+        let run = run.clone();
+        drop(runs);
+
+        self.prune_artifacts(Some(&run.workflow_id)).await;
+        Ok(run)
+    }
+
+    /// Keep a file produced by a run as one of its artifacts
+    pub async fn register_artifact(&self, run_id: &str, source: ArtifactSource) -> Result<RunArtifact, String> {
+        let mut runs = self.runs.write().await;
+        let run = runs.get_mut(run_id)
+            .ok_or_else(|| format!("Run {} not found", run_id))?;
+        if let Some(step_id) = &source.step_id {
+            let workflow = self.storage.get(&run.workflow_id).await
+                .ok_or_else(|| "Workflow not found".to_string())?;
+            if !workflow.steps.iter().any(|s| &s.id == step_id) {
+                return Err(format!("Step {} not found", step_id));
+            }
+        }
+
+        let artifact = self.artifacts.store(run_id, &source)?;
+        run.artifacts.push(artifact.clone());
+        run.updated_at = chrono::Utc::now().timestamp();
+        let _ = self.save_run(run);
+        publish_run(run);
+        Ok(artifact)
+    }
+
+    /// An artifact of a run and the path of its stored copy
+    pub async fn artifact(&self, run_id: &str, artifact_id: &str) -> Option<(RunArtifact, std::path::PathBuf)> {
+        let runs = self.runs.read().await;
+        let artifact = runs.get(run_id)?
+            .artifacts
+            .iter()
+            .find(|a| a.id == artifact_id)?
+            .clone();
+        let path = self.artifacts.file_path(run_id, &artifact.id);
+        Some((artifact, path))
+    }
+
+    /// Delete the artifacts of runs their workflow's retention policy no
+    /// longer covers, for one workflow or all of them
+    pub async fn prune_artifacts(&self, workflow_id: Option<&str>) {
+        let workflows = match workflow_id {
+            Some(id) => self.storage.get(id).await.into_iter().collect(),
+            None => self.storage.list().await,
+        };
+        let now = chrono::Utc::now().timestamp();
+        let mut runs = self.runs.write().await;
+
+        for workflow in workflows {
+            let expired = {
+                let of_workflow: Vec<&WorkflowRun> = runs.values()
+                    .filter(|r| r.workflow_id == workflow.id)
+                    .collect();
+                expired_runs(&of_workflow, &workflow.output_settings.retention, now)
+            };
+            for run_id in expired {
+                if let Some(run) = runs.get_mut(&run_id) {
+                    self.artifacts.remove_run(&run_id);
+                    run.artifacts.clear();
+                    let _ = self.save_run(run);
+                }
+            }
+        }
+    }
+
+    /// Get a run by ID
+    pub async fn get_run(&self, run_id: &str) -> Option<WorkflowRun> {
+        self.runs.read().await.get(run_id).cloned()
+    }
+
+    /// List the runs visible from a context, newest first, optionally
+    /// filtered by workflow and status
+    pub async fn list_runs(
+        &self,
+        ctx: &ContextId,
+        workflow_id: Option<&str>,
+        status: Option<WorkflowStatus>,
+    ) -> Vec<WorkflowRun> {
+        let mut runs: Vec<WorkflowRun> = self.runs.read().await
+            .values()
+            .filter(|r| ctx.can_access(&r.context_id))
+            .filter(|r| workflow_id.is_none_or(|id| r.workflow_id == id))
+            .filter(|r| status.is_none_or(|s| r.status == s))
+            .cloned()
+            .collect();
+        runs.sort_by_key(|r| std::cmp::Reverse(r.started_at));
+        runs
+    }
+}
+
+fn publish_execution(context: &ExecutionContext) {
+    crate::events::publish(crate::events::Event::WorkflowExecution {
+        workflow_id: context.workflow_id.clone(),
+        execution_id: context.execution_id.clone(),
+        status: context.status,
+        current_step_id: context.current_step_id.clone(),
+    });
+}
+
+fn publish_run(run: &WorkflowRun) {
+    crate::events::publish(crate::events::Event::WorkflowRun {
+        workflow_id: run.workflow_id.clone(),
+        run_id: run.id.clone(),
+        status: run.status,
+        current_step_id: run.current_step_id.clone(),
+    });
 }
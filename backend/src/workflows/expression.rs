@@ -0,0 +1,435 @@
+//! Deterministic decision expressions
+//!
+//! Evaluates decision conditions without an LLM call. Supported syntax:
+//!
+//! - literals: `'text'`, `"text"`, `42`, `3.5`, `true`, `false`, `null`
+//! - references: `name` or `vars.name` (workflow variables),
+//!   `steps.<step_id>.output` / `.success` / `.error`, and `output` for the
+//!   output of the step that owns the decision
+//! - comparisons: `==`, `!=`, `<`, `<=`, `>`, `>=`
+//! - string operators: `contains`, `matches` (regex), `starts_with`, `ends_with`
+//! - boolean logic: `&&` / `and`, `||` / `or`, `!` / `not`, parentheses
+//!
+//! Example: `steps.tests.success && output contains 'PASSED'`
+
+use super::types::{DecisionNode, StepResult, WorkflowStep};
+use regex::Regex;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Values an expression can reference
+pub struct EvalContext<'a> {
+    pub variables: &'a HashMap<String, Value>,
+    pub steps: &'a HashMap<String, StepResult>,
+    /// Step whose output `output` refers to
+    pub current_step_id: Option<&'a str>,
+}
+
+/// Parse and evaluate an expression to a boolean
+pub fn evaluate(expression: &str, ctx: &EvalContext) -> Result<bool, String> {
+    let expr = parse(expression)?;
+    Ok(truthy(&expr.eval(ctx)?))
+}
+
+/// Decision outcome for a step: evaluated locally when the decision is an
+/// expression, otherwise the result supplied by the AI
+pub fn resolve_decision(
+    decision: Option<&DecisionNode>,
+    ctx: &EvalContext,
+    ai_result: Option<bool>,
+) -> Result<Option<bool>, String> {
+    match decision {
+        Some(decision) if decision.expression => evaluate(&decision.condition, ctx)
+            .map(Some)
+            .map_err(|e| format!("Decision '{}' failed: {}", decision.id, e)),
+        _ => Ok(ai_result),
+    }
+}
+
+/// Check an expression parses without evaluating it
+pub fn validate(expression: &str) -> Result<(), String> {
+    parse(expression).map(|_| ())
+}
+
+/// Validate every expression decision and tool or script action in a list
+/// of steps
+pub fn validate_steps(steps: &[WorkflowStep]) -> Result<(), String> {
+    for step in steps {
+        super::tool_step::validate(&step.action)
+            .map_err(|e| format!("Invalid action in step '{}': {}", step.name, e))?;
+        if let Some(decision) = step.decision.as_ref().filter(|d| d.expression) {
+            validate(&decision.condition)
+                .map_err(|e| format!("Invalid condition in step '{}': {}", step.name, e))?;
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Str(String),
+    Num(f64),
+    Ident(String),
+    Op(&'static str),
+    LParen,
+    RParen,
+}
+
+#[derive(Debug)]
+enum Expr {
+    Literal(Value),
+    Ref(String),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Compare(Box<Expr>, &'static str, Box<Expr>),
+}
+
+const OPERATORS: &[&str] = &["==", "!=", "<=", ">=", "&&", "||", "<", ">", "!"];
+const WORD_OPERATORS: &[&str] = &["contains", "matches", "starts_with", "ends_with", "and", "or", "not"];
+
+fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c == '(' {
+            tokens.push(Token::LParen);
+            i += 1;
+        } else if c == ')' {
+            tokens.push(Token::RParen);
+            i += 1;
+        } else if c == '\'' || c == '"' {
+            let quote = c;
+            let mut value = String::new();
+            i += 1;
+            loop {
+                match chars.get(i) {
+                    None => return Err("Unterminated string literal".to_string()),
+                    Some('\\') if i + 1 < chars.len() => {
+                        value.push(chars[i + 1]);
+                        i += 2;
+                    }
+                    Some(&ch) if ch == quote => {
+                        i += 1;
+                        break;
+                    }
+                    Some(&ch) => {
+                        value.push(ch);
+                        i += 1;
+                    }
+                }
+            }
+            tokens.push(Token::Str(value));
+        } else if c.is_ascii_digit() {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            let text: String = chars[start..i].iter().collect();
+            let num = text.parse().map_err(|_| format!("Invalid number '{}'", text))?;
+            tokens.push(Token::Num(num));
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || matches!(chars[i], '_' | '-' | '.')) {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+            match WORD_OPERATORS.iter().find(|op| **op == word) {
+                Some(op) => tokens.push(Token::Op(op)),
+                None => tokens.push(Token::Ident(word)),
+            }
+        } else {
+            let rest: String = chars[i..].iter().take(2).collect();
+            let op = OPERATORS
+                .iter()
+                .find(|op| rest.starts_with(**op))
+                .ok_or_else(|| format!("Unexpected character '{}'", c))?;
+            tokens.push(Token::Op(op));
+            i += op.len();
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn parse(input: &str) -> Result<Expr, String> {
+    let tokens = tokenize(input)?;
+    if tokens.is_empty() {
+        return Err("Expression is empty".to_string());
+    }
+    let mut parser = Parser { tokens, pos: 0 };
+    let expr = parser.parse_or()?;
+    if parser.pos < parser.tokens.len() {
+        return Err(format!("Unexpected token {:?}", parser.tokens[parser.pos]));
+    }
+    Ok(expr)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek_op(&self) -> Option<&'static str> {
+        match self.tokens.get(self.pos) {
+            Some(Token::Op(op)) => Some(op),
+            _ => None,
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, String> {
+        let mut left = self.parse_and()?;
+        while matches!(self.peek_op(), Some("||") | Some("or")) {
+            self.pos += 1;
+            left = Expr::Or(Box::new(left), Box::new(self.parse_and()?));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, String> {
+        let mut left = self.parse_not()?;
+        while matches!(self.peek_op(), Some("&&") | Some("and")) {
+            self.pos += 1;
+            left = Expr::And(Box::new(left), Box::new(self.parse_not()?));
+        }
+        Ok(left)
+    }
+
+    fn parse_not(&mut self) -> Result<Expr, String> {
+        if matches!(self.peek_op(), Some("!") | Some("not")) {
+            self.pos += 1;
+            return Ok(Expr::Not(Box::new(self.parse_not()?)));
+        }
+        self.parse_comparison()
+    }
+
+    fn parse_comparison(&mut self) -> Result<Expr, String> {
+        let left = self.parse_primary()?;
+        match self.peek_op() {
+            Some(op @ ("==" | "!=" | "<" | "<=" | ">" | ">=" | "contains" | "matches" | "starts_with"
+            | "ends_with")) => {
+                self.pos += 1;
+                let right = self.parse_primary()?;
+                Ok(Expr::Compare(Box::new(left), op, Box::new(right)))
+            }
+            _ => Ok(left),
+        }
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, String> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .cloned()
+            .ok_or_else(|| "Unexpected end of expression".to_string())?;
+        self.pos += 1;
+
+        match token {
+            Token::Str(s) => Ok(Expr::Literal(Value::String(s))),
+            Token::Num(n) => Ok(Expr::Literal(serde_json::json!(n))),
+            Token::Ident(name) => Ok(match name.as_str() {
+                "true" => Expr::Literal(Value::Bool(true)),
+                "false" => Expr::Literal(Value::Bool(false)),
+                "null" => Expr::Literal(Value::Null),
+                _ => Expr::Ref(name),
+            }),
+            Token::LParen => {
+                let expr = self.parse_or()?;
+                if self.tokens.get(self.pos) != Some(&Token::RParen) {
+                    return Err("Missing closing parenthesis".to_string());
+                }
+                self.pos += 1;
+                Ok(expr)
+            }
+            other => Err(format!("Unexpected token {:?}", other)),
+        }
+    }
+}
+
+impl Expr {
+    fn eval(&self, ctx: &EvalContext) -> Result<Value, String> {
+        match self {
+            Expr::Literal(v) => Ok(v.clone()),
+            Expr::Ref(name) => Ok(resolve(name, ctx)),
+            Expr::Not(e) => Ok(Value::Bool(!truthy(&e.eval(ctx)?))),
+            Expr::And(l, r) => Ok(Value::Bool(truthy(&l.eval(ctx)?) && truthy(&r.eval(ctx)?))),
+            Expr::Or(l, r) => Ok(Value::Bool(truthy(&l.eval(ctx)?) || truthy(&r.eval(ctx)?))),
+            Expr::Compare(l, op, r) => compare(&l.eval(ctx)?, op, &r.eval(ctx)?).map(Value::Bool),
+        }
+    }
+}
+
+/// Look up a reference (`output`, `steps.<id>.<field>`, `vars.name` or a
+/// dotted path into a JSON variable such as `trigger.file_path`)
+pub(super) fn resolve(name: &str, ctx: &EvalContext) -> Value {
+    if name == "output" {
+        return ctx
+            .current_step_id
+            .and_then(|id| ctx.steps.get(id))
+            .map(|r| Value::String(r.output.clone()))
+            .unwrap_or(Value::Null);
+    }
+
+    if let Some(rest) = name.strip_prefix("steps.") {
+        let (step_id, field) = rest.rsplit_once('.').unwrap_or((rest, "output"));
+        return match ctx.steps.get(step_id) {
+            Some(result) => match field {
+                "output" => Value::String(result.output.clone()),
+                "success" => Value::Bool(result.success),
+                "error" => result.error.clone().map(Value::String).unwrap_or(Value::Null),
+                "decision_result" => result.decision_result.map(Value::Bool).unwrap_or(Value::Null),
+                _ => Value::Null,
+            },
+            None => Value::Null,
+        };
+    }
+
+    let var = name.strip_prefix("vars.").unwrap_or(name);
+    if let Some(value) = ctx.variables.get(var) {
+        return value.clone();
+    }
+
+    let mut path = var.split('.');
+    let root = path.next().and_then(|key| ctx.variables.get(key));
+    path.fold(root, |value, key| match value {
+        Some(Value::Object(map)) => map.get(key),
+        Some(Value::Array(items)) => key.parse::<usize>().ok().and_then(|i| items.get(i)),
+        _ => None,
+    })
+    .cloned()
+    .unwrap_or(Value::Null)
+}
+
+pub(super) fn as_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+fn as_number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+fn truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64().is_some_and(|f| f != 0.0),
+        Value::String(s) => !s.is_empty() && s != "false",
+        Value::Array(a) => !a.is_empty(),
+        Value::Object(o) => !o.is_empty(),
+    }
+}
+
+fn compare(left: &Value, op: &str, right: &Value) -> Result<bool, String> {
+    match op {
+        "contains" => Ok(match left {
+            Value::Array(items) => items.iter().any(|i| as_text(i) == as_text(right)),
+            _ => as_text(left).contains(&as_text(right)),
+        }),
+        "starts_with" => Ok(as_text(left).starts_with(&as_text(right))),
+        "ends_with" => Ok(as_text(left).ends_with(&as_text(right))),
+        "matches" => {
+            let pattern = as_text(right);
+            let re = Regex::new(&pattern).map_err(|e| format!("Invalid regex '{}': {}", pattern, e))?;
+            Ok(re.is_match(&as_text(left)))
+        }
+        "==" => Ok(values_equal(left, right)),
+        "!=" => Ok(!values_equal(left, right)),
+        _ => {
+            let ordering = match (as_number(left), as_number(right)) {
+                (Some(a), Some(b)) => a.partial_cmp(&b).ok_or_else(|| "Cannot compare NaN".to_string())?,
+                _ => as_text(left).cmp(&as_text(right)),
+            };
+            Ok(match op {
+                "<" => ordering.is_lt(),
+                "<=" => ordering.is_le(),
+                ">" => ordering.is_gt(),
+                ">=" => ordering.is_ge(),
+                _ => return Err(format!("Unknown operator '{}'", op)),
+            })
+        }
+    }
+}
+
+fn values_equal(left: &Value, right: &Value) -> bool {
+    if left.is_boolean() || right.is_boolean() {
+        return truthy(left) == truthy(right);
+    }
+    match (as_number(left), as_number(right)) {
+        (Some(a), Some(b)) => a == b,
+        _ => as_text(left) == as_text(right),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(id: &str, output: &str, success: bool) -> (String, StepResult) {
+        (
+            id.to_string(),
+            StepResult {
+                step_id: id.to_string(),
+                success,
+                output: output.to_string(),
+                error: None,
+                duration_ms: 0,
+                decision_result: None,
+            },
+        )
+    }
+
+    fn eval(expression: &str) -> Result<bool, String> {
+        let variables: HashMap<String, Value> = HashMap::from([
+            ("count".to_string(), serde_json::json!(12)),
+            ("env".to_string(), serde_json::json!("prod")),
+            ("tags".to_string(), serde_json::json!(["urgent", "bug"])),
+        ]);
+        let steps = HashMap::from([step("build", "Build OK: 3 warnings", true), step("step-2", "", false)]);
+        let ctx = EvalContext {
+            variables: &variables,
+            steps: &steps,
+            current_step_id: Some("build"),
+        };
+        evaluate(expression, &ctx)
+    }
+
+    #[test]
+    fn test_comparisons_and_logic() {
+        assert!(eval("count > 10 && env == 'prod'").unwrap());
+        assert!(eval("vars.count <= 12").unwrap());
+        assert!(!eval("not (count >= 12)").unwrap());
+        assert!(eval("env != \"dev\" or missing").unwrap());
+        assert!(!eval("missing").unwrap());
+    }
+
+    #[test]
+    fn test_step_references_and_string_operators() {
+        assert!(eval("steps.build.success && output contains 'OK'").unwrap());
+        assert!(eval("steps.step-2.success == false").unwrap());
+        assert!(eval("output matches '\\\\d+ warnings'").unwrap());
+        assert!(eval("output starts_with 'Build'").unwrap());
+        assert!(eval("tags contains 'bug'").unwrap());
+    }
+
+    #[test]
+    fn test_invalid_expressions_are_rejected() {
+        assert!(validate("count >").is_err());
+        assert!(validate("(count > 1").is_err());
+        assert!(validate("'unterminated").is_err());
+        assert!(eval("env matches '('").is_err());
+    }
+}
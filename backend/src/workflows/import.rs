@@ -0,0 +1,655 @@
+//! Importing shared workflows
+//!
+//! A workflow bundle is a `workflow.json` file holding a workflow definition
+//! (the fields of [`CreateWorkflowRequest`], so an exported [`Workflow`] works
+//! too) plus an optional `assets` list of files stored next to it. Bundles
+//! are downloaded from a URL or cloned from a git repository, validated, and
+//! summarized for review: the kinds of step the workflow runs, the hosts and
+//! templates it uses, what triggers it, and how it differs from the copy
+//! installed from the same source. Installing puts the workflow in the
+//! `community` category and its assets under the workflow storage.
+
+use super::expression::validate_steps;
+use super::storage::WorkflowStorage;
+use super::types::*;
+use crate::scaffold::safe_relative;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Category imported workflows are installed under
+pub const COMMUNITY_CATEGORY: &str = "community";
+/// Bundle file looked up in a git repository when no path is given
+pub const BUNDLE_FILE: &str = "workflow.json";
+
+const MAX_BUNDLE_BYTES: usize = 1024 * 1024;
+/// Limit on the combined size of a bundle's assets
+const MAX_ASSETS_BYTES: usize = 10 * 1024 * 1024;
+const MAX_ASSETS: usize = 100;
+const FETCH_TIMEOUT_SECS: u64 = 30;
+const CLONE_TIMEOUT_SECS: u64 = 120;
+/// Transports git may use when cloning a bundle repository
+const GIT_PROTOCOLS: &str = "https:http:ssh:git";
+
+#[derive(Debug, thiserror::Error)]
+pub enum ImportError {
+    #[error("Unsupported workflow source: {0}")]
+    UnsupportedSource(String),
+
+    #[error("Failed to fetch workflow bundle: {0}")]
+    Fetch(String),
+
+    #[error("{0} is larger than {1} bytes")]
+    TooLarge(String, usize),
+
+    #[error("Invalid workflow bundle: {0}")]
+    Invalid(String),
+
+    #[error("Unsafe asset path in bundle: {0}")]
+    UnsafePath(String),
+
+    #[error("The bundle changed since it was reviewed; preview it again")]
+    Changed,
+
+    #[error("Failed to install assets: {0}")]
+    Io(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SourceKind {
+    /// `workflow.json` downloaded over HTTP(S); assets are resolved against its URL
+    Url,
+    /// Git repository cloned at its default branch or `ref`
+    Git,
+}
+
+/// Where to get a bundle from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportSource {
+    pub url: String,
+    /// Detected from the URL when omitted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<SourceKind>,
+    /// Bundle file within a git repository (defaults to `workflow.json`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// Branch or tag of a git repository
+    #[serde(default, rename = "ref", skip_serializing_if = "Option::is_none")]
+    pub reference: Option<String>,
+}
+
+impl ImportSource {
+    pub fn kind(&self) -> SourceKind {
+        self.kind.unwrap_or_else(|| {
+            let url = self.url.trim_end_matches('/');
+            if url.ends_with(".git") || url.starts_with("git@") || url.starts_with("git://") || url.starts_with("ssh://") {
+                SourceKind::Git
+            } else {
+                SourceKind::Url
+            }
+        })
+    }
+
+    /// Identifier recorded on the installed workflow, so importing the same
+    /// source again updates it instead of adding a copy
+    pub fn id(&self) -> String {
+        match self.kind() {
+            SourceKind::Url => self.url.clone(),
+            SourceKind::Git => format!(
+                "git+{}#{}:{}",
+                self.url,
+                self.reference.as_deref().unwrap_or(""),
+                self.path.as_deref().unwrap_or(BUNDLE_FILE)
+            ),
+        }
+    }
+}
+
+/// Contents of `workflow.json`
+#[derive(Debug, Deserialize)]
+struct BundleFile {
+    #[serde(flatten)]
+    workflow: CreateWorkflowRequest,
+    #[serde(default)]
+    variables: HashMap<String, String>,
+    /// Paths of asset files, relative to the bundle file
+    #[serde(default)]
+    assets: Vec<String>,
+}
+
+/// A file shipped with a workflow
+#[derive(Debug, Clone)]
+pub struct BundleAsset {
+    pub path: PathBuf,
+    pub content: Vec<u8>,
+}
+
+/// A fetched and validated bundle
+#[derive(Debug, Clone)]
+pub struct WorkflowBundle {
+    pub workflow: CreateWorkflowRequest,
+    pub variables: HashMap<String, String>,
+    pub assets: Vec<BundleAsset>,
+    /// SHA-256 of the bundle file and assets, to check that what gets
+    /// installed is what was reviewed
+    pub digest: String,
+}
+
+/// What a workflow will be allowed to do once installed
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct PermissionSummary {
+    /// Kinds of step it runs: "prompt", "http", "scaffold"
+    pub tools: Vec<String>,
+    /// Hosts called by HTTP steps; URLs built from placeholders are listed as-is
+    pub hosts: Vec<String>,
+    /// Project templates used by scaffold steps
+    pub templates: Vec<String>,
+    /// What starts it without the user, if anything
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trigger: Option<String>,
+    /// Offered to the agent as a tool
+    pub as_toolcall: bool,
+    pub background: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_folder: Option<String>,
+}
+
+/// A difference from the installed copy
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum WorkflowChange {
+    Field { name: String },
+    StepAdded { id: String },
+    StepRemoved { id: String },
+    StepChanged { id: String },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AssetSummary {
+    pub path: String,
+    pub size: usize,
+}
+
+/// Review of a bundle before installing it
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportPreview {
+    pub source: String,
+    pub digest: String,
+    pub name: String,
+    pub description: String,
+    pub workflow_type: WorkflowType,
+    pub step_count: usize,
+    pub permissions: PermissionSummary,
+    pub assets: Vec<AssetSummary>,
+    /// Workflow this bundle would update
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub installed_id: Option<String>,
+    /// Changes from the installed workflow; empty for a new one
+    pub changes: Vec<WorkflowChange>,
+}
+
+/// Download or clone the bundle described by `source`
+pub async fn fetch_bundle(source: &ImportSource) -> Result<WorkflowBundle, ImportError> {
+    match source.kind() {
+        SourceKind::Url => fetch_url_bundle(&source.url).await,
+        SourceKind::Git => fetch_git_bundle(source).await,
+    }
+}
+
+async fn fetch_url_bundle(url: &str) -> Result<WorkflowBundle, ImportError> {
+    let base = url::Url::parse(url).map_err(|e| ImportError::UnsupportedSource(format!("{}: {}", url, e)))?;
+    if !matches!(base.scheme(), "http" | "https") {
+        return Err(ImportError::UnsupportedSource(url.to_string()));
+    }
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(FETCH_TIMEOUT_SECS))
+        .build()
+        .map_err(|e| ImportError::Fetch(e.to_string()))?;
+
+    let data = download(&client, &base, MAX_BUNDLE_BYTES).await?;
+    let file = parse_bundle(&data)?;
+    check_asset_count(&file)?;
+
+    let mut assets = Vec::new();
+    let mut total = 0;
+    for asset in &file.assets {
+        let path = asset_path(asset)?;
+        let asset_url = base
+            .join(&path.to_string_lossy().replace('\\', "/"))
+            .map_err(|e| ImportError::UnsafePath(format!("{}: {}", asset, e)))?;
+        let content = download(&client, &asset_url, MAX_ASSETS_BYTES - total).await?;
+        total += content.len();
+        assets.push(BundleAsset { path, content });
+    }
+    Ok(finish_bundle(file, &data, assets))
+}
+
+/// GET `url`, failing if the body exceeds `limit` bytes
+async fn download(client: &reqwest::Client, url: &url::Url, limit: usize) -> Result<Vec<u8>, ImportError> {
+    let mut response = client
+        .get(url.clone())
+        .send()
+        .await
+        .map_err(|e| ImportError::Fetch(format!("{}: {}", url, e)))?;
+    if !response.status().is_success() {
+        return Err(ImportError::Fetch(format!("{}: HTTP {}", url, response.status())));
+    }
+    let mut data = Vec::new();
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| ImportError::Fetch(format!("{}: {}", url, e)))?
+    {
+        data.extend_from_slice(&chunk);
+        if data.len() > limit {
+            return Err(ImportError::TooLarge(url.to_string(), limit));
+        }
+    }
+    Ok(data)
+}
+
+async fn fetch_git_bundle(source: &ImportSource) -> Result<WorkflowBundle, ImportError> {
+    let dir = tempfile::tempdir().map_err(|e| ImportError::Io(e.to_string()))?;
+    let checkout = dir.path().join("repo");
+
+    let mut command = tokio::process::Command::new("git");
+    command.args(["clone", "--depth", "1", "--quiet"]);
+    if let Some(reference) = &source.reference {
+        command.args(["--branch", reference]);
+    }
+    command
+        .arg("--")
+        .arg(&source.url)
+        .arg(&checkout)
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .env("GIT_ALLOW_PROTOCOL", GIT_PROTOCOLS)
+        .kill_on_drop(true);
+
+    let output = tokio::time::timeout(Duration::from_secs(CLONE_TIMEOUT_SECS), command.output())
+        .await
+        .map_err(|_| ImportError::Fetch(format!("cloning {} timed out", source.url)))?
+        .map_err(|e| ImportError::Fetch(format!("failed to run git: {}", e)))?;
+    if !output.status.success() {
+        return Err(ImportError::Fetch(format!(
+            "git clone failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    let file = Path::new(source.path.as_deref().unwrap_or(BUNDLE_FILE));
+    load_bundle_dir(&checkout, file)
+}
+
+/// Read a bundle from a checked-out directory. Assets must stay inside
+/// `root`, symlinks included.
+pub fn load_bundle_dir(root: &Path, file: &Path) -> Result<WorkflowBundle, ImportError> {
+    let relative = safe_relative(file).ok_or_else(|| ImportError::UnsafePath(file.display().to_string()))?;
+    let root = root.canonicalize().map_err(|e| ImportError::Io(e.to_string()))?;
+    let data = read_inside(&root, &root.join(&relative), MAX_BUNDLE_BYTES)?;
+    let bundle_dir = relative.parent().map(|p| root.join(p)).unwrap_or_else(|| root.clone());
+
+    let file = parse_bundle(&data)?;
+    check_asset_count(&file)?;
+    let mut assets = Vec::new();
+    let mut total = 0;
+    for asset in &file.assets {
+        let path = asset_path(asset)?;
+        let content = read_inside(&root, &bundle_dir.join(&path), MAX_ASSETS_BYTES - total)?;
+        total += content.len();
+        assets.push(BundleAsset { path, content });
+    }
+    Ok(finish_bundle(file, &data, assets))
+}
+
+fn read_inside(root: &Path, path: &Path, limit: usize) -> Result<Vec<u8>, ImportError> {
+    let display = path.strip_prefix(root).unwrap_or(path).display().to_string();
+    let resolved = path
+        .canonicalize()
+        .map_err(|e| ImportError::Invalid(format!("{}: {}", display, e)))?;
+    if !resolved.starts_with(root) {
+        return Err(ImportError::UnsafePath(display));
+    }
+    let size = std::fs::metadata(&resolved).map_err(|e| ImportError::Io(e.to_string()))?.len();
+    if size > limit as u64 {
+        return Err(ImportError::TooLarge(display, limit));
+    }
+    std::fs::read(&resolved).map_err(|e| ImportError::Io(format!("{}: {}", display, e)))
+}
+
+fn parse_bundle(data: &[u8]) -> Result<BundleFile, ImportError> {
+    let file: BundleFile = serde_json::from_slice(data).map_err(|e| ImportError::Invalid(e.to_string()))?;
+    validate_workflow(&file.workflow).map_err(ImportError::Invalid)?;
+    Ok(file)
+}
+
+fn check_asset_count(file: &BundleFile) -> Result<(), ImportError> {
+    if file.assets.len() > MAX_ASSETS {
+        return Err(ImportError::Invalid(format!("more than {} assets", MAX_ASSETS)));
+    }
+    Ok(())
+}
+
+fn asset_path(asset: &str) -> Result<PathBuf, ImportError> {
+    safe_relative(Path::new(asset)).ok_or_else(|| ImportError::UnsafePath(asset.to_string()))
+}
+
+fn finish_bundle(file: BundleFile, data: &[u8], assets: Vec<BundleAsset>) -> WorkflowBundle {
+    let mut hasher = Sha256::new();
+    hasher.update(data);
+    for asset in &assets {
+        hasher.update(asset.path.to_string_lossy().as_bytes());
+        hasher.update([0]);
+        hasher.update(&asset.content);
+    }
+    WorkflowBundle {
+        workflow: file.workflow,
+        variables: file.variables,
+        assets,
+        digest: hex::encode(hasher.finalize()),
+    }
+}
+
+/// Check that a shared workflow is complete and its steps link up
+pub fn validate_workflow(workflow: &CreateWorkflowRequest) -> Result<(), String> {
+    if workflow.name.trim().is_empty() {
+        return Err("workflow has no name".to_string());
+    }
+    if workflow.steps.is_empty() {
+        return Err("workflow has no steps".to_string());
+    }
+
+    let mut ids = HashSet::new();
+    for step in &workflow.steps {
+        if step.id.is_empty() {
+            return Err(format!("step '{}' has no id", step.name));
+        }
+        if !ids.insert(step.id.as_str()) {
+            return Err(format!("duplicate step id '{}'", step.id));
+        }
+    }
+    for step in &workflow.steps {
+        let decision = step.decision.as_ref();
+        let targets = [
+            step.next_step.as_deref(),
+            decision.and_then(|d| d.true_branch.as_deref()),
+            decision.and_then(|d| d.false_branch.as_deref()),
+        ];
+        if let Some(missing) = targets.into_iter().flatten().find(|id| !ids.contains(id)) {
+            return Err(format!("step '{}' links to unknown step '{}'", step.id, missing));
+        }
+    }
+    validate_steps(&workflow.steps)
+}
+
+/// Summarize what `workflow` will be able to do
+pub fn permissions(workflow: &CreateWorkflowRequest) -> PermissionSummary {
+    let mut tools = BTreeSet::new();
+    let mut hosts = BTreeSet::new();
+    let mut templates = BTreeSet::new();
+    for step in &workflow.steps {
+        if let Some(http) = &step.http {
+            tools.insert("http");
+            let host = url::Url::parse(&http.url)
+                .ok()
+                .and_then(|url| url.host_str().filter(|host| !host.contains("{{")).map(str::to_string));
+            hosts.insert(host.unwrap_or_else(|| http.url.clone()));
+        } else if let Some(scaffold) = &step.scaffold {
+            tools.insert("scaffold");
+            templates.insert(scaffold.template.clone());
+        } else if let StepAction::ToolCall(call) = &step.action {
+            tools.insert(call.tool.as_str());
+        } else if let StepAction::Script(_) = &step.action {
+            tools.insert("script");
+        } else {
+            tools.insert("prompt");
+        }
+    }
+
+    PermissionSummary {
+        tools: tools.into_iter().map(str::to_string).collect(),
+        hosts: hosts.into_iter().collect(),
+        templates: templates.into_iter().collect(),
+        trigger: workflow.trigger.as_ref().map(describe_trigger),
+        as_toolcall: workflow.behavior.as_toolcall,
+        background: workflow.behavior.background,
+        output_folder: workflow.output_settings.folder.clone(),
+    }
+}
+
+fn describe_trigger(trigger: &TriggerType) -> String {
+    match trigger {
+        TriggerType::OnFileSave { patterns } => format!("when a file matching {} is saved", patterns.join(", ")),
+        TriggerType::OnFileCreate { patterns } => format!("when a file matching {} is created", patterns.join(", ")),
+        TriggerType::OnMessage { keywords } => format!("on messages containing {}", keywords.join(", ")),
+        TriggerType::OnGitCommit => "on every git commit".to_string(),
+        TriggerType::OnError { error_patterns } => format!("on errors matching {}", error_patterns.join(", ")),
+        TriggerType::OnSchedule { cron } => format!("on the schedule '{}'", cron),
+        TriggerType::OnAIDetection { intent_patterns } => {
+            format!("when the AI detects {}", intent_patterns.join(", "))
+        }
+        TriggerType::Custom { condition } => format!("when '{}' holds", condition),
+        TriggerType::Webhook { .. } => "by requests to its webhook URL".to_string(),
+    }
+}
+
+/// Differences between an installed workflow and an incoming definition
+pub fn diff(installed: &Workflow, incoming: &CreateWorkflowRequest, variables: &HashMap<String, String>) -> Vec<WorkflowChange> {
+    let mut changes = Vec::new();
+    let mut field = |name: &str, changed: bool| {
+        if changed {
+            changes.push(WorkflowChange::Field { name: name.to_string() });
+        }
+    };
+    field("name", installed.name != incoming.name);
+    field("description", installed.description != incoming.description);
+    field("workflow_type", installed.workflow_type != incoming.workflow_type);
+    field("intent", installed.intent != incoming.intent);
+    field("trigger", json(&without_token(&installed.trigger)) != json(&without_token(&incoming.trigger)));
+    field("output_settings", json(&installed.output_settings) != json(&incoming.output_settings));
+    field("behavior", json(&installed.behavior) != json(&incoming.behavior));
+    field("inputs", json(&installed.inputs) != json(&incoming.inputs));
+    field("variables", &installed.variables != variables);
+
+    let old: HashMap<&str, &WorkflowStep> = installed.steps.iter().map(|s| (s.id.as_str(), s)).collect();
+    let new: HashSet<&str> = incoming.steps.iter().map(|s| s.id.as_str()).collect();
+    for step in &incoming.steps {
+        match old.get(step.id.as_str()) {
+            None => changes.push(WorkflowChange::StepAdded { id: step.id.clone() }),
+            Some(previous) if json(previous) != json(step) => {
+                changes.push(WorkflowChange::StepChanged { id: step.id.clone() })
+            }
+            Some(_) => {}
+        }
+    }
+    for step in &installed.steps {
+        if !new.contains(step.id.as_str()) {
+            changes.push(WorkflowChange::StepRemoved { id: step.id.clone() });
+        }
+    }
+    changes
+}
+
+/// JSON form of a value, to compare definitions field by field
+fn json<T: Serialize>(value: &T) -> Value {
+    serde_json::to_value(value).unwrap_or(Value::Null)
+}
+
+/// Webhook tokens are secrets of the installation, not part of a definition
+fn without_token(trigger: &Option<TriggerType>) -> Option<TriggerType> {
+    match trigger {
+        Some(TriggerType::Webhook { .. }) => Some(TriggerType::Webhook { token: String::new() }),
+        other => other.clone(),
+    }
+}
+
+/// Review `bundle` against what is installed from `source`
+pub async fn preview(storage: &WorkflowStorage, source: &ImportSource, bundle: &WorkflowBundle) -> ImportPreview {
+    let installed = storage.find_by_source(&source.id()).await;
+    ImportPreview {
+        source: source.id(),
+        digest: bundle.digest.clone(),
+        name: bundle.workflow.name.clone(),
+        description: bundle.workflow.description.clone(),
+        workflow_type: bundle.workflow.workflow_type,
+        step_count: bundle.workflow.steps.len(),
+        permissions: permissions(&bundle.workflow),
+        assets: bundle
+            .assets
+            .iter()
+            .map(|a| AssetSummary {
+                path: a.path.to_string_lossy().to_string(),
+                size: a.content.len(),
+            })
+            .collect(),
+        changes: installed
+            .as_ref()
+            .map(|w| diff(w, &bundle.workflow, &bundle.variables))
+            .unwrap_or_default(),
+        installed_id: installed.map(|w| w.id),
+    }
+}
+
+/// Install `bundle` as a community workflow, replacing the copy installed
+/// from the same source. A webhook trigger gets a fresh token, or keeps the
+/// one of the workflow it replaces.
+pub async fn install(
+    storage: &WorkflowStorage,
+    source: &ImportSource,
+    bundle: WorkflowBundle,
+) -> Result<Workflow, ImportError> {
+    let source_id = source.id();
+    let installed = storage.find_by_source(&source_id).await;
+    let request = bundle.workflow;
+
+    let mut workflow = match &installed {
+        Some(existing) => existing.clone(),
+        None => Workflow::new(request.name.clone(), request.workflow_type),
+    };
+    workflow.name = request.name;
+    workflow.description = request.description;
+    workflow.category = COMMUNITY_CATEGORY.to_string();
+    workflow.workflow_type = request.workflow_type;
+    workflow.steps = request.steps;
+    workflow.intent = request.intent;
+    workflow.trigger = match (request.trigger, installed.as_ref().and_then(|w| w.trigger.as_ref())) {
+        (Some(TriggerType::Webhook { .. }), Some(TriggerType::Webhook { token })) => {
+            Some(TriggerType::Webhook { token: token.clone() })
+        }
+        (Some(TriggerType::Webhook { .. }), _) => Some(TriggerType::Webhook { token: String::new() }),
+        (trigger, _) => trigger,
+    };
+    workflow.output_settings = request.output_settings;
+    workflow.behavior = request.behavior;
+    workflow.inputs = request.inputs;
+    workflow.variables = bundle.variables;
+    workflow.source = Some(source_id);
+    workflow.updated_at = chrono::Utc::now().timestamp();
+
+    let assets_dir = storage.assets_dir(&workflow.id);
+    let _ = std::fs::remove_dir_all(&assets_dir);
+    for asset in &bundle.assets {
+        let target = assets_dir.join(&asset.path);
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| ImportError::Io(e.to_string()))?;
+        }
+        std::fs::write(&target, &asset.content).map_err(|e| ImportError::Io(format!("{}: {}", asset.path.display(), e)))?;
+    }
+
+    Ok(storage.insert(workflow).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn write_bundle(dir: &Path, bundle: Value) {
+        std::fs::write(dir.join(BUNDLE_FILE), serde_json::to_vec(&bundle).unwrap()).unwrap();
+    }
+
+    fn definition(prompt: &str) -> Value {
+        json!({
+            "name": "Release notes",
+            "description": "Drafts release notes",
+            "workflow_type": "hook",
+            "trigger": {"webhook": {"token": "published-token"}},
+            "steps": [
+                {"id": "collect", "name": "Collect", "prompt": prompt, "order": 0, "next_step": "post"},
+                {"id": "post", "name": "Post", "prompt": "", "order": 1,
+                 "http": {"url": "https://hooks.slack.com/services/x", "body": "{{notes}}"}}
+            ],
+            "assets": ["templates/notes.md"]
+        })
+    }
+
+    #[test]
+    fn test_load_and_validate_bundle() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("templates")).unwrap();
+        std::fs::write(dir.path().join("templates/notes.md"), "# Notes").unwrap();
+        write_bundle(dir.path(), definition("Summarize commits"));
+
+        let bundle = load_bundle_dir(dir.path(), Path::new(BUNDLE_FILE)).unwrap();
+        assert_eq!(bundle.assets[0].path, PathBuf::from("templates/notes.md"));
+        assert_eq!(bundle.assets[0].content, b"# Notes");
+        assert_eq!(bundle.digest.len(), 64);
+
+        let summary = permissions(&bundle.workflow);
+        assert_eq!(summary.tools, ["http", "prompt"]);
+        assert_eq!(summary.hosts, ["hooks.slack.com"]);
+        assert_eq!(summary.trigger.as_deref(), Some("by requests to its webhook URL"));
+
+        let mut escaping = definition("x");
+        escaping["assets"] = json!(["../secret"]);
+        write_bundle(dir.path(), escaping);
+        assert!(matches!(load_bundle_dir(dir.path(), Path::new(BUNDLE_FILE)), Err(ImportError::UnsafePath(_))));
+
+        let mut broken = definition("x");
+        broken["steps"][0]["next_step"] = json!("missing");
+        write_bundle(dir.path(), broken);
+        let error = load_bundle_dir(dir.path(), Path::new(BUNDLE_FILE)).unwrap_err();
+        assert!(error.to_string().contains("unknown step 'missing'"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn test_install_and_update() {
+        let repo = tempfile::tempdir().unwrap();
+        let store = tempfile::tempdir().unwrap();
+        let storage = WorkflowStorage::with_path(store.path().to_path_buf());
+        let source = ImportSource {
+            url: "https://example.com/release-notes.git".to_string(),
+            kind: None,
+            path: None,
+            reference: None,
+        };
+        assert_eq!(source.kind(), SourceKind::Git);
+
+        std::fs::create_dir(repo.path().join("templates")).unwrap();
+        std::fs::write(repo.path().join("templates/notes.md"), "# Notes").unwrap();
+        write_bundle(repo.path(), definition("Summarize commits"));
+        let bundle = load_bundle_dir(repo.path(), Path::new(BUNDLE_FILE)).unwrap();
+
+        let first = preview(&storage, &source, &bundle).await;
+        assert!(first.installed_id.is_none() && first.changes.is_empty());
+        let installed = install(&storage, &source, bundle).await.unwrap();
+        assert_eq!(installed.category, COMMUNITY_CATEGORY);
+        let token = installed.trigger.as_ref().and_then(|t| t.webhook_token()).unwrap().to_string();
+        assert_ne!(token, "published-token");
+        assert!(storage.assets_dir(&installed.id).join("templates/notes.md").exists());
+
+        write_bundle(repo.path(), definition("Summarize merged pull requests"));
+        let bundle = load_bundle_dir(repo.path(), Path::new(BUNDLE_FILE)).unwrap();
+        let second = preview(&storage, &source, &bundle).await;
+        assert_eq!(second.installed_id.as_deref(), Some(installed.id.as_str()));
+        assert_eq!(second.changes, [WorkflowChange::StepChanged { id: "collect".to_string() }]);
+
+        let updated = install(&storage, &source, bundle).await.unwrap();
+        assert_eq!(updated.id, installed.id);
+        assert_eq!(updated.trigger.as_ref().and_then(|t| t.webhook_token()), Some(token.as_str()));
+        assert_eq!(storage.list().await.len(), 1);
+    }
+}
@@ -12,8 +12,19 @@ pub mod types;
 pub mod engine;
 pub mod storage;
 pub mod triggers;
+pub mod runs;
+pub mod expression;
+pub mod template;
+pub mod webhook;
+pub mod scaffolding;
+pub mod tool_step;
+pub mod artifacts;
+pub mod validation;
+pub mod import;
 
 pub use types::*;
 pub use engine::WorkflowEngine;
 pub use storage::WorkflowStorage;
 pub use triggers::TriggerManager;
+pub use runs::{ApprovalRequest, StepAttempt, StepOutcome, WorkflowRun};
+pub use artifacts::{ArtifactSource, RunArtifact};
@@ -0,0 +1,527 @@
+//! Workflow run records
+//!
+//! A `WorkflowRun` tracks one execution of a workflow: the step being
+//! executed, every attempt with its outcome, step outputs and variables.
+//! Steps are executed by the client, which reports each outcome back; the run
+//! decides whether to advance, retry after a backoff delay, or fail. Steps
+//! with `requires_confirmation` pause the run until a user approves them.
+
+use super::artifacts::{ArtifactSource, RunArtifact};
+use super::expression::{resolve_decision, EvalContext};
+use super::template::render_step_prompt;
+use super::types::*;
+use crate::context::ContextId;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Persistent record of a single workflow execution
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkflowRun {
+    pub id: String,
+    pub workflow_id: String,
+    /// Window that started the run
+    #[serde(default)]
+    pub context_id: ContextId,
+    pub status: WorkflowStatus,
+    pub current_step_id: Option<String>,
+    pub variables: HashMap<String, serde_json::Value>,
+    /// Latest successful result per step
+    pub step_outputs: HashMap<String, StepResult>,
+    /// Every attempt in execution order
+    pub attempts: Vec<StepAttempt>,
+    /// Number of attempts recorded before the last resume; earlier failures
+    /// don't count against the retry budget
+    #[serde(default)]
+    pub resume_offset: usize,
+    /// Step that exhausted its retries
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failed_step_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Earliest time (unix ms) the current step may be retried
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_retry_at_ms: Option<i64>,
+    /// Prompt of the current step with placeholders filled in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub current_prompt: Option<String>,
+    pub started_at: i64,
+    pub updated_at: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub completed_at: Option<i64>,
+    /// Files registered by the run's steps, oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub artifacts: Vec<RunArtifact>,
+    /// Decision the run is paused for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pending_approval: Option<ApprovalRequest>,
+    /// Step the user approved; it runs without asking again until it succeeds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approved_step_id: Option<String>,
+}
+
+/// What a paused run asks the user to approve
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ApprovalRequest {
+    pub step_id: String,
+    pub step_name: String,
+    /// Prompt of the step with placeholders filled in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt: Option<String>,
+    /// Hint for the input the user may give along with the approval
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input_placeholder: Option<String>,
+    /// Latest output of each step that ran so far
+    #[serde(default)]
+    pub step_outputs: HashMap<String, String>,
+    pub requested_at: i64,
+}
+
+/// One attempt at executing a step
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StepAttempt {
+    pub step_id: String,
+    pub attempt: u32,
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub duration_ms: u64,
+    pub finished_at: i64,
+}
+
+/// Outcome of a step reported by the client that executed it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StepOutcome {
+    pub step_id: String,
+    pub success: bool,
+    #[serde(default)]
+    pub output: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(default)]
+    pub duration_ms: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub decision_result: Option<bool>,
+    /// Files the step produced, kept as artifacts of the run
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub artifacts: Vec<ArtifactSource>,
+}
+
+impl WorkflowRun {
+    pub fn new(
+        workflow: &Workflow,
+        variables: HashMap<String, serde_json::Value>,
+        start_step_id: Option<String>,
+    ) -> Self {
+        let now = chrono::Utc::now().timestamp();
+        let current_step_id = start_step_id.or_else(|| workflow.steps.first().map(|s| s.id.clone()));
+        let status = if current_step_id.is_some() {
+            WorkflowStatus::Running
+        } else {
+            WorkflowStatus::Completed
+        };
+
+        let mut run = Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            workflow_id: workflow.id.clone(),
+            context_id: ContextId::default(),
+            status,
+            current_step_id,
+            variables,
+            step_outputs: HashMap::new(),
+            attempts: Vec::new(),
+            resume_offset: 0,
+            failed_step_id: None,
+            error: None,
+            next_retry_at_ms: None,
+            current_prompt: None,
+            started_at: now,
+            updated_at: now,
+            completed_at: if status == WorkflowStatus::Completed { Some(now) } else { None },
+            artifacts: Vec::new(),
+            pending_approval: None,
+            approved_step_id: None,
+        };
+        run.refresh_prompt(workflow);
+        run
+    }
+
+    /// Render the current step's prompt from the run's variables and outputs
+    pub fn refresh_prompt(&mut self, workflow: &Workflow) {
+        self.current_prompt = render_step_prompt(
+            workflow,
+            self.current_step_id.as_deref(),
+            &self.variables,
+            &self.step_outputs,
+        );
+    }
+
+    /// Whether the run reached a final state
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self.status,
+            WorkflowStatus::Completed | WorkflowStatus::Failed | WorkflowStatus::Cancelled
+        )
+    }
+
+    /// Consecutive failed attempts of a step since it last succeeded or was resumed
+    pub fn failed_attempts(&self, step_id: &str) -> u32 {
+        self.attempts[self.resume_offset.min(self.attempts.len())..]
+            .iter()
+            .rev()
+            .take_while(|a| a.step_id == step_id && !a.success)
+            .count() as u32
+    }
+
+    /// Apply a step outcome: advance on success, schedule a retry or fail the run
+    pub fn record_outcome(&mut self, workflow: &Workflow, outcome: StepOutcome) -> Result<(), String> {
+        if self.status != WorkflowStatus::Running {
+            return Err(format!("Run {} is not running", self.id));
+        }
+        if self.current_step_id.as_deref() != Some(outcome.step_id.as_str()) {
+            return Err(format!(
+                "Step {} is not the current step of run {}",
+                outcome.step_id, self.id
+            ));
+        }
+
+        let now = chrono::Utc::now();
+        if let Some(retry_at) = self.next_retry_at_ms {
+            if now.timestamp_millis() < retry_at {
+                return Err(format!(
+                    "Step {} cannot be retried for another {}ms",
+                    outcome.step_id,
+                    retry_at - now.timestamp_millis()
+                ));
+            }
+        }
+
+        let step = workflow
+            .steps
+            .iter()
+            .find(|s| s.id == outcome.step_id)
+            .ok_or_else(|| format!("Step {} not found", outcome.step_id))?;
+
+        let attempt = self.failed_attempts(&step.id) + 1;
+        self.attempts.push(StepAttempt {
+            step_id: step.id.clone(),
+            attempt,
+            success: outcome.success,
+            error: outcome.error.clone(),
+            duration_ms: outcome.duration_ms,
+            finished_at: now.timestamp(),
+        });
+        self.updated_at = now.timestamp();
+        self.next_retry_at_ms = None;
+
+        if outcome.success {
+            if let Some(var) = &step.output_var {
+                self.variables
+                    .insert(var.clone(), serde_json::Value::String(outcome.output.clone()));
+            }
+            self.step_outputs.insert(
+                step.id.clone(),
+                StepResult {
+                    step_id: step.id.clone(),
+                    success: true,
+                    output: outcome.output,
+                    error: None,
+                    duration_ms: outcome.duration_ms,
+                    decision_result: outcome.decision_result,
+                },
+            );
+            self.error = None;
+
+            let decision_result = resolve_decision(
+                step.decision.as_ref(),
+                &EvalContext {
+                    variables: &self.variables,
+                    steps: &self.step_outputs,
+                    current_step_id: Some(&step.id),
+                },
+                outcome.decision_result,
+            );
+            let decision_result = match decision_result {
+                Ok(result) => result,
+                Err(e) => {
+                    // A broken expression can't be fixed by retrying; fail so the
+                    // workflow can be corrected and the run resumed
+                    self.status = WorkflowStatus::Failed;
+                    self.failed_step_id = Some(step.id.clone());
+                    self.error = Some(e);
+                    self.completed_at = Some(now.timestamp());
+                    return Ok(());
+                }
+            };
+            if let Some(result) = self.step_outputs.get_mut(&step.id) {
+                result.decision_result = decision_result;
+            }
+
+            self.approved_step_id = None;
+            self.current_step_id = step.next_step_id(decision_result);
+            if self.current_step_id.is_none() {
+                self.status = WorkflowStatus::Completed;
+                self.completed_at = Some(now.timestamp());
+            }
+            return Ok(());
+        }
+
+        let policy = effective_retry_policy(workflow, step);
+        self.error = outcome.error;
+        if attempt < policy.max_attempts {
+            self.next_retry_at_ms = Some(now.timestamp_millis() + policy.backoff_ms(attempt) as i64);
+        } else {
+            self.status = WorkflowStatus::Failed;
+            self.failed_step_id = Some(step.id.clone());
+            self.completed_at = Some(now.timestamp());
+        }
+        Ok(())
+    }
+
+    /// Restart a failed or cancelled run from the step where it stopped
+    pub fn resume(&mut self) -> Result<(), String> {
+        if !matches!(self.status, WorkflowStatus::Failed | WorkflowStatus::Cancelled) {
+            return Err(format!("Run {} cannot be resumed while {:?}", self.id, self.status));
+        }
+
+        let step_id = self
+            .failed_step_id
+            .take()
+            .or_else(|| self.current_step_id.clone())
+            .ok_or_else(|| format!("Run {} has no step to resume from", self.id))?;
+
+        self.current_step_id = Some(step_id);
+        self.resume_offset = self.attempts.len();
+        self.status = WorkflowStatus::Running;
+        self.error = None;
+        self.next_retry_at_ms = None;
+        self.completed_at = None;
+        self.updated_at = chrono::Utc::now().timestamp();
+        Ok(())
+    }
+
+    /// Pause before the current step if it needs confirmation the user
+    /// hasn't given yet; returns the new approval request
+    pub fn request_approval(&mut self, workflow: &Workflow) -> Option<&ApprovalRequest> {
+        if self.status != WorkflowStatus::Running {
+            return None;
+        }
+        let step = workflow
+            .steps
+            .iter()
+            .find(|s| Some(&s.id) == self.current_step_id.as_ref())
+            .filter(|s| s.requires_confirmation)?;
+        if self.approved_step_id.as_ref() == Some(&step.id) {
+            return None;
+        }
+
+        let now = chrono::Utc::now().timestamp();
+        self.status = WorkflowStatus::Paused;
+        self.updated_at = now;
+        self.pending_approval = Some(ApprovalRequest {
+            step_id: step.id.clone(),
+            step_name: step.name.clone(),
+            prompt: self.current_prompt.clone(),
+            input_placeholder: step
+                .input_request
+                .as_ref()
+                .filter(|r| r.enabled)
+                .and_then(|r| r.placeholder.clone()),
+            step_outputs: self
+                .step_outputs
+                .iter()
+                .map(|(id, result)| (id.clone(), result.output.clone()))
+                .collect(),
+            requested_at: now,
+        });
+        self.pending_approval.as_ref()
+    }
+
+    /// Continue a paused run with the step it waits on, merging the user's
+    /// input into the run variables
+    pub fn approve(&mut self, input: HashMap<String, serde_json::Value>) -> Result<(), String> {
+        let approval = match (self.status, self.pending_approval.take()) {
+            (WorkflowStatus::Paused, Some(approval)) => approval,
+            (_, pending) => {
+                self.pending_approval = pending;
+                return Err(format!("Run {} is not waiting for approval", self.id));
+            }
+        };
+        self.variables.extend(input);
+        self.approved_step_id = Some(approval.step_id);
+        self.status = WorkflowStatus::Running;
+        self.updated_at = chrono::Utc::now().timestamp();
+        Ok(())
+    }
+
+    /// Stop the run; it can be resumed later
+    pub fn cancel(&mut self) -> Result<(), String> {
+        if self.is_finished() {
+            return Err(format!("Run {} already finished", self.id));
+        }
+        let now = chrono::Utc::now().timestamp();
+        self.status = WorkflowStatus::Cancelled;
+        self.pending_approval = None;
+        self.next_retry_at_ms = None;
+        self.completed_at = Some(now);
+        self.updated_at = now;
+        Ok(())
+    }
+}
+
+/// The step's own policy, or one derived from the workflow's auto-retry behavior
+pub fn effective_retry_policy(workflow: &Workflow, step: &WorkflowStep) -> RetryPolicy {
+    match &step.retry {
+        Some(policy) => policy.clone(),
+        None if workflow.behavior.auto_retry => RetryPolicy {
+            max_attempts: workflow.behavior.max_retries + 1,
+            ..Default::default()
+        },
+        None => RetryPolicy::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn workflow_with_steps(ids: &[&str]) -> Workflow {
+        let mut workflow = Workflow::new("test".to_string(), WorkflowType::Process);
+        workflow.steps = ids
+            .iter()
+            .enumerate()
+            .map(|(i, id)| WorkflowStep {
+                id: id.to_string(),
+                name: id.to_string(),
+                next_step: ids.get(i + 1).map(|s| s.to_string()),
+                ..Default::default()
+            })
+            .collect();
+        workflow
+    }
+
+    fn outcome(step_id: &str, success: bool) -> StepOutcome {
+        StepOutcome {
+            step_id: step_id.to_string(),
+            success,
+            output: "out".to_string(),
+            error: if success { None } else { Some("boom".to_string()) },
+            duration_ms: 5,
+            decision_result: None,
+            artifacts: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_backoff_grows_exponentially_and_caps() {
+        let policy = RetryPolicy {
+            max_attempts: 5,
+            initial_backoff_ms: 100,
+            backoff_multiplier: 2.0,
+            max_backoff_ms: 300,
+        };
+        assert_eq!(policy.backoff_ms(1), 100);
+        assert_eq!(policy.backoff_ms(2), 200);
+        assert_eq!(policy.backoff_ms(3), 300);
+    }
+
+    #[test]
+    fn test_run_advances_and_completes() {
+        let mut workflow = workflow_with_steps(&["a", "b"]);
+        workflow.steps[0].output_var = Some("summary".to_string());
+        let mut run = WorkflowRun::new(&workflow, HashMap::new(), None);
+
+        run.record_outcome(&workflow, outcome("a", true)).unwrap();
+        assert_eq!(run.current_step_id.as_deref(), Some("b"));
+        assert_eq!(run.variables["summary"], "out");
+
+        run.record_outcome(&workflow, outcome("b", true)).unwrap();
+        assert_eq!(run.status, WorkflowStatus::Completed);
+    }
+
+    #[test]
+    fn test_failed_step_retries_then_fails_and_resumes() {
+        let mut workflow = workflow_with_steps(&["a", "b"]);
+        workflow.steps[1].retry = Some(RetryPolicy {
+            max_attempts: 2,
+            initial_backoff_ms: 0,
+            ..Default::default()
+        });
+        let mut run = WorkflowRun::new(&workflow, HashMap::new(), None);
+        run.record_outcome(&workflow, outcome("a", true)).unwrap();
+
+        run.record_outcome(&workflow, outcome("b", false)).unwrap();
+        assert_eq!(run.status, WorkflowStatus::Running);
+        assert!(run.next_retry_at_ms.is_some());
+
+        run.record_outcome(&workflow, outcome("b", false)).unwrap();
+        assert_eq!(run.status, WorkflowStatus::Failed);
+        assert_eq!(run.failed_step_id.as_deref(), Some("b"));
+
+        run.resume().unwrap();
+        assert_eq!(run.current_step_id.as_deref(), Some("b"));
+        assert_eq!(run.failed_attempts("b"), 0);
+        run.record_outcome(&workflow, outcome("b", true)).unwrap();
+        assert_eq!(run.status, WorkflowStatus::Completed);
+        assert!(run.step_outputs.contains_key("a"));
+    }
+
+    #[test]
+    fn test_confirmation_step_pauses_until_approved() {
+        let mut workflow = workflow_with_steps(&["draft", "publish"]);
+        workflow.steps[1].requires_confirmation = true;
+        let mut run = WorkflowRun::new(&workflow, HashMap::new(), None);
+        assert!(run.request_approval(&workflow).is_none());
+        run.record_outcome(&workflow, outcome("draft", true)).unwrap();
+
+        let approval = run.request_approval(&workflow).unwrap();
+        assert_eq!(approval.step_id, "publish");
+        assert_eq!(approval.step_outputs["draft"], "out");
+        assert_eq!(run.status, WorkflowStatus::Paused);
+        assert!(run.record_outcome(&workflow, outcome("publish", true)).is_err());
+
+        run.approve(HashMap::from([("channel".to_string(), serde_json::json!("blog"))])).unwrap();
+        assert!(run.approve(HashMap::new()).is_err());
+        assert_eq!(run.status, WorkflowStatus::Running);
+        assert_eq!(run.variables["channel"], "blog");
+        assert!(run.request_approval(&workflow).is_none());
+
+        run.record_outcome(&workflow, outcome("publish", true)).unwrap();
+        assert_eq!(run.status, WorkflowStatus::Completed);
+        assert!(run.approved_step_id.is_none());
+    }
+
+    #[test]
+    fn test_expression_decision_branches_without_ai() {
+        let mut workflow = workflow_with_steps(&["check", "deploy", "report"]);
+        workflow.steps[0].decision = Some(DecisionNode {
+            id: "d1".to_string(),
+            condition: "output contains 'PASS'".to_string(),
+            true_branch: Some("deploy".to_string()),
+            false_branch: Some("report".to_string()),
+            expression: true,
+        });
+        let mut run = WorkflowRun::new(&workflow, HashMap::new(), None);
+
+        let mut result = outcome("check", true);
+        result.output = "tests FAILED".to_string();
+        result.decision_result = Some(true); // ignored for expression decisions
+        run.record_outcome(&workflow, result).unwrap();
+
+        assert_eq!(run.current_step_id.as_deref(), Some("report"));
+        assert_eq!(run.step_outputs["check"].decision_result, Some(false));
+    }
+
+    #[test]
+    fn test_retry_before_backoff_is_rejected() {
+        let mut workflow = workflow_with_steps(&["a"]);
+        workflow.steps[0].retry = Some(RetryPolicy {
+            max_attempts: 3,
+            initial_backoff_ms: 60_000,
+            ..Default::default()
+        });
+        let mut run = WorkflowRun::new(&workflow, HashMap::new(), None);
+
+        run.record_outcome(&workflow, outcome("a", false)).unwrap();
+        assert!(run.record_outcome(&workflow, outcome("a", true)).is_err());
+    }
+}
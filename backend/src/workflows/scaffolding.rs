@@ -0,0 +1,123 @@
+//! Scaffold steps
+//!
+//! Steps with a `scaffold` config create a project from a template of the
+//! [`TemplateLibrary`] instead of prompting the AI. The step output lists
+//! the created files, so later steps can work on the new project.
+
+use super::expression::EvalContext;
+use super::runs::StepOutcome;
+use super::template::render;
+use super::types::*;
+use crate::scaffold::{self, TemplateLibrary};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::time::Instant;
+
+/// Fill placeholders in the destination and variable values
+pub fn render_step(scaffold: &ScaffoldStep, ctx: &EvalContext) -> ScaffoldStep {
+    ScaffoldStep {
+        template: scaffold.template.clone(),
+        destination: render(&scaffold.destination, ctx),
+        variables: scaffold
+            .variables
+            .iter()
+            .map(|(k, v)| (k.clone(), render(v, ctx)))
+            .collect(),
+        overwrite: scaffold.overwrite,
+    }
+}
+
+/// Create the project of a rendered scaffold config for `step`
+pub async fn run(step: &WorkflowStep, scaffold: &ScaffoldStep) -> StepOutcome {
+    let started = Instant::now();
+    let scaffold = scaffold.clone();
+    let result = tokio::task::spawn_blocking(move || create(&scaffold))
+        .await
+        .unwrap_or_else(|e| Err(format!("Scaffold task failed: {}", e)));
+
+    let (success, output, error) = match result {
+        Ok(output) => (true, output, None),
+        Err(e) => (false, String::new(), Some(e)),
+    };
+
+    StepOutcome {
+        step_id: step.id.clone(),
+        success,
+        output,
+        error,
+        duration_ms: started.elapsed().as_millis() as u64,
+        decision_result: None,
+        artifacts: Vec::new(),
+    }
+}
+
+fn create(scaffold: &ScaffoldStep) -> Result<String, String> {
+    let destination = destination_path(&scaffold.destination)?;
+    let template = TemplateLibrary::global().get(&scaffold.template).map_err(|e| e.to_string())?;
+    let variables: BTreeMap<String, String> = scaffold
+        .variables
+        .iter()
+        .map(|(k, v)| (k.clone(), v.clone()))
+        .collect();
+    let outcome = scaffold::create(&template, &destination, &variables, scaffold.overwrite).map_err(|e| e.to_string())?;
+    serde_json::to_string_pretty(&outcome).map_err(|e| e.to_string())
+}
+
+/// Absolute destination, with `~` expanded; workflows have no working
+/// directory to resolve relative paths against
+fn destination_path(destination: &str) -> Result<PathBuf, String> {
+    let path = match destination.strip_prefix("~/").or_else(|| (destination == "~").then_some("")) {
+        Some(rest) => dirs::home_dir()
+            .ok_or_else(|| "No home directory to expand ~ against".to_string())?
+            .join(rest),
+        None => PathBuf::from(destination),
+    };
+    if !path.is_absolute() {
+        return Err(format!("Scaffold destination must be an absolute path: {}", destination));
+    }
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+    use std::collections::HashMap;
+
+    #[tokio::test]
+    async fn test_scaffold_step_renders_and_creates() {
+        let dir = tempfile::tempdir().unwrap();
+        let variables = HashMap::from([
+            ("project".to_string(), Value::String("demo".to_string())),
+            ("root".to_string(), Value::String(dir.path().display().to_string())),
+        ]);
+        let steps = HashMap::new();
+        let ctx = EvalContext {
+            variables: &variables,
+            steps: &steps,
+            current_step_id: None,
+        };
+        let config = ScaffoldStep {
+            template: "node-app".to_string(),
+            destination: "{{root}}/{{project}}".to_string(),
+            variables: HashMap::from([("name".to_string(), "{{project}}".to_string())]),
+            overwrite: false,
+        };
+
+        let rendered = render_step(&config, &ctx);
+        assert_eq!(rendered.destination, format!("{}/demo", dir.path().display()));
+
+        let step = WorkflowStep {
+            id: "s1".to_string(),
+            scaffold: Some(config),
+            ..Default::default()
+        };
+        let outcome = run(&step, &rendered).await;
+        assert!(outcome.success, "{:?}", outcome.error);
+        assert!(outcome.output.contains("package.json"));
+        assert!(dir.path().join("demo").join("index.js").is_file());
+
+        let relative = ScaffoldStep { destination: "demo".to_string(), ..rendered };
+        assert!(!run(&step, &relative).await.success);
+    }
+}
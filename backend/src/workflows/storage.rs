@@ -3,6 +3,7 @@
 //! Handles workflow CRUD operations and persistence.
 
 use super::types::*;
+use super::webhook::assign_token;
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
@@ -16,8 +17,11 @@ pub struct WorkflowStorage {
 impl WorkflowStorage {
     pub fn new() -> Self {
         let home = dirs::home_dir().unwrap_or_else(|| std::path::PathBuf::from("."));
-        let storage_path = home.join(".skhoot").join("workflows");
-        
+        Self::with_path(home.join(".skhoot").join("workflows"))
+    }
+
+    /// Storage kept in `storage_path` instead of `~/.skhoot/workflows`
+    pub fn with_path(storage_path: std::path::PathBuf) -> Self {
         if !storage_path.exists() {
             let _ = std::fs::create_dir_all(&storage_path);
         }
@@ -98,7 +102,8 @@ impl WorkflowStorage {
     }
 
     /// Create a new workflow
-    pub async fn create(&self, request: CreateWorkflowRequest) -> Workflow {
+    pub async fn create(&self, mut request: CreateWorkflowRequest) -> Workflow {
+        assign_token(&mut request.trigger);
         let now = chrono::Utc::now().timestamp();
         let workflow = Workflow {
             id: uuid::Uuid::new_v4().to_string(),
@@ -117,6 +122,8 @@ impl WorkflowStorage {
             last_run: None,
             status: WorkflowStatus::Idle,
             variables: HashMap::new(),
+            inputs: request.inputs,
+            source: None,
         };
 
         self.workflows.write().await.insert(workflow.id.clone(), workflow.clone());
@@ -129,6 +136,7 @@ impl WorkflowStorage {
         let mut workflows = self.workflows.write().await;
         if workflows.contains_key(id) {
             let mut updated = workflow;
+            assign_token(&mut updated.trigger);
             updated.updated_at = chrono::Utc::now().timestamp();
             workflows.insert(id.to_string(), updated.clone());
             let _ = self.save_to_file(&updated);
@@ -138,16 +146,38 @@ impl WorkflowStorage {
         }
     }
 
+    /// Add or replace a complete workflow, e.g. one installed from a bundle
+    pub async fn insert(&self, mut workflow: Workflow) -> Workflow {
+        assign_token(&mut workflow.trigger);
+        self.workflows.write().await.insert(workflow.id.clone(), workflow.clone());
+        let _ = self.save_to_file(&workflow);
+        workflow
+    }
+
     /// Delete a workflow
     pub async fn delete(&self, id: &str) -> bool {
         let deleted = self.workflows.write().await.remove(id).is_some();
         if deleted {
             let file_path = self.storage_path.join(format!("{}.json", id));
             let _ = std::fs::remove_file(file_path);
+            let _ = std::fs::remove_dir_all(self.assets_dir(id));
         }
         deleted
     }
 
+    /// Directory holding the asset files of an imported workflow
+    pub fn assets_dir(&self, id: &str) -> std::path::PathBuf {
+        self.storage_path.join("assets").join(id)
+    }
+
+    /// Find the workflow installed from `source`
+    pub async fn find_by_source(&self, source: &str) -> Option<Workflow> {
+        self.workflows.read().await
+            .values()
+            .find(|w| w.source.as_deref() == Some(source))
+            .cloned()
+    }
+
     /// Update workflow status
     pub async fn update_status(&self, id: &str, status: WorkflowStatus) {
         let mut workflows = self.workflows.write().await;
@@ -169,6 +199,14 @@ impl WorkflowStorage {
         }
     }
 
+    /// Find the workflow whose webhook trigger uses `token`
+    pub async fn find_by_webhook_token(&self, token: &str) -> Option<Workflow> {
+        self.workflows.read().await
+            .values()
+            .find(|w| w.trigger.as_ref().and_then(|t| t.webhook_token()) == Some(token))
+            .cloned()
+    }
+
     /// Get workflows that can be used as tool calls
     pub async fn get_toolcall_workflows(&self) -> Vec<Workflow> {
         self.workflows.read().await
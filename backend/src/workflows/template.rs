@@ -0,0 +1,182 @@
+//! Prompt templating for workflow steps
+//!
+//! Step prompts may reference `{{name}}` placeholders. Values come from the
+//! run's variables (workflow defaults, user inputs, `output_var` results),
+//! the trigger payload (`{{trigger.field}}`) and previous step results
+//! (`{{steps.<step_id>.output}}`). Placeholders that resolve to nothing are
+//! left untouched so missing data stays visible in the prompt.
+
+use super::expression::{as_text, resolve, EvalContext};
+use super::types::*;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Variable under which the trigger payload is exposed
+pub const TRIGGER_VAR: &str = "trigger";
+
+/// Replace `{{...}}` placeholders in `template`
+pub fn render(template: &str, ctx: &EvalContext) -> String {
+    render_escaped(template, ctx, str::to_string)
+}
+
+/// Like [`render`], passing each substituted value through `escape`
+pub fn render_escaped(template: &str, ctx: &EvalContext, escape: impl Fn(&str) -> String) -> String {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        output.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            output.push_str(&rest[start..]);
+            return output;
+        };
+
+        let name = after[..end].trim();
+        match resolve(name, ctx) {
+            Value::Null => output.push_str(&rest[start..start + 2 + end + 2]),
+            value => output.push_str(&escape(&as_text(&value))),
+        }
+        rest = &after[end + 2..];
+    }
+
+    output.push_str(rest);
+    output
+}
+
+/// Names referenced by the `{{...}}` placeholders of `template`, in order
+pub fn placeholders(template: &str) -> Vec<&str> {
+    let mut names = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            break;
+        };
+        names.push(after[..end].trim());
+        rest = &after[end + 2..];
+    }
+    names
+}
+
+/// Render the prompt of a step against a run's state
+pub fn render_step_prompt(
+    workflow: &Workflow,
+    step_id: Option<&str>,
+    variables: &HashMap<String, Value>,
+    steps: &HashMap<String, StepResult>,
+) -> Option<String> {
+    let step = workflow.steps.iter().find(|s| Some(s.id.as_str()) == step_id)?;
+    Some(render(
+        &step.prompt,
+        &EvalContext {
+            variables,
+            steps,
+            current_step_id: None,
+        },
+    ))
+}
+
+/// Build the initial variables of a run: workflow defaults, then declared
+/// input defaults, then user-provided values and the trigger payload.
+/// Fails if a required input has no value.
+pub fn prepare_variables(
+    workflow: &Workflow,
+    provided: HashMap<String, Value>,
+    trigger_payload: Option<Value>,
+) -> Result<HashMap<String, Value>, String> {
+    let mut variables: HashMap<String, Value> = workflow
+        .variables
+        .iter()
+        .map(|(k, v)| (k.clone(), Value::String(v.clone())))
+        .collect();
+
+    for input in &workflow.inputs {
+        if let Some(default) = &input.default {
+            variables.insert(input.name.clone(), default.clone());
+        }
+    }
+
+    variables.extend(provided);
+    if let Some(payload) = trigger_payload {
+        variables.insert(TRIGGER_VAR.to_string(), payload);
+    }
+
+    let missing: Vec<&str> = workflow
+        .inputs
+        .iter()
+        .filter(|input| input.required)
+        .filter(|input| match variables.get(&input.name) {
+            None | Some(Value::Null) => true,
+            Some(Value::String(s)) => s.trim().is_empty(),
+            _ => false,
+        })
+        .map(|input| input.name.as_str())
+        .collect();
+
+    if !missing.is_empty() {
+        return Err(format!("Missing required inputs: {}", missing.join(", ")));
+    }
+
+    Ok(variables)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_variables_steps_and_trigger() {
+        let variables = HashMap::from([
+            ("lang".to_string(), serde_json::json!("Rust")),
+            (TRIGGER_VAR.to_string(), serde_json::json!({"file_path": "src/main.rs"})),
+        ]);
+        let steps = HashMap::from([(
+            "s1".to_string(),
+            StepResult {
+                step_id: "s1".to_string(),
+                success: true,
+                output: "3 issues".to_string(),
+                error: None,
+                duration_ms: 0,
+                decision_result: None,
+            },
+        )]);
+        let ctx = EvalContext {
+            variables: &variables,
+            steps: &steps,
+            current_step_id: None,
+        };
+
+        assert_eq!(
+            render("Review {{ trigger.file_path }} ({{lang}}): {{steps.s1.output}}", &ctx),
+            "Review src/main.rs (Rust): 3 issues"
+        );
+        assert_eq!(render("Keep {{unknown}} and {{unclosed", &ctx), "Keep {{unknown}} and {{unclosed");
+    }
+
+    #[test]
+    fn test_prepare_variables_enforces_required_inputs() {
+        let mut workflow = Workflow::new("w".to_string(), WorkflowType::Manual);
+        workflow.inputs = vec![
+            WorkflowInput {
+                name: "topic".to_string(),
+                required: true,
+                ..Default::default()
+            },
+            WorkflowInput {
+                name: "tone".to_string(),
+                required: false,
+                default: Some(serde_json::json!("neutral")),
+                ..Default::default()
+            },
+        ];
+
+        assert!(prepare_variables(&workflow, HashMap::new(), None).is_err());
+
+        let provided = HashMap::from([("topic".to_string(), serde_json::json!("caching"))]);
+        let variables = prepare_variables(&workflow, provided, None).unwrap();
+        assert_eq!(variables["tone"], "neutral");
+        assert_eq!(variables["topic"], "caching");
+    }
+}
@@ -0,0 +1,248 @@
+//! Tool and script steps
+//!
+//! Steps whose [`StepAction`] is a tool call or a script are deterministic:
+//! the server invokes the agent tool (or runs the command through the
+//! `shell` tool) with the rendered arguments instead of asking the AI to do
+//! it. The tool output becomes the step output.
+
+use super::expression::{resolve, EvalContext};
+use super::runs::StepOutcome;
+use super::template::render;
+use super::types::*;
+use crate::cli_agent::{AgentExecutor, ExecutorConfig, Tool, ToolCall};
+use serde_json::Value;
+use std::path::PathBuf;
+use std::time::Instant;
+
+/// Timeout for tools and scripts when the step doesn't set one
+const DEFAULT_TIMEOUT_SECS: u64 = 60;
+
+/// Fill placeholders in tool arguments, the script command and its directory
+pub fn render_action(action: &StepAction, ctx: &EvalContext) -> StepAction {
+    match action {
+        StepAction::Prompt => StepAction::Prompt,
+        StepAction::ToolCall(call) => StepAction::ToolCall(ToolCallStep {
+            tool: call.tool.clone(),
+            arguments: render_value(&call.arguments, ctx),
+        }),
+        StepAction::Script(script) => StepAction::Script(ScriptStep {
+            command: render(&script.command, ctx),
+            working_directory: script.working_directory.as_ref().map(|d| render(d, ctx)),
+        }),
+    }
+}
+
+fn render_value(value: &Value, ctx: &EvalContext) -> Value {
+    match value {
+        Value::String(text) => match single_placeholder(text).map(|name| resolve(name, ctx)) {
+            Some(Value::Null) | None => Value::String(render(text, ctx)),
+            Some(resolved) => resolved,
+        },
+        Value::Array(items) => Value::Array(items.iter().map(|v| render_value(v, ctx)).collect()),
+        Value::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(k, v)| (k.clone(), render_value(v, ctx)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+/// Name referenced by a string that is nothing but one `{{name}}` placeholder
+fn single_placeholder(text: &str) -> Option<&str> {
+    let name = text.trim().strip_prefix("{{")?.strip_suffix("}}")?;
+    (!name.contains("{{") && !name.contains("}}")).then(|| name.trim())
+}
+
+/// Check a step action can be run: the tool exists and the script has a
+/// command
+pub fn validate(action: &StepAction) -> Result<(), String> {
+    match action {
+        StepAction::Prompt => Ok(()),
+        StepAction::ToolCall(call) => {
+            if !is_known_tool(&call.tool) {
+                return Err(format!("Unknown tool: {}", call.tool));
+            }
+            if !matches!(call.arguments, Value::Object(_) | Value::Null) {
+                return Err(format!("Arguments of tool {} must be an object", call.tool));
+            }
+            Ok(())
+        }
+        StepAction::Script(script) if script.command.trim().is_empty() => {
+            Err("Script command is empty".to_string())
+        }
+        StepAction::Script(_) => Ok(()),
+    }
+}
+
+fn is_known_tool(name: &str) -> bool {
+    Tool::all().iter().any(|tool| tool.name() == name)
+        || crate::mcp::is_mcp_tool(name)
+        || crate::plugins::is_plugin_tool(name)
+}
+
+/// Run a rendered tool call or script for `step`
+pub async fn run(step: &WorkflowStep, action: &StepAction) -> StepOutcome {
+    let started = Instant::now();
+    let result = perform(step, action).await;
+
+    let (success, output, error) = match result {
+        Ok(output) => (true, output, None),
+        Err((output, e)) => (false, output, Some(e)),
+    };
+
+    StepOutcome {
+        step_id: step.id.clone(),
+        success,
+        output,
+        error,
+        duration_ms: started.elapsed().as_millis() as u64,
+        decision_result: None,
+        artifacts: Vec::new(),
+    }
+}
+
+async fn perform(step: &WorkflowStep, action: &StepAction) -> Result<String, (String, String)> {
+    let timeout_ms = step.timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS) * 1000;
+    let call = match action {
+        StepAction::Prompt => return Err((String::new(), "Prompt steps are run by the AI".to_string())),
+        StepAction::ToolCall(call) => ToolCall {
+            id: call_id(step),
+            name: call.tool.clone(),
+            arguments: match &call.arguments {
+                Value::Null => Value::Object(Default::default()),
+                arguments => arguments.clone(),
+            },
+        },
+        StepAction::Script(script) => {
+            let mut arguments = serde_json::json!({
+                "command": script.command,
+                "timeout_ms": timeout_ms,
+            });
+            if let Some(directory) = &script.working_directory {
+                let directory = directory_path(directory).map_err(|e| (String::new(), e))?;
+                arguments["workdir"] = Value::String(directory.to_string_lossy().to_string());
+            }
+            ToolCall {
+                id: call_id(step),
+                name: Tool::Shell.name().to_string(),
+                arguments,
+            }
+        }
+    };
+
+    let executor = AgentExecutor::with_config(ExecutorConfig {
+        default_timeout_ms: timeout_ms,
+        working_directory: dirs::home_dir().unwrap_or_else(|| PathBuf::from(".")),
+        ..Default::default()
+    });
+    let result = executor.execute(&call).await;
+    if result.success {
+        Ok(result.output)
+    } else {
+        let error = result.error.unwrap_or_else(|| format!("Tool {} failed", call.name));
+        Err((result.output, error))
+    }
+}
+
+fn call_id(step: &WorkflowStep) -> String {
+    format!("workflow-{}-{}", step.id, uuid::Uuid::new_v4().simple())
+}
+
+/// Absolute working directory, with `~` expanded; workflows have no working
+/// directory of their own to resolve relative paths against
+fn directory_path(directory: &str) -> Result<PathBuf, String> {
+    let path = match directory.strip_prefix("~/").or_else(|| (directory == "~").then_some("")) {
+        Some(rest) => dirs::home_dir()
+            .ok_or_else(|| "No home directory to expand ~ against".to_string())?
+            .join(rest),
+        None => PathBuf::from(directory),
+    };
+    if !path.is_absolute() {
+        return Err(format!("Script working directory must be an absolute path: {}", directory));
+    }
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_render_action_keeps_placeholder_types() {
+        let variables = HashMap::from([
+            ("path".to_string(), serde_json::json!("/tmp/report.md")),
+            ("limit".to_string(), serde_json::json!(20)),
+        ]);
+        let steps = HashMap::new();
+        let ctx = EvalContext {
+            variables: &variables,
+            steps: &steps,
+            current_step_id: None,
+        };
+        let action = StepAction::ToolCall(ToolCallStep {
+            tool: "read_file".to_string(),
+            arguments: serde_json::json!({
+                "path": "{{path}}",
+                "limit": "{{ limit }}",
+                "note": "read {{path}} for {{missing}}",
+            }),
+        });
+
+        let StepAction::ToolCall(rendered) = render_action(&action, &ctx) else {
+            panic!("expected a tool call");
+        };
+        assert_eq!(rendered.arguments["path"], "/tmp/report.md");
+        assert_eq!(rendered.arguments["limit"], 20);
+        assert_eq!(rendered.arguments["note"], "read /tmp/report.md for {{missing}}");
+    }
+
+    #[test]
+    fn test_validate_and_serde() {
+        let step: WorkflowStep = serde_json::from_value(serde_json::json!({
+            "id": "s1", "name": "Write", "prompt": "", "order": 0,
+            "action": {"type": "tool_call", "tool": "write_file", "arguments": {"path": "/tmp/x"}},
+        }))
+        .unwrap();
+        assert!(validate(&step.action).is_ok());
+        assert!(serde_json::to_value(WorkflowStep::default()).unwrap().get("action").is_none());
+
+        let unknown = StepAction::ToolCall(ToolCallStep {
+            tool: "launch_rockets".to_string(),
+            arguments: Value::Null,
+        });
+        assert!(validate(&unknown).is_err());
+        let empty = StepAction::Script(ScriptStep {
+            command: " ".to_string(),
+            working_directory: None,
+        });
+        assert!(validate(&empty).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_tool_step_writes_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.txt");
+        let action = StepAction::ToolCall(ToolCallStep {
+            tool: "write_file".to_string(),
+            arguments: serde_json::json!({"path": path.display().to_string(), "content": "done"}),
+        });
+        let step = WorkflowStep {
+            id: "s1".to_string(),
+            action: action.clone(),
+            ..Default::default()
+        };
+
+        let outcome = run(&step, &action).await;
+        assert!(outcome.success, "{:?}", outcome.error);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "done");
+
+        let relative = StepAction::Script(ScriptStep {
+            command: "echo hi".to_string(),
+            working_directory: Some("build".to_string()),
+        });
+        assert!(!run(&step, &relative).await.success);
+    }
+}
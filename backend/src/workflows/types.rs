@@ -6,6 +6,8 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+use crate::context::ContextId;
+
 /// Workflow type determines how and when a workflow is triggered
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -62,6 +64,22 @@ pub enum TriggerType {
     OnAIDetection { intent_patterns: Vec<String> },
     /// Custom trigger with condition expression
     Custom { condition: String },
+    /// Triggered by an HTTP request to `/api/v1/hooks/:token`.
+    /// An empty token is replaced with a generated one when the workflow is saved.
+    Webhook {
+        #[serde(default)]
+        token: String,
+    },
+}
+
+impl TriggerType {
+    /// Token of a webhook trigger
+    pub fn webhook_token(&self) -> Option<&str> {
+        match self {
+            TriggerType::Webhook { token } if !token.is_empty() => Some(token),
+            _ => None,
+        }
+    }
 }
 
 /// Decision node in the workflow tree
@@ -71,6 +89,9 @@ pub struct DecisionNode {
     pub condition: String,
     pub true_branch: Option<String>,  // Step ID to go to if true
     pub false_branch: Option<String>, // Step ID to go to if false
+    /// Evaluate `condition` as a deterministic expression instead of asking the AI
+    #[serde(default)]
+    pub expression: bool,
 }
 
 /// A single step in a workflow
@@ -104,6 +125,151 @@ pub struct WorkflowStep {
     /// Interactive input request
     #[serde(skip_serializing_if = "Option::is_none")]
     pub input_request: Option<InputRequest>,
+    /// Retry policy for failed attempts (falls back to the workflow behavior)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry: Option<RetryPolicy>,
+    /// Outbound HTTP call executed by the server instead of an AI prompt
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub http: Option<HttpRequestStep>,
+    /// Project created from a template by the server instead of an AI prompt
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scaffold: Option<ScaffoldStep>,
+    /// Whether links back to this step are intended, e.g. a fix-and-recheck
+    /// loop; validation reports other cycles as errors
+    #[serde(default)]
+    pub allow_cycle: bool,
+    /// What the step does; anything but a prompt runs without the AI
+    #[serde(default, skip_serializing_if = "StepAction::is_prompt")]
+    pub action: StepAction,
+}
+
+/// Outbound HTTP request made by a workflow step.
+/// `url`, header values and `body` support `{{variable}}` placeholders.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HttpRequestStep {
+    #[serde(default = "default_http_method")]
+    pub method: String,
+    pub url: String,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+}
+
+fn default_http_method() -> String {
+    "POST".to_string()
+}
+
+/// Project scaffolding done by a workflow step.
+/// `destination` and variable values support `{{variable}}` placeholders.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ScaffoldStep {
+    /// Template name, as listed by the template library
+    pub template: String,
+    pub destination: String,
+    #[serde(default)]
+    pub variables: HashMap<String, String>,
+    #[serde(default)]
+    pub overwrite: bool,
+}
+
+/// Action of a workflow step. Tool arguments, the script command and its
+/// working directory support `{{variable}}` placeholders.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StepAction {
+    /// Send the step prompt to the AI
+    #[default]
+    Prompt,
+    /// Invoke a registered agent tool with fixed arguments
+    ToolCall(ToolCallStep),
+    /// Run a shell command
+    Script(ScriptStep),
+}
+
+impl StepAction {
+    pub fn is_prompt(&self) -> bool {
+        matches!(self, StepAction::Prompt)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ToolCallStep {
+    /// Tool name, as listed by the tool registry (or an MCP/plugin tool)
+    pub tool: String,
+    /// Tool arguments; a string that is a single placeholder is replaced by
+    /// the variable's value as is, keeping numbers and objects intact
+    #[serde(default)]
+    pub arguments: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ScriptStep {
+    pub command: String,
+    /// Absolute directory to run in (the home directory if unset)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub working_directory: Option<String>,
+}
+
+impl WorkflowStep {
+    /// Next step ID given the outcome of this step's decision node
+    pub fn next_step_id(&self, decision_result: Option<bool>) -> Option<String> {
+        match (&self.decision, decision_result) {
+            (Some(decision), Some(true)) => decision.true_branch.clone(),
+            (Some(decision), Some(false)) => decision.false_branch.clone(),
+            _ => self.next_step.clone(),
+        }
+    }
+}
+
+/// Retry policy for a workflow step with exponential backoff
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RetryPolicy {
+    /// Total attempts including the first one
+    #[serde(default = "default_max_attempts")]
+    pub max_attempts: u32,
+    /// Delay before the first retry
+    #[serde(default = "default_initial_backoff_ms")]
+    pub initial_backoff_ms: u64,
+    /// Factor applied to the delay after each failed retry
+    #[serde(default = "default_backoff_multiplier")]
+    pub backoff_multiplier: f64,
+    /// Upper bound for the delay
+    #[serde(default = "default_max_backoff_ms")]
+    pub max_backoff_ms: u64,
+}
+
+fn default_max_attempts() -> u32 {
+    1
+}
+fn default_initial_backoff_ms() -> u64 {
+    1000
+}
+fn default_backoff_multiplier() -> f64 {
+    2.0
+}
+fn default_max_backoff_ms() -> u64 {
+    60_000
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: default_max_attempts(),
+            initial_backoff_ms: default_initial_backoff_ms(),
+            backoff_multiplier: default_backoff_multiplier(),
+            max_backoff_ms: default_max_backoff_ms(),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay before retrying after `failed_attempts` consecutive failures
+    pub fn backoff_ms(&self, failed_attempts: u32) -> u64 {
+        let exponent = failed_attempts.saturating_sub(1) as i32;
+        let delay = self.initial_backoff_ms as f64 * self.backoff_multiplier.max(1.0).powi(exponent);
+        (delay as u64).min(self.max_backoff_ms)
+    }
 }
 
 /// Loop configuration
@@ -140,6 +306,9 @@ pub struct OutputSettings {
     /// Whether to create timestamped outputs
     #[serde(default)]
     pub timestamped: bool,
+    /// How long run artifacts are kept
+    #[serde(default)]
+    pub retention: ArtifactRetention,
 }
 
 impl Default for OutputSettings {
@@ -150,6 +319,35 @@ impl Default for OutputSettings {
             format_description: None,
             append_mode: false,
             timestamped: false,
+            retention: ArtifactRetention::default(),
+        }
+    }
+}
+
+/// Retention of the artifacts stored for a workflow's runs. Artifacts of
+/// runs beyond the newest `keep_runs`, or finished more than `max_age_days`
+/// ago, are deleted; `None` disables that limit.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ArtifactRetention {
+    #[serde(default = "default_keep_runs")]
+    pub keep_runs: Option<usize>,
+    #[serde(default = "default_max_age_days")]
+    pub max_age_days: Option<u32>,
+}
+
+fn default_keep_runs() -> Option<usize> {
+    Some(20)
+}
+
+fn default_max_age_days() -> Option<u32> {
+    Some(30)
+}
+
+impl Default for ArtifactRetention {
+    fn default() -> Self {
+        Self {
+            keep_runs: default_keep_runs(),
+            max_age_days: default_max_age_days(),
         }
     }
 }
@@ -224,6 +422,31 @@ pub struct Workflow {
     /// Custom variables for the workflow
     #[serde(default)]
     pub variables: HashMap<String, String>,
+    /// Inputs the user is asked for when running the workflow manually
+    #[serde(default)]
+    pub inputs: Vec<WorkflowInput>,
+    /// Bundle an imported workflow was installed from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+}
+
+/// Declared input of a workflow, available to prompts as `{{name}}`
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct WorkflowInput {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Value type hint for the input form: "string", "number" or "boolean"
+    #[serde(rename = "type", default = "default_input_type")]
+    pub input_type: String,
+    #[serde(default)]
+    pub required: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default: Option<serde_json::Value>,
+}
+
+fn default_input_type() -> String {
+    "string".to_string()
 }
 
 impl Workflow {
@@ -246,6 +469,8 @@ impl Workflow {
             last_run: None,
             status: WorkflowStatus::Idle,
             variables: HashMap::new(),
+            inputs: Vec::new(),
+            source: None,
         }
     }
 }
@@ -255,6 +480,9 @@ impl Workflow {
 pub struct ExecutionContext {
     pub workflow_id: String,
     pub execution_id: String,
+    /// Window that started the execution
+    #[serde(default)]
+    pub context_id: ContextId,
     pub current_step_id: Option<String>,
     pub variables: HashMap<String, serde_json::Value>,
     pub step_results: HashMap<String, StepResult>,
@@ -264,6 +492,9 @@ pub struct ExecutionContext {
     pub status: WorkflowStatus,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub loop_state: Option<LoopState>,
+    /// Prompt of the current step with placeholders filled in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub current_prompt: Option<String>,
 }
 
 /// State of an active loop
@@ -296,6 +527,12 @@ pub struct ExecuteWorkflowRequest {
     pub variables: HashMap<String, serde_json::Value>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub start_step_id: Option<String>,
+    /// Data from the event that triggered the run, available as `{{trigger.*}}`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trigger_payload: Option<serde_json::Value>,
+    /// Window starting the run; taken from the request header, not the body
+    #[serde(skip)]
+    pub context_id: ContextId,
 }
 
 /// Workflow creation request
@@ -315,4 +552,6 @@ pub struct CreateWorkflowRequest {
     pub output_settings: OutputSettings,
     #[serde(default)]
     pub behavior: WorkflowBehavior,
+    #[serde(default)]
+    pub inputs: Vec<WorkflowInput>,
 }
@@ -0,0 +1,523 @@
+//! Workflow validation
+//!
+//! Checks a workflow before it runs and reports every problem found instead
+//! of stopping at the first one: the step graph (links to missing steps,
+//! steps no run can reach, cycles not marked with `allow_cycle`), step
+//! actions (unknown tools, scaffold templates and HTTP methods, broken
+//! decision expressions) and placeholders that don't resolve against sample
+//! variables. Nothing is executed.
+
+use super::expression::{self, resolve, EvalContext};
+use super::template::{placeholders, prepare_variables, TRIGGER_VAR};
+use super::tool_step;
+use super::types::*;
+use crate::scaffold::TemplateLibrary;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    /// A run would fail or behave unexpectedly
+    Error,
+    /// Worth a look, but runs work
+    Warning,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiagnosticCode {
+    NoSteps,
+    DuplicateStepId,
+    UnknownStep,
+    UnreachableStep,
+    Cycle,
+    InvalidExpression,
+    InvalidAction,
+    UnknownTemplate,
+    InvalidHttpMethod,
+    UnresolvedPlaceholder,
+    MissingSample,
+}
+
+/// One problem found in a workflow
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: DiagnosticCode,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub step_id: Option<String>,
+    /// Step field the problem is in, e.g. `next_step` or `http.url`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValidationReport {
+    /// No errors were found (warnings allowed)
+    pub valid: bool,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Sample run input placeholders are resolved against
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ValidationRequest {
+    #[serde(default)]
+    pub variables: HashMap<String, Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trigger_payload: Option<Value>,
+}
+
+/// Check `workflow` as a run started with `sample` would see it
+pub fn validate(workflow: &Workflow, sample: ValidationRequest) -> ValidationReport {
+    let mut diagnostics = Vec::new();
+    check_graph(workflow, &mut diagnostics);
+    for step in &workflow.steps {
+        check_actions(step, &mut diagnostics);
+    }
+    check_placeholders(workflow, sample, &mut diagnostics);
+
+    ValidationReport {
+        valid: !diagnostics.iter().any(|d| d.severity == Severity::Error),
+        diagnostics,
+    }
+}
+
+fn diagnostic(
+    severity: Severity,
+    code: DiagnosticCode,
+    step_id: Option<&str>,
+    field: Option<&str>,
+    message: String,
+) -> Diagnostic {
+    Diagnostic {
+        severity,
+        code,
+        message,
+        step_id: step_id.map(str::to_string),
+        field: field.map(str::to_string),
+    }
+}
+
+/// Steps a step can continue with, by the field naming them
+fn links(step: &WorkflowStep) -> Vec<(&'static str, &str)> {
+    let decision = step.decision.as_ref();
+    [
+        ("next_step", step.next_step.as_deref()),
+        ("decision.true_branch", decision.and_then(|d| d.true_branch.as_deref())),
+        ("decision.false_branch", decision.and_then(|d| d.false_branch.as_deref())),
+    ]
+    .into_iter()
+    .filter_map(|(field, target)| target.map(|target| (field, target)))
+    .collect()
+}
+
+fn check_graph(workflow: &Workflow, diagnostics: &mut Vec<Diagnostic>) {
+    let steps = &workflow.steps;
+    if steps.is_empty() {
+        diagnostics.push(diagnostic(
+            Severity::Error,
+            DiagnosticCode::NoSteps,
+            None,
+            None,
+            "Workflow has no steps".to_string(),
+        ));
+        return;
+    }
+
+    let mut index: HashMap<&str, usize> = HashMap::new();
+    for (i, step) in steps.iter().enumerate() {
+        if index.insert(step.id.as_str(), i).is_some() {
+            diagnostics.push(diagnostic(
+                Severity::Error,
+                DiagnosticCode::DuplicateStepId,
+                Some(&step.id),
+                Some("id"),
+                format!("Step id '{}' is used more than once", step.id),
+            ));
+        }
+    }
+
+    let mut edges: Vec<Vec<usize>> = vec![Vec::new(); steps.len()];
+    for (i, step) in steps.iter().enumerate() {
+        for (field, target) in links(step) {
+            match index.get(target) {
+                Some(&j) => edges[i].push(j),
+                None => diagnostics.push(diagnostic(
+                    Severity::Error,
+                    DiagnosticCode::UnknownStep,
+                    Some(&step.id),
+                    Some(field),
+                    format!("Step '{}' links to unknown step '{}'", step.id, target),
+                )),
+            }
+        }
+    }
+
+    // Runs start at the first step
+    let mut reached = vec![false; steps.len()];
+    let mut queue = VecDeque::from([0]);
+    reached[0] = true;
+    while let Some(i) = queue.pop_front() {
+        for &j in &edges[i] {
+            if !reached[j] {
+                reached[j] = true;
+                queue.push_back(j);
+            }
+        }
+    }
+    for (step, _) in steps.iter().zip(&reached).filter(|(_, reached)| !**reached) {
+        diagnostics.push(diagnostic(
+            Severity::Warning,
+            DiagnosticCode::UnreachableStep,
+            Some(&step.id),
+            None,
+            format!("No run reaches step '{}' from the first step", step.id),
+        ));
+    }
+
+    for component in cycles(&edges) {
+        if component.iter().any(|&i| steps[i].allow_cycle) {
+            continue;
+        }
+        let ids: Vec<&str> = component.iter().map(|&i| steps[i].id.as_str()).collect();
+        diagnostics.push(diagnostic(
+            Severity::Error,
+            DiagnosticCode::Cycle,
+            Some(ids[0]),
+            None,
+            format!(
+                "Steps {} form a cycle; set allow_cycle on one of them if the loop is intended",
+                ids.join(", ")
+            ),
+        ));
+    }
+}
+
+/// Groups of steps that can reach each other (including steps linking to
+/// themselves), each in workflow order
+fn cycles(edges: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    struct Tarjan<'a> {
+        edges: &'a [Vec<usize>],
+        order: Vec<Option<usize>>,
+        low: Vec<usize>,
+        on_stack: Vec<bool>,
+        stack: Vec<usize>,
+        next: usize,
+        components: Vec<Vec<usize>>,
+    }
+
+    impl Tarjan<'_> {
+        fn visit(&mut self, v: usize) {
+            self.order[v] = Some(self.next);
+            self.low[v] = self.next;
+            self.next += 1;
+            self.stack.push(v);
+            self.on_stack[v] = true;
+
+            for &w in &self.edges[v] {
+                match self.order[w] {
+                    None => {
+                        self.visit(w);
+                        self.low[v] = self.low[v].min(self.low[w]);
+                    }
+                    Some(order) if self.on_stack[w] => self.low[v] = self.low[v].min(order),
+                    Some(_) => {}
+                }
+            }
+
+            if Some(self.low[v]) == self.order[v] {
+                let mut component = Vec::new();
+                while let Some(w) = self.stack.pop() {
+                    self.on_stack[w] = false;
+                    component.push(w);
+                    if w == v {
+                        break;
+                    }
+                }
+                self.components.push(component);
+            }
+        }
+    }
+
+    let mut tarjan = Tarjan {
+        edges,
+        order: vec![None; edges.len()],
+        low: vec![0; edges.len()],
+        on_stack: vec![false; edges.len()],
+        stack: Vec::new(),
+        next: 0,
+        components: Vec::new(),
+    };
+    for v in 0..edges.len() {
+        if tarjan.order[v].is_none() {
+            tarjan.visit(v);
+        }
+    }
+
+    let mut cycles: Vec<Vec<usize>> = tarjan
+        .components
+        .into_iter()
+        .filter(|c| c.len() > 1 || edges[c[0]].contains(&c[0]))
+        .map(|mut c| {
+            c.sort_unstable();
+            c
+        })
+        .collect();
+    cycles.sort();
+    cycles
+}
+
+fn check_actions(step: &WorkflowStep, diagnostics: &mut Vec<Diagnostic>) {
+    let id = Some(step.id.as_str());
+    if let Some(decision) = step.decision.as_ref().filter(|d| d.expression) {
+        if let Err(e) = expression::validate(&decision.condition) {
+            diagnostics.push(diagnostic(
+                Severity::Error,
+                DiagnosticCode::InvalidExpression,
+                id,
+                Some("decision.condition"),
+                format!("Invalid condition: {}", e),
+            ));
+        }
+    }
+    if let Err(e) = tool_step::validate(&step.action) {
+        diagnostics.push(diagnostic(Severity::Error, DiagnosticCode::InvalidAction, id, Some("action"), e));
+    }
+    if let Some(scaffold) = &step.scaffold {
+        if let Err(e) = TemplateLibrary::global().get(&scaffold.template) {
+            diagnostics.push(diagnostic(
+                Severity::Error,
+                DiagnosticCode::UnknownTemplate,
+                id,
+                Some("scaffold.template"),
+                e.to_string(),
+            ));
+        }
+    }
+    if let Some(http) = &step.http {
+        if reqwest::Method::from_bytes(http.method.to_uppercase().as_bytes()).is_err() {
+            diagnostics.push(diagnostic(
+                Severity::Error,
+                DiagnosticCode::InvalidHttpMethod,
+                id,
+                Some("http.method"),
+                format!("Invalid HTTP method: {}", http.method),
+            ));
+        }
+    }
+}
+
+/// Templated fields of a step that are used when it runs
+fn templates(step: &WorkflowStep) -> Vec<(String, String)> {
+    let mut fields = Vec::new();
+    if let Some(http) = &step.http {
+        fields.push(("http.url".to_string(), http.url.clone()));
+        for (name, value) in &http.headers {
+            fields.push((format!("http.headers.{}", name), value.clone()));
+        }
+        if let Some(body) = &http.body {
+            fields.push(("http.body".to_string(), body.clone()));
+        }
+    } else if let Some(scaffold) = &step.scaffold {
+        fields.push(("scaffold.destination".to_string(), scaffold.destination.clone()));
+        for (name, value) in &scaffold.variables {
+            fields.push((format!("scaffold.variables.{}", name), value.clone()));
+        }
+    } else {
+        match &step.action {
+            StepAction::Prompt => fields.push(("prompt".to_string(), step.prompt.clone())),
+            StepAction::ToolCall(call) => collect_strings("action.arguments", &call.arguments, &mut fields),
+            StepAction::Script(script) => {
+                fields.push(("action.command".to_string(), script.command.clone()));
+                if let Some(directory) = &script.working_directory {
+                    fields.push(("action.working_directory".to_string(), directory.clone()));
+                }
+            }
+        }
+    }
+    fields.sort();
+    fields
+}
+
+fn collect_strings(path: &str, value: &Value, fields: &mut Vec<(String, String)>) {
+    match value {
+        Value::String(text) => fields.push((path.to_string(), text.clone())),
+        Value::Array(items) => {
+            for (i, item) in items.iter().enumerate() {
+                collect_strings(&format!("{}.{}", path, i), item, fields);
+            }
+        }
+        Value::Object(map) => {
+            for (key, item) in map {
+                collect_strings(&format!("{}.{}", path, key), item, fields);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn check_placeholders(workflow: &Workflow, sample: ValidationRequest, diagnostics: &mut Vec<Diagnostic>) {
+    let variables = match prepare_variables(workflow, sample.variables.clone(), sample.trigger_payload.clone()) {
+        Ok(variables) => variables,
+        Err(e) => {
+            diagnostics.push(diagnostic(Severity::Warning, DiagnosticCode::MissingSample, None, None, e));
+            let mut relaxed = workflow.clone();
+            relaxed.inputs.iter_mut().for_each(|input| input.required = false);
+            prepare_variables(&relaxed, sample.variables, sample.trigger_payload).unwrap_or_default()
+        }
+    };
+
+    // Variables a run sets along the way or takes from its inputs and trigger
+    let mut runtime: HashSet<&str> = workflow.inputs.iter().map(|i| i.name.as_str()).collect();
+    for step in &workflow.steps {
+        runtime.extend(step.output_var.as_deref());
+        runtime.extend(step.loop_config.as_ref().map(|l| l.item_var.as_str()));
+    }
+    if workflow.trigger.is_some() {
+        runtime.insert(TRIGGER_VAR);
+    }
+    let step_ids: HashSet<&str> = workflow.steps.iter().map(|s| s.id.as_str()).collect();
+
+    let steps = HashMap::new();
+    for step in &workflow.steps {
+        let ctx = EvalContext {
+            variables: &variables,
+            steps: &steps,
+            current_step_id: Some(&step.id),
+        };
+        for (field, template) in templates(step) {
+            for name in placeholders(&template) {
+                if name == "output" {
+                    continue;
+                }
+                if let Some(rest) = name.strip_prefix("steps.") {
+                    let step_id = rest.rsplit_once('.').map_or(rest, |(id, _)| id);
+                    if !step_ids.contains(step_id) {
+                        diagnostics.push(diagnostic(
+                            Severity::Error,
+                            DiagnosticCode::UnknownStep,
+                            Some(&step.id),
+                            Some(&field),
+                            format!("{{{{{}}}}} refers to unknown step '{}'", name, step_id),
+                        ));
+                    }
+                    continue;
+                }
+                if resolve(name, &ctx) != Value::Null {
+                    continue;
+                }
+
+                let root = name.strip_prefix("vars.").unwrap_or(name).split('.').next().unwrap_or(name);
+                let (severity, code, message) = if runtime.contains(root) {
+                    (
+                        Severity::Warning,
+                        DiagnosticCode::MissingSample,
+                        format!("{{{{{}}}}} has no sample value; it is set during the run", name),
+                    )
+                } else {
+                    (
+                        Severity::Error,
+                        DiagnosticCode::UnresolvedPlaceholder,
+                        format!("{{{{{}}}}} doesn't match any variable, input or step", name),
+                    )
+                };
+                diagnostics.push(diagnostic(severity, code, Some(&step.id), Some(&field), message));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(id: &str, next: Option<&str>) -> WorkflowStep {
+        WorkflowStep {
+            id: id.to_string(),
+            name: id.to_string(),
+            next_step: next.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    fn codes(report: &ValidationReport) -> Vec<(DiagnosticCode, Option<&str>)> {
+        report.diagnostics.iter().map(|d| (d.code, d.step_id.as_deref())).collect()
+    }
+
+    #[test]
+    fn test_graph_diagnostics() {
+        let mut workflow = Workflow::new("test".to_string(), WorkflowType::Process);
+        workflow.steps = vec![
+            step("check", Some("fix")),
+            step("fix", Some("check")),
+            step("orphan", Some("missing")),
+        ];
+
+        let report = validate(&workflow, ValidationRequest::default());
+        assert!(!report.valid);
+        assert_eq!(
+            codes(&report),
+            vec![
+                (DiagnosticCode::UnknownStep, Some("orphan")),
+                (DiagnosticCode::UnreachableStep, Some("orphan")),
+                (DiagnosticCode::Cycle, Some("check")),
+            ]
+        );
+
+        workflow.steps[1].allow_cycle = true;
+        workflow.steps[2].next_step = None;
+        let report = validate(&workflow, ValidationRequest::default());
+        assert!(report.valid);
+        assert_eq!(codes(&report), vec![(DiagnosticCode::UnreachableStep, Some("orphan"))]);
+    }
+
+    #[test]
+    fn test_placeholders_resolve_against_sample() {
+        let mut workflow = Workflow::new("test".to_string(), WorkflowType::Process);
+        workflow.inputs.push(WorkflowInput {
+            name: "repo".to_string(),
+            required: true,
+            ..Default::default()
+        });
+        let mut summarize = step("summarize", Some("notify"));
+        summarize.prompt = "Summarize {{repo}} for {{team}}".to_string();
+        summarize.output_var = Some("summary".to_string());
+        let mut notify = step("notify", None);
+        notify.action = StepAction::ToolCall(ToolCallStep {
+            tool: "write_file".to_string(),
+            arguments: serde_json::json!({"path": "/tmp/{{steps.sumarize.output}}", "content": "{{summary}}"}),
+        });
+        workflow.steps = vec![summarize, notify];
+
+        let report = validate(&workflow, ValidationRequest::default());
+        assert_eq!(
+            codes(&report),
+            vec![
+                (DiagnosticCode::MissingSample, None),
+                (DiagnosticCode::MissingSample, Some("summarize")),
+                (DiagnosticCode::UnresolvedPlaceholder, Some("summarize")),
+                (DiagnosticCode::MissingSample, Some("notify")),
+                (DiagnosticCode::UnknownStep, Some("notify")),
+            ]
+        );
+        assert_eq!(report.diagnostics[4].field.as_deref(), Some("action.arguments.path"));
+
+        workflow.steps[1].action = StepAction::ToolCall(ToolCallStep {
+            tool: "write_file".to_string(),
+            arguments: serde_json::json!({"path": "/tmp/{{steps.summarize.output}}", "content": "{{summary}}"}),
+        });
+        let sample = ValidationRequest {
+            variables: HashMap::from([
+                ("repo".to_string(), serde_json::json!("skhoot")),
+                ("team".to_string(), serde_json::json!("core")),
+            ]),
+            trigger_payload: None,
+        };
+        let report = validate(&workflow, sample);
+        assert!(report.valid);
+        assert_eq!(codes(&report), vec![(DiagnosticCode::MissingSample, Some("notify"))]);
+    }
+}
@@ -0,0 +1,232 @@
+//! Webhooks for workflows
+//!
+//! Inbound: workflows with a `TriggerType::Webhook` trigger are started by a
+//! request to `/api/v1/hooks/:token`; the JSON body becomes the trigger
+//! payload and its top-level fields become run variables.
+//!
+//! Outbound: steps with an `http` config are executed by the server rather
+//! than sent to the AI, so a workflow can call external APIs or notify
+//! Slack/Discord. The response body becomes the step output.
+//!
+//! Placeholders in outbound requests can come from an untrusted webhook
+//! payload: values are percent-encoded in the URL and JSON-escaped in JSON
+//! bodies, and the rendered URL must pass the SSRF rules before it is sent.
+
+use super::expression::EvalContext;
+use super::runs::StepOutcome;
+use super::template::{render, render_escaped};
+use crate::content_extraction::SsrfValidator;
+use super::types::*;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Timeout for outbound requests when the step doesn't set one
+const DEFAULT_TIMEOUT_SECS: u64 = 30;
+/// Response bodies longer than this are truncated in the step output
+const MAX_RESPONSE_CHARS: usize = 16_000;
+
+/// New random webhook token
+pub fn generate_token() -> String {
+    uuid::Uuid::new_v4().simple().to_string()
+}
+
+/// Give webhook triggers without a token a generated one
+pub fn assign_token(trigger: &mut Option<TriggerType>) {
+    if let Some(TriggerType::Webhook { token }) = trigger {
+        if token.is_empty() {
+            *token = generate_token();
+        }
+    }
+}
+
+/// Run variables from a webhook payload: the fields of a JSON object, or
+/// nothing for other payloads (which remain available as `{{trigger}}`)
+pub fn payload_variables(payload: &Value) -> HashMap<String, Value> {
+    match payload {
+        Value::Object(fields) => fields.iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
+        _ => HashMap::new(),
+    }
+}
+
+/// Fill placeholders in the URL, header values and body. URL values are
+/// percent-encoded; values in a JSON body are escaped as JSON string content.
+pub fn render_request(http: &HttpRequestStep, ctx: &EvalContext) -> HttpRequestStep {
+    HttpRequestStep {
+        method: http.method.clone(),
+        url: render_escaped(&http.url, ctx, |value| urlencoding::encode(value).into_owned()),
+        headers: http
+            .headers
+            .iter()
+            .map(|(k, v)| (k.clone(), render(v, ctx)))
+            .collect(),
+        body: http.body.as_ref().map(|b| {
+            if is_json_template(b) {
+                render_escaped(b, ctx, json_escape)
+            } else {
+                render(b, ctx)
+            }
+        }),
+    }
+}
+
+/// Whether a body template is a JSON document
+fn is_json_template(body: &str) -> bool {
+    matches!(body.trim_start().chars().next(), Some('{' | '['))
+}
+
+/// `value` as the content of a JSON string, without the quotes
+fn json_escape(value: &str) -> String {
+    let quoted = Value::String(value.to_string()).to_string();
+    quoted[1..quoted.len() - 1].to_string()
+}
+
+/// Perform a rendered request for `step` and turn the response into an outcome
+pub async fn send(step: &WorkflowStep, http: &HttpRequestStep) -> StepOutcome {
+    let started = Instant::now();
+    let result = perform(http, step.timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS)).await;
+    let duration_ms = started.elapsed().as_millis() as u64;
+
+    let (success, output, error) = match result {
+        Ok(output) => (true, output, None),
+        Err(e) => (false, String::new(), Some(e)),
+    };
+
+    StepOutcome {
+        step_id: step.id.clone(),
+        success,
+        output,
+        error,
+        duration_ms,
+        decision_result: None,
+        artifacts: Vec::new(),
+    }
+}
+
+async fn perform(http: &HttpRequestStep, timeout_secs: u64) -> Result<String, String> {
+    let method = reqwest::Method::from_bytes(http.method.to_uppercase().as_bytes())
+        .map_err(|_| format!("Invalid HTTP method: {}", http.method))?;
+
+    let url = url::Url::parse(&http.url).map_err(|e| format!("Invalid URL '{}': {}", http.url, e))?;
+    SsrfValidator::validate_url(&url).await.map_err(|e| e.to_string())?;
+
+    // Redirects aren't followed, so a response can't point past the SSRF check
+    let client = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .timeout(Duration::from_secs(timeout_secs))
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+    let mut request = client.request(method, url);
+    for (name, value) in &http.headers {
+        request = request.header(name, value);
+    }
+    if let Some(body) = &http.body {
+        let has_content_type = http.headers.keys().any(|k| k.eq_ignore_ascii_case("content-type"));
+        if !has_content_type && serde_json::from_str::<Value>(body).is_ok() {
+            request = request.header(reqwest::header::CONTENT_TYPE, "application/json");
+        }
+        request = request.body(body.clone());
+    }
+
+    let response = request
+        .send()
+        .await
+        .map_err(|e| format!("Request to {} failed: {}", http.url, e))?;
+    let status = response.status();
+    let mut text = response.text().await.unwrap_or_default();
+    if let Some((index, _)) = text.char_indices().nth(MAX_RESPONSE_CHARS) {
+        text.truncate(index);
+    }
+
+    if status.is_success() {
+        Ok(text)
+    } else {
+        Err(format!("HTTP {}: {}", status.as_u16(), text))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_request_fills_placeholders() {
+        let variables = HashMap::from([
+            ("channel".to_string(), serde_json::json!("alerts")),
+            ("trigger".to_string(), serde_json::json!({"repo": "skhoot"})),
+        ]);
+        let steps = HashMap::new();
+        let ctx = EvalContext {
+            variables: &variables,
+            steps: &steps,
+            current_step_id: None,
+        };
+        let http = HttpRequestStep {
+            method: "POST".to_string(),
+            url: "https://hooks.example.com/{{channel}}".to_string(),
+            headers: HashMap::from([("X-Repo".to_string(), "{{trigger.repo}}".to_string())]),
+            body: Some(r#"{"text": "{{trigger.repo}} finished"}"#.to_string()),
+        };
+
+        let rendered = render_request(&http, &ctx);
+        assert_eq!(rendered.url, "https://hooks.example.com/alerts");
+        assert_eq!(rendered.headers["X-Repo"], "skhoot");
+        assert_eq!(rendered.body.as_deref(), Some(r#"{"text": "skhoot finished"}"#));
+    }
+
+    #[test]
+    fn test_render_request_escapes_payload_values() {
+        let variables = HashMap::from([(
+            "trigger".to_string(),
+            serde_json::json!({"channel": "../admin?x=1", "text": "done\", \"admin\": true"}),
+        )]);
+        let steps = HashMap::new();
+        let ctx = EvalContext {
+            variables: &variables,
+            steps: &steps,
+            current_step_id: None,
+        };
+        let http = HttpRequestStep {
+            method: "POST".to_string(),
+            url: "https://hooks.example.com/{{trigger.channel}}".to_string(),
+            headers: HashMap::new(),
+            body: Some(r#"{"text": "{{trigger.text}}"}"#.to_string()),
+        };
+
+        let rendered = render_request(&http, &ctx);
+        assert_eq!(rendered.url, "https://hooks.example.com/..%2Fadmin%3Fx%3D1");
+        let body: Value = serde_json::from_str(rendered.body.as_deref().unwrap()).unwrap();
+        assert_eq!(body, serde_json::json!({"text": "done\", \"admin\": true"}));
+    }
+
+    #[tokio::test]
+    async fn test_send_blocks_internal_hosts() {
+        let step = WorkflowStep {
+            id: "notify".to_string(),
+            ..Default::default()
+        };
+        let http = HttpRequestStep {
+            method: "GET".to_string(),
+            url: "http://127.0.0.1:1/admin".to_string(),
+            headers: HashMap::new(),
+            body: None,
+        };
+        let outcome = send(&step, &http).await;
+        assert!(!outcome.success);
+        assert!(outcome.error.unwrap().contains("SSRF"));
+    }
+
+    #[test]
+    fn test_payload_variables_and_token_assignment() {
+        let vars = payload_variables(&serde_json::json!({"branch": "main", "count": 2}));
+        assert_eq!(vars["branch"], "main");
+        assert!(payload_variables(&serde_json::json!([1, 2])).is_empty());
+
+        let mut trigger = Some(TriggerType::Webhook { token: String::new() });
+        assign_token(&mut trigger);
+        let token = trigger.as_ref().and_then(|t| t.webhook_token()).unwrap().to_string();
+        assign_token(&mut trigger);
+        assert_eq!(trigger.unwrap().webhook_token(), Some(token.as_str()));
+    }
+}
@@ -45,6 +45,8 @@ async fn test_steering_file_workflow_execution() {
         workflow_id: "default-steering-file".to_string(),
         variables: HashMap::new(),
         start_step_id: None,
+        trigger_payload: None,
+        context_id: Default::default(),
     };
     
     let context = engine.execute(request).await.expect("Failed to start workflow");
@@ -79,6 +81,8 @@ async fn test_auto_workflow_execution_valid() {
         workflow_id: "default-auto-workflow".to_string(),
         variables: HashMap::new(),
         start_step_id: None,
+        trigger_payload: None,
+        context_id: Default::default(),
     };
     
     let context = engine.execute(request).await.expect("Failed to start workflow");
@@ -114,6 +118,8 @@ async fn test_auto_workflow_execution_invalid() {
         workflow_id: "default-auto-workflow".to_string(),
         variables: HashMap::new(),
         start_step_id: None,
+        trigger_payload: None,
+        context_id: Default::default(),
     };
     
     let context = engine.execute(request).await.expect("Failed to start workflow");
@@ -145,6 +151,8 @@ async fn test_error_search_workflow_critical() {
         workflow_id: "default-error-search".to_string(),
         variables: HashMap::new(),
         start_step_id: None,
+        trigger_payload: None,
+        context_id: Default::default(),
     };
     
     let context = engine.execute(request).await.expect("Failed to start workflow");
@@ -177,6 +185,8 @@ async fn test_meal_planner_workflow() {
         workflow_id: "demo-meal-planner".to_string(),
         variables: HashMap::new(),
         start_step_id: None,
+        trigger_payload: None,
+        context_id: Default::default(),
     };
     
     let context = engine.execute(request).await.expect("Failed to start workflow");
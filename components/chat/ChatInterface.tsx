@@ -114,6 +114,13 @@ const ChatInterface: React.FC<ChatInterfaceProps> = ({
     },
   });
 
+  // Voice capture toggled by the global hotkey
+  useEffect(() => {
+    const handleToggleVoice = () => { handleMicClick(); };
+    window.addEventListener('toggle-voice-capture', handleToggleVoice);
+    return () => window.removeEventListener('toggle-voice-capture', handleToggleVoice);
+  }, [handleMicClick]);
+
   // Debug: Log agent mode state changes
   useEffect(() => {
     console.log('[ChatInterface] Agent state:', { isAgentMode, agentSessionId, isAgentLoading, agentError });
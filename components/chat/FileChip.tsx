@@ -1,6 +1,7 @@
 import React, { memo } from 'react';
 import { X, Files } from 'lucide-react';
 import { getFileTypeInfo } from './FileAttachmentModal';
+import { withBackendToken } from '../../services/backendAuth';
 
 interface FileChipProps {
   fileName: string;
@@ -53,7 +54,7 @@ export const FileChip = memo<FileChipProps>(({ fileName, filePath, onRemove }) =
     }
     
     // For web/development, use the backend API to serve the image
-    return `http://127.0.0.1:3001/api/v1/files/image?path=${encodeURIComponent(path)}`;
+    return withBackendToken(`http://127.0.0.1:3001/api/v1/files/image?path=${encodeURIComponent(path)}`);
   };
 
   return (
@@ -3,6 +3,7 @@ import { Search, ChevronDown, ChevronUp, Grid, List } from 'lucide-react';
 import { FileInfo } from '../../types';
 import { useSettings } from '../../src/contexts/SettingsContext';
 import { FileCard, type FileCardFile } from '../ui';
+import { backendApi } from '../../services/backendApi';
 
 // Re-export helpers for backward compatibility
 export { openFile, openFolder } from '../ui';
@@ -37,6 +38,11 @@ export const FileList = memo<{ files: FileInfo[]; searchInfo?: any }>(({ files,
   // Always use compact grid layout when showing more results
   const useGridLayout = showAll || (searchDisplay.layout === 'grid' && !searchDisplay.gridOnlyForMore);
 
+  // Opened results teach the backend which folders and file types matter
+  const recordOpen = (file: FileCardFile) => {
+    backendApi.recordSearchClick(file.path, searchInfo?.searchId).catch(() => {});
+  };
+
   return (
     <div className="mt-4 space-y-2">
       {searchInfo && (
@@ -117,14 +123,14 @@ export const FileList = memo<{ files: FileInfo[]; searchInfo?: any }>(({ files,
             <div className="grid grid-cols-3 sm:grid-cols-4 md:grid-cols-5 gap-2">
               {displayedFiles.map((file, index) => (
                 <div key={file.id} style={{ animationDelay: `${index * 0.03}s` }}>
-                  <FileCard file={file as FileCardFile} layout="grid" />
+                  <FileCard file={file as FileCardFile} layout="grid" onOpen={recordOpen} />
                 </div>
               ))}
             </div>
           ) : (
             displayedFiles.map((file, index) => (
               <div key={file.id} style={{ animationDelay: `${index * 0.05}s` }}>
-                <FileCard file={file as FileCardFile} layout="list" />
+                <FileCard file={file as FileCardFile} layout="list" onOpen={recordOpen} />
               </div>
             ))
           )}
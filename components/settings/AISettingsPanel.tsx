@@ -11,6 +11,7 @@ import { providerRegistry } from '../../services/providerRegistry';
 import { tokenTrackingService, TimePeriod } from '../../services/tokenTrackingService';
 import { getMaxOutputTokens } from '../../services/modelCapabilities';
 import { hyperlinkSettingsService } from '../../services/hyperlinkSettingsService';
+import { aiSettingsService } from '../../services/aiSettingsService';
 
 interface AISettingsPanelProps {
   onBack: () => void;
@@ -30,6 +31,9 @@ export const AISettingsPanel: React.FC<AISettingsPanelProps> = ({ onBack }) => {
     const saved = localStorage.getItem('skhoot_agent_mode_default');
     return saved !== 'false';
   });
+  const [fallbackProvider, setFallbackProvider] = useState(() =>
+    aiSettingsService.loadSettings().fallbackProvider || ''
+  );
   const [userInstructions, setUserInstructions] = useState(() => {
     const saved = localStorage.getItem('skhoot_user_instructions');
     return saved || '';
@@ -178,6 +182,11 @@ export const AISettingsPanel: React.FC<AISettingsPanelProps> = ({ onBack }) => {
     hyperlinkSettingsService.saveSetting('sourceHyperlinks', enabled);
   }, [hyperlinkSettings]);
 
+  const handleFallbackProviderChange = useCallback((provider: string) => {
+    setFallbackProvider(provider);
+    aiSettingsService.saveSetting('fallbackProvider', provider || undefined);
+  }, []);
+
   const handleUserInstructionsChange = useCallback((value: string) => {
     setUserInstructions(value);
     localStorage.setItem('skhoot_user_instructions', value);
@@ -343,6 +352,23 @@ export const AISettingsPanel: React.FC<AISettingsPanelProps> = ({ onBack }) => {
             }`} />
           </button>
         </div>
+
+        <div className="flex items-center justify-between p-3 rounded-xl glass-subtle">
+          <div>
+            <p className="text-sm font-medium font-jakarta text-text-primary">Fallback Provider</p>
+            <p className="text-xs text-text-secondary font-jakarta">Retry chats with this provider when the active one is down or rate-limited</p>
+          </div>
+          <select
+            value={fallbackProvider}
+            onChange={(e) => handleFallbackProviderChange(e.target.value)}
+            className="bg-transparent text-sm text-text-primary font-jakarta outline-none border-b border-glass-border focus:border-[#C0B7C9] transition-all"
+          >
+            <option value="">None</option>
+            {PROVIDERS.map(provider => (
+              <option key={provider.id} value={provider.id}>{provider.name}</option>
+            ))}
+          </select>
+        </div>
       </div>
 
       {/* User Instructions */}
@@ -1,5 +1,5 @@
-import React, { useState } from 'react';
-import { Shield, Mail, Lock, FolderOpen } from 'lucide-react';
+import React, { useCallback, useEffect, useState } from 'react';
+import { Shield, Mail, Lock, FolderOpen, Globe, Trash2 } from 'lucide-react';
 import { BackButton } from '../buttonFormat';
 import { isTauriApp } from '../../services/tauriDetection';
 
@@ -7,6 +7,14 @@ interface PrivacyPanelProps {
   onBack: () => void;
 }
 
+interface BrowsingContextInfo {
+  name: string;
+  size_bytes: number;
+  modified_at?: number;
+}
+
+const CONTEXT_NAME_PATTERN = /^[A-Za-z0-9_-]{1,64}$/;
+
 interface SectionLabelProps {
   label: string;
   icon?: React.ReactNode;
@@ -38,6 +46,61 @@ export const PrivacyPanel: React.FC<PrivacyPanelProps> = ({ onBack }) => {
   // Download state
   const [isDownloading, setIsDownloading] = useState(false);
 
+  // Browsing contexts (logged-in sessions used when browsing pages)
+  const [browsingContexts, setBrowsingContexts] = useState<BrowsingContextInfo[]>([]);
+  const [contextName, setContextName] = useState('');
+  const [loginUrl, setLoginUrl] = useState('');
+  const [contextError, setContextError] = useState('');
+
+  const loadBrowsingContexts = useCallback(async () => {
+    if (!isTauriApp()) return;
+    try {
+      const { invoke } = await import('@tauri-apps/api/core');
+      setBrowsingContexts(await invoke<BrowsingContextInfo[]>('list_browsing_contexts'));
+    } catch (error) {
+      console.error('[BrowsingContexts] Failed to list browsing contexts:', error);
+    }
+  }, []);
+
+  useEffect(() => {
+    loadBrowsingContexts();
+    // Logging in happens in a separate window; pick up new contexts when the user comes back
+    window.addEventListener('focus', loadBrowsingContexts);
+    return () => window.removeEventListener('focus', loadBrowsingContexts);
+  }, [loadBrowsingContexts]);
+
+  const handleOpenLogin = async (name: string, url: string) => {
+    setContextError('');
+    if (!CONTEXT_NAME_PATTERN.test(name)) {
+      setContextError('Name the session with letters, digits, "-" or "_"');
+      return;
+    }
+    if (!/^https?:\/\//.test(url)) {
+      setContextError('Enter the login page URL, starting with https://');
+      return;
+    }
+    try {
+      const { invoke } = await import('@tauri-apps/api/core');
+      await invoke('open_browsing_context_login', { name, url });
+      setContextName('');
+      setLoginUrl('');
+      loadBrowsingContexts();
+    } catch (error) {
+      setContextError(`Failed to open login window: ${error}`);
+    }
+  };
+
+  const handleDeleteContext = async (name: string) => {
+    setContextError('');
+    try {
+      const { invoke } = await import('@tauri-apps/api/core');
+      await invoke('delete_browsing_context', { name });
+      loadBrowsingContexts();
+    } catch (error) {
+      setContextError(`Failed to delete session: ${error}`);
+    }
+  };
+
   const handleUpdateEmail = async () => {
     setEmailError('');
     setEmailSuccess('');
@@ -285,6 +348,83 @@ export const PrivacyPanel: React.FC<PrivacyPanelProps> = ({ onBack }) => {
         </div>
       </div>
 
+      {/* Browsing Sessions */}
+      {isTauriApp() && (
+        <div className="space-y-3">
+          <SectionLabel 
+            label="Browsing Sessions" 
+            icon={<Globe size={16} />}
+            iconColor="text-[#C0B7C9]"
+          />
+          <div className="p-3 rounded-xl glass-subtle space-y-3">
+            <p className="text-xs text-text-secondary font-jakarta">
+              Log in to a site once and Skhoot can read pages that require your account. Cookies stay on this device, one session per name; ask the assistant to browse "with the <span className="font-bold">name</span> session".
+            </p>
+
+            {browsingContexts.map((context) => (
+              <div key={context.name} className="flex items-center gap-2">
+                <span className="flex-1 text-sm font-medium font-jakarta text-text-primary truncate">{context.name}</span>
+                <span className="text-xs text-text-secondary font-jakarta">
+                  {(context.size_bytes / (1024 * 1024)).toFixed(1)} MB
+                </span>
+                <button
+                  onClick={() => {
+                    setContextName(context.name);
+                    setContextError('');
+                  }}
+                  className="px-2 py-1 rounded-lg text-xs font-medium font-jakarta text-text-primary hover:bg-black/5 dark:hover:bg-white/5 transition-all"
+                >
+                  Log in again
+                </button>
+                <button
+                  onClick={() => handleDeleteContext(context.name)}
+                  title="Delete session and its cookies"
+                  className="p-1 rounded-lg text-red-500 hover:bg-red-500/10 transition-all"
+                >
+                  <Trash2 size={14} />
+                </button>
+              </div>
+            ))}
+
+            <div className="flex gap-2">
+              <input
+                type="text"
+                value={contextName}
+                onChange={(e) => {
+                  setContextName(e.target.value);
+                  setContextError('');
+                }}
+                placeholder="Session name"
+                className="w-1/3 px-3 py-2 bg-transparent text-sm font-medium font-jakarta text-text-primary placeholder:text-text-secondary/50 border-b border-glass-border focus:border-[#C0B7C9] outline-none transition-all"
+              />
+              <input
+                type="url"
+                value={loginUrl}
+                onChange={(e) => {
+                  setLoginUrl(e.target.value);
+                  setContextError('');
+                }}
+                placeholder="https://example.com/login"
+                className="flex-1 px-3 py-2 bg-transparent text-sm font-medium font-jakarta text-text-primary placeholder:text-text-secondary/50 border-b border-glass-border focus:border-[#C0B7C9] outline-none transition-all"
+              />
+            </div>
+
+            {contextError && (
+              <div className="p-2 rounded-lg bg-red-500/10">
+                <p className="text-xs font-medium font-jakarta text-red-600 dark:text-red-400">❌ {contextError}</p>
+              </div>
+            )}
+
+            <button
+              onClick={() => handleOpenLogin(contextName.trim(), loginUrl.trim())}
+              className="w-full px-4 py-2 rounded-lg text-sm font-medium font-jakarta bg-[#C0B7C9] text-white hover:bg-[#B0A7B9] transition-all"
+            >
+              Open Login Window
+            </button>
+          </div>
+        </div>
+      )}
+
       {/* Privacy Notice */}
       <div className="p-4 rounded-xl glass-subtle">
         <div className="flex items-start gap-3">
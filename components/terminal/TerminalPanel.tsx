@@ -1,5 +1,5 @@
 import React, { useState, useEffect, useRef, useCallback } from 'react';
-import { Terminal, X, Plus, Search, Copy, Trash2, Bot } from 'lucide-react';
+import { Terminal, X, Plus, Search, Copy, Trash2, Bot, Eye, EyeOff } from 'lucide-react';
 import { terminalService } from '../../services/terminal/terminalService';
 import { terminalContextStore } from '../../services/agentTools/terminalTools';
 
@@ -10,6 +10,7 @@ interface TerminalTab {
   sessionId: string;
   createdBy?: 'user' | 'ai';
   workspaceRoot?: string;
+  sharedWithAgent?: boolean;
 }
 
 interface TerminalPanelProps {
@@ -156,6 +157,15 @@ export const TerminalPanel: React.FC<TerminalPanelProps> = ({ isOpen, onClose })
     });
   }, []);
 
+  const handleToggleAgentAccess = useCallback(async (tab: TerminalTab) => {
+    try {
+      const shared = await terminalService.setAgentAccess(tab.sessionId, !tab.sharedWithAgent);
+      setTabs(prev => prev.map(t => t.id === tab.id ? { ...t, sharedWithAgent: shared } : t));
+    } catch (error) {
+      console.error('Failed to change agent access:', error);
+    }
+  }, []);
+
   const handleKeyDown = async (e: React.KeyboardEvent<HTMLTextAreaElement>, sessionId: string) => {
     if (e.key === 'Enter') {
       e.preventDefault();
@@ -209,6 +219,11 @@ export const TerminalPanel: React.FC<TerminalPanelProps> = ({ isOpen, onClose })
             >
               <Terminal size={14} />
               <span className="text-sm font-medium">{tab.title}</span>
+              {tab.sharedWithAgent && (
+                <span title="Shared with AI">
+                  <Eye size={12} className="text-cyan-400" />
+                </span>
+              )}
               {tab.createdBy === 'ai' && (
                 <div 
                   className="flex items-center gap-1 px-2 py-0.5 rounded-full bg-cyan-500/20 border border-cyan-500/30"
@@ -249,6 +264,15 @@ export const TerminalPanel: React.FC<TerminalPanelProps> = ({ isOpen, onClose })
           </button>
           {activeTab && (
             <>
+              {activeTab.createdBy !== 'ai' && (
+                <button
+                  onClick={() => handleToggleAgentAccess(activeTab)}
+                  className={`p-2 rounded-lg glass-subtle hover:glass-elevated transition-all hover:bg-cyan-500/10 hover:text-cyan-500 ${activeTab.sharedWithAgent ? 'text-cyan-400' : ''}`}
+                  title={activeTab.sharedWithAgent ? 'Stop sharing with AI' : 'Let AI read this terminal'}
+                >
+                  {activeTab.sharedWithAgent ? <Eye size={16} /> : <EyeOff size={16} />}
+                </button>
+              )}
               <button
                 onClick={() => handleCopyOutput(activeTab.sessionId)}
                 className="p-2 rounded-lg glass-subtle hover:glass-elevated transition-all hover:bg-cyan-500/10 hover:text-cyan-500"
@@ -10,6 +10,7 @@ import { ChevronDown, ChevronUp, Grid, List, Copy, Check } from 'lucide-react';
 import { ToolCallUIProps } from '../registry/types';
 import { FileCard, FileCardFile } from '../../ui/FileCard';
 import { useSettings } from '../../../src/contexts/SettingsContext';
+import { backendApi } from '../../../services/backendApi';
 
 // ============================================================================
 // Parsing Functions
@@ -40,6 +41,7 @@ function parseSearchResults(output: string): FileCardFile[] {
           safeToRemove: false,
           lastUsed: 'Unknown',
           snippet: `Line ${lineNum}: ${snippet.slice(0, 100)}`,
+          lineNumber: Number(lineNum),
           relevanceScore: 85,
         };
         files.push(fileInfo);
@@ -123,6 +125,10 @@ export const SearchFilesUI = memo<ToolCallUIProps>(({
     }
   }, [onNavigate]);
 
+  const handleOpen = useCallback((file: FileCardFile) => {
+    backendApi.recordSearchClick(file.path).catch(() => {});
+  }, []);
+
   const toggleLayout = useCallback(() => {
     setLocalLayout(prev => {
       if (prev === 'grid') return 'compact';
@@ -194,6 +200,7 @@ export const SearchFilesUI = memo<ToolCallUIProps>(({
                 showSnippet={false}
                 showAddToChat={true}
                 onNavigate={handleNavigate}
+                onOpen={handleOpen}
               />
             ))}
           </div>
@@ -208,6 +215,7 @@ export const SearchFilesUI = memo<ToolCallUIProps>(({
                 showSnippet={true}
                 showAddToChat={true}
                 onNavigate={handleNavigate}
+                onOpen={handleOpen}
               />
             ))}
           </div>
@@ -27,6 +27,7 @@ import {
 import { FileInfo } from '../../types';
 import { Button } from '../buttonFormat';
 import { chatAttachmentService } from '../../services/chatAttachmentService';
+import { MatchPreview } from './MatchPreview';
 
 // ============================================================================
 // Helper Functions
@@ -118,6 +119,7 @@ export interface FileCardFile extends FileInfo {
   relevanceScore?: number;
   scoreReason?: string;
   snippet?: string;
+  lineNumber?: number; // Line of a content match, enables the match preview
   score?: number | string;
   source?: string;
   archivedDate?: string;
@@ -139,6 +141,7 @@ export interface FileCardProps {
   isRestoring?: boolean;
   onDelete?: (file: FileCardFile) => void;
   onNavigate?: (path: string) => void; // For folder navigation
+  onOpen?: (file: FileCardFile) => void; // After the file was opened
 }
 
 // ============================================================================
@@ -195,8 +198,10 @@ export const FileCard = memo<FileCardProps>(({
   isRestoring = false,
   onDelete,
   onNavigate,
+  onOpen,
 }) => {
   const [copied, setCopied] = useState(false);
+  const [showPreview, setShowPreview] = useState(false);
   const [addedToChat, setAddedToChat] = useState(false);
   const isFolder = file.category === 'Folder' || file.size === '-';
   const isArchive = variant === 'archive';
@@ -211,7 +216,9 @@ export const FileCard = memo<FileCardProps>(({
   const handleOpen = async (e?: React.MouseEvent) => {
     e?.stopPropagation();
     const success = await openFile(file.path);
-    if (!success) {
+    if (success) {
+      onOpen?.(file);
+    } else {
       await navigator.clipboard.writeText(file.path);
       alert(`📋 Path copied!\n\n${file.path}\n\nTo open the file:\n• Start the backend: cd backend && cargo run\n• Or paste this path in your file manager`);
     }
@@ -499,9 +506,22 @@ export const FileCard = memo<FileCardProps>(({
           
           {/* Snippet */}
           {showSnippet && file.snippet && (
-            <p className="text-[10px] font-medium text-text-secondary mt-1 italic">
-              "{file.snippet.substring(0, 60)}..."
-            </p>
+            file.lineNumber ? (
+              <button
+                onClick={(e) => { e.stopPropagation(); setShowPreview(prev => !prev); }}
+                className="block text-left text-[10px] font-medium text-text-secondary mt-1 italic hover:text-text-primary"
+                title={showPreview ? 'Hide preview' : `Preview around line ${file.lineNumber}`}
+              >
+                "{file.snippet.substring(0, 60)}..."
+              </button>
+            ) : (
+              <p className="text-[10px] font-medium text-text-secondary mt-1 italic">
+                "{file.snippet.substring(0, 60)}..."
+              </p>
+            )
+          )}
+          {showPreview && file.lineNumber && (
+            <MatchPreview path={file.path} line={file.lineNumber} />
           )}
         </div>
         <span className="text-[10px] font-black whitespace-nowrap opacity-50 font-jakarta text-text-secondary">
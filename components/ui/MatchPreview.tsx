@@ -0,0 +1,98 @@
+/**
+ * MatchPreview - Lines around a content search match
+ *
+ * Fetches a bounded window of the file from /api/v1/files/preview and
+ * colours it using the backend's token hints, so matches can be read in
+ * context without loading the whole file.
+ */
+
+import React, { memo, useEffect, useState } from 'react';
+import { Loader2 } from 'lucide-react';
+import { backendApi, type FilePreview, type FilePreviewLine, type PreviewTokenKind } from '../../services/backendApi';
+
+const TOKEN_CLASSES: Record<PreviewTokenKind, string> = {
+  comment: 'text-text-secondary italic',
+  string: 'text-emerald-500',
+  number: 'text-amber-500',
+  constant: 'text-amber-500',
+  keyword: 'text-fuchsia-500',
+  operator: 'text-sky-500',
+  function: 'text-blue-500',
+  type: 'text-teal-500',
+  tag: 'text-rose-500',
+  attribute: 'text-orange-500',
+  variable: 'text-text-primary',
+  punctuation: 'text-text-secondary',
+};
+
+interface Segment {
+  text: string;
+  className?: string;
+  match: boolean;
+}
+
+/** Split a line into runs that share a token kind and match state */
+function segments(line: FilePreviewLine): Segment[] {
+  const chars = Array.from(line.text);
+  const result: Segment[] = [];
+  for (let i = 0; i < chars.length; i++) {
+    const token = line.tokens?.find(t => t.start <= i && i < t.end);
+    const className = token ? TOKEN_CLASSES[token.kind] : undefined;
+    const match = !!line.matches?.some(m => m.start <= i && i < m.end);
+    const last = result[result.length - 1];
+    if (last && last.className === className && last.match === match) {
+      last.text += chars[i];
+    } else {
+      result.push({ text: chars[i], className, match });
+    }
+  }
+  return result;
+}
+
+export interface MatchPreviewProps {
+  path: string;
+  line: number;
+  query?: string;
+  context?: number;
+}
+
+export const MatchPreview = memo<MatchPreviewProps>(({ path, line, query, context = 3 }) => {
+  const [preview, setPreview] = useState<FilePreview | null>(null);
+  const [error, setError] = useState<string | null>(null);
+
+  useEffect(() => {
+    let cancelled = false;
+    backendApi.getFilePreview(path, { line, context, query })
+      .then(result => { if (!cancelled) setPreview(result); })
+      .catch(e => { if (!cancelled) setError(e instanceof Error ? e.message : String(e)); });
+    return () => { cancelled = true; };
+  }, [path, line, query, context]);
+
+  if (error) {
+    return <p className="text-[10px] text-text-secondary mt-1">{error}</p>;
+  }
+  if (!preview) {
+    return <Loader2 size={12} className="animate-spin text-text-secondary mt-1" />;
+  }
+
+  return (
+    <pre className="mt-2 p-2 rounded-lg glass-subtle text-[10px] leading-4 font-mono overflow-x-auto">
+      {preview.lines.map(previewLine => (
+        <div key={previewLine.number} className={previewLine.number === line ? 'bg-accent/10' : undefined}>
+          <span className="inline-block w-8 pr-2 text-right select-none opacity-40">{previewLine.number}</span>
+          {segments(previewLine).map((segment, i) => (
+            <span
+              key={i}
+              className={[segment.className, segment.match ? 'bg-accent/30 rounded-sm' : undefined].filter(Boolean).join(' ') || undefined}
+            >
+              {segment.text}
+            </span>
+          ))}
+          {previewLine.truncated && <span className="opacity-40">…</span>}
+        </div>
+      ))}
+    </pre>
+  );
+});
+
+MatchPreview.displayName = 'MatchPreview';
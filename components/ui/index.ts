@@ -6,5 +6,6 @@ export { MarkdownRenderer } from './MarkdownRenderer';
 export { Modal } from './Modal';
 export { SecondaryPanel, type SecondaryPanelTab } from './SecondaryPanel';
 export { FileCard, openFile, openFolder, addToChat, type FileCardFile, type FileCardLayout, type FileCardVariant, type FileCardProps } from './FileCard';
+export { MatchPreview, type MatchPreviewProps } from './MatchPreview';
 export { useToast, ToastContainer, type ToastType, type ToastMessage } from './Toast';
 export { AnimationToolcall, type AnimationConfig, type AnimationToolcallProps } from './AnimationToolcall';
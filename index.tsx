@@ -3,8 +3,12 @@ import React from 'react';
 import ReactDOM from 'react-dom/client';
 import App from './App';
 import { initDemo } from './browser-test/demo';
+import { installBackendAuth } from './services/backendAuth';
 import './src/index.css';
 
+// Attach the launch token and window context to every backend request
+installBackendAuth();
+
 const rootElement = document.getElementById('root');
 if (!rootElement) {
   throw new Error("Could not find root element to mount to");
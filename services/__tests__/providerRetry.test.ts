@@ -0,0 +1,47 @@
+import { describe, it, expect, vi, afterEach } from 'vitest';
+import { fetchWithRetry, isRetryableStatus, parseRetryAfter, retryDelay, DEFAULT_RETRY_POLICY } from '../providerRetry';
+
+const policy = { ...DEFAULT_RETRY_POLICY, baseDelayMs: 1 };
+
+describe('providerRetry', () => {
+  afterEach(() => {
+    vi.unstubAllGlobals();
+  });
+
+  it('only retries rejected requests when they are not idempotent', () => {
+    expect(isRetryableStatus(429, false)).toBe(true);
+    expect(isRetryableStatus(503, false)).toBe(true);
+    expect(isRetryableStatus(500, false)).toBe(false);
+    expect(isRetryableStatus(500, true)).toBe(true);
+    expect(isRetryableStatus(400, true)).toBe(false);
+  });
+
+  it('honors Retry-After within the limit', () => {
+    expect(parseRetryAfter('3')).toBe(3000);
+    expect(parseRetryAfter('Wed, 21 Oct 2015 07:28:20 GMT', Date.parse('2015-10-21T07:28:00Z'))).toBe(20000);
+    expect(retryDelay(1, 5000)).toBe(5000);
+    expect(retryDelay(1, 120000)).toBeUndefined();
+    expect(retryDelay(3, undefined)).toBeUndefined();
+    expect(retryDelay(2, undefined)).toBeLessThanOrEqual(1000);
+  });
+
+  it('retries until the provider answers', async () => {
+    const fetchMock = vi.fn()
+      .mockResolvedValueOnce(new Response('', { status: 429, headers: { 'Retry-After': '0' } }))
+      .mockResolvedValueOnce(new Response('ok', { status: 200 }));
+    vi.stubGlobal('fetch', fetchMock);
+
+    const response = await fetchWithRetry('https://api.example.com', { method: 'POST' }, { idempotent: false, policy });
+    expect(response.status).toBe(200);
+    expect(fetchMock).toHaveBeenCalledTimes(2);
+  });
+
+  it('does not resend tool-calling requests after a server error', async () => {
+    const fetchMock = vi.fn().mockResolvedValue(new Response('', { status: 500 }));
+    vi.stubGlobal('fetch', fetchMock);
+
+    const response = await fetchWithRetry('https://api.example.com', { method: 'POST' }, { idempotent: false, policy });
+    expect(response.status).toBe(500);
+    expect(fetchMock).toHaveBeenCalledTimes(1);
+  });
+});
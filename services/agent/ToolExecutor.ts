@@ -1,5 +1,5 @@
 
-import { AgentToolCall, ToolResult, AgentChatOptions } from './types';
+import { AgentToolCall, ToolResult, ToolAttachment, AgentChatOptions } from './types';
 import { backendApi } from '../backendApi';
 import * as terminalTools from '../agentTools/terminalTools';
 import * as agentTools from '../agentTools/agentTools';
@@ -34,6 +34,7 @@ export class ToolExecutor {
     try {
       let output: string;
       let success = true;
+      let attachments: ToolAttachment[] | undefined;
 
       // Check specific handlers first
       let result: ToolResult;
@@ -118,9 +119,12 @@ export class ToolExecutor {
             const shellResult = await backendApi.executeShellCommand(
               toolCall.arguments.command,
               toolCall.arguments.workdir,
-              toolCall.arguments.timeout_ms
+              toolCall.arguments.timeout_ms,
+              options.sessionId,
+              options.environmentProfile
             );
-            output = JSON.stringify(shellResult, null, 2);
+            attachments = shellResult.attachments?.length ? shellResult.attachments : undefined;
+            output = JSON.stringify({ ...shellResult, attachments: undefined }, null, 2);
             success = shellResult.success; // Use the actual success from ephemeral shell
           } else {
             const data = shellTermResult.data;
@@ -207,7 +211,11 @@ export class ToolExecutor {
         case 'browse':
           const browseResult = await backendApi.browse(
             toolCall.arguments.url,
-            toolCall.arguments.render
+            toolCall.arguments.render,
+            toolCall.arguments.depth,
+            toolCall.arguments.max_pages,
+            undefined,
+            toolCall.arguments.context
           );
           output = JSON.stringify(browseResult, null, 2);
           success = true;
@@ -279,7 +287,8 @@ export class ToolExecutor {
         toolCallName: toolCall.name,
         success,
         output,
-        durationMs: Date.now() - startTime
+        durationMs: Date.now() - startTime,
+        attachments,
       };
     } catch (error) {
       return {
@@ -341,7 +350,7 @@ export class ToolExecutor {
   }
 
   private isSystemTool(name: string): boolean {
-    return ['analyze_disk_usage', 'get_cleanup_suggestions', 'get_system_info', 'get_storage_breakdown'].includes(name);
+    return ['analyze_disk_usage', 'get_cleanup_suggestions', 'get_system_info', 'list_storage_volumes', 'get_storage_breakdown'].includes(name);
   }
 
   private isMemoryTool(name: string): boolean {
@@ -153,12 +153,15 @@ export const CORE_TOOLS: ToolDefinition[] = [
   },
   {
     name: 'browse',
-    description: `Extract full content from a specific URL. Use this when you need to read a particular article, documentation page, or website in detail.`,
+    description: `Extract full content from a specific URL. Use this when you need to read a particular article, documentation page, or website in detail. The result's structured_data lists products (prices, availability, ratings), recipes, events and articles the page declares; prefer it over the text for such facts.`,
     parameters: {
       type: 'object',
       properties: {
         url: { type: 'string', description: 'URL to browse and extract content from.' },
-        render: { type: 'boolean', description: 'Enable WebView rendering for JavaScript-heavy pages. Default: false' }
+        render: { type: 'boolean', description: 'Enable WebView rendering for JavaScript-heavy pages. Default: false' },
+        depth: { type: 'number', description: 'Also read linked pages in the same site section, this many links deep (0-3). Use 1-2 to read a documentation section. Default: 0' },
+        max_pages: { type: 'number', description: 'Maximum pages to combine when depth > 0 (max 25). Default: 10' },
+        context: { type: 'string', description: 'Name of a browsing context the user logged in with (Settings > Privacy), for pages that require login. Pages are rendered with its cookies.' }
       },
       required: ['url'],
     },
@@ -16,6 +16,20 @@ export interface AgentToolCall {
   _hidden?: boolean; // Internal flag for UI hidden tools
 }
 
+/** A file or image a tool returned for the user to see */
+export interface ToolAttachment {
+  name: string;
+  mime_type: string;
+  path: string;
+  size_bytes: number;
+  /** data: URL of a small image */
+  thumbnail?: string;
+  width?: number;
+  height?: number;
+  /** Set when only part of the file is meant */
+  range?: { offset: number; length: number };
+}
+
 export interface ToolResult {
   toolCallId: string;
   toolCallName?: string;
@@ -23,6 +37,7 @@ export interface ToolResult {
   output: string;
   error?: string;
   durationMs?: number;
+  attachments?: ToolAttachment[];
 }
 
 export interface AgentChatMessage {
@@ -57,12 +72,16 @@ export interface AgentChatOptions {
   provider?: string;
   model?: string;
   temperature?: number;
+  /** Skip the response cache for temperature-0 requests */
+  bypassCache?: boolean;
   maxTokens?: number;
   topP?: number;
   frequencyPenalty?: number;
   presencePenalty?: number;
   customEndpoint?: string;
   workspaceRoot?: string;
+  /** Environment profile shell commands run with */
+  environmentProfile?: string;
   systemPrompt?: string;
   allowedTools?: string[];
   directToolCall?: { name: string; arguments: Record<string, any> };
@@ -9,8 +9,10 @@
 import { apiKeyService } from './apiKeyService';
 import { providerRegistry } from './providerRegistry';
 import { activityLogger } from './activityLogger';
+import { backendApi, AgentTraceEvent } from './backendApi';
+import { withBackendToken } from './backendAuth';
 import { AgentChatOptions, AgentChatResponse, AgentChatMessage, AgentToolCall, ToolResult } from './agent/types';
-import { ToolRegistry } from './agent/ToolRegistry';
+import { AGENT_TOOLS, ToolRegistry } from './agent/ToolRegistry';
 import { ToolExecutor } from './agent/ToolExecutor';
 import { PromptBuilder } from './agent/PromptBuilder';
 
@@ -104,8 +106,20 @@ class AgentChatService {
       // Get Tools
       const tools = ToolRegistry.getToolsForFormat(apiFormat, options.allowedTools);
 
+      this.trace(options.sessionId, {
+        kind: 'model_request',
+        provider,
+        model,
+        // Images are large and already named in the message
+        messages: [...history, ...(message ? [{ role: 'user', content: message }] : [])]
+          .map(({ images, ...rest }: AgentChatMessage) => rest),
+        tools: modelInfo?.capabilities?.toolCalling
+          ? AGENT_TOOLS.map(t => t.name).filter(name => !options.allowedTools?.length || options.allowedTools.includes(name))
+          : [],
+      });
+
       // Delegate to Provider Registry for actual API call
-      return await providerRegistry.chat(
+      const response = await providerRegistry.chat(
         provider,
         model,
         apiKey,
@@ -114,12 +128,23 @@ class AgentChatService {
         systemPrompt,
         modelInfo?.capabilities?.toolCalling ? tools : undefined,
         options.images,
-        options.abortSignal // ADDED
+        options.abortSignal, // ADDED
+        // Only deterministic requests pin the temperature (and become cacheable);
+        // other requests keep the provider defaults
+        { temperature: options.temperature === 0 ? 0 : undefined, bypassCache: options.bypassCache }
       );
 
+      this.trace(options.sessionId, {
+        kind: 'model_response',
+        content: response.content,
+        tool_calls: (response.toolCalls || []).map(({ id, name, arguments: args }) => ({ id, name, arguments: args })),
+      });
+      return response;
+
     } catch (error) {
       const errorMessage = error instanceof Error ? error.message : String(error);
       activityLogger.log('Agent', message.slice(0, 50), `Error: ${errorMessage}`, 'error');
+      this.trace(options.sessionId, { kind: 'error', message: errorMessage });
       throw error;
     }
   }
@@ -162,7 +187,7 @@ class AgentChatService {
       allToolResults.push(result);
       options.onToolComplete?.(result);
       
-      this.collectImages(toolCall, displayImages);
+      this.collectImages(toolCall, displayImages, result);
       this.collectGeneratedFiles(toolCall, allGeneratedFiles);
       
       return {
@@ -243,7 +268,7 @@ class AgentChatService {
         allToolResults.push(result);
         options.onToolComplete?.(result);
         
-        this.collectImages(toolCall, displayImages);
+        this.collectImages(toolCall, displayImages, result);
         this.collectGeneratedFiles(toolCall, allGeneratedFiles);
 
         // Add tool result to history
@@ -268,12 +293,39 @@ class AgentChatService {
     };
   }
 
+  /**
+   * Record model traffic in the session's trace; tool calls run by the
+   * backend are recorded there directly
+   */
+  private trace(sessionId: string | undefined, event: AgentTraceEvent) {
+    if (!sessionId) return;
+    backendApi.appendAgentTrace(sessionId, [event]).catch((error) => {
+      console.debug('[AgentChatService] Failed to record trace:', error);
+    });
+  }
+
   private async getActiveProvider(): Promise<string> {
     const provider = await apiKeyService.getActiveProvider();
     return provider || 'openai'; // Default fallback
   }
 
-  private collectImages(toolCall: AgentToolCall, displayImages: Array<{ url: string; alt?: string; fileName?: string }>) {
+  private collectImages(
+    toolCall: AgentToolCall,
+    displayImages: Array<{ url: string; alt?: string; fileName?: string }>,
+    result?: ToolResult
+  ) {
+    // Images a tool attached to its result
+    result?.attachments
+      ?.filter((attachment) => attachment.mime_type.startsWith('image/'))
+      .forEach((attachment) => {
+        displayImages.push({
+          url: attachment.thumbnail
+            || withBackendToken(`http://127.0.0.1:3001/api/v1/files/image?path=${encodeURIComponent(attachment.path)}`),
+          alt: attachment.name,
+          fileName: attachment.name,
+        });
+      });
+
     // Collect images from web search results
     if (toolCall.name === 'web_search' && (toolCall as any)._webSearchImages) {
       const images = (toolCall as any)._webSearchImages;
@@ -37,6 +37,15 @@ export const systemToolDefinitions = [
       required: [],
     },
   },
+  {
+    name: 'list_storage_volumes',
+    description: 'List mounted drives and volumes with capacity, free space, filesystem type, whether they are removable, and SMART health where available.',
+    parameters: {
+      type: 'object',
+      properties: {},
+      required: [],
+    },
+  },
   {
     name: 'get_storage_breakdown',
     description: 'Get storage usage broken down by category (e.g., Documents, Images, Code, etc.).',
@@ -79,6 +88,10 @@ export async function executeSystemTool(
         // For now, disk info is the most "system" related structured data we have
         return { success: true, data: { disks: diskInfo } };
 
+      case 'list_storage_volumes':
+        const volumes = await backendApi.getDiskVolumes();
+        return { success: true, data: volumes };
+
       case 'get_storage_breakdown':
         const breakdown = await backendApi.getStorageCategories({
           path: args.path
@@ -8,6 +8,8 @@
 import { apiKeyService } from './apiKeyService';
 import { tokenTrackingService } from './tokenTrackingService';
 import { providerRegistry } from './providerRegistry';
+import { aiSettingsService } from './aiSettingsService';
+import { backendApi } from './backendApi';
 import { fileSearchService } from './search/FileSearchService';
 import { AgentChatMessage } from './agent/types';
 
@@ -117,6 +119,26 @@ const TOOLS_ANTHROPIC = [
   },
 ];
 
+/**
+ * HTTP status of a failed provider request ("OpenAI API error: 429 - ...")
+ */
+function getErrorStatus(error: unknown): number | undefined {
+  const match = error instanceof Error ? error.message.match(/API error: (\d{3})/) : null;
+  return match ? parseInt(match[1], 10) : undefined;
+}
+
+/**
+ * Whether another provider might succeed: network errors, timeouts,
+ * rate limits and server errors. Bad keys or requests would fail anywhere.
+ */
+function isRetryableError(error: unknown, statusCode?: number): boolean {
+  if (statusCode === undefined) {
+    // fetch() rejects with a TypeError when the provider is unreachable
+    return error instanceof TypeError;
+  }
+  return statusCode === 408 || statusCode === 429 || statusCode >= 500;
+}
+
 class AIService {
   private config: AIServiceConfig = {};
 
@@ -202,159 +224,229 @@ Be concise, friendly, and helpful.`;
       };
     }
 
-    onStatusUpdate?.(`Using ${provider}...`);
+    const fallbackProvider = aiSettingsService.loadSettings().fallbackProvider as AIProvider | undefined;
+    const candidates = await this.planProviders(provider, fallbackProvider);
 
-    try {
-      const apiKey = await apiKeyService.loadKey(provider);
-      const savedModel = await apiKeyService.loadModel(provider);
-      const providerConfig = providerRegistry.getProvider(provider);
-      const model = savedModel || this.config.customModel || providerConfig?.defaultModel || 'default';
-
-      // Set custom endpoint if needed
-      if (this.config.customEndpoint && provider === 'custom') {
-        providerRegistry.setCustomEndpoint(provider, this.config.customEndpoint);
+    let lastProvider: AIProvider = provider;
+    let lastError: unknown;
+    let failedProvider: AIProvider | null = null;
+
+    for (const candidate of candidates) {
+      if (candidate !== provider && !(await this.hasApiKey(candidate))) {
+        continue;
+      }
+      if (failedProvider) {
+        this.notifyFailover(failedProvider, candidate, lastError, onStatusUpdate);
+      } else {
+        onStatusUpdate?.(`Using ${candidate}...`);
+      }
+
+      const start = Date.now();
+      try {
+        const result = await this.chatWithProvider(candidate, message, history, onStatusUpdate, images, chatId, messageId);
+        backendApi.reportProviderOutcome(candidate, true, Date.now() - start).catch(() => {});
+        return result;
+      } catch (error) {
+        console.error(`[AIService] Chat failed with ${candidate}:`, error);
+        const statusCode = getErrorStatus(error);
+        backendApi.reportProviderOutcome(candidate, false, Date.now() - start, statusCode).catch(() => {});
+
+        lastProvider = candidate;
+        lastError = error;
+        if (!isRetryableError(error, statusCode)) {
+          break;
+        }
+        failedProvider = candidate;
       }
+    }
+
+    return {
+      text: `❌ Error with ${lastProvider}: ${lastError instanceof Error ? lastError.message : 'Unknown error'}`,
+      type: 'error',
+      provider: lastProvider,
+    };
+  }
+
+  /**
+   * Order in which to try providers: the active one, then the configured fallback.
+   * The backend moves providers it has seen failing to the end.
+   */
+  private async planProviders(provider: AIProvider, fallback?: AIProvider): Promise<AIProvider[]> {
+    const fallbacks = fallback && fallback !== provider ? [fallback] : [];
+    try {
+      return (await backendApi.planFailover(provider, fallbacks)) as AIProvider[];
+    } catch {
+      return [provider, ...fallbacks];
+    }
+  }
+
+  /**
+   * Tell the user (and any listening UI) that another provider is taking over
+   */
+  private notifyFailover(from: AIProvider, to: AIProvider, error: unknown, onStatusUpdate?: (status: string) => void): void {
+    const reason = error instanceof Error ? error.message : 'Unknown error';
+    const toName = providerRegistry.getProvider(to)?.name || to;
+    console.warn(`[AIService] Switching from ${from} to ${to}: ${reason}`);
+    onStatusUpdate?.(`Switched to ${toName}`);
+    if (typeof window !== 'undefined') {
+      window.dispatchEvent(new CustomEvent('ai-provider-failover', { detail: { from, to, reason } }));
+    }
+    backendApi.recordFailover(from, to, reason).catch(() => {});
+  }
 
-      const systemPrompt = this.getSystemPrompt(provider, model);
-
-      // Convert history to AgentChatMessage format
-      const agentHistory: AgentChatMessage[] = history.map(m => ({
-        role: m.role as 'user' | 'assistant' | 'system',
-        content: m.content,
-        thought: (m as any).thought, // Preserve thought if present
-        // Map images if they exist in history
-        images: m.images
-      }));
-
-      // Determine tools to use based on provider format
-      const apiFormat = providerRegistry.getApiFormat(provider);
-      let tools: any;
-      if (apiFormat === 'openai' || apiFormat === 'ollama') {
-        tools = TOOLS_OPENAI;
-      } else if (apiFormat === 'anthropic') {
-        tools = TOOLS_ANTHROPIC;
-      } else if (apiFormat === 'google') {
-        // Gemini specific tool format
-        tools = [{
-          functionDeclarations: [
-            {
-              name: 'findFile',
-              description: 'Find a file on the user computer using natural language keywords.',
-              parameters: {
-                type: 'OBJECT',
-                properties: {
-                  query: { type: 'STRING', description: 'File name or keywords.' },
-                  file_types: { type: 'STRING', description: 'Optional comma-separated extensions.' },
-                  search_path: { type: 'STRING', description: 'Optional folder path.' }
-                },
-                required: ['query'],
+  /**
+   * Run a chat request against one provider. Throws when the request fails.
+   */
+  private async chatWithProvider(
+    provider: AIProvider,
+    message: string,
+    history: AIMessage[],
+    onStatusUpdate?: (status: string) => void,
+    images?: Array<{ fileName: string; base64: string; mimeType: string }>,
+    chatId?: string,
+    messageId?: string
+  ): Promise<AIResponse> {
+    const apiKey = await apiKeyService.loadKey(provider);
+    const savedModel = await apiKeyService.loadModel(provider);
+    const providerConfig = providerRegistry.getProvider(provider);
+    const model = savedModel || this.config.customModel || providerConfig?.defaultModel || 'default';
+
+    // Set custom endpoint if needed
+    if (this.config.customEndpoint && provider === 'custom') {
+      providerRegistry.setCustomEndpoint(provider, this.config.customEndpoint);
+    }
+
+    const systemPrompt = this.getSystemPrompt(provider, model);
+
+    // Convert history to AgentChatMessage format
+    const agentHistory: AgentChatMessage[] = history.map(m => ({
+      role: m.role as 'user' | 'assistant' | 'system',
+      content: m.content,
+      thought: (m as any).thought, // Preserve thought if present
+      // Map images if they exist in history
+      images: m.images
+    }));
+
+    // Determine tools to use based on provider format
+    const apiFormat = providerRegistry.getApiFormat(provider);
+    let tools: any;
+    if (apiFormat === 'openai' || apiFormat === 'ollama') {
+      tools = TOOLS_OPENAI;
+    } else if (apiFormat === 'anthropic') {
+      tools = TOOLS_ANTHROPIC;
+    } else if (apiFormat === 'google') {
+      // Gemini specific tool format
+      tools = [{
+        functionDeclarations: [
+          {
+            name: 'findFile',
+            description: 'Find a file on the user computer using natural language keywords.',
+            parameters: {
+              type: 'OBJECT',
+              properties: {
+                query: { type: 'STRING', description: 'File name or keywords.' },
+                file_types: { type: 'STRING', description: 'Optional comma-separated extensions.' },
+                search_path: { type: 'STRING', description: 'Optional folder path.' }
               },
+              required: ['query'],
             },
-            {
-              name: 'searchContent',
-              description: 'Search inside file contents for specific text or patterns.',
-              parameters: {
-                type: 'OBJECT',
-                properties: {
-                  query: { type: 'STRING', description: 'Text to search for.' },
-                  file_types: { type: 'STRING', description: 'Optional extensions.' },
-                  search_path: { type: 'STRING', description: 'Optional folder path.' }
-                },
-                required: ['query'],
+          },
+          {
+            name: 'searchContent',
+            description: 'Search inside file contents for specific text or patterns.',
+            parameters: {
+              type: 'OBJECT',
+              properties: {
+                query: { type: 'STRING', description: 'Text to search for.' },
+                file_types: { type: 'STRING', description: 'Optional extensions.' },
+                search_path: { type: 'STRING', description: 'Optional folder path.' }
               },
+              required: ['query'],
             },
-          ]
-        }];
-      }
-
-      // Execute chat request via ProviderRegistry
-      const response = await providerRegistry.chat(
-        provider,
-        model,
-        apiKey,
-        message,
-        agentHistory,
-        systemPrompt,
-        tools,
-        images
-      );
-
-      // Handle tool calls
-      if (response.toolCalls && response.toolCalls.length > 0) {
-        const toolCall = response.toolCalls[0]; // Handle first tool call for simple chat
-        let toolResult: SearchResponse | undefined;
-
-        if (toolCall.name === 'findFile') {
-          toolResult = await fileSearchService.findFile(
-            toolCall.arguments, 
-            onStatusUpdate,
-            { provider, apiKey, model, userMessage: message },
-            { chatId, messageId }
-          );
-        } else if (toolCall.name === 'searchContent') {
-          toolResult = await fileSearchService.searchContent(
-            toolCall.arguments,
-            onStatusUpdate,
-            { chatId, messageId }
-          );
-        }
+          },
+        ]
+      }];
+    }
 
-        if (toolResult && toolResult.type === 'file_list' && toolResult.data) {
-          // Send tool output back to AI for summarization
-          // We need to format the tool response message based on provider
-          // But for now, we'll just ask the AI to summarize using a new request context to keep it simple
-          // leveraging the existing connection logic in providerRegistry
-          
-          const summaryPrompt = `I found ${toolResult.data.length} files. Please summarize these results for the user.`;
-          
-          // Add tool result to history conceptually
-          const summaryHistory = [...agentHistory];
-          summaryHistory.push({ role: 'assistant', content: '', toolCalls: [toolCall] });
-          summaryHistory.push({ role: 'tool', toolCallId: toolCall.id, content: JSON.stringify(toolResult), toolCallName: toolCall.name });
-
-          const summaryResponse = await providerRegistry.chat(
-            provider,
-            model,
-            apiKey,
-            summaryPrompt,
-            summaryHistory,
-            systemPrompt
-          );
-
-          return {
-            text: summaryResponse.content,
-            type: 'file_list',
-            data: toolResult.data,
-            provider: provider as AIProvider,
-            model,
-            searchInfo: toolResult.searchInfo,
-          };
-        }
+    // Execute chat request via ProviderRegistry
+    const response = await providerRegistry.chat(
+      provider,
+      model,
+      apiKey,
+      message,
+      agentHistory,
+      systemPrompt,
+      tools,
+      images
+    );
+
+    // Handle tool calls
+    if (response.toolCalls && response.toolCalls.length > 0) {
+      const toolCall = response.toolCalls[0]; // Handle first tool call for simple chat
+      let toolResult: SearchResponse | undefined;
+
+      if (toolCall.name === 'findFile') {
+        toolResult = await fileSearchService.findFile(
+          toolCall.arguments, 
+          onStatusUpdate,
+          { provider, apiKey, model, userMessage: message },
+          { chatId, messageId }
+        );
+      } else if (toolCall.name === 'searchContent') {
+        toolResult = await fileSearchService.searchContent(
+          toolCall.arguments,
+          onStatusUpdate,
+          { chatId, messageId }
+        );
       }
 
-      // Track token usage
-      // Note: providerRegistry chat method doesn't return usage directly yet, 
-      // we might need to enhance AgentChatResponse or rely on internal logging in providerRegistry
-      // For now, we estimate based on text length if needed
-      tokenTrackingService.setCurrentModel(provider, model);
-      tokenTrackingService.recordUsage(0, 0, model, provider, message, response.content);
-
-      return {
-        text: response.content,
-        thought: response.thought, // Include thought in result
-        type: 'text',
-        provider: provider as AIProvider,
-        model,
-      } as any;
-
-    } catch (error) {
-      console.error(`[AIService] Chat failed with ${provider}:`, error);
-      return {
-        text: `❌ Error with ${provider}: ${error instanceof Error ? error.message : 'Unknown error'}`,
-        type: 'error',
-        provider,
-      };
+      if (toolResult && toolResult.type === 'file_list' && toolResult.data) {
+        // Send tool output back to AI for summarization
+        // We need to format the tool response message based on provider
+        // But for now, we'll just ask the AI to summarize using a new request context to keep it simple
+        // leveraging the existing connection logic in providerRegistry
+        
+        const summaryPrompt = `I found ${toolResult.data.length} files. Please summarize these results for the user.`;
+        
+        // Add tool result to history conceptually
+        const summaryHistory = [...agentHistory];
+        summaryHistory.push({ role: 'assistant', content: '', toolCalls: [toolCall] });
+        summaryHistory.push({ role: 'tool', toolCallId: toolCall.id, content: JSON.stringify(toolResult), toolCallName: toolCall.name });
+
+        const summaryResponse = await providerRegistry.chat(
+          provider,
+          model,
+          apiKey,
+          summaryPrompt,
+          summaryHistory,
+          systemPrompt
+        );
+
+        return {
+          text: summaryResponse.content,
+          type: 'file_list',
+          data: toolResult.data,
+          provider: provider as AIProvider,
+          model,
+          searchInfo: toolResult.searchInfo,
+        };
+      }
     }
+
+    // Track token usage
+    // Note: providerRegistry chat method doesn't return usage directly yet, 
+    // we might need to enhance AgentChatResponse or rely on internal logging in providerRegistry
+    // For now, we estimate based on text length if needed
+    tokenTrackingService.setCurrentModel(provider, model);
+    tokenTrackingService.recordUsage(0, 0, model, provider, message, response.content);
+
+    return {
+      text: response.content,
+      thought: response.thought, // Include thought in result
+      type: 'text',
+      provider: provider as AIProvider,
+      model,
+    } as any;
   }
 
   getModelsForProvider(provider: AIProvider): string[] {
@@ -10,6 +10,8 @@ export interface AISettings {
   frequencyPenalty: number;
   presencePenalty: number;
   userInstructions?: string;
+  /** Provider to retry chat requests with when the active one is down */
+  fallbackProvider?: string;
 }
 
 const STORAGE_KEYS = {
@@ -19,6 +21,7 @@ const STORAGE_KEYS = {
   frequencyPenalty: 'skhoot_ai_frequency_penalty',
   presencePenalty: 'skhoot_ai_presence_penalty',
   userInstructions: 'skhoot_user_instructions',
+  fallbackProvider: 'skhoot_ai_fallback_provider',
 };
 
 const DEFAULT_SETTINGS: AISettings = {
@@ -28,6 +31,7 @@ const DEFAULT_SETTINGS: AISettings = {
   frequencyPenalty: 0,
   presencePenalty: 0,
   userInstructions: undefined,
+  fallbackProvider: undefined,
 };
 
 class AISettingsService {
@@ -43,6 +47,7 @@ class AISettingsService {
         frequencyPenalty: parseFloat(localStorage.getItem(STORAGE_KEYS.frequencyPenalty) || String(DEFAULT_SETTINGS.frequencyPenalty)),
         presencePenalty: parseFloat(localStorage.getItem(STORAGE_KEYS.presencePenalty) || String(DEFAULT_SETTINGS.presencePenalty)),
         userInstructions: localStorage.getItem(STORAGE_KEYS.userInstructions) || undefined,
+        fallbackProvider: localStorage.getItem(STORAGE_KEYS.fallbackProvider) || undefined,
       };
     } catch (error) {
       console.warn('[AISettingsService] Failed to load settings, using defaults:', error);
@@ -1,6 +1,6 @@
 /**
   * Secure API Key Service
- * Communicates with Tauri backend for encrypted key storage
+ * Communicates with Tauri backend for key storage in the OS keychain
  * Falls back to localStorage when Tauri is not available (web dev mode)
  */
 
@@ -249,6 +249,17 @@ class APIKeyService {
     console.log(`[ApiKeyService] Set active provider to ${provider} (localStorage)`);
   }
 
+  /**
+   * Export all stored keys as plaintext JSON to a file (Tauri only)
+   * Returns the number of exported keys
+   */
+  async exportKeys(path: string): Promise<number> {
+    if (!(await this.ensureTauri())) {
+      throw new Error('Exporting keys requires the desktop app');
+    }
+    return await this.tauriInvoke('export_api_keys', { path });
+  }
+
   /**
    * Test an API key and fetch available models
    */
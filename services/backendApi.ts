@@ -1,3 +1,6 @@
+import type { ToolAttachment } from './agent/types';
+import { loadBackendToken, withBackendToken } from './backendAuth';
+
 const BACKEND_URL = 'http://127.0.0.1:3001';
 
 export interface HealthResponse {
@@ -12,6 +15,286 @@ export interface ProviderInfo {
   models: string[];
 }
 
+export type ProviderStatus = 'healthy' | 'degraded' | 'down';
+
+export interface ProviderHealthReport {
+  provider: string;
+  status: ProviderStatus;
+  requests: number;
+  error_rate: number;
+  avg_latency_ms?: number | null;
+  consecutive_failures: number;
+  last_error_status?: number | null;
+  retry_after_secs?: number | null;
+}
+
+export interface FailoverEvent {
+  id: number;
+  from: string;
+  to: string;
+  reason: string;
+  at: string;
+}
+
+export interface AIResponseCacheRequest {
+  model: string;
+  system_prompt: string;
+  messages: unknown[];
+  tools?: unknown;
+  temperature: number;
+  bypass_cache?: boolean;
+}
+
+export interface AIResponseCacheStats {
+  enabled: boolean;
+  entries: number;
+  size_bytes: number;
+  hits: number;
+  misses: number;
+  max_entries: number;
+  max_size_bytes: number;
+  ttl_secs: number;
+}
+
+export interface DatabaseMigration {
+  version: number;
+  description: string;
+  state: 'applied' | 'pending' | 'modified' | 'failed' | 'unknown';
+  installed_on?: string;
+  execution_time_ms?: number;
+}
+
+export interface DatabaseStatus {
+  database_path?: string;
+  current_version: number | null;
+  latest_version: number | null;
+  migrations: DatabaseMigration[];
+  /** `["ok"]` when the integrity check passed */
+  integrity: string[];
+  backups: { path: string; size_bytes: number; created_at: string }[];
+}
+
+export interface VaultStatus {
+  enabled: boolean;
+  unlocked: boolean;
+}
+
+export interface LocalApiClientPermissions {
+  /** Tools offered to the model; defaults to read-only tools */
+  tools: string[];
+  allow_writes: boolean;
+  /** File tools are confined to this directory, and commands run in it */
+  workspace_root?: string | null;
+  allowed_http_domains: string[];
+}
+
+export interface LocalApiClient {
+  id: string;
+  name: string;
+  /** Last characters of the token */
+  token_hint: string;
+  provider: 'openai' | 'anthropic' | 'google';
+  model?: string | null;
+  permissions: LocalApiClientPermissions;
+  enabled: boolean;
+  created_at: number;
+  last_used_at?: number | null;
+}
+
+export interface RunSummary {
+  runs: number;
+  completed: number;
+  failed: number;
+  cancelled: number;
+  active: number;
+  /** Completed share of runs that completed or failed */
+  success_rate: number | null;
+  avg_duration_secs: number | null;
+  last_run_at: number | null;
+}
+
+export interface WorkflowRunStats extends RunSummary {
+  days: number;
+  /** Every UTC day of the window, oldest first */
+  runs_per_day: { date: string; total: number; completed: number; failed: number; cancelled: number }[];
+  workflows: (RunSummary & { workflow_id: string; name: string | null })[];
+}
+
+export interface ToolUsage {
+  tool: string;
+  calls: number;
+  failures: number;
+  avg_duration_ms: number | null;
+}
+
+export interface AgentRunStats {
+  days: number;
+  tool_calls: number;
+  tool_failures: number;
+  model_requests: number;
+  activity_per_day: { date: string; tool_calls: number; model_requests: number; active_agents: number }[];
+  agents: {
+    agent_id: string;
+    tool_calls: number;
+    tool_failures: number;
+    model_requests: number;
+    errors: number;
+    last_active_at: string | null;
+    top_tools: ToolUsage[];
+  }[];
+  tools: ToolUsage[];
+}
+
+export interface ConversationSession {
+  session_id: string;
+  agent_id: string | null;
+  message_count: number;
+  first_message_at: string;
+  last_message_at: string;
+}
+
+export interface ConversationTranscript {
+  session_id: string;
+  /** Message text is hidden because the vault is locked */
+  locked: boolean;
+  messages: {
+    id: string;
+    role: string;
+    agent_id?: string;
+    content: string | null;
+    tool_calls: string | null;
+    created_at: string;
+  }[];
+}
+
+export type BackendConfigSection = 'server' | 'search' | 'security' | 'cache' | 'providers' | 'notifications' | 'hotkey' | 'transcription' | 'mcp' | 'lsp' | 'plugins' | 'tool_limits' | 'traces' | 'environments' | 'local_api';
+
+export interface BackendSettings {
+  server: { host: string; port: number };
+  search: {
+    default_mode: 'rust' | 'cli' | 'hybrid' | 'auto';
+    max_results: number;
+    learn_from_clicks: boolean;
+    /** Named sets of directories searched together */
+    workspaces: Array<{ name: string; roots: string[] }>;
+    default_workspace: string | null;
+    /** Index filenames under home and the workspaces for instant search */
+    filename_index: boolean;
+  };
+  security: {
+    allowed_origins: string[];
+    unmatched_command_action: 'allow' | 'deny' | 'require_confirmation';
+    redact_secrets: boolean;
+  };
+  cache: {
+    web_max_mb: number;
+    web_ttl_secs: number;
+    ai_response_enabled: boolean;
+    ai_response_max_entries: number;
+    ai_response_ttl_secs: number;
+  };
+  providers: {
+    fallbacks: string[];
+    error_rate_threshold: number;
+    down_after_failures: number;
+    cooldown_secs: number;
+  };
+  notifications: {
+    enabled: boolean;
+    workflow_completed: boolean;
+    workflow_failed: boolean;
+    agent_completed: boolean;
+    agent_failed: boolean;
+    quota_exceeded: boolean;
+    /** Local time ("HH:MM"); both ends must be set for quiet hours to apply */
+    quiet_hours_start?: string | null;
+    quiet_hours_end?: string | null;
+  };
+  hotkey: {
+    enabled: boolean;
+    /** Accelerator such as "CommandOrControl+Shift+Space" */
+    binding: string;
+    action: 'show' | 'new_conversation' | 'toggle_voice';
+  };
+  transcription: {
+    engine: 'local' | 'provider' | 'auto';
+    /** whisper.cpp model file; defaults to ~/.skhoot/models/ggml-base.bin */
+    model_path?: string | null;
+    /** 0 picks one thread per core, up to 8 */
+    threads: number;
+  };
+  mcp: {
+    servers: McpServerConfig[];
+  };
+  lsp: {
+    enabled: boolean;
+    /** Replace the built-in server for the same language */
+    servers: LspServerConfig[];
+  };
+  tool_limits: {
+    /** Tool calls of one session that run at once */
+    max_concurrent: number;
+    /** 0 means unlimited */
+    default_per_minute: number;
+    /** By tool name; MCP and plugin tools count as 'mcp' and 'plugin' */
+    per_minute: Record<string, number>;
+    max_wait_secs: number;
+    /** Identical calls in a row that pause the session; 0 disables */
+    loop_threshold: number;
+  };
+  plugins: {
+    enabled: boolean;
+    /** Directory of plugin folders; defaults to ~/.skhoot/plugins */
+    directory?: string | null;
+    /** Let plugins that declare write permission run at all */
+    allow_write: boolean;
+    /** Most a plugin's manifest may ask for; lower limits are kept */
+    max_limits: {
+      timeout_secs: number;
+      max_cpu_secs?: number | null;
+      max_memory_mb?: number | null;
+      max_output_kb: number;
+    };
+  };
+  traces: {
+    /** Record a trace file for every agent session */
+    enabled: boolean;
+    /** Keep only the size of file contents in traces */
+    redact_file_contents: boolean;
+  };
+  environments: {
+    /** Profile used when an agent session or terminal doesn't pick one */
+    default_profile?: string | null;
+    profiles: EnvironmentProfile[];
+  };
+  local_api: {
+    /** Serve the OpenAI-compatible /v1/chat/completions endpoint */
+    enabled: boolean;
+    /** Model requests per completion that may call tools */
+    max_tool_rounds: number;
+    tool_timeout_secs: number;
+  };
+}
+
+export interface EnvironmentProfile {
+  name: string;
+  env: Record<string, string>;
+  /** Directories put in front of PATH, in order */
+  path_prepend: string[];
+  /** Python virtualenv to activate */
+  virtualenv?: string | null;
+  /** Working directory for commands that don't name one */
+  cwd?: string | null;
+  shell?: string | null;
+}
+
+export interface BackendConfigResponse {
+  path: string;
+  settings: BackendSettings;
+  error?: string | null;
+  restart_required: boolean;
+}
+
 export interface SearchResult {
   file: {
     id: string;
@@ -55,6 +338,7 @@ export interface FileSearchResults {
     search_time_ms: number;
     query: string;
     truncated: boolean;
+    skipped_mounts?: SkippedMount[];
   };
   cli_results?: {
     files: Array<{
@@ -76,6 +360,9 @@ export interface FileSearchResults {
     modified?: string;
     snippet?: string;
     line_number?: number;
+    metadata?: FileMetadata;
+    /** Search root the result was found under, for multi-root searches */
+    root?: string;
   }>;
   total_execution_time_ms: number;
   suggestions: Array<{
@@ -83,6 +370,124 @@ export interface FileSearchResults {
     reason: string;
     confidence: number;
   }>;
+  /** Per-root summary of a multi-root search */
+  roots?: Array<{
+    root: string;
+    result_count: number;
+    execution_time_ms: number;
+    error?: string;
+  }>;
+}
+
+/** Results one engine found under a root, sent while a search streams */
+export interface SearchResultBatch {
+  root: string;
+  /** 'cli' or 'fuzzy' for hybrid searches, else the search mode */
+  engine: string;
+  results: FileSearchResults['merged_results'];
+  elapsed_ms: number;
+  error?: string;
+}
+
+export interface AiFileSearchOptions {
+  mode?: 'rust' | 'cli' | 'hybrid' | 'auto';
+  max_results?: number;
+  include_indices?: boolean;
+  file_types?: string;
+  exclude_dirs?: string;
+  search_path?: string;  // Custom search path (defaults to user home)
+  search_paths?: string[]; // Several roots, searched concurrently and merged
+  workspace?: string;    // Named set of roots from search settings
+  unrestricted?: boolean; // Enable deep search (hidden files, ignore .gitignore)
+  include_metadata?: boolean; // Read image/media/document metadata for results
+  respect_gitignore?: boolean; // Override whether .gitignore rules apply
+  respect_skhootignore?: boolean; // Override whether .skhootignore rules apply
+  include_network_mounts?: boolean; // Descend into network shares
+  include_removable_mounts?: boolean; // Descend into removable drives
+}
+
+function fileSearchParams(query: string, options?: AiFileSearchOptions): URLSearchParams {
+  const params = new URLSearchParams({ q: query });
+
+  if (options?.mode) params.append('mode', options.mode);
+  if (options?.max_results) params.append('max_results', options.max_results.toString());
+  if (options?.include_indices) params.append('include_indices', 'true');
+  if (options?.file_types) params.append('file_types', options.file_types);
+  if (options?.exclude_dirs) params.append('exclude_dirs', options.exclude_dirs);
+  if (options?.search_path) params.append('search_path', options.search_path);
+  if (options?.search_paths?.length) params.append('search_paths', options.search_paths.join(','));
+  if (options?.workspace) params.append('workspace', options.workspace);
+  if (options?.unrestricted) params.append('unrestricted', 'true');
+  if (options?.include_metadata) params.append('include_metadata', 'true');
+  if (options?.respect_gitignore !== undefined) params.append('respect_gitignore', String(options.respect_gitignore));
+  if (options?.respect_skhootignore !== undefined) params.append('respect_skhootignore', String(options.respect_skhootignore));
+  if (options?.include_network_mounts) params.append('include_network_mounts', 'true');
+  if (options?.include_removable_mounts) params.append('include_removable_mounts', 'true');
+  return params;
+}
+
+export type FileMetadata =
+  | { kind: 'image'; width: number; height: number; exif?: Record<string, string> }
+  | {
+      kind: 'media';
+      duration_seconds?: number;
+      video_codec?: string;
+      audio_codec?: string;
+      width?: number;
+      height?: number;
+    }
+  | { kind: 'document'; page_count?: number; author?: string; title?: string };
+
+export type PreviewTokenKind =
+  | 'comment' | 'string' | 'number' | 'constant' | 'keyword' | 'operator'
+  | 'function' | 'type' | 'tag' | 'attribute' | 'variable' | 'punctuation';
+
+export interface FilePreviewLine {
+  number: number;
+  text: string;
+  /** Character ranges with a syntax class */
+  tokens?: Array<{ start: number; end: number; kind: PreviewTokenKind }>;
+  /** Character ranges matching the query */
+  matches?: Array<{ start: number; end: number }>;
+  truncated?: boolean;
+}
+
+export interface FilePreview {
+  path: string;
+  language: string | null;
+  focus_line: number | null;
+  lines: FilePreviewLine[];
+  has_more: boolean;
+}
+
+export interface DiffLine {
+  kind: 'context' | 'added' | 'removed';
+  old_line?: number;
+  new_line?: number;
+  segments: { text: string; changed: boolean }[];
+}
+
+export interface DiffHunk {
+  old_start: number;
+  old_lines: number;
+  new_start: number;
+  new_lines: number;
+  lines: DiffLine[];
+}
+
+/** Differences between a file and another file or proposed content */
+export interface FileDiff {
+  old_path: string;
+  new_path?: string;
+  old_exists: boolean;
+  binary: boolean;
+  identical: boolean;
+  old_size: number;
+  new_size: number;
+  additions: number;
+  deletions: number;
+  unified_diff?: string;
+  hunks?: DiffHunk[];
 }
 
 export interface SearchSuggestionRequest {
@@ -118,6 +523,101 @@ export interface DiskInfoItem {
   disk_type: string; // "internal", "external", "network"
 }
 
+export interface SearchRankingWeight {
+  key: string;
+  /** Clicks after decay */
+  clicks: number;
+  last_click: string;
+  /** Score added to results this weight applies to */
+  boost: number;
+}
+
+export interface SearchRankingReport {
+  total_clicks: number;
+  extensions: SearchRankingWeight[];
+  directories: SearchRankingWeight[];
+}
+
+export interface FilenameIndexStatus {
+  /** Off when the search settings disable the index */
+  enabled: boolean;
+  roots: Array<{
+    root: string;
+    files: number;
+    reconciled_at: string;
+    /** The root held more files than the index keeps; searches there walk */
+    truncated: boolean;
+  }>;
+  files: number;
+  reconciling: boolean;
+}
+
+export interface VolumeInfo {
+  name: string;
+  mount_point: string;
+  device: string;
+  /** e.g. ext4, apfs, NTFS; empty when unknown */
+  file_system: string;
+  total_bytes: number;
+  used_bytes: number;
+  free_bytes: number;
+  usage_percentage: number;
+  removable: boolean;
+  /** null when SMART data is unavailable on this platform or without privileges */
+  smart_status: 'passed' | 'failing' | null;
+}
+
+export interface DiskVolumesResponse {
+  volumes: VolumeInfo[];
+  total_bytes: number;
+  free_bytes: number;
+}
+
+/** A running process; cpu_percent of 100 is one core kept busy */
+export interface ProcessInfo {
+  pid: number;
+  parent_pid: number | null;
+  name: string;
+  cmdline: string;
+  user: string | null;
+  state: string | null;
+  cpu_percent: number;
+  memory_bytes: number;
+  memory_percent: number;
+  /** Started by Skhoot, directly or not; can be stopped without confirmation */
+  child: boolean;
+}
+
+export interface ProcessList {
+  processes: ProcessInfo[];
+  /** Processes matching the filter, before the limit */
+  matched: number;
+  total: number;
+  cpu_count: number;
+  total_memory_bytes: number;
+}
+
+export interface ProcessDetails extends ProcessInfo {
+  executable: string | null;
+  working_directory: string | null;
+  threads: number | null;
+  children: ProcessInfo[];
+}
+
+export interface KillOutcome {
+  pid: number;
+  name: string;
+  signal: 'SIGTERM' | 'SIGKILL';
+  child: boolean;
+}
+
+export interface SkippedMount {
+  mount_point: string;
+  kind: 'local' | 'network' | 'removable' | 'virtual';
+  /** 'excluded' when not opted in, 'unresponsive' when it timed out */
+  reason: 'excluded' | 'unresponsive';
+}
+
 export interface DiskAnalysisResponse {
   total_size: number;
   total_size_formatted: string;
@@ -125,6 +625,7 @@ export interface DiskAnalysisResponse {
   dir_count: number;
   top_consumers: SpaceConsumer[];
   analysis_time_ms: number;
+  skipped_mounts: SkippedMount[];
 }
 
 export interface SpaceConsumer {
@@ -153,6 +654,8 @@ export interface CleanupSuggestion {
   description: string;
   consequence: string;
   last_accessed?: string;
+  /** Shell command that reclaims the space safely */
+  cleanup_command?: string;
 }
 
 export interface StorageCategoriesResponse {
@@ -170,394 +673,2932 @@ export interface StorageCategory {
   color: string;
 }
 
-// ============================================================================
-// Web Search Types
-// ============================================================================
+export interface TreemapNode {
+  path: string;
+  name: string;
+  /** 'other' nodes hold the children pruned by top_n */
+  kind: 'directory' | 'file' | 'other';
+  size: number;
+  file_count: number;
+  /** Category taking up the most space in the node */
+  category: string;
+  children?: TreemapNode[];
+}
 
-export interface WebSearchResult {
-  title: string;
-  url: string;
-  snippet: string;
-  published_date?: string;
-  relevance_score: number;
-  image_url?: string;
+export interface TreemapScanProgress {
+  scan_id: string;
+  status: 'scanning' | 'complete' | 'cancelled' | 'failed';
+  files_scanned: number;
+  bytes_scanned: number;
+  paths_per_sec: number;
+  elapsed_ms: number;
+  error?: string;
+  /** Tree built so far; grows while the scan is running */
+  root: TreemapNode;
 }
 
-export interface ImageResult {
-  url: string;
-  thumbnail_url?: string;
-  title?: string;
-  source_url?: string;
+export interface TreemapScanSummary {
+  scan_id: string;
+  root: string;
+  status: TreemapScanProgress['status'];
+  files_scanned: number;
+  bytes_scanned: number;
+  /** Files and directories visited, including ones below the size threshold */
+  paths_visited: number;
+  paths_per_sec: number;
+  elapsed_ms: number;
+  error?: string;
+  /** Mounts below the root the scan left out */
+  skipped_mounts: SkippedMount[];
 }
 
-export interface WebSearchResponse {
-  query: string;
-  results: WebSearchResult[];
-  total_results: number;
-  search_time_ms: number;
-  images?: ImageResult[];
+export interface DiskFileQuery {
+  min_size?: number;
+  max_size?: number;
+  /** Untouched for at least this many days */
+  older_than_days?: number;
+  /** Touched within this many days */
+  newer_than_days?: number;
+  age_by?: 'modified' | 'accessed';
+  /** Comma-separated, with or without the dot */
+  extensions?: string;
+  /** Category as in the storage breakdown, e.g. 'Videos' */
+  category?: string;
+  /** User name or numeric ID */
+  owner?: string;
+  /** Only files under this directory */
+  path?: string;
+  sort?: 'size' | 'modified' | 'accessed' | 'name' | 'path';
+  order?: 'asc' | 'desc';
+  offset?: number;
+  limit?: number;
 }
 
-export interface SearchGatherResponse {
-  query: string;
-  search_results: WebSearchResult[];
-  gathered_pages: PageExtract[];
-  total_search_time_ms: number;
-  total_gather_time_ms: number;
+export interface CatalogFile {
+  path: string;
+  name: string;
+  size: number;
+  extension: string;
+  category: string;
+  modified?: string;
+  accessed?: string;
+  owner?: string;
 }
 
-export interface PageExtract {
-  // Core content
+export interface DiskFileQueryPage {
+  scan_id: string;
+  root: string;
+  status: TreemapScanProgress['status'];
+  /** The scan stopped recording files after its limit */
+  catalog_truncated: boolean;
+  total: number;
+  /** Bytes taken by all matching files, not just this page */
+  total_size: number;
+  offset: number;
+  files: CatalogFile[];
+}
+
+export interface DeleteFileResponse {
+  path: string;
+  permanent: boolean;
+  /** Where the file goes back to on restore, when it can be restored */
+  restore_path?: string;
+  /** Pass to `restoreFile` to undo the deletion */
+  trash_id?: string;
+}
+
+/** What to do when the destination exists: fail, replace it, or pick a free name */
+export type FileConflictPolicy = 'abort' | 'overwrite' | 'rename';
+
+export interface FileTransferResponse {
+  kind: 'move' | 'copy' | 'rename';
+  source: string;
+  /** Final destination; differs from the requested one after a 'rename' conflict */
+  destination: string;
+  overwritten: boolean;
+}
+
+export interface FileOperationEntry {
+  id: string;
+  operation: 'move' | 'copy' | 'rename' | 'delete';
+  origin: 'user' | 'agent';
+  source: string;
+  destination?: string;
+  trash_id?: string;
+  timestamp: string;
+}
+
+/** A file that changed on disk, by Skhoot or any other program */
+export interface ChangedFile {
+  path: string;
+  name: string;
+  kind: 'created' | 'modified' | 'removed';
+  changed_at: string;
+  /** Absent for removed files */
+  size?: number;
+  source: 'watcher' | 'scan';
+}
+
+export interface ChangeTimeline {
+  since: string;
+  roots: string[];
+  total: number;
+  truncated: boolean;
+  directories: { directory: string; latest: string; files: ChangedFile[] }[];
+}
+
+export interface DirectoryTreeEntry {
+  name: string;
+  path: string;
+  kind: 'file' | 'directory' | 'symlink';
+  /** Bytes; absent for directories */
+  size?: number;
+  /** Seconds since the Unix epoch */
+  modified?: number;
+  git_ignored: boolean;
+}
+
+export interface DirectoryTreePage {
+  path: string;
+  entries: DirectoryTreeEntry[];
+  total: number;
+  offset: number;
+  /** Pass as `offset` to load the next page; absent on the last page */
+  next_offset?: number;
+}
+
+export interface ArchiveEntry {
+  path: string;
+  is_dir: boolean;
+  size: number;
+  /** Zip only */
+  compressed_size?: number;
+}
+
+export interface ArchiveListing {
+  archive: string;
+  format: 'zip' | 'tar' | 'tar_gz';
+  entries: ArchiveEntry[];
+  total_size: number;
+}
+
+export interface ArchiveExtractResponse {
+  destination: string;
+  extracted: string[];
+  /** Existing files and links that were not written */
+  skipped?: string[];
+  total_bytes: number;
+}
+
+export interface ArchiveCreateResponse {
+  archive: string;
+  format: 'zip' | 'tar' | 'tar_gz';
+  files: number;
+  total_bytes: number;
+  archive_size: number;
+}
+
+export type TranscriptionEngine = 'local' | 'provider' | 'auto';
+
+export interface TranscriptionOptions {
+  /** Defaults to the backend's `transcription.engine` setting */
+  engine?: TranscriptionEngine;
+  language?: string;
+  format?: 'wav' | 'pcm_s16le' | 'pcm_f32le';
+  /** Layout of raw PCM chunks */
+  sample_rate?: number;
+  channels?: number;
+  /** Whisper-compatible endpoint; defaults to OpenAI */
+  provider_url?: string;
+  api_key?: string;
+}
+
+export interface TranscriptionSessionInfo {
+  session_id: string;
+  engine: 'local' | 'provider';
+  /** Whether partial transcripts arrive while recording */
+  partials: boolean;
+}
+
+export interface TranscriptUpdate {
+  session_id: string;
   text: string;
-  word_count: number;
-  
-  // Metadata
-  final_url: string;
-  title?: string;
-  description?: string;
-  author?: string;
-  published_date?: string;
-  canonical_url?: string;
-  
-  // Images
-  primary_image?: string;
-  images: string[];
-  
-  // Links
-  links: string[];
-  
-  // Quality metrics
-  confidence: number;
-  extraction_method: 'DensityHeuristic' | 'ReadabilityAlgorithm' | 'BrowserRender' | 'Fallback';
-  
-  // Performance
-  fetch_time_ms: number;
-  extraction_time_ms: number;
-  total_time_ms: number;
-  
-  // HTTP details
-  status: number;
-  content_type?: string;
+  is_final: boolean;
+  duration_ms: number;
+}
+
+export interface McpServerConfig {
+  /** Used in tool names; letters, digits, '-' and '_' */
+  name: string;
+  transport?: 'stdio' | 'sse';
+  /** Program to start (stdio) */
+  command?: string;
+  args?: string[];
+  env?: Record<string, string>;
+  /** Event stream URL (sse) */
+  url?: string;
+  enabled?: boolean;
+  timeout_secs?: number;
+}
+
+export interface LspServerConfig {
+  /** Language identifier, e.g. 'rust' or 'typescript' */
+  language: string;
+  command: string;
+  args?: string[];
+  env?: Record<string, string>;
+  /** File extensions without the dot */
+  extensions: string[];
+  /** Files marking the project root */
+  root_markers?: string[];
+  enabled?: boolean;
+  timeout_secs?: number;
+}
+
+export interface LspServerStatus {
+  language: string;
+  command: string;
+  root: string;
+  running: boolean;
+  open_documents: number;
+}
+
+/** Lines and columns are 1-based */
+export interface CodeLocation {
+  file: string;
+  line: number;
+  column: number;
+  end_line: number;
+  end_column: number;
+  preview?: string;
+}
+
+export interface CodeSymbol {
+  name: string;
+  kind: string;
+  detail?: string;
+  line: number;
+  column: number;
+  end_line: number;
+  container?: string;
+  children?: CodeSymbol[];
+}
+
+/** A function, type or import in the tree-sitter code index */
+export interface IndexedSymbol {
+  path: string;
+  name: string;
+  kind: string;
+  line: number;
+  end_line: number;
+  container?: string;
+  signature: string;
+}
+
+export interface CodeIndexStats {
+  files_indexed: number;
+  files_unchanged: number;
+  symbols: number;
+}
+
+export interface CodeIndexTotals {
+  files: number;
+  symbols: number;
+}
+
+/** What a find and replace changes, and where */
+export interface ReplaceSpec {
+  pattern: string;
+  replacement: string;
+  regex?: boolean;
+  case_sensitive?: boolean;
+  include?: string[];
+  exclude?: string[];
+}
+
+export interface RefactorFileChange {
+  path: string;
+  replacements: number;
+  diff: string;
+}
+
+/** A planned find and replace awaiting approval */
+export interface RefactorPreview {
+  id: string;
+  root: string;
+  spec: ReplaceSpec;
+  session_id?: string;
+  files: RefactorFileChange[];
+  total_replacements: number;
+  files_scanned: number;
+  created_at: string;
 }
 
-export const backendApi = {
-  baseUrl: BACKEND_URL + '/api/v1',
-  async health(): Promise<HealthResponse> {
-    const response = await fetch(`${BACKEND_URL}/health`);
+export interface AppliedRefactor {
+  id: string;
+  files: string[];
+  total_replacements: number;
+  checkpoint: { id: string; session_id: string; tool: string; created_at: string };
+}
+
+/** A line with either a column or the name of a symbol on it */
+export interface CodePosition {
+  line: number;
+  column?: number;
+  symbol?: string;
+}
+
+export interface McpResource {
+  uri: string;
+  name: string;
+  description?: string;
+  mimeType?: string;
+}
+
+export interface McpServerStatus {
+  name: string;
+  transport: 'stdio' | 'sse';
+  enabled: boolean;
+  connected: boolean;
+  server_info?: { name: string; version: string };
+  tools: number;
+  resources: McpResource[];
+  error?: string;
+}
+
+export interface McpToolInfo {
+  /** Name agents call the tool by (mcp__<server>__<tool>) */
+  name: string;
+  server: string;
+  tool: {
+    name: string;
+    description?: string;
+    inputSchema: Record<string, any>;
+  };
+}
+
+export interface McpCallResult {
+  content: Array<
+    | { type: 'text'; text: string }
+    | { type: 'image' | 'audio'; data: string; mimeType: string }
+    | { type: 'resource'; resource: McpResourceContents }
+  >;
+  isError: boolean;
+}
+
+export interface ToolQueueStatus {
+  session_id: string;
+  running: number;
+  queued: number;
+  /** Set when repeated identical calls paused the session */
+  paused?: { tool: string; repeats: number; message: string };
+}
+
+/** A message to make searchable in conversation history */
+export interface ConversationIndexMessage {
+  id: string;
+  role: string;
+  content: string;
+  tool_calls?: Array<{ name: string; arguments: unknown }>;
+  /** ISO 8601 */
+  timestamp: string;
+}
+
+export interface ConversationSearchParams {
+  /** Words must all appear; "quoted text" must appear as a phrase; a trailing * matches prefixes */
+  q: string;
+  session_id?: string;
+  agent_id?: string;
+  role?: string;
+  /** ISO 8601 bounds on the message time */
+  from?: string;
+  to?: string;
+  limit?: number;
+  offset?: number;
+}
+
+export interface ConversationSearchHit {
+  message_id: string;
+  session_id: string;
+  agent_id?: string | null;
+  role: string;
+  created_at: string;
+  /** Excerpt with matches wrapped in <mark></mark>; other text is not escaped */
+  snippet: string;
+  /** Lower is a better match */
+  rank: number;
+}
+
+/** An event of an agent run, recorded in the session's trace */
+export type AgentTraceEvent =
+  | {
+      kind: 'model_request';
+      provider?: string;
+      model?: string;
+      messages: unknown[];
+      /** Names of the tools offered */
+      tools: string[];
+    }
+  | {
+      kind: 'model_response';
+      content: string;
+      tool_calls: Array<{ id: string; name: string; arguments: Record<string, unknown> }>;
+      usage?: unknown;
+    }
+  | { kind: 'error'; message: string };
+
+/** Whether secrets are redacted from a session's tool output and trace */
+export interface SecretRedactionStatus {
+  enabled: boolean;
+  /** Whether the session overrides the security.redact_secrets setting */
+  overridden: boolean;
+}
+
+/** A message one agent session sent to another */
+export interface AgentMail {
+  id: string;
+  /** Delivery order within the recipient's inbox */
+  seq: number;
+  from: string;
+  to: string;
+  content: string;
+  reply_to?: string;
+  depth: number;
+  sent_at: string;
+  /** Read receipt */
+  read_at?: string;
+}
+
+/** Which part of a file to read */
+export interface FileReadOptions {
+  start_line?: number;
+  end_line?: number;
+  head?: number;
+  tail?: number;
+  /** Byte range */
+  offset?: number;
+  limit?: number;
+  /** Most bytes of content returned (up to 8 MiB) */
+  max_bytes?: number;
+}
+
+/** Part of a file */
+export interface FileReadResult {
+  path: string;
+  content: string;
+  /** Byte offset of the content in the file */
+  offset: number;
+  length: number;
+  file_size: number;
+  start_line?: number;
+  end_line?: number;
+  /** Whether the content was cut at max_bytes */
+  truncated: boolean;
+  /** Whether the file continues after the content */
+  has_more: boolean;
+}
+
+/** Options for following a file */
+export interface FileTailOptions {
+  /** Regular expression; only matching lines are sent */
+  pattern?: string;
+  /** Stop at the first matching line (default true with a pattern) */
+  until_match?: boolean;
+  /** Lines already at the end of the file to send first */
+  lines?: number;
+  /** Up to 3600 seconds (default 300) */
+  max_duration_secs?: number;
+  max_lines?: number;
+}
+
+/** A line appended to a followed file */
+export interface FollowedLine {
+  text: string;
+  /** Byte offset of the line in the file */
+  offset: number;
+}
+
+/** Why and when following a file stopped */
+export interface FileTailSummary {
+  reason: 'matched' | 'max_lines' | 'timeout' | 'cancelled';
+  lines: number;
+  elapsed_ms: number;
+}
+
+/** A variable of an agent session, usable in its shell commands as `${name}` */
+export interface SessionVar {
+  name: string;
+  /** Left out for secret values */
+  value?: string;
+  secret: boolean;
+  updated_at: string;
+}
+
+export type AgentHookEvent = 'file_saved' | 'command_failed' | 'workflow_completed' | 'workflow_failed';
+
+/** An agent started whenever an app event matches */
+export interface AgentHookBinding {
+  id: string;
+  agent_id: string;
+  event: AgentHookEvent;
+  /** Expression over the event's fields; every event matches without one */
+  filter?: string;
+  /** Directory saved files must be in (file_saved only) */
+  workspace?: string;
+  cooldown_secs: number;
+  enabled: boolean;
+  created_at: number;
+  last_fired_at?: number;
+  fire_count: number;
+}
+
+export type ContextPinMode = 'include' | 'priority';
+
+/** A file or folder pinned to a conversation */
+export interface ContextPin {
+  id: string;
+  path: string;
+  is_dir: boolean;
+  /** include: contents go into every request; priority: only listed as the place to look first */
+  mode: ContextPinMode;
+  pinned_at: number;
+}
+
+export interface ContextSet {
+  pins: ContextPin[];
+  /** Bytes of pinned content sent with each request */
+  budget_bytes: number;
+}
+
+/** The system message a conversation's pins render to */
+export interface RenderedContext {
+  text: string;
+  files: {
+    path: string;
+    bytes: number;
+    included_bytes: number;
+    summarized: boolean;
+  }[];
+}
+
+/** What a storage quota measures */
+export type QuotaTarget =
+  | { kind: 'directory'; path: string }
+  /** Every directory called `name` below the roots, e.g. node_modules */
+  | { kind: 'named'; name: string; roots: string[] };
+
+/** A limit on the space some directories may take */
+export interface StorageQuota {
+  id: string;
+  name: string;
+  target: QuotaTarget;
+  limit_bytes: number;
+  /** Workflow started when the limit is crossed */
+  workflow_id?: string;
+  enabled: boolean;
+  created_at: number;
+  last_evaluated_at?: number;
+  last_size?: number;
+  /** Whether the size came from a recent treemap scan or a walk */
+  last_source?: 'scan' | 'walk';
+  /** When the size went over the limit; unset while under it */
+  exceeded_since?: number;
+}
+
+/** An RSS/Atom feed polled for new entries */
+export interface FeedSubscription {
+  id: string;
+  url: string;
+  /** Feed title, once it has been read */
+  title?: string;
+  interval_secs: number;
+  /** Workflow started for every new entry */
+  workflow_id?: string;
+  enabled: boolean;
+  created_at: number;
+  last_checked_at?: number;
+  last_success_at?: number;
+  last_error?: string;
+}
+
+/** An entry read from a feed subscription */
+export interface FeedEntry {
+  id: string;
+  subscription_id: string;
+  /** The entry's guid, link or title, whichever comes first */
+  key: string;
+  title?: string;
+  link?: string;
+  published?: string;
+  summary?: string;
+  fetched_at: number;
+}
+
+export type GoalPhase = 'plan' | 'act' | 'evaluate';
+export type GoalStatus = 'running' | 'succeeded' | 'exhausted' | 'failed' | 'cancelled';
+
+export interface CriterionResult {
+  met: boolean;
+  notes?: string;
+}
+
+export interface GoalIteration {
+  number: number;
+  plan?: string;
+  actions?: string;
+  /** Verdicts in the order of the run's criteria; empty until evaluated */
+  evaluation: CriterionResult[];
+  summary?: string;
+  started_at: number;
+  finished_at?: number;
+}
+
+export interface GoalRun {
+  id: string;
+  session_id?: string;
+  goal: string;
+  criteria: string[];
+  max_iterations: number;
+  status: GoalStatus;
+  /** Phase the agent should run next */
+  phase: GoalPhase;
+  iterations: GoalIteration[];
+  /** Prompt for the next phase; absent once the run is over */
+  current_prompt?: string;
+  error?: string;
+  started_at: number;
+  updated_at: number;
+  completed_at?: number;
+}
+
+export interface GoalPhaseReport {
+  phase?: GoalPhase;
+  /** The agent's reply for the phase */
+  output?: string;
+  /** Verdicts for an evaluation; parsed from output when omitted */
+  criteria?: CriterionResult[];
+  /** Fails the run */
+  error?: string;
+}
+
+export interface PluginInfo {
+  name: string;
+  version: string;
+  description: string;
+  path: string;
+  /** Names agents call the tools by (plugin__<plugin>__<tool>) */
+  tools: string[];
+  permissions: {
+    write: boolean;
+    env: string[];
+  };
+  /** Why the plugin's manifest failed to load */
+  error?: string;
+}
+
+export interface McpResourceContents {
+  uri: string;
+  mimeType?: string;
+  text?: string;
+  /** Base64-encoded binary content */
+  blob?: string;
+}
+
+// ============================================================================
+// Web Search Types
+// ============================================================================
+
+export interface WebSearchResult {
+  title: string;
+  url: string;
+  snippet: string;
+  published_date?: string;
+  relevance_score: number;
+  image_url?: string;
+}
+
+export interface ImageResult {
+  url: string;
+  thumbnail_url?: string;
+  title?: string;
+  source_url?: string;
+}
+
+export interface WebSearchResponse {
+  query: string;
+  results: WebSearchResult[];
+  total_results: number;
+  search_time_ms: number;
+  images?: ImageResult[];
+}
+
+export interface SearchGatherResponse {
+  query: string;
+  search_results: WebSearchResult[];
+  gathered_pages: PageExtract[];
+  total_search_time_ms: number;
+  total_gather_time_ms: number;
+  skipped_urls: SkippedUrl[];
+}
+
+export interface SkippedUrl {
+  url: string;
+  reason: { type: 'robots_disallowed' } | { type: 'fetch_failed'; error: string } | { type: 'budget_exceeded' };
+}
+
+/** Per-request limits for browsing and gathering; the backend caps each */
+export interface ExtractionLimits {
+  /** Timeout per page fetch (default 15000, max 60000) */
+  fetch_timeout_ms?: number;
+  /** Timeout per WebView render (default 30000, max 120000) */
+  render_timeout_ms?: number;
+  /** Time for the whole request; pages not read by then are skipped (default 120000, max 300000) */
+  budget_ms?: number;
+}
+
+function appendLimits(params: URLSearchParams, limits?: ExtractionLimits): void {
+  if (limits?.fetch_timeout_ms) params.append('fetch_timeout_ms', limits.fetch_timeout_ms.toString());
+  if (limits?.render_timeout_ms) params.append('render_timeout_ms', limits.render_timeout_ms.toString());
+  if (limits?.budget_ms) params.append('budget_ms', limits.budget_ms.toString());
+}
+
+/** A page or search result a web answer may cite */
+export interface AnswerSource {
+  /** Number used in the answer's [n] citations */
+  number: number;
+  url: string;
+  title?: string;
+  alternates?: string[];
+  /** Only the search snippet was available, not the page */
+  snippet_only: boolean;
+  cited: boolean;
+}
+
+export interface WebAnswerResponse {
+  query: string;
+  /** Answer text with inline [n] citations of `sources` */
+  answer: string;
+  sources: AnswerSource[];
+  search_time_ms: number;
+  gather_time_ms: number;
+  answer_time_ms: number;
+}
+
+export interface PageExtract {
+  // Core content
+  text: string;
+  word_count: number;
+  
+  // Metadata
+  final_url: string;
+  title?: string;
+  description?: string;
+  author?: string;
+  published_date?: string;
+  canonical_url?: string;
+  
+  // Images
+  primary_image?: string;
+  images: string[];
+  
+  // Links
+  links: string[];
+  
+  // Quality metrics
+  confidence: number;
+  extraction_method: 'DensityHeuristic' | 'ReadabilityAlgorithm' | 'BrowserRender' | 'Fallback' | 'Pdf' | 'PlainText' | 'Json' | 'Feed' | 'SiteRule';
+  
+  // Performance
+  fetch_time_ms: number;
+  extraction_time_ms: number;
+  total_time_ms: number;
+  
+  // HTTP details
+  status: number;
+  content_type?: string;
+  
+  // Text recognized in page images (backend built with the `ocr` feature)
+  ocr_text?: string;
+  
+  // Pages merged into this extract when browsing with a crawl depth
+  crawled_pages?: Array<{
+    url: string;
+    title?: string;
+    word_count: number;
+  }>;
+  
+  // PNG of the rendered page, when requested with `screenshot`
+  screenshot?: ToolAttachment;
+  
+  // Entities declared in JSON-LD, microdata or OpenGraph/Twitter tags
+  structured_data?: StructuredData[];
+  
+  // Other URLs with the same page (AMP copies, mirrors, tracking links),
+  // folded into this one when gathering search results
+  alternates?: string[];
+}
+
+export interface StructuredOffer {
+  price?: string;
+  high_price?: string;
+  currency?: string;
+  availability?: string;
+  seller?: string;
+  url?: string;
+}
+
+export interface StructuredRating {
+  value: number;
+  best?: number;
+  count?: number;
+}
+
+export type StructuredData =
+  | {
+      type: 'article';
+      headline?: string;
+      author?: string;
+      published?: string;
+      modified?: string;
+      section?: string;
+      image?: string;
+    }
+  | {
+      type: 'product';
+      name?: string;
+      description?: string;
+      brand?: string;
+      sku?: string;
+      image?: string;
+      offers?: StructuredOffer[];
+      rating?: StructuredRating;
+    }
+  | {
+      type: 'recipe';
+      name?: string;
+      author?: string;
+      description?: string;
+      prep_time?: string;
+      cook_time?: string;
+      total_time?: string;
+      recipe_yield?: string;
+      ingredients?: string[];
+      instructions?: string[];
+      rating?: StructuredRating;
+    }
+  | {
+      type: 'event';
+      name?: string;
+      description?: string;
+      start_date?: string;
+      end_date?: string;
+      location?: string;
+      organizer?: string;
+      status?: string;
+      offers?: StructuredOffer[];
+    };
+
+async function transferFile(
+  kind: 'move' | 'copy' | 'rename',
+  body: Record<string, string>
+): Promise<FileTransferResponse> {
+  const response = await fetch(`${BACKEND_URL}/api/v1/files/${kind}`, {
+    method: 'POST',
+    headers: { 'Content-Type': 'application/json' },
+    body: JSON.stringify(body),
+  });
+
+  if (!response.ok) {
+    throw new Error(`Failed to ${kind} file: ${response.statusText}`);
+  }
+  return response.json();
+}
+
+export const backendApi = {
+  baseUrl: BACKEND_URL + '/api/v1',
+  async health(): Promise<HealthResponse> {
+    const response = await fetch(`${BACKEND_URL}/health`);
+    if (!response.ok) {
+      throw new Error(`Backend health check failed: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  async ping(): Promise<string> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/ping`);
+    if (!response.ok) {
+      throw new Error(`Backend ping failed: ${response.statusText}`);
+    }
+    return response.text();
+  },
+
+  async detectProvider(apiKey: string): Promise<ProviderInfo> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/ai/detect-provider`, {
+      method: 'POST',
+      headers: {
+        'Content-Type': 'application/json',
+      },
+      body: JSON.stringify({ api_key: apiKey }),
+    });
+    
+    if (!response.ok) {
+      throw new Error(`Provider detection failed: ${response.statusText}`);
+    }
+    
+    return response.json();
+  },
+
+  async getProviderHealth(): Promise<ProviderHealthReport[]> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/ai/health`);
+    if (!response.ok) {
+      throw new Error(`Provider health failed: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  /**
+   * Record the outcome of a chat request for provider health tracking
+   * @param statusCode - HTTP status of a failed request (omit for network errors)
+   */
+  async reportProviderOutcome(
+    provider: string,
+    success: boolean,
+    latencyMs: number,
+    statusCode?: number
+  ): Promise<ProviderHealthReport> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/ai/health/report`, {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify({ provider, success, latency_ms: latencyMs, status_code: statusCode ?? null }),
+    });
+    if (!response.ok) {
+      throw new Error(`Provider outcome report failed: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  /**
+   * Get the order in which to try providers, skipping ones that are down
+   */
+  async planFailover(preferred: string, fallbacks: string[]): Promise<string[]> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/ai/failover/plan`, {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify({ preferred, fallbacks }),
+    });
+    if (!response.ok) {
+      throw new Error(`Failover plan failed: ${response.statusText}`);
+    }
+    const data: { providers: string[] } = await response.json();
+    return data.providers;
+  },
+
+  async recordFailover(from: string, to: string, reason: string): Promise<FailoverEvent> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/ai/failover/events`, {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify({ from, to, reason }),
+    });
+    if (!response.ok) {
+      throw new Error(`Failover event failed: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  async getFailoverEvents(since?: number): Promise<FailoverEvent[]> {
+    const params = since !== undefined ? `?since=${since}` : '';
+    const response = await fetch(`${BACKEND_URL}/api/v1/ai/failover/events${params}`);
+    if (!response.ok) {
+      throw new Error(`Failover events failed: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  /**
+   * Cached response for an identical temperature-0 request, or null
+   */
+  async lookupAIResponse(request: AIResponseCacheRequest): Promise<unknown | null> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/ai/cache/lookup`, {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify(request),
+    });
+    if (!response.ok) {
+      throw new Error(`AI cache lookup failed: ${response.statusText}`);
+    }
+    const data: { hit: boolean; response?: unknown } = await response.json();
+    return data.hit ? data.response ?? null : null;
+  },
+
+  async storeAIResponse(request: AIResponseCacheRequest, aiResponse: unknown): Promise<boolean> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/ai/cache/store`, {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify({ ...request, response: aiResponse }),
+    });
+    if (!response.ok) {
+      throw new Error(`AI cache store failed: ${response.statusText}`);
+    }
+    const data: { stored: boolean } = await response.json();
+    return data.stored;
+  },
+
+  async getAIResponseCacheStats(): Promise<AIResponseCacheStats> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/ai/cache`);
+    if (!response.ok) {
+      throw new Error(`AI cache stats failed: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  async clearAIResponseCache(): Promise<number> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/ai/cache`, { method: 'DELETE' });
+    if (!response.ok) {
+      throw new Error(`AI cache clear failed: ${response.statusText}`);
+    }
+    const data: { removed: number } = await response.json();
+    return data.removed;
+  },
+
+  async getDatabaseStatus(): Promise<DatabaseStatus> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/admin/db/status`);
+    if (!response.ok) {
+      throw new Error(`Database status failed: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  async getConfig(): Promise<BackendConfigResponse> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/config`);
+    if (!response.ok) {
+      throw new Error(`Config fetch failed: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  async updateConfigSection<S extends BackendConfigSection>(
+    section: S,
+    values: Partial<BackendSettings[S]>
+  ): Promise<BackendConfigResponse> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/config/${section}`, {
+      method: 'PUT',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify(values),
+    });
+    if (!response.ok) {
+      const error = await response.json().catch(() => ({}));
+      throw new Error(error.error || `Config update failed: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  async searchFiles(query: string, limit?: number): Promise<SearchResponse> {
+    const params = new URLSearchParams({ q: query });
+    if (limit) {
+      params.append('limit', limit.toString());
+    }
+    
+    const response = await fetch(`${BACKEND_URL}/api/v1/search?${params}`);
+    if (!response.ok) {
+      throw new Error(`Search failed: ${response.statusText}`);
+    }
+    
+    return response.json();
+  },
+
+  async startIndexing(): Promise<void> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/index/start`, {
+      method: 'POST',
+    });
+    
+    if (!response.ok) {
+      throw new Error(`Failed to start indexing: ${response.statusText}`);
+    }
+  },
+
+  // New AI-optimized file search methods
+  /**
+   * Hybrid file search. The query can mix words with filters:
+   * ext:pdf,docx size:>10mb modified:<7d path:~/Projects "exact phrase".
+   * Invalid filter syntax is rejected with the column it starts at.
+   */
+  async aiFileSearch(query: string, options?: AiFileSearchOptions): Promise<FileSearchResults> {
+    const params = fileSearchParams(query, options);
+    const response = await fetch(`${BACKEND_URL}/api/v1/search/files?${params}`);
+    if (!response.ok) {
+      throw new Error(`AI file search failed: ${response.statusText}`);
+    }
+    
+    return response.json();
+  },
+
+  /**
+   * File search that reports each engine's results as soon as it finishes.
+   * `onResults` gets only paths not reported before; `onComplete` gets the
+   * final ranked results, which replace the provisional ones. Returns a
+   * function that stops listening.
+   */
+  streamFileSearch(query: string, handlers: {
+    onResults: (batch: SearchResultBatch) => void;
+    onComplete: (results: FileSearchResults) => void;
+    onError?: (error: Error) => void;
+  }, options?: AiFileSearchOptions): () => void {
+    const params = fileSearchParams(query, options);
+    let source: EventSource | null = null;
+    let closed = false;
+    const close = () => {
+      closed = true;
+      source?.close();
+    };
+
+    // EventSource can't send headers, so the token goes in the query string
+    loadBackendToken().then(() => {
+      if (closed) return;
+      source = new EventSource(withBackendToken(`${BACKEND_URL}/api/v1/search/files/stream?${params}`));
+      source.addEventListener('results', event => {
+        handlers.onResults(JSON.parse((event as MessageEvent).data));
+      });
+      source.addEventListener('complete', event => {
+        close();
+        handlers.onComplete(JSON.parse((event as MessageEvent).data));
+      });
+      source.addEventListener('error', event => {
+        const data = (event as MessageEvent).data;
+        close();
+        handlers.onError?.(new Error(data ? JSON.parse(data).error : 'File search stream failed'));
+      });
+    });
+    return close;
+  },
+
+  /**
+   * Lines around a line of a file with syntax highlighting hints, for
+   * showing content search matches in context
+   */
+  async getFilePreview(path: string, options?: {
+    line?: number;
+    context?: number;
+    highlight?: boolean;
+    query?: string;
+  }): Promise<FilePreview> {
+    const params = new URLSearchParams({ path });
+    if (options?.line) params.append('line', options.line.toString());
+    if (options?.context !== undefined) params.append('context', options.context.toString());
+    if (options?.highlight === false) params.append('highlight', 'false');
+    if (options?.query) params.append('query', options.query);
+
+    const response = await fetch(`${BACKEND_URL}/api/v1/files/preview?${params}`);
+    if (!response.ok) {
+      throw new Error(`Failed to preview file: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  /**
+   * Unified diff between two files, or a file and the content a write
+   * would give it
+   */
+  async diffFiles(path: string, against: { otherPath?: string; content?: string }, options?: {
+    context?: number;
+    wordDiff?: boolean;
+  }): Promise<FileDiff> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/files/diff`, {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify({
+        path,
+        other_path: against.otherPath,
+        content: against.content,
+        context: options?.context,
+        word_diff: options?.wordDiff ?? false,
+      }),
+    });
+    if (!response.ok) {
+      throw new Error(`Failed to diff files: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  async openFileLocation(path: string): Promise<{ success: boolean; message: string }> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/files/open`, {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify({ path }),
+    });
+    
+    if (!response.ok) {
+      throw new Error(`Failed to open file location: ${response.statusText}`);
+    }
+    
+    return response.json();
+  },
+
+  async searchContent(query: string, options?: {
+    context_lines?: number;
+    case_sensitive?: boolean;
+    regex?: boolean;
+    file_types?: string;
+    search_path?: string;  // Custom search path (defaults to user home)
+    respect_gitignore?: boolean; // Override whether .gitignore rules apply
+    respect_skhootignore?: boolean; // Override whether .skhootignore rules apply
+  }): Promise<FileSearchResults> {
+    const params = new URLSearchParams({ q: query });
+    
+    if (options?.context_lines) params.append('context_lines', options.context_lines.toString());
+    if (options?.case_sensitive) params.append('case_sensitive', 'true');
+    if (options?.regex) params.append('regex', 'true');
+    if (options?.file_types) params.append('file_types', options.file_types);
+    if (options?.search_path) params.append('search_path', options.search_path);
+    if (options?.respect_gitignore !== undefined) params.append('respect_gitignore', String(options.respect_gitignore));
+    if (options?.respect_skhootignore !== undefined) params.append('respect_skhootignore', String(options.respect_skhootignore));
+    
+    const response = await fetch(`${BACKEND_URL}/api/v1/search/content?${params}`);
+    if (!response.ok) {
+      throw new Error(`Content search failed: ${response.statusText}`);
+    }
+    
+    return response.json();
+  },
+
+  // Document search using CLI tools (like Codex CLI)
+  // Searches for documents by filename patterns and extensions
+  async searchDocuments(keywords: string, extensions: string, searchPath?: string): Promise<FileSearchResults> {
+    const params = new URLSearchParams({ 
+      keywords,
+      extensions
+    });
+    
+    if (searchPath) params.append('search_path', searchPath);
+    
+    const response = await fetch(`${BACKEND_URL}/api/v1/search/documents?${params}`);
+    if (!response.ok) {
+      throw new Error(`Document search failed: ${response.statusText}`);
+    }
+    
+    return response.json();
+  },
+
+  async getSearchSuggestions(request: SearchSuggestionRequest): Promise<SearchSuggestionResponse> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/search/suggest`, {
+      method: 'POST',
+      headers: {
+        'Content-Type': 'application/json',
+      },
+      body: JSON.stringify(request),
+    });
+    
+    if (!response.ok) {
+      throw new Error(`Search suggestions failed: ${response.statusText}`);
+    }
+    
+    return response.json();
+  },
+
+  async getSearchHistory(limit?: number): Promise<any[]> {
+    const params = new URLSearchParams();
+    if (limit) params.append('limit', limit.toString());
+    
+    const response = await fetch(`${BACKEND_URL}/api/v1/search/history?${params}`);
+    if (!response.ok) {
+      throw new Error(`Failed to get search history: ${response.statusText}`);
+    }
+    
+    return response.json();
+  },
+
+  /**
+   * Record that the user opened a search result, so results from the same
+   * folders and file types rank higher later
+   */
+  async recordSearchClick(path: string, searchId?: string): Promise<void> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/search/feedback`, {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify({ path, search_id: searchId }),
+    });
+    if (!response.ok) {
+      throw new Error(`Failed to record search click: ${response.statusText}`);
+    }
+  },
+
+  /**
+   * Image, media and document metadata for search results, read lazily for
+   * the results being shown
+   */
+  async getSearchMetadata(paths: string[]): Promise<Record<string, FileMetadata | null>> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/search/metadata`, {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify({ paths }),
+    });
+    if (!response.ok) {
+      throw new Error(`Failed to get search metadata: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  /**
+   * Ranking weights learned from opened results
+   */
+  async getSearchRanking(): Promise<SearchRankingReport> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/search/ranking`);
+    if (!response.ok) {
+      throw new Error(`Failed to get search ranking: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  /**
+   * Forget all learned ranking weights
+   */
+  async resetSearchRanking(): Promise<void> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/search/ranking`, { method: 'DELETE' });
+    if (!response.ok) {
+      throw new Error(`Failed to reset search ranking: ${response.statusText}`);
+    }
+  },
+
+  /**
+   * Roots and size of the filename index fuzzy file search uses
+   */
+  async getFilenameIndexStatus(): Promise<FilenameIndexStatus> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/search/filename-index`);
+    if (!response.ok) {
+      throw new Error(`Failed to get filename index status: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  /**
+   * Walk the indexed roots again in the background
+   */
+  async reconcileFilenameIndex(): Promise<FilenameIndexStatus> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/search/filename-index/reconcile`, { method: 'POST' });
+    if (!response.ok) {
+      throw new Error(`Failed to reconcile filename index: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  async getActiveSearches(): Promise<any[]> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/search/active`);
+    if (!response.ok) {
+      throw new Error(`Failed to get active searches: ${response.statusText}`);
+    }
+    
+    return response.json();
+  },
+
+  async cancelSearch(searchId: string): Promise<void> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/search/${searchId}/cancel`, {
+      method: 'POST',
+    });
+    
+    if (!response.ok) {
+      throw new Error(`Failed to cancel search: ${response.statusText}`);
+    }
+  },
+
+  // ============================================================================
+  // Disk Management APIs
+  // ============================================================================
+
+  /**
+   * Get system disk information (all mounted drives)
+   */
+  async getDiskInfo(): Promise<DiskInfoResponse> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/disk/info`);
+    if (!response.ok) {
+      throw new Error(`Failed to get disk info: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  /**
+   * List mounted volumes with filesystem, removable flag and SMART health
+   */
+  async getDiskVolumes(): Promise<DiskVolumesResponse> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/disk/volumes`);
+    if (!response.ok) {
+      throw new Error(`Failed to get disk volumes: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  /**
+   * Running processes, busiest first unless another sort is given
+   */
+  async listProcesses(options?: {
+    filter?: string;
+    sort?: 'cpu' | 'memory' | 'pid' | 'name';
+    limit?: number;
+  }): Promise<ProcessList> {
+    const params = new URLSearchParams();
+    if (options?.filter) params.append('filter', options.filter);
+    if (options?.sort) params.append('sort', options.sort);
+    if (options?.limit) params.append('limit', options.limit.toString());
+    const response = await fetch(`${BACKEND_URL}/api/v1/system/processes?${params}`);
+    if (!response.ok) {
+      throw new Error(`Failed to list processes: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  async inspectProcess(pid: number): Promise<ProcessDetails> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/system/processes/${pid}`);
+    if (!response.ok) {
+      const error = await response.json().catch(() => null);
+      throw new Error(error?.error || `Failed to inspect process: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  /**
+   * Stop a process. One Skhoot didn't start is refused unless `approved`
+   * says the user confirmed it.
+   */
+  async killProcess(pid: number, options: { force?: boolean; approved?: boolean } = {}): Promise<KillOutcome> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/system/processes/${pid}/kill`, {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify(options),
+    });
+    if (!response.ok) {
+      const error = await response.json().catch(() => null);
+      throw new Error(error?.error || `Failed to stop process: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  /**
+   * Analyze disk usage for a path
+   */
+  async analyzeDisk(options?: {
+    path?: string;
+    max_depth?: number;
+    top_n?: number;
+    include_network_mounts?: boolean;
+    include_removable_mounts?: boolean;
+  }): Promise<DiskAnalysisResponse> {
+    const params = new URLSearchParams();
+    if (options?.path) params.append('path', options.path);
+    if (options?.max_depth) params.append('max_depth', options.max_depth.toString());
+    if (options?.top_n) params.append('top_n', options.top_n.toString());
+    if (options?.include_network_mounts) params.append('include_network_mounts', 'true');
+    if (options?.include_removable_mounts) params.append('include_removable_mounts', 'true');
+    
+    const response = await fetch(`${BACKEND_URL}/api/v1/disk/analyze?${params}`);
+    if (!response.ok) {
+      throw new Error(`Failed to analyze disk: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  /**
+   * Get cleanup suggestions. With a path, build output below it (such as
+   * Cargo target directories) is suggested too.
+   */
+  async getCleanupSuggestions(options?: { path?: string; maxDepth?: number }): Promise<CleanupSuggestionsResponse> {
+    const params = new URLSearchParams();
+    if (options?.path) params.append('path', options.path);
+    if (options?.maxDepth !== undefined) params.append('max_depth', options.maxDepth.toString());
+    const response = await fetch(`${BACKEND_URL}/api/v1/disk/cleanup-suggestions?${params}`);
+    if (!response.ok) {
+      throw new Error(`Failed to get cleanup suggestions: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  /**
+   * Get storage breakdown by category
+   */
+  async getStorageCategories(options?: {
+    path?: string;
+    max_depth?: number;
+  }): Promise<StorageCategoriesResponse> {
+    const params = new URLSearchParams();
+    if (options?.path) params.append('path', options.path);
+    if (options?.max_depth) params.append('max_depth', options.max_depth.toString());
+    
+    const response = await fetch(`${BACKEND_URL}/api/v1/disk/categories?${params}`);
+    if (!response.ok) {
+      throw new Error(`Failed to get storage categories: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  /**
+   * Start a treemap scan; poll getDiskTreemap with the returned scan_id
+   */
+  async startDiskTreemap(options?: {
+    path?: string;
+    depth?: number;
+    top_n?: number;
+    /** Walker threads; 0 or unset picks one per CPU */
+    threads?: number;
+    include_network_mounts?: boolean;
+    include_removable_mounts?: boolean;
+  }): Promise<TreemapScanProgress> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/disk/treemap`, {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify(options ?? {}),
+    });
+    if (!response.ok) {
+      throw new Error(`Failed to start treemap scan: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  /**
+   * Get a treemap scan's progress and the tree built so far
+   */
+  async getDiskTreemap(scanId: string): Promise<TreemapScanProgress> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/disk/treemap/${encodeURIComponent(scanId)}`);
+    if (!response.ok) {
+      throw new Error(`Failed to get treemap scan: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  /**
+   * Stop a running treemap scan
+   */
+  async cancelDiskTreemap(scanId: string): Promise<void> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/disk/treemap/${encodeURIComponent(scanId)}`, {
+      method: 'DELETE',
+    });
+    if (!response.ok) {
+      throw new Error(`Failed to cancel treemap scan: ${response.statusText}`);
+    }
+  },
+
+  /**
+   * Recent treemap scans, most recent first, without their trees
+   */
+  async listDiskScans(): Promise<TreemapScanSummary[]> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/disk/scans`);
+    if (!response.ok) {
+      throw new Error(`Failed to list disk scans: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  /**
+   * Status of one treemap scan, without its tree
+   */
+  async getDiskScan(scanId: string): Promise<TreemapScanSummary> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/disk/scans/${encodeURIComponent(scanId)}`);
+    if (!response.ok) {
+      throw new Error(`Failed to get disk scan: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  /**
+   * Filter, sort and page the files a treemap scan found, without rescanning.
+   * Uses the most recent scan unless scanId is given.
+   */
+  async queryDiskFiles(query: DiskFileQuery = {}, scanId?: string): Promise<DiskFileQueryPage> {
+    const params = new URLSearchParams();
+    for (const [key, value] of Object.entries(query)) {
+      if (value !== undefined && value !== '') params.append(key, String(value));
+    }
+    const endpoint = scanId
+      ? `${BACKEND_URL}/api/v1/disk/treemap/${encodeURIComponent(scanId)}/files`
+      : `${BACKEND_URL}/api/v1/disk/files`;
+    const response = await fetch(`${endpoint}?${params}`);
+    if (!response.ok) {
+      throw new Error(`Failed to query disk files: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  // ============================================================================
+  // File Operations APIs
+  // ============================================================================
+
+  /**
+   * Read file content
+   */
+  async readFile(path: string, startLine?: number, endLine?: number): Promise<string> {
+    const params = new URLSearchParams({ path });
+    if (startLine) params.append('start_line', startLine.toString());
+    if (endLine) params.append('end_line', endLine.toString());
+    
+    const response = await fetch(`${BACKEND_URL}/api/v1/files/read?${params}`);
+    if (!response.ok) {
+      throw new Error(`Failed to read file: ${response.statusText}`);
+    }
+    const data = await response.json();
+    return data.content || '';
+  },
+
+  /**
+   * Read part of a large file: a line range, the first or last lines, or a
+   * byte range. Only one of these may be given.
+   */
+  async readFileRange(path: string, range: FileReadOptions): Promise<FileReadResult> {
+    const params = new URLSearchParams({ path });
+    for (const [key, value] of Object.entries(range)) {
+      if (value !== undefined) params.append(key, String(value));
+    }
+    const response = await fetch(`${BACKEND_URL}/api/v1/files/read?${params}`);
+    if (!response.ok) {
+      const error = await response.json().catch(() => null);
+      throw new Error(error?.error || `Failed to read file: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  /**
+   * Follow a file as it grows, like `tail -f`. Returns a function that
+   * stops following.
+   */
+  tailFile(path: string, handlers: {
+    onLine: (line: FollowedLine) => void;
+    onEnd?: (summary: FileTailSummary) => void;
+    onError?: (error: Error) => void;
+  }, options: FileTailOptions = {}): () => void {
+    const params = new URLSearchParams({ path });
+    for (const [key, value] of Object.entries(options)) {
+      if (value !== undefined) params.append(key, String(value));
+    }
+    let source: EventSource | null = null;
+    let closed = false;
+    const close = () => {
+      closed = true;
+      source?.close();
+    };
+
+    loadBackendToken().then(() => {
+      if (closed) return;
+      source = new EventSource(withBackendToken(`${BACKEND_URL}/api/v1/files/tail?${params}`));
+      source.addEventListener('line', event => {
+        handlers.onLine(JSON.parse((event as MessageEvent).data));
+      });
+      source.addEventListener('end', event => {
+        close();
+        handlers.onEnd?.(JSON.parse((event as MessageEvent).data));
+      });
+      source.addEventListener('error', event => {
+        const data = (event as MessageEvent).data;
+        close();
+        handlers.onError?.(new Error(data ? JSON.parse(data).error : 'Following the file failed'));
+      });
+    });
+    return close;
+  },
+
+  /**
+   * Write file content
+   */
+  async writeFile(path: string, content: string, append: boolean = false): Promise<void> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/files/write`, {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify({ path, content, append }),
+    });
+    
+    if (!response.ok) {
+      throw new Error(`Failed to write file: ${response.statusText}`);
+    }
+  },
+
+  /**
+   * Delete a file, moving it to the trash unless `permanent` is set.
+   * Permanent deletion is refused unless the user `approved` it.
+   */
+  async deleteFile(
+    path: string,
+    options?: { permanent?: boolean; approved?: boolean }
+  ): Promise<DeleteFileResponse> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/files/delete`, {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify({ path, permanent: options?.permanent, approved: options?.approved }),
+    });
+
+    if (!response.ok) {
+      throw new Error(`Failed to delete file: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  /**
+   * Restore a file moved to the trash by `deleteFile`
+   */
+  async restoreFile(trashId: string): Promise<string> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/files/restore`, {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify({ trash_id: trashId }),
+    });
+
+    if (!response.ok) {
+      throw new Error(`Failed to restore file: ${response.statusText}`);
+    }
+    const data = await response.json();
+    return data.path;
+  },
+
+  /**
+   * Move a file or directory
+   */
+  async moveFile(
+    source: string,
+    destination: string,
+    onConflict: FileConflictPolicy = 'abort'
+  ): Promise<FileTransferResponse> {
+    return transferFile('move', { source, destination, on_conflict: onConflict });
+  },
+
+  /**
+   * Copy a file or directory
+   */
+  async copyFile(
+    source: string,
+    destination: string,
+    onConflict: FileConflictPolicy = 'abort'
+  ): Promise<FileTransferResponse> {
+    return transferFile('copy', { source, destination, on_conflict: onConflict });
+  },
+
+  /**
+   * Rename a file or directory in place
+   */
+  async renameFile(
+    path: string,
+    newName: string,
+    onConflict: FileConflictPolicy = 'abort'
+  ): Promise<FileTransferResponse> {
+    return transferFile('rename', { path, new_name: newName, on_conflict: onConflict });
+  },
+
+  /**
+   * Recent moves, copies, renames and deletions, newest first
+   */
+  async getRecentFileOperations(limit: number = 50): Promise<FileOperationEntry[]> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/recent/operations?limit=${limit}`);
+    if (!response.ok) {
+      throw new Error(`Failed to get recent file operations: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  /**
+   * Files changed on disk in the last hours, grouped by directory. Covers
+   * the given paths, else the named or default search workspace.
+   */
+  async getChangedFiles(options?: {
+    hours?: number;
+    paths?: string[];
+    workspace?: string;
+    limit?: number;
+  }): Promise<ChangeTimeline> {
+    const params = new URLSearchParams();
+    if (options?.hours) params.append('hours', options.hours.toString());
+    if (options?.paths?.length) params.append('paths', options.paths.join(','));
+    if (options?.workspace) params.append('workspace', options.workspace);
+    if (options?.limit) params.append('limit', options.limit.toString());
+    const response = await fetch(`${BACKEND_URL}/api/v1/recent/changes?${params}`);
+    if (!response.ok) {
+      throw new Error(`Failed to get changed files: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  /**
+   * List directory contents
+   */
+  async listDirectory(path: string, depth?: number, includeHidden?: boolean): Promise<any> {
+    const params = new URLSearchParams({ path });
+    if (depth) params.append('depth', depth.toString());
+    if (includeHidden) params.append('include_hidden', 'true');
+    
+    const response = await fetch(`${BACKEND_URL}/api/v1/files/list?${params}`);
+    if (!response.ok) {
+      throw new Error(`Failed to list directory: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  /**
+   * One level of a directory for the file explorer, folders first.
+   * Page through large folders with `offset`/`limit`.
+   */
+  async getDirectoryTree(
+    path: string,
+    options?: { offset?: number; limit?: number; includeHidden?: boolean }
+  ): Promise<DirectoryTreePage> {
+    const params = new URLSearchParams({ path });
+    if (options?.offset) params.append('offset', options.offset.toString());
+    if (options?.limit) params.append('limit', options.limit.toString());
+    if (options?.includeHidden) params.append('include_hidden', 'true');
+
+    const response = await fetch(`${BACKEND_URL}/api/v1/files/tree?${params}`);
+    if (!response.ok) {
+      throw new Error(`Failed to load directory: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  /**
+   * List the contents of a zip or tar archive
+   */
+  async listArchive(path: string): Promise<ArchiveListing> {
+    const params = new URLSearchParams({ path });
+    const response = await fetch(`${BACKEND_URL}/api/v1/archives/list?${params}`);
+    if (!response.ok) {
+      throw new Error(`Failed to list archive: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  /**
+   * Extract an archive, or only `entries`, into a folder
+   */
+  async extractArchive(
+    path: string,
+    destination: string,
+    options?: { entries?: string[]; overwrite?: boolean }
+  ): Promise<ArchiveExtractResponse> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/archives/extract`, {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify({ path, destination, entries: options?.entries ?? [], overwrite: options?.overwrite }),
+    });
+
+    if (!response.ok) {
+      throw new Error(`Failed to extract archive: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  /**
+   * Create an archive from files and folders; the extension picks the format
+   */
+  async createArchive(path: string, paths: string[], overwrite: boolean = false): Promise<ArchiveCreateResponse> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/archives/create`, {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify({ path, paths, overwrite }),
+    });
+
+    if (!response.ok) {
+      throw new Error(`Failed to create archive: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  /**
+   * Open a voice transcription session; partial transcripts are also
+   * published on the event stream ('audio' topic)
+   */
+  async startTranscription(options: TranscriptionOptions = {}): Promise<TranscriptionSessionInfo> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/audio/transcriptions`, {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify(options),
+    });
+
+    if (!response.ok) {
+      throw new Error(`Failed to start transcription: ${await response.text() || response.statusText}`);
+    }
+    return response.json();
+  },
+
+  /**
+   * Send a chunk of recorded audio and get the transcript so far
+   */
+  async sendAudioChunk(sessionId: string, chunk: Blob | ArrayBuffer): Promise<TranscriptUpdate> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/audio/transcriptions/${encodeURIComponent(sessionId)}/chunks`, {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/octet-stream' },
+      body: chunk,
+    });
+
+    if (!response.ok) {
+      throw new Error(`Failed to send audio: ${await response.text() || response.statusText}`);
+    }
+    return response.json();
+  },
+
+  /**
+   * Transcribe the rest of the recording and close the session
+   */
+  async finishTranscription(sessionId: string): Promise<TranscriptUpdate> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/audio/transcriptions/${encodeURIComponent(sessionId)}/finish`, {
+      method: 'POST',
+    });
+
+    if (!response.ok) {
+      throw new Error(`Failed to finish transcription: ${await response.text() || response.statusText}`);
+    }
+    return response.json();
+  },
+
+  /**
+   * Discard a transcription session
+   */
+  async cancelTranscription(sessionId: string): Promise<void> {
+    await fetch(`${BACKEND_URL}/api/v1/audio/transcriptions/${encodeURIComponent(sessionId)}`, {
+      method: 'DELETE',
+    });
+  },
+
+  /**
+   * Configured MCP servers and their connection state
+   */
+  async listMcpServers(): Promise<McpServerStatus[]> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/mcp/servers`);
+    if (!response.ok) {
+      throw new Error(`Failed to list MCP servers: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  /**
+   * Reconnect every configured MCP server
+   */
+  async reconnectMcpServers(): Promise<McpServerStatus[]> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/mcp/servers/reconnect`, {
+      method: 'POST',
+    });
+    if (!response.ok) {
+      throw new Error(`Failed to reconnect MCP servers: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  /**
+   * Tools offered by the connected MCP servers
+   */
+  async listMcpTools(): Promise<McpToolInfo[]> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/mcp/tools`);
+    if (!response.ok) {
+      throw new Error(`Failed to list MCP tools: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  /**
+   * Call an MCP tool by its registered name
+   */
+  async callMcpTool(name: string, args: Record<string, any> = {}): Promise<McpCallResult> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/mcp/tools/call`, {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify({ name, arguments: args }),
+    });
+    if (!response.ok) {
+      throw new Error(`MCP tool call failed: ${await response.text() || response.statusText}`);
+    }
+    return response.json();
+  },
+
+  /**
+   * Read a resource from an MCP server
+   */
+  async readMcpResource(server: string, uri: string): Promise<McpResourceContents[]> {
+    const params = new URLSearchParams({ server, uri });
+    const response = await fetch(`${BACKEND_URL}/api/v1/mcp/resources/read?${params}`);
+    if (!response.ok) {
+      throw new Error(`Failed to read MCP resource: ${await response.text() || response.statusText}`);
+    }
+    return response.json();
+  },
+
+  /**
+   * Where the symbol at a position is defined
+   */
+  async codeDefinition(path: string, position: CodePosition): Promise<CodeLocation[]> {
+    const params = new URLSearchParams({ path, line: String(position.line) });
+    if (position.column !== undefined) params.set('column', String(position.column));
+    if (position.symbol) params.set('symbol', position.symbol);
+    const response = await fetch(`${BACKEND_URL}/api/v1/code/definition?${params}`);
+    if (!response.ok) {
+      throw new Error(`Go to definition failed: ${await response.text() || response.statusText}`);
+    }
+    return response.json();
+  },
+
+  /**
+   * Uses of the symbol at a position
+   */
+  async codeReferences(path: string, position: CodePosition, includeDeclaration = false): Promise<CodeLocation[]> {
+    const params = new URLSearchParams({
+      path,
+      line: String(position.line),
+      include_declaration: String(includeDeclaration),
+    });
+    if (position.column !== undefined) params.set('column', String(position.column));
+    if (position.symbol) params.set('symbol', position.symbol);
+    const response = await fetch(`${BACKEND_URL}/api/v1/code/references?${params}`);
+    if (!response.ok) {
+      throw new Error(`Find references failed: ${await response.text() || response.statusText}`);
+    }
+    return response.json();
+  },
+
+  /**
+   * Symbols declared in a source file
+   */
+  async codeSymbols(path: string): Promise<CodeSymbol[]> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/code/symbols?${new URLSearchParams({ path })}`);
+    if (!response.ok) {
+      throw new Error(`Failed to list symbols: ${await response.text() || response.statusText}`);
+    }
+    return response.json();
+  },
+
+  /**
+   * Language servers that have been started
+   */
+  async listCodeServers(): Promise<LspServerStatus[]> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/code/servers`);
+    if (!response.ok) {
+      throw new Error(`Failed to list language servers: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  /**
+   * Stop every language server; they restart on the next query
+   */
+  async shutdownCodeServers(): Promise<{ stopped: number }> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/code/servers/shutdown`, { method: 'POST' });
+    if (!response.ok) {
+      throw new Error(`Failed to stop language servers: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  /**
+   * Indexed symbols by name, kind and path prefix
+   */
+  async searchCodeSymbols(query: { q?: string; kind?: string; path?: string; limit?: number }): Promise<IndexedSymbol[]> {
+    const params = new URLSearchParams();
+    if (query.q) params.set('q', query.q);
+    if (query.kind) params.set('kind', query.kind);
+    if (query.path) params.set('path', query.path);
+    if (query.limit) params.set('limit', String(query.limit));
+    const response = await fetch(`${BACKEND_URL}/api/v1/code/index/symbols?${params}`);
+    if (!response.ok) {
+      throw new Error(`Failed to search code symbols: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  /**
+   * Index the functions, types and imports of the code under a directory
+   */
+  async indexCode(path: string): Promise<CodeIndexStats> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/code/index`, {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify({ path }),
+    });
+    if (!response.ok) {
+      throw new Error(`Failed to index code: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  /**
+   * Files and symbols in the code index
+   */
+  async getCodeIndexStats(): Promise<CodeIndexTotals> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/code/index`);
+    if (!response.ok) {
+      throw new Error(`Failed to get code index stats: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  /**
+   * Preview a find and replace under a directory without writing anything
+   */
+  async previewRefactor(path: string, spec: ReplaceSpec, sessionId?: string): Promise<RefactorPreview> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/refactor/preview`, {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify({ path, ...spec, session_id: sessionId }),
+    });
+    if (!response.ok) {
+      throw new Error(`Failed to preview replacement: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  /**
+   * A preview that has not been applied yet
+   */
+  async getRefactor(id: string): Promise<RefactorPreview> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/refactor/${encodeURIComponent(id)}`);
+    if (!response.ok) {
+      throw new Error(`Failed to get replacement preview: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  /**
+   * Apply a previewed replacement the user approved
+   */
+  async applyRefactor(id: string): Promise<AppliedRefactor> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/refactor/${encodeURIComponent(id)}/apply`, {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify({ approved: true }),
+    });
+    if (!response.ok) {
+      throw new Error(`Failed to apply replacement: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  /**
+   * Drop a replacement preview
+   */
+  async discardRefactor(id: string): Promise<{ discarded: boolean }> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/refactor/${encodeURIComponent(id)}`, { method: 'DELETE' });
+    if (!response.ok) {
+      throw new Error(`Failed to discard replacement preview: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  /**
+   * Installed agent tool plugins
+   */
+  async listPlugins(): Promise<PluginInfo[]> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/plugins`);
+    if (!response.ok) {
+      throw new Error(`Failed to list plugins: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  /**
+   * Rescan the plugins directory
+   */
+  async reloadPlugins(): Promise<PluginInfo[]> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/plugins/reload`, {
+      method: 'POST',
+    });
+    if (!response.ok) {
+      throw new Error(`Failed to reload plugins: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  /**
+   * Execute shell command
+   */
+  async executeShellCommand(
+    command: string,
+    workdir?: string,
+    timeoutMs?: number,
+    sessionId?: string,
+    environmentProfile?: string
+  ): Promise<any> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/shell/execute`, {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify({ 
+        command, 
+        // Without a workdir, a profile's default directory applies
+        workdir: workdir || (environmentProfile ? undefined : '.'),
+        timeout_ms: timeoutMs || 30000,
+        // Queues and rate limits the command with the session's other tool calls
+        session_id: sessionId,
+        environment_profile: environmentProfile,
+      }),
+    });
+    
+    if (!response.ok) {
+      throw new Error(`Failed to execute shell command: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  /**
+   * Running and queued tool calls of an agent session, and whether loop
+   * detection paused it
+   */
+  async getToolQueue(sessionId: string): Promise<ToolQueueStatus> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/tool-queue/${encodeURIComponent(sessionId)}`);
+    if (!response.ok) {
+      throw new Error(`Failed to get tool queue: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  /**
+   * Let a session paused by loop detection run tools again
+   */
+  async resumeToolQueue(sessionId: string): Promise<ToolQueueStatus> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/tool-queue/${encodeURIComponent(sessionId)}/resume`, {
+      method: 'POST',
+    });
+    if (!response.ok) {
+      throw new Error(`Failed to resume session: ${await response.text() || response.statusText}`);
+    }
+    return response.json();
+  },
+
+  /**
+   * Index a conversation for history search, replacing what was indexed for it
+   */
+  async indexConversation(sessionId: string, messages: ConversationIndexMessage[], agentId?: string): Promise<number> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/conversations/${encodeURIComponent(sessionId)}`, {
+      method: 'PUT',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify({ agent_id: agentId, messages }),
+    });
+    if (!response.ok) {
+      throw new Error(`Failed to index conversation: ${response.statusText}`);
+    }
+    return (await response.json()).indexed;
+  },
+
+  /**
+   * Remove a deleted conversation from history search
+   */
+  async removeConversationFromIndex(sessionId: string): Promise<void> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/conversations/${encodeURIComponent(sessionId)}`, {
+      method: 'DELETE',
+    });
+    if (!response.ok) {
+      throw new Error(`Failed to remove conversation: ${response.statusText}`);
+    }
+  },
+
+  /**
+   * Full-text search over conversation history, best matches first
+   */
+  async searchConversations(params: ConversationSearchParams): Promise<ConversationSearchHit[]> {
+    const query = new URLSearchParams();
+    Object.entries(params).forEach(([key, value]) => {
+      if (value !== undefined && value !== '') query.set(key, String(value));
+    });
+    const response = await fetch(`${BACKEND_URL}/api/v1/conversations/search?${query}`);
+    if (!response.ok) {
+      throw new Error(`Conversation search failed: ${await response.text() || response.statusText}`);
+    }
+    return (await response.json()).results;
+  },
+
+  /**
+   * Indexed conversations, most recently active first; listed even while
+   * the conversation vault is locked
+   */
+  async listConversations(): Promise<ConversationSession[]> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/conversations`);
+    if (!response.ok) {
+      throw new Error(`Failed to list conversations: ${response.statusText}`);
+    }
+    return (await response.json()).sessions;
+  },
+
+  /**
+   * Indexed messages of a conversation; text is null while the vault is locked
+   */
+  async getConversation(sessionId: string): Promise<ConversationTranscript> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/conversations/${encodeURIComponent(sessionId)}`);
+    if (!response.ok) {
+      throw new Error(`Failed to load conversation: ${await response.text() || response.statusText}`);
+    }
+    return response.json();
+  },
+
+  async getVaultStatus(): Promise<VaultStatus> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/vault`);
+    if (!response.ok) {
+      throw new Error(`Vault status failed: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  /**
+   * Enable, disable or unlock conversation encryption with a passphrase
+   */
+  async vaultAction(action: 'enable' | 'disable' | 'unlock', passphrase: string): Promise<VaultStatus> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/vault/${action}`, {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify({ passphrase }),
+    });
+    if (!response.ok) {
+      const data = await response.json().catch(() => ({}));
+      throw new Error(data.error || `Vault ${action} failed: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  async lockVault(): Promise<VaultStatus> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/vault/lock`, { method: 'POST' });
+    if (!response.ok) {
+      throw new Error(`Vault lock failed: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  /**
+   * Seal or open texts stored by the client (e.g. memories) with the vault key
+   */
+  async vaultTexts(operation: 'seal' | 'open', texts: string[]): Promise<string[]> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/vault/${operation}`, {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify({ texts }),
+    });
+    if (!response.ok) {
+      const data = await response.json().catch(() => ({}));
+      throw new Error(data.error || `Vault ${operation} failed: ${response.statusText}`);
+    }
+    return (await response.json()).texts;
+  },
+
+  async listLocalApiClients(): Promise<LocalApiClient[]> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/local-api/clients`);
+    if (!response.ok) {
+      throw new Error(`Failed to list local API clients: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  /**
+   * Register an app for the local API; the returned token is only shown once
+   */
+  async createLocalApiClient(client: {
+    name: string;
+    provider: LocalApiClient['provider'];
+    model?: string;
+    permissions?: Partial<LocalApiClientPermissions>;
+  }): Promise<LocalApiClient & { token: string }> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/local-api/clients`, {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify(client),
+    });
+    if (!response.ok) {
+      const data = await response.json().catch(() => ({}));
+      throw new Error(data.error || `Failed to create local API client: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  async updateLocalApiClient(
+    id: string,
+    changes: Partial<Pick<LocalApiClient, 'name' | 'provider' | 'model' | 'permissions' | 'enabled'>>,
+  ): Promise<LocalApiClient> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/local-api/clients/${encodeURIComponent(id)}`, {
+      method: 'PUT',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify(changes),
+    });
+    if (!response.ok) {
+      const data = await response.json().catch(() => ({}));
+      throw new Error(data.error || `Failed to update local API client: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  /**
+   * Issue a new token for a client; the old one stops working
+   */
+  async rotateLocalApiToken(id: string): Promise<LocalApiClient & { token: string }> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/local-api/clients/${encodeURIComponent(id)}/token`, {
+      method: 'POST',
+    });
+    if (!response.ok) {
+      throw new Error(`Failed to rotate token: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  async deleteLocalApiClient(id: string): Promise<void> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/local-api/clients/${encodeURIComponent(id)}`, {
+      method: 'DELETE',
+    });
+    if (!response.ok) {
+      throw new Error(`Failed to delete local API client: ${response.statusText}`);
+    }
+  },
+
+  /**
+   * Workflow run counts, success rates and durations over the last `days` days
+   */
+  async getWorkflowRunStats(days?: number, workflowId?: string): Promise<WorkflowRunStats> {
+    const params = new URLSearchParams();
+    if (days !== undefined) params.set('days', String(days));
+    if (workflowId) params.set('workflow_id', workflowId);
+    const response = await fetch(`${BACKEND_URL}/api/v1/stats/workflows?${params}`);
+    if (!response.ok) {
+      throw new Error(`Workflow stats failed: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  /**
+   * Tool usage and activity per agent over the last `days` days
+   */
+  async getAgentRunStats(days?: number): Promise<AgentRunStats> {
+    const params = new URLSearchParams();
+    if (days !== undefined) params.set('days', String(days));
+    const response = await fetch(`${BACKEND_URL}/api/v1/stats/agents?${params}`);
+    if (!response.ok) {
+      throw new Error(`Agent stats failed: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  /**
+   * Append events observed by the chat loop to an agent session's trace
+   */
+  async appendAgentTrace(sessionId: string, events: AgentTraceEvent[]): Promise<void> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/agents/${encodeURIComponent(sessionId)}/trace`, {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify({ events }),
+    });
+    if (!response.ok) {
+      throw new Error(`Failed to record trace: ${response.statusText}`);
+    }
+  },
+
+  /**
+   * Download an agent session's trace as JSON lines, optionally with file
+   * contents replaced by their size
+   */
+  async downloadAgentTrace(sessionId: string, redact = false): Promise<Blob> {
+    const response = await fetch(
+      `${BACKEND_URL}/api/v1/agents/${encodeURIComponent(sessionId)}/trace?redact=${redact}`
+    );
+    if (!response.ok) {
+      throw new Error(`Failed to download trace: ${await response.text() || response.statusText}`);
+    }
+    return response.blob();
+  },
+
+  /**
+   * Get whether secrets are redacted for an agent session
+   */
+  async getSecretRedaction(sessionId: string): Promise<SecretRedactionStatus> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/agents/${encodeURIComponent(sessionId)}/redaction`);
+    if (!response.ok) {
+      throw new Error(`Failed to get secret redaction: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  /**
+   * Turn secret redaction on or off for an agent session; null goes back to
+   * the settings default
+   */
+  async setSecretRedaction(sessionId: string, enabled: boolean | null): Promise<SecretRedactionStatus> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/agents/${encodeURIComponent(sessionId)}/redaction`, {
+      method: 'PUT',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify({ enabled }),
+    });
+    if (!response.ok) {
+      throw new Error(`Failed to set secret redaction: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  /**
+   * Get the messages other agents sent to an agent session
+   */
+  async getAgentInbox(sessionId: string): Promise<AgentMail[]> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/agents/${encodeURIComponent(sessionId)}/inbox`);
+    if (!response.ok) {
+      throw new Error(`Failed to get agent inbox: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  /**
+   * List the variables of an agent session; secret values are left out
+   */
+  async listSessionVars(sessionId: string): Promise<SessionVar[]> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/agents/${encodeURIComponent(sessionId)}/vars`);
+    if (!response.ok) {
+      throw new Error(`Failed to list session variables: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  /**
+   * Set a variable of an agent session
+   */
+  async setSessionVar(sessionId: string, name: string, value: string, secret = false): Promise<SessionVar> {
+    const response = await fetch(
+      `${BACKEND_URL}/api/v1/agents/${encodeURIComponent(sessionId)}/vars/${encodeURIComponent(name)}`,
+      {
+        method: 'PUT',
+        headers: { 'Content-Type': 'application/json' },
+        body: JSON.stringify({ value, secret }),
+      },
+    );
+    if (!response.ok) {
+      const error = await response.json().catch(() => null);
+      throw new Error(error?.error || `Failed to set session variable: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  /**
+   * Remove a variable of an agent session
+   */
+  async deleteSessionVar(sessionId: string, name: string): Promise<void> {
+    const response = await fetch(
+      `${BACKEND_URL}/api/v1/agents/${encodeURIComponent(sessionId)}/vars/${encodeURIComponent(name)}`,
+      { method: 'DELETE' },
+    );
+    if (!response.ok) {
+      throw new Error(`Failed to delete session variable: ${response.statusText}`);
+    }
+  },
+
+  /**
+   * List the event hooks bound to an agent
+   */
+  async listAgentHooks(agentId: string): Promise<AgentHookBinding[]> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/agents/${encodeURIComponent(agentId)}/hooks`);
+    if (!response.ok) {
+      throw new Error(`Failed to list agent hooks: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  /**
+   * Bind an agent to an app event
+   */
+  async createAgentHook(agentId: string, hook: {
+    event: AgentHookEvent;
+    filter?: string;
+    workspace?: string;
+    cooldown_secs?: number;
+    enabled?: boolean;
+  }): Promise<AgentHookBinding> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/agents/${encodeURIComponent(agentId)}/hooks`, {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify(hook),
+    });
+    if (!response.ok) {
+      throw new Error(`Failed to create agent hook: ${await response.text() || response.statusText}`);
+    }
+    return response.json();
+  },
+
+  /**
+   * Change an agent hook; an empty filter or workspace clears it
+   */
+  async updateAgentHook(agentId: string, hookId: string, changes: {
+    event?: AgentHookEvent;
+    filter?: string;
+    workspace?: string;
+    cooldown_secs?: number;
+    enabled?: boolean;
+  }): Promise<AgentHookBinding> {
+    const response = await fetch(
+      `${BACKEND_URL}/api/v1/agents/${encodeURIComponent(agentId)}/hooks/${encodeURIComponent(hookId)}`,
+      {
+        method: 'PUT',
+        headers: { 'Content-Type': 'application/json' },
+        body: JSON.stringify(changes),
+      },
+    );
+    if (!response.ok) {
+      throw new Error(`Failed to update agent hook: ${await response.text() || response.statusText}`);
+    }
+    return response.json();
+  },
+
+  /**
+   * Remove an agent hook
+   */
+  async deleteAgentHook(agentId: string, hookId: string): Promise<boolean> {
+    const response = await fetch(
+      `${BACKEND_URL}/api/v1/agents/${encodeURIComponent(agentId)}/hooks/${encodeURIComponent(hookId)}`,
+      { method: 'DELETE' },
+    );
     if (!response.ok) {
-      throw new Error(`Backend health check failed: ${response.statusText}`);
+      throw new Error(`Failed to delete agent hook: ${response.statusText}`);
     }
     return response.json();
   },
 
-  async ping(): Promise<string> {
-    const response = await fetch(`${BACKEND_URL}/api/v1/ping`);
+  /**
+   * Files and folders pinned to a conversation
+   */
+  async getContextSet(sessionId: string): Promise<ContextSet> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/agents/${encodeURIComponent(sessionId)}/context`);
     if (!response.ok) {
-      throw new Error(`Backend ping failed: ${response.statusText}`);
+      throw new Error(`Failed to get context set: ${response.statusText}`);
     }
-    return response.text();
+    return response.json();
   },
 
-  async detectProvider(apiKey: string): Promise<ProviderInfo> {
-    const response = await fetch(`${BACKEND_URL}/api/v1/ai/detect-provider`, {
+  /**
+   * Pin a file or folder to a conversation; pinning it again changes its mode
+   */
+  async pinContext(sessionId: string, path: string, mode?: ContextPinMode): Promise<ContextPin> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/agents/${encodeURIComponent(sessionId)}/context`, {
       method: 'POST',
-      headers: {
-        'Content-Type': 'application/json',
-      },
-      body: JSON.stringify({ api_key: apiKey }),
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify({ path, mode }),
     });
-    
     if (!response.ok) {
-      throw new Error(`Provider detection failed: ${response.statusText}`);
+      throw new Error(`Failed to pin context: ${await response.text() || response.statusText}`);
     }
-    
     return response.json();
   },
 
-  async searchFiles(query: string, limit?: number): Promise<SearchResponse> {
-    const params = new URLSearchParams({ q: query });
-    if (limit) {
-      params.append('limit', limit.toString());
-    }
-    
-    const response = await fetch(`${BACKEND_URL}/api/v1/search?${params}`);
+  /**
+   * Change how many bytes of pinned content go into each request
+   */
+  async setContextBudget(sessionId: string, budgetBytes: number): Promise<ContextSet> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/agents/${encodeURIComponent(sessionId)}/context`, {
+      method: 'PUT',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify({ budget_bytes: budgetBytes }),
+    });
     if (!response.ok) {
-      throw new Error(`Search failed: ${response.statusText}`);
+      throw new Error(`Failed to set context budget: ${await response.text() || response.statusText}`);
     }
-    
     return response.json();
   },
 
-  async startIndexing(): Promise<void> {
-    const response = await fetch(`${BACKEND_URL}/api/v1/index/start`, {
-      method: 'POST',
-    });
-    
+  /**
+   * The system message a conversation's pins currently render to
+   */
+  async previewContext(sessionId: string): Promise<RenderedContext | null> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/agents/${encodeURIComponent(sessionId)}/context/preview`);
     if (!response.ok) {
-      throw new Error(`Failed to start indexing: ${response.statusText}`);
+      throw new Error(`Failed to preview context: ${response.statusText}`);
     }
+    return response.json();
   },
 
-  // New AI-optimized file search methods
-  async aiFileSearch(query: string, options?: {
-    mode?: 'rust' | 'cli' | 'hybrid' | 'auto';
-    max_results?: number;
-    include_indices?: boolean;
-    file_types?: string;
-    exclude_dirs?: string;
-    search_path?: string;  // Custom search path (defaults to user home)
-    unrestricted?: boolean; // Enable deep search (hidden files, ignore .gitignore)
-  }): Promise<FileSearchResults> {
-    const params = new URLSearchParams({ q: query });
-    
-    if (options?.mode) params.append('mode', options.mode);
-    if (options?.max_results) params.append('max_results', options.max_results.toString());
-    if (options?.include_indices) params.append('include_indices', 'true');
-    if (options?.file_types) params.append('file_types', options.file_types);
-    if (options?.exclude_dirs) params.append('exclude_dirs', options.exclude_dirs);
-    if (options?.search_path) params.append('search_path', options.search_path);
-    if (options?.unrestricted) params.append('unrestricted', 'true');
-    
-    const response = await fetch(`${BACKEND_URL}/api/v1/search/files?${params}`);
+  async setContextPinMode(sessionId: string, pinId: string, mode: ContextPinMode): Promise<ContextPin> {
+    const response = await fetch(
+      `${BACKEND_URL}/api/v1/agents/${encodeURIComponent(sessionId)}/context/${encodeURIComponent(pinId)}`,
+      {
+        method: 'PUT',
+        headers: { 'Content-Type': 'application/json' },
+        body: JSON.stringify({ mode }),
+      },
+    );
     if (!response.ok) {
-      throw new Error(`AI file search failed: ${response.statusText}`);
+      throw new Error(`Failed to update context pin: ${await response.text() || response.statusText}`);
     }
-    
     return response.json();
   },
 
-  async openFileLocation(path: string): Promise<{ success: boolean; message: string }> {
-    const response = await fetch(`${BACKEND_URL}/api/v1/files/open`, {
-      method: 'POST',
-      headers: { 'Content-Type': 'application/json' },
-      body: JSON.stringify({ path }),
-    });
-    
+  async unpinContext(sessionId: string, pinId: string): Promise<boolean> {
+    const response = await fetch(
+      `${BACKEND_URL}/api/v1/agents/${encodeURIComponent(sessionId)}/context/${encodeURIComponent(pinId)}`,
+      { method: 'DELETE' },
+    );
     if (!response.ok) {
-      throw new Error(`Failed to open file location: ${response.statusText}`);
+      throw new Error(`Failed to unpin context: ${response.statusText}`);
     }
-    
     return response.json();
   },
 
-  async searchContent(query: string, options?: {
-    context_lines?: number;
-    case_sensitive?: boolean;
-    regex?: boolean;
-    file_types?: string;
-    search_path?: string;  // Custom search path (defaults to user home)
-  }): Promise<FileSearchResults> {
-    const params = new URLSearchParams({ q: query });
-    
-    if (options?.context_lines) params.append('context_lines', options.context_lines.toString());
-    if (options?.case_sensitive) params.append('case_sensitive', 'true');
-    if (options?.regex) params.append('regex', 'true');
-    if (options?.file_types) params.append('file_types', options.file_types);
-    if (options?.search_path) params.append('search_path', options.search_path);
-    
-    const response = await fetch(`${BACKEND_URL}/api/v1/search/content?${params}`);
+  /**
+   * List RSS/Atom feed subscriptions
+   */
+  async listFeeds(): Promise<FeedSubscription[]> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/feeds`);
     if (!response.ok) {
-      throw new Error(`Content search failed: ${response.statusText}`);
+      throw new Error(`Failed to list feeds: ${response.statusText}`);
     }
-    
     return response.json();
   },
 
-  // Document search using CLI tools (like Codex CLI)
-  // Searches for documents by filename patterns and extensions
-  async searchDocuments(keywords: string, extensions: string, searchPath?: string): Promise<FileSearchResults> {
-    const params = new URLSearchParams({ 
-      keywords,
-      extensions
+  /**
+   * Subscribe to a feed, optionally starting a workflow for each new entry
+   */
+  async createFeed(feed: {
+    url: string;
+    interval_secs?: number;
+    workflow_id?: string;
+    enabled?: boolean;
+  }): Promise<FeedSubscription> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/feeds`, {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify(feed),
     });
-    
-    if (searchPath) params.append('search_path', searchPath);
-    
-    const response = await fetch(`${BACKEND_URL}/api/v1/search/documents?${params}`);
     if (!response.ok) {
-      throw new Error(`Document search failed: ${response.statusText}`);
+      throw new Error(`Failed to create feed: ${response.statusText}`);
     }
-    
     return response.json();
   },
 
-  async getSearchSuggestions(request: SearchSuggestionRequest): Promise<SearchSuggestionResponse> {
-    const response = await fetch(`${BACKEND_URL}/api/v1/search/suggest`, {
-      method: 'POST',
-      headers: {
-        'Content-Type': 'application/json',
-      },
-      body: JSON.stringify(request),
+  /**
+   * Update a feed subscription; an empty workflow_id unbinds the workflow
+   */
+  async updateFeed(id: string, changes: {
+    url?: string;
+    interval_secs?: number;
+    workflow_id?: string;
+    enabled?: boolean;
+  }): Promise<FeedSubscription> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/feeds/${encodeURIComponent(id)}`, {
+      method: 'PUT',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify(changes),
     });
-    
     if (!response.ok) {
-      throw new Error(`Search suggestions failed: ${response.statusText}`);
+      throw new Error(`Failed to update feed: ${response.statusText}`);
     }
-    
     return response.json();
   },
 
-  async getSearchHistory(limit?: number): Promise<any[]> {
-    const params = new URLSearchParams();
-    if (limit) params.append('limit', limit.toString());
-    
-    const response = await fetch(`${BACKEND_URL}/api/v1/search/history?${params}`);
+  /**
+   * Unsubscribe from a feed
+   */
+  async deleteFeed(id: string): Promise<boolean> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/feeds/${encodeURIComponent(id)}`, {
+      method: 'DELETE',
+    });
     if (!response.ok) {
-      throw new Error(`Failed to get search history: ${response.statusText}`);
+      throw new Error(`Failed to delete feed: ${response.statusText}`);
     }
-    
     return response.json();
   },
 
-  async getActiveSearches(): Promise<any[]> {
-    const response = await fetch(`${BACKEND_URL}/api/v1/search/active`);
+  /**
+   * List a feed's stored entries, newest first
+   */
+  async listFeedEntries(id: string, limit?: number): Promise<FeedEntry[]> {
+    const query = limit !== undefined ? `?limit=${limit}` : '';
+    const response = await fetch(`${BACKEND_URL}/api/v1/feeds/${encodeURIComponent(id)}/entries${query}`);
     if (!response.ok) {
-      throw new Error(`Failed to get active searches: ${response.statusText}`);
+      throw new Error(`Failed to list feed entries: ${response.statusText}`);
     }
-    
     return response.json();
   },
 
-  async cancelSearch(searchId: string): Promise<void> {
-    const response = await fetch(`${BACKEND_URL}/api/v1/search/${searchId}/cancel`, {
+  /**
+   * Check a feed now; returns the entries that were new
+   */
+  async checkFeed(id: string): Promise<FeedEntry[]> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/feeds/${encodeURIComponent(id)}/check`, {
       method: 'POST',
     });
-    
     if (!response.ok) {
-      throw new Error(`Failed to cancel search: ${response.statusText}`);
+      throw new Error(`Failed to check feed: ${response.statusText}`);
     }
+    return response.json();
   },
 
-  // ============================================================================
-  // Disk Management APIs
-  // ============================================================================
-
   /**
-   * Get system disk information (all mounted drives)
+   * List storage quotas with their last measured sizes
    */
-  async getDiskInfo(): Promise<DiskInfoResponse> {
-    const response = await fetch(`${BACKEND_URL}/api/v1/disk/info`);
+  async listStorageQuotas(): Promise<StorageQuota[]> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/disk/quotas`);
     if (!response.ok) {
-      throw new Error(`Failed to get disk info: ${response.statusText}`);
+      throw new Error(`Failed to list storage quotas: ${response.statusText}`);
     }
     return response.json();
   },
 
   /**
-   * Analyze disk usage for a path
+   * Add a storage quota, optionally starting a workflow when it is exceeded
    */
-  async analyzeDisk(options?: {
-    path?: string;
-    max_depth?: number;
-    top_n?: number;
-  }): Promise<DiskAnalysisResponse> {
-    const params = new URLSearchParams();
-    if (options?.path) params.append('path', options.path);
-    if (options?.max_depth) params.append('max_depth', options.max_depth.toString());
-    if (options?.top_n) params.append('top_n', options.top_n.toString());
-    
-    const response = await fetch(`${BACKEND_URL}/api/v1/disk/analyze?${params}`);
+  async createStorageQuota(quota: {
+    name?: string;
+    target: QuotaTarget;
+    limit_bytes: number;
+    workflow_id?: string;
+    enabled?: boolean;
+  }): Promise<StorageQuota> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/disk/quotas`, {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify(quota),
+    });
     if (!response.ok) {
-      throw new Error(`Failed to analyze disk: ${response.statusText}`);
+      throw new Error(`Failed to create storage quota: ${response.statusText}`);
     }
     return response.json();
   },
 
   /**
-   * Get cleanup suggestions
+   * Change a storage quota; an empty workflow_id removes its workflow
    */
-  async getCleanupSuggestions(): Promise<CleanupSuggestionsResponse> {
-    const response = await fetch(`${BACKEND_URL}/api/v1/disk/cleanup-suggestions`);
+  async updateStorageQuota(id: string, changes: {
+    name?: string;
+    target?: QuotaTarget;
+    limit_bytes?: number;
+    workflow_id?: string;
+    enabled?: boolean;
+  }): Promise<StorageQuota> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/disk/quotas/${encodeURIComponent(id)}`, {
+      method: 'PUT',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify(changes),
+    });
     if (!response.ok) {
-      throw new Error(`Failed to get cleanup suggestions: ${response.statusText}`);
+      throw new Error(`Failed to update storage quota: ${response.statusText}`);
     }
     return response.json();
   },
 
   /**
-   * Get storage breakdown by category
+   * Remove a storage quota
    */
-  async getStorageCategories(options?: {
-    path?: string;
-    max_depth?: number;
-  }): Promise<StorageCategoriesResponse> {
-    const params = new URLSearchParams();
-    if (options?.path) params.append('path', options.path);
-    if (options?.max_depth) params.append('max_depth', options.max_depth.toString());
-    
-    const response = await fetch(`${BACKEND_URL}/api/v1/disk/categories?${params}`);
+  async deleteStorageQuota(id: string): Promise<boolean> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/disk/quotas/${encodeURIComponent(id)}`, {
+      method: 'DELETE',
+    });
     if (!response.ok) {
-      throw new Error(`Failed to get storage categories: ${response.statusText}`);
+      throw new Error(`Failed to delete storage quota: ${response.statusText}`);
     }
     return response.json();
   },
 
-  // ============================================================================
-  // File Operations APIs
-  // ============================================================================
-
   /**
-   * Read file content
+   * Measure a storage quota now instead of waiting for the next check
    */
-  async readFile(path: string, startLine?: number, endLine?: number): Promise<string> {
-    const params = new URLSearchParams({ path });
-    if (startLine) params.append('start_line', startLine.toString());
-    if (endLine) params.append('end_line', endLine.toString());
-    
-    const response = await fetch(`${BACKEND_URL}/api/v1/files/read?${params}`);
+  async evaluateStorageQuota(id: string): Promise<StorageQuota> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/disk/quotas/${encodeURIComponent(id)}/evaluate`, {
+      method: 'POST',
+    });
     if (!response.ok) {
-      throw new Error(`Failed to read file: ${response.statusText}`);
+      throw new Error(`Failed to evaluate storage quota: ${response.statusText}`);
     }
-    const data = await response.json();
-    return data.content || '';
+    return response.json();
   },
 
   /**
-   * Write file content
+   * Start working toward a goal in plan/act/evaluate iterations
    */
-  async writeFile(path: string, content: string, append: boolean = false): Promise<void> {
-    const response = await fetch(`${BACKEND_URL}/api/v1/files/write`, {
+  async startGoal(request: {
+    goal: string;
+    criteria: string[];
+    max_iterations?: number;
+    session_id?: string;
+  }): Promise<GoalRun> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/goals`, {
       method: 'POST',
       headers: { 'Content-Type': 'application/json' },
-      body: JSON.stringify({ path, content, append }),
+      body: JSON.stringify(request),
     });
-    
     if (!response.ok) {
-      throw new Error(`Failed to write file: ${response.statusText}`);
+      throw new Error(`Failed to start goal: ${await response.text() || response.statusText}`);
     }
+    return response.json();
   },
 
-  /**
-   * List directory contents
-   */
-  async listDirectory(path: string, depth?: number, includeHidden?: boolean): Promise<any> {
-    const params = new URLSearchParams({ path });
-    if (depth) params.append('depth', depth.toString());
-    if (includeHidden) params.append('include_hidden', 'true');
-    
-    const response = await fetch(`${BACKEND_URL}/api/v1/files/list?${params}`);
+  async listGoals(sessionId?: string): Promise<GoalRun[]> {
+    const params = new URLSearchParams();
+    if (sessionId) params.append('session_id', sessionId);
+    const response = await fetch(`${BACKEND_URL}/api/v1/goals?${params}`);
     if (!response.ok) {
-      throw new Error(`Failed to list directory: ${response.statusText}`);
+      throw new Error(`Failed to list goals: ${response.statusText}`);
+    }
+    return response.json();
+  },
+
+  async getGoal(goalId: string): Promise<GoalRun> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/goals/${encodeURIComponent(goalId)}`);
+    if (!response.ok) {
+      throw new Error(`Failed to get goal: ${response.statusText}`);
     }
     return response.json();
   },
 
   /**
-   * Execute shell command
+   * Report the outcome of the current phase; the run moves to the next phase,
+   * starts another iteration or finishes
    */
-  async executeShellCommand(command: string, workdir?: string, timeoutMs?: number): Promise<any> {
-    const response = await fetch(`${BACKEND_URL}/api/v1/shell/execute`, {
+  async reportGoalPhase(goalId: string, report: GoalPhaseReport): Promise<GoalRun> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/goals/${encodeURIComponent(goalId)}/report`, {
       method: 'POST',
       headers: { 'Content-Type': 'application/json' },
-      body: JSON.stringify({ 
-        command, 
-        workdir: workdir || '.',
-        timeout_ms: timeoutMs || 30000 
-      }),
+      body: JSON.stringify(report),
     });
-    
     if (!response.ok) {
-      throw new Error(`Failed to execute shell command: ${response.statusText}`);
+      throw new Error(`Failed to report goal phase: ${await response.text() || response.statusText}`);
+    }
+    return response.json();
+  },
+
+  async cancelGoal(goalId: string): Promise<GoalRun> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/goals/${encodeURIComponent(goalId)}/cancel`, {
+      method: 'POST',
+    });
+    if (!response.ok) {
+      throw new Error(`Failed to cancel goal: ${response.statusText}`);
     }
     return response.json();
   },
@@ -632,6 +3673,7 @@ export const backendApi = {
       depth?: number;           // 0-10 scale: 0=snippets only, 10=maximum depth with rendering
       num_results?: number;     // Number of search results (default: 5, max: 10)
       search_type?: 'general' | 'news' | 'docs';
+      limits?: ExtractionLimits; // Timeouts and budget when gathering
     }
   ): Promise<WebSearchResponse | SearchGatherResponse> {
     const depth = options?.depth ?? 5; // Default to moderate depth
@@ -652,6 +3694,7 @@ export const backendApi = {
     if (shouldGather) {
       params.append('gather_top', gatherTop.toString());
     }
+    appendLimits(params, options?.limits);
     
     // Note: render parameter would be passed to individual browse calls
     // For now, the backend handles rendering based on confidence scores
@@ -664,21 +3707,71 @@ export const backendApi = {
     return response.json();
   },
 
+  /**
+   * Answer a question from the web in one call: the backend searches, reads
+   * the top results and has the AI provider answer with [n] citations of the
+   * returned sources
+   */
+  async webAnswer(
+    query: string,
+    options: {
+      provider: string;
+      api_key: string;
+      model?: string;
+      num_results?: number;     // Search results to consider (default: 5, max: 10)
+      gather_top?: number;      // Pages read in full (default: 3, max: 5)
+    } & ExtractionLimits
+  ): Promise<WebAnswerResponse> {
+    const response = await fetch(`${BACKEND_URL}/api/v1/web/answer`, {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify({ query, ...options }),
+    });
+    if (!response.ok) {
+      const error = await response.json().catch(() => null);
+      throw new Error(`Web answer failed: ${error?.error || response.statusText}`);
+    }
+    return response.json();
+  },
+
   /**
    * Browse and extract content from a specific URL
    * 
    * @param url - URL to browse and extract content from
    * @param render - Enable WebView rendering for JavaScript-heavy pages
+   * @param depth - Follow links within the page's section this many levels deep (max 3)
+   * @param maxPages - Maximum pages combined when crawling (default 10, max 25)
+   * @param screenshot - Attach a PNG of the rendered page, viewport or full page
+   * @param context - Named browsing context to render in, e.g. one logged in from Privacy settings
+   * @param limits - Fetch and render timeouts and a time budget; a crawl returns the pages read in time
    * @returns PageExtract with full content, metadata, and confidence scores
    */
   async browse(
     url: string,
-    render?: boolean
+    render?: boolean,
+    depth?: number,
+    maxPages?: number,
+    screenshot?: 'viewport' | 'full_page',
+    context?: string,
+    limits?: ExtractionLimits
   ): Promise<PageExtract> {
     const params = new URLSearchParams({ 
       url,
       render: (render ?? false).toString()
     });
+    if (depth) {
+      params.append('depth', depth.toString());
+    }
+    if (maxPages) {
+      params.append('max_pages', maxPages.toString());
+    }
+    if (screenshot) {
+      params.append('screenshot', screenshot);
+    }
+    if (context) {
+      params.append('context', context);
+    }
+    appendLimits(params, limits);
     
     const response = await fetch(`${BACKEND_URL}/api/v1/browse?${params}`);
     if (!response.ok) {
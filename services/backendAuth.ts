@@ -0,0 +1,86 @@
+/**
+ * Backend Authentication
+ *
+ * The desktop shell generates a token at every launch and starts the backend
+ * with it; every backend route except /health rejects requests without it.
+ * `installBackendAuth` wraps `fetch` so requests to the backend carry the
+ * token automatically, along with the window's context ID (see
+ * windowContext.ts). Clients that can't set headers (EventSource, <img>)
+ * use `withBackendToken` to put the token in the query string instead.
+ *
+ * In the browser build there is no shell, so no token is sent and the
+ * backend runs with authentication disabled.
+ */
+
+import { invoke } from '@tauri-apps/api/core';
+import { isTauriApp } from './tauriDetection';
+import { CONTEXT_HEADER, getWindowContextId } from './windowContext';
+
+const BACKEND_ORIGIN = 'http://127.0.0.1:3001';
+
+let token: string | null = null;
+let tokenPromise: Promise<string | null> | null = null;
+let installed = false;
+
+/**
+ * Fetch the launch token from the shell once; null outside Tauri
+ */
+export function loadBackendToken(): Promise<string | null> {
+  if (!tokenPromise) {
+    tokenPromise = isTauriApp()
+      ? invoke<string | null>('get_backend_auth_token')
+          .then(value => {
+            token = value ?? null;
+            return token;
+          })
+          .catch(error => {
+            console.warn('[BackendAuth] Failed to get backend token:', error);
+            tokenPromise = null;
+            return null;
+          })
+      : Promise.resolve(null);
+  }
+  return tokenPromise;
+}
+
+/**
+ * Append the token to a backend URL, for clients that can't send headers.
+ * Call after `loadBackendToken` has resolved.
+ */
+export function withBackendToken(url: string): string {
+  if (!token || !url.startsWith(BACKEND_ORIGIN)) {
+    return url;
+  }
+  const separator = url.includes('?') ? '&' : '?';
+  return `${url}${separator}token=${encodeURIComponent(token)}`;
+}
+
+/**
+ * Wrap window.fetch so every request to the backend carries the token and
+ * this window's context ID
+ */
+export function installBackendAuth(): void {
+  if (installed || typeof window === 'undefined') {
+    return;
+  }
+  installed = true;
+  void loadBackendToken();
+
+  const originalFetch = window.fetch.bind(window);
+  window.fetch = async (input: RequestInfo | URL, init?: RequestInit) => {
+    const url = typeof input === 'string' ? input : input instanceof URL ? input.href : input.url;
+    if (!url.startsWith(BACKEND_ORIGIN)) {
+      return originalFetch(input, init);
+    }
+
+    const value = await loadBackendToken();
+    const headers = new Headers(init?.headers ?? (input instanceof Request ? input.headers : undefined));
+    if (value) {
+      headers.set('Authorization', `Bearer ${value}`);
+    }
+    if (!headers.has(CONTEXT_HEADER)) {
+      headers.set(CONTEXT_HEADER, getWindowContextId());
+    }
+    return originalFetch(input, { ...init, headers });
+  };
+}
@@ -0,0 +1,177 @@
+/**
+ * Backend Event Stream
+ *
+ * Keeps a single server-sent events connection to the backend event bus
+ * (/api/v1/events). Services subscribe by event type instead of running
+ * their own polling loops.
+ */
+
+import { loadBackendToken, withBackendToken } from './backendAuth';
+
+const EVENTS_URL = 'http://127.0.0.1:3001/api/v1/events';
+
+const TOPICS = ['agents', 'workflows', 'terminal', 'indexer', 'search', 'ai', 'config', 'files', 'audio', 'feeds', 'disk'] as const;
+const MAX_RECONNECT_DELAY_MS = 30000;
+
+export type BackendEventTopic = typeof TOPICS[number];
+
+export interface BackendEvent {
+  id: number;
+  topic: BackendEventTopic;
+  timestamp: string;
+  /** Snake-case event type, e.g. 'terminal_output' or 'workflow_run' */
+  type: string;
+  [field: string]: unknown;
+}
+
+type EventHandler = (event: BackendEvent) => void;
+
+class BackendEventStream {
+  private source: EventSource | null = null;
+  private handlers: Map<string, Set<EventHandler>> = new Map();
+  private resyncHandlers: Set<() => void> = new Set();
+  private connected = false;
+  private connecting = false;
+  private hasConnected = false;
+  private reconnectDelay = 1000;
+  private reconnectTimer: ReturnType<typeof setTimeout> | null = null;
+
+  /**
+   * Whether this environment can open an event stream
+   */
+  isSupported(): boolean {
+    return typeof EventSource !== 'undefined';
+  }
+
+  /**
+   * Whether the stream is currently open; events may be missed while it isn't
+   */
+  isConnected(): boolean {
+    return this.connected;
+  }
+
+  /**
+   * Subscribe to an event type ('*' for every event). Returns an unsubscribe function.
+   */
+  subscribe(type: string, handler: EventHandler): () => void {
+    if (!this.handlers.has(type)) {
+      this.handlers.set(type, new Set());
+    }
+    this.handlers.get(type)!.add(handler);
+    this.connect();
+
+    return () => {
+      this.handlers.get(type)?.delete(handler);
+      this.disconnectIfIdle();
+    };
+  }
+
+  /**
+   * Called when events may have been missed (stream reconnected or the
+   * client fell behind), so subscribers can refetch their state
+   */
+  onResync(handler: () => void): () => void {
+    this.resyncHandlers.add(handler);
+    return () => {
+      this.resyncHandlers.delete(handler);
+    };
+  }
+
+  private connect(): void {
+    if (this.source || this.connecting || this.reconnectTimer || !this.isSupported()) {
+      return;
+    }
+
+    // EventSource can't send headers, so the token goes in the query string
+    this.connecting = true;
+    loadBackendToken().then(() => {
+      this.connecting = false;
+      if (this.handlers.size > 0 && !this.source) {
+        this.open();
+      }
+    });
+  }
+
+  private open(): void {
+    const source = new EventSource(withBackendToken(EVENTS_URL));
+    this.source = source;
+
+    source.onopen = () => {
+      this.connected = true;
+      this.reconnectDelay = 1000;
+      if (this.hasConnected) {
+        this.resync();
+      }
+      this.hasConnected = true;
+    };
+
+    const onMessage = (message: MessageEvent) => {
+      try {
+        this.dispatch(JSON.parse(message.data) as BackendEvent);
+      } catch (error) {
+        console.warn('[BackendEvents] Ignoring malformed event:', error);
+      }
+    };
+    TOPICS.forEach(topic => source.addEventListener(topic, onMessage as EventListener));
+
+    source.addEventListener('lagged', () => {
+      console.warn('[BackendEvents] Fell behind the event stream, resyncing');
+      this.resync();
+    });
+
+    source.onerror = () => {
+      this.connected = false;
+      source.close();
+      this.source = null;
+      this.scheduleReconnect();
+    };
+  }
+
+  private scheduleReconnect(): void {
+    if (this.reconnectTimer || this.handlers.size === 0) {
+      return;
+    }
+    this.reconnectTimer = setTimeout(() => {
+      this.reconnectTimer = null;
+      this.connect();
+    }, this.reconnectDelay);
+    this.reconnectDelay = Math.min(this.reconnectDelay * 2, MAX_RECONNECT_DELAY_MS);
+  }
+
+  private disconnectIfIdle(): void {
+    for (const [type, handlers] of this.handlers) {
+      if (handlers.size === 0) {
+        this.handlers.delete(type);
+      }
+    }
+    if (this.handlers.size > 0) {
+      return;
+    }
+
+    this.source?.close();
+    this.source = null;
+    this.connected = false;
+    if (this.reconnectTimer) {
+      clearTimeout(this.reconnectTimer);
+      this.reconnectTimer = null;
+    }
+  }
+
+  private dispatch(event: BackendEvent): void {
+    for (const type of [event.type, '*']) {
+      this.handlers.get(type)?.forEach(handler => {
+        try {
+          handler(event);
+        } catch (error) {
+          console.error('[BackendEvents] Handler error for', event.type, error);
+        }
+      });
+    }
+  }
+
+  private resync(): void {
+    this.resyncHandlers.forEach(handler => handler());
+  }
+}
+
+export const backendEvents = new BackendEventStream();
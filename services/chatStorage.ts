@@ -1,4 +1,5 @@
 import { Chat, Message } from '../types';
+import { backendApi } from './backendApi';
 
 const STORAGE_KEY_PREFIX = 'skhoot_chat_';
 const INDEX_KEY = 'skhoot_chat_index';
@@ -71,6 +72,27 @@ const deserializeChat = (data: any): Chat => {
   };
 };
 
+// Chats are saved on every message; index them for search once they settle
+const INDEX_DELAY_MS = 2000;
+const pendingIndex = new Map<string, ReturnType<typeof setTimeout>>();
+
+const scheduleIndex = (chat: Chat): void => {
+  clearTimeout(pendingIndex.get(chat.id));
+  pendingIndex.set(chat.id, setTimeout(() => {
+    pendingIndex.delete(chat.id);
+    const messages = chat.messages.map(m => ({
+      id: m.id,
+      role: m.role,
+      content: m.content,
+      tool_calls: m.toolCalls?.map(tc => ({ name: tc.name, arguments: tc.arguments })),
+      timestamp: m.timestamp.toISOString(),
+    }));
+    backendApi.indexConversation(chat.id, messages).catch(error => {
+      console.debug('Failed to index chat for search:', error);
+    });
+  }, INDEX_DELAY_MS));
+};
+
 export const chatStorage = {
   // Get all chats (metadata only for the list)
   getChats(): Chat[] {
@@ -159,6 +181,7 @@ export const chatStorage = {
       }
       
       localStorage.setItem(INDEX_KEY, JSON.stringify(index));
+      scheduleIndex(chat);
     } catch (error) {
       if (error instanceof Error && error.name === 'QuotaExceededError') {
         console.error('CRITICAL: LocalStorage quota exceeded. Attempting to emergency cleanup...');
@@ -194,6 +217,9 @@ export const chatStorage = {
 
   // Delete a chat
   deleteChat(id: string): void {
+    clearTimeout(pendingIndex.get(id));
+    pendingIndex.delete(id);
+    backendApi.removeConversationFromIndex(id).catch(() => {});
     try {
       localStorage.removeItem(STORAGE_KEY_PREFIX + id);
       
@@ -27,6 +27,13 @@ export interface ModelInfo {
   description?: string;
 }
 
+export interface ChatRequestOptions {
+  /** Sampling temperature sent to the provider; 0 makes the request cacheable */
+  temperature?: number;
+  /** Skip the response cache lookup for this request (the fresh response is still cached) */
+  bypassCache?: boolean;
+}
+
 export interface ProviderConfig {
   id: string;
   name: string;
@@ -91,6 +98,8 @@ export const KNOWN_PROVIDERS: Record<string, ProviderConfig> = {
 };
 
 import { AgentChatMessage, AgentChatResponse, AgentToolCall } from './agent/types';
+import { backendApi } from './backendApi';
+import { fetchWithRetry } from './providerRetry';
 
 // ============================================================================
 // Provider Registry Class
@@ -113,7 +122,48 @@ class ProviderRegistry {
     systemPrompt: string,
     tools?: any[],
     images?: Array<{ fileName: string; base64: string; mimeType: string }>,
-    abortSignal?: AbortSignal
+    abortSignal?: AbortSignal,
+    options: ChatRequestOptions = {}
+  ): Promise<AgentChatResponse> {
+    // Temperature 0 requests without images are deterministic enough to replay
+    const cacheRequest = options.temperature === 0 && !images?.length
+      ? {
+          model: `${providerId}/${model}`,
+          system_prompt: systemPrompt,
+          messages: [...history, { role: 'user', content: message }],
+          tools: tools ?? null,
+          temperature: 0,
+          bypass_cache: options.bypassCache ?? false,
+        }
+      : null;
+
+    if (cacheRequest && !options.bypassCache) {
+      const cached = await backendApi.lookupAIResponse(cacheRequest).catch(() => null);
+      if (cached) {
+        console.log(`[ProviderRegistry] Response cache hit for ${providerId}/${model}`);
+        return cached as AgentChatResponse;
+      }
+    }
+
+    const response = await this.chatUncached(providerId, model, apiKey, message, history, systemPrompt, tools, images, abortSignal, options.temperature);
+
+    if (cacheRequest) {
+      backendApi.storeAIResponse(cacheRequest, response).catch(() => {});
+    }
+    return response;
+  }
+
+  private async chatUncached(
+    providerId: string,
+    model: string,
+    apiKey: string,
+    message: string,
+    history: AgentChatMessage[],
+    systemPrompt: string,
+    tools?: any[],
+    images?: Array<{ fileName: string; base64: string; mimeType: string }>,
+    abortSignal?: AbortSignal,
+    temperature?: number
   ): Promise<AgentChatResponse> {
     const apiFormat = this.getApiFormat(providerId);
     const baseUrl = this.getBaseUrl(providerId);
@@ -121,18 +171,18 @@ class ProviderRegistry {
     switch (apiFormat) {
       case 'openai':
       case 'kiro':
-        return this.chatOpenAI(baseUrl, apiKey, model, message, history, systemPrompt, tools, images, abortSignal);
+        return this.chatOpenAI(baseUrl, apiKey, model, message, history, systemPrompt, tools, images, abortSignal, temperature);
       case 'anthropic':
-        return this.chatAnthropic(baseUrl, apiKey, model, message, history, systemPrompt, tools, images, abortSignal);
+        return this.chatAnthropic(baseUrl, apiKey, model, message, history, systemPrompt, tools, images, abortSignal, temperature);
       case 'google':
-        return this.chatGoogle(baseUrl, apiKey, model, message, history, systemPrompt, tools, images, abortSignal);
+        return this.chatGoogle(baseUrl, apiKey, model, message, history, systemPrompt, tools, images, abortSignal, temperature);
       default:
         throw new Error(`Unsupported API format: ${apiFormat}`);
     }
   }
 
   // --- OpenAI Adapter ---
-  private async chatOpenAI(baseUrl: string, apiKey: string, model: string, message: string, history: AgentChatMessage[], systemPrompt: string, tools?: any[], images?: any[], abortSignal?: AbortSignal): Promise<AgentChatResponse> {
+  private async chatOpenAI(baseUrl: string, apiKey: string, model: string, message: string, history: AgentChatMessage[], systemPrompt: string, tools?: any[], images?: any[], abortSignal?: AbortSignal, temperature?: number): Promise<AgentChatResponse> {
     const messages = [
       { role: 'system', content: systemPrompt },
       ...history.map(msg => {
@@ -152,12 +202,12 @@ class ProviderRegistry {
       }
     }
 
-    const response = await fetch(`${baseUrl}/chat/completions`, {
+    const response = await fetchWithRetry(`${baseUrl}/chat/completions`, {
       method: 'POST',
       headers: { 'Content-Type': 'application/json', 'Authorization': `Bearer ${apiKey}` },
-      body: JSON.stringify({ model, messages, tools, tool_choice: tools ? 'auto' : undefined, stream: false }),
+      body: JSON.stringify({ model, messages, tools, tool_choice: tools ? 'auto' : undefined, temperature, stream: false }),
       signal: abortSignal
-    });
+    }, { idempotent: !tools?.length });
 
     if (!response.ok) throw new Error(`OpenAI API error: ${response.status} - ${await response.text()}`);
     const data = await response.json();
@@ -172,7 +222,7 @@ class ProviderRegistry {
   }
 
   // --- Anthropic Adapter ---
-  private async chatAnthropic(baseUrl: string, apiKey: string, model: string, message: string, history: AgentChatMessage[], systemPrompt: string, tools?: any[], images?: any[], abortSignal?: AbortSignal): Promise<AgentChatResponse> {
+  private async chatAnthropic(baseUrl: string, apiKey: string, model: string, message: string, history: AgentChatMessage[], systemPrompt: string, tools?: any[], images?: any[], abortSignal?: AbortSignal, temperature?: number): Promise<AgentChatResponse> {
     const messages = history.filter(msg => msg.role !== 'system').map(msg => {
       if (msg.role === 'tool') return { role: 'user', content: [{ type: 'tool_result', tool_use_id: msg.toolCallId, content: msg.content }] };
       if (msg.toolCalls) return { role: 'assistant', content: [...(msg.content ? [{ type: 'text', text: msg.content }] : []), ...msg.toolCalls.map(tc => ({ type: 'tool_use', id: tc.id, name: tc.name, input: tc.arguments }))] };
@@ -189,12 +239,12 @@ class ProviderRegistry {
       }
     }
 
-    const response = await fetch(`${baseUrl}/messages`, {
+    const response = await fetchWithRetry(`${baseUrl}/messages`, {
       method: 'POST',
       headers: { 'Content-Type': 'application/json', 'x-api-key': apiKey, 'anthropic-version': '2023-06-01', 'anthropic-dangerous-direct-browser-access': 'true' },
-      body: JSON.stringify({ model, messages, system: systemPrompt, tools, max_tokens: 4096, stream: false }),
+      body: JSON.stringify({ model, messages, system: systemPrompt, tools, max_tokens: 4096, temperature, stream: false }),
       signal: abortSignal
-    });
+    }, { idempotent: !tools?.length });
 
     if (!response.ok) throw new Error(`Anthropic API error: ${response.status} - ${await response.text()}`);
     const data = await response.json();
@@ -207,7 +257,7 @@ class ProviderRegistry {
   }
 
   // --- Google Gemini Adapter ---
-  private async chatGoogle(baseUrl: string, apiKey: string, model: string, message: string, history: AgentChatMessage[], systemPrompt: string, tools?: any[], images?: any[], abortSignal?: AbortSignal): Promise<AgentChatResponse> {
+  private async chatGoogle(baseUrl: string, apiKey: string, model: string, message: string, history: AgentChatMessage[], systemPrompt: string, tools?: any[], images?: any[], abortSignal?: AbortSignal, temperature?: number): Promise<AgentChatResponse> {
     const contents: any[] = [];
     let i = 0;
     while (i < history.length) {
@@ -252,12 +302,12 @@ class ProviderRegistry {
       contents.push({ role: 'user', parts });
     }
 
-    const response = await fetch(`${baseUrl}/models/${model}:generateContent?key=${apiKey}`, {
+    const response = await fetchWithRetry(`${baseUrl}/models/${model}:generateContent?key=${apiKey}`, {
       method: 'POST',
       headers: { 'Content-Type': 'application/json' },
-      body: JSON.stringify({ contents, systemInstruction: { parts: [{ text: systemPrompt }] }, generationConfig: { temperature: 0.7, maxOutputTokens: 8192 }, tools }),
+      body: JSON.stringify({ contents, systemInstruction: { parts: [{ text: systemPrompt }] }, generationConfig: { temperature: temperature ?? 0.7, maxOutputTokens: 8192 }, tools }),
       signal: abortSignal
-    });
+    }, { idempotent: !tools?.length });
 
     if (!response.ok) throw new Error(`Google API error: ${response.status} - ${await response.text()}`);
     const data = await response.json();
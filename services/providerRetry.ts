@@ -0,0 +1,109 @@
+/**
+ * Retries of transient provider errors
+ *
+ * Rate limits (429) and server errors (5xx) usually clear up a moment later,
+ * so requests are retried with exponential backoff and full jitter, waiting at
+ * least as long as the provider's Retry-After header asks. Requests that are
+ * not idempotent (chat requests offering tools) are only retried when the
+ * provider turned them away before doing any work: rate limits and overload.
+ * After a timeout or a 500 the model may already have answered, and asking
+ * again could produce a different set of tool calls.
+ */
+
+export interface RetryPolicy {
+  /** Attempts in total, including the first */
+  maxAttempts: number;
+  /** Backoff before the first retry in ms, doubled for each further one */
+  baseDelayMs: number;
+  maxDelayMs: number;
+  /** Longest Retry-After honored; asking for more fails the request */
+  maxRetryAfterMs: number;
+}
+
+export const DEFAULT_RETRY_POLICY: RetryPolicy = {
+  maxAttempts: 3,
+  baseDelayMs: 500,
+  maxDelayMs: 8000,
+  maxRetryAfterMs: 60000,
+};
+
+/** Status codes meaning the request was turned away unprocessed */
+const REJECTED = [429, 503, 529];
+
+export function isRetryableStatus(status: number, idempotent: boolean): boolean {
+  if (REJECTED.includes(status)) return true;
+  return idempotent && (status === 408 || (status >= 500 && status < 600));
+}
+
+/**
+ * Parse a Retry-After value (seconds or an HTTP date) into milliseconds
+ */
+export function parseRetryAfter(value: string | null, now = Date.now()): number | undefined {
+  if (!value) return undefined;
+  const trimmed = value.trim();
+  if (/^\d+$/.test(trimmed)) return parseInt(trimmed, 10) * 1000;
+  const at = Date.parse(trimmed);
+  return Number.isNaN(at) ? undefined : Math.max(0, at - now);
+}
+
+/**
+ * Wait before retry number `retry` (from 1), or undefined if the budget is
+ * spent or the provider asks for a longer pause than we accept
+ */
+export function retryDelay(retry: number, retryAfterMs: number | undefined, policy = DEFAULT_RETRY_POLICY): number | undefined {
+  if (retry >= policy.maxAttempts) return undefined;
+  if (retryAfterMs !== undefined && retryAfterMs > policy.maxRetryAfterMs) return undefined;
+  const ceiling = Math.min(policy.baseDelayMs * 2 ** (retry - 1), policy.maxDelayMs);
+  const jittered = Math.random() * ceiling;
+  return Math.max(retryAfterMs ?? 0, jittered);
+}
+
+function sleep(ms: number, signal?: AbortSignal | null): Promise<void> {
+  return new Promise((resolve, reject) => {
+    if (signal?.aborted) {
+      reject(new DOMException('Aborted', 'AbortError'));
+      return;
+    }
+    const timer = setTimeout(() => {
+      signal?.removeEventListener('abort', onAbort);
+      resolve();
+    }, ms);
+    const onAbort = () => {
+      clearTimeout(timer);
+      reject(new DOMException('Aborted', 'AbortError'));
+    };
+    signal?.addEventListener('abort', onAbort, { once: true });
+  });
+}
+
+/**
+ * fetch() with retries of transient failures. Returns the last response when
+ * retries run out, so the caller still sees the provider's error.
+ */
+export async function fetchWithRetry(
+  url: string,
+  init: RequestInit,
+  options: { idempotent: boolean; policy?: RetryPolicy; onRetry?: (reason: string, delayMs: number) => void }
+): Promise<Response> {
+  const policy = options.policy ?? DEFAULT_RETRY_POLICY;
+  for (let retry = 1; ; retry++) {
+    let response: Response;
+    try {
+      response = await fetch(url, init);
+    } catch (error) {
+      // fetch() rejects with a TypeError when the request could not be
+      // completed; it may still have reached the provider
+      const delay = options.idempotent && error instanceof TypeError ? retryDelay(retry, undefined, policy) : undefined;
+      if (delay === undefined) throw error;
+      options.onRetry?.(error.message, delay);
+      await sleep(delay, init.signal);
+      continue;
+    }
+
+    if (!isRetryableStatus(response.status, options.idempotent)) return response;
+    const delay = retryDelay(retry, parseRetryAfter(response.headers.get('Retry-After')), policy);
+    if (delay === undefined) return response;
+    options.onRetry?.(`HTTP ${response.status}`, delay);
+    await sleep(delay, init.signal);
+  }
+}
@@ -83,7 +83,9 @@ function convertFileSearchResults(backendResults: any, fileTypesFilter?: string)
       score: result.relevance_score,
       source: result.source_engine,
       snippet: result.snippet,
-      fileType: result.file_type
+      lineNumber: result.line_number,
+      fileType: result.file_type,
+      metadata: result.metadata
     };
   }) || [];
 
@@ -97,6 +99,7 @@ function convertFileSearchResults(backendResults: any, fileTypesFilter?: string)
   return {
     files,
     searchInfo: {
+      searchId: backendResults.search_id,
       query: backendResults.query,
       totalResults: files.length,
       executionTime: backendResults.total_execution_time_ms,
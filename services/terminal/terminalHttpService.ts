@@ -5,6 +5,8 @@
  * This replaces the Tauri IPC approach for better separation of concerns.
  */
 
+import { backendEvents } from '../backendEvents';
+
 const BACKEND_URL = 'http://127.0.0.1:3001/api/v1/terminal';
 
 export interface SessionInfo {
@@ -14,12 +16,15 @@ export interface SessionInfo {
   rows: number;
   created_at: string;
   last_activity: string;
+  shared_with_agent: boolean;
 }
 
 export interface CreateSessionRequest {
   shell?: string;
   cols?: number;
   rows?: number;
+  /** Environment profile from the `environments` settings section */
+  profile?: string;
 }
 
 export interface ReadResponse {
@@ -31,6 +36,9 @@ class TerminalHttpService {
   private pollingIntervals: Map<string, NodeJS.Timeout> = new Map();
   private outputBuffer: Map<string, string[]> = new Map(); // Buffer recent output per session
   private cursors: Map<string, number> = new Map(); // Track read cursor per session
+  private subscriptions: Map<string, () => void> = new Map(); // Event stream unsubscribers per session
+  private readsInFlight: Set<string> = new Set();
+  private pendingReads: Set<string> = new Set();
   private readonly MAX_BUFFER_SIZE = 100; // Keep last 100 lines per session
   
   /**
@@ -98,6 +106,25 @@ class TerminalHttpService {
     }
   }
   
+  /**
+   * Let the agent read a session's recent output, or stop it from doing so
+   */
+  async setAgentAccess(sessionId: string, enabled: boolean): Promise<boolean> {
+    const response = await fetch(`${BACKEND_URL}/sessions/${sessionId}/agent-access`, {
+      method: 'PUT',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify({ enabled }),
+    });
+
+    if (!response.ok) {
+      const error = await response.json();
+      throw new Error(error.error || 'Failed to change agent access');
+    }
+
+    const data = await response.json();
+    return data.shared_with_agent;
+  }
+
   /**
    * List all terminal sessions
    */
@@ -112,63 +139,106 @@ class TerminalHttpService {
   }
   
   /**
-   * Start polling for output and emit events
+   * Start streaming output and emit events
+   *
+   * Output is read when the backend event stream announces it; the interval
+   * only polls while that stream is unavailable.
    */
   startPolling(sessionId: string, intervalMs: number = 100): void {
     if (this.pollingIntervals.has(sessionId)) {
       return;
     }
     
-    console.log('[TerminalHttpService] Starting polling for session:', sessionId);
+    console.log('[TerminalHttpService] Starting output stream for session:', sessionId);
     
     // Initialize buffer for this session
     if (!this.outputBuffer.has(sessionId)) {
       this.outputBuffer.set(sessionId, []);
     }
     
-    const interval = setInterval(async () => {
-      try {
+    if (backendEvents.isSupported()) {
+      const unsubscribeOutput = backendEvents.subscribe('terminal_output', event => {
         const cursor = this.cursors.get(sessionId) || 0;
-        const response = await this.read(sessionId, cursor);
-        const output = response.output;
-        
-        // Update cursor
-        this.cursors.set(sessionId, response.next_cursor);
-        
-        if (output.length > 0) {
-          console.log('[TerminalHttpService] Received output for session:', sessionId, 'lines:', output.length);
-          output.forEach(content => {
-            // Strip ANSI escape codes for cleaner display
-            const cleanContent = this.stripAnsi(content);
-            if (cleanContent.trim()) {
-              console.log('[TerminalHttpService] Emitting terminal-data:', { sessionId, data: cleanContent.substring(0, 50) });
-              
-              // Add to buffer
-              const buffer = this.outputBuffer.get(sessionId) || [];
-              buffer.push(cleanContent);
-              // Keep only last MAX_BUFFER_SIZE lines
-              if (buffer.length > this.MAX_BUFFER_SIZE) {
-                buffer.shift();
-              }
-              this.outputBuffer.set(sessionId, buffer);
-              
-              // Emit event
-              window.dispatchEvent(new CustomEvent('terminal-data', {
-                detail: { sessionId, data: cleanContent, type: 'stdout' }
-              }));
-            }
-          });
+        if (event.session_id === sessionId && (event.cursor as number) !== cursor) {
+          this.scheduleRead(sessionId);
         }
-      } catch (error) {
-        console.error('[TerminalHttpService] Polling error for session:', sessionId, error);
-        // Session might be closed
-        this.stopPolling(sessionId);
+      });
+      const unsubscribeResync = backendEvents.onResync(() => this.scheduleRead(sessionId));
+      this.subscriptions.set(sessionId, () => {
+        unsubscribeOutput();
+        unsubscribeResync();
+      });
+      // Catch up on output produced before subscribing
+      this.scheduleRead(sessionId);
+    }
+    
+    const interval = setInterval(() => {
+      if (!backendEvents.isConnected()) {
+        this.scheduleRead(sessionId);
       }
     }, intervalMs);
     
     this.pollingIntervals.set(sessionId, interval);
   }
   
+  /**
+   * Read new output, coalescing requests made while a read is in flight
+   */
+  private scheduleRead(sessionId: string): void {
+    if (this.readsInFlight.has(sessionId)) {
+      this.pendingReads.add(sessionId);
+      return;
+    }
+    
+    this.readsInFlight.add(sessionId);
+    this.readOutput(sessionId).finally(() => {
+      this.readsInFlight.delete(sessionId);
+      if (this.pendingReads.delete(sessionId) && this.pollingIntervals.has(sessionId)) {
+        this.scheduleRead(sessionId);
+      }
+    });
+  }
+  
+  private async readOutput(sessionId: string): Promise<void> {
+    try {
+      const cursor = this.cursors.get(sessionId) || 0;
+      const response = await this.read(sessionId, cursor);
+      const output = response.output;
+      
+      // Update cursor
+      this.cursors.set(sessionId, response.next_cursor);
+      
+      if (output.length > 0) {
+        console.log('[TerminalHttpService] Received output for session:', sessionId, 'lines:', output.length);
+        output.forEach(content => {
+          // Strip ANSI escape codes for cleaner display
+          const cleanContent = this.stripAnsi(content);
+          if (cleanContent.trim()) {
+            console.log('[TerminalHttpService] Emitting terminal-data:', { sessionId, data: cleanContent.substring(0, 50) });
+            
+            // Add to buffer
+            const buffer = this.outputBuffer.get(sessionId) || [];
+            buffer.push(cleanContent);
+            // Keep only last MAX_BUFFER_SIZE lines
+            if (buffer.length > this.MAX_BUFFER_SIZE) {
+              buffer.shift();
+            }
+            this.outputBuffer.set(sessionId, buffer);
+            
+            // Emit event
+            window.dispatchEvent(new CustomEvent('terminal-data', {
+              detail: { sessionId, data: cleanContent, type: 'stdout' }
+            }));
+          }
+        });
+      }
+    } catch (error) {
+      console.error('[TerminalHttpService] Read error for session:', sessionId, error);
+      // Session might be closed
+      this.stopPolling(sessionId);
+    }
+  }
+  
   /**
    * Get buffered output for a session
    * This allows components to retrieve output that was emitted before they mounted
@@ -194,7 +264,7 @@ class TerminalHttpService {
   }
   
   /**
-   * Stop polling for a session
+   * Stop streaming output for a session
    */
   stopPolling(sessionId: string): void {
     const interval = this.pollingIntervals.get(sessionId);
@@ -202,6 +272,9 @@ class TerminalHttpService {
       clearInterval(interval);
       this.pollingIntervals.delete(sessionId);
     }
+    this.subscriptions.get(sessionId)?.();
+    this.subscriptions.delete(sessionId);
+    this.pendingReads.delete(sessionId);
     // Clean up buffer and cursor when polling stops
     this.outputBuffer.delete(sessionId);
     this.cursors.delete(sessionId);
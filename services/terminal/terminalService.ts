@@ -372,6 +372,21 @@ class TerminalService {
     }
   }
 
+  /**
+   * Share a session's recent output with the agent (read_terminal tool), or
+   * stop sharing it. Only supported by the HTTP backend.
+   *
+   * @param sessionId - Session ID to share
+   * @param enabled - Whether the agent may read the session
+   * @returns Whether the session is now shared
+   */
+  async setAgentAccess(sessionId: string, enabled: boolean): Promise<boolean> {
+    if (!(await this.checkHttpBackend())) {
+      throw new Error('Sharing terminals with the agent requires the backend terminal service');
+    }
+    return terminalHttpService.setAgentAccess(sessionId, enabled);
+  }
+
   /**
    * List all active terminal sessions from backend
    * 
@@ -0,0 +1,39 @@
+/**
+ * Window Context
+ *
+ * Each window gets its own context ID, sent to the backend in the
+ * X-Skhoot-Context header so agent sessions, terminals and workflow runs
+ * started here stay isolated from other windows. The ID lives in
+ * sessionStorage: it survives reloads but is not shared between windows.
+ */
+
+const STORAGE_KEY = 'skhoot_window_context';
+
+export const CONTEXT_HEADER = 'X-Skhoot-Context';
+
+let contextId: string | null = null;
+
+/**
+ * Context ID of this window
+ */
+export function getWindowContextId(): string {
+  if (contextId) {
+    return contextId;
+  }
+
+  try {
+    contextId = sessionStorage.getItem(STORAGE_KEY);
+  } catch {
+    // Storage unavailable; use an in-memory ID for this page load
+  }
+
+  if (!contextId) {
+    contextId = crypto.randomUUID();
+    try {
+      sessionStorage.setItem(STORAGE_KEY, contextId);
+    } catch {
+      // Ignore
+    }
+  }
+  return contextId;
+}
@@ -147,6 +147,21 @@ export interface CreateWorkflowRequest {
   variables?: WorkflowVariable[];
 }
 
+/** Problem reported by the backend's workflow validation */
+export interface WorkflowDiagnostic {
+  severity: 'error' | 'warning';
+  code: string;
+  message: string;
+  step_id?: string;
+  /** Step field the problem is in, e.g. 'next_step' or 'http.url' */
+  field?: string;
+}
+
+export interface WorkflowValidationReport {
+  valid: boolean;
+  diagnostics: WorkflowDiagnostic[];
+}
+
 // ============================================================================
 // Default Workflows
 // ============================================================================
@@ -445,6 +460,26 @@ class WorkflowService {
     return deleted;
   }
 
+  /**
+   * Check a workflow without running it. Placeholders are resolved against
+   * the sample variables and trigger payload.
+   */
+  async validate(
+    id: string,
+    variables: Record<string, any> = {},
+    triggerPayload?: any
+  ): Promise<WorkflowValidationReport> {
+    const response = await fetch(`${backendApi.baseUrl}/workflows/${id}/validate`, {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify({ variables, trigger_payload: triggerPayload })
+    });
+    if (!response.ok) {
+      throw new Error(`Failed to validate workflow ${id}: ${response.status}`);
+    }
+    return response.json();
+  }
+
   // ==========================================================================
   // Execution
   // ==========================================================================
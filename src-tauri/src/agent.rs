@@ -11,7 +11,13 @@ use std::time::{SystemTime, UNIX_EPOCH};
 use tauri::{AppHandle, Emitter, State};
 use tokio::sync::RwLock;
 
-use skhoot_backend::cli_agent::{AgentExecutor, ExecutorConfig};
+use skhoot_backend::cli_agent::prompt_templates::PromptTemplateSet;
+use skhoot_backend::cli_agent::session::{AgentMessage, MessageRole};
+use skhoot_backend::cli_agent::{
+    AgentExecutor, Checkpoint, CheckpointManager, ConversationArchive, ConversationMetadata, DispatchOutcome,
+    ExecutorConfig, ExportFormat, MessageDispatcher, ProjectDetector, ProjectProfile, PromptScope, PromptTemplate,
+    PromptTemplateStore, SystemPrompt, ToolAttachment, ToolCall,
+};
 
 /// Session state - lightweight, no PTY or complex types
 #[derive(Debug, Clone)]
@@ -27,6 +33,26 @@ struct AgentSessionState {
     created_at: u64,
     last_activity: u64,
     terminal_session_id: Option<String>,
+    /// Root directory agent file tools are confined to
+    workspace_root: Option<PathBuf>,
+    /// Whether the user allowed file access outside the workspace root
+    allow_workspace_escape: bool,
+    /// Whether the user allowed deleting files without the trash
+    allow_permanent_delete: bool,
+    /// Whether the user allowed killing processes the agent didn't start
+    allow_process_kill: bool,
+    /// Whether the user allowed the agent to use the clipboard
+    allow_clipboard: bool,
+    /// Whether the user allowed the agent to capture the screen
+    allow_screen_capture: bool,
+    /// Domains the user allowed the agent's HTTP requests to reach
+    allowed_http_domains: Vec<String>,
+    /// Whether the agent may create git commits
+    allow_git_commits: bool,
+    /// Imported conversations can be read but not continued
+    read_only: bool,
+    /// Project detected in the working directory
+    project: Option<ProjectProfile>,
 }
 
 /// Stored message in session
@@ -43,12 +69,15 @@ struct StoredMessage {
 /// Agent state managed by Tauri
 pub struct AgentTauriState {
     sessions: Arc<RwLock<HashMap<String, AgentSessionState>>>,
+    /// Deduplicates user messages sent from several windows or retries
+    dispatcher: MessageDispatcher,
 }
 
 impl Default for AgentTauriState {
     fn default() -> Self {
         Self {
             sessions: Arc::new(RwLock::new(HashMap::new())),
+            dispatcher: MessageDispatcher::new(),
         }
     }
 }
@@ -61,6 +90,10 @@ pub struct CreateAgentSessionOptions {
     pub working_directory: Option<String>,
     pub temperature: Option<f32>,
     pub max_tokens: Option<u32>,
+    /// Confine file tools to this directory (sandbox disabled if unset)
+    pub workspace_root: Option<String>,
+    /// Allow the git_commit tool (defaults to true)
+    pub allow_git_commits: Option<bool>,
 }
 
 /// Agent message for frontend
@@ -103,6 +136,9 @@ pub struct ToolResultDto {
     pub error: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub duration_ms: Option<u64>,
+    /// Screenshots and other files to show next to the output
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<ToolAttachment>,
 }
 
 /// Agent status response
@@ -116,6 +152,9 @@ pub struct AgentStatusDto {
     pub last_activity: u64,
     pub provider: String,
     pub model: String,
+    /// Project detected in the working directory
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project: Option<ProjectProfile>,
 }
 
 fn current_timestamp() -> u64 {
@@ -174,6 +213,17 @@ pub async fn create_agent_session(
         .map_err(|e| format!("Failed to create terminal: {}", e))?;
     
     println!("[Agent] Created persistent terminal session {} for agent {}", terminal_id, session_id);
+
+    let workspace_root = match opts.workspace_root {
+        Some(root) => Some(
+            PathBuf::from(&root)
+                .canonicalize()
+                .map_err(|e| format!("Invalid workspace root {}: {}", root, e))?,
+        ),
+        None => None,
+    };
+
+    let project = ProjectDetector::new().detect(&working_directory);
     
     let session = AgentSessionState {
         id: session_id.clone(),
@@ -187,6 +237,16 @@ pub async fn create_agent_session(
         created_at: now,
         last_activity: now,
         terminal_session_id: Some(terminal_id),
+        workspace_root,
+        allow_workspace_escape: false,
+        allow_permanent_delete: false,
+        allow_process_kill: false,
+        allow_clipboard: false,
+        allow_screen_capture: false,
+        allowed_http_domains: Vec::new(),
+        allow_git_commits: opts.allow_git_commits.unwrap_or(true),
+        read_only: false,
+        project,
     };
     
     let status = AgentStatusDto {
@@ -198,6 +258,7 @@ pub async fn create_agent_session(
         last_activity: session.last_activity,
         provider: session.provider.clone(),
         model: session.model.clone(),
+        project: session.project.clone(),
     };
     
     state.sessions.write().await.insert(session_id.clone(), session);
@@ -206,32 +267,50 @@ pub async fn create_agent_session(
     Ok(status)
 }
 
-/// Send a message to the agent
+/// Send a message to the agent.
+/// Messages carrying an already delivered `idempotency_key` are not stored
+/// again; the original message is returned instead.
 #[tauri::command]
 pub async fn send_agent_message(
     state: State<'_, AgentTauriState>,
     app_handle: AppHandle,
     session_id: String,
     message: String,
+    idempotency_key: Option<String>,
 ) -> Result<AgentMessageDto, String> {
     println!("[Agent] Message to session {}: {}", session_id, &message[..message.len().min(50)]);
-    
-    let mut sessions = state.sessions.write().await;
-    let session = sessions.get_mut(&session_id)
+
+    let key = idempotency_key.unwrap_or_else(generate_id);
+    let outcome = state.dispatcher
+        .dispatch(&session_id, &key, || async {
+            let mut sessions = state.sessions.write().await;
+            let session = sessions.get_mut(&session_id)
+                .ok_or_else(|| format!("Session not found: {}", session_id))?;
+            if session.read_only {
+                return Err(format!("Session {} is read-only", session_id));
+            }
+
+            let msg = StoredMessage {
+                id: generate_id(),
+                role: "user".to_string(),
+                content: message.clone(),
+                tool_calls: None,
+                tool_call_id: None,
+                timestamp: current_timestamp(),
+            };
+            session.messages.push(msg.clone());
+            session.last_activity = current_timestamp();
+            Ok::<_, String>(msg.id)
+        })
+        .await?;
+
+    let sessions = state.sessions.read().await;
+    let msg = sessions.get(&session_id)
+        .and_then(|s| s.messages.iter().find(|m| m.id == outcome.message_id()))
+        .cloned()
         .ok_or_else(|| format!("Session not found: {}", session_id))?;
-    
-    let msg = StoredMessage {
-        id: generate_id(),
-        role: "user".to_string(),
-        content: message.clone(),
-        tool_calls: None,
-        tool_call_id: None,
-        timestamp: current_timestamp(),
-    };
-    
-    session.messages.push(msg.clone());
-    session.last_activity = current_timestamp();
-    
+    drop(sessions);
+
     let dto = AgentMessageDto {
         id: msg.id,
         role: msg.role,
@@ -241,6 +320,11 @@ pub async fn send_agent_message(
         timestamp: msg.timestamp,
     };
     
+    if let DispatchOutcome::Duplicate { .. } = outcome {
+        println!("[Agent] Dropped duplicate message {} for session {}", key, session_id);
+        return Ok(dto);
+    }
+
     let _ = app_handle.emit(&format!("agent:message:{}", session_id), &dto);
     Ok(dto)
 }
@@ -269,6 +353,7 @@ pub async fn get_agent_status(
         last_activity: session.last_activity,
         provider: session.provider.clone(),
         model: session.model.clone(),
+        project: session.project.clone(),
     })
 }
 
@@ -285,7 +370,18 @@ pub async fn execute_agent_tool(
     println!("[Agent] Executing tool {} for session {}", request.tool_name, session_id);
     
     // Get session context and ensure terminal exists if needed
-    let (working_dir, terminal_session_id) = {
+    let (
+        working_dir,
+        terminal_session_id,
+        workspace_root,
+        allow_workspace_escape,
+        allow_git_commits,
+        allow_permanent_delete,
+        allow_process_kill,
+        allow_clipboard,
+        allow_screen_capture,
+        allowed_http_domains,
+    ) = {
         let mut sessions = state.sessions.write().await;
         let session = sessions.get_mut(&session_id)
             .ok_or_else(|| format!("Session not found: {}", session_id))?;
@@ -315,7 +411,18 @@ pub async fn execute_agent_tool(
             }
         }
         
-        (session.working_directory.clone(), session.terminal_session_id.clone())
+        (
+            session.working_directory.clone(),
+            session.terminal_session_id.clone(),
+            session.workspace_root.clone(),
+            session.allow_workspace_escape,
+            session.allow_git_commits,
+            session.allow_permanent_delete,
+            session.allow_process_kill,
+            session.allow_clipboard,
+            session.allow_screen_capture,
+            session.allowed_http_domains.clone(),
+        )
     };
     
     let _ = app_handle.emit(&format!("agent:tool_start:{}", session_id), &ToolCallDto {
@@ -331,10 +438,22 @@ pub async fn execute_agent_tool(
         max_output_size: 1024 * 1024,
         allow_writes: true,
         terminal_session_id: terminal_session_id.clone(),
+        workspace_root,
+        allow_workspace_escape,
+        allow_git_commits,
+        session_id: Some(session_id.clone()),
+        allow_permanent_delete,
+        allow_process_kill,
+        allow_clipboard,
+        allow_screen_capture,
+        allowed_http_domains,
+        ..Default::default()
     };
     
     let executor = AgentExecutor::with_config(executor_config)
-        .with_terminal_manager(terminal_state.manager.clone());
+        .with_terminal_manager(terminal_state.manager.clone())
+        .with_clipboard(Arc::new(crate::clipboard::TauriClipboard::new(app_handle.clone())))
+        .with_screen_capture(Arc::new(crate::screen::DesktopScreenCapture));
     
     // Map request to ToolCall
     let tool_call = skhoot_backend::cli_agent::ToolCall {
@@ -346,12 +465,14 @@ pub async fn execute_agent_tool(
     // Execute
     let result = executor.execute(&tool_call).await;
     
+    let metadata = result.metadata.unwrap_or_default();
     let result_dto = ToolResultDto {
         tool_call_id: result.tool_call_id,
         success: result.success,
         output: result.output,
         error: result.error,
-        duration_ms: result.metadata.and_then(|m| m.duration_ms),
+        duration_ms: metadata.duration_ms,
+        attachments: metadata.attachments,
     };
     
     {
@@ -400,6 +521,7 @@ async fn execute_tool_direct(request: &ExecuteToolRequest, working_dir: &PathBuf
             output,
             error: None,
             duration_ms: Some(duration_ms),
+            attachments: Vec::new(),
         },
         Err(e) => ToolResultDto {
             tool_call_id: request.tool_call_id.clone(),
@@ -407,6 +529,7 @@ async fn execute_tool_direct(request: &ExecuteToolRequest, working_dir: &PathBuf
             output: String::new(),
             error: Some(e),
             duration_ms: Some(duration_ms),
+            attachments: Vec::new(),
         },
     }
 }
@@ -734,7 +857,181 @@ pub async fn cancel_agent_action(
     Ok(())
 }
 
+/// Grant or revoke file access outside the session's workspace root
+#[tauri::command]
+pub async fn set_agent_workspace_escape(
+    state: State<'_, AgentTauriState>,
+    session_id: String,
+    allowed: bool,
+) -> Result<(), String> {
+    println!("[Agent] Workspace escape for session {}: {}", session_id, allowed);
+
+    let mut sessions = state.sessions.write().await;
+    let session = sessions.get_mut(&session_id)
+        .ok_or_else(|| format!("Session not found: {}", session_id))?;
+    session.allow_workspace_escape = allowed;
+    session.last_activity = current_timestamp();
+    Ok(())
+}
+
+/// Grant or revoke permanent deletion (bypassing the trash) for a session
+#[tauri::command]
+pub async fn set_agent_permanent_delete(
+    state: State<'_, AgentTauriState>,
+    session_id: String,
+    allowed: bool,
+) -> Result<(), String> {
+    println!("[Agent] Permanent delete for session {}: {}", session_id, allowed);
+
+    let mut sessions = state.sessions.write().await;
+    let session = sessions.get_mut(&session_id)
+        .ok_or_else(|| format!("Session not found: {}", session_id))?;
+    session.allow_permanent_delete = allowed;
+    session.last_activity = current_timestamp();
+    Ok(())
+}
+
+/// Grant or revoke killing processes the agent didn't start for a session
+#[tauri::command]
+pub async fn set_agent_process_kill(
+    state: State<'_, AgentTauriState>,
+    session_id: String,
+    allowed: bool,
+) -> Result<(), String> {
+    println!("[Agent] Process kill for session {}: {}", session_id, allowed);
+
+    let mut sessions = state.sessions.write().await;
+    let session = sessions.get_mut(&session_id)
+        .ok_or_else(|| format!("Session not found: {}", session_id))?;
+    session.allow_process_kill = allowed;
+    session.last_activity = current_timestamp();
+    Ok(())
+}
+
+/// Grant or revoke clipboard access for a session
+#[tauri::command]
+pub async fn set_agent_clipboard_access(
+    state: State<'_, AgentTauriState>,
+    session_id: String,
+    allowed: bool,
+) -> Result<(), String> {
+    println!("[Agent] Clipboard access for session {}: {}", session_id, allowed);
+
+    let mut sessions = state.sessions.write().await;
+    let session = sessions.get_mut(&session_id)
+        .ok_or_else(|| format!("Session not found: {}", session_id))?;
+    session.allow_clipboard = allowed;
+    session.last_activity = current_timestamp();
+    Ok(())
+}
+
+/// Grant or revoke screen capture for a session
+#[tauri::command]
+pub async fn set_agent_screen_capture_access(
+    state: State<'_, AgentTauriState>,
+    session_id: String,
+    allowed: bool,
+) -> Result<(), String> {
+    println!("[Agent] Screen capture for session {}: {}", session_id, allowed);
+
+    let mut sessions = state.sessions.write().await;
+    let session = sessions.get_mut(&session_id)
+        .ok_or_else(|| format!("Session not found: {}", session_id))?;
+    session.allow_screen_capture = allowed;
+    session.last_activity = current_timestamp();
+    Ok(())
+}
+
+/// Set the domains a session's HTTP requests may reach; empty disables them
+#[tauri::command]
+pub async fn set_agent_http_domains(
+    state: State<'_, AgentTauriState>,
+    session_id: String,
+    domains: Vec<String>,
+) -> Result<(), String> {
+    println!("[Agent] HTTP domains for session {}: {:?}", session_id, domains);
+
+    let mut sessions = state.sessions.write().await;
+    let session = sessions.get_mut(&session_id)
+        .ok_or_else(|| format!("Session not found: {}", session_id))?;
+    session.allowed_http_domains = domains
+        .iter()
+        .map(|domain| domain.trim().to_ascii_lowercase())
+        .filter(|domain| !domain.is_empty())
+        .collect();
+    session.last_activity = current_timestamp();
+    Ok(())
+}
+
+/// List the file checkpoints created by an agent session
+#[tauri::command]
+pub async fn list_agent_checkpoints(session_id: String) -> Result<Vec<Checkpoint>, String> {
+    CheckpointManager::global()
+        .list(&session_id)
+        .map_err(|e| e.to_string())
+}
+
+/// Revert a checkpoint and every later checkpoint of the session
+#[tauri::command]
+pub async fn revert_agent_checkpoint(
+    session_id: String,
+    checkpoint_id: String,
+) -> Result<Vec<PathBuf>, String> {
+    println!("[Agent] Reverting checkpoint {} for session {}", checkpoint_id, session_id);
+    CheckpointManager::global()
+        .revert_checkpoint(&session_id, &checkpoint_id)
+        .map_err(|e| e.to_string())
+}
+
+/// Revert every file modification made by an agent session
+#[tauri::command]
+pub async fn revert_agent_session(session_id: String) -> Result<Vec<PathBuf>, String> {
+    println!("[Agent] Reverting all changes for session {}", session_id);
+    CheckpointManager::global()
+        .revert_session(&session_id)
+        .map_err(|e| e.to_string())
+}
+
 /// Close an agent session
+/// List every stored system prompt template
+#[tauri::command]
+pub async fn list_prompt_templates() -> Result<PromptTemplateSet, String> {
+    Ok(PromptTemplateStore::global().all())
+}
+
+/// Store the prompt template for a scope (global, agent or conversation)
+#[tauri::command]
+pub async fn set_prompt_template(scope: PromptScope, template: PromptTemplate) -> Result<(), String> {
+    println!("[Agent] Updating prompt template for {:?}", scope);
+    PromptTemplateStore::global()
+        .set(scope, template)
+        .map_err(|e| format!("Failed to save prompt template: {}", e))
+}
+
+/// Remove the prompt template for a scope
+#[tauri::command]
+pub async fn delete_prompt_template(scope: PromptScope) -> Result<bool, String> {
+    PromptTemplateStore::global()
+        .remove(&scope)
+        .map_err(|e| format!("Failed to save prompt templates: {}", e))
+}
+
+/// Build the system prompt with the templates that apply to an agent and
+/// conversation, and the project found in the working directory
+#[tauri::command]
+pub async fn resolve_system_prompt(
+    agent_id: Option<String>,
+    conversation_id: Option<String>,
+    working_directory: Option<String>,
+) -> Result<String, String> {
+    let template = PromptTemplateStore::global()
+        .resolve(agent_id.as_deref(), conversation_id.as_deref());
+    let project = working_directory
+        .and_then(|dir| ProjectDetector::new().detect(std::path::Path::new(&dir)))
+        .map(|project| project.summary());
+    Ok(SystemPrompt::default_skhoot().with_template(template).with_project(project).build())
+}
+
 #[tauri::command]
 pub async fn close_agent_session(
     state: State<'_, AgentTauriState>,
@@ -744,6 +1041,7 @@ pub async fn close_agent_session(
     
     state.sessions.write().await.remove(&session_id)
         .ok_or_else(|| format!("Session not found: {}", session_id))?;
+    state.dispatcher.forget_session(&session_id).await;
     
     println!("[Agent] Session closed: {}", session_id);
     Ok(())
@@ -771,6 +1069,7 @@ pub async fn list_agent_sessions(
             last_activity: s.last_activity,
             provider: s.provider.clone(),
             model: s.model.clone(),
+            project: s.project.clone(),
         }
     }).collect())
 }
@@ -807,6 +1106,9 @@ pub async fn add_assistant_message(
     let mut sessions = state.sessions.write().await;
     let session = sessions.get_mut(&session_id)
         .ok_or_else(|| format!("Session not found: {}", session_id))?;
+    if session.read_only {
+        return Err(format!("Session {} is read-only", session_id));
+    }
     
     let msg = StoredMessage {
         id: generate_id(),
@@ -833,6 +1135,163 @@ pub async fn add_assistant_message(
     Ok(dto)
 }
 
+/// Export a session through a save dialog as a JSON archive or Markdown transcript.
+/// Returns the written path, or `None` if the user cancelled.
+#[tauri::command]
+pub async fn export_agent_session(
+    state: State<'_, AgentTauriState>,
+    app: AppHandle,
+    session_id: String,
+    format: Option<ExportFormat>,
+) -> Result<Option<String>, String> {
+    use tauri_plugin_dialog::DialogExt;
+
+    let format = format.unwrap_or_default();
+    let archive = {
+        let sessions = state.sessions.read().await;
+        let session = sessions.get(&session_id)
+            .ok_or_else(|| format!("Session not found: {}", session_id))?;
+        session_archive(session)
+    };
+
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    app.dialog()
+        .file()
+        .set_file_name(archive.file_name(format))
+        .add_filter(
+            match format {
+                ExportFormat::Json => "Conversation archive",
+                ExportFormat::Markdown => "Markdown",
+            },
+            &[format.extension()],
+        )
+        .save_file(move |path| {
+            let _ = tx.send(path);
+        });
+
+    let Some(path) = rx.await.map_err(|e| e.to_string())? else {
+        println!("[Agent] Export of session {} cancelled", session_id);
+        return Ok(None);
+    };
+    let path = path.into_path().map_err(|e| e.to_string())?;
+
+    std::fs::write(&path, archive.render(format))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+
+    println!("[Agent] Exported session {} to {}", session_id, path.display());
+    Ok(Some(path.to_string_lossy().to_string()))
+}
+
+/// Import a JSON conversation archive as a new read-only session
+#[tauri::command]
+pub async fn import_agent_session(
+    state: State<'_, AgentTauriState>,
+    path: String,
+) -> Result<AgentStatusDto, String> {
+    let json = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let archive = ConversationArchive::from_json(&json).map_err(|e| e.to_string())?;
+
+    let meta = archive.metadata;
+    let session = AgentSessionState {
+        id: format!("imported-{}", generate_id()),
+        provider: meta.provider.unwrap_or_default(),
+        model: meta.model.unwrap_or_default(),
+        working_directory: meta.working_directory.map(PathBuf::from).unwrap_or_default(),
+        temperature: 0.7,
+        max_tokens: 4096,
+        messages: archive.messages.into_iter().map(stored_message).collect(),
+        state: "read_only".to_string(),
+        created_at: meta.created_at,
+        last_activity: meta.last_activity,
+        terminal_session_id: None,
+        workspace_root: None,
+        allow_workspace_escape: false,
+        allow_permanent_delete: false,
+        allow_process_kill: false,
+        allow_clipboard: false,
+        allow_screen_capture: false,
+        allowed_http_domains: Vec::new(),
+        allow_git_commits: false,
+        read_only: true,
+        project: None,
+    };
+
+    let status = AgentStatusDto {
+        session_id: session.id.clone(),
+        state: session.state.clone(),
+        message_count: session.messages.len(),
+        pending_tool_calls: 0,
+        created_at: session.created_at,
+        last_activity: session.last_activity,
+        provider: session.provider.clone(),
+        model: session.model.clone(),
+        project: session.project.clone(),
+    };
+
+    println!("[Agent] Imported {} as read-only session {}", path, session.id);
+    state.sessions.write().await.insert(session.id.clone(), session);
+    Ok(status)
+}
+
+fn session_archive(session: &AgentSessionState) -> ConversationArchive {
+    let messages = session.messages.iter().map(|m| AgentMessage {
+        id: m.id.clone(),
+        role: match m.role.as_str() {
+            "system" => MessageRole::System,
+            "assistant" => MessageRole::Assistant,
+            "tool" => MessageRole::Tool,
+            _ => MessageRole::User,
+        },
+        content: m.content.clone(),
+        tool_calls: m.tool_calls.as_ref().map(|calls| {
+            calls.iter().map(|tc| ToolCall {
+                id: tc.id.clone(),
+                name: tc.name.clone(),
+                arguments: tc.arguments.clone(),
+            }).collect()
+        }),
+        tool_call_id: m.tool_call_id.clone(),
+        attachments: Vec::new(),
+        timestamp: m.timestamp,
+    }).collect();
+
+    ConversationArchive::new(
+        ConversationMetadata {
+            session_id: session.id.clone(),
+            provider: Some(session.provider.clone()),
+            model: Some(session.model.clone()),
+            working_directory: Some(session.working_directory.to_string_lossy().to_string()),
+            created_at: session.created_at,
+            last_activity: session.last_activity,
+            ..Default::default()
+        },
+        messages,
+    )
+}
+
+fn stored_message(message: AgentMessage) -> StoredMessage {
+    StoredMessage {
+        id: message.id,
+        role: match message.role {
+            MessageRole::System => "system",
+            MessageRole::User => "user",
+            MessageRole::Assistant => "assistant",
+            MessageRole::Tool => "tool",
+        }.to_string(),
+        content: message.content,
+        tool_calls: message.tool_calls.map(|calls| {
+            calls.into_iter().map(|tc| ToolCallDto {
+                id: tc.id,
+                name: tc.name,
+                arguments: tc.arguments,
+            }).collect()
+        }),
+        tool_call_id: message.tool_call_id,
+        timestamp: message.timestamp,
+    }
+}
+
 /// Get session configuration
 #[tauri::command]
 pub async fn get_agent_config(
@@ -120,6 +120,19 @@ pub async fn set_active_provider(
     Ok(())
 }
 
+/// Export all API keys as plaintext JSON to a user-chosen file
+#[tauri::command]
+pub async fn export_api_keys(
+    state: State<'_, ApiKeyState>,
+    path: String,
+) -> Result<usize, String> {
+    let storage = state.storage.lock().map_err(|e| e.to_string())?;
+    
+    storage
+        .export_keys_to_file(std::path::Path::new(&path))
+        .map_err(|e| format!("Failed to export API keys: {}", e))
+}
+
 /// Test an API key and fetch available models
 #[tauri::command]
 pub async fn test_api_key(
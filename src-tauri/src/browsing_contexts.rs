@@ -0,0 +1,125 @@
+//! Named Browsing Contexts
+//!
+//! A browsing context is a WebView data directory of its own, so cookies and
+//! localStorage persist per context. The user logs in once in a visible
+//! window opened on the context; render jobs naming the context then load
+//! pages with that session (see `WebViewRenderer::render`).
+
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use tauri::{AppHandle, Manager, WebviewUrl, WebviewWindowBuilder};
+
+use skhoot_backend::content_extraction::is_valid_context_name;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BrowsingContextInfo {
+    pub name: String,
+    /// Disk used by the context's cookies, storage and cache
+    pub size_bytes: u64,
+    /// Last change, in milliseconds since the Unix epoch
+    pub modified_at: Option<i64>,
+}
+
+fn contexts_root(app: &AppHandle) -> Result<PathBuf, String> {
+    let data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    Ok(data_dir.join("browsing-contexts"))
+}
+
+/// Data directory of the context named `name`
+pub fn context_dir(app: &AppHandle, name: &str) -> Result<PathBuf, String> {
+    if !is_valid_context_name(name) {
+        return Err(format!(
+            "Invalid browsing context '{}': use letters, digits, '-' and '_'",
+            name
+        ));
+    }
+    Ok(contexts_root(app)?.join(name))
+}
+
+fn login_label(name: &str) -> String {
+    format!("login-{}", name)
+}
+
+fn dir_size(path: &Path) -> u64 {
+    std::fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| match entry.metadata() {
+                    Ok(meta) if meta.is_dir() => dir_size(&entry.path()),
+                    Ok(meta) => meta.len(),
+                    Err(_) => 0,
+                })
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+/// List the browsing contexts created so far
+#[tauri::command]
+pub async fn list_browsing_contexts(app: AppHandle) -> Result<Vec<BrowsingContextInfo>, String> {
+    let root = contexts_root(&app)?;
+    let Ok(entries) = std::fs::read_dir(&root) else {
+        return Ok(Vec::new());
+    };
+
+    let mut contexts: Vec<BrowsingContextInfo> = entries
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !is_valid_context_name(&name) {
+                return None;
+            }
+            let modified_at = entry
+                .metadata()
+                .and_then(|meta| meta.modified())
+                .ok()
+                .map(|time| chrono::DateTime::<chrono::Utc>::from(time).timestamp_millis());
+            Some(BrowsingContextInfo {
+                size_bytes: dir_size(&entry.path()),
+                name,
+                modified_at,
+            })
+        })
+        .collect();
+    contexts.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(contexts)
+}
+
+/// Open a visible window on `url` in the context, creating the context if
+/// needed, so the user can log in; the session is kept when it's closed
+#[tauri::command]
+pub async fn open_browsing_context_login(app: AppHandle, name: String, url: String) -> Result<(), String> {
+    let dir = context_dir(&app, &name)?;
+    let parsed_url = url.parse().map_err(|_| format!("Invalid URL: {}", url))?;
+
+    let label = login_label(&name);
+    if let Some(window) = app.get_webview_window(&label) {
+        return window.set_focus().map_err(|e| e.to_string());
+    }
+
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create browsing context: {}", e))?;
+    WebviewWindowBuilder::new(&app, &label, WebviewUrl::External(parsed_url))
+        .title(format!("Log in - {}", name))
+        .inner_size(1000.0, 760.0)
+        .data_directory(dir)
+        .build()
+        .map_err(|e| format!("Failed to open login window: {}", e))?;
+    Ok(())
+}
+
+/// Delete a context along with its cookies and storage
+#[tauri::command]
+pub async fn delete_browsing_context(app: AppHandle, name: String) -> Result<(), String> {
+    let dir = context_dir(&app, &name)?;
+    if let Some(window) = app.get_webview_window(&login_label(&name)) {
+        let _ = window.destroy();
+    }
+    match std::fs::remove_dir_all(&dir) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Err(format!("No browsing context '{}'", name)),
+        Err(e) => Err(format!("Failed to delete browsing context: {}", e)),
+    }
+}
@@ -0,0 +1,55 @@
+//! Clipboard Tauri Commands
+//!
+//! Gives the frontend and the agent access to the system clipboard through
+//! the clipboard-manager plugin. The agent's `clipboard` tool reaches it via
+//! [`TauriClipboard`], and only in sessions where the user allowed it.
+
+use tauri::image::Image;
+use tauri::AppHandle;
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+use skhoot_backend::cli_agent::ClipboardProvider;
+
+/// System clipboard backed by the Tauri clipboard plugin
+pub struct TauriClipboard {
+    app: AppHandle,
+}
+
+impl TauriClipboard {
+    pub fn new(app: AppHandle) -> Self {
+        Self { app }
+    }
+}
+
+impl ClipboardProvider for TauriClipboard {
+    fn read_text(&self) -> Result<String, String> {
+        self.app.clipboard().read_text().map_err(|e| e.to_string())
+    }
+
+    fn write_text(&self, text: &str) -> Result<(), String> {
+        self.app.clipboard().write_text(text).map_err(|e| e.to_string())
+    }
+
+    fn write_image(&self, png: &[u8]) -> Result<(), String> {
+        let image = Image::from_bytes(png).map_err(|e| format!("Invalid PNG image: {}", e))?;
+        self.app.clipboard().write_image(&image).map_err(|e| e.to_string())
+    }
+}
+
+/// Read text from the clipboard
+#[tauri::command]
+pub fn clipboard_read_text(app_handle: AppHandle) -> Result<String, String> {
+    TauriClipboard::new(app_handle).read_text()
+}
+
+/// Copy text to the clipboard
+#[tauri::command]
+pub fn clipboard_write_text(app_handle: AppHandle, text: String) -> Result<(), String> {
+    TauriClipboard::new(app_handle).write_text(&text)
+}
+
+/// Copy a PNG image to the clipboard
+#[tauri::command]
+pub fn clipboard_write_image(app_handle: AppHandle, png: Vec<u8>) -> Result<(), String> {
+    TauriClipboard::new(app_handle).write_image(&png)
+}
@@ -0,0 +1,75 @@
+//! Global Hotkey
+//!
+//! Registers the `[hotkey]` binding from skhoot.toml with the global-shortcut
+//! plugin. Pressing it shows and focuses the main window and, depending on the
+//! configured action, emits `global-hotkey` so the frontend starts a new
+//! conversation or toggles voice capture. Edits made through the config API
+//! or the file are picked up without a restart.
+
+use std::time::Duration;
+
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+
+use skhoot_backend::config::{HotkeyAction, HotkeySettings, SettingsStore};
+
+/// Register the configured hotkey and re-register it when the settings change
+pub fn init(app: &AppHandle) {
+    let mut current = SettingsStore::global().get().hotkey;
+    apply(app, &current);
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(Duration::from_secs(2)).await;
+            let hotkey = SettingsStore::global().get().hotkey;
+            if hotkey != current {
+                apply(&app, &hotkey);
+                current = hotkey;
+            }
+        }
+    });
+}
+
+fn apply(app: &AppHandle, settings: &HotkeySettings) {
+    let shortcuts = app.global_shortcut();
+    if let Err(e) = shortcuts.unregister_all() {
+        eprintln!("[Skhoot] Failed to clear global hotkey: {}", e);
+    }
+    if !settings.enabled {
+        println!("[Skhoot] Global hotkey disabled");
+        return;
+    }
+
+    let shortcut: Shortcut = match settings.binding.parse() {
+        Ok(shortcut) => shortcut,
+        Err(e) => {
+            eprintln!("[Skhoot] Invalid global hotkey '{}': {}", settings.binding, e);
+            return;
+        }
+    };
+
+    let action = settings.action;
+    let registered = shortcuts.on_shortcut(shortcut, move |app, _shortcut, event| {
+        if event.state() == ShortcutState::Pressed {
+            summon(app, action);
+        }
+    });
+    match registered {
+        Ok(()) => println!("[Skhoot] Global hotkey registered: {}", settings.binding),
+        // Usually another application already owns the binding
+        Err(e) => eprintln!("[Skhoot] Failed to register global hotkey '{}': {}", settings.binding, e),
+    }
+}
+
+/// Bring the main window to the front and pass the action to the frontend
+fn summon(app: &AppHandle, action: HotkeyAction) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+    if action != HotkeyAction::Show {
+        let _ = app.emit("global-hotkey", action);
+    }
+}
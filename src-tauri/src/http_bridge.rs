@@ -12,7 +12,11 @@ use axum::{
 };
 use serde_json::json;
 use std::sync::Arc;
-use tauri::AppHandle;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_notification::NotificationExt;
+
+use skhoot_backend::auth::{require_token, AuthToken};
+use skhoot_backend::notifications::Notification;
 
 use crate::webview_renderer::{RenderJob, RenderResult, WebViewRendererState};
 
@@ -23,9 +27,13 @@ pub struct HttpBridgeState {
 }
 
 /// Start the HTTP bridge server
+///
+/// Render and notification requests must carry the launch token shared with
+/// the backend.
 pub async fn start_http_bridge(
     app_handle: AppHandle,
     renderer_state: WebViewRendererState,
+    auth: AuthToken,
 ) {
     let state = Arc::new(HttpBridgeState {
         app_handle,
@@ -33,8 +41,10 @@ pub async fn start_http_bridge(
     });
 
     let app = Router::new()
-        .route("/api/health", get(health_check))
         .route("/api/render", post(render_endpoint))
+        .route("/api/notify", post(notify_endpoint))
+        .route_layer(axum::middleware::from_fn_with_state(auth, require_token))
+        .route("/api/health", get(health_check))
         .with_state(state);
 
     let listener = tokio::net::TcpListener::bind("127.0.0.1:1420")
@@ -75,3 +85,32 @@ async fn render_endpoint(
         }
     }
 }
+
+/// Notify endpoint - shows a desktop notification unless the main window has
+/// focus, in which case the user already sees the result in the app
+async fn notify_endpoint(
+    State(state): State<Arc<HttpBridgeState>>,
+    Json(notification): Json<Notification>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let focused = state
+        .app_handle
+        .get_webview_window("main")
+        .and_then(|window| window.is_focused().ok())
+        .unwrap_or(false);
+    if focused {
+        return Ok(Json(json!({ "shown": false })));
+    }
+
+    state
+        .app_handle
+        .notification()
+        .builder()
+        .title(&notification.title)
+        .body(&notification.body)
+        .show()
+        .map_err(|e| {
+            eprintln!("[Skhoot] Notification failed: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+    Ok(Json(json!({ "shown": true })))
+}
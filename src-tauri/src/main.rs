@@ -5,13 +5,18 @@
 mod terminal;
 mod api_keys;
 mod agent;
+mod clipboard;
+mod screen;
 mod disk_info;
+mod hotkey;
 mod webview_renderer;
+mod browsing_contexts;
 mod http_bridge;
 
 use tauri::Manager;
 use std::process::{Command, Stdio};
 use skhoot_backend::KeyStorage;
+use skhoot_backend::auth::{AuthToken, AUTH_TOKEN_ENV};
 
 /// Add current user to the audio group on Linux using pkexec (PolicyKit)
 /// This shows the native authentication dialog
@@ -106,6 +111,12 @@ async fn check_audio_server() -> Result<String, String> {
     }
 }
 
+/// Get the per-launch token the backend requires on its API routes
+#[tauri::command]
+fn get_backend_auth_token(auth: tauri::State<'_, AuthToken>) -> Option<String> {
+    auth.value().map(str::to_string)
+}
+
 /// Get the app's local data directory path
 #[tauri::command]
 async fn get_local_data_dir(app: tauri::AppHandle) -> Result<String, String> {
@@ -345,6 +356,8 @@ fn main() {
     .plugin(tauri_plugin_dialog::init())
     .plugin(tauri_plugin_notification::init())
     .plugin(tauri_plugin_fs::init())
+    .plugin(tauri_plugin_clipboard_manager::init())
+    .plugin(tauri_plugin_global_shortcut::Builder::new().build())
     .setup(|app| {
       // Initialize API key storage
       let app_data_dir = app.path().app_data_dir()
@@ -367,10 +380,15 @@ fn main() {
       renderer_state.initialize(app.handle().clone());
       app.manage(renderer_state.clone());
       
+      // Generate the token shared with the backend for this launch
+      let auth = AuthToken::generate();
+      app.manage(auth.clone());
+      
       // Start HTTP bridge server for backend communication
       let app_handle_for_bridge = app.handle().clone();
+      let bridge_auth = auth.clone();
       tauri::async_runtime::spawn(async move {
-        http_bridge::start_http_bridge(app_handle_for_bridge, renderer_state).await;
+        http_bridge::start_http_bridge(app_handle_for_bridge, renderer_state, bridge_auth).await;
       });
       
       #[cfg(desktop)]
@@ -378,12 +396,15 @@ fn main() {
         // Use an empty app-wide menu to avoid showing a menubar.
         let menu = tauri::menu::Menu::new(app.handle())?;
         app.set_menu(menu)?;
+
+        // Summon the window from anywhere with the configured hotkey
+        hotkey::init(app.handle());
       }
 
       // Start the backend sidecar
       let app_handle_clone = app.handle().clone();
       tauri::async_runtime::spawn(async move {
-        start_backend_sidecar(&app_handle_clone).await;
+        start_backend_sidecar(&app_handle_clone, &auth).await;
       });
 
       // Log platform-specific audio permission info
@@ -446,6 +467,7 @@ fn main() {
         check_audio_group_membership,
         check_audio_server,
         start_audio_services,
+        get_backend_auth_token,
         get_local_data_dir,
         open_local_data_dir,
         terminal::create_terminal_session,
@@ -456,12 +478,19 @@ fn main() {
         terminal::list_terminal_sessions,
         terminal::get_session_history,
         terminal::get_session_state,
+        terminal::get_command_policy,
+        terminal::set_command_policy,
+        terminal::add_command_policy_rule,
+        terminal::remove_command_policy_rule,
+        terminal::reset_command_policy,
+        terminal::evaluate_command_policy,
         api_keys::save_api_key,
         api_keys::load_api_key,
         api_keys::delete_api_key,
         api_keys::list_providers,
         api_keys::get_active_provider,
         api_keys::set_active_provider,
+        api_keys::export_api_keys,
         api_keys::test_api_key,
         api_keys::fetch_provider_models,
         api_keys::get_kiro_token,
@@ -470,13 +499,35 @@ fn main() {
         agent::get_agent_status,
         agent::execute_agent_tool,
         agent::cancel_agent_action,
+        agent::set_agent_workspace_escape,
+        agent::set_agent_permanent_delete,
+        agent::set_agent_process_kill,
+        agent::set_agent_clipboard_access,
+        agent::set_agent_screen_capture_access,
+        agent::set_agent_http_domains,
+        agent::list_agent_checkpoints,
+        agent::revert_agent_checkpoint,
+        agent::revert_agent_session,
+        agent::list_prompt_templates,
+        agent::set_prompt_template,
+        agent::delete_prompt_template,
+        agent::resolve_system_prompt,
         agent::close_agent_session,
+        agent::export_agent_session,
+        agent::import_agent_session,
         agent::list_agent_sessions,
         agent::get_agent_messages,
         agent::add_assistant_message,
         agent::get_agent_config,
         disk_info::get_system_disks,
+        clipboard::clipboard_read_text,
+        clipboard::clipboard_write_text,
+        clipboard::clipboard_write_image,
+        screen::capture_screen,
         webview_renderer::render_page,
+        browsing_contexts::list_browsing_contexts,
+        browsing_contexts::open_browsing_context_login,
+        browsing_contexts::delete_browsing_context,
         pick_folder,
         pick_files,
     ])
@@ -509,7 +560,7 @@ fn log_audio_permission_info() {
   }
 }
 
-async fn start_backend_sidecar(app_handle: &tauri::AppHandle) {
+async fn start_backend_sidecar(app_handle: &tauri::AppHandle, auth: &AuthToken) {
   if cfg!(debug_assertions) {
     // In development, run cargo directly from the backend directory
     println!("[Skhoot] Starting backend in development mode...");
@@ -528,6 +579,7 @@ async fn start_backend_sidecar(app_handle: &tauri::AppHandle) {
     let mut cmd = Command::new("cargo");
     cmd.args(&["run"])
        .current_dir(&backend_dir)
+       .env(AUTH_TOKEN_ENV, auth.value().unwrap_or_default())
        .stdout(Stdio::inherit())
        .stderr(Stdio::inherit());
     
@@ -591,7 +643,9 @@ async fn start_backend_sidecar(app_handle: &tauri::AppHandle) {
     }
     
       let mut cmd = Command::new(&backend_path);
-      cmd.stdout(Stdio::null()).stderr(Stdio::null());
+      cmd.env(AUTH_TOKEN_ENV, auth.value().unwrap_or_default())
+         .stdout(Stdio::null())
+         .stderr(Stdio::null());
 
       #[cfg(windows)]
       {
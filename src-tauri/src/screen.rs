@@ -0,0 +1,105 @@
+//! Screen Capture Tauri Commands
+//!
+//! Captures a monitor, a window or a screen region as PNG through xcap. The
+//! frontend calls [`capture_screen`] directly; the agent's `capture_screen`
+//! tool reaches it via [`DesktopScreenCapture`], and only in sessions where
+//! the user allowed it.
+
+use std::io::Cursor;
+
+use xcap::image::{imageops, DynamicImage, ImageFormat, RgbaImage};
+use xcap::{Monitor, Window};
+
+use skhoot_backend::cli_agent::{CaptureTarget, ScreenCapture};
+
+/// Our own windows are skipped when picking the frontmost window
+const APP_NAME: &str = "Skhoot";
+
+/// Screen capture of the desktop the app runs on
+pub struct DesktopScreenCapture;
+
+impl ScreenCapture for DesktopScreenCapture {
+    fn capture(&self, target: &CaptureTarget) -> Result<Vec<u8>, String> {
+        let image = match target {
+            CaptureTarget::Screen { monitor } => capture_monitor(*monitor)?,
+            CaptureTarget::Window { title } => capture_window(title.as_deref())?,
+            CaptureTarget::Region { x, y, width, height } => capture_region(*x, *y, *width, *height)?,
+        };
+        encode_png(image)
+    }
+}
+
+fn capture_monitor(index: Option<usize>) -> Result<RgbaImage, String> {
+    let monitors = Monitor::all().map_err(|e| e.to_string())?;
+    let monitor = match index {
+        Some(index) => monitors
+            .get(index)
+            .ok_or_else(|| format!("No monitor {}; there are {}", index, monitors.len()))?,
+        None => monitors
+            .iter()
+            .find(|m| m.is_primary())
+            .or_else(|| monitors.first())
+            .ok_or("No monitor found")?,
+    };
+    monitor.capture_image().map_err(|e| e.to_string())
+}
+
+fn capture_window(title: Option<&str>) -> Result<RgbaImage, String> {
+    let windows = Window::all().map_err(|e| e.to_string())?;
+    // Windows are listed front to back
+    let window = match title {
+        Some(title) => {
+            let needle = title.to_lowercase();
+            windows.iter().find(|w| {
+                !w.is_minimized()
+                    && (w.title().to_lowercase().contains(&needle) || w.app_name().to_lowercase().contains(&needle))
+            })
+        }
+        None => windows
+            .iter()
+            .find(|w| !w.is_minimized() && !w.title().is_empty() && w.app_name() != APP_NAME),
+    };
+    let window = window.ok_or_else(|| match title {
+        Some(title) => format!("No window matches '{}'", title),
+        None => "No window to capture".to_string(),
+    })?;
+    window.capture_image().map_err(|e| e.to_string())
+}
+
+fn capture_region(x: i32, y: i32, width: u32, height: u32) -> Result<RgbaImage, String> {
+    if width == 0 || height == 0 {
+        return Err("Region must not be empty".to_string());
+    }
+    let monitors = Monitor::all().map_err(|e| e.to_string())?;
+    let monitor = monitors
+        .iter()
+        .find(|m| {
+            x >= m.x() && y >= m.y() && x < m.x() + m.width() as i32 && y < m.y() + m.height() as i32
+        })
+        .ok_or_else(|| format!("({}, {}) is not on any monitor", x, y))?;
+
+    let image = monitor.capture_image().map_err(|e| e.to_string())?;
+    // Monitor coordinates are logical; the capture may be scaled up
+    let scale = image.width() as f32 / monitor.width() as f32;
+    let left = ((x - monitor.x()) as f32 * scale) as u32;
+    let top = ((y - monitor.y()) as f32 * scale) as u32;
+    let width = ((width as f32 * scale) as u32).min(image.width() - left);
+    let height = ((height as f32 * scale) as u32).min(image.height() - top);
+    Ok(imageops::crop_imm(&image, left, top, width, height).to_image())
+}
+
+pub(crate) fn encode_png(image: RgbaImage) -> Result<Vec<u8>, String> {
+    let mut png = Vec::new();
+    DynamicImage::ImageRgba8(image)
+        .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+        .map_err(|e| format!("Failed to encode screenshot: {}", e))?;
+    Ok(png)
+}
+
+/// Capture the screen, a window or a region as PNG
+#[tauri::command]
+pub async fn capture_screen(target: CaptureTarget) -> Result<Vec<u8>, String> {
+    tauri::async_runtime::spawn_blocking(move || DesktopScreenCapture.capture(&target))
+        .await
+        .map_err(|e| e.to_string())?
+}
@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 use skhoot_backend::TerminalManager;
+use skhoot_backend::cli_bridge::{CommandPolicy, PatternKind, PolicyAction, PolicyRule, PolicyStore};
 use tauri::State;
 
 /// Terminal session state
@@ -169,3 +170,46 @@ pub async fn get_session_state(
         Err("Session not found".to_string())
     }
 }
+
+/// Get the command allow/deny policy
+#[tauri::command]
+pub fn get_command_policy() -> Result<CommandPolicy, String> {
+    Ok(PolicyStore::global().get())
+}
+
+/// Replace the command allow/deny policy
+#[tauri::command]
+pub fn set_command_policy(policy: CommandPolicy) -> Result<(), String> {
+    PolicyStore::global().set(policy).map_err(|e| e.to_string())
+}
+
+/// Add a command policy rule with highest precedence
+#[tauri::command]
+pub fn add_command_policy_rule(
+    pattern: String,
+    kind: PatternKind,
+    action: PolicyAction,
+    description: Option<String>,
+) -> Result<CommandPolicy, String> {
+    let mut rule = PolicyRule::new(&pattern, kind, action);
+    rule.description = description;
+    PolicyStore::global().add_rule(rule).map_err(|e| e.to_string())
+}
+
+/// Remove a command policy rule by ID
+#[tauri::command]
+pub fn remove_command_policy_rule(rule_id: String) -> Result<CommandPolicy, String> {
+    PolicyStore::global().remove_rule(&rule_id).map_err(|e| e.to_string())
+}
+
+/// Restore the default command policy
+#[tauri::command]
+pub fn reset_command_policy() -> Result<CommandPolicy, String> {
+    PolicyStore::global().reset().map_err(|e| e.to_string())
+}
+
+/// Check which policy rule a command would match
+#[tauri::command]
+pub fn evaluate_command_policy(command: String) -> Result<Option<PolicyRule>, String> {
+    Ok(PolicyStore::global().get().evaluate(&command).cloned())
+}
@@ -9,7 +9,7 @@ use tauri::{AppHandle, WebviewUrl, WebviewWindowBuilder, Manager, Listener};
 use std::time::{Duration, Instant};
 
 // Re-export types from backend for convenience
-pub use skhoot_backend::content_extraction::types::{RenderJob, RenderResult, RenderWait};
+pub use skhoot_backend::content_extraction::types::{RenderJob, RenderResult, RenderWait, ScreenshotMode};
 
 /// WebView Renderer state
 /// 
@@ -67,15 +67,33 @@ impl WebViewRenderer {
         // Create a unique label for this render window
         let window_label = format!("render-{}", job.job_id);
         
+        // Pages rendered in a named context see its cookies and storage
+        let data_directory = match &job.context {
+            Some(context) => Some(crate::browsing_contexts::context_dir(&self.app_handle, context)?),
+            None => None,
+        };
+
         // Create hidden WebView window
-        let window = self.create_hidden_window(&window_label, &job.url)
+        let window = self.create_hidden_window(&window_label, &job.url, data_directory)
             .map_err(|e| format!("Failed to create hidden window: {}", e))?;
 
         // Wait for the page to be ready based on the wait condition
         self.wait_for_ready(&job.wait, job.timeout_ms).await?;
 
         // Extract the DOM content via JavaScript
-        let result = self.extract_dom(&job).await?;
+        let mut result = self.extract_dom(&job).await?;
+
+        // Screenshot the page while the window is still open; the extract is
+        // still useful without it
+        if let Some(mode) = job.screenshot {
+            match snapshot_png(&window, mode).await {
+                Ok(png) => {
+                    use base64::{engine::general_purpose::STANDARD, Engine};
+                    result.screenshot = Some(STANDARD.encode(png));
+                }
+                Err(e) => eprintln!("[WebViewRenderer] Warning: Screenshot failed: {}", e),
+            }
+        }
 
         // Clean up: close the hidden window
         if let Err(e) = window.close() {
@@ -98,10 +116,12 @@ impl WebViewRenderer {
     /// - Offscreen (positioned outside visible screen area)
     /// - No decorations or titlebar
     /// - Minimal size
+    /// - Its own data directory when rendering in a browsing context
     fn create_hidden_window(
         &self,
         label: &str,
         url: &str,
+        data_directory: Option<std::path::PathBuf>,
     ) -> Result<tauri::WebviewWindow, tauri::Error> {
         // Parse URL first
         let parsed_url = url.parse()
@@ -189,6 +209,10 @@ impl WebViewRenderer {
             window_builder = window_builder.transparent(true); // Transparent window (helps prevent titlebar on Windows)
         }
 
+        if let Some(dir) = data_directory {
+            window_builder = window_builder.data_directory(dir);
+        }
+
         let window = window_builder
             .skip_taskbar(true) // Don't show in taskbar
             .always_on_bottom(true) // Keep below all other windows if somehow visible
@@ -351,10 +375,74 @@ impl WebViewRenderer {
             title,
             html,
             elapsed_ms,
+            screenshot: None,
         })
     }
 }
 
+/// Captures the WebView's content as PNG
+///
+/// Uses WebKitGTK's snapshot API, which renders the page itself rather than
+/// the (hidden, offscreen) window, so it works for render windows too.
+#[cfg(target_os = "linux")]
+async fn snapshot_png(window: &tauri::WebviewWindow, mode: ScreenshotMode) -> Result<Vec<u8>, String> {
+    use webkit2gtk::{SnapshotOptions, SnapshotRegion, WebViewExt};
+
+    let region = match mode {
+        ScreenshotMode::Viewport => SnapshotRegion::Visible,
+        ScreenshotMode::FullPage => SnapshotRegion::FullDocument,
+    };
+    let (tx, rx) = std::sync::mpsc::channel();
+    window
+        .with_webview(move |webview| {
+            webview.inner().snapshot(
+                region,
+                SnapshotOptions::NONE,
+                None::<&webkit2gtk::gio::Cancellable>,
+                move |surface| {
+                    let png = surface
+                        .map_err(|e| e.to_string())
+                        .and_then(|surface| surface_to_png(&surface));
+                    let _ = tx.send(png);
+                },
+            );
+        })
+        .map_err(|e| e.to_string())?;
+
+    tauri::async_runtime::spawn_blocking(move || rx.recv_timeout(Duration::from_secs(10)))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|_| "Timeout waiting for WebView snapshot".to_string())?
+}
+
+/// Encodes a cairo ARGB32 surface (premultiplied, BGRA in memory) as PNG
+#[cfg(target_os = "linux")]
+fn surface_to_png(surface: &webkit2gtk::gtk::cairo::Surface) -> Result<Vec<u8>, String> {
+    use webkit2gtk::gtk::cairo::ImageSurface;
+    use xcap::image::RgbaImage;
+
+    let surface = ImageSurface::try_from(surface.clone()).map_err(|_| "Snapshot is not an image".to_string())?;
+    let (width, height, stride) = (surface.width() as u32, surface.height() as u32, surface.stride() as usize);
+    let mut image = RgbaImage::new(width, height);
+    surface
+        .with_data(|data| {
+            for (y, row) in data.chunks(stride).take(height as usize).enumerate() {
+                for (x, pixel) in row.chunks_exact(4).take(width as usize).enumerate() {
+                    let (b, g, r, a) = (pixel[0], pixel[1], pixel[2], pixel[3]);
+                    let unpremultiply = |c: u8| if a == 0 { 0 } else { ((c as u32 * 255) / a as u32) as u8 };
+                    image.put_pixel(x as u32, y as u32, xcap::image::Rgba([unpremultiply(r), unpremultiply(g), unpremultiply(b), a]));
+                }
+            }
+        })
+        .map_err(|e| e.to_string())?;
+    crate::screen::encode_png(image)
+}
+
+#[cfg(not(target_os = "linux"))]
+async fn snapshot_png(_window: &tauri::WebviewWindow, _mode: ScreenshotMode) -> Result<Vec<u8>, String> {
+    Err("Page screenshots are not supported on this platform".to_string())
+}
+
 /// State wrapper for Tauri state management
 #[derive(Clone)]
 pub struct WebViewRendererState {